@@ -0,0 +1,206 @@
+//! 相机视角的书签
+//!
+//! 按数字键 1~9 读取对应 slot 的视角，Ctrl + 数字键保存当前视角；也可以在 "Camera Bookmarks" 面板中管理。
+//! 书签随用户设置一起保存在 `[camera_bookmarks]` 中，key 为 slot：
+//!
+//! ```toml
+//! [camera_bookmarks.1]
+//! position = [0.0, 2.0, 10.0]
+//! euler_yaw_deg = 0.0
+//! euler_pitch_deg = -10.0
+//! projection_mode = { type = "perspective", fov_y_deg = 60.0 }
+//! ```
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use truvis_renderer::platform::camera::CameraBookmark;
+
+use crate::platform::camera_controller::CameraController;
+use crate::platform::input_event::KeyCode;
+use crate::platform::input_state::InputState;
+
+/// slot 到视角的映射
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "BTreeMap<String, CameraBookmark>", into = "BTreeMap<String, CameraBookmark>")]
+pub struct CameraBookmarks {
+    slots: BTreeMap<u32, CameraBookmark>,
+}
+impl From<BTreeMap<String, CameraBookmark>> for CameraBookmarks {
+    fn from(value: BTreeMap<String, CameraBookmark>) -> Self {
+        let mut slots = BTreeMap::new();
+        for (slot, bookmark) in value {
+            match slot.parse::<u32>() {
+                Ok(slot) => {
+                    slots.insert(slot, bookmark);
+                }
+                Err(_) => log::warn!("invalid camera bookmark slot: {}", slot),
+            }
+        }
+        Self { slots }
+    }
+}
+impl From<CameraBookmarks> for BTreeMap<String, CameraBookmark> {
+    fn from(value: CameraBookmarks) -> Self {
+        value.slots.into_iter().map(|(slot, bookmark)| (slot.to_string(), bookmark)).collect()
+    }
+}
+// getter
+impl CameraBookmarks {
+    #[inline]
+    pub fn get(&self, slot: u32) -> Option<&CameraBookmark> {
+        self.slots.get(&slot)
+    }
+
+    /// 没有被占用的最小 slot，从 1 开始
+    fn next_free_slot(&self) -> u32 {
+        (1..).find(|slot| !self.slots.contains_key(slot)).unwrap()
+    }
+}
+// update
+impl CameraBookmarks {
+    /// 保存视角，覆盖 slot 中已有的书签
+    pub fn save_bookmark(&mut self, slot: u32, camera_controller: &CameraController) {
+        self.slots.insert(slot, camera_controller.camera().bookmark());
+    }
+
+    /// 读取视角，slot 为空时返回 false
+    pub fn load_bookmark(&self, slot: u32, camera_controller: &mut CameraController) -> bool {
+        match self.slots.get(&slot) {
+            Some(bookmark) => {
+                camera_controller.load_bookmark(bookmark);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove(&mut self, slot: u32) {
+        self.slots.remove(&slot);
+    }
+}
+// tools
+impl CameraBookmarks {
+    /// 数字键对应的 slot
+    const DIGIT_KEYS: [(KeyCode, u32); 9] = [
+        (KeyCode::Digit1, 1),
+        (KeyCode::Digit2, 2),
+        (KeyCode::Digit3, 3),
+        (KeyCode::Digit4, 4),
+        (KeyCode::Digit5, 5),
+        (KeyCode::Digit6, 6),
+        (KeyCode::Digit7, 7),
+        (KeyCode::Digit8, 8),
+        (KeyCode::Digit9, 9),
+    ];
+
+    /// 数字键读取，Ctrl + 数字键保存；返回书签是否被修改
+    pub fn update_shortcuts(&mut self, input_state: &InputState, camera_controller: &mut CameraController) -> bool {
+        let ctrl_pressed =
+            input_state.is_key_pressed(KeyCode::ControlLeft) || input_state.is_key_pressed(KeyCode::ControlRight);
+
+        let mut changed = false;
+        for (key, slot) in Self::DIGIT_KEYS {
+            if !input_state.keys_triggered.contains(&key) {
+                continue;
+            }
+            if ctrl_pressed {
+                self.save_bookmark(slot, camera_controller);
+                log::info!("camera bookmark {} saved", slot);
+                changed = true;
+            } else if !self.load_bookmark(slot, camera_controller) {
+                log::warn!("camera bookmark {} is empty", slot);
+            }
+        }
+        changed
+    }
+
+    /// 书签列表，返回书签是否被修改
+    pub fn draw_ui(&mut self, ui: &imgui::Ui, camera_controller: &mut CameraController) -> bool {
+        let mut changed = false;
+        let mut removed_slot = None;
+        for (&slot, bookmark) in &self.slots {
+            let _id = ui.push_id(format!("bookmark_{}", slot));
+            if ui.small_button("Go") {
+                camera_controller.load_bookmark(bookmark);
+            }
+            ui.same_line();
+            if ui.small_button("X") {
+                removed_slot = Some(slot);
+            }
+            ui.same_line();
+            ui.text(format!(
+                "{}: ({:.1}, {:.1}, {:.1}) yaw {:.0} pitch {:.0}",
+                slot,
+                bookmark.position.x,
+                bookmark.position.y,
+                bookmark.position.z,
+                bookmark.euler_yaw_deg,
+                bookmark.euler_pitch_deg
+            ));
+        }
+        if let Some(slot) = removed_slot {
+            self.remove(slot);
+            changed = true;
+        }
+
+        ui.separator();
+        if ui.button("Save Current View") {
+            self.save_bookmark(self.next_free_slot(), camera_controller);
+            changed = true;
+        }
+        ui.slider("Transition (s)", 0.0, 2.0, &mut camera_controller.bookmark_transition_s);
+        ui.text_disabled("1~9: load, Ctrl + 1~9: save");
+
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use truvis_renderer::platform::camera::ProjectionMode;
+
+    #[test]
+    fn test_toml_round_trip() {
+        #[derive(Serialize, Deserialize)]
+        struct Wrapper {
+            camera_bookmarks: CameraBookmarks,
+        }
+
+        let content = r#"
+            [camera_bookmarks.1]
+            position = [0.0, 2.0, 10.0]
+            euler_yaw_deg = 90.0
+            euler_pitch_deg = -10.0
+            projection_mode = { type = "perspective", fov_y_deg = 45.0 }
+
+            [camera_bookmarks.3]
+            position = [1.0, 1.0, 1.0]
+            euler_yaw_deg = 0.0
+            euler_pitch_deg = 0.0
+            euler_roll_deg = 5.0
+            projection_mode = { type = "orthographic", height = 20.0 }
+
+            [camera_bookmarks.invalid]
+            position = [0.0, 0.0, 0.0]
+            euler_yaw_deg = 0.0
+            euler_pitch_deg = 0.0
+            projection_mode = { type = "perspective", fov_y_deg = 60.0 }
+        "#;
+        let bookmarks = toml::from_str::<Wrapper>(content).unwrap().camera_bookmarks;
+        assert_eq!(bookmarks.slots.keys().copied().collect::<Vec<_>>(), vec![1, 3]);
+        let bookmark = bookmarks.get(1).unwrap();
+        assert_eq!(bookmark.position, glam::vec3(0.0, 2.0, 10.0));
+        assert_eq!(bookmark.euler_roll_deg, 0.0);
+        assert_eq!(bookmark.projection_mode, ProjectionMode::Perspective { fov_y_deg: 45.0 });
+        assert_eq!(bookmarks.get(3).unwrap().projection_mode, ProjectionMode::Orthographic { height: 20.0 });
+        assert_eq!(bookmarks.next_free_slot(), 2);
+
+        let saved = toml::to_string(&Wrapper {
+            camera_bookmarks: bookmarks.clone(),
+        })
+        .unwrap();
+        assert_eq!(toml::from_str::<Wrapper>(&saved).unwrap().camera_bookmarks, bookmarks);
+    }
+}
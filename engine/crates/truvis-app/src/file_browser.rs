@@ -0,0 +1,141 @@
+use std::path::{Path, PathBuf};
+
+use truvis_crate_tools::resource::TruvisPath;
+
+/// imgui 弹窗形式的简单文件浏览器，只列出子目录以及满足过滤条件的文件
+///
+/// 点击目录进入该目录，点击 `..` 返回上一级，点击文件之后关闭弹窗并返回该文件的路径。
+/// 多次打开时保留上一次浏览的目录
+pub struct FileBrowser {
+    /// 当前浏览的目录
+    dir: PathBuf,
+    /// 只显示满足条件的文件，目录总是显示
+    file_filter: fn(&Path) -> bool,
+}
+
+/// 目录中的一项
+struct FileEntry {
+    path: PathBuf,
+    is_dir: bool,
+}
+// new & init
+impl FileBrowser {
+    pub fn new(dir: PathBuf, file_filter: fn(&Path) -> bool) -> Self {
+        Self { dir, file_filter }
+    }
+
+    /// 选择贴图文件，从 `resources/` 目录开始浏览
+    pub fn texture_browser() -> Self {
+        Self::new(TruvisPath::workspace_path().join("resources"), is_texture_file)
+    }
+}
+// tools
+impl FileBrowser {
+    const SIZE: [f32; 2] = [420.0, 260.0];
+
+    /// 打开弹窗，需要与 [`Self::draw`] 处于同一个 id 栈中
+    pub fn open(&self, ui: &imgui::Ui, popup_id: &str) {
+        ui.open_popup(popup_id);
+    }
+
+    /// 每帧调用，选中文件时返回文件的路径并关闭弹窗
+    pub fn draw(&mut self, ui: &imgui::Ui, popup_id: &str) -> Option<PathBuf> {
+        let mut picked = None;
+        let mut next_dir = None;
+        ui.popup(popup_id, || {
+            ui.text(self.dir.display().to_string());
+            ui.separator();
+            ui.child_window("##file_entries").size(Self::SIZE).build(|| {
+                if let Some(parent) = self.dir.parent()
+                    && ui.selectable("..")
+                {
+                    next_dir = Some(parent.to_path_buf());
+                }
+                for entry in Self::list_entries(&self.dir, self.file_filter) {
+                    let name = entry.path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+                    if entry.is_dir {
+                        if ui.selectable(format!("{name}/")) {
+                            next_dir = Some(entry.path);
+                        }
+                    } else if ui.selectable(&name) {
+                        picked = Some(entry.path);
+                        ui.close_current_popup();
+                    }
+                }
+            });
+        });
+        if let Some(dir) = next_dir {
+            self.dir = dir;
+        }
+        picked
+    }
+
+    /// 目录中的子目录以及满足条件的文件，目录在前，各自按名字排序；目录无法读取时返回空
+    fn list_entries(dir: &Path, file_filter: fn(&Path) -> bool) -> Vec<FileEntry> {
+        let Ok(read_dir) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut entries = read_dir
+            .filter_map(|entry| entry.ok())
+            .map(|entry| {
+                let path = entry.path();
+                FileEntry {
+                    is_dir: path.is_dir(),
+                    path,
+                }
+            })
+            .filter(|entry| entry.is_dir || file_filter(&entry.path))
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.path.cmp(&b.path)));
+        entries
+    }
+}
+
+/// `AssetHub` 可以加载的贴图：image crate 支持的格式以及 ktx2
+pub fn is_texture_file(path: &Path) -> bool {
+    let is_ktx2 = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("ktx2"));
+    is_ktx2 || image::ImageFormat::from_path(path).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_texture_file() {
+        assert!(is_texture_file(Path::new("a/b.png")));
+        assert!(is_texture_file(Path::new("b.JPG")));
+        assert!(is_texture_file(Path::new("b.ktx2")));
+        assert!(!is_texture_file(Path::new("b.gltf")));
+        assert!(!is_texture_file(Path::new("b")));
+    }
+
+    #[test]
+    fn test_list_entries_dirs_first_and_filtered() {
+        let dir = std::env::temp_dir().join(format!("truvis-file-browser-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("textures")).unwrap();
+        std::fs::create_dir_all(dir.join("a_models")).unwrap();
+        for file in ["b.png", "a.ktx2", "scene.gltf"] {
+            std::fs::write(dir.join(file), b"").unwrap();
+        }
+
+        let entries = FileBrowser::list_entries(&dir, is_texture_file);
+        let names = entries
+            .iter()
+            .map(|entry| (entry.path.file_name().unwrap().to_str().unwrap(), entry.is_dir))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                ("a_models", true),
+                ("textures", true),
+                ("a.ktx2", false),
+                ("b.png", false)
+            ]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(FileBrowser::list_entries(&dir, is_texture_file).is_empty());
+    }
+}
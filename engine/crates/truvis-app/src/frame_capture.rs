@@ -0,0 +1,104 @@
+//! 截图：将当前帧的 present image 保存为 PNG
+//!
+//! 与 [`truvis_renderer::frame_dump`] 不同，这里保存的是最终呈现在窗口上的结果（包含 GUI），
+//! 只用于调试时快速保存画面。
+
+use std::path::Path;
+
+use ash::vk;
+use truvis_renderer::renderer::Renderer;
+
+pub struct FrameCapture;
+
+impl FrameCapture {
+    /// 读回当前帧的 present image 并写入 `path`
+    ///
+    /// 需要在该帧的命令提交之后、present 之前调用；内部会等待 GPU 执行完毕，开销较大，只适合手动触发
+    pub fn capture(renderer: &Renderer, path: impl AsRef<Path>) -> Result<(), String> {
+        let _span = tracy_client::span!("FrameCapture::capture");
+
+        let render_present = renderer.render_present.as_ref().ok_or("no window to capture")?;
+        let swapchain = render_present.swapchain.as_ref().ok_or("swapchain is not created")?;
+        if !swapchain.support_read_back() {
+            return Err("swapchain images do not support TRANSFER_SRC".to_string());
+        }
+
+        let (image_handle, _) = render_present.current_image_and_view();
+        let image = renderer.render_context.gfx_resource_manager.get_image(image_handle).unwrap();
+        if !Self::is_supported_format(image.format()) {
+            return Err(format!("unsupported swapchain format: {:?}", image.format()));
+        }
+
+        // render graph 执行之后会把 present image 的最终状态（PRESENT_SRC_KHR）同步到 GfxImage 上
+        let layout = image.state().layout;
+        debug_assert_eq!(layout, vk::ImageLayout::PRESENT_SRC_KHR);
+        let data = image.read_back(layout);
+        let pixels = Self::to_rgba8(image.format(), data);
+        let rgba_image = image::RgbaImage::from_raw(image.width(), image.height(), pixels).unwrap();
+        rgba_image.save(path.as_ref()).map_err(|e| e.to_string())?;
+
+        log::info!("frame captured to {}", path.as_ref().display());
+        Ok(())
+    }
+}
+
+// tools
+impl FrameCapture {
+    #[inline]
+    fn is_bgra(format: vk::Format) -> bool {
+        matches!(format, vk::Format::B8G8R8A8_UNORM | vk::Format::B8G8R8A8_SRGB)
+    }
+
+    #[inline]
+    fn is_supported_format(format: vk::Format) -> bool {
+        Self::is_bgra(format) || matches!(format, vk::Format::R8G8B8A8_UNORM | vk::Format::R8G8B8A8_SRGB)
+    }
+
+    /// 将 present image 的像素转换为 RGBA8
+    ///
+    /// SRGB format 中存储的已经是 gamma 编码之后的值，与 PNG 的约定一致；UNORM format 的值会被直接显示，
+    /// 因此两者都原样拷贝，保证截图与窗口上看到的一致。BGRA 需要交换 R/B 通道，
+    /// alpha 对于 present image 没有意义，统一写为不透明
+    fn to_rgba8(format: vk::Format, mut data: Vec<u8>) -> Vec<u8> {
+        debug_assert!(Self::is_supported_format(format));
+
+        let is_bgra = Self::is_bgra(format);
+        for pixel in data.chunks_exact_mut(4) {
+            if is_bgra {
+                pixel.swap(0, 2);
+            }
+            pixel[3] = u8::MAX;
+        }
+        data
+    }
+
+    /// 截图的默认文件名，带有本地时间戳，例如 `capture-20250101-120000-123.png`
+    pub fn file_name_with_timestamp() -> String {
+        format!("capture-{}.png", chrono::Local::now().format("%Y%m%d-%H%M%S-%3f"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_rgba8_bgra() {
+        let data = vec![10, 20, 30, 0, 40, 50, 60, 128];
+        assert_eq!(FrameCapture::to_rgba8(vk::Format::B8G8R8A8_SRGB, data), vec![30, 20, 10, 255, 60, 50, 40, 255]);
+    }
+
+    #[test]
+    fn test_to_rgba8_rgba() {
+        let data = vec![10, 20, 30, 0];
+        assert_eq!(FrameCapture::to_rgba8(vk::Format::R8G8B8A8_UNORM, data), vec![10, 20, 30, 255]);
+    }
+
+    #[test]
+    fn test_supported_format() {
+        assert!(FrameCapture::is_supported_format(vk::Format::B8G8R8A8_UNORM));
+        assert!(FrameCapture::is_supported_format(vk::Format::R8G8B8A8_SRGB));
+        assert!(!FrameCapture::is_supported_format(vk::Format::A2B10G10R10_UNORM_PACK32));
+        assert!(!FrameCapture::is_supported_format(vk::Format::R16G16B16A16_SFLOAT));
+    }
+}
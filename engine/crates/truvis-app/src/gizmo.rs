@@ -0,0 +1,449 @@
+//! 选中物体的变换手柄
+//!
+//! 与 ImGuizmo 类似，手柄使用 imgui 的 draw list 绘制在场景之上，拖动时将新的变换写回场景：
+//! - 平移、缩放：求鼠标射线与轴的最近点，按最近点在轴上的移动量修改变换
+//! - 旋转：求鼠标射线与垂直于轴的平面的交点，按交点绕轴转过的角度修改变换
+
+use truvis_render_graph::render_context::RenderContext;
+use truvis_renderer::platform::camera::Camera;
+use truvis_scene::guid_new_type::InstanceHandle;
+
+use crate::platform::input_map::Action;
+use crate::platform::input_state::InputState;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GizmoMode {
+    #[default]
+    Translate,
+    Rotate,
+    Scale,
+}
+
+/// 手柄的轴向，缩放总是使用物体自身的轴
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GizmoSpace {
+    #[default]
+    World,
+    Local,
+}
+
+/// 相机的投影信息，用于世界空间与屏幕像素坐标之间的转换
+#[derive(Clone, Copy)]
+struct GizmoView {
+    view_proj: glam::Mat4,
+    inv_view_proj: glam::Mat4,
+    screen_size: glam::Vec2,
+    /// 参见 `CameraConvention::ndc_y_sign`
+    ndc_y_sign: f32,
+}
+// new & init
+impl GizmoView {
+    fn new(view_proj: glam::Mat4, screen_size: glam::Vec2, ndc_y_sign: f32) -> Self {
+        Self {
+            view_proj,
+            inv_view_proj: view_proj.inverse(),
+            screen_size,
+            ndc_y_sign,
+        }
+    }
+}
+// tools
+impl GizmoView {
+    /// 世界空间的点投影到屏幕像素坐标，位于相机后方时返回 None
+    fn world_to_screen(&self, pos: glam::Vec3) -> Option<glam::Vec2> {
+        let clip = self.view_proj * pos.extend(1.0);
+        if clip.w <= 1e-6 {
+            return None;
+        }
+        let ndc = clip.truncate() / clip.w;
+        Some(glam::vec2(
+            (ndc.x + 1.0) * 0.5 * self.screen_size.x,
+            (1.0 - ndc.y * self.ndc_y_sign) * 0.5 * self.screen_size.y,
+        ))
+    }
+
+    /// 屏幕像素坐标对应的世界空间射线，返回射线上的一点以及单位方向
+    fn screen_ray(&self, pos: glam::Vec2) -> (glam::Vec3, glam::Vec3) {
+        let ndc_x = pos.x / self.screen_size.x * 2.0 - 1.0;
+        let ndc_y = (1.0 - pos.y / self.screen_size.y * 2.0) * self.ndc_y_sign;
+        // 深度取 (0, 1) 内的两个值，对 reversed-z 以及无限远的透视投影同样有效
+        let p0 = self.inv_view_proj.project_point3(glam::vec3(ndc_x, ndc_y, 0.25));
+        let p1 = self.inv_view_proj.project_point3(glam::vec3(ndc_x, ndc_y, 0.75));
+        (p0, (p1 - p0).normalize())
+    }
+
+    /// `center` 处屏幕上 `pixels` 个像素对应的世界空间长度，用于保持手柄在屏幕上的大小不变
+    fn world_size(&self, center: glam::Vec3, pixels: f32) -> f32 {
+        let clip = self.view_proj * center.extend(1.0);
+        let ndc = clip.truncate() / clip.w;
+        let offset = glam::vec3(2.0 * pixels / self.screen_size.x, 0.0, 0.0);
+        self.inv_view_proj.project_point3(ndc + offset).distance(center)
+    }
+}
+
+/// 直线 `origin + axis * t` 上距离射线所在直线最近的点的参数 t，`axis` 与 `ray_dir` 需要是单位向量
+///
+/// 两者接近平行时返回 None
+fn closest_axis_param(
+    origin: glam::Vec3,
+    axis: glam::Vec3,
+    ray_origin: glam::Vec3,
+    ray_dir: glam::Vec3,
+) -> Option<f32> {
+    let w = origin - ray_origin;
+    let b = axis.dot(ray_dir);
+    let denom = 1.0 - b * b;
+    if denom < 1e-4 {
+        return None;
+    }
+    Some((b * ray_dir.dot(w) - axis.dot(w)) / denom)
+}
+
+/// 射线与过 `center`、法线为 `axis` 的平面的交点绕 `axis` 的角度（右手定则），射线与平面接近平行时返回 None
+fn plane_angle(center: glam::Vec3, axis: glam::Vec3, ray_origin: glam::Vec3, ray_dir: glam::Vec3) -> Option<f32> {
+    let denom = axis.dot(ray_dir);
+    if denom.abs() < 1e-4 {
+        return None;
+    }
+    let t = axis.dot(center - ray_origin) / denom;
+    let offset = ray_origin + ray_dir * t - center;
+
+    let (u, v) = plane_basis(axis);
+    Some(offset.dot(v).atan2(offset.dot(u)))
+}
+
+/// 垂直于 `axis` 的平面内的一组正交基，满足 `u × v = axis`
+fn plane_basis(axis: glam::Vec3) -> (glam::Vec3, glam::Vec3) {
+    let u = axis.any_orthonormal_vector();
+    (u, axis.cross(u))
+}
+
+/// 点到线段的距离
+fn distance_to_segment(p: glam::Vec2, a: glam::Vec2, b: glam::Vec2) -> f32 {
+    let ab = b - a;
+    let t = if ab.length_squared() > 0.0 { ((p - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0) } else { 0.0 };
+    p.distance(a + ab * t)
+}
+
+/// 正在拖动的手柄
+struct GizmoDrag {
+    instance: InstanceHandle,
+    mode: GizmoMode,
+    axis_idx: usize,
+    axis: glam::Vec3,
+    start_transform: glam::Mat4,
+    /// 开始拖动时鼠标在轴上的参数，旋转时为绕轴的角度
+    start_value: f32,
+}
+
+/// 选中 instance 的变换手柄
+///
+/// 选中项为 `RenderContext::selected_instance`，修改通过 `SceneManager::set_instance_transform` 写回场景
+#[derive(Default)]
+pub struct Gizmo {
+    pub mode: GizmoMode,
+    pub space: GizmoSpace,
+
+    /// 鼠标悬停的轴，0、1、2 分别为 x、y、z
+    hovered_axis: Option<usize>,
+    drag: Option<GizmoDrag>,
+}
+// getter
+impl Gizmo {
+    /// 鼠标悬停在手柄上或者正在拖动，此时场景不应该响应鼠标（拾取、相机）
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        self.hovered_axis.is_some() || self.drag.is_some()
+    }
+}
+// tools
+impl Gizmo {
+    /// 手柄在屏幕上的长度
+    const SIZE_PX: f32 = 100.0;
+    /// 鼠标距离手柄小于该值时视为悬停
+    const PICK_PX: f32 = 8.0;
+    /// 旋转手柄的圆环分段数
+    const CIRCLE_SEGMENTS: usize = 48;
+    /// 缩放的下限，避免变换矩阵退化
+    const MIN_SCALE: f32 = 0.001;
+
+    const AXIS_COLORS: [[f32; 4]; 3] = [[0.9, 0.2, 0.2, 1.0], [0.2, 0.9, 0.2, 1.0], [0.2, 0.4, 1.0, 1.0]];
+    const ACTIVE_COLOR: [f32; 4] = [1.0, 0.9, 0.1, 1.0];
+
+    /// 快捷键切换模式，默认为 G 平移、R 旋转、T 缩放
+    ///
+    /// 按住右键旋转相机时不响应，避免拖动视角时误触
+    pub fn update_shortcuts(&mut self, input_state: &InputState) {
+        if input_state.is_right_button_pressed() {
+            return;
+        }
+        for (action, mode) in [
+            (Action::GizmoTranslate, GizmoMode::Translate),
+            (Action::GizmoRotate, GizmoMode::Rotate),
+            (Action::GizmoScale, GizmoMode::Scale),
+        ] {
+            if input_state.is_action_triggered(action) {
+                self.mode = mode;
+            }
+        }
+    }
+
+    /// 模式与空间的设置
+    pub fn draw_settings_ui(&mut self, ui: &imgui::Ui) {
+        let mut mode_idx = self.mode as usize;
+        if ui.combo_simple_string("Gizmo Mode", &mut mode_idx, &["Translate", "Rotate", "Scale"]) {
+            self.mode = [GizmoMode::Translate, GizmoMode::Rotate, GizmoMode::Scale][mode_idx];
+        }
+        let mut space_idx = self.space as usize;
+        if ui.combo_simple_string("Gizmo Space", &mut space_idx, &["World", "Local"]) {
+            self.space = [GizmoSpace::World, GizmoSpace::Local][space_idx];
+        }
+    }
+
+    /// 处理鼠标交互并绘制手柄，需要在 imgui 的帧内调用
+    pub fn draw_ui(&mut self, ui: &imgui::Ui, camera: &Camera, render_context: &mut RenderContext) {
+        let Some((handle, transform)) = render_context.selected_instance.and_then(|handle| {
+            render_context.scene_manager.get_instance(handle).map(|instance| (handle, instance.transform))
+        }) else {
+            self.hovered_axis = None;
+            self.drag = None;
+            return;
+        };
+
+        let screen_size = glam::Vec2::from(ui.io().display_size);
+        let view = GizmoView::new(
+            camera.get_projection_matrix() * camera.get_view_matrix(),
+            screen_size,
+            camera.convention.ndc_y_sign(),
+        );
+        let center = transform.w_axis.truncate();
+        if screen_size.min_element() <= 0.0 || view.world_to_screen(center).is_none() {
+            self.hovered_axis = None;
+            self.drag = None;
+            return;
+        }
+
+        let mouse = glam::Vec2::from(ui.io().mouse_pos);
+        if let Some(drag) = &self.drag {
+            // 拖动过程中选中项被切换时取消拖动
+            if !ui.is_mouse_down(imgui::MouseButton::Left) || drag.instance != handle {
+                self.drag = None;
+            } else if let Some(new_transform) = Self::drag_transform(drag, &view, mouse)
+                && new_transform != transform
+            {
+                render_context.scene_manager.set_instance_transform(handle, new_transform);
+                render_context.accum_data.reset();
+            }
+        } else {
+            // 鼠标在 imgui 窗口上时不响应
+            let axes = self.axes(&transform);
+            let length = view.world_size(center, Self::SIZE_PX);
+            self.hovered_axis =
+                if ui.io().want_capture_mouse { None } else { self.pick_axis(&view, center, &axes, length, mouse) };
+            if let Some(axis_idx) = self.hovered_axis
+                && ui.is_mouse_clicked(imgui::MouseButton::Left)
+            {
+                self.drag = self.begin_drag(&view, handle, &transform, axes[axis_idx], axis_idx, mouse);
+            }
+        }
+
+        // 拖动时使用更新之后的变换绘制
+        let transform = render_context.scene_manager.get_instance(handle).unwrap().transform;
+        self.draw(ui, &view, &transform);
+    }
+
+    /// 手柄的三个轴向，均为单位向量
+    fn axes(&self, transform: &glam::Mat4) -> [glam::Vec3; 3] {
+        let world = [glam::Vec3::X, glam::Vec3::Y, glam::Vec3::Z];
+        if self.space == GizmoSpace::World && self.mode != GizmoMode::Scale {
+            return world;
+        }
+        let local = [transform.x_axis, transform.y_axis, transform.z_axis];
+        std::array::from_fn(|idx| local[idx].truncate().try_normalize().unwrap_or(world[idx]))
+    }
+
+    /// 旋转手柄的圆环上的点
+    fn circle_points(center: glam::Vec3, axis: glam::Vec3, radius: f32) -> impl Iterator<Item = glam::Vec3> {
+        let (u, v) = plane_basis(axis);
+        (0..=Self::CIRCLE_SEGMENTS).map(move |idx| {
+            let angle = idx as f32 / Self::CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+            center + (u * angle.cos() + v * angle.sin()) * radius
+        })
+    }
+
+    /// 手柄在屏幕上的折线，平移与缩放为一条线段，旋转为圆环
+    fn handle_polyline(&self, view: &GizmoView, center: glam::Vec3, axis: glam::Vec3, length: f32) -> Vec<glam::Vec2> {
+        match self.mode {
+            GizmoMode::Translate | GizmoMode::Scale => {
+                [center, center + axis * length].into_iter().filter_map(|p| view.world_to_screen(p)).collect()
+            }
+            GizmoMode::Rotate => {
+                Self::circle_points(center, axis, length).filter_map(|p| view.world_to_screen(p)).collect()
+            }
+        }
+    }
+
+    fn pick_axis(
+        &self,
+        view: &GizmoView,
+        center: glam::Vec3,
+        axes: &[glam::Vec3; 3],
+        length: f32,
+        mouse: glam::Vec2,
+    ) -> Option<usize> {
+        axes.iter()
+            .enumerate()
+            .filter_map(|(idx, &axis)| {
+                let polyline = self.handle_polyline(view, center, axis, length);
+                let distance = polyline
+                    .windows(2)
+                    .map(|segment| distance_to_segment(mouse, segment[0], segment[1]))
+                    .fold(f32::MAX, f32::min);
+                (distance < Self::PICK_PX).then_some((idx, distance))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(idx, _)| idx)
+    }
+
+    fn begin_drag(
+        &self,
+        view: &GizmoView,
+        instance: InstanceHandle,
+        transform: &glam::Mat4,
+        axis: glam::Vec3,
+        axis_idx: usize,
+        mouse: glam::Vec2,
+    ) -> Option<GizmoDrag> {
+        let center = transform.w_axis.truncate();
+        let (ray_origin, ray_dir) = view.screen_ray(mouse);
+        let start_value = match self.mode {
+            GizmoMode::Translate => closest_axis_param(center, axis, ray_origin, ray_dir)?,
+            // 缩放比例为当前参数与初始参数之比，初始参数不能为 0
+            GizmoMode::Scale => {
+                closest_axis_param(center, axis, ray_origin, ray_dir).filter(|param| param.abs() > 1e-4)?
+            }
+            GizmoMode::Rotate => plane_angle(center, axis, ray_origin, ray_dir)?,
+        };
+        Some(GizmoDrag {
+            instance,
+            mode: self.mode,
+            axis_idx,
+            axis,
+            start_transform: *transform,
+            start_value,
+        })
+    }
+
+    /// 根据当前鼠标位置计算拖动之后的变换，无法求交时返回 None
+    fn drag_transform(drag: &GizmoDrag, view: &GizmoView, mouse: glam::Vec2) -> Option<glam::Mat4> {
+        let center = drag.start_transform.w_axis.truncate();
+        let (ray_origin, ray_dir) = view.screen_ray(mouse);
+        match drag.mode {
+            GizmoMode::Translate => {
+                let param = closest_axis_param(center, drag.axis, ray_origin, ray_dir)?;
+                Some(glam::Mat4::from_translation(drag.axis * (param - drag.start_value)) * drag.start_transform)
+            }
+            GizmoMode::Scale => {
+                let param = closest_axis_param(center, drag.axis, ray_origin, ray_dir)?;
+                let mut scale = glam::Vec3::ONE;
+                scale[drag.axis_idx] = (param / drag.start_value).max(Self::MIN_SCALE);
+                Some(drag.start_transform * glam::Mat4::from_scale(scale))
+            }
+            GizmoMode::Rotate => {
+                let angle = plane_angle(center, drag.axis, ray_origin, ray_dir)?;
+                let rotation = glam::Mat4::from_axis_angle(drag.axis, angle - drag.start_value);
+                Some(
+                    glam::Mat4::from_translation(center)
+                        * rotation
+                        * glam::Mat4::from_translation(-center)
+                        * drag.start_transform,
+                )
+            }
+        }
+    }
+
+    fn draw(&self, ui: &imgui::Ui, view: &GizmoView, transform: &glam::Mat4) {
+        let center = transform.w_axis.truncate();
+        let axes = match &self.drag {
+            // 拖动过程中轴向保持开始拖动时的方向
+            Some(drag) => self.axes(&drag.start_transform),
+            None => self.axes(transform),
+        };
+        let length = view.world_size(center, Self::SIZE_PX);
+        let active_axis = self.drag.as_ref().map(|drag| drag.axis_idx).or(self.hovered_axis);
+
+        let draw_list = ui.get_background_draw_list();
+        for (idx, &axis) in axes.iter().enumerate() {
+            let color = if active_axis == Some(idx) { Self::ACTIVE_COLOR } else { Self::AXIS_COLORS[idx] };
+            let polyline = self.handle_polyline(view, center, axis, length);
+            if polyline.len() < 2 {
+                continue;
+            }
+            let points = polyline.iter().map(|p| p.to_array()).collect::<Vec<_>>();
+            draw_list.add_polyline(points, color).thickness(2.0).build();
+
+            // 平移手柄的末端为圆点，缩放手柄的末端为方块
+            let end = polyline[polyline.len() - 1];
+            match self.mode {
+                GizmoMode::Translate => draw_list.add_circle(end.to_array(), 5.0, color).filled(true).build(),
+                GizmoMode::Scale => draw_list
+                    .add_rect(
+                        (end - glam::Vec2::splat(4.0)).to_array(),
+                        (end + glam::Vec2::splat(4.0)).to_array(),
+                        color,
+                    )
+                    .filled(true)
+                    .build(),
+                GizmoMode::Rotate => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_view() -> GizmoView {
+        let proj = glam::Mat4::perspective_rh(60f32.to_radians(), 1.0, 0.1, 100.0);
+        let view = glam::Mat4::look_at_rh(glam::vec3(0.0, 0.0, 5.0), glam::Vec3::ZERO, glam::Vec3::Y);
+        GizmoView::new(proj * view, glam::vec2(800.0, 800.0), -1.0)
+    }
+
+    #[test]
+    fn test_screen_ray_round_trip() {
+        let view = test_view();
+        let pos = glam::vec3(0.5, -0.3, 1.0);
+        let screen = view.world_to_screen(pos).unwrap();
+
+        // 射线经过原来的点
+        let (origin, dir) = view.screen_ray(screen);
+        let to_pos = pos - origin;
+        assert!((to_pos - dir * to_pos.dot(dir)).length() < 1e-3);
+
+        // 位于相机后方的点无法投影
+        assert!(view.world_to_screen(glam::vec3(0.0, 0.0, 10.0)).is_none());
+    }
+
+    #[test]
+    fn test_closest_axis_param() {
+        // 射线沿 -z 经过 (2, 0, 0)，与 x 轴最近的点为 x = 2
+        let param = closest_axis_param(glam::Vec3::ZERO, glam::Vec3::X, glam::vec3(2.0, 1.0, 5.0), -glam::Vec3::Z);
+        assert!((param.unwrap() - 2.0).abs() < 1e-5);
+
+        // 平行时无解
+        assert!(closest_axis_param(glam::Vec3::ZERO, glam::Vec3::X, glam::Vec3::Y, glam::Vec3::X).is_none());
+    }
+
+    #[test]
+    fn test_plane_angle_follows_right_hand_rule() {
+        let axis = glam::Vec3::Z;
+        let (u, _) = plane_basis(axis);
+        let angle0 = plane_angle(glam::Vec3::ZERO, axis, u + glam::Vec3::Z, -glam::Vec3::Z).unwrap();
+
+        // u 绕 z 轴旋转 90° 之后，交点的角度增加 90°
+        let rotated = glam::Quat::from_axis_angle(axis, std::f32::consts::FRAC_PI_2) * u;
+        let angle1 = plane_angle(glam::Vec3::ZERO, axis, rotated + glam::Vec3::Z, -glam::Vec3::Z).unwrap();
+        assert!((angle1 - angle0 - std::f32::consts::FRAC_PI_2).abs() < 1e-5);
+    }
+}
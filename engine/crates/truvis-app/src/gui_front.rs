@@ -2,9 +2,6 @@ use crate::platform::input_event::{ElementState, InputEvent, MouseButton};
 use imgui::{DrawData, FontAtlasTexture, TextureId};
 use truvis_crate_tools::resource::TruvisPath;
 
-const FONT_TEXTURE_ID: usize = 0;
-const RENDER_IMAGE_ID: usize = 1;
-
 pub struct GuiHost {
     pub imgui_ctx: imgui::Context,
     pub hidpi_factor: f64,
@@ -38,7 +35,11 @@ impl GuiHost {
         }
     }
 
-    pub fn init_font(&mut self) -> (FontAtlasTexture<'_>, TextureId) {
+    /// 构建字体 atlas
+    ///
+    /// 返回的 atlas 需要交给 GuiBackend 注册为 bindless 纹理，
+    /// 之后通过 [`Self::set_font_texture_id`] 设置字体纹理对应的 `TextureId`
+    pub fn init_font(&mut self) -> FontAtlasTexture<'_> {
         let hidpi_factor = self.hidpi_factor;
         let font_size = (13.0 * hidpi_factor) as f32;
 
@@ -61,18 +62,18 @@ impl GuiHost {
             },
         ]);
 
-        let font_texture_id = imgui::TextureId::from(0);
-        self.imgui_ctx.fonts().tex_id = font_texture_id;
-
         let io = self.imgui_ctx.io_mut();
         // io.font_global_scale = (1.0 / hidpi_factor) as f32;
         io.font_global_scale = 1.0;
         io.config_flags |= imgui::ConfigFlags::DOCKING_ENABLE;
 
         let fonts = self.imgui_ctx.fonts();
-        let atlas_texture = fonts.build_rgba32_texture();
+        fonts.build_rgba32_texture()
+    }
 
-        (atlas_texture, imgui::TextureId::new(FONT_TEXTURE_ID))
+    #[inline]
+    pub fn set_font_texture_id(&mut self, font_tex_id: TextureId) {
+        self.imgui_ctx.fonts().tex_id = font_tex_id;
     }
 }
 // update
@@ -110,9 +111,13 @@ impl GuiHost {
         ui_func(ui);
     }
 
+    /// 带有 dock 布局的帧：左侧显示渲染结果，右侧放置设置
+    ///
+    /// `render_image` 为渲染结果对应的 `TextureId`，通过 `GuiBackend::texture_id` 由 render target 的 srv 得到
     pub fn new_frame_dock(
         &mut self,
         duration: std::time::Duration,
+        render_image: TextureId,
         ui_build_func_main: impl FnOnce(&imgui::Ui, [f32; 2]),
         ui_build_func_right: impl FnOnce(&imgui::Ui),
     ) {
@@ -218,8 +223,7 @@ impl GuiHost {
                     //     height: (window_size[1] * hidpi_factor) as u32,
                     // };
 
-                    imgui::Image::new(imgui::TextureId::new(RENDER_IMAGE_ID), [window_size[0], window_size[1]])
-                        .build(ui);
+                    imgui::Image::new(render_image, [window_size[0], window_size[1]]).build(ui);
 
                     ui_build_func_main(ui, window_size);
                 });
@@ -247,6 +251,18 @@ impl GuiHost {
         self.imgui_ctx.render();
     }
 
+    /// GUI 是否正在使用鼠标（鼠标悬停在窗口上或者正在拖动控件），需要在构建 GUI 之后调用
+    #[inline]
+    pub fn want_capture_mouse(&self) -> bool {
+        self.imgui_ctx.io().want_capture_mouse
+    }
+
+    /// GUI 是否正在使用键盘（例如正在编辑文本框），此时不应该响应快捷键
+    #[inline]
+    pub fn want_capture_keyboard(&self) -> bool {
+        self.imgui_ctx.io().want_capture_keyboard
+    }
+
     /// 确保之前调用过 compile_ui
     pub fn get_render_data(&self) -> &DrawData {
         unsafe { &*(imgui::sys::igGetDrawData() as *mut DrawData) }
@@ -0,0 +1,79 @@
+use crate::platform::input_event::KeyCode;
+use crate::platform::input_map::{Action, InputMap};
+use crate::platform::input_state::InputState;
+
+/// 按键绑定的编辑面板
+///
+/// imgui 不接收键盘事件，改键时从 `InputState::keys_triggered` 中读取下一个按下的按键，Esc 取消
+#[derive(Default)]
+pub struct InputMapEditor {
+    /// 正在等待按键的动作
+    capturing: Option<Action>,
+}
+// getter
+impl InputMapEditor {
+    /// 正在等待按键，此时不应该响应快捷键
+    #[inline]
+    pub fn is_capturing(&self) -> bool {
+        self.capturing.is_some()
+    }
+}
+// tools
+impl InputMapEditor {
+    /// 返回绑定是否被修改
+    pub fn draw_ui(&mut self, ui: &imgui::Ui, input_map: &mut InputMap, input_state: &InputState) -> bool {
+        let mut changed = self.capture_key(input_map, input_state);
+
+        for action in Action::ALL {
+            let _id = ui.push_id(action.name());
+            ui.text(action.label());
+            ui.same_line_with_pos(150.0);
+
+            // 点击已经绑定的按键以解绑
+            for key in input_map.keys(action).to_vec() {
+                if ui.small_button(key.display_name()) {
+                    input_map.unbind(action, key);
+                    changed = true;
+                }
+                ui.same_line();
+            }
+            if self.capturing == Some(action) {
+                ui.text_disabled("press a key...");
+            } else if ui.small_button("+") {
+                self.capturing = Some(action);
+            }
+        }
+
+        ui.separator();
+        if ui.button("Reset to Default") {
+            *input_map = InputMap::default();
+            self.capturing = None;
+            changed = true;
+        }
+        for (key, action_a, action_b) in input_map.conflicts() {
+            ui.text_colored(
+                [1.0, 0.4, 0.3, 1.0],
+                format!("{} is bound to both {} and {}", key.display_name(), action_a.label(), action_b.label()),
+            );
+        }
+
+        changed
+    }
+
+    /// 等待按键时，将当前帧按下的第一个按键绑定到动作上
+    fn capture_key(&mut self, input_map: &mut InputMap, input_state: &InputState) -> bool {
+        let Some(action) = self.capturing else {
+            return false;
+        };
+        let Some(&key) = input_state.keys_triggered.iter().find(|&&key| key != KeyCode::Other) else {
+            return false;
+        };
+
+        self.capturing = None;
+        if key == KeyCode::Escape {
+            return false;
+        }
+        input_map.bind(action, key);
+        true
+    }
+}
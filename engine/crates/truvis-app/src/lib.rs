@@ -3,8 +3,20 @@
 //! 提供基于 [`OuterApp`] trait 的应用开发模式，集成窗口系统、输入处理、GUI 等功能。
 //! 开发者只需实现 [`OuterApp`] trait，即可快速构建渲染应用。
 
+pub mod camera_bookmarks;
+pub mod file_browser;
+pub mod frame_capture;
+pub mod gizmo;
 pub mod gui_front;
+pub mod input_map_editor;
+pub mod material_editor;
+pub mod model_drop_loader;
 pub mod outer_app;
+pub mod perf_overlay;
 pub mod platform;
 pub mod render_app;
 pub mod render_pipeline;
+pub mod render_test;
+pub mod resource_stats_panel;
+pub mod scene_hierarchy;
+pub mod settings;
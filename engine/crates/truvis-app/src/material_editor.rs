@@ -0,0 +1,201 @@
+use std::path::{Path, PathBuf};
+
+use truvis_render_graph::render_context::RenderContext;
+use truvis_scene::components::material::{CullMode, Material};
+use truvis_scene::guid_new_type::MaterialHandle;
+
+use crate::file_browser::FileBrowser;
+
+/// 场景材质的编辑面板
+///
+/// 修改通过 `SceneManager::update_material` 写回场景，只会标记被修改的材质为脏，
+/// GpuScene 在下一帧只重新上传变化的材质。贴图路径修改之后会先加载贴图，
+/// 贴图就绪之前材质中的 bindless 索引为 fallback 纹理，就绪之后同样会触发该材质的重新上传。
+/// 贴图路径可以直接输入，也可以通过 [`FileBrowser`] 选择。
+///
+/// 选中的材质会写入 `RenderContext::highlight_material`，由 app 在 viewport 中高亮使用它的物体
+pub struct MaterialEditor {
+    selected: Option<MaterialHandle>,
+    /// 材质列表的过滤条件，按名字匹配，忽略大小写
+    filter: String,
+    /// 编辑中的贴图路径，点击 Apply 之后才会生效
+    diffuse_map_input: String,
+    normal_map_input: String,
+    /// 选择贴图文件，diffuse 与 normal 贴图共用，保留上一次浏览的目录
+    texture_browser: FileBrowser,
+}
+// new & init
+impl Default for MaterialEditor {
+    fn default() -> Self {
+        Self {
+            selected: None,
+            filter: String::new(),
+            diffuse_map_input: String::new(),
+            normal_map_input: String::new(),
+            texture_browser: FileBrowser::texture_browser(),
+        }
+    }
+}
+// tools
+impl MaterialEditor {
+    /// 材质列表的高度，材质很多时在列表内部滚动
+    const LIST_HEIGHT: f32 = 120.0;
+    const BROWSER_POPUP: &'static str = "texture_browser";
+
+    pub fn draw_ui(&mut self, ui: &imgui::Ui, render_context: &mut RenderContext) {
+        ui.input_text("Filter", &mut self.filter).hint("name").build();
+        self.draw_list(ui, render_context);
+
+        // 选中的材质可能已经不存在
+        let selected = self.selected.filter(|&handle| render_context.scene_manager.get_material(handle).is_some());
+        render_context.highlight_material = selected;
+        let Some(handle) = selected else {
+            ui.text("No material selected");
+            return;
+        };
+
+        ui.separator();
+        self.draw_params(ui, render_context, handle);
+    }
+
+    /// 材质列表，只显示名字匹配过滤条件的材质
+    fn draw_list(&mut self, ui: &imgui::Ui, render_context: &RenderContext) {
+        let mat_map = render_context.scene_manager.mat_map();
+        let filter = self.filter.to_lowercase();
+
+        ui.child_window("##material_list").size([0.0, Self::LIST_HEIGHT]).border(true).build(|| {
+            for (idx, (handle, mat)) in mat_map.iter().enumerate() {
+                if !Self::matches_filter(mat, &filter) {
+                    continue;
+                }
+                let label = Self::label(idx, mat);
+                if ui.selectable_config(&label).selected(self.selected == Some(handle)).build()
+                    && self.selected != Some(handle)
+                {
+                    self.selected = Some(handle);
+                    self.diffuse_map_input = mat.diffuse_map.clone();
+                    self.normal_map_input = mat.normal_map.clone();
+                }
+            }
+        });
+        ui.text(format!("{} materials", mat_map.len()));
+    }
+
+    fn draw_params(&mut self, ui: &imgui::Ui, render_context: &mut RenderContext, handle: MaterialHandle) {
+        let mat = render_context.scene_manager.get_material(handle).unwrap();
+
+        let mut base_color = mat.base_color.to_array();
+        let mut emissive = mat.emissive.truncate().to_array();
+        let mut metallic = mat.metallic;
+        let mut roughness = mat.roughness;
+        let mut opaque = mat.opaque;
+        let mut normal_scale = mat.normal_scale;
+        let mut two_sided = mat.cull_mode == CullMode::None;
+
+        let mut changed = false;
+        changed |= ui.color_edit4("Base Color", &mut base_color);
+        changed |= ui.color_edit3("Emissive", &mut emissive);
+        changed |= ui.slider("Metallic", 0.0, 1.0, &mut metallic);
+        changed |= ui.slider("Roughness", 0.0, 1.0, &mut roughness);
+        changed |= ui.slider("Opaque", 0.0, 1.0, &mut opaque);
+        changed |= ui.slider("Normal Scale", 0.0, 4.0, &mut normal_scale);
+        changed |= ui.checkbox("Two Sided", &mut two_sided);
+
+        // 贴图路径相对于工作目录，也可以是绝对路径；为空表示不使用贴图
+        let browser = &mut self.texture_browser;
+        for (label, input) in [
+            ("Diffuse Map", &mut self.diffuse_map_input),
+            ("Normal Map", &mut self.normal_map_input),
+        ] {
+            let _id = ui.push_id(label);
+            ui.input_text(label, input).build();
+            ui.same_line();
+            if ui.button("Browse") {
+                browser.open(ui, Self::BROWSER_POPUP);
+            }
+            if let Some(path) = browser.draw(ui, Self::BROWSER_POPUP) {
+                *input = path.to_string_lossy().into_owned();
+            }
+        }
+        let maps_changed = ui.button("Apply Maps")
+            && (self.diffuse_map_input != mat.diffuse_map || self.normal_map_input != mat.normal_map);
+
+        if !changed && !maps_changed {
+            return;
+        }
+
+        // 材质中引用的贴图路径需要先在 AssetHub 中登记
+        if maps_changed {
+            for (path, is_srgb) in [(&self.diffuse_map_input, true), (&self.normal_map_input, false)] {
+                if !path.is_empty() {
+                    render_context.asset_hub.load_texture(PathBuf::from(path), is_srgb);
+                }
+            }
+        }
+
+        let diffuse_map = self.diffuse_map_input.clone();
+        let normal_map = self.normal_map_input.clone();
+        render_context.scene_manager.update_material(handle, |mat| {
+            mat.base_color = glam::Vec4::from_array(base_color);
+            mat.emissive = glam::Vec3::from_array(emissive).extend(mat.emissive.w);
+            mat.metallic = metallic;
+            mat.roughness = roughness;
+            mat.opaque = opaque;
+            mat.normal_scale = normal_scale;
+            mat.cull_mode = if two_sided { CullMode::None } else { CullMode::Back };
+            if maps_changed {
+                mat.diffuse_map = diffuse_map;
+                mat.normal_map = normal_map;
+            }
+        });
+        render_context.accum_data.reset();
+    }
+
+    /// 列表中显示的名字：序号 + 材质名；材质没有名字时使用 diffuse 贴图的文件名
+    fn label(idx: usize, mat: &Material) -> String {
+        if !mat.name.is_empty() {
+            return format!("#{idx} {}", mat.name);
+        }
+        match Path::new(&mat.diffuse_map).file_name() {
+            Some(name) => format!("#{idx} {}", name.to_string_lossy()),
+            None => format!("#{idx}"),
+        }
+    }
+
+    /// 材质名包含过滤条件时匹配，忽略大小写；`filter` 需要已经转为小写，为空时匹配所有材质
+    fn matches_filter(mat: &Material, filter: &str) -> bool {
+        filter.is_empty() || mat.name.to_lowercase().contains(filter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn named(name: &str) -> Material {
+        Material {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_matches_filter_by_name() {
+        assert!(MaterialEditor::matches_filter(&named("Brick_Wall"), ""));
+        assert!(MaterialEditor::matches_filter(&named("Brick_Wall"), "brick"));
+        assert!(!MaterialEditor::matches_filter(&named("Brick_Wall"), "glass"));
+        // 没有名字的材质只在过滤条件为空时显示
+        assert!(!MaterialEditor::matches_filter(&named(""), "brick"));
+    }
+
+    #[test]
+    fn test_label_falls_back_to_diffuse_map() {
+        assert_eq!(MaterialEditor::label(3, &named("Glass")), "#3 Glass");
+        let unnamed = Material {
+            diffuse_map: "assets/textures/brick.png".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(MaterialEditor::label(1, &unnamed), "#1 brick.png");
+        assert_eq!(MaterialEditor::label(0, &Material::default()), "#0");
+    }
+}
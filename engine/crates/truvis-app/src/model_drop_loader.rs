@@ -0,0 +1,147 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+use truvis_render_graph::render_context::RenderContext;
+use truvis_renderer::model_loader::parsed_scene::{ParseProgress, ParsedScene};
+use truvis_renderer::model_loader::{self, ModelLoadOptions};
+use truvis_renderer::platform::camera::Camera;
+use truvis_scene::aabb::Aabb;
+
+/// 拖放到窗口中的模型文件，依次加入场景
+///
+/// - 文件在工作线程中解析，解析期间渲染不受影响，UI 上显示解析的进度；同一时刻只解析一个文件
+/// - 解析完成之后，mesh 的上传与 BLAS 的创建在渲染线程上完成；纹理由 `AssetHub` 在后台线程解码
+/// - 加载完成之后相机对准新模型的包围盒
+#[derive(Default)]
+pub struct ModelDropLoader {
+    /// 等待解析的文件
+    pending: VecDeque<PathBuf>,
+    /// 正在工作线程中解析的文件
+    parsing: Option<ParseTask>,
+    /// 本轮拖放中已经处理完成（包括加载失败）的文件数量，队列清空后归零
+    finished_cnt: usize,
+
+    /// 加载拖放的文件时使用的导入选项，使用默认值
+    load_options: ModelLoadOptions,
+}
+
+/// 在工作线程中解析的模型文件
+struct ParseTask {
+    model_file: PathBuf,
+    progress: Arc<ParseProgress>,
+    /// 工作线程解析完成之后发送结果；工作线程 panic 时 sender 被丢弃
+    receiver: Receiver<anyhow::Result<ParsedScene>>,
+}
+// new & init
+impl ParseTask {
+    fn spawn(model_file: PathBuf, options: ModelLoadOptions) -> Self {
+        let progress = Arc::new(ParseProgress::default());
+        let (sender, receiver) = mpsc::channel();
+        {
+            let model_file = model_file.clone();
+            let progress = progress.clone();
+            // 不需要 join：app 退出时丢弃 receiver，工作线程的发送结果被忽略
+            std::thread::Builder::new()
+                .name("model-parser".to_string())
+                .spawn(move || {
+                    let _span = tracy_client::span!("ModelDropLoader::parse");
+                    let _ = sender.send(model_loader::parse_any(&model_file, &options, &progress));
+                })
+                .expect("Failed to spawn model parser thread");
+        }
+
+        Self {
+            model_file,
+            progress,
+            receiver,
+        }
+    }
+}
+// update
+impl ModelDropLoader {
+    /// 不支持的格式以及不存在的文件直接忽略
+    pub fn enqueue(&mut self, model_file: PathBuf) {
+        if !model_loader::is_supported_model(&model_file) {
+            log::warn!(
+                "unsupported model file: {}, supported: {:?}",
+                model_file.display(),
+                model_loader::supported_extensions().collect::<Vec<_>>()
+            );
+            return;
+        }
+        if !model_file.is_file() {
+            log::error!("model file not found: {}", model_file.display());
+            return;
+        }
+        log::info!("model file dropped: {}", model_file.display());
+        self.pending.push_back(model_file);
+    }
+
+    /// 每帧在更新场景之前调用，返回这一帧是否加载了模型
+    ///
+    /// 解析完成的模型在这一帧注册到场景中，之后开始解析下一个文件。
+    /// 加载之后相机会对准新的模型，调用者需要丢弃时域算法的历史
+    pub fn update(&mut self, render_context: &mut RenderContext, camera: &mut Camera) -> bool {
+        let mut loaded = false;
+        if let Some(task) = self.parsing.take() {
+            match task.receiver.try_recv() {
+                Err(TryRecvError::Empty) => self.parsing = Some(task),
+                Ok(Ok(parsed_scene)) => {
+                    Self::register(&task.model_file, parsed_scene, render_context, camera);
+                    self.finished_cnt += 1;
+                    loaded = true;
+                }
+                Ok(Err(e)) => {
+                    log::error!("Failed to load model {}: {:#}", task.model_file.display(), e);
+                    self.finished_cnt += 1;
+                }
+                Err(TryRecvError::Disconnected) => {
+                    log::error!("model parser thread of {} exited unexpectedly", task.model_file.display());
+                    self.finished_cnt += 1;
+                }
+            }
+        }
+
+        if self.parsing.is_none() {
+            match self.pending.pop_front() {
+                Some(model_file) => self.parsing = Some(ParseTask::spawn(model_file, self.load_options)),
+                None => self.finished_cnt = 0,
+            }
+        }
+        loaded
+    }
+
+    fn register(model_file: &Path, parsed_scene: ParsedScene, render_context: &mut RenderContext, camera: &mut Camera) {
+        let _span = tracy_client::span!("ModelDropLoader::register");
+        let instances = parsed_scene.register(&mut render_context.scene_manager, &mut render_context.asset_hub);
+        log::info!("loaded {} instances from {}", instances.len(), model_file.display());
+
+        let scene_manager = &render_context.scene_manager;
+        let aabb = instances
+            .iter()
+            .filter_map(|&instance| scene_manager.instance_world_aabb(instance))
+            .fold(Aabb::EMPTY, |aabb, instance_aabb| aabb.union(&instance_aabb));
+        camera.frame_aabb(&aabb);
+    }
+}
+// tools
+impl ModelDropLoader {
+    /// 在 overlay 中显示正在解析的文件以及解析的进度
+    pub fn draw_status(&self, ui: &imgui::Ui) {
+        let Some(task) = self.parsing.as_ref() else {
+            return;
+        };
+        let total_cnt = self.finished_cnt + 1 + self.pending.len();
+        ui.text(format!(
+            "Loading model {}/{}: {}",
+            self.finished_cnt + 1,
+            total_cnt,
+            task.model_file.file_name().unwrap_or_default().to_string_lossy()
+        ));
+        imgui::ProgressBar::new(task.progress.fraction())
+            .overlay_text(format!("parsing {:.0}%", task.progress.fraction() * 100.0))
+            .build(ui);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::render_pipeline::overlay_pass::OverlayContext;
+use truvis_gfx::commands::command_buffer::GfxCommandBuffer;
 use truvis_gfx::commands::semaphore::GfxSemaphore;
 use truvis_renderer::platform::camera::Camera;
 use truvis_renderer::renderer::Renderer;
@@ -17,6 +19,49 @@ pub trait OuterApp {
     /// 渲染主逻辑（发生于 acquire_frame 之后，submit_frame 之前）
     fn draw(&self, renderer: &Renderer, gui_draw_data: &imgui::DrawData, fence: &GfxSemaphore);
 
+    /// 不创建窗口时的渲染主逻辑，结果写入 `renderer.render_context.fif_buffers` 中当前帧的 render target，
+    /// 不绘制 GUI，也不访问 swapchain；需要在渲染完成时 signal `fence` 的帧 timeline 值
+    ///
+    /// 用于 [`crate::render_test::run_headless`]，只有 [`Self::supports_offscreen`] 返回 true 时才会被调用；
+    /// 不支持 headless 渲染的应用不需要实现
+    fn draw_offscreen(&self, _renderer: &Renderer, _fence: &GfxSemaphore) {
+        unreachable!("draw_offscreen is called on an app without headless support")
+    }
+
+    /// 是否支持 headless 渲染，也就是实现了 [`Self::draw_offscreen`]，并且 [`Self::init`] 不会访问 swapchain
+    ///
+    /// [`crate::render_test::run_headless`] 在初始化之前检查，不支持时不会调用任何钩子
+    fn supports_offscreen(&self) -> bool {
+        false
+    }
+
+    /// 绘制自定义的全屏 overlay，例如 HUD、准星、调试文字（可选）
+    ///
+    /// 由渲染管线在场景 resolve 到 present image 之后、GUI 之前调用，
+    /// image layout 与 render state 的约定见 [`OverlayContext`]
+    fn draw_overlay(&self, _cmd: &GfxCommandBuffer, _ctx: &OverlayContext) {}
+
+    /// 追加到左上角 overlay 中的状态文字（可选），例如当前生效的渲染选项
+    fn overlay_text(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// 鼠标左键单击场景（可选），GUI 使用鼠标时不会调用
+    ///
+    /// `uv` 为单击位置在画面中的归一化坐标，左上角为 (0, 0)，乘以 render target 的大小即为像素坐标
+    fn on_mouse_click(&mut self, _renderer: &mut Renderer, _uv: glam::Vec2) {}
+
     /// 窗口大小改变后重建资源（可选）
     fn on_window_resized(&mut self, _renderer: &mut Renderer) {}
+
+    /// 丢弃 app 自身时域算法（例如 TAA）的历史（可选）
+    ///
+    /// 在相机跳转视角、窗口大小改变以及加载新的模型之后调用
+    fn reset_temporal_history(&mut self) {}
+
+    /// 天空贴图被替换之后调用（可选），例如重新预计算依赖天空贴图的 IBL
+    fn on_sky_changed(&mut self, _renderer: &mut Renderer) {}
+
+    /// 释放 app 在 renderer 中创建的资源（可选），在 GPU 空闲之后、renderer 销毁之前调用
+    fn destroy(&mut self, _renderer: &mut Renderer) {}
 }
@@ -1,78 +1,190 @@
 use crate::outer_app::base::OuterApp;
+use crate::render_pipeline::overlay_pass::OverlayContext;
 use crate::render_pipeline::rt_render_graph::RtPipeline;
 use imgui::Ui;
 use truvis_crate_tools::resource::TruvisPath;
+use truvis_gfx::commands::command_buffer::GfxCommandBuffer;
 use truvis_gfx::commands::semaphore::GfxSemaphore;
-use truvis_renderer::model_loader::assimp_loader::AssimpSceneLoader;
+use truvis_renderer::model_loader::{self, ModelLoadOptions};
 use truvis_renderer::platform::camera::Camera;
 use truvis_renderer::renderer::Renderer;
+use truvis_scene::aabb::Aabb;
 use truvis_shader_binding::truvisl;
 
-#[derive(Default)]
 pub struct CornellApp {
     rt_pipeline: Option<RtPipeline>,
+
+    /// 点光源的位置和颜色，用于绘制 debug 包围盒
+    point_lights: Vec<(glam::Vec3, glam::Vec3)>,
+    /// 在光追结果之上叠加点光源的包围盒，用于验证光追与光栅内容的合成
+    show_light_bounds: bool,
+    /// 在屏幕中心绘制十字准星，默认关闭
+    show_crosshair: bool,
+    /// 将每帧的结果导出为 dma-buf，供其他进程或 API 零拷贝读取；设备不支持时会被重置为 false
+    #[cfg(target_os = "linux")]
+    external_export: bool,
+    /// UI 上请求在相机位置拍摄全景图，在下一次 update 中执行
+    pending_panorama: bool,
+}
+
+impl Default for CornellApp {
+    fn default() -> Self {
+        Self {
+            rt_pipeline: None,
+            point_lights: vec![],
+            show_light_bounds: true,
+            show_crosshair: false,
+            #[cfg(target_os = "linux")]
+            external_export: false,
+            pending_panorama: false,
+        }
+    }
 }
 
 impl CornellApp {
-    fn create_scene(renderer: &mut Renderer, camera: &mut Camera) {
+    /// 点光源包围盒的半边长
+    const LIGHT_BOUNDS_HALF_EXTENT: f32 = 4.0;
+    /// 全景图的宽度，高度为宽度的一半
+    const PANORAMA_RESOLUTION: u32 = 2048;
+    const PANORAMA_SPP: u32 = 16;
+
+    fn create_scene(&mut self, renderer: &mut Renderer, camera: &mut Camera) {
         camera.position = glam::vec3(-400.0, 1000.0, 1000.0);
         camera.euler_yaw_deg = 330.0;
         camera.euler_pitch_deg = -27.0;
 
-        renderer.render_context.scene_manager.register_point_light(truvisl::PointLight {
-            pos: glam::vec3(-20.0, 40.0, 0.0).into(),
-            color: (glam::vec3(5.0, 6.0, 1.0) * 2.0).into(),
-
-            _pos_padding: Default::default(),
-            _color_padding: Default::default(),
-        });
-        renderer.render_context.scene_manager.register_point_light(truvisl::PointLight {
-            pos: glam::vec3(40.0, 40.0, -30.0).into(),
-            color: (glam::vec3(1.0, 6.0, 7.0) * 3.0).into(),
-
-            _pos_padding: Default::default(),
-            _color_padding: Default::default(),
-        });
-        renderer.render_context.scene_manager.register_point_light(truvisl::PointLight {
-            pos: glam::vec3(40.0, 40.0, 30.0).into(),
-            color: (glam::vec3(5.0, 1.0, 8.0) * 3.0).into(),
-
-            _pos_padding: Default::default(),
-            _color_padding: Default::default(),
-        });
+        self.point_lights = vec![
+            (glam::vec3(-20.0, 40.0, 0.0), glam::vec3(5.0, 6.0, 1.0) * 2.0),
+            (glam::vec3(40.0, 40.0, -30.0), glam::vec3(1.0, 6.0, 7.0) * 3.0),
+            (glam::vec3(40.0, 40.0, 30.0), glam::vec3(5.0, 1.0, 8.0) * 3.0),
+        ];
+        for &(pos, color) in &self.point_lights {
+            renderer.render_context.scene_manager.register_point_light(truvisl::PointLight {
+                pos: pos.into(),
+                color: color.into(),
+
+                _pos_padding: Default::default(),
+                _color_padding: Default::default(),
+            });
+        }
         log::info!("Loading scene...");
-        AssimpSceneLoader::load_scene(
+        model_loader::load_any(
             TruvisPath::assets_path_str("fbx/cornell-box.fbx").as_ref(),
+            &ModelLoadOptions::default(),
             &mut renderer.render_context.scene_manager,
             &mut renderer.render_context.asset_hub,
         );
         log::info!("Scene loaded.");
     }
+
+    /// 在相机位置拍摄一张 HDR 全景图，以 exr 格式写入 [`TruvisPath::temp_dir`]
+    fn capture_panorama(&self, renderer: &mut Renderer) {
+        let panorama_dir = TruvisPath::temp_dir();
+        if let Err(e) = std::fs::create_dir_all(&panorama_dir) {
+            log::error!("failed to create {}: {}", panorama_dir.display(), e);
+            return;
+        }
+
+        let position = renderer.render_context.camera_pos;
+        let panorama = self.rt_pipeline.as_ref().unwrap().render_panorama(
+            &mut renderer.render_context,
+            position,
+            Self::PANORAMA_RESOLUTION,
+            Self::PANORAMA_SPP,
+        );
+        let panorama_path =
+            panorama_dir.join(format!("panorama-{}.exr", renderer.render_context.frame_counter.frame_id()));
+        match panorama.save_exr(&panorama_path) {
+            Ok(()) => log::info!("panorama saved to {}", panorama_path.display()),
+            Err(e) => log::error!("failed to save panorama to {}: {}", panorama_path.display(), e),
+        }
+    }
+
+    /// 根据 UI 的勾选状态开启或关闭共享纹理输出，输出尺寸跟随渲染尺寸
+    #[cfg(target_os = "linux")]
+    fn sync_external_export(&mut self, renderer: &Renderer) {
+        let rt_pipeline = self.rt_pipeline.as_mut().unwrap();
+        let frame_extent = renderer.render_context.frame_settings.frame_extent;
+        let export_extent = rt_pipeline.external_export_pass().map(|pass| pass.extent());
+        match (self.external_export, export_extent) {
+            (true, Some(extent)) if extent == frame_extent => {}
+            (true, _) => {
+                if !rt_pipeline.enable_external_export(frame_extent) {
+                    self.external_export = false;
+                }
+            }
+            (false, Some(_)) => rt_pipeline.disable_external_export(),
+            (false, None) => {}
+        }
+    }
 }
 
 impl OuterApp for CornellApp {
     fn init(&mut self, renderer: &mut Renderer, camera: &mut Camera) {
-        let rt_pipeline = RtPipeline::new(
-            &renderer.render_context.global_descriptor_sets,
-            renderer.render_present.as_ref().unwrap().swapchain.as_ref().unwrap(),
-            &mut renderer.cmd_allocator,
-        );
+        let rt_pipeline = RtPipeline::new_for_renderer(renderer);
 
-        Self::create_scene(renderer, camera);
+        self.create_scene(renderer, camera);
 
         self.rt_pipeline = Some(rt_pipeline);
     }
 
-    fn draw_ui(&mut self, _ui: &Ui) {}
+    fn draw_ui(&mut self, ui: &Ui) {
+        ui.checkbox("light bounds", &mut self.show_light_bounds);
+        ui.checkbox("crosshair", &mut self.show_crosshair);
+        #[cfg(target_os = "linux")]
+        ui.checkbox("external export (dma-buf)", &mut self.external_export);
+        if ui.button("capture panorama") {
+            self.pending_panorama = true;
+        }
+    }
+
+    fn update(&mut self, renderer: &mut Renderer) {
+        #[cfg(target_os = "linux")]
+        self.sync_external_export(renderer);
+
+        if std::mem::take(&mut self.pending_panorama) {
+            self.capture_panorama(renderer);
+        }
+    }
 
-    fn update(&mut self, _renderer: &mut Renderer) {}
+    /// 示例：在屏幕中心绘制十字准星，需要在 UI 中勾选
+    fn draw_overlay(&self, cmd: &GfxCommandBuffer, ctx: &OverlayContext) {
+        if self.show_crosshair {
+            ctx.draw_crosshair(cmd, 10, 2, [0.0, 1.0, 0.0, 1.0]);
+        }
+    }
 
     fn draw(&self, renderer: &Renderer, gui_draw_data: &imgui::DrawData, fence: &GfxSemaphore) {
-        self.rt_pipeline.as_ref().unwrap().render(
+        let rt_pipeline = self.rt_pipeline.as_ref().unwrap();
+        if self.show_light_bounds {
+            let drawer = rt_pipeline.debug_drawer();
+            let half_extent = glam::Vec3::splat(Self::LIGHT_BOUNDS_HALF_EXTENT);
+            for &(pos, color) in &self.point_lights {
+                let color = (color / color.max_element()).extend(1.0);
+                drawer.draw_aabb(&Aabb::new(pos - half_extent, pos + half_extent), color);
+            }
+        }
+
+        rt_pipeline.render(
             &renderer.render_context,
             renderer.render_present.as_ref().unwrap(),
             gui_draw_data,
             fence,
+            &|cmd, ctx| self.draw_overlay(cmd, ctx),
         );
     }
+
+    fn draw_offscreen(&self, renderer: &Renderer, fence: &GfxSemaphore) {
+        self.rt_pipeline.as_ref().unwrap().render_offscreen(&renderer.render_context, fence);
+    }
+
+    fn supports_offscreen(&self) -> bool {
+        true
+    }
+
+    fn reset_temporal_history(&mut self) {
+        if let Some(rt_pipeline) = &self.rt_pipeline {
+            rt_pipeline.reset_taa();
+        }
+    }
 }
@@ -0,0 +1,281 @@
+use crate::outer_app::base::OuterApp;
+use crate::outer_app::indirect_draw::indirect_draw_pass::{
+    IndirectDrawGeometry, IndirectDrawPass, IndirectDrawRgPass, IndirectDrawSource,
+};
+use crate::render_pipeline::gpu_cull_pass::{GpuCullPass, GpuCullStats};
+use crate::render_pipeline::tone_mapping_pass::{ToneMappingPass, ToneMappingRgPass};
+use ash::vk;
+use imgui::Ui;
+use std::cell::Cell;
+use truvis_gfx::commands::command_buffer::GfxCommandBuffer;
+use truvis_gfx::commands::semaphore::GfxSemaphore;
+use truvis_gfx::gfx::Gfx;
+use truvis_gui_backend::gui_pass::{GuiPass, GuiRgPass};
+use truvis_render_graph::render_graph::{RenderGraphBuilder, RgImageState, RgSemaphoreInfo};
+use truvis_render_interface::frame_counter::FrameCounter;
+use truvis_renderer::platform::camera::Camera;
+use truvis_renderer::renderer::Renderer;
+use truvis_shader_binding::truvisl;
+
+/// 示例：使用静态的 indirect buffer，一次调用绘制多个 mesh 的多个实例
+///
+/// 立方体、四棱锥、八面体共享同一个 index buffer，每个 mesh 对应一个 indirect command；
+/// 通过 UI 调整 command 数量，支持 drawIndirectCount 时数量从 count buffer 中读取。
+/// 开启 GPU 剔除后，改为由 compute shader 为视锥内的每个实例生成 command
+#[derive(Default)]
+pub struct IndirectDrawApp {
+    indirect_draw_pass: Option<IndirectDrawPass>,
+    tone_mapping_pass: Option<ToneMappingPass>,
+    gui_pass: Option<GuiPass>,
+    gpu_cull_pass: Option<GpuCullPass>,
+
+    geometry: Option<IndirectDrawGeometry>,
+    /// 所有实例的世界空间包围盒及其 mesh 范围，序号与实例数组一致
+    cull_instances: Vec<truvisl::gpu_cull::CullInstance>,
+
+    draw_cnt: u32,
+    use_count_buffer: bool,
+    use_gpu_cull: bool,
+    /// 与当前帧使用同一个 fif 的上一帧的剔除结果
+    cull_stats: Cell<GpuCullStats>,
+
+    cmds: Vec<GfxCommandBuffer>,
+}
+
+impl IndirectDrawApp {
+    /// 每个 mesh 的实例数量
+    const INSTANCE_CNT_PER_MESH: usize = 16;
+    const INSTANCE_SPACING: f32 = 2.0;
+
+    /// 第 `mesh_idx` 个 mesh 的实例排成一行，颜色随序号渐变，返回每个实例的 (model, color)
+    fn mesh_instances(mesh_idx: usize, mesh_cnt: usize) -> Vec<(glam::Mat4, glam::Vec4)> {
+        let row_z = (mesh_idx as f32 - (mesh_cnt - 1) as f32 * 0.5) * Self::INSTANCE_SPACING;
+        (0..Self::INSTANCE_CNT_PER_MESH)
+            .map(|i| {
+                let t = i as f32 / (Self::INSTANCE_CNT_PER_MESH - 1) as f32;
+                let x = (i as f32 - (Self::INSTANCE_CNT_PER_MESH - 1) as f32 * 0.5) * Self::INSTANCE_SPACING;
+                let model = glam::Mat4::from_rotation_translation(
+                    glam::Quat::from_rotation_y(t * std::f32::consts::PI),
+                    glam::vec3(x, 0.5, row_z),
+                );
+                let mut color = [0.25; 3];
+                color[mesh_idx % 3] = 0.5 + 0.5 * t;
+                (model, glam::Vec3::from(color).extend(1.0))
+            })
+            .collect()
+    }
+}
+
+impl OuterApp for IndirectDrawApp {
+    fn init(&mut self, renderer: &mut Renderer, camera: &mut Camera) {
+        let render_context = &renderer.render_context;
+        let present_format = renderer.swapchain_image_info().image_format;
+
+        let geometry = IndirectDrawGeometry::new(
+            &[
+                IndirectDrawGeometry::cube_faces(),
+                IndirectDrawGeometry::pyramid_faces(),
+                IndirectDrawGeometry::octahedron_faces(),
+            ],
+            "indirect-draw",
+        );
+        let instances = (0..geometry.mesh_cnt())
+            .map(|mesh_idx| Self::mesh_instances(mesh_idx, geometry.mesh_cnt()))
+            .collect::<Vec<_>>();
+
+        self.cull_instances = geometry
+            .meshes
+            .iter()
+            .zip(&instances)
+            .flat_map(|(mesh, mesh_instances)| mesh_instances.iter().map(move |instance| (mesh, instance)))
+            .enumerate()
+            .map(|(instance_idx, (mesh, (model, _)))| {
+                GpuCullPass::cull_instance(
+                    &mesh.aabb.transform(model),
+                    mesh.index_cnt,
+                    mesh.first_index,
+                    mesh.vertex_offset,
+                    instance_idx as u32,
+                )
+            })
+            .collect();
+
+        let instances = instances
+            .iter()
+            .map(|mesh_instances| {
+                mesh_instances
+                    .iter()
+                    .map(|(model, color)| truvisl::indirect_draw::InstanceData {
+                        model: (*model).into(),
+                        color: (*color).into(),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let indirect_draw_pass = IndirectDrawPass::new(
+            render_context.fif_buffers.render_target_format(),
+            render_context.frame_settings.depth_format,
+            &geometry,
+            &instances,
+        );
+        self.draw_cnt = indirect_draw_pass.max_draw_cnt();
+        self.use_count_buffer = Gfx::get().physical_device().support_draw_indirect_count();
+
+        self.indirect_draw_pass = Some(indirect_draw_pass);
+        self.geometry = Some(geometry);
+        self.tone_mapping_pass = Some(ToneMappingPass::new(&render_context.global_descriptor_sets, present_format));
+        self.gui_pass = Some(GuiPass::new(&render_context.global_descriptor_sets, present_format));
+        self.gpu_cull_pass = Some(GpuCullPass::new(&render_context.global_descriptor_sets, self.cull_instances.len()));
+
+        self.cmds = FrameCounter::frame_labes()
+            .map(|label| renderer.cmd_allocator.alloc_command_buffer(label, "indirect-draw-app"))
+            .collect();
+
+        camera.position = glam::vec3(0.0, 6.0, 14.0);
+        camera.euler_pitch_deg = -25.0;
+    }
+
+    fn draw_ui(&mut self, ui: &Ui) {
+        let physical_device = Gfx::get().physical_device();
+        ui.text(format!("multiDrawIndirect: {}", physical_device.support_multi_draw_indirect()));
+        ui.text(format!("drawIndirectCount: {}", physical_device.support_draw_indirect_count()));
+
+        if let Some(pass) = &self.indirect_draw_pass {
+            ui.text(format!("instances: {}", pass.instance_cnt()));
+            ui.slider("Draw Count", 0, pass.max_draw_cnt(), &mut self.draw_cnt);
+        }
+        // 不支持时 count buffer 会被忽略，直接绘制前 Draw Count 个 command
+        ui.checkbox("Use Count Buffer", &mut self.use_count_buffer);
+
+        ui.checkbox("GPU Culling", &mut self.use_gpu_cull);
+        if self.use_gpu_cull {
+            let stats = self.cull_stats.get();
+            ui.text(format!("visible: {}, culled: {}", stats.visible, stats.culled));
+        }
+    }
+
+    fn update(&mut self, _renderer: &mut Renderer) {}
+
+    fn draw(&self, renderer: &Renderer, gui_draw_data: &imgui::DrawData, fence: &GfxSemaphore) {
+        let render_context = &renderer.render_context;
+        let frame_label = render_context.frame_counter.frame_label();
+        let frame_id = render_context.frame_counter.frame_id();
+        let fif_buffers = &render_context.fif_buffers;
+        let render_present = renderer.render_present.as_ref().unwrap();
+
+        let mut graph = RenderGraphBuilder::new();
+        graph.signal_semaphore(RgSemaphoreInfo::timeline(
+            fence.handle(),
+            vk::PipelineStageFlags2::BOTTOM_OF_PIPE,
+            frame_id,
+        ));
+
+        let (render_target_image_handle, render_target_view_handle) = fif_buffers.render_target_handle(frame_label);
+        let render_target = graph.import_image(
+            "render-target",
+            render_target_image_handle,
+            Some(render_target_view_handle),
+            fif_buffers.render_target_format(),
+            RgImageState::UNDEFINED_TOP,
+            None,
+        );
+        // depth image 在各帧之间共享，需要等待上一帧的深度写入完成；内容会被 clear，因此 layout 可以视为 UNDEFINED
+        let depth_image = graph.import_image(
+            "depth",
+            fif_buffers.depth_image,
+            Some(fif_buffers.depth_image_view_handle()),
+            render_context.frame_settings.depth_format,
+            RgImageState::new(
+                vk::PipelineStageFlags2::LATE_FRAGMENT_TESTS,
+                vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE,
+                vk::ImageLayout::UNDEFINED,
+            ),
+            None,
+        );
+
+        let (present_image, present_view) = render_present.current_image_and_view();
+        let present_image = graph.import_image(
+            "present-image",
+            present_image,
+            Some(present_view),
+            render_present.swapchain_image_info().image_format,
+            RgImageState::UNDEFINED_BOTTOM,
+            Some(RgSemaphoreInfo::binary(
+                render_present.current_present_complete_semaphore(frame_label).handle(),
+                vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+            )),
+        );
+        graph.export_image(
+            present_image,
+            RgImageState::PRESENT_BOTTOM,
+            Some(RgSemaphoreInfo::binary(
+                render_present.current_render_compute_semaphore().handle(),
+                vk::PipelineStageFlags2::BOTTOM_OF_PIPE,
+            )),
+        );
+
+        let source = if self.use_gpu_cull {
+            IndirectDrawSource::GpuCull {
+                cull_pass: self.gpu_cull_pass.as_ref().unwrap(),
+                instance_cnt: self.cull_instances.len() as u32,
+            }
+        } else {
+            IndirectDrawSource::Static {
+                draw_cnt: self.draw_cnt,
+                use_count_buffer: self.use_count_buffer,
+            }
+        };
+
+        graph
+            .add_pass(
+                "indirect-draw",
+                IndirectDrawRgPass {
+                    indirect_draw_pass: self.indirect_draw_pass.as_ref().unwrap(),
+                    render_context,
+                    geometry: self.geometry.as_ref().unwrap(),
+                    source,
+                    render_target,
+                    depth_image,
+                },
+            )
+            .add_pass(
+                "tone-mapping",
+                ToneMappingRgPass {
+                    tone_mapping_pass: self.tone_mapping_pass.as_ref().unwrap(),
+                    render_context,
+                    hdr_input: render_target,
+                    swapchain_image: present_image,
+                    swapchain_extent: render_present.swapchain_image_info().image_extent,
+                },
+            )
+            .add_pass(
+                "gui",
+                GuiRgPass {
+                    gui_pass: self.gui_pass.as_ref().unwrap(),
+                    render_context,
+
+                    ui_draw_data: gui_draw_data,
+                    gui_mesh: &render_present.gui_backend.gui_meshes[*frame_label],
+
+                    canvas_color: present_image,
+                    canvas_extent: render_present.swapchain_image_info().image_extent,
+                },
+            );
+
+        let compiled_graph = graph.compile();
+
+        let cmd = &self.cmds[*frame_label];
+        cmd.begin(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT, "indirect-draw-graph");
+        if self.use_gpu_cull {
+            // 剔除的结果只被 indirect draw 使用，dispatch 自带所需的 barrier，因此放在 graph 之前
+            let gpu_cull_pass = self.gpu_cull_pass.as_ref().unwrap();
+            self.cull_stats.set(gpu_cull_pass.stats(frame_label));
+            gpu_cull_pass.upload_instances(frame_label, &self.cull_instances);
+            gpu_cull_pass.dispatch(cmd, render_context, self.cull_instances.len() as u32);
+        }
+        compiled_graph.execute(cmd, &render_context.gfx_resource_manager);
+        cmd.end();
+
+        Gfx::get().gfx_queue().submit(vec![compiled_graph.build_submit_info(std::slice::from_ref(cmd))], None);
+    }
+}
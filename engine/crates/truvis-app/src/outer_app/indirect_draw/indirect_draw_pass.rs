@@ -0,0 +1,393 @@
+use std::rc::Rc;
+
+use ash::vk;
+use truvis_crate_tools::resource::TruvisPath;
+use truvis_gfx::basic::bytes::BytesConvert;
+use truvis_gfx::gfx::Gfx;
+use truvis_gfx::resources::special_buffers::index_buffer::GfxIndex32Buffer;
+use truvis_gfx::resources::special_buffers::indirect_buffer::GfxIndexedIndirectBuffer;
+use truvis_gfx::resources::special_buffers::structured_buffer::GfxStructuredBuffer;
+use truvis_gfx::{
+    basic::color::LabelColor,
+    commands::command_buffer::GfxCommandBuffer,
+    pipelines::{
+        graphics_pipeline::{GfxGraphicsPipeline, GfxGraphicsPipelineCreateInfo, GfxPipelineLayout},
+        rendering_info::GfxRenderingInfo,
+    },
+};
+use truvis_render_graph::render_context::RenderContext;
+use truvis_render_graph::render_graph::{RgImageHandle, RgImageState, RgPass, RgPassBuilder, RgPassContext};
+use truvis_render_interface::frame_counter::FrameCounter;
+use truvis_scene::aabb::Aabb;
+use truvis_shader_binding::truvisl;
+
+use crate::render_pipeline::gpu_cull_pass::GpuCullPass;
+
+/// 合并后的 mesh 在共享 buffer 中的范围
+#[derive(Clone, Copy, Debug)]
+pub struct IndirectMeshRange {
+    pub first_index: u32,
+    pub index_cnt: u32,
+    pub vertex_offset: i32,
+    /// mesh 空间的包围盒
+    pub aabb: Aabb,
+}
+
+/// 若干凸多面体合并到同一组顶点和 index buffer 中，每个 mesh 内的 index 从 0 开始，
+/// 通过 command 的 `vertex_offset` 定位到各自的顶点
+pub struct IndirectDrawGeometry {
+    pub positions: GfxStructuredBuffer<glam::Vec3>,
+    pub normals: GfxStructuredBuffer<glam::Vec3>,
+    pub indices: GfxIndex32Buffer,
+
+    pub meshes: Vec<IndirectMeshRange>,
+}
+// new & init
+impl IndirectDrawGeometry {
+    /// # Params
+    /// * `meshes` - 每个 mesh 是一组中心位于原点的凸多边形面，面内的顶点绕序任意
+    pub fn new(meshes: &[Vec<Vec<glam::Vec3>>], name: &str) -> Self {
+        let _span = tracy_client::span!("IndirectDrawGeometry::new");
+
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        let mut indices = Vec::new();
+        let mut ranges = Vec::with_capacity(meshes.len());
+        for faces in meshes {
+            let first_index = indices.len() as u32;
+            let vertex_offset = positions.len() as i32;
+            let mut local_vertex_cnt = 0;
+            let mut aabb = Aabb::EMPTY;
+            for face in faces {
+                // 面片为平面着色，每个面使用独立的顶点；
+                // 形体是凸的且中心在原点，法线与面中心同向即为朝外，据此统一为 CCW
+                let mut face = face.clone();
+                let center = face.iter().sum::<glam::Vec3>() / face.len() as f32;
+                let mut normal = (face[1] - face[0]).cross(face[2] - face[0]).normalize();
+                if normal.dot(center) < 0.0 {
+                    face.reverse();
+                    normal = -normal;
+                }
+
+                for i in 1..face.len() as u32 - 1 {
+                    indices.extend([local_vertex_cnt, local_vertex_cnt + i, local_vertex_cnt + i + 1]);
+                }
+                aabb = aabb.union(&Aabb::from_points(&face));
+                positions.extend_from_slice(&face);
+                normals.extend(std::iter::repeat_n(normal, face.len()));
+                local_vertex_cnt += face.len() as u32;
+            }
+            ranges.push(IndirectMeshRange {
+                first_index,
+                index_cnt: indices.len() as u32 - first_index,
+                vertex_offset,
+                aabb,
+            });
+        }
+
+        fn upload<T: Copy>(data: &[T], name: String) -> GfxStructuredBuffer<T> {
+            let buffer = GfxStructuredBuffer::new_ssbo(data.len(), name);
+            buffer.transfer_data_sync(data);
+            buffer
+        }
+
+        Self {
+            positions: upload(&positions, format!("{name}-indirect-positions")),
+            normals: upload(&normals, format!("{name}-indirect-normals")),
+            indices: GfxIndex32Buffer::new_with_data(&indices, format!("{name}-indirect-indices")),
+            meshes: ranges,
+        }
+    }
+
+    /// 边长为 1 的立方体
+    pub fn cube_faces() -> Vec<Vec<glam::Vec3>> {
+        let mut faces = Vec::with_capacity(6);
+        for axis in 0..3 {
+            for sign in [-0.5, 0.5] {
+                let corner = |u: f32, v: f32| {
+                    let mut p = [0.0; 3];
+                    p[axis] = sign;
+                    p[(axis + 1) % 3] = u;
+                    p[(axis + 2) % 3] = v;
+                    glam::Vec3::from(p)
+                };
+                faces.push(vec![
+                    corner(-0.5, -0.5),
+                    corner(0.5, -0.5),
+                    corner(0.5, 0.5),
+                    corner(-0.5, 0.5),
+                ]);
+            }
+        }
+        faces
+    }
+
+    /// 底面为正方形的四棱锥
+    pub fn pyramid_faces() -> Vec<Vec<glam::Vec3>> {
+        let apex = glam::vec3(0.0, 0.5, 0.0);
+        let base = [
+            glam::vec3(-0.5, -0.5, -0.5),
+            glam::vec3(0.5, -0.5, -0.5),
+            glam::vec3(0.5, -0.5, 0.5),
+            glam::vec3(-0.5, -0.5, 0.5),
+        ];
+        let mut faces = vec![base.to_vec()];
+        faces.extend((0..4).map(|i| vec![base[i], base[(i + 1) % 4], apex]));
+        faces
+    }
+
+    /// 正八面体
+    pub fn octahedron_faces() -> Vec<Vec<glam::Vec3>> {
+        let mut faces = Vec::with_capacity(8);
+        for x in [-0.6, 0.6] {
+            for y in [-0.6, 0.6] {
+                for z in [-0.6, 0.6] {
+                    faces.push(vec![
+                        glam::vec3(x, 0.0, 0.0),
+                        glam::vec3(0.0, y, 0.0),
+                        glam::vec3(0.0, 0.0, z),
+                    ]);
+                }
+            }
+        }
+        faces
+    }
+}
+// getter
+impl IndirectDrawGeometry {
+    #[inline]
+    pub fn mesh_cnt(&self) -> usize {
+        self.meshes.len()
+    }
+}
+
+/// indirect command 的来源
+#[derive(Clone, Copy)]
+pub enum IndirectDrawSource<'a> {
+    /// 静态 command 中的前 `draw_cnt` 个，每个 mesh 一个 command
+    Static { draw_cnt: u32, use_count_buffer: bool },
+    /// GPU 剔除生成的 command，每个可见的实例一个，需要已经 [`GpuCullPass::dispatch`]
+    GpuCull {
+        cull_pass: &'a GpuCullPass,
+        instance_cnt: u32,
+    },
+}
+
+/// 使用一次 indexed indirect 调用绘制 [`IndirectDrawGeometry`] 中所有 mesh 的多个实例
+///
+/// 实例数据是静态的，按 mesh 依次排列。静态的 command 中第 i 个 command 绘制第 i 个 mesh 的所有实例，
+/// `first_instance` 指向这些实例在实例数组中的起点。
+/// 设备支持 drawIndirectCount 时，实际绘制的 command 数量从 count buffer 中读取。
+/// 需要设备支持 drawIndirectFirstInstance
+pub struct IndirectDrawPass {
+    pipeline: GfxGraphicsPipeline,
+
+    instances: GfxStructuredBuffer<truvisl::indirect_draw::InstanceData>,
+    indirect_buffer: GfxIndexedIndirectBuffer,
+    /// 每帧一个，存放本帧的 command 数量
+    count_buffers: Vec<GfxStructuredBuffer<u32>>,
+}
+// new & init
+impl IndirectDrawPass {
+    pub fn new(
+        color_format: vk::Format,
+        depth_format: vk::Format,
+        geometry: &IndirectDrawGeometry,
+        instances: &[Vec<truvisl::indirect_draw::InstanceData>],
+    ) -> Self {
+        assert_eq!(geometry.mesh_cnt(), instances.len(), "IndirectDrawPass: one instance list per mesh");
+        assert!(
+            Gfx::get().physical_device().support_draw_indirect_first_instance(),
+            "IndirectDrawPass: drawIndirectFirstInstance is not supported"
+        );
+
+        let shader_path = TruvisPath::shader_build_path_str("indirect_draw/indirect_draw.slang");
+
+        let mut ci = GfxGraphicsPipelineCreateInfo::default();
+        ci.vertex_shader_stage(&shader_path, c"vs_main");
+        ci.fragment_shader_stage(&shader_path, c"ps_main");
+
+        ci.attach_info(vec![color_format], Some(depth_format), None);
+        ci.color_blend(
+            vec![
+                vk::PipelineColorBlendAttachmentState::default()
+                    .blend_enable(false)
+                    .color_write_mask(vk::ColorComponentFlags::RGBA),
+            ],
+            [0.0; 4],
+        );
+
+        let pipeline_layout = Rc::new(GfxPipelineLayout::new(
+            &[],
+            &[vk::PushConstantRange::default()
+                .stage_flags(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT)
+                .offset(0)
+                .size(size_of::<truvisl::indirect_draw::PushConstants>() as u32)],
+            "indirect-draw-pass",
+        ));
+        let pipeline = GfxGraphicsPipeline::new(&ci, pipeline_layout, "indirect-draw-pipe");
+
+        let mut commands = Vec::with_capacity(geometry.mesh_cnt());
+        let mut first_instance = 0;
+        for (mesh, mesh_instances) in geometry.meshes.iter().zip(instances) {
+            commands.push(GfxIndexedIndirectBuffer::command(
+                mesh.index_cnt,
+                mesh.first_index,
+                mesh.vertex_offset,
+                mesh_instances.len() as u32,
+                first_instance,
+            ));
+            first_instance += mesh_instances.len() as u32;
+        }
+        let indirect_buffer = GfxIndexedIndirectBuffer::new_with_commands(&commands, "indirect-draw-commands");
+
+        let all_instances = instances.concat();
+        let instance_buffer = GfxStructuredBuffer::new_ssbo(all_instances.len(), "indirect-draw-instances");
+        instance_buffer.transfer_data_sync(&all_instances);
+
+        let count_buffers = FrameCounter::frame_labes()
+            .map(|label| {
+                GfxStructuredBuffer::new(
+                    format!("indirect-draw-count-{label}"),
+                    1,
+                    vk::BufferUsageFlags::INDIRECT_BUFFER,
+                    true,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            pipeline,
+            instances: instance_buffer,
+            indirect_buffer,
+            count_buffers,
+        }
+    }
+}
+// getter
+impl IndirectDrawPass {
+    /// indirect buffer 中 command 的数量，也就是可以绘制的最大 command 数量
+    #[inline]
+    pub fn max_draw_cnt(&self) -> u32 {
+        self.indirect_buffer.draw_cnt() as u32
+    }
+
+    #[inline]
+    pub fn instance_cnt(&self) -> usize {
+        self.instances.capacity()
+    }
+}
+// tools
+impl IndirectDrawPass {
+    /// 绘制 `source` 中的 command
+    ///
+    /// 不支持 drawIndirectCount 时 count buffer 会被忽略，直接绘制前 `draw_cnt` 个 command
+    pub fn draw(
+        &self,
+        cmd: &GfxCommandBuffer,
+        render_context: &RenderContext,
+        geometry: &IndirectDrawGeometry,
+        source: IndirectDrawSource<'_>,
+        color_view: vk::ImageView,
+        depth_view: vk::ImageView,
+    ) {
+        let frame_label = render_context.frame_counter.frame_label();
+        let extent = render_context.frame_settings.frame_extent;
+
+        let rendering_info = GfxRenderingInfo::new(
+            vec![color_view],
+            Some(depth_view),
+            vk::Rect2D {
+                offset: vk::Offset2D::default(),
+                extent,
+            },
+        );
+        cmd.cmd_begin_rendering2(&rendering_info);
+        cmd.begin_label("[indirect-draw-pass]draw", LabelColor::COLOR_PASS);
+
+        cmd.cmd_bind_pipeline(vk::PipelineBindPoint::GRAPHICS, self.pipeline.handle());
+        let viewport = render_context.frame_settings.camera_convention.viewport(extent.into());
+        cmd.cmd_set_viewport(0, std::slice::from_ref(&viewport));
+        cmd.cmd_set_scissor(0, &[extent.into()]);
+
+        let push_constant = truvisl::indirect_draw::PushConstants {
+            frame_data: render_context.per_frame_data_buffers[*frame_label].device_address(),
+            positions: geometry.positions.device_address(),
+            normals: geometry.normals.device_address(),
+            instances: self.instances.device_address(),
+        };
+        cmd.cmd_push_constants(
+            self.pipeline.layout(),
+            vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+            0,
+            BytesConvert::bytes_of(&push_constant),
+        );
+
+        cmd.cmd_bind_index_buffer(&geometry.indices, 0);
+        match source {
+            IndirectDrawSource::Static {
+                draw_cnt,
+                use_count_buffer: true,
+            } if Gfx::get().physical_device().support_draw_indirect_count() => {
+                // 当前帧的 count buffer 在上一次使用它的帧结束之后才会被覆盖
+                let count_buffer = &self.count_buffers[*frame_label];
+                count_buffer.transfer_data_by_mmap(&[draw_cnt.min(self.max_draw_cnt())]);
+                cmd.cmd_draw_indexed_indirect_count(
+                    &self.indirect_buffer,
+                    0,
+                    count_buffer,
+                    0,
+                    self.max_draw_cnt(),
+                    GfxIndexedIndirectBuffer::stride(),
+                );
+            }
+            IndirectDrawSource::Static { draw_cnt, .. } => {
+                cmd.cmd_draw_indexed_indirect(
+                    &self.indirect_buffer,
+                    0,
+                    draw_cnt.min(self.max_draw_cnt()),
+                    GfxIndexedIndirectBuffer::stride(),
+                );
+            }
+            IndirectDrawSource::GpuCull {
+                cull_pass,
+                instance_cnt,
+            } => cull_pass.draw(cmd, frame_label, instance_cnt),
+        }
+
+        cmd.end_label();
+        cmd.end_rendering();
+    }
+}
+pub struct IndirectDrawRgPass<'a> {
+    pub indirect_draw_pass: &'a IndirectDrawPass,
+
+    pub render_context: &'a RenderContext,
+    pub geometry: &'a IndirectDrawGeometry,
+    pub source: IndirectDrawSource<'a>,
+
+    pub render_target: RgImageHandle,
+    pub depth_image: RgImageHandle,
+}
+
+impl RgPass for IndirectDrawRgPass<'_> {
+    fn setup(&mut self, builder: &mut RgPassBuilder) {
+        builder.write_image(self.render_target, RgImageState::COLOR_ATTACHMENT_WRITE);
+        builder.write_image(self.depth_image, RgImageState::DEPTH_ATTACHMENT_WRITE);
+    }
+
+    fn execute(&self, ctx: &RgPassContext<'_>) {
+        let render_target_view =
+            ctx.get_image_view(self.render_target).expect("IndirectDrawPass: render_target not found");
+        let depth_view = ctx.get_image_view(self.depth_image).expect("IndirectDrawPass: depth_image not found");
+
+        self.indirect_draw_pass.draw(
+            ctx.cmd,
+            self.render_context,
+            self.geometry,
+            self.source,
+            render_target_view.handle(),
+            depth_view.handle(),
+        );
+    }
+}
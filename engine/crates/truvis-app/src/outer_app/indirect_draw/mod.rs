@@ -0,0 +1,2 @@
+pub mod indirect_draw_app;
+pub mod indirect_draw_pass;
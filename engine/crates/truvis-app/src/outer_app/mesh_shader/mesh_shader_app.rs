@@ -0,0 +1,239 @@
+use crate::outer_app::base::OuterApp;
+use crate::outer_app::mesh_shader::mesh_shader_pass::{MeshShaderPass, MeshShaderRgPass};
+use crate::render_pipeline::tone_mapping_pass::{ToneMappingPass, ToneMappingRgPass};
+use ash::vk;
+use imgui::Ui;
+use truvis_gfx::commands::command_buffer::GfxCommandBuffer;
+use truvis_gfx::commands::semaphore::GfxSemaphore;
+use truvis_gfx::gfx::Gfx;
+use truvis_gfx::pipelines::mesh_shader_pipeline::GfxMeshShaderPipeline;
+use truvis_gui_backend::gui_pass::{GuiPass, GuiRgPass};
+use truvis_render_graph::render_graph::{RenderGraphBuilder, RgImageState, RgSemaphoreInfo};
+use truvis_render_interface::frame_counter::FrameCounter;
+use truvis_render_interface::meshlet::MeshletGeometry;
+use truvis_renderer::platform::camera::Camera;
+use truvis_renderer::renderer::Renderer;
+
+/// 示例：将高面数的几何体划分为 meshlet，使用 task shader 剔除、mesh shader 绘制
+///
+/// 每个 meshlet 使用不同的颜色；关闭剔除后可以对比 task shader 剔除的效果
+pub struct MeshShaderApp {
+    mesh_shader_pass: Option<MeshShaderPass>,
+    tone_mapping_pass: Option<ToneMappingPass>,
+    gui_pass: Option<GuiPass>,
+
+    geometry: Option<MeshletGeometry>,
+    cull_enabled: bool,
+
+    cmds: Vec<GfxCommandBuffer>,
+}
+impl Default for MeshShaderApp {
+    fn default() -> Self {
+        Self {
+            mesh_shader_pass: None,
+            tone_mapping_pass: None,
+            gui_pass: None,
+            geometry: None,
+            cull_enabled: true,
+            cmds: Vec::new(),
+        }
+    }
+}
+
+impl MeshShaderApp {
+    /// 球体排成 SPHERE_GRID x SPHERE_GRID 的方阵
+    const SPHERE_GRID: usize = 8;
+    /// 每个球体的经线、纬线数量
+    const SPHERE_SLICES: u32 = 96;
+    const SPHERE_STACKS: u32 = 48;
+
+    /// 生成一组 UV 球体，顶点直接位于世界空间，三角形绕序为 CCW
+    ///
+    /// 返回 (positions, normals, indices)
+    fn create_spheres() -> (Vec<glam::Vec3>, Vec<glam::Vec3>, Vec<u32>) {
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        let mut indices = Vec::new();
+
+        for sphere_idx in 0..Self::SPHERE_GRID * Self::SPHERE_GRID {
+            let center = glam::vec3(
+                (sphere_idx % Self::SPHERE_GRID) as f32 * 2.5,
+                1.0,
+                -((sphere_idx / Self::SPHERE_GRID) as f32) * 2.5,
+            );
+            let base_vertex = positions.len() as u32;
+            let ring_vertex_cnt = Self::SPHERE_SLICES + 1;
+
+            for stack in 0..=Self::SPHERE_STACKS {
+                let theta = stack as f32 / Self::SPHERE_STACKS as f32 * std::f32::consts::PI;
+                for slice in 0..=Self::SPHERE_SLICES {
+                    let phi = slice as f32 / Self::SPHERE_SLICES as f32 * std::f32::consts::TAU;
+                    let normal = glam::vec3(theta.sin() * phi.cos(), theta.cos(), -theta.sin() * phi.sin());
+                    positions.push(center + normal);
+                    normals.push(normal);
+                }
+            }
+
+            for stack in 0..Self::SPHERE_STACKS {
+                for slice in 0..Self::SPHERE_SLICES {
+                    let a = base_vertex + stack * ring_vertex_cnt + slice;
+                    let b = a + ring_vertex_cnt;
+                    let c = b + 1;
+                    let d = a + 1;
+                    // 两极处的三角形退化，跳过
+                    if stack != Self::SPHERE_STACKS - 1 {
+                        indices.extend_from_slice(&[a, b, c]);
+                    }
+                    if stack != 0 {
+                        indices.extend_from_slice(&[a, c, d]);
+                    }
+                }
+            }
+        }
+
+        (positions, normals, indices)
+    }
+}
+
+impl OuterApp for MeshShaderApp {
+    fn init(&mut self, renderer: &mut Renderer, camera: &mut Camera) {
+        assert!(
+            GfxMeshShaderPipeline::is_supported(),
+            "mesh shader app: the device does not support task/mesh shader (VK_EXT_mesh_shader)"
+        );
+
+        let render_context = &renderer.render_context;
+        let present_format = renderer.swapchain_image_info().image_format;
+
+        self.mesh_shader_pass = Some(MeshShaderPass::new(
+            render_context.fif_buffers.render_target_format(),
+            render_context.frame_settings.depth_format,
+        ));
+        self.tone_mapping_pass = Some(ToneMappingPass::new(&render_context.global_descriptor_sets, present_format));
+        self.gui_pass = Some(GuiPass::new(&render_context.global_descriptor_sets, present_format));
+
+        self.cmds = FrameCounter::frame_labes()
+            .map(|label| renderer.cmd_allocator.alloc_command_buffer(label, "mesh-shader-app"))
+            .collect();
+
+        let (positions, normals, indices) = Self::create_spheres();
+        self.geometry = Some(MeshletGeometry::new(&positions, &normals, &indices, "spheres"));
+
+        camera.position = glam::vec3(8.75, 6.0, 8.0);
+        camera.euler_pitch_deg = -25.0;
+    }
+
+    fn draw_ui(&mut self, ui: &Ui) {
+        if let Some(geometry) = &self.geometry {
+            ui.text(format!("triangles: {}", geometry.triangle_cnt()));
+            ui.text(format!("meshlets: {}", geometry.meshlet_cnt()));
+        }
+        ui.checkbox("Meshlet Culling", &mut self.cull_enabled);
+    }
+
+    fn update(&mut self, _renderer: &mut Renderer) {}
+
+    fn draw(&self, renderer: &Renderer, gui_draw_data: &imgui::DrawData, fence: &GfxSemaphore) {
+        let render_context = &renderer.render_context;
+        let frame_label = render_context.frame_counter.frame_label();
+        let frame_id = render_context.frame_counter.frame_id();
+        let fif_buffers = &render_context.fif_buffers;
+        let render_present = renderer.render_present.as_ref().unwrap();
+
+        let mut graph = RenderGraphBuilder::new();
+        graph.signal_semaphore(RgSemaphoreInfo::timeline(
+            fence.handle(),
+            vk::PipelineStageFlags2::BOTTOM_OF_PIPE,
+            frame_id,
+        ));
+
+        let (render_target_image_handle, render_target_view_handle) = fif_buffers.render_target_handle(frame_label);
+        let render_target = graph.import_image(
+            "render-target",
+            render_target_image_handle,
+            Some(render_target_view_handle),
+            fif_buffers.render_target_format(),
+            RgImageState::UNDEFINED_TOP,
+            None,
+        );
+        // depth image 在各帧之间共享，需要等待上一帧的深度写入完成；内容会被 clear，因此 layout 可以视为 UNDEFINED
+        let depth_image = graph.import_image(
+            "depth",
+            fif_buffers.depth_image,
+            Some(fif_buffers.depth_image_view_handle()),
+            render_context.frame_settings.depth_format,
+            RgImageState::new(
+                vk::PipelineStageFlags2::LATE_FRAGMENT_TESTS,
+                vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE,
+                vk::ImageLayout::UNDEFINED,
+            ),
+            None,
+        );
+
+        let (present_image, present_view) = render_present.current_image_and_view();
+        let present_image = graph.import_image(
+            "present-image",
+            present_image,
+            Some(present_view),
+            render_present.swapchain_image_info().image_format,
+            RgImageState::UNDEFINED_BOTTOM,
+            Some(RgSemaphoreInfo::binary(
+                render_present.current_present_complete_semaphore(frame_label).handle(),
+                vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+            )),
+        );
+        graph.export_image(
+            present_image,
+            RgImageState::PRESENT_BOTTOM,
+            Some(RgSemaphoreInfo::binary(
+                render_present.current_render_compute_semaphore().handle(),
+                vk::PipelineStageFlags2::BOTTOM_OF_PIPE,
+            )),
+        );
+
+        graph
+            .add_pass(
+                "mesh-shader",
+                MeshShaderRgPass {
+                    mesh_shader_pass: self.mesh_shader_pass.as_ref().unwrap(),
+                    render_context,
+                    geometry: self.geometry.as_ref().unwrap(),
+                    cull_enabled: self.cull_enabled,
+                    render_target,
+                    depth_image,
+                },
+            )
+            .add_pass(
+                "tone-mapping",
+                ToneMappingRgPass {
+                    tone_mapping_pass: self.tone_mapping_pass.as_ref().unwrap(),
+                    render_context,
+                    hdr_input: render_target,
+                    swapchain_image: present_image,
+                    swapchain_extent: render_present.swapchain_image_info().image_extent,
+                },
+            )
+            .add_pass(
+                "gui",
+                GuiRgPass {
+                    gui_pass: self.gui_pass.as_ref().unwrap(),
+                    render_context,
+
+                    ui_draw_data: gui_draw_data,
+                    gui_mesh: &render_present.gui_backend.gui_meshes[*frame_label],
+
+                    canvas_color: present_image,
+                    canvas_extent: render_present.swapchain_image_info().image_extent,
+                },
+            );
+
+        let compiled_graph = graph.compile();
+
+        let cmd = &self.cmds[*frame_label];
+        cmd.begin(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT, "mesh-shader-graph");
+        compiled_graph.execute(cmd, &render_context.gfx_resource_manager);
+        cmd.end();
+
+        Gfx::get().gfx_queue().submit(vec![compiled_graph.build_submit_info(std::slice::from_ref(cmd))], None);
+    }
+}
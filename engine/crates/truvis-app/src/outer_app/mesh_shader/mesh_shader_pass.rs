@@ -0,0 +1,152 @@
+use std::rc::Rc;
+
+use ash::vk;
+use truvis_crate_tools::resource::TruvisPath;
+use truvis_gfx::basic::bytes::BytesConvert;
+use truvis_gfx::{
+    basic::color::LabelColor,
+    commands::command_buffer::GfxCommandBuffer,
+    pipelines::{
+        graphics_pipeline::{GfxGraphicsPipelineCreateInfo, GfxPipelineLayout},
+        mesh_shader_pipeline::GfxMeshShaderPipeline,
+        rendering_info::GfxRenderingInfo,
+    },
+};
+use truvis_render_graph::render_context::RenderContext;
+use truvis_render_graph::render_graph::{RgImageHandle, RgImageState, RgPass, RgPassBuilder, RgPassContext};
+use truvis_render_interface::meshlet::MeshletGeometry;
+use truvis_shader_binding::truvisl;
+
+/// 使用 task shader + mesh shader 绘制 meshlet
+///
+/// - task shader 的每个线程对一个 meshlet 做视锥剔除和法线锥剔除，只为可见的 meshlet 启动 mesh shader
+/// - mesh shader 从 device address 读取 meshlet 的顶点和三角形，不使用 vertex buffer 和 index buffer
+pub struct MeshShaderPass {
+    pipeline: GfxMeshShaderPipeline,
+}
+// new & init
+impl MeshShaderPass {
+    /// # Panics
+    /// 设备不支持 mesh shader
+    pub fn new(color_format: vk::Format, depth_format: vk::Format) -> Self {
+        let shader_path = TruvisPath::shader_build_path_str("mesh_shader/meshlet.slang");
+
+        let mut ci = GfxGraphicsPipelineCreateInfo::default();
+        ci.task_shader_stage(&shader_path, c"task_main");
+        ci.mesh_shader_stage(&shader_path, c"mesh_main");
+        ci.fragment_shader_stage(&shader_path, c"ps_main");
+
+        ci.attach_info(vec![color_format], Some(depth_format), None);
+        ci.color_blend(
+            vec![
+                vk::PipelineColorBlendAttachmentState::default()
+                    .blend_enable(false)
+                    .color_write_mask(vk::ColorComponentFlags::RGBA),
+            ],
+            [0.0; 4],
+        );
+
+        let pipeline_layout = Rc::new(GfxPipelineLayout::new(
+            &[],
+            &[vk::PushConstantRange::default()
+                .stage_flags(
+                    vk::ShaderStageFlags::TASK_EXT | vk::ShaderStageFlags::MESH_EXT | vk::ShaderStageFlags::FRAGMENT,
+                )
+                .offset(0)
+                .size(size_of::<truvisl::meshlet::PushConstants>() as u32)],
+            "mesh-shader-pass",
+        ));
+        let pipeline = GfxMeshShaderPipeline::new(&ci, pipeline_layout, "mesh-shader-pipe");
+
+        Self { pipeline }
+    }
+}
+// tools
+impl MeshShaderPass {
+    pub fn draw(
+        &self,
+        cmd: &GfxCommandBuffer,
+        render_context: &RenderContext,
+        geometry: &MeshletGeometry,
+        cull_enabled: bool,
+        color_view: vk::ImageView,
+        depth_view: vk::ImageView,
+    ) {
+        let frame_label = render_context.frame_counter.frame_label();
+        let extent = render_context.frame_settings.frame_extent;
+
+        let rendering_info = GfxRenderingInfo::new(
+            vec![color_view],
+            Some(depth_view),
+            vk::Rect2D {
+                offset: vk::Offset2D::default(),
+                extent,
+            },
+        );
+        cmd.cmd_begin_rendering2(&rendering_info);
+        cmd.begin_label("[mesh-shader-pass]draw", LabelColor::COLOR_PASS);
+
+        cmd.cmd_bind_pipeline(vk::PipelineBindPoint::GRAPHICS, self.pipeline.handle());
+        let viewport = render_context.frame_settings.camera_convention.viewport(extent.into());
+        cmd.cmd_set_viewport(0, std::slice::from_ref(&viewport));
+        cmd.cmd_set_scissor(0, &[extent.into()]);
+
+        let push_constant = truvisl::meshlet::PushConstants {
+            frame_data: render_context.per_frame_data_buffers[*frame_label].device_address(),
+            positions: geometry.positions.device_address(),
+            normals: geometry.normals.device_address(),
+            meshlets: geometry.meshlets.device_address(),
+            bounds: geometry.bounds.device_address(),
+            vertex_indices: geometry.vertex_indices.device_address(),
+            triangles: geometry.triangles.device_address(),
+            meshlet_count: geometry.meshlet_cnt(),
+            cull_enabled: cull_enabled as u32,
+        };
+        cmd.cmd_push_constants(
+            self.pipeline.layout(),
+            vk::ShaderStageFlags::TASK_EXT | vk::ShaderStageFlags::MESH_EXT | vk::ShaderStageFlags::FRAGMENT,
+            0,
+            BytesConvert::bytes_of(&push_constant),
+        );
+
+        // 每个 task shader 工作组负责 TASK_GROUP_SIZE 个 meshlet
+        let task_group_cnt = geometry.meshlet_cnt().div_ceil(truvisl::meshlet::TASK_GROUP_SIZE as u32);
+        cmd.cmd_draw_mesh_tasks([task_group_cnt, 1, 1]);
+
+        cmd.end_label();
+        cmd.end_rendering();
+    }
+}
+
+pub struct MeshShaderRgPass<'a> {
+    pub mesh_shader_pass: &'a MeshShaderPass,
+
+    pub render_context: &'a RenderContext,
+    pub geometry: &'a MeshletGeometry,
+    pub cull_enabled: bool,
+
+    pub render_target: RgImageHandle,
+    pub depth_image: RgImageHandle,
+}
+
+impl RgPass for MeshShaderRgPass<'_> {
+    fn setup(&mut self, builder: &mut RgPassBuilder) {
+        builder.write_image(self.render_target, RgImageState::COLOR_ATTACHMENT_WRITE);
+        builder.write_image(self.depth_image, RgImageState::DEPTH_ATTACHMENT_WRITE);
+    }
+
+    fn execute(&self, ctx: &RgPassContext<'_>) {
+        let render_target_view =
+            ctx.get_image_view(self.render_target).expect("MeshShaderPass: render_target not found");
+        let depth_view = ctx.get_image_view(self.depth_image).expect("MeshShaderPass: depth_image not found");
+
+        self.mesh_shader_pass.draw(
+            ctx.cmd,
+            self.render_context,
+            self.geometry,
+            self.cull_enabled,
+            render_target_view.handle(),
+            depth_view.handle(),
+        );
+    }
+}
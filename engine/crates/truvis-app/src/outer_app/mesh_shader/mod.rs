@@ -0,0 +1,2 @@
+pub mod mesh_shader_app;
+pub mod mesh_shader_pass;
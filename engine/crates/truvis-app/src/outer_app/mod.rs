@@ -1,5 +1,11 @@
 pub mod base;
 pub mod cornell_app;
+pub mod indirect_draw;
+pub mod mesh_shader;
+pub mod multi_draw;
+pub mod normal_map_app;
 pub mod shader_toy;
+pub mod skinning_app;
 pub mod sponza_app;
+pub mod terrain_strip;
 pub mod triangle;
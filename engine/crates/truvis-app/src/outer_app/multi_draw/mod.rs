@@ -0,0 +1,2 @@
+pub mod multi_draw_app;
+pub mod multi_draw_pass;
@@ -0,0 +1,603 @@
+use crate::outer_app::base::OuterApp;
+use crate::outer_app::multi_draw::multi_draw_pass::{MultiDrawPass, MultiDrawRgPass, MultiDrawTransparentRgPass};
+use crate::render_pipeline::debug_draw_pass::{DebugDrawPass, DebugDrawRgPass};
+use crate::render_pipeline::deferred_lighting_pass::{DeferredLightingPass, DeferredLightingRgPass};
+use crate::render_pipeline::gbuffer_pass::{GBufferPass, GBufferRgPass};
+use crate::render_pipeline::ibl_baker::IblBaker;
+use crate::render_pipeline::phong_pass::PhongPass;
+use crate::render_pipeline::picking_pass::{PickingPass, PickingRgPass};
+use crate::render_pipeline::shadow_pass::{ShadowPass, ShadowRgPass};
+use crate::render_pipeline::skybox_pass::{SkyboxPass, SkyboxRgPass};
+use crate::render_pipeline::tone_mapping_pass::{ToneMappingPass, ToneMappingRgPass};
+use ash::vk;
+use imgui::Ui;
+use truvis_crate_tools::resource::TruvisPath;
+use truvis_gfx::commands::command_buffer::GfxCommandBuffer;
+use truvis_gfx::commands::semaphore::GfxSemaphore;
+use truvis_gfx::gfx::Gfx;
+use truvis_gui_backend::gui_pass::{GuiPass, GuiRgPass};
+use truvis_render_graph::render_context::RenderContext;
+use truvis_render_graph::render_graph::{
+    RenderGraphBuilder, RgImageDesc, RgImageHandle, RgImageState, RgSemaphoreInfo, RgTransientImagePool,
+};
+use truvis_render_graph::resources::fif_buffer::FifBuffers;
+use truvis_render_interface::frame_counter::FrameCounter;
+use truvis_renderer::platform::camera::Camera;
+use truvis_renderer::renderer::Renderer;
+use truvis_scene::components::instance::Instance;
+use truvis_scene::components::light::DirectionalLight;
+use truvis_scene::components::material::Material;
+use truvis_scene::guid_new_type::MeshHandle;
+use truvis_scene::shapes::cube::CubeSoA;
+use truvis_scene::shapes::floor::FloorSoA;
+use truvis_scene::shapes::register_shape_mesh;
+use truvis_shader_binding::truvisl;
+
+/// 光栅化场景的着色方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RenderMode {
+    /// 使用 multi-draw indirect 在片元着色器中直接计算 phong 光照
+    #[default]
+    Forward,
+    /// 先写入 GBuffer，再由全屏的 lighting pass 对每个像素累加灯光
+    Deferred,
+    /// 使用 [`PhongPass`]，共享 mesh 的 instance 合批为一次 instanced draw；不区分透明物体
+    Instanced,
+}
+impl RenderMode {
+    const ALL: [Self; 3] = [Self::Forward, Self::Deferred, Self::Instanced];
+}
+
+/// 示例：使用一次 multi-draw indirect 调用光栅化多个使用不同材质的物体
+///
+/// 左键单击物体可以选中它，选中的物体会显示包围盒，包围盒可以选择画在最上层或者被场景遮挡。
+/// 可以在 UI 中切换前向着色、延迟着色和 instanced 着色，参见 [`RenderMode`]；前向着色可以开启 depth prepass。
+/// 前向着色时透明材质的物体在天空盒之后按从远到近的顺序混合绘制
+#[derive(Default)]
+pub struct MultiDrawApp {
+    shadow_pass: Option<ShadowPass>,
+    multi_draw_pass: Option<MultiDrawPass>,
+    phong_pass: Option<PhongPass>,
+    gbuffer_pass: Option<GBufferPass>,
+    deferred_lighting_pass: Option<DeferredLightingPass>,
+    skybox_pass: Option<SkyboxPass>,
+    picking_pass: Option<PickingPass>,
+    tone_mapping_pass: Option<ToneMappingPass>,
+    debug_draw_pass: Option<DebugDrawPass>,
+    gui_pass: Option<GuiPass>,
+
+    cmds: Vec<GfxCommandBuffer>,
+    /// 延迟着色的 GBuffer 只在一帧内使用，作为 render graph 的 transient 图像
+    transient_pool: RgTransientImagePool,
+
+    render_mode: RenderMode,
+    /// UI 中选择的阴影贴图分辨率，在 update 中应用
+    shadow_map_resolution: u32,
+
+    /// 选中物体的包围盒是否与场景做深度测试
+    selection_depth_test: bool,
+    /// 前向着色时是否先绘制 depth prepass，减少 overdraw 带来的片段着色开销
+    depth_prepass: bool,
+}
+
+impl MultiDrawApp {
+    /// 一排立方体的数量，每个立方体使用不同的材质
+    const CUBE_CNT: usize = 6;
+    /// UI 中可选的阴影贴图分辨率
+    const SHADOW_MAP_RESOLUTIONS: [u32; 4] = [512, 1024, 2048, 4096];
+    /// 选中物体的包围盒颜色
+    const SELECTION_COLOR: glam::Vec4 = glam::vec4(1.0, 0.8, 0.0, 1.0);
+    /// 材质编辑面板中选中的材质，使用它的物体的包围盒颜色
+    const MATERIAL_HIGHLIGHT_COLOR: glam::Vec4 = glam::vec4(0.0, 0.9, 1.0, 1.0);
+
+    fn create_scene(renderer: &mut Renderer, camera: &mut Camera) {
+        camera.position = glam::vec3(0.0, 4.0, 10.0);
+        camera.euler_pitch_deg = -20.0;
+
+        let scene_manager = &mut renderer.render_context.scene_manager;
+        scene_manager.register_point_light(truvisl::PointLight {
+            pos: glam::vec3(-4.0, 4.0, 3.0).into(),
+            color: glam::vec3(1.0, 1.0, 1.0).into(),
+
+            _pos_padding: Default::default(),
+            _color_padding: Default::default(),
+        });
+        scene_manager.register_point_light(truvisl::PointLight {
+            pos: glam::vec3(4.0, 3.0, -2.0).into(),
+            color: glam::vec3(0.6, 0.6, 0.8).into(),
+
+            _pos_padding: Default::default(),
+            _color_padding: Default::default(),
+        });
+        scene_manager.set_directional_light(Some(DirectionalLight {
+            direction: glam::vec3(-0.4, -1.0, -0.6),
+            color: glam::vec3(0.8, 0.8, 0.7),
+        }));
+
+        let floor_mesh = register_shape_mesh(scene_manager, "floor", (FloorSoA::create_mesh(), FloorSoA::aabb()));
+        // 所有立方体共享同一个 mesh，阴影 pass 中会被合批为一次 instanced draw
+        let cube_mesh = register_shape_mesh(scene_manager, "cube", (CubeSoA::create_mesh(), CubeSoA::aabb()));
+
+        let mut add_instance = |name: String, mesh: MeshHandle, base_color: glam::Vec4, transform: glam::Mat4| {
+            let mat = scene_manager.register_mat(Material {
+                base_color,
+                opaque: 1.0,
+                ..Default::default()
+            });
+            scene_manager.register_instance(Instance {
+                mesh,
+                materials: vec![mat],
+                transform,
+                name,
+            });
+        };
+
+        add_instance(
+            "floor".to_string(),
+            floor_mesh,
+            glam::vec4(0.5, 0.5, 0.5, 1.0),
+            glam::Mat4::from_scale(glam::Vec3::splat(10.0)),
+        );
+        for cube_idx in 0..Self::CUBE_CNT {
+            let hue = cube_idx as f32 / Self::CUBE_CNT as f32;
+            let x = (cube_idx as f32 - (Self::CUBE_CNT - 1) as f32 * 0.5) * 1.6;
+            add_instance(
+                format!("cube-{cube_idx}"),
+                cube_mesh,
+                Self::hue_to_color(hue),
+                glam::Mat4::from_rotation_translation(
+                    glam::Quat::from_rotation_y(0.3 * cube_idx as f32),
+                    glam::vec3(x, 0.5, 0.0),
+                ),
+            );
+        }
+    }
+
+    /// 延迟着色时 GBuffer A/B/C 的描述
+    ///
+    /// GBuffer pass 作为 color attachment 写入，lighting pass 作为 storage image 读取
+    fn gbuffer_descs(extent: vk::Extent2D) -> [RgImageDesc; 3] {
+        [
+            FifBuffers::gbuffer_a_format(),
+            FifBuffers::gbuffer_b_format(),
+            FifBuffers::gbuffer_c_format(),
+        ]
+        .map(|format| {
+            RgImageDesc::new_2d(
+                extent.width,
+                extent.height,
+                format,
+                vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::STORAGE,
+            )
+        })
+    }
+
+    /// 饱和度和亮度都为 1 的 HSV 颜色
+    fn hue_to_color(hue: f32) -> glam::Vec4 {
+        let channel = |offset: f32| (((hue + offset).fract() * 6.0 - 3.0).abs() - 1.0).clamp(0.0, 1.0);
+        glam::vec4(channel(0.0), channel(2.0 / 3.0), channel(1.0 / 3.0), 1.0)
+    }
+
+    /// 导入场景 pass 共用的图像：render target、depth 和阴影贴图
+    fn import_scene_images(
+        &self,
+        graph: &mut RenderGraphBuilder,
+        render_context: &RenderContext,
+    ) -> [RgImageHandle; 3] {
+        let fif_buffers = &render_context.fif_buffers;
+        let frame_label = render_context.frame_counter.frame_label();
+
+        let (render_target_image_handle, render_target_view_handle) = fif_buffers.render_target_handle(frame_label);
+        let render_target = graph.import_image(
+            "render-target",
+            render_target_image_handle,
+            Some(render_target_view_handle),
+            fif_buffers.render_target_format(),
+            RgImageState::UNDEFINED_TOP,
+            None,
+        );
+        // depth image 在各帧之间共享，需要等待上一帧的深度写入完成；内容会被 clear，因此 layout 可以视为 UNDEFINED
+        let depth_image = graph.import_image(
+            "depth",
+            fif_buffers.depth_image,
+            Some(fif_buffers.depth_image_view_handle()),
+            render_context.frame_settings.depth_format,
+            RgImageState::new(
+                vk::PipelineStageFlags2::LATE_FRAGMENT_TESTS,
+                vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE,
+                vk::ImageLayout::UNDEFINED,
+            ),
+            None,
+        );
+
+        // 阴影贴图在各帧之间共享，需要等待上一帧的采样完成；内容会被 clear，因此 layout 可以视为 UNDEFINED
+        let (shadow_map_image, shadow_map_view) = self.shadow_pass.as_ref().unwrap().shadow_map();
+        let shadow_map = graph.import_image(
+            "shadow-map",
+            shadow_map_image,
+            Some(shadow_map_view),
+            ShadowPass::SHADOW_MAP_FORMAT,
+            RgImageState::new(
+                vk::PipelineStageFlags2::FRAGMENT_SHADER,
+                vk::AccessFlags2::SHADER_SAMPLED_READ,
+                vk::ImageLayout::UNDEFINED,
+            ),
+            None,
+        );
+
+        [render_target, depth_image, shadow_map]
+    }
+
+    /// 延迟着色：GBuffer pass 写入 transient 的 GBuffer，lighting pass 读取之后写入 render target
+    fn add_deferred_passes<'a>(
+        &'a self,
+        graph: &mut RenderGraphBuilder<'a>,
+        render_context: &'a RenderContext,
+        [render_target, depth_image, shadow_map]: [RgImageHandle; 3],
+    ) {
+        // GBuffer 的内容会被 clear，由 render graph 管理 layout 与同步
+        let [gbuffer_a_desc, gbuffer_b_desc, gbuffer_c_desc] =
+            Self::gbuffer_descs(render_context.frame_settings.frame_extent);
+        let gbuffers = [
+            graph.create_image("gbuffer-a", gbuffer_a_desc),
+            graph.create_image("gbuffer-b", gbuffer_b_desc),
+            graph.create_image("gbuffer-c", gbuffer_c_desc),
+        ];
+
+        graph
+            .add_pass(
+                "gbuffer",
+                GBufferRgPass {
+                    gbuffer_pass: self.gbuffer_pass.as_ref().unwrap(),
+                    render_context,
+                    gbuffers,
+                    depth_image,
+                },
+            )
+            .add_pass(
+                "deferred-lighting",
+                DeferredLightingRgPass {
+                    deferred_lighting_pass: self.deferred_lighting_pass.as_ref().unwrap(),
+                    render_context,
+                    gbuffers,
+                    shadow_map: Some(shadow_map),
+                    render_target,
+                    image_extent: render_context.frame_settings.frame_extent,
+                },
+            );
+    }
+
+    /// 当前渲染方式需要的 transient 槽位，用于在 update 中准备 [`RgTransientImagePool`]
+    ///
+    /// 只有延迟着色使用 transient 图像。这里只声明延迟着色的 pass 并编译，槽位由 render graph 推导，
+    /// 因此与 draw 中编译出的槽位一致
+    fn transient_slot_descs(&self, render_context: &RenderContext) -> Vec<RgImageDesc> {
+        if self.render_mode != RenderMode::Deferred {
+            return Vec::new();
+        }
+
+        let mut graph = RenderGraphBuilder::new();
+        let scene_images = self.import_scene_images(&mut graph, render_context);
+        self.add_deferred_passes(&mut graph, render_context, scene_images);
+        graph.compile().transient_slot_descs().to_vec()
+    }
+
+    /// 为当前的天空贴图预计算 IBL，之前的 IBL 贴图在当前帧完成之后销毁
+    fn bake_ibl(renderer: &mut Renderer) {
+        let render_context = &mut renderer.render_context;
+        let ibl_maps = IblBaker::new(&render_context.global_descriptor_sets)
+            .bake(render_context, Some(&TruvisPath::ibl_cache_dir()));
+        if let Some(old_ibl_maps) = render_context.gpu_scene.set_ibl_maps(Some(ibl_maps)) {
+            let frame_id = render_context.frame_counter.frame_id();
+            old_ibl_maps.destroy(
+                &mut render_context.gfx_resource_manager,
+                &mut render_context.bindless_manager,
+                frame_id,
+            );
+        }
+    }
+}
+
+impl OuterApp for MultiDrawApp {
+    fn init(&mut self, renderer: &mut Renderer, camera: &mut Camera) {
+        self.shadow_map_resolution = ShadowPass::DEFAULT_RESOLUTION;
+        self.shadow_pass = Some(ShadowPass::new(&mut renderer.render_context, self.shadow_map_resolution));
+        self.picking_pass = Some(PickingPass::new(&mut renderer.render_context));
+
+        let render_context = &renderer.render_context;
+        let present_format = renderer.swapchain_image_info().image_format;
+
+        self.multi_draw_pass = Some(MultiDrawPass::new(
+            render_context.fif_buffers.render_target_format(),
+            render_context.frame_settings.depth_format,
+            &render_context.global_descriptor_sets,
+        ));
+        self.phong_pass = Some(PhongPass::new(
+            render_context.fif_buffers.render_target_format(),
+            render_context.frame_settings.depth_format,
+            &render_context.global_descriptor_sets,
+        ));
+        self.gbuffer_pass =
+            Some(GBufferPass::new(render_context.frame_settings.depth_format, &render_context.global_descriptor_sets));
+        self.deferred_lighting_pass = Some(DeferredLightingPass::new(&render_context.global_descriptor_sets));
+        self.skybox_pass = Some(SkyboxPass::new(
+            render_context.fif_buffers.render_target_format(),
+            render_context.frame_settings.depth_format,
+            &render_context.global_descriptor_sets,
+        ));
+        self.tone_mapping_pass = Some(ToneMappingPass::new(&render_context.global_descriptor_sets, present_format));
+        self.debug_draw_pass = Some(DebugDrawPass::new(
+            present_format,
+            Some((render_context.fif_buffers.render_target_format(), render_context.frame_settings.depth_format)),
+        ));
+        self.gui_pass = Some(GuiPass::new(&render_context.global_descriptor_sets, present_format));
+
+        self.cmds = FrameCounter::frame_labes()
+            .map(|label| renderer.cmd_allocator.alloc_command_buffer(label, "multi-draw-app"))
+            .collect();
+
+        Self::create_scene(renderer, camera);
+
+        // 环境光来自天空贴图的 IBL
+        Self::bake_ibl(renderer);
+    }
+
+    fn on_sky_changed(&mut self, renderer: &mut Renderer) {
+        Self::bake_ibl(renderer);
+    }
+
+    fn draw_ui(&mut self, ui: &Ui) {
+        let mut mode_idx = RenderMode::ALL.iter().position(|&mode| mode == self.render_mode).unwrap_or(0);
+        let items = RenderMode::ALL.map(|mode| format!("{:?}", mode));
+        if ui.combo_simple_string("Render Mode", &mut mode_idx, &items) {
+            self.render_mode = RenderMode::ALL[mode_idx];
+        }
+        ui.checkbox("Depth Prepass", &mut self.depth_prepass);
+        let mut resolution_idx =
+            Self::SHADOW_MAP_RESOLUTIONS.iter().position(|&r| r == self.shadow_map_resolution).unwrap_or(0);
+        let items = Self::SHADOW_MAP_RESOLUTIONS.map(|resolution| resolution.to_string());
+        if ui.combo_simple_string("Shadow Map", &mut resolution_idx, &items) {
+            self.shadow_map_resolution = Self::SHADOW_MAP_RESOLUTIONS[resolution_idx];
+        }
+        ui.slider("Sky Intensity", 0.0, 4.0, &mut self.skybox_pass.as_mut().unwrap().intensity);
+        ui.checkbox("Selection Depth Test", &mut self.selection_depth_test);
+    }
+
+    fn update(&mut self, renderer: &mut Renderer) {
+        self.shadow_pass.as_mut().unwrap().set_resolution(&mut renderer.render_context, self.shadow_map_resolution);
+
+        // 录制 render graph 时资源是只读的，需要提前准备好 transient 图像
+        let transient_slot_descs = self.transient_slot_descs(&renderer.render_context);
+        let render_context = &mut renderer.render_context;
+        self.transient_pool.prepare(
+            &mut render_context.gfx_resource_manager,
+            &mut render_context.bindless_manager,
+            &render_context.frame_counter,
+            &transient_slot_descs,
+        );
+
+        if let Some(pick_result) = self.picking_pass.as_mut().unwrap().poll(&renderer.render_context) {
+            log::info!("pick {:?}: {:?}", pick_result.pixel, pick_result.instance);
+            renderer.render_context.selected_instance = pick_result.instance;
+        }
+    }
+
+    fn overlay_text(&self) -> Vec<String> {
+        // 延迟着色的 GBuffer pass 本身只写一次 GBuffer，不使用 depth prepass
+        let state = match (self.depth_prepass, self.render_mode) {
+            (false, _) => "off",
+            (true, RenderMode::Forward) => "on",
+            (true, RenderMode::Deferred | RenderMode::Instanced) => "off (forward only)",
+        };
+        vec![format!("Depth Prepass: {}", state)]
+    }
+
+    fn on_mouse_click(&mut self, renderer: &mut Renderer, uv: glam::Vec2) {
+        let pixel = PickingPass::pixel_from_uv(uv, renderer.render_context.frame_settings.frame_extent);
+        self.picking_pass.as_mut().unwrap().request_pick(pixel);
+    }
+
+    fn destroy(&mut self, renderer: &mut Renderer) {
+        let render_context = &mut renderer.render_context;
+        self.transient_pool.destroy_mut(&mut render_context.gfx_resource_manager, &mut render_context.bindless_manager);
+    }
+
+    fn draw(&self, renderer: &Renderer, gui_draw_data: &imgui::DrawData, fence: &GfxSemaphore) {
+        let render_context = &renderer.render_context;
+        let frame_label = render_context.frame_counter.frame_label();
+        let frame_id = render_context.frame_counter.frame_id();
+        let render_present = renderer.render_present.as_ref().unwrap();
+
+        // 选中的物体可能已经被移除
+        let debug_draw_pass = self.debug_draw_pass.as_ref().unwrap();
+        if let Some(aabb) = render_context
+            .selected_instance
+            .and_then(|instance| render_context.scene_manager.instance_world_aabb(instance))
+        {
+            let drawer = debug_draw_pass.drawer();
+            drawer.set_depth_test(self.selection_depth_test);
+            drawer.draw_aabb(&aabb, Self::SELECTION_COLOR);
+        }
+        // 材质编辑面板中选中的材质
+        if let Some(material) = render_context.highlight_material {
+            let drawer = debug_draw_pass.drawer();
+            drawer.set_depth_test(self.selection_depth_test);
+            for instance in render_context.scene_manager.instances_with_material(material) {
+                if let Some(aabb) = render_context.scene_manager.instance_world_aabb(instance) {
+                    drawer.draw_aabb(&aabb, Self::MATERIAL_HIGHLIGHT_COLOR);
+                }
+            }
+        }
+        debug_draw_pass.prepare(frame_label);
+
+        let mut graph = RenderGraphBuilder::new();
+        graph.signal_semaphore(RgSemaphoreInfo::timeline(
+            fence.handle(),
+            vk::PipelineStageFlags2::BOTTOM_OF_PIPE,
+            frame_id,
+        ));
+
+        let scene_images = self.import_scene_images(&mut graph, render_context);
+        let [render_target, depth_image, shadow_map] = scene_images;
+
+        let (present_image, present_view) = render_present.current_image_and_view();
+        let present_image = graph.import_image(
+            "present-image",
+            present_image,
+            Some(present_view),
+            render_present.swapchain_image_info().image_format,
+            RgImageState::UNDEFINED_BOTTOM,
+            Some(RgSemaphoreInfo::binary(
+                render_present.current_present_complete_semaphore(frame_label).handle(),
+                vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+            )),
+        );
+        graph.export_image(
+            present_image,
+            RgImageState::PRESENT_BOTTOM,
+            Some(RgSemaphoreInfo::binary(
+                render_present.current_render_compute_semaphore().handle(),
+                vk::PipelineStageFlags2::BOTTOM_OF_PIPE,
+            )),
+        );
+
+        graph.add_pass(
+            "shadow",
+            ShadowRgPass {
+                shadow_pass: self.shadow_pass.as_ref().unwrap(),
+                render_context,
+                shadow_map,
+            },
+        );
+        match self.render_mode {
+            RenderMode::Forward => {
+                graph.add_pass(
+                    "multi-draw",
+                    MultiDrawRgPass {
+                        multi_draw_pass: self.multi_draw_pass.as_ref().unwrap(),
+                        render_context,
+                        render_target,
+                        depth_image,
+                        shadow_map: Some(shadow_map),
+                        depth_prepass: self.depth_prepass,
+                    },
+                );
+            }
+            RenderMode::Deferred => self.add_deferred_passes(&mut graph, render_context, scene_images),
+            RenderMode::Instanced => {
+                let phong_pass = self.phong_pass.as_ref().unwrap();
+                graph.add_pass_simple(
+                    "phong",
+                    &[(shadow_map, RgImageState::SHADER_READ_FRAGMENT)],
+                    &[
+                        (render_target, RgImageState::COLOR_ATTACHMENT_WRITE),
+                        (depth_image, RgImageState::DEPTH_ATTACHMENT_WRITE),
+                    ],
+                    move |ctx| {
+                        let render_target_view =
+                            ctx.get_image_view(render_target).expect("PhongPass: render_target not found");
+                        let depth_view = ctx.get_image_view(depth_image).expect("PhongPass: depth_image not found");
+                        phong_pass.draw(ctx.cmd, render_context, render_target_view.handle(), depth_view.handle());
+                    },
+                );
+            }
+        }
+        graph.add_pass(
+            "skybox",
+            SkyboxRgPass {
+                skybox_pass: self.skybox_pass.as_ref().unwrap(),
+                render_context,
+                render_target,
+                depth_image,
+            },
+        );
+        // 透明物体不写入深度，需要在天空盒之后绘制，否则背景为天空的部分会被覆盖
+        if self.render_mode == RenderMode::Forward {
+            graph.add_pass(
+                "multi-draw-transparent",
+                MultiDrawTransparentRgPass {
+                    multi_draw_pass: self.multi_draw_pass.as_ref().unwrap(),
+                    render_context,
+                    render_target,
+                    depth_image,
+                    shadow_map: Some(shadow_map),
+                },
+            );
+        }
+
+        if debug_draw_pass.has_lines(frame_label, true) {
+            graph.add_pass(
+                "debug-draw-depth-test",
+                DebugDrawRgPass {
+                    debug_draw_pass,
+                    render_context,
+                    canvas_color: render_target,
+                    canvas_extent: render_context.frame_settings.frame_extent,
+                    depth_image: Some(depth_image),
+                },
+            );
+        }
+
+        graph.add_pass(
+            "tone-mapping",
+            ToneMappingRgPass {
+                tone_mapping_pass: self.tone_mapping_pass.as_ref().unwrap(),
+                render_context,
+                hdr_input: render_target,
+                swapchain_image: present_image,
+                swapchain_extent: render_present.swapchain_image_info().image_extent,
+            },
+        );
+
+        let picking_pass = self.picking_pass.as_ref().unwrap();
+        if picking_pass.has_request() {
+            let (id_image, picking_depth) = picking_pass.import_images(&mut graph);
+            graph.add_pass(
+                "picking",
+                PickingRgPass {
+                    picking_pass,
+                    render_context,
+                    id_image,
+                    depth_image: picking_depth,
+                },
+            );
+        }
+
+        if debug_draw_pass.has_lines(frame_label, false) {
+            graph.add_pass(
+                "debug-draw",
+                DebugDrawRgPass {
+                    debug_draw_pass,
+                    render_context,
+                    canvas_color: present_image,
+                    canvas_extent: render_present.swapchain_image_info().image_extent,
+                    depth_image: None,
+                },
+            );
+        }
+
+        graph.add_pass(
+            "gui",
+            GuiRgPass {
+                gui_pass: self.gui_pass.as_ref().unwrap(),
+                render_context,
+
+                ui_draw_data: gui_draw_data,
+                gui_mesh: &render_present.gui_backend.gui_meshes[*frame_label],
+
+                canvas_color: present_image,
+                canvas_extent: render_present.swapchain_image_info().image_extent,
+            },
+        );
+
+        let mut compiled_graph = graph.compile();
+        compiled_graph.bind_transients(&self.transient_pool, frame_label);
+
+        let cmd = &self.cmds[*frame_label];
+        cmd.begin(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT, "multi-draw-graph");
+        compiled_graph.execute(cmd, &render_context.gfx_resource_manager);
+        cmd.end();
+
+        Gfx::get().gfx_queue().submit(vec![compiled_graph.build_submit_info(std::slice::from_ref(cmd))], None);
+    }
+}
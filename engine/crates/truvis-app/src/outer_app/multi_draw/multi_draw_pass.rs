@@ -0,0 +1,482 @@
+use std::rc::Rc;
+
+use ash::vk;
+use truvis_crate_tools::resource::TruvisPath;
+use truvis_gfx::basic::bytes::BytesConvert;
+use truvis_gfx::resources::special_buffers::structured_buffer::GfxStructuredBuffer;
+use truvis_gfx::{
+    basic::color::LabelColor,
+    commands::command_buffer::GfxCommandBuffer,
+    pipelines::{
+        graphics_pipeline::{GfxGraphicsPipeline, GfxGraphicsPipelineCreateInfo, GfxPipelineLayout},
+        rendering_info::GfxRenderingInfo,
+    },
+};
+use truvis_render_graph::render_context::RenderContext;
+use truvis_render_graph::render_graph::{RgImageHandle, RgImageState, RgPass, RgPassBuilder, RgPassContext};
+use truvis_render_interface::frame_counter::FrameCounter;
+use truvis_render_interface::global_descriptor_sets::GlobalDescriptorSets;
+use truvis_render_interface::render_data::{DrawItem, RenderData};
+use truvis_shader_binding::truvisl;
+
+/// 面剔除方式相同的一段连续的 draw，对应一次 indirect 调用
+struct DrawRun {
+    cull_mode: vk::CullModeFlags,
+    first_draw: u32,
+    draw_cnt: u32,
+}
+
+/// 一组 submesh 的 draw data 和 indirect command，每帧一份
+///
+/// 每帧由 CPU 直接写入，因此使用 mapped 的 buffer
+struct MultiDrawBuffers {
+    /// shader 通过 device address 访问
+    draw_data_buffers: Vec<GfxStructuredBuffer<truvisl::raster::DrawData>>,
+    indirect_buffers: Vec<GfxStructuredBuffer<vk::DrawIndirectCommand>>,
+}
+// new & init
+impl MultiDrawBuffers {
+    /// 单次调用最多绘制的 submesh 数量
+    const MAX_DRAW_CNT: usize = 1024;
+
+    fn new(name: &str) -> Self {
+        let draw_data_buffers = FrameCounter::frame_labes()
+            .map(|frame_label| {
+                GfxStructuredBuffer::new(
+                    format!("{name}-data-{frame_label}"),
+                    Self::MAX_DRAW_CNT,
+                    vk::BufferUsageFlags::STORAGE_BUFFER | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+                    true,
+                )
+            })
+            .collect::<Vec<_>>();
+        let indirect_buffers = FrameCounter::frame_labes()
+            .map(|frame_label| {
+                GfxStructuredBuffer::new(
+                    format!("{name}-indirect-{frame_label}"),
+                    Self::MAX_DRAW_CNT,
+                    vk::BufferUsageFlags::INDIRECT_BUFFER,
+                    true,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            draw_data_buffers,
+            indirect_buffers,
+        }
+    }
+}
+// tools
+impl MultiDrawBuffers {
+    /// 按 `draw_items` 的顺序生成 draw data 和 indirect command，
+    /// 返回面剔除方式相同的连续的 draw，不同的 cull mode 需要分开调用
+    ///
+    /// instance 的序号和 GPUScene 中的 instance 序号一致；
+    /// 顶点在 shader 中按 index 读取，因此 vertex count 为 index 的数量
+    fn fill(&self, render_data: &RenderData<'_>, draw_items: &[DrawItem], frame_label: usize) -> Vec<DrawRun> {
+        let mut draw_items = draw_items;
+        if draw_items.len() > Self::MAX_DRAW_CNT {
+            log::warn!(
+                "multi-draw: {} submeshes exceed the limit {}, the rest are skipped",
+                draw_items.len(),
+                Self::MAX_DRAW_CNT
+            );
+            draw_items = &draw_items[..Self::MAX_DRAW_CNT];
+        }
+
+        let draw_data = draw_items
+            .iter()
+            .map(|item| truvisl::raster::DrawData {
+                instance_idx: item.instance_idx,
+                submesh_idx: item.submesh_idx,
+            })
+            .collect::<Vec<_>>();
+        let indirect_commands = draw_items
+            .iter()
+            .map(|item| {
+                let instance = &render_data.all_instances[item.instance_idx as usize];
+                let geometry = &render_data.all_meshes[instance.mesh_index].geometries[item.submesh_idx as usize];
+                vk::DrawIndirectCommand {
+                    vertex_count: geometry.index_cnt(),
+                    instance_count: 1,
+                    first_vertex: 0,
+                    first_instance: 0,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        self.draw_data_buffers[frame_label].transfer_data_by_mmap(&draw_data);
+        self.indirect_buffers[frame_label].transfer_data_by_mmap(&indirect_commands);
+
+        let mut runs: Vec<DrawRun> = Vec::new();
+        for (draw_idx, item) in draw_items.iter().enumerate() {
+            let cull_mode = render_data.submesh_cull_mode(item.instance_idx, item.submesh_idx);
+            match runs.last_mut() {
+                Some(run) if run.cull_mode == cull_mode => run.draw_cnt += 1,
+                _ => runs.push(DrawRun {
+                    cull_mode,
+                    first_draw: draw_idx as u32,
+                    draw_cnt: 1,
+                }),
+            }
+        }
+        runs
+    }
+}
+
+/// 使用一次 multi-draw indirect 调用绘制场景中所有的 submesh
+///
+/// - 每个 submesh 对应一个 [`vk::DrawIndirectCommand`] 和一个 [`truvisl::raster::DrawData`]，
+///   二者的下标相同，shader 通过 `SV_DrawIndex`（gl_DrawID）找到当前 draw 的 instance 和 submesh
+/// - cull mode 是 dynamic state，按材质的面剔除方式将 draw 分为几段，每段一次调用；
+///   `SV_DrawIndex` 在每次调用中从 0 开始，因此 push constant 中的 draw data 地址指向该段的起始位置
+/// - 顶点着色器从 bindless 的 geometry buffer 中读取顶点，不绑定 vertex buffer 和 index buffer，
+///   因此不同 geometry、不同材质的 submesh 可以合并到同一次调用中
+/// - 可选的 depth prepass：先只写入深度，主 pass 再以 `EQUAL` 做深度测试，每个像素只执行一次片段着色。
+///   两次绘制使用同一个顶点着色器、pipeline layout 和 push constant，保证深度完全一致
+/// - 透明材质的 submesh 不在 [`Self::draw`] 中绘制，而是在天空盒之后由 [`Self::draw_transparent`]
+///   按从远到近的顺序混合绘制，不写入深度
+pub struct MultiDrawPass {
+    pipeline: GfxGraphicsPipeline,
+    /// 只有顶点着色器，只写入深度
+    depth_prepass_pipeline: GfxGraphicsPipeline,
+    /// 开启 depth prepass 时的主 pass：深度测试为 `EQUAL`，不写入深度
+    depth_equal_pipeline: GfxGraphicsPipeline,
+    /// 透明物体：开启 alpha 混合，不写入深度
+    transparent_pipeline: GfxGraphicsPipeline,
+
+    opaque_buffers: MultiDrawBuffers,
+    transparent_buffers: MultiDrawBuffers,
+}
+// new & init
+impl MultiDrawPass {
+    pub fn new(
+        color_format: vk::Format,
+        depth_format: vk::Format,
+        global_descriptor_sets: &GlobalDescriptorSets,
+    ) -> Self {
+        let mut ci = GfxGraphicsPipelineCreateInfo::default();
+        ci.vertex_shader_stage(&TruvisPath::shader_build_path_str("phong/phong_multi_draw.vs.slang"), c"main");
+        ci.fragment_shader_stage(&TruvisPath::shader_build_path_str("phong/phong_multi_draw.ps.slang"), c"main");
+
+        ci.attach_info(vec![color_format], Some(depth_format), None);
+        ci.dynamic_cull_mode();
+        ci.color_blend(
+            vec![
+                vk::PipelineColorBlendAttachmentState::default()
+                    .blend_enable(false)
+                    .color_write_mask(vk::ColorComponentFlags::RGBA),
+            ],
+            [0.0; 4],
+        );
+
+        let pipeline_layout = Rc::new(GfxPipelineLayout::new(
+            &global_descriptor_sets.global_set_layouts(),
+            &[vk::PushConstantRange::default()
+                .stage_flags(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT)
+                .offset(0)
+                .size(size_of::<truvisl::raster::MultiDrawPushConstants>() as u32)],
+            "multi-draw-pass",
+        ));
+        let pipeline = GfxGraphicsPipeline::new(&ci, pipeline_layout.clone(), "multi-draw-pipe");
+
+        ci.depth_test(Some(vk::CompareOp::EQUAL), false, false);
+        let depth_equal_pipeline =
+            GfxGraphicsPipeline::new(&ci, pipeline_layout.clone(), "multi-draw-depth-equal-pipe");
+
+        // render target 的 alpha 保持不透明物体写入的值
+        ci.depth_test(Some(vk::CompareOp::LESS), false, false);
+        ci.color_blend(
+            vec![
+                vk::PipelineColorBlendAttachmentState::default()
+                    .blend_enable(true)
+                    .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
+                    .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+                    .color_blend_op(vk::BlendOp::ADD)
+                    .src_alpha_blend_factor(vk::BlendFactor::ZERO)
+                    .dst_alpha_blend_factor(vk::BlendFactor::ONE)
+                    .alpha_blend_op(vk::BlendOp::ADD)
+                    .color_write_mask(vk::ColorComponentFlags::RGBA),
+            ],
+            [0.0; 4],
+        );
+        let transparent_pipeline =
+            GfxGraphicsPipeline::new(&ci, pipeline_layout.clone(), "multi-draw-transparent-pipe");
+
+        let mut prepass_ci = GfxGraphicsPipelineCreateInfo::default();
+        prepass_ci.vertex_shader_stage(&TruvisPath::shader_build_path_str("phong/phong_multi_draw.vs.slang"), c"main");
+        prepass_ci.attach_info(vec![], Some(depth_format), None);
+        prepass_ci.dynamic_cull_mode();
+        prepass_ci.color_blend(vec![], [0.0; 4]);
+        let depth_prepass_pipeline =
+            GfxGraphicsPipeline::new(&prepass_ci, pipeline_layout, "multi-draw-depth-prepass-pipe");
+
+        Self {
+            pipeline,
+            depth_prepass_pipeline,
+            depth_equal_pipeline,
+            transparent_pipeline,
+            opaque_buffers: MultiDrawBuffers::new("multi-draw-opaque"),
+            transparent_buffers: MultiDrawBuffers::new("multi-draw-transparent"),
+        }
+    }
+}
+// tools
+impl MultiDrawPass {
+    /// 与视锥体相交的 instance 中的 submesh，保持 `draw_items` 的顺序
+    fn visible_draw_items(render_context: &RenderContext, draw_items: Vec<DrawItem>) -> Vec<DrawItem> {
+        let scene_manager = &render_context.scene_manager;
+        let mut visible = vec![false; scene_manager.instance_map().len()];
+        for instance_idx in scene_manager.visible_instances(&render_context.camera_frustum) {
+            visible[instance_idx as usize] = true;
+        }
+        draw_items.into_iter().filter(|item| visible[item.instance_idx as usize]).collect()
+    }
+
+    /// 绘制不透明的 submesh
+    ///
+    /// `depth_prepass` 为 true 时先绘制一遍只写深度的 prepass，主 pass 复用其深度
+    pub fn draw(
+        &self,
+        cmd: &GfxCommandBuffer,
+        render_context: &RenderContext,
+        color_view: vk::ImageView,
+        depth_view: vk::ImageView,
+        extent: vk::Extent2D,
+        depth_prepass: bool,
+    ) {
+        let frame_label = render_context.frame_counter.frame_label();
+
+        let render_data = render_context
+            .scene_manager
+            .prepare_render_data(&render_context.bindless_manager, &render_context.asset_hub);
+        let mut draw_items = Self::visible_draw_items(render_context, render_context.scene_manager.opaque_draw_items());
+        // 不透明物体的顺序不影响结果，按 cull mode 排序以减少调用次数
+        draw_items.sort_by_key(|item| render_data.submesh_cull_mode(item.instance_idx, item.submesh_idx).as_raw());
+        let draw_runs = self.opaque_buffers.fill(&render_data, &draw_items, *frame_label);
+
+        let render_area = vk::Rect2D {
+            offset: vk::Offset2D::default(),
+            extent,
+        };
+
+        if depth_prepass {
+            let rendering_info = GfxRenderingInfo::new(vec![], Some(depth_view), render_area);
+            cmd.cmd_begin_rendering2(&rendering_info);
+            cmd.begin_label("[multi-draw-pass]depth-prepass", LabelColor::COLOR_PASS);
+            self.record_draw(
+                cmd,
+                render_context,
+                &self.depth_prepass_pipeline,
+                &self.opaque_buffers,
+                extent,
+                &draw_runs,
+            );
+            cmd.end_label();
+            cmd.end_rendering();
+
+            // 主 pass 的深度测试需要读取 prepass 写入的深度
+            let depth_stages =
+                vk::PipelineStageFlags2::EARLY_FRAGMENT_TESTS | vk::PipelineStageFlags2::LATE_FRAGMENT_TESTS;
+            cmd.memory_barrier(&[vk::MemoryBarrier2::default()
+                .src_stage_mask(depth_stages)
+                .src_access_mask(vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE)
+                .dst_stage_mask(depth_stages)
+                .dst_access_mask(vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_READ)]);
+        }
+
+        let (rendering_info, pipeline) = if depth_prepass {
+            (
+                GfxRenderingInfo::new(vec![color_view], Some(depth_view), render_area).load_depth(),
+                &self.depth_equal_pipeline,
+            )
+        } else {
+            (GfxRenderingInfo::new(vec![color_view], Some(depth_view), render_area), &self.pipeline)
+        };
+        cmd.cmd_begin_rendering2(&rendering_info);
+        cmd.begin_label("[multi-draw-pass]draw", LabelColor::COLOR_PASS);
+        self.record_draw(cmd, render_context, pipeline, &self.opaque_buffers, extent, &draw_runs);
+        cmd.end_label();
+        cmd.end_rendering();
+    }
+
+    /// 以 LOAD 的方式在 `color_view` 上混合绘制透明的 submesh，`depth_view` 中需要已经有不透明物体的深度
+    ///
+    /// submesh 的顺序为 [`truvis_scene::scene_manager::SceneManager::sorted_transparent`] 的结果
+    pub fn draw_transparent(
+        &self,
+        cmd: &GfxCommandBuffer,
+        render_context: &RenderContext,
+        color_view: vk::ImageView,
+        depth_view: vk::ImageView,
+        extent: vk::Extent2D,
+    ) {
+        let frame_label = render_context.frame_counter.frame_label();
+
+        let render_data = render_context
+            .scene_manager
+            .prepare_render_data(&render_context.bindless_manager, &render_context.asset_hub);
+        let draw_items = Self::visible_draw_items(
+            render_context,
+            render_context.scene_manager.sorted_transparent(render_context.camera_pos),
+        );
+        let draw_runs = self.transparent_buffers.fill(&render_data, &draw_items, *frame_label);
+        if draw_runs.is_empty() {
+            return;
+        }
+
+        let color_attach_info = vk::RenderingAttachmentInfo::default()
+            .image_view(color_view)
+            .image_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+            .load_op(vk::AttachmentLoadOp::LOAD)
+            .store_op(vk::AttachmentStoreOp::STORE);
+        let depth_attach_info = vk::RenderingAttachmentInfo::default()
+            .image_view(depth_view)
+            .image_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)
+            .load_op(vk::AttachmentLoadOp::LOAD)
+            .store_op(vk::AttachmentStoreOp::NONE);
+        let render_info = vk::RenderingInfo::default()
+            .layer_count(1)
+            .render_area(extent.into())
+            .color_attachments(std::slice::from_ref(&color_attach_info))
+            .depth_attachment(&depth_attach_info);
+
+        cmd.cmd_begin_rendering(&render_info);
+        cmd.begin_label("[multi-draw-pass]transparent", LabelColor::COLOR_PASS);
+        self.record_draw(
+            cmd,
+            render_context,
+            &self.transparent_pipeline,
+            &self.transparent_buffers,
+            extent,
+            &draw_runs,
+        );
+        cmd.end_label();
+        cmd.end_rendering();
+    }
+
+    /// 绑定 `pipeline` 并录制 indirect draw，各个 pass 共用，保证顶点变换完全一致
+    fn record_draw(
+        &self,
+        cmd: &GfxCommandBuffer,
+        render_context: &RenderContext,
+        pipeline: &GfxGraphicsPipeline,
+        buffers: &MultiDrawBuffers,
+        extent: vk::Extent2D,
+        draw_runs: &[DrawRun],
+    ) {
+        let frame_label = render_context.frame_counter.frame_label();
+
+        cmd.cmd_bind_pipeline(vk::PipelineBindPoint::GRAPHICS, pipeline.handle());
+        let viewport = render_context.frame_settings.camera_convention.viewport(extent.into());
+        cmd.cmd_set_viewport(0, std::slice::from_ref(&viewport));
+        cmd.cmd_set_scissor(0, &[extent.into()]);
+
+        cmd.bind_descriptor_sets(
+            vk::PipelineBindPoint::GRAPHICS,
+            pipeline.layout(),
+            0,
+            &render_context.global_descriptor_sets.global_sets(frame_label),
+            None,
+        );
+        for run in draw_runs {
+            cmd.cmd_set_cull_mode(run.cull_mode);
+            let push_constant = truvisl::raster::MultiDrawPushConstants {
+                frame_data: render_context.per_frame_data_buffers[*frame_label].device_address(),
+                scene: render_context.gpu_scene.scene_buffer(frame_label).device_address(),
+                draw_data: buffers.draw_data_buffers[*frame_label].device_address()
+                    + run.first_draw as vk::DeviceAddress * size_of::<truvisl::raster::DrawData>() as vk::DeviceAddress,
+            };
+            cmd.cmd_push_constants(
+                pipeline.layout(),
+                vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+                0,
+                BytesConvert::bytes_of(&push_constant),
+            );
+
+            // 同一段中的所有 submesh 只需要一次调用
+            cmd.cmd_draw_indirect(
+                &buffers.indirect_buffers[*frame_label],
+                run.first_draw as vk::DeviceSize * size_of::<vk::DrawIndirectCommand>() as vk::DeviceSize,
+                run.draw_cnt,
+                size_of::<vk::DrawIndirectCommand>() as u32,
+            );
+        }
+    }
+}
+
+pub struct MultiDrawRgPass<'a> {
+    pub multi_draw_pass: &'a MultiDrawPass,
+
+    pub render_context: &'a RenderContext,
+
+    pub render_target: RgImageHandle,
+    pub depth_image: RgImageHandle,
+    /// 方向光的阴影贴图，通过 bindless 采样
+    pub shadow_map: Option<RgImageHandle>,
+    /// 是否先绘制 depth prepass
+    pub depth_prepass: bool,
+}
+
+impl RgPass for MultiDrawRgPass<'_> {
+    fn setup(&mut self, builder: &mut RgPassBuilder) {
+        builder.write_image(self.render_target, RgImageState::COLOR_ATTACHMENT_WRITE);
+        builder.write_image(self.depth_image, RgImageState::DEPTH_ATTACHMENT_WRITE);
+        if let Some(shadow_map) = self.shadow_map {
+            builder.read_image(shadow_map, RgImageState::SHADER_READ_FRAGMENT);
+        }
+    }
+
+    fn execute(&self, ctx: &RgPassContext<'_>) {
+        let render_target_view =
+            ctx.get_image_view(self.render_target).expect("MultiDrawPass: render_target not found");
+        let depth_view = ctx.get_image_view(self.depth_image).expect("MultiDrawPass: depth_image not found");
+
+        self.multi_draw_pass.draw(
+            ctx.cmd,
+            self.render_context,
+            render_target_view.handle(),
+            depth_view.handle(),
+            self.render_context.frame_settings.frame_extent,
+            self.depth_prepass,
+        );
+    }
+}
+
+/// 透明物体的混合 pass，需要在不透明物体和天空盒之后执行
+pub struct MultiDrawTransparentRgPass<'a> {
+    pub multi_draw_pass: &'a MultiDrawPass,
+
+    pub render_context: &'a RenderContext,
+
+    pub render_target: RgImageHandle,
+    pub depth_image: RgImageHandle,
+    /// 方向光的阴影贴图，通过 bindless 采样
+    pub shadow_map: Option<RgImageHandle>,
+}
+
+impl RgPass for MultiDrawTransparentRgPass<'_> {
+    fn setup(&mut self, builder: &mut RgPassBuilder) {
+        builder.read_write_image(self.render_target, RgImageState::COLOR_ATTACHMENT_READ_WRITE);
+        builder.read_image(self.depth_image, RgImageState::DEPTH_ATTACHMENT_READ);
+        if let Some(shadow_map) = self.shadow_map {
+            builder.read_image(shadow_map, RgImageState::SHADER_READ_FRAGMENT);
+        }
+    }
+
+    fn execute(&self, ctx: &RgPassContext<'_>) {
+        let render_target_view =
+            ctx.get_image_view(self.render_target).expect("MultiDrawTransparentRgPass: render_target not found");
+        let depth_view =
+            ctx.get_image_view(self.depth_image).expect("MultiDrawTransparentRgPass: depth_image not found");
+
+        self.multi_draw_pass.draw_transparent(
+            ctx.cmd,
+            self.render_context,
+            render_target_view.handle(),
+            depth_view.handle(),
+            self.render_context.frame_settings.frame_extent,
+        );
+    }
+}
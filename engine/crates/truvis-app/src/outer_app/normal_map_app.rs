@@ -0,0 +1,114 @@
+use crate::outer_app::base::OuterApp;
+use crate::render_pipeline::rt_render_graph::RtPipeline;
+use imgui::Ui;
+use itertools::Itertools;
+use truvis_crate_tools::resource::TruvisPath;
+use truvis_gfx::commands::semaphore::GfxSemaphore;
+use truvis_renderer::model_loader::{self, ModelLoadOptions};
+use truvis_renderer::platform::camera::Camera;
+use truvis_renderer::renderer::Renderer;
+use truvis_scene::guid_new_type::MaterialHandle;
+use truvis_shader_binding::truvisl;
+
+/// 示例：在 sponza 场景中统一调节所有材质的 normal_scale，观察法线贴图强度的变化
+pub struct NormalMapApp {
+    rt_pipeline: Option<RtPipeline>,
+    materials: Vec<MaterialHandle>,
+
+    normal_scale: f32,
+    /// UI 修改了 normal_scale，需要在 update 中同步到场景
+    normal_scale_dirty: bool,
+}
+
+impl Default for NormalMapApp {
+    fn default() -> Self {
+        Self {
+            rt_pipeline: None,
+            materials: vec![],
+            normal_scale: 1.0,
+            normal_scale_dirty: false,
+        }
+    }
+}
+
+impl NormalMapApp {
+    fn create_scene(&mut self, renderer: &mut Renderer, camera: &mut Camera) {
+        // 贴近墙面和柱子，便于观察砖缝、浮雕的凹凸
+        camera.position = glam::vec3(-80.0, 120.0, 30.0);
+        camera.euler_yaw_deg = 90.0;
+        camera.euler_pitch_deg = 0.0;
+
+        // 掠射角的光照能让法线的变化更明显
+        renderer.render_context.scene_manager.register_point_light(truvisl::PointLight {
+            pos: glam::vec3(-200.0, 60.0, 0.0).into(),
+            color: (glam::vec3(1.0, 0.9, 0.7) * 20.0).into(),
+
+            _pos_padding: Default::default(),
+            _color_padding: Default::default(),
+        });
+
+        model_loader::load_any(
+            &TruvisPath::assets_path("fbx/sponza/sponza.fbx"),
+            &ModelLoadOptions::default(),
+            &mut renderer.render_context.scene_manager,
+            &mut renderer.render_context.asset_hub,
+        );
+        self.materials = renderer.render_context.scene_manager.mat_map().keys().collect_vec();
+    }
+}
+
+impl OuterApp for NormalMapApp {
+    fn init(&mut self, renderer: &mut Renderer, camera: &mut Camera) {
+        let rt_pipeline = RtPipeline::new_for_renderer(renderer);
+
+        self.create_scene(renderer, camera);
+
+        self.rt_pipeline = Some(rt_pipeline);
+    }
+
+    fn draw_ui(&mut self, ui: &Ui) {
+        ui.window("Normal Map")
+            .position([10.0, 420.0], imgui::Condition::FirstUseEver)
+            .size([250.0, 80.0], imgui::Condition::FirstUseEver)
+            .build(|| {
+                self.normal_scale_dirty |= ui.slider("Normal Scale", 0.0, 4.0, &mut self.normal_scale);
+            });
+    }
+
+    fn update(&mut self, renderer: &mut Renderer) {
+        if !self.normal_scale_dirty {
+            return;
+        }
+        self.normal_scale_dirty = false;
+
+        for &handle in &self.materials {
+            renderer.render_context.scene_manager.update_material(handle, |mat| {
+                mat.normal_scale = self.normal_scale;
+            });
+        }
+    }
+
+    fn draw(&self, renderer: &Renderer, gui_draw_data: &imgui::DrawData, fence: &GfxSemaphore) {
+        self.rt_pipeline.as_ref().unwrap().render(
+            &renderer.render_context,
+            renderer.render_present.as_ref().unwrap(),
+            gui_draw_data,
+            fence,
+            &|cmd, ctx| self.draw_overlay(cmd, ctx),
+        );
+    }
+
+    fn draw_offscreen(&self, renderer: &Renderer, fence: &GfxSemaphore) {
+        self.rt_pipeline.as_ref().unwrap().render_offscreen(&renderer.render_context, fence);
+    }
+
+    fn supports_offscreen(&self) -> bool {
+        true
+    }
+
+    fn reset_temporal_history(&mut self) {
+        if let Some(rt_pipeline) = &self.rt_pipeline {
+            rt_pipeline.reset_taa();
+        }
+    }
+}
@@ -5,6 +5,8 @@ use bytemuck::{Pod, Zeroable};
 use itertools::Itertools;
 
 use truvis_crate_tools::resource::TruvisPath;
+use truvis_descriptor_layout_macro::PushConstant;
+use truvis_descriptor_layout_trait::PushConstantLayout;
 use truvis_gfx::resources::image_view::GfxImageView;
 use truvis_gfx::{
     commands::command_buffer::GfxCommandBuffer,
@@ -31,8 +33,10 @@ enumed_map!(ShaderStage<GfxShaderStageInfo>:{
     },
 });
 
+/// 与 `shadertoy.inc.glsl` 中的 `PushConstants` 对应
 #[repr(C)]
-#[derive(Pod, Zeroable, Copy, Clone)]
+#[derive(Pod, Zeroable, Copy, Clone, PushConstant)]
+#[stage = "VERTEX | FRAGMENT"]
 pub struct PushConstants {
     /// 鼠标位置和状态
     mouse: glam::Vec4,
@@ -69,15 +73,7 @@ impl ShaderToyPass {
             [0.0; 4],
         );
 
-        let pipeline_layout = Rc::new(GfxPipelineLayout::new(
-            &[],
-            &[vk::PushConstantRange {
-                stage_flags: vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
-                offset: 0,
-                size: size_of::<PushConstants>() as u32,
-            }],
-            "shader-toy",
-        ));
+        let pipeline_layout = Rc::new(GfxPipelineLayout::new(&[], &PushConstants::ranges(), "shader-toy"));
         let pipeline = GfxGraphicsPipeline::new(&pipeline_ci, pipeline_layout.clone(), "shader-toy");
 
         Self {
@@ -120,12 +116,15 @@ impl ShaderToyPass {
         );
 
         {
-            cmd.cmd_push_constants(
-                self.pipeline.layout(),
-                vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
-                0,
-                bytemuck::bytes_of(&push_constants),
-            );
+            let bytes = bytemuck::bytes_of(&push_constants);
+            for range in PushConstants::push_ranges(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT) {
+                cmd.cmd_push_constants(
+                    self.pipeline.layout(),
+                    range.stage_flags,
+                    range.offset,
+                    &bytes[range.offset as usize..(range.offset + range.size) as usize],
+                );
+            }
 
             cmd.cmd_begin_rendering2(&rendering_info);
             cmd.cmd_bind_pipeline(vk::PipelineBindPoint::GRAPHICS, self.pipeline.handle());
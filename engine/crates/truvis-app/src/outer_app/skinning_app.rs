@@ -0,0 +1,298 @@
+use crate::outer_app::base::OuterApp;
+use crate::render_pipeline::rt_render_graph::RtPipeline;
+use imgui::Ui;
+use itertools::Itertools;
+use truvis_gfx::commands::semaphore::GfxSemaphore;
+use truvis_gfx::resources::special_buffers::index_buffer::GfxIndex32Buffer;
+use truvis_gfx::resources::vertex_layout::soa_3d::VertexLayoutSoA3D;
+use truvis_render_interface::geometry::RtGeometry;
+use truvis_renderer::platform::camera::Camera;
+use truvis_renderer::renderer::Renderer;
+use truvis_scene::aabb::Aabb;
+use truvis_scene::components::material::Material;
+use truvis_scene::components::skeleton::{AnimationClip, AnimationState, Joint, JointChannel, JointPose, Skeleton};
+use truvis_scene::components::skin::SkinnedMesh;
+use truvis_scene::guid_new_type::SkinnedInstanceHandle;
+use truvis_scene::shapes::add_shape;
+use truvis_scene::shapes::floor::FloorSoA;
+use truvis_shader_binding::truvisl;
+
+/// 示例：多个 instance 共享同一个蒙皮 mesh，各自以不同的相位和速度播放骨骼动画
+pub struct SkinningApp {
+    rt_pipeline: Option<RtPipeline>,
+    skinned_instances: Vec<SkinnedInstanceHandle>,
+
+    playing: bool,
+    speed: f32,
+    /// UI 修改了动画参数，需要在 update 中同步到场景
+    animation_dirty: bool,
+}
+
+impl Default for SkinningApp {
+    fn default() -> Self {
+        Self {
+            rt_pipeline: None,
+            skinned_instances: vec![],
+            playing: true,
+            speed: 1.0,
+            animation_dirty: false,
+        }
+    }
+}
+
+impl SkinningApp {
+    const TENTACLE_HEIGHT: f32 = 240.0;
+    const TENTACLE_RADIUS: f32 = 18.0;
+    const JOINT_CNT: usize = 4;
+    const RING_CNT: usize = 32;
+    const RING_SEGMENTS: usize = 16;
+
+    const INSTANCE_CNT: usize = 5;
+    const INSTANCE_SPACING: f32 = 120.0;
+
+    /// 各个 instance 的基础播放速度，使它们的动画错开
+    #[inline]
+    fn base_speed(instance_idx: usize) -> f32 {
+        0.6 + 0.2 * instance_idx as f32
+    }
+
+    /// 沿 +Y 方向的圆柱，由一条骨骼链驱动，相邻骨骼之间的顶点权重线性过渡
+    fn create_tentacle() -> SkinnedMesh {
+        let segment_len = Self::TENTACLE_HEIGHT / Self::JOINT_CNT as f32;
+
+        let mut positions = Vec::with_capacity(Self::RING_CNT * (Self::RING_SEGMENTS + 1));
+        let mut normals = Vec::with_capacity(positions.capacity());
+        let mut tangents = Vec::with_capacity(positions.capacity());
+        let mut uvs = Vec::with_capacity(positions.capacity());
+        let mut skin_vertices = Vec::with_capacity(positions.capacity());
+        for ring in 0..Self::RING_CNT {
+            let v = ring as f32 / (Self::RING_CNT - 1) as f32;
+            let y = v * Self::TENTACLE_HEIGHT;
+            // 越靠近末端越细
+            let radius = Self::TENTACLE_RADIUS * (1.0 - 0.6 * v);
+
+            let joint_coord = (y / segment_len).clamp(0.0, (Self::JOINT_CNT - 1) as f32);
+            let joint0 = joint_coord.floor() as u32;
+            let joint1 = (joint0 + 1).min(Self::JOINT_CNT as u32 - 1);
+            let weight1 = joint_coord - joint0 as f32;
+
+            // 首尾顶点重合，uv 才能连续
+            for segment in 0..=Self::RING_SEGMENTS {
+                let u = segment as f32 / Self::RING_SEGMENTS as f32;
+                let (sin, cos) = (u * std::f32::consts::TAU).sin_cos();
+
+                positions.push(glam::vec3(radius * cos, y, radius * sin));
+                normals.push(glam::vec3(cos, 0.0, sin));
+                tangents.push(glam::vec4(-sin, 0.0, cos, 1.0));
+                uvs.push(glam::vec2(u, v));
+                skin_vertices.push(truvisl::skinning::SkinVertex {
+                    joints: glam::uvec4(joint0, joint1, 0, 0).into(),
+                    weights: glam::vec4(1.0 - weight1, weight1, 0.0, 0.0).into(),
+                });
+            }
+        }
+
+        let ring_vertex_cnt = (Self::RING_SEGMENTS + 1) as u32;
+        let mut indices = Vec::with_capacity((Self::RING_CNT - 1) * Self::RING_SEGMENTS * 6);
+        for ring in 0..(Self::RING_CNT - 1) as u32 {
+            for segment in 0..Self::RING_SEGMENTS as u32 {
+                let a = ring * ring_vertex_cnt + segment;
+                let b = a + 1;
+                let c = a + ring_vertex_cnt;
+                let d = c + 1;
+                // 从外侧看为 CCW
+                indices.extend_from_slice(&[a, c, b, b, c, d]);
+            }
+        }
+
+        let geometry = RtGeometry {
+            vertex_buffer: VertexLayoutSoA3D::create_vertex_buffer(
+                &positions,
+                &normals,
+                &tangents,
+                &uvs,
+                "tentacle-vertex-buffer",
+            ),
+            index_buffer: GfxIndex32Buffer::new_with_data(&indices, "tentacle-index-buffer"),
+        };
+
+        let joints = (0..Self::JOINT_CNT)
+            .map(|joint_idx| Joint {
+                name: format!("tentacle-joint-{joint_idx}"),
+                parent: joint_idx.checked_sub(1),
+                bind_pose: JointPose {
+                    translation: if joint_idx == 0 { glam::Vec3::ZERO } else { glam::vec3(0.0, segment_len, 0.0) },
+                    ..Default::default()
+                },
+                inverse_bind_matrix: glam::Mat4::from_translation(glam::vec3(
+                    0.0,
+                    -(joint_idx as f32) * segment_len,
+                    0.0,
+                )),
+            })
+            .collect_vec();
+
+        SkinnedMesh::new(
+            vec![geometry],
+            &[skin_vertices],
+            Skeleton { joints },
+            vec![Self::create_swing_clip()],
+            Aabb::from_points(&positions),
+            "tentacle",
+        )
+    }
+
+    /// 每根骨骼绕 Z 轴来回摆动，越靠近末端幅度越大，并带有一定的相位延迟
+    fn create_swing_clip() -> AnimationClip {
+        const DURATION: f32 = 2.0;
+        const KEY_CNT: usize = 17;
+
+        let channels = (0..Self::JOINT_CNT)
+            .map(|joint_idx| {
+                let amplitude = 0.15 + 0.12 * joint_idx as f32;
+                let phase = joint_idx as f32 * 0.6;
+                let rotations = (0..KEY_CNT)
+                    .map(|key_idx| {
+                        let t = key_idx as f32 / (KEY_CNT - 1) as f32;
+                        let angle = amplitude * (t * std::f32::consts::TAU - phase).sin();
+                        (t * DURATION, glam::Quat::from_rotation_z(angle))
+                    })
+                    .collect_vec();
+
+                JointChannel {
+                    joint: joint_idx,
+                    rotations,
+                    ..Default::default()
+                }
+            })
+            .collect_vec();
+
+        AnimationClip {
+            name: "swing".to_string(),
+            duration: DURATION,
+            channels,
+        }
+    }
+
+    fn create_scene(&mut self, renderer: &mut Renderer, camera: &mut Camera) {
+        camera.position = glam::vec3(0.0, 200.0, 600.0);
+        camera.euler_yaw_deg = 0.0;
+        camera.euler_pitch_deg = -10.0;
+
+        let scene_manager = &mut renderer.render_context.scene_manager;
+
+        scene_manager.register_point_light(truvisl::PointLight {
+            pos: glam::vec3(-200.0, 400.0, 200.0).into(),
+            color: (glam::vec3(1.0, 0.9, 0.8) * 40.0).into(),
+
+            _pos_padding: Default::default(),
+            _color_padding: Default::default(),
+        });
+        scene_manager.register_point_light(truvisl::PointLight {
+            pos: glam::vec3(250.0, 300.0, 150.0).into(),
+            color: (glam::vec3(0.6, 0.8, 1.0) * 30.0).into(),
+
+            _pos_padding: Default::default(),
+            _color_padding: Default::default(),
+        });
+
+        // 地面
+        add_shape(
+            scene_manager,
+            "floor",
+            (FloorSoA::create_mesh(), FloorSoA::aabb()),
+            Material {
+                base_color: glam::vec4(0.6, 0.6, 0.6, 1.0),
+                roughness: 0.8,
+                opaque: 1.0,
+                ..Default::default()
+            },
+            glam::Mat4::from_scale(glam::Vec3::splat(600.0)),
+        );
+
+        // 共享同一个蒙皮 mesh 的多个 instance
+        let tentacle = scene_manager.register_skinned_mesh(Self::create_tentacle());
+        let tentacle_mat = scene_manager.register_mat(Material {
+            base_color: glam::vec4(0.8, 0.3, 0.2, 1.0),
+            roughness: 0.4,
+            opaque: 1.0,
+            ..Default::default()
+        });
+        let half_width = (Self::INSTANCE_CNT - 1) as f32 * Self::INSTANCE_SPACING * 0.5;
+        self.skinned_instances = (0..Self::INSTANCE_CNT)
+            .map(|instance_idx| {
+                let x = instance_idx as f32 * Self::INSTANCE_SPACING - half_width;
+                scene_manager.register_skinned_instance(
+                    tentacle,
+                    vec![tentacle_mat],
+                    glam::Mat4::from_translation(glam::vec3(x, 0.0, 0.0)),
+                    AnimationState {
+                        time: instance_idx as f32 * 0.37,
+                        speed: Self::base_speed(instance_idx),
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect_vec();
+    }
+}
+
+impl OuterApp for SkinningApp {
+    fn init(&mut self, renderer: &mut Renderer, camera: &mut Camera) {
+        let rt_pipeline = RtPipeline::new_for_renderer(renderer);
+
+        self.create_scene(renderer, camera);
+
+        self.rt_pipeline = Some(rt_pipeline);
+    }
+
+    fn draw_ui(&mut self, ui: &Ui) {
+        ui.window("Skinning")
+            .position([10.0, 420.0], imgui::Condition::FirstUseEver)
+            .size([250.0, 100.0], imgui::Condition::FirstUseEver)
+            .build(|| {
+                self.animation_dirty |= ui.checkbox("Playing", &mut self.playing);
+                self.animation_dirty |= ui.slider("Speed Scale", 0.0, 3.0, &mut self.speed);
+            });
+    }
+
+    fn update(&mut self, renderer: &mut Renderer) {
+        if !self.animation_dirty {
+            return;
+        }
+        self.animation_dirty = false;
+
+        // 每个 instance 保留自己的速度差异，UI 只调整整体倍率
+        for (instance_idx, &handle) in self.skinned_instances.iter().enumerate() {
+            let speed = Self::base_speed(instance_idx) * self.speed;
+            renderer.render_context.scene_manager.update_animation(handle, |animation| {
+                animation.playing = self.playing;
+                animation.speed = speed;
+            });
+        }
+    }
+
+    fn draw(&self, renderer: &Renderer, gui_draw_data: &imgui::DrawData, fence: &GfxSemaphore) {
+        self.rt_pipeline.as_ref().unwrap().render(
+            &renderer.render_context,
+            renderer.render_present.as_ref().unwrap(),
+            gui_draw_data,
+            fence,
+            &|cmd, ctx| self.draw_overlay(cmd, ctx),
+        );
+    }
+
+    fn draw_offscreen(&self, renderer: &Renderer, fence: &GfxSemaphore) {
+        self.rt_pipeline.as_ref().unwrap().render_offscreen(&renderer.render_context, fence);
+    }
+
+    fn supports_offscreen(&self) -> bool {
+        true
+    }
+
+    fn reset_temporal_history(&mut self) {
+        if let Some(rt_pipeline) = &self.rt_pipeline {
+            rt_pipeline.reset_taa();
+        }
+    }
+}
@@ -3,7 +3,7 @@ use crate::render_pipeline::rt_render_graph::RtPipeline;
 use imgui::Ui;
 use truvis_crate_tools::resource::TruvisPath;
 use truvis_gfx::commands::semaphore::GfxSemaphore;
-use truvis_renderer::model_loader::assimp_loader::AssimpSceneLoader;
+use truvis_renderer::model_loader::{self, ModelLoadOptions};
 use truvis_renderer::platform::camera::Camera;
 use truvis_renderer::renderer::Renderer;
 use truvis_shader_binding::truvisl;
@@ -40,14 +40,10 @@ impl SponzaApp {
             _pos_padding: Default::default(),
             _color_padding: Default::default(),
         });
-        // scene_manager.load_scene(
-        //     &renderer.render_context,
-        //     std::path::Path::new("assets/fbx/sponza/Sponza.fbx"),
-        //     &glam::Mat4::from_translation(glam::vec3(10.0, 10.0, 10.0)),
-        // );
         log::info!("start load sponza scene");
-        AssimpSceneLoader::load_scene(
+        model_loader::load_any(
             &TruvisPath::assets_path("fbx/sponza/sponza.fbx"),
+            &ModelLoadOptions::default(),
             &mut renderer.render_context.scene_manager,
             &mut renderer.render_context.asset_hub,
         );
@@ -57,11 +53,7 @@ impl SponzaApp {
 
 impl OuterApp for SponzaApp {
     fn init(&mut self, renderer: &mut Renderer, camera: &mut Camera) {
-        let rt_pipeline = RtPipeline::new(
-            &renderer.render_context.global_descriptor_sets,
-            renderer.render_present.as_ref().unwrap().swapchain.as_ref().unwrap(),
-            &mut renderer.cmd_allocator,
-        );
+        let rt_pipeline = RtPipeline::new_for_renderer(renderer);
 
         Self::create_scene(renderer, camera);
 
@@ -77,6 +69,21 @@ impl OuterApp for SponzaApp {
             renderer.render_present.as_ref().unwrap(),
             gui_draw_data,
             fence,
+            &|cmd, ctx| self.draw_overlay(cmd, ctx),
         );
     }
+
+    fn draw_offscreen(&self, renderer: &Renderer, fence: &GfxSemaphore) {
+        self.rt_pipeline.as_ref().unwrap().render_offscreen(&renderer.render_context, fence);
+    }
+
+    fn supports_offscreen(&self) -> bool {
+        true
+    }
+
+    fn reset_temporal_history(&mut self) {
+        if let Some(rt_pipeline) = &self.rt_pipeline {
+            rt_pipeline.reset_taa();
+        }
+    }
 }
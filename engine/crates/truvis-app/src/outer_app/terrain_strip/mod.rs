@@ -0,0 +1,2 @@
+pub mod terrain_strip_app;
+pub mod terrain_strip_pass;
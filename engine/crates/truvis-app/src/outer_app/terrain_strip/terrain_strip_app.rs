@@ -0,0 +1,184 @@
+use crate::outer_app::base::OuterApp;
+use crate::outer_app::terrain_strip::terrain_strip_pass::{TerrainStripGeometry, TerrainStripPass, TerrainStripRgPass};
+use crate::render_pipeline::tone_mapping_pass::{ToneMappingPass, ToneMappingRgPass};
+use ash::vk;
+use imgui::Ui;
+use truvis_gfx::commands::command_buffer::GfxCommandBuffer;
+use truvis_gfx::commands::semaphore::GfxSemaphore;
+use truvis_gfx::gfx::Gfx;
+use truvis_gui_backend::gui_pass::{GuiPass, GuiRgPass};
+use truvis_render_graph::render_graph::{RenderGraphBuilder, RgImageState, RgSemaphoreInfo};
+use truvis_render_interface::frame_counter::FrameCounter;
+use truvis_renderer::platform::camera::Camera;
+use truvis_renderer::renderer::Renderer;
+
+/// 示例：使用 triangle strip + primitive restart 绘制高度场地形
+///
+/// 每行格子是一条 strip，相比 triangle list 每个三角形平均只需要约 1 个 index
+#[derive(Default)]
+pub struct TerrainStripApp {
+    terrain_strip_pass: Option<TerrainStripPass>,
+    tone_mapping_pass: Option<ToneMappingPass>,
+    gui_pass: Option<GuiPass>,
+
+    terrain: Option<TerrainStripGeometry>,
+
+    cmds: Vec<GfxCommandBuffer>,
+}
+
+impl TerrainStripApp {
+    /// 地形的格子数量
+    const GRID_SIZE: u32 = 256;
+    const CELL_SIZE: f32 = 0.25;
+
+    /// 若干不同频率的正弦波叠加出的起伏地形
+    fn terrain_height(x: f32, z: f32) -> f32 {
+        let mut height = 0.0;
+        let mut amplitude = 4.0;
+        let mut frequency = 0.05;
+        for octave in 0..4 {
+            let phase = octave as f32 * 1.7;
+            height += amplitude * (x * frequency + phase).sin() * (z * frequency * 1.3 - phase).cos();
+            amplitude *= 0.45;
+            frequency *= 2.1;
+        }
+        height
+    }
+}
+
+impl OuterApp for TerrainStripApp {
+    fn init(&mut self, renderer: &mut Renderer, camera: &mut Camera) {
+        let render_context = &renderer.render_context;
+        let present_format = renderer.swapchain_image_info().image_format;
+
+        self.terrain_strip_pass = Some(TerrainStripPass::new(
+            render_context.fif_buffers.render_target_format(),
+            render_context.frame_settings.depth_format,
+        ));
+        self.tone_mapping_pass = Some(ToneMappingPass::new(&render_context.global_descriptor_sets, present_format));
+        self.gui_pass = Some(GuiPass::new(&render_context.global_descriptor_sets, present_format));
+
+        self.cmds = FrameCounter::frame_labes()
+            .map(|label| renderer.cmd_allocator.alloc_command_buffer(label, "terrain-strip-app"))
+            .collect();
+
+        self.terrain =
+            Some(TerrainStripGeometry::new(Self::GRID_SIZE, Self::CELL_SIZE, Self::terrain_height, "heightfield"));
+
+        camera.position = glam::vec3(0.0, 14.0, 36.0);
+        camera.euler_pitch_deg = -25.0;
+    }
+
+    fn draw_ui(&mut self, ui: &Ui) {
+        if let Some(terrain) = &self.terrain {
+            ui.text(format!("vertices: {}", terrain.vertex_cnt()));
+            ui.text(format!("indices: {}", terrain.index_cnt()));
+            ui.text(format!("strips: {}", Self::GRID_SIZE));
+        }
+    }
+
+    fn update(&mut self, _renderer: &mut Renderer) {}
+
+    fn draw(&self, renderer: &Renderer, gui_draw_data: &imgui::DrawData, fence: &GfxSemaphore) {
+        let render_context = &renderer.render_context;
+        let frame_label = render_context.frame_counter.frame_label();
+        let frame_id = render_context.frame_counter.frame_id();
+        let fif_buffers = &render_context.fif_buffers;
+        let render_present = renderer.render_present.as_ref().unwrap();
+
+        let mut graph = RenderGraphBuilder::new();
+        graph.signal_semaphore(RgSemaphoreInfo::timeline(
+            fence.handle(),
+            vk::PipelineStageFlags2::BOTTOM_OF_PIPE,
+            frame_id,
+        ));
+
+        let (render_target_image_handle, render_target_view_handle) = fif_buffers.render_target_handle(frame_label);
+        let render_target = graph.import_image(
+            "render-target",
+            render_target_image_handle,
+            Some(render_target_view_handle),
+            fif_buffers.render_target_format(),
+            RgImageState::UNDEFINED_TOP,
+            None,
+        );
+        // depth image 在各帧之间共享，需要等待上一帧的深度写入完成；内容会被 clear，因此 layout 可以视为 UNDEFINED
+        let depth_image = graph.import_image(
+            "depth",
+            fif_buffers.depth_image,
+            Some(fif_buffers.depth_image_view_handle()),
+            render_context.frame_settings.depth_format,
+            RgImageState::new(
+                vk::PipelineStageFlags2::LATE_FRAGMENT_TESTS,
+                vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE,
+                vk::ImageLayout::UNDEFINED,
+            ),
+            None,
+        );
+
+        let (present_image, present_view) = render_present.current_image_and_view();
+        let present_image = graph.import_image(
+            "present-image",
+            present_image,
+            Some(present_view),
+            render_present.swapchain_image_info().image_format,
+            RgImageState::UNDEFINED_BOTTOM,
+            Some(RgSemaphoreInfo::binary(
+                render_present.current_present_complete_semaphore(frame_label).handle(),
+                vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+            )),
+        );
+        graph.export_image(
+            present_image,
+            RgImageState::PRESENT_BOTTOM,
+            Some(RgSemaphoreInfo::binary(
+                render_present.current_render_compute_semaphore().handle(),
+                vk::PipelineStageFlags2::BOTTOM_OF_PIPE,
+            )),
+        );
+
+        graph
+            .add_pass(
+                "terrain-strip",
+                TerrainStripRgPass {
+                    terrain_strip_pass: self.terrain_strip_pass.as_ref().unwrap(),
+                    render_context,
+                    terrain: self.terrain.as_ref().unwrap(),
+                    render_target,
+                    depth_image,
+                },
+            )
+            .add_pass(
+                "tone-mapping",
+                ToneMappingRgPass {
+                    tone_mapping_pass: self.tone_mapping_pass.as_ref().unwrap(),
+                    render_context,
+                    hdr_input: render_target,
+                    swapchain_image: present_image,
+                    swapchain_extent: render_present.swapchain_image_info().image_extent,
+                },
+            )
+            .add_pass(
+                "gui",
+                GuiRgPass {
+                    gui_pass: self.gui_pass.as_ref().unwrap(),
+                    render_context,
+
+                    ui_draw_data: gui_draw_data,
+                    gui_mesh: &render_present.gui_backend.gui_meshes[*frame_label],
+
+                    canvas_color: present_image,
+                    canvas_extent: render_present.swapchain_image_info().image_extent,
+                },
+            );
+
+        let compiled_graph = graph.compile();
+
+        let cmd = &self.cmds[*frame_label];
+        cmd.begin(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT, "terrain-strip-graph");
+        compiled_graph.execute(cmd, &render_context.gfx_resource_manager);
+        cmd.end();
+
+        Gfx::get().gfx_queue().submit(vec![compiled_graph.build_submit_info(std::slice::from_ref(cmd))], None);
+    }
+}
@@ -0,0 +1,227 @@
+use std::rc::Rc;
+
+use ash::vk;
+use truvis_crate_tools::resource::TruvisPath;
+use truvis_gfx::basic::bytes::BytesConvert;
+use truvis_gfx::resources::special_buffers::index_buffer::GfxIndex32Buffer;
+use truvis_gfx::resources::special_buffers::structured_buffer::GfxStructuredBuffer;
+use truvis_gfx::{
+    basic::color::LabelColor,
+    commands::command_buffer::GfxCommandBuffer,
+    pipelines::{
+        graphics_pipeline::{GfxGraphicsPipeline, GfxGraphicsPipelineCreateInfo, GfxPipelineLayout},
+        rendering_info::GfxRenderingInfo,
+    },
+};
+use truvis_render_graph::render_context::RenderContext;
+use truvis_render_graph::render_graph::{RgImageHandle, RgImageState, RgPass, RgPassBuilder, RgPassContext};
+use truvis_shader_binding::truvisl;
+
+/// 以 triangle strip 组织的高度场地形
+///
+/// 每一行格子对应一条 strip，行与行之间插入 [`Self::RESTART_INDEX`]，
+/// 整个地形只需要一次 draw call
+pub struct TerrainStripGeometry {
+    pub positions: GfxStructuredBuffer<glam::Vec3>,
+    pub normals: GfxStructuredBuffer<glam::Vec3>,
+    pub indices: GfxIndex32Buffer,
+
+    pub min_height: f32,
+    pub max_height: f32,
+}
+// new & init
+impl TerrainStripGeometry {
+    /// index 类型为 u32，开启 primitive restart 后最大值会断开 strip
+    pub const RESTART_INDEX: u32 = u32::MAX;
+
+    /// 生成 `grid_size x grid_size` 个格子的地形，中心位于原点
+    ///
+    /// # Params
+    /// * `cell_size` - 每个格子的边长
+    /// * `height_fn` - 输入世界空间的 (x, z)，返回地形高度
+    pub fn new(grid_size: u32, cell_size: f32, height_fn: impl Fn(f32, f32) -> f32, name: &str) -> Self {
+        let _span = tracy_client::span!("TerrainStripGeometry::new");
+        assert!(grid_size > 0, "terrain {name}: empty grid");
+
+        let vertex_cnt_per_row = grid_size + 1;
+        let half_extent = grid_size as f32 * cell_size * 0.5;
+        let world_xz = |ix: u32, iz: u32| (ix as f32 * cell_size - half_extent, iz as f32 * cell_size - half_extent);
+
+        let mut positions = Vec::with_capacity((vertex_cnt_per_row * vertex_cnt_per_row) as usize);
+        let mut normals = Vec::with_capacity(positions.capacity());
+        for iz in 0..vertex_cnt_per_row {
+            for ix in 0..vertex_cnt_per_row {
+                let (x, z) = world_xz(ix, iz);
+                positions.push(glam::vec3(x, height_fn(x, z), z));
+
+                // 中心差分求法线
+                let dx = height_fn(x + cell_size, z) - height_fn(x - cell_size, z);
+                let dz = height_fn(x, z + cell_size) - height_fn(x, z - cell_size);
+                normals.push(glam::vec3(-dx, 2.0 * cell_size, -dz).normalize());
+            }
+        }
+
+        // 每行的顶点按 (ix, iz), (ix, iz + 1) 交替排列，第一个三角形从上方看为 CCW，
+        // strip 中后续三角形的绕序由硬件保持一致
+        let mut indices = Vec::with_capacity((grid_size * (2 * vertex_cnt_per_row + 1)) as usize);
+        for iz in 0..grid_size {
+            if iz != 0 {
+                indices.push(Self::RESTART_INDEX);
+            }
+            for ix in 0..vertex_cnt_per_row {
+                indices.push(iz * vertex_cnt_per_row + ix);
+                indices.push((iz + 1) * vertex_cnt_per_row + ix);
+            }
+        }
+
+        let (min_height, max_height) = positions
+            .iter()
+            .fold((f32::MAX, f32::MIN), |(min_h, max_h), position| (min_h.min(position.y), max_h.max(position.y)));
+
+        fn upload<T: Copy>(data: &[T], name: String) -> GfxStructuredBuffer<T> {
+            let buffer = GfxStructuredBuffer::new_ssbo(data.len(), name);
+            buffer.transfer_data_sync(data);
+            buffer
+        }
+
+        Self {
+            positions: upload(&positions, format!("{name}-terrain-positions")),
+            normals: upload(&normals, format!("{name}-terrain-normals")),
+            indices: GfxIndex32Buffer::new_with_data(&indices, format!("{name}-terrain-indices")),
+            min_height,
+            max_height,
+        }
+    }
+}
+// getter
+impl TerrainStripGeometry {
+    #[inline]
+    pub fn vertex_cnt(&self) -> usize {
+        self.positions.len()
+    }
+
+    #[inline]
+    pub fn index_cnt(&self) -> usize {
+        self.indices.index_cnt()
+    }
+}
+
+/// 使用 triangle strip + primitive restart 绘制 [`TerrainStripGeometry`]
+pub struct TerrainStripPass {
+    pipeline: GfxGraphicsPipeline,
+}
+// new & init
+impl TerrainStripPass {
+    pub fn new(color_format: vk::Format, depth_format: vk::Format) -> Self {
+        let shader_path = TruvisPath::shader_build_path_str("terrain/terrain_strip.slang");
+
+        let mut ci = GfxGraphicsPipelineCreateInfo::default();
+        ci.vertex_shader_stage(&shader_path, c"vs_main");
+        ci.fragment_shader_stage(&shader_path, c"ps_main");
+        ci.primitive_topology(vk::PrimitiveTopology::TRIANGLE_STRIP);
+        ci.primitive_restart(true);
+
+        ci.attach_info(vec![color_format], Some(depth_format), None);
+        ci.color_blend(
+            vec![
+                vk::PipelineColorBlendAttachmentState::default()
+                    .blend_enable(false)
+                    .color_write_mask(vk::ColorComponentFlags::RGBA),
+            ],
+            [0.0; 4],
+        );
+
+        let pipeline_layout = Rc::new(GfxPipelineLayout::new(
+            &[],
+            &[vk::PushConstantRange::default()
+                .stage_flags(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT)
+                .offset(0)
+                .size(size_of::<truvisl::terrain::PushConstants>() as u32)],
+            "terrain-strip-pass",
+        ));
+        let pipeline = GfxGraphicsPipeline::new(&ci, pipeline_layout, "terrain-strip-pipe");
+
+        Self { pipeline }
+    }
+}
+// tools
+impl TerrainStripPass {
+    pub fn draw(
+        &self,
+        cmd: &GfxCommandBuffer,
+        render_context: &RenderContext,
+        terrain: &TerrainStripGeometry,
+        color_view: vk::ImageView,
+        depth_view: vk::ImageView,
+    ) {
+        let frame_label = render_context.frame_counter.frame_label();
+        let extent = render_context.frame_settings.frame_extent;
+
+        let rendering_info = GfxRenderingInfo::new(
+            vec![color_view],
+            Some(depth_view),
+            vk::Rect2D {
+                offset: vk::Offset2D::default(),
+                extent,
+            },
+        );
+        cmd.cmd_begin_rendering2(&rendering_info);
+        cmd.begin_label("[terrain-strip-pass]draw", LabelColor::COLOR_PASS);
+
+        cmd.cmd_bind_pipeline(vk::PipelineBindPoint::GRAPHICS, self.pipeline.handle());
+        let viewport = render_context.frame_settings.camera_convention.viewport(extent.into());
+        cmd.cmd_set_viewport(0, std::slice::from_ref(&viewport));
+        cmd.cmd_set_scissor(0, &[extent.into()]);
+
+        let push_constant = truvisl::terrain::PushConstants {
+            frame_data: render_context.per_frame_data_buffers[*frame_label].device_address(),
+            positions: terrain.positions.device_address(),
+            normals: terrain.normals.device_address(),
+            min_height: terrain.min_height,
+            max_height: terrain.max_height,
+        };
+        cmd.cmd_push_constants(
+            self.pipeline.layout(),
+            vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+            0,
+            BytesConvert::bytes_of(&push_constant),
+        );
+
+        cmd.cmd_bind_index_buffer(&terrain.indices, 0);
+        cmd.draw_indexed(terrain.index_cnt() as u32, 0, 1, 0, 0);
+
+        cmd.end_label();
+        cmd.end_rendering();
+    }
+}
+
+pub struct TerrainStripRgPass<'a> {
+    pub terrain_strip_pass: &'a TerrainStripPass,
+
+    pub render_context: &'a RenderContext,
+    pub terrain: &'a TerrainStripGeometry,
+
+    pub render_target: RgImageHandle,
+    pub depth_image: RgImageHandle,
+}
+
+impl RgPass for TerrainStripRgPass<'_> {
+    fn setup(&mut self, builder: &mut RgPassBuilder) {
+        builder.write_image(self.render_target, RgImageState::COLOR_ATTACHMENT_WRITE);
+        builder.write_image(self.depth_image, RgImageState::DEPTH_ATTACHMENT_WRITE);
+    }
+
+    fn execute(&self, ctx: &RgPassContext<'_>) {
+        let render_target_view =
+            ctx.get_image_view(self.render_target).expect("TerrainStripPass: render_target not found");
+        let depth_view = ctx.get_image_view(self.depth_image).expect("TerrainStripPass: depth_image not found");
+
+        self.terrain_strip_pass.draw(
+            ctx.cmd,
+            self.render_context,
+            self.terrain,
+            render_target_view.handle(),
+            depth_view.handle(),
+        );
+    }
+}
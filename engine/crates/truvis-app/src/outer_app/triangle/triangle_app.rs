@@ -31,8 +31,7 @@ impl OuterApp for HelloTriangleApp {
         ));
 
         self.cmds = FrameCounter::frame_labes()
-            .iter()
-            .map(|label| renderer.cmd_allocator.alloc_command_buffer(*label, "triangle-app"))
+            .map(|label| renderer.cmd_allocator.alloc_command_buffer(label, "triangle-app"))
             .collect_vec();
     }
 
@@ -99,7 +98,6 @@ impl OuterApp for HelloTriangleApp {
 
                     ui_draw_data: gui_draw_data,
                     gui_mesh: &render_present.gui_backend.gui_meshes[*frame_label],
-                    tex_map: &render_present.gui_backend.tex_map,
 
                     canvas_color: swapchain_image_rg_handle,
                     canvas_extent: render_present.swapchain_image_info().image_extent,
@@ -0,0 +1,189 @@
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use truvis_gfx::query::gpu_timer::GfxGpuTimer;
+
+/// 最近若干帧的耗时（毫秒），超出容量时丢弃最旧的一帧
+struct FrameTimeHistory {
+    samples: VecDeque<f32>,
+    capacity: usize,
+}
+// new & init
+impl FrameTimeHistory {
+    fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+}
+// getter
+impl FrameTimeHistory {
+    fn latest(&self) -> f32 {
+        self.samples.back().copied().unwrap_or_default()
+    }
+
+    fn average(&self) -> f32 {
+        if self.samples.is_empty() { 0.0 } else { self.samples.iter().sum::<f32>() / self.samples.len() as f32 }
+    }
+
+    fn max(&self) -> f32 {
+        self.samples.iter().copied().fold(0.0, f32::max)
+    }
+}
+// update
+impl FrameTimeHistory {
+    fn push(&mut self, ms: f32) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(ms);
+    }
+}
+
+/// 性能 overlay：CPU 帧时间、GPU 总耗时的滚动曲线，以及各个 pass 的 GPU 耗时
+///
+/// - CPU 帧时间为相邻两次 present 之间的间隔，包含等待 fif 的时间
+/// - GPU 耗时来自 `GfxGpuTimer` 的 timestamp，各个 pass 在同一个 queue 上顺序执行，总耗时为各个 pass 之和；
+///   结果会延迟 fif 数量的帧
+pub struct PerfOverlay {
+    pub visible: bool,
+
+    cpu_history: FrameTimeHistory,
+    gpu_history: FrameTimeHistory,
+    /// 上一次 present 的时间
+    last_present: Option<Instant>,
+}
+impl Default for PerfOverlay {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            cpu_history: FrameTimeHistory::new(Self::HISTORY_LEN),
+            gpu_history: FrameTimeHistory::new(Self::HISTORY_LEN),
+            last_present: None,
+        }
+    }
+}
+// update
+impl PerfOverlay {
+    /// 曲线保留的帧数
+    const HISTORY_LEN: usize = 240;
+
+    /// 每帧 present 之后调用，overlay 隐藏时同样需要调用，保证曲线是连续的
+    pub fn on_present(&mut self, gpu_timer: &GfxGpuTimer) {
+        let now = Instant::now();
+        if let Some(last_present) = self.last_present {
+            self.cpu_history.push(now.duration_since(last_present).as_secs_f32() * 1000.0);
+        }
+        self.last_present = Some(now);
+
+        if gpu_timer.is_supported() {
+            self.gpu_history.push(gpu_timer.results().iter().map(|(_, ms)| ms).sum());
+        }
+    }
+}
+// tools
+impl PerfOverlay {
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn draw_ui(&self, ui: &imgui::Ui, gpu_timer: &GfxGpuTimer) {
+        if !self.visible {
+            return;
+        }
+
+        ui.window("Performance")
+            .position([10.0, 420.0], imgui::Condition::FirstUseEver)
+            .size([320.0, 360.0], imgui::Condition::FirstUseEver)
+            .build(|| {
+                Self::draw_history(ui, "CPU", &self.cpu_history);
+                if !gpu_timer.is_supported() {
+                    ui.text("GPU: timestamp query is not supported");
+                    return;
+                }
+                Self::draw_history(ui, "GPU", &self.gpu_history);
+
+                ui.separator();
+                Self::draw_pass_table(ui, &gpu_timer.results());
+            });
+    }
+
+    fn draw_history(ui: &imgui::Ui, label: &str, history: &FrameTimeHistory) {
+        let samples = history.samples.iter().copied().collect::<Vec<_>>();
+        ui.plot_lines(format!("##{}_frame_time", label), &samples)
+            .overlay_text(format!(
+                "{}: {:.2} ms (avg {:.2}, max {:.2})",
+                label,
+                history.latest(),
+                history.average(),
+                history.max()
+            ))
+            .scale_min(0.0)
+            .scale_max(history.max() * 1.2 + 1.0)
+            .graph_size([0.0, 60.0])
+            .build();
+    }
+
+    fn draw_pass_table(ui: &imgui::Ui, results: &[(String, f32)]) {
+        let Some(_table) = ui.begin_table_header_with_flags(
+            "##gpu_passes",
+            [
+                imgui::TableColumnSetup::new("Pass"),
+                imgui::TableColumnSetup::new("ms"),
+                imgui::TableColumnSetup::new("%"),
+            ],
+            imgui::TableFlags::ROW_BG | imgui::TableFlags::BORDERS_INNER_V,
+        ) else {
+            return;
+        };
+
+        for (name, ms, share) in Self::pass_shares(results) {
+            ui.table_next_row();
+            ui.table_next_column();
+            ui.text(name);
+            ui.table_next_column();
+            ui.text(format!("{:.3}", ms));
+            ui.table_next_column();
+            ui.text(format!("{:.1}", share * 100.0));
+        }
+    }
+
+    /// 各个 pass 的耗时以及在总耗时中的占比，总耗时为 0 时占比为 0
+    fn pass_shares(results: &[(String, f32)]) -> Vec<(&str, f32, f32)> {
+        let total_ms: f32 = results.iter().map(|(_, ms)| ms).sum();
+        results
+            .iter()
+            .map(|(name, ms)| (name.as_str(), *ms, if total_ms > 0.0 { ms / total_ms } else { 0.0 }))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_time_history_ring() {
+        let mut history = FrameTimeHistory::new(3);
+        assert_eq!(history.average(), 0.0);
+        for ms in [1.0, 2.0, 3.0, 6.0] {
+            history.push(ms);
+        }
+        // 最旧的一帧被丢弃
+        assert_eq!(history.samples, [2.0, 3.0, 6.0]);
+        assert_eq!(history.latest(), 6.0);
+        assert_eq!(history.max(), 6.0);
+        assert!((history.average() - 11.0 / 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_pass_shares() {
+        let results = vec![("gbuffer".to_string(), 1.0), ("rt".to_string(), 3.0)];
+        let shares = PerfOverlay::pass_shares(&results);
+        assert_eq!(shares, vec![("gbuffer", 1.0, 0.25), ("rt", 3.0, 0.75)]);
+
+        let results = vec![("empty".to_string(), 0.0)];
+        assert_eq!(PerfOverlay::pass_shares(&results), vec![("empty", 0.0, 0.0)]);
+    }
+}
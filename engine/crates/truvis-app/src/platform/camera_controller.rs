@@ -1,9 +1,45 @@
-use crate::platform::input_event::KeyCode;
+use crate::platform::input_event::GamepadAxis;
+use crate::platform::input_map::Action;
 use crate::platform::input_state::InputState;
-use truvis_renderer::platform::camera::Camera;
+use truvis_renderer::platform::camera::{Camera, CameraBookmark, ProjectionMode};
+
+/// 相机的操作方式
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum CameraMode {
+    /// 右键拖动旋转视角，WASD 水平移动，QE 上下移动（默认绑定，参见 `InputMap`）
+    ///
+    /// 手柄：左摇杆水平移动，右摇杆旋转视角，右扳机上升，左扳机下降
+    #[default]
+    Fps,
+    /// 围绕 target 旋转：左键拖动旋转，滚轮缩放距离，中键拖动平移 target
+    ///
+    /// 手柄：右摇杆旋转，左摇杆平移 target，扳机缩放距离
+    Orbit,
+}
+
+/// 从当前视角平滑过渡到保存的视角
+struct CameraTransition {
+    from: CameraBookmark,
+    to: CameraBookmark,
+    elapsed_s: f32,
+}
 
 pub struct CameraController {
     camera: Camera,
+    mode: CameraMode,
+    /// 正在进行的视角过渡，过渡期间不响应输入
+    transition: Option<CameraTransition>,
+    /// 调用了 [`Self::load_bookmark`]，还没有被 [`Self::take_jumped`] 取走
+    jumped: bool,
+
+    /// 移动速度（单位/秒）
+    pub move_speed: f32,
+    /// 鼠标灵敏度（度/像素）
+    pub mouse_sensitivity: f32,
+    /// 手柄摇杆推到底时视角旋转的速度（度/秒）
+    pub gamepad_look_speed: f32,
+    /// 读取视角时过渡的时长（秒），为 0 时直接跳转
+    pub bookmark_transition_s: f32,
 }
 
 impl Default for CameraController {
@@ -13,10 +49,20 @@ impl Default for CameraController {
 }
 
 impl CameraController {
+    /// 滚轮每滚动一格，轨道模式下到 target 的距离缩放的比例
+    const ORBIT_ZOOM_STEP: f32 = 0.9;
+
     /// 创建新的相机控制器
     pub fn new() -> Self {
         Self {
             camera: Camera::default(),
+            mode: CameraMode::default(),
+            transition: None,
+            jumped: false,
+            move_speed: 320.0,
+            mouse_sensitivity: 1.0 / 7.0,
+            gamepad_look_speed: 120.0,
+            bookmark_transition_s: 0.4,
         }
     }
 
@@ -30,37 +76,178 @@ impl CameraController {
         &mut self.camera
     }
 
+    #[inline]
+    pub fn mode(&self) -> CameraMode {
+        self.mode
+    }
+
+    /// 切换操作方式，相机的位置与朝向保持不变
+    pub fn set_mode(&mut self, mode: CameraMode) {
+        if mode == CameraMode::Orbit && self.mode != CameraMode::Orbit {
+            self.camera.begin_orbit();
+        }
+        self.mode = mode;
+    }
+
+    /// 过渡到保存的视角，参见 [`Self::bookmark_transition_s`]
+    pub fn load_bookmark(&mut self, bookmark: &CameraBookmark) {
+        if self.bookmark_transition_s > 0.0 {
+            self.transition = Some(CameraTransition {
+                from: self.camera.bookmark(),
+                to: *bookmark,
+                elapsed_s: 0.0,
+            });
+        } else {
+            self.transition = None;
+            self.camera.apply_bookmark(bookmark);
+        }
+        self.jumped = true;
+    }
+
+    /// 上一次调用之后相机是否跳转过视角，跳转之后时域算法的历史不再可用
+    pub fn take_jumped(&mut self) -> bool {
+        std::mem::take(&mut self.jumped)
+    }
+
     /// 根据输入更新相机状态
     pub fn update(&mut self, input_state: &InputState, viewport_size: glam::Vec2, deltatime: std::time::Duration) {
-        let delta_time_s = deltatime.as_secs_f32();
-
         self.camera.set_aspect_ratio(viewport_size.x / viewport_size.y);
 
+        if self.update_transition(deltatime.as_secs_f32()) {
+            return;
+        }
+
+        match self.mode {
+            CameraMode::Fps => self.update_fps(input_state, deltatime.as_secs_f32()),
+            CameraMode::Orbit => self.update_orbit(input_state, viewport_size),
+        }
+        self.update_gamepad(input_state, viewport_size, deltatime.as_secs_f32());
+    }
+
+    fn update_fps(&mut self, input_state: &InputState, delta_time_s: f32) {
         if input_state.is_right_button_pressed() {
             let mouse_delta = input_state.get_mouse_delta();
 
-            self.camera.rotate_yaw(-mouse_delta[0] as f32 / 7.0);
-            self.camera.rotate_pitch(-mouse_delta[1] as f32 / 7.0);
+            self.camera.rotate_yaw(-mouse_delta[0] as f32 * self.mouse_sensitivity);
+            self.camera.rotate_pitch(-mouse_delta[1] as f32 * self.mouse_sensitivity);
         }
 
-        let move_speed = 320.0_f32;
-        if input_state.is_key_pressed(KeyCode::KeyW) {
+        let move_speed = self.move_speed;
+        if input_state.is_action_pressed(Action::MoveForward) {
             self.camera.move_forward(delta_time_s * move_speed);
         }
-        if input_state.is_key_pressed(KeyCode::KeyS) {
+        if input_state.is_action_pressed(Action::MoveBackward) {
             self.camera.move_forward(-delta_time_s * move_speed);
         }
-        if input_state.is_key_pressed(KeyCode::KeyA) {
+        if input_state.is_action_pressed(Action::MoveLeft) {
             self.camera.move_right(-delta_time_s * move_speed);
         }
-        if input_state.is_key_pressed(KeyCode::KeyD) {
+        if input_state.is_action_pressed(Action::MoveRight) {
             self.camera.move_right(delta_time_s * move_speed);
         }
-        if input_state.is_key_pressed(KeyCode::KeyE) {
+        if input_state.is_action_pressed(Action::MoveUp) {
             self.camera.move_up(delta_time_s * move_speed);
         }
-        if input_state.is_key_pressed(KeyCode::KeyQ) {
+        if input_state.is_action_pressed(Action::MoveDown) {
             self.camera.move_up(-delta_time_s * move_speed);
         }
     }
+
+    /// 轨道模式下的鼠标操作，GUI 使用鼠标时不响应
+    pub fn update_orbit(&mut self, input_state: &InputState, viewport_size: glam::Vec2) {
+        if input_state.mouse_captured_by_gui {
+            return;
+        }
+        let mouse_delta = input_state.get_mouse_delta();
+        let mouse_delta = glam::vec2(mouse_delta[0] as f32, mouse_delta[1] as f32);
+
+        if input_state.is_left_button_pressed() {
+            self.camera.orbit_rotate(-mouse_delta.x * self.mouse_sensitivity, -mouse_delta.y * self.mouse_sensitivity);
+        }
+
+        if input_state.is_middle_button_pressed() {
+            // target 跟随鼠标移动，屏幕的 y 轴向下
+            let world_per_pixel = self.orbit_world_per_pixel(viewport_size.y);
+            self.camera.orbit_pan(-mouse_delta.x * world_per_pixel, mouse_delta.y * world_per_pixel);
+        }
+
+        let wheel_delta = input_state.get_mouse_wheel_delta() as f32;
+        if wheel_delta != 0.0 {
+            let scale = Self::ORBIT_ZOOM_STEP.powf(wheel_delta);
+            self.camera.orbit_zoom(scale);
+            // 正交投影的画面大小与距离无关，同时缩放可视区域
+            if let ProjectionMode::Orthographic { height } = &mut self.camera.projection_mode {
+                *height *= scale;
+            }
+        }
+    }
+
+    /// 手柄与键鼠同时生效，两者的作用叠加
+    fn update_gamepad(&mut self, input_state: &InputState, viewport_size: glam::Vec2, delta_time_s: f32) {
+        let left_stick = input_state.gamepad_left_stick();
+        let right_stick = input_state.gamepad_right_stick();
+        // 右扳机为正，左扳机为负
+        let trigger = input_state.gamepad_trigger(GamepadAxis::RightTrigger)
+            - input_state.gamepad_trigger(GamepadAxis::LeftTrigger);
+        if left_stick == glam::Vec2::ZERO && right_stick == glam::Vec2::ZERO && trigger == 0.0 {
+            return;
+        }
+
+        // 摇杆的 y 轴向上为正
+        let look_delta = right_stick * self.gamepad_look_speed * delta_time_s;
+        match self.mode {
+            CameraMode::Fps => {
+                self.camera.rotate_yaw(-look_delta.x);
+                self.camera.rotate_pitch(look_delta.y);
+
+                let move_delta = self.move_speed * delta_time_s;
+                self.camera.move_forward(left_stick.y * move_delta);
+                self.camera.move_right(left_stick.x * move_delta);
+                self.camera.move_up(trigger * move_delta);
+            }
+            CameraMode::Orbit => {
+                self.camera.orbit_rotate(-look_delta.x, look_delta.y);
+
+                // 摇杆推到底时，每秒平移半个画面
+                let pan_delta =
+                    left_stick * 0.5 * viewport_size.y * self.orbit_world_per_pixel(viewport_size.y) * delta_time_s;
+                self.camera.orbit_pan(pan_delta.x, pan_delta.y);
+
+                // 扳机按到底时，每秒缩放的比例与滚轮滚动 4 格相同
+                let scale = Self::ORBIT_ZOOM_STEP.powf(trigger * 4.0 * delta_time_s);
+                self.camera.orbit_zoom(scale);
+                if let ProjectionMode::Orthographic { height } = &mut self.camera.projection_mode {
+                    *height *= scale;
+                }
+            }
+        }
+    }
+
+    /// 推进视角过渡，返回过渡是否仍在进行
+    fn update_transition(&mut self, delta_time_s: f32) -> bool {
+        let Some(transition) = self.transition.as_mut() else {
+            return false;
+        };
+        transition.elapsed_s += delta_time_s;
+        let t = (transition.elapsed_s / self.bookmark_transition_s).min(1.0);
+        // smoothstep，开始与结束时速度为 0
+        let eased_t = t * t * (3.0 - 2.0 * t);
+        self.camera.apply_bookmark(&transition.from.lerp(&transition.to, eased_t));
+
+        if t >= 1.0 {
+            self.transition = None;
+        }
+        true
+    }
+
+    /// target 所在的平面上，一个像素对应的世界空间距离
+    fn orbit_world_per_pixel(&self, viewport_height: f32) -> f32 {
+        let view_height = match self.camera.projection_mode {
+            ProjectionMode::Perspective { fov_y_deg } => {
+                2.0 * self.camera.orbit_distance * (fov_y_deg.to_radians() * 0.5).tan()
+            }
+            ProjectionMode::Orthographic { height } => height,
+        };
+        view_height / viewport_height.max(1.0)
+    }
 }
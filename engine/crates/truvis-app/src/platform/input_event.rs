@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 // 参考 winit::MouseButton
 #[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub enum MouseButton {
@@ -16,17 +18,128 @@ pub enum ElementState {
     Released,
 }
 
-// 参考 winit::KeyCode
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum KeyCode {
-    KeyW,
+/// 定义 [`KeyCode`] 以及包含所有按键的 `KeyCode::ALL`
+macro_rules! define_key_codes {
+    ($($key:ident),* $(,)?) => {
+        // 参考 winit::KeyCode，变体名与 winit::KeyCode 以及浏览器的 KeyboardEvent.code 一致
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+        pub enum KeyCode {
+            $($key,)*
+
+            Other,
+        }
+        impl KeyCode {
+            /// 除 `Other` 之外的所有按键
+            pub const ALL: &'static [Self] = &[$(Self::$key),*];
+        }
+    };
+}
+define_key_codes!(
     KeyA,
-    KeyS,
+    KeyB,
+    KeyC,
     KeyD,
     KeyE,
+    KeyF,
+    KeyG,
+    KeyH,
+    KeyI,
+    KeyJ,
+    KeyK,
+    KeyL,
+    KeyM,
+    KeyN,
+    KeyO,
+    KeyP,
     KeyQ,
+    KeyR,
+    KeyS,
+    KeyT,
+    KeyU,
+    KeyV,
+    KeyW,
+    KeyX,
+    KeyY,
+    KeyZ,
+    Digit0,
+    Digit1,
+    Digit2,
+    Digit3,
+    Digit4,
+    Digit5,
+    Digit6,
+    Digit7,
+    Digit8,
+    Digit9,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    ArrowUp,
+    ArrowDown,
+    ArrowLeft,
+    ArrowRight,
+    Space,
+    Enter,
+    Escape,
+    Tab,
+    Backspace,
+    ShiftLeft,
+    ShiftRight,
+    ControlLeft,
+    ControlRight,
+    AltLeft,
+    AltRight,
+);
+impl KeyCode {
+    /// 在 GUI 中显示的名字，例如 `KeyW` 显示为 `W`，`Digit1` 显示为 `1`
+    pub fn display_name(self) -> String {
+        let name = format!("{:?}", self);
+        match name.strip_prefix("Key").or_else(|| name.strip_prefix("Digit")) {
+            Some(short_name) => short_name.to_string(),
+            None => name,
+        }
+    }
 
-    Other,
+    /// 按名字查找按键，忽略大小写；名字可以是变体名（`KeyW`、`ArrowUp`）或者显示的名字（`w`、`1`）
+    ///
+    /// 以字符串传递按键的窗口系统（例如 WebView 中的 KeyboardEvent.code）通过这里转换为 KeyCode
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|key| {
+            format!("{:?}", key).eq_ignore_ascii_case(name) || key.display_name().eq_ignore_ascii_case(name)
+        })
+    }
+}
+
+/// 手柄的模拟量输入，摇杆的 y 轴向上为正
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    /// 扳机的范围为 [0, 1]
+    LeftTrigger,
+    RightTrigger,
+}
+impl GamepadAxis {
+    pub const COUNT: usize = 6;
+}
+
+/// 手柄事件，`id` 用于区分同时连接的多个手柄
+#[derive(Debug, Clone, PartialEq)]
+pub enum GamepadEvent {
+    Connected { id: usize },
+    Disconnected { id: usize },
+    AxisChanged { id: usize, axis: GamepadAxis, value: f32 },
 }
 
 /// 输入事件类型
@@ -55,6 +168,28 @@ pub enum InputEvent {
         physical_width: u32,
         physical_height: u32,
     },
+    /// 手柄事件，由窗口系统的事件循环轮询得到
+    Gamepad(GamepadEvent),
+    /// 文件被拖放到窗口中，一次拖放多个文件时每个文件一个事件
+    FileDropped(std::path::PathBuf),
 
     Other,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_code_from_name() {
+        assert_eq!(KeyCode::from_name("KeyW"), Some(KeyCode::KeyW));
+        assert_eq!(KeyCode::from_name("w"), Some(KeyCode::KeyW));
+        assert_eq!(KeyCode::from_name("1"), Some(KeyCode::Digit1));
+        assert_eq!(KeyCode::from_name("arrowup"), Some(KeyCode::ArrowUp));
+        assert_eq!(KeyCode::from_name("f12"), Some(KeyCode::F12));
+        assert_eq!(KeyCode::from_name("Other"), None);
+        assert_eq!(KeyCode::from_name("NumpadEnter"), None);
+        assert_eq!(KeyCode::Digit1.display_name(), "1");
+        assert_eq!(KeyCode::Space.display_name(), "Space");
+    }
+}
@@ -1,4 +1,5 @@
-use crate::platform::input_event::{ElementState, InputEvent, MouseButton};
+use crate::platform::input_event::{ElementState, GamepadAxis, GamepadEvent, InputEvent, MouseButton};
+use crate::platform::input_map::{Action, InputMap};
 use crate::platform::input_state::InputState;
 use std::collections::VecDeque;
 
@@ -22,6 +23,9 @@ impl InputManager {
     }
 }
 impl InputManager {
+    /// 按下和松开之间鼠标移动的距离不超过该值时视为单击，单位 pixel
+    const CLICK_MAX_DISTANCE: f64 = 4.0;
+
     /// 创建新的输入管理器
     pub fn new() -> Self {
         Self {
@@ -40,20 +44,44 @@ impl InputManager {
     }
 
     /// 更新输入状态
-    /// 处理所有队列中的事件，更新输入状态
-    pub fn process_events(&mut self) {
+    /// 处理所有队列中的事件，更新输入状态，并通过 `input_map` 将按键转换为动作
+    pub fn process_events(&mut self, input_map: &InputMap) {
         // 保存上一帧的鼠标位置
         self.state.last_mouse_pos = self.state.crt_mouse_pos;
+        self.state.mouse_wheel_delta = 0.0;
+        self.state.left_button_clicked = false;
+        self.state.keys_triggered.clear();
+        self.state.actions_triggered.clear();
 
         // 处理事件队列中的所有事件
         while let Some(event) = self.events.pop_front() {
             match event {
                 InputEvent::KeyboardInput { key_code, state } => {
-                    self.state.key_pressed.insert(key_code, state == ElementState::Pressed);
+                    let pressed = state == ElementState::Pressed;
+                    // 按住时的重复事件不会再次触发
+                    if pressed && !self.state.is_key_pressed(key_code) {
+                        self.state.keys_triggered.push(key_code);
+                        self.state.actions_triggered.extend(input_map.actions_for_key(key_code));
+                    }
+                    self.state.key_pressed.insert(key_code, pressed);
                 }
                 InputEvent::MouseButtonInput { button, state } => {
-                    if button == MouseButton::Right {
-                        self.state.right_button_pressed = state == ElementState::Pressed;
+                    let pressed = state == ElementState::Pressed;
+                    match button {
+                        MouseButton::Left => {
+                            if pressed {
+                                self.state.left_button_press_pos = self.state.crt_mouse_pos;
+                            } else if self.state.left_button_pressed {
+                                let dx = self.state.crt_mouse_pos[0] - self.state.left_button_press_pos[0];
+                                let dy = self.state.crt_mouse_pos[1] - self.state.left_button_press_pos[1];
+                                self.state.left_button_clicked =
+                                    dx * dx + dy * dy <= Self::CLICK_MAX_DISTANCE * Self::CLICK_MAX_DISTANCE;
+                            }
+                            self.state.left_button_pressed = pressed;
+                        }
+                        MouseButton::Right => self.state.right_button_pressed = pressed,
+                        MouseButton::Middle => self.state.middle_button_pressed = pressed,
+                        _ => {}
                     }
                 }
                 InputEvent::MouseMoved {
@@ -61,12 +89,87 @@ impl InputManager {
                 } => {
                     self.state.crt_mouse_pos = position;
                 }
-                InputEvent::MouseWheel { delta: _ } => {
-                    // 可以在这里处理鼠标滚轮事件
+                InputEvent::MouseWheel { delta } => {
+                    self.state.mouse_wheel_delta += delta;
                 }
+                InputEvent::Gamepad(gamepad_event) => match gamepad_event {
+                    GamepadEvent::Connected { id } => {
+                        self.state.gamepads.insert(id, [0.0; GamepadAxis::COUNT]);
+                    }
+                    GamepadEvent::Disconnected { id } => {
+                        self.state.gamepads.remove(&id);
+                    }
+                    // 没有收到 Connected 事件的手柄同样记录下来
+                    GamepadEvent::AxisChanged { id, axis, value } => {
+                        self.state.gamepads.entry(id).or_insert([0.0; GamepadAxis::COUNT])[axis as usize] = value;
+                    }
+                },
                 InputEvent::Resized { .. } => {}
+                InputEvent::FileDropped(_) => {}
                 InputEvent::Other => {}
             }
         }
+
+        self.state.actions_pressed = Action::ALL
+            .into_iter()
+            .filter(|&action| input_map.keys(action).iter().any(|&key| self.state.is_key_pressed(key)))
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn left_button(state: ElementState) -> InputEvent {
+        InputEvent::MouseButtonInput {
+            button: MouseButton::Left,
+            state,
+        }
+    }
+
+    fn mouse_moved(x: f64, y: f64) -> InputEvent {
+        InputEvent::MouseMoved {
+            physical_position: [x, y],
+        }
+    }
+
+    /// 按下左键，移动到 `release_pos` 之后松开，返回这一帧是否单击
+    fn click(input_manager: &mut InputManager, release_pos: [f64; 2]) -> bool {
+        let input_map = InputMap::default();
+        for event in [
+            mouse_moved(100.0, 100.0),
+            left_button(ElementState::Pressed),
+            mouse_moved(release_pos[0], release_pos[1]),
+            left_button(ElementState::Released),
+        ] {
+            input_manager.push_event(event);
+        }
+        input_manager.process_events(&input_map);
+        input_manager.state().left_click_position().is_some()
+    }
+
+    #[test]
+    fn test_left_click() {
+        let mut input_manager = InputManager::new();
+        let input_map = InputMap::default();
+
+        // 轻微的抖动仍然是单击
+        assert!(click(&mut input_manager, [102.0, 103.0]));
+        assert_eq!(input_manager.state().left_click_position(), Some([102.0, 103.0]));
+        // 单击只持续一帧
+        input_manager.process_events(&input_map);
+        assert_eq!(input_manager.state().left_click_position(), None);
+
+        // 拖动不是单击
+        assert!(!click(&mut input_manager, [140.0, 100.0]));
+
+        // 按下发生在上一帧，松开时同样算作单击
+        input_manager.push_event(left_button(ElementState::Pressed));
+        input_manager.process_events(&input_map);
+        assert!(input_manager.state().is_left_button_pressed());
+        input_manager.push_event(left_button(ElementState::Released));
+        input_manager.process_events(&input_map);
+        assert!(input_manager.state().left_click_position().is_some());
     }
 }
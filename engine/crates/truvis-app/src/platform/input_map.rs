@@ -0,0 +1,272 @@
+//! 按键到抽象动作的映射
+//!
+//! 相机与工具从 [`InputState`] 中读取动作的状态，而不是直接读取按键，从而允许用户改键。
+//! 映射随用户设置一起保存在 `[input]` 中，key 为动作的名字，value 为绑定的按键：
+//!
+//! ```toml
+//! [input]
+//! move_forward = ["KeyW", "ArrowUp"]
+//! capture_frame = ["F12"]
+//! ```
+//!
+//! 缺失的动作使用默认的绑定，空数组表示不绑定任何按键。
+//!
+//! [`InputState`]: crate::platform::input_state::InputState
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::platform::input_event::KeyCode;
+
+/// 可以绑定按键的动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Action {
+    MoveForward,
+    MoveBackward,
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    GizmoTranslate,
+    GizmoRotate,
+    GizmoScale,
+    TogglePerfOverlay,
+    CaptureFrame,
+    TogglePause,
+    StepFrame,
+    FrameSelection,
+}
+
+impl Action {
+    pub const ALL: [Self; 14] = [
+        Self::MoveForward,
+        Self::MoveBackward,
+        Self::MoveLeft,
+        Self::MoveRight,
+        Self::MoveUp,
+        Self::MoveDown,
+        Self::GizmoTranslate,
+        Self::GizmoRotate,
+        Self::GizmoScale,
+        Self::TogglePerfOverlay,
+        Self::CaptureFrame,
+        Self::TogglePause,
+        Self::StepFrame,
+        Self::FrameSelection,
+    ];
+
+    /// 配置文件中使用的名字
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::MoveForward => "move_forward",
+            Self::MoveBackward => "move_backward",
+            Self::MoveLeft => "move_left",
+            Self::MoveRight => "move_right",
+            Self::MoveUp => "move_up",
+            Self::MoveDown => "move_down",
+            Self::GizmoTranslate => "gizmo_translate",
+            Self::GizmoRotate => "gizmo_rotate",
+            Self::GizmoScale => "gizmo_scale",
+            Self::TogglePerfOverlay => "toggle_perf_overlay",
+            Self::CaptureFrame => "capture_frame",
+            Self::TogglePause => "toggle_pause",
+            Self::StepFrame => "step_frame",
+            Self::FrameSelection => "frame_selection",
+        }
+    }
+
+    /// GUI 中显示的名字
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::MoveForward => "Move Forward",
+            Self::MoveBackward => "Move Backward",
+            Self::MoveLeft => "Move Left",
+            Self::MoveRight => "Move Right",
+            Self::MoveUp => "Move Up",
+            Self::MoveDown => "Move Down",
+            Self::GizmoTranslate => "Gizmo Translate",
+            Self::GizmoRotate => "Gizmo Rotate",
+            Self::GizmoScale => "Gizmo Scale",
+            Self::TogglePerfOverlay => "Toggle Perf Overlay",
+            Self::CaptureFrame => "Capture Frame",
+            Self::TogglePause => "Pause / Resume",
+            Self::StepFrame => "Step Frame",
+            Self::FrameSelection => "Frame Selection",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    fn default_keys(self) -> &'static [KeyCode] {
+        match self {
+            Self::MoveForward => &[KeyCode::KeyW],
+            Self::MoveBackward => &[KeyCode::KeyS],
+            Self::MoveLeft => &[KeyCode::KeyA],
+            Self::MoveRight => &[KeyCode::KeyD],
+            Self::MoveUp => &[KeyCode::KeyE],
+            Self::MoveDown => &[KeyCode::KeyQ],
+            Self::GizmoTranslate => &[KeyCode::KeyG],
+            Self::GizmoRotate => &[KeyCode::KeyR],
+            Self::GizmoScale => &[KeyCode::KeyT],
+            Self::TogglePerfOverlay => &[KeyCode::F3],
+            Self::CaptureFrame => &[KeyCode::F12],
+            Self::TogglePause => &[KeyCode::Space],
+            Self::StepFrame => &[KeyCode::ArrowRight],
+            Self::FrameSelection => &[KeyCode::KeyF],
+        }
+    }
+}
+
+/// 按键到动作的映射，一个动作可以绑定多个按键
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "BTreeMap<String, Vec<KeyCode>>", into = "BTreeMap<String, Vec<KeyCode>>")]
+pub struct InputMap {
+    bindings: BTreeMap<Action, Vec<KeyCode>>,
+}
+impl Default for InputMap {
+    fn default() -> Self {
+        Self {
+            bindings: Action::ALL.into_iter().map(|action| (action, action.default_keys().to_vec())).collect(),
+        }
+    }
+}
+impl From<BTreeMap<String, Vec<KeyCode>>> for InputMap {
+    fn from(value: BTreeMap<String, Vec<KeyCode>>) -> Self {
+        let mut input_map = Self::default();
+        for (name, keys) in value {
+            match Action::from_name(&name) {
+                Some(action) => {
+                    let mut deduped_keys = Vec::with_capacity(keys.len());
+                    for key in keys {
+                        if key != KeyCode::Other && !deduped_keys.contains(&key) {
+                            deduped_keys.push(key);
+                        }
+                    }
+                    input_map.bindings.insert(action, deduped_keys);
+                }
+                None => log::warn!("unknown input action: {}", name),
+            }
+        }
+        input_map
+    }
+}
+impl From<InputMap> for BTreeMap<String, Vec<KeyCode>> {
+    fn from(value: InputMap) -> Self {
+        value.bindings.into_iter().map(|(action, keys)| (action.name().to_string(), keys)).collect()
+    }
+}
+// getter
+impl InputMap {
+    /// 动作绑定的按键
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.bindings.get(&action).map_or(&[], |keys| keys.as_slice())
+    }
+
+    /// 绑定了 `key` 的所有动作
+    pub fn actions_for_key(&self, key: KeyCode) -> impl Iterator<Item = Action> + '_ {
+        self.bindings.iter().filter(move |(_, keys)| keys.contains(&key)).map(|(action, _)| *action)
+    }
+
+    /// 同一个按键绑定到了多个动作
+    ///
+    /// 相机移动不需要按住鼠标，两个动作共用按键时总会同时触发，因此都视为冲突
+    pub fn conflicts(&self) -> Vec<(KeyCode, Action, Action)> {
+        let mut conflicts = Vec::new();
+        for (idx, (&action_a, keys_a)) in self.bindings.iter().enumerate() {
+            for (&action_b, keys_b) in self.bindings.iter().skip(idx + 1) {
+                for &key in keys_a.iter().filter(|key| keys_b.contains(key)) {
+                    conflicts.push((key, action_a, action_b));
+                }
+            }
+        }
+        conflicts
+    }
+}
+// update
+impl InputMap {
+    /// 为动作追加一个按键，已经绑定时不做任何事
+    pub fn bind(&mut self, action: Action, key: KeyCode) {
+        let keys = self.bindings.entry(action).or_default();
+        if key != KeyCode::Other && !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+
+    pub fn unbind(&mut self, action: Action, key: KeyCode) {
+        if let Some(keys) = self.bindings.get_mut(&action) {
+            keys.retain(|&k| k != key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_has_no_conflicts() {
+        let input_map = InputMap::default();
+        assert!(input_map.conflicts().is_empty());
+        // 每个按键只对应一个动作
+        for action in Action::ALL {
+            for &key in input_map.keys(action) {
+                assert_eq!(input_map.actions_for_key(key).collect::<Vec<_>>(), vec![action]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let mut input_map = InputMap::default();
+        input_map.bind(Action::MoveForward, KeyCode::ArrowUp);
+        input_map.bind(Action::MoveForward, KeyCode::ArrowUp);
+        assert_eq!(input_map.keys(Action::MoveForward), &[KeyCode::KeyW, KeyCode::ArrowUp]);
+
+        input_map.bind(Action::MoveBackward, KeyCode::ArrowUp);
+        input_map.bind(Action::CaptureFrame, KeyCode::KeyT);
+        // 相机与 gizmo 共用按键同样是冲突
+        input_map.bind(Action::GizmoTranslate, KeyCode::KeyW);
+        assert_eq!(
+            input_map.conflicts(),
+            vec![
+                (KeyCode::ArrowUp, Action::MoveForward, Action::MoveBackward),
+                (KeyCode::KeyW, Action::MoveForward, Action::GizmoTranslate),
+                (KeyCode::KeyT, Action::GizmoScale, Action::CaptureFrame),
+            ]
+        );
+
+        input_map.unbind(Action::MoveBackward, KeyCode::ArrowUp);
+        input_map.unbind(Action::CaptureFrame, KeyCode::KeyT);
+        input_map.unbind(Action::GizmoTranslate, KeyCode::KeyW);
+        assert!(input_map.conflicts().is_empty());
+    }
+
+    #[test]
+    fn test_toml_round_trip() {
+        #[derive(Serialize, Deserialize)]
+        struct Wrapper {
+            input: InputMap,
+        }
+
+        let content = r#"
+            [input]
+            move_forward = ["KeyW", "ArrowUp", "KeyW"]
+            capture_frame = []
+            unknown_action = ["KeyX"]
+        "#;
+        let input_map = toml::from_str::<Wrapper>(content).unwrap().input;
+        assert_eq!(input_map.keys(Action::MoveForward), &[KeyCode::KeyW, KeyCode::ArrowUp]);
+        assert!(input_map.keys(Action::CaptureFrame).is_empty());
+        // 缺失的动作使用默认绑定
+        assert_eq!(input_map.keys(Action::GizmoScale), &[KeyCode::KeyT]);
+
+        let saved = toml::to_string(&Wrapper {
+            input: input_map.clone(),
+        })
+        .unwrap();
+        assert_eq!(toml::from_str::<Wrapper>(&saved).unwrap().input, input_map);
+    }
+}
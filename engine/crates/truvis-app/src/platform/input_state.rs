@@ -1,5 +1,6 @@
-use crate::platform::input_event::KeyCode;
-use std::collections::HashMap;
+use crate::platform::input_event::{GamepadAxis, KeyCode};
+use crate::platform::input_map::Action;
+use std::collections::{HashMap, HashSet};
 
 /// 记录输入信息
 #[derive(Default, Clone)]
@@ -8,16 +9,49 @@ pub struct InputState {
     pub crt_mouse_pos: [f64; 2],
     /// 上一帧的鼠标位置 pixel
     pub last_mouse_pos: [f64; 2],
+    pub left_button_pressed: bool,
     pub right_button_pressed: bool,
+    pub middle_button_pressed: bool,
+    /// 当前帧是否完成了一次左键单击：按下和松开之间鼠标几乎没有移动，拖动（例如旋转相机）不算单击
+    pub left_button_clicked: bool,
+    /// 左键按下时的鼠标位置 pixel
+    pub left_button_press_pos: [f64; 2],
+    /// 当前帧累计的滚轮滚动量，向上滚动为正
+    pub mouse_wheel_delta: f64,
+    /// GUI 正在使用鼠标（例如拖动滑块），此时相机不应该响应鼠标
+    pub mouse_captured_by_gui: bool,
     pub key_pressed: HashMap<KeyCode, bool>,
+    /// 当前帧按下的按键，按住时的重复事件不算
+    pub keys_triggered: Vec<KeyCode>,
+    /// 绑定的任意一个按键处于按下状态的动作，参见 `InputMap`
+    pub actions_pressed: HashSet<Action>,
+    /// 当前帧刚刚按下的动作
+    pub actions_triggered: HashSet<Action>,
+    /// 已连接的手柄，key 为手柄 id，value 为各个模拟量未经死区处理的值，按 `GamepadAxis` 的顺序排列
+    pub gamepads: HashMap<usize, [f32; GamepadAxis::COUNT]>,
 }
 
 impl InputState {
+    /// 摇杆的死区半径，摇杆回中时通常不会精确地回到 0
+    const STICK_DEAD_ZONE: f32 = 0.15;
+    /// 扳机的死区
+    const TRIGGER_DEAD_ZONE: f32 = 0.05;
+
     /// 检查键盘按键是否被按下
     pub fn is_key_pressed(&self, key_code: KeyCode) -> bool {
         self.key_pressed.get(&key_code).copied().unwrap_or(false)
     }
 
+    /// 动作绑定的任意一个按键是否被按下
+    pub fn is_action_pressed(&self, action: Action) -> bool {
+        self.actions_pressed.contains(&action)
+    }
+
+    /// 动作是否在当前帧刚刚被按下，用于快捷键
+    pub fn is_action_triggered(&self, action: Action) -> bool {
+        self.actions_triggered.contains(&action)
+    }
+
     /// 获取鼠标位置
     pub fn get_mouse_position(&self) -> [f64; 2] {
         self.crt_mouse_pos
@@ -31,8 +65,133 @@ impl InputState {
         ]
     }
 
+    /// 检查鼠标左键是否被按下
+    pub fn is_left_button_pressed(&self) -> bool {
+        self.left_button_pressed
+    }
+
+    /// 当前帧是否完成了一次左键单击，返回单击的位置
+    pub fn left_click_position(&self) -> Option<[f64; 2]> {
+        self.left_button_clicked.then_some(self.crt_mouse_pos)
+    }
+
+    /// 当前帧左键单击的位置在窗口中的归一化坐标，左上角为 (0, 0)
+    ///
+    /// 没有单击、窗口最小化或者单击的位置在窗口之外时返回 None
+    pub fn left_click_uv(&self, window_extent: [u32; 2]) -> Option<glam::Vec2> {
+        let [x, y] = self.left_click_position()?;
+        if window_extent[0] == 0 || window_extent[1] == 0 {
+            return None;
+        }
+        let uv = glam::vec2(x as f32 / window_extent[0] as f32, y as f32 / window_extent[1] as f32);
+        (uv.cmpge(glam::Vec2::ZERO).all() && uv.cmplt(glam::Vec2::ONE).all()).then_some(uv)
+    }
+
     /// 检查鼠标右键是否被按下
     pub fn is_right_button_pressed(&self) -> bool {
         self.right_button_pressed
     }
+
+    /// 检查鼠标中键是否被按下
+    pub fn is_middle_button_pressed(&self) -> bool {
+        self.middle_button_pressed
+    }
+
+    /// 当前帧的滚轮滚动量
+    pub fn get_mouse_wheel_delta(&self) -> f64 {
+        self.mouse_wheel_delta
+    }
+
+    /// 左摇杆，经过死区处理；连接了多个手柄时取推动幅度最大的一个
+    pub fn gamepad_left_stick(&self) -> glam::Vec2 {
+        self.gamepad_stick(GamepadAxis::LeftStickX, GamepadAxis::LeftStickY)
+    }
+
+    /// 右摇杆，经过死区处理；连接了多个手柄时取推动幅度最大的一个
+    pub fn gamepad_right_stick(&self) -> glam::Vec2 {
+        self.gamepad_stick(GamepadAxis::RightStickX, GamepadAxis::RightStickY)
+    }
+
+    /// 扳机的值 [0, 1]，经过死区处理；连接了多个手柄时取最大的一个
+    pub fn gamepad_trigger(&self, axis: GamepadAxis) -> f32 {
+        self.gamepads
+            .values()
+            .map(|axes| Self::apply_dead_zone(glam::vec2(axes[axis as usize].max(0.0), 0.0), Self::TRIGGER_DEAD_ZONE).x)
+            .fold(0.0, f32::max)
+    }
+
+    fn gamepad_stick(&self, axis_x: GamepadAxis, axis_y: GamepadAxis) -> glam::Vec2 {
+        self.gamepads
+            .values()
+            .map(|axes| {
+                let stick = glam::vec2(axes[axis_x as usize], axes[axis_y as usize]);
+                Self::apply_dead_zone(stick, Self::STICK_DEAD_ZONE)
+            })
+            .max_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+            .unwrap_or(glam::Vec2::ZERO)
+    }
+
+    /// 径向死区：幅度小于死区时为 0，之外的部分重新映射到 [0, 1]，方向保持不变
+    fn apply_dead_zone(value: glam::Vec2, dead_zone: f32) -> glam::Vec2 {
+        let len = value.length();
+        if len <= dead_zone {
+            return glam::Vec2::ZERO;
+        }
+        let scaled_len = ((len - dead_zone) / (1.0 - dead_zone)).min(1.0);
+        value * (scaled_len / len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_dead_zone() {
+        assert_eq!(InputState::apply_dead_zone(glam::vec2(0.1, 0.1), 0.15), glam::Vec2::ZERO);
+        // 死区之外的部分重新映射，方向不变
+        let value = InputState::apply_dead_zone(glam::vec2(0.0, -0.575), 0.15);
+        assert!((value - glam::vec2(0.0, -0.5)).length() < 1e-5);
+        // 对角线上的幅度可能超过 1
+        let value = InputState::apply_dead_zone(glam::vec2(1.0, 1.0), 0.15);
+        assert!((value.length() - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_left_click_uv() {
+        let mut input_state = InputState {
+            crt_mouse_pos: [200.0, 150.0],
+            ..Default::default()
+        };
+        assert_eq!(input_state.left_click_uv([800, 600]), None);
+
+        input_state.left_button_clicked = true;
+        assert_eq!(input_state.left_click_uv([800, 600]), Some(glam::vec2(0.25, 0.25)));
+        // 窗口最小化
+        assert_eq!(input_state.left_click_uv([0, 0]), None);
+        // 按住左键拖动到窗口之外再松开
+        input_state.crt_mouse_pos = [800.0, 10.0];
+        assert_eq!(input_state.left_click_uv([800, 600]), None);
+        input_state.crt_mouse_pos = [-1.0, 10.0];
+        assert_eq!(input_state.left_click_uv([800, 600]), None);
+    }
+
+    #[test]
+    fn test_multiple_gamepads() {
+        let mut input_state = InputState::default();
+        assert_eq!(input_state.gamepad_left_stick(), glam::Vec2::ZERO);
+
+        let mut axes = [0.0; GamepadAxis::COUNT];
+        axes[GamepadAxis::LeftStickX as usize] = 0.05;
+        axes[GamepadAxis::RightTrigger as usize] = 1.0;
+        input_state.gamepads.insert(0, axes);
+        let mut axes = [0.0; GamepadAxis::COUNT];
+        axes[GamepadAxis::LeftStickY as usize] = 1.0;
+        input_state.gamepads.insert(1, axes);
+
+        // 第一个手柄的摇杆在死区内，使用第二个手柄
+        assert!((input_state.gamepad_left_stick() - glam::vec2(0.0, 1.0)).length() < 1e-5);
+        assert_eq!(input_state.gamepad_trigger(GamepadAxis::RightTrigger), 1.0);
+        assert_eq!(input_state.gamepad_trigger(GamepadAxis::LeftTrigger), 0.0);
+    }
 }
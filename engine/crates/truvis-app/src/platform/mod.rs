@@ -1,4 +1,5 @@
 pub mod camera_controller;
 pub mod input_event;
 pub mod input_manager;
+pub mod input_map;
 pub mod input_state;
@@ -1,15 +1,35 @@
+use crate::frame_capture::FrameCapture;
+use crate::gizmo::Gizmo;
 use crate::gui_front::GuiHost;
+use crate::input_map_editor::InputMapEditor;
+use crate::material_editor::MaterialEditor;
+use crate::model_drop_loader::ModelDropLoader;
 use crate::outer_app::base::OuterApp;
-use crate::platform::camera_controller::CameraController;
+use crate::perf_overlay::PerfOverlay;
+use crate::platform::camera_controller::{CameraController, CameraMode};
 use crate::platform::input_event::InputEvent;
 use crate::platform::input_manager::InputManager;
+use crate::platform::input_map::Action;
 use crate::platform::input_state::InputState;
+use crate::resource_stats_panel::ResourceStatsPanel;
+use crate::scene_hierarchy::SceneHierarchy;
+use crate::settings::{RenderSettings, Settings};
 use ash::vk;
 use raw_window_handle::{RawDisplayHandle, RawWindowHandle};
 use std::ffi::CStr;
+use std::path::Path;
 use truvis_crate_tools::init_log::init_log;
+use truvis_crate_tools::resource::TruvisPath;
 use truvis_gfx::gfx::Gfx;
+use truvis_render_interface::gpu_scene::GpuScene;
+use truvis_render_interface::pipeline_settings::PipelineSettings;
+#[cfg(feature = "metrics")]
+use truvis_renderer::metrics::RenderMetrics;
+use truvis_renderer::platform::camera::ProjectionMode;
+use truvis_renderer::quality_governor::QualityKnob;
 use truvis_renderer::renderer::Renderer;
+use truvis_shader_binding::truvisl;
+use truvis_ui_edit_trait::UiEdit;
 
 pub fn panic_handler(info: &std::panic::PanicHookInfo) {
     log::error!("{}", info);
@@ -24,6 +44,27 @@ pub struct RenderApp {
 
     pub last_render_area: vk::Extent2D,
 
+    /// 用户设置，启动时加载，退出时保存
+    pub settings: Settings,
+    /// UI 修改了设置，需要同步到各个子系统
+    settings_dirty: bool,
+
+    /// 在当前帧结束时将渲染目标导出为 EXR
+    pending_frame_dump: bool,
+    /// 在当前帧 present 之前将 present image 保存为 PNG
+    pending_frame_capture: bool,
+
+    material_editor: MaterialEditor,
+    scene_hierarchy: SceneHierarchy,
+    /// 选中 instance 的变换手柄
+    gizmo: Gizmo,
+    resource_stats_panel: ResourceStatsPanel,
+    /// CPU/GPU 帧时间曲线与各个 pass 的 GPU 耗时，默认 F3 开关
+    perf_overlay: PerfOverlay,
+    input_map_editor: InputMapEditor,
+    /// 拖放到窗口中的模型文件；拖放的 `.hdr`/`.exr` 文件作为天空贴图加载，不经过该队列
+    model_drop_loader: ModelDropLoader,
+
     pub outer_app: Option<Box<dyn OuterApp>>,
 }
 // new & init
@@ -36,17 +77,34 @@ impl RenderApp {
             .map(|ext| unsafe { CStr::from_ptr(*ext) })
             .collect();
 
-        let renderer = Renderer::new(extra_instance_ext);
+        let settings = Settings::load(TruvisPath::user_settings_path());
+        #[allow(unused_mut)]
+        let mut renderer = Renderer::new(extra_instance_ext, settings.render.frames_in_flight as usize);
+        #[cfg(feature = "metrics")]
+        renderer.metrics.start_prometheus_exporter(&RenderMetrics::exporter_addr());
         let camera_controller = CameraController::new();
 
-        Self {
+        let mut app = Self {
             renderer,
             outer_app: Some(outer_app),
             camera_controller,
             input_manager: InputManager::new(),
             gui_host: GuiHost::new(),
             last_render_area: vk::Extent2D::default(),
-        }
+            settings,
+            settings_dirty: false,
+            pending_frame_dump: false,
+            pending_frame_capture: false,
+            material_editor: MaterialEditor::default(),
+            scene_hierarchy: SceneHierarchy::default(),
+            gizmo: Gizmo::default(),
+            resource_stats_panel: ResourceStatsPanel::default(),
+            perf_overlay: PerfOverlay::default(),
+            input_map_editor: InputMapEditor::default(),
+            model_drop_loader: ModelDropLoader::default(),
+        };
+        app.apply_settings();
+        app
     }
     pub fn init_after_window(
         &mut self,
@@ -57,20 +115,25 @@ impl RenderApp {
     ) {
         self.gui_host.hidpi_factor = window_scale_factor;
 
-        self.renderer.init_after_window(raw_display_handle, raw_window_handle, window_physical_size);
+        self.renderer.init_after_window(
+            raw_display_handle,
+            raw_window_handle,
+            window_physical_size,
+            self.settings.render.present_mode.present_modes(),
+        );
 
         {
             let _span = tracy_client::span!("OuterApp::init");
             self.outer_app.as_mut().unwrap().init(&mut self.renderer, self.camera_controller.camera_mut());
         };
 
-        let (fonts_atlas, font_tex_id) = self.gui_host.init_font();
-        self.renderer.render_present.as_mut().unwrap().gui_backend.register_font(
+        let fonts_atlas = self.gui_host.init_font();
+        let font_tex_id = self.renderer.render_present.as_mut().unwrap().gui_backend.register_font(
             &mut self.renderer.render_context.bindless_manager,
             &mut self.renderer.render_context.gfx_resource_manager,
             fonts_atlas,
-            font_tex_id,
         );
+        self.gui_host.set_font_texture_id(font_tex_id);
     }
 
     pub fn init_env() {
@@ -85,18 +148,111 @@ impl RenderApp {
 // destroy
 impl RenderApp {
     pub fn destroy(mut self) {
+        self.settings.save(TruvisPath::user_settings_path());
+
         Gfx::get().wait_idel();
 
-        self.outer_app = None;
+        if let Some(mut outer_app) = self.outer_app.take() {
+            outer_app.destroy(&mut self.renderer);
+        }
         self.renderer.destroy();
 
         Gfx::destroy();
     }
+
+    /// 渲染过程中 panic 之后尽量释放 Vulkan 资源，使得之后可以重新创建 RenderApp
+    ///
+    /// `render_app` 为 None 表示 panic 发生在 RenderApp 创建的过程中，此时只能销毁 Gfx 单例，
+    /// 其余已经创建的资源会泄漏。
+    ///
+    /// 返回 false 表示清理的过程再次 panic（例如 device lost），此时不应该再重新创建 RenderApp
+    pub fn destroy_after_panic(render_app: Option<Self>) -> bool {
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || match render_app {
+            Some(render_app) => render_app.destroy(),
+            None if Gfx::is_initialized() => {
+                log::warn!("RenderApp is not created, only destroy Gfx");
+                Gfx::get().wait_idel();
+                Gfx::destroy();
+            }
+            None => {}
+        }));
+        result.is_ok()
+    }
 }
 // update
 impl RenderApp {
-    fn time_to_render(&mut self) -> bool {
-        self.renderer.time_to_render()
+    /// 将设置同步到各个子系统
+    fn apply_settings(&mut self) {
+        let render_settings = &mut self.settings.render;
+        render_settings.render_scale =
+            render_settings.render_scale.clamp(RenderSettings::MIN_RENDER_SCALE, RenderSettings::MAX_RENDER_SCALE);
+        self.renderer.render_context.frame_settings.render_scale = render_settings.render_scale;
+        if let Some(render_present) = self.renderer.render_present.as_mut() {
+            render_present.set_present_modes(render_settings.present_mode.present_modes());
+        }
+        self.renderer.frame_limiter.set_target_fps(render_settings.target_fps.0);
+
+        self.camera_controller.move_speed = self.settings.camera.move_speed;
+        self.camera_controller.mouse_sensitivity = self.settings.camera.mouse_sensitivity;
+    }
+
+    /// 暂停时不推进时间、动画、相机与 RT 累积，但仍然每帧重绘，GUI、resize 与截图照常工作
+    ///
+    /// 暂停期间编辑了场景或者相机时重新开始累积，参见 `AccumData`
+    pub fn set_paused(&mut self, paused: bool) {
+        self.renderer.timer.set_paused(paused);
+    }
+
+    /// 只推进一帧，之后保持暂停
+    pub fn step_once(&mut self) {
+        self.renderer.timer.step_once();
+    }
+
+    #[inline]
+    pub fn is_paused(&self) -> bool {
+        self.renderer.timer.is_paused()
+    }
+
+    /// 相机对准选中的物体，没有选中时对准整个场景
+    pub fn frame_selection(&mut self) {
+        let render_context = &self.renderer.render_context;
+        let scene_manager = &render_context.scene_manager;
+        let aabb = render_context
+            .selected_instance
+            .and_then(|instance| scene_manager.instance_world_aabb(instance))
+            .unwrap_or_else(|| scene_manager.scene_aabb());
+        if aabb.is_empty() {
+            return;
+        }
+
+        self.camera_controller.camera_mut().frame_aabb(&aabb);
+        self.reset_temporal_history();
+    }
+
+    /// 替换天空贴图，app 在 [`OuterApp::on_sky_changed`] 中更新依赖天空贴图的资源
+    fn load_sky(&mut self, sky_path: &Path) {
+        if !sky_path.is_file() {
+            log::error!("sky file not found: {}", sky_path.display());
+            return;
+        }
+        log::info!("sky file dropped: {}", sky_path.display());
+
+        let render_context = &mut self.renderer.render_context;
+        let frame_id = render_context.frame_counter.frame_id();
+        render_context.gpu_scene.load_sky(
+            &mut render_context.gfx_resource_manager,
+            &mut render_context.bindless_manager,
+            sky_path,
+            frame_id,
+        );
+        self.outer_app.as_mut().unwrap().on_sky_changed(&mut self.renderer);
+        self.reset_temporal_history();
+    }
+
+    /// 相机发生了跳变或者画面尺寸改变，上一帧的矩阵以及 TAA 的历史都不能再使用
+    fn reset_temporal_history(&mut self) {
+        self.renderer.render_context.camera_history.reset();
+        self.outer_app.as_mut().unwrap().reset_temporal_history();
     }
 
     pub fn handle_event(&mut self, event: &InputEvent) {
@@ -105,7 +261,8 @@ impl RenderApp {
     }
 
     fn build_ui(&mut self) {
-        let elapsed = self.renderer.timer.delta_time();
+        // 暂停时 imgui 仍然使用真实经过的时间
+        let elapsed = self.renderer.timer.real_delta_time();
         let swapchain_image_size = self.renderer.render_present.as_ref().unwrap().swapchain.as_ref().unwrap().extent();
 
         self.gui_host.new_frame(elapsed, |ui| {
@@ -138,6 +295,12 @@ impl RenderApp {
                             "swapchain: {:.0}x{:.0}",
                             swapchain_image_size.width, swapchain_image_size.height
                         ));
+
+                        let (loaded_textures, total_textures) = self.renderer.render_context.asset_hub.load_progress();
+                        if loaded_textures < total_textures {
+                            ui.text(format!("Loading textures: {}/{}", loaded_textures, total_textures));
+                        }
+                        self.model_drop_loader.draw_status(ui);
                     }
 
                     // camera info
@@ -158,13 +321,66 @@ impl RenderApp {
                             camera.camera_forward().z
                         ));
                         ui.text(format!("CameraAspect: {:.2}", camera.asp));
-                        ui.text(format!("CameraFov(Vertical): {:.2}°", camera.fov_deg_vertical));
+                        match camera.projection_mode {
+                            ProjectionMode::Perspective { fov_y_deg } => {
+                                ui.text(format!("CameraFov(Vertical): {:.2}°", fov_y_deg))
+                            }
+                            ProjectionMode::Orthographic { height } => {
+                                ui.text(format!("CameraOrthoHeight: {:.2}", height))
+                            }
+                        }
                         ui.text(format!(
                             "Accum Frames: {}",
                             self.renderer.render_context.accum_data.accum_frames_num()
                         ));
+                        ui.same_line();
+                        if ui.small_button("Reset##accum") {
+                            self.renderer.render_context.accum_data.reset();
+                        }
                         ui.new_line();
                     }
+
+                    // gpu scene 上传和绘制统计（上一帧）
+                    {
+                        let upload_stats = self.renderer.render_context.gpu_scene.upload_stats();
+                        ui.text(format!("Scene Upload: {} B", upload_stats.total_bytes()));
+                        ui.text(format!(
+                            "  inst {} / mat {} / light {} / geo {} / scene {}",
+                            upload_stats.instance_bytes,
+                            upload_stats.material_bytes,
+                            upload_stats.light_bytes,
+                            upload_stats.geometry_bytes,
+                            upload_stats.scene_bytes
+                        ));
+
+                        let draw_stats = self.renderer.render_context.gpu_scene.draw_stats();
+                        if draw_stats.draw_calls > 0 {
+                            ui.text(format!(
+                                "Raster Draw Calls: {} ({} instances)",
+                                draw_stats.draw_calls, draw_stats.instances
+                            ));
+                        }
+                    }
+
+                    // 各个 pass 的 GPU 耗时（延迟 fif 数量的帧）
+                    {
+                        let gpu_timer = &self.renderer.render_context.gpu_timer;
+                        if gpu_timer.is_supported() {
+                            ui.text("GPU:");
+                            for (name, ms) in gpu_timer.results() {
+                                ui.text(format!("  {}: {:.3} ms", name, ms));
+                            }
+                        }
+                    }
+
+                    if self.renderer.timer.is_paused() {
+                        ui.text_colored([1.0, 0.8, 0.2, 1.0], "PAUSED");
+                    }
+
+                    // 应用自定义的状态
+                    for line in self.outer_app.as_ref().unwrap().overlay_text() {
+                        ui.text(line);
+                    }
                 });
 
             // 可交互的控制面板窗口
@@ -172,26 +388,88 @@ impl RenderApp {
                 .position([10.0, 200.0], imgui::Condition::FirstUseEver)
                 .size([250.0, 200.0], imgui::Condition::FirstUseEver)
                 .build(|| {
+                    // 暂停时时间、动画、相机与累积都不推进，画面仍然每帧重绘
+                    let timer = &mut self.renderer.timer;
+                    let paused = timer.is_paused();
+                    if ui.button(if paused { "Play" } else { "Pause" }) {
+                        timer.set_paused(!paused);
+                    }
+                    ui.same_line();
+                    if ui.button("Step") {
+                        timer.step_once();
+                    }
+                    ui.separator();
+
                     let pipeline_settings = &mut self.renderer.render_context.pipeline_settings;
-                    ui.slider("channel", 0, 9, &mut pipeline_settings.channel);
+                    ui.slider("channel", 0, PipelineSettings::RAY_QUERY_SHADOW_CHANNEL, &mut pipeline_settings.channel);
                     ui.text(match pipeline_settings.channel {
-                        0 => "final",
+                        PipelineSettings::FINAL_CHANNEL => "final",
                         1 => "normal",
                         2 => "base color",
-                        3 => "not accum",
-                        4 => "from NEE HDRI",
-                        5 => "from emission",
+                        PipelineSettings::NOT_ACCUM_CHANNEL => "not accum",
+                        4 => "from NEE",
+                        5 => "from emission & rect light",
                         6 => "from BDRF HDRi",
                         7 => "NEE bounce 0",
                         8 => "NEE bounce 1",
                         9 => "Irradiance Cache",
+                        PipelineSettings::SSAO_CHANNEL => "SSAO",
+                        PipelineSettings::RAY_QUERY_SHADOW_CHANNEL => "Ray Query Shadow",
                         _ => "Unknown",
                     });
 
+                    ui.separator();
+                    ui.text("Camera");
+                    {
+                        let mut mode_idx = match self.camera_controller.mode() {
+                            CameraMode::Fps => 0,
+                            CameraMode::Orbit => 1,
+                        };
+                        if ui.combo_simple_string("Mode", &mut mode_idx, &["FPS", "Orbit"]) {
+                            self.camera_controller.set_mode(match mode_idx {
+                                0 => CameraMode::Fps,
+                                _ => CameraMode::Orbit,
+                            });
+                        }
+                        if self.camera_controller.mode() == CameraMode::Orbit {
+                            ui.text("LMB: rotate, MMB: pan, Wheel: zoom");
+                        }
+
+                        // 切换投影方式时相机的位置与朝向保持不变
+                        let camera = self.camera_controller.camera_mut();
+                        let mut projection_idx = camera.projection_mode.is_orthographic() as usize;
+                        if ui.combo_simple_string("Projection", &mut projection_idx, &["Perspective", "Orthographic"]) {
+                            camera.projection_mode = match projection_idx {
+                                0 => ProjectionMode::DEFAULT_PERSPECTIVE,
+                                _ => ProjectionMode::DEFAULT_ORTHOGRAPHIC,
+                            };
+                            self.renderer.render_context.accum_data.reset();
+                            // 投影突变，上一帧的矩阵不能用于重投影
+                            self.renderer.render_context.camera_history.reset();
+                        }
+                        let projection_changed = match &mut camera.projection_mode {
+                            ProjectionMode::Perspective { fov_y_deg } => ui.slider("Fov Y", 10.0, 120.0, fov_y_deg),
+                            ProjectionMode::Orthographic { height } => ui.slider("Ortho Height", 0.1, 200.0, height),
+                        };
+                        if projection_changed {
+                            self.renderer.render_context.accum_data.reset();
+                        }
+                    }
+
                     ui.separator();
                     ui.text("Irradiance Cache");
                     ui.checkbox("Enable IC", &mut pipeline_settings.ic_enabled);
 
+                    ui.separator();
+                    ui.text("Light Sampling");
+                    // 与 shader 中 LightSamplingMode 的顺序一致
+                    let mut light_sampling = pipeline_settings.light_sampling as usize;
+                    if ui.combo_simple_string("Strategy", &mut light_sampling, &["MIS", "Light Only", "BRDF Only"]) {
+                        pipeline_settings.light_sampling = light_sampling as truvisl::rt::LightSamplingMode;
+                        // 切换策略后重新累积，便于在相同 spp 下对比噪声
+                        self.renderer.render_context.accum_data.reset();
+                    }
+
                     ui.separator();
                     ui.text("Denoise Settings");
 
@@ -205,17 +483,144 @@ impl RenderApp {
                     ui.slider("Sigma Depth", 0.01, 2.0, &mut denoise.sigma_depth);
                     ui.slider("Sigma Normal", 0.01, 2.0, &mut denoise.sigma_normal);
                     ui.slider("Kernel Radius", 1, 5, &mut denoise.kernel_radius);
+                    _disabled.end();
+
+                    ui.separator();
+                    ui.text("SSAO Settings");
+
+                    let ssao = &mut pipeline_settings.ssao;
+                    ui.checkbox("Enable SSAO", &mut ssao.enabled);
+
+                    // AO 调试通道下即使未启用也需要能调整参数
+                    let _disabled =
+                        ui.begin_disabled(!ssao.enabled && pipeline_settings.channel != PipelineSettings::SSAO_CHANNEL);
+                    ui.slider("Radius", 0.1, 100.0, &mut ssao.radius);
+                    ui.slider("Sample Count", 1, 64, &mut ssao.sample_count);
+                    ui.slider("Intensity", 0.0, 4.0, &mut ssao.intensity);
+                    ui.slider("Bias", 0.0, 5.0, &mut ssao.bias);
+                    _disabled.end();
+
+                    ui.separator();
+                    ui.text("Height Fog Settings");
+
+                    pipeline_settings.height_fog.draw_ui(ui);
+
+                    ui.separator();
+                    ui.text("TAA Settings");
+
+                    pipeline_settings.taa.draw_ui(ui);
+
+                    ui.separator();
+                    ui.text("Bloom Settings");
+
+                    pipeline_settings.bloom.draw_ui(ui);
+
+                    ui.separator();
+                    ui.text("Tone Mapping");
+
+                    pipeline_settings.tone_mapping.draw_ui(ui);
+
+                    ui.separator();
+                    ui.text("Quality Governor");
+
+                    let quality_governor = &mut self.renderer.quality_governor;
+                    ui.checkbox("Auto Quality", &mut quality_governor.enabled);
+                    ui.slider("Frame Budget (ms)", 4.0, 50.0, &mut quality_governor.frame_budget_ms);
+                    ui.text(format!("Frame Time: {:.2} ms", quality_governor.smoothed_frame_ms()));
+                    for knob in QualityKnob::ALL {
+                        // 第 0 项为自动调节，其余为锁定的档位
+                        let state = quality_governor.knobs().iter().find(|state| state.knob == knob).unwrap();
+                        let mut selected = state.locked.map_or(0, |level| level + 1);
+                        let items = std::iter::once("Auto".to_string())
+                            .chain((0..knob.level_count()).map(|level| format!("Level {level}")))
+                            .collect::<Vec<_>>();
+                        ui.text(format!("{}: level {}", knob.name(), quality_governor.level(knob)));
+                        if ui.combo_simple_string(format!("Lock##{}", knob.name()), &mut selected, &items) {
+                            quality_governor.lock(knob, selected.checked_sub(1));
+                        }
+                    }
+
+                    ui.separator();
+                    ui.text("Gizmo");
+                    self.gizmo.draw_settings_ui(ui);
+
+                    ui.separator();
+                    ui.text("User Settings");
+                    self.settings_dirty |= self.settings.draw_ui(ui);
+                    if let Some(render_present) = self.renderer.render_present.as_ref() {
+                        ui.text(format!("Current Present Mode: {:?}", render_present.current_present_mode()));
+                    }
+
+                    ui.separator();
+                    if ui.button("Dump EXR") {
+                        self.pending_frame_dump = true;
+                    }
+                    ui.same_line();
+                    if ui.button("Capture PNG") {
+                        self.pending_frame_capture = true;
+                    }
                 });
 
+            // 材质编辑面板，修改会在下一帧上传到 GPU
+            let materials_window = ui
+                .window("Materials")
+                .position([270.0, 200.0], imgui::Condition::FirstUseEver)
+                .size([300.0, 260.0], imgui::Condition::FirstUseEver)
+                .collapsed(true, imgui::Condition::FirstUseEver)
+                .build(|| {
+                    self.material_editor.draw_ui(ui, &mut self.renderer.render_context);
+                });
+            // 面板折叠时不高亮
+            if materials_window.is_none() {
+                self.renderer.render_context.highlight_material = None;
+            }
+
+            // 场景层级面板，选中项与鼠标拾取联动
+            ui.window("Scene Hierarchy")
+                .position([580.0, 200.0], imgui::Condition::FirstUseEver)
+                .size([300.0, 360.0], imgui::Condition::FirstUseEver)
+                .collapsed(true, imgui::Condition::FirstUseEver)
+                .build(|| {
+                    self.scene_hierarchy.draw_ui(ui, &mut self.renderer.render_context);
+                });
+
+            // 显存与资源统计面板，用于定位泄漏
+            self.resource_stats_panel.update();
+            ui.window("Resources")
+                .position([890.0, 200.0], imgui::Condition::FirstUseEver)
+                .size([300.0, 320.0], imgui::Condition::FirstUseEver)
+                .collapsed(true, imgui::Condition::FirstUseEver)
+                .build(|| {
+                    self.resource_stats_panel.draw_ui(ui, &self.renderer.render_context, &self.renderer.cmd_allocator);
+                });
+
+            // 按键绑定面板，修改随用户设置一起保存
+            ui.window("Key Bindings")
+                .position([1200.0, 200.0], imgui::Condition::FirstUseEver)
+                .size([320.0, 340.0], imgui::Condition::FirstUseEver)
+                .collapsed(true, imgui::Condition::FirstUseEver)
+                .build(|| {
+                    self.input_map_editor.draw_ui(ui, &mut self.settings.input, self.input_manager.state());
+                });
+
+            // 相机视角书签，随用户设置一起保存
+            ui.window("Camera Bookmarks")
+                .position([1200.0, 560.0], imgui::Condition::FirstUseEver)
+                .size([320.0, 200.0], imgui::Condition::FirstUseEver)
+                .collapsed(true, imgui::Condition::FirstUseEver)
+                .build(|| {
+                    self.settings.camera_bookmarks.draw_ui(ui, &mut self.camera_controller);
+                });
+
+            self.perf_overlay.draw_ui(ui, &self.renderer.render_context.gpu_timer);
+
+            self.gizmo.draw_ui(ui, self.camera_controller.camera(), &mut self.renderer.render_context);
+
             self.outer_app.as_mut().unwrap().draw_ui(ui);
         });
     }
 
     pub fn big_update(&mut self) {
-        if !self.time_to_render() {
-            return;
-        }
-
         // Begin Frame
         {
             let _span = tracy_client::span!("Begin Frame");
@@ -226,11 +631,21 @@ impl RenderApp {
         {
             let _span = tracy_client::span!("Process Input Events");
 
+            // 拖放的天空贴图在处理完所有事件之后加载，多个时只使用最后一个
+            let mut dropped_sky = None;
             for event in self.input_manager.get_events() {
                 // imgui 处理事件
                 // TODO imgui 是否吞掉事件
                 self.gui_host.handle_event(event);
 
+                if let InputEvent::FileDropped(file) = event {
+                    if GpuScene::is_sky_file(file) {
+                        dropped_sky = Some(file.clone());
+                    } else {
+                        self.model_drop_loader.enqueue(file.clone());
+                    }
+                }
+
                 // resize 相关事件
                 if let InputEvent::Resized {
                     physical_width,
@@ -250,21 +665,57 @@ impl RenderApp {
                 }
             }
 
+            if let Some(sky_path) = dropped_sky {
+                self.load_sky(&sky_path);
+            }
+
             // input manager 处理事件
-            self.input_manager.process_events();
+            self.input_manager.process_events(&self.settings.input);
+
+            // 快捷键，改键或者在 GUI 中输入文字时不响应
+            // want_capture_keyboard 来自上一帧的 GUI，文本框的焦点会一直保持到下一帧
+            if !self.input_map_editor.is_capturing() && !self.gui_host.want_capture_keyboard() {
+                let input_state = self.input_manager.state();
+                if input_state.is_action_triggered(Action::CaptureFrame) {
+                    self.pending_frame_capture = true;
+                }
+                if input_state.is_action_triggered(Action::TogglePerfOverlay) {
+                    self.perf_overlay.toggle();
+                }
+                let toggle_pause = input_state.is_action_triggered(Action::TogglePause);
+                let step = input_state.is_action_triggered(Action::StepFrame);
+                let frame_selection = input_state.is_action_triggered(Action::FrameSelection);
+                self.gizmo.update_shortcuts(input_state);
+                self.settings.camera_bookmarks.update_shortcuts(input_state, &mut self.camera_controller);
+                if toggle_pause {
+                    self.set_paused(!self.is_paused());
+                }
+                if step {
+                    self.step_once();
+                }
+                if frame_selection {
+                    self.frame_selection();
+                }
+            }
         }
 
         // resize
         if self.renderer.need_resize() {
             self.renderer.recreate_swapchain();
             self.outer_app.as_mut().unwrap().on_window_resized(&mut self.renderer);
+            self.reset_temporal_history();
         }
         self.renderer.update_frame_settings();
 
         // GPU 帧的开始
         // acquire image 应该等到 CPU world 更新完毕再执行，但是放到这里可以简化 resize 的处理
         {
-            self.renderer.acquire_image();
+            // swapchain out-of-date 时会在 acquire 中重建，依赖 swapchain 尺寸的资源也需要随之重建
+            if self.renderer.acquire_image() {
+                self.outer_app.as_mut().unwrap().on_window_resized(&mut self.renderer);
+                self.reset_temporal_history();
+                self.renderer.update_frame_settings();
+            }
         }
 
         // GUI 绘制
@@ -283,11 +734,25 @@ impl RenderApp {
                 .prepare_render_data(self.gui_host.get_render_data(), frame_label);
         }
 
+        // 同步 UI 修改的设置，present mode 与渲染缩放在下一帧生效
+        if self.settings_dirty {
+            self.settings_dirty = false;
+            self.apply_settings();
+        }
+
         // 更新 CPU world
         {
             let _span = tracy_client::span!("Renderer Update");
 
-            self.update_scene(&self.input_manager.state().clone());
+            let model_loaded =
+                self.model_drop_loader.update(&mut self.renderer.render_context, self.camera_controller.camera_mut());
+            // 书签可能在快捷键或者 UI 中读取
+            if model_loaded || self.camera_controller.take_jumped() {
+                self.reset_temporal_history();
+            }
+            let mut input_state = self.input_manager.state().clone();
+            input_state.mouse_captured_by_gui = self.gui_host.want_capture_mouse() || self.gizmo.is_active();
+            self.update_scene(&input_state);
         }
 
         // 将数据上传到 GPU
@@ -303,13 +768,39 @@ impl RenderApp {
             self.outer_app.as_mut().unwrap().draw(
                 &self.renderer,
                 self.gui_host.get_render_data(),
-                &self.renderer.fif_timeline_semaphore,
+                self.renderer.fif_timeline.semaphore(),
             );
         }
 
+        // 截图需要在 present 之前，present 之后 image 的所有权属于 presentation engine
+        if self.pending_frame_capture {
+            self.pending_frame_capture = false;
+
+            let capture_dir = TruvisPath::temp_dir();
+            if let Err(e) = std::fs::create_dir_all(&capture_dir) {
+                log::error!("failed to create {}: {}", capture_dir.display(), e);
+            } else {
+                self.capture_frame(capture_dir.join(FrameCapture::file_name_with_timestamp()));
+            }
+        }
+
         // GPU 帧的结束
         {
             self.renderer.present_image();
+            self.perf_overlay.on_present(&self.renderer.render_context.gpu_timer);
+        }
+
+        // 导出当前帧的渲染目标
+        if self.pending_frame_dump {
+            self.pending_frame_dump = false;
+
+            let dump_dir = TruvisPath::temp_dir();
+            if let Err(e) = std::fs::create_dir_all(&dump_dir) {
+                log::error!("failed to create {}: {}", dump_dir.display(), e);
+            } else {
+                let frame_id = self.renderer.render_context.frame_counter.frame_id();
+                self.renderer.dump_frame_exr(dump_dir.join(format!("frame-{}.exr", frame_id)));
+            }
         }
 
         // End Frame ===================================
@@ -319,17 +810,51 @@ impl RenderApp {
         }
 
         tracy_client::frame_mark();
+
+        // 帧率上限：等待的时间计入帧间隔（FPS、性能曲线与 metrics 中的帧时间），
+        // 只有质量调节使用的帧耗时不包含这段等待，参见 `Renderer::end_frame`
+        self.renderer.wait_frame_limit();
+    }
+
+    /// 将当前帧的 present image 保存为 PNG，参见 [`FrameCapture`]
+    ///
+    /// 需要在当前帧的渲染命令提交之后、present 之前调用；窗口最小化时跳过
+    pub fn capture_frame(&self, path: impl AsRef<Path>) {
+        let extent = self.renderer.swapchain_image_info().image_extent;
+        if extent.width == 0 || extent.height == 0 {
+            log::warn!("window is minimized, skip frame capture");
+            return;
+        }
+
+        if let Err(e) = FrameCapture::capture(&self.renderer, path.as_ref()) {
+            log::error!("failed to capture frame to {}: {}", path.as_ref().display(), e);
+        }
     }
 
     fn update_scene(&mut self, input_state: &InputState) {
         let frame_extent = self.renderer.render_context.frame_settings.frame_extent;
 
         // Renderer: Update Input and Camera
-        self.camera_controller.update(
-            input_state,
-            glam::vec2(frame_extent.width as f32, frame_extent.height as f32),
-            self.renderer.timer.delta_time(),
-        );
+        // 暂停时相机保持不动，只同步 resize 带来的宽高比变化
+        if self.renderer.timer.is_advancing() {
+            self.camera_controller.update(
+                input_state,
+                glam::vec2(frame_extent.width as f32, frame_extent.height as f32),
+                self.renderer.timer.delta_time(),
+            );
+        } else {
+            self.camera_controller
+                .camera_mut()
+                .set_aspect_ratio(frame_extent.width as f32 / frame_extent.height as f32);
+        }
+
+        // Outer App: 单击场景
+        let swapchain_extent = self.renderer.swapchain_image_info().image_extent;
+        if let Some(uv) = input_state.left_click_uv([swapchain_extent.width, swapchain_extent.height])
+            && !input_state.mouse_captured_by_gui
+        {
+            self.outer_app.as_mut().unwrap().on_mouse_click(&mut self.renderer, uv);
+        }
 
         // Outer App: Update
         {
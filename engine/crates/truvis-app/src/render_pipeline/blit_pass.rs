@@ -2,6 +2,7 @@ use ash::vk;
 use truvis_crate_tools::resource::TruvisPath;
 use truvis_gfx::commands::command_buffer::GfxCommandBuffer;
 use truvis_render_graph::compute_pass::ComputePass;
+use truvis_render_graph::pipeline_warmup::ComputePipelineDesc;
 use truvis_render_graph::render_context::RenderContext;
 use truvis_render_graph::render_graph::{RgImageHandle, RgImageState, RgPass, RgPassBuilder, RgPassContext};
 use truvis_render_interface::bindless_manager::BindlessUavHandle;
@@ -21,15 +22,20 @@ pub struct BlitPass {
 }
 impl BlitPass {
     pub fn new(render_descriptor_sets: &GlobalDescriptorSets) -> Self {
-        let blit_pass = ComputePass::<truvisl::blit::PushConstant>::new(
-            render_descriptor_sets,
-            c"main",
-            TruvisPath::shader_build_path_str("imgui/blit.slang").as_str(),
-        );
+        let [blit_desc] = Self::pipeline_descs();
+        let blit_pass = ComputePass::from_desc(render_descriptor_sets, &blit_desc);
 
         Self { blit_pass }
     }
 
+    /// 该 pass 使用的所有 compute pipeline，[`Self::new`] 以及启动时的预热都使用这份描述
+    pub fn pipeline_descs() -> [ComputePipelineDesc; 1] {
+        [ComputePipelineDesc::new::<truvisl::blit::PushConstant>(
+            c"main",
+            TruvisPath::shader_build_path_str("imgui/blit.slang"),
+        )]
+    }
+
     pub fn exec(&self, cmd: &GfxCommandBuffer, data: BlitPassData, render_context: &RenderContext) {
         self.blit_pass.exec(
             cmd,
@@ -0,0 +1,173 @@
+use ash::vk;
+use truvis_crate_tools::resource::TruvisPath;
+use truvis_gfx::basic::color::LabelColor;
+use truvis_gfx::commands::command_buffer::GfxCommandBuffer;
+use truvis_render_graph::compute_pass::ComputePass;
+use truvis_render_graph::pipeline_warmup::ComputePipelineDesc;
+use truvis_render_graph::render_context::RenderContext;
+use truvis_render_graph::render_graph::{RgImageHandle, RgImageState, RgPass, RgPassBuilder, RgPassContext};
+use truvis_render_graph::resources::fif_buffer::FifBuffers;
+use truvis_render_interface::bindless_manager::BindlessUavHandle;
+use truvis_render_interface::global_descriptor_sets::GlobalDescriptorSets;
+use truvis_render_interface::pipeline_settings::BloomSettings;
+use truvis_shader_binding::truvisl;
+
+/// Bloom Pass 的数据
+pub struct BloomPassData {
+    /// HDR 输入（只读）
+    pub hdr_input: BindlessUavHandle,
+    /// 叠加了泛光的结果，可以与 `hdr_input` 不同
+    pub output: BindlessUavHandle,
+    /// mip 链各级，第 i 级的尺寸为 [`FifBuffers::bloom_mip_extent`]
+    pub mips: [BindlessUavHandle; FifBuffers::BLOOM_MIP_COUNT],
+    /// hdr_input 和 output 的尺寸
+    pub image_size: vk::Extent2D,
+    /// 调试通道，泛光只作用于最终结果的通道
+    pub channel: u32,
+    pub settings: BloomSettings,
+}
+
+/// Bloom Pass - 阈值提取亮部到半分辨率，逐级 downsample 模糊，再逐级 upsample 累加，最后叠加回 HDR 结果
+pub struct BloomPass {
+    bloom_pass: ComputePass<truvisl::bloom::PushConstant>,
+}
+// new & init
+impl BloomPass {
+    pub fn new(render_descriptor_sets: &GlobalDescriptorSets) -> Self {
+        let [bloom_desc] = Self::pipeline_descs();
+        let bloom_pass = ComputePass::from_desc(render_descriptor_sets, &bloom_desc);
+
+        Self { bloom_pass }
+    }
+
+    /// 该 pass 使用的所有 compute pipeline，[`Self::new`] 以及启动时的预热都使用这份描述
+    pub fn pipeline_descs() -> [ComputePipelineDesc; 1] {
+        [ComputePipelineDesc::new::<truvisl::bloom::PushConstant>(
+            c"main",
+            TruvisPath::shader_build_path_str("pp/bloom.slang"),
+        )]
+    }
+}
+// tools
+impl BloomPass {
+    /// 依次执行 prefilter、downsample、upsample 和 composite，每一步之间插入 compute -> compute 的 barrier
+    ///
+    /// mip 链需要处于 GENERAL layout
+    pub fn apply(&self, cmd: &GfxCommandBuffer, data: BloomPassData, render_context: &RenderContext) {
+        let settings = &data.settings;
+        let mip_extents: [vk::Extent2D; FifBuffers::BLOOM_MIP_COUNT] =
+            std::array::from_fn(|level| FifBuffers::bloom_mip_extent(data.image_size, level));
+
+        cmd.begin_label("[bloom]apply", LabelColor::COLOR_PASS);
+
+        let dispatch = |mode: u32,
+                        src: BindlessUavHandle,
+                        src_size: vk::Extent2D,
+                        dst: BindlessUavHandle,
+                        dst_size: vk::Extent2D| {
+            self.bloom_pass.exec(
+                cmd,
+                render_context,
+                &truvisl::bloom::PushConstant {
+                    src_image: src.0,
+                    dst_image: dst.0,
+                    src_size: glam::uvec2(src_size.width, src_size.height).into(),
+                    dst_size: glam::uvec2(dst_size.width, dst_size.height).into(),
+                    hdr_input: data.hdr_input.0,
+                    mode,
+                    threshold: settings.threshold,
+                    knee: settings.knee,
+                    intensity: settings.intensity,
+                    channel: data.channel,
+                },
+                Self::group_cnt(dst_size),
+            );
+            // 下一步会读取这一步的结果
+            cmd.memory_barrier(std::slice::from_ref(&vk::MemoryBarrier2 {
+                src_stage_mask: vk::PipelineStageFlags2::COMPUTE_SHADER,
+                src_access_mask: vk::AccessFlags2::SHADER_STORAGE_WRITE,
+                dst_stage_mask: vk::PipelineStageFlags2::COMPUTE_SHADER,
+                dst_access_mask: vk::AccessFlags2::SHADER_STORAGE_READ | vk::AccessFlags2::SHADER_STORAGE_WRITE,
+                ..Default::default()
+            }));
+        };
+
+        dispatch(truvisl::bloom::MODE_PREFILTER, data.hdr_input, data.image_size, data.mips[0], mip_extents[0]);
+        for level in 1..FifBuffers::BLOOM_MIP_COUNT {
+            dispatch(
+                truvisl::bloom::MODE_DOWNSAMPLE,
+                data.mips[level - 1],
+                mip_extents[level - 1],
+                data.mips[level],
+                mip_extents[level],
+            );
+        }
+        for level in (1..FifBuffers::BLOOM_MIP_COUNT).rev() {
+            dispatch(
+                truvisl::bloom::MODE_UPSAMPLE,
+                data.mips[level],
+                mip_extents[level],
+                data.mips[level - 1],
+                mip_extents[level - 1],
+            );
+        }
+        dispatch(truvisl::bloom::MODE_COMPOSITE, data.mips[0], mip_extents[0], data.output, data.image_size);
+
+        cmd.end_label();
+    }
+
+    #[inline]
+    fn group_cnt(extent: vk::Extent2D) -> glam::UVec3 {
+        glam::uvec3(
+            extent.width.div_ceil(truvisl::bloom::SHADER_X as u32),
+            extent.height.div_ceil(truvisl::bloom::SHADER_Y as u32),
+            1,
+        )
+    }
+}
+
+/// Bloom Pass 的 RenderGraph 封装
+pub struct BloomRgPass<'a> {
+    pub bloom_pass: &'a BloomPass,
+
+    pub render_context: &'a RenderContext,
+
+    /// HDR 输入（只读）
+    pub hdr_input: RgImageHandle,
+    /// 叠加了泛光的结果（只写）
+    pub output: RgImageHandle,
+    /// mip 链各级（读写）
+    pub mips: [RgImageHandle; FifBuffers::BLOOM_MIP_COUNT],
+
+    pub image_extent: vk::Extent2D,
+}
+
+impl<'a> RgPass for BloomRgPass<'a> {
+    fn setup(&mut self, builder: &mut RgPassBuilder) {
+        builder.read_image(self.hdr_input, RgImageState::STORAGE_READ_COMPUTE);
+        for mip in self.mips {
+            builder.read_write_image(mip, RgImageState::STORAGE_READ_WRITE_COMPUTE);
+        }
+        builder.write_image(self.output, RgImageState::STORAGE_WRITE_COMPUTE);
+    }
+
+    fn execute(&self, ctx: &RgPassContext) {
+        let bindless_manager = &self.render_context.bindless_manager;
+        let pipeline_settings = &self.render_context.pipeline_settings;
+        let uav_handle =
+            |image: RgImageHandle| bindless_manager.get_shader_uav_handle(ctx.get_image_view_handle(image).unwrap());
+
+        self.bloom_pass.apply(
+            ctx.cmd,
+            BloomPassData {
+                hdr_input: uav_handle(self.hdr_input),
+                output: uav_handle(self.output),
+                mips: self.mips.map(uav_handle),
+                image_size: self.image_extent,
+                channel: pipeline_settings.channel,
+                settings: pipeline_settings.bloom,
+            },
+            self.render_context,
+        );
+    }
+}
@@ -0,0 +1,465 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use ash::vk;
+use truvis_crate_tools::resource::TruvisPath;
+use truvis_gfx::basic::bytes::BytesConvert;
+use truvis_gfx::basic::color::LabelColor;
+use truvis_gfx::commands::command_buffer::GfxCommandBuffer;
+use truvis_gfx::pipelines::graphics_pipeline::{GfxGraphicsPipeline, GfxGraphicsPipelineCreateInfo, GfxPipelineLayout};
+use truvis_gfx::resources::special_buffers::structured_buffer::GfxStructuredBuffer;
+use truvis_render_graph::render_context::RenderContext;
+use truvis_render_graph::render_graph::{RgImageHandle, RgImageState, RgPass, RgPassBuilder, RgPassContext};
+use truvis_render_interface::frame_counter::FrameCounter;
+use truvis_render_interface::pipeline_settings::FrameLabel;
+use truvis_scene::aabb::Aabb;
+use truvis_shader_binding::truvisl;
+
+/// 一帧内需要绘制的 debug 线段，每两个顶点组成一条线段
+#[derive(Default)]
+pub struct DebugDrawList {
+    vertices: Vec<truvisl::debug_draw::Vertex>,
+}
+// getter
+impl DebugDrawList {
+    #[inline]
+    pub fn vertices(&self) -> &[truvisl::debug_draw::Vertex] {
+        &self.vertices
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+}
+// tools
+impl DebugDrawList {
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+    }
+
+    /// 世界空间中从 `from` 到 `to` 的线段
+    pub fn line(&mut self, from: glam::Vec3, to: glam::Vec3, color: glam::Vec4) {
+        for position in [from, to] {
+            self.vertices.push(truvisl::debug_draw::Vertex {
+                position: position.into(),
+                _padding: Default::default(),
+                color: color.into(),
+            });
+        }
+    }
+
+    /// 世界空间中轴对齐包围盒的 12 条边
+    pub fn aabb(&mut self, min: glam::Vec3, max: glam::Vec3, color: glam::Vec4) {
+        let corner = |i: u32| {
+            glam::vec3(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+            )
+        };
+        for i in 0..8 {
+            // 每个角向坐标更大的方向连一条边，8 个角刚好覆盖 12 条边
+            for axis_bit in [1, 2, 4] {
+                if i & axis_bit == 0 {
+                    self.line(corner(i), corner(i | axis_bit), color);
+                }
+            }
+        }
+    }
+
+    /// 世界空间中的球体，用三个坐标平面上的大圆表示
+    pub fn sphere(&mut self, center: glam::Vec3, radius: f32, color: glam::Vec4) {
+        const SEGMENT_CNT: u32 = 32;
+
+        let point = |i: u32, axis_u: glam::Vec3, axis_v: glam::Vec3| {
+            let angle = i as f32 / SEGMENT_CNT as f32 * std::f32::consts::TAU;
+            center + radius * (angle.cos() * axis_u + angle.sin() * axis_v)
+        };
+        for (axis_u, axis_v) in [
+            (glam::Vec3::X, glam::Vec3::Y),
+            (glam::Vec3::Y, glam::Vec3::Z),
+            (glam::Vec3::Z, glam::Vec3::X),
+        ] {
+            for i in 0..SEGMENT_CNT {
+                self.line(point(i, axis_u, axis_v), point(i + 1, axis_u, axis_v), color);
+            }
+        }
+    }
+}
+
+/// 立即模式的 debug 绘制接口，可以在 [`crate::outer_app::base::OuterApp::draw`] 等只持有 `&self` 的地方随手调用
+///
+/// 累积的线段在 [`DebugDrawPass::prepare`] 中被取走并上传，因此每帧都需要重新绘制。
+/// 默认画在最上层；通过 [`Self::set_depth_test`] 开启深度测试后，之后的线段会被场景遮挡
+#[derive(Default)]
+pub struct DebugDrawer {
+    depth_test: Cell<bool>,
+    depth_tested_lines: RefCell<DebugDrawList>,
+    on_top_lines: RefCell<DebugDrawList>,
+}
+// update
+impl DebugDrawer {
+    /// 之后绘制的线段是否与场景做深度测试
+    #[inline]
+    pub fn set_depth_test(&self, depth_test: bool) {
+        self.depth_test.set(depth_test);
+    }
+
+    fn lines(&self) -> std::cell::RefMut<'_, DebugDrawList> {
+        if self.depth_test.get() { self.depth_tested_lines.borrow_mut() } else { self.on_top_lines.borrow_mut() }
+    }
+
+    pub fn draw_line(&self, from: glam::Vec3, to: glam::Vec3, color: glam::Vec4) {
+        self.lines().line(from, to, color);
+    }
+
+    pub fn draw_aabb(&self, aabb: &Aabb, color: glam::Vec4) {
+        self.lines().aabb(aabb.min, aabb.max, color);
+    }
+
+    pub fn draw_sphere(&self, center: glam::Vec3, radius: f32, color: glam::Vec4) {
+        self.lines().sphere(center, radius, color);
+    }
+
+    /// 追加 `list` 中所有的线段
+    pub fn draw_list(&self, list: &DebugDrawList) {
+        self.lines().vertices.extend_from_slice(list.vertices());
+    }
+
+    /// 取走累积的线段：(需要深度测试的, 画在最上层的)
+    fn take_lines(&self) -> (DebugDrawList, DebugDrawList) {
+        (self.depth_tested_lines.take(), self.on_top_lines.take())
+    }
+}
+
+/// 世界空间的 debug 线段，使用 LINE_LIST 一次绘制，顶点数据通过 device address 读取
+///
+/// 分为两部分绘制：
+/// - 画在最上层的线段：位于 resolve 之后、overlay 和 GUI 之前，绘制在 present image 上，不做深度测试
+/// - 需要深度测试的线段：位于场景之后、resolve 之前，绘制在 render target 上，与场景的深度比较但不写入深度。
+///   创建时没有提供 render target 和深度格式（例如光追管线没有深度）时，这部分线段也画在最上层
+pub struct DebugDrawPass {
+    on_top_pipeline: GfxGraphicsPipeline,
+    depth_test_pipeline: Option<GfxGraphicsPipeline>,
+
+    drawer: DebugDrawer,
+
+    /// 每个 fif 一份，host 可见，顶点数量变多时自动扩容；需要深度测试的顶点在前
+    vertex_buffers: Vec<RefCell<GfxStructuredBuffer<truvisl::debug_draw::Vertex>>>,
+    /// 每个 fif 中 (需要深度测试的, 画在最上层的) 顶点数量
+    vertex_cnts: Vec<Cell<(u32, u32)>>,
+    /// 每个 fif 的 vertex buffer 的 device address，扩容之后在 [`Self::prepare`] 中刷新
+    vertex_addresses: Vec<Cell<vk::DeviceAddress>>,
+}
+// new & init
+impl DebugDrawPass {
+    /// vertex buffer 的初始容量（线段数量），不够时自动扩容
+    const INITIAL_LINE_CNT: usize = 4 * 1024;
+
+    /// - `color_format`: present image 的格式，用于画在最上层的线段
+    /// - `depth_test_formats`: render target 和深度的格式，用于需要深度测试的线段
+    pub fn new(color_format: vk::Format, depth_test_formats: Option<(vk::Format, vk::Format)>) -> Self {
+        let on_top_pipeline = Self::create_pipeline(color_format, None);
+        let depth_test_pipeline = depth_test_formats.map(|(render_target_format, depth_format)| {
+            Self::create_pipeline(render_target_format, Some(depth_format))
+        });
+
+        let vertex_buffers = FrameCounter::frame_labes()
+            .map(|frame_label| {
+                RefCell::new(GfxStructuredBuffer::new(
+                    format!("debug-draw-vertices-{}", frame_label),
+                    Self::INITIAL_LINE_CNT * 2,
+                    vk::BufferUsageFlags::STORAGE_BUFFER
+                        | vk::BufferUsageFlags::TRANSFER_SRC // 扩容时需要拷贝
+                        | vk::BufferUsageFlags::TRANSFER_DST
+                        | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+                    true,
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        let vertex_addresses =
+            vertex_buffers.iter().map(|buffer| Cell::new(buffer.borrow().device_address())).collect::<Vec<_>>();
+
+        Self {
+            on_top_pipeline,
+            depth_test_pipeline,
+            drawer: DebugDrawer::default(),
+            vertex_buffers,
+            vertex_cnts: FrameCounter::frame_labes().map(|_| Cell::default()).collect(),
+            vertex_addresses,
+        }
+    }
+
+    fn create_pipeline(color_format: vk::Format, depth_format: Option<vk::Format>) -> GfxGraphicsPipeline {
+        let shader_path = TruvisPath::shader_build_path_str("debug_draw/debug_line.slang");
+
+        let mut ci = GfxGraphicsPipelineCreateInfo::default();
+        ci.vertex_shader_stage(&shader_path, c"vs_main");
+        ci.fragment_shader_stage(&shader_path, c"ps_main");
+        ci.primitive_topology(vk::PrimitiveTopology::LINE_LIST);
+
+        ci.attach_info(vec![color_format], depth_format, Some(vk::Format::UNDEFINED));
+        if depth_format.is_some() {
+            // 与场景深度相同的线段（例如贴在表面上的包围盒）也能显示
+            ci.depth_test(Some(vk::CompareOp::LESS_OR_EQUAL), false, false);
+        } else {
+            ci.depth_test(None, false, false);
+        }
+        ci.vertex_binding(vec![]);
+        ci.vertex_attribute(vec![]);
+        ci.color_blend(
+            vec![
+                vk::PipelineColorBlendAttachmentState::default()
+                    .blend_enable(true)
+                    .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
+                    .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+                    .color_blend_op(vk::BlendOp::ADD)
+                    .src_alpha_blend_factor(vk::BlendFactor::ONE)
+                    .dst_alpha_blend_factor(vk::BlendFactor::ZERO)
+                    .alpha_blend_op(vk::BlendOp::ADD)
+                    .color_write_mask(vk::ColorComponentFlags::RGBA),
+            ],
+            [0.0; 4],
+        );
+
+        let pipeline_layout = Rc::new(GfxPipelineLayout::new(
+            &[],
+            &[vk::PushConstantRange::default()
+                .stage_flags(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT)
+                .offset(0)
+                .size(size_of::<truvisl::debug_draw::PushConstants>() as u32)],
+            "debug-draw-pass",
+        ));
+        GfxGraphicsPipeline::new(&ci, pipeline_layout, "debug-draw-pipe")
+    }
+}
+// getter
+impl DebugDrawPass {
+    /// 用于累积这一帧的线段
+    #[inline]
+    pub fn drawer(&self) -> &DebugDrawer {
+        &self.drawer
+    }
+
+    /// `frame_label` 这一帧是否有需要绘制的线段，`depth_test` 区分两部分线段
+    #[inline]
+    pub fn has_lines(&self, frame_label: FrameLabel, depth_test: bool) -> bool {
+        self.vertex_range(frame_label, depth_test).1 > 0
+    }
+
+    /// (first_vertex, vertex_cnt)
+    fn vertex_range(&self, frame_label: FrameLabel, depth_test: bool) -> (u32, u32) {
+        let (depth_tested_cnt, on_top_cnt) = self.vertex_cnts[*frame_label].get();
+        if depth_test { (0, depth_tested_cnt) } else { (depth_tested_cnt, on_top_cnt) }
+    }
+}
+// update
+impl DebugDrawPass {
+    /// 取走 [`Self::drawer`] 中累积的线段，写入 `frame_label` 对应的 buffer
+    ///
+    /// 每帧在构建 render graph 之前调用一次，此时 GPU 已经不再使用该 buffer
+    pub fn prepare(&self, frame_label: FrameLabel) {
+        let (mut depth_tested_lines, mut on_top_lines) = self.drawer.take_lines();
+        if self.depth_test_pipeline.is_none() {
+            on_top_lines.vertices.append(&mut depth_tested_lines.vertices);
+        }
+
+        let mut vertex_buffer = self.vertex_buffers[*frame_label].borrow_mut();
+        vertex_buffer.clear_elements();
+        vertex_buffer.extend(depth_tested_lines.vertices());
+        vertex_buffer.extend(on_top_lines.vertices());
+        if vertex_buffer.device_address_changed() {
+            self.vertex_addresses[*frame_label].set(vertex_buffer.device_address());
+            vertex_buffer.clear_device_address_changed();
+        }
+
+        self.vertex_cnts[*frame_label]
+            .set((depth_tested_lines.vertices().len() as u32, on_top_lines.vertices().len() as u32));
+    }
+}
+// tools
+impl DebugDrawPass {
+    /// 以 LOAD 的方式绘制到 `color_view` 上，保留已经绘制的场景
+    ///
+    /// `depth_view` 不为 None 时绘制需要深度测试的线段，否则绘制画在最上层的线段
+    pub fn draw(
+        &self,
+        cmd: &GfxCommandBuffer,
+        render_context: &RenderContext,
+        color_view: vk::ImageView,
+        depth_view: Option<vk::ImageView>,
+        extent: vk::Extent2D,
+    ) {
+        let frame_label = render_context.frame_counter.frame_label();
+        let (first_vertex, vertex_cnt) = self.vertex_range(frame_label, depth_view.is_some());
+        if vertex_cnt == 0 {
+            return;
+        }
+        let pipeline = match depth_view {
+            Some(_) => self.depth_test_pipeline.as_ref().expect("DebugDrawPass: depth test is not enabled"),
+            None => &self.on_top_pipeline,
+        };
+
+        let color_attach_info = vk::RenderingAttachmentInfo::default()
+            .image_view(color_view)
+            .image_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+            .load_op(vk::AttachmentLoadOp::LOAD)
+            .store_op(vk::AttachmentStoreOp::STORE);
+        let depth_attach_info = depth_view.map(|depth_view| {
+            vk::RenderingAttachmentInfo::default()
+                .image_view(depth_view)
+                .image_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)
+                .load_op(vk::AttachmentLoadOp::LOAD)
+                .store_op(vk::AttachmentStoreOp::NONE)
+        });
+        let mut render_info = vk::RenderingInfo::default()
+            .layer_count(1)
+            .render_area(extent.into())
+            .color_attachments(std::slice::from_ref(&color_attach_info));
+        if let Some(depth_attach_info) = &depth_attach_info {
+            render_info = render_info.depth_attachment(depth_attach_info);
+        }
+
+        cmd.cmd_begin_rendering(&render_info);
+        cmd.begin_label("[debug-draw-pass]draw", LabelColor::COLOR_PASS);
+
+        cmd.cmd_bind_pipeline(vk::PipelineBindPoint::GRAPHICS, pipeline.handle());
+        // present image 与 render target 的宽高比一致，resolve 只做缩放，因此直接使用整个 canvas 作为 viewport
+        let viewport = render_context.frame_settings.camera_convention.viewport(extent.into());
+        cmd.cmd_set_viewport(0, std::slice::from_ref(&viewport));
+        cmd.cmd_set_scissor(0, &[extent.into()]);
+
+        let push_constant = truvisl::debug_draw::PushConstants {
+            frame_data: render_context.per_frame_data_buffers[*frame_label].device_address(),
+            vertices: self.vertex_addresses[*frame_label].get(),
+        };
+        cmd.cmd_push_constants(
+            pipeline.layout(),
+            vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+            0,
+            BytesConvert::bytes_of(&push_constant),
+        );
+        cmd.cmd_draw(vertex_cnt, 1, first_vertex, 0);
+
+        cmd.end_label();
+        cmd.end_rendering();
+    }
+}
+
+pub struct DebugDrawRgPass<'a> {
+    pub debug_draw_pass: &'a DebugDrawPass,
+
+    pub render_context: &'a RenderContext,
+
+    /// 画在最上层时为 present image，此时已经包含 resolve 之后的场景；需要深度测试时为 render target
+    pub canvas_color: RgImageHandle,
+    pub canvas_extent: vk::Extent2D,
+    /// 场景的深度，只读；为 None 时绘制画在最上层的线段
+    pub depth_image: Option<RgImageHandle>,
+}
+
+impl RgPass for DebugDrawRgPass<'_> {
+    fn setup(&mut self, builder: &mut RgPassBuilder) {
+        builder.read_write_image(self.canvas_color, RgImageState::COLOR_ATTACHMENT_READ_WRITE);
+        if let Some(depth_image) = self.depth_image {
+            builder.read_image(depth_image, RgImageState::DEPTH_ATTACHMENT_READ);
+        }
+    }
+
+    fn execute(&self, ctx: &RgPassContext<'_>) {
+        let canvas_view = ctx.get_image_view(self.canvas_color).expect("DebugDrawPass: canvas_color not found");
+        let depth_view = self
+            .depth_image
+            .map(|depth_image| ctx.get_image_view(depth_image).expect("DebugDrawPass: depth_image not found").handle());
+        self.debug_draw_pass.draw(ctx.cmd, self.render_context, canvas_view.handle(), depth_view, self.canvas_extent);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: glam::Vec4 = glam::vec4(1.0, 0.0, 0.0, 1.0);
+
+    fn positions(list: &DebugDrawList) -> Vec<glam::Vec3> {
+        list.vertices()
+            .iter()
+            .map(|vertex| glam::vec3(vertex.position.x, vertex.position.y, vertex.position.z))
+            .collect()
+    }
+
+    #[test]
+    fn test_line() {
+        let mut list = DebugDrawList::default();
+        assert!(list.is_empty());
+        list.line(glam::Vec3::ZERO, glam::Vec3::X, RED);
+        assert_eq!(positions(&list), vec![glam::Vec3::ZERO, glam::Vec3::X]);
+        assert!(list.vertices().iter().all(|vertex| vertex.color.x == 1.0 && vertex.color.w == 1.0));
+
+        list.clear();
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn test_aabb_edges() {
+        let min = glam::vec3(-1.0, 0.0, 2.0);
+        let max = glam::vec3(3.0, 1.0, 4.0);
+        let mut list = DebugDrawList::default();
+        list.aabb(min, max, RED);
+
+        let positions = positions(&list);
+        assert_eq!(positions.len(), 12 * 2);
+        let mut edges = Vec::new();
+        for edge in positions.chunks(2) {
+            let (from, to) = (edge[0], edge[1]);
+            // 端点都是角点，每条边只沿一个坐标轴，长度为该轴上的尺寸
+            for point in [from, to] {
+                assert!((point.cmpeq(min) | point.cmpeq(max)).all());
+            }
+            let axis_mask = (to - from).cmpne(glam::Vec3::ZERO);
+            assert_eq!(axis_mask.bitmask().count_ones(), 1);
+            assert_eq!(to - from, glam::Vec3::select(axis_mask, max - min, glam::Vec3::ZERO));
+            edges.push([from.to_array(), to.to_array()]);
+        }
+        // 没有重复的边
+        edges.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        edges.dedup();
+        assert_eq!(edges.len(), 12);
+    }
+
+    #[test]
+    fn test_sphere_points_on_surface() {
+        let center = glam::vec3(1.0, 2.0, 3.0);
+        let mut list = DebugDrawList::default();
+        list.sphere(center, 2.0, RED);
+
+        let positions = positions(&list);
+        assert_eq!(positions.len(), 3 * 32 * 2);
+        assert!(positions.iter().all(|point| (point.distance(center) - 2.0).abs() < 1e-5));
+        // 每个大圆是闭合的
+        for circle in positions.chunks(32 * 2) {
+            assert!(circle[0].distance(circle[circle.len() - 1]) < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_drawer_routes_lines_by_depth_test() {
+        let drawer = DebugDrawer::default();
+        drawer.draw_line(glam::Vec3::ZERO, glam::Vec3::X, RED);
+        drawer.set_depth_test(true);
+        drawer.draw_aabb(&Aabb::new(glam::Vec3::ZERO, glam::Vec3::ONE), RED);
+        let mut list = DebugDrawList::default();
+        list.line(glam::Vec3::Y, glam::Vec3::Z, RED);
+        drawer.draw_list(&list);
+
+        let (depth_tested, on_top) = drawer.take_lines();
+        assert_eq!(depth_tested.vertices().len(), 12 * 2 + 2);
+        assert_eq!(positions(&on_top), vec![glam::Vec3::ZERO, glam::Vec3::X]);
+
+        // 取走之后清空，下一帧需要重新绘制
+        let (depth_tested, on_top) = drawer.take_lines();
+        assert!(depth_tested.is_empty() && on_top.is_empty());
+    }
+}
@@ -0,0 +1,110 @@
+use ash::vk;
+use truvis_crate_tools::resource::TruvisPath;
+use truvis_gfx::commands::command_buffer::GfxCommandBuffer;
+use truvis_render_graph::compute_pass::ComputePass;
+use truvis_render_graph::render_context::RenderContext;
+use truvis_render_graph::render_graph::{RgImageHandle, RgImageState, RgPass, RgPassBuilder, RgPassContext};
+use truvis_render_interface::bindless_manager::BindlessUavHandle;
+use truvis_render_interface::global_descriptor_sets::GlobalDescriptorSets;
+use truvis_shader_binding::truvisl;
+
+/// 延迟着色 lighting Pass 的数据
+pub struct DeferredLightingPassData {
+    pub gbuffer_a_bindless_uav_handle: BindlessUavHandle,
+    pub gbuffer_b_bindless_uav_handle: BindlessUavHandle,
+    pub gbuffer_c_bindless_uav_handle: BindlessUavHandle,
+    pub render_target_bindless_uav_handle: BindlessUavHandle,
+    pub image_size: vk::Extent2D,
+}
+
+/// 延迟着色的 lighting Pass - 全屏 compute shader 读取 GBuffer，对每个像素累加所有灯光
+///
+/// 光照模型与前向的 phong 着色相同，参见 [`crate::render_pipeline::gbuffer_pass::GBufferPass`]
+pub struct DeferredLightingPass {
+    lighting_pass: ComputePass<truvisl::deferred_lighting::PushConstant>,
+}
+
+impl DeferredLightingPass {
+    pub fn new(render_descriptor_sets: &GlobalDescriptorSets) -> Self {
+        let lighting_pass = ComputePass::<truvisl::deferred_lighting::PushConstant>::new(
+            render_descriptor_sets,
+            c"main",
+            TruvisPath::shader_build_path_str("phong/phong_deferred_lighting.slang").as_str(),
+        );
+
+        Self { lighting_pass }
+    }
+
+    pub fn exec(&self, cmd: &GfxCommandBuffer, data: DeferredLightingPassData, render_context: &RenderContext) {
+        let frame_label = render_context.frame_counter.frame_label();
+        self.lighting_pass.exec(
+            cmd,
+            render_context,
+            &truvisl::deferred_lighting::PushConstant {
+                frame_data: render_context.per_frame_data_buffers[*frame_label].device_address(),
+                scene: render_context.gpu_scene.scene_buffer(frame_label).device_address(),
+                gbuffer_a: data.gbuffer_a_bindless_uav_handle.0,
+                gbuffer_b: data.gbuffer_b_bindless_uav_handle.0,
+                gbuffer_c: data.gbuffer_c_bindless_uav_handle.0,
+                render_target: data.render_target_bindless_uav_handle.0,
+                image_size: glam::uvec2(data.image_size.width, data.image_size.height).into(),
+            },
+            glam::uvec3(
+                data.image_size.width.div_ceil(truvisl::deferred_lighting::SHADER_X as u32),
+                data.image_size.height.div_ceil(truvisl::deferred_lighting::SHADER_Y as u32),
+                1,
+            ),
+        );
+    }
+}
+
+/// 延迟着色 lighting Pass 的 RenderGraph 封装
+pub struct DeferredLightingRgPass<'a> {
+    pub deferred_lighting_pass: &'a DeferredLightingPass,
+
+    pub render_context: &'a RenderContext,
+
+    /// GBuffer A/B/C（只读）
+    pub gbuffers: [RgImageHandle; 3],
+    /// 方向光的阴影贴图，通过 bindless 采样
+    pub shadow_map: Option<RgImageHandle>,
+    /// 光照结果（只写）
+    pub render_target: RgImageHandle,
+
+    pub image_extent: vk::Extent2D,
+}
+
+impl RgPass for DeferredLightingRgPass<'_> {
+    fn setup(&mut self, builder: &mut RgPassBuilder) {
+        for gbuffer in self.gbuffers {
+            builder.read_image(gbuffer, RgImageState::STORAGE_READ_COMPUTE);
+        }
+        if let Some(shadow_map) = self.shadow_map {
+            builder.read_image(shadow_map, RgImageState::SHADER_READ_COMPUTE);
+        }
+        builder.write_image(self.render_target, RgImageState::STORAGE_WRITE_COMPUTE);
+    }
+
+    fn execute(&self, ctx: &RgPassContext<'_>) {
+        let bindless_manager = &self.render_context.bindless_manager;
+        let [gbuffer_a, gbuffer_b, gbuffer_c] = self.gbuffers.map(|gbuffer| {
+            bindless_manager.get_shader_uav_handle(
+                ctx.get_image_view_handle(gbuffer).expect("DeferredLightingPass: gbuffer not found"),
+            )
+        });
+        let render_target_view_handle =
+            ctx.get_image_view_handle(self.render_target).expect("DeferredLightingPass: render_target not found");
+
+        self.deferred_lighting_pass.exec(
+            ctx.cmd,
+            DeferredLightingPassData {
+                gbuffer_a_bindless_uav_handle: gbuffer_a,
+                gbuffer_b_bindless_uav_handle: gbuffer_b,
+                gbuffer_c_bindless_uav_handle: gbuffer_c,
+                render_target_bindless_uav_handle: bindless_manager.get_shader_uav_handle(render_target_view_handle),
+                image_size: self.image_extent,
+            },
+            self.render_context,
+        );
+    }
+}
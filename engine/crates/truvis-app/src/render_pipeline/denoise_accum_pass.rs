@@ -2,6 +2,7 @@ use ash::vk;
 use truvis_crate_tools::resource::TruvisPath;
 use truvis_gfx::commands::command_buffer::GfxCommandBuffer;
 use truvis_render_graph::compute_pass::ComputePass;
+use truvis_render_graph::pipeline_warmup::ComputePipelineDesc;
 use truvis_render_graph::render_context::RenderContext;
 use truvis_render_graph::render_graph::{RgImageHandle, RgImageState, RgPass, RgPassBuilder, RgPassContext};
 use truvis_render_interface::bindless_manager::BindlessUavHandle;
@@ -34,6 +35,9 @@ pub struct DenoiseAccumPassData {
     pub roughness_adaptive_enabled: bool,
     pub roughness_radius_scale: f32,
     pub roughness_sigma_scale: f32,
+
+    /// 暂停时冻结累积结果，参见 `RenderContext::frame_frozen`
+    pub accum_frozen: bool,
 }
 
 /// 降噪累积 Pass - 对单帧 RT 结果进行双边滤波降噪，然后累积到 accum_image 中
@@ -43,15 +47,20 @@ pub struct DenoiseAccumPass {
 
 impl DenoiseAccumPass {
     pub fn new(render_descriptor_sets: &GlobalDescriptorSets) -> Self {
-        let denoise_accum_pass = ComputePass::<truvisl::denoise_accum::PushConstant>::new(
-            render_descriptor_sets,
-            c"main",
-            TruvisPath::shader_build_path_str("pp/denoise_accum.slang").as_str(),
-        );
+        let [denoise_accum_desc] = Self::pipeline_descs();
+        let denoise_accum_pass = ComputePass::from_desc(render_descriptor_sets, &denoise_accum_desc);
 
         Self { denoise_accum_pass }
     }
 
+    /// 该 pass 使用的所有 compute pipeline，[`Self::new`] 以及启动时的预热都使用这份描述
+    pub fn pipeline_descs() -> [ComputePipelineDesc; 1] {
+        [ComputePipelineDesc::new::<truvisl::denoise_accum::PushConstant>(
+            c"main",
+            TruvisPath::shader_build_path_str("pp/denoise_accum.slang"),
+        )]
+    }
+
     pub fn exec(&self, cmd: &GfxCommandBuffer, data: DenoiseAccumPassData, render_context: &RenderContext) {
         self.denoise_accum_pass.exec(
             cmd,
@@ -79,6 +88,7 @@ impl DenoiseAccumPass {
                 roughness_adaptive_enabled: if data.roughness_adaptive_enabled { 1 } else { 0 },
                 roughness_radius_scale: data.roughness_radius_scale,
                 roughness_sigma_scale: data.roughness_sigma_scale,
+                accum_frozen: if data.accum_frozen { 1 } else { 0 },
             },
             glam::uvec3(
                 data.image_size.width.div_ceil(truvisl::denoise_accum::SHADER_X as u32),
@@ -155,7 +165,9 @@ impl<'a> RgPass for DenoiseAccumRgPass<'a> {
                 sigma_color: denoise_settings.sigma_color,
                 sigma_depth: denoise_settings.sigma_depth,
                 sigma_normal: denoise_settings.sigma_normal,
-                kernel_radius: denoise_settings.kernel_radius,
+                kernel_radius: denoise_settings
+                    .kernel_radius
+                    .min(self.render_context.frame_settings.quality.denoise_max_kernel_radius),
                 channel: self.render_context.pipeline_settings.channel,
                 // 增强联合双边滤波参数
                 sigma_albedo: denoise_settings.sigma_albedo,
@@ -165,6 +177,7 @@ impl<'a> RgPass for DenoiseAccumRgPass<'a> {
                 roughness_adaptive_enabled: denoise_settings.roughness_adaptive_enabled,
                 roughness_radius_scale: denoise_settings.roughness_radius_scale,
                 roughness_sigma_scale: denoise_settings.roughness_sigma_scale,
+                accum_frozen: self.render_context.frame_frozen,
             },
             self.render_context,
         );
@@ -0,0 +1,148 @@
+//! 将渲染结果复制到可以跨 API 共享的 image 中
+//!
+//! 每个 fif 持有一个 [`GfxExternalImage`]，尺寸由消费方决定，每帧将 render target 缩放 blit 到当前帧的 image。
+//! 消费方（例如 web 前端的合成进程）通过 [`ExternalExportPass::export_dma_buf`] 拿到 dma-buf 后自行导入，
+//! 典型的方式是 EGL 的 `EGL_EXT_image_dma_buf_import` 或者另一个 Vulkan 设备。
+//! 目前浏览器的 WebGPU 还不能直接导入 dma-buf，webview 中需要经过合成器的原生纹理接口中转。
+//!
+//! # 同步
+//! 导出的 image 不带 semaphore。某一帧的 image 只有在该帧的 timeline fence 到达之后才能读取，
+//! 并且需要在同一个 fif 的下一帧开始之前读取完毕（即消费方最多落后 `fif_count - 1` 帧）。
+
+use ash::vk;
+use truvis_gfx::commands::barrier::GfxImageBarrier;
+use truvis_gfx::resources::external_image::{GfxDmaBufDesc, GfxExternalImage};
+use truvis_render_graph::render_context::RenderContext;
+use truvis_render_graph::render_graph::{RgImageHandle, RgImageState, RgPass, RgPassBuilder, RgPassContext};
+use truvis_render_interface::frame_counter::FrameCounter;
+use truvis_render_interface::pipeline_settings::FrameLabel;
+
+pub struct ExternalExportPass {
+    images: Vec<GfxExternalImage>,
+}
+// new & init
+impl ExternalExportPass {
+    pub const FORMAT: vk::Format = vk::Format::R8G8B8A8_UNORM;
+    const USAGE: vk::ImageUsageFlags = vk::ImageUsageFlags::TRANSFER_DST;
+
+    /// 设备不支持导出 dma-buf 时返回 None
+    pub fn new(extent: vk::Extent2D) -> Option<Self> {
+        if !GfxExternalImage::is_supported(Self::FORMAT, Self::USAGE) {
+            log::warn!("external export: {:?} can not be exported as dma-buf", Self::FORMAT);
+            return None;
+        }
+
+        let images = FrameCounter::frame_labes()
+            .map(|frame_label| {
+                GfxExternalImage::new(extent, Self::FORMAT, Self::USAGE, format!("export-{frame_label}"))
+            })
+            .collect::<Vec<_>>();
+        Some(Self { images })
+    }
+}
+// getter
+impl ExternalExportPass {
+    #[inline]
+    pub fn image(&self, frame_label: FrameLabel) -> &GfxExternalImage {
+        &self.images[*frame_label]
+    }
+
+    #[inline]
+    pub fn extent(&self) -> vk::Extent2D {
+        self.images[0].extent()
+    }
+}
+// tools
+impl ExternalExportPass {
+    /// 导出指定 fif 的 image，每个 fif 只需要导出一次，之后每帧复用
+    pub fn export_dma_buf(&self, frame_label: FrameLabel) -> GfxDmaBufDesc {
+        self.images[*frame_label].export_dma_buf()
+    }
+
+    fn exec(&self, ctx: &RgPassContext<'_>, src_image: vk::Image, src_extent: vk::Extent2D, frame_label: FrameLabel) {
+        let dst = &self.images[*frame_label];
+        let dst_extent = dst.extent();
+
+        // 每帧都会覆盖整张 image，因此不需要保留之前的内容
+        ctx.cmd.image_memory_barrier(
+            vk::DependencyFlags::empty(),
+            &[GfxImageBarrier::new()
+                .image(dst.handle())
+                .image_aspect_flag(vk::ImageAspectFlags::COLOR)
+                .layout_transfer(vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+                .src_mask(vk::PipelineStageFlags2::TOP_OF_PIPE, vk::AccessFlags2::NONE)
+                .dst_mask(vk::PipelineStageFlags2::BLIT, vk::AccessFlags2::TRANSFER_WRITE)],
+        );
+
+        let subresource = vk::ImageSubresourceLayers {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            mip_level: 0,
+            base_array_layer: 0,
+            layer_count: 1,
+        };
+        let region = vk::ImageBlit2::default()
+            .src_subresource(subresource)
+            .src_offsets([
+                vk::Offset3D::default(),
+                vk::Offset3D {
+                    x: src_extent.width as i32,
+                    y: src_extent.height as i32,
+                    z: 1,
+                },
+            ])
+            .dst_subresource(subresource)
+            .dst_offsets([
+                vk::Offset3D::default(),
+                vk::Offset3D {
+                    x: dst_extent.width as i32,
+                    y: dst_extent.height as i32,
+                    z: 1,
+                },
+            ]);
+        ctx.cmd.cmd_blit_image(
+            &vk::BlitImageInfo2::default()
+                .src_image(src_image)
+                .src_image_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+                .dst_image(dst.handle())
+                .dst_image_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+                .regions(std::slice::from_ref(&region))
+                .filter(vk::Filter::LINEAR),
+        );
+
+        // 消费方按 linear 内存读取，GENERAL 保证内存布局与 subresource layout 一致
+        ctx.cmd.image_memory_barrier(
+            vk::DependencyFlags::empty(),
+            &[GfxImageBarrier::new()
+                .image(dst.handle())
+                .image_aspect_flag(vk::ImageAspectFlags::COLOR)
+                .layout_transfer(vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::GENERAL)
+                .src_mask(vk::PipelineStageFlags2::BLIT, vk::AccessFlags2::TRANSFER_WRITE)
+                .dst_mask(vk::PipelineStageFlags2::BOTTOM_OF_PIPE, vk::AccessFlags2::NONE)],
+        );
+    }
+}
+
+pub struct ExternalExportRgPass<'a> {
+    pub external_export_pass: &'a ExternalExportPass,
+
+    pub render_context: &'a RenderContext,
+
+    pub src_image: RgImageHandle,
+    pub src_image_extent: vk::Extent2D,
+}
+impl RgPass for ExternalExportRgPass<'_> {
+    fn setup(&mut self, builder: &mut RgPassBuilder) {
+        builder.read_image(self.src_image, RgImageState::TRANSFER_SRC);
+    }
+
+    fn execute(&self, ctx: &RgPassContext<'_>) {
+        let (src_image, _) = ctx.get_image_and_view(self.src_image).expect("ExternalExportPass: src_image not found");
+
+        self.external_export_pass.exec(
+            ctx,
+            src_image.handle(),
+            self.src_image_extent,
+            self.render_context.frame_counter.frame_label(),
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use ash::vk;
+use std::{mem::offset_of, rc::Rc};
+use truvis_crate_tools::resource::TruvisPath;
+use truvis_gfx::basic::bytes::BytesConvert;
+use truvis_gfx::resources::layout::GfxVertexLayout;
+use truvis_gfx::resources::vertex_layout::soa_3d::VertexLayoutSoA3D;
+use truvis_gfx::{
+    basic::color::LabelColor,
+    commands::command_buffer::GfxCommandBuffer,
+    pipelines::{
+        graphics_pipeline::{GfxGraphicsPipeline, GfxGraphicsPipelineCreateInfo, GfxPipelineLayout},
+        rendering_info::GfxRenderingInfo,
+    },
+};
+use truvis_render_graph::render_context::RenderContext;
+use truvis_render_graph::render_graph::{RgImageHandle, RgImageState, RgPass, RgPassBuilder, RgPassContext};
+use truvis_render_graph::resources::fif_buffer::FifBuffers;
+use truvis_render_interface::global_descriptor_sets::GlobalDescriptorSets;
+use truvis_shader_binding::truvisl;
+
+use crate::render_pipeline::instance_index_buffer::InstanceIndexBuffers;
+
+/// 延迟着色的 G-buffer pass：光栅化视锥内的 instance，将表面属性写入 [`FifBuffers`] 的 GBuffer A/B/C
+///
+/// 布局与 RT 输出的 GBuffer 一致，参见 [`FifBuffers::gbuffer_a_handle`] 等。
+/// 没有几何体覆盖的像素保留 clear 值，其中 linear_depth 为 [`Self::BACKGROUND_LINEAR_DEPTH`]
+pub struct GBufferPass {
+    pipeline: GfxGraphicsPipeline,
+    instance_indices: InstanceIndexBuffers,
+}
+// new & init
+impl GBufferPass {
+    /// 背景像素的 linear_depth，与 shader 中的 `gbuffer::DEFAULT_LINEAR_DEPTH` 一致
+    pub const BACKGROUND_LINEAR_DEPTH: f32 = 10000.0;
+
+    pub fn new(depth_format: vk::Format, render_descriptor_sets: &GlobalDescriptorSets) -> Self {
+        let mut ci = GfxGraphicsPipelineCreateInfo::default();
+        ci.vertex_shader_stage(&TruvisPath::shader_build_path_str("phong/phong3d.vs.slang"), c"main");
+        ci.fragment_shader_stage(&TruvisPath::shader_build_path_str("phong/phong_gbuffer.ps.slang"), c"main");
+
+        ci.vertex_binding(VertexLayoutSoA3D::vertex_input_bindings());
+        ci.vertex_attribute(VertexLayoutSoA3D::vertex_input_attributes());
+
+        ci.attach_info(
+            vec![
+                FifBuffers::gbuffer_a_format(),
+                FifBuffers::gbuffer_b_format(),
+                FifBuffers::gbuffer_c_format(),
+            ],
+            Some(depth_format),
+            None,
+        );
+        ci.dynamic_cull_mode();
+        ci.color_blend(
+            vec![
+                vk::PipelineColorBlendAttachmentState::default()
+                    .blend_enable(false)
+                    .color_write_mask(vk::ColorComponentFlags::RGBA);
+                3
+            ],
+            [0.0; 4],
+        );
+
+        let pipeline_layout = Rc::new(GfxPipelineLayout::new(
+            &render_descriptor_sets.global_set_layouts(),
+            &[vk::PushConstantRange::default()
+                .stage_flags(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT)
+                .offset(0)
+                .size(size_of::<truvisl::raster::InstancedPushConstants>() as u32)],
+            "gbuffer-pass",
+        ));
+
+        Self {
+            pipeline: GfxGraphicsPipeline::new(&ci, pipeline_layout, "gbuffer-pipe"),
+            instance_indices: InstanceIndexBuffers::new("gbuffer-pass"),
+        }
+    }
+}
+// tools
+impl GBufferPass {
+    /// # Params
+    /// * `gbuffer_views` - GBuffer A/B/C 的 image view
+    pub fn draw(
+        &self,
+        cmd: &GfxCommandBuffer,
+        render_context: &RenderContext,
+        gbuffer_views: [vk::ImageView; 3],
+        depth_view: vk::ImageView,
+        extent: vk::Extent2D,
+    ) {
+        let frame_label = render_context.frame_counter.frame_label();
+
+        let rendering_info = GfxRenderingInfo::new(
+            gbuffer_views.to_vec(),
+            Some(depth_view),
+            vk::Rect2D {
+                offset: vk::Offset2D::default(),
+                extent,
+            },
+        )
+        .clear_color(0, [0.0; 4])
+        .clear_color(1, [0.0, 0.0, 0.0, Self::BACKGROUND_LINEAR_DEPTH])
+        .clear_color(2, [0.0; 4]);
+
+        let render_data = render_context
+            .scene_manager
+            .prepare_render_data(&render_context.bindless_manager, &render_context.asset_hub);
+        let visible_instances = render_context.scene_manager.visible_instances(&render_context.camera_frustum);
+        let batches = render_context.scene_manager.instance_batches(&visible_instances);
+
+        cmd.cmd_begin_rendering2(&rendering_info);
+        cmd.begin_label("[gbuffer-pass]draw", LabelColor::COLOR_PASS);
+
+        cmd.cmd_bind_pipeline(vk::PipelineBindPoint::GRAPHICS, self.pipeline.handle());
+        let viewport = render_context.frame_settings.camera_convention.viewport(extent.into());
+        cmd.cmd_set_viewport(0, std::slice::from_ref(&viewport));
+        cmd.cmd_set_scissor(0, &[extent.into()]);
+
+        let push_constant = truvisl::raster::InstancedPushConstants {
+            frame_data: render_context.per_frame_data_buffers[*frame_label].device_address(),
+            scene: render_context.gpu_scene.scene_buffer(frame_label).device_address(),
+            instance_indices: self.instance_indices.upload(frame_label, &batches),
+
+            first_instance: 0, // 这个值在 draw 时会被更新
+            submesh_idx: 0,    // 这个值在 draw 时会被更新
+        };
+        cmd.cmd_push_constants(
+            self.pipeline.layout(),
+            vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+            0,
+            BytesConvert::bytes_of(&push_constant),
+        );
+        cmd.bind_descriptor_sets(
+            vk::PipelineBindPoint::GRAPHICS,
+            self.pipeline.layout(),
+            0,
+            &render_context.global_descriptor_sets.global_sets(frame_label),
+            None,
+        );
+
+        render_context.gpu_scene.draw_batches(cmd, &render_data, &batches, |batch, submesh_idx| {
+            // 同一批次的 instance 可能使用不同的材质，只剔除所有材质都会剔除的面
+            let batch_instances = &batches.instance_indices
+                [batch.first_instance as usize..(batch.first_instance + batch.instance_count) as usize];
+            let cull_mode =
+                batch_instances.iter().fold(vk::CullModeFlags::FRONT_AND_BACK, |cull_mode, &instance_idx| {
+                    cull_mode & render_data.submesh_cull_mode(instance_idx, submesh_idx)
+                });
+            cmd.cmd_set_cull_mode(cull_mode);
+
+            // NOTE 这个数据和 PushConstant 中的内存布局是一致的
+            let data = [batch.first_instance, submesh_idx];
+            cmd.cmd_push_constants(
+                self.pipeline.layout(),
+                vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+                offset_of!(truvisl::raster::InstancedPushConstants, first_instance) as u32,
+                bytemuck::bytes_of(&data),
+            );
+        });
+
+        cmd.end_label();
+        cmd.end_rendering();
+    }
+}
+
+pub struct GBufferRgPass<'a> {
+    pub gbuffer_pass: &'a GBufferPass,
+
+    pub render_context: &'a RenderContext,
+
+    /// GBuffer A/B/C
+    pub gbuffers: [RgImageHandle; 3],
+    pub depth_image: RgImageHandle,
+}
+
+impl RgPass for GBufferRgPass<'_> {
+    fn setup(&mut self, builder: &mut RgPassBuilder) {
+        for gbuffer in self.gbuffers {
+            builder.write_image(gbuffer, RgImageState::COLOR_ATTACHMENT_WRITE);
+        }
+        builder.write_image(self.depth_image, RgImageState::DEPTH_ATTACHMENT_WRITE);
+    }
+
+    fn execute(&self, ctx: &RgPassContext<'_>) {
+        let gbuffer_views =
+            self.gbuffers.map(|gbuffer| ctx.get_image_view(gbuffer).expect("GBufferPass: gbuffer not found").handle());
+        let depth_view = ctx.get_image_view(self.depth_image).expect("GBufferPass: depth_image not found");
+
+        self.gbuffer_pass.draw(
+            ctx.cmd,
+            self.render_context,
+            gbuffer_views,
+            depth_view.handle(),
+            self.render_context.frame_settings.frame_extent,
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use ash::vk;
+use truvis_crate_tools::resource::TruvisPath;
+use truvis_gfx::basic::color::LabelColor;
+use truvis_gfx::commands::command_buffer::GfxCommandBuffer;
+use truvis_gfx::gfx::Gfx;
+use truvis_gfx::resources::special_buffers::indirect_buffer::GfxIndexedIndirectBuffer;
+use truvis_gfx::resources::special_buffers::structured_buffer::GfxStructuredBuffer;
+use truvis_render_graph::compute_pass::ComputePass;
+use truvis_render_graph::render_context::RenderContext;
+use truvis_render_interface::frame_counter::FrameCounter;
+use truvis_render_interface::global_descriptor_sets::GlobalDescriptorSets;
+use truvis_render_interface::pipeline_settings::FrameLabel;
+use truvis_scene::aabb::Aabb;
+use truvis_shader_binding::truvisl;
+
+/// GPU 剔除的统计结果
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GpuCullStats {
+    pub visible: u32,
+    pub culled: u32,
+}
+
+/// GPU 视锥剔除
+///
+/// compute shader 使用相机视锥测试每个 instance 的世界空间 AABB，
+/// 为通过测试的 instance 生成 [`vk::DrawIndexedIndirectCommand`]，之后通过 [`Self::draw`] 一次绘制。
+///
+/// - 支持 drawIndirectCount 时，可见的 command 紧密排列，数量从统计 buffer 中读取
+/// - 否则第 i 个 instance 总是写入第 i 个 command，被剔除的 command 的 instance_count 为 0
+///
+/// 所有 buffer 每个 fif 一份，统计 buffer 是 host 可见的，参见 [`Self::stats`]。
+/// 需要设备支持 drawIndirectFirstInstance
+pub struct GpuCullPass {
+    cull_pass: ComputePass<truvisl::gpu_cull::PushConstant>,
+
+    max_instance_cnt: usize,
+    /// 是否将可见的 command 紧密排列
+    compact: bool,
+
+    instances: Vec<GfxStructuredBuffer<truvisl::gpu_cull::CullInstance>>,
+    commands: Vec<GfxIndexedIndirectBuffer>,
+    /// visible_count 位于 offset 0，同时作为 drawIndirectCount 的 count buffer
+    stats: Vec<GfxStructuredBuffer<truvisl::gpu_cull::CullStats>>,
+}
+// new & init
+impl GpuCullPass {
+    pub fn new(global_descriptor_sets: &GlobalDescriptorSets, max_instance_cnt: usize) -> Self {
+        // 生成的 command 通过 first_instance 传递 instance 的序号
+        assert!(
+            Gfx::get().physical_device().support_draw_indirect_first_instance(),
+            "GpuCullPass: drawIndirectFirstInstance is not supported"
+        );
+        let cull_pass = ComputePass::<truvisl::gpu_cull::PushConstant>::new(
+            global_descriptor_sets,
+            c"main",
+            TruvisPath::shader_build_path_str("cull/gpu_cull.slang").as_str(),
+        );
+
+        // instance 数据每帧由 CPU 直接写入
+        let instances = FrameCounter::frame_labes()
+            .map(|frame_label| {
+                GfxStructuredBuffer::new(
+                    format!("gpu-cull-instances-{frame_label}"),
+                    max_instance_cnt,
+                    vk::BufferUsageFlags::STORAGE_BUFFER | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+                    true,
+                )
+            })
+            .collect::<Vec<_>>();
+        let commands = FrameCounter::frame_labes()
+            .map(|frame_label| {
+                GfxIndexedIndirectBuffer::new(max_instance_cnt, false, format!("gpu-cull-commands-{frame_label}"))
+            })
+            .collect::<Vec<_>>();
+        let stats = FrameCounter::frame_labes()
+            .map(|frame_label| {
+                GfxStructuredBuffer::new(
+                    format!("gpu-cull-stats-{frame_label}"),
+                    1,
+                    vk::BufferUsageFlags::INDIRECT_BUFFER
+                    | vk::BufferUsageFlags::STORAGE_BUFFER
+                    | vk::BufferUsageFlags::TRANSFER_DST // dispatch 之前清零
+                    | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+                    true,
+                )
+            })
+            .collect::<Vec<_>>();
+        // 保证在第一次 dispatch 之前读取到的统计结果为 0
+        for buffer in &stats {
+            buffer.transfer_data_by_mmap(&[truvisl::gpu_cull::CullStats {
+                visible_count: 0,
+                culled_count: 0,
+            }]);
+        }
+
+        Self {
+            cull_pass,
+            max_instance_cnt,
+            compact: Gfx::get().physical_device().support_draw_indirect_count(),
+            instances,
+            commands,
+            stats,
+        }
+    }
+
+    /// 使用 mesh 在 index buffer 中的范围和 instance 的世界空间包围盒构造一个待剔除的 instance
+    ///
+    /// `instance_idx` 会写入 command 的 first_instance
+    pub fn cull_instance(
+        world_aabb: &Aabb,
+        index_cnt: u32,
+        first_index: u32,
+        vertex_offset: i32,
+        instance_idx: u32,
+    ) -> truvisl::gpu_cull::CullInstance {
+        truvisl::gpu_cull::CullInstance {
+            aabb_min: world_aabb.min.into(),
+            index_count: index_cnt,
+            aabb_max: world_aabb.max.into(),
+            first_index,
+            vertex_offset,
+            instance_idx,
+            _padding0: 0,
+            _padding1: 0,
+        }
+    }
+}
+// getter
+impl GpuCullPass {
+    #[inline]
+    pub fn max_instance_cnt(&self) -> usize {
+        self.max_instance_cnt
+    }
+
+    /// `frame_label` 上一次剔除的统计结果
+    ///
+    /// 需要在该帧的命令执行完成之后、再次 [`Self::dispatch`] 之前读取，例如录制同一个 fif 的下一帧时
+    pub fn stats(&self, frame_label: FrameLabel) -> GpuCullStats {
+        let buffer = &self.stats[*frame_label];
+        buffer.invalidate(0, size_of::<truvisl::gpu_cull::CullStats>() as vk::DeviceSize);
+        let stats = unsafe { buffer.mapped_ptr().cast::<truvisl::gpu_cull::CullStats>().read() };
+        GpuCullStats {
+            visible: stats.visible_count,
+            culled: stats.culled_count,
+        }
+    }
+}
+// update
+impl GpuCullPass {
+    /// 写入当前帧待剔除的 instance
+    ///
+    /// 需要在 GPU 已经不再使用该 buffer 时调用，例如录制这一帧的命令时
+    pub fn upload_instances(&self, frame_label: FrameLabel, instances: &[truvisl::gpu_cull::CullInstance]) {
+        assert!(instances.len() <= self.max_instance_cnt, "GpuCullPass: too many instances");
+        self.instances[*frame_label].transfer_data_by_mmap(instances);
+    }
+}
+// tools
+impl GpuCullPass {
+    /// 剔除当前帧前 `instance_cnt` 个 instance，视锥为 [`RenderContext::camera_frustum`]
+    ///
+    /// 结束时插入 barrier，之后的 indirect draw 可以直接读取 command 和 count
+    pub fn dispatch(&self, cmd: &GfxCommandBuffer, render_context: &RenderContext, instance_cnt: u32) {
+        assert!(instance_cnt as usize <= self.max_instance_cnt, "GpuCullPass: too many instances");
+        let frame_label = render_context.frame_counter.frame_label();
+        let stats = &self.stats[*frame_label];
+
+        cmd.begin_label("[gpu-cull]dispatch", LabelColor::COLOR_PASS);
+
+        // 统计数据通过原子操作累加，需要先清零
+        cmd.cmd_fill_buffer(stats.vk_buffer(), 0, size_of::<truvisl::gpu_cull::CullStats>() as vk::DeviceSize, 0);
+        cmd.memory_barrier(std::slice::from_ref(&vk::MemoryBarrier2 {
+            src_stage_mask: vk::PipelineStageFlags2::TRANSFER,
+            src_access_mask: vk::AccessFlags2::TRANSFER_WRITE,
+            dst_stage_mask: vk::PipelineStageFlags2::COMPUTE_SHADER,
+            dst_access_mask: vk::AccessFlags2::SHADER_STORAGE_READ | vk::AccessFlags2::SHADER_STORAGE_WRITE,
+            ..Default::default()
+        }));
+
+        self.cull_pass.exec(
+            cmd,
+            render_context,
+            &truvisl::gpu_cull::PushConstant {
+                frustum_planes: render_context.camera_frustum.planes.map(Into::into),
+                instances: self.instances[*frame_label].device_address(),
+                commands: self.commands[*frame_label].device_address(),
+                stats: stats.device_address(),
+                instance_count: instance_cnt,
+                compact: self.compact as u32,
+            },
+            glam::uvec3(instance_cnt.div_ceil(truvisl::gpu_cull::SHADER_X as u32), 1, 1),
+        );
+
+        // command 和 count 会被 indirect draw 读取，统计结果会被 host 读取
+        cmd.memory_barrier(std::slice::from_ref(&vk::MemoryBarrier2 {
+            src_stage_mask: vk::PipelineStageFlags2::COMPUTE_SHADER,
+            src_access_mask: vk::AccessFlags2::SHADER_STORAGE_WRITE,
+            dst_stage_mask: vk::PipelineStageFlags2::DRAW_INDIRECT | vk::PipelineStageFlags2::HOST,
+            dst_access_mask: vk::AccessFlags2::INDIRECT_COMMAND_READ | vk::AccessFlags2::HOST_READ,
+            ..Default::default()
+        }));
+
+        cmd.end_label();
+    }
+
+    /// 绘制当前帧剔除后的 command，需要已经绑定 pipeline 和 index buffer
+    ///
+    /// `instance_cnt` 需要与 [`Self::dispatch`] 的一致
+    pub fn draw(&self, cmd: &GfxCommandBuffer, frame_label: FrameLabel, instance_cnt: u32) {
+        // 不支持 drawIndirectCount 时会退化为绘制全部 `instance_cnt` 个 command，此时 command 没有紧密排列
+        cmd.cmd_draw_indexed_indirect_count(
+            &self.commands[*frame_label],
+            0,
+            &self.stats[*frame_label],
+            0,
+            instance_cnt,
+            GfxIndexedIndirectBuffer::stride(),
+        );
+    }
+}
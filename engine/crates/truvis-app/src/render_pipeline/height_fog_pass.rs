@@ -0,0 +1,115 @@
+use ash::vk;
+use truvis_crate_tools::resource::TruvisPath;
+use truvis_gfx::commands::command_buffer::GfxCommandBuffer;
+use truvis_render_graph::compute_pass::ComputePass;
+use truvis_render_graph::pipeline_warmup::ComputePipelineDesc;
+use truvis_render_graph::render_context::RenderContext;
+use truvis_render_graph::render_graph::{RgImageHandle, RgImageState, RgPass, RgPassBuilder, RgPassContext};
+use truvis_render_interface::bindless_manager::BindlessUavHandle;
+use truvis_render_interface::global_descriptor_sets::GlobalDescriptorSets;
+use truvis_render_interface::pipeline_settings::HeightFogSettings;
+use truvis_shader_binding::truvisl;
+
+/// 高度雾 Pass 的数据
+pub struct HeightFogPassData {
+    pub gbuffer_b_bindless_uav_handle: BindlessUavHandle,
+    pub single_frame_bindless_uav_handle: BindlessUavHandle,
+    pub image_size: vk::Extent2D,
+    /// 调试通道，雾只作用于最终结果的通道
+    pub channel: u32,
+    pub settings: HeightFogSettings,
+}
+
+/// 指数高度雾 Pass - 按片元到相机的距离与高度，将雾色混合到单帧 RT 结果上
+pub struct HeightFogPass {
+    height_fog_pass: ComputePass<truvisl::height_fog::PushConstant>,
+}
+
+impl HeightFogPass {
+    pub fn new(render_descriptor_sets: &GlobalDescriptorSets) -> Self {
+        let [height_fog_desc] = Self::pipeline_descs();
+        let height_fog_pass = ComputePass::from_desc(render_descriptor_sets, &height_fog_desc);
+
+        Self { height_fog_pass }
+    }
+
+    /// 该 pass 使用的所有 compute pipeline，[`Self::new`] 以及启动时的预热都使用这份描述
+    pub fn pipeline_descs() -> [ComputePipelineDesc; 1] {
+        [ComputePipelineDesc::new::<truvisl::height_fog::PushConstant>(
+            c"main",
+            TruvisPath::shader_build_path_str("pp/height_fog.slang"),
+        )]
+    }
+
+    pub fn exec(&self, cmd: &GfxCommandBuffer, data: HeightFogPassData, render_context: &RenderContext) {
+        let settings = &data.settings;
+        let sun_direction = glam::Vec3::from(settings.sun_direction).normalize_or_zero();
+        let sun_color = glam::Vec3::from(settings.sun_color) * settings.sun_intensity;
+
+        self.height_fog_pass.exec(
+            cmd,
+            render_context,
+            &truvisl::height_fog::PushConstant {
+                gbuffer_b: data.gbuffer_b_bindless_uav_handle.0,
+                single_frame_image: data.single_frame_bindless_uav_handle.0,
+                image_size: glam::uvec2(data.image_size.width, data.image_size.height).into(),
+                fog_color: glam::Vec3::from(settings.color).into(),
+                density: settings.density,
+                sun_direction: sun_direction.into(),
+                height_falloff: settings.height_falloff,
+                sun_color: sun_color.into(),
+                sun_exponent: settings.sun_exponent,
+                base_height: settings.base_height,
+                max_distance: settings.max_distance,
+                channel: data.channel,
+                _padding0: 0,
+            },
+            glam::uvec3(
+                data.image_size.width.div_ceil(truvisl::height_fog::SHADER_X as u32),
+                data.image_size.height.div_ceil(truvisl::height_fog::SHADER_Y as u32),
+                1,
+            ),
+        );
+    }
+}
+
+/// 高度雾 Pass 的 RenderGraph 封装
+pub struct HeightFogRgPass<'a> {
+    pub height_fog_pass: &'a HeightFogPass,
+
+    pub render_context: &'a RenderContext,
+
+    /// GBufferB: world_position.xyz + linear_depth（只读）
+    pub gbuffer_b: RgImageHandle,
+    /// 单帧 RT 输出（读写）
+    pub single_frame_image: RgImageHandle,
+
+    pub image_extent: vk::Extent2D,
+}
+
+impl<'a> RgPass for HeightFogRgPass<'a> {
+    fn setup(&mut self, builder: &mut RgPassBuilder) {
+        builder.read_image(self.gbuffer_b, RgImageState::STORAGE_READ_COMPUTE);
+        builder.read_write_image(self.single_frame_image, RgImageState::STORAGE_READ_WRITE_COMPUTE);
+    }
+
+    fn execute(&self, ctx: &RgPassContext) {
+        let gbuffer_b_view_handle = ctx.get_image_view_handle(self.gbuffer_b).unwrap();
+        let single_frame_view_handle = ctx.get_image_view_handle(self.single_frame_image).unwrap();
+
+        let bindless_manager = &self.render_context.bindless_manager;
+        let pipeline_settings = &self.render_context.pipeline_settings;
+
+        self.height_fog_pass.exec(
+            ctx.cmd,
+            HeightFogPassData {
+                gbuffer_b_bindless_uav_handle: bindless_manager.get_shader_uav_handle(gbuffer_b_view_handle),
+                single_frame_bindless_uav_handle: bindless_manager.get_shader_uav_handle(single_frame_view_handle),
+                image_size: self.image_extent,
+                channel: pipeline_settings.channel,
+                settings: pipeline_settings.height_fog,
+            },
+            self.render_context,
+        );
+    }
+}
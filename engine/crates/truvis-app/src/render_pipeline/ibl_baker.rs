@@ -0,0 +1,441 @@
+use std::path::{Path, PathBuf};
+
+use ash::vk;
+use truvis_crate_tools::resource::TruvisPath;
+use truvis_gfx::commands::barrier::GfxImageBarrier;
+use truvis_gfx::gfx::Gfx;
+use truvis_gfx::resources::image::{GfxImage, GfxImageCreateInfo};
+use truvis_gfx::resources::image_view::GfxImageViewDesc;
+use truvis_render_graph::compute_pass::ComputePass;
+use truvis_render_graph::render_context::RenderContext;
+use truvis_render_interface::global_descriptor_sets::GlobalDescriptorSets;
+use truvis_render_interface::handles::{GfxImageHandle, GfxImageViewHandle};
+use truvis_render_interface::ibl_maps::IblMaps;
+use truvis_shader_binding::truvisl;
+
+/// 基于图像的光照（IBL）预计算
+///
+/// 从 GpuScene 当前的天空贴图计算 irradiance、prefiltered 环境贴图和 BRDF LUT，结果注册为 bindless srv。
+/// 预计算在一次 one-time 提交中同步完成，只适合在初始化或者替换天空贴图时调用。
+///
+/// 结果会以天空贴图的路径、大小和修改时间为 key 缓存到磁盘，再次启动时直接加载
+pub struct IblBaker {
+    irradiance_pass: ComputePass<truvisl::ibl_bake::PushConstants>,
+    prefilter_pass: ComputePass<truvisl::ibl_bake::PushConstants>,
+    brdf_lut_pass: ComputePass<truvisl::ibl_bake::PushConstants>,
+}
+// new & init
+impl IblBaker {
+    const IRRADIANCE_SAMPLE_COUNT: u32 = 2048;
+    const PREFILTER_SAMPLE_COUNT: u32 = 1024;
+    const BRDF_LUT_SAMPLE_COUNT: u32 = 1024;
+
+    /// 缓存文件的格式或者预计算的算法改变时需要修改，使旧的缓存失效
+    const CACHE_VERSION: u32 = 1;
+    const CACHE_MAGIC: &'static [u8; 8] = b"TRVS_IBL";
+
+    pub fn new(global_descriptor_sets: &GlobalDescriptorSets) -> Self {
+        let shader_path = TruvisPath::shader_build_path_str("ibl/ibl_bake.slang");
+        Self {
+            irradiance_pass: ComputePass::new(global_descriptor_sets, c"irradiance_main", &shader_path),
+            prefilter_pass: ComputePass::new(global_descriptor_sets, c"prefilter_main", &shader_path),
+            brdf_lut_pass: ComputePass::new(global_descriptor_sets, c"brdf_lut_main", &shader_path),
+        }
+    }
+}
+// tools
+impl IblBaker {
+    /// 为 GpuScene 当前的天空贴图生成 IBL 贴图
+    ///
+    /// `cache_dir` 不为 None 时，优先从缓存加载，缓存不存在时预计算并写入缓存
+    pub fn bake(&self, render_context: &mut RenderContext, cache_dir: Option<&Path>) -> IblMaps {
+        let _span = tracy_client::span!("IblBaker::bake");
+
+        let cache_path =
+            cache_dir.and_then(|cache_dir| Self::cache_path(cache_dir, render_context.gpu_scene.sky_path()));
+        if let Some(cache_path) = &cache_path {
+            match Self::load_cache(render_context, cache_path) {
+                Ok(ibl_maps) => {
+                    log::info!("load ibl maps from {}", cache_path.display());
+                    return ibl_maps;
+                }
+                Err(e) => log::info!("no ibl cache loaded from {}: {}", cache_path.display(), e),
+            }
+        }
+
+        let ibl_maps = self.bake_on_gpu(render_context);
+
+        if let Some(cache_path) = &cache_path {
+            match Self::save_cache(render_context, &ibl_maps, cache_path) {
+                Ok(()) => log::info!("ibl maps cached to {}", cache_path.display()),
+                Err(e) => log::warn!("failed to cache ibl maps to {}: {}", cache_path.display(), e),
+            }
+        }
+
+        ibl_maps
+    }
+
+    fn bake_on_gpu(&self, render_context: &mut RenderContext) -> IblMaps {
+        let usage = vk::ImageUsageFlags::STORAGE | vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_SRC;
+        let mut create_image = |extent: vk::Extent2D, name: &str| {
+            let image_handle = render_context.gfx_resource_manager.create_image(
+                &GfxImageCreateInfo::new_image_2d_info(extent, IblMaps::FORMAT, usage),
+                &vk_mem::AllocationCreateInfo {
+                    usage: vk_mem::MemoryUsage::AutoPreferDevice,
+                    ..Default::default()
+                },
+                name,
+            );
+            Self::create_view(render_context, image_handle, name)
+        };
+        let ibl_maps = IblMaps {
+            irradiance: create_image(IblMaps::IRRADIANCE_EXTENT, "ibl-irradiance"),
+            prefiltered: std::array::from_fn(|level| {
+                create_image(IblMaps::prefiltered_extent(level), &format!("ibl-prefiltered-{level}"))
+            }),
+            brdf_lut: create_image(IblMaps::BRDF_LUT_EXTENT, "ibl-brdf-lut"),
+        };
+
+        // 写入时作为 uav，之后只作为 srv 使用
+        let images = ibl_maps.images();
+        for ((_, view_handle), _) in &images {
+            render_context.bindless_manager.register_uav(*view_handle);
+        }
+        render_context.bindless_manager.prepare_render_data(
+            &render_context.gfx_resource_manager,
+            &mut render_context.global_descriptor_sets,
+            &render_context.frame_counter,
+        );
+
+        let ctx = &*render_context;
+        let bindless_manager = &ctx.bindless_manager;
+        let sky_srv = bindless_manager.get_shader_srv_handle(ctx.gpu_scene.sky_texture().1).0;
+        let vk_images = images
+            .iter()
+            .map(|((image_handle, _), _)| ctx.gfx_resource_manager.get_image(*image_handle).unwrap().handle())
+            .collect::<Vec<_>>();
+
+        Gfx::get().one_time_exec(
+            |cmd| {
+                let barriers = vk_images
+                    .iter()
+                    .map(|image| {
+                        GfxImageBarrier::new()
+                            .image(*image)
+                            .src_mask(vk::PipelineStageFlags2::TOP_OF_PIPE, vk::AccessFlags2::empty())
+                            .dst_mask(vk::PipelineStageFlags2::COMPUTE_SHADER, vk::AccessFlags2::SHADER_STORAGE_WRITE)
+                            .layout_transfer(vk::ImageLayout::UNDEFINED, vk::ImageLayout::GENERAL)
+                            .image_aspect_flag(vk::ImageAspectFlags::COLOR)
+                    })
+                    .collect::<Vec<_>>();
+                cmd.image_memory_barrier(vk::DependencyFlags::empty(), &barriers);
+
+                let exec = |pass: &ComputePass<truvisl::ibl_bake::PushConstants>,
+                            view_handle: GfxImageViewHandle,
+                            extent: vk::Extent2D,
+                            roughness: f32,
+                            sample_count: u32| {
+                    pass.exec(
+                        cmd,
+                        ctx,
+                        &truvisl::ibl_bake::PushConstants {
+                            env_map: sky_srv,
+                            env_map_sampler_type: truvisl::ESamplerType_LinearClamp,
+                            dst_image: bindless_manager.get_shader_uav_handle(view_handle).0,
+                            roughness,
+                            dst_size: glam::uvec2(extent.width, extent.height).into(),
+                            sample_count,
+                            _padding_0: Default::default(),
+                        },
+                        glam::uvec3(
+                            extent.width.div_ceil(truvisl::ibl_bake::SHADER_X as u32),
+                            extent.height.div_ceil(truvisl::ibl_bake::SHADER_Y as u32),
+                            1,
+                        ),
+                    );
+                };
+
+                exec(
+                    &self.irradiance_pass,
+                    ibl_maps.irradiance.1,
+                    IblMaps::IRRADIANCE_EXTENT,
+                    0.0,
+                    Self::IRRADIANCE_SAMPLE_COUNT,
+                );
+                for (level, (_, view_handle)) in ibl_maps.prefiltered.iter().enumerate() {
+                    exec(
+                        &self.prefilter_pass,
+                        *view_handle,
+                        IblMaps::prefiltered_extent(level),
+                        IblMaps::prefiltered_roughness(level),
+                        Self::PREFILTER_SAMPLE_COUNT,
+                    );
+                }
+                exec(
+                    &self.brdf_lut_pass,
+                    ibl_maps.brdf_lut.1,
+                    IblMaps::BRDF_LUT_EXTENT,
+                    0.0,
+                    Self::BRDF_LUT_SAMPLE_COUNT,
+                );
+
+                // bindless srv 要求 SHADER_READ_ONLY_OPTIMAL
+                let barriers = vk_images
+                    .iter()
+                    .map(|image| {
+                        GfxImageBarrier::new()
+                            .image(*image)
+                            .src_mask(vk::PipelineStageFlags2::COMPUTE_SHADER, vk::AccessFlags2::SHADER_STORAGE_WRITE)
+                            .dst_mask(vk::PipelineStageFlags2::ALL_COMMANDS, vk::AccessFlags2::SHADER_READ)
+                            .layout_transfer(vk::ImageLayout::GENERAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                            .image_aspect_flag(vk::ImageAspectFlags::COLOR)
+                    })
+                    .collect::<Vec<_>>();
+                cmd.image_memory_barrier(vk::DependencyFlags::empty(), &barriers);
+            },
+            "ibl-bake",
+        );
+
+        for ((_, view_handle), _) in &images {
+            render_context.bindless_manager.unregister_uav(*view_handle);
+        }
+
+        ibl_maps
+    }
+
+    /// 创建 image view 并注册为 bindless srv
+    fn create_view(
+        render_context: &mut RenderContext,
+        image_handle: GfxImageHandle,
+        name: &str,
+    ) -> (GfxImageHandle, GfxImageViewHandle) {
+        let view_handle = render_context.gfx_resource_manager.get_or_create_image_view(
+            image_handle,
+            GfxImageViewDesc::new_2d(IblMaps::FORMAT, vk::ImageAspectFlags::COLOR),
+            name,
+        );
+        render_context.bindless_manager.register_srv(view_handle);
+        (image_handle, view_handle)
+    }
+}
+// cache
+impl IblBaker {
+    /// 缓存文件的路径，天空贴图不存在时返回 None
+    ///
+    /// 文件名中的 hash 由天空贴图的路径、大小、修改时间以及缓存版本计算，
+    /// 使用固定的 FNV-1a 而不是 `DefaultHasher`，后者的结果在不同的 Rust 版本之间可能不同
+    fn cache_path(cache_dir: &Path, sky_path: &Path) -> Option<PathBuf> {
+        let metadata = std::fs::metadata(sky_path).ok()?;
+        let modified_ns = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |duration| duration.as_nanos());
+
+        let hash = Self::fnv1a_64(&[
+            sky_path.to_string_lossy().as_bytes(),
+            &metadata.len().to_le_bytes(),
+            &modified_ns.to_le_bytes(),
+            &Self::CACHE_VERSION.to_le_bytes(),
+        ]);
+
+        let stem = sky_path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("sky");
+        Some(cache_dir.join(format!("{}-{:016x}.ibl", stem, hash)))
+    }
+
+    /// 64 位 FNV-1a，依次处理 `chunks` 中的字节
+    fn fnv1a_64(chunks: &[&[u8]]) -> u64 {
+        const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        chunks
+            .iter()
+            .flat_map(|chunk| chunk.iter())
+            .fold(OFFSET_BASIS, |hash, &byte| (hash ^ byte as u64).wrapping_mul(PRIME))
+    }
+
+    fn load_cache(render_context: &mut RenderContext, cache_path: &Path) -> Result<IblMaps, String> {
+        let data = std::fs::read(cache_path).map_err(|e| e.to_string())?;
+        let extents = Self::cache_extents();
+        let images = Self::decode_cache(&data, &extents).ok_or("invalid cache data")?;
+
+        let mut images = images.into_iter().enumerate().map(|(idx, pixels)| {
+            let extent = extents[idx];
+            let name = format!("ibl-cache-{idx}");
+            let image = GfxImage::from_rgba32f(extent.width, extent.height, &pixels, &name);
+            let image_handle = render_context.gfx_resource_manager.register_image(image);
+            Self::create_view(render_context, image_handle, &name)
+        });
+        let irradiance = images.next().unwrap();
+        let prefiltered = std::array::from_fn(|_| images.next().unwrap());
+        let brdf_lut = images.next().unwrap();
+
+        Ok(IblMaps {
+            irradiance,
+            prefiltered,
+            brdf_lut,
+        })
+    }
+
+    fn save_cache(render_context: &RenderContext, ibl_maps: &IblMaps, cache_path: &Path) -> Result<(), String> {
+        let images = ibl_maps
+            .images()
+            .into_iter()
+            .map(|((image_handle, _), extent)| {
+                let image = render_context.gfx_resource_manager.get_image(image_handle).unwrap();
+                let data = image.read_back(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
+                (extent, bytemuck::pod_collect_to_vec::<u8, f32>(&data))
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(parent) = cache_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        std::fs::write(cache_path, Self::encode_cache(&images)).map_err(|e| e.to_string())
+    }
+
+    /// 缓存中各个贴图的分辨率，与 [`IblMaps::images`] 的顺序一致
+    fn cache_extents() -> Vec<vk::Extent2D> {
+        let mut extents = vec![IblMaps::IRRADIANCE_EXTENT];
+        extents.extend((0..IblMaps::PREFILTERED_LEVEL_COUNT).map(IblMaps::prefiltered_extent));
+        extents.push(IblMaps::BRDF_LUT_EXTENT);
+        extents
+    }
+
+    /// 缓存的格式：magic、version、贴图数量，之后依次是每张贴图的宽、高和 RGBA32F 像素，均为小端序
+    fn encode_cache(images: &[(vk::Extent2D, Vec<f32>)]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(Self::CACHE_MAGIC);
+        data.extend_from_slice(&Self::CACHE_VERSION.to_le_bytes());
+        data.extend_from_slice(&(images.len() as u32).to_le_bytes());
+        for (extent, pixels) in images {
+            data.extend_from_slice(&extent.width.to_le_bytes());
+            data.extend_from_slice(&extent.height.to_le_bytes());
+            pixels.iter().for_each(|value| data.extend_from_slice(&value.to_le_bytes()));
+        }
+        data
+    }
+
+    /// 解析缓存，格式、版本或者贴图分辨率与 `extents` 不一致时返回 None
+    fn decode_cache(data: &[u8], extents: &[vk::Extent2D]) -> Option<Vec<Vec<f32>>> {
+        fn read_bytes<'a>(data: &'a [u8], offset: &mut usize, len: usize) -> Option<&'a [u8]> {
+            let bytes = data.get(*offset..*offset + len)?;
+            *offset += len;
+            Some(bytes)
+        }
+        fn read_u32(data: &[u8], offset: &mut usize) -> Option<u32> {
+            read_bytes(data, offset, 4).map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+        }
+
+        let mut offset = 0;
+        if read_bytes(data, &mut offset, Self::CACHE_MAGIC.len())? != Self::CACHE_MAGIC {
+            return None;
+        }
+        if read_u32(data, &mut offset)? != Self::CACHE_VERSION || read_u32(data, &mut offset)? as usize != extents.len()
+        {
+            return None;
+        }
+
+        let mut images = Vec::with_capacity(extents.len());
+        for extent in extents {
+            if read_u32(data, &mut offset)? != extent.width || read_u32(data, &mut offset)? != extent.height {
+                return None;
+            }
+            let value_cnt = (extent.width * extent.height * 4) as usize;
+            let pixels = read_bytes(data, &mut offset, value_cnt * 4)?
+                .chunks_exact(4)
+                .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+                .collect();
+            images.push(pixels);
+        }
+
+        // 多余的数据说明文件已经损坏
+        (offset == data.len()).then_some(images)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_images() -> Vec<(vk::Extent2D, Vec<f32>)> {
+        vec![
+            (vk::Extent2D { width: 2, height: 1 }, (0..8).map(|i| i as f32 * 0.5).collect()),
+            (vk::Extent2D { width: 1, height: 2 }, vec![1.0e6, -1.0, 0.25, 1.0, 3.0, 2.0, 1.0, 0.0]),
+        ]
+    }
+
+    #[test]
+    fn test_cache_roundtrip() {
+        let images = test_images();
+        let extents = images.iter().map(|(extent, _)| *extent).collect::<Vec<_>>();
+
+        let decoded = IblBaker::decode_cache(&IblBaker::encode_cache(&images), &extents).unwrap();
+        assert_eq!(decoded, images.into_iter().map(|(_, pixels)| pixels).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_cache_mismatch() {
+        let images = test_images();
+        let data = IblBaker::encode_cache(&images);
+        let extents = images.iter().map(|(extent, _)| *extent).collect::<Vec<_>>();
+
+        // 分辨率或者数量不一致
+        let mut other_extents = extents.clone();
+        other_extents[1] = vk::Extent2D { width: 2, height: 1 };
+        assert!(IblBaker::decode_cache(&data, &other_extents).is_none());
+        assert!(IblBaker::decode_cache(&data, &extents[..1]).is_none());
+
+        // 截断、多余的数据以及错误的 magic
+        assert!(IblBaker::decode_cache(&data[..data.len() - 1], &extents).is_none());
+        let mut longer = data.clone();
+        longer.push(0);
+        assert!(IblBaker::decode_cache(&longer, &extents).is_none());
+        let mut bad_magic = data.clone();
+        bad_magic[0] ^= 0xff;
+        assert!(IblBaker::decode_cache(&bad_magic, &extents).is_none());
+    }
+
+    #[test]
+    fn test_cache_extents_match_ibl_maps() {
+        let extents = IblBaker::cache_extents();
+        assert_eq!(extents.len(), IblMaps::PREFILTERED_LEVEL_COUNT + 2);
+        assert_eq!(extents[1], IblMaps::PREFILTERED_BASE_EXTENT);
+        assert_eq!(IblMaps::prefiltered_roughness(IblMaps::PREFILTERED_LEVEL_COUNT - 1), 1.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fnv1a_64() {
+        // FNV 官方的测试向量
+        assert_eq!(IblBaker::fnv1a_64(&[]), 0xcbf29ce484222325);
+        assert_eq!(IblBaker::fnv1a_64(&[b"a"]), 0xaf63dc4c8601ec8c);
+        assert_eq!(IblBaker::fnv1a_64(&[b"foobar"]), 0x85944171f73967e8);
+        // 分块不影响结果
+        assert_eq!(IblBaker::fnv1a_64(&[b"foo", b"", b"bar"]), IblBaker::fnv1a_64(&[b"foobar"]));
+    }
+
+    #[test]
+    fn test_cache_path() {
+        let dir = std::env::temp_dir().join(format!("truvis-ibl-cache-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sky_path = dir.join("sky.hdr");
+        assert_eq!(IblBaker::cache_path(&dir, &sky_path), None);
+
+        std::fs::write(&sky_path, b"sky").unwrap();
+        let cache_path = IblBaker::cache_path(&dir, &sky_path).unwrap();
+        assert_eq!(IblBaker::cache_path(&dir, &sky_path).unwrap(), cache_path);
+        let file_name = cache_path.file_name().unwrap().to_str().unwrap();
+        assert!(file_name.starts_with("sky-") && file_name.ends_with(".ibl"));
+
+        // 天空贴图的大小变化之后使用新的缓存
+        std::fs::write(&sky_path, b"a larger sky").unwrap();
+        assert_ne!(IblBaker::cache_path(&dir, &sky_path).unwrap(), cache_path);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
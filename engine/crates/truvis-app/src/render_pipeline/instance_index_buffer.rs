@@ -0,0 +1,48 @@
+use std::cell::RefCell;
+
+use ash::vk;
+use truvis_gfx::resources::special_buffers::structured_buffer::GfxStructuredBuffer;
+use truvis_render_interface::frame_counter::FrameCounter;
+use truvis_render_interface::pipeline_settings::FrameLabel;
+use truvis_render_interface::render_data::InstanceBatches;
+
+/// 合批之后的 instance 序号，供 instanced draw 的 shader 通过 device address 读取
+///
+/// 每个 fif 一份，host 可见，数量变多时自动扩容。参见 `raster::InstancedPushConstants`
+pub struct InstanceIndexBuffers {
+    buffers: Vec<RefCell<GfxStructuredBuffer<u32>>>,
+}
+// new & init
+impl InstanceIndexBuffers {
+    /// 初始容量（instance 数量），不够时自动扩容
+    const INITIAL_CAPACITY: usize = 1024;
+
+    pub fn new(debug_name: &str) -> Self {
+        let buffers = FrameCounter::frame_labes()
+            .map(|frame_label| {
+                RefCell::new(GfxStructuredBuffer::new(
+                    format!("{}-instance-indices-{}", debug_name, frame_label),
+                    Self::INITIAL_CAPACITY,
+                    vk::BufferUsageFlags::STORAGE_BUFFER
+                    | vk::BufferUsageFlags::TRANSFER_SRC // 扩容时需要拷贝
+                    | vk::BufferUsageFlags::TRANSFER_DST
+                    | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+                    true,
+                ))
+            })
+            .collect::<Vec<_>>();
+        Self { buffers }
+    }
+}
+// update
+impl InstanceIndexBuffers {
+    /// 将 `batches` 的 instance 序号写入 `frame_label` 对应的 buffer，返回 buffer 的 device address
+    ///
+    /// 需要在 GPU 已经不再使用该 buffer 时调用，例如录制这一帧的命令时
+    pub fn upload(&self, frame_label: FrameLabel, batches: &InstanceBatches) -> vk::DeviceAddress {
+        let mut buffer = self.buffers[*frame_label].borrow_mut();
+        buffer.clear_elements();
+        // 扩容时 device address 会变化，使用追加之后的地址
+        buffer.extend(&batches.instance_indices)
+    }
+}
@@ -1,8 +1,27 @@
 pub mod accum_pass;
 pub mod blit_pass;
+pub mod bloom_pass;
+pub mod debug_draw_pass;
+pub mod deferred_lighting_pass;
 pub mod denoise_accum_pass;
+#[cfg(target_os = "linux")]
+pub mod external_export_pass;
+pub mod gbuffer_pass;
+pub mod gpu_cull_pass;
+pub mod height_fog_pass;
+pub mod ibl_baker;
+pub mod instance_index_buffer;
+pub mod overlay_pass;
+pub mod panorama_capture;
 pub mod phong_pass;
+pub mod picking_pass;
+pub mod ray_query_shadow_pass;
 pub mod realtime_rt_pass;
 pub mod resolve_pass;
 pub mod rt_render_graph;
 pub mod sdr_pass;
+pub mod shadow_pass;
+pub mod skybox_pass;
+pub mod ssao_pass;
+pub mod taa_pass;
+pub mod tone_mapping_pass;
@@ -0,0 +1,186 @@
+use ash::vk;
+use itertools::Itertools;
+use truvis_gfx::commands::command_buffer::GfxCommandBuffer;
+use truvis_render_graph::render_context::RenderContext;
+use truvis_render_graph::render_graph::{RgImageHandle, RgImageState, RgPass, RgPassBuilder, RgPassContext};
+
+/// 自定义 overlay 的绘制回调，参见 [`crate::outer_app::base::OuterApp::draw_overlay`]
+pub type OverlayDrawFn<'a> = dyn Fn(&GfxCommandBuffer, &OverlayContext) + 'a;
+
+/// 调用 overlay 钩子时传入的上下文
+///
+/// # 约定
+/// - 调用时机：场景已经 resolve 到 present image 上，debug 线段已经绘制，GUI 尚未绘制
+/// - `present_image` 处于 `COLOR_ATTACHMENT_OPTIMAL` layout，所需的 barrier 由 RenderGraph 负责；
+///   钩子内不能改变它的 layout（如果改变了，必须在返回前恢复）
+/// - 调用时没有处于 rendering 中，也没有绑定任何 pipeline、viewport、scissor 或 descriptor set；
+///   需要的 render state 都由钩子自己设置，[`Self::begin_rendering`] 可以以 LOAD 的方式开始绘制
+/// - 坐标以 present image 的像素为单位，原点在左上角
+pub struct OverlayContext<'a> {
+    pub render_context: &'a RenderContext,
+
+    pub present_image: vk::Image,
+    pub present_view: vk::ImageView,
+    pub present_format: vk::Format,
+    pub present_extent: vk::Extent2D,
+}
+
+// tools
+impl OverlayContext<'_> {
+    /// 以 LOAD 的方式开始 dynamic rendering，保留场景已经绘制的内容
+    pub fn begin_rendering(&self, cmd: &GfxCommandBuffer) {
+        let color_attach_info = vk::RenderingAttachmentInfo::default()
+            .image_view(self.present_view)
+            .image_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+            .load_op(vk::AttachmentLoadOp::LOAD)
+            .store_op(vk::AttachmentStoreOp::STORE);
+
+        let render_info = vk::RenderingInfo::default()
+            .layer_count(1)
+            .render_area(self.present_extent.into())
+            .color_attachments(std::slice::from_ref(&color_attach_info));
+
+        cmd.cmd_begin_rendering(&render_info);
+    }
+
+    /// 在屏幕中心绘制一个十字准星
+    ///
+    /// 通过 clear attachment 实现，不需要额外的 pipeline。
+    /// vkCmdClearAttachments 要求 rect 位于 render area 之内，超出 present image 的部分会被裁掉
+    ///
+    /// # 参数
+    /// - `half_length`: 准星每条臂的长度（像素）
+    /// - `thickness`: 线宽（像素）
+    /// - `color`: 准星颜色（RGBA）
+    pub fn draw_crosshair(&self, cmd: &GfxCommandBuffer, half_length: u32, thickness: u32, color: [f32; 4]) {
+        let center_x = (self.present_extent.width / 2) as i64;
+        let center_y = (self.present_extent.height / 2) as i64;
+        let half_length = half_length as i64;
+        let thickness = thickness as i64;
+        let half_thickness = thickness / 2;
+
+        let rects = [
+            // 水平线
+            (center_x - half_length, center_y - half_thickness, half_length * 2, thickness),
+            // 竖直线
+            (center_x - half_thickness, center_y - half_length, thickness, half_length * 2),
+        ]
+        .into_iter()
+        .filter_map(|(x, y, width, height)| clamp_rect(x, y, width, height, self.present_extent))
+        .map(|rect| vk::ClearRect {
+            rect,
+            base_array_layer: 0,
+            layer_count: 1,
+        })
+        .collect_vec();
+        if rects.is_empty() {
+            return;
+        }
+
+        let clear_attachment = vk::ClearAttachment {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            color_attachment: 0,
+            clear_value: vk::ClearValue {
+                color: vk::ClearColorValue { float32: color },
+            },
+        };
+
+        self.begin_rendering(cmd);
+        cmd.cmd_clear_attachments(std::slice::from_ref(&clear_attachment), &rects);
+        cmd.end_rendering();
+    }
+}
+
+/// 将 `(x, y, width, height)` 描述的矩形裁剪到 `[0, extent)` 之内，裁剪后为空时返回 None
+fn clamp_rect(x: i64, y: i64, width: i64, height: i64, extent: vk::Extent2D) -> Option<vk::Rect2D> {
+    let x0 = x.clamp(0, extent.width as i64);
+    let y0 = y.clamp(0, extent.height as i64);
+    let x1 = (x + width).clamp(0, extent.width as i64);
+    let y1 = (y + height).clamp(0, extent.height as i64);
+    if x1 <= x0 || y1 <= y0 {
+        return None;
+    }
+
+    Some(vk::Rect2D {
+        offset: vk::Offset2D {
+            x: x0 as i32,
+            y: y0 as i32,
+        },
+        extent: vk::Extent2D {
+            width: (x1 - x0) as u32,
+            height: (y1 - y0) as u32,
+        },
+    })
+}
+
+/// overlay 的 RenderGraph 封装，位于 resolve（以及 debug-draw）和 GUI 之间
+pub struct OverlayRgPass<'a> {
+    pub overlay: &'a OverlayDrawFn<'a>,
+
+    pub render_context: &'a RenderContext,
+
+    /// present image（读写）
+    pub canvas_color: RgImageHandle,
+    pub canvas_format: vk::Format,
+    pub canvas_extent: vk::Extent2D,
+}
+
+impl RgPass for OverlayRgPass<'_> {
+    fn setup(&mut self, builder: &mut RgPassBuilder) {
+        builder.read_write_image(self.canvas_color, RgImageState::COLOR_ATTACHMENT_READ_WRITE);
+    }
+
+    fn execute(&self, ctx: &RgPassContext<'_>) {
+        let (canvas_image_handle, canvas_view_handle) =
+            ctx.get_image_and_view_handle(self.canvas_color).expect("OverlayPass: canvas_color not found");
+        let canvas_image = ctx.resource_manager.get_image(canvas_image_handle).unwrap();
+        let canvas_view = ctx.resource_manager.get_image_view(canvas_view_handle).unwrap();
+
+        let overlay_context = OverlayContext {
+            render_context: self.render_context,
+            present_image: canvas_image.handle(),
+            present_view: canvas_view.handle(),
+            present_format: self.canvas_format,
+            present_extent: self.canvas_extent,
+        };
+
+        (self.overlay)(ctx.cmd, &overlay_context);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXTENT: vk::Extent2D = vk::Extent2D { width: 8, height: 6 };
+
+    fn rect(x: i32, y: i32, width: u32, height: u32) -> vk::Rect2D {
+        vk::Rect2D {
+            offset: vk::Offset2D { x, y },
+            extent: vk::Extent2D { width, height },
+        }
+    }
+
+    #[test]
+    fn test_clamp_rect_inside() {
+        assert_eq!(clamp_rect(1, 2, 3, 4, EXTENT), Some(rect(1, 2, 3, 4)));
+    }
+
+    #[test]
+    fn test_clamp_rect_crosses_border() {
+        // 左上角越界时，宽高随 offset 一起缩小
+        assert_eq!(clamp_rect(-2, -1, 5, 3, EXTENT), Some(rect(0, 0, 3, 2)));
+        // 右下角越界
+        assert_eq!(clamp_rect(6, 4, 10, 10, EXTENT), Some(rect(6, 4, 2, 2)));
+        // 比 render area 还大
+        assert_eq!(clamp_rect(-10, -10, 100, 100, EXTENT), Some(rect(0, 0, 8, 6)));
+    }
+
+    #[test]
+    fn test_clamp_rect_outside() {
+        assert_eq!(clamp_rect(-5, 0, 5, 3, EXTENT), None);
+        assert_eq!(clamp_rect(8, 0, 2, 3, EXTENT), None);
+        assert_eq!(clamp_rect(0, 0, 3, 0, EXTENT), None);
+        assert_eq!(clamp_rect(0, 0, 3, 3, vk::Extent2D { width: 0, height: 0 }), None);
+    }
+}
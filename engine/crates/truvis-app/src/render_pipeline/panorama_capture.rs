@@ -0,0 +1,193 @@
+//! 从场景中任意位置拍摄等距柱状投影（equirectangular）的 HDR 全景图
+//!
+//! 直接使用光追，对全景图的每个像素按经纬度发射一条方向射线，多个样本在输出图像中累积，
+//! 结果可以作为环境贴图或者反射探针的数据源。
+
+use std::path::Path;
+
+use ash::vk;
+use itertools::Itertools;
+use truvis_gfx::commands::barrier::GfxImageBarrier;
+use truvis_gfx::gfx::Gfx;
+use truvis_gfx::resources::image::{GfxImage, GfxImageCreateInfo};
+use truvis_gfx::resources::image_view::GfxImageViewDesc;
+use truvis_render_graph::render_context::RenderContext;
+use truvis_render_graph::resources::fif_buffer::FifBuffers;
+
+use crate::render_pipeline::realtime_rt_pass::{RealtimeRtPass, RealtimeRtPassData, RtProjection};
+
+/// CPU 端的全景图，RGBA 交错排列的线性 HDR 数据
+///
+/// 宽高比固定为 2:1，`u` 方向对应经度，`v` 方向对应纬度（`v = 0` 为正上方）
+pub struct PanoramaImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<f32>,
+}
+// tools
+impl PanoramaImage {
+    /// 读取 `(x, y)` 处的像素
+    pub fn pixel(&self, x: u32, y: u32) -> [f32; 4] {
+        let offset = (y * self.width + x) as usize * 4;
+        self.pixels[offset..offset + 4].try_into().unwrap()
+    }
+
+    /// 以 f32 RGBA 写出为 OpenEXR 文件，不做 tone mapping
+    pub fn save_exr(&self, path: impl AsRef<Path>) -> exr::error::UnitResult {
+        exr::prelude::write_rgba_file(path, self.width as usize, self.height as usize, |x, y| {
+            let [r, g, b, a] = self.pixel(x as u32, y as u32);
+            (r, g, b, a)
+        })
+    }
+}
+
+pub struct PanoramaCapture;
+
+impl PanoramaCapture {
+    const OUTPUT_FORMAT: vk::Format = vk::Format::R32G32B32A32_SFLOAT;
+
+    /// 在 `position` 处渲染一张 `resolution x resolution / 2` 的全景图
+    ///
+    /// 内部会等待 GPU 空闲并同步读回，开销较大，只适合手动触发或者离线烘焙。
+    /// 光追使用当前帧的 TLAS 和全局描述符，需要在场景至少准备过一帧之后调用。
+    ///
+    /// # Panics
+    /// `resolution` 小于 2 或者 `spp` 为 0
+    pub fn capture(
+        rt_pass: &RealtimeRtPass,
+        render_context: &mut RenderContext,
+        position: glam::Vec3,
+        resolution: u32,
+        spp: u32,
+    ) -> PanoramaImage {
+        let _span = tracy_client::span!("PanoramaCapture::capture");
+        assert!(resolution >= 2, "panorama: resolution must be at least 2");
+        assert!(spp > 0, "panorama: spp must be greater than 0");
+
+        let extent = vk::Extent2D {
+            width: resolution,
+            height: resolution / 2,
+        };
+
+        // 正常的帧可能还在使用 TLAS 和 irradiance cache
+        Gfx::get().wait_idel();
+
+        // 输出图像以及 raygen 顺带写入的 gbuffer，只在本次拍摄中使用
+        let targets = [
+            ("panorama-output", Self::OUTPUT_FORMAT),
+            ("panorama-gbuffer-a", FifBuffers::gbuffer_a_format()),
+            ("panorama-gbuffer-b", FifBuffers::gbuffer_b_format()),
+            ("panorama-gbuffer-c", FifBuffers::gbuffer_c_format()),
+        ]
+        .map(|(name, format)| {
+            let image_create_info = GfxImageCreateInfo::new_image_2d_info(
+                extent,
+                format,
+                vk::ImageUsageFlags::STORAGE | vk::ImageUsageFlags::TRANSFER_SRC,
+            );
+            let image = GfxImage::new(
+                &image_create_info,
+                &vk_mem::AllocationCreateInfo {
+                    usage: vk_mem::MemoryUsage::AutoPreferDevice,
+                    ..Default::default()
+                },
+                name,
+            );
+            let image_handle = render_context.gfx_resource_manager.register_image(image);
+            let view_handle = render_context.gfx_resource_manager.get_or_create_image_view(
+                image_handle,
+                GfxImageViewDesc::new_2d(format, vk::ImageAspectFlags::COLOR),
+                name,
+            );
+            (image_handle, view_handle)
+        });
+        let [output, gbuffer_a, gbuffer_b, gbuffer_c] = targets;
+
+        let image_barriers = targets
+            .iter()
+            .map(|&(image_handle, _)| {
+                GfxImageBarrier::new()
+                    .image(render_context.gfx_resource_manager.get_image(image_handle).unwrap().handle())
+                    .src_mask(vk::PipelineStageFlags2::TOP_OF_PIPE, vk::AccessFlags2::NONE)
+                    .dst_mask(
+                        vk::PipelineStageFlags2::RAY_TRACING_SHADER_KHR,
+                        vk::AccessFlags2::SHADER_READ | vk::AccessFlags2::SHADER_WRITE,
+                    )
+                    .layout_transfer(vk::ImageLayout::UNDEFINED, vk::ImageLayout::GENERAL)
+                    .image_aspect_flag(vk::ImageAspectFlags::COLOR)
+            })
+            .collect_vec();
+
+        Gfx::get().one_time_exec(
+            |cmd| {
+                cmd.image_memory_barrier(vk::DependencyFlags::empty(), &image_barriers);
+                rt_pass.ray_trace(
+                    &*render_context,
+                    cmd,
+                    RealtimeRtPassData {
+                        projection: RtProjection::Panorama { origin: position, spp },
+                        single_frame_output: output.0,
+                        single_frame_output_view: output.1,
+                        single_frame_extent: extent,
+                        gbuffer_a: gbuffer_a.0,
+                        gbuffer_a_view: gbuffer_a.1,
+                        gbuffer_b: gbuffer_b.0,
+                        gbuffer_b_view: gbuffer_b.1,
+                        gbuffer_c: gbuffer_c.0,
+                        gbuffer_c_view: gbuffer_c.1,
+                    },
+                );
+            },
+            "panorama-capture",
+        );
+
+        // one_time_exec 会等待提交完成，这里可以直接读回
+        let data = render_context.gfx_resource_manager.get_image(output.0).unwrap().read_back(vk::ImageLayout::GENERAL);
+        let pixels = data.chunks_exact(4).map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap())).collect_vec();
+
+        for (image_handle, _) in targets {
+            render_context.gfx_resource_manager.destroy_image_immediate(image_handle);
+        }
+
+        PanoramaImage {
+            width: extent.width,
+            height: extent.height,
+            pixels,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_panorama_pixel_and_exr_round_trip() {
+        let (width, height) = (4, 2);
+        let pixels = (0..width * height * 4).map(|value| value as f32 * 0.5).collect_vec();
+        let panorama = PanoramaImage { width, height, pixels };
+        assert_eq!(panorama.pixel(0, 0), [0.0, 0.5, 1.0, 1.5]);
+        assert_eq!(panorama.pixel(1, 1), [10.0, 10.5, 11.0, 11.5]);
+
+        let path = std::env::temp_dir().join(format!("truvis-panorama-test-{}.exr", std::process::id()));
+        panorama.save_exr(&path).unwrap();
+        let image = exr::prelude::read_first_rgba_layer_from_file(
+            &path,
+            |resolution, _| vec![[0.0f32; 4]; resolution.width() * resolution.height()],
+            move |pixels, position, (r, g, b, a): (f32, f32, f32, f32)| {
+                pixels[position.y() * width as usize + position.x()] = [r, g, b, a];
+            },
+        )
+        .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // 不做 tone mapping，HDR 的值原样写出
+        let layer_size = image.layer_data.size;
+        assert_eq!((layer_size.width(), layer_size.height()), (width as usize, height as usize));
+        for y in 0..height {
+            for x in 0..width {
+                assert_eq!(image.layer_data.channel_data.pixels[(y * width + x) as usize], panorama.pixel(x, y));
+            }
+        }
+    }
+}
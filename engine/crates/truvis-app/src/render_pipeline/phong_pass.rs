@@ -17,8 +17,14 @@ use truvis_render_interface::global_descriptor_sets::GlobalDescriptorSets;
 use truvis_render_interface::pipeline_settings::FrameLabel;
 use truvis_shader_binding::truvisl;
 
+use crate::render_pipeline::instance_index_buffer::InstanceIndexBuffers;
+
+/// 使用 phong 光照光栅化场景中视锥内的 instance
+///
+/// 使用同一个 mesh 的 instance 合批，每个 submesh 只需要一次 instanced draw
 pub struct PhongPass {
     pipeline: GfxGraphicsPipeline,
+    instance_indices: InstanceIndexBuffers,
 }
 impl PhongPass {
     pub fn new(
@@ -48,13 +54,16 @@ impl PhongPass {
             &[vk::PushConstantRange::default()
                 .stage_flags(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT)
                 .offset(0)
-                .size(size_of::<truvisl::raster::PushConstants>() as u32)],
+                .size(size_of::<truvisl::raster::InstancedPushConstants>() as u32)],
             "phong-pass",
         ));
 
         let d3_pipe = GfxGraphicsPipeline::new(&ci, pipeline_layout, "phong-d3-pipe");
 
-        Self { pipeline: d3_pipe }
+        Self {
+            pipeline: d3_pipe,
+            instance_indices: InstanceIndexBuffers::new("phong-pass"),
+        }
     }
 
     fn bind(
@@ -62,21 +71,12 @@ impl PhongPass {
         cmd: &GfxCommandBuffer,
         render_context: &RenderContext,
         viewport: &vk::Rect2D,
-        push_constant: &truvisl::raster::PushConstants,
+        push_constant: &truvisl::raster::InstancedPushConstants,
         frame_label: FrameLabel,
     ) {
         cmd.cmd_bind_pipeline(vk::PipelineBindPoint::GRAPHICS, self.pipeline.handle());
-        cmd.cmd_set_viewport(
-            0,
-            &[vk::Viewport {
-                x: viewport.offset.x as f32,
-                y: viewport.offset.y as f32 + viewport.extent.height as f32,
-                width: viewport.extent.width as f32,
-                height: -(viewport.extent.height as f32),
-                min_depth: 0.0,
-                max_depth: 1.0,
-            }],
-        );
+        let vk_viewport = render_context.frame_settings.camera_convention.viewport(*viewport);
+        cmd.cmd_set_viewport(0, std::slice::from_ref(&vk_viewport));
         cmd.cmd_set_scissor(0, &[*viewport]);
         cmd.cmd_push_constants(
             self.pipeline.layout(),
@@ -95,25 +95,31 @@ impl PhongPass {
         );
     }
 
-    pub fn draw(&self, cmd: &GfxCommandBuffer, render_context: &RenderContext) {
+    /// clear 两个 attachment 之后绘制，layout 与同步由 render graph 负责
+    pub fn draw(
+        &self,
+        cmd: &GfxCommandBuffer,
+        render_context: &RenderContext,
+        render_target_view: vk::ImageView,
+        depth_view: vk::ImageView,
+    ) {
         let frame_label = render_context.frame_counter.frame_label();
 
-        let (_, render_target_view_handle) = render_context.fif_buffers.render_target_handle(frame_label);
-        let render_target_view = render_context.gfx_resource_manager.get_image_view(render_target_view_handle).unwrap();
-        let depth_image_view = render_context
-            .gfx_resource_manager
-            .get_image_view(render_context.fif_buffers.depth_image_view_handle())
-            .unwrap();
-
         let rendering_info = GfxRenderingInfo::new(
-            vec![render_target_view.handle()],
-            Some(depth_image_view.handle()),
+            vec![render_target_view],
+            Some(depth_view),
             vk::Rect2D {
                 offset: vk::Offset2D::default(),
                 extent: render_context.frame_settings.frame_extent,
             },
         );
 
+        let render_data = render_context
+            .scene_manager
+            .prepare_render_data(&render_context.bindless_manager, &render_context.asset_hub);
+        let visible_instances = render_context.scene_manager.visible_instances(&render_context.camera_frustum);
+        let batches = render_context.scene_manager.instance_batches(&visible_instances);
+
         cmd.cmd_begin_rendering2(&rendering_info);
         cmd.begin_label("[phong-pass]draw", LabelColor::COLOR_PASS);
 
@@ -121,34 +127,26 @@ impl PhongPass {
             cmd,
             render_context,
             &render_context.frame_settings.frame_extent.into(),
-            &truvisl::raster::PushConstants {
+            &truvisl::raster::InstancedPushConstants {
                 frame_data: render_context.per_frame_data_buffers[*frame_label].device_address(),
                 scene: render_context.gpu_scene.scene_buffer(frame_label).device_address(),
+                instance_indices: self.instance_indices.upload(frame_label, &batches),
 
-                submesh_idx: 0,  // 这个值在 draw 时会被更新
-                instance_idx: 0, // 这个值在 draw 时会被更新
-
-                _padding_1: Default::default(),
-                _padding_2: Default::default(),
+                first_instance: 0, // 这个值在 draw 时会被更新
+                submesh_idx: 0,    // 这个值在 draw 时会被更新
             },
             frame_label,
         );
-        render_context.gpu_scene.draw(
-            cmd,
-            &render_context
-                .scene_manager
-                .prepare_render_data(&render_context.bindless_manager, &render_context.asset_hub),
-            &mut |ins_idx, submesh_idx| {
-                // NOTE 这个数据和 PushConstant 中的内存布局是一致的
-                let data = [ins_idx, submesh_idx];
-                cmd.cmd_push_constants(
-                    self.pipeline.layout(),
-                    vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
-                    offset_of!(truvisl::raster::PushConstants, instance_idx) as u32,
-                    bytemuck::bytes_of(&data),
-                );
-            },
-        );
+        render_context.gpu_scene.draw_batches(cmd, &render_data, &batches, |batch, submesh_idx| {
+            // NOTE 这个数据和 PushConstant 中的内存布局是一致的
+            let data = [batch.first_instance, submesh_idx];
+            cmd.cmd_push_constants(
+                self.pipeline.layout(),
+                vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+                offset_of!(truvisl::raster::InstancedPushConstants, first_instance) as u32,
+                bytemuck::bytes_of(&data),
+            );
+        });
 
         cmd.end_label();
         cmd.end_rendering();
@@ -0,0 +1,391 @@
+use std::cell::{Cell, RefCell};
+use std::{mem::offset_of, rc::Rc};
+
+use ash::vk;
+use truvis_crate_tools::resource::TruvisPath;
+use truvis_gfx::basic::bytes::BytesConvert;
+use truvis_gfx::resources::image::GfxImageCreateInfo;
+use truvis_gfx::resources::image_view::GfxImageViewDesc;
+use truvis_gfx::resources::layout::GfxVertexLayout;
+use truvis_gfx::resources::special_buffers::readback_buffer::GfxReadbackBuffer;
+use truvis_gfx::resources::vertex_layout::soa_3d::VertexLayoutSoA3D;
+use truvis_gfx::{
+    basic::color::LabelColor,
+    commands::command_buffer::GfxCommandBuffer,
+    pipelines::{
+        graphics_pipeline::{GfxGraphicsPipeline, GfxGraphicsPipelineCreateInfo, GfxPipelineLayout},
+        rendering_info::GfxRenderingInfo,
+    },
+};
+use truvis_render_graph::render_context::RenderContext;
+use truvis_render_graph::render_graph::{
+    RenderGraphBuilder, RgImageHandle, RgImageState, RgPass, RgPassBuilder, RgPassContext,
+};
+use truvis_render_interface::handles::{GfxImageHandle, GfxImageViewHandle};
+use truvis_scene::guid_new_type::InstanceHandle;
+use truvis_shader_binding::truvisl;
+
+/// 一次拾取的结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PickResult {
+    /// 拾取的像素，位于 render target 的坐标系中
+    pub pixel: glam::UVec2,
+    /// 该像素上的 instance，点击到背景或者 instance 已经被移除时为 None
+    pub instance: Option<InstanceHandle>,
+}
+
+/// 已经录制了回读命令，等待所在的帧完成
+struct PendingPick {
+    frame_id: u64,
+    pixel: glam::UVec2,
+    readback: GfxReadbackBuffer,
+    /// 录制时 GPU 上 instance 序号到 handle 的映射
+    instance_handles: Vec<InstanceHandle>,
+}
+
+/// 鼠标拾取（GPU picking）
+///
+/// 有拾取请求的帧中，将每个 instance 的 id 渲染到 R32_UINT 的 target 上，并在同一个 command buffer 中回读 id，
+/// 所在的帧完成之后（通常延迟一到两帧）通过 [`Self::poll`] 得到结果。id 为 instance 的序号 + 1，0 表示背景，
+/// 序号通过录制时的 [`truvis_scene::scene_manager::SceneManager::instance_handles`] 映射回 [`InstanceHandle`]。
+///
+/// 只关心一个像素：viewport 按照拾取的像素平移，id target 和深度都只有 1x1，不需要随窗口大小重建
+pub struct PickingPass {
+    pipeline: GfxGraphicsPipeline,
+
+    id_image: (GfxImageHandle, GfxImageViewHandle),
+    depth_image: (GfxImageHandle, GfxImageViewHandle),
+    depth_format: vk::Format,
+
+    /// 下一次录制时拾取的像素
+    request: Cell<Option<glam::UVec2>>,
+    pending: RefCell<Vec<PendingPick>>,
+}
+// new & init
+impl PickingPass {
+    pub const ID_FORMAT: vk::Format = vk::Format::R32_UINT;
+
+    pub fn new(render_context: &mut RenderContext) -> Self {
+        let depth_format = render_context.frame_settings.depth_format;
+        let shader_path = TruvisPath::shader_build_path_str("picking/picking.slang");
+
+        let mut ci = GfxGraphicsPipelineCreateInfo::default();
+        ci.vertex_shader_stage(&shader_path, c"vs_main");
+        ci.fragment_shader_stage(&shader_path, c"ps_main");
+
+        // 只需要位置
+        ci.vertex_binding(
+            VertexLayoutSoA3D::vertex_input_bindings().into_iter().filter(|binding| binding.binding == 0).collect(),
+        );
+        ci.vertex_attribute(
+            VertexLayoutSoA3D::vertex_input_attributes()
+                .into_iter()
+                .filter(|attribute| attribute.location == 0)
+                .collect(),
+        );
+
+        ci.attach_info(vec![Self::ID_FORMAT], Some(depth_format), None);
+        ci.color_blend(
+            vec![
+                vk::PipelineColorBlendAttachmentState::default()
+                    .blend_enable(false)
+                    .color_write_mask(vk::ColorComponentFlags::R),
+            ],
+            [0.0; 4],
+        );
+
+        let pipeline_layout = Rc::new(GfxPipelineLayout::new(
+            &render_context.global_descriptor_sets.global_set_layouts(),
+            &[vk::PushConstantRange::default()
+                .stage_flags(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT)
+                .offset(0)
+                .size(size_of::<truvisl::raster::PushConstants>() as u32)],
+            "picking-pass",
+        ));
+        let pipeline = GfxGraphicsPipeline::new(&ci, pipeline_layout, "picking-pipe");
+
+        let id_image = Self::create_image(
+            render_context,
+            Self::ID_FORMAT,
+            vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC,
+            vk::ImageAspectFlags::COLOR,
+            "picking-id",
+        );
+        let depth_image = Self::create_image(
+            render_context,
+            depth_format,
+            vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+            vk::ImageAspectFlags::DEPTH,
+            "picking-depth",
+        );
+
+        Self {
+            pipeline,
+            id_image,
+            depth_image,
+            depth_format,
+            request: Cell::new(None),
+            pending: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn create_image(
+        render_context: &mut RenderContext,
+        format: vk::Format,
+        usage: vk::ImageUsageFlags,
+        aspect: vk::ImageAspectFlags,
+        name: &str,
+    ) -> (GfxImageHandle, GfxImageViewHandle) {
+        let image_create_info =
+            GfxImageCreateInfo::new_image_2d_info(vk::Extent2D { width: 1, height: 1 }, format, usage);
+        let image_handle = render_context.gfx_resource_manager.create_image(
+            &image_create_info,
+            &vk_mem::AllocationCreateInfo {
+                usage: vk_mem::MemoryUsage::AutoPreferDevice,
+                ..Default::default()
+            },
+            name,
+        );
+        let view_handle = render_context.gfx_resource_manager.get_or_create_image_view(
+            image_handle,
+            GfxImageViewDesc::new_2d(format, aspect),
+            name,
+        );
+
+        (image_handle, view_handle)
+    }
+}
+// getter
+impl PickingPass {
+    /// 本帧是否需要执行拾取
+    #[inline]
+    pub fn has_request(&self) -> bool {
+        self.request.get().is_some()
+    }
+}
+// update
+impl PickingPass {
+    /// 请求拾取 render target 中的 `pixel`，在下一次录制时执行；同一帧中多次请求只保留最后一次
+    pub fn request_pick(&mut self, pixel: glam::UVec2) {
+        self.request.set(Some(pixel));
+    }
+
+    /// 取出所在帧已经完成的拾取中最新的一个结果，没有完成的拾取时返回 None
+    pub fn poll(&mut self, render_context: &RenderContext) -> Option<PickResult> {
+        let completed_frame_id = render_context.frame_counter.completed_frame_id();
+
+        let mut result = None;
+        self.pending.get_mut().retain(|pick| {
+            if pick.frame_id > completed_frame_id {
+                return true;
+            }
+
+            let id = pick.readback.as_slice::<u32>()[0];
+            result = Some(PickResult {
+                pixel: pick.pixel,
+                instance: Self::resolve_id(id, &pick.instance_handles),
+            });
+            false
+        });
+
+        // 回读的 buffer 已经不再被 GPU 使用，随 PendingPick 一起销毁
+        result
+    }
+}
+// tools
+impl PickingPass {
+    /// 画面中的归一化坐标（左上角为 (0, 0)）对应的 render target 像素，参见 [`OuterApp::on_mouse_click`]
+    ///
+    /// [`OuterApp::on_mouse_click`]: crate::outer_app::base::OuterApp::on_mouse_click
+    pub fn pixel_from_uv(uv: glam::Vec2, frame_extent: vk::Extent2D) -> glam::UVec2 {
+        let frame_size = glam::uvec2(frame_extent.width, frame_extent.height).max(glam::UVec2::ONE);
+        (uv * frame_size.as_vec2()).as_uvec2().min(frame_size - glam::UVec2::ONE)
+    }
+
+    /// 将回读的 id 映射回 instance：id 为 0 表示背景，其余为序号 + 1
+    fn resolve_id(id: u32, instance_handles: &[InstanceHandle]) -> Option<InstanceHandle> {
+        let instance_idx = id.checked_sub(1)?;
+        instance_handles.get(instance_idx as usize).copied()
+    }
+
+    /// 将 id target 和深度导入 render graph，二者在各帧之间共享，每次拾取都会 clear
+    pub fn import_images(&self, graph: &mut RenderGraphBuilder) -> (RgImageHandle, RgImageHandle) {
+        // 需要等待上一次拾取的回读完成
+        let id_image = graph.import_image(
+            "picking-id",
+            self.id_image.0,
+            Some(self.id_image.1),
+            Self::ID_FORMAT,
+            RgImageState::new(vk::PipelineStageFlags2::TRANSFER, vk::AccessFlags2::NONE, vk::ImageLayout::UNDEFINED),
+            None,
+        );
+        let depth_image = graph.import_image(
+            "picking-depth",
+            self.depth_image.0,
+            Some(self.depth_image.1),
+            self.depth_format,
+            RgImageState::new(
+                vk::PipelineStageFlags2::LATE_FRAGMENT_TESTS,
+                vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE,
+                vk::ImageLayout::UNDEFINED,
+            ),
+            None,
+        );
+
+        (id_image, depth_image)
+    }
+
+    /// 绘制所有 instance 的 id 并录制回读命令，没有拾取请求时什么都不做
+    pub fn draw(
+        &self,
+        cmd: &GfxCommandBuffer,
+        render_context: &RenderContext,
+        id_image_view: vk::ImageView,
+        depth_view: vk::ImageView,
+    ) {
+        let Some(pixel) = self.request.take() else {
+            return;
+        };
+
+        let frame_label = render_context.frame_counter.frame_label();
+        let frame_extent = render_context.frame_settings.frame_extent;
+        let target_rect = vk::Rect2D {
+            offset: vk::Offset2D::default(),
+            extent: vk::Extent2D { width: 1, height: 1 },
+        };
+
+        let rendering_info = GfxRenderingInfo::new(vec![id_image_view], Some(depth_view), target_rect);
+        cmd.cmd_begin_rendering2(&rendering_info);
+        cmd.begin_label("[picking-pass]draw", LabelColor::COLOR_PASS);
+
+        cmd.cmd_bind_pipeline(vk::PipelineBindPoint::GRAPHICS, self.pipeline.handle());
+        // 与主 pass 使用相同的 viewport，平移之后拾取的像素落在 target 的 (0, 0) 上
+        let viewport = render_context.frame_settings.camera_convention.viewport(vk::Rect2D {
+            offset: vk::Offset2D {
+                x: -(pixel.x as i32),
+                y: -(pixel.y as i32),
+            },
+            extent: frame_extent,
+        });
+        cmd.cmd_set_viewport(0, std::slice::from_ref(&viewport));
+        cmd.cmd_set_scissor(0, std::slice::from_ref(&target_rect));
+        cmd.bind_descriptor_sets(
+            vk::PipelineBindPoint::GRAPHICS,
+            self.pipeline.layout(),
+            0,
+            &render_context.global_descriptor_sets.global_sets(frame_label),
+            None,
+        );
+        cmd.cmd_push_constants(
+            self.pipeline.layout(),
+            vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+            0,
+            BytesConvert::bytes_of(&truvisl::raster::PushConstants {
+                frame_data: render_context.per_frame_data_buffers[*frame_label].device_address(),
+                scene: render_context.gpu_scene.scene_buffer(frame_label).device_address(),
+
+                submesh_idx: 0,  // 这个值在 draw 时会被更新
+                instance_idx: 0, // 这个值在 draw 时会被更新
+
+                _padding_1: Default::default(),
+                _padding_2: Default::default(),
+            }),
+        );
+
+        // 只有一个像素，scissor 之外的图元在光栅化之前就会被丢弃，不做视锥剔除
+        render_context.gpu_scene.draw(
+            cmd,
+            &render_context
+                .scene_manager
+                .prepare_render_data(&render_context.bindless_manager, &render_context.asset_hub),
+            |ins_idx, submesh_idx| {
+                let data = [ins_idx, submesh_idx];
+                cmd.cmd_push_constants(
+                    self.pipeline.layout(),
+                    vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+                    offset_of!(truvisl::raster::PushConstants, instance_idx) as u32,
+                    bytemuck::bytes_of(&data),
+                );
+            },
+        );
+
+        cmd.end_label();
+        cmd.end_rendering();
+
+        let id_image = render_context.gfx_resource_manager.get_image(self.id_image.0).unwrap();
+        let readback = id_image.read_back_region(cmd, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL, target_rect);
+
+        self.pending.borrow_mut().push(PendingPick {
+            frame_id: render_context.frame_counter.frame_id(),
+            pixel,
+            readback,
+            instance_handles: render_context.scene_manager.instance_handles(),
+        });
+    }
+}
+
+pub struct PickingRgPass<'a> {
+    pub picking_pass: &'a PickingPass,
+
+    pub render_context: &'a RenderContext,
+
+    pub id_image: RgImageHandle,
+    pub depth_image: RgImageHandle,
+}
+
+impl RgPass for PickingRgPass<'_> {
+    fn setup(&mut self, builder: &mut RgPassBuilder) {
+        builder.write_image(self.id_image, RgImageState::COLOR_ATTACHMENT_WRITE);
+        builder.write_image(self.depth_image, RgImageState::DEPTH_ATTACHMENT_WRITE);
+    }
+
+    fn execute(&self, ctx: &RgPassContext<'_>) {
+        let id_image_view = ctx.get_image_view(self.id_image).expect("PickingPass: id_image not found");
+        let depth_view = ctx.get_image_view(self.depth_image).expect("PickingPass: depth_image not found");
+
+        self.picking_pass.draw(ctx.cmd, self.render_context, id_image_view.handle(), depth_view.handle());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use truvis_scene::components::instance::Instance;
+    use truvis_scene::guid_new_type::MeshHandle;
+    use truvis_scene::scene_manager::SceneManager;
+
+    use super::*;
+
+    #[test]
+    fn test_pixel_from_uv() {
+        let frame_extent = vk::Extent2D {
+            width: 800,
+            height: 600,
+        };
+        assert_eq!(PickingPass::pixel_from_uv(glam::vec2(0.0, 0.0), frame_extent), glam::uvec2(0, 0));
+        assert_eq!(PickingPass::pixel_from_uv(glam::vec2(0.5, 0.25), frame_extent), glam::uvec2(400, 150));
+        // 不会超出 render target
+        assert_eq!(PickingPass::pixel_from_uv(glam::vec2(0.99999, 0.99999), frame_extent), glam::uvec2(799, 599));
+        assert_eq!(PickingPass::pixel_from_uv(glam::vec2(0.5, 0.5), vk::Extent2D::default()), glam::uvec2(0, 0));
+    }
+
+    #[test]
+    fn test_resolve_id() {
+        let mut scene_manager = SceneManager::new();
+        for name in ["a", "b"] {
+            // 不需要 mesh，只使用 instance 的 handle
+            scene_manager.register_instance(Instance {
+                mesh: MeshHandle::default(),
+                materials: vec![],
+                transform: glam::Mat4::IDENTITY,
+                name: name.to_string(),
+            });
+        }
+        let instance_handles = scene_manager.instance_handles();
+
+        assert_eq!(PickingPass::resolve_id(0, &instance_handles), None);
+        assert_eq!(PickingPass::resolve_id(1, &instance_handles), Some(instance_handles[0]));
+        assert_eq!(PickingPass::resolve_id(2, &instance_handles), Some(instance_handles[1]));
+        // instance 在录制之后被移除，GPU 上的序号超出了映射表
+        assert_eq!(PickingPass::resolve_id(3, &instance_handles), None);
+    }
+}
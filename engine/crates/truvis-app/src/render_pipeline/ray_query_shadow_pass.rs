@@ -0,0 +1,169 @@
+use ash::vk;
+use truvis_crate_tools::resource::TruvisPath;
+use truvis_gfx::basic::bytes::BytesConvert;
+use truvis_gfx::commands::command_buffer::GfxCommandBuffer;
+use truvis_gfx::descriptors::descriptor::GfxDescriptorSetLayout;
+use truvis_gfx::pipelines::compute_pipeline::{GfxComputePipeline, GfxComputePipelineCreateInfo};
+use truvis_render_graph::render_context::RenderContext;
+use truvis_render_graph::render_graph::{RgImageHandle, RgImageState, RgPass, RgPassBuilder, RgPassContext};
+use truvis_render_interface::bindless_manager::BindlessUavHandle;
+use truvis_render_interface::global_descriptor_sets::GlobalDescriptorSets;
+use truvis_shader_binding::truvisl;
+
+/// TLAS 的 descriptor，在 compute 管线中通过 push descriptor 绑定
+///
+/// 由 shader-build 根据 slang 的反射信息生成，shader 修改 binding 之后重新编译 shader 即可同步
+mod layout {
+    include!(concat!(env!("CARGO_MANIFEST_DIR"), "/../../shader/generated/pp/ray_query_shadow.slang.layout.rs"));
+}
+use layout::RayQueryShadowSet3Layout as RayQueryShadowDescriptorBinding;
+
+/// 内联光线查询阴影 Pass 的数据
+pub struct RayQueryShadowPassData {
+    pub gbuffer_a_bindless_uav_handle: BindlessUavHandle,
+    pub gbuffer_b_bindless_uav_handle: BindlessUavHandle,
+    pub single_frame_bindless_uav_handle: BindlessUavHandle,
+    pub image_size: vk::Extent2D,
+    /// 指向太阳的方向（世界空间）
+    pub light_direction: glam::Vec3,
+}
+
+/// 内联光线查询阴影 Pass - 在 compute shader 中使用 RayQuery 计算太阳的可见性
+///
+/// 作为在普通 compute 管线中使用 TLAS 的示例：pipeline layout 在全局 descriptor set 之后
+/// 追加一个只包含 TLAS 的 push descriptor set，每帧通过 [`GfxCommandBuffer::push_descriptor_set`] 绑定当前帧的 TLAS。
+/// graphics 管线的做法相同，只需要把 stage 和 bind point 换成 graphics。
+pub struct RayQueryShadowPass {
+    pipeline: GfxComputePipeline,
+
+    _descriptor_set_layout: GfxDescriptorSetLayout<RayQueryShadowDescriptorBinding>,
+}
+// new & init
+impl RayQueryShadowPass {
+    pub fn new(render_descriptor_sets: &GlobalDescriptorSets) -> Self {
+        let descriptor_set_layout = GfxDescriptorSetLayout::<RayQueryShadowDescriptorBinding>::new(
+            vk::DescriptorSetLayoutCreateFlags::PUSH_DESCRIPTOR_KHR,
+            "ray-query-shadow-descriptor-set-layout",
+        );
+
+        let push_constant_range = vk::PushConstantRange::default()
+            .stage_flags(vk::ShaderStageFlags::COMPUTE)
+            .offset(0)
+            .size(size_of::<truvisl::ray_query_shadow::PushConstant>() as u32);
+
+        let mut create_info =
+            GfxComputePipelineCreateInfo::new(TruvisPath::shader_build_path_str("pp/ray_query_shadow.slang"), c"main");
+        create_info
+            .descriptor_set_layouts(Self::descriptor_set_layouts(render_descriptor_sets, &descriptor_set_layout))
+            .push_constant_ranges(vec![push_constant_range]);
+
+        Self {
+            pipeline: GfxComputePipeline::new(&create_info, "ray-query-shadow"),
+            _descriptor_set_layout: descriptor_set_layout,
+        }
+    }
+
+    /// 全局 descriptor set 之后是 TLAS 所在的 set，与 shader 中的 `RAY_QUERY_SHADOW_SET_NUM` 对应
+    fn descriptor_set_layouts(
+        render_descriptor_sets: &GlobalDescriptorSets,
+        descriptor_set_layout: &GfxDescriptorSetLayout<RayQueryShadowDescriptorBinding>,
+    ) -> Vec<vk::DescriptorSetLayout> {
+        let mut descriptor_set_layouts = render_descriptor_sets.global_set_layouts();
+        debug_assert_eq!(descriptor_set_layouts.len() as u32, truvisl::RAY_QUERY_SHADOW_SET_NUM);
+        descriptor_set_layouts.push(descriptor_set_layout.handle());
+        descriptor_set_layouts
+    }
+}
+// tools
+impl RayQueryShadowPass {
+    pub fn exec(&self, cmd: &GfxCommandBuffer, data: RayQueryShadowPassData, render_context: &RenderContext) {
+        let frame_label = render_context.frame_counter.frame_label();
+
+        cmd.cmd_bind_pipeline(vk::PipelineBindPoint::COMPUTE, self.pipeline.handle());
+        cmd.bind_descriptor_sets(
+            vk::PipelineBindPoint::COMPUTE,
+            self.pipeline.layout(),
+            0,
+            &render_context.global_descriptor_sets.global_sets(frame_label),
+            None,
+        );
+        cmd.push_descriptor_set(
+            vk::PipelineBindPoint::COMPUTE,
+            self.pipeline.layout(),
+            truvisl::RAY_QUERY_SHADOW_SET_NUM,
+            &[RayQueryShadowDescriptorBinding::tlas().write_tals(
+                vk::DescriptorSet::null(),
+                0,
+                vec![render_context.gpu_scene.tlas(frame_label).unwrap().handle()],
+            )],
+        );
+
+        let params = truvisl::ray_query_shadow::PushConstant {
+            gbuffer_a: data.gbuffer_a_bindless_uav_handle.0,
+            gbuffer_b: data.gbuffer_b_bindless_uav_handle.0,
+            single_frame_image: data.single_frame_bindless_uav_handle.0,
+            _padding0: 0,
+            image_size: glam::uvec2(data.image_size.width, data.image_size.height).into(),
+            t_min: 0.0,
+            t_max: 10_000.0,
+            light_direction: data.light_direction.normalize_or_zero().into(),
+            normal_offset: 1e-3,
+        };
+        cmd.cmd_push_constants(
+            self.pipeline.layout(),
+            vk::ShaderStageFlags::COMPUTE,
+            0,
+            BytesConvert::bytes_of(&params),
+        );
+
+        cmd.cmd_dispatch(glam::uvec3(
+            data.image_size.width.div_ceil(truvisl::ray_query_shadow::SHADER_X as u32),
+            data.image_size.height.div_ceil(truvisl::ray_query_shadow::SHADER_Y as u32),
+            1,
+        ));
+    }
+}
+
+/// 内联光线查询阴影 Pass 的 RenderGraph 封装
+pub struct RayQueryShadowRgPass<'a> {
+    pub ray_query_shadow_pass: &'a RayQueryShadowPass,
+
+    pub render_context: &'a RenderContext,
+
+    /// GBufferA: normal.xyz + roughness（只读）
+    pub gbuffer_a: RgImageHandle,
+    /// GBufferB: world_position.xyz + linear_depth（只读）
+    pub gbuffer_b: RgImageHandle,
+    /// 单帧 RT 输出（只写）
+    pub single_frame_image: RgImageHandle,
+
+    pub image_extent: vk::Extent2D,
+}
+
+impl<'a> RgPass for RayQueryShadowRgPass<'a> {
+    fn setup(&mut self, builder: &mut RgPassBuilder) {
+        builder.read_image(self.gbuffer_a, RgImageState::STORAGE_READ_COMPUTE);
+        builder.read_image(self.gbuffer_b, RgImageState::STORAGE_READ_COMPUTE);
+        builder.write_image(self.single_frame_image, RgImageState::STORAGE_WRITE_COMPUTE);
+    }
+
+    fn execute(&self, ctx: &RgPassContext) {
+        let gbuffer_a_view_handle = ctx.get_image_view_handle(self.gbuffer_a).unwrap();
+        let gbuffer_b_view_handle = ctx.get_image_view_handle(self.gbuffer_b).unwrap();
+        let single_frame_view_handle = ctx.get_image_view_handle(self.single_frame_image).unwrap();
+
+        let bindless_manager = &self.render_context.bindless_manager;
+
+        self.ray_query_shadow_pass.exec(
+            ctx.cmd,
+            RayQueryShadowPassData {
+                gbuffer_a_bindless_uav_handle: bindless_manager.get_shader_uav_handle(gbuffer_a_view_handle),
+                gbuffer_b_bindless_uav_handle: bindless_manager.get_shader_uav_handle(gbuffer_b_view_handle),
+                single_frame_bindless_uav_handle: bindless_manager.get_shader_uav_handle(single_frame_view_handle),
+                image_size: self.image_extent,
+                light_direction: glam::Vec3::from(self.render_context.pipeline_settings.height_fog.sun_direction),
+            },
+            self.render_context,
+        );
+    }
+}
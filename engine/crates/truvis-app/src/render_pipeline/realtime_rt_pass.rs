@@ -1,5 +1,6 @@
+use crate::render_pipeline::ssao_pass::SsaoPass;
+
 use ash::vk;
-use itertools::Itertools;
 use truvis_crate_tools::resource::TruvisPath;
 use truvis_descriptor_layout_macro::DescriptorBinding;
 use truvis_gfx::basic::bytes::BytesConvert;
@@ -9,9 +10,8 @@ use truvis_gfx::resources::special_buffers::structured_buffer::GfxStructuredBuff
 use truvis_gfx::utilities::descriptor_cursor::GfxDescriptorCursor;
 use truvis_gfx::{
     commands::{barrier::GfxImageBarrier, command_buffer::GfxCommandBuffer},
-    gfx::Gfx,
-    pipelines::shader::{GfxShaderGroupInfo, GfxShaderModuleCache, GfxShaderStageInfo},
-    resources::special_buffers::sbt_buffer::GfxSBTBuffer,
+    pipelines::shader::GfxShaderStageInfo,
+    raytracing::rt_pipeline::{GfxRtPipeline, GfxRtPipelineBuilder},
 };
 use truvis_render_graph::render_context::RenderContext;
 use truvis_render_graph::render_graph::{RgImageHandle, RgImageState, RgPass, RgPassBuilder, RgPassContext};
@@ -21,20 +21,6 @@ use truvis_shader_binding::truvisl;
 use truvis_utils::count_indexed_array;
 use truvis_utils::enumed_map;
 
-pub struct GfxRtPipeline {
-    pub pipeline: vk::Pipeline,
-    pub pipeline_layout: vk::PipelineLayout,
-}
-impl Drop for GfxRtPipeline {
-    fn drop(&mut self) {
-        let gfx_device = Gfx::get().gfx_device();
-        unsafe {
-            gfx_device.destroy_pipeline(self.pipeline, None);
-            gfx_device.destroy_pipeline_layout(self.pipeline_layout, None);
-        }
-    }
-}
-
 enumed_map!(ShaderStages<GfxShaderStageInfo>: {
     RayGen: GfxShaderStageInfo {
         stage: vk::ShaderStageFlags::RAYGEN_KHR,
@@ -68,190 +54,19 @@ enumed_map!(ShaderStages<GfxShaderStageInfo>: {
     },
 });
 
-enumed_map!(ShaderGroups<GfxShaderGroupInfo>: {
-    RayGen: GfxShaderGroupInfo {
-        ty: vk::RayTracingShaderGroupTypeKHR::GENERAL,
-        general: ShaderStages::RayGen.index() as u32,
-        ..GfxShaderGroupInfo::unused()
-    },
-    SkyMiss: GfxShaderGroupInfo {
-        ty: vk::RayTracingShaderGroupTypeKHR::GENERAL,
-        general: ShaderStages::SkyMiss.index() as u32,
-        ..GfxShaderGroupInfo::unused()
-    },
-    ShadowMiss: GfxShaderGroupInfo {
-        ty: vk::RayTracingShaderGroupTypeKHR::GENERAL,
-        general: ShaderStages::ShadowMiss.index() as u32,
-        ..GfxShaderGroupInfo::unused()
-    },
-    Hit: GfxShaderGroupInfo {
-        ty: vk::RayTracingShaderGroupTypeKHR::TRIANGLES_HIT_GROUP,
-        closest_hit: ShaderStages::ClosestHit.index() as u32,
-        any_hit: ShaderStages::TransAny.index() as u32,
-        ..GfxShaderGroupInfo::unused()
-    },
-    DiffuseCall: GfxShaderGroupInfo {
-        ty: vk::RayTracingShaderGroupTypeKHR::GENERAL,
-        general: ShaderStages::DiffuseCall.index() as u32,
-        ..GfxShaderGroupInfo::unused()
-    },
-});
-
-pub struct SBTRegions {
-    sbt_region_raygen: vk::StridedDeviceAddressRegionKHR,
-    sbt_region_miss: vk::StridedDeviceAddressRegionKHR,
-    sbt_region_hit: vk::StridedDeviceAddressRegionKHR,
-    sbt_region_callable: vk::StridedDeviceAddressRegionKHR,
-
-    _sbt_buffer: GfxSBTBuffer,
-}
-impl SBTRegions {
-    const RAYGEN_SBT_REGION: usize = ShaderGroups::RayGen.index();
-    const MISS_SBT_REGION: &'static [usize] = &[ShaderGroups::SkyMiss.index(), ShaderGroups::ShadowMiss.index()];
-    const HIT_SBT_REGION: &'static [usize] = &[ShaderGroups::Hit.index()];
-    const CALLABLE_SBT_REGION: &'static [usize] = &[ShaderGroups::DiffuseCall.index()];
-
-    pub fn create_sbt(pipeline: &GfxRtPipeline) -> Self {
-        let rt_pipeline_props = Gfx::get().rt_pipeline_props();
-
-        // 因为不需要 user data，所以可以直接使用 shader group handle size
-        let aligned_shader_group_handle_size = helper::align_up(
-            rt_pipeline_props.shader_group_handle_size,
-            rt_pipeline_props.shader_group_handle_alignment,
-        );
-
-        // 每一个 region 需要使用 base align 进行对齐
-        let raygen_shader_group_region_size =
-            helper::align_up(aligned_shader_group_handle_size, rt_pipeline_props.shader_group_base_alignment);
-        let miss_shader_group_region_size = helper::align_up(
-            Self::MISS_SBT_REGION.len() as u32 * aligned_shader_group_handle_size,
-            rt_pipeline_props.shader_group_base_alignment,
-        );
-        let hit_shader_group_region_size = helper::align_up(
-            Self::HIT_SBT_REGION.len() as u32 * aligned_shader_group_handle_size,
-            rt_pipeline_props.shader_group_base_alignment,
-        );
-        let callable_shader_group_region_size = helper::align_up(
-            Self::CALLABLE_SBT_REGION.len() as u32 * aligned_shader_group_handle_size,
-            rt_pipeline_props.shader_group_base_alignment,
-        );
-
-        let sbt_buffer = GfxSBTBuffer::new(
-            (raygen_shader_group_region_size
-                + miss_shader_group_region_size
-                + hit_shader_group_region_size
-                + callable_shader_group_region_size) as vk::DeviceSize,
-            rt_pipeline_props.shader_group_base_alignment as vk::DeviceSize,
-            "simple-rt-sbt",
-        );
-
-        // 找到每个 shader group 在 SBT 中的地址
-        let sbt_address = sbt_buffer.device_address();
-
-        let sbt_region_raygen = vk::StridedDeviceAddressRegionKHR::default()
-            .stride(raygen_shader_group_region_size as vk::DeviceSize) // raygen 的 stride 需要和 size 一样
-            .size(raygen_shader_group_region_size as vk::DeviceSize)
-            .device_address(sbt_address);
-        let sbt_region_miss = vk::StridedDeviceAddressRegionKHR::default()
-            .stride(aligned_shader_group_handle_size as vk::DeviceSize)
-            .size(miss_shader_group_region_size as vk::DeviceSize)
-            .device_address(sbt_address + raygen_shader_group_region_size as vk::DeviceSize);
-        let sbt_region_hit = vk::StridedDeviceAddressRegionKHR::default()
-            .stride(aligned_shader_group_handle_size as vk::DeviceSize)
-            .size(hit_shader_group_region_size as vk::DeviceSize)
-            .device_address(
-                sbt_address
-                    + raygen_shader_group_region_size as vk::DeviceSize
-                    + miss_shader_group_region_size as vk::DeviceSize,
-            );
-        let sbt_region_callable = vk::StridedDeviceAddressRegionKHR::default()
-            .stride(aligned_shader_group_handle_size as vk::DeviceSize)
-            .size(callable_shader_group_region_size as vk::DeviceSize)
-            .device_address(
-                sbt_address
-                    + raygen_shader_group_region_size as vk::DeviceSize
-                    + miss_shader_group_region_size as vk::DeviceSize
-                    + hit_shader_group_region_size as vk::DeviceSize,
-            );
-
-        // 从 pipeline 中获取 shader 的 handle，并且将 shader handle 写入到 shader
-        // binding table 中
-        {
-            let shader_group_handle_data = unsafe {
-                Gfx::get()
-                    .gfx_device()
-                    .ray_tracing_pipeline()
-                    .get_ray_tracing_shader_group_handles(
-                        pipeline.pipeline,
-                        0,
-                        ShaderGroups::COUNT as u32,
-                        (ShaderGroups::COUNT as u32 * rt_pipeline_props.shader_group_handle_size) as usize,
-                    )
-                    .unwrap()
-            };
-
-            let copy_shader_group_hande = |group_handle_idx: usize, sbt_handle_host_addr: *mut u8| unsafe {
-                let start_bytes = rt_pipeline_props.shader_group_handle_size as usize * group_handle_idx;
-                let length_bytes = rt_pipeline_props.shader_group_handle_size as usize;
-                let src = &shader_group_handle_data[start_bytes..start_bytes + length_bytes];
-
-                let dst = std::slice::from_raw_parts_mut(
-                    sbt_handle_host_addr,
-                    rt_pipeline_props.shader_group_handle_size as usize,
-                );
-                dst.copy_from_slice(src);
-            };
-
-            let sbt_buffer_size = sbt_buffer.size();
-            let sbt_host_address = sbt_buffer.mapped_ptr();
-
-            let sbt_host_addr_raygen = sbt_host_address;
-            copy_shader_group_hande(Self::RAYGEN_SBT_REGION, sbt_host_address);
-
-            let sbt_host_addr_miss = sbt_host_addr_raygen.wrapping_byte_add(sbt_region_raygen.size as usize);
-            for (idx, group_handle_idx) in Self::MISS_SBT_REGION.iter().enumerate() {
-                copy_shader_group_hande(
-                    *group_handle_idx,
-                    sbt_host_addr_miss.wrapping_byte_add(idx * sbt_region_miss.stride as usize),
-                );
-            }
-
-            let sbt_host_addr_hit = sbt_host_addr_miss.wrapping_byte_add(sbt_region_miss.size as usize);
-            for (idx, group_handle_idx) in Self::HIT_SBT_REGION.iter().enumerate() {
-                copy_shader_group_hande(
-                    *group_handle_idx,
-                    sbt_host_addr_hit.wrapping_byte_add(idx * sbt_region_hit.stride as usize),
-                );
-            }
-
-            let sbt_host_addr_callable = sbt_host_addr_hit.wrapping_byte_add(sbt_region_hit.size as usize);
-            for (idx, group_handle_idx) in Self::CALLABLE_SBT_REGION.iter().enumerate() {
-                copy_shader_group_hande(
-                    *group_handle_idx,
-                    sbt_host_addr_callable.wrapping_byte_add(idx * sbt_region_callable.stride as usize),
-                );
-            }
-
-            sbt_buffer.flush(0, sbt_buffer_size);
-        }
-
-        Self {
-            sbt_region_raygen,
-            sbt_region_miss,
-            sbt_region_hit,
-            sbt_region_callable,
-            _sbt_buffer: sbt_buffer,
-        }
-    }
-}
-impl Drop for SBTRegions {
-    fn drop(&mut self) {
-        log::info!("Destroy SBTRegions");
-    }
+/// 光线的投影方式
+#[derive(Clone, Copy)]
+pub enum RtProjection {
+    /// 使用当前相机的透视投影，结果由后续的 accum pass 累积
+    Camera,
+    /// 从 `origin` 出发的等距柱状投影全景图，`spp` 个样本在 pass 内部累积
+    Panorama { origin: glam::Vec3, spp: u32 },
 }
 
 /// 传入 pass 的数据
 pub struct RealtimeRtPassData {
+    pub projection: RtProjection,
+
     /// 单帧 RT 输出图像
     pub single_frame_output: GfxImageHandle,
     pub single_frame_output_view: GfxImageViewHandle,
@@ -309,92 +124,27 @@ struct RealtimeRtDescriptorBinding {
 
 pub struct RealtimeRtPass {
     pipeline: GfxRtPipeline,
-    _sbt: SBTRegions,
     _rt_descriptor_set_layout: GfxDescriptorSetLayout<RealtimeRtDescriptorBinding>,
 
     hash_table: GfxStructuredBuffer<truvisl::ic::Table>,
     entry_pool: GfxStructuredBuffer<truvisl::ic::EntryPool>,
 }
 impl RealtimeRtPass {
-    pub fn new(render_descriptor_sets: &GlobalDescriptorSets) -> Self {
-        let mut shader_module_cache = GfxShaderModuleCache::new();
-        let stage_infos = ShaderStages::iter()
-            .map(|stage| stage.value())
-            .map(|stage| {
-                vk::PipelineShaderStageCreateInfo::default()
-                    .module(shader_module_cache.get_or_load(stage.path()).handle())
-                    .stage(stage.stage)
-                    .name(stage.entry_point)
-            })
-            .collect_vec();
-
-        let shader_groups = ShaderGroups::iter()
-            .map(|group| group.value())
-            .map(|group| vk::RayTracingShaderGroupCreateInfoKHR {
-                ty: group.ty,
-                general_shader: group.general,
-                any_hit_shader: group.any_hit,
-                closest_hit_shader: group.closest_hit,
-                intersection_shader: group.intersection,
-                ..Default::default()
-            })
-            .collect_vec();
-
-        let push_constant_range = vk::PushConstantRange::default()
-            .stage_flags(
-                vk::ShaderStageFlags::RAYGEN_KHR
-                    | vk::ShaderStageFlags::MISS_KHR
-                    | vk::ShaderStageFlags::ANY_HIT_KHR
-                    | vk::ShaderStageFlags::CALLABLE_KHR
-                    | vk::ShaderStageFlags::CLOSEST_HIT_KHR,
-            )
-            .offset(0)
-            .size(size_of::<truvisl::rt::PushConstants>() as u32);
+    const PUSH_CONSTANT_STAGES: vk::ShaderStageFlags = vk::ShaderStageFlags::from_raw(
+        vk::ShaderStageFlags::RAYGEN_KHR.as_raw()
+            | vk::ShaderStageFlags::MISS_KHR.as_raw()
+            | vk::ShaderStageFlags::ANY_HIT_KHR.as_raw()
+            | vk::ShaderStageFlags::CALLABLE_KHR.as_raw()
+            | vk::ShaderStageFlags::CLOSEST_HIT_KHR.as_raw(),
+    );
 
+    pub fn new(render_descriptor_sets: &GlobalDescriptorSets) -> Self {
         let rt_descriptor_set_layout = GfxDescriptorSetLayout::<RealtimeRtDescriptorBinding>::new(
             vk::DescriptorSetLayoutCreateFlags::PUSH_DESCRIPTOR_KHR,
             "simple-rt-descriptor-set-layout",
         );
-
-        let pipeline_layout = {
-            let mut descriptor_set_layouts = render_descriptor_sets.global_set_layouts();
-            descriptor_set_layouts.push(rt_descriptor_set_layout.handle());
-            let pipeline_layout_ci = vk::PipelineLayoutCreateInfo::default()
-                .set_layouts(&descriptor_set_layouts)
-                .push_constant_ranges(std::slice::from_ref(&push_constant_range));
-
-            unsafe { Gfx::get().gfx_device().create_pipeline_layout(&pipeline_layout_ci, None).unwrap() }
-        };
-        Gfx::get().gfx_device().set_object_debug_name(pipeline_layout, "simple-rt-pipeline-layout");
-        let pipeline_ci = vk::RayTracingPipelineCreateInfoKHR::default()
-            .stages(&stage_infos)
-            .groups(&shader_groups)
-            .layout(pipeline_layout)
-            // 这个仅仅是用来分配栈内存的，并不会在超过递归深度后让调用被丢弃
-            // 需要手动跟踪递归深度
-            .max_pipeline_ray_recursion_depth(2);
-
-        let pipeline = unsafe {
-            Gfx::get()
-                .gfx_device()
-                .ray_tracing_pipeline()
-                .create_ray_tracing_pipelines(
-                    vk::DeferredOperationKHR::null(),
-                    vk::PipelineCache::null(),
-                    std::slice::from_ref(&pipeline_ci),
-                    None,
-                )
-                .unwrap()[0]
-        };
-        Gfx::get().gfx_device().set_object_debug_name(pipeline, "simple-rt-pipeline");
-
-        shader_module_cache.destroy();
-
-        let rt_pipeline = GfxRtPipeline {
-            pipeline,
-            pipeline_layout,
-        };
-        let sbt = SBTRegions::create_sbt(&rt_pipeline);
+        let rt_pipeline = Self::create_pipeline(render_descriptor_sets, &rt_descriptor_set_layout)
+            .unwrap_or_else(|e| panic!("{}", e));
 
         let mut hash_table = GfxStructuredBuffer::<truvisl::ic::Table>::new(
             "ic-hash-table",
@@ -417,17 +167,52 @@ impl RealtimeRtPass {
 
         Self {
             pipeline: rt_pipeline,
-            _sbt: sbt,
             _rt_descriptor_set_layout: rt_descriptor_set_layout,
 
             hash_table,
             entry_pool,
         }
     }
+
+    /// 创建光追 pipeline 以及对应的 SBT，spv 无法加载或者 pipeline 创建失败时返回错误
+    fn create_pipeline(
+        render_descriptor_sets: &GlobalDescriptorSets,
+        rt_descriptor_set_layout: &GfxDescriptorSetLayout<RealtimeRtDescriptorBinding>,
+    ) -> Result<GfxRtPipeline, String> {
+        let push_constant_range = vk::PushConstantRange::default()
+            .stage_flags(Self::PUSH_CONSTANT_STAGES)
+            .offset(0)
+            .size(size_of::<truvisl::rt::PushConstants>() as u32);
+
+        let mut descriptor_set_layouts = render_descriptor_sets.global_set_layouts();
+        descriptor_set_layouts.push(rt_descriptor_set_layout.handle());
+
+        // shader 中 miss shader 的序号：sky 为 0，shadow 为 1
+        let stage = |stage: ShaderStages| {
+            let info = stage.value();
+            (info.path.as_str(), info.entry_point)
+        };
+        let (raygen_path, raygen_entry) = stage(ShaderStages::RayGen);
+        let (sky_miss_path, sky_miss_entry) = stage(ShaderStages::SkyMiss);
+        let (shadow_miss_path, shadow_miss_entry) = stage(ShaderStages::ShadowMiss);
+        let (callable_path, callable_entry) = stage(ShaderStages::DiffuseCall);
+        GfxRtPipelineBuilder::default()
+            .raygen_shader(raygen_path, raygen_entry)
+            .miss_shader(sky_miss_path, sky_miss_entry)
+            .miss_shader(shadow_miss_path, shadow_miss_entry)
+            .hit_group(Some(stage(ShaderStages::ClosestHit)), Some(stage(ShaderStages::TransAny)))
+            .callable_shader(callable_path, callable_entry)
+            .descriptor_set_layouts(descriptor_set_layouts)
+            .push_constant_ranges(vec![push_constant_range])
+            // 这个仅仅是用来分配栈内存的，并不会在超过递归深度后让调用被丢弃
+            // 需要手动跟踪递归深度
+            .max_recursion_depth(2)
+            .build("simple-rt")
+    }
+
     pub fn ray_trace(&self, render_context: &RenderContext, cmd: &GfxCommandBuffer, pass_data: RealtimeRtPassData) {
         let frame_label = render_context.frame_counter.frame_label();
 
-        let _rt_handle = render_context.bindless_manager.get_shader_uav_handle(pass_data.single_frame_output_view);
         let rt_image = render_context.gfx_resource_manager.get_image(pass_data.single_frame_output).unwrap().handle();
         let rt_image_view =
             render_context.gfx_resource_manager.get_image_view(pass_data.single_frame_output_view).unwrap().handle();
@@ -442,11 +227,11 @@ impl RealtimeRtPass {
 
         cmd.begin_label("Ray trace", glam::vec4(0.0, 1.0, 0.0, 1.0));
 
-        cmd.cmd_bind_pipeline(vk::PipelineBindPoint::RAY_TRACING_KHR, self.pipeline.pipeline);
+        cmd.cmd_bind_pipeline(vk::PipelineBindPoint::RAY_TRACING_KHR, self.pipeline.handle());
 
         cmd.push_descriptor_set(
             vk::PipelineBindPoint::RAY_TRACING_KHR,
-            self.pipeline.pipeline_layout,
+            self.pipeline.layout(),
             truvisl::RT_SET_NUM,
             &[
                 RealtimeRtDescriptorBinding::tlas().write_tals(
@@ -496,21 +281,35 @@ impl RealtimeRtPass {
 
         cmd.bind_descriptor_sets(
             vk::PipelineBindPoint::RAY_TRACING_KHR,
-            self.pipeline.pipeline_layout,
+            self.pipeline.layout(),
             0,
             &render_context.global_descriptor_sets.global_sets(frame_label),
             None,
         );
-        // FIXME 这个变量废除了，现在只有 spp 1
-        let spp = 1;
         let mut push_constant = truvisl::rt::PushConstants {
-            spp,
+            spp: 1,
             spp_idx: 0,
             channel: render_context.pipeline_settings.channel,
             ic_table: self.hash_table.device_address(),
             ic_entry_pool: self.entry_pool.device_address(),
             ic_enabled: render_context.pipeline_settings.ic_enabled as u32,
+            light_sampling: render_context.pipeline_settings.light_sampling,
+            panorama: 0,
+            ambient_ratio_in_alpha: SsaoPass::is_active(render_context) as u32,
+            _padding0: 0,
+            panorama_origin: glam::Vec3::ZERO.into(),
+            _padding1: 0,
         };
+        if let RtProjection::Panorama { origin, spp } = pass_data.projection {
+            // 全景图只输出最终结果；irradiance cache 是按相机位置积累的，不参与全景图的计算
+            push_constant.spp = spp;
+            push_constant.channel = truvisl::debug_channel::FINAL;
+            push_constant.ic_enabled = 0;
+            push_constant.panorama = 1;
+            push_constant.ambient_ratio_in_alpha = 0;
+            push_constant.panorama_origin = origin.into();
+        }
+        let spp = push_constant.spp;
         for spp_idx in 0..spp {
             push_constant.spp_idx = spp_idx;
 
@@ -560,26 +359,16 @@ impl RealtimeRtPass {
             );
 
             cmd.cmd_push_constants(
-                self.pipeline.pipeline_layout,
-                vk::ShaderStageFlags::RAYGEN_KHR
-                    | vk::ShaderStageFlags::MISS_KHR
-                    | vk::ShaderStageFlags::ANY_HIT_KHR
-                    | vk::ShaderStageFlags::CALLABLE_KHR
-                    | vk::ShaderStageFlags::CLOSEST_HIT_KHR,
+                self.pipeline.layout(),
+                Self::PUSH_CONSTANT_STAGES,
                 0,
                 BytesConvert::bytes_of(&push_constant),
             );
 
-            cmd.trace_rays(
-                &self._sbt.sbt_region_raygen,
-                &self._sbt.sbt_region_miss,
-                &self._sbt.sbt_region_hit,
-                &self._sbt.sbt_region_callable,
-                [
-                    pass_data.single_frame_extent.width,
-                    pass_data.single_frame_extent.height,
-                    1,
-                ],
+            cmd.cmd_trace_rays(
+                &self.pipeline.sbt(),
+                pass_data.single_frame_extent.width,
+                pass_data.single_frame_extent.height,
             );
         }
 
@@ -592,15 +381,6 @@ impl Drop for RealtimeRtPass {
     }
 }
 
-mod helper {
-    /// round x up to a multiple of align
-    pub fn align_up(x: u32, align: u32) -> u32 {
-        assert!(align.is_power_of_two());
-
-        (x + (align - 1)) & !(align - 1)
-    }
-}
-
 pub struct RealtimeRtRgPass<'a> {
     pub rt_pass: &'a RealtimeRtPass,
 
@@ -644,6 +424,7 @@ impl RgPass for RealtimeRtRgPass<'_> {
             self.render_context,
             ctx.cmd,
             RealtimeRtPassData {
+                projection: RtProjection::Camera,
                 single_frame_output: single_frame_image,
                 single_frame_output_view: single_frame_view,
                 single_frame_extent: self.single_frame_extent,
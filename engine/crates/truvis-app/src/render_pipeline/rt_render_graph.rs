@@ -1,13 +1,25 @@
 use ash::vk;
+use itertools::Itertools;
+use truvis_render_graph::pipeline_warmup::PipelineWarmup;
 use truvis_render_graph::render_context::RenderContext;
 use truvis_render_graph::render_graph::{RenderGraphBuilder, RgImageState, RgSemaphoreInfo};
 use truvis_render_graph::resources::fif_buffer::FifBuffers;
 
 use crate::render_pipeline::blit_pass::{BlitPass, BlitRgPass};
+use crate::render_pipeline::bloom_pass::{BloomPass, BloomRgPass};
+use crate::render_pipeline::debug_draw_pass::{DebugDrawPass, DebugDrawRgPass, DebugDrawer};
 use crate::render_pipeline::denoise_accum_pass::{DenoiseAccumPass, DenoiseAccumRgPass};
+#[cfg(target_os = "linux")]
+use crate::render_pipeline::external_export_pass::{ExternalExportPass, ExternalExportRgPass};
+use crate::render_pipeline::height_fog_pass::{HeightFogPass, HeightFogRgPass};
+use crate::render_pipeline::overlay_pass::{OverlayDrawFn, OverlayRgPass};
+use crate::render_pipeline::panorama_capture::{PanoramaCapture, PanoramaImage};
+use crate::render_pipeline::ray_query_shadow_pass::{RayQueryShadowPass, RayQueryShadowRgPass};
 use crate::render_pipeline::realtime_rt_pass::{RealtimeRtPass, RealtimeRtRgPass};
 use crate::render_pipeline::resolve_pass::{ResolvePass, ResolveRgPass};
 use crate::render_pipeline::sdr_pass::{SdrPass, SdrRgPass};
+use crate::render_pipeline::ssao_pass::{SsaoBlurRgPass, SsaoPass, SsaoRgPass};
+use crate::render_pipeline::taa_pass::{TaaMotionVectorRgPass, TaaPass, TaaRgPass};
 use truvis_gfx::commands::command_buffer::GfxCommandBuffer;
 use truvis_gfx::commands::semaphore::GfxSemaphore;
 use truvis_gfx::gfx::Gfx;
@@ -16,22 +28,39 @@ use truvis_gui_backend::gui_pass::{GuiPass, GuiRgPass};
 use truvis_render_interface::cmd_allocator::CmdAllocator;
 use truvis_render_interface::frame_counter::FrameCounter;
 use truvis_render_interface::global_descriptor_sets::GlobalDescriptorSets;
+use truvis_render_interface::pipeline_settings::{DefaultRendererSettings, PipelineSettings};
 use truvis_renderer::present::render_present::RenderPresent;
+use truvis_renderer::renderer::Renderer;
 
 pub struct RtPipeline {
     /// 光追 pass
     realtime_rt_pass: RealtimeRtPass,
+    /// SSAO pass（AO 计算 + 模糊，结果乘到单帧 RT 输出的 ambient 项上）
+    ssao_pass: SsaoPass,
+    /// 高度雾 pass（解析指数高度雾，混合到单帧 RT 输出上）
+    height_fog_pass: HeightFogPass,
+    /// 内联光线查询阴影 pass（只在调试通道中使用）
+    ray_query_shadow_pass: RayQueryShadowPass,
     /// 降噪累积 pass（双边滤波降噪 + 时域累积）
     denoise_accum_pass: DenoiseAccumPass,
+    /// TAA pass（运动向量 + 历史重投影，在累积结果上做时间抗锯齿）
+    taa_pass: TaaPass,
+    /// Bloom pass（在累积结果上提取亮部并模糊，叠加后写入单帧 RT 输出，再由 SDR pass 读取）
+    bloom_pass: BloomPass,
     /// Blit pass
     blit_pass: BlitPass,
     /// SDR pass
     sdr_pass: SdrPass,
     resolve_pass: ResolvePass,
+    /// 在合成后的光追结果上绘制 debug 线段
+    debug_draw_pass: DebugDrawPass,
     gui_pass: GuiPass,
+    /// 将结果复制到可共享的 image，供其他进程或 API 使用，默认关闭
+    #[cfg(target_os = "linux")]
+    external_export_pass: Option<ExternalExportPass>,
 
-    compute_cmds: [GfxCommandBuffer; FrameCounter::fif_count()],
-    present_cmds: [GfxCommandBuffer; FrameCounter::fif_count()],
+    compute_cmds: Vec<GfxCommandBuffer>,
+    present_cmds: Vec<GfxCommandBuffer>,
 }
 
 // new & init
@@ -42,31 +71,149 @@ impl RtPipeline {
         swapchain: &GfxSwapchain,
         cmd_allocator: &mut CmdAllocator,
     ) -> Self {
+        Self::new_with_present_format(global_descriptor_sets, swapchain.image_infos().image_format, cmd_allocator)
+    }
+
+    /// 有窗口时使用 swapchain 的 format，没有窗口（headless）时使用默认的 surface format
+    ///
+    /// 同时标记 renderer 的管线会执行 TAA resolve，参见 [`RenderContext::taa_resolve_available`]
+    pub fn new_for_renderer(renderer: &mut Renderer) -> Self {
+        let present_format = match renderer.render_present.as_ref() {
+            Some(render_present) => render_present.swapchain_image_info().image_format,
+            None => DefaultRendererSettings::DEFAULT_SURFACE_FORMAT.format,
+        };
+        renderer.render_context.taa_resolve_available = true;
+        Self::new_with_present_format(
+            &renderer.render_context.global_descriptor_sets,
+            present_format,
+            &mut renderer.cmd_allocator,
+        )
+    }
+
+    /// 不依赖 swapchain 创建管线，`present_format` 只影响 resolve / gui pass
+    ///
+    /// 用于没有窗口的场合，此时只能通过 [`Self::render_offscreen`] 渲染
+    pub fn new_with_present_format(
+        global_descriptor_sets: &GlobalDescriptorSets,
+        present_format: vk::Format,
+        cmd_allocator: &mut CmdAllocator,
+    ) -> Self {
+        // 先并行编译所有 compute pipeline，之后各个 pass 的创建可以直接命中 pipeline cache
+        let warmup_descs = std::iter::empty()
+            .chain(SsaoPass::pipeline_descs())
+            .chain(HeightFogPass::pipeline_descs())
+            .chain(DenoiseAccumPass::pipeline_descs())
+            .chain(TaaPass::pipeline_descs())
+            .chain(BloomPass::pipeline_descs())
+            .chain(BlitPass::pipeline_descs())
+            .chain(SdrPass::pipeline_descs())
+            .collect_vec();
+        PipelineWarmup::warmup_compute(global_descriptor_sets, &warmup_descs);
+
         let realtime_rt_pass = RealtimeRtPass::new(global_descriptor_sets);
+        let ssao_pass = SsaoPass::new(global_descriptor_sets);
+        let height_fog_pass = HeightFogPass::new(global_descriptor_sets);
+        let ray_query_shadow_pass = RayQueryShadowPass::new(global_descriptor_sets);
         let denoise_accum_pass = DenoiseAccumPass::new(global_descriptor_sets);
+        let taa_pass = TaaPass::new(global_descriptor_sets);
+        let bloom_pass = BloomPass::new(global_descriptor_sets);
         let blit_pass = BlitPass::new(global_descriptor_sets);
         let sdr_pass = SdrPass::new(global_descriptor_sets);
-        let resolve_pass = ResolvePass::new(global_descriptor_sets, swapchain.image_infos().image_format);
-        let gui_pass = GuiPass::new(global_descriptor_sets, swapchain.image_infos().image_format);
+        let resolve_pass = ResolvePass::new(global_descriptor_sets, present_format);
+        let debug_draw_pass = DebugDrawPass::new(present_format, None);
+        let gui_pass = GuiPass::new(global_descriptor_sets, present_format);
 
         let compute_cmds = FrameCounter::frame_labes()
-            .map(|frame_label| cmd_allocator.alloc_command_buffer(frame_label, "rt-compute-subgraph"));
+            .map(|frame_label| cmd_allocator.alloc_command_buffer(frame_label, "rt-compute-subgraph"))
+            .collect_vec();
         let present_cmds = FrameCounter::frame_labes()
-            .map(|frame_label| cmd_allocator.alloc_command_buffer(frame_label, "rt-present-subgraph"));
+            .map(|frame_label| cmd_allocator.alloc_command_buffer(frame_label, "rt-present-subgraph"))
+            .collect_vec();
 
         Self {
             realtime_rt_pass,
+            ssao_pass,
+            height_fog_pass,
+            ray_query_shadow_pass,
             denoise_accum_pass,
+            taa_pass,
+            bloom_pass,
             blit_pass,
             sdr_pass,
             resolve_pass,
+            debug_draw_pass,
             gui_pass,
+            #[cfg(target_os = "linux")]
+            external_export_pass: None,
             compute_cmds,
             present_cmds,
         }
     }
 }
 
+// external export
+#[cfg(target_os = "linux")]
+impl RtPipeline {
+    /// 开启共享纹理输出，每帧的结果会被缩放到 `extent` 并写入可以导出为 dma-buf 的 image
+    ///
+    /// 设备不支持时返回 false，已经开启时会按新的尺寸重建
+    pub fn enable_external_export(&mut self, extent: vk::Extent2D) -> bool {
+        if self.external_export_pass.is_some() {
+            // 旧的 image 可能还在被 GPU 使用
+            Gfx::get().wait_idel();
+        }
+        self.external_export_pass = ExternalExportPass::new(extent);
+        self.external_export_pass.is_some()
+    }
+
+    pub fn disable_external_export(&mut self) {
+        if self.external_export_pass.take().is_some() {
+            Gfx::get().wait_idel();
+        }
+    }
+
+    #[inline]
+    pub fn external_export_pass(&self) -> Option<&ExternalExportPass> {
+        self.external_export_pass.as_ref()
+    }
+}
+
+// debug draw
+impl RtPipeline {
+    /// 用于绘制这一帧的 debug 线段，需要在 [`Self::render`] 之前调用，每帧都需要重新绘制
+    ///
+    /// 光追管线没有深度，所有线段都画在最上层
+    #[inline]
+    pub fn debug_drawer(&self) -> &DebugDrawer {
+        self.debug_draw_pass.drawer()
+    }
+}
+
+// taa
+impl RtPipeline {
+    /// 丢弃 TAA 的历史，用于相机突变等场合，参见 [`TaaPass::reset`]
+    #[inline]
+    pub fn reset_taa(&self) {
+        self.taa_pass.reset();
+    }
+}
+
+// panorama
+impl RtPipeline {
+    /// 使用光追在 `position` 处拍摄一张宽为 `resolution`、高为 `resolution / 2` 的 HDR 全景图
+    ///
+    /// 参见 [`PanoramaCapture::capture`]
+    pub fn render_panorama(
+        &self,
+        render_context: &mut RenderContext,
+        position: glam::Vec3,
+        resolution: u32,
+        spp: u32,
+    ) -> PanoramaImage {
+        PanoramaCapture::capture(&self.realtime_rt_pass, render_context, position, resolution, spp)
+    }
+}
+
 // render
 impl RtPipeline {
     pub fn render(
@@ -75,10 +222,13 @@ impl RtPipeline {
         render_present: &RenderPresent,
         gui_draw_data: &imgui::DrawData,
         frame_fence: &GfxSemaphore,
+        overlay: &OverlayDrawFn,
     ) {
         let frame_label = render_context.frame_counter.frame_label();
         let frame_id = render_context.frame_counter.frame_id();
 
+        self.debug_draw_pass.prepare(frame_label);
+
         // compute subgraph
         let compute_subgraph_submit = {
             let mut compute_graph_builder = RenderGraphBuilder::new();
@@ -95,7 +245,11 @@ impl RtPipeline {
 
             let compute_cmd = &self.compute_cmds[*frame_label];
             compute_cmd.begin(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT, "rt-render-graph");
-            compute_graph.execute(compute_cmd, &render_context.gfx_resource_manager);
+            compute_graph.execute_with_timer(
+                compute_cmd,
+                &render_context.gfx_resource_manager,
+                Some(&render_context.gpu_timer),
+            );
             compute_cmd.end();
 
             compute_graph.build_submit_info(std::slice::from_ref(compute_cmd))
@@ -110,7 +264,13 @@ impl RtPipeline {
                 frame_id,
             ));
 
-            self.prepare_present_graph(&mut present_graph_builder, render_context, render_present, gui_draw_data);
+            self.prepare_present_graph(
+                &mut present_graph_builder,
+                render_context,
+                render_present,
+                gui_draw_data,
+                overlay,
+            );
             let present_graph = present_graph_builder.compile();
 
             // 调试输出执行计划
@@ -123,7 +283,11 @@ impl RtPipeline {
 
             let present_cmd = &self.present_cmds[*frame_label];
             present_cmd.begin(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT, "rt-present-graph");
-            present_graph.execute(present_cmd, &render_context.gfx_resource_manager);
+            present_graph.execute_with_timer(
+                present_cmd,
+                &render_context.gfx_resource_manager,
+                Some(&render_context.gpu_timer),
+            );
             present_cmd.end();
 
             present_graph.build_submit_info(std::slice::from_ref(present_cmd))
@@ -132,6 +296,34 @@ impl RtPipeline {
         Gfx::get().gfx_queue().submit(vec![compute_subgraph_submit, present_subgraph_submit], None);
     }
 
+    /// 只执行 compute subgraph，结果留在 render target 中，不进行 present
+    ///
+    /// 提交完成时会 signal `frame_fence`，与 [`Self::render`] 一致
+    pub fn render_offscreen(&self, render_context: &RenderContext, frame_fence: &GfxSemaphore) {
+        let frame_label = render_context.frame_counter.frame_label();
+        let frame_id = render_context.frame_counter.frame_id();
+
+        let mut compute_graph_builder = RenderGraphBuilder::new();
+        compute_graph_builder.signal_semaphore(RgSemaphoreInfo::timeline(
+            frame_fence.handle(),
+            vk::PipelineStageFlags2::BOTTOM_OF_PIPE,
+            frame_id,
+        ));
+        self.prepare_compute_graph(&mut compute_graph_builder, render_context);
+        let compute_graph = compute_graph_builder.compile();
+
+        let compute_cmd = &self.compute_cmds[*frame_label];
+        compute_cmd.begin(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT, "rt-offscreen-graph");
+        compute_graph.execute_with_timer(
+            compute_cmd,
+            &render_context.gfx_resource_manager,
+            Some(&render_context.gpu_timer),
+        );
+        compute_cmd.end();
+
+        Gfx::get().gfx_queue().submit(vec![compute_graph.build_submit_info(std::slice::from_ref(compute_cmd))], None);
+    }
+
     pub fn prepare_compute_graph<'a>(
         &'a self,
         rg_builder: &mut RenderGraphBuilder<'a>,
@@ -183,6 +375,26 @@ impl RtPipeline {
             None,
         );
 
+        let (ssao_raw_image_handle, ssao_raw_view_handle) = fif_buffers.ssao_raw_handle(frame_label);
+        let ssao_raw = rg_builder.import_image(
+            "ssao-raw",
+            ssao_raw_image_handle,
+            Some(ssao_raw_view_handle),
+            FifBuffers::ssao_format(),
+            RgImageState::UNDEFINED_TOP,
+            None,
+        );
+
+        let (ssao_image_handle, ssao_view_handle) = fif_buffers.ssao_handle(frame_label);
+        let ssao = rg_builder.import_image(
+            "ssao",
+            ssao_image_handle,
+            Some(ssao_view_handle),
+            FifBuffers::ssao_format(),
+            RgImageState::UNDEFINED_TOP,
+            None,
+        );
+
         // 累积图像（跨帧持久）
         let accum_image = rg_builder.import_image(
             "accum-image",
@@ -207,33 +419,181 @@ impl RtPipeline {
         rg_builder.export_image(render_target, RgImageState::SHADER_READ_FRAGMENT, None);
 
         // 添加 pass
-        // 流程: ray-tracing → denoise-accum → blit → hdr-to-sdr
-        rg_builder
-            .add_pass(
-                "ray-tracing",
-                RealtimeRtRgPass {
-                    rt_pass: &self.realtime_rt_pass,
+        // 流程: ray-tracing → (ssao → ssao-blur) → (height-fog) → (ray-query-shadow) → denoise-accum → (taa) → (bloom) → blit → hdr-to-sdr → (external-export)
+        rg_builder.add_pass(
+            "ray-tracing",
+            RealtimeRtRgPass {
+                rt_pass: &self.realtime_rt_pass,
+                render_context,
+                single_frame_image,
+                single_frame_extent: render_context.frame_settings.frame_extent,
+                gbuffer_a,
+                gbuffer_b,
+                gbuffer_c,
+            },
+        );
+
+        // 未启用 SSAO（或被质量调节关闭）且不在 AO 调试通道时，跳过 SSAO
+        let pipeline_settings = &render_context.pipeline_settings;
+        if SsaoPass::is_active(render_context) {
+            rg_builder
+                .add_pass(
+                    "ssao",
+                    SsaoRgPass {
+                        ssao_pass: &self.ssao_pass,
+                        render_context,
+                        gbuffer_a,
+                        gbuffer_b,
+                        ao_raw: ssao_raw,
+                        image_extent: render_context.frame_settings.frame_extent,
+                    },
+                )
+                .add_pass(
+                    "ssao-blur",
+                    SsaoBlurRgPass {
+                        ssao_pass: &self.ssao_pass,
+                        render_context,
+                        ao_raw: ssao_raw,
+                        ao: ssao,
+                        gbuffer_b,
+                        single_frame_image,
+                        image_extent: render_context.frame_settings.frame_extent,
+                    },
+                );
+        }
+
+        if pipeline_settings.height_fog.enabled {
+            rg_builder.add_pass(
+                "height-fog",
+                HeightFogRgPass {
+                    height_fog_pass: &self.height_fog_pass,
                     render_context,
-                    single_frame_image,
-                    single_frame_extent: render_context.frame_settings.frame_extent,
-                    gbuffer_a,
                     gbuffer_b,
-                    gbuffer_c,
+                    single_frame_image,
+                    image_extent: render_context.frame_settings.frame_extent,
                 },
-            )
-            .add_pass(
-                "denoise-accum",
-                DenoiseAccumRgPass {
-                    denoise_accum_pass: &self.denoise_accum_pass,
+            );
+        }
+
+        // 阴影调试通道：用 ray query 计算的太阳可见性覆盖单帧 RT 结果
+        if pipeline_settings.channel == PipelineSettings::RAY_QUERY_SHADOW_CHANNEL {
+            rg_builder.add_pass(
+                "ray-query-shadow",
+                RayQueryShadowRgPass {
+                    ray_query_shadow_pass: &self.ray_query_shadow_pass,
                     render_context,
-                    single_frame_image,
-                    accum_image,
                     gbuffer_a,
                     gbuffer_b,
-                    gbuffer_c,
+                    single_frame_image,
                     image_extent: render_context.frame_settings.frame_extent,
                 },
-            )
+            );
+        }
+
+        rg_builder.add_pass(
+            "denoise-accum",
+            DenoiseAccumRgPass {
+                denoise_accum_pass: &self.denoise_accum_pass,
+                render_context,
+                single_frame_image,
+                accum_image,
+                gbuffer_a,
+                gbuffer_b,
+                gbuffer_c,
+                image_extent: render_context.frame_settings.frame_extent,
+            },
+        );
+
+        // TAA 的结果写入当前帧的历史图像，之后代替累积图像作为 HDR 结果
+        let taa_image = if pipeline_settings.taa.enabled {
+            let (motion_vector_image_handle, motion_vector_view_handle) = fif_buffers.motion_vector_handle(frame_label);
+            let motion_vector = rg_builder.import_image(
+                "motion-vector",
+                motion_vector_image_handle,
+                Some(motion_vector_view_handle),
+                FifBuffers::motion_vector_format(),
+                RgImageState::UNDEFINED_TOP,
+                None,
+            );
+
+            // 历史图像跨帧持久
+            let [history_input, history_output] =
+                fif_buffers.taa_history_handles(render_context.frame_counter.frame_id());
+            let [history_input, history_output] = [
+                ("taa-history-input", history_input),
+                ("taa-history-output", history_output),
+            ]
+            .map(|(name, (history_image_handle, history_view_handle))| {
+                rg_builder.import_image(
+                    name,
+                    history_image_handle,
+                    Some(history_view_handle),
+                    fif_buffers.accum_image_format(),
+                    RgImageState::STORAGE_READ_WRITE_COMPUTE,
+                    None,
+                )
+            });
+
+            rg_builder
+                .add_pass(
+                    "taa-motion-vector",
+                    TaaMotionVectorRgPass {
+                        taa_pass: &self.taa_pass,
+                        render_context,
+                        gbuffer_b,
+                        motion_vector,
+                        image_extent: render_context.frame_settings.frame_extent,
+                    },
+                )
+                .add_pass(
+                    "taa",
+                    TaaRgPass {
+                        taa_pass: &self.taa_pass,
+                        render_context,
+                        motion_vector,
+                        color_input: accum_image,
+                        history_input,
+                        history_output,
+                        image_extent: render_context.frame_settings.frame_extent,
+                    },
+                );
+            history_output
+        } else {
+            accum_image
+        };
+
+        // 累积图像跨帧持久，不能写入泛光；叠加的结果写入已经不再使用的单帧 RT 输出，作为 SDR pass 的输入
+        let hdr_image = if pipeline_settings.bloom.enabled {
+            let bloom_mips = fif_buffers.bloom_mip_handles(frame_label);
+            let mips = std::array::from_fn(|level| {
+                let (bloom_image_handle, bloom_view_handle) = bloom_mips[level];
+                rg_builder.import_image(
+                    format!("bloom-mip{}", level),
+                    bloom_image_handle,
+                    Some(bloom_view_handle),
+                    FifBuffers::bloom_format(),
+                    RgImageState::UNDEFINED_TOP,
+                    None,
+                )
+            });
+
+            rg_builder.add_pass(
+                "bloom",
+                BloomRgPass {
+                    bloom_pass: &self.bloom_pass,
+                    render_context,
+                    hdr_input: taa_image,
+                    output: single_frame_image,
+                    mips,
+                    image_extent: render_context.frame_settings.frame_extent,
+                },
+            );
+            single_frame_image
+        } else {
+            taa_image
+        };
+
+        rg_builder
             .add_pass(
                 "blit",
                 BlitRgPass {
@@ -250,12 +610,25 @@ impl RtPipeline {
                 SdrRgPass {
                     sdr_pass: &self.sdr_pass,
                     render_context,
-                    src_image: accum_image,
+                    src_image: hdr_image,
                     dst_image: render_target,
                     src_image_extent: render_context.frame_settings.frame_extent,
                     dst_image_extent: render_context.frame_settings.frame_extent,
                 },
             );
+
+        #[cfg(target_os = "linux")]
+        if let Some(external_export_pass) = &self.external_export_pass {
+            rg_builder.add_pass(
+                "external-export",
+                ExternalExportRgPass {
+                    external_export_pass,
+                    render_context,
+                    src_image: render_target,
+                    src_image_extent: render_context.frame_settings.frame_extent,
+                },
+            );
+        }
     }
 
     pub fn prepare_present_graph<'a>(
@@ -264,6 +637,7 @@ impl RtPipeline {
         render_context: &'a RenderContext,
         render_present: &'a RenderPresent,
         gui_draw_data: &'a imgui::DrawData,
+        overlay: &'a OverlayDrawFn<'a>,
     ) {
         let frame_label = render_context.frame_counter.frame_label();
         let fif_buffers = &render_context.fif_buffers;
@@ -302,16 +676,42 @@ impl RtPipeline {
             )),
         );
 
-        // 添加 Pass
+        // 合成顺序: resolve（光追结果 → present image）→ (debug-draw) → overlay → gui
+        // render target 在 compute subgraph 中作为 storage image 写入，导出时转换为 SHADER_READ_FRAGMENT；
+        // present image 在 resolve 中转换为 COLOR_ATTACHMENT_OPTIMAL，之后的光栅 pass 都以 LOAD 的方式叠加在上面
+        rg_builder.add_pass(
+            "resolve",
+            ResolveRgPass {
+                resolve_pass: &self.resolve_pass,
+                render_context,
+                render_target,
+                swapchain_image: present_image,
+                swapchain_extent: render_present.swapchain_image_info().image_extent,
+            },
+        );
+
+        if self.debug_draw_pass.has_lines(frame_label, false) {
+            rg_builder.add_pass(
+                "debug-draw",
+                DebugDrawRgPass {
+                    debug_draw_pass: &self.debug_draw_pass,
+                    render_context,
+                    canvas_color: present_image,
+                    canvas_extent: render_present.swapchain_image_info().image_extent,
+                    depth_image: None,
+                },
+            );
+        }
+
         rg_builder
             .add_pass(
-                "resolve",
-                ResolveRgPass {
-                    resolve_pass: &self.resolve_pass,
+                "overlay",
+                OverlayRgPass {
+                    overlay,
                     render_context,
-                    render_target,
-                    swapchain_image: present_image,
-                    swapchain_extent: render_present.swapchain_image_info().image_extent,
+                    canvas_color: present_image,
+                    canvas_format: render_present.swapchain_image_info().image_format,
+                    canvas_extent: render_present.swapchain_image_info().image_extent,
                 },
             )
             .add_pass(
@@ -322,7 +722,6 @@ impl RtPipeline {
 
                     ui_draw_data: gui_draw_data,
                     gui_mesh: &render_present.gui_backend.gui_meshes[*frame_label],
-                    tex_map: &render_present.gui_backend.tex_map,
 
                     canvas_color: present_image,
                     canvas_extent: render_present.swapchain_image_info().image_extent,
@@ -2,6 +2,7 @@ use ash::vk;
 use truvis_crate_tools::resource::TruvisPath;
 use truvis_gfx::commands::command_buffer::GfxCommandBuffer;
 use truvis_render_graph::compute_pass::ComputePass;
+use truvis_render_graph::pipeline_warmup::ComputePipelineDesc;
 use truvis_render_graph::render_context::RenderContext;
 use truvis_render_graph::render_graph::{RgImageHandle, RgImageState, RgPass, RgPassBuilder, RgPassContext};
 use truvis_render_interface::global_descriptor_sets::GlobalDescriptorSets;
@@ -21,15 +22,20 @@ pub struct SdrPass {
 }
 impl SdrPass {
     pub fn new(render_descriptor_sets: &GlobalDescriptorSets) -> Self {
-        let sdr_pass = ComputePass::<truvisl::sdr::PushConstant>::new(
-            render_descriptor_sets,
-            c"main",
-            TruvisPath::shader_build_path_str("pp/sdr.slang").as_str(),
-        );
+        let [sdr_desc] = Self::pipeline_descs();
+        let sdr_pass = ComputePass::from_desc(render_descriptor_sets, &sdr_desc);
 
         Self { sdr_pass }
     }
 
+    /// 该 pass 使用的所有 compute pipeline，[`Self::new`] 以及启动时的预热都使用这份描述
+    pub fn pipeline_descs() -> [ComputePipelineDesc; 1] {
+        [ComputePipelineDesc::new::<truvisl::sdr::PushConstant>(
+            c"main",
+            TruvisPath::shader_build_path_str("pp/sdr.slang"),
+        )]
+    }
+
     pub fn exec(&self, cmd: &GfxCommandBuffer, data: SdrPassData, render_context: &RenderContext) {
         let src_image_bindless_handle = render_context.bindless_manager.get_shader_uav_handle(data.src_image);
         let dst_image_bindless_handle = render_context.bindless_manager.get_shader_uav_handle(data.dst_image);
@@ -42,6 +48,8 @@ impl SdrPass {
                 dst_image: dst_image_bindless_handle.0,
                 image_size: glam::uvec2(data.src_image_size.width, data.src_image_size.height).into(),
                 channel: render_context.pipeline_settings.channel,
+                tone_operator: render_context.pipeline_settings.tone_mapping.operator.to_shader(),
+                exposure: render_context.pipeline_settings.tone_mapping.exposure,
                 _padding_1: Default::default(),
             },
             glam::uvec3(
@@ -0,0 +1,236 @@
+use std::{mem::offset_of, rc::Rc};
+
+use crate::render_pipeline::instance_index_buffer::InstanceIndexBuffers;
+
+use ash::vk;
+use truvis_crate_tools::resource::TruvisPath;
+use truvis_gfx::basic::bytes::BytesConvert;
+use truvis_gfx::resources::image::GfxImageCreateInfo;
+use truvis_gfx::resources::image_view::GfxImageViewDesc;
+use truvis_gfx::resources::layout::GfxVertexLayout;
+use truvis_gfx::resources::vertex_layout::soa_3d::VertexLayoutSoA3D;
+use truvis_gfx::{
+    basic::color::LabelColor,
+    commands::command_buffer::GfxCommandBuffer,
+    pipelines::{
+        graphics_pipeline::{GfxGraphicsPipeline, GfxGraphicsPipelineCreateInfo, GfxPipelineLayout},
+        rendering_info::GfxRenderingInfo,
+    },
+};
+use truvis_render_graph::render_context::RenderContext;
+use truvis_render_graph::render_graph::{RgImageHandle, RgImageState, RgPass, RgPassBuilder, RgPassContext};
+use truvis_render_interface::handles::{GfxImageHandle, GfxImageViewHandle};
+use truvis_shader_binding::truvisl;
+
+/// 方向光的阴影贴图
+///
+/// 从光源的方向使用正交投影渲染整个场景的深度，view-projection 矩阵由 SceneManager 根据场景包围盒计算，
+/// shader 从 GPUScene 中读取。阴影贴图注册为 bindless srv，主 pass 通过 GPUScene 中的 handle 采样并做 PCF。
+///
+/// 阴影贴图在各帧之间共享：每帧都会重新 clear，依赖 render graph 的 barrier 等待上一帧的采样完成
+///
+/// 使用同一个 mesh 的 instance 合批，每个 submesh 只需要一次 instanced draw
+pub struct ShadowPass {
+    pipeline: GfxGraphicsPipeline,
+    instance_indices: InstanceIndexBuffers,
+
+    shadow_map: GfxImageHandle,
+    shadow_map_view: GfxImageViewHandle,
+    resolution: u32,
+}
+// new & init
+impl ShadowPass {
+    pub const SHADOW_MAP_FORMAT: vk::Format = vk::Format::D32_SFLOAT;
+    pub const DEFAULT_RESOLUTION: u32 = 2048;
+
+    pub fn new(render_context: &mut RenderContext, resolution: u32) -> Self {
+        let mut ci = GfxGraphicsPipelineCreateInfo::default();
+        ci.vertex_shader_stage(&TruvisPath::shader_build_path_str("shadow/shadow.vs.slang"), c"main");
+
+        // 只需要位置
+        ci.vertex_binding(
+            VertexLayoutSoA3D::vertex_input_bindings().into_iter().filter(|binding| binding.binding == 0).collect(),
+        );
+        ci.vertex_attribute(
+            VertexLayoutSoA3D::vertex_input_attributes()
+                .into_iter()
+                .filter(|attribute| attribute.location == 0)
+                .collect(),
+        );
+
+        ci.attach_info(vec![], Some(Self::SHADOW_MAP_FORMAT), None);
+        // 不剔除背面，单面的几何体（例如地板）也能投射阴影
+        ci.cull_mode(vk::CullModeFlags::NONE, vk::FrontFace::COUNTER_CLOCKWISE);
+        // 减少 shadow acne，倾斜的表面需要更大的偏移
+        ci.depth_bias(1.25, 1.75);
+
+        let pipeline_layout = Rc::new(GfxPipelineLayout::new(
+            &render_context.global_descriptor_sets.global_set_layouts(),
+            &[vk::PushConstantRange::default()
+                .stage_flags(vk::ShaderStageFlags::VERTEX)
+                .offset(0)
+                .size(size_of::<truvisl::raster::InstancedPushConstants>() as u32)],
+            "shadow-pass",
+        ));
+        let pipeline = GfxGraphicsPipeline::new(&ci, pipeline_layout, "shadow-pipe");
+
+        let (shadow_map, shadow_map_view) = Self::create_shadow_map(render_context, resolution);
+
+        Self {
+            pipeline,
+            instance_indices: InstanceIndexBuffers::new("shadow-pass"),
+            shadow_map,
+            shadow_map_view,
+            resolution,
+        }
+    }
+
+    /// 创建阴影贴图，注册为 bindless srv 并设置到 GpuScene 中
+    fn create_shadow_map(render_context: &mut RenderContext, resolution: u32) -> (GfxImageHandle, GfxImageViewHandle) {
+        assert!(resolution > 0, "shadow map resolution must be greater than 0");
+
+        let image_create_info = GfxImageCreateInfo::new_image_2d_info(
+            vk::Extent2D {
+                width: resolution,
+                height: resolution,
+            },
+            Self::SHADOW_MAP_FORMAT,
+            vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT | vk::ImageUsageFlags::SAMPLED,
+        );
+        let image_handle = render_context.gfx_resource_manager.create_image(
+            &image_create_info,
+            &vk_mem::AllocationCreateInfo {
+                usage: vk_mem::MemoryUsage::AutoPreferDevice,
+                ..Default::default()
+            },
+            "shadow-map",
+        );
+        let view_handle = render_context.gfx_resource_manager.get_or_create_image_view(
+            image_handle,
+            GfxImageViewDesc::new_2d(Self::SHADOW_MAP_FORMAT, vk::ImageAspectFlags::DEPTH),
+            "shadow-map",
+        );
+
+        render_context.bindless_manager.register_srv(view_handle);
+        render_context.gpu_scene.set_shadow_map(Some((view_handle, resolution)));
+
+        (image_handle, view_handle)
+    }
+}
+// getter
+impl ShadowPass {
+    #[inline]
+    pub fn resolution(&self) -> u32 {
+        self.resolution
+    }
+
+    #[inline]
+    pub fn shadow_map(&self) -> (GfxImageHandle, GfxImageViewHandle) {
+        (self.shadow_map, self.shadow_map_view)
+    }
+}
+// update
+impl ShadowPass {
+    /// 使用新的分辨率重新创建阴影贴图，旧的阴影贴图在当前帧完成之后销毁
+    pub fn set_resolution(&mut self, render_context: &mut RenderContext, resolution: u32) {
+        if resolution == self.resolution {
+            return;
+        }
+
+        render_context.bindless_manager.unregister_srv(self.shadow_map_view);
+        render_context.gfx_resource_manager.destroy_image(self.shadow_map, render_context.frame_counter.frame_id());
+
+        (self.shadow_map, self.shadow_map_view) = Self::create_shadow_map(render_context, resolution);
+        self.resolution = resolution;
+    }
+}
+// tools
+impl ShadowPass {
+    pub fn draw(&self, cmd: &GfxCommandBuffer, render_context: &RenderContext, shadow_map_view: vk::ImageView) {
+        let frame_label = render_context.frame_counter.frame_label();
+        let extent = vk::Extent2D {
+            width: self.resolution,
+            height: self.resolution,
+        };
+
+        // 即使没有方向光也需要 clear，保证阴影贴图的 layout 正确
+        let rendering_info = GfxRenderingInfo::new(vec![], Some(shadow_map_view), extent.into());
+        cmd.cmd_begin_rendering2(&rendering_info);
+        cmd.begin_label("[shadow-pass]draw", LabelColor::COLOR_PASS);
+
+        if render_context.scene_manager.directional_light().is_some() {
+            cmd.cmd_bind_pipeline(vk::PipelineBindPoint::GRAPHICS, self.pipeline.handle());
+            // 光源的投影矩阵与相机约定无关，不需要翻转 viewport
+            let viewport = vk::Viewport {
+                x: 0.0,
+                y: 0.0,
+                width: extent.width as f32,
+                height: extent.height as f32,
+                min_depth: 0.0,
+                max_depth: 1.0,
+            };
+            cmd.cmd_set_viewport(0, std::slice::from_ref(&viewport));
+            cmd.cmd_set_scissor(0, &[extent.into()]);
+            cmd.bind_descriptor_sets(
+                vk::PipelineBindPoint::GRAPHICS,
+                self.pipeline.layout(),
+                0,
+                &render_context.global_descriptor_sets.global_sets(frame_label),
+                None,
+            );
+
+            // 阴影贴图覆盖整个场景，不做视锥剔除
+            let render_data = render_context
+                .scene_manager
+                .prepare_render_data(&render_context.bindless_manager, &render_context.asset_hub);
+            let all_instances = (0..render_data.all_instances.len() as u32).collect::<Vec<_>>();
+            let batches = render_context.scene_manager.instance_batches(&all_instances);
+
+            cmd.cmd_push_constants(
+                self.pipeline.layout(),
+                vk::ShaderStageFlags::VERTEX,
+                0,
+                BytesConvert::bytes_of(&truvisl::raster::InstancedPushConstants {
+                    frame_data: render_context.per_frame_data_buffers[*frame_label].device_address(),
+                    scene: render_context.gpu_scene.scene_buffer(frame_label).device_address(),
+                    instance_indices: self.instance_indices.upload(frame_label, &batches),
+
+                    first_instance: 0, // 这个值在 draw 时会被更新
+                    submesh_idx: 0,    // 这个值在 draw 时会被更新
+                }),
+            );
+            render_context.gpu_scene.draw_batches(cmd, &render_data, &batches, |batch, submesh_idx| {
+                let data = [batch.first_instance, submesh_idx];
+                cmd.cmd_push_constants(
+                    self.pipeline.layout(),
+                    vk::ShaderStageFlags::VERTEX,
+                    offset_of!(truvisl::raster::InstancedPushConstants, first_instance) as u32,
+                    bytemuck::bytes_of(&data),
+                );
+            });
+        }
+
+        cmd.end_label();
+        cmd.end_rendering();
+    }
+}
+
+pub struct ShadowRgPass<'a> {
+    pub shadow_pass: &'a ShadowPass,
+
+    pub render_context: &'a RenderContext,
+
+    pub shadow_map: RgImageHandle,
+}
+
+impl RgPass for ShadowRgPass<'_> {
+    fn setup(&mut self, builder: &mut RgPassBuilder) {
+        builder.write_image(self.shadow_map, RgImageState::DEPTH_ATTACHMENT_WRITE);
+    }
+
+    fn execute(&self, ctx: &RgPassContext<'_>) {
+        let shadow_map_view = ctx.get_image_view(self.shadow_map).expect("ShadowPass: shadow_map not found");
+
+        self.shadow_pass.draw(ctx.cmd, self.render_context, shadow_map_view.handle());
+    }
+}
@@ -0,0 +1,162 @@
+use std::rc::Rc;
+
+use ash::vk;
+use truvis_crate_tools::resource::TruvisPath;
+use truvis_gfx::basic::bytes::BytesConvert;
+use truvis_gfx::basic::color::LabelColor;
+use truvis_gfx::commands::command_buffer::GfxCommandBuffer;
+use truvis_gfx::pipelines::graphics_pipeline::{GfxGraphicsPipeline, GfxGraphicsPipelineCreateInfo, GfxPipelineLayout};
+use truvis_render_graph::render_context::RenderContext;
+use truvis_render_graph::render_graph::{RgImageHandle, RgImageState, RgPass, RgPassBuilder, RgPassContext};
+use truvis_render_interface::global_descriptor_sets::GlobalDescriptorSets;
+use truvis_shader_binding::truvisl;
+
+/// 在光栅化的不透明物体之后绘制天空盒
+///
+/// 使用一个位于远平面的全屏三角形，深度测试为 EQUAL 且不写入深度，只会覆盖深度仍然是 clear 值的像素。
+/// 天空贴图为 GPUScene 中的 sky（等距柱状投影），shader 根据相机矩阵反算每个像素的视线方向后采样，
+/// 因此天空会跟随相机旋转，而不受相机位置影响
+pub struct SkyboxPass {
+    pipeline: GfxGraphicsPipeline,
+
+    /// 天空颜色的缩放
+    pub intensity: f32,
+}
+// new & init
+impl SkyboxPass {
+    pub fn new(
+        color_format: vk::Format,
+        depth_format: vk::Format,
+        global_descriptor_sets: &GlobalDescriptorSets,
+    ) -> Self {
+        let shader_path = TruvisPath::shader_build_path_str("skybox/skybox.slang");
+
+        let mut ci = GfxGraphicsPipelineCreateInfo::default();
+        ci.vertex_shader_stage(&shader_path, c"vs_main");
+        ci.fragment_shader_stage(&shader_path, c"ps_main");
+        ci.vertex_binding(vec![]);
+        ci.vertex_attribute(vec![]);
+
+        ci.attach_info(vec![color_format], Some(depth_format), None);
+        // 全屏三角形的深度为 1，与 depth clear 的值相同
+        ci.depth_test(Some(vk::CompareOp::EQUAL), false, false);
+        ci.cull_mode(vk::CullModeFlags::NONE, vk::FrontFace::COUNTER_CLOCKWISE);
+        ci.color_blend(
+            vec![
+                vk::PipelineColorBlendAttachmentState::default()
+                    .blend_enable(false)
+                    .color_write_mask(vk::ColorComponentFlags::RGBA),
+            ],
+            [0.0; 4],
+        );
+
+        let pipeline_layout = Rc::new(GfxPipelineLayout::new(
+            &global_descriptor_sets.global_set_layouts(),
+            &[vk::PushConstantRange::default()
+                .stage_flags(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT)
+                .offset(0)
+                .size(size_of::<truvisl::skybox::PushConstants>() as u32)],
+            "skybox-pass",
+        ));
+        let pipeline = GfxGraphicsPipeline::new(&ci, pipeline_layout, "skybox-pipe");
+
+        Self {
+            pipeline,
+            intensity: 1.0,
+        }
+    }
+}
+// tools
+impl SkyboxPass {
+    /// 以 LOAD 的方式绘制到 `color_view` 上，`depth_view` 中需要已经有不透明物体的深度
+    pub fn draw(
+        &self,
+        cmd: &GfxCommandBuffer,
+        render_context: &RenderContext,
+        color_view: vk::ImageView,
+        depth_view: vk::ImageView,
+        extent: vk::Extent2D,
+    ) {
+        let frame_label = render_context.frame_counter.frame_label();
+
+        let color_attach_info = vk::RenderingAttachmentInfo::default()
+            .image_view(color_view)
+            .image_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+            .load_op(vk::AttachmentLoadOp::LOAD)
+            .store_op(vk::AttachmentStoreOp::STORE);
+        let depth_attach_info = vk::RenderingAttachmentInfo::default()
+            .image_view(depth_view)
+            .image_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)
+            .load_op(vk::AttachmentLoadOp::LOAD)
+            .store_op(vk::AttachmentStoreOp::NONE);
+        let render_info = vk::RenderingInfo::default()
+            .layer_count(1)
+            .render_area(extent.into())
+            .color_attachments(std::slice::from_ref(&color_attach_info))
+            .depth_attachment(&depth_attach_info);
+
+        cmd.cmd_begin_rendering(&render_info);
+        cmd.begin_label("[skybox-pass]draw", LabelColor::COLOR_PASS);
+
+        cmd.cmd_bind_pipeline(vk::PipelineBindPoint::GRAPHICS, self.pipeline.handle());
+        // 与不透明物体使用相同的 viewport，shader 中的 NDC 才能与投影矩阵的约定一致
+        let viewport = render_context.frame_settings.camera_convention.viewport(extent.into());
+        cmd.cmd_set_viewport(0, std::slice::from_ref(&viewport));
+        cmd.cmd_set_scissor(0, &[extent.into()]);
+
+        cmd.bind_descriptor_sets(
+            vk::PipelineBindPoint::GRAPHICS,
+            self.pipeline.layout(),
+            0,
+            &render_context.global_descriptor_sets.global_sets(frame_label),
+            None,
+        );
+        let push_constant = truvisl::skybox::PushConstants {
+            frame_data: render_context.per_frame_data_buffers[*frame_label].device_address(),
+            scene: render_context.gpu_scene.scene_buffer(frame_label).device_address(),
+            intensity: self.intensity,
+            _padding_0: Default::default(),
+        };
+        cmd.cmd_push_constants(
+            self.pipeline.layout(),
+            vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+            0,
+            BytesConvert::bytes_of(&push_constant),
+        );
+        cmd.cmd_draw(3, 1, 0, 0);
+
+        cmd.end_label();
+        cmd.end_rendering();
+    }
+}
+
+pub struct SkyboxRgPass<'a> {
+    pub skybox_pass: &'a SkyboxPass,
+
+    pub render_context: &'a RenderContext,
+
+    /// 已经绘制了不透明物体的 render target（读写）
+    pub render_target: RgImageHandle,
+    /// 不透明物体的深度（只读）
+    pub depth_image: RgImageHandle,
+}
+
+impl RgPass for SkyboxRgPass<'_> {
+    fn setup(&mut self, builder: &mut RgPassBuilder) {
+        builder.read_write_image(self.render_target, RgImageState::COLOR_ATTACHMENT_READ_WRITE);
+        builder.read_image(self.depth_image, RgImageState::DEPTH_ATTACHMENT_READ);
+    }
+
+    fn execute(&self, ctx: &RgPassContext<'_>) {
+        let render_target_view = ctx.get_image_view(self.render_target).expect("SkyboxPass: render_target not found");
+        let depth_view = ctx.get_image_view(self.depth_image).expect("SkyboxPass: depth_image not found");
+
+        self.skybox_pass.draw(
+            ctx.cmd,
+            self.render_context,
+            render_target_view.handle(),
+            depth_view.handle(),
+            self.render_context.frame_settings.frame_extent,
+        );
+    }
+}
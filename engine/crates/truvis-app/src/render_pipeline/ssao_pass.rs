@@ -0,0 +1,233 @@
+use ash::vk;
+use truvis_crate_tools::resource::TruvisPath;
+use truvis_gfx::commands::command_buffer::GfxCommandBuffer;
+use truvis_render_graph::compute_pass::ComputePass;
+use truvis_render_graph::pipeline_warmup::ComputePipelineDesc;
+use truvis_render_graph::render_context::RenderContext;
+use truvis_render_graph::render_graph::{RgImageHandle, RgImageState, RgPass, RgPassBuilder, RgPassContext};
+use truvis_render_interface::bindless_manager::{BindlessSrvHandle, BindlessUavHandle};
+use truvis_render_interface::global_descriptor_sets::GlobalDescriptorSets;
+use truvis_render_interface::pipeline_settings::PipelineSettings;
+use truvis_shader_binding::truvisl;
+
+/// SSAO Pass 的数据
+pub struct SsaoPassData {
+    pub gbuffer_a_bindless_uav_handle: BindlessUavHandle,
+    pub gbuffer_b_bindless_uav_handle: BindlessUavHandle,
+    pub ao_bindless_uav_handle: BindlessUavHandle,
+    pub noise_bindless_srv_handle: BindlessSrvHandle,
+    pub image_size: vk::Extent2D,
+    pub sample_count: u32,
+    pub radius: f32,
+    pub intensity: f32,
+    pub bias: f32,
+}
+
+/// SSAO 模糊 Pass 的数据
+pub struct SsaoBlurPassData {
+    pub ao_input_bindless_uav_handle: BindlessUavHandle,
+    pub ao_output_bindless_uav_handle: BindlessUavHandle,
+    pub gbuffer_b_bindless_uav_handle: BindlessUavHandle,
+    pub single_frame_bindless_uav_handle: BindlessUavHandle,
+    pub image_size: vk::Extent2D,
+    /// 调试通道，[`PipelineSettings::SSAO_CHANNEL`] 时只输出 AO
+    pub channel: u32,
+}
+
+/// SSAO Pass - 基于 GBuffer 计算 AO，模糊后乘到单帧 RT 结果的 ambient 项上
+///
+/// 包含两个 compute shader：
+/// - ssao：半球采样估计遮蔽，使用 [`FifBuffers`] 中的旋转噪声纹理打乱采样核，输出带噪声的 AO
+/// - ssao_blur：深度感知的模糊，消除噪声后作用到单帧 RT 结果
+///
+/// 直接光照已经由阴影光线计算了可见性，AO 只衰减 ambient 项（环境光与间接光），
+/// ambient 项的占比由 RT pass 写在单帧输出的 alpha 中，参见 [`Self::is_active`]
+///
+/// [`FifBuffers`]: truvis_render_graph::resources::fif_buffer::FifBuffers
+pub struct SsaoPass {
+    ssao_pass: ComputePass<truvisl::ssao::PushConstant>,
+    ssao_blur_pass: ComputePass<truvisl::ssao_blur::PushConstant>,
+}
+
+impl SsaoPass {
+    pub fn new(render_descriptor_sets: &GlobalDescriptorSets) -> Self {
+        let [ssao_desc, ssao_blur_desc] = Self::pipeline_descs();
+        let ssao_pass = ComputePass::from_desc(render_descriptor_sets, &ssao_desc);
+        let ssao_blur_pass = ComputePass::from_desc(render_descriptor_sets, &ssao_blur_desc);
+
+        Self {
+            ssao_pass,
+            ssao_blur_pass,
+        }
+    }
+
+    /// 该 pass 使用的所有 compute pipeline，[`Self::new`] 以及启动时的预热都使用这份描述
+    pub fn pipeline_descs() -> [ComputePipelineDesc; 2] {
+        [
+            ComputePipelineDesc::new::<truvisl::ssao::PushConstant>(
+                c"main",
+                TruvisPath::shader_build_path_str("pp/ssao.slang"),
+            ),
+            ComputePipelineDesc::new::<truvisl::ssao_blur::PushConstant>(
+                c"main",
+                TruvisPath::shader_build_path_str("pp/ssao_blur.slang"),
+            ),
+        ]
+    }
+
+    /// 本帧是否执行 SSAO：启用且未被质量调节关闭，或者处于 AO 调试通道
+    ///
+    /// 执行时 RT pass 需要在单帧输出的 alpha 中写入 ambient 项的占比
+    pub fn is_active(render_context: &RenderContext) -> bool {
+        let pipeline_settings = &render_context.pipeline_settings;
+        (pipeline_settings.ssao.enabled && render_context.frame_settings.quality.ssao_allowed)
+            || pipeline_settings.channel == PipelineSettings::SSAO_CHANNEL
+    }
+
+    pub fn exec_ssao(&self, cmd: &GfxCommandBuffer, data: SsaoPassData, render_context: &RenderContext) {
+        self.ssao_pass.exec(
+            cmd,
+            render_context,
+            &truvisl::ssao::PushConstant {
+                gbuffer_a: data.gbuffer_a_bindless_uav_handle.0,
+                gbuffer_b: data.gbuffer_b_bindless_uav_handle.0,
+                ao_output: data.ao_bindless_uav_handle.0,
+                sample_count: data.sample_count,
+                image_size: glam::uvec2(data.image_size.width, data.image_size.height).into(),
+                radius: data.radius,
+                intensity: data.intensity,
+                bias: data.bias,
+                noise: data.noise_bindless_srv_handle.0,
+            },
+            glam::uvec3(
+                data.image_size.width.div_ceil(truvisl::ssao::SHADER_X as u32),
+                data.image_size.height.div_ceil(truvisl::ssao::SHADER_Y as u32),
+                1,
+            ),
+        );
+    }
+
+    pub fn exec_blur(&self, cmd: &GfxCommandBuffer, data: SsaoBlurPassData, render_context: &RenderContext) {
+        self.ssao_blur_pass.exec(
+            cmd,
+            render_context,
+            &truvisl::ssao_blur::PushConstant {
+                ao_input: data.ao_input_bindless_uav_handle.0,
+                ao_output: data.ao_output_bindless_uav_handle.0,
+                gbuffer_b: data.gbuffer_b_bindless_uav_handle.0,
+                single_frame_image: data.single_frame_bindless_uav_handle.0,
+                image_size: glam::uvec2(data.image_size.width, data.image_size.height).into(),
+                channel: data.channel,
+                _padding0: 0,
+            },
+            glam::uvec3(
+                data.image_size.width.div_ceil(truvisl::ssao_blur::SHADER_X as u32),
+                data.image_size.height.div_ceil(truvisl::ssao_blur::SHADER_Y as u32),
+                1,
+            ),
+        );
+    }
+}
+
+/// SSAO Pass 的 RenderGraph 封装
+pub struct SsaoRgPass<'a> {
+    pub ssao_pass: &'a SsaoPass,
+
+    pub render_context: &'a RenderContext,
+
+    /// GBufferA: normal.xyz + roughness（只读）
+    pub gbuffer_a: RgImageHandle,
+    /// GBufferB: world_position.xyz + linear_depth（只读）
+    pub gbuffer_b: RgImageHandle,
+    /// 未模糊的 AO（只写）
+    pub ao_raw: RgImageHandle,
+
+    pub image_extent: vk::Extent2D,
+}
+
+impl<'a> RgPass for SsaoRgPass<'a> {
+    fn setup(&mut self, builder: &mut RgPassBuilder) {
+        builder.read_image(self.gbuffer_a, RgImageState::STORAGE_READ_COMPUTE);
+        builder.read_image(self.gbuffer_b, RgImageState::STORAGE_READ_COMPUTE);
+        builder.write_image(self.ao_raw, RgImageState::STORAGE_WRITE_COMPUTE);
+    }
+
+    fn execute(&self, ctx: &RgPassContext) {
+        let gbuffer_a_view_handle = ctx.get_image_view_handle(self.gbuffer_a).unwrap();
+        let gbuffer_b_view_handle = ctx.get_image_view_handle(self.gbuffer_b).unwrap();
+        let ao_raw_view_handle = ctx.get_image_view_handle(self.ao_raw).unwrap();
+
+        let bindless_manager = &self.render_context.bindless_manager;
+        let ssao_settings = &self.render_context.pipeline_settings.ssao;
+
+        self.ssao_pass.exec_ssao(
+            ctx.cmd,
+            SsaoPassData {
+                gbuffer_a_bindless_uav_handle: bindless_manager.get_shader_uav_handle(gbuffer_a_view_handle),
+                gbuffer_b_bindless_uav_handle: bindless_manager.get_shader_uav_handle(gbuffer_b_view_handle),
+                ao_bindless_uav_handle: bindless_manager.get_shader_uav_handle(ao_raw_view_handle),
+                noise_bindless_srv_handle: bindless_manager
+                    .get_shader_srv_handle(self.render_context.fif_buffers.ssao_noise_view_handle()),
+                image_size: self.image_extent,
+                // 质量调节关闭 SSAO 时上限为 0，AO 调试通道下至少保留 1 个采样
+                sample_count: ssao_settings
+                    .sample_count
+                    .min(self.render_context.frame_settings.quality.ssao_max_sample_count)
+                    .max(1),
+                radius: ssao_settings.radius,
+                intensity: ssao_settings.intensity,
+                bias: ssao_settings.bias,
+            },
+            self.render_context,
+        );
+    }
+}
+
+/// SSAO 模糊 Pass 的 RenderGraph 封装
+pub struct SsaoBlurRgPass<'a> {
+    pub ssao_pass: &'a SsaoPass,
+
+    pub render_context: &'a RenderContext,
+
+    /// 未模糊的 AO（只读）
+    pub ao_raw: RgImageHandle,
+    /// 模糊后的 AO（只写）
+    pub ao: RgImageHandle,
+    /// GBufferB: world_position.xyz + linear_depth（只读）
+    pub gbuffer_b: RgImageHandle,
+    /// 单帧 RT 输出（读写）
+    pub single_frame_image: RgImageHandle,
+
+    pub image_extent: vk::Extent2D,
+}
+
+impl<'a> RgPass for SsaoBlurRgPass<'a> {
+    fn setup(&mut self, builder: &mut RgPassBuilder) {
+        builder.read_image(self.ao_raw, RgImageState::STORAGE_READ_COMPUTE);
+        builder.read_image(self.gbuffer_b, RgImageState::STORAGE_READ_COMPUTE);
+        builder.write_image(self.ao, RgImageState::STORAGE_WRITE_COMPUTE);
+        builder.read_write_image(self.single_frame_image, RgImageState::STORAGE_READ_WRITE_COMPUTE);
+    }
+
+    fn execute(&self, ctx: &RgPassContext) {
+        let ao_raw_view_handle = ctx.get_image_view_handle(self.ao_raw).unwrap();
+        let ao_view_handle = ctx.get_image_view_handle(self.ao).unwrap();
+        let gbuffer_b_view_handle = ctx.get_image_view_handle(self.gbuffer_b).unwrap();
+        let single_frame_view_handle = ctx.get_image_view_handle(self.single_frame_image).unwrap();
+
+        let bindless_manager = &self.render_context.bindless_manager;
+
+        self.ssao_pass.exec_blur(
+            ctx.cmd,
+            SsaoBlurPassData {
+                ao_input_bindless_uav_handle: bindless_manager.get_shader_uav_handle(ao_raw_view_handle),
+                ao_output_bindless_uav_handle: bindless_manager.get_shader_uav_handle(ao_view_handle),
+                gbuffer_b_bindless_uav_handle: bindless_manager.get_shader_uav_handle(gbuffer_b_view_handle),
+                single_frame_bindless_uav_handle: bindless_manager.get_shader_uav_handle(single_frame_view_handle),
+                image_size: self.image_extent,
+                channel: self.render_context.pipeline_settings.channel,
+            },
+            self.render_context,
+        );
+    }
+}
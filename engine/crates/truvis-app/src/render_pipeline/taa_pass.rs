@@ -0,0 +1,264 @@
+use std::cell::Cell;
+
+use ash::vk;
+use truvis_crate_tools::resource::TruvisPath;
+use truvis_gfx::basic::color::LabelColor;
+use truvis_gfx::commands::command_buffer::GfxCommandBuffer;
+use truvis_render_graph::compute_pass::ComputePass;
+use truvis_render_graph::pipeline_warmup::ComputePipelineDesc;
+use truvis_render_graph::render_context::RenderContext;
+use truvis_render_graph::render_graph::{RgImageHandle, RgImageState, RgPass, RgPassBuilder, RgPassContext};
+use truvis_render_interface::bindless_manager::BindlessUavHandle;
+use truvis_render_interface::global_descriptor_sets::GlobalDescriptorSets;
+use truvis_shader_binding::truvisl;
+
+/// TAA Pass 的数据
+pub struct TaaPassData {
+    /// GBufferB: world_position.xyz + linear_depth（只读）
+    pub gbuffer_b: BindlessUavHandle,
+    pub motion_vector: BindlessUavHandle,
+    /// 当前帧的 HDR 结果（只读）
+    pub color_input: BindlessUavHandle,
+    /// 上一帧的 TAA 结果（只读）
+    pub history_input: BindlessUavHandle,
+    /// 当前帧的 TAA 结果（只写）
+    pub history_output: BindlessUavHandle,
+    pub image_size: vk::Extent2D,
+    pub history_weight: f32,
+    /// 为 false 时使用不做邻域 clamp 的 shader 变体，参见 [`TaaPass::NO_NEIGHBORHOOD_CLAMP_DEFINES`]
+    pub neighborhood_clamp: bool,
+}
+
+/// TAA Pass - 用运动向量重投影上一帧的结果，邻域 clamp 后与当前帧混合
+///
+/// 相机的 jitter 和上一帧的矩阵由 [`truvis_render_interface::camera_history::CameraHistory`] 提供；
+/// 历史图像参见 [`truvis_render_graph::resources::fif_buffer::FifBuffers::taa_history_handles`]
+pub struct TaaPass {
+    taa_pass: ComputePass<truvisl::taa::PushConstant>,
+    /// 不做邻域 clamp 的变体，只用于 resolve
+    taa_no_clamp_pass: ComputePass<truvisl::taa::PushConstant>,
+
+    /// 调用了 [`Self::reset`]，下一次 resolve 时丢弃历史
+    reset_requested: Cell<bool>,
+    /// 上一次写入历史的帧，只有上一帧写入过的历史才可用
+    last_frame_id: Cell<Option<u64>>,
+}
+// new & init
+impl TaaPass {
+    /// 关闭邻域 clamp 的 shader 变体，由 shader-build 根据 `permutations.toml` 编译
+    pub const NO_NEIGHBORHOOD_CLAMP_DEFINES: &'static [&'static str] = &["TAA_NO_NEIGHBORHOOD_CLAMP"];
+
+    pub fn new(render_descriptor_sets: &GlobalDescriptorSets) -> Self {
+        let [taa_desc, taa_no_clamp_desc] = Self::pipeline_descs();
+        let taa_pass = ComputePass::from_desc(render_descriptor_sets, &taa_desc);
+        let taa_no_clamp_pass = ComputePass::from_desc(render_descriptor_sets, &taa_no_clamp_desc);
+
+        Self {
+            taa_pass,
+            taa_no_clamp_pass,
+            reset_requested: Cell::new(false),
+            last_frame_id: Cell::new(None),
+        }
+    }
+
+    /// 该 pass 使用的所有 compute pipeline，[`Self::new`] 以及启动时的预热都使用这份描述
+    pub fn pipeline_descs() -> [ComputePipelineDesc; 2] {
+        [
+            ComputePipelineDesc::new::<truvisl::taa::PushConstant>(
+                c"main",
+                TruvisPath::shader_build_path_str("pp/taa.slang"),
+            ),
+            ComputePipelineDesc::new::<truvisl::taa::PushConstant>(
+                c"main",
+                TruvisPath::shader_variant_build_path_str("pp/taa.slang", Self::NO_NEIGHBORHOOD_CLAMP_DEFINES),
+            ),
+        ]
+    }
+}
+// update
+impl TaaPass {
+    /// 丢弃历史，下一帧直接输出当前帧的结果，用于相机突变（传送、切换视角）等场合
+    ///
+    /// 由 `OuterApp::reset_temporal_history` 在相机跳转、窗口大小改变以及加载模型之后调用
+    pub fn reset(&self) {
+        self.reset_requested.set(true);
+    }
+}
+// tools
+impl TaaPass {
+    pub fn exec_motion_vector(&self, cmd: &GfxCommandBuffer, data: TaaPassData, render_context: &RenderContext) {
+        cmd.begin_label("[taa]motion-vector", LabelColor::COLOR_PASS);
+        self.exec(cmd, render_context, &data, truvisl::taa::MODE_MOTION_VECTOR, false);
+        cmd.end_label();
+    }
+
+    /// 历史是否可用由上一帧是否执行过 TAA、[`Self::reset`] 和相机历史共同决定
+    pub fn exec_resolve(&self, cmd: &GfxCommandBuffer, data: TaaPassData, render_context: &RenderContext) {
+        let frame_id = render_context.frame_counter.frame_id();
+        let history_valid = Self::is_history_valid(
+            self.last_frame_id.replace(Some(frame_id)),
+            frame_id,
+            self.reset_requested.replace(false),
+            render_context.camera_history.prev_valid(),
+        );
+
+        cmd.begin_label("[taa]resolve", LabelColor::COLOR_PASS);
+        self.exec(cmd, render_context, &data, truvisl::taa::MODE_RESOLVE, history_valid);
+        cmd.end_label();
+    }
+
+    fn exec(
+        &self,
+        cmd: &GfxCommandBuffer,
+        render_context: &RenderContext,
+        data: &TaaPassData,
+        mode: u32,
+        history_valid: bool,
+    ) {
+        let pass = if mode == truvisl::taa::MODE_RESOLVE && !data.neighborhood_clamp {
+            &self.taa_no_clamp_pass
+        } else {
+            &self.taa_pass
+        };
+        pass.exec(
+            cmd,
+            render_context,
+            &truvisl::taa::PushConstant {
+                gbuffer_b: data.gbuffer_b.0,
+                motion_vector: data.motion_vector.0,
+                color_input: data.color_input.0,
+                history_input: data.history_input.0,
+                history_output: data.history_output.0,
+                mode,
+                image_size: glam::uvec2(data.image_size.width, data.image_size.height).into(),
+                history_weight: data.history_weight,
+                history_valid: history_valid as u32,
+                _padding0: Default::default(),
+            },
+            glam::uvec3(
+                data.image_size.width.div_ceil(truvisl::taa::SHADER_X as u32),
+                data.image_size.height.div_ceil(truvisl::taa::SHADER_Y as u32),
+                1,
+            ),
+        );
+    }
+
+    /// 只有上一帧写入过历史、没有 reset 并且相机历史可用时，才能使用历史
+    fn is_history_valid(
+        last_frame_id: Option<u64>,
+        frame_id: u64,
+        reset_requested: bool,
+        camera_prev_valid: bool,
+    ) -> bool {
+        !reset_requested && camera_prev_valid && last_frame_id.is_some_and(|id| id + 1 == frame_id)
+    }
+}
+
+/// 运动向量的 RenderGraph 封装
+pub struct TaaMotionVectorRgPass<'a> {
+    pub taa_pass: &'a TaaPass,
+
+    pub render_context: &'a RenderContext,
+
+    /// GBufferB: world_position.xyz + linear_depth（只读）
+    pub gbuffer_b: RgImageHandle,
+    /// 运动向量（只写）
+    pub motion_vector: RgImageHandle,
+
+    pub image_extent: vk::Extent2D,
+}
+
+impl<'a> RgPass for TaaMotionVectorRgPass<'a> {
+    fn setup(&mut self, builder: &mut RgPassBuilder) {
+        builder.read_image(self.gbuffer_b, RgImageState::STORAGE_READ_COMPUTE);
+        builder.write_image(self.motion_vector, RgImageState::STORAGE_WRITE_COMPUTE);
+    }
+
+    fn execute(&self, ctx: &RgPassContext) {
+        let bindless_manager = &self.render_context.bindless_manager;
+        let uav_handle =
+            |image: RgImageHandle| bindless_manager.get_shader_uav_handle(ctx.get_image_view_handle(image).unwrap());
+
+        self.taa_pass.exec_motion_vector(
+            ctx.cmd,
+            TaaPassData {
+                gbuffer_b: uav_handle(self.gbuffer_b),
+                motion_vector: uav_handle(self.motion_vector),
+                color_input: BindlessUavHandle::null(),
+                history_input: BindlessUavHandle::null(),
+                history_output: BindlessUavHandle::null(),
+                image_size: self.image_extent,
+                history_weight: self.render_context.pipeline_settings.taa.history_weight,
+                neighborhood_clamp: self.render_context.pipeline_settings.taa.neighborhood_clamp,
+            },
+            self.render_context,
+        );
+    }
+}
+
+/// TAA resolve 的 RenderGraph 封装
+pub struct TaaRgPass<'a> {
+    pub taa_pass: &'a TaaPass,
+
+    pub render_context: &'a RenderContext,
+
+    /// 运动向量（只读）
+    pub motion_vector: RgImageHandle,
+    /// 当前帧的 HDR 结果（只读）
+    pub color_input: RgImageHandle,
+    /// 上一帧的 TAA 结果（只读）
+    pub history_input: RgImageHandle,
+    /// 当前帧的 TAA 结果（只写）
+    pub history_output: RgImageHandle,
+
+    pub image_extent: vk::Extent2D,
+}
+
+impl<'a> RgPass for TaaRgPass<'a> {
+    fn setup(&mut self, builder: &mut RgPassBuilder) {
+        builder.read_image(self.motion_vector, RgImageState::STORAGE_READ_COMPUTE);
+        builder.read_image(self.color_input, RgImageState::STORAGE_READ_COMPUTE);
+        builder.read_image(self.history_input, RgImageState::STORAGE_READ_COMPUTE);
+        builder.write_image(self.history_output, RgImageState::STORAGE_WRITE_COMPUTE);
+    }
+
+    fn execute(&self, ctx: &RgPassContext) {
+        let bindless_manager = &self.render_context.bindless_manager;
+        let uav_handle =
+            |image: RgImageHandle| bindless_manager.get_shader_uav_handle(ctx.get_image_view_handle(image).unwrap());
+
+        self.taa_pass.exec_resolve(
+            ctx.cmd,
+            TaaPassData {
+                gbuffer_b: BindlessUavHandle::null(),
+                motion_vector: uav_handle(self.motion_vector),
+                color_input: uav_handle(self.color_input),
+                history_input: uav_handle(self.history_input),
+                history_output: uav_handle(self.history_output),
+                image_size: self.image_extent,
+                history_weight: self.render_context.pipeline_settings.taa.history_weight,
+                neighborhood_clamp: self.render_context.pipeline_settings.taa.neighborhood_clamp,
+            },
+            self.render_context,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_requires_previous_frame() {
+        assert!(TaaPass::is_history_valid(Some(9), 10, false, true));
+        // 第一帧，或者中间有帧没有执行 TAA
+        assert!(!TaaPass::is_history_valid(None, 10, false, true));
+        assert!(!TaaPass::is_history_valid(Some(8), 10, false, true));
+    }
+
+    #[test]
+    fn test_reset_discards_history() {
+        assert!(!TaaPass::is_history_valid(Some(9), 10, true, true));
+        assert!(!TaaPass::is_history_valid(Some(9), 10, false, false));
+    }
+}
@@ -0,0 +1,186 @@
+use std::rc::Rc;
+
+use ash::vk;
+use truvis_crate_tools::resource::TruvisPath;
+use truvis_gfx::basic::bytes::BytesConvert;
+use truvis_gfx::basic::color::LabelColor;
+use truvis_gfx::commands::command_buffer::GfxCommandBuffer;
+use truvis_gfx::pipelines::graphics_pipeline::{GfxGraphicsPipeline, GfxGraphicsPipelineCreateInfo, GfxPipelineLayout};
+use truvis_gfx::pipelines::rendering_info::GfxRenderingInfo;
+use truvis_render_graph::render_context::RenderContext;
+use truvis_render_graph::render_graph::{RgImageHandle, RgImageState, RgPass, RgPassBuilder, RgPassContext};
+use truvis_render_interface::global_descriptor_sets::GlobalDescriptorSets;
+use truvis_render_interface::handles::GfxImageViewHandle;
+use truvis_shader_binding::truvisl;
+
+/// 将 HDR color target 经过 tone mapping 绘制到 swapchain 上
+///
+/// 使用全屏三角形采样 HDR 图像，算子和曝光来自 [`truvis_render_interface::pipeline_settings::ToneMappingSettings`]。
+/// swapchain 为 sRGB 格式时由硬件完成 gamma 校正，否则在 shader 中手动转换到 sRGB 空间
+pub struct ToneMappingPass {
+    pipeline: GfxGraphicsPipeline,
+    /// 输出格式不是 sRGB 时需要在 shader 中做 gamma 校正
+    apply_gamma: bool,
+}
+// new & init
+impl ToneMappingPass {
+    /// `color_format` 为 swapchain（color attachment）的格式
+    pub fn new(global_descriptor_sets: &GlobalDescriptorSets, color_format: vk::Format) -> Self {
+        let shader_path = TruvisPath::shader_build_path_str("pp/tone_mapping.slang");
+
+        let mut ci = GfxGraphicsPipelineCreateInfo::default();
+        ci.vertex_shader_stage(&shader_path, c"vs_main");
+        ci.fragment_shader_stage(&shader_path, c"ps_main");
+        ci.vertex_binding(vec![]);
+        ci.vertex_attribute(vec![]);
+
+        ci.attach_info(vec![color_format], None, Some(vk::Format::UNDEFINED));
+        ci.cull_mode(vk::CullModeFlags::NONE, vk::FrontFace::COUNTER_CLOCKWISE);
+        ci.color_blend(
+            vec![
+                vk::PipelineColorBlendAttachmentState::default()
+                    .blend_enable(false)
+                    .color_write_mask(vk::ColorComponentFlags::RGBA),
+            ],
+            [0.0; 4],
+        );
+
+        let pipeline_layout = Rc::new(GfxPipelineLayout::new(
+            &global_descriptor_sets.global_set_layouts(),
+            &[vk::PushConstantRange::default()
+                .stage_flags(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT)
+                .offset(0)
+                .size(size_of::<truvisl::tone_mapping::PushConstant>() as u32)],
+            "tone-mapping-pass",
+        ));
+        let pipeline = GfxGraphicsPipeline::new(&ci, pipeline_layout, "tone-mapping-pipe");
+
+        Self {
+            pipeline,
+            apply_gamma: !Self::is_srgb_format(color_format),
+        }
+    }
+}
+// getter
+impl ToneMappingPass {
+    /// 写入时是否会由硬件将 linear 转换为 sRGB
+    pub fn is_srgb_format(format: vk::Format) -> bool {
+        matches!(
+            format,
+            vk::Format::R8_SRGB
+                | vk::Format::R8G8_SRGB
+                | vk::Format::R8G8B8_SRGB
+                | vk::Format::B8G8R8_SRGB
+                | vk::Format::R8G8B8A8_SRGB
+                | vk::Format::B8G8R8A8_SRGB
+                | vk::Format::A8B8G8R8_SRGB_PACK32
+        )
+    }
+}
+// tools
+impl ToneMappingPass {
+    /// 采样 `hdr_input`，覆盖整个 `color_view`
+    pub fn draw(
+        &self,
+        cmd: &GfxCommandBuffer,
+        render_context: &RenderContext,
+        hdr_input: GfxImageViewHandle,
+        color_view: vk::ImageView,
+        extent: vk::Extent2D,
+    ) {
+        let frame_label = render_context.frame_counter.frame_label();
+        let tone_mapping = &render_context.pipeline_settings.tone_mapping;
+
+        let rendering_info = GfxRenderingInfo::new(vec![color_view], None, extent.into());
+        cmd.cmd_begin_rendering2(&rendering_info);
+        cmd.begin_label("[tone-mapping-pass]draw", LabelColor::COLOR_PASS);
+
+        cmd.cmd_bind_pipeline(vk::PipelineBindPoint::GRAPHICS, self.pipeline.handle());
+        // shader 中的 uv 直接由 NDC 得到，不需要翻转 viewport
+        cmd.cmd_set_viewport(
+            0,
+            &[vk::Viewport {
+                x: 0.0,
+                y: 0.0,
+                width: extent.width as f32,
+                height: extent.height as f32,
+                min_depth: 0.0,
+                max_depth: 1.0,
+            }],
+        );
+        cmd.cmd_set_scissor(0, &[extent.into()]);
+
+        cmd.bind_descriptor_sets(
+            vk::PipelineBindPoint::GRAPHICS,
+            self.pipeline.layout(),
+            0,
+            &render_context.global_descriptor_sets.global_sets(frame_label),
+            None,
+        );
+        let push_constant = truvisl::tone_mapping::PushConstant {
+            src_texture: render_context.bindless_manager.get_shader_srv_handle(hdr_input).0,
+            sampler_type: truvisl::ESamplerType_LinearClamp,
+            tone_operator: tone_mapping.operator.to_shader(),
+            exposure: tone_mapping.exposure,
+            apply_gamma: self.apply_gamma as u32,
+            _padding0: Default::default(),
+        };
+        cmd.cmd_push_constants(
+            self.pipeline.layout(),
+            vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+            0,
+            BytesConvert::bytes_of(&push_constant),
+        );
+        cmd.cmd_draw(3, 1, 0, 0);
+
+        cmd.end_label();
+        cmd.end_rendering();
+    }
+}
+
+pub struct ToneMappingRgPass<'a> {
+    pub tone_mapping_pass: &'a ToneMappingPass,
+
+    pub render_context: &'a RenderContext,
+
+    /// HDR color target（只读，需要注册为 bindless srv）
+    pub hdr_input: RgImageHandle,
+    /// 输出的 swapchain image（只写）
+    pub swapchain_image: RgImageHandle,
+
+    pub swapchain_extent: vk::Extent2D,
+}
+
+impl RgPass for ToneMappingRgPass<'_> {
+    fn setup(&mut self, builder: &mut RgPassBuilder) {
+        builder.read_image(self.hdr_input, RgImageState::SHADER_READ_FRAGMENT);
+        builder.write_image(self.swapchain_image, RgImageState::COLOR_ATTACHMENT_READ_WRITE);
+    }
+
+    fn execute(&self, ctx: &RgPassContext<'_>) {
+        let hdr_input = ctx.get_image_view_handle(self.hdr_input).expect("ToneMappingPass: hdr_input not found");
+        let swapchain_view =
+            ctx.get_image_view(self.swapchain_image).expect("ToneMappingPass: swapchain_image not found");
+
+        self.tone_mapping_pass.draw(
+            ctx.cmd,
+            self.render_context,
+            hdr_input,
+            swapchain_view.handle(),
+            self.swapchain_extent,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_srgb_formats_skip_manual_gamma() {
+        assert!(ToneMappingPass::is_srgb_format(vk::Format::B8G8R8A8_SRGB));
+        assert!(ToneMappingPass::is_srgb_format(vk::Format::R8G8B8A8_SRGB));
+        assert!(!ToneMappingPass::is_srgb_format(vk::Format::B8G8R8A8_UNORM));
+        assert!(!ToneMappingPass::is_srgb_format(vk::Format::R16G16B16A16_SFLOAT));
+    }
+}
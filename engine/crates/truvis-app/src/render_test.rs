@@ -0,0 +1,427 @@
+//! 渲染正确性的参考图对比测试
+//!
+//! 以 headless 方式（不创建窗口与 swapchain）渲染固定场景、固定相机的若干帧，
+//! 读回最后一帧 tone mapping 之后的结果，与存储的参考图（PNG）逐像素比对。
+//!
+//! 为了让结果可以复现：
+//! - 使用固定的帧间隔（[`RenderTestSettings::fixed_delta_time_s`]），动画与 `time_ms` 与机器快慢无关
+//! - 每次测试都重新创建 [`Renderer`]，帧号从同一个初始值开始，光追的随机种子因此也是确定的
+//! - 渲染之前等待所有纹理加载完成
+//!
+//! 参考图缺失时，本次的渲染结果会被写入参考图路径作为新的参考图，测试通过并输出警告；
+//! 需要更新已有的参考图时，设置环境变量 `TRUVIS_UPDATE_REFERENCE=1` 运行测试。
+//!
+//! 比对失败时，本次渲染结果与差异图会输出到 `target/render-tests/` 下。
+//!
+//! ```ignore
+//! assert_render_matches(
+//!     |renderer| build_scene(renderer),
+//!     &camera,
+//!     TruvisPath::resources_path("render-tests/cube.png"),
+//!     RenderTolerance::default(),
+//! );
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use ash::vk;
+use truvis_crate_tools::resource::TruvisPath;
+use truvis_gfx::gfx::Gfx;
+use truvis_render_interface::pipeline_settings::DefaultRendererSettings;
+use truvis_renderer::platform::camera::Camera;
+use truvis_renderer::renderer::Renderer;
+
+use crate::outer_app::base::OuterApp;
+use crate::render_pipeline::rt_render_graph::RtPipeline;
+
+/// `Gfx` 是进程内的单例，而测试默认是多线程并行执行的，需要串行化
+static RENDER_TEST_LOCK: Mutex<()> = Mutex::new(());
+
+/// 设置该环境变量后，将渲染结果写入参考图而不进行比对
+const UPDATE_REFERENCE_ENV: &str = "TRUVIS_UPDATE_REFERENCE";
+
+/// headless 渲染的参数
+#[derive(Debug, Clone, Copy)]
+pub struct RenderTestSettings {
+    /// 渲染分辨率；相机的 `asp` 需要由调用者设置为与之一致
+    pub extent: vk::Extent2D,
+    /// 渲染的帧数，累积的帧数越多噪声越小
+    pub frame_cnt: u32,
+    /// 固定的帧间隔（秒）
+    pub fixed_delta_time_s: f32,
+    /// 等待纹理加载完成的最长时间（秒）
+    pub asset_timeout_s: f32,
+}
+impl Default for RenderTestSettings {
+    fn default() -> Self {
+        Self {
+            extent: vk::Extent2D {
+                width: 256,
+                height: 256,
+            },
+            frame_cnt: 16,
+            fixed_delta_time_s: 1.0 / 60.0,
+            asset_timeout_s: 30.0,
+        }
+    }
+}
+
+/// 比对的容差
+#[derive(Debug, Clone, Copy)]
+pub struct RenderTolerance {
+    /// 单个通道允许的最大差值（0-255），超过则认为该像素不匹配
+    pub max_channel_diff: u8,
+    /// 允许不匹配的像素占比（0-1）
+    pub max_mismatch_ratio: f32,
+}
+impl Default for RenderTolerance {
+    fn default() -> Self {
+        Self {
+            max_channel_diff: 2,
+            max_mismatch_ratio: 0.001,
+        }
+    }
+}
+
+/// 比对的结果
+#[derive(Debug, Clone)]
+pub struct ImageDiff {
+    pub mismatch_cnt: usize,
+    pub pixel_cnt: usize,
+    pub max_channel_diff: u8,
+    /// 差异图：不匹配的像素为红色，其余像素为参考图变暗后的灰度
+    pub diff_image: image::RgbaImage,
+}
+impl ImageDiff {
+    #[inline]
+    pub fn mismatch_ratio(&self) -> f32 {
+        self.mismatch_cnt as f32 / self.pixel_cnt.max(1) as f32
+    }
+
+    #[inline]
+    pub fn within(&self, tolerance: &RenderTolerance) -> bool {
+        self.mismatch_ratio() <= tolerance.max_mismatch_ratio
+    }
+}
+
+/// 渲染场景并与参考图比对，差异超过容差时 panic
+///
+/// # 参数
+/// - `scene`: 在 [`Renderer`] 创建之后调用，向 `scene_manager` 中注册场景
+/// - `camera`: 渲染使用的相机
+/// - `reference_path`: 参考图（PNG）路径
+/// - `tolerance`: 比对的容差
+pub fn assert_render_matches(
+    scene: impl FnOnce(&mut Renderer),
+    camera: &Camera,
+    reference_path: impl AsRef<Path>,
+    tolerance: RenderTolerance,
+) {
+    assert_render_matches_with(scene, camera, reference_path, tolerance, &RenderTestSettings::default());
+}
+
+/// 同 [`assert_render_matches`]，可以指定 headless 渲染的参数
+pub fn assert_render_matches_with(
+    scene: impl FnOnce(&mut Renderer),
+    camera: &Camera,
+    reference_path: impl AsRef<Path>,
+    tolerance: RenderTolerance,
+    settings: &RenderTestSettings,
+) {
+    let reference_path = reference_path.as_ref();
+    let actual = render_headless(scene, camera, settings);
+
+    if std::env::var_os(UPDATE_REFERENCE_ENV).is_some_and(|value| value != "0") {
+        save_reference(&actual, reference_path);
+        log::info!("reference image updated: {}", reference_path.display());
+        return;
+    }
+    // 第一次运行时还没有参考图，以本次的渲染结果作为参考图
+    if !reference_path.is_file() {
+        save_reference(&actual, reference_path);
+        log::warn!(
+            "reference image not found, created from the current output: {}; check it before committing",
+            reference_path.display()
+        );
+        return;
+    }
+
+    let output_dir = output_dir();
+    std::fs::create_dir_all(&output_dir).unwrap();
+    let stem = reference_path.file_stem().unwrap().to_string_lossy().to_string();
+    let actual_path = output_dir.join(format!("{stem}.actual.png"));
+    actual.save(&actual_path).unwrap();
+
+    let reference = image::open(reference_path)
+        .unwrap_or_else(|e| panic!("failed to open reference image {}: {e}", reference_path.display()))
+        .to_rgba8();
+
+    assert_eq!(
+        reference.dimensions(),
+        actual.dimensions(),
+        "reference image size mismatch: {}",
+        reference_path.display()
+    );
+
+    let diff = compare_images(&reference, &actual, &tolerance);
+    if !diff.within(&tolerance) {
+        let diff_path = output_dir.join(format!("{stem}.diff.png"));
+        diff.diff_image.save(&diff_path).unwrap();
+        panic!(
+            "render mismatch: {}\n{} / {} pixels differ ({:.4}% > {:.4}%), max channel diff {}\nactual: {}\ndiff: {}",
+            reference_path.display(),
+            diff.mismatch_cnt,
+            diff.pixel_cnt,
+            diff.mismatch_ratio() * 100.0,
+            tolerance.max_mismatch_ratio * 100.0,
+            diff.max_channel_diff,
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+/// 不创建窗口渲染若干帧，返回最后一帧 render target 的内容（RGBA8）
+///
+/// 内部会独占地初始化并销毁 [`Gfx`]，因此不能在已经初始化了 [`Gfx`] 的进程中调用
+pub fn render_headless(
+    scene: impl FnOnce(&mut Renderer),
+    camera: &Camera,
+    settings: &RenderTestSettings,
+) -> image::RgbaImage {
+    let _guard = RENDER_TEST_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    // 没有 surface 也需要开启该扩展，否则 device 上的 swapchain 扩展不合法
+    let mut renderer = Renderer::new(vec![ash::khr::surface::NAME], DefaultRendererSettings::DEFAULT_FRAMES_IN_FLIGHT);
+    renderer.timer.set_fixed_delta_time(Some(std::time::Duration::from_secs_f32(settings.fixed_delta_time_s)));
+    if renderer.render_context.frame_settings.frame_extent != settings.extent {
+        renderer.resize_frame_buffer(settings.extent);
+    }
+
+    scene(&mut renderer);
+    wait_assets(&mut renderer, settings.asset_timeout_s);
+
+    let rt_pipeline = RtPipeline::new_with_present_format(
+        &renderer.render_context.global_descriptor_sets,
+        vk::Format::B8G8R8A8_UNORM,
+        &mut renderer.cmd_allocator,
+    );
+    renderer.render_context.taa_resolve_available = true;
+
+    let mut result = None;
+    for frame_idx in 0..settings.frame_cnt.max(1) {
+        renderer.begin_frame();
+        renderer.before_render(camera);
+        rt_pipeline.render_offscreen(&renderer.render_context, renderer.fif_timeline.semaphore());
+
+        if frame_idx + 1 == settings.frame_cnt.max(1) {
+            result = Some(read_back_render_target(&renderer));
+        }
+        renderer.end_frame();
+    }
+
+    Gfx::get().wait_idel();
+    drop(rt_pipeline);
+    renderer.destroy();
+    Gfx::destroy();
+
+    result.unwrap()
+}
+
+/// 不创建窗口与 swapchain，驱动一个 [`OuterApp`] 渲染若干帧，返回每一帧 render target 的内容（RGBA8）
+///
+/// 每帧依次调用 [`OuterApp::update`] 与 [`OuterApp::draw_offscreen`]，不会调用任何与窗口、GUI 相关的钩子。
+/// 相机的 `asp` 会被设置为与 `settings.extent` 一致，之后可以在 [`OuterApp::init`] 中修改。
+///
+/// 与 [`render_headless`] 一样会独占地初始化并销毁 [`Gfx`]
+///
+/// 应用不支持 headless 渲染（[`OuterApp::supports_offscreen`] 返回 false）时，不初始化 [`Gfx`]，直接返回 Err
+pub fn run_headless(
+    mut outer_app: Box<dyn OuterApp>,
+    settings: &RenderTestSettings,
+) -> Result<Vec<image::RgbaImage>, String> {
+    // 不支持的应用可能在 init 中访问 swapchain，因此需要在创建 Renderer 之前检查
+    if !outer_app.supports_offscreen() {
+        return Err("headless rendering is not supported by this app".to_string());
+    }
+
+    let _guard = RENDER_TEST_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    // 没有 surface 也需要开启该扩展，否则 device 上的 swapchain 扩展不合法
+    let mut renderer = Renderer::new(vec![ash::khr::surface::NAME], DefaultRendererSettings::DEFAULT_FRAMES_IN_FLIGHT);
+    renderer.timer.set_fixed_delta_time(Some(std::time::Duration::from_secs_f32(settings.fixed_delta_time_s)));
+    if renderer.render_context.frame_settings.frame_extent != settings.extent {
+        renderer.resize_frame_buffer(settings.extent);
+    }
+
+    let mut camera = Camera::default();
+    camera.set_aspect_ratio(settings.extent.width as f32 / settings.extent.height.max(1) as f32);
+    outer_app.init(&mut renderer, &mut camera);
+    wait_assets(&mut renderer, settings.asset_timeout_s);
+
+    let mut frames = Vec::with_capacity(settings.frame_cnt as usize);
+    for _ in 0..settings.frame_cnt {
+        renderer.begin_frame();
+        outer_app.update(&mut renderer);
+        renderer.before_render(&camera);
+        outer_app.draw_offscreen(&renderer, renderer.fif_timeline.semaphore());
+
+        frames.push(read_back_render_target(&renderer));
+        renderer.end_frame();
+    }
+
+    Gfx::get().wait_idel();
+    // outer app 持有的 GPU 资源需要在 Gfx 销毁之前释放
+    outer_app.destroy(&mut renderer);
+    drop(outer_app);
+    renderer.destroy();
+    Gfx::destroy();
+
+    Ok(frames)
+}
+
+/// 逐像素比对两张同样大小的图片
+pub fn compare_images(
+    reference: &image::RgbaImage,
+    actual: &image::RgbaImage,
+    tolerance: &RenderTolerance,
+) -> ImageDiff {
+    assert_eq!(reference.dimensions(), actual.dimensions());
+
+    let mut diff_image = image::RgbaImage::new(reference.width(), reference.height());
+    let mut mismatch_cnt = 0;
+    let mut max_channel_diff = 0;
+    for ((reference_pixel, actual_pixel), diff_pixel) in
+        reference.pixels().zip(actual.pixels()).zip(diff_image.pixels_mut())
+    {
+        let pixel_diff =
+            reference_pixel.0.iter().zip(actual_pixel.0.iter()).map(|(a, b)| a.abs_diff(*b)).max().unwrap();
+        max_channel_diff = max_channel_diff.max(pixel_diff);
+
+        *diff_pixel = if pixel_diff > tolerance.max_channel_diff {
+            mismatch_cnt += 1;
+            image::Rgba([255, 0, 0, 255])
+        } else {
+            let [r, g, b, _] = reference_pixel.0;
+            let luma = ((r as u32 * 3 + g as u32 * 6 + b as u32) / 10 / 3) as u8;
+            image::Rgba([luma, luma, luma, 255])
+        };
+    }
+
+    ImageDiff {
+        mismatch_cnt,
+        pixel_cnt: (reference.width() * reference.height()) as usize,
+        max_channel_diff,
+        diff_image,
+    }
+}
+
+// tools
+fn output_dir() -> PathBuf {
+    TruvisPath::target_path().join("render-tests")
+}
+
+fn save_reference(actual: &image::RgbaImage, reference_path: &Path) {
+    if let Some(parent) = reference_path.parent() {
+        std::fs::create_dir_all(parent).unwrap();
+    }
+    actual.save(reference_path).unwrap();
+}
+
+/// 驱动纹理的异步加载，直到全部完成或超时
+fn wait_assets(renderer: &mut Renderer, timeout_s: f32) {
+    let start = std::time::Instant::now();
+    let render_context = &mut renderer.render_context;
+    while render_context.asset_hub.has_pending_loads() {
+        if start.elapsed().as_secs_f32() > timeout_s {
+            log::warn!("render test: textures still loading after {timeout_s}s, rendering with fallback textures");
+            break;
+        }
+        render_context.asset_hub.update(&mut render_context.gfx_resource_manager, &mut render_context.bindless_manager);
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+}
+
+/// 读回当前帧的 render target（tone mapping 之后的 RGBA32F），转换为 RGBA8
+fn read_back_render_target(renderer: &Renderer) -> image::RgbaImage {
+    let render_context = &renderer.render_context;
+    let fif_buffers = &render_context.fif_buffers;
+    debug_assert_eq!(fif_buffers.render_target_format(), vk::Format::R32G32B32A32_SFLOAT);
+
+    // 等待当前帧的渲染完成
+    Gfx::get().wait_idel();
+
+    let (image_handle, _) = fif_buffers.render_target_handle(renderer.frame_label());
+    let image = render_context.gfx_resource_manager.get_image(image_handle).unwrap();
+    // compute subgraph 结束时 render target 被导出为 fragment shader 可读的状态
+    let data = image.read_back(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
+
+    let pixels = data
+        .chunks_exact(4)
+        .map(|bytes| {
+            let value = f32::from_le_bytes(bytes.try_into().unwrap());
+            (value.clamp(0.0, 1.0) * 255.0).round() as u8
+        })
+        .collect::<Vec<_>>();
+    image::RgbaImage::from_raw(image.width(), image.height(), pixels).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid_image(width: u32, height: u32, value: u8) -> image::RgbaImage {
+        image::RgbaImage::from_pixel(width, height, image::Rgba([value, value, value, 255]))
+    }
+
+    #[test]
+    fn test_compare_identical_images() {
+        let reference = solid_image(4, 4, 128);
+        let diff = compare_images(&reference, &reference.clone(), &RenderTolerance::default());
+        assert_eq!(diff.mismatch_cnt, 0);
+        assert_eq!(diff.pixel_cnt, 16);
+        assert_eq!(diff.max_channel_diff, 0);
+        assert!(diff.within(&RenderTolerance::default()));
+    }
+
+    #[test]
+    fn test_compare_images_within_channel_tolerance() {
+        let tolerance = RenderTolerance {
+            max_channel_diff: 2,
+            max_mismatch_ratio: 0.0,
+        };
+        let reference = solid_image(4, 4, 128);
+        let actual = solid_image(4, 4, 130);
+        let diff = compare_images(&reference, &actual, &tolerance);
+        assert_eq!(diff.mismatch_cnt, 0);
+        assert_eq!(diff.max_channel_diff, 2);
+        assert!(diff.within(&tolerance));
+    }
+
+    #[test]
+    fn test_compare_images_mismatch() {
+        let tolerance = RenderTolerance {
+            max_channel_diff: 2,
+            max_mismatch_ratio: 0.1,
+        };
+        let reference = solid_image(4, 4, 128);
+        let mut actual = reference.clone();
+        actual.put_pixel(1, 2, image::Rgba([128, 200, 128, 255]));
+        actual.put_pixel(3, 0, image::Rgba([0, 128, 128, 255]));
+
+        let diff = compare_images(&reference, &actual, &tolerance);
+        assert_eq!(diff.mismatch_cnt, 2);
+        assert_eq!(diff.max_channel_diff, 128);
+        // 2 / 16 超过了 10% 的容差
+        assert!(!diff.within(&tolerance));
+        assert!(diff.within(&RenderTolerance {
+            max_mismatch_ratio: 0.125,
+            ..tolerance
+        }));
+
+        // 不匹配的像素在差异图中标为红色
+        assert_eq!(*diff.diff_image.get_pixel(1, 2), image::Rgba([255, 0, 0, 255]));
+        assert_ne!(*diff.diff_image.get_pixel(0, 0), image::Rgba([255, 0, 0, 255]));
+    }
+}
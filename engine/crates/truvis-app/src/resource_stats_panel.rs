@@ -0,0 +1,110 @@
+use std::collections::VecDeque;
+
+use truvis_gfx::gfx::Gfx;
+use truvis_gfx::resources::resource_stats::GfxResourceKind;
+use truvis_render_graph::render_context::RenderContext;
+use truvis_render_interface::cmd_allocator::CmdAllocator;
+
+/// 单个显存堆的占用情况
+struct HeapBudget {
+    heap_index: u32,
+    device_local: bool,
+    usage_bytes: u64,
+    budget_bytes: u64,
+}
+
+/// 显存与资源统计面板
+///
+/// - 显存占用来自 VMA 的 heap budget
+/// - 资源数量来自 `Gfx::resource_stats`，在资源创建、销毁时更新
+/// - 当前帧的 command buffer 数量来自 [`CmdAllocator`]，与所有帧加起来的活跃数量不同
+/// - 持续上涨的曲线或者资源数量通常意味着泄漏
+#[derive(Default)]
+pub struct ResourceStatsPanel {
+    /// 最近一次查询的显存堆占用
+    heaps: Vec<HeapBudget>,
+    /// 最近若干帧 GPU 本地显存的占用（MB），用于绘制曲线
+    vram_history: VecDeque<f32>,
+}
+// update
+impl ResourceStatsPanel {
+    /// 曲线保留的帧数
+    const HISTORY_LEN: usize = 300;
+
+    /// 每帧调用一次，面板折叠时同样需要调用，保证曲线是连续的
+    pub fn update(&mut self) {
+        self.heaps = Self::heap_budgets();
+        let device_local_usage: u64 =
+            self.heaps.iter().filter(|heap| heap.device_local).map(|heap| heap.usage_bytes).sum();
+        if self.vram_history.len() == Self::HISTORY_LEN {
+            self.vram_history.pop_front();
+        }
+        self.vram_history.push_back((device_local_usage as f64 / Self::MB) as f32);
+    }
+}
+// tools
+impl ResourceStatsPanel {
+    const MB: f64 = 1024.0 * 1024.0;
+
+    pub fn draw_ui(&self, ui: &imgui::Ui, render_context: &RenderContext, cmd_allocator: &CmdAllocator) {
+        ui.text("VRAM");
+        for heap in &self.heaps {
+            let fraction = if heap.budget_bytes > 0 { heap.usage_bytes as f32 / heap.budget_bytes as f32 } else { 0.0 };
+            imgui::ProgressBar::new(fraction)
+                .overlay_text(format!(
+                    "heap {}{}: {:.1} / {:.1} MB",
+                    heap.heap_index,
+                    if heap.device_local { " (device)" } else { "" },
+                    heap.usage_bytes as f64 / Self::MB,
+                    heap.budget_bytes as f64 / Self::MB
+                ))
+                .build(ui);
+        }
+
+        let history = self.vram_history.iter().copied().collect::<Vec<_>>();
+        let max_usage = history.iter().copied().fold(0.0, f32::max);
+        ui.plot_lines("##vram_history", &history)
+            .overlay_text(format!("device local: {:.1} MB", history.last().copied().unwrap_or_default()))
+            .scale_min(0.0)
+            .scale_max(max_usage * 1.2 + 1.0)
+            .graph_size([0.0, 60.0])
+            .build();
+
+        ui.separator();
+        ui.text("Live Resources");
+        let resource_stats = Gfx::get().resource_stats();
+        for kind in GfxResourceKind::ALL {
+            ui.text(format!("{}: {}", kind.name(), resource_stats.live_count(kind)));
+        }
+        let frame_label = render_context.frame_counter.frame_label();
+        ui.text(format!("Command Buffers (this frame): {}", cmd_allocator.frame_command_buffer_count(frame_label)));
+
+        ui.separator();
+        ui.text("Bindless");
+        let bindless_manager = &render_context.bindless_manager;
+        ui.text(format!("SRV: {} / {}", bindless_manager.used_srv_count(), bindless_manager.srv_capacity()));
+        ui.text(format!("UAV: {} / {}", bindless_manager.used_uav_count(), bindless_manager.uav_capacity()));
+    }
+
+    /// 有预算的显存堆，查询失败时返回空
+    fn heap_budgets() -> Vec<HeapBudget> {
+        let physical_device = Gfx::get().physical_device();
+        match Gfx::get().allocator().get_heap_budgets() {
+            Ok(budgets) => budgets
+                .iter()
+                .enumerate()
+                .filter(|(_, budget)| budget.budget > 0)
+                .map(|(heap_index, budget)| HeapBudget {
+                    heap_index: heap_index as u32,
+                    device_local: physical_device.is_device_local_heap(heap_index as u32),
+                    usage_bytes: budget.usage,
+                    budget_bytes: budget.budget,
+                })
+                .collect(),
+            Err(e) => {
+                log::warn!("failed to query vma heap budgets: {:?}", e);
+                Vec::new()
+            }
+        }
+    }
+}
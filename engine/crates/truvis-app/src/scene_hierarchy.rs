@@ -0,0 +1,130 @@
+use truvis_render_graph::render_context::RenderContext;
+use truvis_scene::guid_new_type::{InstanceHandle, MeshHandle};
+
+/// 场景层级面板，按 mesh 分组列出所有 instance
+///
+/// 选中项保存在 `RenderContext::selected_instance` 中，与鼠标拾取共享；
+/// 修改 transform 通过 `SceneManager::set_instance_transform` 写回场景，只会标记该 instance 为脏
+#[derive(Default)]
+pub struct SceneHierarchy {
+    /// instance 列表的过滤条件，按 instance 或 mesh 的名字匹配，忽略大小写
+    filter: String,
+    /// 上一帧的选中项，选中项变化时（例如鼠标拾取）展开其所在的分组
+    last_selected: Option<InstanceHandle>,
+}
+// tools
+impl SceneHierarchy {
+    /// instance 列表的高度，instance 很多时在列表内部滚动
+    const LIST_HEIGHT: f32 = 200.0;
+
+    pub fn draw_ui(&mut self, ui: &imgui::Ui, render_context: &mut RenderContext) {
+        // 选中的 instance 可能已经被移除
+        let selected = render_context
+            .selected_instance
+            .filter(|&handle| render_context.scene_manager.get_instance(handle).is_some());
+        let selection_changed = selected != self.last_selected;
+        self.last_selected = selected;
+
+        ui.input_text("Filter", &mut self.filter).hint("name").build();
+        if let Some(clicked) = self.draw_tree(ui, render_context, selected, selection_changed) {
+            render_context.selected_instance = Some(clicked);
+            self.last_selected = Some(clicked);
+        }
+
+        let Some(handle) = self.last_selected else {
+            ui.text("No instance selected");
+            return;
+        };
+        ui.separator();
+        Self::draw_transform(ui, render_context, handle);
+    }
+
+    /// 返回本帧点击的 instance
+    fn draw_tree(
+        &self,
+        ui: &imgui::Ui,
+        render_context: &RenderContext,
+        selected: Option<InstanceHandle>,
+        selection_changed: bool,
+    ) -> Option<InstanceHandle> {
+        let scene_manager = &render_context.scene_manager;
+        let filter = self.filter.to_lowercase();
+
+        // 按 mesh 分组，分组的顺序为 mesh 第一次出现的顺序
+        let mut groups: Vec<(MeshHandle, Vec<(usize, InstanceHandle)>)> = Vec::new();
+        for (idx, (handle, instance)) in scene_manager.instance_map().iter().enumerate() {
+            match groups.iter_mut().find(|(mesh, _)| *mesh == instance.mesh) {
+                Some((_, instances)) => instances.push((idx, handle)),
+                None => groups.push((instance.mesh, vec![(idx, handle)])),
+            }
+        }
+
+        let mut clicked = None;
+        ui.child_window("##instance_tree").size([0.0, Self::LIST_HEIGHT]).border(true).build(|| {
+            for (mesh, instances) in &groups {
+                let mesh_name = scene_manager.get_mesh(*mesh).map_or("", |mesh| mesh.name.as_str());
+                let mesh_matched = filter.is_empty() || mesh_name.to_lowercase().contains(&filter);
+                let labels = instances
+                    .iter()
+                    .map(|&(idx, handle)| (handle, Self::label(idx, &scene_manager.instance_map()[handle].name)))
+                    .filter(|(_, label)| mesh_matched || label.to_lowercase().contains(&filter))
+                    .collect::<Vec<_>>();
+                if labels.is_empty() {
+                    continue;
+                }
+
+                let contains_selected = labels.iter().any(|&(handle, _)| Some(handle) == selected);
+                let mut tree_node = ui.tree_node_config(format!("{} ({})##{:?}", mesh_name, labels.len(), mesh));
+                if selection_changed && contains_selected {
+                    tree_node = tree_node.opened(true, imgui::Condition::Always);
+                }
+                tree_node.build(|| {
+                    for (handle, label) in &labels {
+                        // 同名的 instance 使用 handle 区分 imgui id
+                        let id = format!("{}##{:?}", label, handle);
+                        if ui.selectable_config(id).selected(Some(*handle) == selected).build() {
+                            clicked = Some(*handle);
+                        }
+                    }
+                });
+            }
+        });
+        ui.text(format!("{} instances", scene_manager.instance_map().len()));
+
+        clicked
+    }
+
+    /// 以 position、欧拉角（度）、scale 的形式编辑 instance 的 transform
+    fn draw_transform(ui: &imgui::Ui, render_context: &mut RenderContext, handle: InstanceHandle) {
+        let instance = &render_context.scene_manager.instance_map()[handle];
+        ui.text(format!("Selected: {} {:?}", instance.name, handle));
+
+        let (scale, rotation, translation) = instance.transform.to_scale_rotation_translation();
+        let (x, y, z) = rotation.to_euler(glam::EulerRot::XYZ);
+        let mut position = translation.to_array();
+        let mut rotation_deg = [x.to_degrees(), y.to_degrees(), z.to_degrees()];
+        let mut scale = scale.to_array();
+
+        let mut changed = false;
+        changed |= imgui::Drag::new("Position").speed(0.01).build_array(ui, &mut position);
+        changed |= imgui::Drag::new("Rotation").speed(0.5).build_array(ui, &mut rotation_deg);
+        changed |= imgui::Drag::new("Scale").speed(0.01).range(0.001, f32::MAX).build_array(ui, &mut scale);
+        if !changed {
+            return;
+        }
+
+        let [x, y, z] = rotation_deg.map(f32::to_radians);
+        let transform = glam::Mat4::from_scale_rotation_translation(
+            glam::Vec3::from_array(scale),
+            glam::Quat::from_euler(glam::EulerRot::XYZ, x, y, z),
+            glam::Vec3::from_array(position),
+        );
+        render_context.scene_manager.set_instance_transform(handle, transform);
+        render_context.accum_data.reset();
+    }
+
+    /// 列表中显示的名字，没有名字的 instance 使用序号
+    fn label(idx: usize, name: &str) -> String {
+        if name.is_empty() { format!("instance #{idx}") } else { name.to_string() }
+    }
+}
@@ -0,0 +1,327 @@
+//! 用户设置的持久化
+//!
+//! 窗口大小、VSync、渲染缩放、相机速度等设置集中在 [`Settings`] 中，各子系统从它读取初值。
+//! 启动时从 [`TruvisPath::user_settings_path`] 加载，退出时保存；
+//! 配置文件缺失或字段缺失时使用默认值，并写回配置文件以补全。
+//!
+//! ```toml
+//! [window]
+//! width = 1200
+//! height = 800
+//!
+//! [render]
+//! present_mode = "mailbox"
+//! frames_in_flight = 3
+//! render_scale = 1.0
+//! target_fps = 60
+//!
+//! [camera]
+//! move_speed = 320.0
+//! mouse_sensitivity = 0.14285715
+//!
+//! [input]
+//! move_forward = ["KeyW"]
+//! capture_frame = ["F12"]
+//!
+//! [camera_bookmarks.1]
+//! position = [0.0, 2.0, 10.0]
+//! euler_yaw_deg = 0.0
+//! euler_pitch_deg = -10.0
+//! projection_mode = { type = "perspective", fov_y_deg = 60.0 }
+//! ```
+//!
+//! [`TruvisPath::user_settings_path`]: truvis_crate_tools::resource::TruvisPath::user_settings_path
+
+use std::path::Path;
+
+use ash::vk;
+use serde::{Deserialize, Serialize};
+use truvis_render_interface::pipeline_settings::DefaultRendererSettings;
+use truvis_ui_edit_macro::UiEdit;
+use truvis_ui_edit_trait::{UiEditField, UiFieldOptions};
+
+use crate::camera_bookmarks::CameraBookmarks;
+use crate::platform::input_map::InputMap;
+
+/// 窗口设置，大小为逻辑像素
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowSettings {
+    pub width: u32,
+    pub height: u32,
+}
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            width: 1200,
+            height: 800,
+        }
+    }
+}
+
+/// 期望的 present mode，surface 不支持时按照 Mailbox → Immediate → Fifo 的顺序退化，Fifo 一定可用
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PresentModePreference {
+    /// 低延迟且没有撕裂
+    #[default]
+    Mailbox,
+    /// 延迟最低，可能出现撕裂
+    Immediate,
+    /// 垂直同步，最省电
+    Fifo,
+}
+impl PresentModePreference {
+    const ALL: [Self; 3] = [Self::Mailbox, Self::Immediate, Self::Fifo];
+
+    /// 按照偏好排列的 present mode，参见 `GfxSwapchain::new`
+    pub fn present_modes(self) -> &'static [vk::PresentModeKHR] {
+        match self {
+            Self::Mailbox => &[
+                vk::PresentModeKHR::MAILBOX,
+                vk::PresentModeKHR::IMMEDIATE,
+                vk::PresentModeKHR::FIFO,
+            ],
+            Self::Immediate => &[
+                vk::PresentModeKHR::IMMEDIATE,
+                vk::PresentModeKHR::MAILBOX,
+                vk::PresentModeKHR::FIFO,
+            ],
+            Self::Fifo => &[vk::PresentModeKHR::FIFO],
+        }
+    }
+}
+impl UiEditField for PresentModePreference {
+    fn edit_field(&mut self, ui: &imgui::Ui, label: &str, _options: &UiFieldOptions) -> bool {
+        let mut idx = Self::ALL.iter().position(|mode| mode == self).unwrap_or_default();
+        let changed = ui.combo_simple_string(label, &mut idx, &["Mailbox", "Immediate", "Fifo"]);
+        if changed {
+            *self = Self::ALL[idx];
+        }
+        changed
+    }
+}
+
+/// 帧率上限，None 表示不限制，参见 `FrameLimiter`
+///
+/// 与 present mode 正交，例如 Mailbox 配合帧率上限可以在没有撕裂的同时降低 GPU 负载。
+/// 配置文件中以 0 表示不限制，否则缺失的字段会被默认值覆盖
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "u32", into = "u32")]
+pub struct FpsLimit(pub Option<u32>);
+impl From<u32> for FpsLimit {
+    fn from(value: u32) -> Self {
+        Self((value > 0).then_some(value))
+    }
+}
+impl From<FpsLimit> for u32 {
+    fn from(value: FpsLimit) -> Self {
+        value.0.unwrap_or(0)
+    }
+}
+impl Default for FpsLimit {
+    fn default() -> Self {
+        Self(Some(60))
+    }
+}
+impl FpsLimit {
+    const MIN_FPS: u32 = 10;
+    const MAX_FPS: u32 = 360;
+    /// 快捷选项
+    const PRESETS: [u32; 4] = [30, 60, 120, 144];
+}
+impl UiEditField for FpsLimit {
+    fn edit_field(&mut self, ui: &imgui::Ui, label: &str, _options: &UiFieldOptions) -> bool {
+        let mut limited = self.0.is_some();
+        let mut changed = ui.checkbox(format!("Limit {}", label), &mut limited);
+        if changed {
+            self.0 = if limited { Self::default().0 } else { None };
+        }
+
+        if let Some(fps) = self.0.as_mut() {
+            changed |= ui.slider(label, Self::MIN_FPS, Self::MAX_FPS, fps);
+            for preset in Self::PRESETS {
+                if ui.small_button(format!("{}##{}", preset, label)) {
+                    *fps = preset;
+                    changed = true;
+                }
+                ui.same_line();
+            }
+            ui.new_line();
+        }
+        changed
+    }
+}
+
+/// 渲染设置
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, UiEdit)]
+#[serde(default)]
+pub struct RenderSettings {
+    pub present_mode: PresentModePreference,
+    /// 同时在 GPU 上执行的帧数，只在启动时生效
+    #[ui(skip)]
+    pub frames_in_flight: u32,
+    /// 渲染分辨率相对于窗口分辨率的缩放，范围为 [`Self::MIN_RENDER_SCALE`, `Self::MAX_RENDER_SCALE`]
+    #[ui(min = Self::MIN_RENDER_SCALE, max = Self::MAX_RENDER_SCALE, format = "%.2f")]
+    pub render_scale: f32,
+    /// 帧率上限，实时生效
+    #[ui(label = "FPS")]
+    pub target_fps: FpsLimit,
+}
+impl RenderSettings {
+    pub const MIN_RENDER_SCALE: f32 = 0.25;
+    pub const MAX_RENDER_SCALE: f32 = 2.0;
+}
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            present_mode: PresentModePreference::default(),
+            frames_in_flight: DefaultRendererSettings::DEFAULT_FRAMES_IN_FLIGHT as u32,
+            render_scale: 1.0,
+            target_fps: FpsLimit::default(),
+        }
+    }
+}
+
+/// 相机控制设置
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, UiEdit)]
+#[serde(default)]
+pub struct CameraSettings {
+    /// 移动速度（单位/秒）
+    #[ui(min = 1.0, max = 2000.0, format = "%.0f")]
+    pub move_speed: f32,
+    /// 鼠标灵敏度（度/像素）
+    #[ui(min = 0.01, max = 1.0, format = "%.3f")]
+    pub mouse_sensitivity: f32,
+}
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            move_speed: 320.0,
+            mouse_sensitivity: 1.0 / 7.0,
+        }
+    }
+}
+
+/// 需要在多次启动之间保留的用户设置
+#[derive(Debug, Clone, Default, Serialize, Deserialize, UiEdit)]
+#[serde(default)]
+pub struct Settings {
+    #[ui(skip)]
+    pub window: WindowSettings,
+    pub render: RenderSettings,
+    pub camera: CameraSettings,
+    /// 按键绑定，在单独的面板中编辑
+    #[ui(skip)]
+    pub input: InputMap,
+    /// 相机视角的书签，在单独的面板中管理
+    #[ui(skip)]
+    pub camera_bookmarks: CameraBookmarks,
+}
+// new & init
+impl Settings {
+    /// 从配置文件加载设置
+    ///
+    /// 文件缺失或字段缺失时使用默认值，并将补全后的设置写回文件；
+    /// 文件无法解析时使用默认值，但不会立即覆盖该文件
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        let settings = match std::fs::read_to_string(path) {
+            Ok(content) => match toml::from_str::<Settings>(&content) {
+                Ok(settings) => settings,
+                Err(e) => {
+                    log::warn!("failed to parse settings {}, use default settings: {}", path.display(), e);
+                    return Self::default();
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                log::info!("settings {} not found, use default settings", path.display());
+                Self::default()
+            }
+            Err(e) => {
+                log::warn!("failed to read settings {}, use default settings: {}", path.display(), e);
+                return Self::default();
+            }
+        };
+
+        // 补全缺失的字段
+        settings.save(path);
+        settings
+    }
+}
+// tools
+impl Settings {
+    /// 将设置写入配置文件，失败时只输出警告
+    pub fn save(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        let content = match toml::to_string_pretty(self) {
+            Ok(content) => content,
+            Err(e) => {
+                log::warn!("failed to serialize settings: {}", e);
+                return;
+            }
+        };
+
+        if let Some(parent) = path.parent()
+            && let Err(e) = std::fs::create_dir_all(parent)
+        {
+            log::warn!("failed to create {}: {}", parent.display(), e);
+            return;
+        }
+        if let Err(e) = std::fs::write(path, content) {
+            log::warn!("failed to save settings {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_settings_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("truvis-settings-test-{}-{}.toml", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_settings_save_load_round_trip() {
+        let path = temp_settings_path("round-trip");
+        let mut settings = Settings::default();
+        settings.window.width = 1920;
+        settings.render.present_mode = PresentModePreference::Immediate;
+        settings.render.render_scale = 0.5;
+        settings.render.target_fps = FpsLimit(None);
+        settings.camera.move_speed = 42.0;
+        settings.save(&path);
+
+        let loaded = Settings::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(toml::to_string(&loaded).unwrap(), toml::to_string(&settings).unwrap());
+    }
+
+    #[test]
+    fn test_settings_load_fills_missing_fields() {
+        let path = temp_settings_path("missing-fields");
+        std::fs::write(&path, "[render]\nrender_scale = 0.75\n").unwrap();
+
+        let loaded = Settings::load(&path);
+        assert_eq!(loaded.render.render_scale, 0.75);
+        assert_eq!(loaded.camera.move_speed, CameraSettings::default().move_speed);
+
+        // 补全之后写回文件
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(content.contains("[camera]"));
+    }
+
+    #[test]
+    fn test_settings_load_keeps_unparsable_file() {
+        let path = temp_settings_path("unparsable");
+        std::fs::write(&path, "[render\n").unwrap();
+
+        let loaded = Settings::load(&path);
+        assert_eq!(loaded.render.render_scale, RenderSettings::default().render_scale);
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(content, "[render\n");
+    }
+}
@@ -0,0 +1,35 @@
+//! 以 headless 方式驱动 [`OuterApp`] 的测试
+//!
+//! 支持 headless 的应用需要 GPU 以及编译好的 shader，默认不执行：
+//! ```text
+//! cargo test -p truvis-app --test headless_app -- --ignored
+//! ```
+
+use truvis_app::outer_app::cornell_app::CornellApp;
+use truvis_app::outer_app::triangle::triangle_app::HelloTriangleApp;
+use truvis_app::render_test::{RenderTestSettings, run_headless};
+
+#[test]
+fn unsupported_app_is_rejected_before_init() {
+    // 在创建 Renderer 之前就会返回，因此不需要 GPU；HelloTriangleApp 在 init 中访问 swapchain
+    let result = run_headless(Box::new(HelloTriangleApp::default()), &RenderTestSettings::default());
+    assert!(result.is_err());
+}
+
+#[test]
+#[ignore = "requires a GPU and compiled shaders"]
+fn supported_app_renders_every_frame() {
+    let settings = RenderTestSettings {
+        frame_cnt: 3,
+        ..Default::default()
+    };
+    let frames = run_headless(Box::new(CornellApp::default()), &settings).unwrap();
+
+    assert_eq!(frames.len(), settings.frame_cnt as usize);
+    for frame in &frames {
+        assert_eq!(frame.dimensions(), (settings.extent.width, settings.extent.height));
+    }
+    // 场景不是一片空白
+    let last = frames.last().unwrap();
+    assert!(last.pixels().any(|pixel| pixel != last.get_pixel(0, 0)));
+}
@@ -0,0 +1,134 @@
+//! 直接光照采样策略的噪声对比
+//!
+//! 在相同的 spp 下分别使用 MIS、只做光源采样、只做 BRDF 采样渲染同一个场景，
+//! 以高 spp 的 MIS 结果作为参考，比较各个策略的 RMSE。
+//! 渲染结果输出到 `target/render-tests/light-sampling-*.png`，便于直接对比噪声。
+//!
+//! 需要 GPU 以及编译好的 shader，默认不执行：
+//! ```text
+//! cargo test -p truvis-app --test light_sampling_noise -- --ignored --nocapture
+//! ```
+
+use truvis_app::render_test::{RenderTestSettings, render_headless};
+use truvis_crate_tools::resource::TruvisPath;
+use truvis_renderer::platform::camera::Camera;
+use truvis_renderer::renderer::Renderer;
+use truvis_scene::components::material::Material;
+use truvis_scene::shapes::add_shape;
+use truvis_scene::shapes::cube::CubeSoA;
+use truvis_scene::shapes::floor::FloorSoA;
+use truvis_shader_binding::truvisl;
+
+/// 对比时每种策略的 spp
+const COMPARE_SPP: u32 = 16;
+/// 参考图的 spp
+const REFERENCE_SPP: u32 = 1024;
+
+/// 光滑的地面上放置一个粗糙的立方体，由一个面光源和一个点光源照亮
+///
+/// 光滑表面上面光源的高光适合 BRDF 采样，粗糙表面上的漫反射适合光源采样
+fn lit_scene(renderer: &mut Renderer, light_sampling: truvisl::rt::LightSamplingMode) {
+    let pipeline_settings = &mut renderer.render_context.pipeline_settings;
+    pipeline_settings.light_sampling = light_sampling;
+    // 只保留路径追踪本身的噪声
+    pipeline_settings.denoise.enabled = false;
+    pipeline_settings.ic_enabled = false;
+
+    let scene_manager = &mut renderer.render_context.scene_manager;
+
+    scene_manager.register_rect_light(truvisl::RectLight {
+        corner: glam::vec3(-1.0, 3.0, -1.0).into(),
+        edge_u: glam::vec3(2.0, 0.0, 0.0).into(),
+        edge_v: glam::vec3(0.0, 0.0, 2.0).into(),
+        radiance: glam::vec3(5.0, 5.0, 5.0).into(),
+
+        _corner_padding: Default::default(),
+        _edge_u_padding: Default::default(),
+        _edge_v_padding: Default::default(),
+        _radiance_padding: Default::default(),
+    });
+    scene_manager.register_point_light(truvisl::PointLight {
+        pos: glam::vec3(-3.0, 2.0, 2.0).into(),
+        color: glam::vec3(4.0, 3.0, 2.0).into(),
+
+        _pos_padding: Default::default(),
+        _color_padding: Default::default(),
+    });
+
+    let shape_material = |roughness: f32| Material {
+        base_color: glam::vec4(0.7, 0.7, 0.7, 1.0),
+        roughness,
+        opaque: 1.0,
+        ..Default::default()
+    };
+    add_shape(
+        scene_manager,
+        "floor",
+        (FloorSoA::create_mesh(), FloorSoA::aabb()),
+        shape_material(0.2),
+        glam::Mat4::from_scale(glam::Vec3::splat(10.0)),
+    );
+    add_shape(
+        scene_manager,
+        "cube",
+        (CubeSoA::create_mesh(), CubeSoA::aabb()),
+        shape_material(0.9),
+        glam::Mat4::from_rotation_translation(glam::Quat::from_rotation_y(0.6), glam::vec3(0.0, 0.5, 0.0)),
+    );
+}
+
+fn render(camera: &Camera, light_sampling: truvisl::rt::LightSamplingMode, spp: u32) -> image::RgbaImage {
+    render_headless(
+        |renderer| lit_scene(renderer, light_sampling),
+        camera,
+        &RenderTestSettings {
+            frame_cnt: spp,
+            ..Default::default()
+        },
+    )
+}
+
+/// 两张图片 RGB 通道的均方根误差（0-255）
+fn rmse(reference: &image::RgbaImage, actual: &image::RgbaImage) -> f32 {
+    let mut sum = 0.0;
+    for (reference_pixel, actual_pixel) in reference.pixels().zip(actual.pixels()) {
+        for channel in 0..3 {
+            sum += (reference_pixel.0[channel] as f32 - actual_pixel.0[channel] as f32).powi(2);
+        }
+    }
+    let cnt = (reference.width() * reference.height() * 3).max(1);
+    (sum / cnt as f32).sqrt()
+}
+
+#[test]
+#[ignore = "requires a GPU and compiled shaders"]
+fn mis_has_lowest_noise_at_fixed_spp() {
+    let camera = Camera {
+        position: glam::vec3(0.0, 2.0, 5.0),
+        euler_pitch_deg: -20.0,
+        ..Default::default()
+    };
+
+    let output_dir = TruvisPath::target_path().join("render-tests");
+    std::fs::create_dir_all(&output_dir).unwrap();
+
+    let reference = render(&camera, truvisl::rt::LightSamplingMode_MIS, REFERENCE_SPP);
+    reference.save(output_dir.join("light-sampling-reference.png")).unwrap();
+
+    let errors = [
+        ("mis", truvisl::rt::LightSamplingMode_MIS),
+        ("light-only", truvisl::rt::LightSamplingMode_LIGHT_ONLY),
+        ("brdf-only", truvisl::rt::LightSamplingMode_BRDF_ONLY),
+    ]
+    .map(|(name, light_sampling)| {
+        let image = render(&camera, light_sampling, COMPARE_SPP);
+        image.save(output_dir.join(format!("light-sampling-{name}.png"))).unwrap();
+        let error = rmse(&reference, &image);
+        println!("{name:>10} @ {COMPARE_SPP} spp: rmse {error:.3}");
+        error
+    });
+
+    let [mis, light_only, brdf_only] = errors;
+    assert!(mis <= light_only, "MIS ({mis:.3}) is noisier than light sampling only ({light_only:.3})");
+    assert!(mis <= brdf_only, "MIS ({mis:.3}) is noisier than BRDF sampling only ({brdf_only:.3})");
+}
@@ -0,0 +1,151 @@
+//! 参考图对比测试
+//!
+//! 需要 GPU 以及编译好的 shader，默认不执行：
+//! ```text
+//! cargo test -p truvis-app --test render_reference -- --ignored
+//! ```
+//! 首次运行时参考图缺失，会以本次的渲染结果生成参考图；渲染效果有意变更时，设置 `TRUVIS_UPDATE_REFERENCE=1` 重新生成参考图
+
+use std::rc::Rc;
+
+use truvis_app::render_test::{
+    RenderTestSettings, RenderTolerance, assert_render_matches, compare_images, render_headless,
+};
+use truvis_crate_tools::resource::TruvisPath;
+use truvis_render_interface::geometry::RtGeometry;
+use truvis_renderer::model_loader::mesh_aabb;
+use truvis_renderer::platform::camera::Camera;
+use truvis_renderer::renderer::Renderer;
+use truvis_scene::aabb::Aabb;
+use truvis_scene::components::instance::Instance;
+use truvis_scene::components::material::Material;
+use truvis_scene::components::mesh::Mesh;
+use truvis_scene::shapes::add_shape;
+use truvis_scene::shapes::cube::CubeSoA;
+use truvis_scene::shapes::floor::FloorSoA;
+use truvis_shader_binding::truvisl;
+
+/// floor 和 cube 的 (几何体, 颜色, 变换)
+fn floor_and_cube_shapes() -> [((RtGeometry, Aabb), glam::Vec4, glam::Mat4); 2] {
+    [
+        (
+            (FloorSoA::create_mesh(), FloorSoA::aabb()),
+            glam::vec4(0.7, 0.7, 0.7, 1.0),
+            glam::Mat4::from_scale(glam::Vec3::splat(10.0)),
+        ),
+        (
+            (CubeSoA::create_mesh(), CubeSoA::aabb()),
+            glam::vec4(0.8, 0.2, 0.2, 1.0),
+            glam::Mat4::from_rotation_translation(glam::Quat::from_rotation_y(0.6), glam::vec3(0.0, 0.5, 0.0)),
+        ),
+    ]
+}
+
+fn register_light(renderer: &mut Renderer) {
+    renderer.render_context.scene_manager.register_point_light(truvisl::PointLight {
+        pos: glam::vec3(2.0, 4.0, 3.0).into(),
+        color: glam::vec3(20.0, 20.0, 20.0).into(),
+
+        _pos_padding: Default::default(),
+        _color_padding: Default::default(),
+    });
+}
+
+fn shape_material(base_color: glam::Vec4) -> Material {
+    Material {
+        base_color,
+        roughness: 0.6,
+        opaque: 1.0,
+        ..Default::default()
+    }
+}
+
+fn floor_and_cube(renderer: &mut Renderer) {
+    register_light(renderer);
+    let scene_manager = &mut renderer.render_context.scene_manager;
+
+    for (name, (shape, base_color, transform)) in ["floor", "cube"].into_iter().zip(floor_and_cube_shapes()) {
+        add_shape(scene_manager, name, shape, shape_material(base_color), transform);
+    }
+}
+
+/// 与 [`floor_and_cube`] 相同的场景，floor 和 cube 作为两个 geometry 放在同一个 Mesh 中，
+/// 各自的变换通过 `geometry_transforms` 在构建 BLAS 时应用，instance 的变换为单位矩阵
+fn floor_and_cube_geometry_transforms(renderer: &mut Renderer) {
+    register_light(renderer);
+    let scene_manager = &mut renderer.render_context.scene_manager;
+
+    let (shapes, (base_colors, transforms)): (Vec<_>, (Vec<_>, Vec<_>)) = floor_and_cube_shapes()
+        .into_iter()
+        .map(|(shape, base_color, transform)| (shape, (base_color, transform)))
+        .unzip();
+    let (geometries, aabbs): (Vec<_>, Vec<_>) =
+        shapes.into_iter().map(|(geometry, aabb)| (Rc::new(geometry), aabb)).unzip();
+
+    let local_aabb = mesh_aabb(&aabbs, Some(&transforms));
+    // floor 被放大了 10 倍，cube 的中心被移动到了 y = 0.5
+    assert!(local_aabb.max.x > 9.0 && local_aabb.min.x < -9.0);
+    assert!(local_aabb.max.y > 0.9);
+
+    let mut mesh = Mesh {
+        geometries,
+        geometry_transforms: Some(transforms),
+        local_aabb,
+        blas: None,
+        dynamic_blas: None,
+        name: "floor-and-cube".to_string(),
+        blas_device_address: None,
+    };
+    mesh.build_blas();
+    let mesh = scene_manager.register_mesh(mesh);
+    let materials =
+        base_colors.into_iter().map(|base_color| scene_manager.register_mat(shape_material(base_color))).collect();
+    scene_manager.register_instance(Instance {
+        mesh,
+        materials,
+        transform: glam::Mat4::IDENTITY,
+        name: String::new(),
+    });
+}
+
+fn floor_and_cube_camera() -> Camera {
+    Camera {
+        position: glam::vec3(0.0, 2.0, 5.0),
+        euler_pitch_deg: -20.0,
+        ..Default::default()
+    }
+}
+
+#[test]
+#[ignore = "requires a GPU and compiled shaders"]
+fn floor_and_cube_matches_reference() {
+    assert_render_matches(
+        floor_and_cube,
+        &floor_and_cube_camera(),
+        TruvisPath::resources_path("render-tests/floor_and_cube.png"),
+        RenderTolerance::default(),
+    );
+}
+
+/// geometry 的变换在 BLAS 构建与着色时都需要生效：位置、法线不对时，渲染结果会和使用 instance 变换的场景不同
+#[test]
+#[ignore = "requires a GPU and compiled shaders"]
+fn geometry_transforms_match_instance_transforms() {
+    let settings = RenderTestSettings::default();
+    let expected = render_headless(floor_and_cube, &floor_and_cube_camera(), &settings);
+    let actual = render_headless(floor_and_cube_geometry_transforms, &floor_and_cube_camera(), &settings);
+
+    // 两次渲染只有 BLAS 的构建方式不同，浮点误差可能让极少数边缘像素命中不同的三角形
+    let tolerance = RenderTolerance {
+        max_channel_diff: 4,
+        max_mismatch_ratio: 0.005,
+    };
+    let diff = compare_images(&expected, &actual, &tolerance);
+    assert!(
+        diff.within(&tolerance),
+        "{} / {} pixels differ, max channel diff {}",
+        diff.mismatch_cnt,
+        diff.pixel_cnt,
+        diff.max_channel_diff
+    );
+}
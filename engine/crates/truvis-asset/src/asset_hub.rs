@@ -7,7 +7,7 @@ use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use truvis_gfx::resources::image::GfxImage;
 use truvis_gfx::resources::image_view::GfxImageViewDesc;
-use truvis_render_interface::bindless_manager::BindlessManager;
+use truvis_render_interface::bindless_manager::{BindlessManager, BindlessSrvHandle};
 use truvis_render_interface::gfx_resource_manager::GfxResourceManager;
 use truvis_shader_binding::truvisl;
 
@@ -19,6 +19,9 @@ use truvis_shader_binding::truvisl;
 /// 2. 管理 IO 线程 (IoWorker) 和 GPU 传输 (TransferManager)。
 /// 3. 提供统一的加载接口 (load_texture) 和访问接口 (get_texture)。
 /// 4. 提供 Fallback 机制 (未加载完成时返回粉色纹理)。
+///
+/// 纹理在 [`Self::update`] 中变为 Ready，材质每帧通过 [`Self::get_texture`] 解析 bindless 索引，
+/// 因此同一帧内要么全部使用占位纹理，要么全部使用真正的纹理，不会出现中间状态。
 pub struct AssetHub {
     // 存储纹理的状态
     texture_states: SlotMap<AssetTextureHandle, LoadStatus>,
@@ -29,6 +32,9 @@ pub struct AssetHub {
     // 路径到句柄的映射，用于去重 (避免重复加载同一文件)
     texture_cache: HashMap<PathBuf, AssetTextureHandle>,
 
+    // 请求加载时指定的色彩空间，上传完成后记录到 AssetTexture 中
+    texture_is_srgb: SecondaryMap<AssetTextureHandle, bool>,
+
     // 默认资源 (1x1 粉色纹理)，用于 Loading/Failed 状态时的占位
     fallback_texture: AssetTexture,
 
@@ -45,6 +51,7 @@ impl AssetHub {
             texture_states: SlotMap::with_key(),
             textures: SecondaryMap::new(),
             texture_cache: HashMap::new(),
+            texture_is_srgb: SecondaryMap::new(),
             fallback_texture,
             asset_loader: AssetLoader::new(),
             upload_manager: AssetUploadManager::new(),
@@ -73,6 +80,7 @@ impl AssetHub {
         AssetTexture {
             image_handle,
             view_handle,
+            srv_handle: bindless_manager.get_shader_srv_handle(view_handle),
             sampler: truvisl::ESamplerType_LinearRepeat,
             is_srgb: false,
             mip_levels: 1,
@@ -82,7 +90,12 @@ impl AssetHub {
 
 // destroy
 impl AssetHub {
+    /// 退出时可能仍有纹理在加载或上传：
+    /// 先等待后台线程结束并丢弃解码结果，再等待正在上传的纹理完成后将其销毁
     pub fn destroy(self, gfx_resource_manager: &mut GfxResourceManager, bindless_manager: &mut BindlessManager) {
+        self.asset_loader.join();
+        self.upload_manager.destroy();
+
         bindless_manager.unregister_srv(self.fallback_texture.view_handle);
         gfx_resource_manager.destroy_image_immediate(self.fallback_texture.image_handle);
     }
@@ -97,28 +110,126 @@ impl AssetHub {
     /// 2. 如果是新请求，分配 Handle，状态设为 Loading。
     /// 3. 发送请求给后台 IO 线程。
     /// 4. 立即返回 Handle。
-    pub fn load_texture(&mut self, path: PathBuf) -> AssetTextureHandle {
+    ///
+    /// `is_srgb` 参见 [`AssetLoadRequest::is_srgb`]：漫反射、自发光等颜色贴图为 true，
+    /// 法线、metallic-roughness、遮蔽等存放数据的贴图为 false
+    pub fn load_texture(&mut self, path: PathBuf, is_srgb: bool) -> AssetTextureHandle {
         let _span = tracy_client::span!("load_texture");
-        if let Some(&handle) = self.texture_cache.get(&path) {
+        if let Some(handle) = self.cached_texture(&path, is_srgb) {
             return handle;
         }
 
         // 分配句柄，初始状态为 Loading
         let handle = self.texture_states.insert(LoadStatus::Loading);
         self.texture_cache.insert(path.clone(), handle);
+        self.texture_is_srgb.insert(handle, is_srgb);
 
         log::info!("Request load texture: {:?}", path);
 
         // 发送 IO 请求到后台线程
-        self.asset_loader.request_load(AssetLoadRequest { path, handle });
+        self.asset_loader.request_load(AssetLoadRequest {
+            path,
+            encoded: None,
+            is_srgb,
+            handle,
+        });
+
+        handle
+    }
+
+    /// 请求加载内存中已编码的纹理，例如模型文件中内嵌的图片
+    ///
+    /// `key` 只用于去重和之后通过路径查询，不需要是真实存在的文件；解码同样在后台线程中进行
+    pub fn load_texture_from_memory(&mut self, key: PathBuf, encoded: Vec<u8>, is_srgb: bool) -> AssetTextureHandle {
+        let _span = tracy_client::span!("load_texture_from_memory");
+        if let Some(handle) = self.cached_texture(&key, is_srgb) {
+            return handle;
+        }
+
+        let handle = self.texture_states.insert(LoadStatus::Loading);
+        self.texture_cache.insert(key.clone(), handle);
+        self.texture_is_srgb.insert(handle, is_srgb);
+
+        log::info!("Request load texture from memory: {:?}, {} bytes", key, encoded.len());
+
+        self.asset_loader.request_load(AssetLoadRequest {
+            path: key,
+            encoded: Some(encoded),
+            is_srgb,
+            handle,
+        });
 
         handle
     }
 
+    /// 已经请求过加载的纹理，同一张图片只会按照第一次请求的色彩空间加载
+    fn cached_texture(&self, path: &Path, is_srgb: bool) -> Option<AssetTextureHandle> {
+        let handle = *self.texture_cache.get(path)?;
+        if self.texture_is_srgb.get(handle) != Some(&is_srgb) {
+            log::warn!("Texture {:?} is used both as color and as data, loaded as is_srgb = {}", path, !is_srgb);
+        }
+        Some(handle)
+    }
+
+    /// 卸载纹理，例如场景切换时释放不再使用的纹理
+    ///
+    /// 纹理的 bindless index 会在 GPU 完成当前帧之后才被复用，image 也会延迟到那时销毁，
+    /// 因此正在 GPU 上执行的帧仍然可以安全地访问该纹理。
+    /// 卸载之后 handle 失效，再次通过同一路径加载会得到新的 handle。
+    pub fn unload_texture(
+        &mut self,
+        handle: AssetTextureHandle,
+        gfx_resource_manager: &mut GfxResourceManager,
+        bindless_manager: &mut BindlessManager,
+        current_frame_id: u64,
+    ) {
+        if self.texture_states.remove(handle).is_none() {
+            log::warn!("Unload invalid texture handle: {:?}", handle);
+            return;
+        }
+        self.texture_cache.retain(|_, cached_handle| *cached_handle != handle);
+        self.texture_is_srgb.remove(handle);
+
+        // 仍在加载或上传中的纹理，会在完成时被丢弃
+        if let Some(texture) = self.textures.remove(handle) {
+            bindless_manager.unregister_srv(texture.view_handle);
+            gfx_resource_manager.destroy_image(texture.image_handle, current_frame_id);
+        }
+        log::info!("Unload texture handle: {:?}", handle);
+    }
+
     pub fn get_status(&self, handle: AssetTextureHandle) -> LoadStatus {
         self.texture_states.get(handle).copied().unwrap_or(LoadStatus::Failed)
     }
 
+    /// 纹理是否已经上传完成，可以用于渲染
+    ///
+    /// 加载失败、已卸载或者无效的 handle 都返回 false
+    pub fn is_loaded(&self, handle: AssetTextureHandle) -> bool {
+        self.get_status(handle) == LoadStatus::Ready
+    }
+
+    /// 纹理的加载进度：(已经结束加载的数量, 请求加载的总数)，加载失败的纹理也算作结束
+    ///
+    /// 用于在 UI 上显示加载进度
+    pub fn load_progress(&self) -> (usize, usize) {
+        let finished = self
+            .texture_states
+            .values()
+            .filter(|status| matches!(status, LoadStatus::Ready | LoadStatus::Failed))
+            .count();
+        (finished, self.texture_states.len())
+    }
+
+    /// 是否还有纹理处于加载或上传中
+    ///
+    /// 需要确定性结果的场合（例如参考图测试）可以据此等待所有纹理就绪后再渲染
+    pub fn has_pending_loads(&self) -> bool {
+        self.texture_states
+            .values()
+            .any(|status| matches!(status, LoadStatus::Unloaded | LoadStatus::Loading | LoadStatus::Uploading))
+    }
+
     /// 获取纹理资源
     ///
     /// 如果资源已 Ready，返回实际纹理。
@@ -128,11 +239,32 @@ impl AssetHub {
         self.textures.get(asset_tex_handle).unwrap_or(&self.fallback_texture)
     }
 
+    /// 获取已经 Ready 的纹理的 bindless handle
+    ///
+    /// handle 过期（generation 与槽位当前的 generation 不一致，例如纹理已经卸载、槽位被新的纹理复用）、
+    /// 纹理尚未加载完成，或者纹理的 bindless 注册已经失效时返回 None，调用者可以据此回退到 fallback 纹理
+    pub fn get_texture_srv_handle(
+        &self,
+        asset_tex_handle: AssetTextureHandle,
+        bindless_manager: &BindlessManager,
+    ) -> Option<BindlessSrvHandle> {
+        let texture = self.textures.get(asset_tex_handle)?;
+        bindless_manager.validate_srv_handle(texture.srv_handle)
+    }
+
     pub fn get_texture_by_path(&self, tex_path: &Path) -> &AssetTexture {
         let asset_tex_handle = self.texture_cache.get(tex_path).unwrap();
         self.get_texture(*asset_tex_handle)
     }
 
+    /// 获取已经 Ready 的纹理，尚未加载完成或者从未请求加载时返回 None
+    ///
+    /// 用于不能使用 fallback 纹理的场合，例如法线贴图：粉色的 fallback 会被解码为严重倾斜的法线
+    pub fn get_ready_texture_by_path(&self, tex_path: &Path) -> Option<&AssetTexture> {
+        let asset_tex_handle = self.texture_cache.get(tex_path)?;
+        self.textures.get(*asset_tex_handle)
+    }
+
     /// 驱动加载流程 (每帧调用)
     ///
     /// 1. 检查 IO 线程是否有完成的任务 -> 提交给 TransferManager。
@@ -177,11 +309,24 @@ impl AssetHub {
         for (tex_handle, image) in finished_uploads {
             log::info!("Upload finished for texture handle: {:?}", tex_handle);
 
+            // 上传期间纹理已经被卸载
+            if !self.texture_states.contains_key(tex_handle) {
+                image.destroy();
+                continue;
+            }
+
             let image_format = image.format();
+            let mip_levels = image.mip_levels();
             let image_handle = gfx_resource_manager.register_image(image);
             let view_handle = gfx_resource_manager.get_or_create_image_view(
                 image_handle,
-                GfxImageViewDesc::new_2d(image_format, vk::ImageAspectFlags::COLOR),
+                GfxImageViewDesc::new(
+                    image_format,
+                    vk::ImageViewType::TYPE_2D,
+                    vk::ImageAspectFlags::COLOR,
+                    (0, mip_levels as u8),
+                    (0, 1),
+                ),
                 "TextureView",
             );
             bindless_manager.register_srv(view_handle);
@@ -189,9 +334,10 @@ impl AssetHub {
             let texture = AssetTexture {
                 image_handle,
                 view_handle,
+                srv_handle: bindless_manager.get_shader_srv_handle(view_handle),
                 sampler: truvisl::ESamplerType_LinearRepeat,
-                is_srgb: true, // TODO 从加载数据中获取
-                mip_levels: 1, // TODO 从加载数据中获取
+                is_srgb: self.texture_is_srgb.get(tex_handle).copied().unwrap_or_default(),
+                mip_levels,
             };
 
             self.textures.insert(tex_handle, texture);
@@ -8,6 +8,11 @@ use std::thread;
 
 pub struct AssetLoadRequest {
     pub path: PathBuf,
+    /// 内存中已编码的图片数据（png、jpg、ktx2 等），为 None 时从 `path` 读取文件
+    pub encoded: Option<Vec<u8>>,
+    /// 颜色贴图为 true，解码为 sRGB 格式，采样时由硬件转换到线性空间；
+    /// 法线、粗糙度等存放数据的贴图为 false。KTX2 使用文件中记录的格式，不受影响
+    pub is_srgb: bool,
     pub handle: AssetTextureHandle,
     // pub params: AssetParams, // Future expansion
 }
@@ -15,11 +20,13 @@ pub struct AssetLoadRequest {
 /// 解码后的原始资产数据 (CPU 端)
 /// 准备好上传到 GPU
 pub struct RawAssetData {
-    pub pixels: Vec<u8>,
+    /// 从 level 0 开始每个 mip level 紧密排列的数据
+    ///
+    /// 只有一层时由上传管理器生成其余的 mip level，否则直接使用文件中预先生成的 mip 链
+    pub levels: Vec<Vec<u8>>,
     pub extent: vk::Extent3D,
     pub format: vk::Format,
     pub handle: AssetTextureHandle,
-    pub mip_levels: u32,
 }
 
 pub enum LoadResult {
@@ -137,37 +144,192 @@ impl Drop for AssetLoader {
 }
 
 /// 实际的加载任务 (运行在 Rayon 线程池中)
-/// 执行: 文件读取 -> 图片解码 -> 格式转换
+/// 执行: 文件读取（内存中的数据则跳过） -> 图片解码 -> 格式转换
+///
+/// `.ktx2` 文件（或者以 KTX2 标识开头的内存数据）保留原始的压缩格式和 mip 链，其余格式统一解码为 RGBA8
 fn load_texture_task(req: AssetLoadRequest) -> LoadResult {
     let _span = tracy_client::span!("load_texture_task");
     log::info!("Loading texture: {:?}", req.path);
 
-    let img_result = image::open(&req.path);
-
-    match img_result {
-        Ok(img) => {
-            let (width, height) = img.dimensions();
-            // 强制转换为 RGBA8
-            let img = img.into_rgba8();
-            let pixels = img.into_raw();
-
-            let raw_data = RawAssetData {
-                pixels,
-                extent: vk::Extent3D {
-                    width,
-                    height,
-                    depth: 1,
-                },
-                format: vk::Format::R8G8B8A8_UNORM, // 目前统一转为 RGBA8
-                handle: req.handle,
-                mip_levels: 1, // 暂时只加载 level 0
-            };
-
-            LoadResult::Success(raw_data)
+    let is_ktx2 = match &req.encoded {
+        Some(encoded) => encoded.starts_with(&KTX2_IDENTIFIER),
+        None => req.path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("ktx2")),
+    };
+    let decode_result = if is_ktx2 {
+        match &req.encoded {
+            Some(encoded) => decode_ktx2(encoded),
+            None => std::fs::read(&req.path).map_err(anyhow::Error::from).and_then(|bytes| decode_ktx2(&bytes)),
         }
+    } else {
+        decode_image(&req)
+    };
+
+    match decode_result {
+        Ok((extent, format, levels)) => LoadResult::Success(RawAssetData {
+            levels,
+            extent,
+            format,
+            handle: req.handle,
+        }),
         Err(e) => {
             log::error!("Failed to load texture {:?}: {}", req.path, e);
             LoadResult::Failure(req.handle, e.to_string())
         }
     }
 }
+
+/// KTX2 文件开头的 12 字节标识：«KTX 20»\r\n\x1A\n
+const KTX2_IDENTIFIER: [u8; 12] = [0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// 通过 image crate 解码 png、jpg 等格式，统一转换为 RGBA8，只有 level 0
+///
+/// 根据 [`AssetLoadRequest::is_srgb`] 选择 sRGB 或者 UNORM 格式
+fn decode_image(req: &AssetLoadRequest) -> anyhow::Result<(vk::Extent3D, vk::Format, Vec<Vec<u8>>)> {
+    let img = match &req.encoded {
+        Some(encoded) => image::load_from_memory(encoded)?,
+        None => image::open(&req.path)?,
+    };
+
+    let (width, height) = img.dimensions();
+    // 强制转换为 RGBA8
+    let pixels = img.into_rgba8().into_raw();
+    let format = if req.is_srgb { vk::Format::R8G8B8A8_SRGB } else { vk::Format::R8G8B8A8_UNORM };
+
+    Ok((
+        vk::Extent3D {
+            width,
+            height,
+            depth: 1,
+        },
+        format,
+        vec![pixels],
+    ))
+}
+
+/// 读取 KTX2 容器中的 2D 纹理，数据按照原始的 vk format（BCn、ASTC 等）逐层拷贝
+///
+/// 设备是否支持该格式由上传时检查。
+/// Basis Universal 以及其他超压缩（supercompression）的数据需要转码，目前不支持
+fn decode_ktx2(bytes: &[u8]) -> anyhow::Result<(vk::Extent3D, vk::Format, Vec<Vec<u8>>)> {
+    let reader = ktx2::Reader::new(bytes).map_err(|e| anyhow::anyhow!("invalid ktx2 data: {:?}", e))?;
+    let header = reader.header();
+
+    if let Some(scheme) = header.supercompression_scheme {
+        anyhow::bail!("supercompressed ktx2 ({:?}) is not supported, transcode it offline", scheme);
+    }
+    let Some(format) = header.format else {
+        anyhow::bail!("ktx2 without vk format (Basis Universal) is not supported, transcode it offline");
+    };
+    if header.pixel_depth > 1 || header.layer_count > 1 || header.face_count != 1 {
+        anyhow::bail!(
+            "only 2d ktx2 textures are supported, got depth {}, layers {}, faces {}",
+            header.pixel_depth,
+            header.layer_count,
+            header.face_count
+        );
+    }
+
+    let levels = reader.levels().map(|level| level.to_vec()).collect();
+
+    Ok((
+        vk::Extent3D {
+            width: header.pixel_width,
+            height: header.pixel_height.max(1),
+            depth: 1,
+        },
+        vk::Format::from_raw(format.0.get() as i32),
+        levels,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 没有 DFD、KVD 与 SGD 的最小 KTX2 文件，`levels` 从 level 0 开始依次存放
+    fn ktx2_bytes(
+        vk_format: vk::Format,
+        extent: [u32; 2],
+        layer_count: u32,
+        face_count: u32,
+        supercompression_scheme: u32,
+        levels: &[&[u8]],
+    ) -> Vec<u8> {
+        const IDENTIFIER: [u8; 12] = [0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A];
+        // identifier + header + index + level index
+        let data_offset = 12 + 9 * 4 + 4 * 4 + 2 * 8 + levels.len() * 3 * 8;
+
+        let mut bytes = IDENTIFIER.to_vec();
+        for value in [
+            vk_format.as_raw() as u32,
+            1,
+            extent[0],
+            extent[1],
+            0,
+            layer_count,
+            face_count,
+            levels.len() as u32,
+            supercompression_scheme,
+        ] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        // dfd、kvd 均为空
+        bytes.extend_from_slice(&[0; 4 * 4]);
+        // sgd 为空
+        bytes.extend_from_slice(&[0; 2 * 8]);
+
+        let mut level_offset = data_offset as u64;
+        for level in levels {
+            let length = level.len() as u64;
+            for value in [level_offset, length, length] {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            level_offset += length;
+        }
+        for level in levels {
+            bytes.extend_from_slice(level);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_decode_ktx2_levels() {
+        // 8x8 的 BC7 有 4 个 block，4x4 只有 1 个
+        let level0 = [1u8; 64];
+        let level1 = [2u8; 16];
+        let bytes = ktx2_bytes(vk::Format::BC7_UNORM_BLOCK, [8, 8], 0, 1, 0, &[&level0, &level1]);
+
+        let (extent, format, levels) = decode_ktx2(&bytes).unwrap();
+        assert_eq!(
+            extent,
+            vk::Extent3D {
+                width: 8,
+                height: 8,
+                depth: 1
+            }
+        );
+        assert_eq!(format, vk::Format::BC7_UNORM_BLOCK);
+        assert_eq!(levels, vec![level0.to_vec(), level1.to_vec()]);
+
+        // 1D 纹理的高度记录为 0
+        let bytes = ktx2_bytes(vk::Format::R8G8B8A8_UNORM, [4, 0], 0, 1, 0, &[&[0; 16]]);
+        let (extent, _, _) = decode_ktx2(&bytes).unwrap();
+        assert_eq!((extent.width, extent.height), (4, 1));
+    }
+
+    #[test]
+    fn test_decode_ktx2_rejects_unsupported() {
+        let level = [0u8; 16];
+        let decode_err = |bytes: Vec<u8>| decode_ktx2(&bytes).unwrap_err().to_string();
+
+        assert!(decode_err(b"not a ktx2 file".to_vec()).contains("invalid ktx2 data"));
+        assert!(decode_err(ktx2_bytes(vk::Format::UNDEFINED, [4, 4], 0, 1, 0, &[&level])).contains("Basis Universal"));
+        // supercompression scheme 2: Zstandard
+        assert!(
+            decode_err(ktx2_bytes(vk::Format::BC7_UNORM_BLOCK, [4, 4], 0, 1, 2, &[&level])).contains("supercompressed")
+        );
+        // cubemap 与数组纹理
+        assert!(decode_err(ktx2_bytes(vk::Format::BC7_UNORM_BLOCK, [4, 4], 0, 6, 0, &[&level])).contains("only 2d"));
+        assert!(decode_err(ktx2_bytes(vk::Format::BC7_UNORM_BLOCK, [4, 4], 2, 1, 0, &[&level])).contains("only 2d"));
+    }
+}
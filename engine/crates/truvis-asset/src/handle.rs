@@ -1,14 +1,49 @@
-use slotmap::new_key_type;
+use slotmap::{Key, KeyData, new_key_type};
+use truvis_render_interface::bindless_manager::BindlessSrvHandle;
 use truvis_render_interface::handles::{GfxImageHandle, GfxImageViewHandle};
 use truvis_shader_binding::truvisl;
 
-new_key_type! { pub struct AssetTextureHandle; }
+/// 纹理的 handle：槽位下标 + generation
+///
+/// 槽位被卸载的纹理释放之后会被新的纹理复用，复用时 generation 自增。
+/// 过期的 handle 不会匹配到新的纹理：[`crate::asset_hub::AssetHub::get_texture`] 返回 fallback 纹理，
+/// [`crate::asset_hub::AssetHub::get_texture_srv_handle`] 返回 None
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct AssetTextureHandle {
+    pub index: u32,
+    pub generation: u32,
+}
+impl Default for AssetTextureHandle {
+    /// 空的 handle，不指向任何纹理
+    fn default() -> Self {
+        KeyData::default().into()
+    }
+}
+impl From<KeyData> for AssetTextureHandle {
+    fn from(key_data: KeyData) -> Self {
+        // ffi 的低 32 位是槽位下标，高 32 位是 version
+        let ffi = key_data.as_ffi();
+        Self {
+            index: ffi as u32,
+            generation: (ffi >> 32) as u32,
+        }
+    }
+}
+// SAFETY: `data` 与 `From<KeyData>` 互为逆运算，Default 返回空的 key
+unsafe impl Key for AssetTextureHandle {
+    fn data(&self) -> KeyData {
+        KeyData::from_ffi(((self.generation as u64) << 32) | self.index as u64)
+    }
+}
+
 new_key_type! { pub struct AssetMeshHandle; }
 
 #[derive(Debug)]
 pub struct AssetTexture {
     pub image_handle: GfxImageHandle,
     pub view_handle: GfxImageViewHandle,
+    /// 注册纹理时得到的 bindless handle，使用前需要通过 [`truvis_render_interface::bindless_manager::BindlessManager::validate_srv_handle`] 检查
+    pub srv_handle: BindlessSrvHandle,
     pub sampler: truvisl::ESamplerType,
     pub is_srgb: bool,
     pub mip_levels: u32,
@@ -32,3 +67,27 @@ pub enum LoadStatus {
     /// 失败状态：文件不存在、格式错误或解码失败
     Failed,
 }
+
+#[cfg(test)]
+mod tests {
+    use slotmap::SlotMap;
+
+    use super::*;
+
+    #[test]
+    fn test_texture_handle_generation() {
+        let mut textures = SlotMap::<AssetTextureHandle, &str>::with_key();
+        let old_handle = textures.insert("old");
+        assert_eq!(AssetTextureHandle::from(old_handle.data()), old_handle);
+        assert!(!old_handle.is_null());
+        assert!(AssetTextureHandle::default().is_null());
+
+        // 复用同一个槽位之后，只有 generation 不同
+        textures.remove(old_handle);
+        let new_handle = textures.insert("new");
+        assert_eq!(new_handle.index, old_handle.index);
+        assert_ne!(new_handle.generation, old_handle.generation);
+        assert_eq!(textures.get(old_handle), None);
+        assert_eq!(textures.get(new_handle), Some(&"new"));
+    }
+}
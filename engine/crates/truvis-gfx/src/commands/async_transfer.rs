@@ -0,0 +1,269 @@
+//! 基于 transfer queue 的异步上传
+//!
+//! 设备有专用的 transfer queue family 时，拷贝在 transfer queue 上执行，不会阻塞 graphics queue。
+//! 此时资源的所有权需要从 transfer family 转移回 graphics family：
+//! 1. transfer queue 上录制拷贝命令和 release barrier
+//! 2. transfer 完成之后，在 graphics queue 上提交只包含 acquire barrier 的命令
+//!
+//! acquire 命令在 CPU 观察到 transfer 完成之后才提交（参见 [`GfxAsyncTransfer::update`]），
+//! 避免 graphics queue 等待 transfer 而卡住渲染。
+//!
+//! 没有专用 transfer queue 时回退到 graphics family 的队列，不需要所有权转移。
+//!
+//! 每次上传返回一个 [`TransferTicket`]，可以在后续帧通过 timeline semaphore 查询是否完成；
+//! 完成之后资源可以直接在 graphics queue 上使用。
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+
+use ash::vk;
+
+use crate::commands::command_buffer::GfxCommandBuffer;
+use crate::commands::command_pool::GfxCommandPool;
+use crate::commands::semaphore::GfxSemaphore;
+use crate::commands::submit_info::GfxSubmitInfo;
+use crate::gfx::Gfx;
+use crate::resources::buffer::GfxBuffer;
+
+/// 一次异步上传的句柄
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferTicket {
+    timeline_value: u64,
+}
+impl TransferTicket {
+    /// 对应的 timeline semaphore 的值，参见 [`GfxAsyncTransfer::timeline_semaphore`]
+    #[inline]
+    pub fn timeline_value(&self) -> u64 {
+        self.timeline_value
+    }
+
+    /// 上传是否已经完成，完成后资源可以在 graphics queue 上使用
+    pub fn is_complete(&self) -> bool {
+        let async_transfer = Gfx::get().async_transfer();
+        async_transfer.update();
+        async_transfer.timeline_semaphore.counter_value() >= self.timeline_value
+    }
+
+    /// 阻塞等待上传完成
+    pub fn wait(&self) {
+        const WAIT_TIMEOUT_NS: u64 = 30 * 1000 * 1000 * 1000; // 30s
+
+        let async_transfer = Gfx::get().async_transfer();
+        // 需要所有权转移时，acquire 命令在 transfer 完成之后才会提交
+        async_transfer.transfer_semaphore.wait_timeline(self.timeline_value, WAIT_TIMEOUT_NS);
+        async_transfer.update();
+        async_transfer.timeline_semaphore.wait_timeline(self.timeline_value, WAIT_TIMEOUT_NS);
+    }
+}
+
+/// 资源所有权从 transfer family 转移到 graphics family 时，barrier 需要填写的 queue family
+#[derive(Debug, Clone, Copy)]
+pub struct GfxQueueOwnershipTransfer {
+    pub src_queue_family_index: u32,
+    pub dst_queue_family_index: u32,
+}
+
+type AcquireFn = Box<dyn FnOnce(&GfxCommandBuffer, GfxQueueOwnershipTransfer)>;
+
+struct PendingTransfer {
+    timeline_value: u64,
+    /// 需要保持存活直到上传完成
+    _stage_buffer: GfxBuffer,
+    transfer_cmd: GfxCommandBuffer,
+
+    /// 尚未提交的 acquire 命令的录制函数
+    acquire: Option<AcquireFn>,
+    acquire_cmd: Option<GfxCommandBuffer>,
+}
+
+pub struct GfxAsyncTransfer {
+    transfer_command_pool: GfxCommandPool,
+    /// 只在有专用 transfer queue 时存在，用于录制 acquire barrier
+    acquire_command_pool: Option<GfxCommandPool>,
+    ownership_transfer: Option<GfxQueueOwnershipTransfer>,
+
+    /// transfer queue 上的拷贝完成时 signal
+    transfer_semaphore: GfxSemaphore,
+    /// 资源可以被 graphics queue 使用时 signal，[`TransferTicket`] 查询的就是它
+    timeline_semaphore: GfxSemaphore,
+    next_timeline_value: Cell<u64>,
+
+    /// 按提交顺序排列
+    pending: RefCell<VecDeque<PendingTransfer>>,
+}
+
+// new & init
+impl GfxAsyncTransfer {
+    pub(crate) fn new() -> Self {
+        let gfx = Gfx::get();
+        let transfer_queue_family = gfx.transfer_queue().queue_family().clone();
+        let gfx_queue_family = gfx.gfx_queue().queue_family().clone();
+
+        let ownership_transfer = (transfer_queue_family.queue_family_index != gfx_queue_family.queue_family_index)
+            .then_some(GfxQueueOwnershipTransfer {
+                src_queue_family_index: transfer_queue_family.queue_family_index,
+                dst_queue_family_index: gfx_queue_family.queue_family_index,
+            });
+        log::info!("async transfer: queue ownership transfer {:?}", ownership_transfer);
+
+        let transfer_command_pool =
+            GfxCommandPool::new(transfer_queue_family, vk::CommandPoolCreateFlags::TRANSIENT, "async-transfer");
+        let acquire_command_pool = ownership_transfer.map(|_| {
+            GfxCommandPool::new(gfx_queue_family, vk::CommandPoolCreateFlags::TRANSIENT, "async-transfer-acquire")
+        });
+
+        Self {
+            transfer_command_pool,
+            acquire_command_pool,
+            ownership_transfer,
+            transfer_semaphore: GfxSemaphore::new_timeline(0, "async-transfer-copy"),
+            timeline_semaphore: GfxSemaphore::new_timeline(0, "async-transfer"),
+            next_timeline_value: Cell::new(1),
+            pending: RefCell::new(VecDeque::new()),
+        }
+    }
+}
+// destroy
+impl GfxAsyncTransfer {
+    /// 需要在 Gfx 单例仍然可用时调用
+    pub(crate) fn destroy(mut self) {
+        let gfx = Gfx::get();
+        gfx.transfer_queue().wait_idle();
+        gfx.gfx_queue().wait_idle();
+
+        for pending in self.pending.take() {
+            self.transfer_command_pool.free_command_buffers(vec![pending.transfer_cmd]);
+            if let (Some(acquire_cmd), Some(acquire_command_pool)) = (pending.acquire_cmd, &self.acquire_command_pool) {
+                acquire_command_pool.free_command_buffers(vec![acquire_cmd]);
+            }
+        }
+
+        self.transfer_command_pool.destroy();
+        if let Some(mut acquire_command_pool) = self.acquire_command_pool.take() {
+            acquire_command_pool.destroy();
+        }
+        self.transfer_semaphore.destroy();
+        self.timeline_semaphore.destroy();
+    }
+}
+// getter
+impl GfxAsyncTransfer {
+    /// 是否需要在 transfer family 和 graphics family 之间转移资源所有权
+    #[inline]
+    pub fn ownership_transfer(&self) -> Option<GfxQueueOwnershipTransfer> {
+        self.ownership_transfer
+    }
+
+    /// 资源可以被 graphics queue 使用时 signal，值为 [`TransferTicket::timeline_value`]
+    ///
+    /// 需要在 GPU 上等待上传完成时，可以在 graphics queue 的提交中等待该 semaphore
+    #[inline]
+    pub fn timeline_semaphore(&self) -> &GfxSemaphore {
+        &self.timeline_semaphore
+    }
+}
+// tools
+impl GfxAsyncTransfer {
+    /// 录制并提交一次异步上传
+    ///
+    /// # Params
+    /// * `stage_buffer` - 上传使用的 stage buffer，会保持存活直到上传完成
+    /// * `record` - 在 transfer queue 上录制从 stage buffer 拷贝的命令；需要所有权转移时还需要录制 release barrier
+    /// * `acquire` - 需要所有权转移时，在 graphics queue 上录制与 release 对应的 acquire barrier
+    pub fn submit(
+        &self,
+        stage_buffer: GfxBuffer,
+        record: impl FnOnce(&GfxCommandBuffer, &GfxBuffer, Option<GfxQueueOwnershipTransfer>),
+        acquire: impl FnOnce(&GfxCommandBuffer, GfxQueueOwnershipTransfer) + 'static,
+        name: &str,
+    ) -> TransferTicket {
+        let _span = tracy_client::span!("GfxAsyncTransfer::submit");
+        self.update();
+
+        let timeline_value = self.next_timeline_value.get();
+        self.next_timeline_value.set(timeline_value + 1);
+
+        let transfer_cmd = GfxCommandBuffer::new(&self.transfer_command_pool, &format!("async-transfer-{name}"));
+        transfer_cmd.begin(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT, name);
+        record(&transfer_cmd, &stage_buffer, self.ownership_transfer);
+        transfer_cmd.end();
+
+        // 不需要所有权转移时，拷贝完成即可使用，两个 semaphore 同时 signal
+        let mut submit_info = GfxSubmitInfo::new(std::slice::from_ref(&transfer_cmd)).signal(
+            &self.transfer_semaphore,
+            vk::PipelineStageFlags2::ALL_COMMANDS,
+            Some(timeline_value),
+        );
+        if self.ownership_transfer.is_none() {
+            submit_info = submit_info.signal(
+                &self.timeline_semaphore,
+                vk::PipelineStageFlags2::ALL_COMMANDS,
+                Some(timeline_value),
+            );
+        }
+        Gfx::get().transfer_queue().submit(vec![submit_info], None);
+
+        self.pending.borrow_mut().push_back(PendingTransfer {
+            timeline_value,
+            _stage_buffer: stage_buffer,
+            transfer_cmd,
+            acquire: self.ownership_transfer.map(|_| Box::new(acquire) as AcquireFn),
+            acquire_cmd: None,
+        });
+
+        TransferTicket { timeline_value }
+    }
+
+    /// 推进上传任务：transfer 已经完成的任务提交 acquire 命令，已经完成的任务回收资源
+    ///
+    /// 需要每帧调用，[`TransferTicket::is_complete`] 也会调用它
+    pub fn update(&self) {
+        let transfer_value = self.transfer_semaphore.counter_value();
+        let complete_value = self.timeline_semaphore.counter_value();
+
+        let mut pending = self.pending.borrow_mut();
+
+        // 1. transfer 已经完成，提交 acquire 命令
+        if let (Some(ownership_transfer), Some(acquire_command_pool)) =
+            (self.ownership_transfer, &self.acquire_command_pool)
+        {
+            for task in pending.iter_mut().filter(|task| task.timeline_value <= transfer_value) {
+                let Some(acquire) = task.acquire.take() else {
+                    continue;
+                };
+
+                let acquire_cmd = GfxCommandBuffer::new(acquire_command_pool, "async-transfer-acquire");
+                acquire_cmd.begin(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT, "async-transfer-acquire");
+                acquire(&acquire_cmd, ownership_transfer);
+                acquire_cmd.end();
+
+                Gfx::get().gfx_queue().submit(
+                    vec![
+                        GfxSubmitInfo::new(std::slice::from_ref(&acquire_cmd))
+                            .wait(
+                                &self.transfer_semaphore,
+                                vk::PipelineStageFlags2::ALL_COMMANDS,
+                                Some(task.timeline_value),
+                            )
+                            .signal(
+                                &self.timeline_semaphore,
+                                vk::PipelineStageFlags2::ALL_COMMANDS,
+                                Some(task.timeline_value),
+                            ),
+                    ],
+                    None,
+                );
+                task.acquire_cmd = Some(acquire_cmd);
+            }
+        }
+
+        // 2. 回收已经完成的任务，任务按顺序完成
+        while pending.front().is_some_and(|task| task.timeline_value <= complete_value) {
+            let task = pending.pop_front().unwrap();
+            self.transfer_command_pool.free_command_buffers(vec![task.transfer_cmd]);
+            if let (Some(acquire_cmd), Some(acquire_command_pool)) = (task.acquire_cmd, &self.acquire_command_pool) {
+                acquire_command_pool.free_command_buffers(vec![acquire_cmd]);
+            }
+        }
+    }
+}
@@ -85,6 +85,15 @@ impl GfxImageBarrier {
         self
     }
 
+    /// builder
+    /// 默认只包含 level 0
+    #[inline]
+    pub fn mip_range(mut self, base_mip_level: u32, level_count: u32) -> Self {
+        self.inner.subresource_range.base_mip_level = base_mip_level;
+        self.inner.subresource_range.level_count = level_count;
+        self
+    }
+
     /// builder
     #[inline]
     pub fn image(mut self, image: vk::Image) -> Self {
@@ -142,6 +151,13 @@ impl GfxBufferBarrier {
         self
     }
 
+    #[inline]
+    pub fn queue_family_transfer(mut self, src_queue_family_index: u32, dst_queue_family_index: u32) -> Self {
+        self.inner.src_queue_family_index = src_queue_family_index;
+        self.inner.dst_queue_family_index = dst_queue_family_index;
+        self
+    }
+
     #[inline]
     pub fn buffer(mut self, buffer: vk::Buffer, offset: vk::DeviceSize, size: vk::DeviceSize) -> Self {
         self.inner.buffer = buffer;
@@ -10,11 +10,16 @@ use crate::{
     commands::{
         barrier::{GfxBufferBarrier, GfxImageBarrier},
         command_pool::GfxCommandPool,
+        resource_state::{GfxBufferState, GfxImageState},
     },
     foundation::debug_messenger::DebugType,
     pipelines::rendering_info::GfxRenderingInfo,
     query::query_pool::GfxQueryPool,
-    resources::buffer::GfxBuffer,
+    raytracing::rt_pipeline::GfxShaderBindingTable,
+    resources::{
+        buffer::GfxBuffer,
+        image::{GfxImage, VulkanFormatUtils},
+    },
 };
 
 /// 命令缓冲封装
@@ -53,6 +58,7 @@ impl GfxCommandBuffer {
             _name: debug_name.to_string(),
         };
         Gfx::get().gfx_device().set_debug_name(&cmd_buffer, debug_name);
+        command_pool.on_command_buffer_allocated();
         cmd_buffer
     }
 }
@@ -107,6 +113,22 @@ impl GfxCommandBuffer {
         unsafe { Gfx::get().gfx_device().cmd_copy_buffer_to_image2(self.vk_handle, copy_info) }
     }
 
+    /// - command type: action
+    /// - 支持的 queue：transfer，graphics，compute
+    #[inline]
+    pub fn cmd_copy_image_to_buffer(&self, copy_info: &vk::CopyImageToBufferInfo2) {
+        unsafe { Gfx::get().gfx_device().cmd_copy_image_to_buffer2(self.vk_handle, copy_info) }
+    }
+
+    /// - command type: action
+    /// - 支持的 queue：graphics
+    ///
+    /// 可以在不同尺寸、不同格式之间复制，会进行缩放和格式转换
+    #[inline]
+    pub fn cmd_blit_image(&self, blit_info: &vk::BlitImageInfo2) {
+        unsafe { Gfx::get().gfx_device().cmd_blit_image2(self.vk_handle, blit_info) }
+    }
+
     /// 将 data 传输到 buffer 中，大小限制：65536Bytes=64KB
     ///
     /// 首先将 data copy 到 cmd buffer 中，然后再 transfer 到指定 buffer
@@ -213,6 +235,114 @@ impl GfxCommandBuffer {
         }
     }
 
+    /// - command type: action
+    /// - supported queue types: graphics
+    ///
+    /// 不使用 index buffer 的 multi-draw indirect，`buffer` 中存放 `draw_count` 个 [`vk::DrawIndirectCommand`]
+    ///
+    /// shader 中可以通过 `SV_DrawIndex`（gl_DrawID）得到当前 draw 的序号。
+    /// 设备不支持 multiDrawIndirect 时逐个发起 indirect 调用，此时 `SV_DrawIndex` 总是 0
+    #[inline]
+    pub fn cmd_draw_indirect(&self, buffer: &GfxBuffer, offset: vk::DeviceSize, draw_count: u32, stride: u32) {
+        let gfx_device = Gfx::get().gfx_device();
+        unsafe {
+            if draw_count <= 1 || Gfx::get().physical_device().support_multi_draw_indirect() {
+                gfx_device.cmd_draw_indirect(self.vk_handle, buffer.vk_buffer(), offset, draw_count, stride);
+            } else {
+                for draw_idx in 0..draw_count {
+                    let draw_offset = offset + draw_idx as vk::DeviceSize * stride as vk::DeviceSize;
+                    gfx_device.cmd_draw_indirect(self.vk_handle, buffer.vk_buffer(), draw_offset, 1, stride);
+                }
+            }
+        }
+    }
+
+    /// - command type: action
+    /// - supported queue types: graphics
+    ///
+    /// 使用 index buffer 的 multi-draw indirect，`buffer` 中存放 `draw_count` 个 [`vk::DrawIndexedIndirectCommand`]，
+    /// 参见 [`crate::resources::special_buffers::indirect_buffer::GfxIndexedIndirectBuffer`]
+    ///
+    /// 设备不支持 multiDrawIndirect 时逐个发起 indirect 调用，此时 `SV_DrawIndex` 总是 0；
+    /// 需要区分不同 draw 时应当使用 `first_instance`
+    #[inline]
+    pub fn cmd_draw_indexed_indirect(&self, buffer: &GfxBuffer, offset: vk::DeviceSize, draw_count: u32, stride: u32) {
+        let gfx_device = Gfx::get().gfx_device();
+        unsafe {
+            if draw_count <= 1 || Gfx::get().physical_device().support_multi_draw_indirect() {
+                gfx_device.cmd_draw_indexed_indirect(self.vk_handle, buffer.vk_buffer(), offset, draw_count, stride);
+            } else {
+                for draw_idx in 0..draw_count {
+                    let draw_offset = offset + draw_idx as vk::DeviceSize * stride as vk::DeviceSize;
+                    gfx_device.cmd_draw_indexed_indirect(self.vk_handle, buffer.vk_buffer(), draw_offset, 1, stride);
+                }
+            }
+        }
+    }
+
+    /// - command type: action
+    /// - supported queue types: graphics
+    ///
+    /// 与 [`Self::cmd_draw_indexed_indirect`] 相同，但 draw 的数量从 `count_buffer` 的 `count_offset` 处读取（一个 u32），
+    /// 并且不超过 `max_draw_count`；用于 GPU 剔除之后由 shader 写入 draw 数量
+    ///
+    /// 设备不支持 drawIndirectCount 时回退为绘制全部 `max_draw_count` 个 command，
+    /// 此时需要保证多余的 command 的 `instance_count` 为 0
+    #[inline]
+    pub fn cmd_draw_indexed_indirect_count(
+        &self,
+        buffer: &GfxBuffer,
+        offset: vk::DeviceSize,
+        count_buffer: &GfxBuffer,
+        count_offset: vk::DeviceSize,
+        max_draw_count: u32,
+        stride: u32,
+    ) {
+        if !Gfx::get().physical_device().support_draw_indirect_count() {
+            self.cmd_draw_indexed_indirect(buffer, offset, max_draw_count, stride);
+            return;
+        }
+        unsafe {
+            Gfx::get().gfx_device().draw_indirect_count.cmd_draw_indexed_indirect_count(
+                self.vk_handle,
+                buffer.vk_buffer(),
+                offset,
+                count_buffer.vk_buffer(),
+                count_offset,
+                max_draw_count,
+                stride,
+            );
+        }
+    }
+
+    /// - command type: action
+    /// - supported queue types: graphics
+    ///
+    /// 使用 mesh shader pipeline 绘制，启动 `group_count` 个 task shader 工作组；
+    /// 没有 task shader 时直接启动 mesh shader 工作组
+    #[inline]
+    pub fn cmd_draw_mesh_tasks(&self, group_count: [u32; 3]) {
+        unsafe {
+            Gfx::get().gfx_device().mesh_shader.cmd_draw_mesh_tasks(
+                self.vk_handle,
+                group_count[0],
+                group_count[1],
+                group_count[2],
+            );
+        }
+    }
+
+    /// - command type: action
+    /// - supported queue types: graphics
+    ///
+    /// 清除当前 rendering 中 attachment 的指定区域，需要在 begin_rendering 和 end_rendering 之间调用
+    #[inline]
+    pub fn cmd_clear_attachments(&self, attachments: &[vk::ClearAttachment], rects: &[vk::ClearRect]) {
+        unsafe {
+            Gfx::get().gfx_device().cmd_clear_attachments(self.vk_handle, attachments, rects);
+        }
+    }
+
     /// - command type: state
     /// - supported queue types: graphics, compute
     #[inline]
@@ -300,6 +430,18 @@ impl GfxCommandBuffer {
             Gfx::get().gfx_device().cmd_set_scissor(self.vk_handle, first_scissor, scissors);
         }
     }
+
+    /// pipeline 需要通过 [`crate::pipelines::graphics_pipeline::GfxGraphicsPipelineCreateInfo::dynamic_cull_mode`]
+    /// 将 cull mode 声明为 dynamic state
+    ///
+    /// - command type: state
+    /// - supported queue types: graphics
+    #[inline]
+    pub fn cmd_set_cull_mode(&self, cull_mode: vk::CullModeFlags) {
+        unsafe {
+            Gfx::get().gfx_device().cmd_set_cull_mode(self.vk_handle, cull_mode);
+        }
+    }
 }
 // 光追相关
 impl GfxCommandBuffer {
@@ -351,6 +493,14 @@ impl GfxCommandBuffer {
         }
     }
 
+    /// 在 `stage` 之前的命令全部完成时，将 GPU 的时间写入 `query_pool` 的第 `query` 个 query
+    /// - command type: action
+    /// - supported queue types: graphics, compute, transfer
+    #[inline]
+    pub fn write_timestamp(&self, stage: vk::PipelineStageFlags2, query_pool: &GfxQueryPool, query: u32) {
+        unsafe { Gfx::get().gfx_device().cmd_write_timestamp2(self.vk_handle, stage, query_pool.handle(), query) }
+    }
+
     /// 光追的入口
     /// - command type: action
     /// - supported queue types: compute
@@ -376,6 +526,20 @@ impl GfxCommandBuffer {
             );
         }
     }
+
+    /// 使用 `sbt` 中的第一个 raygen shader 发射 `width * height` 条光线
+    /// - command type: action
+    /// - supported queue types: compute
+    #[inline]
+    pub fn cmd_trace_rays(&self, sbt: &GfxShaderBindingTable, width: u32, height: u32) {
+        self.trace_rays(
+            &sbt.raygen_region(0),
+            sbt.miss_region(),
+            sbt.hit_region(),
+            sbt.callable_region(),
+            [width, height, 1],
+        );
+    }
 }
 // 计算着色器相关命令
 impl GfxCommandBuffer {
@@ -385,6 +549,18 @@ impl GfxCommandBuffer {
             Gfx::get().gfx_device().cmd_dispatch(self.vk_handle, group_cnt.x, group_cnt.y, group_cnt.z);
         }
     }
+
+    /// - command type: action
+    /// - supported queue types: graphics, compute
+    ///
+    /// 工作组数量从 `buffer` 的 `offset` 处读取，内容为一个 [`vk::DispatchIndirectCommand`]，
+    /// 通常由之前的 compute pass 写入（例如 GPU 剔除之后的数量）
+    #[inline]
+    pub fn cmd_dispatch_indirect(&self, buffer: &GfxBuffer, offset: vk::DeviceSize) {
+        unsafe {
+            Gfx::get().gfx_device().cmd_dispatch_indirect(self.vk_handle, buffer.vk_buffer(), offset);
+        }
+    }
 }
 // 同步相关命令
 impl GfxCommandBuffer {
@@ -421,6 +597,51 @@ impl GfxCommandBuffer {
             Gfx::get().gfx_device().cmd_pipeline_barrier2(self.vk_handle, &dependency_info);
         }
     }
+
+    /// 将 `image` 从追踪的状态转换到 `new_state`，只在需要时录制 barrier，并更新追踪的状态
+    ///
+    /// 追踪的状态按录制顺序更新，因此要求这些 command buffer 按录制顺序提交到同一个 queue；
+    /// 跨 queue 使用时需要手动录制所有权转移的 release / acquire，之后通过 [`GfxImage::set_state`] 同步状态。
+    /// 所有 mip level 和 layer 一起转换
+    ///
+    /// - command type: synchronize
+    /// - supported queue types: graphics, compute, transfer
+    pub fn transition(&self, image: &GfxImage, new_state: GfxImageState) {
+        let old_state = image.state();
+        if !old_state.needs_barrier(&new_state) {
+            image.set_state(old_state.merge_read(&new_state));
+            return;
+        }
+
+        let barrier = GfxImageBarrier::new()
+            .image(image.handle())
+            .layout_transfer(old_state.layout, new_state.layout)
+            .src_mask(old_state.stage, old_state.src_access())
+            .dst_mask(new_state.stage, new_state.access)
+            .image_aspect_flag(VulkanFormatUtils::infer_aspect(image.format()))
+            .mip_range(0, image.mip_levels());
+        self.image_memory_barrier(vk::DependencyFlags::empty(), std::slice::from_ref(&barrier));
+        image.set_state(new_state);
+    }
+
+    /// 与 [`Self::transition`] 相同，作用于整个 buffer
+    ///
+    /// - command type: synchronize
+    /// - supported queue types: graphics, compute, transfer
+    pub fn transition_buffer(&self, buffer: &GfxBuffer, new_state: GfxBufferState) {
+        let old_state = buffer.state();
+        if !old_state.needs_barrier(&new_state) {
+            buffer.set_state(old_state.merge_read(&new_state));
+            return;
+        }
+
+        let barrier = GfxBufferBarrier::new()
+            .buffer(buffer.vk_buffer(), 0, vk::WHOLE_SIZE)
+            .src_mask(old_state.stage, old_state.src_access())
+            .dst_mask(new_state.stage, new_state.access);
+        self.buffer_memory_barrier(vk::DependencyFlags::empty(), std::slice::from_ref(&barrier));
+        buffer.set_state(new_state);
+    }
 }
 // debug 相关命令
 impl GfxCommandBuffer {
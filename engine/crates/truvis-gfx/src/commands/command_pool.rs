@@ -1,12 +1,17 @@
+use std::cell::Cell;
+
 use ash::vk;
 
 use crate::commands::command_buffer::GfxCommandBuffer;
+use crate::resources::resource_stats::GfxResourceKind;
 use crate::{commands::command_queue::GfxQueueFamily, foundation::debug_messenger::DebugType, gfx::Gfx};
 
 /// command pool 是和 queue family 绑定的，而不是和 queue 绑定的
 pub struct GfxCommandPool {
     handle: vk::CommandPool,
     _queue_family: GfxQueueFamily,
+    /// 从该 pool 分配并且尚未释放的 command buffer 数量，pool 销毁时一起释放
+    allocated_cmd_cnt: Cell<u64>,
 
     _debug_name: String,
     valid: bool,
@@ -31,6 +36,7 @@ impl GfxCommandPool {
         let command_pool = Self {
             handle: pool,
             _queue_family: queue_family,
+            allocated_cmd_cnt: Cell::new(0),
             _debug_name: debug_name.to_string(),
             valid: true,
         };
@@ -61,6 +67,7 @@ impl GfxCommandPool {
         let command_pool = Self {
             handle: pool,
             _queue_family: queue_family,
+            allocated_cmd_cnt: Cell::new(0),
             _debug_name: debug_name.to_string(),
             valid: true,
         };
@@ -75,6 +82,7 @@ impl GfxCommandPool {
         unsafe {
             gfx_device.destroy_command_pool(self.handle, None);
         }
+        Gfx::get().resource_stats.on_destroy(GfxResourceKind::CommandBuffer, self.allocated_cmd_cnt.replace(0));
         self.valid = false;
     }
 
@@ -114,6 +122,17 @@ impl GfxCommandPool {
         unsafe {
             Gfx::get().gfx_device().free_command_buffers(self.handle, &command_buffer_handles);
         }
+
+        let cnt = command_buffer_handles.len() as u64;
+        self.allocated_cmd_cnt.set(self.allocated_cmd_cnt.get().saturating_sub(cnt));
+        Gfx::get().resource_stats.on_destroy(GfxResourceKind::CommandBuffer, cnt);
+    }
+
+    /// 记录一次 command buffer 的分配
+    #[inline]
+    pub(crate) fn on_command_buffer_allocated(&self) {
+        self.allocated_cmd_cnt.set(self.allocated_cmd_cnt.get() + 1);
+        Gfx::get().resource_stats.on_create(GfxResourceKind::CommandBuffer, 1);
     }
 }
 impl DebugType for GfxCommandPool {
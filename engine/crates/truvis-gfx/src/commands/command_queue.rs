@@ -14,6 +14,8 @@ pub struct GfxQueueFamily {
     pub queue_family_index: u32,
     pub queue_flags: vk::QueueFlags,
     pub queue_count: u32,
+    /// timestamp 查询结果中有效的位数，0 表示该 queue family 不支持 timestamp
+    pub timestamp_valid_bits: u32,
 }
 
 /// # destroy
@@ -59,6 +61,19 @@ impl GfxCommandQueue {
         }
     }
 
+    /// 提交 sparse 资源的内存绑定操作，需要 queue family 支持 SPARSE_BINDING
+    ///
+    /// 绑定操作与 command buffer 的提交之间没有隐式的顺序，需要通过 semaphore 或 fence 同步
+    pub fn bind_sparse(&self, bind_infos: &[vk::BindSparseInfo], fence: Option<&GfxFence>) {
+        debug_assert!(self.queue_family.queue_flags.contains(vk::QueueFlags::SPARSE_BINDING));
+        unsafe {
+            self.gfx_device
+                .device
+                .queue_bind_sparse(self.vk_queue, bind_infos, fence.map_or(vk::Fence::null(), |f| f.handle()))
+                .unwrap()
+        }
+    }
+
     /// 根据 specification，vkQueueWaitIdle 应该和 Fence 效率相同
     #[inline]
     pub fn wait_idle(&self) {
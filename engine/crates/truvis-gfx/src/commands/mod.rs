@@ -1,7 +1,10 @@
+pub mod async_transfer;
 pub mod barrier;
 pub mod command_buffer;
 pub mod command_pool;
 pub mod command_queue;
 pub mod fence;
+pub mod resource_state;
 pub mod semaphore;
 pub mod submit_info;
+pub mod timeline;
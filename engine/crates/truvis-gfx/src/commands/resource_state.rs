@@ -0,0 +1,354 @@
+//! 资源状态定义
+//!
+//! 封装 Vulkan 的 pipeline stage、access mask 和 image layout，
+//! 提供预定义的常用状态组合。
+
+use ash::vk;
+
+/// 图像资源状态
+///
+/// 描述图像在某个 Pass 中的使用方式，用于自动计算 barrier。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GfxImageState {
+    /// Pipeline stage
+    pub stage: vk::PipelineStageFlags2,
+    /// Access mask
+    pub access: vk::AccessFlags2,
+    /// Image layout
+    pub layout: vk::ImageLayout,
+}
+
+impl Default for GfxImageState {
+    fn default() -> Self {
+        Self::UNDEFINED_TOP
+    }
+}
+
+// new & 常量定义
+impl GfxImageState {
+    /// 创建自定义状态
+    #[inline]
+    pub const fn new(stage: vk::PipelineStageFlags2, access: vk::AccessFlags2, layout: vk::ImageLayout) -> Self {
+        Self { stage, access, layout }
+    }
+
+    pub const UNDEFINED_TOP: Self =
+        Self::new(vk::PipelineStageFlags2::TOP_OF_PIPE, vk::AccessFlags2::NONE, vk::ImageLayout::UNDEFINED);
+
+    pub const UNDEFINED_BOTTOM: Self =
+        Self::new(vk::PipelineStageFlags2::BOTTOM_OF_PIPE, vk::AccessFlags2::NONE, vk::ImageLayout::UNDEFINED);
+
+    pub const GENERAL: Self = Self::new(
+        vk::PipelineStageFlags2::ALL_COMMANDS,
+        vk::AccessFlags2::from_raw(vk::AccessFlags2::MEMORY_READ.as_raw() | vk::AccessFlags2::MEMORY_WRITE.as_raw()),
+        vk::ImageLayout::GENERAL,
+    );
+
+    pub const COLOR_ATTACHMENT_WRITE: Self = Self::new(
+        vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+        vk::AccessFlags2::COLOR_ATTACHMENT_WRITE,
+        vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+    );
+
+    pub const COLOR_ATTACHMENT_READ_WRITE: Self = Self::new(
+        vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+        vk::AccessFlags2::from_raw(
+            vk::AccessFlags2::COLOR_ATTACHMENT_READ.as_raw() | vk::AccessFlags2::COLOR_ATTACHMENT_WRITE.as_raw(),
+        ),
+        vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+    );
+
+    pub const DEPTH_ATTACHMENT_WRITE: Self = Self::new(
+        vk::PipelineStageFlags2::from_raw(
+            vk::PipelineStageFlags2::EARLY_FRAGMENT_TESTS.as_raw()
+                | vk::PipelineStageFlags2::LATE_FRAGMENT_TESTS.as_raw(),
+        ),
+        vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE,
+        vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+    );
+
+    /// 只做深度测试，不写入深度
+    pub const DEPTH_ATTACHMENT_READ: Self = Self::new(
+        vk::PipelineStageFlags2::from_raw(
+            vk::PipelineStageFlags2::EARLY_FRAGMENT_TESTS.as_raw()
+                | vk::PipelineStageFlags2::LATE_FRAGMENT_TESTS.as_raw(),
+        ),
+        vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_READ,
+        vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+    );
+
+    pub const DEPTH_ATTACHMENT_READ_WRITE: Self = Self::new(
+        vk::PipelineStageFlags2::from_raw(
+            vk::PipelineStageFlags2::EARLY_FRAGMENT_TESTS.as_raw()
+                | vk::PipelineStageFlags2::LATE_FRAGMENT_TESTS.as_raw(),
+        ),
+        vk::AccessFlags2::from_raw(
+            vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_READ.as_raw()
+                | vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE.as_raw(),
+        ),
+        vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+    );
+
+    pub const SHADER_READ_FRAGMENT: Self = Self::new(
+        vk::PipelineStageFlags2::FRAGMENT_SHADER,
+        vk::AccessFlags2::SHADER_SAMPLED_READ,
+        vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+    );
+
+    pub const SHADER_READ_COMPUTE: Self = Self::new(
+        vk::PipelineStageFlags2::COMPUTE_SHADER,
+        vk::AccessFlags2::SHADER_SAMPLED_READ,
+        vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+    );
+
+    pub const SHADER_READ_RAY_TRACING: Self = Self::new(
+        vk::PipelineStageFlags2::RAY_TRACING_SHADER_KHR,
+        vk::AccessFlags2::SHADER_SAMPLED_READ,
+        vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+    );
+
+    pub const STORAGE_READ_COMPUTE: Self = Self::new(
+        vk::PipelineStageFlags2::COMPUTE_SHADER,
+        vk::AccessFlags2::SHADER_STORAGE_READ,
+        vk::ImageLayout::GENERAL,
+    );
+
+    pub const STORAGE_WRITE_COMPUTE: Self = Self::new(
+        vk::PipelineStageFlags2::COMPUTE_SHADER,
+        vk::AccessFlags2::SHADER_STORAGE_WRITE,
+        vk::ImageLayout::GENERAL,
+    );
+
+    pub const STORAGE_READ_WRITE_COMPUTE: Self = Self::new(
+        vk::PipelineStageFlags2::COMPUTE_SHADER,
+        vk::AccessFlags2::from_raw(
+            vk::AccessFlags2::SHADER_STORAGE_READ.as_raw() | vk::AccessFlags2::SHADER_STORAGE_WRITE.as_raw(),
+        ),
+        vk::ImageLayout::GENERAL,
+    );
+
+    pub const STORAGE_WRITE_RAY_TRACING: Self = Self::new(
+        vk::PipelineStageFlags2::RAY_TRACING_SHADER_KHR,
+        vk::AccessFlags2::SHADER_STORAGE_WRITE,
+        vk::ImageLayout::GENERAL,
+    );
+
+    pub const STORAGE_READ_WRITE_RAY_TRACING: Self = Self::new(
+        vk::PipelineStageFlags2::RAY_TRACING_SHADER_KHR,
+        vk::AccessFlags2::from_raw(
+            vk::AccessFlags2::SHADER_STORAGE_READ.as_raw() | vk::AccessFlags2::SHADER_STORAGE_WRITE.as_raw(),
+        ),
+        vk::ImageLayout::GENERAL,
+    );
+
+    pub const TRANSFER_SRC: Self = Self::new(
+        vk::PipelineStageFlags2::TRANSFER,
+        vk::AccessFlags2::TRANSFER_READ,
+        vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+    );
+
+    pub const TRANSFER_DST: Self = Self::new(
+        vk::PipelineStageFlags2::TRANSFER,
+        vk::AccessFlags2::TRANSFER_WRITE,
+        vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+    );
+
+    /// 任意 shader 采样读取，例如异步上传完成之后的纹理
+    pub const SHADER_READ_ALL: Self = Self::new(
+        vk::PipelineStageFlags2::ALL_COMMANDS,
+        vk::AccessFlags2::SHADER_READ,
+        vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+    );
+
+    pub const PRESENT_BOTTOM: Self =
+        Self::new(vk::PipelineStageFlags2::BOTTOM_OF_PIPE, vk::AccessFlags2::NONE, vk::ImageLayout::PRESENT_SRC_KHR);
+}
+
+// 辅助方法
+impl GfxImageState {
+    /// 写操作的 access flags
+    const WRITE_ACCESS: vk::AccessFlags2 = vk::AccessFlags2::from_raw(
+        vk::AccessFlags2::SHADER_STORAGE_WRITE.as_raw()
+            | vk::AccessFlags2::COLOR_ATTACHMENT_WRITE.as_raw()
+            | vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE.as_raw()
+            | vk::AccessFlags2::TRANSFER_WRITE.as_raw()
+            | vk::AccessFlags2::MEMORY_WRITE.as_raw(),
+    );
+
+    /// 检查是否为写操作
+    #[inline]
+    pub fn is_write(&self) -> bool {
+        self.access.intersects(Self::WRITE_ACCESS)
+    }
+
+    /// 检查是否为只读操作
+    #[inline]
+    pub fn is_read_only(&self) -> bool {
+        !self.is_write()
+    }
+
+    /// 获取用于 barrier src 的 access（去掉读操作）
+    #[inline]
+    pub fn src_access(&self) -> vk::AccessFlags2 {
+        self.access
+            & !(vk::AccessFlags2::SHADER_SAMPLED_READ
+                | vk::AccessFlags2::SHADER_STORAGE_READ
+                | vk::AccessFlags2::TRANSFER_READ
+                | vk::AccessFlags2::MEMORY_READ)
+    }
+
+    /// 从 self 转换到 `dst` 是否需要 barrier
+    ///
+    /// layout 不同或者任意一方有写操作时需要，只读到只读可以跳过
+    #[inline]
+    pub fn needs_barrier(&self, dst: &Self) -> bool {
+        self.layout != dst.layout || self.is_write() || dst.is_write()
+    }
+
+    /// 跳过 barrier 时的合并状态：之后的写操作需要等待两次读取都完成
+    #[inline]
+    pub fn merge_read(&self, dst: &Self) -> Self {
+        debug_assert!(!self.needs_barrier(dst));
+        Self::new(self.stage | dst.stage, self.access | dst.access, dst.layout)
+    }
+}
+
+/// 缓冲区资源状态
+///
+/// 描述缓冲区在某个 Pass 中的使用方式。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GfxBufferState {
+    /// Pipeline stage
+    pub stage: vk::PipelineStageFlags2,
+    /// Access mask
+    pub access: vk::AccessFlags2,
+}
+
+impl Default for GfxBufferState {
+    fn default() -> Self {
+        Self::UNDEFINED
+    }
+}
+
+// new & 常量定义
+impl GfxBufferState {
+    /// 创建自定义状态
+    #[inline]
+    pub const fn new(stage: vk::PipelineStageFlags2, access: vk::AccessFlags2) -> Self {
+        Self { stage, access }
+    }
+
+    // ============ 预定义状态常量 ============
+
+    /// 未定义状态
+    pub const UNDEFINED: Self = Self::new(vk::PipelineStageFlags2::TOP_OF_PIPE, vk::AccessFlags2::NONE);
+
+    /// 顶点缓冲区读取
+    pub const VERTEX_BUFFER: Self =
+        Self::new(vk::PipelineStageFlags2::VERTEX_INPUT, vk::AccessFlags2::VERTEX_ATTRIBUTE_READ);
+
+    /// 索引缓冲区读取
+    pub const INDEX_BUFFER: Self = Self::new(vk::PipelineStageFlags2::INDEX_INPUT, vk::AccessFlags2::INDEX_READ);
+
+    /// Uniform 缓冲区读取（顶点着色器）
+    pub const UNIFORM_VERTEX: Self = Self::new(vk::PipelineStageFlags2::VERTEX_SHADER, vk::AccessFlags2::UNIFORM_READ);
+
+    /// Uniform 缓冲区读取（片段着色器）
+    pub const UNIFORM_FRAGMENT: Self =
+        Self::new(vk::PipelineStageFlags2::FRAGMENT_SHADER, vk::AccessFlags2::UNIFORM_READ);
+
+    /// Uniform 缓冲区读取（计算着色器）
+    pub const UNIFORM_COMPUTE: Self =
+        Self::new(vk::PipelineStageFlags2::COMPUTE_SHADER, vk::AccessFlags2::UNIFORM_READ);
+
+    /// 存储缓冲区读写（计算着色器）
+    pub const STORAGE_READ_WRITE_COMPUTE: Self = Self::new(
+        vk::PipelineStageFlags2::COMPUTE_SHADER,
+        vk::AccessFlags2::from_raw(
+            vk::AccessFlags2::SHADER_STORAGE_READ.as_raw() | vk::AccessFlags2::SHADER_STORAGE_WRITE.as_raw(),
+        ),
+    );
+
+    /// 间接命令缓冲区
+    pub const INDIRECT_BUFFER: Self =
+        Self::new(vk::PipelineStageFlags2::DRAW_INDIRECT, vk::AccessFlags2::INDIRECT_COMMAND_READ);
+
+    /// 传输源
+    pub const TRANSFER_SRC: Self = Self::new(vk::PipelineStageFlags2::TRANSFER, vk::AccessFlags2::TRANSFER_READ);
+
+    /// 传输目标
+    pub const TRANSFER_DST: Self = Self::new(vk::PipelineStageFlags2::TRANSFER, vk::AccessFlags2::TRANSFER_WRITE);
+
+    /// 加速结构构建输入
+    pub const ACCELERATION_STRUCTURE_BUILD_INPUT: Self = Self::new(
+        vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR,
+        vk::AccessFlags2::ACCELERATION_STRUCTURE_READ_KHR,
+    );
+}
+
+// 辅助方法
+impl GfxBufferState {
+    /// 写操作的 access flags
+    const WRITE_ACCESS: vk::AccessFlags2 = vk::AccessFlags2::from_raw(
+        vk::AccessFlags2::SHADER_STORAGE_WRITE.as_raw()
+            | vk::AccessFlags2::TRANSFER_WRITE.as_raw()
+            | vk::AccessFlags2::MEMORY_WRITE.as_raw(),
+    );
+
+    /// 检查是否为写操作
+    #[inline]
+    pub fn is_write(&self) -> bool {
+        self.access.intersects(Self::WRITE_ACCESS)
+    }
+
+    /// 获取用于 barrier src 的 access（去掉读操作），参见 [`GfxImageState::src_access`]
+    #[inline]
+    pub fn src_access(&self) -> vk::AccessFlags2 {
+        self.access & Self::WRITE_ACCESS
+    }
+
+    /// 从 self 转换到 `dst` 是否需要 barrier，只读到只读可以跳过
+    #[inline]
+    pub fn needs_barrier(&self, dst: &Self) -> bool {
+        self.is_write() || dst.is_write()
+    }
+
+    /// 跳过 barrier 时的合并状态，参见 [`GfxImageState::merge_read`]
+    #[inline]
+    pub fn merge_read(&self, dst: &Self) -> Self {
+        debug_assert!(!self.needs_barrier(dst));
+        Self::new(self.stage | dst.stage, self.access | dst.access)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image_read_after_read_skips_barrier() {
+        let fragment = GfxImageState::SHADER_READ_FRAGMENT;
+        let compute = GfxImageState::SHADER_READ_COMPUTE;
+        assert!(!fragment.needs_barrier(&compute));
+
+        let merged = fragment.merge_read(&compute);
+        assert_eq!(merged.stage, vk::PipelineStageFlags2::FRAGMENT_SHADER | vk::PipelineStageFlags2::COMPUTE_SHADER);
+        assert_eq!(merged.layout, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
+    }
+
+    #[test]
+    fn test_image_layout_change_or_write_needs_barrier() {
+        assert!(GfxImageState::UNDEFINED_TOP.needs_barrier(&GfxImageState::TRANSFER_DST));
+        assert!(GfxImageState::STORAGE_WRITE_COMPUTE.needs_barrier(&GfxImageState::STORAGE_READ_COMPUTE));
+        assert!(GfxImageState::SHADER_READ_FRAGMENT.needs_barrier(&GfxImageState::PRESENT_BOTTOM));
+        assert!(GfxBufferState::TRANSFER_DST.needs_barrier(&GfxBufferState::UNIFORM_COMPUTE));
+        assert!(!GfxBufferState::VERTEX_BUFFER.needs_barrier(&GfxBufferState::INDEX_BUFFER));
+    }
+
+    #[test]
+    fn test_buffer_src_access_drops_reads() {
+        assert_eq!(GfxBufferState::STORAGE_READ_WRITE_COMPUTE.src_access(), vk::AccessFlags2::SHADER_STORAGE_WRITE);
+        assert_eq!(GfxBufferState::TRANSFER_DST.src_access(), vk::AccessFlags2::TRANSFER_WRITE);
+        assert_eq!(GfxBufferState::INDIRECT_BUFFER.src_access(), vk::AccessFlags2::NONE);
+    }
+}
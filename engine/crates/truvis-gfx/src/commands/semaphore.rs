@@ -51,6 +51,12 @@ impl GfxSemaphore {
 
 // tools
 impl GfxSemaphore {
+    /// timeline semaphore 当前的值，不会阻塞
+    #[inline]
+    pub fn counter_value(&self) -> u64 {
+        unsafe { Gfx::get().gfx_device().get_semaphore_counter_value(self.semaphore).unwrap() }
+    }
+
     #[inline]
     pub fn wait_timeline(&self, timeline_value: u64, timeout_ns: u64) {
         let gfx_device = Gfx::get().gfx_device();
@@ -0,0 +1,268 @@
+//! 基于 timeline semaphore 的多帧资源生命周期管理
+//!
+//! 每个需要延迟回收的资源都记录「最后一次被 GPU 使用时的 timeline 值」，
+//! 当 GPU 已经完成的 timeline 值不小于它时，资源即可安全回收。
+//! 相比按照 frames-in-flight 索引轮转，资源在 GPU 完成后就可以立即回收，
+//! 也不需要每类资源各自推算需要等待多少帧。
+
+use std::cell::Cell;
+use std::collections::VecDeque;
+
+use crate::commands::semaphore::GfxSemaphore;
+
+/// 一个单调递增的 GPU timeline
+///
+/// 对 timeline semaphore 的封装，缓存最近一次查询到的完成值，避免频繁查询驱动
+///
+/// # Destroy
+/// 需要手动调用 [`GfxTimeline::destroy`]
+pub struct GfxTimeline {
+    semaphore: GfxSemaphore,
+    /// 最近一次查询到的、GPU 已经完成的 timeline 值
+    completed_value: Cell<u64>,
+}
+// new & init
+impl GfxTimeline {
+    /// 初始值为 0，因此第一次提交应该 signal 1
+    pub fn new(debug_name: &str) -> Self {
+        Self {
+            semaphore: GfxSemaphore::new_timeline(0, debug_name),
+            completed_value: Cell::new(0),
+        }
+    }
+}
+// destroy
+impl GfxTimeline {
+    pub fn destroy(self) {
+        self.semaphore.destroy();
+    }
+}
+// getter
+impl GfxTimeline {
+    /// 提交命令时用于 wait 和 signal 的 semaphore
+    #[inline]
+    pub fn semaphore(&self) -> &GfxSemaphore {
+        &self.semaphore
+    }
+
+    /// 最近一次查询到的完成值，不会访问驱动；实际完成值只会更大
+    #[inline]
+    pub fn cached_completed_value(&self) -> u64 {
+        self.completed_value.get()
+    }
+}
+// tools
+impl GfxTimeline {
+    /// 查询 GPU 已经完成的 timeline 值，不会阻塞
+    pub fn completed_value(&self) -> u64 {
+        let value = self.semaphore.counter_value().max(self.completed_value.get());
+        self.completed_value.set(value);
+        value
+    }
+
+    /// `value` 对应的 GPU 工作是否已经完成，优先使用缓存的完成值
+    pub fn is_complete(&self, value: u64) -> bool {
+        value <= self.completed_value.get() || value <= self.completed_value()
+    }
+
+    /// 阻塞直到 GPU 完成 `value`；已经完成时不会访问驱动
+    pub fn wait(&self, value: u64, timeout_ns: u64) {
+        if value <= self.completed_value.get() {
+            return;
+        }
+        self.semaphore.wait_timeline(value, timeout_ns);
+        self.completed_value.set(self.completed_value.get().max(value));
+    }
+}
+
+/// 等待 GPU 使用完毕后才能回收的资源队列
+///
+/// 资源按照最后使用的 timeline 值排序，回收时只需要从队首检查
+pub struct GfxRetireQueue<T> {
+    /// (最后使用的 timeline 值, 资源)，按 timeline 值升序排列
+    pending: VecDeque<(u64, T)>,
+}
+impl<T> Default for GfxRetireQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+// new & init
+impl<T> GfxRetireQueue<T> {
+    pub fn new() -> Self {
+        Self {
+            pending: VecDeque::new(),
+        }
+    }
+}
+// getter
+impl<T> GfxRetireQueue<T> {
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// 队列中最早可以回收的资源所需的 timeline 值
+    #[inline]
+    pub fn oldest_value(&self) -> Option<u64> {
+        self.pending.front().map(|(value, _)| *value)
+    }
+}
+// update
+impl<T> GfxRetireQueue<T> {
+    /// 资源不再被 CPU 使用，GPU 最后一次使用它的工作会 signal `last_use_value`
+    ///
+    /// 通常按照 timeline 值递增的顺序调用；乱序时会插入到对应的位置。返回队列中的该资源
+    pub fn retire(&mut self, last_use_value: u64, item: T) -> &mut T {
+        // 按顺序调用时插入到队尾
+        let idx = self.pending.partition_point(|(value, _)| *value <= last_use_value);
+        self.pending.insert(idx, (last_use_value, item));
+        &mut self.pending[idx].1
+    }
+
+    /// 取出所有 GPU 已经使用完毕的资源，即最后使用的 timeline 值不大于 `completed_value`
+    pub fn drain_completed(&mut self, completed_value: u64) -> impl Iterator<Item = T> + '_ {
+        let cnt = self.pending.partition_point(|(value, _)| *value <= completed_value);
+        self.pending.drain(..cnt).map(|(_, item)| item)
+    }
+
+    /// 取出所有资源，不检查 GPU 是否使用完毕，用于销毁时调用者已经等待 GPU 空闲的情况
+    pub fn drain_all(&mut self) -> impl Iterator<Item = T> + '_ {
+        self.pending.drain(..).map(|(_, item)| item)
+    }
+
+    /// 丢弃所有资源，用于资源已经通过其他途径销毁的情况
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    /// 只保留满足条件的资源，例如资源被提前立即销毁时将其移出队列
+    pub fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+        self.pending.retain(|(_, item)| f(item));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drain_completed() {
+        let mut queue = GfxRetireQueue::new();
+        queue.retire(1, "a");
+        queue.retire(2, "b");
+        queue.retire(2, "c");
+        queue.retire(4, "d");
+
+        assert_eq!(queue.drain_completed(0).count(), 0);
+        assert_eq!(queue.drain_completed(2).collect::<Vec<_>>(), vec!["a", "b", "c"]);
+        assert_eq!(queue.oldest_value(), Some(4));
+        assert_eq!(queue.drain_completed(3).count(), 0);
+        assert_eq!(queue.drain_completed(10).collect::<Vec<_>>(), vec!["d"]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_retire_out_of_order() {
+        let mut queue = GfxRetireQueue::new();
+        queue.retire(5, 5);
+        queue.retire(3, 3);
+        queue.retire(7, 7);
+        queue.retire(4, 4);
+
+        assert_eq!(queue.drain_completed(4).collect::<Vec<_>>(), vec![3, 4]);
+        assert_eq!(queue.drain_all().collect::<Vec<_>>(), vec![5, 7]);
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut queue = GfxRetireQueue::new();
+        for i in 0..6 {
+            queue.retire(i, i);
+        }
+        queue.retain(|item| item % 2 == 0);
+        assert_eq!(queue.drain_completed(100).collect::<Vec<_>>(), vec![0, 2, 4]);
+    }
+
+    /// 模拟 CPU 每帧提交工作、GPU 以不确定的速度完成，资源在随机的帧被使用和释放
+    ///
+    /// 检查两点：
+    /// - 不会 use-after-free：回收时 GPU 已经完成了资源最后一次被使用的工作
+    /// - 不会过早等待：GPU 完成之后的第一次回收就能拿到资源，不会多等若干帧
+    #[test]
+    fn test_stress_no_use_after_free_no_late_reclaim() {
+        const FRAME_CNT: u64 = 20_000;
+        const MAX_IN_FLIGHT: u64 = 3;
+
+        // 固定种子的 LCG，保证测试结果可复现
+        let mut seed = 0x2545_f491_4f6c_dd1du64;
+        let mut rand = move |n: u64| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) % n
+        };
+
+        struct Resource {
+            id: u64,
+            /// 最后一次被提交使用的 timeline 值
+            last_use: u64,
+        }
+
+        let mut queue = GfxRetireQueue::<Resource>::new();
+        // 仍被 CPU 持有的资源
+        let mut alive: Vec<Resource> = vec![];
+        let mut next_id = 0;
+        let mut gpu_completed = 0;
+        let mut reclaimed_cnt = 0;
+        let mut retired_cnt = 0;
+
+        for frame in 1..=FRAME_CNT {
+            // GPU 随机推进，但 in-flight 的帧数不超过上限
+            gpu_completed = (gpu_completed + rand(3)).max(frame.saturating_sub(MAX_IN_FLIGHT)).min(frame - 1);
+
+            // 回收：GPU 已经完成的资源必须全部回收，并且不能回收 GPU 仍在使用的资源
+            for resource in queue.drain_completed(gpu_completed) {
+                assert!(resource.last_use <= gpu_completed, "use after free: resource {}", resource.id);
+                reclaimed_cnt += 1;
+            }
+            assert!(queue.oldest_value().is_none_or(|value| value > gpu_completed), "late reclaim");
+
+            // 创建新资源
+            for _ in 0..rand(4) {
+                alive.push(Resource {
+                    id: next_id,
+                    last_use: 0,
+                });
+                next_id += 1;
+            }
+
+            // 本帧的工作使用一部分资源
+            for resource in alive.iter_mut() {
+                if rand(2) == 0 {
+                    resource.last_use = frame;
+                }
+            }
+
+            // 释放一部分资源
+            let mut idx = 0;
+            while idx < alive.len() {
+                if rand(4) == 0 {
+                    let resource = alive.swap_remove(idx);
+                    queue.retire(resource.last_use, resource);
+                    retired_cnt += 1;
+                } else {
+                    idx += 1;
+                }
+            }
+        }
+
+        // 所有帧完成之后，所有资源都可以回收
+        reclaimed_cnt += queue.drain_completed(FRAME_CNT).count();
+        assert_eq!(reclaimed_cnt, retired_cnt);
+        assert!(queue.is_empty());
+    }
+}
@@ -1,7 +1,8 @@
 use ash::vk;
-use truvis_descriptor_layout_trait::DescriptorBindingLayout;
+use truvis_descriptor_layout_trait::{DescriptorBindingItem, DescriptorBindingLayout};
 
 use crate::gfx::Gfx;
+use crate::resources::resource_stats::GfxResourceKind;
 use crate::{descriptors::descriptor_pool::GfxDescriptorPool, foundation::debug_messenger::DebugType};
 
 /// 描述符集布局
@@ -100,6 +101,8 @@ pub struct GfxDescriptorSet<T: DescriptorBindingLayout> {
 impl<T: DescriptorBindingLayout> GfxDescriptorSet<T> {
     /// 创建新的描述符集
     ///
+    /// layout 中有运行时数量的 binding 时，按照声明的上限分配，参见 [`Self::new_with_variable_count`]
+    ///
     /// # 参数
     /// - render_context: RHI 实例
     /// - layout: 描述符集布局
@@ -112,6 +115,15 @@ impl<T: DescriptorBindingLayout> GfxDescriptorSet<T> {
         layout: &GfxDescriptorSetLayout<T>,
         debug_name: impl AsRef<str>,
     ) -> Self {
+        if T::variable_binding().is_some() {
+            return Self::new_with_variable_count(
+                descriptor_pool,
+                layout,
+                DescriptorBindingItem::RUNTIME_COUNT_UPPER_BOUND,
+                debug_name,
+            );
+        }
+
         // 分配描述符集
         let alloc_info = vk::DescriptorSetAllocateInfo::default()
             .descriptor_pool(descriptor_pool.handle())
@@ -124,6 +136,48 @@ impl<T: DescriptorBindingLayout> GfxDescriptorSet<T> {
             _descriptor_pool: descriptor_pool.handle(),
         };
         gfx_device.set_debug_name(&set, debug_name);
+        descriptor_pool.on_set_allocated();
+        set
+    }
+
+    /// 为带有运行时数量 binding（`#[count = "runtime"]`）的 layout 创建描述符集
+    ///
+    /// # 参数
+    /// - variable_count: [`DescriptorBindingLayout::variable_binding`] 实际分配的描述符数量，
+    ///   不能超过 [`DescriptorBindingItem::RUNTIME_COUNT_UPPER_BOUND`]
+    ///
+    /// # Panics
+    /// layout 中没有运行时数量的 binding，或者数量超过上限
+    pub fn new_with_variable_count(
+        descriptor_pool: &GfxDescriptorPool,
+        layout: &GfxDescriptorSetLayout<T>,
+        variable_count: u32,
+        debug_name: impl AsRef<str>,
+    ) -> Self {
+        assert!(T::variable_binding().is_some(), "descriptor set layout has no runtime sized binding");
+        assert!(
+            variable_count <= DescriptorBindingItem::RUNTIME_COUNT_UPPER_BOUND,
+            "variable descriptor count {} exceeds the upper bound {}",
+            variable_count,
+            DescriptorBindingItem::RUNTIME_COUNT_UPPER_BOUND
+        );
+
+        let variable_counts = [variable_count];
+        let mut variable_count_info =
+            vk::DescriptorSetVariableDescriptorCountAllocateInfo::default().descriptor_counts(&variable_counts);
+        let alloc_info = vk::DescriptorSetAllocateInfo::default()
+            .descriptor_pool(descriptor_pool.handle())
+            .set_layouts(std::slice::from_ref(&layout.layout))
+            .push_next(&mut variable_count_info);
+        let gfx_device = Gfx::get().gfx_device();
+        let descriptor_set = unsafe { gfx_device.allocate_descriptor_sets(&alloc_info).unwrap()[0] };
+        let set = Self {
+            handle: descriptor_set,
+            phantom_data: std::marker::PhantomData,
+            _descriptor_pool: descriptor_pool.handle(),
+        };
+        gfx_device.set_debug_name(&set, debug_name);
+        descriptor_pool.on_set_allocated();
         set
     }
 
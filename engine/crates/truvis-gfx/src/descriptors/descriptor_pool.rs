@@ -1,9 +1,11 @@
+use std::cell::Cell;
 use std::rc::Rc;
 
 use ash::vk;
 
 use crate::foundation::debug_messenger::DebugType;
 use crate::gfx::Gfx;
+use crate::resources::resource_stats::GfxResourceKind;
 
 /// 描述符池
 ///
@@ -14,6 +16,8 @@ pub struct GfxDescriptorPool {
     handle: vk::DescriptorPool,
     /// 描述符池创建信息
     _info: Rc<GfxDescriptorPoolCreateInfo>,
+    /// 从该 pool 分配的描述符集数量，pool 销毁时一起释放
+    allocated_set_cnt: Cell<u64>,
 
     _name: String,
 }
@@ -34,6 +38,7 @@ impl GfxDescriptorPool {
         let pool = Self {
             handle: pool,
             _info: ci,
+            allocated_set_cnt: Cell::new(0),
             _name: name.to_string(),
         };
         gfx_device.set_debug_name(&pool, name);
@@ -49,6 +54,13 @@ impl GfxDescriptorPool {
         self.handle
     }
 
+    /// 记录一次描述符集的分配
+    #[inline]
+    pub(crate) fn on_set_allocated(&self) {
+        self.allocated_set_cnt.set(self.allocated_set_cnt.get() + 1);
+        Gfx::get().resource_stats.on_create(GfxResourceKind::DescriptorSet, 1);
+    }
+
     #[inline]
     pub fn destroy(self) {
         // drop
@@ -58,6 +70,7 @@ impl Drop for GfxDescriptorPool {
     /// 释放 Vulkan 描述符池
     fn drop(&mut self) {
         unsafe { Gfx::get().gfx_device().destroy_descriptor_pool(self.handle, None) };
+        Gfx::get().resource_stats.on_destroy(GfxResourceKind::DescriptorSet, self.allocated_set_cnt.get());
     }
 }
 impl DebugType for GfxDescriptorPool {
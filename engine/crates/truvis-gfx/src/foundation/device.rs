@@ -19,6 +19,9 @@ use crate::{foundation::debug_messenger::DebugType, utilities::descriptor_cursor
 /// - Ray Tracing Pipeline (KHR)
 /// - Debug Utils (EXT)
 /// - Swapchain (KHR)
+/// - External Memory FD (KHR) / DMA-BUF (EXT)，仅 Linux，并且仅在设备支持时开启
+/// - Mesh Shader (EXT)，仅在设备支持时开启
+/// - Draw Indirect Count (KHR)，仅在设备支持时开启
 pub struct GfxDevice {
     /// 核心 Vulkan 设备 API
     pub(crate) device: ash::Device,
@@ -34,6 +37,13 @@ pub struct GfxDevice {
     pub(crate) swapchain: ash::khr::swapchain::Device,
     /// 推送描述符扩展 API
     pub(crate) push_descriptor: ash::khr::push_descriptor::Device,
+    /// mesh shader 扩展 API，设备不支持时调用会 panic
+    pub(crate) mesh_shader: ash::ext::mesh_shader::Device,
+    /// indirect count 扩展 API，设备不支持时调用会 panic
+    pub(crate) draw_indirect_count: ash::khr::draw_indirect_count::Device,
+    /// 以 fd 的形式导入导出 device memory（用于 dma-buf 共享），设备不支持时调用会 panic
+    #[cfg(target_os = "linux")]
+    pub(crate) external_memory_fd: ash::khr::external_memory_fd::Device,
 
     #[cfg(debug_assertions)]
     destroyed: Cell<bool>,
@@ -44,12 +54,30 @@ impl GfxDevice {
     pub fn new(
         instance: &ash::Instance,
         pdevice: vk::PhysicalDevice,
+        supported_features: &vk::PhysicalDeviceFeatures,
+        mesh_shader_supported: bool,
+        draw_indirect_count_supported: bool,
+        external_memory_dma_buf_supported: bool,
         queue_create_info: &[vk::DeviceQueueCreateInfo],
     ) -> Self {
         let _span = tracy_client::span!("GfxDevice::new");
 
         // device 所需的所有 extension
-        let device_exts = Self::basic_device_exts().iter().map(|e| e.as_ptr()).collect_vec();
+        if !draw_indirect_count_supported {
+            log::warn!("VK_KHR_draw_indirect_count is not supported, indirect count draws fall back to max draw count");
+        }
+        #[cfg(target_os = "linux")]
+        if !external_memory_dma_buf_supported {
+            log::warn!("VK_EXT_external_memory_dma_buf is not supported, dma-buf export is disabled");
+        }
+        let device_exts = Self::basic_device_exts(
+            mesh_shader_supported,
+            draw_indirect_count_supported,
+            external_memory_dma_buf_supported,
+        )
+        .iter()
+        .map(|e| e.as_ptr())
+        .collect_vec();
         let mut exts_str = String::new();
         for ext in &device_exts {
             exts_str.push_str(&format!("\n\t{:?}", unsafe { CStr::from_ptr(*ext) }));
@@ -57,8 +85,9 @@ impl GfxDevice {
         log::info!("device exts: {}", exts_str);
 
         // device 所需的所有 features
-        let mut all_features = vk::PhysicalDeviceFeatures2::default().features(Self::physical_device_basic_features());
-        let mut physical_device_ext_features = Self::physical_device_extra_features();
+        let mut all_features =
+            vk::PhysicalDeviceFeatures2::default().features(Self::physical_device_basic_features(supported_features));
+        let mut physical_device_ext_features = Self::physical_device_extra_features(mesh_shader_supported);
         unsafe {
             physical_device_ext_features.iter_mut().for_each(|f| {
                 let ptr = <*mut dyn vk::ExtendsPhysicalDeviceFeatures2>::cast::<vk::BaseOutStructure>(f.as_mut());
@@ -80,6 +109,10 @@ impl GfxDevice {
         let vk_debug_utils_device = ash::ext::debug_utils::Device::new(instance, &device);
         let vk_swapchain = ash::khr::swapchain::Device::new(instance, &device);
         let vk_push_descriptor = ash::khr::push_descriptor::Device::new(instance, &device);
+        let vk_mesh_shader = ash::ext::mesh_shader::Device::new(instance, &device);
+        let vk_draw_indirect_count = ash::khr::draw_indirect_count::Device::new(instance, &device);
+        #[cfg(target_os = "linux")]
+        let vk_external_memory_fd = ash::khr::external_memory_fd::Device::new(instance, &device);
 
         Self {
             device: device.clone(),
@@ -89,6 +122,10 @@ impl GfxDevice {
             debug_utils: vk_debug_utils_device,
             swapchain: vk_swapchain,
             push_descriptor: vk_push_descriptor,
+            mesh_shader: vk_mesh_shader,
+            draw_indirect_count: vk_draw_indirect_count,
+            #[cfg(target_os = "linux")]
+            external_memory_fd: vk_external_memory_fd,
 
             #[cfg(debug_assertions)]
             destroyed: Cell::new(false),
@@ -109,18 +146,25 @@ impl GfxDevice {
 
 // 创建过程的辅助函数
 impl GfxDevice {
-    /// 必要的 physical device core features
-    fn physical_device_basic_features() -> vk::PhysicalDeviceFeatures {
+    /// 必要的 physical device core features，以及设备支持时才开启的可选 features
+    fn physical_device_basic_features(supported: &vk::PhysicalDeviceFeatures) -> vk::PhysicalDeviceFeatures {
         vk::PhysicalDeviceFeatures::default()
             .sampler_anisotropy(true)
             .fragment_stores_and_atomics(true)
             .independent_blend(true)
             .shader_int64(true) // 用于 buffer device address
+            // 可选：一次 indirect 调用中 draw count 大于 1，不支持时 GfxCommandBuffer 会逐个发起 indirect 调用
+            .multi_draw_indirect(supported.multi_draw_indirect == vk::TRUE)
+            // 可选：indirect command 的 first_instance 可以不为 0，参见 GfxPhysicalDevice::support_draw_indirect_first_instance
+            .draw_indirect_first_instance(supported.draw_indirect_first_instance == vk::TRUE)
+            // 可选：用于虚拟纹理，参见 GfxSparseImage
+            .sparse_binding(supported.sparse_binding == vk::TRUE)
+            .sparse_residency_image2_d(supported.sparse_residency_image2_d == vk::TRUE)
     }
 
-    /// 必要的 physical device extension features
-    fn physical_device_extra_features() -> Vec<Box<dyn vk::ExtendsPhysicalDeviceFeatures2>> {
-        vec![
+    /// 必要的 physical device extension features，以及设备支持时才开启的可选 features
+    fn physical_device_extra_features(mesh_shader_supported: bool) -> Vec<Box<dyn vk::ExtendsPhysicalDeviceFeatures2>> {
+        let mut features: Vec<Box<dyn vk::ExtendsPhysicalDeviceFeatures2>> = vec![
             Box::new(vk::PhysicalDeviceDynamicRenderingFeatures::default().dynamic_rendering(true)),
             Box::new(vk::PhysicalDeviceBufferDeviceAddressFeatures::default().buffer_device_address(true)),
             Box::new(vk::PhysicalDeviceRayTracingPipelineFeaturesKHR::default().ray_tracing_pipeline(true)),
@@ -145,11 +189,23 @@ impl GfxDevice {
             Box::new(
                 vk::PhysicalDeviceUniformBufferStandardLayoutFeatures::default().uniform_buffer_standard_layout(true),
             ),
-        ]
+        ];
+
+        // 可选：task shader 和 mesh shader
+        if mesh_shader_supported {
+            features
+                .push(Box::new(vk::PhysicalDeviceMeshShaderFeaturesEXT::default().task_shader(true).mesh_shader(true)));
+        }
+
+        features
     }
 
-    /// 必要的 device extensions
-    fn basic_device_exts() -> Vec<&'static CStr> {
+    /// 必要的 device extensions，以及设备支持时才开启的可选 extensions
+    fn basic_device_exts(
+        mesh_shader_supported: bool,
+        draw_indirect_count_supported: bool,
+        external_memory_dma_buf_supported: bool,
+    ) -> Vec<&'static CStr> {
         let mut exts = vec![];
 
         // swapchain
@@ -182,6 +238,24 @@ impl GfxDevice {
         // push descriptor
         exts.push(ash::khr::push_descriptor::NAME);
 
+        // 可选：mesh shader
+        if mesh_shader_supported {
+            exts.push(ash::ext::mesh_shader::NAME);
+        }
+
+        // 可选：通过 dma-buf 与其他 API 共享 image，external_memory 已经提升到 core-1.1.0，只在 Linux 上支持
+        if external_memory_dma_buf_supported {
+            exts.append(&mut vec![
+                ash::khr::external_memory_fd::NAME,
+                ash::ext::external_memory_dma_buf::NAME,
+            ]);
+        }
+
+        // 可选：indirect count，已经提升到 core-1.2.0，通过 extension 开启就不需要单独的 Vulkan12Features
+        if draw_indirect_count_supported {
+            exts.push(ash::khr::draw_indirect_count::NAME);
+        }
+
         exts
     }
 }
@@ -212,6 +286,19 @@ impl GfxDevice {
     pub fn swapchain(&self) -> &ash::khr::swapchain::Device {
         &self.swapchain
     }
+    #[inline]
+    pub fn mesh_shader(&self) -> &ash::ext::mesh_shader::Device {
+        &self.mesh_shader
+    }
+    #[inline]
+    pub fn draw_indirect_count(&self) -> &ash::khr::draw_indirect_count::Device {
+        &self.draw_indirect_count
+    }
+    #[cfg(target_os = "linux")]
+    #[inline]
+    pub fn external_memory_fd(&self) -> &ash::khr::external_memory_fd::Device {
+        &self.external_memory_fd
+    }
 }
 
 // tools
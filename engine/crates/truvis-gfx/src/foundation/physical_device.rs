@@ -10,7 +10,7 @@ pub struct GfxPhysicalDevice {
     pub(crate) vk_handle: vk::PhysicalDevice,
 
     /// 当前 gpu 支持的 features
-    pub(crate) _features: vk::PhysicalDeviceFeatures,
+    pub(crate) features: vk::PhysicalDeviceFeatures,
 
     /// 当前 gpu 支持的 device extensions
     pub(crate) _device_extensions: Vec<vk::ExtensionProperties>,
@@ -24,7 +24,17 @@ pub struct GfxPhysicalDevice {
     /// 当前 gpu 的加速结构属性
     pub(crate) _acc_struct_props: vk::PhysicalDeviceAccelerationStructurePropertiesKHR<'static>,
 
-    pub(crate) _mem_props: vk::PhysicalDeviceMemoryProperties,
+    /// 是否支持 task shader 和 mesh shader（VK_EXT_mesh_shader）
+    pub(crate) mesh_shader_supported: bool,
+    /// 当前 gpu 的 mesh shader 属性，不支持 mesh shader 时为默认值
+    pub(crate) mesh_shader_props: vk::PhysicalDeviceMeshShaderPropertiesEXT<'static>,
+
+    /// 是否支持从 buffer 中读取 indirect draw 的数量（VK_KHR_draw_indirect_count）
+    pub(crate) draw_indirect_count_supported: bool,
+    /// 是否支持将内存导出为 dma-buf（VK_KHR_external_memory_fd + VK_EXT_external_memory_dma_buf），仅 Linux
+    pub(crate) external_memory_dma_buf_supported: bool,
+
+    pub(crate) mem_props: vk::PhysicalDeviceMemoryProperties,
 
     pub(crate) gfx_queue_family: GfxQueueFamily,
     pub(crate) compute_queue_family: Option<GfxQueueFamily>,
@@ -89,6 +99,39 @@ impl GfxPhysicalDevice {
                 .join("\n");
             log::trace!("physical device supports extensions: {}", device_extension_strs);
 
+            // mesh shader 是可选的，需要同时支持 extension 和 task/mesh 两个 feature
+            let mesh_shader_ext_supported = device_extensions
+                .iter()
+                .any(|ext| CStr::from_ptr(ext.extension_name.as_ptr()) == ash::ext::mesh_shader::NAME);
+            let mut mesh_shader_supported = false;
+            let mut mesh_shader_props = vk::PhysicalDeviceMeshShaderPropertiesEXT::default();
+            if mesh_shader_ext_supported {
+                let mut mesh_shader_features = vk::PhysicalDeviceMeshShaderFeaturesEXT::default();
+                let mut features2 = vk::PhysicalDeviceFeatures2::default().push_next(&mut mesh_shader_features);
+                instance.get_physical_device_features2(pdevice, &mut features2);
+                mesh_shader_supported =
+                    mesh_shader_features.task_shader == vk::TRUE && mesh_shader_features.mesh_shader == vk::TRUE;
+
+                let mut props2 = vk::PhysicalDeviceProperties2::default().push_next(&mut mesh_shader_props);
+                instance.get_physical_device_properties2(pdevice, &mut props2);
+                mesh_shader_props.p_next = null_mut();
+            }
+            log::info!("physical device supports mesh shader: {}", mesh_shader_supported);
+
+            // indirect count 是可选的，已经提升到 core-1.2.0，这里通过 extension 开启
+            let draw_indirect_count_supported = device_extensions
+                .iter()
+                .any(|ext| CStr::from_ptr(ext.extension_name.as_ptr()) == ash::khr::draw_indirect_count::NAME);
+            log::info!("physical device supports draw indirect count: {}", draw_indirect_count_supported);
+
+            // dma-buf 导出是可选的，只在 Linux 上开启
+            let has_ext =
+                |name: &CStr| device_extensions.iter().any(|ext| CStr::from_ptr(ext.extension_name.as_ptr()) == name);
+            let external_memory_dma_buf_supported = cfg!(target_os = "linux")
+                && has_ext(ash::khr::external_memory_fd::NAME)
+                && has_ext(ash::ext::external_memory_dma_buf::NAME);
+            log::info!("physical device supports dma-buf export: {}", external_memory_dma_buf_supported);
+
             // 找到所有的队列信息并打印出来
 
             let props_cnt = instance.get_physical_device_queue_family_properties2_len(pdevice);
@@ -110,6 +153,7 @@ impl GfxPhysicalDevice {
                         queue_family_index: family_idx as u32,
                         queue_flags: props.queue_family_properties.queue_flags,
                         queue_count: props.queue_family_properties.queue_count,
+                        timestamp_valid_bits: props.queue_family_properties.timestamp_valid_bits,
                     })
             };
 
@@ -136,12 +180,16 @@ impl GfxPhysicalDevice {
             );
 
             Self {
-                _mem_props: instance.get_physical_device_memory_properties(pdevice),
-                _features: instance.get_physical_device_features(pdevice),
+                mem_props: instance.get_physical_device_memory_properties(pdevice),
+                features: instance.get_physical_device_features(pdevice),
                 vk_handle: pdevice,
                 basic_props,
                 rt_pipeline_props: rt_props,
                 _acc_struct_props: acc_props,
+                mesh_shader_supported,
+                mesh_shader_props,
+                draw_indirect_count_supported,
+                external_memory_dma_buf_supported,
                 gfx_queue_family,
                 compute_queue_family,
                 transfer_queue_family,
@@ -159,6 +207,61 @@ impl GfxPhysicalDevice {
     pub fn is_descrete_gpu(&self) -> bool {
         self.basic_props.device_type == vk::PhysicalDeviceType::DISCRETE_GPU
     }
+
+    /// 是否支持 2D 的 sparse residency image，并且 gfx queue 可以执行 sparse binding
+    pub fn support_sparse_residency_image_2d(&self) -> bool {
+        self.features.sparse_binding == vk::TRUE
+            && self.features.sparse_residency_image2_d == vk::TRUE
+            && self.gfx_queue_family.queue_flags.contains(vk::QueueFlags::SPARSE_BINDING)
+    }
+
+    /// 是否支持 task shader 和 mesh shader，参见 `GfxMeshShaderPipeline`
+    #[inline]
+    pub fn support_mesh_shader(&self) -> bool {
+        self.mesh_shader_supported
+    }
+
+    /// 一次 indirect 调用中的 draw count 是否可以大于 1，参见 `GfxCommandBuffer::cmd_draw_indexed_indirect`
+    #[inline]
+    pub fn support_multi_draw_indirect(&self) -> bool {
+        self.features.multi_draw_indirect == vk::TRUE
+    }
+
+    /// indirect command 的 `first_instance` 是否可以不为 0；不支持时只能为 0
+    #[inline]
+    pub fn support_draw_indirect_first_instance(&self) -> bool {
+        self.features.draw_indirect_first_instance == vk::TRUE
+    }
+
+    /// 是否支持从 buffer 中读取 indirect draw 的数量，参见 `GfxCommandBuffer::cmd_draw_indexed_indirect_count`
+    #[inline]
+    pub fn support_draw_indirect_count(&self) -> bool {
+        self.draw_indirect_count_supported
+    }
+
+    /// 是否可以将内存导出为 dma-buf，参见 `GfxExternalImage`
+    #[inline]
+    pub fn support_external_memory_dma_buf(&self) -> bool {
+        self.external_memory_dma_buf_supported
+    }
+
+    /// 是否可以在 gfx queue 上写入 timestamp，参见 `GfxGpuTimer`
+    pub fn support_timestamp(&self) -> bool {
+        self.basic_props.limits.timestamp_compute_and_graphics == vk::TRUE
+            && self.gfx_queue_family.timestamp_valid_bits > 0
+    }
+
+    /// timestamp 每增加 1 所经过的纳秒数
+    #[inline]
+    pub fn timestamp_period_ns(&self) -> f32 {
+        self.basic_props.limits.timestamp_period
+    }
+
+    /// 显存堆是否位于 GPU 本地
+    #[inline]
+    pub fn is_device_local_heap(&self, heap_index: u32) -> bool {
+        self.mem_props.memory_heaps[heap_index as usize].flags.contains(vk::MemoryHeapFlags::DEVICE_LOCAL)
+    }
 }
 
 impl DebugType for GfxPhysicalDevice {
@@ -1,10 +1,20 @@
+use std::cell::OnceCell;
 use std::ffi::CStr;
+use std::path::PathBuf;
 
 use ash::vk;
 
 use crate::gfx_core::GfxCore;
+use crate::pipelines::hot_reload::GfxPipelineReloader;
+use crate::pipelines::pipeline_cache::GfxPipelineCache;
+#[cfg(debug_assertions)]
+use crate::resources::buffer_tracker::{GfxBufferRecord, GfxBufferTracker};
+use crate::resources::resource_stats::GfxResourceStats;
+use crate::swapchain::surface::GfxSurface;
+use crate::swapchain::surface_info::GfxSurfaceInfo;
 use crate::{
     commands::{
+        async_transfer::GfxAsyncTransfer,
         command_buffer::GfxCommandBuffer,
         command_pool::GfxCommandPool,
         command_queue::{GfxCommandQueue, GfxQueueFamily},
@@ -22,7 +32,7 @@ use crate::{
 ///
 /// # 初始化流程
 /// ```ignore
-/// Gfx::init("MyApp".to_string(), extra_extensions);
+/// Gfx::init("MyApp".to_string(), extra_extensions, None);
 /// let device = Gfx::get().gfx_device();
 /// // 使用...
 /// Gfx::destroy();
@@ -33,6 +43,23 @@ pub struct Gfx {
 
     /// 临时的 graphics command pool，主要用于临时的命令缓冲区
     pub(crate) temp_graphics_command_pool: GfxCommandPool,
+
+    /// 全局共享的 pipeline cache，所有 pipeline 的创建都应该使用它
+    pub(crate) pipeline_cache: GfxPipelineCache,
+    /// pipeline cache 在磁盘上的位置：初始化时从这里加载，销毁时写回
+    pub(crate) pipeline_cache_path: Option<PathBuf>,
+    /// 所有可以热重载的 pipeline，创建时自动注册
+    pub(crate) pipeline_reloader: GfxPipelineReloader,
+
+    /// 基于 transfer queue 的异步上传，第一次使用时创建（创建过程依赖单例）
+    pub(crate) async_transfer: OnceCell<GfxAsyncTransfer>,
+
+    /// 记录所有存活 buffer 的创建信息，仅 debug build
+    #[cfg(debug_assertions)]
+    pub(crate) buffer_tracker: GfxBufferTracker,
+
+    /// 各类资源的存活数量
+    pub(crate) resource_stats: GfxResourceStats,
 }
 
 // 创建与销毁
@@ -40,7 +67,7 @@ impl Gfx {
     // region init 相关
     const ENGINE_NAME: &'static str = "DruvisIII";
 
-    fn new(app_name: String, instance_extra_exts: Vec<&'static CStr>) -> Self {
+    fn new(app_name: String, instance_extra_exts: Vec<&'static CStr>, pipeline_cache_path: Option<PathBuf>) -> Self {
         let _span = tracy_client::span!("Gfx::new");
 
         let gfx_core = GfxCore::new(app_name, Self::ENGINE_NAME.to_string(), instance_extra_exts);
@@ -60,10 +87,27 @@ impl Gfx {
             &gfx_core.gfx_device,
         );
 
+        let pipeline_cache = match &pipeline_cache_path {
+            Some(path) => GfxPipelineCache::load_internal(
+                &gfx_core.gfx_device,
+                &gfx_core.physical_device,
+                path,
+                "global-pipeline-cache",
+            ),
+            None => GfxPipelineCache::new_internal(&gfx_core.gfx_device, &[], "global-pipeline-cache"),
+        };
+
         Self {
             gfx_core,
             vm_allocator: allocator,
             temp_graphics_command_pool: gfx_command_pool,
+            pipeline_cache,
+            pipeline_cache_path,
+            pipeline_reloader: GfxPipelineReloader::default(),
+            async_transfer: OnceCell::new(),
+            #[cfg(debug_assertions)]
+            buffer_tracker: GfxBufferTracker::default(),
+            resource_stats: GfxResourceStats::default(),
         }
     }
 }
@@ -92,23 +136,36 @@ impl Gfx {
         }
     }
 
+    /// 单例是否已经初始化
+    ///
+    /// # Safety
+    /// 此方法仅在单线程环境下安全
+    #[inline]
+    pub fn is_initialized() -> bool {
+        unsafe {
+            let ptr = std::ptr::addr_of!(G_GFX);
+            (*ptr).is_some()
+        }
+    }
+
     /// 初始化 RenderContext 单例
     ///
     /// # Parameters
     /// - `app_name`: 应用程序名称
     /// - `instance_extra_exts`: 额外的 Vulkan 实例扩展
+    /// - `pipeline_cache_path`: pipeline cache 的持久化路径，为 None 时不读写磁盘
     ///
     /// # Panics
     /// 如果 RenderContext 已经被初始化，此方法会 panic
     ///
     /// # Safety
     /// 此方法仅在单线程环境下安全
-    pub fn init(app_name: String, instance_extra_exts: Vec<&'static CStr>) {
+    pub fn init(app_name: String, instance_extra_exts: Vec<&'static CStr>, pipeline_cache_path: Option<PathBuf>) {
         unsafe {
             // 使用 addr_of_mut! 避免直接对 static mut 创建可变引用
             let ptr = std::ptr::addr_of_mut!(G_GFX);
             assert!((*ptr).is_none(), "RenderContext already initialized");
-            *ptr = Some(Self::new(app_name, instance_extra_exts));
+            *ptr = Some(Self::new(app_name, instance_extra_exts, pipeline_cache_path));
         }
     }
 
@@ -121,10 +178,28 @@ impl Gfx {
         unsafe {
             // 使用 addr_of_mut! 避免直接对 static mut 创建可变引用
             let ptr = std::ptr::addr_of_mut!(G_GFX);
+
+            // 异步上传持有的 buffer 等资源在销毁时需要访问单例，因此先于单例销毁
+            let async_transfer = (*ptr).as_mut().and_then(|context| context.async_transfer.take());
+            if let Some(async_transfer) = async_transfer {
+                async_transfer.destroy();
+            }
+
             let context = (*ptr).take().expect("RenderContext not initialized");
 
+            // 此时仍然存活的 buffer 都是疑似泄漏
+            #[cfg(debug_assertions)]
+            if !context.buffer_tracker.live_buffers().is_empty() {
+                log::warn!("some GfxBuffers are still alive when destroying Gfx");
+                context.buffer_tracker.dump();
+            }
+
             context.vm_allocator.destroy();
             context.temp_graphics_command_pool.destroy_internal(&context.gfx_core.gfx_device);
+            if let Some(path) = &context.pipeline_cache_path {
+                context.pipeline_cache.save_internal(&context.gfx_core.gfx_device, path);
+            }
+            context.pipeline_cache.destroy_internal(&context.gfx_core.gfx_device);
             context.gfx_core.destroy();
         }
     }
@@ -152,6 +227,22 @@ impl Gfx {
         &self.vm_allocator
     }
 
+    #[inline]
+    pub fn pipeline_cache(&self) -> &GfxPipelineCache {
+        &self.pipeline_cache
+    }
+
+    #[inline]
+    pub fn pipeline_reloader(&self) -> &GfxPipelineReloader {
+        &self.pipeline_reloader
+    }
+
+    /// 各类资源的存活数量
+    #[inline]
+    pub fn resource_stats(&self) -> &GfxResourceStats {
+        &self.resource_stats
+    }
+
     #[inline]
     pub fn physical_device(&self) -> &GfxPhysicalDevice {
         &self.gfx_core.physical_device
@@ -167,9 +258,10 @@ impl Gfx {
         self.gfx_core.physical_device.compute_queue_family.as_ref().unwrap().clone()
     }
 
+    /// transfer queue 实际所属的 queue family，没有专用 transfer queue 时为 graphics queue family
     #[inline]
     pub fn transfer_queue_family(&self) -> GfxQueueFamily {
-        self.gfx_core.physical_device.transfer_queue_family.as_ref().unwrap().clone()
+        self.gfx_core.transfer_queue.queue_family.clone()
     }
 
     #[inline]
@@ -177,11 +269,25 @@ impl Gfx {
         &self.gfx_core.gfx_queue
     }
 
+    /// 优先来自专用的 transfer queue family，没有时回退到 graphics queue family
     #[inline]
     pub fn transfer_queue(&self) -> &GfxCommandQueue {
         &self.gfx_core.transfer_queue
     }
 
+    /// transfer queue 是否来自专用的 transfer queue family
+    #[inline]
+    pub fn has_dedicated_transfer_queue(&self) -> bool {
+        self.gfx_core.transfer_queue.queue_family.queue_family_index
+            != self.gfx_core.gfx_queue.queue_family.queue_family_index
+    }
+
+    /// 基于 transfer queue 的异步上传，参见 [`GfxAsyncTransfer`]
+    #[inline]
+    pub fn async_transfer(&self) -> &GfxAsyncTransfer {
+        self.async_transfer.get_or_init(GfxAsyncTransfer::new)
+    }
+
     /// 当 uniform buffer 的 descriptor 在更新时，其 offset 必须是这个值的整数倍
     ///
     /// 注：这个值一定是 power of 2
@@ -194,6 +300,12 @@ impl Gfx {
     pub fn rt_pipeline_props(&self) -> &vk::PhysicalDeviceRayTracingPipelinePropertiesKHR<'_> {
         &self.gfx_core.physical_device.rt_pipeline_props
     }
+
+    /// 不支持 mesh shader 时为默认值，参见 [`GfxPhysicalDevice::support_mesh_shader`]
+    #[inline]
+    pub fn mesh_shader_props(&self) -> &vk::PhysicalDeviceMeshShaderPropertiesEXT<'_> {
+        &self.gfx_core.physical_device.mesh_shader_props
+    }
 }
 
 // tools
@@ -223,6 +335,20 @@ impl Gfx {
             .collect()
     }
 
+    /// 查询 surface 在当前物理设备上支持的 format、present mode、image 数量、变换等全部能力
+    pub fn surface_capabilities(&self, surface: &GfxSurface) -> GfxSurfaceInfo {
+        let physical_device = self.physical_device().vk_handle;
+        unsafe {
+            let capabilities =
+                surface.pf.get_physical_device_surface_capabilities(physical_device, surface.handle).unwrap();
+            let formats = surface.pf.get_physical_device_surface_formats(physical_device, surface.handle).unwrap();
+            let present_modes =
+                surface.pf.get_physical_device_surface_present_modes(physical_device, surface.handle).unwrap();
+
+            GfxSurfaceInfo::new(&capabilities, formats, present_modes)
+        }
+    }
+
     /// 立即执行某个 command，并同步等待执行结果
     pub fn one_time_exec<F, R>(&self, func: F, name: impl AsRef<str>) -> R
     where
@@ -238,14 +364,28 @@ impl Gfx {
         let command_buffer_clone = command_buffer.clone();
         self.gfx_queue().submit(vec![GfxSubmitInfo::new(&[command_buffer_clone])], None);
         self.gfx_queue().wait_idle();
-        unsafe {
-            self.gfx_device()
-                .free_command_buffers(self.temp_graphics_command_pool.handle(), &[command_buffer.vk_handle()]);
-        }
+        self.temp_graphics_command_pool.free_command_buffers(vec![command_buffer]);
 
         result
     }
 
+    /// 按大小降序输出所有存活的 GfxBuffer，包括名称、usage 以及创建时的调用栈
+    ///
+    /// 仅在 debug build 下有效；调用栈需要设置 `RUST_BACKTRACE=1`
+    pub fn dump_buffers(&self) {
+        #[cfg(debug_assertions)]
+        self.buffer_tracker.dump();
+
+        #[cfg(not(debug_assertions))]
+        log::warn!("Gfx::dump_buffers is only available in debug build");
+    }
+
+    /// 所有存活的 GfxBuffer，按大小降序排列
+    #[cfg(debug_assertions)]
+    pub fn live_buffers(&self) -> Vec<GfxBufferRecord> {
+        self.buffer_tracker.live_buffers()
+    }
+
     pub fn wait_idel(&self) {
         unsafe {
             self.gfx_device().device_wait_idle().unwrap();
@@ -48,26 +48,43 @@ impl GfxCore {
         // Nvidia 使用的是 Unified Scheduler，因此 Graphics 和 Compute 并没法做到真正的并行
         // Graphics 和 Compute 会争夺 SM，L2 以及显存
         // 驱动层给出了专用的 compute queue family，但是底层硬件资源依然是共享的
-        // Transfer(DMA) 可以做到部分并行，不过为了简化设计，仍然然使用同一个 queue family
+        // Transfer(DMA) 可以做到部分并行，因此优先使用专用的 transfer queue family 做异步上传
 
-        // 尝试从 Graphics Queue Family 中申请两个队列，一个用于 Graphics，一个用于 Transfer
         let gfx_family_idx = physical_device.gfx_queue_family.queue_family_index;
-        let max_queues = physical_device.gfx_queue_family.queue_count;
-
-        if max_queues < 2 {
-            panic!(
-                "Graphics queue family has {} queues, but at least 2 are required for separate Graphics and Transfer queues.",
-                max_queues
+        let priorities = [1.0_f32; 2];
+
+        // transfer queue 的来源：
+        // 1. 专用的 transfer queue family
+        // 2. 回退：graphics queue family 中的第二个队列
+        // 3. 回退：graphics queue 本身
+        let transfer_queue_family =
+            physical_device.transfer_queue_family.clone().unwrap_or_else(|| physical_device.gfx_queue_family.clone());
+        let has_dedicated_transfer_family = physical_device.transfer_queue_family.is_some();
+        let gfx_family_queue_count =
+            if has_dedicated_transfer_family { 1 } else { physical_device.gfx_queue_family.queue_count.min(2) };
+
+        let mut queue_create_infos = vec![
+            vk::DeviceQueueCreateInfo::default()
+                .queue_family_index(gfx_family_idx)
+                .queue_priorities(&priorities[..gfx_family_queue_count as usize]),
+        ];
+        if has_dedicated_transfer_family {
+            queue_create_infos.push(
+                vk::DeviceQueueCreateInfo::default()
+                    .queue_family_index(transfer_queue_family.queue_family_index)
+                    .queue_priorities(&priorities[..1]),
             );
         }
 
-        let request_queue_count = 2;
-        let priorities = vec![1.0; request_queue_count as usize];
-
-        let queue_create_infos =
-            [vk::DeviceQueueCreateInfo::default().queue_family_index(gfx_family_idx).queue_priorities(&priorities)];
-
-        let device = Rc::new(GfxDevice::new(&instance.ash_instance, physical_device.vk_handle, &queue_create_infos));
+        let device = Rc::new(GfxDevice::new(
+            &instance.ash_instance,
+            physical_device.vk_handle,
+            &physical_device.features,
+            physical_device.mesh_shader_supported,
+            physical_device.draw_indirect_count_supported,
+            physical_device.external_memory_dma_buf_supported,
+            &queue_create_infos,
+        ));
 
         let gfx_queue = GfxCommandQueue {
             vk_queue: unsafe { device.get_device_queue(gfx_family_idx, 0) },
@@ -75,9 +92,10 @@ impl GfxCore {
             gfx_device: device.clone(),
         };
 
+        let transfer_queue_idx = if has_dedicated_transfer_family { 0 } else { gfx_family_queue_count - 1 };
         let transfer_queue = GfxCommandQueue {
-            vk_queue: unsafe { device.get_device_queue(gfx_family_idx, 1) },
-            queue_family: physical_device.gfx_queue_family.clone(),
+            vk_queue: unsafe { device.get_device_queue(transfer_queue_family.queue_family_index, transfer_queue_idx) },
+            queue_family: transfer_queue_family,
             gfx_device: device.clone(),
         };
 
@@ -0,0 +1,218 @@
+use std::ffi::{CStr, CString};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use ash::vk;
+
+use crate::foundation::debug_messenger::DebugType;
+use crate::gfx::Gfx;
+use crate::pipelines::graphics_pipeline::GfxPipelineLayout;
+use crate::pipelines::hot_reload::{GfxPipelineHandle, GfxReloadablePipeline};
+use crate::pipelines::shader::GfxShaderModule;
+use crate::pipelines::specialization::GfxSpecializationMap;
+
+/// compute pipeline 的创建参数
+///
+/// # 使用示例
+/// ```ignore
+/// let mut create_info = GfxComputePipelineCreateInfo::new("shader/.build/hiz.slang.spv", c"main");
+/// create_info
+///     .descriptor_set_layouts(global_descriptor_sets.global_set_layouts())
+///     .push_constant_ranges(vec![push_constant_range])
+///     .workgroup_size(glam::uvec3(8, 8, 1));
+/// let pipeline = GfxComputePipeline::new(&create_info, "hiz");
+/// ```
+#[derive(Clone)]
+pub struct GfxComputePipelineCreateInfo {
+    shader_path: PathBuf,
+    entry_point: CString,
+
+    descriptor_set_layouts: Vec<vk::DescriptorSetLayout>,
+    push_constant_ranges: Vec<vk::PushConstantRange>,
+
+    specialization: GfxSpecializationMap,
+}
+// new & init
+impl GfxComputePipelineCreateInfo {
+    /// workgroup size 的 x, y, z 依次使用的 specialization constant id，参见 [`Self::workgroup_size`]
+    pub const WORKGROUP_SIZE_CONSTANT_IDS: [u32; 3] = [0, 1, 2];
+
+    pub fn new(shader_path: impl Into<PathBuf>, entry_point: &CStr) -> Self {
+        Self {
+            shader_path: shader_path.into(),
+            entry_point: entry_point.to_owned(),
+            descriptor_set_layouts: vec![],
+            push_constant_ranges: vec![],
+            specialization: GfxSpecializationMap::new(),
+        }
+    }
+}
+// builder
+impl GfxComputePipelineCreateInfo {
+    /// builder
+    #[inline]
+    pub fn descriptor_set_layouts(&mut self, layouts: Vec<vk::DescriptorSetLayout>) -> &mut Self {
+        self.descriptor_set_layouts = layouts;
+        self
+    }
+
+    /// builder
+    #[inline]
+    pub fn push_constant_ranges(&mut self, ranges: Vec<vk::PushConstantRange>) -> &mut Self {
+        self.push_constant_ranges = ranges;
+        self
+    }
+
+    /// builder
+    ///
+    /// 设置一个 32 位的 specialization constant，同一个 `constant_id` 设置多次时以最后一次为准
+    pub fn specialization_constant(&mut self, constant_id: u32, value: u32) -> &mut Self {
+        self.specialization.extend(&GfxSpecializationMap::new().set_u32(constant_id, value));
+        self
+    }
+
+    /// builder
+    ///
+    /// 合并 `specialization` 中的所有常量，相同的 `constant_id` 以后设置的为准
+    pub fn specialization(&mut self, specialization: GfxSpecializationMap) -> &mut Self {
+        self.specialization.extend(&specialization);
+        self
+    }
+
+    /// builder
+    ///
+    /// 通过 specialization constant 指定 workgroup size，
+    /// shader 中需要使用 [`Self::WORKGROUP_SIZE_CONSTANT_IDS`] 声明对应的常量，例如 glsl 中的
+    /// `layout(local_size_x_id = 0, local_size_y_id = 1, local_size_z_id = 2) in;`
+    pub fn workgroup_size(&mut self, size: glam::UVec3) -> &mut Self {
+        let [x_id, y_id, z_id] = Self::WORKGROUP_SIZE_CONSTANT_IDS;
+        self.specialization_constant(x_id, size.x)
+            .specialization_constant(y_id, size.y)
+            .specialization_constant(z_id, size.z)
+    }
+}
+// getter
+impl GfxComputePipelineCreateInfo {
+    #[inline]
+    pub fn shader_path(&self) -> &Path {
+        &self.shader_path
+    }
+
+    #[inline]
+    pub fn entry_point(&self) -> &CStr {
+        &self.entry_point
+    }
+}
+
+/// compute 管线，持有自己的 pipeline layout
+///
+/// 绑定时使用 `vk::PipelineBindPoint::COMPUTE`，通过 [`GfxCommandBuffer::cmd_dispatch`] 或
+/// [`GfxCommandBuffer::cmd_dispatch_indirect`] 执行
+///
+/// [`GfxCommandBuffer::cmd_dispatch`]: crate::commands::command_buffer::GfxCommandBuffer::cmd_dispatch
+/// [`GfxCommandBuffer::cmd_dispatch_indirect`]: crate::commands::command_buffer::GfxCommandBuffer::cmd_dispatch_indirect
+pub struct GfxComputePipeline {
+    /// 依赖的 spv 发生变化时原地重建，参见 [`GfxPipelineReloader`]
+    ///
+    /// [`GfxPipelineReloader`]: crate::pipelines::hot_reload::GfxPipelineReloader
+    pipeline: Rc<GfxReloadablePipeline<GfxPipelineHandle>>,
+
+    /// 与 graphics pipeline 保持一致，pipeline layout 可以被共享
+    pipeline_layout: Rc<GfxPipelineLayout>,
+}
+// new & init
+impl GfxComputePipeline {
+    /// # Panics
+    /// spv 无法加载或者 pipeline 创建失败
+    pub fn new(create_info: &GfxComputePipelineCreateInfo, debug_name: &str) -> Self {
+        Self::try_new(create_info, debug_name).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// spv 无法加载或者 pipeline 创建失败时返回错误，用于允许失败的场合
+    pub fn try_new(create_info: &GfxComputePipelineCreateInfo, debug_name: &str) -> Result<Self, String> {
+        let pipeline_layout = Rc::new(GfxPipelineLayout::new(
+            &create_info.descriptor_set_layouts,
+            &create_info.push_constant_ranges,
+            debug_name,
+        ));
+
+        let debug_name = format!("{}::{}", Self::debug_type_name(), debug_name);
+        let create = {
+            let create_info = create_info.clone();
+            let pipeline_layout = pipeline_layout.clone();
+            let debug_name = debug_name.clone();
+            move || {
+                let pipeline = Self::create_vk_pipeline(&create_info, pipeline_layout.handle())?;
+                Gfx::get().gfx_device().set_object_debug_name(pipeline, &debug_name);
+                Ok(GfxPipelineHandle(pipeline))
+            }
+        };
+        let pipeline = create()?;
+
+        Ok(Self {
+            pipeline: GfxReloadablePipeline::new(pipeline, [&create_info.shader_path], &debug_name, create),
+            pipeline_layout,
+        })
+    }
+
+    fn create_vk_pipeline(
+        create_info: &GfxComputePipelineCreateInfo,
+        pipeline_layout: vk::PipelineLayout,
+    ) -> Result<vk::Pipeline, String> {
+        let shader_module = GfxShaderModule::try_new(&create_info.shader_path)?;
+
+        let specialization_info = create_info.specialization.vk_info();
+
+        let mut stage_info = vk::PipelineShaderStageCreateInfo::default()
+            .module(shader_module.handle())
+            .stage(vk::ShaderStageFlags::COMPUTE)
+            .name(&create_info.entry_point);
+        if !create_info.specialization.is_empty() {
+            stage_info = stage_info.specialization_info(&specialization_info);
+        }
+
+        let pipeline_ci = vk::ComputePipelineCreateInfo::default().stage(stage_info).layout(pipeline_layout);
+        let pipeline = unsafe {
+            Gfx::get().gfx_device().create_compute_pipelines(
+                Gfx::get().pipeline_cache().handle(),
+                std::slice::from_ref(&pipeline_ci),
+                None,
+            )
+        };
+
+        shader_module.destroy();
+
+        match pipeline {
+            Ok(pipelines) => Ok(pipelines[0]),
+            Err((_, e)) => Err(format!("Failed to create compute pipeline {:?}: {:?}", create_info.shader_path, e)),
+        }
+    }
+}
+// getter
+impl GfxComputePipeline {
+    #[inline]
+    pub fn handle(&self) -> vk::Pipeline {
+        self.pipeline.current().0
+    }
+
+    #[inline]
+    pub fn layout(&self) -> vk::PipelineLayout {
+        self.pipeline_layout.handle()
+    }
+}
+// destroy
+impl GfxComputePipeline {
+    #[inline]
+    pub fn destroy(self) {
+        // drop
+    }
+}
+impl DebugType for GfxComputePipeline {
+    fn debug_type_name() -> &'static str {
+        "GfxComputePipeline"
+    }
+
+    fn vk_handle(&self) -> impl vk::Handle {
+        self.handle()
+    }
+}
@@ -1,10 +1,11 @@
 use std::{convert::identity, ffi::CStr, rc::Rc};
 
 use ash::vk;
-use itertools::Itertools;
 
 use crate::gfx::Gfx;
+use crate::pipelines::hot_reload::{GfxPipelineHandle, GfxReloadablePipeline};
 use crate::pipelines::shader::GfxShaderModuleCache;
+use crate::pipelines::specialization::GfxSpecializationMap;
 use crate::{foundation::debug_messenger::DebugType, pipelines::shader::GfxShaderStageInfo};
 
 /// 管线布局封装
@@ -57,7 +58,10 @@ impl DebugType for GfxPipelineLayout {
 }
 
 pub struct GfxGraphicsPipeline {
-    pipeline: vk::Pipeline,
+    /// 依赖的 spv 发生变化时原地重建，参见 [`GfxPipelineReloader`]
+    ///
+    /// [`GfxPipelineReloader`]: crate::pipelines::hot_reload::GfxPipelineReloader
+    pipeline: Rc<GfxReloadablePipeline<GfxPipelineHandle>>,
 
     /// 因为多个 pipeline 可以使用同一个 pipeline layout，所以这里使用 Rc
     pipeline_layout: Rc<GfxPipelineLayout>,
@@ -68,88 +72,16 @@ impl GfxGraphicsPipeline {
         pipeline_layout: Rc<GfxPipelineLayout>,
         debug_name: &str,
     ) -> Self {
-        // dynamic rendering 需要的 framebuffer 信息
-        let mut attach_info = vk::PipelineRenderingCreateInfo::default()
-            .color_attachment_formats(&create_info.color_attach_formats)
-            .depth_attachment_format(create_info.depth_attach_format)
-            .stencil_attachment_format(create_info.stencil_attach_format);
-
-        let mut shader_modules_cache = GfxShaderModuleCache::new();
-        let shader_stages_info = create_info
-            .shader_stages
-            .iter()
-            .map(|stage| {
-                vk::PipelineShaderStageCreateInfo::default()
-                    .stage(stage.stage)
-                    .module(shader_modules_cache.get_or_load(stage.path()).handle())
-                    .name(stage.entry_point)
-            })
-            .collect_vec();
-
-        // 顶点和 index
-        let vertex_input_state_info = vk::PipelineVertexInputStateCreateInfo::default()
-            .vertex_binding_descriptions(&create_info.vertex_binding_desc)
-            .vertex_attribute_descriptions(&create_info.vertex_attribute_desec);
-
-        let input_assembly_info = vk::PipelineInputAssemblyStateCreateInfo::default()
-            .topology(create_info.primitive_topology)
-            .primitive_restart_enable(false);
-
-        // viewport 和 scissor 具体值由 dynamic 决定，但是数量由该 create info 决定
-        let viewport_info = vk::PipelineViewportStateCreateInfo {
-            viewport_count: 1,
-            scissor_count: 1,
-            ..Default::default()
-        };
-
-        // MSAA 配置
-        let msaa_info = vk::PipelineMultisampleStateCreateInfo::default()
-            .sample_shading_enable(create_info.enable_sample_shading)
-            .rasterization_samples(create_info.msaa_sample);
-
-        // 混合设置：需要为每个 color attachment 分别指定
-        let color_blend_info = create_info.blend_info.attachments(&create_info.color_attach_blend_states);
-
-        let dynamic_state_info =
-            vk::PipelineDynamicStateCreateInfo::default().dynamic_states(&create_info.dynamic_states);
-
-        // =======================================
-        // === 创建 pipeline
-
-        let pipeline_info = vk::GraphicsPipelineCreateInfo::default()
-            .stages(&shader_stages_info)
-            .vertex_input_state(&vertex_input_state_info)
-            .input_assembly_state(&input_assembly_info)
-            .viewport_state(&viewport_info)
-            .rasterization_state(&create_info.rasterize_state_info)
-            .multisample_state(&msaa_info)
-            .color_blend_state(&color_blend_info)
-            .depth_stencil_state(&create_info.depth_stencil_info)
-            .layout(pipeline_layout.handle)
-            .dynamic_state(&dynamic_state_info)
-            .push_next(&mut attach_info);
-
-        let gfx_device = Gfx::get().gfx_device();
-        let pipeline = unsafe {
-            gfx_device
-                .create_graphics_pipelines(vk::PipelineCache::null(), std::slice::from_ref(&pipeline_info), None)
-                .unwrap()[0]
-        };
-        let pipeline = GfxGraphicsPipeline {
-            pipeline,
+        GfxGraphicsPipeline {
+            pipeline: create_info
+                .create_reloadable_pipeline(&pipeline_layout, &format!("{}::{}", Self::debug_type_name(), debug_name)),
             pipeline_layout,
-        };
-
-        gfx_device.set_debug_name(&pipeline, debug_name);
-
-        shader_modules_cache.destroy();
-
-        pipeline
+        }
     }
 
     #[inline]
     pub fn handle(&self) -> vk::Pipeline {
-        self.pipeline
+        self.pipeline.current().0
     }
 
     #[inline]
@@ -162,23 +94,17 @@ impl GfxGraphicsPipeline {
         // drop
     }
 }
-impl Drop for GfxGraphicsPipeline {
-    fn drop(&mut self) {
-        unsafe {
-            Gfx::get().gfx_device().destroy_pipeline(self.pipeline, None);
-        }
-    }
-}
 impl DebugType for GfxGraphicsPipeline {
     fn debug_type_name() -> &'static str {
         "GfxGraphicsPipeline"
     }
 
     fn vk_handle(&self) -> impl vk::Handle {
-        self.pipeline
+        self.handle()
     }
 }
 
+#[derive(Clone)]
 pub struct GfxGraphicsPipelineCreateInfo {
     /// dynamic render 需要的 framebuffer 信息
     color_attach_formats: Vec<vk::Format>,
@@ -193,6 +119,8 @@ pub struct GfxGraphicsPipelineCreateInfo {
     vertex_attribute_desec: Vec<vk::VertexInputAttributeDescription>,
 
     primitive_topology: vk::PrimitiveTopology,
+    /// 开启后 index 为最大值（u32 为 `0xFFFFFFFF`）时会断开当前的 strip / fan
+    primitive_restart_enable: bool,
 
     rasterize_state_info: vk::PipelineRasterizationStateCreateInfo<'static>,
 
@@ -205,6 +133,9 @@ pub struct GfxGraphicsPipelineCreateInfo {
     depth_stencil_info: vk::PipelineDepthStencilStateCreateInfo<'static>,
 
     dynamic_states: Vec<vk::DynamicState>,
+
+    /// 所有 stage 共用
+    specialization: GfxSpecializationMap,
 }
 impl Default for GfxGraphicsPipelineCreateInfo {
     fn default() -> Self {
@@ -221,6 +152,7 @@ impl Default for GfxGraphicsPipelineCreateInfo {
             vertex_attribute_desec: vec![],
 
             primitive_topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+            primitive_restart_enable: false,
 
             rasterize_state_info: vk::PipelineRasterizationStateCreateInfo::default()
                 .depth_clamp_enable(false)
@@ -246,6 +178,7 @@ impl Default for GfxGraphicsPipelineCreateInfo {
                 .depth_bounds_test_enable(false)
                 .stencil_test_enable(false),
             dynamic_states: vec![vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR],
+            specialization: GfxSpecializationMap::new(),
         }
     }
 }
@@ -288,6 +221,36 @@ impl GfxGraphicsPipelineCreateInfo {
         self
     }
 
+    /// builder
+    ///
+    /// 仅用于 [`GfxMeshShaderPipeline`]，task shader 是可选的
+    ///
+    /// [`GfxMeshShaderPipeline`]: crate::pipelines::mesh_shader_pipeline::GfxMeshShaderPipeline
+    #[inline]
+    pub fn task_shader_stage(&mut self, path: &str, entry_point: &'static CStr) -> &mut Self {
+        self.shader_stages.push(GfxShaderStageInfo {
+            stage: vk::ShaderStageFlags::TASK_EXT,
+            entry_point,
+            path: path.to_string(),
+        });
+        self
+    }
+
+    /// builder
+    ///
+    /// 仅用于 [`GfxMeshShaderPipeline`]，替代 vertex shader
+    ///
+    /// [`GfxMeshShaderPipeline`]: crate::pipelines::mesh_shader_pipeline::GfxMeshShaderPipeline
+    #[inline]
+    pub fn mesh_shader_stage(&mut self, path: &str, entry_point: &'static CStr) -> &mut Self {
+        self.shader_stages.push(GfxShaderStageInfo {
+            stage: vk::ShaderStageFlags::MESH_EXT,
+            entry_point,
+            path: path.to_string(),
+        });
+        self
+    }
+
     #[inline]
     pub fn shader_stages(&mut self, stages: Vec<GfxShaderStageInfo>) -> &mut Self {
         self.shader_stages = stages;
@@ -308,6 +271,26 @@ impl GfxGraphicsPipelineCreateInfo {
         self
     }
 
+    /// 图元的拓扑类型，默认为 `TRIANGLE_LIST`
+    ///
+    /// - `POINT_LIST`：vertex shader 需要写入 `SV_PointSize`（`[[vk::builtin("PointSize")]]`）
+    /// - `PATCH_LIST`：需要同时包含 tessellation control 和 evaluation stage
+    /// - `*_WITH_ADJACENCY`：邻接信息只有 geometry shader 可以读取，因此需要包含 geometry stage
+    ///
+    /// 兼容性在创建 pipeline 时检查
+    #[inline]
+    pub fn primitive_topology(&mut self, topology: vk::PrimitiveTopology) -> &mut Self {
+        self.primitive_topology = topology;
+        self
+    }
+
+    /// 是否开启 primitive restart，只能用于 strip 和 fan 类型的拓扑
+    #[inline]
+    pub fn primitive_restart(&mut self, enable: bool) -> &mut Self {
+        self.primitive_restart_enable = enable;
+        self
+    }
+
     /// 为每个 color attachment 指定 blend 操作
     #[inline]
     pub fn color_blend(
@@ -336,6 +319,27 @@ impl GfxGraphicsPipelineCreateInfo {
         self
     }
 
+    /// cull mode 作为 dynamic state，在录制时通过 [`crate::commands::command_buffer::GfxCommandBuffer::cmd_set_cull_mode`] 设置，
+    /// 使同一个 pipeline 可以绘制单面和双面的材质；[`Self::cull_mode`] 中的 cull mode 会被忽略
+    ///
+    /// `vkCmdSetCullMode` 来自 extendedDynamicState，已经提升到 core-1.3.0
+    #[inline]
+    pub fn dynamic_cull_mode(&mut self) -> &mut Self {
+        if !self.dynamic_states.contains(&vk::DynamicState::CULL_MODE) {
+            self.dynamic_states.push(vk::DynamicState::CULL_MODE);
+        }
+        self
+    }
+
+    /// 光栅化时给深度加上 `constant_factor * r + slope_factor * max_slope` 的偏移，常用于阴影贴图
+    #[inline]
+    pub fn depth_bias(&mut self, constant_factor: f32, slope_factor: f32) -> &mut Self {
+        self.rasterize_state_info.depth_bias_enable = vk::TRUE;
+        self.rasterize_state_info.depth_bias_constant_factor = constant_factor;
+        self.rasterize_state_info.depth_bias_slope_factor = slope_factor;
+        self
+    }
+
     #[inline]
     pub fn depth_test(
         &mut self,
@@ -355,4 +359,275 @@ impl GfxGraphicsPipelineCreateInfo {
         self.depth_stencil_info.stencil_test_enable = if enable { vk::TRUE } else { vk::FALSE };
         self
     }
+
+    /// builder
+    ///
+    /// 所有 stage 使用同一组 specialization constant，相同的 `constant_id` 以后设置的为准
+    #[inline]
+    pub fn specialization(&mut self, specialization: GfxSpecializationMap) -> &mut Self {
+        self.specialization.extend(&specialization);
+        self
+    }
+}
+// tools
+impl GfxGraphicsPipelineCreateInfo {
+    /// 创建 pipeline 并注册热重载，重建时使用同样的创建参数和 pipeline layout
+    ///
+    /// # Panics
+    /// spv 无法加载或者 pipeline 创建失败
+    pub(crate) fn create_reloadable_pipeline(
+        &self,
+        pipeline_layout: &Rc<GfxPipelineLayout>,
+        debug_name: &str,
+    ) -> Rc<GfxReloadablePipeline<GfxPipelineHandle>> {
+        let create = {
+            let create_info = self.clone();
+            let pipeline_layout = pipeline_layout.clone();
+            let debug_name = debug_name.to_string();
+            move || {
+                let pipeline = create_info.create_vk_pipeline(pipeline_layout.handle())?;
+                Gfx::get().gfx_device().set_object_debug_name(pipeline, &debug_name);
+                Ok(GfxPipelineHandle(pipeline))
+            }
+        };
+        let pipeline = create().unwrap_or_else(|e| panic!("{}", e));
+        GfxReloadablePipeline::new(pipeline, self.shader_stages.iter().map(|stage| stage.path()), debug_name, create)
+    }
+
+    /// 创建 vk::Pipeline，包含 mesh shader stage 时会忽略 vertex input 和 input assembly
+    ///
+    /// spv 无法加载或者 pipeline 创建失败时返回错误
+    fn create_vk_pipeline(&self, pipeline_layout: vk::PipelineLayout) -> Result<vk::Pipeline, String> {
+        // dynamic rendering 需要的 framebuffer 信息
+        let mut attach_info = vk::PipelineRenderingCreateInfo::default()
+            .color_attachment_formats(&self.color_attach_formats)
+            .depth_attachment_format(self.depth_attach_format)
+            .stencil_attachment_format(self.stencil_attach_format);
+
+        let mut shader_modules_cache = GfxShaderModuleCache::new();
+        let specialization_info = self.specialization.vk_info();
+        let mut shader_stages_info = Vec::with_capacity(self.shader_stages.len());
+        for stage in &self.shader_stages {
+            let shader_module = match shader_modules_cache.try_get_or_load(stage.path()) {
+                Ok(shader_module) => shader_module,
+                Err(e) => {
+                    shader_modules_cache.destroy();
+                    return Err(e);
+                }
+            };
+            let stage_info = vk::PipelineShaderStageCreateInfo::default()
+                .stage(stage.stage)
+                .module(shader_module.handle())
+                .name(stage.entry_point);
+            shader_stages_info.push(if self.specialization.is_empty() {
+                stage_info
+            } else {
+                stage_info.specialization_info(&specialization_info)
+            });
+        }
+
+        // 顶点和 index
+        let vertex_input_state_info = vk::PipelineVertexInputStateCreateInfo::default()
+            .vertex_binding_descriptions(&self.vertex_binding_desc)
+            .vertex_attribute_descriptions(&self.vertex_attribute_desec);
+
+        let input_assembly_info = vk::PipelineInputAssemblyStateCreateInfo::default()
+            .topology(self.primitive_topology)
+            .primitive_restart_enable(self.primitive_restart_enable);
+
+        // viewport 和 scissor 具体值由 dynamic 决定，但是数量由该 create info 决定
+        let viewport_info = vk::PipelineViewportStateCreateInfo {
+            viewport_count: 1,
+            scissor_count: 1,
+            ..Default::default()
+        };
+
+        // MSAA 配置
+        let msaa_info = vk::PipelineMultisampleStateCreateInfo::default()
+            .sample_shading_enable(self.enable_sample_shading)
+            .rasterization_samples(self.msaa_sample);
+
+        // 混合设置：需要为每个 color attachment 分别指定
+        let color_blend_info = self.blend_info.attachments(&self.color_attach_blend_states);
+
+        let dynamic_state_info = vk::PipelineDynamicStateCreateInfo::default().dynamic_states(&self.dynamic_states);
+
+        // =======================================
+        // === 创建 pipeline
+
+        let mut pipeline_info = vk::GraphicsPipelineCreateInfo::default()
+            .stages(&shader_stages_info)
+            .viewport_state(&viewport_info)
+            .rasterization_state(&self.rasterize_state_info)
+            .multisample_state(&msaa_info)
+            .color_blend_state(&color_blend_info)
+            .depth_stencil_state(&self.depth_stencil_info)
+            .layout(pipeline_layout)
+            .dynamic_state(&dynamic_state_info)
+            .push_next(&mut attach_info);
+        // mesh shader pipeline 没有顶点输入阶段
+        if !self.has_mesh_shader_stage() {
+            self.validate_input_assembly();
+            pipeline_info =
+                pipeline_info.vertex_input_state(&vertex_input_state_info).input_assembly_state(&input_assembly_info);
+        }
+
+        let pipeline = unsafe {
+            Gfx::get().gfx_device().create_graphics_pipelines(
+                Gfx::get().pipeline_cache().handle(),
+                std::slice::from_ref(&pipeline_info),
+                None,
+            )
+        };
+
+        shader_modules_cache.destroy();
+
+        match pipeline {
+            Ok(pipelines) => Ok(pipelines[0]),
+            Err((_, e)) => Err(format!("Failed to create graphics pipeline: {:?}", e)),
+        }
+    }
+
+    /// 检查图元拓扑与 shader stage、primitive restart 的兼容性
+    ///
+    /// # Panics
+    /// 组合不合法时 panic，避免在驱动中产生未定义行为
+    fn validate_input_assembly(&self) {
+        let stages = self.shader_stages.iter().fold(vk::ShaderStageFlags::empty(), |acc, stage| acc | stage.stage);
+        let has_tessellation = stages
+            .intersects(vk::ShaderStageFlags::TESSELLATION_CONTROL | vk::ShaderStageFlags::TESSELLATION_EVALUATION);
+        let has_geometry = stages.contains(vk::ShaderStageFlags::GEOMETRY);
+        let topology = self.primitive_topology;
+
+        assert_eq!(
+            topology == vk::PrimitiveTopology::PATCH_LIST,
+            has_tessellation,
+            "graphics pipeline: PATCH_LIST must be used if and only if tessellation stages exist, topology: {:?}",
+            topology
+        );
+        if has_tessellation {
+            assert!(
+                stages.contains(
+                    vk::ShaderStageFlags::TESSELLATION_CONTROL | vk::ShaderStageFlags::TESSELLATION_EVALUATION
+                ),
+                "graphics pipeline: tessellation control and evaluation stages must be used together"
+            );
+        }
+
+        let is_adjacency = matches!(
+            topology,
+            vk::PrimitiveTopology::LINE_LIST_WITH_ADJACENCY
+                | vk::PrimitiveTopology::LINE_STRIP_WITH_ADJACENCY
+                | vk::PrimitiveTopology::TRIANGLE_LIST_WITH_ADJACENCY
+                | vk::PrimitiveTopology::TRIANGLE_STRIP_WITH_ADJACENCY
+        );
+        assert!(
+            !is_adjacency || has_geometry,
+            "graphics pipeline: {:?} requires a geometry stage to consume adjacency",
+            topology
+        );
+
+        // list 类型的 primitive restart 需要 VK_EXT_primitive_topology_list_restart，目前没有开启
+        let is_strip_or_fan = matches!(
+            topology,
+            vk::PrimitiveTopology::LINE_STRIP
+                | vk::PrimitiveTopology::TRIANGLE_STRIP
+                | vk::PrimitiveTopology::TRIANGLE_FAN
+                | vk::PrimitiveTopology::LINE_STRIP_WITH_ADJACENCY
+                | vk::PrimitiveTopology::TRIANGLE_STRIP_WITH_ADJACENCY
+        );
+        assert!(
+            !self.primitive_restart_enable || is_strip_or_fan,
+            "graphics pipeline: primitive restart is only supported for strip/fan topology, topology: {:?}",
+            topology
+        );
+    }
+
+    #[inline]
+    pub(crate) fn has_mesh_shader_stage(&self) -> bool {
+        self.shader_stages.iter().any(|stage| stage.stage == vk::ShaderStageFlags::MESH_EXT)
+    }
+
+    #[inline]
+    pub(crate) fn has_vertex_shader_stage(&self) -> bool {
+        self.shader_stages.iter().any(|stage| stage.stage == vk::ShaderStageFlags::VERTEX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stage(stage: vk::ShaderStageFlags) -> GfxShaderStageInfo {
+        GfxShaderStageInfo {
+            stage,
+            entry_point: c"main",
+            path: String::new(),
+        }
+    }
+
+    fn create_info(stages: &[vk::ShaderStageFlags], topology: vk::PrimitiveTopology) -> GfxGraphicsPipelineCreateInfo {
+        let mut create_info = GfxGraphicsPipelineCreateInfo::default();
+        create_info.shader_stages(stages.iter().copied().map(stage).collect()).primitive_topology(topology);
+        create_info
+    }
+
+    const VS_PS: [vk::ShaderStageFlags; 2] = [vk::ShaderStageFlags::VERTEX, vk::ShaderStageFlags::FRAGMENT];
+
+    #[test]
+    fn test_validate_input_assembly_accepts_valid_combinations() {
+        create_info(&VS_PS, vk::PrimitiveTopology::TRIANGLE_LIST).validate_input_assembly();
+        create_info(&VS_PS, vk::PrimitiveTopology::TRIANGLE_STRIP).primitive_restart(true).validate_input_assembly();
+        create_info(
+            &[
+                vk::ShaderStageFlags::VERTEX,
+                vk::ShaderStageFlags::TESSELLATION_CONTROL,
+                vk::ShaderStageFlags::TESSELLATION_EVALUATION,
+                vk::ShaderStageFlags::FRAGMENT,
+            ],
+            vk::PrimitiveTopology::PATCH_LIST,
+        )
+        .validate_input_assembly();
+        create_info(
+            &[
+                vk::ShaderStageFlags::VERTEX,
+                vk::ShaderStageFlags::GEOMETRY,
+                vk::ShaderStageFlags::FRAGMENT,
+            ],
+            vk::PrimitiveTopology::TRIANGLE_LIST_WITH_ADJACENCY,
+        )
+        .validate_input_assembly();
+    }
+
+    #[test]
+    #[should_panic(expected = "PATCH_LIST must be used if and only if tessellation stages exist")]
+    fn test_validate_input_assembly_patch_list_without_tessellation() {
+        create_info(&VS_PS, vk::PrimitiveTopology::PATCH_LIST).validate_input_assembly();
+    }
+
+    #[test]
+    #[should_panic(expected = "tessellation control and evaluation stages must be used together")]
+    fn test_validate_input_assembly_incomplete_tessellation() {
+        create_info(
+            &[
+                vk::ShaderStageFlags::VERTEX,
+                vk::ShaderStageFlags::TESSELLATION_EVALUATION,
+                vk::ShaderStageFlags::FRAGMENT,
+            ],
+            vk::PrimitiveTopology::PATCH_LIST,
+        )
+        .validate_input_assembly();
+    }
+
+    #[test]
+    #[should_panic(expected = "requires a geometry stage to consume adjacency")]
+    fn test_validate_input_assembly_adjacency_without_geometry() {
+        create_info(&VS_PS, vk::PrimitiveTopology::LINE_STRIP_WITH_ADJACENCY).validate_input_assembly();
+    }
+
+    #[test]
+    #[should_panic(expected = "primitive restart is only supported for strip/fan topology")]
+    fn test_validate_input_assembly_list_restart() {
+        create_info(&VS_PS, vk::PrimitiveTopology::TRIANGLE_LIST).primitive_restart(true).validate_input_assembly();
+    }
 }
@@ -0,0 +1,199 @@
+use std::cell::{Ref, RefCell};
+use std::path::{Path, PathBuf};
+use std::rc::{Rc, Weak};
+
+use ash::vk;
+
+use crate::gfx::Gfx;
+use crate::pipelines::shader_watcher::normalize_path;
+
+/// 可以热重载的对象，由 [`GfxPipelineReloader`] 在依赖的 spv 发生变化时重建
+pub(crate) trait GfxReloadTarget {
+    /// 依赖的 spv 文件，已经规范化
+    fn shader_paths(&self) -> &[PathBuf];
+
+    /// 使用当前的 spv 重建，失败时保留旧的对象
+    fn reload(&self) -> Result<(), String>;
+
+    fn debug_name(&self) -> &str;
+}
+
+/// 拥有所有权的 vk::Pipeline，drop 时销毁
+pub(crate) struct GfxPipelineHandle(pub(crate) vk::Pipeline);
+impl Drop for GfxPipelineHandle {
+    fn drop(&mut self) {
+        unsafe {
+            Gfx::get().gfx_device().destroy_pipeline(self.0, None);
+        }
+    }
+}
+
+/// 可以热重载的 pipeline 数据（例如 vk::Pipeline，光追还包括 SBT）
+///
+/// 持有者每次录制命令时通过 [`Self::current`] 读取，重建之后原地替换，持有者不需要做任何处理
+pub(crate) struct GfxReloadablePipeline<T> {
+    current: RefCell<T>,
+    shader_paths: Vec<PathBuf>,
+    rebuild: Box<dyn Fn() -> Result<T, String>>,
+    debug_name: String,
+}
+// new & init
+impl<T: 'static> GfxReloadablePipeline<T> {
+    /// 创建之后注册到 [`Gfx::pipeline_reloader`]
+    ///
+    /// `rebuild` 使用当前的 spv 重新创建 pipeline 数据，热重载时调用
+    pub(crate) fn new(
+        current: T,
+        shader_paths: impl IntoIterator<Item = impl AsRef<Path>>,
+        debug_name: &str,
+        rebuild: impl Fn() -> Result<T, String> + 'static,
+    ) -> Rc<Self> {
+        let pipeline = Rc::new(Self {
+            current: RefCell::new(current),
+            shader_paths: shader_paths.into_iter().map(|path| normalize_path(path.as_ref())).collect(),
+            rebuild: Box::new(rebuild),
+            debug_name: debug_name.to_string(),
+        });
+        let target: Rc<dyn GfxReloadTarget> = pipeline.clone();
+        Gfx::get().pipeline_reloader().register(Rc::downgrade(&target));
+        pipeline
+    }
+}
+// getter
+impl<T> GfxReloadablePipeline<T> {
+    #[inline]
+    pub(crate) fn current(&self) -> Ref<'_, T> {
+        self.current.borrow()
+    }
+}
+impl<T> GfxReloadTarget for GfxReloadablePipeline<T> {
+    fn shader_paths(&self) -> &[PathBuf] {
+        &self.shader_paths
+    }
+
+    fn reload(&self) -> Result<(), String> {
+        let new_value = (self.rebuild)()?;
+        // 旧的数据在这里被释放
+        *self.current.borrow_mut() = new_value;
+        Ok(())
+    }
+
+    fn debug_name(&self) -> &str {
+        &self.debug_name
+    }
+}
+
+/// 所有可以热重载的 pipeline 的注册表，graphics、compute、mesh shader 和光追 pipeline 在创建时自动注册
+///
+/// 只保存弱引用，pipeline 被释放之后自动失效；由 renderer 在每帧开始时根据 spv 的变化驱动，
+/// 各个 pass 不需要关心热重载
+#[derive(Default)]
+pub struct GfxPipelineReloader {
+    targets: RefCell<Vec<Weak<dyn GfxReloadTarget>>>,
+}
+// update
+impl GfxPipelineReloader {
+    pub(crate) fn register(&self, target: Weak<dyn GfxReloadTarget>) {
+        self.targets.borrow_mut().push(target);
+    }
+
+    /// 重建依赖 `changed_shaders` 中任意一个文件的 pipeline，返回重建成功的数量
+    ///
+    /// 有需要重建的 pipeline 时会先等待 GPU 空闲；重建失败（例如 spv 不完整）时保留旧的 pipeline
+    pub fn reload(&self, changed_shaders: &[PathBuf]) -> usize {
+        let dirty_targets = self.dirty_targets(changed_shaders);
+        if dirty_targets.is_empty() {
+            return 0;
+        }
+
+        // 旧的 pipeline 可能还在被 GPU 使用
+        Gfx::get().wait_idel();
+        let mut reloaded_cnt = 0;
+        for target in dirty_targets {
+            match target.reload() {
+                Ok(()) => {
+                    log::info!("Reloaded pipeline {}", target.debug_name());
+                    reloaded_cnt += 1;
+                }
+                Err(e) => log::error!("Failed to reload pipeline {}, keep the old one: {}", target.debug_name(), e),
+            }
+        }
+        reloaded_cnt
+    }
+
+    /// 依赖 `changed_shaders` 中任意一个文件、仍然存活的 pipeline，同时清理已经释放的 pipeline
+    fn dirty_targets(&self, changed_shaders: &[PathBuf]) -> Vec<Rc<dyn GfxReloadTarget>> {
+        let changed_shaders = changed_shaders.iter().map(|path| normalize_path(path)).collect::<Vec<_>>();
+
+        let mut targets = self.targets.borrow_mut();
+        targets.retain(|target| target.strong_count() > 0);
+        targets
+            .iter()
+            .filter_map(Weak::upgrade)
+            .filter(|target| target.shader_paths().iter().any(|path| changed_shaders.contains(path)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FakeTarget {
+        shader_paths: Vec<PathBuf>,
+    }
+    impl GfxReloadTarget for FakeTarget {
+        fn shader_paths(&self) -> &[PathBuf] {
+            &self.shader_paths
+        }
+
+        fn reload(&self) -> Result<(), String> {
+            Ok(())
+        }
+
+        fn debug_name(&self) -> &str {
+            "fake"
+        }
+    }
+
+    fn fake_target(shader_paths: &[&str]) -> Rc<dyn GfxReloadTarget> {
+        Rc::new(FakeTarget {
+            shader_paths: shader_paths.iter().map(PathBuf::from).collect(),
+        })
+    }
+
+    #[test]
+    fn test_dirty_targets_match_changed_shaders() {
+        let reloader = GfxPipelineReloader::default();
+        let blit = fake_target(&["shader/.build/blit.slang.spv"]);
+        let phong = fake_target(&["shader/.build/phong.vs.spv", "shader/.build/phong.ps.spv"]);
+        reloader.register(Rc::downgrade(&blit));
+        reloader.register(Rc::downgrade(&phong));
+
+        assert!(reloader.dirty_targets(&[]).is_empty());
+        assert!(reloader.dirty_targets(&[PathBuf::from("shader/.build/other.spv")]).is_empty());
+
+        let dirty = reloader.dirty_targets(&[PathBuf::from("shader/.build/phong.ps.spv")]);
+        assert_eq!(dirty.len(), 1);
+        assert!(Rc::ptr_eq(&dirty[0], &phong));
+
+        // 同一个 pipeline 的多个 spv 同时变化时只重建一次
+        let dirty = reloader.dirty_targets(&[
+            PathBuf::from("shader/.build/phong.vs.spv"),
+            PathBuf::from("shader/.build/phong.ps.spv"),
+            PathBuf::from("shader/.build/blit.slang.spv"),
+        ]);
+        assert_eq!(dirty.len(), 2);
+    }
+
+    #[test]
+    fn test_released_targets_are_dropped() {
+        let reloader = GfxPipelineReloader::default();
+        let blit = fake_target(&["shader/.build/blit.slang.spv"]);
+        reloader.register(Rc::downgrade(&blit));
+        drop(blit);
+
+        assert!(reloader.dirty_targets(&[PathBuf::from("shader/.build/blit.slang.spv")]).is_empty());
+        assert!(reloader.targets.borrow().is_empty());
+    }
+}
@@ -0,0 +1,90 @@
+use std::rc::Rc;
+
+use ash::vk;
+
+use crate::foundation::debug_messenger::DebugType;
+use crate::gfx::Gfx;
+use crate::pipelines::graphics_pipeline::{GfxGraphicsPipelineCreateInfo, GfxPipelineLayout};
+use crate::pipelines::hot_reload::{GfxPipelineHandle, GfxReloadablePipeline};
+
+/// mesh shader 管线（VK_EXT_mesh_shader）
+///
+/// 使用 task shader（可选）+ mesh shader 替代传统的顶点输入和 vertex shader，
+/// 光栅化之后的阶段与 [`GfxGraphicsPipeline`] 相同，因此复用 [`GfxGraphicsPipelineCreateInfo`]：
+/// 通过 [`GfxGraphicsPipelineCreateInfo::task_shader_stage`] 和
+/// [`GfxGraphicsPipelineCreateInfo::mesh_shader_stage`] 指定 shader，vertex binding 等顶点输入的设置会被忽略。
+///
+/// 绘制时使用 [`GfxCommandBuffer::cmd_draw_mesh_tasks`]，bind point 仍然是 `GRAPHICS`。
+///
+/// [`GfxGraphicsPipeline`]: crate::pipelines::graphics_pipeline::GfxGraphicsPipeline
+/// [`GfxCommandBuffer::cmd_draw_mesh_tasks`]: crate::commands::command_buffer::GfxCommandBuffer::cmd_draw_mesh_tasks
+pub struct GfxMeshShaderPipeline {
+    /// 依赖的 spv 发生变化时原地重建，参见 [`GfxPipelineReloader`]
+    ///
+    /// [`GfxPipelineReloader`]: crate::pipelines::hot_reload::GfxPipelineReloader
+    pipeline: Rc<GfxReloadablePipeline<GfxPipelineHandle>>,
+
+    /// 因为多个 pipeline 可以使用同一个 pipeline layout，所以这里使用 Rc
+    pipeline_layout: Rc<GfxPipelineLayout>,
+}
+// new & init
+impl GfxMeshShaderPipeline {
+    /// # Panics
+    /// - 设备不支持 mesh shader，调用前需要通过 [`Self::is_supported`] 检查
+    /// - 没有指定 mesh shader stage，或者同时指定了 vertex shader stage
+    pub fn new(
+        create_info: &GfxGraphicsPipelineCreateInfo,
+        pipeline_layout: Rc<GfxPipelineLayout>,
+        debug_name: &str,
+    ) -> Self {
+        assert!(Self::is_supported(), "mesh shader pipeline {debug_name}: device does not support mesh shader");
+        assert!(
+            create_info.has_mesh_shader_stage(),
+            "mesh shader pipeline {debug_name}: mesh shader stage is required"
+        );
+        assert!(
+            !create_info.has_vertex_shader_stage(),
+            "mesh shader pipeline {debug_name}: vertex shader stage is not allowed"
+        );
+
+        GfxMeshShaderPipeline {
+            pipeline: create_info
+                .create_reloadable_pipeline(&pipeline_layout, &format!("{}::{}", Self::debug_type_name(), debug_name)),
+            pipeline_layout,
+        }
+    }
+}
+// getter
+impl GfxMeshShaderPipeline {
+    /// 当前设备是否支持 task shader 和 mesh shader
+    #[inline]
+    pub fn is_supported() -> bool {
+        Gfx::get().physical_device().support_mesh_shader()
+    }
+
+    #[inline]
+    pub fn handle(&self) -> vk::Pipeline {
+        self.pipeline.current().0
+    }
+
+    #[inline]
+    pub fn layout(&self) -> vk::PipelineLayout {
+        self.pipeline_layout.handle()
+    }
+}
+// destroy
+impl GfxMeshShaderPipeline {
+    #[inline]
+    pub fn destroy(self) {
+        // drop
+    }
+}
+impl DebugType for GfxMeshShaderPipeline {
+    fn debug_type_name() -> &'static str {
+        "GfxMeshShaderPipeline"
+    }
+
+    fn vk_handle(&self) -> impl vk::Handle {
+        self.handle()
+    }
+}
@@ -1,3 +1,9 @@
+pub mod compute_pipeline;
 pub mod graphics_pipeline;
+pub mod hot_reload;
+pub mod mesh_shader_pipeline;
+pub mod pipeline_cache;
 pub mod rendering_info;
 pub mod shader;
+pub mod shader_watcher;
+pub mod specialization;
@@ -0,0 +1,299 @@
+use std::path::Path;
+
+use ash::vk;
+
+use crate::foundation::debug_messenger::DebugType;
+use crate::foundation::device::GfxDevice;
+use crate::foundation::physical_device::GfxPhysicalDevice;
+use crate::gfx::Gfx;
+
+/// Pipeline Cache 封装，同时提供 pipeline 的并行创建
+///
+/// `vkCreate*Pipelines` 本身是线程安全的，对同一个 pipeline cache 的并发访问由驱动负责内部同步
+/// （cache 创建时不带 `EXTERNALLY_SYNCHRONIZED` flag），因此多个线程可以共享同一个 cache。
+///
+/// # Destroy
+/// 需要手动调用 `destroy` 方法来释放资源。
+pub struct GfxPipelineCache {
+    handle: vk::PipelineCache,
+
+    #[cfg(debug_assertions)]
+    destroyed: bool,
+}
+
+// new & init
+impl GfxPipelineCache {
+    pub fn new(debug_name: impl AsRef<str>) -> Self {
+        Self::new_internal(Gfx::get().gfx_device(), &[], debug_name)
+    }
+
+    /// 在 Gfx 单例初始化之前使用
+    ///
+    /// `initial_data` 是之前通过 `vkGetPipelineCacheData` 得到的数据，需要事先通过
+    /// [`Self::is_compatible_data`] 校验；为空时创建空的 cache
+    pub(crate) fn new_internal(gfx_device: &GfxDevice, initial_data: &[u8], debug_name: impl AsRef<str>) -> Self {
+        let cache_ci = vk::PipelineCacheCreateInfo::default().initial_data(initial_data);
+        let handle = unsafe { gfx_device.create_pipeline_cache(&cache_ci, None).unwrap() };
+
+        let cache = Self {
+            handle,
+
+            #[cfg(debug_assertions)]
+            destroyed: false,
+        };
+        gfx_device.set_debug_name(&cache, debug_name);
+        cache
+    }
+
+    /// 使用磁盘上的 cache 数据创建，在 Gfx 单例初始化之前使用
+    ///
+    /// 文件不存在，或者数据不是当前设备生成的时候，丢弃这份数据并创建空的 cache
+    pub(crate) fn load_internal(
+        gfx_device: &GfxDevice,
+        physical_device: &GfxPhysicalDevice,
+        path: &Path,
+        debug_name: impl AsRef<str>,
+    ) -> Self {
+        let _span = tracy_client::span!("GfxPipelineCache::load");
+
+        let data = match std::fs::read(path) {
+            Ok(data) if Self::is_compatible_data(&data, &physical_device.basic_props) => {
+                log::info!("load pipeline cache from {:?}, {} bytes", path, data.len());
+                data
+            }
+            Ok(_) => {
+                log::warn!("pipeline cache {:?} is not compatible with current device, discard it", path);
+                vec![]
+            }
+            Err(e) => {
+                log::info!("no pipeline cache loaded from {:?}: {}", path, e);
+                vec![]
+            }
+        };
+
+        Self::new_internal(gfx_device, &data, debug_name)
+    }
+}
+
+// getter
+impl GfxPipelineCache {
+    #[inline]
+    pub fn handle(&self) -> vk::PipelineCache {
+        self.handle
+    }
+
+    /// cache 当前序列化后的大小（字节），可以用来观察预热的效果
+    pub fn data_size(&self) -> usize {
+        unsafe { Gfx::get().gfx_device().get_pipeline_cache_data(self.handle).map(|data| data.len()).unwrap_or(0) }
+    }
+
+    /// 检查 cache 数据的 header（`VkPipelineCacheHeaderVersionOne`）是否由当前设备生成
+    ///
+    /// 驱动在加载不兼容的数据时应当忽略它，但并不是所有驱动都能正确处理，因此在加载之前自己校验一遍
+    pub fn is_compatible_data(data: &[u8], props: &vk::PhysicalDeviceProperties) -> bool {
+        // header_size, header_version, vendor_id, device_id 各 4 字节，之后是 16 字节的 uuid
+        const HEADER_SIZE: usize = 16 + vk::UUID_SIZE;
+        if data.len() < HEADER_SIZE {
+            return false;
+        }
+
+        let read_u32 = |offset: usize| u32::from_ne_bytes(data[offset..offset + 4].try_into().unwrap());
+        let header_size = read_u32(0) as usize;
+        let header_version = read_u32(4) as i32;
+        let vendor_id = read_u32(8);
+        let device_id = read_u32(12);
+        let uuid = &data[16..HEADER_SIZE];
+
+        header_size >= HEADER_SIZE
+            && header_size <= data.len()
+            && header_version == vk::PipelineCacheHeaderVersion::ONE.as_raw()
+            && vendor_id == props.vendor_id
+            && device_id == props.device_id
+            && uuid == props.pipeline_cache_uuid.as_slice()
+    }
+}
+
+// tools
+impl GfxPipelineCache {
+    /// 创建多个 compute pipeline，返回的 pipeline 与 `create_infos` 一一对应
+    ///
+    /// - `parallel = true`：按 CPU 核数切分，在多个线程中同时创建
+    /// - `parallel = false`：在当前线程中逐个创建，主要用于耗时对比
+    pub fn create_compute_pipelines(
+        &self,
+        create_infos: &[vk::ComputePipelineCreateInfo],
+        parallel: bool,
+    ) -> Vec<vk::Pipeline> {
+        let _span = tracy_client::span!("GfxPipelineCache::create_compute_pipelines");
+        self.create_pipelines(create_infos, parallel, |device, cache, create_info| unsafe {
+            device.create_compute_pipelines(cache, std::slice::from_ref(create_info), None).unwrap()[0]
+        })
+    }
+
+    /// 创建多个 graphics pipeline，返回的 pipeline 与 `create_infos` 一一对应
+    ///
+    /// 参数含义与 [`Self::create_compute_pipelines`] 相同
+    pub fn create_graphics_pipelines(
+        &self,
+        create_infos: &[vk::GraphicsPipelineCreateInfo],
+        parallel: bool,
+    ) -> Vec<vk::Pipeline> {
+        let _span = tracy_client::span!("GfxPipelineCache::create_graphics_pipelines");
+        self.create_pipelines(create_infos, parallel, |device, cache, create_info| unsafe {
+            device.create_graphics_pipelines(cache, std::slice::from_ref(create_info), None).unwrap()[0]
+        })
+    }
+
+    fn create_pipelines<T>(
+        &self,
+        create_infos: &[T],
+        parallel: bool,
+        create_fn: impl Fn(&ash::Device, vk::PipelineCache, &T) -> vk::Pipeline + Sync,
+    ) -> Vec<vk::Pipeline> {
+        // GfxDevice 中有 Cell，不能跨线程；ash::Device 只是函数指针表，可以共享
+        let device = &Gfx::get().gfx_device().device;
+        let cache = self.handle;
+
+        let thread_cnt = std::thread::available_parallelism().map_or(1, |n| n.get()).min(create_infos.len());
+        if !parallel || thread_cnt <= 1 {
+            return create_infos.iter().map(|create_info| create_fn(device, cache, create_info)).collect();
+        }
+
+        // create info 中包含裸指针，不是 Sync 的；
+        // 但创建期间只会被读取，且指向的数据在 scope 结束前都有效，因此可以安全地跨线程共享
+        struct SharedCreateInfos<'a, T>(&'a [T]);
+        unsafe impl<T> Sync for SharedCreateInfos<'_, T> {}
+        let shared_create_infos = SharedCreateInfos(create_infos);
+
+        let chunk_size = create_infos.len().div_ceil(thread_cnt);
+        std::thread::scope(|scope| {
+            let create_fn = &create_fn;
+            let shared_create_infos = &shared_create_infos;
+            let workers = (0..create_infos.len())
+                .step_by(chunk_size)
+                .map(|begin| {
+                    scope.spawn(move || {
+                        let end = (begin + chunk_size).min(shared_create_infos.0.len());
+                        shared_create_infos.0[begin..end]
+                            .iter()
+                            .map(|create_info| create_fn(device, cache, create_info))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            workers.into_iter().flat_map(|worker| worker.join().unwrap()).collect()
+        })
+    }
+}
+
+// tools
+impl GfxPipelineCache {
+    /// 将 cache 当前的内容写入磁盘，下次启动时通过 `load_internal` 加载
+    pub fn save(&self, path: &Path) {
+        self.save_internal(Gfx::get().gfx_device(), path);
+    }
+
+    /// 在 Gfx 单例销毁的过程中使用
+    pub(crate) fn save_internal(&self, gfx_device: &GfxDevice, path: &Path) {
+        let _span = tracy_client::span!("GfxPipelineCache::save");
+
+        let data = match unsafe { gfx_device.get_pipeline_cache_data(self.handle) } {
+            Ok(data) => data,
+            Err(e) => {
+                log::error!("Failed to get pipeline cache data: {:?}", e);
+                return;
+            }
+        };
+
+        // 先写入临时文件再替换，避免写到一半退出时留下损坏的 cache
+        let temp_path = path.with_extension("tmp");
+        let result = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(&temp_path, &data))
+            .and_then(|_| std::fs::rename(&temp_path, path));
+        match result {
+            Ok(()) => log::info!("save pipeline cache to {:?}, {} bytes", path, data.len()),
+            Err(e) => log::error!("Failed to save pipeline cache to {:?}: {}", path, e),
+        }
+    }
+}
+
+// destroy
+impl GfxPipelineCache {
+    #[inline]
+    pub fn destroy(self) {
+        self.destroy_internal(Gfx::get().gfx_device());
+    }
+
+    pub(crate) fn destroy_internal(mut self, gfx_device: &GfxDevice) {
+        unsafe {
+            gfx_device.destroy_pipeline_cache(self.handle, None);
+        }
+        #[cfg(debug_assertions)]
+        {
+            self.destroyed = true;
+        }
+    }
+}
+impl Drop for GfxPipelineCache {
+    fn drop(&mut self) {
+        #[cfg(debug_assertions)]
+        debug_assert!(self.destroyed, "PipelineCache must be destroyed manually before drop.");
+    }
+}
+impl DebugType for GfxPipelineCache {
+    fn debug_type_name() -> &'static str {
+        "GfxPipelineCache"
+    }
+
+    fn vk_handle(&self) -> impl vk::Handle {
+        self.handle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_props() -> vk::PhysicalDeviceProperties {
+        vk::PhysicalDeviceProperties {
+            vendor_id: 0x10de,
+            device_id: 0x2684,
+            pipeline_cache_uuid: [7; vk::UUID_SIZE],
+            ..Default::default()
+        }
+    }
+
+    fn cache_data(vendor_id: u32, device_id: u32, uuid: [u8; vk::UUID_SIZE]) -> Vec<u8> {
+        let mut data = vec![];
+        data.extend_from_slice(&32u32.to_ne_bytes());
+        data.extend_from_slice(&(vk::PipelineCacheHeaderVersion::ONE.as_raw() as u32).to_ne_bytes());
+        data.extend_from_slice(&vendor_id.to_ne_bytes());
+        data.extend_from_slice(&device_id.to_ne_bytes());
+        data.extend_from_slice(&uuid);
+        // header 之后是驱动自定义的数据
+        data.extend_from_slice(&[1, 2, 3, 4]);
+        data
+    }
+
+    #[test]
+    fn test_compatible_data() {
+        let props = test_props();
+        assert!(GfxPipelineCache::is_compatible_data(&cache_data(0x10de, 0x2684, [7; vk::UUID_SIZE]), &props));
+    }
+
+    #[test]
+    fn test_incompatible_data() {
+        let props = test_props();
+        assert!(!GfxPipelineCache::is_compatible_data(&[], &props));
+        assert!(!GfxPipelineCache::is_compatible_data(&cache_data(0x1002, 0x2684, [7; vk::UUID_SIZE]), &props));
+        assert!(!GfxPipelineCache::is_compatible_data(&cache_data(0x10de, 0x2704, [7; vk::UUID_SIZE]), &props));
+        assert!(!GfxPipelineCache::is_compatible_data(&cache_data(0x10de, 0x2684, [0; vk::UUID_SIZE]), &props));
+
+        // header 不完整
+        let data = cache_data(0x10de, 0x2684, [7; vk::UUID_SIZE]);
+        assert!(!GfxPipelineCache::is_compatible_data(&data[..20], &props));
+    }
+}
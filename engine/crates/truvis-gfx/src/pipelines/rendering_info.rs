@@ -6,6 +6,7 @@ pub struct GfxRenderingInfo {
     range: vk::Rect2D,
 }
 impl GfxRenderingInfo {
+    /// 每个 color attachment 都会被 clear 为 (0, 0, 0, 1)，可以通过 [`Self::clear_color`] 单独修改
     pub fn new(
         color_attach_image: Vec<vk::ImageView>,
         depth_attach_image: Option<vk::ImageView>,
@@ -18,6 +19,22 @@ impl GfxRenderingInfo {
         }
     }
 
+    /// 修改第 `attachment_idx` 个 color attachment 的 clear 值，例如 G-buffer 中不同含义的 attachment
+    pub fn clear_color(mut self, attachment_idx: usize, color: [f32; 4]) -> Self {
+        self.color_attach_info[attachment_idx].clear_value = vk::ClearValue {
+            color: vk::ClearColorValue { float32: color },
+        };
+        self
+    }
+
+    /// depth attachment 保留已有的内容而不是 clear，例如主 pass 复用 depth prepass 写入的深度
+    pub fn load_depth(mut self) -> Self {
+        if let Some(depth_attach) = &mut self.depth_attach_info {
+            depth_attach.load_op = vk::AttachmentLoadOp::LOAD;
+        }
+        self
+    }
+
     pub fn rendering_info(&self) -> vk::RenderingInfo<'_> {
         let mut info = vk::RenderingInfo::default()
             .layer_count(1)
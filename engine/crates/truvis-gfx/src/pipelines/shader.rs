@@ -18,14 +18,21 @@ impl GfxShaderModule {
     /// # param
     /// * path - spv shader 文件路径
     pub fn new(path: &std::path::Path) -> Self {
+        Self::try_new(path).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// 文件不存在、不是合法的 spv 或者创建失败时返回错误，用于 shader 热重载等允许失败的场合
+    pub fn try_new(path: &std::path::Path) -> Result<Self, String> {
         let gfx_device = Gfx::get().gfx_device();
-        let mut file = std::fs::File::open(path).unwrap();
-        let shader_code = ash::util::read_spv(&mut file).unwrap();
+        let mut file = std::fs::File::open(path).map_err(|e| format!("Failed to open shader {:?}: {}", path, e))?;
+        let shader_code = ash::util::read_spv(&mut file).map_err(|e| format!("Invalid spv {:?}: {}", path, e))?;
 
         let shader_module_info = vk::ShaderModuleCreateInfo::default().code(&shader_code);
 
         unsafe {
-            let shader_module = gfx_device.create_shader_module(&shader_module_info, None).unwrap();
+            let shader_module = gfx_device
+                .create_shader_module(&shader_module_info, None)
+                .map_err(|e| format!("Failed to create shader module {:?}: {:?}", path, e))?;
             let shader_module = Self {
                 handle: shader_module,
 
@@ -33,7 +40,7 @@ impl GfxShaderModule {
                 destroyed: false,
             };
             gfx_device.set_debug_name(&shader_module, path.to_str().unwrap());
-            shader_module
+            Ok(shader_module)
         }
     }
 
@@ -96,6 +103,15 @@ impl GfxShaderModuleCache {
         self.shader_modules.entry(path_str).or_insert_with(|| GfxShaderModule::new(path))
     }
 
+    /// 与 [`Self::get_or_load`] 相同，加载失败时返回错误
+    pub fn try_get_or_load(&mut self, path: &std::path::Path) -> Result<&GfxShaderModule, String> {
+        let path_str = path.to_str().unwrap().to_string();
+        match self.shader_modules.entry(path_str) {
+            std::collections::hash_map::Entry::Occupied(entry) => Ok(entry.into_mut()),
+            std::collections::hash_map::Entry::Vacant(entry) => Ok(entry.insert(GfxShaderModule::try_new(path)?)),
+        }
+    }
+
     pub fn destroy(mut self) {
         #[cfg(debug_assertions)]
         {
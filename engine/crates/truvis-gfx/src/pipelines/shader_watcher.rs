@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use notify::Watcher;
+
+/// 监听 spv 文件的变化，用于 shader 热重载
+///
+/// 每帧调用 [`Self::poll_changed`] 取出已经写入完成的 spv，
+/// 再交给 [`GfxPipelineReloader::reload`] 重建依赖这些文件的 pipeline
+///
+/// [`GfxPipelineReloader::reload`]: crate::pipelines::hot_reload::GfxPipelineReloader::reload
+pub struct GfxShaderWatcher {
+    _watcher: notify::RecommendedWatcher,
+
+    /// 发生变化的文件 -> 最后一次变化的时间，由 notify 的线程写入
+    changed_files: Arc<Mutex<HashMap<PathBuf, Instant>>>,
+}
+// new & init
+impl GfxShaderWatcher {
+    /// 文件最后一次变化之后经过这么长时间才认为写入完成，避免读到 shader-build 写了一半的 spv
+    const DEBOUNCE: Duration = Duration::from_millis(200);
+
+    /// 递归监听 `watch_dir` 下的文件变化，监听失败时返回 None
+    pub fn new(watch_dir: &Path) -> Option<Self> {
+        let changed_files = Arc::new(Mutex::new(HashMap::new()));
+
+        let changed_files_in_watcher = changed_files.clone();
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    log::warn!("shader watcher error: {}", e);
+                    return;
+                }
+            };
+            if !matches!(event.kind, notify::EventKind::Create(_) | notify::EventKind::Modify(_)) {
+                return;
+            }
+
+            let now = Instant::now();
+            let mut changed_files = changed_files_in_watcher.lock().unwrap();
+            for path in event.paths.iter().filter(|path| path.extension().is_some_and(|ext| ext == "spv")) {
+                changed_files.insert(normalize_path(path), now);
+            }
+        });
+
+        let mut watcher = match watcher {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                log::error!("Failed to create shader watcher: {}", e);
+                return None;
+            }
+        };
+        if let Err(e) = watcher.watch(watch_dir, notify::RecursiveMode::Recursive) {
+            log::error!("Failed to watch shader directory {:?}: {}", watch_dir, e);
+            return None;
+        }
+        log::info!("Watching shader directory: {:?}", watch_dir);
+
+        Some(Self {
+            _watcher: watcher,
+            changed_files,
+        })
+    }
+}
+// update
+impl GfxShaderWatcher {
+    /// 取出已经写入完成的 spv 文件，路径已经规范化
+    ///
+    /// 还在写入中的文件会保留到之后的调用
+    pub fn poll_changed(&self) -> Vec<PathBuf> {
+        let mut changed_files = self.changed_files.lock().unwrap();
+        if changed_files.is_empty() {
+            return vec![];
+        }
+
+        let now = Instant::now();
+        let mut settled_files = vec![];
+        changed_files.retain(|path, last_change| {
+            let settled = now.duration_since(*last_change) >= Self::DEBOUNCE;
+            if settled {
+                settled_files.push(path.clone());
+            }
+            !settled
+        });
+        settled_files
+    }
+}
+
+/// 注册的路径和 notify 给出的路径写法可能不同（例如分隔符），统一成规范化的绝对路径再比较
+pub(crate) fn normalize_path(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
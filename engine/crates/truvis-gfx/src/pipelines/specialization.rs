@@ -0,0 +1,129 @@
+use ash::vk;
+
+/// specialization constant 的集合，用于从同一份 spv 创建多个 pipeline 变体
+///
+/// 所有常量都是 32 位的（bool 对应 `VkBool32`），同一个 `constant_id` 设置多次时以最后一次为准。
+/// shader 中没有声明的 `constant_id` 会被忽略，因此同一个 map 可以用于 pipeline 的所有 stage。
+///
+/// # 使用示例
+/// ```ignore
+/// let spec = GfxSpecializationMap::new().set_u32(0, 16).set_bool(1, true);
+/// create_info.specialization(spec);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct GfxSpecializationMap {
+    map_entries: Vec<vk::SpecializationMapEntry>,
+    data: Vec<u8>,
+}
+// new & init
+impl GfxSpecializationMap {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+// builder
+impl GfxSpecializationMap {
+    /// builder
+    #[inline]
+    pub fn set_u32(mut self, constant_id: u32, value: u32) -> Self {
+        self.insert(constant_id, value.to_ne_bytes());
+        self
+    }
+
+    /// builder
+    #[inline]
+    pub fn set_i32(mut self, constant_id: u32, value: i32) -> Self {
+        self.insert(constant_id, value.to_ne_bytes());
+        self
+    }
+
+    /// builder
+    #[inline]
+    pub fn set_f32(mut self, constant_id: u32, value: f32) -> Self {
+        self.insert(constant_id, value.to_ne_bytes());
+        self
+    }
+
+    /// builder
+    #[inline]
+    pub fn set_bool(mut self, constant_id: u32, value: bool) -> Self {
+        self.insert(constant_id, vk::Bool32::from(value).to_ne_bytes());
+        self
+    }
+}
+// getter
+impl GfxSpecializationMap {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.map_entries.is_empty()
+    }
+
+    /// `constant_id` 对应的 32 位原始值
+    pub fn get_u32(&self, constant_id: u32) -> Option<u32> {
+        let entry = self.map_entries.iter().find(|entry| entry.constant_id == constant_id)?;
+        let offset = entry.offset as usize;
+        Some(u32::from_ne_bytes(self.data[offset..offset + size_of::<u32>()].try_into().unwrap()))
+    }
+
+    /// 用于 `vk::PipelineShaderStageCreateInfo::specialization_info`
+    #[inline]
+    pub fn vk_info(&self) -> vk::SpecializationInfo<'_> {
+        vk::SpecializationInfo::default().map_entries(&self.map_entries).data(&self.data)
+    }
+}
+// tools
+impl GfxSpecializationMap {
+    /// 将 `other` 中的常量合并进来，相同的 `constant_id` 以 `other` 为准
+    pub fn extend(&mut self, other: &Self) {
+        for entry in &other.map_entries {
+            let offset = entry.offset as usize;
+            self.insert(entry.constant_id, other.data[offset..offset + size_of::<u32>()].try_into().unwrap());
+        }
+    }
+
+    fn insert(&mut self, constant_id: u32, bytes: [u8; 4]) {
+        match self.map_entries.iter().find(|entry| entry.constant_id == constant_id) {
+            Some(entry) => {
+                let offset = entry.offset as usize;
+                self.data[offset..offset + bytes.len()].copy_from_slice(&bytes);
+            }
+            None => {
+                self.map_entries.push(vk::SpecializationMapEntry {
+                    constant_id,
+                    offset: self.data.len() as u32,
+                    size: bytes.len(),
+                });
+                self.data.extend_from_slice(&bytes);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_overrides_same_id() {
+        let spec = GfxSpecializationMap::new().set_u32(0, 16).set_bool(1, true).set_u32(0, 32);
+
+        assert_eq!(spec.get_u32(0), Some(32));
+        assert_eq!(spec.get_u32(1), Some(vk::TRUE));
+        assert_eq!(spec.get_u32(2), None);
+
+        let info = spec.vk_info();
+        assert_eq!(info.map_entry_count, 2);
+        assert_eq!(info.data_size, 8);
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut spec = GfxSpecializationMap::new().set_u32(0, 8).set_u32(1, 8);
+        spec.extend(&GfxSpecializationMap::new().set_u32(1, 4).set_f32(2, 0.5));
+
+        assert_eq!(spec.get_u32(0), Some(8));
+        assert_eq!(spec.get_u32(1), Some(4));
+        assert_eq!(spec.get_u32(2), Some(0.5f32.to_bits()));
+    }
+}
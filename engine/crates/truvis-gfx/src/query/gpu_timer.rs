@@ -0,0 +1,229 @@
+use std::cell::RefCell;
+
+use ash::vk;
+
+use crate::commands::command_buffer::GfxCommandBuffer;
+use crate::gfx::Gfx;
+use crate::query::query_pool::GfxQueryPool;
+
+/// 一帧内的一个计时区间
+struct GpuTimerScope {
+    name: String,
+    begin_query: u32,
+    end_query: Option<u32>,
+}
+
+/// 一帧使用的 query pool 以及这一帧写入的计时区间
+struct GpuTimerFrame {
+    query_pool: GfxQueryPool,
+    scopes: Vec<GpuTimerScope>,
+    /// 已经使用的 query 数量
+    query_cnt: u32,
+}
+
+/// 使用 timestamp query 统计 GPU 上各个区间的耗时
+///
+/// 每个 fif 使用独立的 query pool：在 [`Self::begin_frame`] 中读取同一个 fif 上一次写入的结果，
+/// 此时这一帧的 GPU 工作已经完成，读取不会阻塞。因此结果会延迟 fif 数量的帧。
+///
+/// 设备不支持 timestamp 时，所有的操作都不做任何事，[`Self::results`] 始终为空
+pub struct GfxGpuTimer {
+    /// 每个 fif 一份；不支持 timestamp 时为空
+    frames: Vec<RefCell<GpuTimerFrame>>,
+    /// 当前正在录制的 fif
+    current_frame: usize,
+
+    /// timestamp 每增加 1 所经过的纳秒数
+    timestamp_period_ns: f32,
+    /// timestamp 中有效位的掩码
+    timestamp_mask: u64,
+    /// 每帧最多的计时区间数量
+    max_scope_cnt: u32,
+
+    /// 最近一次读取到的结果：(区间名称, 毫秒)
+    results: Vec<(String, f32)>,
+}
+// new & init
+impl GfxGpuTimer {
+    /// # 参数
+    /// - frame_cnt: fif 的数量
+    /// - max_scope_cnt: 每帧最多的计时区间数量，超出的区间会被忽略
+    pub fn new(frame_cnt: usize, max_scope_cnt: u32, debug_name: &str) -> Self {
+        let physical_device = Gfx::get().physical_device();
+        let supported = physical_device.support_timestamp();
+        if !supported {
+            log::warn!("GPU timer: timestamp query is not supported by the gfx queue");
+        }
+
+        let frames = if supported {
+            (0..frame_cnt)
+                .map(|frame_idx| {
+                    let mut query_pool = GfxQueryPool::new(
+                        vk::QueryType::TIMESTAMP,
+                        max_scope_cnt * 2,
+                        &format!("{}-{}", debug_name, frame_idx),
+                    );
+                    // query 在使用之前需要 reset
+                    query_pool.reset(0, max_scope_cnt * 2);
+                    RefCell::new(GpuTimerFrame {
+                        query_pool,
+                        scopes: vec![],
+                        query_cnt: 0,
+                    })
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+
+        let timestamp_valid_bits = Gfx::get().gfx_queue_family().timestamp_valid_bits;
+        Self {
+            frames,
+            current_frame: 0,
+            timestamp_period_ns: physical_device.timestamp_period_ns(),
+            timestamp_mask: Self::timestamp_mask(timestamp_valid_bits),
+            max_scope_cnt,
+            results: vec![],
+        }
+    }
+}
+// getter
+impl GfxGpuTimer {
+    #[inline]
+    pub fn is_supported(&self) -> bool {
+        !self.frames.is_empty()
+    }
+
+    /// 最近一次读取到的各个区间的耗时（毫秒），按照区间开始的顺序排列
+    pub fn results(&self) -> Vec<(String, f32)> {
+        self.results.clone()
+    }
+}
+// update
+impl GfxGpuTimer {
+    /// 读取 `frame_idx` 上一次写入的结果，并开始记录这一帧的计时区间
+    ///
+    /// 需要在等待 `frame_idx` 上一次的 GPU 工作完成之后、录制命令之前调用
+    pub fn begin_frame(&mut self, frame_idx: usize) {
+        if !self.is_supported() {
+            return;
+        }
+        self.current_frame = frame_idx;
+
+        let frame = self.frames[frame_idx].get_mut();
+        if frame.query_cnt > 0 {
+            let mut timestamps = vec![0u64; frame.query_cnt as usize];
+            let read_result = unsafe {
+                Gfx::get().gfx_device().get_query_pool_results(
+                    frame.query_pool.handle(),
+                    0,
+                    &mut timestamps,
+                    vk::QueryResultFlags::TYPE_64,
+                )
+            };
+            match read_result {
+                Ok(()) => {
+                    self.results = frame
+                        .scopes
+                        .iter()
+                        .filter_map(|scope| {
+                            let end_query = scope.end_query?;
+                            let elapsed_ms = Self::elapsed_ms(
+                                timestamps[scope.begin_query as usize],
+                                timestamps[end_query as usize],
+                                self.timestamp_mask,
+                                self.timestamp_period_ns,
+                            );
+                            Some((scope.name.clone(), elapsed_ms))
+                        })
+                        .collect();
+                }
+                // 正常情况下 GPU 已经完成，读取不到时保留之前的结果
+                Err(e) => log::debug!("GPU timer: query results are not available: {:?}", e),
+            }
+
+            frame.query_pool.reset(0, frame.query_cnt);
+        }
+
+        frame.scopes.clear();
+        frame.query_cnt = 0;
+    }
+}
+// destroy
+impl GfxGpuTimer {
+    pub fn destroy(&mut self) {
+        self.frames.clear();
+        self.results.clear();
+    }
+}
+// tools
+impl GfxGpuTimer {
+    /// 开始一个计时区间，记录的是之前的命令全部完成的时间
+    pub fn begin(&self, cmd: &GfxCommandBuffer, name: impl Into<String>) {
+        if !self.is_supported() {
+            return;
+        }
+        let mut frame = self.frames[self.current_frame].borrow_mut();
+        if frame.scopes.len() as u32 >= self.max_scope_cnt {
+            return;
+        }
+
+        let begin_query = frame.query_cnt;
+        cmd.write_timestamp(vk::PipelineStageFlags2::ALL_COMMANDS, &frame.query_pool, begin_query);
+        frame.query_cnt += 1;
+        frame.scopes.push(GpuTimerScope {
+            name: name.into(),
+            begin_query,
+            end_query: None,
+        });
+    }
+
+    /// 结束最近一个名为 `name` 且尚未结束的计时区间
+    pub fn end(&self, cmd: &GfxCommandBuffer, name: &str) {
+        if !self.is_supported() {
+            return;
+        }
+        let mut frame = self.frames[self.current_frame].borrow_mut();
+        let end_query = frame.query_cnt;
+        let Some(scope) = frame.scopes.iter_mut().rev().find(|scope| scope.name == name && scope.end_query.is_none())
+        else {
+            // 区间数量超出上限时 begin 被忽略，对应的 end 也会走到这里
+            return;
+        };
+
+        scope.end_query = Some(end_query);
+        cmd.write_timestamp(vk::PipelineStageFlags2::ALL_COMMANDS, &frame.query_pool, end_query);
+        frame.query_cnt += 1;
+    }
+
+    fn timestamp_mask(valid_bits: u32) -> u64 {
+        if valid_bits >= 64 { u64::MAX } else { (1u64 << valid_bits) - 1 }
+    }
+
+    /// 两个 timestamp 之间的毫秒数，只有低 `valid_bits` 位有效，计数器回绕时也能得到正确的结果
+    fn elapsed_ms(begin: u64, end: u64, mask: u64, period_ns: f32) -> f32 {
+        let ticks = end.wrapping_sub(begin) & mask;
+        (ticks as f64 * period_ns as f64 / 1_000_000.0) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_elapsed_ms() {
+        let mask = GfxGpuTimer::timestamp_mask(64);
+        assert_eq!(mask, u64::MAX);
+        assert_eq!(GfxGpuTimer::elapsed_ms(1_000, 3_000_000, mask, 1.0), 2.999);
+        assert_eq!(GfxGpuTimer::elapsed_ms(0, 1_000_000, mask, 2.5), 2.5);
+    }
+
+    #[test]
+    fn test_elapsed_ms_wrap_around() {
+        // 只有低 36 位有效，计数器从最大值回绕到 0
+        let mask = GfxGpuTimer::timestamp_mask(36);
+        assert_eq!(mask, (1 << 36) - 1);
+        assert_eq!(GfxGpuTimer::elapsed_ms(mask - 499_999, 500_000, mask, 1.0), 1.0);
+    }
+}
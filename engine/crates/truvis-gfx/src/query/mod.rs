@@ -1 +1,2 @@
+pub mod gpu_timer;
 pub mod query_pool;
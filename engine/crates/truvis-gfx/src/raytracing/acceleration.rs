@@ -3,8 +3,10 @@
 use ash::vk;
 use itertools::Itertools;
 
+use crate::commands::command_buffer::GfxCommandBuffer;
 use crate::resources::special_buffers::acceleration_buffer::{
     GfxAccelerationInstanceBuffer, GfxAccelerationScratchBuffer, GfxAccelerationStructureBuffer,
+    GfxAccelerationTransformBuffer,
 };
 use crate::{foundation::debug_messenger::DebugType, gfx::Gfx, query::query_pool::GfxQueryPool};
 
@@ -19,17 +21,10 @@ pub struct GfxAcceleration {
 }
 // 构造与销毁
 impl GfxAcceleration {
-    /// 同步构建 blas
+    /// 同步构建 blas，不进行 compact
     ///
     /// 需要指定每个 geometry 的信息，以及每个 geometry 拥有的 max primitives
-    /// 数量 会自动添加 compact 和 trace 的 flag
-    ///
-    /// # 构建过程
-    ///
-    /// 1. 查询构建 blas 所需的尺寸
-    /// 2. 构建 blas
-    /// 3. 查询 blas 的 compact size
-    /// 4. 将 blas copy 到 compact 的 blas
+    /// 数量 会自动添加 trace 的 flag
     ///
     /// # params
     /// - primitives 每个 geometry 的 max primitives 数量
@@ -40,6 +35,87 @@ impl GfxAcceleration {
     ) -> Self {
         let _span = tracy_client::span!("GfxAcceleration::build_blas_sync");
 
+        Self::build_blas_with_query(
+            blas_inputs,
+            build_flags | vk::BuildAccelerationStructureFlagsKHR::PREFER_FAST_TRACE,
+            None,
+            format!("{}-blas", debug_name.as_ref()),
+        )
+    }
+
+    /// 同步构建 blas，并 compact 到更小的 buffer 中
+    ///
+    /// 会自动添加 compact 和 trace 的 flag，压缩前后的大小会输出到日志中
+    ///
+    /// # 构建过程
+    ///
+    /// 1. 查询构建 blas 所需的尺寸
+    /// 2. 构建 blas，并在同一个命令中写入 compact size 的 query
+    /// 3. 读取 compact size，由于构建是同步的，query 的结果在提交完成后就已经可用，不需要跨帧等待
+    /// 4. 将 blas copy 到 compact 的 blas，并销毁原始的 blas
+    pub fn build_blas_compacted(
+        blas_inputs: &[GfxBlasInputInfo],
+        build_flags: vk::BuildAccelerationStructureFlagsKHR,
+        debug_name: impl AsRef<str>,
+    ) -> Self {
+        let _span = tracy_client::span!("GfxAcceleration::build_blas_compacted");
+
+        // 创建一个 QueryPool，用于查询 compact size
+        let mut query_pool = GfxQueryPool::new(vk::QueryType::ACCELERATION_STRUCTURE_COMPACTED_SIZE_KHR, 1, "");
+        query_pool.reset(0, 1);
+
+        let uncompact_acceleration = Self::build_blas_with_query(
+            blas_inputs,
+            build_flags
+                | vk::BuildAccelerationStructureFlagsKHR::ALLOW_COMPACTION
+                | vk::BuildAccelerationStructureFlagsKHR::PREFER_FAST_TRACE,
+            Some(&mut query_pool),
+            format!("{}-uncompact-blas", debug_name.as_ref()),
+        );
+
+        // 提供更紧凑的 acceleration
+        let compact_size: Vec<vk::DeviceSize> = query_pool.get_query_result(0, 1);
+        let compact_acceleration = Self::new(
+            compact_size[0],
+            vk::AccelerationStructureTypeKHR::BOTTOM_LEVEL,
+            format!("{}-compact-blas", debug_name.as_ref()),
+        );
+
+        Gfx::get().one_time_exec(
+            |cmd| {
+                cmd.cmd_copy_acceleration_structure(
+                    &vk::CopyAccelerationStructureInfoKHR::default()
+                        .src(uncompact_acceleration.acceleration_handle)
+                        .dst(compact_acceleration.acceleration_handle)
+                        .mode(vk::CopyAccelerationStructureModeKHR::COMPACT),
+                );
+            },
+            "compact-blas",
+        );
+
+        log::info!(
+            "blas {}: compacted {} -> {} bytes",
+            debug_name.as_ref(),
+            uncompact_acceleration.size(),
+            compact_acceleration.size()
+        );
+
+        // 回收临时资源
+        {
+            uncompact_acceleration.destroy();
+            query_pool.destroy();
+        }
+
+        compact_acceleration
+    }
+
+    /// 同步构建 blas；`compact_size_query` 不为空时，会在构建完成后将 compact size 写入 query 的第 0 个位置
+    fn build_blas_with_query(
+        blas_inputs: &[GfxBlasInputInfo],
+        build_flags: vk::BuildAccelerationStructureFlagsKHR,
+        compact_size_query: Option<&mut GfxQueryPool>,
+        debug_name: String,
+    ) -> Self {
         let geometries = blas_inputs.iter().map(|blas_input| blas_input.geometry).collect_vec();
         let range_infos = blas_inputs.iter().map(|blas_input| blas_input.range).collect_vec();
         let max_primitives = blas_inputs.iter().map(|blas_input| blas_input.range.primitive_count).collect_vec();
@@ -47,11 +123,7 @@ impl GfxAcceleration {
         // 使用部分完整的 AccelerationStructureBuildGeometryInfo 来查询所需的资源大小
         let mut build_geometry_info = vk::AccelerationStructureBuildGeometryInfoKHR::default()
             .ty(vk::AccelerationStructureTypeKHR::BOTTOM_LEVEL)
-            .flags(
-                build_flags
-                    | vk::BuildAccelerationStructureFlagsKHR::ALLOW_COMPACTION
-                    | vk::BuildAccelerationStructureFlagsKHR::PREFER_FAST_TRACE,
-            )
+            .flags(build_flags)
             .geometries(&geometries)
             .mode(vk::BuildAccelerationStructureModeKHR::BUILD);
 
@@ -67,75 +139,45 @@ impl GfxAcceleration {
             size_info
         };
 
-        let uncompact_acceleration = Self::new(
+        let acceleration = Self::new(
             size_info.acceleration_structure_size,
             vk::AccelerationStructureTypeKHR::BOTTOM_LEVEL,
-            format!("{}-uncompact-blas", debug_name.as_ref()),
+            &debug_name,
         );
 
-        let scratch_buffer = GfxAccelerationScratchBuffer::new(
-            size_info.build_scratch_size,
-            format!("{}-blas-scratch-buffer", debug_name.as_ref()),
-        );
+        let scratch_buffer =
+            GfxAccelerationScratchBuffer::new(size_info.build_scratch_size, format!("{debug_name}-scratch-buffer"));
 
         // 填充 build geometry info 的剩余部分以 build blas
-        build_geometry_info.dst_acceleration_structure = uncompact_acceleration.acceleration_handle;
+        build_geometry_info.dst_acceleration_structure = acceleration.acceleration_handle;
         build_geometry_info.scratch_data = vk::DeviceOrHostAddressKHR {
             device_address: scratch_buffer.device_address(),
         };
 
-        // 创建一个 QueryPool，用于查询 compact size
-        let mut query_pool = GfxQueryPool::new(vk::QueryType::ACCELERATION_STRUCTURE_COMPACTED_SIZE_KHR, 1, "");
-        query_pool.reset(0, 1);
-
-        // 等待初步 build 完成
+        // 等待 build 完成
         Gfx::get().one_time_exec(
             |cmd| {
                 cmd.build_acceleration_structure(&build_geometry_info, &range_infos);
-                // 查询 compact size 属于 read 操作，需要同步
-                cmd.memory_barrier(std::slice::from_ref(&vk::MemoryBarrier2 {
-                    src_stage_mask: vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR,
-                    dst_stage_mask: vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR,
-                    src_access_mask: vk::AccessFlags2::ACCELERATION_STRUCTURE_WRITE_KHR,
-                    dst_access_mask: vk::AccessFlags2::ACCELERATION_STRUCTURE_READ_KHR,
-                    ..Default::default()
-                }));
-                cmd.write_acceleration_structure_properties(
-                    &mut query_pool,
-                    0,
-                    std::slice::from_ref(&build_geometry_info.dst_acceleration_structure),
-                );
+                if let Some(query_pool) = compact_size_query {
+                    // 查询 compact size 属于 read 操作，需要同步
+                    cmd.memory_barrier(std::slice::from_ref(&vk::MemoryBarrier2 {
+                        src_stage_mask: vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR,
+                        dst_stage_mask: vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR,
+                        src_access_mask: vk::AccessFlags2::ACCELERATION_STRUCTURE_WRITE_KHR,
+                        dst_access_mask: vk::AccessFlags2::ACCELERATION_STRUCTURE_READ_KHR,
+                        ..Default::default()
+                    }));
+                    cmd.write_acceleration_structure_properties(
+                        query_pool,
+                        0,
+                        std::slice::from_ref(&build_geometry_info.dst_acceleration_structure),
+                    );
+                }
             },
             "build-blas",
         );
 
-        // 提供更紧凑的 acceleration
-        let compact_size: Vec<vk::DeviceSize> = query_pool.get_query_result(0, 1);
-        let compact_acceleration = Self::new(
-            compact_size[0],
-            vk::AccelerationStructureTypeKHR::BOTTOM_LEVEL,
-            format!("{}-compact-blas", debug_name.as_ref()),
-        );
-
-        Gfx::get().one_time_exec(
-            |cmd| {
-                cmd.cmd_copy_acceleration_structure(
-                    &vk::CopyAccelerationStructureInfoKHR::default()
-                        .src(uncompact_acceleration.acceleration_handle)
-                        .dst(compact_acceleration.acceleration_handle)
-                        .mode(vk::CopyAccelerationStructureModeKHR::COMPACT),
-                );
-            },
-            "compact-blas",
-        );
-
-        // 回收临时资源
-        {
-            uncompact_acceleration.destroy();
-            query_pool.destroy();
-        }
-
-        compact_acceleration
+        acceleration
     }
 
     /// 同步构建 tlas
@@ -246,6 +288,12 @@ impl GfxAcceleration {
         self.acceleration_handle
     }
 
+    /// 加速结构占用的显存大小
+    #[inline]
+    pub fn size(&self) -> vk::DeviceSize {
+        self._buffer.size()
+    }
+
     #[inline]
     pub fn device_address(&self) -> vk::DeviceAddress {
         unsafe {
@@ -275,6 +323,274 @@ impl DebugType for GfxAcceleration {
     }
 }
 
+/// 可以 refit 的 blas，用于顶点会在 GPU 上被修改的 geometry（例如蒙皮）
+///
+/// 与 [`GfxAcceleration::build_blas_compacted`] 不同：
+/// - 构建时带有 `ALLOW_UPDATE` 和 `PREFER_FAST_BUILD`，不进行 compact
+/// - 持有一个 scratch buffer，可以在每帧的命令中通过 [`Self::cmd_refit`] 原地更新
+///
+/// refit 只会更新包围盒，拓扑（index、primitive 数量）必须与构建时一致；
+/// 顶点变化过大时 BVH 质量会下降，需要重新构建
+pub struct GfxUpdatableBlas {
+    acceleration: GfxAcceleration,
+    /// 构建和 refit 共用的 scratch buffer，大小取两者的较大值
+    scratch_buffer: GfxAccelerationScratchBuffer,
+}
+// new & init
+impl GfxUpdatableBlas {
+    /// 同步构建 blas
+    pub fn new_sync(blas_inputs: &[GfxBlasInputInfo], debug_name: impl AsRef<str>) -> Self {
+        let _span = tracy_client::span!("GfxUpdatableBlas::new_sync");
+
+        let geometries = blas_inputs.iter().map(|blas_input| blas_input.geometry).collect_vec();
+        let range_infos = blas_inputs.iter().map(|blas_input| blas_input.range).collect_vec();
+        let max_primitives = blas_inputs.iter().map(|blas_input| blas_input.range.primitive_count).collect_vec();
+
+        let mut build_geometry_info =
+            Self::build_geometry_info(&geometries, vk::BuildAccelerationStructureModeKHR::BUILD);
+
+        let size_info = unsafe {
+            let mut size_info = vk::AccelerationStructureBuildSizesInfoKHR::default();
+            Gfx::get().gfx_device().acceleration_structure.get_acceleration_structure_build_sizes(
+                vk::AccelerationStructureBuildTypeKHR::DEVICE,
+                &build_geometry_info,
+                &max_primitives,
+                &mut size_info,
+            );
+            size_info
+        };
+
+        let acceleration = GfxAcceleration::new(
+            size_info.acceleration_structure_size,
+            vk::AccelerationStructureTypeKHR::BOTTOM_LEVEL,
+            format!("{}-updatable-blas", debug_name.as_ref()),
+        );
+        let scratch_buffer = GfxAccelerationScratchBuffer::new(
+            size_info.build_scratch_size.max(size_info.update_scratch_size),
+            format!("{}-updatable-blas-scratch-buffer", debug_name.as_ref()),
+        );
+
+        build_geometry_info.dst_acceleration_structure = acceleration.acceleration_handle;
+        build_geometry_info.scratch_data = vk::DeviceOrHostAddressKHR {
+            device_address: scratch_buffer.device_address(),
+        };
+
+        Gfx::get().one_time_exec(
+            |cmd| {
+                cmd.build_acceleration_structure(&build_geometry_info, &range_infos);
+            },
+            "build-updatable-blas",
+        );
+
+        Self {
+            acceleration,
+            scratch_buffer,
+        }
+    }
+
+    fn build_geometry_info<'a>(
+        geometries: &'a [vk::AccelerationStructureGeometryKHR<'a>],
+        mode: vk::BuildAccelerationStructureModeKHR,
+    ) -> vk::AccelerationStructureBuildGeometryInfoKHR<'a> {
+        vk::AccelerationStructureBuildGeometryInfoKHR::default()
+            .ty(vk::AccelerationStructureTypeKHR::BOTTOM_LEVEL)
+            .flags(
+                vk::BuildAccelerationStructureFlagsKHR::ALLOW_UPDATE
+                    | vk::BuildAccelerationStructureFlagsKHR::PREFER_FAST_BUILD,
+            )
+            .geometries(geometries)
+            .mode(mode)
+    }
+}
+// getters
+impl GfxUpdatableBlas {
+    #[inline]
+    pub fn acceleration(&self) -> &GfxAcceleration {
+        &self.acceleration
+    }
+
+    #[inline]
+    pub fn device_address(&self) -> vk::DeviceAddress {
+        self.acceleration.device_address()
+    }
+}
+// tools
+impl GfxUpdatableBlas {
+    /// 录制原地 refit 的命令
+    ///
+    /// `blas_inputs` 需要与构建时的 geometry 一一对应；调用方负责顶点写入与 refit 之间，
+    /// 以及 refit 与后续 TLAS 构建 / ray tracing 之间的 barrier。
+    /// 同一个 blas 的多次 refit 共用 scratch buffer，不能在没有 barrier 的情况下连续录制
+    pub fn cmd_refit(&self, cmd: &GfxCommandBuffer, blas_inputs: &[GfxBlasInputInfo]) {
+        let geometries = blas_inputs.iter().map(|blas_input| blas_input.geometry).collect_vec();
+        let range_infos = blas_inputs.iter().map(|blas_input| blas_input.range).collect_vec();
+
+        let mut build_geometry_info =
+            Self::build_geometry_info(&geometries, vk::BuildAccelerationStructureModeKHR::UPDATE);
+        build_geometry_info.src_acceleration_structure = self.acceleration.acceleration_handle;
+        build_geometry_info.dst_acceleration_structure = self.acceleration.acceleration_handle;
+        build_geometry_info.scratch_data = vk::DeviceOrHostAddressKHR {
+            device_address: self.scratch_buffer.device_address(),
+        };
+
+        cmd.build_acceleration_structure(&build_geometry_info, &range_infos);
+    }
+}
+/// 可以 refit 的 tlas，用于只有 instance 变换发生变化的动态场景
+///
+/// 构建时带有 `ALLOW_UPDATE`，持有 instance buffer 和 scratch buffer，
+/// 通过 [`Self::update_tlas`] 原地刷新，避免每帧完整重建以及重新分配内存。
+///
+/// refit 不会重新组织 BVH，instance 移动幅度过大时遍历性能会下降
+pub struct GfxUpdatableTlas {
+    acceleration: GfxAcceleration,
+    instance_buffer: GfxAccelerationInstanceBuffer,
+    /// 构建和 refit 共用的 scratch buffer，大小取两者的较大值
+    scratch_buffer: GfxAccelerationScratchBuffer,
+    /// 构建时的 instance 数量，refit 要求数量不变
+    instance_cnt: usize,
+
+    debug_name: String,
+}
+// new & init
+impl GfxUpdatableTlas {
+    /// 同步构建 tlas
+    pub fn new_sync(instances: &[vk::AccelerationStructureInstanceKHR], debug_name: impl AsRef<str>) -> Self {
+        let _span = tracy_client::span!("GfxUpdatableTlas::new_sync");
+
+        let instance_buffer = GfxAccelerationInstanceBuffer::new(
+            size_of_val(instances) as vk::DeviceSize,
+            format!("{}-tlas-instance-buffer", debug_name.as_ref()),
+        );
+        instance_buffer.transfer_data_sync(instances);
+
+        let geometry = Self::instances_geometry(&instance_buffer);
+        let mut build_geometry_info =
+            Self::build_geometry_info(std::slice::from_ref(&geometry), vk::BuildAccelerationStructureModeKHR::BUILD);
+
+        let size_info = unsafe {
+            let mut size_info = vk::AccelerationStructureBuildSizesInfoKHR::default();
+            Gfx::get().gfx_device().acceleration_structure.get_acceleration_structure_build_sizes(
+                vk::AccelerationStructureBuildTypeKHR::DEVICE,
+                &build_geometry_info,
+                &[instances.len() as u32],
+                &mut size_info,
+            );
+            size_info
+        };
+
+        let acceleration = GfxAcceleration::new(
+            size_info.acceleration_structure_size,
+            vk::AccelerationStructureTypeKHR::TOP_LEVEL,
+            format!("{}-updatable-tlas", debug_name.as_ref()),
+        );
+        let scratch_buffer = GfxAccelerationScratchBuffer::new(
+            size_info.build_scratch_size.max(size_info.update_scratch_size),
+            format!("{}-updatable-tlas-scratch-buffer", debug_name.as_ref()),
+        );
+
+        build_geometry_info.dst_acceleration_structure = acceleration.acceleration_handle;
+        build_geometry_info.scratch_data = vk::DeviceOrHostAddressKHR {
+            device_address: scratch_buffer.device_address(),
+        };
+
+        let range_info = vk::AccelerationStructureBuildRangeInfoKHR::default().primitive_count(instances.len() as u32);
+        Gfx::get().one_time_exec(
+            |cmd| {
+                cmd.build_acceleration_structure(&build_geometry_info, std::slice::from_ref(&range_info));
+            },
+            "build-updatable-tlas",
+        );
+
+        Self {
+            acceleration,
+            instance_buffer,
+            scratch_buffer,
+            instance_cnt: instances.len(),
+            debug_name: debug_name.as_ref().to_string(),
+        }
+    }
+
+    fn instances_geometry(
+        instance_buffer: &GfxAccelerationInstanceBuffer,
+    ) -> vk::AccelerationStructureGeometryKHR<'static> {
+        vk::AccelerationStructureGeometryKHR::default().geometry_type(vk::GeometryTypeKHR::INSTANCES).geometry(
+            vk::AccelerationStructureGeometryDataKHR {
+                instances: vk::AccelerationStructureGeometryInstancesDataKHR::default().array_of_pointers(false).data(
+                    vk::DeviceOrHostAddressConstKHR {
+                        device_address: instance_buffer.device_address(),
+                    },
+                ),
+            },
+        )
+    }
+
+    fn build_geometry_info<'a>(
+        geometries: &'a [vk::AccelerationStructureGeometryKHR<'a>],
+        mode: vk::BuildAccelerationStructureModeKHR,
+    ) -> vk::AccelerationStructureBuildGeometryInfoKHR<'a> {
+        vk::AccelerationStructureBuildGeometryInfoKHR::default()
+            .ty(vk::AccelerationStructureTypeKHR::TOP_LEVEL)
+            .flags(
+                vk::BuildAccelerationStructureFlagsKHR::ALLOW_UPDATE
+                    | vk::BuildAccelerationStructureFlagsKHR::PREFER_FAST_TRACE,
+            )
+            .geometries(geometries)
+            .mode(mode)
+    }
+}
+// getters
+impl GfxUpdatableTlas {
+    #[inline]
+    pub fn acceleration(&self) -> &GfxAcceleration {
+        &self.acceleration
+    }
+
+    #[inline]
+    pub fn instance_cnt(&self) -> usize {
+        self.instance_cnt
+    }
+}
+// update
+impl GfxUpdatableTlas {
+    /// 同步刷新 tlas
+    ///
+    /// instance 数量与构建时一致时使用 `UPDATE` 模式原地 refit；数量变化时完整重建。
+    /// 调用方需要保证 tlas 当前没有被 GPU 使用。
+    ///
+    /// # 返回值
+    /// 是否走了 refit 路径
+    pub fn update_tlas(&mut self, instances: &[vk::AccelerationStructureInstanceKHR]) -> bool {
+        let _span = tracy_client::span!("GfxUpdatableTlas::update_tlas");
+
+        if instances.len() != self.instance_cnt {
+            *self = Self::new_sync(instances, &self.debug_name);
+            return false;
+        }
+
+        self.instance_buffer.transfer_data_sync(instances);
+
+        let geometry = Self::instances_geometry(&self.instance_buffer);
+        let mut build_geometry_info =
+            Self::build_geometry_info(std::slice::from_ref(&geometry), vk::BuildAccelerationStructureModeKHR::UPDATE);
+        build_geometry_info.src_acceleration_structure = self.acceleration.acceleration_handle;
+        build_geometry_info.dst_acceleration_structure = self.acceleration.acceleration_handle;
+        build_geometry_info.scratch_data = vk::DeviceOrHostAddressKHR {
+            device_address: self.scratch_buffer.device_address(),
+        };
+
+        let range_info = vk::AccelerationStructureBuildRangeInfoKHR::default().primitive_count(instances.len() as u32);
+        Gfx::get().one_time_exec(
+            |cmd| {
+                cmd.build_acceleration_structure(&build_geometry_info, std::slice::from_ref(&range_info));
+            },
+            "update-tlas",
+        );
+
+        true
+    }
+}
+
 /// 用于构建 Blas 的输入信息
 ///
 /// 包含 geometry 的 buffer 信息，以及图元的描述信息
@@ -282,3 +598,37 @@ pub struct GfxBlasInputInfo<'a> {
     pub geometry: vk::AccelerationStructureGeometryKHR<'a>,
     pub range: vk::AccelerationStructureBuildRangeInfoKHR,
 }
+// builder
+impl GfxBlasInputInfo<'_> {
+    /// 为三角形 geometry 指定构建 BLAS 时使用的变换矩阵
+    ///
+    /// 顶点在构建时会先经过 `transform_buffer` 中第 `transform_index` 个矩阵的变换，
+    /// 这样同一份顶点 buffer 可以在不同变换下构建 BLAS。
+    ///
+    /// 注：BLAS 构建时才会读取 transform buffer，调用方需要保证 buffer 在构建完成前有效
+    pub fn with_transform(mut self, transform_buffer: &GfxAccelerationTransformBuffer, transform_index: usize) -> Self {
+        assert_eq!(
+            self.geometry.geometry_type,
+            vk::GeometryTypeKHR::TRIANGLES,
+            "geometry transform is only valid for triangles geometry"
+        );
+
+        let transform_address = transform_buffer.device_address();
+        let transform_offset = transform_buffer.transform_offset(transform_index);
+        debug_assert!(
+            (transform_address + transform_offset as vk::DeviceAddress)
+                .is_multiple_of(GfxAccelerationTransformBuffer::ALIGNMENT),
+            "transform data must be aligned to {} bytes",
+            GfxAccelerationTransformBuffer::ALIGNMENT
+        );
+
+        // geometry 是 union，上面已经确认了是 triangles
+        unsafe {
+            self.geometry.geometry.triangles.transform_data = vk::DeviceOrHostAddressConstKHR {
+                device_address: transform_address,
+            };
+        }
+        self.range.transform_offset = transform_offset;
+        self
+    }
+}
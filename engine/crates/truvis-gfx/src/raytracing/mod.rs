@@ -1 +1,2 @@
 pub mod acceleration;
+pub mod rt_pipeline;
@@ -0,0 +1,535 @@
+use std::cell::Ref;
+use std::ffi::CStr;
+use std::rc::Rc;
+
+use ash::vk;
+
+use crate::foundation::debug_messenger::DebugType;
+use crate::gfx::Gfx;
+use crate::pipelines::graphics_pipeline::GfxPipelineLayout;
+use crate::pipelines::hot_reload::{GfxPipelineHandle, GfxReloadablePipeline};
+use crate::pipelines::shader::{GfxShaderModuleCache, GfxShaderStageInfo};
+use crate::pipelines::specialization::GfxSpecializationMap;
+use crate::resources::special_buffers::sbt_buffer::GfxSBTBuffer;
+
+/// ray tracing pipeline 的创建参数，创建时会同时构建 shader binding table
+///
+/// shader group 按照 raygen、miss、hit、callable 的顺序排列，
+/// 同一类 group 在 SBT 中的序号就是添加的顺序，例如 shader 中 `TraceRay` 的 miss index
+///
+/// # 使用示例
+/// ```ignore
+/// let pipeline = GfxRtPipelineBuilder::default()
+///     .raygen_shader("rt/raygen.slang.spv", c"main_ray_gen")
+///     .miss_shader("rt/miss.slang.spv", c"main_miss")
+///     .hit_group(Some(("rt/closest_hit.slang.spv", c"main_closest_hit")), None)
+///     .descriptor_set_layouts(set_layouts)
+///     .push_constant_ranges(vec![push_constant_range])
+///     .build("simple-rt")?;
+/// ```
+#[derive(Clone)]
+pub struct GfxRtPipelineBuilder {
+    stages: Vec<GfxShaderStageInfo>,
+
+    raygen_groups: Vec<vk::RayTracingShaderGroupCreateInfoKHR<'static>>,
+    miss_groups: Vec<vk::RayTracingShaderGroupCreateInfoKHR<'static>>,
+    hit_groups: Vec<vk::RayTracingShaderGroupCreateInfoKHR<'static>>,
+    callable_groups: Vec<vk::RayTracingShaderGroupCreateInfoKHR<'static>>,
+
+    descriptor_set_layouts: Vec<vk::DescriptorSetLayout>,
+    push_constant_ranges: Vec<vk::PushConstantRange>,
+    max_recursion_depth: u32,
+
+    /// 所有 stage 共用
+    specialization: GfxSpecializationMap,
+}
+impl Default for GfxRtPipelineBuilder {
+    fn default() -> Self {
+        Self {
+            stages: vec![],
+            raygen_groups: vec![],
+            miss_groups: vec![],
+            hit_groups: vec![],
+            callable_groups: vec![],
+            descriptor_set_layouts: vec![],
+            push_constant_ranges: vec![],
+            max_recursion_depth: 1,
+            specialization: GfxSpecializationMap::new(),
+        }
+    }
+}
+// builder
+impl GfxRtPipelineBuilder {
+    /// builder
+    pub fn raygen_shader(&mut self, path: &str, entry_point: &'static CStr) -> &mut Self {
+        let stage = self.add_stage(vk::ShaderStageFlags::RAYGEN_KHR, path, entry_point);
+        self.raygen_groups.push(Self::general_group(stage));
+        self
+    }
+
+    /// builder
+    pub fn miss_shader(&mut self, path: &str, entry_point: &'static CStr) -> &mut Self {
+        let stage = self.add_stage(vk::ShaderStageFlags::MISS_KHR, path, entry_point);
+        self.miss_groups.push(Self::general_group(stage));
+        self
+    }
+
+    /// builder
+    ///
+    /// 三角形的 hit group，closest hit 和 any hit 都是可选的，参数为 (spv 路径, entry point)
+    pub fn hit_group(
+        &mut self,
+        closest_hit: Option<(&str, &'static CStr)>,
+        any_hit: Option<(&str, &'static CStr)>,
+    ) -> &mut Self {
+        let closest_hit = closest_hit.map_or(vk::SHADER_UNUSED_KHR, |(path, entry_point)| {
+            self.add_stage(vk::ShaderStageFlags::CLOSEST_HIT_KHR, path, entry_point)
+        });
+        let any_hit = any_hit.map_or(vk::SHADER_UNUSED_KHR, |(path, entry_point)| {
+            self.add_stage(vk::ShaderStageFlags::ANY_HIT_KHR, path, entry_point)
+        });
+        self.hit_groups.push(vk::RayTracingShaderGroupCreateInfoKHR {
+            ty: vk::RayTracingShaderGroupTypeKHR::TRIANGLES_HIT_GROUP,
+            general_shader: vk::SHADER_UNUSED_KHR,
+            closest_hit_shader: closest_hit,
+            any_hit_shader: any_hit,
+            intersection_shader: vk::SHADER_UNUSED_KHR,
+            ..Default::default()
+        });
+        self
+    }
+
+    /// builder
+    pub fn callable_shader(&mut self, path: &str, entry_point: &'static CStr) -> &mut Self {
+        let stage = self.add_stage(vk::ShaderStageFlags::CALLABLE_KHR, path, entry_point);
+        self.callable_groups.push(Self::general_group(stage));
+        self
+    }
+
+    /// builder
+    #[inline]
+    pub fn descriptor_set_layouts(&mut self, layouts: Vec<vk::DescriptorSetLayout>) -> &mut Self {
+        self.descriptor_set_layouts = layouts;
+        self
+    }
+
+    /// builder
+    #[inline]
+    pub fn push_constant_ranges(&mut self, ranges: Vec<vk::PushConstantRange>) -> &mut Self {
+        self.push_constant_ranges = ranges;
+        self
+    }
+
+    /// builder
+    ///
+    /// 仅用于分配栈内存，超过递归深度的 `TraceRay` 不会被丢弃，需要在 shader 中自己跟踪递归深度
+    #[inline]
+    pub fn max_recursion_depth(&mut self, depth: u32) -> &mut Self {
+        self.max_recursion_depth = depth;
+        self
+    }
+
+    /// builder
+    ///
+    /// 所有 stage 使用同一组 specialization constant，相同的 `constant_id` 以后设置的为准
+    #[inline]
+    pub fn specialization(&mut self, specialization: GfxSpecializationMap) -> &mut Self {
+        self.specialization.extend(&specialization);
+        self
+    }
+}
+// getter
+impl GfxRtPipelineBuilder {
+    /// 依赖的 spv 文件，用于 shader 热重载
+    pub fn shader_paths(&self) -> Vec<&std::path::Path> {
+        self.stages.iter().map(|stage| stage.path()).collect()
+    }
+}
+// tools
+impl GfxRtPipelineBuilder {
+    /// 创建 pipeline 以及对应的 SBT，spv 无法加载或者 pipeline 创建失败时返回错误
+    ///
+    /// 创建的 pipeline 会注册热重载，依赖的 spv 发生变化时 pipeline 和 SBT 一起重建
+    ///
+    /// # Panics
+    /// 没有添加 raygen shader
+    pub fn build(&self, debug_name: &str) -> Result<GfxRtPipeline, String> {
+        assert!(!self.raygen_groups.is_empty(), "rt pipeline {debug_name}: raygen shader is required");
+
+        let pipeline_layout = Rc::new(GfxPipelineLayout::new(
+            &self.descriptor_set_layouts,
+            &self.push_constant_ranges,
+            format!("{debug_name}-layout"),
+        ));
+        let create = {
+            let builder = self.clone();
+            let pipeline_layout = pipeline_layout.clone();
+            let debug_name = debug_name.to_string();
+            move || builder.create_pipeline(pipeline_layout.handle(), &debug_name)
+        };
+        let pipeline = create()?;
+
+        Ok(GfxRtPipeline {
+            pipeline: GfxReloadablePipeline::new(
+                pipeline,
+                self.shader_paths(),
+                &format!("{}::{}", GfxRtPipeline::debug_type_name(), debug_name),
+                create,
+            ),
+            pipeline_layout,
+        })
+    }
+
+    /// 创建 vk::Pipeline 以及对应的 SBT
+    fn create_pipeline(
+        &self,
+        pipeline_layout: vk::PipelineLayout,
+        debug_name: &str,
+    ) -> Result<GfxRtPipelineState, String> {
+        let mut shader_module_cache = GfxShaderModuleCache::new();
+        let specialization_info = self.specialization.vk_info();
+        let mut stage_infos = Vec::with_capacity(self.stages.len());
+        for stage in &self.stages {
+            let shader_module = match shader_module_cache.try_get_or_load(stage.path()) {
+                Ok(shader_module) => shader_module,
+                Err(e) => {
+                    shader_module_cache.destroy();
+                    return Err(e);
+                }
+            };
+            let stage_info = vk::PipelineShaderStageCreateInfo::default()
+                .module(shader_module.handle())
+                .stage(stage.stage)
+                .name(stage.entry_point);
+            stage_infos.push(if self.specialization.is_empty() {
+                stage_info
+            } else {
+                stage_info.specialization_info(&specialization_info)
+            });
+        }
+
+        let groups = [
+            &self.raygen_groups,
+            &self.miss_groups,
+            &self.hit_groups,
+            &self.callable_groups,
+        ]
+        .into_iter()
+        .flatten()
+        .copied()
+        .collect::<Vec<_>>();
+
+        let pipeline_ci = vk::RayTracingPipelineCreateInfoKHR::default()
+            .stages(&stage_infos)
+            .groups(&groups)
+            .layout(pipeline_layout)
+            .max_pipeline_ray_recursion_depth(self.max_recursion_depth);
+
+        let pipeline = unsafe {
+            Gfx::get().gfx_device().ray_tracing_pipeline().create_ray_tracing_pipelines(
+                vk::DeferredOperationKHR::null(),
+                Gfx::get().pipeline_cache().handle(),
+                std::slice::from_ref(&pipeline_ci),
+                None,
+            )
+        };
+
+        shader_module_cache.destroy();
+
+        let pipeline = match pipeline {
+            Ok(pipelines) => GfxPipelineHandle(pipelines[0]),
+            Err((_, e)) => return Err(format!("Failed to create ray tracing pipeline {}: {:?}", debug_name, e)),
+        };
+        Gfx::get()
+            .gfx_device()
+            .set_object_debug_name(pipeline.0, format!("{}::{}", GfxRtPipeline::debug_type_name(), debug_name));
+
+        let group_cnts = [
+            self.raygen_groups.len() as u32,
+            self.miss_groups.len() as u32,
+            self.hit_groups.len() as u32,
+            self.callable_groups.len() as u32,
+        ];
+        Ok(GfxRtPipelineState {
+            sbt: GfxShaderBindingTable::new(pipeline.0, group_cnts, debug_name),
+            pipeline,
+        })
+    }
+
+    /// 相同的 shader 只会出现在 stages 中一次，返回 shader 在 stages 中的序号
+    fn add_stage(&mut self, stage: vk::ShaderStageFlags, path: &str, entry_point: &'static CStr) -> u32 {
+        let existing = self
+            .stages
+            .iter()
+            .position(|info| info.stage == stage && info.path == path && info.entry_point == entry_point);
+        let idx = existing.unwrap_or_else(|| {
+            self.stages.push(GfxShaderStageInfo {
+                stage,
+                entry_point,
+                path: path.to_string(),
+            });
+            self.stages.len() - 1
+        });
+        idx as u32
+    }
+
+    fn general_group(stage: u32) -> vk::RayTracingShaderGroupCreateInfoKHR<'static> {
+        vk::RayTracingShaderGroupCreateInfoKHR {
+            ty: vk::RayTracingShaderGroupTypeKHR::GENERAL,
+            general_shader: stage,
+            closest_hit_shader: vk::SHADER_UNUSED_KHR,
+            any_hit_shader: vk::SHADER_UNUSED_KHR,
+            intersection_shader: vk::SHADER_UNUSED_KHR,
+            ..Default::default()
+        }
+    }
+}
+
+/// ray tracing 管线，持有自己的 pipeline layout 和 shader binding table
+///
+/// 绑定时使用 `vk::PipelineBindPoint::RAY_TRACING_KHR`，通过
+/// [`GfxCommandBuffer::cmd_trace_rays`] 执行
+///
+/// [`GfxCommandBuffer::cmd_trace_rays`]: crate::commands::command_buffer::GfxCommandBuffer::cmd_trace_rays
+pub struct GfxRtPipeline {
+    /// 依赖的 spv 发生变化时原地重建，参见 [`GfxPipelineReloader`]
+    ///
+    /// [`GfxPipelineReloader`]: crate::pipelines::hot_reload::GfxPipelineReloader
+    pipeline: Rc<GfxReloadablePipeline<GfxRtPipelineState>>,
+
+    /// 与 graphics pipeline 保持一致，pipeline layout 可以被共享
+    pipeline_layout: Rc<GfxPipelineLayout>,
+}
+// getter
+impl GfxRtPipeline {
+    #[inline]
+    pub fn handle(&self) -> vk::Pipeline {
+        self.pipeline.current().pipeline.0
+    }
+
+    #[inline]
+    pub fn layout(&self) -> vk::PipelineLayout {
+        self.pipeline_layout.handle()
+    }
+
+    /// 热重载时会被替换，不要跨帧持有
+    #[inline]
+    pub fn sbt(&self) -> Ref<'_, GfxShaderBindingTable> {
+        Ref::map(self.pipeline.current(), |state| &state.sbt)
+    }
+}
+// destroy
+impl GfxRtPipeline {
+    #[inline]
+    pub fn destroy(self) {
+        // drop
+    }
+}
+impl DebugType for GfxRtPipeline {
+    fn debug_type_name() -> &'static str {
+        "GfxRtPipeline"
+    }
+
+    fn vk_handle(&self) -> impl vk::Handle {
+        self.handle()
+    }
+}
+
+/// SBT 中的 shader group handle 来自 pipeline，热重载时两者一起替换
+struct GfxRtPipelineState {
+    pipeline: GfxPipelineHandle,
+    sbt: GfxShaderBindingTable,
+}
+
+/// 一个 region 在 SBT buffer 中的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SbtRegionLayout {
+    offset: vk::DeviceSize,
+    stride: vk::DeviceSize,
+    size: vk::DeviceSize,
+}
+
+/// shader binding table，依次存放 raygen、miss、hit、callable 四个 region
+///
+/// 不需要 shader record 的 user data，每个 record 只有 shader group handle
+pub struct GfxShaderBindingTable {
+    /// raygen, miss, hit, callable
+    regions: [vk::StridedDeviceAddressRegionKHR; 4],
+    raygen_cnt: u32,
+
+    _buffer: GfxSBTBuffer,
+}
+// new & init
+impl GfxShaderBindingTable {
+    const RAYGEN: usize = 0;
+    const MISS: usize = 1;
+    const HIT: usize = 2;
+    const CALLABLE: usize = 3;
+
+    /// # 参数
+    /// - group_cnts: raygen、miss、hit、callable 各自的 group 数量，pipeline 中的 group 需要按照这个顺序排列
+    fn new(pipeline: vk::Pipeline, group_cnts: [u32; 4], debug_name: &str) -> Self {
+        let rt_pipeline_props = Gfx::get().rt_pipeline_props();
+        let handle_size = rt_pipeline_props.shader_group_handle_size;
+        let base_alignment = rt_pipeline_props.shader_group_base_alignment;
+        let (layouts, total_size) =
+            Self::layout(handle_size, rt_pipeline_props.shader_group_handle_alignment, base_alignment, group_cnts);
+
+        let buffer = GfxSBTBuffer::new(total_size, base_alignment as vk::DeviceSize, format!("{debug_name}-sbt"));
+
+        // 从 pipeline 中获取 shader group handle，写入到各个 region 中
+        let group_cnt = group_cnts.iter().sum::<u32>();
+        let handle_data = unsafe {
+            Gfx::get()
+                .gfx_device()
+                .ray_tracing_pipeline()
+                .get_ray_tracing_shader_group_handles(pipeline, 0, group_cnt, (group_cnt * handle_size) as usize)
+                .unwrap()
+        };
+        // 先在 CPU 上排列好整个 SBT，再一次性上传到 device local 的 buffer 中
+        let mut sbt_data = vec![0u8; total_size as usize];
+        let mut group_idx = 0;
+        for (layout, cnt) in layouts.iter().zip(group_cnts) {
+            for record_idx in 0..cnt as usize {
+                let src_begin = group_idx * handle_size as usize;
+                let src = &handle_data[src_begin..src_begin + handle_size as usize];
+                let dst_offset = layout.offset as usize + record_idx * layout.stride as usize;
+                sbt_data[dst_offset..dst_offset + handle_size as usize].copy_from_slice(src);
+                group_idx += 1;
+            }
+        }
+        buffer.transfer_data_sync(&sbt_data);
+
+        let sbt_address = buffer.device_address();
+        let regions = layouts.map(|layout| {
+            // 空的 region 需要全部为 0
+            if layout.size == 0 {
+                return vk::StridedDeviceAddressRegionKHR::default();
+            }
+            vk::StridedDeviceAddressRegionKHR::default()
+                .device_address(sbt_address + layout.offset)
+                .stride(layout.stride)
+                .size(layout.size)
+        });
+
+        Self {
+            regions,
+            raygen_cnt: group_cnts[Self::RAYGEN],
+            _buffer: buffer,
+        }
+    }
+
+    /// 计算每个 region 的位置以及 SBT 的总大小
+    ///
+    /// - 每个 region 的起始位置需要对齐到 `base_alignment`
+    /// - region 中每个 record 的 stride 需要对齐到 `handle_alignment`
+    /// - raygen region 的 size 必须等于 stride，因此每个 raygen record 都单独对齐到 `base_alignment`
+    fn layout(
+        handle_size: u32,
+        handle_alignment: u32,
+        base_alignment: u32,
+        group_cnts: [u32; 4],
+    ) -> ([SbtRegionLayout; 4], vk::DeviceSize) {
+        let handle_size = handle_size as vk::DeviceSize;
+        let handle_alignment = handle_alignment as vk::DeviceSize;
+        let base_alignment = base_alignment as vk::DeviceSize;
+
+        let record_stride = handle_size.next_multiple_of(handle_alignment);
+        let raygen_stride = record_stride.next_multiple_of(base_alignment);
+
+        let mut offset = 0;
+        let layouts = std::array::from_fn(|region| {
+            let cnt = group_cnts[region] as vk::DeviceSize;
+            let stride = if region == Self::RAYGEN { raygen_stride } else { record_stride };
+            let size = (cnt * stride).next_multiple_of(base_alignment);
+            let layout = SbtRegionLayout {
+                offset,
+                stride: if cnt == 0 { 0 } else { stride },
+                size,
+            };
+            offset += size;
+            layout
+        });
+        (layouts, offset)
+    }
+}
+// getter
+impl GfxShaderBindingTable {
+    /// 第 `idx` 个 raygen shader 对应的 region
+    ///
+    /// # Panics
+    /// `idx` 超出 raygen shader 的数量
+    pub fn raygen_region(&self, idx: u32) -> vk::StridedDeviceAddressRegionKHR {
+        assert!(idx < self.raygen_cnt, "raygen index {} out of range {}", idx, self.raygen_cnt);
+        let region = self.regions[Self::RAYGEN];
+        region.device_address(region.device_address + idx as vk::DeviceSize * region.stride).size(region.stride)
+    }
+
+    #[inline]
+    pub fn miss_region(&self) -> &vk::StridedDeviceAddressRegionKHR {
+        &self.regions[Self::MISS]
+    }
+
+    #[inline]
+    pub fn hit_region(&self) -> &vk::StridedDeviceAddressRegionKHR {
+        &self.regions[Self::HIT]
+    }
+
+    #[inline]
+    pub fn callable_region(&self) -> &vk::StridedDeviceAddressRegionKHR {
+        &self.regions[Self::CALLABLE]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sbt_layout() {
+        // 常见 NVIDIA 设备的参数：handle 32 字节，handle 对齐 32，region 对齐 64
+        let (layouts, total_size) = GfxShaderBindingTable::layout(32, 32, 64, [1, 2, 1, 1]);
+        assert_eq!(
+            layouts,
+            [
+                SbtRegionLayout {
+                    offset: 0,
+                    stride: 64,
+                    size: 64
+                },
+                SbtRegionLayout {
+                    offset: 64,
+                    stride: 32,
+                    size: 64
+                },
+                SbtRegionLayout {
+                    offset: 128,
+                    stride: 32,
+                    size: 64
+                },
+                SbtRegionLayout {
+                    offset: 192,
+                    stride: 32,
+                    size: 64
+                },
+            ]
+        );
+        assert_eq!(total_size, 256);
+    }
+
+    #[test]
+    fn test_sbt_layout_multiple_raygen_and_empty_region() {
+        let (layouts, total_size) = GfxShaderBindingTable::layout(32, 32, 64, [2, 3, 1, 0]);
+        // 每个 raygen record 单独对齐到 base alignment
+        assert_eq!(layouts[0].stride, 64);
+        assert_eq!(layouts[0].size, 128);
+        // 3 个 miss record 占用 96 字节，region 对齐到 128
+        assert_eq!(layouts[1].offset, 128);
+        assert_eq!(layouts[1].size, 128);
+        assert_eq!(layouts[2].offset, 256);
+        // 没有 callable shader
+        assert_eq!(layouts[3].stride, 0);
+        assert_eq!(layouts[3].size, 0);
+        assert_eq!(total_size, 320);
+    }
+}
@@ -1,9 +1,15 @@
 use ash::vk;
+use std::cell::Cell;
 use std::ptr;
 
 use vk_mem::Alloc;
 
-use crate::{foundation::debug_messenger::DebugType, gfx::Gfx};
+use crate::{
+    commands::{async_transfer::TransferTicket, barrier::GfxBufferBarrier, resource_state::GfxBufferState},
+    foundation::debug_messenger::DebugType,
+    gfx::Gfx,
+    resources::resource_stats::GfxResourceKind,
+};
 
 pub struct GfxBuffer {
     handle: vk::Buffer,
@@ -18,7 +24,10 @@ pub struct GfxBuffer {
 
     debug_name: String,
 
-    _usage: vk::BufferUsageFlags,
+    usage: vk::BufferUsageFlags,
+
+    /// 按录制顺序追踪的状态，参见 [`crate::commands::command_buffer::GfxCommandBuffer::transition_buffer`]
+    state: Cell<GfxBufferState>,
 }
 impl DebugType for GfxBuffer {
     fn debug_type_name() -> &'static str {
@@ -31,6 +40,10 @@ impl DebugType for GfxBuffer {
 }
 impl Drop for GfxBuffer {
     fn drop(&mut self) {
+        #[cfg(debug_assertions)]
+        Gfx::get().buffer_tracker.on_destroy(self.handle);
+        Gfx::get().resource_stats.on_destroy(GfxResourceKind::Buffer, 1);
+
         let allocator = Gfx::get().allocator();
         unsafe {
             if self.map_ptr.is_some() {
@@ -93,6 +106,9 @@ impl GfxBuffer {
         }
 
         Gfx::get().gfx_device().set_object_debug_name(buffer, format!("Buffer::{}", name.as_ref()));
+        #[cfg(debug_assertions)]
+        Gfx::get().buffer_tracker.on_create(buffer, name.as_ref(), buffer_size, buffer_usage);
+        Gfx::get().resource_stats.on_create(GfxResourceKind::Buffer, 1);
         Self {
             handle: buffer,
             allocation: alloc,
@@ -102,7 +118,9 @@ impl GfxBuffer {
 
             debug_name: name.as_ref().to_string(),
 
-            _usage: buffer_usage,
+            usage: buffer_usage,
+
+            state: Cell::new(GfxBufferState::UNDEFINED),
         }
     }
 
@@ -136,6 +154,35 @@ impl GfxBuffer {
     pub fn size(&self) -> vk::DeviceSize {
         self.size
     }
+
+    #[inline]
+    pub fn usage(&self) -> vk::BufferUsageFlags {
+        self.usage
+    }
+
+    #[inline]
+    pub fn is_mapped(&self) -> bool {
+        self.map_ptr.is_some()
+    }
+
+    #[inline]
+    pub fn debug_name(&self) -> &str {
+        &self.debug_name
+    }
+
+    /// 最近一次录制的命令执行之后 buffer 所处的状态
+    #[inline]
+    pub fn state(&self) -> GfxBufferState {
+        self.state.get()
+    }
+}
+// 状态追踪
+impl GfxBuffer {
+    /// 直接覆盖追踪的状态，不录制任何命令，参见 [`crate::resources::image::GfxImage::set_state`]
+    #[inline]
+    pub fn set_state(&self, state: GfxBufferState) {
+        self.state.set(state);
+    }
 }
 // tools
 impl GfxBuffer {
@@ -150,6 +197,13 @@ impl GfxBuffer {
         allocator.flush_allocation(&self.allocation, offset, size).unwrap();
     }
 
+    /// 使 GPU 写入的内容对 host 可见，在读取 mapped 内存之前调用
+    #[inline]
+    pub fn invalidate(&self, offset: vk::DeviceSize, size: vk::DeviceSize) {
+        let allocator = Gfx::get().allocator();
+        allocator.invalidate_allocation(&self.allocation, offset, size).unwrap();
+    }
+
     /// 通过 mem map 的方式将 data 传入到 buffer 中
     pub fn transfer_data_by_mmap<T>(&self, data: &[T])
     where
@@ -233,6 +287,60 @@ impl GfxBuffer {
         );
     }
 
+    /// 通过 transfer queue 异步上传数据，不会阻塞 CPU 和 graphics queue
+    ///
+    /// 返回的 [`TransferTicket`] 完成之前，需要保证 self 存活，并且不被 GPU 访问；
+    /// 完成之后可以直接在 graphics queue 上使用，buffer 需要带有 TRANSFER_DST usage
+    pub fn transfer_data_async(&self, data: &[impl Sized + Copy]) -> TransferTicket {
+        let size = size_of_val(data) as vk::DeviceSize;
+        let stage_buffer = Self::new_stage_buffer(size, format!("{}-async-stage-buffer", self.debug_name));
+        stage_buffer.transfer_data_by_mmap(data);
+
+        let dst_buffer = self.handle;
+        Gfx::get().async_transfer().submit(
+            stage_buffer,
+            |cmd, stage_buffer, ownership_transfer| {
+                cmd.cmd_copy_buffer(
+                    stage_buffer,
+                    self,
+                    &[vk::BufferCopy {
+                        size,
+                        ..Default::default()
+                    }],
+                );
+
+                // release：之后的访问在 acquire 中声明，这里只需要让拷贝的写入可用
+                if let Some(ownership_transfer) = ownership_transfer {
+                    cmd.buffer_memory_barrier(
+                        vk::DependencyFlags::empty(),
+                        &[GfxBufferBarrier::new()
+                            .buffer(dst_buffer, 0, size)
+                            .queue_family_transfer(
+                                ownership_transfer.src_queue_family_index,
+                                ownership_transfer.dst_queue_family_index,
+                            )
+                            .src_mask(vk::PipelineStageFlags2::TRANSFER, vk::AccessFlags2::TRANSFER_WRITE)
+                            .dst_mask(vk::PipelineStageFlags2::NONE, vk::AccessFlags2::NONE)],
+                    );
+                }
+            },
+            move |cmd, ownership_transfer| {
+                cmd.buffer_memory_barrier(
+                    vk::DependencyFlags::empty(),
+                    &[GfxBufferBarrier::new()
+                        .buffer(dst_buffer, 0, size)
+                        .queue_family_transfer(
+                            ownership_transfer.src_queue_family_index,
+                            ownership_transfer.dst_queue_family_index,
+                        )
+                        .src_mask(vk::PipelineStageFlags2::NONE, vk::AccessFlags2::NONE)
+                        .dst_mask(vk::PipelineStageFlags2::ALL_COMMANDS, vk::AccessFlags2::MEMORY_READ)],
+                );
+            },
+            &self.debug_name,
+        )
+    }
+
     /// 清空 buffer 内容为 0
     pub fn clear(&mut self) {
         Gfx::get().one_time_exec(
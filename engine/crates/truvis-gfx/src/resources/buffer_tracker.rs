@@ -0,0 +1,79 @@
+//! GfxBuffer 的创建来源追踪
+//!
+//! 仅在 debug build 下记录，用于排查显存占用和疑似泄漏。
+//! 调用栈通过 `std::backtrace::Backtrace::capture` 获取，只有设置了 `RUST_BACKTRACE=1`
+//! 时才会真正捕获，否则只记录名称、usage 和大小。
+
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use ash::vk;
+
+/// 一个存活 buffer 的创建信息
+#[derive(Clone, Debug)]
+pub struct GfxBufferRecord {
+    pub name: String,
+    pub size: vk::DeviceSize,
+    pub usage: vk::BufferUsageFlags,
+    /// 创建时的调用栈
+    pub backtrace: Rc<Backtrace>,
+}
+
+/// 记录所有存活的 GfxBuffer
+#[derive(Default)]
+pub struct GfxBufferTracker {
+    records: RefCell<HashMap<vk::Buffer, GfxBufferRecord>>,
+}
+
+// update
+impl GfxBufferTracker {
+    pub fn on_create(
+        &self,
+        buffer: vk::Buffer,
+        name: impl AsRef<str>,
+        size: vk::DeviceSize,
+        usage: vk::BufferUsageFlags,
+    ) {
+        self.records.borrow_mut().insert(
+            buffer,
+            GfxBufferRecord {
+                name: name.as_ref().to_string(),
+                size,
+                usage,
+                backtrace: Rc::new(Backtrace::capture()),
+            },
+        );
+    }
+
+    pub fn on_destroy(&self, buffer: vk::Buffer) {
+        self.records.borrow_mut().remove(&buffer);
+    }
+}
+
+// tools
+impl GfxBufferTracker {
+    /// 所有存活的 buffer，按大小降序排列
+    pub fn live_buffers(&self) -> Vec<GfxBufferRecord> {
+        let mut records = self.records.borrow().values().cloned().collect::<Vec<_>>();
+        records.sort_by(|a, b| b.size.cmp(&a.size));
+        records
+    }
+
+    /// 将所有存活的 buffer 按大小降序输出到日志
+    pub fn dump(&self) {
+        let records = self.live_buffers();
+        let total_size: vk::DeviceSize = records.iter().map(|r| r.size).sum();
+
+        log::info!("==================== Live GfxBuffers ====================");
+        log::info!("count: {}, total: {:.2} MB", records.len(), total_size as f64 / (1024.0 * 1024.0));
+        for record in &records {
+            log::info!("{:>10.2} KB | {} | {:?}", record.size as f64 / 1024.0, record.name, record.usage);
+            if record.backtrace.status() == BacktraceStatus::Captured {
+                log::info!("created at:\n{}", record.backtrace);
+            }
+        }
+        log::info!("=========================================================");
+    }
+}
@@ -0,0 +1,285 @@
+//! 跨 API / 跨进程共享的 image
+//!
+//! 通过 `VK_KHR_external_memory_fd` + `VK_EXT_external_memory_dma_buf` 将 image 的内存导出为 dma-buf，
+//! 其他 API（EGL 的 `EGL_EXT_image_dma_buf_import`、Wayland 的 `linux-dmabuf`、另一个 Vulkan 设备等）
+//! 可以零拷贝地导入同一块内存。
+//!
+//! # 约束
+//! - 目前只支持 Linux 的 dma-buf
+//! - 没有启用 `VK_EXT_image_drm_format_modifier`，因此 image 使用 `LINEAR` tiling，
+//!   对方导入时需要使用 linear modifier，并按照 [`GfxDmaBufDesc::row_pitch`] 读取
+//! - 导出的 image 不带同步原语，写入方需要保证对方读取时 GPU 已经写完（例如等待 fence 之后再通知对方）
+
+use std::os::fd::{AsRawFd, FromRawFd, IntoRawFd, OwnedFd};
+
+use ash::vk;
+
+use crate::resources::resource_stats::GfxResourceKind;
+use crate::{gfx::Gfx, resources::image::GfxImage};
+
+/// 导出的 dma-buf 及其内存布局，导入方需要这些信息重建 image
+#[derive(Debug)]
+pub struct GfxDmaBufDesc {
+    /// dma-buf 的文件描述符，每次导出都是一个新的 fd，由接收方负责关闭
+    pub fd: OwnedFd,
+
+    pub width: u32,
+    pub height: u32,
+    pub format: vk::Format,
+    /// 对应的 DRM fourcc，参见 [`GfxExternalImage::drm_fourcc`]
+    pub drm_fourcc: u32,
+
+    /// 第一个像素相对于内存起始处的偏移（字节）
+    pub offset: vk::DeviceSize,
+    /// 每行的字节数，可能大于 `width * pixel_size`
+    pub row_pitch: vk::DeviceSize,
+    /// 整块内存的大小（字节）
+    pub size: vk::DeviceSize,
+}
+
+/// 内存可以以 dma-buf 的形式导出或导入的 2D image
+///
+/// 内部的 [`GfxImage`] 不管理内存，可以像普通 image 一样录制 barrier、copy、blit 等命令；
+/// drop 时释放 image 和内存，需要保证 GPU 已经不再使用
+pub struct GfxExternalImage {
+    image: GfxImage,
+    memory: vk::DeviceMemory,
+    memory_size: vk::DeviceSize,
+    subresource_layout: vk::SubresourceLayout,
+}
+
+// new & init
+impl GfxExternalImage {
+    const HANDLE_TYPE: vk::ExternalMemoryHandleTypeFlags = vk::ExternalMemoryHandleTypeFlags::DMA_BUF_EXT;
+
+    /// 创建一个可以导出的 image
+    ///
+    /// 调用前可以通过 [`Self::is_supported`] 检查格式和 usage 是否可以导出
+    pub fn new(extent: vk::Extent2D, format: vk::Format, usage: vk::ImageUsageFlags, name: impl AsRef<str>) -> Self {
+        let gfx_device = Gfx::get().gfx_device();
+
+        let image = Self::create_image(extent, format, usage);
+        let mem_reqs = unsafe { gfx_device.get_image_memory_requirements(image) };
+        let memory_type_index =
+            Self::find_memory_type(mem_reqs.memory_type_bits).expect("no memory type for exportable image");
+
+        let mut dedicated_info = vk::MemoryDedicatedAllocateInfo::default().image(image);
+        let mut export_info = vk::ExportMemoryAllocateInfo::default().handle_types(Self::HANDLE_TYPE);
+        let alloc_info = vk::MemoryAllocateInfo::default()
+            .allocation_size(mem_reqs.size)
+            .memory_type_index(memory_type_index)
+            .push_next(&mut dedicated_info)
+            .push_next(&mut export_info);
+
+        let memory = unsafe { gfx_device.allocate_memory(&alloc_info, None).unwrap() };
+        Self::from_memory(image, memory, mem_reqs.size, extent, format, name)
+    }
+
+    /// 导入一个 dma-buf，`desc` 中的 fd 所有权会转移给 Vulkan
+    pub fn import_dma_buf(desc: GfxDmaBufDesc, usage: vk::ImageUsageFlags, name: impl AsRef<str>) -> Self {
+        let gfx_device = Gfx::get().gfx_device();
+        let extent = vk::Extent2D {
+            width: desc.width,
+            height: desc.height,
+        };
+
+        let image = Self::create_image(extent, desc.format, usage);
+        let mem_reqs = unsafe { gfx_device.get_image_memory_requirements(image) };
+
+        let mut fd_props = vk::MemoryFdPropertiesKHR::default();
+        unsafe {
+            gfx_device
+                .external_memory_fd()
+                .get_memory_fd_properties(Self::HANDLE_TYPE, desc.fd.as_raw_fd(), &mut fd_props)
+                .unwrap();
+        }
+        let memory_type_index = Self::find_memory_type(mem_reqs.memory_type_bits & fd_props.memory_type_bits)
+            .expect("no memory type for imported dma-buf");
+
+        // 导入成功后 fd 由 Vulkan 持有，不能再手动关闭
+        let raw_fd = desc.fd.into_raw_fd();
+        let mut dedicated_info = vk::MemoryDedicatedAllocateInfo::default().image(image);
+        let mut import_info = vk::ImportMemoryFdInfoKHR::default().handle_type(Self::HANDLE_TYPE).fd(raw_fd);
+        let alloc_info = vk::MemoryAllocateInfo::default()
+            .allocation_size(mem_reqs.size.max(desc.size))
+            .memory_type_index(memory_type_index)
+            .push_next(&mut dedicated_info)
+            .push_next(&mut import_info);
+
+        let memory = match unsafe { gfx_device.allocate_memory(&alloc_info, None) } {
+            Ok(memory) => memory,
+            Err(e) => {
+                // 导入失败时 fd 的所有权仍然在调用者这边
+                drop(unsafe { OwnedFd::from_raw_fd(raw_fd) });
+                panic!("failed to import dma-buf: {e:?}");
+            }
+        };
+        Self::from_memory(image, memory, mem_reqs.size.max(desc.size), extent, desc.format, name)
+    }
+
+    fn create_image(extent: vk::Extent2D, format: vk::Format, usage: vk::ImageUsageFlags) -> vk::Image {
+        let mut external_info = vk::ExternalMemoryImageCreateInfo::default().handle_types(Self::HANDLE_TYPE);
+        let image_ci = vk::ImageCreateInfo::default()
+            .image_type(vk::ImageType::TYPE_2D)
+            .format(format)
+            .extent(extent.into())
+            .mip_levels(1)
+            .array_layers(1)
+            .samples(vk::SampleCountFlags::TYPE_1)
+            .tiling(vk::ImageTiling::LINEAR)
+            .usage(usage)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE)
+            .initial_layout(vk::ImageLayout::UNDEFINED)
+            .push_next(&mut external_info);
+
+        let image = unsafe { Gfx::get().gfx_device().create_image(&image_ci, None).unwrap() };
+        Gfx::get().resource_stats.on_create(GfxResourceKind::Image, 1);
+        image
+    }
+
+    fn from_memory(
+        image: vk::Image,
+        memory: vk::DeviceMemory,
+        memory_size: vk::DeviceSize,
+        extent: vk::Extent2D,
+        format: vk::Format,
+        name: impl AsRef<str>,
+    ) -> Self {
+        let gfx_device = Gfx::get().gfx_device();
+        unsafe {
+            gfx_device.bind_image_memory(image, memory, 0).unwrap();
+        }
+        gfx_device.set_object_debug_name(memory, format!("ExternalMemory::{}", name.as_ref()));
+
+        let subresource_layout = unsafe {
+            gfx_device.get_image_subresource_layout(
+                image,
+                vk::ImageSubresource {
+                    aspect_mask: vk::ImageAspectFlags::COLOR,
+                    mip_level: 0,
+                    array_layer: 0,
+                },
+            )
+        };
+
+        Self {
+            image: GfxImage::from_external(image, extent.into(), format, format!("external-{}", name.as_ref())),
+            memory,
+            memory_size,
+            subresource_layout,
+        }
+    }
+
+    /// 找到第一个满足 `memory_type_bits` 的 device local 内存类型
+    fn find_memory_type(memory_type_bits: u32) -> Option<u32> {
+        let mem_props = &Gfx::get().physical_device().mem_props;
+        (0..mem_props.memory_type_count).find(|&i| {
+            memory_type_bits & (1 << i) != 0
+                && mem_props.memory_types[i as usize].property_flags.contains(vk::MemoryPropertyFlags::DEVICE_LOCAL)
+        })
+    }
+}
+impl Drop for GfxExternalImage {
+    fn drop(&mut self) {
+        let gfx_device = Gfx::get().gfx_device();
+        unsafe {
+            gfx_device.destroy_image(self.image.handle(), None);
+            gfx_device.free_memory(self.memory, None);
+        }
+        Gfx::get().resource_stats.on_destroy(GfxResourceKind::Image, 1);
+        // vk::Image 已经在上面销毁，这里只是清空 handle
+        self.image.destroy_mut();
+    }
+}
+// getter
+impl GfxExternalImage {
+    #[inline]
+    pub fn image(&self) -> &GfxImage {
+        &self.image
+    }
+
+    #[inline]
+    pub fn handle(&self) -> vk::Image {
+        self.image.handle()
+    }
+
+    #[inline]
+    pub fn extent(&self) -> vk::Extent2D {
+        vk::Extent2D {
+            width: self.image.width(),
+            height: self.image.height(),
+        }
+    }
+
+    #[inline]
+    pub fn format(&self) -> vk::Format {
+        self.image.format()
+    }
+
+    /// 每行的字节数
+    #[inline]
+    pub fn row_pitch(&self) -> vk::DeviceSize {
+        self.subresource_layout.row_pitch
+    }
+}
+// tools
+impl GfxExternalImage {
+    /// 检查当前设备能否创建指定格式、usage 的可导出 image
+    pub fn is_supported(format: vk::Format, usage: vk::ImageUsageFlags) -> bool {
+        if !Gfx::get().physical_device().support_external_memory_dma_buf() {
+            return false;
+        }
+
+        let mut external_format_info =
+            vk::PhysicalDeviceExternalImageFormatInfo::default().handle_type(Self::HANDLE_TYPE);
+        let format_info = vk::PhysicalDeviceImageFormatInfo2::default()
+            .format(format)
+            .ty(vk::ImageType::TYPE_2D)
+            .tiling(vk::ImageTiling::LINEAR)
+            .usage(usage)
+            .push_next(&mut external_format_info);
+
+        let mut external_props = vk::ExternalImageFormatProperties::default();
+        let mut props = vk::ImageFormatProperties2::default().push_next(&mut external_props);
+        let result = unsafe {
+            Gfx::get().instance().ash_instance().get_physical_device_image_format_properties2(
+                Gfx::get().physical_device().vk_handle,
+                &format_info,
+                &mut props,
+            )
+        };
+        result.is_ok()
+            && external_props
+                .external_memory_properties
+                .external_memory_features
+                .contains(vk::ExternalMemoryFeatureFlags::EXPORTABLE | vk::ExternalMemoryFeatureFlags::IMPORTABLE)
+    }
+
+    /// 将内存导出为 dma-buf，每次调用都会得到一个新的 fd
+    pub fn export_dma_buf(&self) -> GfxDmaBufDesc {
+        let fd_info = vk::MemoryGetFdInfoKHR::default().memory(self.memory).handle_type(Self::HANDLE_TYPE);
+        let raw_fd = unsafe { Gfx::get().gfx_device().external_memory_fd().get_memory_fd(&fd_info).unwrap() };
+
+        GfxDmaBufDesc {
+            fd: unsafe { OwnedFd::from_raw_fd(raw_fd) },
+            width: self.image.width(),
+            height: self.image.height(),
+            format: self.format(),
+            drm_fourcc: Self::drm_fourcc(self.format()).expect("format has no drm fourcc"),
+            offset: self.subresource_layout.offset,
+            row_pitch: self.subresource_layout.row_pitch,
+            size: self.memory_size,
+        }
+    }
+
+    /// Vulkan 格式对应的 DRM fourcc（小端序下 DRM 格式名中的通道顺序与 Vulkan 相反）
+    pub fn drm_fourcc(format: vk::Format) -> Option<u32> {
+        let fourcc = |code: &[u8; 4]| u32::from_le_bytes(*code);
+        match format {
+            vk::Format::R8G8B8A8_UNORM | vk::Format::R8G8B8A8_SRGB => Some(fourcc(b"AB24")),
+            vk::Format::B8G8R8A8_UNORM | vk::Format::B8G8R8A8_SRGB => Some(fourcc(b"AR24")),
+            vk::Format::R16G16B16A16_SFLOAT => Some(fourcc(b"AB4H")),
+            _ => None,
+        }
+    }
+}
@@ -1,12 +1,20 @@
+use std::cell::Cell;
+
 use ash::vk;
 use ash::vk::Handle;
 use vk_mem::{Alloc, Allocation};
 
+use crate::resources::resource_stats::GfxResourceKind;
 use crate::{
-    commands::{barrier::GfxImageBarrier, command_buffer::GfxCommandBuffer},
+    commands::{
+        async_transfer::TransferTicket,
+        barrier::{GfxBufferBarrier, GfxImageBarrier},
+        command_buffer::GfxCommandBuffer,
+        resource_state::GfxImageState,
+    },
     foundation::debug_messenger::DebugType,
     gfx::Gfx,
-    resources::buffer::GfxBuffer,
+    resources::{buffer::GfxBuffer, special_buffers::readback_buffer::GfxReadbackBuffer},
 };
 
 /// Vulkan 格式相关的工具类
@@ -28,8 +36,8 @@ impl VulkanFormatUtils {
         const BYTE_4_FORMAT: [(vk::Format, vk::Format); 1] = [(vk::Format::R8G8B8A8_UNORM, vk::Format::B8G8R8A8_SRGB)];
         const BYTE_6_FORMAT: [(vk::Format, vk::Format); 1] =
             [(vk::Format::R16G16B16_UNORM, vk::Format::R16G16B16_SFLOAT)];
-        const BYTE_8_FORMAT: [(vk::Format, vk::Format); 1] =
-            [(vk::Format::R16G16B16A16_UNORM, vk::Format::R16G16B16A16_SFLOAT)];
+        const BYTE_8_FORMAT: [(vk::Format, vk::Format); 1] = [(vk::Format::R16G16B16A16_UNORM, vk::Format::R32_UINT)];
+        const BYTE_16_FORMAT: [(vk::Format, vk::Format); 1] = [(vk::Format::R32G32B32A32_UINT, vk::Format::R64_UINT)];
 
         let is_in_format_region = |format: vk::Format, regions: &[(vk::Format, vk::Format)]| {
             let n = format.as_raw();
@@ -37,13 +45,75 @@ impl VulkanFormatUtils {
         };
 
         match format {
+            vk::Format::R8_UNORM
+            | vk::Format::R8_SNORM
+            | vk::Format::R8_UINT
+            | vk::Format::R8_SINT
+            | vk::Format::R8_SRGB
+            | vk::Format::S8_UINT => 1,
+            vk::Format::R8G8_UNORM
+            | vk::Format::R8G8_SNORM
+            | vk::Format::R8G8_UINT
+            | vk::Format::R8G8_SINT
+            | vk::Format::R8G8_SRGB
+            | vk::Format::R16_UNORM
+            | vk::Format::R16_SNORM
+            | vk::Format::R16_UINT
+            | vk::Format::R16_SINT
+            | vk::Format::R16_SFLOAT
+            | vk::Format::D16_UNORM => 2,
+            vk::Format::B8G8R8_SRGB => 3,
+            vk::Format::B8G8R8A8_SRGB
+            | vk::Format::R16G16_UNORM
+            | vk::Format::R16G16_SNORM
+            | vk::Format::R16G16_UINT
+            | vk::Format::R16G16_SINT
+            | vk::Format::R16G16_SFLOAT
+            | vk::Format::R32_UINT
+            | vk::Format::R32_SINT
+            | vk::Format::R32_SFLOAT
+            | vk::Format::D32_SFLOAT
+            | vk::Format::X8_D24_UNORM_PACK32
+            | vk::Format::A2R10G10B10_UNORM_PACK32
+            | vk::Format::A2B10G10R10_UNORM_PACK32
+            | vk::Format::B10G11R11_UFLOAT_PACK32
+            | vk::Format::E5B9G9R9_UFLOAT_PACK32 => 4,
+            vk::Format::R32G32_UINT | vk::Format::R32G32_SINT | vk::Format::R32G32_SFLOAT => 8,
+            vk::Format::R32G32B32_UINT | vk::Format::R32G32B32_SINT | vk::Format::R32G32B32_SFLOAT => 12,
             f if is_in_format_region(f, &BYTE_3_FORMAT) => 3,
             f if is_in_format_region(f, &BYTE_4_FORMAT) => 4,
             f if is_in_format_region(f, &BYTE_6_FORMAT) => 6,
             f if is_in_format_region(f, &BYTE_8_FORMAT) => 8,
+            f if is_in_format_region(f, &BYTE_16_FORMAT) => 16,
             _ => panic!("unsupported format: {:?}", format),
         }
     }
+
+    /// 根据格式推断 image 的 aspect
+    pub fn infer_aspect(format: vk::Format) -> vk::ImageAspectFlags {
+        match format {
+            vk::Format::D16_UNORM | vk::Format::D32_SFLOAT | vk::Format::X8_D24_UNORM_PACK32 => {
+                vk::ImageAspectFlags::DEPTH
+            }
+            vk::Format::S8_UINT => vk::ImageAspectFlags::STENCIL,
+            vk::Format::D16_UNORM_S8_UINT | vk::Format::D24_UNORM_S8_UINT | vk::Format::D32_SFLOAT_S8_UINT => {
+                vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL
+            }
+            _ => vk::ImageAspectFlags::COLOR,
+        }
+    }
+
+    /// 将图像复制到 buffer 时使用的 aspect 以及每个像素的字节数
+    ///
+    /// 一次 copy 只能包含一个 aspect，depth stencil 格式只复制 depth
+    pub fn copy_aspect_and_pixel_size(format: vk::Format) -> (vk::ImageAspectFlags, usize) {
+        match format {
+            vk::Format::D16_UNORM_S8_UINT => (vk::ImageAspectFlags::DEPTH, 2),
+            // D24 的 depth 复制到 buffer 时每个像素占 4 个字节
+            vk::Format::D24_UNORM_S8_UINT | vk::Format::D32_SFLOAT_S8_UINT => (vk::ImageAspectFlags::DEPTH, 4),
+            _ => (Self::infer_aspect(format), Self::pixel_size_in_bytes(format)),
+        }
+    }
 }
 
 /// Image 来源枚举
@@ -60,6 +130,10 @@ pub struct GfxImage {
 
     extent: vk::Extent3D,
     format: vk::Format,
+    mip_levels: u32,
+
+    /// 按录制顺序追踪的状态，参见 [`GfxCommandBuffer::transition`]
+    state: Cell<GfxImageState>,
 
     name: String,
 }
@@ -85,6 +159,28 @@ impl GfxImage {
     pub fn format(&self) -> vk::Format {
         self.format
     }
+
+    #[inline]
+    pub fn mip_levels(&self) -> u32 {
+        self.mip_levels
+    }
+
+    /// 最近一次录制的命令执行之后图像所处的状态，所有 mip level 视为同一个状态
+    #[inline]
+    pub fn state(&self) -> GfxImageState {
+        self.state.get()
+    }
+}
+
+// 状态追踪
+impl GfxImage {
+    /// 直接覆盖追踪的状态，不录制任何命令
+    ///
+    /// 用于手动录制 barrier（例如跨 queue 的所有权转移、render graph）之后同步状态
+    #[inline]
+    pub fn set_state(&self, state: GfxImageState) {
+        self.state.set(state);
+    }
 }
 
 // new & init
@@ -98,10 +194,13 @@ impl GfxImage {
             source: ImageSource::Allocated(alloc),
             extent: image_info.inner.extent,
             format: image_info.inner.format,
+            mip_levels: image_info.inner.mip_levels,
+            state: Cell::new(GfxImageState::UNDEFINED_TOP),
 
             name: debug_name.to_string(),
         };
         gfx_device.set_debug_name(&image, debug_name);
+        Gfx::get().resource_stats.on_create(GfxResourceKind::Image, 1);
         image
     }
 
@@ -112,6 +211,8 @@ impl GfxImage {
             source: ImageSource::External,
             extent,
             format,
+            mip_levels: 1,
+            state: Cell::new(GfxImageState::UNDEFINED_TOP),
 
             name: name.as_ref().to_string(),
         };
@@ -122,9 +223,18 @@ impl GfxImage {
     // TODO 考虑将 GfxImage::from_rgba8 放入 UploadManager 中，并提供异步版本
     /// 根据 RGBA8_UNORM 的 data 创建 image
     pub fn from_rgba8(width: u32, height: u32, data: &[u8], name: impl AsRef<str>) -> Self {
+        Self::from_pixels(width, height, vk::Format::R8G8B8A8_UNORM, data, name)
+    }
+
+    /// 根据 RGBA32_SFLOAT 的 data 创建 image，用于 HDR 贴图
+    pub fn from_rgba32f(width: u32, height: u32, data: &[f32], name: impl AsRef<str>) -> Self {
+        Self::from_pixels(width, height, vk::Format::R32G32B32A32_SFLOAT, bytemuck::cast_slice(data), name)
+    }
+
+    fn from_pixels(width: u32, height: u32, format: vk::Format, data: &[u8], name: impl AsRef<str>) -> Self {
         let image_create_info = GfxImageCreateInfo::new_image_2d_info(
             vk::Extent2D { width, height },
-            vk::Format::R8G8B8A8_UNORM,
+            format,
             vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED,
         );
         let image = Self::new(
@@ -161,9 +271,10 @@ impl GfxImage {
 
         match &mut self.source {
             ImageSource::External => (),
-            ImageSource::Allocated(allocation) => unsafe {
-                Gfx::get().allocator().destroy_image(self.handle, allocation)
-            },
+            ImageSource::Allocated(allocation) => {
+                unsafe { Gfx::get().allocator().destroy_image(self.handle, allocation) };
+                Gfx::get().resource_stats.on_destroy(GfxResourceKind::Image, 1);
+            }
         }
         self.handle = vk::Image::null();
     }
@@ -191,15 +302,9 @@ impl GfxImage {
 
         // 1. transition the image layout
         // 2. copy the buffer into the image
-        // 3. transition the layout 为了让 fragment shader 可读
+        // 3. transition the layout 为了让 shader 可读
         {
-            let image_barrier = GfxImageBarrier::new()
-                .image(self.handle)
-                .src_mask(vk::PipelineStageFlags2::TOP_OF_PIPE, vk::AccessFlags2::empty())
-                .dst_mask(vk::PipelineStageFlags2::TRANSFER, vk::AccessFlags2::TRANSFER_WRITE)
-                .layout_transfer(vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL)
-                .image_aspect_flag(vk::ImageAspectFlags::COLOR);
-            command_buffer.image_memory_barrier(vk::DependencyFlags::empty(), std::slice::from_ref(&image_barrier));
+            command_buffer.transition(self, GfxImageState::TRANSFER_DST);
 
             let buffer_image_copy = vk::BufferImageCopy2::default()
                 .buffer_offset(0)
@@ -225,17 +330,338 @@ impl GfxImage {
                     .regions(std::slice::from_ref(&buffer_image_copy)),
             );
 
-            let image_barrier = GfxImageBarrier::new()
-                .image(self.handle)
-                .src_mask(vk::PipelineStageFlags2::TRANSFER, vk::AccessFlags2::TRANSFER_WRITE)
-                .dst_mask(vk::PipelineStageFlags2::FRAGMENT_SHADER, vk::AccessFlags2::SHADER_READ)
-                .layout_transfer(vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
-                .image_aspect_flag(vk::ImageAspectFlags::COLOR);
-            command_buffer.image_memory_barrier(vk::DependencyFlags::empty(), std::slice::from_ref(&image_barrier));
+            command_buffer.transition(self, GfxImageState::SHADER_READ_ALL);
         }
 
         stage_buffer
     }
+
+    /// 通过 transfer queue 异步上传 level 0，完成后 level 0 处于 SHADER_READ_ONLY_OPTIMAL
+    ///
+    /// 返回的 [`TransferTicket`] 完成之前，需要保证 self 存活，并且不被 GPU 访问
+    pub fn transfer_data_async(&self, data: &[u8]) -> TransferTicket {
+        let pixels_cnt = self.width() * self.height();
+        assert_eq!(data.len(), VulkanFormatUtils::pixel_size_in_bytes(self.format()) * pixels_cnt as usize);
+
+        self.transfer_mip_levels_async(&[data])
+    }
+
+    /// 通过 transfer queue 异步上传从 level 0 开始的若干 mip level，完成后这些 level 处于 SHADER_READ_ONLY_OPTIMAL
+    ///
+    /// 所有权转移由内部处理，追踪的状态直接设置为 acquire 之后的 [`GfxImageState::SHADER_READ_ALL`]
+    ///
+    /// `levels[i]` 是第 i 层紧密排列的数据，可以是块压缩格式（BCn、ASTC 等），因此不检查数据的大小
+    ///
+    /// 返回的 [`TransferTicket`] 完成之前，需要保证 self 存活，并且不被 GPU 访问
+    pub fn transfer_mip_levels_async(&self, levels: &[&[u8]]) -> TransferTicket {
+        assert!(!levels.is_empty() && levels.len() as u32 <= self.mip_levels);
+        let level_count = levels.len() as u32;
+
+        let level_offsets: Vec<usize> = levels
+            .iter()
+            .scan(0, |offset, level| {
+                let level_offset = *offset;
+                *offset += level.len();
+                Some(level_offset)
+            })
+            .collect();
+        let data = levels.concat();
+
+        let stage_buffer = GfxBuffer::new_stage_buffer(data.len() as vk::DeviceSize, "image-stage-buffer");
+        stage_buffer.transfer_data_by_mmap(&data);
+
+        let extent = self.extent;
+
+        let image = self.handle;
+        // 所有权转移时，release 和 acquire 需要声明相同的 layout 转换，转换只会执行一次
+        let ownership_barrier = move |src_queue_family_index: u32, dst_queue_family_index: u32| {
+            GfxImageBarrier::new()
+                .image(image)
+                .image_aspect_flag(vk::ImageAspectFlags::COLOR)
+                .mip_range(0, level_count)
+                .layout_transfer(vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                .queue_family_transfer(src_queue_family_index, dst_queue_family_index)
+        };
+
+        let ticket = Gfx::get().async_transfer().submit(
+            stage_buffer,
+            |cmd, stage_buffer, ownership_transfer| {
+                cmd.image_memory_barrier(
+                    vk::DependencyFlags::empty(),
+                    &[GfxImageBarrier::new()
+                        .image(image)
+                        .image_aspect_flag(vk::ImageAspectFlags::COLOR)
+                        .mip_range(0, level_count)
+                        .layout_transfer(vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+                        .src_mask(vk::PipelineStageFlags2::NONE, vk::AccessFlags2::NONE)
+                        .dst_mask(vk::PipelineStageFlags2::TRANSFER, vk::AccessFlags2::TRANSFER_WRITE)],
+                );
+
+                let buffer_image_copies: Vec<_> = level_offsets
+                    .iter()
+                    .enumerate()
+                    .map(|(level, &offset)| {
+                        vk::BufferImageCopy2::default()
+                            .buffer_offset(offset as vk::DeviceSize)
+                            .image_extent(vk::Extent3D {
+                                width: (extent.width >> level).max(1),
+                                height: (extent.height >> level).max(1),
+                                depth: 1,
+                            })
+                            .image_subresource(vk::ImageSubresourceLayers {
+                                aspect_mask: vk::ImageAspectFlags::COLOR,
+                                mip_level: level as u32,
+                                base_array_layer: 0,
+                                layer_count: 1,
+                            })
+                    })
+                    .collect();
+                cmd.cmd_copy_buffer_to_image(
+                    &vk::CopyBufferToImageInfo2::default()
+                        .src_buffer(stage_buffer.vk_buffer())
+                        .dst_image(image)
+                        .dst_image_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+                        .regions(&buffer_image_copies),
+                );
+
+                // 专用 transfer queue 不支持 shader stage，release 的 dst 只能留空，由 acquire 声明
+                let barrier = match ownership_transfer {
+                    Some(ownership_transfer) => ownership_barrier(
+                        ownership_transfer.src_queue_family_index,
+                        ownership_transfer.dst_queue_family_index,
+                    )
+                    .dst_mask(vk::PipelineStageFlags2::NONE, vk::AccessFlags2::NONE),
+                    None => ownership_barrier(vk::QUEUE_FAMILY_IGNORED, vk::QUEUE_FAMILY_IGNORED)
+                        .dst_mask(vk::PipelineStageFlags2::ALL_COMMANDS, vk::AccessFlags2::SHADER_READ),
+                };
+                cmd.image_memory_barrier(
+                    vk::DependencyFlags::empty(),
+                    &[barrier.src_mask(vk::PipelineStageFlags2::TRANSFER, vk::AccessFlags2::TRANSFER_WRITE)],
+                );
+            },
+            move |cmd, ownership_transfer| {
+                cmd.image_memory_barrier(
+                    vk::DependencyFlags::empty(),
+                    &[ownership_barrier(
+                        ownership_transfer.src_queue_family_index,
+                        ownership_transfer.dst_queue_family_index,
+                    )
+                    .src_mask(vk::PipelineStageFlags2::NONE, vk::AccessFlags2::NONE)
+                    .dst_mask(vk::PipelineStageFlags2::ALL_COMMANDS, vk::AccessFlags2::SHADER_READ)],
+                );
+            },
+            "image-transfer",
+        );
+        self.set_state(GfxImageState::SHADER_READ_ALL);
+
+        ticket
+    }
+
+    /// 完整 mip 链的层数：floor(log2(max(width, height))) + 1
+    #[inline]
+    pub fn full_mip_level_count(width: u32, height: u32) -> u32 {
+        u32::BITS - width.max(height).max(1).leading_zeros()
+    }
+
+    /// 该格式是否可以通过 linear filter 的 blit 生成 mipmap，参见 [`Self::generate_mipmaps`]
+    pub fn supports_mipmap_blit(format: vk::Format) -> bool {
+        !Gfx::get()
+            .find_supported_format(
+                &[format],
+                vk::ImageTiling::OPTIMAL,
+                vk::FormatFeatureFlags::BLIT_SRC
+                    | vk::FormatFeatureFlags::BLIT_DST
+                    | vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR,
+            )
+            .is_empty()
+    }
+
+    /// 使用 blit 从 level 0 逐级生成其余的 mip level
+    ///
+    /// 调用前 level 0 需要处于 `SHADER_READ_ONLY_OPTIMAL`（例如 [`Self::transfer_data`] 之后），其余 level 的内容会被丢弃；
+    /// 完成后所有 level 都处于 `SHADER_READ_ONLY_OPTIMAL`。
+    /// 需要图像带有 `TRANSFER_SRC | TRANSFER_DST` usage，格式支持 [`Self::supports_mipmap_blit`]，并且在 graphics queue 上执行
+    pub fn generate_mipmaps(&self, cmd: &GfxCommandBuffer) {
+        if self.mip_levels <= 1 {
+            return;
+        }
+
+        let mip_extent = |level: u32| vk::Offset3D {
+            x: (self.width() >> level).max(1) as i32,
+            y: (self.height() >> level).max(1) as i32,
+            z: 1,
+        };
+        let subresource = |level: u32| vk::ImageSubresourceLayers {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            mip_level: level,
+            base_array_layer: 0,
+            layer_count: 1,
+        };
+        let barrier = || GfxImageBarrier::new().image(self.handle).image_aspect_flag(vk::ImageAspectFlags::COLOR);
+
+        for level in 1..self.mip_levels {
+            let src_level = level - 1;
+            // 上一级作为 blit 的输入；level 0 来自上传，之后的 level 来自上一次 blit
+            let src_old_layout = if src_level == 0 {
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL
+            } else {
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL
+            };
+            cmd.image_memory_barrier(
+                vk::DependencyFlags::empty(),
+                &[
+                    barrier()
+                        .mip_range(src_level, 1)
+                        .src_mask(vk::PipelineStageFlags2::ALL_COMMANDS, vk::AccessFlags2::MEMORY_WRITE)
+                        .dst_mask(vk::PipelineStageFlags2::BLIT, vk::AccessFlags2::TRANSFER_READ)
+                        .layout_transfer(src_old_layout, vk::ImageLayout::TRANSFER_SRC_OPTIMAL),
+                    barrier()
+                        .mip_range(level, 1)
+                        .src_mask(vk::PipelineStageFlags2::TOP_OF_PIPE, vk::AccessFlags2::empty())
+                        .dst_mask(vk::PipelineStageFlags2::BLIT, vk::AccessFlags2::TRANSFER_WRITE)
+                        .layout_transfer(vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL),
+                ],
+            );
+
+            let region = vk::ImageBlit2::default()
+                .src_subresource(subresource(src_level))
+                .src_offsets([vk::Offset3D::default(), mip_extent(src_level)])
+                .dst_subresource(subresource(level))
+                .dst_offsets([vk::Offset3D::default(), mip_extent(level)]);
+            cmd.cmd_blit_image(
+                &vk::BlitImageInfo2::default()
+                    .src_image(self.handle)
+                    .src_image_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+                    .dst_image(self.handle)
+                    .dst_image_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+                    .regions(std::slice::from_ref(&region))
+                    .filter(vk::Filter::LINEAR),
+            );
+
+            cmd.image_memory_barrier(
+                vk::DependencyFlags::empty(),
+                &[barrier()
+                    .mip_range(src_level, 1)
+                    .src_mask(vk::PipelineStageFlags2::BLIT, vk::AccessFlags2::TRANSFER_READ)
+                    .dst_mask(vk::PipelineStageFlags2::ALL_COMMANDS, vk::AccessFlags2::SHADER_READ)
+                    .layout_transfer(vk::ImageLayout::TRANSFER_SRC_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)],
+            );
+        }
+
+        // 最后一级只作为 blit 的输出
+        cmd.image_memory_barrier(
+            vk::DependencyFlags::empty(),
+            &[barrier()
+                .mip_range(self.mip_levels - 1, 1)
+                .src_mask(vk::PipelineStageFlags2::BLIT, vk::AccessFlags2::TRANSFER_WRITE)
+                .dst_mask(vk::PipelineStageFlags2::ALL_COMMANDS, vk::AccessFlags2::SHADER_READ)
+                .layout_transfer(vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)],
+        );
+        self.set_state(GfxImageState::SHADER_READ_ALL);
+    }
+
+    /// 将图像内容读回到 CPU，返回紧密排列的像素数据（逐行，从左上角开始）
+    ///
+    /// 会同步等待 GPU 执行完毕；调用前需要保证图像带有 `TRANSFER_SRC` usage，
+    /// 并且 `layout` 为图像当前所处的 layout，读回后图像保持该 layout 不变
+    pub fn read_back(&self, layout: vk::ImageLayout) -> Vec<u8> {
+        let region = vk::Rect2D {
+            offset: vk::Offset2D::default(),
+            extent: vk::Extent2D {
+                width: self.width(),
+                height: self.height(),
+            },
+        };
+        let readback_buffer = Gfx::get()
+            .one_time_exec(|cmd| self.read_back_region(cmd, layout, region), format!("read-back-{}", self.name));
+
+        let data = readback_buffer.as_bytes().to_vec();
+        readback_buffer.destroy();
+
+        data
+    }
+
+    /// 录制将 level 0 中 `region` 区域复制到 host-visible buffer 的命令，例如 object picking 只需要读回鼠标下的一个像素
+    ///
+    /// 返回的 buffer 需要在命令执行完成之后才能读取，buffer 中的数据逐行紧密排列（`bufferRowLength` 为区域宽度）。
+    /// 调用前需要保证图像带有 `TRANSFER_SRC` usage，并且 `layout` 为图像当前所处的 layout，
+    /// 如果 layout 不适合 copy，会临时切换到 `TRANSFER_SRC_OPTIMAL`，copy 之后恢复为 `layout`
+    pub fn read_back_region(
+        &self,
+        cmd: &GfxCommandBuffer,
+        layout: vk::ImageLayout,
+        region: vk::Rect2D,
+    ) -> GfxReadbackBuffer {
+        assert!(
+            region.offset.x >= 0
+                && region.offset.y >= 0
+                && region.offset.x as u32 + region.extent.width <= self.width()
+                && region.offset.y as u32 + region.extent.height <= self.height(),
+            "read back region {:?} is out of image {}",
+            region,
+            self.name
+        );
+
+        let (copy_aspect, pixel_size) = VulkanFormatUtils::copy_aspect_and_pixel_size(self.format());
+        let readback_buffer = GfxReadbackBuffer::new(region.extent, pixel_size, format!("{}-readback", self.name));
+
+        // 之前的任意写入对 transfer 可见
+        let copy_layout = match layout {
+            vk::ImageLayout::GENERAL | vk::ImageLayout::TRANSFER_SRC_OPTIMAL => layout,
+            _ => vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+        };
+        let image_barrier = GfxImageBarrier::new()
+            .image(self.handle)
+            .src_mask(vk::PipelineStageFlags2::ALL_COMMANDS, vk::AccessFlags2::MEMORY_WRITE)
+            .dst_mask(vk::PipelineStageFlags2::TRANSFER, vk::AccessFlags2::TRANSFER_READ)
+            .layout_transfer(layout, copy_layout)
+            .image_aspect_flag(VulkanFormatUtils::infer_aspect(self.format()));
+        cmd.image_memory_barrier(vk::DependencyFlags::empty(), std::slice::from_ref(&image_barrier));
+
+        let buffer_image_copy = vk::BufferImageCopy2::default()
+            .buffer_offset(0)
+            .buffer_row_length(region.extent.width)
+            .buffer_image_height(region.extent.height)
+            .image_offset(vk::Offset3D {
+                x: region.offset.x,
+                y: region.offset.y,
+                z: 0,
+            })
+            .image_extent(vk::Extent3D {
+                width: region.extent.width,
+                height: region.extent.height,
+                depth: 1,
+            })
+            .image_subresource(vk::ImageSubresourceLayers {
+                aspect_mask: copy_aspect,
+                mip_level: 0,
+                base_array_layer: 0,
+                layer_count: 1,
+            });
+        cmd.cmd_copy_image_to_buffer(
+            &vk::CopyImageToBufferInfo2::default()
+                .src_image(self.handle)
+                .src_image_layout(copy_layout)
+                .dst_buffer(readback_buffer.vk_buffer())
+                .regions(std::slice::from_ref(&buffer_image_copy)),
+        );
+
+        let image_barrier = GfxImageBarrier::new()
+            .image(self.handle)
+            .src_mask(vk::PipelineStageFlags2::TRANSFER, vk::AccessFlags2::empty())
+            .dst_mask(vk::PipelineStageFlags2::ALL_COMMANDS, vk::AccessFlags2::empty())
+            .layout_transfer(copy_layout, layout)
+            .image_aspect_flag(VulkanFormatUtils::infer_aspect(self.format()));
+        cmd.image_memory_barrier(vk::DependencyFlags::empty(), std::slice::from_ref(&image_barrier));
+
+        let buffer_barrier = GfxBufferBarrier::new()
+            .buffer(readback_buffer.vk_buffer(), 0, vk::WHOLE_SIZE)
+            .src_mask(vk::PipelineStageFlags2::TRANSFER, vk::AccessFlags2::TRANSFER_WRITE)
+            .dst_mask(vk::PipelineStageFlags2::HOST, vk::AccessFlags2::HOST_READ);
+        cmd.buffer_memory_barrier(vk::DependencyFlags::empty(), std::slice::from_ref(&buffer_barrier));
+
+        readback_buffer
+    }
 }
 
 pub struct GfxImageCreateInfo {
@@ -270,6 +696,14 @@ impl GfxImageCreateInfo {
         self.inner.queue_family_indices(&self.queue_family_indices)
     }
 
+    // builder
+    /// mip level 的数量，参见 [`GfxImage::full_mip_level_count`]
+    #[inline]
+    pub fn mip_levels(mut self, mip_levels: u32) -> Self {
+        self.inner.mip_levels = mip_levels;
+        self
+    }
+
     // builder
     #[inline]
     pub fn queue_family_indices(mut self, queue_family_indices: &[u32]) -> Self {
@@ -281,3 +715,46 @@ impl GfxImageCreateInfo {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pixel_size_in_bytes() {
+        assert_eq!(VulkanFormatUtils::pixel_size_in_bytes(vk::Format::R8_UNORM), 1);
+        assert_eq!(VulkanFormatUtils::pixel_size_in_bytes(vk::Format::R16_SFLOAT), 2);
+        assert_eq!(VulkanFormatUtils::pixel_size_in_bytes(vk::Format::R8G8B8A8_UNORM), 4);
+        assert_eq!(VulkanFormatUtils::pixel_size_in_bytes(vk::Format::B8G8R8A8_SRGB), 4);
+        assert_eq!(VulkanFormatUtils::pixel_size_in_bytes(vk::Format::R32_UINT), 4);
+        assert_eq!(VulkanFormatUtils::pixel_size_in_bytes(vk::Format::R16G16B16A16_SFLOAT), 8);
+        assert_eq!(VulkanFormatUtils::pixel_size_in_bytes(vk::Format::R32G32_SFLOAT), 8);
+        assert_eq!(VulkanFormatUtils::pixel_size_in_bytes(vk::Format::R32G32B32_SFLOAT), 12);
+        assert_eq!(VulkanFormatUtils::pixel_size_in_bytes(vk::Format::R32G32B32A32_SFLOAT), 16);
+    }
+
+    #[test]
+    fn test_copy_aspect_and_pixel_size() {
+        let color = VulkanFormatUtils::copy_aspect_and_pixel_size(vk::Format::R8G8B8A8_UNORM);
+        assert_eq!(color, (vk::ImageAspectFlags::COLOR, 4));
+        let depth = VulkanFormatUtils::copy_aspect_and_pixel_size(vk::Format::D32_SFLOAT);
+        assert_eq!(depth, (vk::ImageAspectFlags::DEPTH, 4));
+        // depth stencil 只复制 depth
+        let depth_stencil = VulkanFormatUtils::copy_aspect_and_pixel_size(vk::Format::D24_UNORM_S8_UINT);
+        assert_eq!(depth_stencil, (vk::ImageAspectFlags::DEPTH, 4));
+        assert_eq!(
+            VulkanFormatUtils::infer_aspect(vk::Format::D24_UNORM_S8_UINT),
+            vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL
+        );
+    }
+
+    #[test]
+    fn test_full_mip_level_count() {
+        assert_eq!(GfxImage::full_mip_level_count(1, 1), 1);
+        assert_eq!(GfxImage::full_mip_level_count(2, 1), 2);
+        assert_eq!(GfxImage::full_mip_level_count(256, 256), 9);
+        assert_eq!(GfxImage::full_mip_level_count(1024, 300), 11);
+        assert_eq!(GfxImage::full_mip_level_count(300, 1023), 10);
+        assert_eq!(GfxImage::full_mip_level_count(0, 0), 1);
+    }
+}
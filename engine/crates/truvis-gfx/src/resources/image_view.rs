@@ -1,3 +1,4 @@
+use crate::resources::resource_stats::GfxResourceKind;
 use crate::{foundation::debug_messenger::DebugType, gfx::Gfx};
 use ash::vk;
 use ash::vk::Handle;
@@ -46,6 +47,7 @@ impl GfxImageView {
             name: name.as_ref().to_string(),
         };
         gfx_device.set_debug_name(&image_view, &name);
+        Gfx::get().resource_stats.on_create(GfxResourceKind::ImageView, 1);
         image_view
     }
 }
@@ -55,6 +57,9 @@ impl GfxImageView {
         self.destroy_mut();
     }
     pub fn destroy_mut(&mut self) {
+        if !self.handle.is_null() {
+            Gfx::get().resource_stats.on_destroy(GfxResourceKind::ImageView, 1);
+        }
         unsafe {
             let gfx_device = Gfx::get().gfx_device();
             gfx_device.destroy_image_view(self.handle, None);
@@ -1,6 +1,12 @@
 pub mod buffer;
+#[cfg(debug_assertions)]
+pub mod buffer_tracker;
+#[cfg(target_os = "linux")]
+pub mod external_image;
 pub mod image;
 pub mod image_view;
 pub mod layout;
+pub mod resource_stats;
+pub mod sparse_image;
 pub mod special_buffers;
 pub mod vertex_layout;
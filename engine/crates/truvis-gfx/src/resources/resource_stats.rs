@@ -0,0 +1,65 @@
+//! 存活的 Vulkan 资源计数
+//!
+//! 在资源创建、销毁时更新，release build 下同样有效，用于在 UI 中观察资源数量随时间的变化、定位泄漏。
+//! 描述符集和 command buffer 跟随所属的 pool 一起释放时，由 pool 负责扣除剩余的数量。
+
+use std::cell::Cell;
+
+/// 统计的资源种类
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GfxResourceKind {
+    Buffer,
+    /// 包括 VMA 分配的 image、sparse image 以及可导出的 external image，不包括 swapchain image
+    Image,
+    ImageView,
+    DescriptorSet,
+    CommandBuffer,
+}
+impl GfxResourceKind {
+    pub const ALL: [Self; 5] = [
+        Self::Buffer,
+        Self::Image,
+        Self::ImageView,
+        Self::DescriptorSet,
+        Self::CommandBuffer,
+    ];
+
+    #[inline]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Buffer => "Buffer",
+            Self::Image => "Image",
+            Self::ImageView => "ImageView",
+            Self::DescriptorSet => "DescriptorSet",
+            Self::CommandBuffer => "CommandBuffer",
+        }
+    }
+}
+
+/// 各类资源当前存活的数量
+#[derive(Default)]
+pub struct GfxResourceStats {
+    live_counts: [Cell<u64>; GfxResourceKind::ALL.len()],
+}
+// update
+impl GfxResourceStats {
+    #[inline]
+    pub fn on_create(&self, kind: GfxResourceKind, cnt: u64) {
+        let count = &self.live_counts[kind as usize];
+        count.set(count.get() + cnt);
+    }
+
+    #[inline]
+    pub fn on_destroy(&self, kind: GfxResourceKind, cnt: u64) {
+        let count = &self.live_counts[kind as usize];
+        debug_assert!(count.get() >= cnt, "{} live count underflow", kind.name());
+        count.set(count.get().saturating_sub(cnt));
+    }
+}
+// getter
+impl GfxResourceStats {
+    #[inline]
+    pub fn live_count(&self, kind: GfxResourceKind) -> u64 {
+        self.live_counts[kind as usize].get()
+    }
+}
@@ -0,0 +1,373 @@
+//! 按 tile 驻留的 sparse image
+//!
+//! image 使用 `SPARSE_BINDING | SPARSE_RESIDENCY` 创建，创建时不绑定任何内存，
+//! 之后以 tile（即 sparse image 的 `imageGranularity`）为单位按需绑定/解绑显存，
+//! 因此 image 的尺寸可以远大于实际占用的显存，用于虚拟纹理。
+//!
+//! # 约束
+//! - 需要设备支持 `sparseBinding` 和 `sparseResidencyImage2D`，参见 [`GfxSparseImage::is_supported`]
+//! - 目前只支持单个 mip level 的 2D image，尺寸需要是 tile 尺寸的整数倍
+//! - 未驻留的 tile 读取结果由 `residencyNonResidentStrict` 决定，可能是 0 也可能是未定义的值，
+//!   shader 不应该访问未驻留的 tile
+//!
+//! # 内存
+//! 创建时一次性申请 `max_resident_tiles` 个 tile 大小的内存作为页池，绑定 tile 时从池中取出一页，
+//! 避免每个 tile 单独申请内存而触及 `maxMemoryAllocationCount`。
+
+use std::collections::HashMap;
+
+use ash::vk;
+
+use crate::resources::resource_stats::GfxResourceKind;
+use crate::{commands::fence::GfxFence, gfx::Gfx};
+
+/// sparse image 中的一个 tile，以 tile 为单位的坐标
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GfxSparseTile {
+    pub x: u32,
+    pub y: u32,
+}
+
+pub struct GfxSparseImage {
+    handle: vk::Image,
+    extent: vk::Extent2D,
+    format: vk::Format,
+
+    /// 一个 tile 覆盖的像素范围
+    tile_extent: vk::Extent2D,
+    /// 一个 tile 占用的内存大小
+    page_size: vk::DeviceSize,
+
+    /// 所有 tile 共用的页池
+    page_pool: vk::DeviceMemory,
+    free_pages: Vec<u32>,
+    /// 已经驻留的 tile 及其占用的页
+    resident_tiles: HashMap<GfxSparseTile, u32>,
+
+    /// 部分实现要求额外绑定 metadata aspect 的内存，与 tile 无关，创建时绑定
+    metadata_memory: Option<vk::DeviceMemory>,
+
+    name: String,
+}
+
+// new & init
+impl GfxSparseImage {
+    /// 创建 sparse image，所有 tile 一开始都没有驻留
+    ///
+    /// 调用前需要通过 [`Self::is_supported`] 检查设备和格式是否支持
+    pub fn new(
+        extent: vk::Extent2D,
+        format: vk::Format,
+        usage: vk::ImageUsageFlags,
+        max_resident_tiles: u32,
+        name: impl AsRef<str>,
+    ) -> Self {
+        let gfx_device = Gfx::get().gfx_device();
+
+        let image_ci = vk::ImageCreateInfo::default()
+            .flags(vk::ImageCreateFlags::SPARSE_BINDING | vk::ImageCreateFlags::SPARSE_RESIDENCY)
+            .image_type(vk::ImageType::TYPE_2D)
+            .format(format)
+            .extent(extent.into())
+            .mip_levels(1)
+            .array_layers(1)
+            .samples(vk::SampleCountFlags::TYPE_1)
+            .tiling(vk::ImageTiling::OPTIMAL)
+            .usage(usage)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE)
+            .initial_layout(vk::ImageLayout::UNDEFINED);
+        let handle = unsafe { gfx_device.create_image(&image_ci, None).unwrap() };
+        Gfx::get().resource_stats.on_create(GfxResourceKind::Image, 1);
+
+        let mem_reqs = unsafe { gfx_device.get_image_memory_requirements(handle) };
+        let sparse_reqs = unsafe { gfx_device.get_image_sparse_memory_requirements(handle) };
+
+        let color_reqs = sparse_reqs
+            .iter()
+            .find(|reqs| reqs.format_properties.aspect_mask.contains(vk::ImageAspectFlags::COLOR))
+            .expect("sparse image has no color aspect requirements");
+        let granularity = color_reqs.format_properties.image_granularity;
+        let tile_extent = vk::Extent2D {
+            width: granularity.width,
+            height: granularity.height,
+        };
+        assert!(
+            extent.width % tile_extent.width == 0 && extent.height % tile_extent.height == 0,
+            "sparse image extent {:?} must be a multiple of tile extent {:?}",
+            extent,
+            tile_extent
+        );
+        debug_assert!(color_reqs.image_mip_tail_first_lod >= 1, "mip 0 should not be in the mip tail");
+
+        // 每个 tile 占用的内存就是 memory requirements 中的对齐大小
+        let page_size = mem_reqs.alignment;
+        let memory_type_index = Self::find_memory_type(mem_reqs.memory_type_bits).expect("no memory type for sparse image");
+        let page_pool = unsafe {
+            gfx_device
+                .allocate_memory(
+                    &vk::MemoryAllocateInfo::default()
+                        .allocation_size(page_size * max_resident_tiles as vk::DeviceSize)
+                        .memory_type_index(memory_type_index),
+                    None,
+                )
+                .unwrap()
+        };
+        gfx_device.set_object_debug_name(handle, format!("GfxSparseImage::{}", name.as_ref()));
+        gfx_device.set_object_debug_name(page_pool, format!("SparsePagePool::{}", name.as_ref()));
+
+        log::info!(
+            "sparse image {}: {}x{}, tile {}x{}, page size {}KB, max resident tiles {}",
+            name.as_ref(),
+            extent.width,
+            extent.height,
+            tile_extent.width,
+            tile_extent.height,
+            page_size / 1024,
+            max_resident_tiles
+        );
+
+        let mut sparse_image = Self {
+            handle,
+            extent,
+            format,
+            tile_extent,
+            page_size,
+            page_pool,
+            // 倒序存放，优先使用低地址的页
+            free_pages: (0..max_resident_tiles).rev().collect(),
+            resident_tiles: HashMap::new(),
+            metadata_memory: None,
+            name: name.as_ref().to_string(),
+        };
+
+        if let Some(metadata_reqs) = sparse_reqs
+            .iter()
+            .find(|reqs| reqs.format_properties.aspect_mask.contains(vk::ImageAspectFlags::METADATA))
+        {
+            sparse_image.bind_metadata(metadata_reqs, mem_reqs.alignment, memory_type_index);
+        }
+
+        sparse_image
+    }
+
+    /// metadata 整体位于 mip tail 中，需要以 opaque 的方式绑定
+    fn bind_metadata(
+        &mut self,
+        metadata_reqs: &vk::SparseImageMemoryRequirements,
+        alignment: vk::DeviceSize,
+        memory_type_index: u32,
+    ) {
+        let gfx_device = Gfx::get().gfx_device();
+
+        let size = metadata_reqs.image_mip_tail_size.next_multiple_of(alignment);
+        let memory = unsafe {
+            gfx_device
+                .allocate_memory(
+                    &vk::MemoryAllocateInfo::default().allocation_size(size).memory_type_index(memory_type_index),
+                    None,
+                )
+                .unwrap()
+        };
+
+        let bind = vk::SparseMemoryBind::default()
+            .resource_offset(metadata_reqs.image_mip_tail_offset)
+            .size(metadata_reqs.image_mip_tail_size)
+            .memory(memory)
+            .memory_offset(0)
+            .flags(vk::SparseMemoryBindFlags::METADATA);
+        let opaque_bind_info =
+            vk::SparseImageOpaqueMemoryBindInfo::default().image(self.handle).binds(std::slice::from_ref(&bind));
+        Self::submit_and_wait(
+            &vk::BindSparseInfo::default().image_opaque_binds(std::slice::from_ref(&opaque_bind_info)),
+            &self.name,
+        );
+
+        self.metadata_memory = Some(memory);
+    }
+
+    /// 找到第一个满足 `memory_type_bits` 的 device local 内存类型
+    fn find_memory_type(memory_type_bits: u32) -> Option<u32> {
+        let mem_props = &Gfx::get().physical_device().mem_props;
+        (0..mem_props.memory_type_count).find(|&i| {
+            memory_type_bits & (1 << i) != 0
+                && mem_props.memory_types[i as usize].property_flags.contains(vk::MemoryPropertyFlags::DEVICE_LOCAL)
+        })
+    }
+}
+impl Drop for GfxSparseImage {
+    fn drop(&mut self) {
+        log::debug!("Destroying GfxSparseImage: {}", self.name);
+
+        let gfx_device = Gfx::get().gfx_device();
+        unsafe {
+            gfx_device.destroy_image(self.handle, None);
+            Gfx::get().resource_stats.on_destroy(GfxResourceKind::Image, 1);
+            gfx_device.free_memory(self.page_pool, None);
+            if let Some(metadata_memory) = self.metadata_memory.take() {
+                gfx_device.free_memory(metadata_memory, None);
+            }
+        }
+    }
+}
+// getter
+impl GfxSparseImage {
+    #[inline]
+    pub fn handle(&self) -> vk::Image {
+        self.handle
+    }
+
+    #[inline]
+    pub fn extent(&self) -> vk::Extent2D {
+        self.extent
+    }
+
+    #[inline]
+    pub fn format(&self) -> vk::Format {
+        self.format
+    }
+
+    /// 一个 tile 覆盖的像素范围
+    #[inline]
+    pub fn tile_extent(&self) -> vk::Extent2D {
+        self.tile_extent
+    }
+
+    /// 横向和纵向的 tile 数量
+    #[inline]
+    pub fn tile_count(&self) -> (u32, u32) {
+        (self.extent.width / self.tile_extent.width, self.extent.height / self.tile_extent.height)
+    }
+
+    /// 一个 tile 占用的显存大小
+    #[inline]
+    pub fn page_size(&self) -> vk::DeviceSize {
+        self.page_size
+    }
+
+    /// 最多可以同时驻留的 tile 数量
+    #[inline]
+    pub fn max_resident_tiles(&self) -> usize {
+        self.resident_tiles.len() + self.free_pages.len()
+    }
+
+    #[inline]
+    pub fn is_resident(&self, tile: GfxSparseTile) -> bool {
+        self.resident_tiles.contains_key(&tile)
+    }
+
+    #[inline]
+    pub fn resident_tiles(&self) -> impl Iterator<Item = GfxSparseTile> + '_ {
+        self.resident_tiles.keys().copied()
+    }
+
+    /// tile 在 image 中的像素区域，用于向 tile 上传数据
+    pub fn tile_region(&self, tile: GfxSparseTile) -> (vk::Offset3D, vk::Extent3D) {
+        let offset = vk::Offset3D {
+            x: (tile.x * self.tile_extent.width) as i32,
+            y: (tile.y * self.tile_extent.height) as i32,
+            z: 0,
+        };
+        (offset, self.tile_extent.into())
+    }
+}
+// tools
+impl GfxSparseImage {
+    /// 检查当前设备能否创建指定格式、usage 的 sparse residency image
+    pub fn is_supported(format: vk::Format, usage: vk::ImageUsageFlags) -> bool {
+        if !Gfx::get().physical_device().support_sparse_residency_image_2d() {
+            return false;
+        }
+
+        let sparse_format_props = unsafe {
+            Gfx::get().instance().ash_instance().get_physical_device_sparse_image_format_properties(
+                Gfx::get().physical_device().vk_handle,
+                format,
+                vk::ImageType::TYPE_2D,
+                vk::SampleCountFlags::TYPE_1,
+                usage,
+                vk::ImageTiling::OPTIMAL,
+            )
+        };
+        !sparse_format_props.is_empty()
+    }
+
+    /// 为 tile 绑定显存，已经驻留的 tile 会被跳过
+    ///
+    /// 绑定完成后才返回；新绑定的 tile 内容是未定义的，需要调用者上传数据
+    ///
+    /// # Panics
+    /// 页池中剩余的页不足时 panic，调用者需要根据 [`Self::max_resident_tiles`] 控制驻留数量
+    pub fn bind_tiles(&mut self, tiles: &[GfxSparseTile]) {
+        let mut binds = Vec::with_capacity(tiles.len());
+        for &tile in tiles {
+            if self.resident_tiles.contains_key(&tile) {
+                continue;
+            }
+            let page = self.free_pages.pop().unwrap_or_else(|| {
+                panic!("sparse image {}: out of pages, max resident tiles: {}", self.name, self.max_resident_tiles())
+            });
+            self.resident_tiles.insert(tile, page);
+            binds.push(self.tile_bind(tile, self.page_pool, page as vk::DeviceSize * self.page_size));
+        }
+
+        self.submit_image_binds(&binds);
+    }
+
+    /// 解除 tile 的显存绑定，并将占用的页归还到页池，没有驻留的 tile 会被跳过
+    ///
+    /// 调用者需要保证 GPU 已经不再访问这些 tile
+    pub fn unbind_tiles(&mut self, tiles: &[GfxSparseTile]) {
+        let mut binds = Vec::with_capacity(tiles.len());
+        for &tile in tiles {
+            let Some(page) = self.resident_tiles.remove(&tile) else {
+                continue;
+            };
+            self.free_pages.push(page);
+            binds.push(self.tile_bind(tile, vk::DeviceMemory::null(), 0));
+        }
+
+        self.submit_image_binds(&binds);
+    }
+
+    fn tile_bind(
+        &self,
+        tile: GfxSparseTile,
+        memory: vk::DeviceMemory,
+        memory_offset: vk::DeviceSize,
+    ) -> vk::SparseImageMemoryBind {
+        let (offset, extent) = self.tile_region(tile);
+        vk::SparseImageMemoryBind::default()
+            .subresource(vk::ImageSubresource {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level: 0,
+                array_layer: 0,
+            })
+            .offset(offset)
+            .extent(extent)
+            .memory(memory)
+            .memory_offset(memory_offset)
+    }
+
+    fn submit_image_binds(&self, binds: &[vk::SparseImageMemoryBind]) {
+        if binds.is_empty() {
+            return;
+        }
+
+        let image_bind_info = vk::SparseImageMemoryBindInfo::default().image(self.handle).binds(binds);
+        Self::submit_and_wait(
+            &vk::BindSparseInfo::default().image_binds(std::slice::from_ref(&image_bind_info)),
+            &self.name,
+        );
+    }
+
+    /// sparse binding 与 command buffer 的提交之间没有隐式顺序，这里直接阻塞等待绑定完成，
+    /// 之后提交的 command buffer 一定能看到新的绑定
+    fn submit_and_wait(bind_info: &vk::BindSparseInfo, name: &str) {
+        let _span = tracy_client::span!("GfxSparseImage::bind_sparse");
+
+        let fence = GfxFence::new(false, &format!("sparse-bind-{name}"));
+        Gfx::get().gfx_queue().bind_sparse(std::slice::from_ref(bind_info), Some(&fence));
+        fence.wait();
+        fence.destroy();
+    }
+}
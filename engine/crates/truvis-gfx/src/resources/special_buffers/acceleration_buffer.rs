@@ -61,3 +61,59 @@ impl GfxAccelerationInstanceBuffer {
         Self { inner: buffer }
     }
 }
+
+/// 构建 BLAS 时 geometry 的变换矩阵 buffer
+///
+/// 每个元素是一个 `vk::TransformMatrixKHR`（3x4 行主序），
+/// 同一个顶点 buffer 可以配合不同的变换构建 BLAS，而不需要复制顶点。
+///
+/// spec 要求 transform_data 的地址以及 transform_offset 都是 16 字节对齐的，
+/// `vk::TransformMatrixKHR` 的大小是 48 字节，因此逐元素寻址天然满足对齐要求。
+pub struct GfxAccelerationTransformBuffer {
+    inner: GfxBuffer,
+    transform_cnt: usize,
+}
+impl_derive_buffer!(GfxAccelerationTransformBuffer, GfxBuffer, inner);
+impl GfxAccelerationTransformBuffer {
+    /// transform_data 的地址对齐要求
+    pub const ALIGNMENT: vk::DeviceSize = 16;
+
+    /// 单个变换矩阵的字节大小
+    pub const TRANSFORM_STRIDE: vk::DeviceSize = size_of::<vk::TransformMatrixKHR>() as vk::DeviceSize;
+
+    pub fn new(transform_cnt: usize, name: impl AsRef<str>) -> Self {
+        let buffer = GfxBuffer::new(
+            transform_cnt as vk::DeviceSize * Self::TRANSFORM_STRIDE,
+            vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS
+                | vk::BufferUsageFlags::ACCELERATION_STRUCTURE_BUILD_INPUT_READ_ONLY_KHR
+                | vk::BufferUsageFlags::TRANSFER_DST,
+            Some(Self::ALIGNMENT),
+            false,
+            name,
+        );
+
+        Self {
+            inner: buffer,
+            transform_cnt,
+        }
+    }
+
+    /// 创建 buffer 并同步上传变换矩阵
+    pub fn new_with_data(transforms: &[vk::TransformMatrixKHR], name: impl AsRef<str>) -> Self {
+        let buffer = Self::new(transforms.len(), name);
+        buffer.transfer_data_sync(transforms);
+        buffer
+    }
+
+    #[inline]
+    pub fn transform_cnt(&self) -> usize {
+        self.transform_cnt
+    }
+
+    /// 第 index 个变换矩阵相对于 buffer 起始地址的字节偏移，用于 `transform_offset`
+    #[inline]
+    pub fn transform_offset(&self, index: usize) -> u32 {
+        assert!(index < self.transform_cnt, "transform index out of range: {} >= {}", index, self.transform_cnt);
+        (index as vk::DeviceSize * Self::TRANSFORM_STRIDE) as u32
+    }
+}
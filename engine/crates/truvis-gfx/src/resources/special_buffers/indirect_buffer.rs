@@ -0,0 +1,106 @@
+use std::ops::{Deref, DerefMut};
+
+use ash::vk;
+
+use crate::{impl_derive_buffer, resources::buffer::GfxBuffer};
+
+/// 存放 [`vk::DrawIndexedIndirectCommand`] 数组的 buffer，
+/// 参见 [`crate::commands::command_buffer::GfxCommandBuffer::cmd_draw_indexed_indirect`]
+///
+/// 同时带有 STORAGE_BUFFER 和 device address，之后可以由 compute shader（例如 GPU 剔除）直接写入
+pub struct GfxIndexedIndirectBuffer {
+    inner: GfxBuffer,
+
+    /// command 的数量
+    draw_cnt: usize,
+}
+impl_derive_buffer!(GfxIndexedIndirectBuffer, GfxBuffer, inner);
+// new & init
+impl GfxIndexedIndirectBuffer {
+    pub fn new(draw_cnt: usize, mmap: bool, debug_name: impl AsRef<str>) -> Self {
+        let size = draw_cnt * size_of::<vk::DrawIndexedIndirectCommand>();
+        let inner = GfxBuffer::new(
+            size as vk::DeviceSize,
+            vk::BufferUsageFlags::INDIRECT_BUFFER
+                | vk::BufferUsageFlags::STORAGE_BUFFER
+                | vk::BufferUsageFlags::TRANSFER_DST
+                | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+            None,
+            mmap,
+            debug_name,
+        );
+        Self { inner, draw_cnt }
+    }
+
+    /// 创建 device local 的 buffer，并同步写入 `commands`
+    pub fn new_with_commands(commands: &[vk::DrawIndexedIndirectCommand], debug_name: impl AsRef<str>) -> Self {
+        let buffer = Self::new(commands.len(), false, debug_name);
+        buffer.transfer_data_sync(commands);
+        buffer
+    }
+}
+// getter
+impl GfxIndexedIndirectBuffer {
+    #[inline]
+    pub fn draw_cnt(&self) -> usize {
+        self.draw_cnt
+    }
+
+    /// 相邻两个 command 之间的字节数
+    #[inline]
+    pub fn stride() -> u32 {
+        size_of::<vk::DrawIndexedIndirectCommand>() as u32
+    }
+}
+// tools
+impl GfxIndexedIndirectBuffer {
+    /// 绘制 index buffer 中 `[first_index, first_index + index_cnt)` 的 `instance_cnt` 个实例，
+    /// shader 中的实例序号从 `first_instance` 开始
+    #[inline]
+    pub fn command(
+        index_cnt: u32,
+        first_index: u32,
+        vertex_offset: i32,
+        instance_cnt: u32,
+        first_instance: u32,
+    ) -> vk::DrawIndexedIndirectCommand {
+        vk::DrawIndexedIndirectCommand {
+            index_count: index_cnt,
+            instance_count: instance_cnt,
+            first_index,
+            vertex_offset,
+            first_instance,
+        }
+    }
+
+    /// 通过 mmap 覆盖前 `commands.len()` 个 command，需要以 mmap 的方式创建
+    pub fn write_commands(&self, commands: &[vk::DrawIndexedIndirectCommand]) {
+        assert!(commands.len() <= self.draw_cnt, "GfxIndexedIndirectBuffer: too many commands");
+        self.inner.transfer_data_by_mmap(commands);
+    }
+}
+// destroy
+impl GfxIndexedIndirectBuffer {
+    #[inline]
+    pub fn destroy(self) {
+        self.inner.destroy();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_layout() {
+        // stride 需要与 vkCmdDrawIndexedIndirect 要求的结构体大小一致
+        assert_eq!(GfxIndexedIndirectBuffer::stride(), 20);
+
+        let command = GfxIndexedIndirectBuffer::command(36, 6, -4, 8, 16);
+        assert_eq!(command.index_count, 36);
+        assert_eq!(command.first_index, 6);
+        assert_eq!(command.vertex_offset, -4);
+        assert_eq!(command.instance_count, 8);
+        assert_eq!(command.first_instance, 16);
+    }
+}
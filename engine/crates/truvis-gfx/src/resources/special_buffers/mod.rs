@@ -1,5 +1,7 @@
 pub mod acceleration_buffer;
 pub mod index_buffer;
+pub mod indirect_buffer;
+pub mod readback_buffer;
 pub mod sbt_buffer;
 pub mod stage_buffer;
 pub mod structured_buffer;
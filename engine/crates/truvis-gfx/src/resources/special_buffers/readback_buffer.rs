@@ -0,0 +1,77 @@
+use std::ops::{Deref, DerefMut};
+
+use ash::vk;
+
+use crate::{impl_derive_buffer, resources::buffer::GfxBuffer};
+
+/// 从 GPU 读回数据的 host-visible buffer，参见 [`crate::resources::image::GfxImage::read_back_region`]
+///
+/// 数据逐行紧密排列，每行 `extent.width` 个像素，从区域的左上角开始。
+/// 只有在 copy 命令执行完成（例如等待 fence 或 timeline semaphore）之后才能读取
+pub struct GfxReadbackBuffer {
+    inner: GfxBuffer,
+    extent: vk::Extent2D,
+    texel_size: usize,
+}
+impl_derive_buffer!(GfxReadbackBuffer, GfxBuffer, inner);
+// new & init
+impl GfxReadbackBuffer {
+    pub fn new(extent: vk::Extent2D, texel_size: usize, debug_name: impl AsRef<str>) -> Self {
+        let size = (extent.width * extent.height) as usize * texel_size;
+        let inner = GfxBuffer::new(size as vk::DeviceSize, vk::BufferUsageFlags::TRANSFER_DST, None, true, debug_name);
+        Self {
+            inner,
+            extent,
+            texel_size,
+        }
+    }
+}
+// getter
+impl GfxReadbackBuffer {
+    /// 读回区域的大小
+    #[inline]
+    pub fn extent(&self) -> vk::Extent2D {
+        self.extent
+    }
+
+    /// 每个像素的字节数
+    #[inline]
+    pub fn texel_size(&self) -> usize {
+        self.texel_size
+    }
+
+    /// 每行的字节数
+    #[inline]
+    pub fn row_pitch(&self) -> usize {
+        self.extent.width as usize * self.texel_size
+    }
+
+    /// 读回的原始字节
+    pub fn as_bytes(&self) -> &[u8] {
+        let size = self.row_pitch() * self.extent.height as usize;
+        self.inner.invalidate(0, size as vk::DeviceSize);
+        unsafe { std::slice::from_raw_parts(self.inner.mapped_ptr(), size) }
+    }
+
+    /// 以 `T` 解释读回的数据，例如 RGBA32F 的图像可以使用 `[f32; 4]` 或者 `f32`
+    ///
+    /// # Panic
+    /// 数据大小不是 `T` 的整数倍时会 panic
+    pub fn as_slice<T: bytemuck::Pod>(&self) -> &[T] {
+        bytemuck::cast_slice(self.as_bytes())
+    }
+
+    /// 像素 (x, y) 处的字节
+    pub fn texel_bytes(&self, x: u32, y: u32) -> &[u8] {
+        assert!(x < self.extent.width && y < self.extent.height);
+        let offset = y as usize * self.row_pitch() + x as usize * self.texel_size;
+        &self.as_bytes()[offset..offset + self.texel_size]
+    }
+}
+// destroy
+impl GfxReadbackBuffer {
+    #[inline]
+    pub fn destroy(self) {
+        self.inner.destroy();
+    }
+}
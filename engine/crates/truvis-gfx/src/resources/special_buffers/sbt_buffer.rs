@@ -4,6 +4,7 @@ use ash::{vk, vk::Handle};
 
 use crate::{foundation::debug_messenger::DebugType, gfx::Gfx, impl_derive_buffer, resources::buffer::GfxBuffer};
 
+/// device local 的 SBT buffer，内容通过 stage buffer 上传，例如 [`GfxBuffer::transfer_data_sync`]
 pub struct GfxSBTBuffer {
     _inner: GfxBuffer,
 }
@@ -15,9 +16,10 @@ impl GfxSBTBuffer {
             size,
             vk::BufferUsageFlags::SHADER_BINDING_TABLE_KHR
                 | vk::BufferUsageFlags::TRANSFER_SRC
+                | vk::BufferUsageFlags::TRANSFER_DST
                 | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
             Some(align),
-            true,
+            false,
             format!("SBTBuffer::{}", name.as_ref()),
         );
         let buffer = Self { _inner: inner };
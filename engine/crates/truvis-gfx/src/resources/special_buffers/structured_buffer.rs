@@ -5,13 +5,20 @@ use std::{
 
 use ash::vk;
 
-use crate::{foundation::debug_messenger::DebugType, impl_derive_buffer, resources::buffer::GfxBuffer};
+use crate::{foundation::debug_messenger::DebugType, gfx::Gfx, impl_derive_buffer, resources::buffer::GfxBuffer};
 
 /// buffer 内存放的是结构体或者结构体的数组
+///
+/// 既可以当作固定大小的数组使用，也可以通过 [`Self::push`]、[`Self::extend`] 追加元素，
+/// 容量不足时自动扩容，扩容后底层 buffer 和 device address 都会变化，参见 [`Self::device_address_changed`]
 pub struct GfxStructuredBuffer<T: Sized> {
     inner: GfxBuffer,
-    /// 结构体的数量
+    /// 结构体的数量，也就是 buffer 的容量
     ele_num: usize,
+    /// 通过 push/extend 追加的元素数量
+    len: usize,
+    /// 扩容导致底层 buffer 被替换，需要调用方刷新描述符后清除
+    device_address_changed: bool,
     _phantom: PhantomData<T>,
 }
 impl_derive_buffer!(GfxStructuredBuffer<T>, GfxBuffer, inner);
@@ -23,6 +30,7 @@ impl<T> GfxStructuredBuffer<T> {
             len,
             vk::BufferUsageFlags::STORAGE_BUFFER
                 | vk::BufferUsageFlags::TRANSFER_DST
+                | vk::BufferUsageFlags::TRANSFER_SRC // 扩容时需要将旧数据拷贝到新的 buffer
                 | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
             false,
         )
@@ -51,6 +59,8 @@ impl<T> GfxStructuredBuffer<T> {
         Self {
             inner: buffer,
             ele_num: len,
+            len: 0,
+            device_address_changed: false,
             _phantom: PhantomData,
         }
     }
@@ -60,6 +70,162 @@ impl<T> GfxStructuredBuffer<T> {
         unsafe { std::slice::from_raw_parts_mut(mapped_ptr as *mut T, self.ele_num) }
     }
 }
+// getter
+impl<T> GfxStructuredBuffer<T> {
+    /// 通过 push/extend 追加的元素数量
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 可以容纳的元素数量
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.ele_num
+    }
+
+    /// 自上次 [`Self::clear_device_address_changed`] 以来，底层 buffer 是否因为扩容被替换
+    ///
+    /// 为 true 时 vk::Buffer 和 device address 都已经变化，调用方需要在帧末刷新引用它们的描述符
+    #[inline]
+    pub fn device_address_changed(&self) -> bool {
+        self.device_address_changed
+    }
+}
+// push
+impl<T: Copy> GfxStructuredBuffer<T> {
+    /// 扩容时容量的增长倍数
+    const GROWTH_FACTOR: f32 = 1.5;
+
+    /// 在末尾追加一个元素，容量不足时自动扩容，参见 [`Self::extend`]
+    pub fn push(&mut self, value: T) -> vk::DeviceAddress {
+        self.extend(std::slice::from_ref(&value))
+    }
+
+    /// 在末尾追加一组元素，容量不足时自动扩容，返回（扩容之后的）device address
+    ///
+    /// mapped buffer 直接写入映射的内存；否则通过临时 stage buffer 同步上传，会阻塞运行
+    ///
+    /// # Panics
+    /// buffer usage 不包含 SHADER_DEVICE_ADDRESS 时 panic
+    pub fn extend(&mut self, values: &[T]) -> vk::DeviceAddress {
+        if values.is_empty() {
+            return self.inner.device_address();
+        }
+        self.reserve(values.len());
+
+        let offset = (self.len * size_of::<T>()) as vk::DeviceSize;
+        let size = size_of_val(values) as vk::DeviceSize;
+        if self.inner.is_mapped() {
+            unsafe {
+                std::ptr::copy_nonoverlapping(
+                    values.as_ptr() as *const u8,
+                    self.inner.mapped_ptr().add(offset as usize),
+                    size as usize,
+                );
+            }
+            self.inner.flush(offset, size);
+        } else {
+            let stage_buffer = GfxBuffer::new_stage_buffer(size, format!("{}-push-stage", self.inner.debug_name()));
+            stage_buffer.transfer_data_by_mmap(values);
+            Gfx::get().one_time_exec(
+                |cmd| {
+                    cmd.cmd_copy_buffer(
+                        &stage_buffer,
+                        &self.inner,
+                        &[vk::BufferCopy {
+                            src_offset: 0,
+                            dst_offset: offset,
+                            size,
+                        }],
+                    );
+                },
+                format!("{}-push", self.inner.debug_name()),
+            );
+        }
+
+        self.len += values.len();
+        self.inner.device_address()
+    }
+
+    /// 保证还能容纳 additional 个元素，容量不足时按 1.5 倍扩容
+    ///
+    /// 返回是否重新分配了底层 buffer
+    ///
+    /// # Note
+    /// 扩容通过 cmd_copy 将旧 buffer 的全部内容拷贝到新的 buffer（包括通过 [`Self::mapped_slice`]
+    /// 等方式写入、不计入 [`Self::len`] 的数据），要求 buffer usage 包含 TRANSFER_SRC 和 TRANSFER_DST；
+    /// 拷贝是阻塞执行的，返回时 queue 上之前提交的命令都已经完成，旧的 buffer 可以立即释放
+    pub fn reserve(&mut self, additional: usize) -> bool {
+        let required = self.len + additional;
+        if required <= self.ele_num {
+            return false;
+        }
+
+        let usage = self.inner.usage();
+        assert!(
+            usage.contains(vk::BufferUsageFlags::TRANSFER_SRC | vk::BufferUsageFlags::TRANSFER_DST),
+            "GfxStructuredBuffer::reserve: {} needs TRANSFER_SRC | TRANSFER_DST to grow",
+            self.inner.debug_name()
+        );
+
+        let new_capacity = Self::grown_capacity(self.ele_num, required);
+        let new_buffer = GfxBuffer::new(
+            (new_capacity * size_of::<T>()) as vk::DeviceSize,
+            usage,
+            None,
+            self.inner.is_mapped(),
+            self.inner.debug_name(),
+        );
+
+        if self.ele_num > 0 {
+            Gfx::get().one_time_exec(
+                |cmd| {
+                    cmd.cmd_copy_buffer(
+                        &self.inner,
+                        &new_buffer,
+                        &[vk::BufferCopy {
+                            size: (self.ele_num * size_of::<T>()) as vk::DeviceSize,
+                            ..Default::default()
+                        }],
+                    );
+                },
+                format!("{}-grow", self.inner.debug_name()),
+            );
+        } else {
+            // 旧的 buffer 没有内容，但仍可能被之前提交的命令使用
+            Gfx::get().gfx_queue().wait_idle();
+        }
+
+        log::debug!("GfxStructuredBuffer {} grows: {} -> {}", self.inner.debug_name(), self.ele_num, new_capacity);
+        self.inner = new_buffer;
+        self.ele_num = new_capacity;
+        self.device_address_changed = true;
+        true
+    }
+
+    /// 容量为 `capacity` 时，为了容纳 `required` 个元素扩容之后的容量
+    fn grown_capacity(capacity: usize, required: usize) -> usize {
+        required.max((capacity as f32 * Self::GROWTH_FACTOR).ceil() as usize)
+    }
+
+    /// 清空追加的元素，容量保持不变
+    #[inline]
+    pub fn clear_elements(&mut self) {
+        self.len = 0;
+    }
+
+    /// 调用方刷新了描述符之后调用
+    #[inline]
+    pub fn clear_device_address_changed(&mut self) {
+        self.device_address_changed = false;
+    }
+}
 impl<T: bytemuck::Pod> DebugType for GfxStructuredBuffer<T> {
     #[inline]
     fn debug_type_name() -> &'static str {
@@ -71,3 +237,18 @@ impl<T: bytemuck::Pod> DebugType for GfxStructuredBuffer<T> {
         self.vk_buffer()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grown_capacity() {
+        // 按 1.5 倍增长，向上取整
+        assert_eq!(GfxStructuredBuffer::<u32>::grown_capacity(4, 5), 6);
+        assert_eq!(GfxStructuredBuffer::<u32>::grown_capacity(3, 4), 5);
+        // 一次追加很多元素时直接扩容到需要的数量
+        assert_eq!(GfxStructuredBuffer::<u32>::grown_capacity(4, 100), 100);
+        assert_eq!(GfxStructuredBuffer::<u32>::grown_capacity(0, 1), 1);
+    }
+}
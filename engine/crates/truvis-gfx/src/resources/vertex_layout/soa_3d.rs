@@ -4,6 +4,8 @@ use ash::vk;
 use std::ptr;
 
 /// SoA 的顶点 buffer 布局，包含：Positions, Normals, Tangents, UVs
+///
+/// tangent 的 w 分量为副切线的方向（glTF 约定），在 buffer 中放在最后，保证 16 字节对齐
 pub struct VertexLayoutSoA3D;
 impl GfxVertexLayout for VertexLayoutSoA3D {
     fn vertex_input_bindings() -> Vec<vk::VertexInputBindingDescription> {
@@ -23,7 +25,7 @@ impl GfxVertexLayout for VertexLayoutSoA3D {
             // tangents
             vk::VertexInputBindingDescription {
                 binding: 2,
-                stride: size_of::<glam::Vec3>() as u32,
+                stride: size_of::<glam::Vec4>() as u32,
                 input_rate: vk::VertexInputRate::VERTEX,
             },
             // uvs
@@ -55,7 +57,7 @@ impl GfxVertexLayout for VertexLayoutSoA3D {
             vk::VertexInputAttributeDescription {
                 binding: 2,
                 location: 2,
-                format: vk::Format::R32G32B32_SFLOAT,
+                format: vk::Format::R32G32B32A32_SFLOAT,
                 offset: 0,
             },
             // uvs
@@ -69,7 +71,7 @@ impl GfxVertexLayout for VertexLayoutSoA3D {
     }
 
     fn buffer_size(vertex_cnt: usize) -> usize {
-        vertex_cnt * (size_of::<glam::Vec3>() * 3 + size_of::<glam::Vec2>())
+        vertex_cnt * (size_of::<glam::Vec3>() * 2 + size_of::<glam::Vec2>() + size_of::<glam::Vec4>())
     }
     fn pos_stride() -> u32 {
         size_of::<glam::Vec3>() as u32
@@ -81,10 +83,10 @@ impl GfxVertexLayout for VertexLayoutSoA3D {
         (vertex_cnt * size_of::<glam::Vec3>()) as vk::DeviceSize
     }
     fn tangent_offset(vertex_cnt: usize) -> vk::DeviceSize {
-        (vertex_cnt * size_of::<glam::Vec3>() * 2) as vk::DeviceSize
+        (vertex_cnt * (size_of::<glam::Vec3>() * 2 + size_of::<glam::Vec2>())) as vk::DeviceSize
     }
     fn uv_offset(vertex_cnt: usize) -> vk::DeviceSize {
-        (vertex_cnt * (size_of::<glam::Vec3>() * 3)) as vk::DeviceSize
+        (vertex_cnt * size_of::<glam::Vec3>() * 2) as vk::DeviceSize
     }
 }
 
@@ -92,7 +94,7 @@ impl VertexLayoutSoA3D {
     pub fn create_vertex_buffer(
         positions: &[glam::Vec3],
         normals: &[glam::Vec3],
-        tangents: &[glam::Vec3],
+        tangents: &[glam::Vec4],
         uvs: &[glam::Vec2],
         name: impl AsRef<str>,
     ) -> GfxVertexBuffer<Self> {
@@ -1,2 +1,3 @@
 pub mod surface;
+pub mod surface_info;
 pub mod swapchain;
@@ -0,0 +1,238 @@
+use std::fmt::Display;
+
+use ash::vk;
+use itertools::Itertools;
+
+/// surface 在当前物理设备上支持的全部能力
+///
+/// 通过 [`crate::gfx::Gfx::surface_capabilities`] 获取，创建 swapchain 时据此选择合法的参数
+#[derive(Debug, Clone)]
+pub struct GfxSurfaceInfo {
+    pub formats: Vec<vk::SurfaceFormatKHR>,
+    pub present_modes: Vec<vk::PresentModeKHR>,
+
+    pub min_image_count: u32,
+    /// 为 0 表示不限制 image 数量
+    pub max_image_count: u32,
+
+    /// 包含特殊值 0xFFFFFFFF 时表示由 swapchain 决定 extent
+    pub current_extent: vk::Extent2D,
+    pub min_image_extent: vk::Extent2D,
+    pub max_image_extent: vk::Extent2D,
+
+    pub current_transform: vk::SurfaceTransformFlagsKHR,
+    pub supported_transforms: vk::SurfaceTransformFlagsKHR,
+    pub supported_composite_alpha: vk::CompositeAlphaFlagsKHR,
+    pub supported_usage_flags: vk::ImageUsageFlags,
+}
+
+// new & init
+impl GfxSurfaceInfo {
+    pub fn new(
+        capabilities: &vk::SurfaceCapabilitiesKHR,
+        formats: Vec<vk::SurfaceFormatKHR>,
+        present_modes: Vec<vk::PresentModeKHR>,
+    ) -> Self {
+        Self {
+            formats,
+            present_modes,
+            min_image_count: capabilities.min_image_count,
+            max_image_count: capabilities.max_image_count,
+            current_extent: capabilities.current_extent,
+            min_image_extent: capabilities.min_image_extent,
+            max_image_extent: capabilities.max_image_extent,
+            current_transform: capabilities.current_transform,
+            supported_transforms: capabilities.supported_transforms,
+            supported_composite_alpha: capabilities.supported_composite_alpha,
+            supported_usage_flags: capabilities.supported_usage_flags,
+        }
+    }
+}
+
+// tools
+impl GfxSurfaceInfo {
+    /// 优先使用 `preferred`；不支持时依次尝试 B8G8R8A8_SRGB、R8G8B8A8_SRGB（sRGB 非线性色彩空间），最后退化为第一个支持的格式
+    pub fn choose_format(&self, preferred: vk::SurfaceFormatKHR) -> vk::SurfaceFormatKHR {
+        const FALLBACK_FORMATS: [vk::Format; 2] = [vk::Format::B8G8R8A8_SRGB, vk::Format::R8G8B8A8_SRGB];
+
+        let is_supported = |format: vk::Format, color_space: vk::ColorSpaceKHR| {
+            self.formats.iter().any(|f| f.format == format && f.color_space == color_space)
+        };
+        if is_supported(preferred.format, preferred.color_space) {
+            return preferred;
+        }
+
+        let chosen = FALLBACK_FORMATS
+            .iter()
+            .find(|&&format| is_supported(format, vk::ColorSpaceKHR::SRGB_NONLINEAR))
+            .map(|&format| vk::SurfaceFormatKHR {
+                format,
+                color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR,
+            })
+            .or_else(|| self.formats.first().copied())
+            .expect("surface supports no format");
+        log::warn!("surface format {:?} is not supported, fallback to {:?}", preferred, chosen);
+        chosen
+    }
+
+    /// 按照 `preferred` 的顺序选择第一个支持的 present mode；都不支持时使用规范保证一定支持的 FIFO
+    pub fn choose_present_mode(&self, preferred: &[vk::PresentModeKHR]) -> vk::PresentModeKHR {
+        let chosen = preferred
+            .iter()
+            .copied()
+            .find(|present_mode| self.present_modes.contains(present_mode))
+            .unwrap_or(vk::PresentModeKHR::FIFO);
+        if preferred.first() != Some(&chosen) {
+            log::warn!("present mode {:?} is not supported, fallback to {:?}", preferred.first(), chosen);
+        }
+        chosen
+    }
+
+    /// 比最小值多一张，避免等待驱动释放 image；不超过最大值
+    pub fn choose_image_count(&self) -> u32 {
+        let image_count = self.min_image_count + 1;
+        if self.max_image_count == 0 { image_count } else { image_count.min(self.max_image_count) }
+    }
+
+    /// 优先不做变换，否则沿用 surface 当前的变换
+    pub fn choose_pre_transform(&self) -> vk::SurfaceTransformFlagsKHR {
+        if self.supported_transforms.contains(vk::SurfaceTransformFlagsKHR::IDENTITY) {
+            vk::SurfaceTransformFlagsKHR::IDENTITY
+        } else {
+            self.current_transform
+        }
+    }
+
+    /// 优先 OPAQUE，否则选择第一个支持的模式
+    pub fn choose_composite_alpha(&self) -> vk::CompositeAlphaFlagsKHR {
+        [
+            vk::CompositeAlphaFlagsKHR::OPAQUE,
+            vk::CompositeAlphaFlagsKHR::INHERIT,
+            vk::CompositeAlphaFlagsKHR::PRE_MULTIPLIED,
+            vk::CompositeAlphaFlagsKHR::POST_MULTIPLIED,
+        ]
+        .into_iter()
+        .find(|&mode| self.supported_composite_alpha.contains(mode))
+        .unwrap_or(vk::CompositeAlphaFlagsKHR::OPAQUE)
+    }
+}
+
+impl Display for GfxSurfaceInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "surface info:")?;
+        writeln!(
+            f,
+            "  formats: [{}]",
+            self.formats.iter().map(|format| format!("{:?}/{:?}", format.format, format.color_space)).join(", ")
+        )?;
+        writeln!(f, "  present modes: {:?}", self.present_modes)?;
+        writeln!(f, "  image count: {} ~ {}", self.min_image_count, self.max_image_count)?;
+        writeln!(
+            f,
+            "  extent: current {}x{}, min {}x{}, max {}x{}",
+            self.current_extent.width,
+            self.current_extent.height,
+            self.min_image_extent.width,
+            self.min_image_extent.height,
+            self.max_image_extent.width,
+            self.max_image_extent.height
+        )?;
+        writeln!(f, "  transform: current {:?}, supported {:?}", self.current_transform, self.supported_transforms)?;
+        writeln!(f, "  composite alpha: {:?}", self.supported_composite_alpha)?;
+        write!(f, "  usage: {:?}", self.supported_usage_flags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn surface_info(present_modes: Vec<vk::PresentModeKHR>) -> GfxSurfaceInfo {
+        GfxSurfaceInfo::new(&vk::SurfaceCapabilitiesKHR::default(), vec![], present_modes)
+    }
+
+    #[test]
+    fn test_choose_present_mode_in_preferred_order() {
+        let preferred = [
+            vk::PresentModeKHR::MAILBOX,
+            vk::PresentModeKHR::IMMEDIATE,
+            vk::PresentModeKHR::FIFO,
+        ];
+
+        let info = surface_info(vec![vk::PresentModeKHR::FIFO, vk::PresentModeKHR::MAILBOX]);
+        assert_eq!(info.choose_present_mode(&preferred), vk::PresentModeKHR::MAILBOX);
+
+        let info = surface_info(vec![vk::PresentModeKHR::FIFO, vk::PresentModeKHR::IMMEDIATE]);
+        assert_eq!(info.choose_present_mode(&preferred), vk::PresentModeKHR::IMMEDIATE);
+    }
+
+    #[test]
+    fn test_choose_present_mode_fallback_to_fifo() {
+        let info = surface_info(vec![vk::PresentModeKHR::FIFO]);
+        assert_eq!(info.choose_present_mode(&[vk::PresentModeKHR::MAILBOX]), vk::PresentModeKHR::FIFO);
+        assert_eq!(info.choose_present_mode(&[]), vk::PresentModeKHR::FIFO);
+    }
+
+    fn surface_format(format: vk::Format, color_space: vk::ColorSpaceKHR) -> vk::SurfaceFormatKHR {
+        vk::SurfaceFormatKHR { format, color_space }
+    }
+
+    #[test]
+    fn test_choose_format_prefers_requested_then_srgb() {
+        let preferred = surface_format(vk::Format::A2B10G10R10_UNORM_PACK32, vk::ColorSpaceKHR::HDR10_ST2084_EXT);
+        let unorm = surface_format(vk::Format::B8G8R8A8_UNORM, vk::ColorSpaceKHR::SRGB_NONLINEAR);
+        let bgra_srgb = surface_format(vk::Format::B8G8R8A8_SRGB, vk::ColorSpaceKHR::SRGB_NONLINEAR);
+        let rgba_srgb = surface_format(vk::Format::R8G8B8A8_SRGB, vk::ColorSpaceKHR::SRGB_NONLINEAR);
+
+        let mut info = surface_info(vec![]);
+        info.formats = vec![unorm, rgba_srgb, preferred];
+        assert_eq!(info.choose_format(preferred), preferred);
+
+        // 格式相同但色彩空间不同时不算支持
+        let preferred_linear = surface_format(preferred.format, vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT);
+        assert_eq!(info.choose_format(preferred_linear), rgba_srgb);
+
+        info.formats = vec![unorm, rgba_srgb, bgra_srgb];
+        assert_eq!(info.choose_format(preferred), bgra_srgb);
+
+        info.formats = vec![unorm];
+        assert_eq!(info.choose_format(preferred), unorm);
+    }
+
+    #[test]
+    fn test_choose_image_count_within_limits() {
+        let mut info = surface_info(vec![]);
+        info.min_image_count = 2;
+        info.max_image_count = 0;
+        assert_eq!(info.choose_image_count(), 3);
+
+        info.max_image_count = 8;
+        assert_eq!(info.choose_image_count(), 3);
+
+        info.max_image_count = 2;
+        assert_eq!(info.choose_image_count(), 2);
+    }
+
+    #[test]
+    fn test_choose_pre_transform_prefers_identity() {
+        let mut info = surface_info(vec![]);
+        info.current_transform = vk::SurfaceTransformFlagsKHR::ROTATE_90;
+        info.supported_transforms = vk::SurfaceTransformFlagsKHR::IDENTITY | vk::SurfaceTransformFlagsKHR::ROTATE_90;
+        assert_eq!(info.choose_pre_transform(), vk::SurfaceTransformFlagsKHR::IDENTITY);
+
+        info.supported_transforms = vk::SurfaceTransformFlagsKHR::ROTATE_90;
+        assert_eq!(info.choose_pre_transform(), vk::SurfaceTransformFlagsKHR::ROTATE_90);
+    }
+
+    #[test]
+    fn test_choose_composite_alpha_prefers_opaque() {
+        let mut info = surface_info(vec![]);
+        info.supported_composite_alpha =
+            vk::CompositeAlphaFlagsKHR::OPAQUE | vk::CompositeAlphaFlagsKHR::PRE_MULTIPLIED;
+        assert_eq!(info.choose_composite_alpha(), vk::CompositeAlphaFlagsKHR::OPAQUE);
+
+        info.supported_composite_alpha =
+            vk::CompositeAlphaFlagsKHR::POST_MULTIPLIED | vk::CompositeAlphaFlagsKHR::INHERIT;
+        assert_eq!(info.choose_composite_alpha(), vk::CompositeAlphaFlagsKHR::INHERIT);
+    }
+}
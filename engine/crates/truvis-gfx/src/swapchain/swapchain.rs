@@ -3,39 +3,89 @@ use crate::commands::fence::GfxFence;
 use crate::commands::semaphore::GfxSemaphore;
 use crate::gfx::Gfx;
 use crate::swapchain::surface::GfxSurface;
+use crate::swapchain::surface_info::GfxSurfaceInfo;
 use ash::vk;
 use ash::vk::Handle;
 use itertools::Itertools;
 
+/// acquire 或者 present 之后 swapchain 的状态
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum GfxSwapchainStatus {
+    Optimal,
+    /// 仍然可以使用，但是和 surface 不再完全匹配（例如窗口尺寸发生了变化），应该尽快重建
+    Suboptimal,
+    /// 已经无法使用，必须重建；acquire 返回这个状态时并没有获取到 image
+    OutOfDate,
+}
+impl GfxSwapchainStatus {
+    #[inline]
+    pub fn need_recreate(self) -> bool {
+        self != Self::Optimal
+    }
+}
+
 pub struct GfxSwapchain {
     swapchain_handle: vk::SwapchainKHR,
 
     swapchain_images: Vec<vk::Image>,
     swapchain_image_index: usize,
 
-    format: vk::Format,
+    /// 实际使用的 format，重建时沿用
+    surface_format: vk::SurfaceFormatKHR,
+    /// 实际使用的 present mode
+    present_mode: vk::PresentModeKHR,
+    /// 实际使用的 image usage，取决于 surface 支持的 usage
+    image_usage: vk::ImageUsageFlags,
     swapchain_extent: vk::Extent2D,
 }
 
 // new & init
 impl GfxSwapchain {
+    /// `present_modes` 是按照偏好排列的 present mode，使用其中第一个被 surface 支持的，都不支持时使用 FIFO；
+    /// `surface_format` 是期望值，surface 不支持时会退化为合法的值。
+    /// 实际使用的 format 与 present mode 可以通过 [`Self::image_infos`] 与 [`Self::present_mode`] 获取
     pub fn new(
         surface: &GfxSurface,
-        present_mode: vk::PresentModeKHR,
+        present_modes: &[vk::PresentModeKHR],
         surface_format: vk::SurfaceFormatKHR,
         window_physical_extent: vk::Extent2D,
         old_swapchain: Option<GfxSwapchain>,
     ) -> Self {
+        let swapchain = Self::new_internal(
+            surface,
+            present_modes,
+            surface_format,
+            window_physical_extent,
+            old_swapchain.as_ref().map(|s| s.swapchain_handle),
+        );
+        if let Some(old_swapchain) = old_swapchain {
+            old_swapchain.destroy();
+        }
+        swapchain
+    }
+
+    /// `old_swapchain` 会被填入 oldSwapchain 字段，但是不会被销毁
+    fn new_internal(
+        surface: &GfxSurface,
+        present_modes: &[vk::PresentModeKHR],
+        surface_format: vk::SurfaceFormatKHR,
+        window_physical_extent: vk::Extent2D,
+        old_swapchain: Option<vk::SwapchainKHR>,
+    ) -> Self {
+        let surface_info = Gfx::get().surface_capabilities(surface);
         let surface_capabilities = surface.get_capabilities();
 
         // 确定 window 的 extent 尺寸
         // 如果 surface_capabilities.current_extent 包含特殊值 0xFFFFFFFF，则表示可以自己设置交换链的 extent
         let extent = Self::calculate_swapchain_extent(&surface_capabilities, window_physical_extent);
+        let surface_format = surface_info.choose_format(surface_format);
+        let present_mode = surface_info.choose_present_mode(present_modes);
         log::debug!(
             "create swapchain:
             surface current extent: {}x{}, min extent: {}x{}, max extent: {}x{}
             window physical extent: {}x{}
-            final swapchain extent: {}x{}",
+            final swapchain extent: {}x{}
+            format: {:?}, color space: {:?}, present mode: {:?}",
             surface_capabilities.current_extent.width,
             surface_capabilities.current_extent.height,
             surface_capabilities.min_image_extent.width,
@@ -45,20 +95,22 @@ impl GfxSwapchain {
             window_physical_extent.width,
             window_physical_extent.height,
             extent.width,
-            extent.height
+            extent.height,
+            surface_format.format,
+            surface_format.color_space,
+            present_mode,
         );
 
+        let image_usage = Self::choose_image_usage(&surface_info);
         let swapchain_handle = Self::create_swapchain(
             surface,
-            surface_format.format,
-            surface_format.color_space,
+            &surface_info,
+            surface_format,
             extent,
             present_mode,
-            old_swapchain.as_ref().map(|s| s.swapchain_handle),
+            image_usage,
+            old_swapchain,
         );
-        if let Some(old_swapchain) = old_swapchain {
-            old_swapchain.destroy();
-        }
 
         let images = unsafe { Gfx::get().gfx_device().swapchain.get_swapchain_images(swapchain_handle).unwrap() };
 
@@ -67,39 +119,45 @@ impl GfxSwapchain {
             swapchain_images: images,
             swapchain_image_index: 0,
             swapchain_extent: extent,
-            format: surface_format.format,
+            surface_format,
+            present_mode,
+            image_usage,
         }
     }
 
+    /// - TRANSFER_DST 用于 Nsight 分析
+    /// - TRANSFER_SRC 用于截图，参见 [`Self::support_read_back`]
+    ///
+    /// 两者都仅在 surface 支持时开启
+    fn choose_image_usage(surface_info: &GfxSurfaceInfo) -> vk::ImageUsageFlags {
+        let mut image_usage = vk::ImageUsageFlags::COLOR_ATTACHMENT;
+        for optional_usage in [vk::ImageUsageFlags::TRANSFER_DST, vk::ImageUsageFlags::TRANSFER_SRC] {
+            if surface_info.supported_usage_flags.contains(optional_usage) {
+                image_usage |= optional_usage;
+            }
+        }
+        image_usage
+    }
+
     fn create_swapchain(
         surface: &GfxSurface,
-        format: vk::Format,
-        color_space: vk::ColorSpaceKHR,
+        surface_info: &GfxSurfaceInfo,
+        surface_format: vk::SurfaceFormatKHR,
         extent: vk::Extent2D,
         present_mode: vk::PresentModeKHR,
+        image_usage: vk::ImageUsageFlags,
         old_swapchain: Option<vk::SwapchainKHR>,
     ) -> vk::SwapchainKHR {
-        // 确定 image count
-        // max_image_count == 0，表示不限制 image 数量
-        let surface_capabilities = surface.get_capabilities();
-
-        let image_count = if surface_capabilities.max_image_count == 0 {
-            surface_capabilities.min_image_count + 1
-        } else {
-            u32::min(surface_capabilities.max_image_count, surface_capabilities.min_image_count + 1)
-        };
-
         let create_info = vk::SwapchainCreateInfoKHR::default()
             .surface(surface.handle)
-            .min_image_count(image_count)
-            .image_format(format)
-            .image_color_space(color_space)
+            .min_image_count(surface_info.choose_image_count())
+            .image_format(surface_format.format)
+            .image_color_space(surface_format.color_space)
             .image_extent(extent)
             .image_array_layers(1)
-            // TRANSFER_DST 用于 Nsight 分析
-            .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_DST)
-            .pre_transform(surface_capabilities.current_transform)
-            .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
+            .image_usage(image_usage)
+            .pre_transform(surface_info.choose_pre_transform())
+            .composite_alpha(surface_info.choose_composite_alpha())
             .present_mode(present_mode)
             .image_sharing_mode(vk::SharingMode::EXCLUSIVE)
             .old_swapchain(old_swapchain.unwrap_or_default())
@@ -133,6 +191,18 @@ impl GfxSwapchain {
         self.swapchain_extent
     }
 
+    /// 实际使用的 present mode
+    #[inline]
+    pub fn present_mode(&self) -> vk::PresentModeKHR {
+        self.present_mode
+    }
+
+    /// swapchain image 是否带有 `TRANSFER_SRC` usage，可以被读回到 CPU
+    #[inline]
+    pub fn support_read_back(&self) -> bool {
+        self.image_usage.contains(vk::ImageUsageFlags::TRANSFER_SRC)
+    }
+
     #[inline]
     pub fn current_image_index(&self) -> usize {
         self.swapchain_image_index
@@ -143,7 +213,7 @@ impl GfxSwapchain {
         GfxSwapchainImageInfo {
             image_extent: self.swapchain_extent,
             image_cnt: self.swapchain_images.len(),
-            image_format: self.format,
+            image_format: self.surface_format.format,
         }
     }
 }
@@ -174,15 +244,29 @@ impl GfxSwapchain {
 
 // update
 impl GfxSwapchain {
+    /// 按照新的尺寸重建 swapchain，format 保持不变，present mode 的选择方式与 [`Self::new`] 相同
+    ///
+    /// 会等待 device idle，旧的 swapchain 作为 oldSwapchain 传入以便平滑过渡，之后被销毁。
+    /// 之前获取的 swapchain image 全部失效，依赖 swapchain image 或者 extent 的资源需要调用者重建
+    pub fn recreate(&mut self, surface: &GfxSurface, present_modes: &[vk::PresentModeKHR], new_extent: vk::Extent2D) {
+        Gfx::get().wait_idel();
+
+        let new_swapchain =
+            Self::new_internal(surface, present_modes, self.surface_format, new_extent, Some(self.swapchain_handle));
+        let old_swapchain = std::mem::replace(self, new_swapchain);
+        old_swapchain.destroy();
+    }
+
     /// timeout: nano seconds
-    /// return: need recreate
+    ///
+    /// 返回 [`GfxSwapchainStatus::OutOfDate`] 时没有获取到 image，需要重建之后再次 acquire
     #[inline]
     pub fn acquire_next_image(
         &mut self,
         semaphore: Option<&GfxSemaphore>,
         fence: Option<&GfxFence>,
         timeout: u64,
-    ) -> bool {
+    ) -> GfxSwapchainStatus {
         let result = unsafe {
             Gfx::get().gfx_device().swapchain.acquire_next_image(
                 self.swapchain_handle,
@@ -198,11 +282,11 @@ impl GfxSwapchain {
                     log::warn!("swapchain acquire image index {} is not optimal", image_index);
                 }
                 self.swapchain_image_index = image_index as usize;
-                is_suboptimal
+                if is_suboptimal { GfxSwapchainStatus::Suboptimal } else { GfxSwapchainStatus::Optimal }
             }
             Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
                 log::warn!("swapchain is out of date when acquire next image");
-                true
+                GfxSwapchainStatus::OutOfDate
             }
             Err(e) => {
                 panic!("failed to acquire next swapchain image: {:?}", e);
@@ -210,9 +294,8 @@ impl GfxSwapchain {
         }
     }
 
-    /// return: need recreate
     #[inline]
-    pub fn present_image(&self, queue: &GfxCommandQueue, wait_semaphores: &[GfxSemaphore]) -> bool {
+    pub fn present_image(&self, queue: &GfxCommandQueue, wait_semaphores: &[GfxSemaphore]) -> GfxSwapchainStatus {
         let wait_semaphores = wait_semaphores.iter().map(|s| s.handle()).collect_vec();
         let image_indices = [self.swapchain_image_index as u32];
         let present_info = vk::PresentInfoKHR::default()
@@ -226,11 +309,11 @@ impl GfxSwapchain {
                 if is_suboptimal {
                     log::warn!("swapchain present image index {} is not optimal", self.swapchain_image_index);
                 }
-                is_suboptimal
+                if is_suboptimal { GfxSwapchainStatus::Suboptimal } else { GfxSwapchainStatus::Optimal }
             }
             Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
                 log::warn!("swapchain is out of date when present image");
-                true
+                GfxSwapchainStatus::OutOfDate
             }
             Err(e) => {
                 panic!("failed to present swapchain image: {:?}", e);
@@ -254,3 +337,26 @@ impl Drop for GfxSwapchain {
         assert!(self.swapchain_handle.is_null());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_choose_image_usage_only_enables_supported() {
+        let mut surface_info = GfxSurfaceInfo::new(&vk::SurfaceCapabilitiesKHR::default(), vec![], vec![]);
+        surface_info.supported_usage_flags = vk::ImageUsageFlags::COLOR_ATTACHMENT;
+        assert_eq!(GfxSwapchain::choose_image_usage(&surface_info), vk::ImageUsageFlags::COLOR_ATTACHMENT);
+
+        surface_info.supported_usage_flags = vk::ImageUsageFlags::COLOR_ATTACHMENT
+            | vk::ImageUsageFlags::TRANSFER_SRC
+            | vk::ImageUsageFlags::TRANSFER_DST
+            | vk::ImageUsageFlags::STORAGE;
+        assert_eq!(
+            GfxSwapchain::choose_image_usage(&surface_info),
+            vk::ImageUsageFlags::COLOR_ATTACHMENT
+                | vk::ImageUsageFlags::TRANSFER_SRC
+                | vk::ImageUsageFlags::TRANSFER_DST
+        );
+    }
+}
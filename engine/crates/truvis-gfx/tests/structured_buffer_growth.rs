@@ -0,0 +1,84 @@
+//! 通过真实的 Vulkan 对象检查 GfxStructuredBuffer 的自动扩容
+//!
+//! 需要 GPU，默认不执行：
+//! ```text
+//! cargo test -p truvis-gfx --test structured_buffer_growth -- --ignored
+//! ```
+
+use ash::vk;
+use truvis_gfx::gfx::Gfx;
+use truvis_gfx::resources::special_buffers::structured_buffer::GfxStructuredBuffer;
+
+fn growable_buffer(capacity: usize, mapped: bool) -> GfxStructuredBuffer<u32> {
+    GfxStructuredBuffer::new(
+        "structured-buffer-growth-test",
+        capacity,
+        vk::BufferUsageFlags::STORAGE_BUFFER
+            | vk::BufferUsageFlags::TRANSFER_SRC
+            | vk::BufferUsageFlags::TRANSFER_DST
+            | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+        mapped,
+    )
+}
+
+fn read_mapped(buffer: &mut GfxStructuredBuffer<u32>, cnt: usize) -> Vec<u32> {
+    buffer.invalidate(0, (cnt * size_of::<u32>()) as vk::DeviceSize);
+    buffer.mapped_slice()[..cnt].to_vec()
+}
+
+#[test]
+#[ignore = "requires a GPU"]
+fn push_and_extend_grow_and_keep_elements() {
+    // 没有 surface 也需要开启该扩展，否则 device 上的 swapchain 扩展不合法
+    Gfx::init("structured-buffer-growth-test".to_string(), vec![ash::khr::surface::NAME], None);
+    {
+        let mut buffer = growable_buffer(2, true);
+        let old_address = buffer.device_address();
+
+        // 容量足够时不扩容
+        assert_eq!(buffer.push(1), old_address);
+        assert_eq!(buffer.push(2), old_address);
+        assert!(!buffer.device_address_changed());
+
+        // 扩容之后返回新的 device address，已有元素被拷贝到新的 buffer
+        let new_address = buffer.push(3);
+        assert_ne!(new_address, old_address);
+        assert_eq!(new_address, buffer.device_address());
+        assert!(buffer.device_address_changed());
+        assert_eq!(buffer.capacity(), 3);
+        assert_eq!(buffer.len(), 3);
+
+        buffer.clear_device_address_changed();
+        buffer.extend(&[4, 5, 6, 7, 8]);
+        assert!(buffer.device_address_changed());
+        assert_eq!(buffer.capacity(), 8);
+        assert_eq!(read_mapped(&mut buffer, 8), (1..=8).collect::<Vec<_>>());
+
+        // 清空之后容量不变，再次追加不会扩容
+        buffer.clear_device_address_changed();
+        buffer.clear_elements();
+        buffer.extend(&[9, 10]);
+        assert!(!buffer.device_address_changed());
+        assert_eq!(buffer.capacity(), 8);
+        assert_eq!(read_mapped(&mut buffer, 2), vec![9, 10]);
+    }
+    {
+        // 不通过 push 写入的数据（len 为 0）在扩容时同样保留
+        let mut buffer = growable_buffer(4, true);
+        buffer.mapped_slice().copy_from_slice(&[11, 12, 13, 14]);
+        buffer.flush(0, (4 * size_of::<u32>()) as vk::DeviceSize);
+        assert!(buffer.reserve(6));
+        assert_eq!(buffer.capacity(), 6);
+        assert_eq!(read_mapped(&mut buffer, 4), vec![11, 12, 13, 14]);
+    }
+    {
+        // 不是 mapped 的 buffer 通过 stage buffer 上传
+        let mut buffer = growable_buffer(1, false);
+        buffer.extend(&[1, 2, 3]);
+        assert_eq!(buffer.len(), 3);
+        assert_eq!(buffer.capacity(), 3);
+        assert!(buffer.device_address_changed());
+    }
+    Gfx::get().wait_idel();
+    Gfx::destroy();
+}
@@ -3,7 +3,7 @@
 use crate::gui_mesh::GuiMesh;
 use ash::vk;
 use imgui::{DrawData, FontAtlasTexture, TextureId};
-use std::collections::HashMap;
+use slotmap::{Key, KeyData};
 use truvis_gfx::resources::image_view::GfxImageViewDesc;
 use truvis_gfx::{basic::color::LabelColor, gfx::Gfx, resources::image::GfxImage};
 use truvis_render_interface::bindless_manager::BindlessManager;
@@ -12,18 +12,23 @@ use truvis_render_interface::gfx_resource_manager::GfxResourceManager;
 use truvis_render_interface::handles::GfxImageViewHandle;
 use truvis_render_interface::pipeline_settings::FrameLabel;
 
-// TODO 这个东西和 GuiHost 的重复了
-const FONT_TEXTURE_ID: usize = 0;
-const RENDER_IMAGE_ID: usize = 1;
-
+/// imgui 渲染后端
+///
+/// imgui 的 `TextureId` 直接编码 [`GfxImageViewHandle`]，绘制时通过 [`BindlessManager`]
+/// 查询对应的 bindless srv index。因此任意注册过 srv 的纹理都可以直接用于 `imgui::Image`：
+///
+/// ```ignore
+/// let tex_id = GuiBackend::texture_id(view_handle);
+/// imgui::Image::new(tex_id, [128.0, 128.0]).build(ui);
+/// ```
+///
+/// 不直接存放 bindless index 的原因：image view 注销后其 bindless index 会被回收复用，
+/// 存放 view handle 可以在查询时发现纹理已经失效，而不是错误地采样到复用该 index 的其他纹理。
 pub struct GuiBackend {
     /// 存放多帧 imgui 的 mesh 数据
-    pub gui_meshes: [GuiMesh; FrameCounter::fif_count()],
+    pub gui_meshes: Vec<GuiMesh>,
 
     fonts_image_view_handle: Option<GfxImageViewHandle>,
-    font_tex_id: TextureId,
-
-    pub tex_map: HashMap<TextureId, GfxImageViewHandle>,
 }
 impl Default for GuiBackend {
     fn default() -> Self {
@@ -34,24 +39,24 @@ impl Default for GuiBackend {
 // new & init
 impl GuiBackend {
     pub fn new() -> Self {
-        let gui_meshes = FrameCounter::frame_labes().map(GuiMesh::new);
+        let gui_meshes = FrameCounter::frame_labes().map(GuiMesh::new).collect::<Vec<_>>();
 
         Self {
             gui_meshes,
             fonts_image_view_handle: None,
-            font_tex_id: TextureId::new(0),
-
-            tex_map: Default::default(),
         }
     }
 
+    /// 将字体 atlas 注册为 bindless srv
+    ///
+    /// # 返回
+    /// 字体 atlas 对应的 `TextureId`，需要设置给 imgui 的 `fonts().tex_id`
     pub fn register_font(
         &mut self,
         bindless_manager: &mut BindlessManager,
         gfx_resource_manager: &mut GfxResourceManager,
         font_atlas: FontAtlasTexture,
-        font_tex_id: TextureId,
-    ) {
+    ) -> TextureId {
         let fonts_image = GfxImage::from_rgba8(font_atlas.width, font_atlas.height, font_atlas.data, "imgui-fonts");
         let fonts_image_handle = gfx_resource_manager.register_image(fonts_image);
         let fonts_image_view_handle = gfx_resource_manager.get_or_create_image_view(
@@ -62,10 +67,26 @@ impl GuiBackend {
         bindless_manager.register_srv(fonts_image_view_handle);
 
         self.fonts_image_view_handle = Some(fonts_image_view_handle);
-        self.font_tex_id = font_tex_id;
+        Self::texture_id(fonts_image_view_handle)
     }
 }
 // tools
+impl GuiBackend {
+    /// 将 image view 编码为 imgui 的 `TextureId`
+    ///
+    /// 对应的 image view 需要已经通过 [`BindlessManager::register_srv`] 注册
+    #[inline]
+    pub fn texture_id(image_view_handle: GfxImageViewHandle) -> TextureId {
+        TextureId::new(image_view_handle.data().as_ffi() as usize)
+    }
+
+    /// 从 imgui 的 `TextureId` 中解码出 image view
+    #[inline]
+    pub fn image_view_handle(texture_id: TextureId) -> GfxImageViewHandle {
+        GfxImageViewHandle::from(KeyData::from_ffi(texture_id.id() as u64))
+    }
+}
+// update
 impl GuiBackend {
     // TODO 这个函数设计的非常别扭
     /// # Phase: Render
@@ -79,10 +100,5 @@ impl GuiBackend {
             self.gui_meshes[*frame_label].fill_index_buffer(draw_data);
         }
         Gfx::get().gfx_queue().end_label();
-
-        self.tex_map = HashMap::from([(
-            imgui::TextureId::new(FONT_TEXTURE_ID) as imgui::TextureId,
-            self.fonts_image_view_handle.unwrap(),
-        )]);
     }
 }
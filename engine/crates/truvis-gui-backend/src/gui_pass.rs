@@ -1,9 +1,8 @@
+use crate::gui_backend::GuiBackend;
 use crate::gui_mesh::GuiMesh;
 use crate::gui_vertex_layout::ImGuiVertexLayoutAoS;
 use ash::vk;
-use imgui::TextureId;
 use itertools::Itertools;
-use std::collections::HashMap;
 use std::rc::Rc;
 use truvis_crate_tools::resource::TruvisPath;
 use truvis_gfx::basic::bytes::BytesConvert;
@@ -17,8 +16,8 @@ use truvis_gfx::{
 };
 use truvis_render_graph::render_context::RenderContext;
 use truvis_render_graph::render_graph::{RgImageHandle, RgImageState, RgPass, RgPassBuilder, RgPassContext};
+use truvis_render_interface::bindless_manager::BindlessSrvHandle;
 use truvis_render_interface::global_descriptor_sets::GlobalDescriptorSets;
-use truvis_render_interface::handles::GfxImageViewHandle;
 use truvis_shader_binding::truvisl;
 use truvis_shader_binding::truvisl::SrvHandle;
 use truvis_utils::count_indexed_array;
@@ -100,7 +99,6 @@ impl GuiPass {
         cmd: &GfxCommandBuffer,
         gui_mesh: &GuiMesh,
         draw_data: &imgui::DrawData,
-        tex_map: &HashMap<TextureId, GfxImageViewHandle>,
     ) {
         // 使用 LOAD 保留 resolve pass 绘制的内容
         let color_attach_info = vk::RenderingAttachmentInfo::default()
@@ -207,9 +205,14 @@ impl GuiPass {
                         // 加载 texture，如果和上一个 command 使用的 texture
                         // 不是同一个，则需要重新加载
                         if Some(texture_id) != last_texture_id {
-                            let texture_image_view_handle = tex_map.get(&texture_id).unwrap();
-                            let srv_bindless_handle =
-                                bindless_manager.get_shader_srv_handle(*texture_image_view_handle);
+                            // texture id 中编码的是 image view handle，在这里转换为 bindless index
+                            let texture_image_view_handle = GuiBackend::image_view_handle(texture_id);
+                            let srv_bindless_handle = bindless_manager
+                                .try_get_shader_srv_handle(texture_image_view_handle)
+                                .unwrap_or_else(|| {
+                                    log::warn!("imgui texture {:?} is not registered as bindless srv", texture_id);
+                                    BindlessSrvHandle::null()
+                                });
 
                             push_constant.texture = srv_bindless_handle.0;
 
@@ -254,7 +257,6 @@ pub struct GuiRgPass<'a> {
 
     pub ui_draw_data: &'a imgui::DrawData,
     pub gui_mesh: &'a GuiMesh,
-    pub tex_map: &'a HashMap<imgui::TextureId, GfxImageViewHandle>,
 
     pub canvas_color: RgImageHandle,
     pub canvas_extent: vk::Extent2D,
@@ -283,7 +285,6 @@ impl RgPass for GuiRgPass<'_> {
             cmd,
             self.gui_mesh,
             self.ui_draw_data,
-            self.tex_map,
         );
     }
 }
@@ -1,66 +1,67 @@
 use std::ffi::CStr;
 
+use crate::pipeline_warmup::ComputePipelineDesc;
 use crate::render_context::RenderContext;
 use ash::vk;
 use truvis_gfx::basic::bytes::BytesConvert;
-use truvis_gfx::{commands::command_buffer::GfxCommandBuffer, gfx::Gfx, pipelines::shader::GfxShaderModule};
+use truvis_gfx::commands::command_buffer::GfxCommandBuffer;
+use truvis_gfx::pipelines::compute_pipeline::{GfxComputePipeline, GfxComputePipelineCreateInfo};
 use truvis_render_interface::global_descriptor_sets::GlobalDescriptorSets;
 
 /// 泛型参数 P 表示 compute shader 的参数，以 push constant 的形式传入 shader
+///
+/// 依赖的 spv 发生变化时 pipeline 会被自动重建，参见 [`truvis_gfx::pipelines::hot_reload::GfxPipelineReloader`]
 pub struct ComputePass<P: Sized> {
-    pipeline: vk::Pipeline,
-    pipeline_layout: vk::PipelineLayout,
+    pipeline: GfxComputePipeline,
 
     _phantom: std::marker::PhantomData<P>,
 }
+// new & init
 impl<P: Sized> ComputePass<P> {
     pub fn new(global_descriptor_sets: &GlobalDescriptorSets, entry_point: &CStr, shader_path: &str) -> Self {
-        let shader_module = GfxShaderModule::new(std::path::Path::new(shader_path));
-        let stage_info = vk::PipelineShaderStageCreateInfo::default()
-            .module(shader_module.handle())
-            .stage(vk::ShaderStageFlags::COMPUTE)
-            .name(entry_point);
+        let push_constant_range = vk::PushConstantRange::default()
+            .stage_flags(vk::ShaderStageFlags::COMPUTE)
+            .offset(0)
+            .size(size_of::<P>() as u32);
 
-        let pipeline_layout = {
-            let push_constant_range = vk::PushConstantRange::default()
-                .stage_flags(vk::ShaderStageFlags::COMPUTE)
-                .offset(0)
-                .size(size_of::<P>() as u32);
-
-            let descriptor_set_layouts = global_descriptor_sets.global_set_layouts();
-            let pipeline_layout_ci = vk::PipelineLayoutCreateInfo::default()
-                .set_layouts(&descriptor_set_layouts)
-                .push_constant_ranges(std::slice::from_ref(&push_constant_range));
-
-            unsafe { Gfx::get().gfx_device().create_pipeline_layout(&pipeline_layout_ci, None).unwrap() }
-        };
-
-        let pipeline_ci = vk::ComputePipelineCreateInfo::default().stage(stage_info).layout(pipeline_layout);
-        let pipeline = unsafe {
-            Gfx::get()
-                .gfx_device()
-                .create_compute_pipelines(vk::PipelineCache::null(), std::slice::from_ref(&pipeline_ci), None)
-                .unwrap()[0]
-        };
-
-        shader_module.destroy();
+        let mut create_info = GfxComputePipelineCreateInfo::new(shader_path, entry_point);
+        create_info
+            .descriptor_set_layouts(global_descriptor_sets.global_set_layouts())
+            .push_constant_ranges(vec![push_constant_range]);
 
         Self {
-            pipeline,
-            pipeline_layout,
+            pipeline: GfxComputePipeline::new(&create_info, shader_path),
 
             _phantom: std::marker::PhantomData,
         }
     }
 
+    /// 使用与预热相同的描述创建，保证预热的 pipeline 与实际使用的一致，参见 [`crate::pipeline_warmup::PipelineWarmup`]
+    pub fn from_desc(global_descriptor_sets: &GlobalDescriptorSets, desc: &ComputePipelineDesc) -> Self {
+        assert_eq!(
+            desc.push_constant_size,
+            size_of::<P>() as u32,
+            "push constant of {} does not match the pass",
+            desc.shader_path
+        );
+        Self::new(global_descriptor_sets, desc.entry_point, &desc.shader_path)
+    }
+}
+// tools
+impl<P: Sized> ComputePass<P> {
     pub fn exec(&self, cmd: &GfxCommandBuffer, render_context: &RenderContext, params: &P, group_cnt: glam::UVec3) {
         let frame_label = render_context.frame_counter.frame_label();
-        cmd.cmd_bind_pipeline(vk::PipelineBindPoint::COMPUTE, self.pipeline);
+        cmd.cmd_bind_pipeline(vk::PipelineBindPoint::COMPUTE, self.pipeline.handle());
 
-        cmd.cmd_push_constants(self.pipeline_layout, vk::ShaderStageFlags::COMPUTE, 0, BytesConvert::bytes_of(params));
+        cmd.cmd_push_constants(
+            self.pipeline.layout(),
+            vk::ShaderStageFlags::COMPUTE,
+            0,
+            BytesConvert::bytes_of(params),
+        );
         cmd.bind_descriptor_sets(
             vk::PipelineBindPoint::COMPUTE,
-            self.pipeline_layout,
+            self.pipeline.layout(),
             0,
             &render_context.global_descriptor_sets.global_sets(frame_label),
             None,
@@ -74,12 +75,3 @@ impl<P: Sized> ComputePass<P> {
         // drop
     }
 }
-impl<P: Sized> Drop for ComputePass<P> {
-    fn drop(&mut self) {
-        let gfx_device = Gfx::get().gfx_device();
-        unsafe {
-            gfx_device.destroy_pipeline(self.pipeline, None);
-            gfx_device.destroy_pipeline_layout(self.pipeline_layout, None);
-        }
-    }
-}
@@ -0,0 +1,166 @@
+//! 帧边界钩子
+//!
+//! 需要在每帧开始或结束时做事的子系统（延迟销毁、资源上传、query 轮转、临时资源池重置等）
+//! 实现 [`FrameSubsystem`]，通过 [`RenderContext::register_frame_subsystem`] 注册，
+//! 由 `Renderer` 在帧边界统一调用，新增帧级子系统不需要修改核心循环。
+//!
+//! # 调用时机
+//! - `on_frame_begin`：已经等待当前 fif 上一次的渲染完成，当前帧的命令尚未录制
+//! - `on_frame_end`：当前帧的命令已经提交，帧计数尚未推进
+//!
+//! # 调用顺序
+//! 按注册时指定的 order 从小到大调用，order 相同时按注册的先后调用；
+//! 帧开始和帧结束使用相同的顺序。内置子系统的 order 参见 [`FrameHookOrder`]。
+
+use crate::render_context::RenderContext;
+
+/// 挂载在帧边界上的子系统
+pub trait FrameSubsystem {
+    /// 子系统的名字，用于调试输出
+    fn name(&self) -> &str;
+
+    /// 帧开始时调用
+    fn on_frame_begin(&mut self, _render_context: &mut RenderContext) {}
+
+    /// 帧结束时调用
+    fn on_frame_end(&mut self, _render_context: &mut RenderContext) {}
+}
+
+/// 内置子系统的 order，自定义子系统可以据此插入到它们之前或之后
+pub struct FrameHookOrder;
+impl FrameHookOrder {
+    /// 销毁 GPU 已经不再使用的资源
+    pub const RESOURCE_CLEANUP: i32 = -200;
+    /// 上传异步加载完成的资源，并注册到 bindless
+    pub const ASSET_UPLOAD: i32 = -100;
+    /// 没有特殊顺序要求的子系统
+    pub const DEFAULT: i32 = 0;
+}
+
+struct RegisteredSubsystem {
+    order: i32,
+    subsystem: Box<dyn FrameSubsystem>,
+}
+
+/// 已注册的帧级子系统，按调用顺序存放
+#[derive(Default)]
+pub struct FrameHooks {
+    subsystems: Vec<RegisteredSubsystem>,
+}
+// tools
+impl FrameHooks {
+    /// 注册子系统，插入到 order 不大于它的所有子系统之后
+    pub fn register(&mut self, order: i32, subsystem: Box<dyn FrameSubsystem>) {
+        let pos = self.subsystems.partition_point(|registered| registered.order <= order);
+        self.subsystems.insert(pos, RegisteredSubsystem { order, subsystem });
+    }
+
+    /// 按调用顺序返回所有子系统的名字
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.subsystems.iter().map(|registered| registered.subsystem.name())
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.subsystems.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.subsystems.is_empty()
+    }
+
+    /// 合并另一组子系统，保持 order 的约定
+    fn merge(&mut self, other: FrameHooks) {
+        for registered in other.subsystems {
+            self.register(registered.order, registered.subsystem);
+        }
+    }
+}
+// destroy
+impl FrameHooks {
+    /// 释放所有子系统，需要在 Gfx 销毁之前调用，子系统持有的 GPU 资源会在 drop 时释放
+    pub fn clear(&mut self) {
+        self.subsystems.clear();
+    }
+}
+
+// frame hooks
+impl RenderContext {
+    /// 注册一个帧级子系统，参见 [`FrameHookOrder`]
+    pub fn register_frame_subsystem(&mut self, order: i32, subsystem: impl FrameSubsystem + 'static) {
+        self.frame_hooks.register(order, Box::new(subsystem));
+    }
+
+    /// 依次调用所有子系统的 `on_frame_begin`
+    pub fn dispatch_frame_begin(&mut self) {
+        let _span = tracy_client::span!("RenderContext::dispatch_frame_begin");
+        self.dispatch(|subsystem, render_context| subsystem.on_frame_begin(render_context));
+    }
+
+    /// 依次调用所有子系统的 `on_frame_end`
+    pub fn dispatch_frame_end(&mut self) {
+        let _span = tracy_client::span!("RenderContext::dispatch_frame_end");
+        self.dispatch(|subsystem, render_context| subsystem.on_frame_end(render_context));
+    }
+
+    /// 子系统需要可变地访问 RenderContext，因此调用期间先将它们取出；
+    /// 调用期间新注册的子系统会在之后合并进来，从下一次调用开始生效
+    fn dispatch(&mut self, mut f: impl FnMut(&mut dyn FrameSubsystem, &mut RenderContext)) {
+        let mut hooks = std::mem::take(&mut self.frame_hooks);
+        for registered in &mut hooks.subsystems {
+            f(registered.subsystem.as_mut(), self);
+        }
+
+        let registered_during_dispatch = std::mem::replace(&mut self.frame_hooks, hooks);
+        self.frame_hooks.merge(registered_during_dispatch);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Named(&'static str);
+    impl FrameSubsystem for Named {
+        fn name(&self) -> &str {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_register_sorted_by_order() {
+        let mut hooks = FrameHooks::default();
+        hooks.register(FrameHookOrder::DEFAULT, Box::new(Named("default")));
+        hooks.register(FrameHookOrder::RESOURCE_CLEANUP, Box::new(Named("cleanup")));
+        hooks.register(10, Box::new(Named("late")));
+        hooks.register(FrameHookOrder::ASSET_UPLOAD, Box::new(Named("upload")));
+
+        assert_eq!(hooks.names().collect::<Vec<_>>(), ["cleanup", "upload", "default", "late"]);
+    }
+
+    #[test]
+    fn test_same_order_keeps_registration_order() {
+        let mut hooks = FrameHooks::default();
+        hooks.register(FrameHookOrder::DEFAULT, Box::new(Named("a")));
+        hooks.register(FrameHookOrder::DEFAULT, Box::new(Named("b")));
+        hooks.register(FrameHookOrder::DEFAULT, Box::new(Named("c")));
+
+        assert_eq!(hooks.names().collect::<Vec<_>>(), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_merge_keeps_order() {
+        let mut hooks = FrameHooks::default();
+        hooks.register(FrameHookOrder::RESOURCE_CLEANUP, Box::new(Named("cleanup")));
+        hooks.register(FrameHookOrder::DEFAULT, Box::new(Named("default")));
+
+        let mut added = FrameHooks::default();
+        added.register(FrameHookOrder::ASSET_UPLOAD, Box::new(Named("upload")));
+        added.register(FrameHookOrder::DEFAULT, Box::new(Named("added")));
+        hooks.merge(added);
+
+        assert_eq!(hooks.names().collect::<Vec<_>>(), ["cleanup", "upload", "default", "added"]);
+        assert_eq!(hooks.len(), 4);
+    }
+}
@@ -1,4 +1,6 @@
 pub mod compute_pass;
+pub mod frame_hooks;
+pub mod pipeline_warmup;
 pub mod render_context;
 pub mod render_graph;
 pub mod resources;
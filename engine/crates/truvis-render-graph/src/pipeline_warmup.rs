@@ -0,0 +1,138 @@
+use std::ffi::CStr;
+
+use ash::vk;
+use itertools::Itertools;
+use truvis_gfx::gfx::Gfx;
+use truvis_gfx::pipelines::pipeline_cache::GfxPipelineCache;
+use truvis_gfx::pipelines::shader::GfxShaderModule;
+use truvis_render_interface::global_descriptor_sets::GlobalDescriptorSets;
+
+/// 描述一个需要预热的 compute pipeline variant
+///
+/// 与 [`crate::compute_pass::ComputePass::new`] 的参数一一对应，
+/// 只有 layout 和 shader 完全一致时，预热结果才能被后续的创建命中
+pub struct ComputePipelineDesc {
+    pub entry_point: &'static CStr,
+    pub shader_path: String,
+    pub push_constant_size: u32,
+}
+
+impl ComputePipelineDesc {
+    /// 泛型参数 P 与 `ComputePass<P>` 相同，即 shader 的 push constant
+    pub fn new<P: Sized>(entry_point: &'static CStr, shader_path: impl Into<String>) -> Self {
+        Self {
+            entry_point,
+            shader_path: shader_path.into(),
+            push_constant_size: size_of::<P>() as u32,
+        }
+    }
+}
+
+/// 启动时的 pipeline 预热
+///
+/// 在多个线程中并行创建所有已知的 pipeline variant，编译结果写入全局共享的 pipeline cache，
+/// 之后各个 pass 串行创建 pipeline 时可以直接命中 cache，避免启动慢以及运行时首次创建的卡顿。
+///
+/// 设置环境变量 `TRUVIS_PIPELINE_WARMUP_COMPARE=1` 时，会额外使用两个独立的空 cache
+/// 分别串行、并行地创建一遍，并输出两者的耗时对比。
+pub struct PipelineWarmup;
+
+impl PipelineWarmup {
+    pub fn warmup_compute(global_descriptor_sets: &GlobalDescriptorSets, descs: &[ComputePipelineDesc]) {
+        let _span = tracy_client::span!("PipelineWarmup::warmup_compute");
+        if descs.is_empty() {
+            return;
+        }
+
+        if std::env::var("TRUVIS_PIPELINE_WARMUP_COMPARE").is_ok_and(|v| v == "1") {
+            Self::compare_compute(global_descriptor_sets, descs);
+        }
+
+        let pipeline_cache = Gfx::get().pipeline_cache();
+        let time_ms = Self::create_compute_pipelines(global_descriptor_sets, descs, pipeline_cache, true);
+        log::info!(
+            "pipeline warmup: {} compute pipelines in {:.2} ms, pipeline cache size: {} bytes",
+            descs.len(),
+            time_ms,
+            pipeline_cache.data_size()
+        );
+    }
+
+    /// 使用空的 cache 分别串行、并行创建，输出耗时对比
+    fn compare_compute(global_descriptor_sets: &GlobalDescriptorSets, descs: &[ComputePipelineDesc]) {
+        let serial_cache = GfxPipelineCache::new("warmup-compare-serial");
+        let serial_ms = Self::create_compute_pipelines(global_descriptor_sets, descs, &serial_cache, false);
+        serial_cache.destroy();
+
+        let parallel_cache = GfxPipelineCache::new("warmup-compare-parallel");
+        let parallel_ms = Self::create_compute_pipelines(global_descriptor_sets, descs, &parallel_cache, true);
+        parallel_cache.destroy();
+
+        log::info!(
+            "pipeline warmup compare: {} compute pipelines, serial {:.2} ms, parallel {:.2} ms ({:.2}x)",
+            descs.len(),
+            serial_ms,
+            parallel_ms,
+            serial_ms / parallel_ms.max(f32::EPSILON)
+        );
+    }
+
+    /// 创建所有 pipeline 并立即销毁，只保留 cache 中的编译结果
+    ///
+    /// # return
+    /// 创建 pipeline 的耗时（毫秒），不包括 shader module 的加载
+    fn create_compute_pipelines(
+        global_descriptor_sets: &GlobalDescriptorSets,
+        descs: &[ComputePipelineDesc],
+        pipeline_cache: &GfxPipelineCache,
+        parallel: bool,
+    ) -> f32 {
+        let gfx_device = Gfx::get().gfx_device();
+        let descriptor_set_layouts = global_descriptor_sets.global_set_layouts();
+
+        let shader_modules =
+            descs.iter().map(|desc| GfxShaderModule::new(std::path::Path::new(&desc.shader_path))).collect_vec();
+        let pipeline_layouts = descs
+            .iter()
+            .map(|desc| {
+                let push_constant_range = vk::PushConstantRange::default()
+                    .stage_flags(vk::ShaderStageFlags::COMPUTE)
+                    .offset(0)
+                    .size(desc.push_constant_size);
+                let pipeline_layout_ci = vk::PipelineLayoutCreateInfo::default()
+                    .set_layouts(&descriptor_set_layouts)
+                    .push_constant_ranges(std::slice::from_ref(&push_constant_range));
+
+                unsafe { gfx_device.create_pipeline_layout(&pipeline_layout_ci, None).unwrap() }
+            })
+            .collect_vec();
+
+        let pipeline_cis = descs
+            .iter()
+            .zip_eq(shader_modules.iter().zip_eq(pipeline_layouts.iter()))
+            .map(|(desc, (shader_module, pipeline_layout))| {
+                let stage_info = vk::PipelineShaderStageCreateInfo::default()
+                    .module(shader_module.handle())
+                    .stage(vk::ShaderStageFlags::COMPUTE)
+                    .name(desc.entry_point);
+                vk::ComputePipelineCreateInfo::default().stage(stage_info).layout(*pipeline_layout)
+            })
+            .collect_vec();
+
+        let begin = std::time::Instant::now();
+        let pipelines = pipeline_cache.create_compute_pipelines(&pipeline_cis, parallel);
+        let time_ms = begin.elapsed().as_secs_f32() * 1000.0;
+
+        unsafe {
+            for pipeline in pipelines {
+                gfx_device.destroy_pipeline(pipeline, None);
+            }
+            for pipeline_layout in pipeline_layouts {
+                gfx_device.destroy_pipeline_layout(pipeline_layout, None);
+            }
+        }
+        shader_modules.into_iter().for_each(|shader_module| shader_module.destroy());
+
+        time_ms
+    }
+}
@@ -1,13 +1,18 @@
+use crate::frame_hooks::FrameHooks;
 use crate::resources::fif_buffer::FifBuffers;
 use truvis_asset::asset_hub::AssetHub;
+use truvis_gfx::query::gpu_timer::GfxGpuTimer;
 use truvis_gfx::resources::special_buffers::structured_buffer::GfxStructuredBuffer;
 use truvis_render_interface::bindless_manager::BindlessManager;
+use truvis_render_interface::camera_history::CameraHistory;
 use truvis_render_interface::frame_counter::FrameCounter;
 use truvis_render_interface::gfx_resource_manager::GfxResourceManager;
 use truvis_render_interface::global_descriptor_sets::GlobalDescriptorSets;
 use truvis_render_interface::gpu_scene::GpuScene;
 use truvis_render_interface::pipeline_settings::{AccumData, FrameSettings, PipelineSettings};
 use truvis_render_interface::sampler_manager::RenderSamplerManager;
+use truvis_scene::frustum::Frustum;
+use truvis_scene::guid_new_type::{InstanceHandle, MaterialHandle};
 use truvis_scene::scene_manager::SceneManager;
 use truvis_shader_binding::truvisl;
 
@@ -19,7 +24,7 @@ pub struct RenderContext {
 
     pub fif_buffers: FifBuffers,
     pub bindless_manager: BindlessManager,
-    pub per_frame_data_buffers: [GfxStructuredBuffer<truvisl::PerFrameData>; FrameCounter::fif_count()],
+    pub per_frame_data_buffers: Vec<GfxStructuredBuffer<truvisl::PerFrameData>>,
     pub gfx_resource_manager: GfxResourceManager,
     pub sampler_manager: RenderSamplerManager,
 
@@ -27,11 +32,34 @@ pub struct RenderContext {
 
     pub delta_time_s: f32,
     pub total_time_s: f32,
+    /// 累积结果保持不变的帧，参见 [`AccumData::is_frozen`]；在 `Renderer::before_render` 中更新
+    pub frame_frozen: bool,
     pub accum_data: AccumData,
 
     pub frame_counter: FrameCounter,
     pub frame_settings: FrameSettings,
     pub pipeline_settings: PipelineSettings,
+    /// 当前帧相机的视锥体，在 before_render 中更新，用于光栅化时的视锥剔除
+    pub camera_frustum: Frustum,
+    /// 当前帧相机在世界空间中的位置，在 before_render 中更新，用于透明物体的排序
+    pub camera_pos: glam::Vec3,
+    /// 相邻两帧的相机矩阵和当前帧的 jitter，在 before_render 中更新，用于 TAA 等时域算法
+    pub camera_history: CameraHistory,
+    /// 当前的渲染管线是否执行 TAA resolve，由管线在创建时设置
+    ///
+    /// 只有为 true 时 TAA 设置才会让投影矩阵带上 jitter，否则没有 resolve 的光栅化管线会产生抖动
+    pub taa_resolve_available: bool,
+
+    /// 统计每个 render graph pass 的 GPU 耗时
+    pub gpu_timer: GfxGpuTimer,
+
+    /// 通过鼠标拾取或者场景层级面板选中的 instance
+    pub selected_instance: Option<InstanceHandle>,
+    /// 材质编辑面板中选中的材质，使用它的物体会在 viewport 中高亮
+    pub highlight_material: Option<MaterialHandle>,
+
+    /// 在帧边界调用的子系统，参见 [`crate::frame_hooks`]
+    pub frame_hooks: FrameHooks,
 }
 
 /// 使用 <'a>，表示这些资源是临时的，可以被消费的，是对外展示的一个切片
@@ -45,7 +73,7 @@ pub struct RenderContext2<'a> {
 
     pub fif_buffers: &'a FifBuffers,
     pub bindless_manager: &'a BindlessManager,
-    pub per_frame_data_buffers: &'a [GfxStructuredBuffer<truvisl::PerFrameData>; FrameCounter::fif_count()],
+    pub per_frame_data_buffers: &'a [GfxStructuredBuffer<truvisl::PerFrameData>],
     pub gfx_resource_manager: &'a GfxResourceManager,
     pub sampler_manager: &'a RenderSamplerManager,
 
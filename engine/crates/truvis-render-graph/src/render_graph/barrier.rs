@@ -42,18 +42,7 @@ impl RgImageBarrierDesc {
     ///
     /// 如果 layout 相同且 access 兼容，可能不需要 barrier
     pub fn needs_barrier(&self) -> bool {
-        // Layout 不同一定需要 barrier
-        if self.src_state.layout != self.dst_state.layout {
-            return true;
-        }
-
-        // 有写操作需要 barrier（确保可见性）
-        if self.src_state.is_write() || self.dst_state.is_write() {
-            return true;
-        }
-
-        // 只读到只读可以跳过 barrier
-        false
+        self.src_state.needs_barrier(&self.dst_state)
     }
 
     /// 转换为 GfxImageBarrier
@@ -98,8 +87,7 @@ impl BufferBarrierDesc {
 
     /// 检查是否需要 barrier
     pub fn needs_barrier(&self) -> bool {
-        // 有写操作需要 barrier
-        self.src_state.is_write() || self.dst_state.is_write()
+        self.src_state.needs_barrier(&self.dst_state)
     }
 
     /// 转换为 GfxBufferBarrier
@@ -11,6 +11,8 @@ use super::pass::{RgLambdaPassWrapper, RgPass, RgPassBuilder, RgPassContext, RgP
 use super::resource_handle::{RgBufferHandle, RgImageHandle};
 use super::resource_manager::RgResourceManager;
 use super::resource_state::{RgBufferState, RgImageState};
+use super::transient::{RgResourceLifetime, RgTransientAliasing};
+use super::transient_pool::RgTransientImagePool;
 use crate::render_graph::export_info::RgExportInfo;
 use crate::render_graph::semaphore_info::RgSemaphoreInfo;
 use crate::render_graph::{RgBufferDesc, RgBufferResource, RgImageDesc, RgImageResource};
@@ -19,8 +21,10 @@ use itertools::Itertools;
 use slotmap::SecondaryMap;
 use truvis_gfx::commands::command_buffer::GfxCommandBuffer;
 use truvis_gfx::commands::submit_info::GfxSubmitInfo;
+use truvis_gfx::query::gpu_timer::GfxGpuTimer;
 use truvis_render_interface::gfx_resource_manager::GfxResourceManager;
 use truvis_render_interface::handles::{GfxBufferHandle, GfxImageHandle, GfxImageViewHandle};
+use truvis_render_interface::pipeline_settings::FrameLabel;
 
 /// RenderGraph 构建器
 ///
@@ -29,10 +33,11 @@ use truvis_render_interface::handles::{GfxBufferHandle, GfxImageHandle, GfxImage
 /// # 使用流程
 ///
 /// 1. 创建 builder: `RenderGraphBuilder::new()`
-/// 2. 导入外部资源: `builder.import_image(...)`
+/// 2. 导入外部资源: `builder.import_image(...)`，或者声明 transient 资源: `builder.create_image(...)`
 /// 3. 添加 Pass: `builder.add_pass("name", pass)`
 /// 4. 编译: `builder.compile()`
-/// 5. 执行: `compiled.execute(...)`
+/// 5. 绑定 transient 资源（如果有）: `compiled.bind_transients(...)`
+/// 6. 执行: `compiled.execute(...)`
 ///
 /// # 生命周期
 ///
@@ -141,10 +146,17 @@ impl<'a> RenderGraphBuilder<'a> {
         self.resources.register_buffer(RgBufferResource::imported(name, buffer_handle, initial_state))
     }
 
+    /// 声明只在这个渲染图内使用的临时图像，初始内容未定义
+    ///
+    /// 编译时根据生命周期与其他描述相同的临时图像复用物理资源，物理资源由 [`RgTransientImagePool`] 提供，
+    /// 参见 [`CompiledGraph::bind_transients`]
     pub fn create_image(&mut self, name: impl Into<String>, desc: RgImageDesc) -> RgImageHandle {
         self.resources.register_image(RgImageResource::transient(name, desc))
     }
 
+    /// 声明临时缓冲区
+    ///
+    /// TODO 尚未支持分配物理资源，目前只能使用 [`Self::import_buffer`]
     pub fn create_buffer(&mut self, name: impl Into<String>, desc: RgBufferDesc) -> RgBufferHandle {
         self.resources.register_buffer(RgBufferResource::transient(name, desc))
     }
@@ -199,6 +211,35 @@ impl<'a> RenderGraphBuilder<'a> {
         let pass = RgLambdaPassWrapper::new(setup_fn, execute_fn);
         self.add_pass(name, pass)
     }
+
+    /// 直接给出读写的图像及其访问状态，不需要 setup 闭包
+    ///
+    /// 同一个图像同时出现在 `image_reads` 和 `image_writes` 中表示读写；缓冲区依赖需要使用 [`Self::add_pass_lambda`]
+    pub fn add_pass_simple<E>(
+        &mut self,
+        name: impl Into<String>,
+        image_reads: &[(RgImageHandle, RgImageState)],
+        image_writes: &[(RgImageHandle, RgImageState)],
+        execute_fn: E,
+    ) -> &mut Self
+    where
+        E: Fn(&RgPassContext<'_>) + 'a,
+    {
+        let image_reads = image_reads.to_vec();
+        let image_writes = image_writes.to_vec();
+        self.add_pass_lambda(
+            name,
+            move |builder| {
+                for &(handle, state) in &image_reads {
+                    builder.read_image(handle, state);
+                }
+                for &(handle, state) in &image_writes {
+                    builder.write_image(handle, state);
+                }
+            },
+            execute_fn,
+        )
+    }
 }
 
 // compile 阶段
@@ -233,8 +274,25 @@ impl<'a> RenderGraphBuilder<'a> {
             panic!("RenderGraph: Cycle detected involving passes: {:?}", cycle_names);
         });
 
+        // transient 图像的生命周期与 alias 分配
+        let transient_images = self.compute_transient_lifetimes(&execution_order);
+        let transient_aliasing = RgTransientAliasing::assign(
+            &transient_images
+                .iter()
+                .map(|(handle, lifetime)| {
+                    (self.resources.get_image(*handle).unwrap().transient_desc().unwrap().clone(), *lifetime)
+                })
+                .collect_vec(),
+        );
+        let transient_slots: SecondaryMap<RgImageHandle, usize> = transient_images
+            .iter()
+            .zip(&transient_aliasing.slots)
+            .map(|((handle, _), slot)| (*handle, *slot))
+            .collect();
+
         // 计算每个 Pass 的 barriers（同时返回最终的资源状态用于计算 epilogue barriers）
-        let (barriers, final_image_states) = self.compute_barriers(&execution_order);
+        let (barriers, final_image_states) =
+            self.compute_barriers(&execution_order, &transient_images, &transient_slots);
 
         // 收集外部 wait semaphores（来自导入资源）
         let wait_semaphores = self.resources.iter_images().filter_map(|(_, res)| res.wait_semaphore()).collect_vec();
@@ -246,12 +304,26 @@ impl<'a> RenderGraphBuilder<'a> {
         // 计算 epilogue barriers：将导出资源从最后使用状态转换到 final_state
         let epilogue_barriers = self.compute_epilogue_barriers(&final_image_states);
 
+        // 导入图像在执行之后的状态，用于同步到 GfxImage 上追踪的状态
+        let imported_final_states = final_image_states
+            .iter()
+            .filter(|(handle, _)| self.resources.get_image(*handle).is_some_and(|res| res.physical_handle().is_some()))
+            .map(|(handle, &state)| {
+                let state = self.export_images.get(&handle).map_or(state, |info| info.final_state);
+                (handle, state)
+            })
+            .collect_vec();
+
         CompiledGraph {
             resources: self.resources,
             passes: self.passes,
             execution_order,
+            transient_slots,
+            transient_slot_descs: transient_aliasing.slot_descs,
+            transient_bindings: SecondaryMap::new(),
             barriers,
             epilogue_barriers,
+            imported_final_states,
             dep_graph,
             wait_semaphores,
             signal_semaphores,
@@ -286,9 +358,30 @@ impl<'a> RenderGraphBuilder<'a> {
         epilogue
     }
 
+    /// 统计被 pass 使用的 transient 图像在执行顺序中的生命周期，按首次使用排序
+    ///
+    /// 没有被任何 pass 使用的 transient 图像不需要物理资源
+    fn compute_transient_lifetimes(&self, execution_order: &[usize]) -> Vec<(RgImageHandle, RgResourceLifetime)> {
+        let mut lifetimes: Vec<(RgImageHandle, RgResourceLifetime)> = Vec::new();
+        for (order, &pass_idx) in execution_order.iter().enumerate() {
+            let pass = &self.passes[pass_idx];
+            for (handle, _) in pass.image_reads.iter().chain(&pass.image_writes) {
+                if self.resources.get_image(*handle).and_then(|res| res.transient_desc()).is_none() {
+                    continue;
+                }
+                match lifetimes.iter_mut().find(|(h, _)| h == handle) {
+                    Some((_, lifetime)) => lifetime.extend(order),
+                    None => lifetimes.push((*handle, RgResourceLifetime::new(order, order))),
+                }
+            }
+        }
+        lifetimes
+    }
+
     /// 计算每个 Pass 需要的 barriers
     ///
-    /// 模拟 pass 的执行顺序，跟踪资源的状态变化，生成必要的 barriers
+    /// 模拟 pass 的执行顺序，跟踪资源的状态变化，生成必要的 barriers。
+    /// 共用物理槽位的 transient 图像在首次使用时需要等待上一个使用者完成，layout 视为 UNDEFINED
     ///
     /// # 返回
     /// - barriers: 每个 Pass 的 barriers
@@ -296,6 +389,8 @@ impl<'a> RenderGraphBuilder<'a> {
     fn compute_barriers(
         &self,
         execution_order: &[usize],
+        transient_lifetimes: &[(RgImageHandle, RgResourceLifetime)],
+        transient_slots: &SecondaryMap<RgImageHandle, usize>,
     ) -> (Vec<PassBarriers>, SecondaryMap<RgImageHandle, RgImageState>) {
         let mut barriers = vec![PassBarriers::new(); self.passes.len()];
 
@@ -316,10 +411,23 @@ impl<'a> RenderGraphBuilder<'a> {
             image_resource.infer_aspect()
         };
 
-        for &pass_idx in execution_order {
+        // 每个 transient 槽位上一个使用者的最终状态
+        let mut slot_states: HashMap<usize, RgImageState> = HashMap::new();
+
+        for (order, &pass_idx) in execution_order.iter().enumerate() {
             let pass = &self.passes[pass_idx];
             let pass_barriers = &mut barriers[pass_idx];
 
+            // 首次使用的 transient 图像继承槽位上一个使用者的 stage 和 access
+            for (handle, _) in transient_lifetimes.iter().filter(|(_, lifetime)| lifetime.first == order) {
+                if let Some(prev_state) = slot_states.get(&transient_slots[*handle]) {
+                    image_states.insert(
+                        *handle,
+                        RgImageState::new(prev_state.stage, prev_state.access, vk::ImageLayout::UNDEFINED),
+                    );
+                }
+            }
+
             // 收集此 Pass 中每个图像的所有使用
             // Key: handle, Value: (is_write, required_state)
             let mut image_usage: HashMap<RgImageHandle, (bool, RgImageState)> = HashMap::new();
@@ -335,7 +443,8 @@ impl<'a> RenderGraphBuilder<'a> {
             }
 
             // 为每个使用的图像生成 barrier
-            for (handle, (is_write, required_state)) in image_usage {
+            // 跳过 barrier 的只读访问合并到当前状态中，之后的写入需要等待这些读取完成
+            for (handle, (_, required_state)) in image_usage {
                 if let Some(&crt_state) = image_states.get(handle) {
                     let aspect = get_image_aspect(handle);
 
@@ -343,10 +452,12 @@ impl<'a> RenderGraphBuilder<'a> {
                         RgImageBarrierDesc::new(handle, crt_state, required_state).with_aspect(aspect),
                     );
 
-                    // 如果是写入或 layout 改变，更新状态
-                    if is_write || crt_state.layout != required_state.layout {
-                        image_states.insert(handle, required_state);
-                    }
+                    let new_state = if crt_state.needs_barrier(&required_state) {
+                        required_state
+                    } else {
+                        crt_state.merge_read(&required_state)
+                    };
+                    image_states.insert(handle, new_state);
                 }
             }
 
@@ -361,15 +472,20 @@ impl<'a> RenderGraphBuilder<'a> {
                 buffer_usage.insert(*handle, (true, *state));
             }
 
-            for (handle, (is_write, required)) in buffer_usage {
+            for (handle, (_, required)) in buffer_usage {
                 if let Some(&current) = buffer_states.get(handle) {
                     pass_barriers.add_buffer_barrier(BufferBarrierDesc::new(handle, current, required));
 
-                    if is_write {
-                        buffer_states.insert(handle, required);
-                    }
+                    let new_state =
+                        if current.needs_barrier(&required) { required } else { current.merge_read(&required) };
+                    buffer_states.insert(handle, new_state);
                 }
             }
+
+            // 最后一次使用之后，槽位交给下一个 transient 图像
+            for (handle, _) in transient_lifetimes.iter().filter(|(_, lifetime)| lifetime.last == order) {
+                slot_states.insert(transient_slots[*handle], image_states[*handle]);
+            }
         }
 
         (barriers, image_states)
@@ -391,10 +507,18 @@ pub struct CompiledGraph<'a> {
     passes: Vec<RgPassNode<'a>>,
     /// 执行顺序（拓扑排序后）
     execution_order: Vec<usize>,
+    /// 被使用的 transient 图像所在的物理槽位
+    transient_slots: SecondaryMap<RgImageHandle, usize>,
+    /// 每个物理槽位的描述
+    transient_slot_descs: Vec<RgImageDesc>,
+    /// transient 图像绑定的物理资源，参见 [`Self::bind_transients`]
+    transient_bindings: SecondaryMap<RgImageHandle, (GfxImageHandle, GfxImageViewHandle)>,
     /// 每个 Pass 的 barriers（按 pass 索引）
     barriers: Vec<PassBarriers>,
     /// 尾声 barriers：将导出资源转换到最终状态
     epilogue_barriers: PassBarriers,
+    /// 导入图像在执行之后的状态
+    imported_final_states: Vec<(RgImageHandle, RgImageState)>,
     /// 依赖图（用于调试）
     #[allow(dead_code)]
     dep_graph: DependencyGraph,
@@ -420,22 +544,70 @@ impl CompiledGraph<'_> {
         &self.passes[index].name
     }
 
+    /// transient 图像 alias 之后需要的物理槽位，用于 [`RgTransientImagePool::prepare`]
+    #[inline]
+    pub fn transient_slot_descs(&self) -> &[RgImageDesc] {
+        &self.transient_slot_descs
+    }
+
+    /// 将 transient 图像绑定到 `pool` 中 `frame_label` 的物理图像上，需要在执行之前调用
+    ///
+    /// # Panics
+    /// 如果 `pool` 没有按照 [`Self::transient_slot_descs`] 准备好
+    pub fn bind_transients(&mut self, pool: &RgTransientImagePool, frame_label: FrameLabel) {
+        self.transient_bindings.clear();
+        for (handle, &slot) in &self.transient_slots {
+            let (desc, image, view) = pool.get(frame_label, slot).unwrap_or_else(|| {
+                panic!("RenderGraph: transient slot {} is not prepared in the pool", slot);
+            });
+            assert_eq!(
+                desc, &self.transient_slot_descs[slot],
+                "RenderGraph: transient slot {} in the pool has a different desc",
+                slot
+            );
+            self.transient_bindings.insert(handle, (image, view));
+        }
+    }
+
+    /// 导入图像或者已绑定的 transient 图像的物理句柄
+    #[inline]
+    fn physical_image(&self, handle: RgImageHandle) -> Option<(GfxImageHandle, GfxImageViewHandle)> {
+        let res = self.resources.get_image(handle)?;
+        match res.physical_handle() {
+            Some(image) => Some((image, res.physical_view_handle().unwrap_or_default())),
+            None => self.transient_bindings.get(handle).copied(),
+        }
+    }
+
     /// 执行渲染图
     ///
     /// # 参数
     /// - `cmd`: 命令缓冲区（已经 begin）
     /// - `resource_manager`: 资源管理器（用于获取物理资源）
     pub fn execute(&self, cmd: &GfxCommandBuffer, resource_manager: &GfxResourceManager) {
+        self.execute_with_timer(cmd, resource_manager, None);
+    }
+
+    /// 执行渲染图，并使用 `gpu_timer` 统计每个 Pass 的 GPU 耗时，区间名称为 Pass 名称
+    pub fn execute_with_timer(
+        &self,
+        cmd: &GfxCommandBuffer,
+        resource_manager: &GfxResourceManager,
+        gpu_timer: Option<&GfxGpuTimer>,
+    ) {
         let _span = tracy_client::span!("CompiledGraph::execute");
 
         // 构建物理资源查询表（使用 SecondaryMap）
         let mut image_handles: SecondaryMap<RgImageHandle, (GfxImageHandle, GfxImageViewHandle)> = SecondaryMap::new();
         let mut buffer_handles: SecondaryMap<RgBufferHandle, GfxBufferHandle> = SecondaryMap::new();
 
-        for (image_handle, image_resource) in self.resources.iter_images() {
-            if let Some(img) = image_resource.physical_handle() {
-                let view = image_resource.physical_view_handle().unwrap_or_default();
-                image_handles.insert(image_handle, (img, view));
+        debug_assert!(
+            self.transient_slots.keys().all(|handle| self.transient_bindings.contains_key(handle)),
+            "RenderGraph: transient images are not bound, call bind_transients before execute"
+        );
+        for (image_handle, _) in self.resources.iter_images() {
+            if let Some(physical) = self.physical_image(image_handle) {
+                image_handles.insert(image_handle, physical);
             }
         }
 
@@ -457,6 +629,9 @@ impl CompiledGraph<'_> {
 
             // 开始 Pass debug label
             cmd.begin_label(&pass.name, truvis_gfx::basic::color::LabelColor::COLOR_PASS);
+            if let Some(gpu_timer) = gpu_timer {
+                gpu_timer.begin(cmd, pass.name.as_str());
+            }
 
             // 执行 Pass
             let ctx = RgPassContext {
@@ -467,6 +642,9 @@ impl CompiledGraph<'_> {
             };
             pass.executor.execute(&ctx);
 
+            if let Some(gpu_timer) = gpu_timer {
+                gpu_timer.end(cmd, &pass.name);
+            }
             // 结束 Pass debug label
             cmd.end_label();
         }
@@ -477,6 +655,13 @@ impl CompiledGraph<'_> {
             self.record_barriers(cmd, &self.epilogue_barriers, resource_manager);
             cmd.end_label();
         }
+
+        // 之后直接通过 GfxCommandBuffer::transition 使用这些图像时，可以从正确的状态开始
+        for &(handle, state) in &self.imported_final_states {
+            if let Some(image) = image_handles.get(handle).and_then(|(image, _)| resource_manager.get_image(*image)) {
+                image.set_state(state);
+            }
+        }
     }
 
     /// 构建包含外部同步信息的 SubmitInfo
@@ -530,7 +715,7 @@ impl CompiledGraph<'_> {
         pass_barriers: &PassBarriers,
         resource_manager: &GfxResourceManager,
     ) {
-        use truvis_gfx::commands::barrier::GfxImageBarrier;
+        use truvis_gfx::commands::barrier::{GfxBufferBarrier, GfxImageBarrier};
 
         let image_barriers: Vec<GfxImageBarrier> = pass_barriers
             .image_barriers
@@ -541,8 +726,7 @@ impl CompiledGraph<'_> {
                     return None;
                 }
 
-                let res = self.resources.get_image(desc.handle)?;
-                let phys_handle = res.physical_handle()?;
+                let (phys_handle, _) = self.physical_image(desc.handle)?;
                 let image = resource_manager.get_image(phys_handle)?;
 
                 Some(desc.to_gfx_barrier(image.handle()))
@@ -553,8 +737,25 @@ impl CompiledGraph<'_> {
             cmd.image_memory_barrier(vk::DependencyFlags::empty(), &image_barriers);
         }
 
-        // 缓冲区 barriers（类似处理）
-        // TODO: 实现缓冲区 barrier 录制
+        let buffer_barriers: Vec<GfxBufferBarrier> = pass_barriers
+            .buffer_barriers
+            .iter()
+            .filter_map(|desc| {
+                if !desc.needs_barrier() {
+                    return None;
+                }
+
+                let res = self.resources.get_buffer(desc.handle)?;
+                let phys_handle = res.physical_handle()?;
+                let buffer = resource_manager.get_buffer(phys_handle)?;
+
+                Some(desc.to_gfx_barrier(buffer.vk_buffer()))
+            })
+            .collect();
+
+        if !buffer_barriers.is_empty() {
+            cmd.buffer_memory_barrier(vk::DependencyFlags::empty(), &buffer_barriers);
+        }
     }
 }
 
@@ -770,3 +971,144 @@ impl CompiledGraph<'_> {
         if flags.is_empty() { format!("{:?}", access) } else { flags.join(" | ") }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn color_desc() -> RgImageDesc {
+        RgImageDesc::new_2d(64, 64, vk::Format::R16G16B16A16_SFLOAT, vk::ImageUsageFlags::STORAGE)
+    }
+
+    /// 按照名称找到 Pass 的 barriers
+    fn barriers_of<'g>(compiled: &'g CompiledGraph<'_>, name: &str) -> &'g PassBarriers {
+        let pass_idx = (0..compiled.pass_count()).find(|&idx| compiled.pass_name(idx) == name).unwrap();
+        &compiled.barriers[pass_idx]
+    }
+
+    #[test]
+    fn test_add_pass_simple_declares_dependencies() {
+        let mut graph = RenderGraphBuilder::new();
+        let image = graph.create_image("image", color_desc());
+        graph
+            .add_pass_simple("write", &[], &[(image, RgImageState::STORAGE_WRITE_COMPUTE)], |_| {})
+            .add_pass_simple(
+                "read-write",
+                &[(image, RgImageState::STORAGE_READ_WRITE_COMPUTE)],
+                &[(image, RgImageState::STORAGE_READ_WRITE_COMPUTE)],
+                |_| {},
+            )
+            .add_pass_simple("read", &[(image, RgImageState::SHADER_READ_FRAGMENT)], &[], |_| {});
+
+        let compiled = graph.compile();
+        assert_eq!(compiled.passes[1].image_reads, [(image, RgImageState::STORAGE_READ_WRITE_COMPUTE)]);
+        assert_eq!(compiled.passes[1].image_writes, [(image, RgImageState::STORAGE_READ_WRITE_COMPUTE)]);
+        assert_eq!(compiled.execution_order(), [0, 1, 2]);
+
+        // 每次写入之后的使用都需要等待写入完成
+        let transitions = |name: &str| {
+            barriers_of(&compiled, name)
+                .image_barriers
+                .iter()
+                .map(|barrier| (barrier.src_state, barrier.dst_state))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(transitions("write"), [(RgImageState::UNDEFINED_TOP, RgImageState::STORAGE_WRITE_COMPUTE)]);
+        assert_eq!(
+            transitions("read-write"),
+            [(RgImageState::STORAGE_WRITE_COMPUTE, RgImageState::STORAGE_READ_WRITE_COMPUTE)]
+        );
+        assert_eq!(
+            transitions("read"),
+            [(RgImageState::STORAGE_READ_WRITE_COMPUTE, RgImageState::SHADER_READ_FRAGMENT)]
+        );
+    }
+
+    #[test]
+    fn test_aliased_transient_inherits_slot_state() {
+        let mut graph = RenderGraphBuilder::new();
+        let a = graph.create_image("a", color_desc());
+        let b = graph.create_image("b", color_desc());
+        let output = graph.import_image(
+            "output",
+            GfxImageHandle::default(),
+            None,
+            vk::Format::R8G8B8A8_UNORM,
+            RgImageState::UNDEFINED_TOP,
+            None,
+        );
+        // output 把各个 pass 串成一条链，b 在 a 的最后一次使用之后才开始使用
+        graph
+            .add_pass_simple("write-a", &[], &[(a, RgImageState::STORAGE_WRITE_COMPUTE)], |_| {})
+            .add_pass_simple(
+                "read-a",
+                &[(a, RgImageState::SHADER_READ_FRAGMENT)],
+                &[(output, RgImageState::COLOR_ATTACHMENT_WRITE)],
+                |_| {},
+            )
+            .add_pass_simple(
+                "write-b",
+                &[(output, RgImageState::SHADER_READ_FRAGMENT)],
+                &[(b, RgImageState::STORAGE_WRITE_COMPUTE)],
+                |_| {},
+            )
+            .add_pass_simple("read-b", &[(b, RgImageState::STORAGE_READ_COMPUTE)], &[], |_| {});
+
+        let compiled = graph.compile();
+        assert_eq!(compiled.transient_slot_descs().len(), 1);
+        assert_eq!(compiled.transient_slots[a], compiled.transient_slots[b]);
+
+        // b 首次使用时需要等待 a 在片元着色器中的读取完成，内容不需要保留
+        let barriers = barriers_of(&compiled, "write-b");
+        let barrier = barriers.image_barriers.iter().find(|barrier| barrier.handle == b).unwrap();
+        assert_eq!(barrier.src_state.stage, vk::PipelineStageFlags2::FRAGMENT_SHADER);
+        assert_eq!(barrier.src_state.layout, vk::ImageLayout::UNDEFINED);
+        assert_eq!(barrier.dst_state, RgImageState::STORAGE_WRITE_COMPUTE);
+    }
+
+    #[test]
+    fn test_buffer_barriers() {
+        let mut graph = RenderGraphBuilder::new();
+        let buffer = graph.import_buffer("buffer", GfxBufferHandle::default(), RgBufferState::UNDEFINED);
+        let write = |state: RgBufferState| {
+            move |builder: &mut RgPassBuilder| {
+                builder.write_buffer(buffer, state);
+            }
+        };
+        let read = |state: RgBufferState| {
+            move |builder: &mut RgPassBuilder| {
+                builder.read_buffer(buffer, state);
+            }
+        };
+        graph
+            .add_pass_lambda("cull", write(RgBufferState::STORAGE_READ_WRITE_COMPUTE), |_| {})
+            .add_pass_lambda("draw", read(RgBufferState::INDIRECT_BUFFER), |_| {})
+            .add_pass_lambda("draw-again", read(RgBufferState::INDIRECT_BUFFER), |_| {})
+            .add_pass_lambda("clear", write(RgBufferState::TRANSFER_DST), |_| {});
+
+        let compiled = graph.compile();
+        let transitions = |name: &str| {
+            barriers_of(&compiled, name)
+                .buffer_barriers
+                .iter()
+                .map(|barrier| (barrier.src_state, barrier.dst_state))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(transitions("cull"), [(RgBufferState::UNDEFINED, RgBufferState::STORAGE_READ_WRITE_COMPUTE)]);
+        // 两次读取之间没有依赖，先执行的一次需要等待写入，之后的只读访问不需要 barrier
+        let draws = compiled
+            .execution_order()
+            .iter()
+            .map(|&idx| compiled.pass_name(idx))
+            .filter(|name| name.starts_with("draw"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            transitions(draws[0]),
+            [(RgBufferState::STORAGE_READ_WRITE_COMPUTE, RgBufferState::INDIRECT_BUFFER)]
+        );
+        assert!(transitions(draws[1]).is_empty());
+        // 之后的写入需要等待两次读取完成
+        assert_eq!(transitions("clear"), [(RgBufferState::INDIRECT_BUFFER, RgBufferState::TRANSFER_DST)]);
+    }
+}
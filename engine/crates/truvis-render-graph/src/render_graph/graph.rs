@@ -34,8 +34,8 @@ impl DependencyGraph {
     /// 分析资源依赖，构建依赖图
     ///
     /// 规则：
-    /// - 写后读（WAR）：reader 依赖 writer
-    /// - 读后写（RAW）：writer 依赖 reader（保证读取完成）
+    /// - 写后读（RAW）：reader 依赖 writer
+    /// - 读后写（WAR）：writer 依赖上一次写入之后的所有 reader（保证读取完成）
     /// - 写后写（WAW）：后一个 writer 依赖前一个 writer
     pub fn analyze(
         pass_count: usize,
@@ -56,6 +56,9 @@ impl DependencyGraph {
         // 跟踪每个资源的最后写入者
         let mut last_image_writer: SecondaryMap<RgImageHandle, usize> = SecondaryMap::new();
         let mut last_buffer_writer: SecondaryMap<RgBufferHandle, usize> = SecondaryMap::new();
+        // 跟踪每个资源在最后一次写入之后的读取者
+        let mut image_readers: SecondaryMap<RgImageHandle, Vec<usize>> = SecondaryMap::new();
+        let mut buffer_readers: SecondaryMap<RgBufferHandle, Vec<usize>> = SecondaryMap::new();
 
         for pass_idx in 0..pass_count {
             // 处理图像读取
@@ -66,10 +69,23 @@ impl DependencyGraph {
                 {
                     graph.add_edge(writer, pass_idx, vec![img_handle], vec![]);
                 }
+                match image_readers.get_mut(img_handle) {
+                    Some(readers) => readers.push(pass_idx),
+                    None => {
+                        image_readers.insert(img_handle, vec![pass_idx]);
+                    }
+                }
             }
 
             // 处理图像写入
             for &img_handle in &image_writes[pass_idx] {
+                // 之前的读取者需要先完成，添加 WAR 依赖
+                for reader in image_readers.remove(img_handle).unwrap_or_default() {
+                    if reader != pass_idx {
+                        graph.add_edge(reader, pass_idx, vec![img_handle], vec![]);
+                    }
+                }
+
                 // 如果有之前的写入者，添加 WAW 依赖
                 if let Some(&prev_writer) = last_image_writer.get(img_handle)
                     && prev_writer != pass_idx
@@ -88,10 +104,22 @@ impl DependencyGraph {
                 {
                     graph.add_edge(writer, pass_idx, vec![], vec![buf_handle]);
                 }
+                match buffer_readers.get_mut(buf_handle) {
+                    Some(readers) => readers.push(pass_idx),
+                    None => {
+                        buffer_readers.insert(buf_handle, vec![pass_idx]);
+                    }
+                }
             }
 
             // 处理缓冲区写入
             for &buf_handle in &buffer_writes[pass_idx] {
+                for reader in buffer_readers.remove(buf_handle).unwrap_or_default() {
+                    if reader != pass_idx {
+                        graph.add_edge(reader, pass_idx, vec![], vec![buf_handle]);
+                    }
+                }
+
                 if let Some(&prev_writer) = last_buffer_writer.get(buf_handle)
                     && prev_writer != pass_idx
                 {
@@ -233,4 +261,24 @@ mod tests {
         assert!(order[1] == 0 || order[1] == 1);
         assert_eq!(order[2], 2);
     }
+
+    #[test]
+    fn test_write_after_read() {
+        // Pass 0 写入 image 0
+        // Pass 1 读取 image 0
+        // Pass 2 写入 image 0，需要等待 Pass 1 的读取完成
+        let (_sm, handles) = create_test_image_handles(1);
+        let img0 = handles[0];
+
+        let image_reads = vec![vec![], vec![img0], vec![]];
+        let image_writes = vec![vec![img0], vec![], vec![img0]];
+        let buffer_reads = vec![vec![], vec![], vec![]];
+        let buffer_writes = vec![vec![], vec![], vec![]];
+
+        let graph = DependencyGraph::analyze(3, &image_reads, &image_writes, &buffer_reads, &buffer_writes);
+
+        assert_eq!(graph.get_predecessors(2).len(), 2);
+        let order = graph.topological_sort().unwrap();
+        assert_eq!(order, vec![0, 1, 2]);
+    }
 }
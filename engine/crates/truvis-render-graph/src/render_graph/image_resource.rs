@@ -1,13 +1,15 @@
 use crate::render_graph::RgImageState;
 use crate::render_graph::semaphore_info::RgSemaphoreInfo;
 use ash::vk;
+use truvis_gfx::resources::image::VulkanFormatUtils;
 use truvis_gfx::resources::image_view::GfxImageViewDesc;
 use truvis_render_interface::handles::{GfxImageHandle, GfxImageViewHandle};
 
 /// 图像资源描述（用于创建临时资源）
 ///
 /// 包含创建 `vk::Image` 所需的所有信息，以及可选的默认视图描述。
-#[derive(Clone, Debug)]
+/// 描述完全相同的 transient 图像才可以共用物理资源，参见 [`super::RgTransientAliasing`]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct RgImageDesc {
     /// 图像宽度
     pub width: u32,
@@ -87,18 +89,9 @@ impl RgImageDesc {
         GfxImageViewDesc::new(self.format, view_type, aspect, (0, self.mip_levels as u8), (0, self.array_layers as u8))
     }
 
-    /// 从格式推断 aspect
+    /// 从格式推断 aspect，参见 [`VulkanFormatUtils::infer_aspect`]
     pub fn infer_aspect(format: vk::Format) -> vk::ImageAspectFlags {
-        match format {
-            vk::Format::D16_UNORM | vk::Format::D32_SFLOAT | vk::Format::X8_D24_UNORM_PACK32 => {
-                vk::ImageAspectFlags::DEPTH
-            }
-            vk::Format::S8_UINT => vk::ImageAspectFlags::STENCIL,
-            vk::Format::D16_UNORM_S8_UINT | vk::Format::D24_UNORM_S8_UINT | vk::Format::D32_SFLOAT_S8_UINT => {
-                vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL
-            }
-            _ => vk::ImageAspectFlags::COLOR,
-        }
+        VulkanFormatUtils::infer_aspect(format)
     }
 
     /// 从图像类型推断视图类型
@@ -224,6 +217,15 @@ impl RgImageResource {
         }
     }
 
+    /// 获取创建描述（仅对临时资源有效）
+    #[inline]
+    pub fn transient_desc(&self) -> Option<&RgImageDesc> {
+        match &self.source {
+            RgImageSource::Imported { .. } => None,
+            RgImageSource::Transient { desc } => Some(desc),
+        }
+    }
+
     /// 获取等待的外部 semaphore（仅对导入资源有效）
     #[inline]
     pub fn wait_semaphore(&self) -> Option<RgSemaphoreInfo> {
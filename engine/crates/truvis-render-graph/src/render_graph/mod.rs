@@ -9,6 +9,8 @@ mod resource_handle;
 mod resource_manager;
 mod resource_state;
 mod semaphore_info;
+mod transient;
+mod transient_pool;
 
 // Re-exports
 pub use barrier::{BufferBarrierDesc, PassBarriers, RgImageBarrierDesc};
@@ -21,3 +23,5 @@ pub use resource_handle::{RgBufferHandle, RgImageHandle};
 pub use resource_manager::RgResourceManager;
 pub use resource_state::{RgBufferState, RgImageState};
 pub use semaphore_info::RgSemaphoreInfo;
+pub use transient::{RgResourceLifetime, RgTransientAliasing};
+pub use transient_pool::RgTransientImagePool;
@@ -0,0 +1,127 @@
+//! Transient 资源的生命周期与 alias 复用
+//!
+//! 在拓扑排序之后，按执行顺序统计每个 transient 图像首次和最后一次被使用的位置，
+//! 描述相同且生命周期不重叠的图像共用同一个物理槽位。
+
+use super::image_resource::RgImageDesc;
+
+/// 资源在执行顺序中的使用区间（闭区间，单位为执行顺序中的序号）
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RgResourceLifetime {
+    /// 首次使用
+    pub first: usize,
+    /// 最后一次使用
+    pub last: usize,
+}
+
+impl RgResourceLifetime {
+    #[inline]
+    pub fn new(first: usize, last: usize) -> Self {
+        debug_assert!(first <= last);
+        Self { first, last }
+    }
+
+    /// 将 `order` 纳入使用区间
+    #[inline]
+    pub fn extend(&mut self, order: usize) {
+        self.first = self.first.min(order);
+        self.last = self.last.max(order);
+    }
+
+    /// 两个区间是否有公共的 pass
+    #[inline]
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.first <= other.last && other.first <= self.last
+    }
+}
+
+/// transient 图像的 alias 分配结果
+#[derive(Clone, Debug, Default)]
+pub struct RgTransientAliasing {
+    /// 每个 transient 图像使用的物理槽位，与输入的顺序一致
+    pub slots: Vec<usize>,
+    /// 每个物理槽位的描述，参见 [`super::RgTransientImagePool::prepare`]
+    pub slot_descs: Vec<RgImageDesc>,
+}
+
+impl RgTransientAliasing {
+    /// 按首次使用的顺序贪心分配：优先复用描述相同、且上一个使用者已经结束的槽位
+    ///
+    /// 参数为每个 transient 图像的描述与生命周期
+    pub fn assign(images: &[(RgImageDesc, RgResourceLifetime)]) -> Self {
+        let mut order = (0..images.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&idx| (images[idx].1.first, idx));
+
+        let mut slots = vec![0; images.len()];
+        let mut slot_descs: Vec<RgImageDesc> = Vec::new();
+        // 每个槽位当前使用者的生命周期
+        let mut slot_lifetimes: Vec<RgResourceLifetime> = Vec::new();
+
+        for idx in order {
+            let (desc, lifetime) = &images[idx];
+            let reusable = (0..slot_descs.len())
+                .find(|&slot| slot_descs[slot] == *desc && slot_lifetimes[slot].last < lifetime.first);
+
+            slots[idx] = match reusable {
+                Some(slot) => {
+                    slot_lifetimes[slot] = *lifetime;
+                    slot
+                }
+                None => {
+                    slot_descs.push(desc.clone());
+                    slot_lifetimes.push(*lifetime);
+                    slot_descs.len() - 1
+                }
+            };
+        }
+
+        Self { slots, slot_descs }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ash::vk;
+
+    use super::*;
+
+    fn desc(format: vk::Format) -> RgImageDesc {
+        RgImageDesc::new_2d(64, 64, format, vk::ImageUsageFlags::STORAGE)
+    }
+
+    #[test]
+    fn test_lifetime_overlap() {
+        let a = RgResourceLifetime::new(0, 2);
+        assert!(a.overlaps(&RgResourceLifetime::new(2, 3)));
+        assert!(!a.overlaps(&RgResourceLifetime::new(3, 4)));
+
+        let mut b = RgResourceLifetime::new(3, 3);
+        b.extend(1);
+        assert_eq!(b, RgResourceLifetime::new(1, 3));
+    }
+
+    #[test]
+    fn test_disjoint_lifetimes_share_slot() {
+        let aliasing = RgTransientAliasing::assign(&[
+            (desc(vk::Format::R16G16B16A16_SFLOAT), RgResourceLifetime::new(0, 1)),
+            (desc(vk::Format::R16G16B16A16_SFLOAT), RgResourceLifetime::new(1, 2)),
+            (desc(vk::Format::R16G16B16A16_SFLOAT), RgResourceLifetime::new(2, 3)),
+        ]);
+
+        // 第二个与第一个在 pass 1 重叠，需要新的槽位；第三个开始时第一个已经结束，可以复用它的槽位
+        assert_eq!(aliasing.slots, [0, 1, 0]);
+        assert_eq!(aliasing.slot_descs.len(), 2);
+    }
+
+    #[test]
+    fn test_different_desc_never_alias() {
+        let aliasing = RgTransientAliasing::assign(&[
+            (desc(vk::Format::R8G8B8A8_UNORM), RgResourceLifetime::new(0, 0)),
+            (desc(vk::Format::R16G16B16A16_SFLOAT), RgResourceLifetime::new(1, 1)),
+            (desc(vk::Format::R8G8B8A8_UNORM), RgResourceLifetime::new(2, 2)),
+        ]);
+
+        assert_eq!(aliasing.slots, [0, 1, 0]);
+        assert_eq!(aliasing.slot_descs[1].format, vk::Format::R16G16B16A16_SFLOAT);
+    }
+}
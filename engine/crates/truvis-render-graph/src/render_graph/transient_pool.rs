@@ -0,0 +1,209 @@
+use ash::vk;
+use slotmap::{Key, SlotMap, new_key_type};
+use truvis_gfx::resources::image::GfxImageCreateInfo;
+use truvis_render_interface::bindless_manager::BindlessManager;
+use truvis_render_interface::frame_counter::FrameCounter;
+use truvis_render_interface::gfx_resource_manager::GfxResourceManager;
+use truvis_render_interface::handles::{GfxImageHandle, GfxImageViewHandle};
+use truvis_render_interface::pipeline_settings::FrameLabel;
+
+use super::image_resource::RgImageDesc;
+
+new_key_type! { struct RgTransientImageKey; }
+
+/// 池中的一个物理图像
+struct RgTransientImage {
+    desc: RgImageDesc,
+    image: GfxImageHandle,
+    view: GfxImageViewHandle,
+    /// 最后一次使用该图像的帧 timeline 值
+    last_use_frame_id: u64,
+}
+
+/// RenderGraph transient 图像的物理资源池
+///
+/// 录制 render graph 时资源都是只读的，因此需要在此之前（例如 `OuterApp::update`）调用 [`Self::prepare`]
+/// 准备好物理图像，之后通过 [`super::CompiledGraph::bind_transients`] 将 transient 图像绑定到池中的槽位上。
+/// 各槽位的描述来自 [`super::CompiledGraph::transient_slot_descs`]，同一帧内生命周期不重叠的 transient 图像会共用一个槽位。
+///
+/// 图像不属于某个 frame label：每个图像记录最后使用它的帧 timeline 值，GPU 完成该帧之后即可被任意一帧复用。
+///
+/// usage 包含 STORAGE 或 SAMPLED 时会注册为 bindless uav / srv。
+/// 没有调用 [`Self::destroy`] 的图像会在 [`GfxResourceManager`] 销毁时一起释放
+pub struct RgTransientImagePool {
+    images: SlotMap<RgTransientImageKey, RgTransientImage>,
+    /// 每个 frame label 最近一次 [`Self::prepare`] 时各个槽位使用的图像
+    slots: Vec<Vec<RgTransientImageKey>>,
+}
+// new & init
+impl RgTransientImagePool {
+    pub fn new() -> Self {
+        Self {
+            images: SlotMap::with_key(),
+            slots: FrameCounter::frame_labes().map(|_| Vec::new()).collect(),
+        }
+    }
+}
+impl Default for RgTransientImagePool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+// getter
+impl RgTransientImagePool {
+    /// 第 `slot` 个槽位的描述和物理图像
+    #[inline]
+    pub fn get(
+        &self,
+        frame_label: FrameLabel,
+        slot: usize,
+    ) -> Option<(&RgImageDesc, GfxImageHandle, GfxImageViewHandle)> {
+        let image = self.images.get(*self.slots[*frame_label].get(slot)?)?;
+        Some((&image.desc, image.image, image.view))
+    }
+
+    /// `frame_label` 当前的槽位数量
+    #[inline]
+    pub fn slot_count(&self, frame_label: FrameLabel) -> usize {
+        self.slots[*frame_label].len()
+    }
+}
+// update
+impl RgTransientImagePool {
+    /// call phase: Update（录制 render graph 之前）
+    ///
+    /// 使当前 frame label 的槽位与 `slot_descs` 一一对应：每个槽位优先复用描述相同、且 GPU 已经不再使用的图像，
+    /// 没有时新建一个。GPU 已经不再使用、但这一帧没有用到的图像会被释放
+    pub fn prepare(
+        &mut self,
+        gfx_resource_manager: &mut GfxResourceManager,
+        bindless_manager: &mut BindlessManager,
+        frame_counter: &FrameCounter,
+        slot_descs: &[RgImageDesc],
+    ) {
+        let frame_label = frame_counter.frame_label();
+        let frame_id = frame_counter.frame_id();
+        let completed_frame_id = frame_counter.completed_frame_id();
+
+        let mut slots = Vec::with_capacity(slot_descs.len());
+        for (slot, desc) in slot_descs.iter().enumerate() {
+            let reusable = self.images.iter().find(|(key, image)| {
+                image.last_use_frame_id <= completed_frame_id && image.desc == *desc && !slots.contains(key)
+            });
+            let key = match reusable {
+                Some((key, _)) => key,
+                None => {
+                    let name = format!("rg-transient-{}-{}-{}", *frame_label, slot, self.images.len());
+                    self.images.insert(Self::create(gfx_resource_manager, bindless_manager, desc, &name))
+                }
+            };
+            self.images[key].last_use_frame_id = frame_id;
+            slots.push(key);
+        }
+        self.slots[*frame_label] = slots;
+
+        let idle_keys = self
+            .images
+            .iter()
+            .filter(|(_, image)| image.last_use_frame_id <= completed_frame_id)
+            .map(|(key, _)| key)
+            .collect::<Vec<_>>();
+        for key in idle_keys {
+            let image = self.images.remove(key).unwrap();
+            let last_use_frame_id = image.last_use_frame_id;
+            Self::release(gfx_resource_manager, bindless_manager, image, Some(last_use_frame_id));
+        }
+    }
+}
+// destroy
+impl RgTransientImagePool {
+    /// 立即销毁所有图像并注销 bindless，调用者需要确保 GPU 已经不再使用
+    pub fn destroy(mut self, gfx_resource_manager: &mut GfxResourceManager, bindless_manager: &mut BindlessManager) {
+        self.destroy_mut(gfx_resource_manager, bindless_manager);
+    }
+
+    pub fn destroy_mut(
+        &mut self,
+        gfx_resource_manager: &mut GfxResourceManager,
+        bindless_manager: &mut BindlessManager,
+    ) {
+        for (_, image) in self.images.drain() {
+            Self::release(gfx_resource_manager, bindless_manager, image, None);
+        }
+        self.slots.iter_mut().for_each(Vec::clear);
+    }
+}
+// tools
+impl RgTransientImagePool {
+    fn create(
+        gfx_resource_manager: &mut GfxResourceManager,
+        bindless_manager: &mut BindlessManager,
+        desc: &RgImageDesc,
+        name: &str,
+    ) -> RgTransientImage {
+        assert!(
+            desc.image_type == vk::ImageType::TYPE_2D && desc.depth == 1 && desc.array_layers == 1,
+            "RgTransientImagePool: only single layer 2D images are supported, got {:?}",
+            desc
+        );
+        assert_eq!(desc.samples, vk::SampleCountFlags::TYPE_1, "RgTransientImagePool: multisample is not supported");
+
+        let image_create_info = GfxImageCreateInfo::new_image_2d_info(
+            vk::Extent2D {
+                width: desc.width,
+                height: desc.height,
+            },
+            desc.format,
+            desc.usage,
+        )
+        .mip_levels(desc.mip_levels);
+        let image = gfx_resource_manager.create_image(
+            &image_create_info,
+            &vk_mem::AllocationCreateInfo {
+                usage: vk_mem::MemoryUsage::AutoPreferDevice,
+                ..Default::default()
+            },
+            name,
+        );
+        let view = gfx_resource_manager.get_or_create_image_view(
+            image,
+            desc.default_view_desc.unwrap_or_else(|| desc.infer_default_view()),
+            name,
+        );
+
+        if desc.usage.contains(vk::ImageUsageFlags::STORAGE) {
+            bindless_manager.register_uav(view);
+        }
+        if desc.usage.contains(vk::ImageUsageFlags::SAMPLED) {
+            bindless_manager.register_srv(view);
+        }
+
+        RgTransientImage {
+            desc: desc.clone(),
+            image,
+            view,
+            last_use_frame_id: 0,
+        }
+    }
+
+    /// `last_use_frame_id` 为 None 时立即销毁
+    fn release(
+        gfx_resource_manager: &mut GfxResourceManager,
+        bindless_manager: &mut BindlessManager,
+        image: RgTransientImage,
+        last_use_frame_id: Option<u64>,
+    ) {
+        debug_assert!(!image.view.is_null());
+        if image.desc.usage.contains(vk::ImageUsageFlags::STORAGE) {
+            bindless_manager.unregister_uav(image.view);
+        }
+        if image.desc.usage.contains(vk::ImageUsageFlags::SAMPLED) {
+            bindless_manager.unregister_srv(image.view);
+        }
+
+        match last_use_frame_id {
+            Some(frame_id) => gfx_resource_manager.destroy_image(image.image, frame_id),
+            None => gfx_resource_manager.destroy_image_immediate(image.image),
+        }
+    }
+}
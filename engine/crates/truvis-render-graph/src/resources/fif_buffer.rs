@@ -12,13 +12,14 @@ use truvis_render_interface::frame_counter::FrameCounter;
 use truvis_render_interface::gfx_resource_manager::GfxResourceManager;
 use truvis_render_interface::handles::{GfxImageHandle, GfxImageViewHandle};
 use truvis_render_interface::pipeline_settings::{FrameLabel, FrameSettings};
+use truvis_shader_binding::truvisl;
 
 // TODO FifBuffers 放到 app 里面去，由 App 进行管理
 /// 所有帧会用到的 buffers
 pub struct FifBuffers {
     /// RT 单帧输出结果，每帧一个
-    single_frame_rt_images: [GfxImageHandle; FrameCounter::fif_count()],
-    single_frame_rt_views: [GfxImageViewHandle; FrameCounter::fif_count()],
+    single_frame_rt_images: Vec<GfxImageHandle>,
+    single_frame_rt_views: Vec<GfxImageViewHandle>,
     single_frame_format: vk::Format,
     #[allow(dead_code)]
     single_frame_extent: vk::Extent2D,
@@ -37,26 +38,53 @@ pub struct FifBuffers {
     depth_extent: vk::Extent2D,
 
     /// 离屏渲染的结果，数量和 fif 相同
-    pub off_screen_target_image_handles: [GfxImageHandle; FrameCounter::fif_count()],
-    pub off_screen_target_view_handles: [GfxImageViewHandle; FrameCounter::fif_count()],
+    pub off_screen_target_image_handles: Vec<GfxImageHandle>,
+    pub off_screen_target_view_handles: Vec<GfxImageViewHandle>,
     render_target_format: vk::Format,
     #[allow(dead_code)]
     render_target_extent: vk::Extent2D,
 
     // ========== GBuffer ==========
     /// GBufferA: normal.xyz + roughness (R16G16B16A16_SFLOAT)
-    gbuffer_a_images: [GfxImageHandle; FrameCounter::fif_count()],
-    gbuffer_a_views: [GfxImageViewHandle; FrameCounter::fif_count()],
+    gbuffer_a_images: Vec<GfxImageHandle>,
+    gbuffer_a_views: Vec<GfxImageViewHandle>,
     /// GBufferB: world_position.xyz + linear_depth (R16G16B16A16_SFLOAT)
-    gbuffer_b_images: [GfxImageHandle; FrameCounter::fif_count()],
-    gbuffer_b_views: [GfxImageViewHandle; FrameCounter::fif_count()],
+    gbuffer_b_images: Vec<GfxImageHandle>,
+    gbuffer_b_views: Vec<GfxImageViewHandle>,
     /// GBufferC: albedo.rgb + metallic (R8G8B8A8_UNORM)
-    gbuffer_c_images: [GfxImageHandle; FrameCounter::fif_count()],
-    gbuffer_c_views: [GfxImageViewHandle; FrameCounter::fif_count()],
+    gbuffer_c_images: Vec<GfxImageHandle>,
+    gbuffer_c_views: Vec<GfxImageViewHandle>,
     gbuffer_extent: vk::Extent2D,
+
+    // ========== SSAO ==========
+    /// 未模糊的 AO (R8G8B8A8_UNORM)
+    ssao_raw_images: Vec<GfxImageHandle>,
+    ssao_raw_views: Vec<GfxImageViewHandle>,
+    /// 模糊后的 AO (R8G8B8A8_UNORM)
+    ssao_images: Vec<GfxImageHandle>,
+    ssao_views: Vec<GfxImageViewHandle>,
+    /// SSAO 的旋转噪声 (R32G32B32A32_SFLOAT)，NOISE_SIZE x NOISE_SIZE 平铺，各帧共享，参见 [`Self::ssao_noise_pixels`]
+    ssao_noise_image: GfxImageHandle,
+    ssao_noise_view: GfxImageViewHandle,
+
+    // ========== Bloom ==========
+    /// Bloom 的 mip 链 (R16G16B16A16_SFLOAT)，按 `[level][frame_label]` 索引，尺寸参见 [`Self::bloom_mip_extent`]
+    bloom_images: [Vec<GfxImageHandle>; Self::BLOOM_MIP_COUNT],
+    bloom_views: [Vec<GfxImageViewHandle>; Self::BLOOM_MIP_COUNT],
+
+    // ========== TAA ==========
+    /// 屏幕空间的运动向量 (R16G16B16A16_SFLOAT)，xy 为当前帧 uv 减去上一帧 uv
+    motion_vector_images: Vec<GfxImageHandle>,
+    motion_vector_views: Vec<GfxImageViewHandle>,
+    /// TAA 的历史结果，跨帧持久，按 frame_id 的奇偶交替读写，参见 [`Self::taa_history_handles`]
+    taa_history_images: [GfxImageHandle; 2],
+    taa_history_views: [GfxImageViewHandle; 2],
 }
 // new & init
 impl FifBuffers {
+    /// Bloom mip 链的级数，第 0 级为半分辨率
+    pub const BLOOM_MIP_COUNT: usize = 5;
+
     pub fn new(
         frame_settigns: &FrameSettings,
         bindless_manager: &mut BindlessManager,
@@ -75,8 +103,13 @@ impl FifBuffers {
 
         let accum_format = frame_settigns.color_format;
         let accum_extent = frame_settigns.frame_extent;
-        let (color_image, color_image_view) =
-            Self::create_color_image(gfx_resource_manager, accum_format, accum_extent, frame_counter);
+        let (color_image, color_image_view) = Self::create_color_image(
+            gfx_resource_manager,
+            accum_format,
+            accum_extent,
+            frame_counter,
+            "fif-buffer-color",
+        );
 
         let depth_format = frame_settigns.depth_format;
         let depth_extent = frame_settigns.frame_extent;
@@ -116,6 +149,56 @@ impl FifBuffers {
             "gbuffer-c",
         );
 
+        // 创建 SSAO 图像，尺寸与 GBuffer 一致
+        let (ssao_raw_images, ssao_raw_views) = Self::create_gbuffer_images(
+            gfx_resource_manager,
+            Self::ssao_format(),
+            gbuffer_extent,
+            frame_counter,
+            "ssao-raw",
+        );
+        let (ssao_images, ssao_views) = Self::create_gbuffer_images(
+            gfx_resource_manager,
+            Self::ssao_format(),
+            gbuffer_extent,
+            frame_counter,
+            "ssao",
+        );
+        let (ssao_noise_image, ssao_noise_view) = Self::create_ssao_noise_image(gfx_resource_manager);
+
+        // 创建 Bloom 的 mip 链，每一级是单独的图像，便于作为 storage image 读写
+        let bloom_mips: [_; Self::BLOOM_MIP_COUNT] = std::array::from_fn(|level| {
+            Self::create_gbuffer_images(
+                gfx_resource_manager,
+                Self::bloom_format(),
+                Self::bloom_mip_extent(gbuffer_extent, level),
+                frame_counter,
+                &format!("bloom-mip{}", level),
+            )
+        });
+        let bloom_images = bloom_mips.each_ref().map(|(images, _)| images.clone());
+        let bloom_views = bloom_mips.map(|(_, views)| views);
+
+        // 创建 TAA 的运动向量和历史图像，历史图像的格式与累积图像相同
+        let (motion_vector_images, motion_vector_views) = Self::create_gbuffer_images(
+            gfx_resource_manager,
+            Self::motion_vector_format(),
+            gbuffer_extent,
+            frame_counter,
+            "motion-vector",
+        );
+        let taa_histories: [_; 2] = std::array::from_fn(|idx| {
+            Self::create_color_image(
+                gfx_resource_manager,
+                accum_format,
+                accum_extent,
+                frame_counter,
+                &format!("taa-history-{}", idx),
+            )
+        });
+        let taa_history_images = taa_histories.map(|(image, _)| image);
+        let taa_history_views = taa_histories.map(|(_, view)| view);
+
         let fif_buffers = Self {
             single_frame_rt_images,
             single_frame_rt_views,
@@ -144,6 +227,21 @@ impl FifBuffers {
             gbuffer_c_images,
             gbuffer_c_views,
             gbuffer_extent,
+
+            ssao_raw_images,
+            ssao_raw_views,
+            ssao_images,
+            ssao_views,
+            ssao_noise_image,
+            ssao_noise_view,
+
+            bloom_images,
+            bloom_views,
+
+            motion_vector_images,
+            motion_vector_views,
+            taa_history_images,
+            taa_history_views,
         };
         fif_buffers.register_bindless(bindless_manager);
         fif_buffers
@@ -180,6 +278,19 @@ impl FifBuffers {
         for gbuffer_view in &self.gbuffer_c_views {
             bindless_manager.register_uav(*gbuffer_view);
         }
+        // 注册 SSAO
+        for ssao_view in self.ssao_raw_views.iter().chain(&self.ssao_views) {
+            bindless_manager.register_uav(*ssao_view);
+        }
+        bindless_manager.register_srv(self.ssao_noise_view);
+        // 注册 Bloom
+        for bloom_view in self.bloom_views.iter().flatten() {
+            bindless_manager.register_uav(*bloom_view);
+        }
+        // 注册 TAA
+        for taa_view in self.motion_vector_views.iter().chain(&self.taa_history_views) {
+            bindless_manager.register_uav(*taa_view);
+        }
     }
 
     fn unregister_bindless(&self, bindless_manager: &mut BindlessManager) {
@@ -201,6 +312,19 @@ impl FifBuffers {
         for gbuffer_view in &self.gbuffer_c_views {
             bindless_manager.unregister_uav(*gbuffer_view);
         }
+        // 取消注册 SSAO
+        for ssao_view in self.ssao_raw_views.iter().chain(&self.ssao_views) {
+            bindless_manager.unregister_uav(*ssao_view);
+        }
+        bindless_manager.unregister_srv(self.ssao_noise_view);
+        // 取消注册 Bloom
+        for bloom_view in self.bloom_views.iter().flatten() {
+            bindless_manager.unregister_uav(*bloom_view);
+        }
+        // 取消注册 TAA
+        for taa_view in self.motion_vector_views.iter().chain(&self.taa_history_views) {
+            bindless_manager.unregister_uav(*taa_view);
+        }
     }
 
     /// 创建 per-frame 的单帧 RT 输出图像
@@ -209,7 +333,7 @@ impl FifBuffers {
         format: vk::Format,
         extent: vk::Extent2D,
         frame_counter: &FrameCounter,
-    ) -> ([GfxImageHandle; FrameCounter::fif_count()], [GfxImageViewHandle; FrameCounter::fif_count()]) {
+    ) -> (Vec<GfxImageHandle>, Vec<GfxImageViewHandle>) {
         let create_one_image = |frame_label: FrameLabel| {
             let name = format!("single-frame-rt-{}-{}", frame_label, frame_counter.frame_id());
 
@@ -228,7 +352,7 @@ impl FifBuffers {
                 &name,
             )
         };
-        let images = FrameCounter::frame_labes().map(create_one_image);
+        let images = FrameCounter::frame_labes().map(create_one_image).collect_vec();
 
         // 将 layout 设置为 general
         Gfx::get().one_time_exec(
@@ -250,24 +374,29 @@ impl FifBuffers {
             "transfer-single-frame-rt-image-layout",
         );
 
-        let image_handles = images.map(|image| gfx_resource_manager.register_image(image));
-        let image_view_handles = FrameCounter::frame_labes().map(|frame_label| {
-            gfx_resource_manager.get_or_create_image_view(
-                image_handles[*frame_label],
-                GfxImageViewDesc::new_2d(format, vk::ImageAspectFlags::COLOR),
-                format!("single-frame-rt-{}-{}", frame_label, frame_counter.frame_id()),
-            )
-        });
+        let image_handles = images.into_iter().map(|image| gfx_resource_manager.register_image(image)).collect_vec();
+        let image_view_handles = FrameCounter::frame_labes()
+            .map(|frame_label| {
+                gfx_resource_manager.get_or_create_image_view(
+                    image_handles[*frame_label],
+                    GfxImageViewDesc::new_2d(format, vk::ImageAspectFlags::COLOR),
+                    format!("single-frame-rt-{}-{}", frame_label, frame_counter.frame_id()),
+                )
+            })
+            .collect_vec();
 
         (image_handles, image_view_handles)
     }
 
     /// 创建 RayTracing 需要的 image
+    ///
+    /// TAA 的历史图像同样跨帧持久，也通过该函数创建
     fn create_color_image(
         gfx_resource_manager: &mut GfxResourceManager,
         format: vk::Format,
         extent: vk::Extent2D,
         frame_counter: &FrameCounter,
+        name_prefix: &str,
     ) -> (GfxImageHandle, GfxImageViewHandle) {
         let color_image_create_info = GfxImageCreateInfo::new_image_2d_info(
             extent,
@@ -281,7 +410,7 @@ impl FifBuffers {
                 usage: vk_mem::MemoryUsage::AutoPreferDevice,
                 ..Default::default()
             },
-            &format!("fif-buffer-color-{}", frame_counter.frame_id()),
+            &format!("{}-{}", name_prefix, frame_counter.frame_id()),
         );
 
         // 将 layout 设置为 general
@@ -297,14 +426,14 @@ impl FifBuffers {
                         .image_aspect_flag(vk::ImageAspectFlags::COLOR)],
                 );
             },
-            "transfer-fif-buffer-color-image-layout",
+            &format!("transfer-{}-layout", name_prefix),
         );
 
         let color_image_handle = gfx_resource_manager.register_image(color_image);
         let color_image_view_handle = gfx_resource_manager.get_or_create_image_view(
             color_image_handle,
             GfxImageViewDesc::new_2d(format, vk::ImageAspectFlags::COLOR),
-            format!("fif-buffer-color-{}", frame_counter.frame_id()),
+            format!("{}-{}", name_prefix, frame_counter.frame_id()),
         );
 
         (color_image_handle, color_image_view_handle)
@@ -341,7 +470,7 @@ impl FifBuffers {
         format: vk::Format,
         extent: vk::Extent2D,
         frame_counter: &FrameCounter,
-    ) -> ([GfxImageHandle; FrameCounter::fif_count()], [GfxImageViewHandle; FrameCounter::fif_count()]) {
+    ) -> (Vec<GfxImageHandle>, Vec<GfxImageViewHandle>) {
         let create_one_target = |fif_labe: FrameLabel| {
             let name = format!("render-target-{}-{}", fif_labe, frame_counter.frame_id());
 
@@ -363,7 +492,7 @@ impl FifBuffers {
                 &name,
             )
         };
-        let images = FrameCounter::frame_labes().map(create_one_target);
+        let images = FrameCounter::frame_labes().map(create_one_target).collect_vec();
 
         // 将 layout 设置为 general
         Gfx::get().one_time_exec(
@@ -385,14 +514,16 @@ impl FifBuffers {
             "transfer-fif-buffer-render-target-layout",
         );
 
-        let image_handles = images.map(|image| gfx_resource_manager.register_image(image));
-        let image_view_handles = FrameCounter::frame_labes().map(|frame_label| {
-            gfx_resource_manager.get_or_create_image_view(
-                image_handles[*frame_label],
-                GfxImageViewDesc::new_2d(format, vk::ImageAspectFlags::COLOR),
-                format!("render-target-{}-{}", frame_label, frame_counter.frame_id()),
-            )
-        });
+        let image_handles = images.into_iter().map(|image| gfx_resource_manager.register_image(image)).collect_vec();
+        let image_view_handles = FrameCounter::frame_labes()
+            .map(|frame_label| {
+                gfx_resource_manager.get_or_create_image_view(
+                    image_handles[*frame_label],
+                    GfxImageViewDesc::new_2d(format, vk::ImageAspectFlags::COLOR),
+                    format!("render-target-{}-{}", frame_label, frame_counter.frame_id()),
+                )
+            })
+            .collect_vec();
 
         (image_handles, image_view_handles)
     }
@@ -401,20 +532,26 @@ impl FifBuffers {
     /// - GBufferA (R16G16B16A16_SFLOAT): normal.xyz + roughness
     /// - GBufferB (R16G16B16A16_SFLOAT): world_position.xyz + linear_depth
     /// - GBufferC (R8G8B8A8_UNORM): albedo.rgb + metallic
+    ///
+    /// SSAO 的中间结果、Bloom 的 mip 链与 GBuffer 的用法相同，也通过该函数创建
     fn create_gbuffer_images(
         gfx_resource_manager: &mut GfxResourceManager,
         format: vk::Format,
         extent: vk::Extent2D,
         frame_counter: &FrameCounter,
         name_prefix: &str,
-    ) -> ([GfxImageHandle; FrameCounter::fif_count()], [GfxImageViewHandle; FrameCounter::fif_count()]) {
+    ) -> (Vec<GfxImageHandle>, Vec<GfxImageViewHandle>) {
         let create_one_image = |frame_label: FrameLabel| {
             let name = format!("{}-{}-{}", name_prefix, frame_label, frame_counter.frame_id());
 
             let image_create_info = GfxImageCreateInfo::new_image_2d_info(
                 extent,
                 format,
-                vk::ImageUsageFlags::STORAGE | vk::ImageUsageFlags::SAMPLED,
+                // TRANSFER_SRC 用于将 GBuffer 导出到文件，COLOR_ATTACHMENT 用于延迟着色时由光栅化写入
+                vk::ImageUsageFlags::STORAGE
+                    | vk::ImageUsageFlags::SAMPLED
+                    | vk::ImageUsageFlags::TRANSFER_SRC
+                    | vk::ImageUsageFlags::COLOR_ATTACHMENT,
             );
 
             GfxImage::new(
@@ -426,7 +563,7 @@ impl FifBuffers {
                 &name,
             )
         };
-        let images = FrameCounter::frame_labes().map(create_one_image);
+        let images = FrameCounter::frame_labes().map(create_one_image).collect_vec();
 
         // 将 layout 设置为 general（用于 storage image）
         Gfx::get().one_time_exec(
@@ -448,17 +585,33 @@ impl FifBuffers {
             &format!("transfer-{}-layout", name_prefix),
         );
 
-        let image_handles = images.map(|image| gfx_resource_manager.register_image(image));
-        let image_view_handles = FrameCounter::frame_labes().map(|frame_label| {
-            gfx_resource_manager.get_or_create_image_view(
-                image_handles[*frame_label],
-                GfxImageViewDesc::new_2d(format, vk::ImageAspectFlags::COLOR),
-                format!("{}-{}-{}", name_prefix, frame_label, frame_counter.frame_id()),
-            )
-        });
+        let image_handles = images.into_iter().map(|image| gfx_resource_manager.register_image(image)).collect_vec();
+        let image_view_handles = FrameCounter::frame_labes()
+            .map(|frame_label| {
+                gfx_resource_manager.get_or_create_image_view(
+                    image_handles[*frame_label],
+                    GfxImageViewDesc::new_2d(format, vk::ImageAspectFlags::COLOR),
+                    format!("{}-{}-{}", name_prefix, frame_label, frame_counter.frame_id()),
+                )
+            })
+            .collect_vec();
 
         (image_handles, image_view_handles)
     }
+
+    /// 创建 SSAO 的旋转噪声图像，只用于采样，不需要 storage
+    fn create_ssao_noise_image(gfx_resource_manager: &mut GfxResourceManager) -> (GfxImageHandle, GfxImageViewHandle) {
+        let noise_size = truvisl::ssao::NOISE_SIZE as u32;
+        let image = GfxImage::from_rgba32f(noise_size, noise_size, &Self::ssao_noise_pixels(), "ssao-noise");
+        let image_handle = gfx_resource_manager.register_image(image);
+        let view_handle = gfx_resource_manager.get_or_create_image_view(
+            image_handle,
+            GfxImageViewDesc::new_2d(vk::Format::R32G32B32A32_SFLOAT, vk::ImageAspectFlags::COLOR),
+            "ssao-noise",
+        );
+
+        (image_handle, view_handle)
+    }
 }
 // destroy
 impl FifBuffers {
@@ -488,6 +641,28 @@ impl FifBuffers {
             gfx_resource_manager.destroy_image_immediate(gbuffer_image);
         }
 
+        // 销毁 SSAO 图像
+        for ssao_image in std::mem::take(&mut self.ssao_raw_images) {
+            gfx_resource_manager.destroy_image_immediate(ssao_image);
+        }
+        for ssao_image in std::mem::take(&mut self.ssao_images) {
+            gfx_resource_manager.destroy_image_immediate(ssao_image);
+        }
+        gfx_resource_manager.destroy_image_immediate(std::mem::take(&mut self.ssao_noise_image));
+
+        // 销毁 Bloom 图像
+        for bloom_image in std::mem::take(&mut self.bloom_images).into_iter().flatten() {
+            gfx_resource_manager.destroy_image_immediate(bloom_image);
+        }
+
+        // 销毁 TAA 图像
+        for taa_image in std::mem::take(&mut self.motion_vector_images)
+            .into_iter()
+            .chain(std::mem::take(&mut self.taa_history_images))
+        {
+            gfx_resource_manager.destroy_image_immediate(taa_image);
+        }
+
         // image view 无需销毁，只需要销毁 image 即可
         gfx_resource_manager.destroy_image_immediate(self.depth_image);
         gfx_resource_manager.destroy_image_immediate(self.accum_image);
@@ -500,6 +675,12 @@ impl FifBuffers {
         self.gbuffer_a_views = Default::default();
         self.gbuffer_b_views = Default::default();
         self.gbuffer_c_views = Default::default();
+        self.ssao_raw_views = Default::default();
+        self.ssao_views = Default::default();
+        self.ssao_noise_view = GfxImageViewHandle::default();
+        self.bloom_views = Default::default();
+        self.motion_vector_views = Default::default();
+        self.taa_history_views = Default::default();
     }
 }
 impl Drop for FifBuffers {
@@ -509,6 +690,12 @@ impl Drop for FifBuffers {
         debug_assert!(self.gbuffer_a_images.iter().all(|img| img.is_null()));
         debug_assert!(self.gbuffer_b_images.iter().all(|img| img.is_null()));
         debug_assert!(self.gbuffer_c_images.iter().all(|img| img.is_null()));
+        debug_assert!(self.ssao_raw_images.iter().all(|img| img.is_null()));
+        debug_assert!(self.ssao_images.iter().all(|img| img.is_null()));
+        debug_assert!(self.ssao_noise_image.is_null());
+        debug_assert!(self.bloom_images.iter().flatten().all(|img| img.is_null()));
+        debug_assert!(self.motion_vector_images.iter().all(|img| img.is_null()));
+        debug_assert!(self.taa_history_images.iter().all(|img| img.is_null()));
         debug_assert!(self.depth_image.is_null());
         debug_assert!(self.depth_image_view.is_null());
         debug_assert!(self.accum_image.is_null());
@@ -520,10 +707,7 @@ impl FifBuffers {
     /// 获取当前帧的单帧 RT 输出图像句柄
     #[inline]
     pub fn single_frame_rt_handle(&self, frame_label: FrameLabel) -> (GfxImageHandle, GfxImageViewHandle) {
-        (
-            self.single_frame_rt_images[*frame_label],
-            self.single_frame_rt_views[*frame_label],
-        )
+        (self.single_frame_rt_images[*frame_label], self.single_frame_rt_views[*frame_label])
     }
 
     /// 获取单帧 RT 输出图像的格式
@@ -642,4 +826,177 @@ impl FifBuffers {
     pub const fn gbuffer_c_format() -> vk::Format {
         vk::Format::R8G8B8A8_UNORM
     }
+
+    // ========== SSAO Getters ==========
+
+    /// 获取未模糊的 AO 的 handle
+    #[inline]
+    pub fn ssao_raw_handle(&self, frame_label: FrameLabel) -> (GfxImageHandle, GfxImageViewHandle) {
+        (self.ssao_raw_images[*frame_label], self.ssao_raw_views[*frame_label])
+    }
+
+    /// 获取模糊后的 AO 的 handle
+    #[inline]
+    pub fn ssao_handle(&self, frame_label: FrameLabel) -> (GfxImageHandle, GfxImageViewHandle) {
+        (self.ssao_images[*frame_label], self.ssao_views[*frame_label])
+    }
+
+    /// SSAO 格式: R8G8B8A8_UNORM，AO 值存储在 r 通道
+    #[inline]
+    pub const fn ssao_format() -> vk::Format {
+        vk::Format::R8G8B8A8_UNORM
+    }
+
+    /// 获取 SSAO 旋转噪声的 view handle，已注册为 bindless srv
+    #[inline]
+    pub fn ssao_noise_view_handle(&self) -> GfxImageViewHandle {
+        self.ssao_noise_view
+    }
+
+    /// SSAO 旋转噪声的像素数据（RGBA），按行排列
+    ///
+    /// 每个像素的 xy 是切平面内的单位方向，z = 0。16 个方向把圆周等分，
+    /// 按 4x4 Bayer 矩阵的顺序排列，使相邻像素的方向相差较大；
+    /// blur pass 的 NOISE_SIZE x NOISE_SIZE 滤波核正好覆盖全部方向
+    pub fn ssao_noise_pixels() -> Vec<f32> {
+        const BAYER_4X4: [[u32; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+        const _: () = assert!(truvisl::ssao::NOISE_SIZE == 4, "ssao noise uses a 4x4 bayer matrix");
+
+        BAYER_4X4
+            .iter()
+            .flatten()
+            .flat_map(|&order| {
+                let angle = (order as f32 + 0.5) / 16.0 * std::f32::consts::TAU;
+                [angle.cos(), angle.sin(), 0.0, 1.0]
+            })
+            .collect()
+    }
+
+    // ========== Bloom Getters ==========
+
+    /// 获取 Bloom mip 链各级的 handle，第 0 级为半分辨率
+    #[inline]
+    pub fn bloom_mip_handles(
+        &self,
+        frame_label: FrameLabel,
+    ) -> [(GfxImageHandle, GfxImageViewHandle); Self::BLOOM_MIP_COUNT] {
+        std::array::from_fn(|level| (self.bloom_images[level][*frame_label], self.bloom_views[level][*frame_label]))
+    }
+
+    /// Bloom 第 `level` 级的尺寸：第 0 级为 `frame_extent` 的一半，之后逐级减半
+    ///
+    /// 奇数尺寸向下取整，最小为 1
+    #[inline]
+    pub fn bloom_mip_extent(frame_extent: vk::Extent2D, level: usize) -> vk::Extent2D {
+        vk::Extent2D {
+            width: (frame_extent.width >> (level + 1)).max(1),
+            height: (frame_extent.height >> (level + 1)).max(1),
+        }
+    }
+
+    /// Bloom 格式: R16G16B16A16_SFLOAT
+    #[inline]
+    pub const fn bloom_format() -> vk::Format {
+        vk::Format::R16G16B16A16_SFLOAT
+    }
+
+    // ========== TAA Getters ==========
+
+    /// 获取运动向量的 handle
+    #[inline]
+    pub fn motion_vector_handle(&self, frame_label: FrameLabel) -> (GfxImageHandle, GfxImageViewHandle) {
+        (self.motion_vector_images[*frame_label], self.motion_vector_views[*frame_label])
+    }
+
+    /// 运动向量格式: R16G16B16A16_SFLOAT，只使用 xy
+    #[inline]
+    pub const fn motion_vector_format() -> vk::Format {
+        vk::Format::R16G16B16A16_SFLOAT
+    }
+
+    /// 第 `frame_id` 帧 TAA 的历史图像，依次为读取的上一帧结果和写入的当前帧结果
+    #[inline]
+    pub fn taa_history_handles(&self, frame_id: u64) -> [(GfxImageHandle, GfxImageViewHandle); 2] {
+        let write_idx = (frame_id % 2) as usize;
+        let read_idx = 1 - write_idx;
+        [
+            (self.taa_history_images[read_idx], self.taa_history_views[read_idx]),
+            (self.taa_history_images[write_idx], self.taa_history_views[write_idx]),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bloom_mip_extent_floors_odd_sizes() {
+        let frame_extent = vk::Extent2D {
+            width: 1921,
+            height: 1081,
+        };
+        assert_eq!(
+            FifBuffers::bloom_mip_extent(frame_extent, 0),
+            vk::Extent2D {
+                width: 960,
+                height: 540
+            }
+        );
+        assert_eq!(
+            FifBuffers::bloom_mip_extent(frame_extent, 1),
+            vk::Extent2D {
+                width: 480,
+                height: 270
+            }
+        );
+        assert_eq!(
+            FifBuffers::bloom_mip_extent(frame_extent, 2),
+            vk::Extent2D {
+                width: 240,
+                height: 135
+            }
+        );
+        assert_eq!(FifBuffers::bloom_mip_extent(frame_extent, 3), vk::Extent2D { width: 120, height: 67 });
+    }
+
+    #[test]
+    fn test_ssao_noise_directions_are_unit_and_distinct() {
+        let pixels = FifBuffers::ssao_noise_pixels();
+        let noise_size = truvisl::ssao::NOISE_SIZE as usize;
+        assert_eq!(pixels.len(), noise_size * noise_size * 4);
+
+        let directions = pixels.chunks_exact(4).map(|p| glam::vec3(p[0], p[1], p[2])).collect_vec();
+        for dir in &directions {
+            assert!((dir.length() - 1.0).abs() < 1e-5);
+            assert_eq!(dir.z, 0.0);
+        }
+        // 任意两个方向之间至少相差 360 / 16 度
+        let min_cos = (std::f32::consts::TAU / 16.0).cos();
+        for (a, b) in directions.iter().tuple_combinations() {
+            assert!(a.dot(*b) <= min_cos + 1e-5);
+        }
+        // 区块内水平与竖直相邻的像素不会朝向相近的方向
+        for y in 0..noise_size {
+            for x in 0..noise_size {
+                let dir = directions[y * noise_size + x];
+                if x + 1 < noise_size {
+                    assert!(dir.dot(directions[y * noise_size + x + 1]) < 0.5);
+                }
+                if y + 1 < noise_size {
+                    assert!(dir.dot(directions[(y + 1) * noise_size + x]) < 0.5);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_bloom_mip_extent_is_at_least_one() {
+        let frame_extent = vk::Extent2D { width: 3, height: 1 };
+        assert_eq!(FifBuffers::bloom_mip_extent(frame_extent, 0), vk::Extent2D { width: 1, height: 1 });
+        assert_eq!(
+            FifBuffers::bloom_mip_extent(frame_extent, FifBuffers::BLOOM_MIP_COUNT - 1),
+            vk::Extent2D { width: 1, height: 1 }
+        );
+    }
 }
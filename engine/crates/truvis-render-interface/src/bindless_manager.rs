@@ -1,29 +1,43 @@
+use crate::frame_counter::FrameCounter;
 use crate::gfx_resource_manager::GfxResourceManager;
 use crate::global_descriptor_sets::{BindlessDescriptorBinding, GlobalDescriptorSets};
 use crate::handles::GfxImageViewHandle;
-use crate::pipeline_settings::FrameLabel;
 use ash::vk;
 use slotmap::{Key, SecondaryMap};
+use truvis_descriptor_layout_trait::DescriptorBindingItem;
+use truvis_gfx::commands::timeline::GfxRetireQueue;
 use truvis_gfx::{gfx::Gfx, utilities::descriptor_cursor::GfxDescriptorCursor};
 use truvis_shader_binding::truvisl;
 
-#[derive(Copy, Clone)]
-pub struct BindlessUavHandle(pub truvisl::UavHandle);
+/// 第二个字段是槽位的 generation，只在 CPU 端用于检测过期的 handle，参见 [`BindlessSrvHandle`]
+#[derive(Copy, Clone, Debug)]
+pub struct BindlessUavHandle(pub truvisl::UavHandle, u32);
 impl BindlessUavHandle {
     #[inline]
-    pub fn new(index: usize) -> Self {
-        Self(truvisl::UavHandle { index: index as i32 })
+    pub fn new(index: usize, generation: u32) -> Self {
+        Self(truvisl::UavHandle { index: index as i32 }, generation)
     }
     #[inline]
     pub fn null() -> Self {
-        Self(truvisl::UavHandle {
-            index: truvisl::INVALID_TEX_ID,
-        })
+        Self(
+            truvisl::UavHandle {
+                index: truvisl::INVALID_TEX_ID,
+            },
+            0,
+        )
     }
     #[inline]
     pub fn index(&self) -> usize {
         self.0.index as usize
     }
+    #[inline]
+    pub fn generation(&self) -> u32 {
+        self.1
+    }
+    #[inline]
+    pub fn is_null(&self) -> bool {
+        self.0.index == truvisl::INVALID_TEX_ID
+    }
 }
 impl Default for BindlessUavHandle {
     fn default() -> Self {
@@ -31,23 +45,35 @@ impl Default for BindlessUavHandle {
     }
 }
 
-#[derive(Copy, Clone)]
-pub struct BindlessSrvHandle(pub truvisl::SrvHandle);
+/// 第二个字段是槽位的 generation，只在 CPU 端用于检测过期的 handle，参见 [`BindlessManager::validate_srv_handle`]
+#[derive(Copy, Clone, Debug)]
+pub struct BindlessSrvHandle(pub truvisl::SrvHandle, u32);
 impl BindlessSrvHandle {
     #[inline]
-    pub fn new(index: usize) -> Self {
-        Self(truvisl::SrvHandle { index: index as i32 })
+    pub fn new(index: usize, generation: u32) -> Self {
+        Self(truvisl::SrvHandle { index: index as i32 }, generation)
     }
     #[inline]
     pub fn null() -> Self {
-        Self(truvisl::SrvHandle {
-            index: truvisl::INVALID_TEX_ID,
-        })
+        Self(
+            truvisl::SrvHandle {
+                index: truvisl::INVALID_TEX_ID,
+            },
+            0,
+        )
     }
     #[inline]
     pub fn index(&self) -> usize {
         self.0.index as usize
     }
+    #[inline]
+    pub fn generation(&self) -> u32 {
+        self.1
+    }
+    #[inline]
+    pub fn is_null(&self) -> bool {
+        self.0.index == truvisl::INVALID_TEX_ID
+    }
 }
 impl Default for BindlessSrvHandle {
     fn default() -> Self {
@@ -55,14 +81,137 @@ impl Default for BindlessSrvHandle {
     }
 }
 
+/// bindless 数组的容量配置
+///
+/// srv 数组按需扩容，避免一开始就分配很大的数组；uav 数量很少，使用固定容量
+#[derive(Debug, Clone, Copy)]
+pub struct BindlessConfig {
+    /// srv 数组的初始容量
+    pub initial_srv_capacity: usize,
+    /// srv 数组用尽时，容量扩大为原来的多少倍
+    pub srv_growth_factor: usize,
+}
+impl Default for BindlessConfig {
+    fn default() -> Self {
+        Self {
+            initial_srv_capacity: 128,
+            srv_growth_factor: 2,
+        }
+    }
+}
+
+/// bindless 数组的槽位分配器
+///
+/// 释放的槽位不会立即复用：GPU 上可能还有正在执行的帧通过该槽位访问旧的资源，
+/// 需要等待帧 timeline 达到释放时的 frame id 之后才会进入空闲列表。
+///
+/// 每个槽位维护一个 generation，释放时自增，用于识别指向已释放槽位的过期 handle。
+struct BindlessSlotAllocator {
+    /// 数组当前的容量，分配的槽位都小于该值
+    capacity: usize,
+    /// 容量的上限
+    max_capacity: usize,
+    /// 槽位用尽时，容量扩大为原来的多少倍；为 1 时不会扩容
+    growth_factor: usize,
+    /// 从未分配过的最小槽位
+    next_slot: usize,
+    /// 可以立即复用的槽位
+    free_slots: Vec<usize>,
+    /// 等待 GPU 使用完毕的槽位，按照释放时的 frame id 回收
+    pending_free_slots: GfxRetireQueue<usize>,
+    /// 每个分配过的槽位当前的 generation
+    generations: Vec<u32>,
+}
+impl BindlessSlotAllocator {
+    fn new(capacity: usize, max_capacity: usize, growth_factor: usize) -> Self {
+        assert!(0 < capacity && capacity <= max_capacity);
+        assert!(growth_factor >= 1);
+        Self {
+            capacity,
+            max_capacity,
+            growth_factor,
+            next_slot: 0,
+            free_slots: Vec::new(),
+            pending_free_slots: GfxRetireQueue::new(),
+            generations: Vec::new(),
+        }
+    }
+
+    /// 优先复用空闲列表中的槽位，没有空闲槽位并且容量用尽时扩容
+    ///
+    /// # Panics
+    /// 容量已经达到上限，并且槽位已经耗尽
+    fn alloc(&mut self) -> usize {
+        if let Some(slot) = self.free_slots.pop() {
+            return slot;
+        }
+
+        if self.next_slot == self.capacity {
+            self.capacity = (self.capacity * self.growth_factor).min(self.max_capacity);
+        }
+        assert!(self.next_slot < self.capacity, "bindless slots exhausted, capacity: {}", self.capacity);
+        let slot = self.next_slot;
+        self.next_slot += 1;
+        self.generations.push(0);
+        slot
+    }
+
+    fn release(&mut self, slot: usize, frame_id: u64) {
+        // 释放后旧的 handle 立即失效，不需要等到槽位被复用
+        self.generations[slot] = self.generations[slot].wrapping_add(1);
+        self.pending_free_slots.retire(frame_id, slot);
+    }
+
+    /// 将已经不再被 GPU 使用的槽位放入空闲列表
+    ///
+    /// `completed_frame_id` 为帧 timeline 已经完成的值，释放时的 frame id 不大于它的槽位可以复用
+    fn reclaim(&mut self, completed_frame_id: u64) {
+        self.free_slots.extend(self.pending_free_slots.drain_completed(completed_frame_id));
+    }
+
+    #[inline]
+    fn free_slot_count(&self) -> usize {
+        self.free_slots.len()
+    }
+
+    /// 已经分配出去的槽位数量，包括尚在等待 GPU 使用完毕的槽位
+    #[inline]
+    fn used_slot_count(&self) -> usize {
+        self.next_slot - self.free_slots.len()
+    }
+
+    #[inline]
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    #[inline]
+    fn generation(&self, slot: usize) -> u32 {
+        self.generations[slot]
+    }
+
+    /// handle 指向的槽位是否仍然属于分配时的资源
+    #[inline]
+    fn is_alive(&self, slot: usize, generation: u32) -> bool {
+        self.generations.get(slot) == Some(&generation)
+    }
+}
+
 /// Bindless 描述符管理器
 ///
 /// 管理 Bindless 纹理和存储图像，通过数组索引访问资源。
 /// 每帧独立的描述符集，支持 UPDATE_AFTER_BIND 和 PARTIALLY_BOUND。
 ///
+/// 注册时就会分配固定的 bindless index，注销后该 index 会在 GPU 完成注销时的帧之后被复用，
+/// 因此长时间运行、反复加载卸载纹理也不会耗尽 bindless 数组。
+///
+/// srv 数组的容量按照 [`BindlessConfig`] 动态增长：扩容发生在注册时，
+/// 各帧的 descriptor set 在 [`Self::prepare_render_data`] 中重新分配并写入所有 descriptor。
+///
 /// # Bindless 架构
 /// - Binding 0: 纹理数组（COMBINED_IMAGE_SAMPLER，最多 128 个）
 /// - Binding 1: 存储图像数组（STORAGE_IMAGE，最多 128 个）
+/// - Binding 2: 采样图像数组（SAMPLED_IMAGE，按需扩容，最多 16384 个）
 /// - 着色器通过索引访问：`textures[index]`
 ///
 /// # 使用示例
@@ -76,14 +225,35 @@ pub struct BindlessManager {
 
     // sampled image
     srvs: SecondaryMap<GfxImageViewHandle, BindlessSrvHandle>,
+
+    uav_slots: BindlessSlotAllocator,
+    srv_slots: BindlessSlotAllocator,
+
+    /// 最近一次 [`Self::prepare_render_data`] 的帧序号，注销时用于记录槽位的释放时间
+    current_frame_id: u64,
 }
 
 // new & init
 impl BindlessManager {
+    /// 和 [`BindlessDescriptorBinding`] 中数组的长度一致，srv 数组的数量在运行时决定，这里是它的上限
+    const MAX_UAV_COUNT: usize = 128;
+    const MAX_SRV_COUNT: usize = DescriptorBindingItem::RUNTIME_COUNT_UPPER_BOUND as usize;
+
     pub fn new() -> Self {
+        Self::with_config(BindlessConfig::default())
+    }
+
+    pub fn with_config(config: BindlessConfig) -> Self {
         Self {
             uavs: SecondaryMap::new(),
             srvs: SecondaryMap::new(),
+            uav_slots: BindlessSlotAllocator::new(Self::MAX_UAV_COUNT, Self::MAX_UAV_COUNT, 1),
+            srv_slots: BindlessSlotAllocator::new(
+                config.initial_srv_capacity,
+                Self::MAX_SRV_COUNT,
+                config.srv_growth_factor,
+            ),
+            current_frame_id: 0,
         }
     }
 }
@@ -107,66 +277,92 @@ impl Drop for BindlessManager {
 impl BindlessManager {
     /// # Phase: Before Render
     ///
-    /// 在每一帧绘制之前，将纹理数据绑定到 descriptor set 中
+    /// 在每一帧绘制之前，回收帧 timeline 已经完成的槽位，并将纹理数据绑定到 descriptor set 中
+    ///
+    /// 当前帧的 descriptor set 容量不足时会在这里重新分配，旧的 set 在 GPU 不再使用之后才会销毁
     pub fn prepare_render_data(
         &mut self,
         gfx_resource_manager: &GfxResourceManager,
-        render_descriptor_sets: &GlobalDescriptorSets,
-        frame_label: FrameLabel,
+        render_descriptor_sets: &mut GlobalDescriptorSets,
+        frame_counter: &FrameCounter,
     ) {
         let _span = tracy_client::span!("BindlessManager::prepare_render_data");
 
-        // combined image sampler 信息
-        let combined_sampler_srvs_inofs = Vec::new();
+        self.current_frame_id = frame_counter.frame_id();
+        self.uav_slots.reclaim(frame_counter.completed_frame_id());
+        self.srv_slots.reclaim(frame_counter.completed_frame_id());
+
+        let frame_label = frame_counter.frame_label();
+        render_descriptor_sets.ensure_bindless_srv_capacity(frame_counter, self.srv_slots.capacity() as u32);
+        let bindless_set = render_descriptor_sets.current_bindless_set(frame_label).handle();
+
+        // 每个资源的 index 是固定的，但是不一定连续，因此逐个写入 descriptor
+        let mut writes = Vec::with_capacity(self.uavs.len() + self.srvs.len());
 
         // UAV 信息
-        let mut uav_infos = Vec::with_capacity(self.uavs.len());
-        for (image_view_handle, shader_uav_handle) in self.uavs.iter_mut() {
+        for (image_view_handle, shader_uav_handle) in self.uavs.iter() {
             let image_view = gfx_resource_manager.get_image_view(image_view_handle).unwrap();
-            uav_infos.push(
-                vk::DescriptorImageInfo::default()
-                    .image_view(image_view.handle())
-                    .image_layout(vk::ImageLayout::GENERAL),
-            );
-            shader_uav_handle.0.index = uav_infos.len() as i32 - 1;
+            writes.push(BindlessDescriptorBinding::uavs().write_image(
+                bindless_set,
+                shader_uav_handle.index() as u32,
+                vec![
+                    vk::DescriptorImageInfo::default()
+                        .image_view(image_view.handle())
+                        .image_layout(vk::ImageLayout::GENERAL),
+                ],
+            ));
         }
 
         // SRV 信息
-        let mut srv_infos = Vec::with_capacity(self.srvs.len());
-        for (image_view_handle, shader_src_handle) in self.srvs.iter_mut() {
+        for (image_view_handle, shader_srv_handle) in self.srvs.iter() {
             let image_view = gfx_resource_manager.get_image_view(image_view_handle).unwrap();
-            srv_infos.push(
-                vk::DescriptorImageInfo::default()
-                    .image_view(image_view.handle())
-                    .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL),
-            );
-            shader_src_handle.0.index = srv_infos.len() as i32 - 1;
+            writes.push(BindlessDescriptorBinding::srvs().write_image(
+                bindless_set,
+                shader_srv_handle.index() as u32,
+                vec![
+                    vk::DescriptorImageInfo::default()
+                        .image_view(image_view.handle())
+                        .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL),
+                ],
+            ));
         }
 
-        // 将 images 和 textures 信息写入 descriptor set
-        let mut writes = Vec::new();
-        if !combined_sampler_srvs_inofs.is_empty() {
-            writes.push(BindlessDescriptorBinding::textures().write_image(
-                render_descriptor_sets.current_bindless_set(frame_label).handle(),
-                0,
-                combined_sampler_srvs_inofs,
-            ))
-        }
-        if !uav_infos.is_empty() {
-            writes.push(BindlessDescriptorBinding::uavs().write_image(
-                render_descriptor_sets.current_bindless_set(frame_label).handle(),
-                0,
-                uav_infos,
-            ))
-        }
-        if !srv_infos.is_empty() {
-            writes.push(BindlessDescriptorBinding::srvs().write_image(
-                render_descriptor_sets.current_bindless_set(frame_label).handle(),
-                0,
-                srv_infos,
-            ))
+        if !writes.is_empty() {
+            Gfx::get().gfx_device().write_descriptor_sets(&writes);
         }
-        Gfx::get().gfx_device().write_descriptor_sets(&writes);
+    }
+}
+
+// getter
+impl BindlessManager {
+    /// srv 数组当前的容量
+    #[inline]
+    pub fn srv_capacity(&self) -> usize {
+        self.srv_slots.capacity()
+    }
+
+    /// 已经使用的 srv 槽位数量，包括尚在等待 GPU 使用完毕的槽位
+    #[inline]
+    pub fn used_srv_count(&self) -> usize {
+        self.srv_slots.used_slot_count()
+    }
+
+    /// uav 数组当前的容量
+    #[inline]
+    pub fn uav_capacity(&self) -> usize {
+        self.uav_slots.capacity()
+    }
+
+    /// 已经使用的 uav 槽位数量，包括尚在等待 GPU 使用完毕的槽位
+    #[inline]
+    pub fn used_uav_count(&self) -> usize {
+        self.uav_slots.used_slot_count()
+    }
+
+    /// 可以立即复用的槽位数量（UAV 和 SRV 之和），不包含尚在等待 GPU 使用完毕的槽位
+    #[inline]
+    pub fn free_slot_count(&self) -> usize {
+        self.uav_slots.free_slot_count() + self.srv_slots.free_slot_count()
     }
 }
 
@@ -180,14 +376,17 @@ impl BindlessManager {
             log::error!("Image view handle {:?} is already registered", image_view_handle);
             return;
         }
-        self.uavs.insert(image_view_handle, BindlessUavHandle::null());
+        let slot = self.uav_slots.alloc();
+        self.uavs.insert(image_view_handle, BindlessUavHandle::new(slot, self.uav_slots.generation(slot)));
     }
 
+    /// 注销后对应的 index 在 GPU 完成最近一次准备的帧之后才会被复用
     #[inline]
     pub fn unregister_uav(&mut self, image_view_handle: GfxImageViewHandle) {
         debug_assert!(!image_view_handle.is_null());
 
-        self.uavs.remove(image_view_handle).unwrap();
+        let uav_handle = self.uavs.remove(image_view_handle).unwrap();
+        self.uav_slots.release(uav_handle.index(), self.current_frame_id);
     }
 
     #[inline]
@@ -208,14 +407,17 @@ impl BindlessManager {
             log::error!("Image view handle {:?} is already registered", image_view_handle);
             return;
         }
-        self.srvs.insert(image_view_handle, BindlessSrvHandle::null());
+        let slot = self.srv_slots.alloc();
+        self.srvs.insert(image_view_handle, BindlessSrvHandle::new(slot, self.srv_slots.generation(slot)));
     }
 
+    /// 注销后对应的 index 在 GPU 完成最近一次准备的帧之后才会被复用
     #[inline]
     pub fn unregister_srv(&mut self, image_view_handle: GfxImageViewHandle) {
         debug_assert!(!image_view_handle.is_null());
 
-        self.srvs.remove(image_view_handle).unwrap();
+        let srv_handle = self.srvs.remove(image_view_handle).unwrap();
+        self.srv_slots.release(srv_handle.index(), self.current_frame_id);
     }
 
     #[inline]
@@ -224,4 +426,131 @@ impl BindlessManager {
 
         self.srvs.get(image_view_handle).copied().unwrap()
     }
+
+    /// 和 [`Self::get_shader_srv_handle`] 相同，但是 image view 未注册时返回 None
+    #[inline]
+    pub fn try_get_shader_srv_handle(&self, image_view_handle: GfxImageViewHandle) -> Option<BindlessSrvHandle> {
+        self.srvs.get(image_view_handle).copied()
+    }
+
+    /// 检查之前获取的 handle 是否仍然有效
+    ///
+    /// 对应的 image view 注销之后返回 None，即使槽位已经被其他纹理复用。
+    /// 缓存了 handle 的调用者可以据此回退到 fallback 纹理，而不是采样到错误的纹理。
+    #[inline]
+    pub fn validate_srv_handle(&self, srv_handle: BindlessSrvHandle) -> Option<BindlessSrvHandle> {
+        (!srv_handle.is_null() && self.srv_slots.is_alive(srv_handle.index(), srv_handle.generation()))
+            .then_some(srv_handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_reuse_after_frame_completed() {
+        let mut allocator = BindlessSlotAllocator::new(4, 4, 1);
+        let a = allocator.alloc();
+        let b = allocator.alloc();
+        assert_ne!(a, b);
+
+        allocator.release(a, 10);
+        // GPU 可能还在使用，不能复用
+        allocator.reclaim(9);
+        assert_eq!(allocator.free_slot_count(), 0);
+        assert_ne!(allocator.alloc(), a);
+
+        // 释放时的帧一旦完成就可以复用，不需要再等待 fif_count 帧
+        allocator.reclaim(10);
+        assert_eq!(allocator.free_slot_count(), 1);
+        assert_eq!(allocator.alloc(), a);
+        assert_eq!(allocator.free_slot_count(), 0);
+    }
+
+    #[test]
+    fn test_used_slot_count() {
+        let mut allocator = BindlessSlotAllocator::new(4, 4, 1);
+        let a = allocator.alloc();
+        allocator.alloc();
+        assert_eq!(allocator.used_slot_count(), 2);
+
+        // 等待 GPU 使用完毕的槽位仍然算作已使用
+        allocator.release(a, 1);
+        assert_eq!(allocator.used_slot_count(), 2);
+        allocator.reclaim(1);
+        assert_eq!(allocator.used_slot_count(), 1);
+    }
+
+    #[test]
+    fn test_slot_reclaim_in_release_order() {
+        let mut allocator = BindlessSlotAllocator::new(4, 4, 1);
+        let slots = [allocator.alloc(), allocator.alloc(), allocator.alloc()];
+
+        allocator.release(slots[0], 1);
+        allocator.release(slots[1], 2);
+        allocator.release(slots[2], 3);
+
+        allocator.reclaim(2);
+        assert_eq!(allocator.free_slot_count(), 2);
+        allocator.reclaim(3);
+        assert_eq!(allocator.free_slot_count(), 3);
+    }
+
+    #[test]
+    fn test_slot_generation() {
+        let mut allocator = BindlessSlotAllocator::new(1, 1, 1);
+        let slot = allocator.alloc();
+        let old_generation = allocator.generation(slot);
+        assert!(allocator.is_alive(slot, old_generation));
+
+        // 释放后立即失效
+        allocator.release(slot, 0);
+        assert!(!allocator.is_alive(slot, old_generation));
+
+        // 复用后只有新的 generation 有效
+        allocator.reclaim(0);
+        assert_eq!(allocator.alloc(), slot);
+        let new_generation = allocator.generation(slot);
+        assert_ne!(new_generation, old_generation);
+        assert!(allocator.is_alive(slot, new_generation));
+        assert!(!allocator.is_alive(slot, old_generation));
+    }
+
+    #[test]
+    fn test_slot_growth() {
+        let mut allocator = BindlessSlotAllocator::new(2, 5, 2);
+        allocator.alloc();
+        allocator.alloc();
+        assert_eq!(allocator.capacity(), 2);
+
+        allocator.alloc();
+        assert_eq!(allocator.capacity(), 4);
+
+        // 扩容不会超过上限
+        allocator.alloc();
+        allocator.alloc();
+        assert_eq!(allocator.capacity(), 5);
+    }
+
+    #[test]
+    fn test_slot_reuse_before_growth() {
+        let mut allocator = BindlessSlotAllocator::new(2, 8, 2);
+        let a = allocator.alloc();
+        allocator.alloc();
+        allocator.release(a, 0);
+        allocator.reclaim(0);
+
+        assert_eq!(allocator.alloc(), a);
+        assert_eq!(allocator.capacity(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "bindless slots exhausted")]
+    fn test_slot_exhausted() {
+        let mut allocator = BindlessSlotAllocator::new(2, 2, 1);
+        allocator.alloc();
+        allocator.alloc();
+        allocator.alloc();
+    }
 }
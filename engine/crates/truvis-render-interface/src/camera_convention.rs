@@ -0,0 +1,128 @@
+//! 相机矩阵约定
+//!
+//! 集中描述 view / projection 矩阵所遵循的坐标系约定，`Camera` 据此生成矩阵，
+//! 依赖矩阵的 pass（光追 raygen、SSAO、高度雾、光栅化 viewport）都从这里读取约定。
+//!
+//! # 默认约定
+//! - 世界空间：右手系，Y 轴向上（与场景资源一致，不受本约定影响）
+//! - ViewSpace：右手系，Y 轴向上，相机看向 -Z
+//! - NDC：Y 轴向上，深度范围 [0, 1]，近平面为 0，远平面在无穷远处为 1
+//!
+//! Vulkan 的 NDC 为 Y 轴向下，因此默认约定下光栅化使用负高度的 viewport 进行翻转；
+//! 使用 [`NdcYAxis::Down`] 时翻转由投影矩阵完成，viewport 不再翻转。光栅化的 pass 通过
+//! [`CameraConvention::viewport`] 创建 viewport，不需要各自处理翻转。
+//!
+//! # 注意
+//! - 左右手系只影响 ViewSpace 与 projection 矩阵的形式，两者总是成对变化，最终的 NDC 结果不变
+//! - Vulkan 在不开启 `VK_EXT_depth_clip_control` 时只接受 [0, 1] 的深度范围；
+//!   [`DepthRange::NegativeOneToOne`] 主要用于将矩阵导出给 OpenGL 约定的外部工具，
+//!   在该约定下光栅化的深度测试结果是不正确的
+
+use ash::vk;
+
+/// ViewSpace 的手性
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Handedness {
+    /// 右手系，相机看向 -Z
+    #[default]
+    Right,
+    /// 左手系，相机看向 +Z
+    Left,
+}
+
+/// NDC 中 Y 轴的方向
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum NdcYAxis {
+    /// Y 轴向上，与 OpenGL / D3D 一致
+    #[default]
+    Up,
+    /// Y 轴向下，与 Vulkan 一致
+    Down,
+}
+
+/// NDC 的深度范围
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum DepthRange {
+    /// [0, 1]，Vulkan / D3D 约定
+    #[default]
+    ZeroToOne,
+    /// [-1, 1]，OpenGL 约定
+    NegativeOneToOne,
+}
+
+/// 相机矩阵约定，默认值见模块文档
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CameraConvention {
+    pub handedness: Handedness,
+    pub ndc_y: NdcYAxis,
+    pub depth_range: DepthRange,
+}
+
+// tools
+impl CameraConvention {
+    /// NDC 中 Y 轴相对于「向上」的符号，传给 shader 用于 uv 与 ndc 之间的转换
+    #[inline]
+    pub fn ndc_y_sign(&self) -> f32 {
+        match self.ndc_y {
+            NdcYAxis::Up => 1.0,
+            NdcYAxis::Down => -1.0,
+        }
+    }
+
+    /// 光栅化时是否需要使用负高度的 viewport 翻转 Y 轴
+    #[inline]
+    pub fn flip_viewport_y(&self) -> bool {
+        self.ndc_y == NdcYAxis::Up
+    }
+
+    /// 覆盖 `rect` 的 viewport，深度范围为 [0, 1]
+    ///
+    /// NDC 为 Y 轴向上时使用负高度的 viewport 翻转到 Vulkan 的 Y 轴向下，参见 [`Self::flip_viewport_y`]
+    pub fn viewport(&self, rect: vk::Rect2D) -> vk::Viewport {
+        let (x, y) = (rect.offset.x as f32, rect.offset.y as f32);
+        let (width, height) = (rect.extent.width as f32, rect.extent.height as f32);
+        if self.flip_viewport_y() {
+            vk::Viewport {
+                x,
+                y: y + height,
+                width,
+                height: -height,
+                min_depth: 0.0,
+                max_depth: 1.0,
+            }
+        } else {
+            vk::Viewport {
+                x,
+                y,
+                width,
+                height,
+                min_depth: 0.0,
+                max_depth: 1.0,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_viewport_flips_y_for_y_up_ndc() {
+        let rect = vk::Rect2D {
+            offset: vk::Offset2D { x: -2, y: 3 },
+            extent: vk::Extent2D { width: 100, height: 50 },
+        };
+
+        let viewport = CameraConvention::default().viewport(rect);
+        assert_eq!((viewport.x, viewport.y, viewport.width, viewport.height), (-2.0, 53.0, 100.0, -50.0));
+
+        let convention = CameraConvention {
+            ndc_y: NdcYAxis::Down,
+            ..Default::default()
+        };
+        let viewport = convention.viewport(rect);
+        assert_eq!((viewport.x, viewport.y, viewport.width, viewport.height), (-2.0, 3.0, 100.0, 50.0));
+        assert_eq!((viewport.min_depth, viewport.max_depth), (0.0, 1.0));
+    }
+}
@@ -0,0 +1,155 @@
+/// 记录相机在相邻两帧的 view-projection 矩阵，以及当前帧的子像素 jitter
+///
+/// 用于 TAA 等时域算法：通过上一帧的矩阵将当前帧的世界坐标重投影到上一帧的屏幕上。
+/// 记录的矩阵都不包含 jitter
+#[derive(Copy, Clone, Default)]
+pub struct CameraHistory {
+    view_projection: glam::Mat4,
+    prev_view_projection: glam::Mat4,
+    /// 当前帧的子像素偏移，单位为像素，范围为 [-0.5, 0.5)
+    jitter: glam::Vec2,
+    jitter_index: u32,
+
+    /// 为 false 时上一帧的矩阵不可用（第一帧或者调用了 [`Self::reset`]），此时与当前帧相同
+    prev_valid: bool,
+    /// 自上一次 reset 之后是否有过 update
+    has_current: bool,
+}
+// update
+impl CameraHistory {
+    /// jitter 使用的 Halton 序列的长度
+    pub const JITTER_SEQUENCE_LEN: u32 = 8;
+
+    /// call phase: BeforeRender-CollectData
+    ///
+    /// 当前帧的矩阵成为上一帧的矩阵；`jitter_enabled` 为 false 时 jitter 为 0
+    pub fn update(&mut self, view_projection: glam::Mat4, jitter_enabled: bool) {
+        self.prev_valid = self.has_current;
+        self.prev_view_projection = if self.has_current { self.view_projection } else { view_projection };
+        self.view_projection = view_projection;
+        self.has_current = true;
+
+        if jitter_enabled {
+            self.jitter_index = (self.jitter_index + 1) % Self::JITTER_SEQUENCE_LEN;
+            // 序号从 1 开始，跳过 Halton 序列的 0
+            self.jitter = glam::vec2(Self::halton(self.jitter_index + 1, 2), Self::halton(self.jitter_index + 1, 3))
+                - glam::Vec2::splat(0.5);
+        } else {
+            self.jitter = glam::Vec2::ZERO;
+        }
+    }
+
+    /// 丢弃历史，例如相机发生突变或者分辨率变化时；下一次 update 时上一帧的矩阵与当前帧相同
+    pub fn reset(&mut self) {
+        self.prev_valid = false;
+        self.has_current = false;
+    }
+}
+// getters
+impl CameraHistory {
+    #[inline]
+    pub fn view_projection(&self) -> glam::Mat4 {
+        self.view_projection
+    }
+
+    #[inline]
+    pub fn prev_view_projection(&self) -> glam::Mat4 {
+        self.prev_view_projection
+    }
+
+    /// 上一帧的矩阵是否来自真实的上一帧
+    #[inline]
+    pub fn prev_valid(&self) -> bool {
+        self.prev_valid
+    }
+
+    /// 当前帧的子像素偏移，单位为像素
+    #[inline]
+    pub fn jitter(&self) -> glam::Vec2 {
+        self.jitter
+    }
+}
+// tools
+impl CameraHistory {
+    /// 以 `base` 为底的 Halton 序列的第 `index` 项，范围为 [0, 1)
+    pub fn halton(mut index: u32, base: u32) -> f32 {
+        let mut result = 0.0;
+        let mut fraction = 1.0;
+        while index > 0 {
+            fraction /= base as f32;
+            result += fraction * (index % base) as f32;
+            index /= base;
+        }
+        result
+    }
+
+    /// 将 `projection` 在 NDC 中平移 `jitter` 个像素，透视投影和正交投影都适用
+    pub fn jittered_projection(projection: glam::Mat4, jitter: glam::Vec2, extent: glam::Vec2) -> glam::Mat4 {
+        let offset = jitter * 2.0 / extent;
+        glam::Mat4::from_translation(offset.extend(0.0)) * projection
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_halton_sequence() {
+        let base2 = (1..5).map(|i| CameraHistory::halton(i, 2)).collect::<Vec<_>>();
+        assert_eq!(base2, [0.5, 0.25, 0.75, 0.125]);
+
+        let base3 = (1..4).map(|i| CameraHistory::halton(i, 3)).collect::<Vec<_>>();
+        for (value, expected) in base3.iter().zip([1.0 / 3.0, 2.0 / 3.0, 1.0 / 9.0]) {
+            assert!((value - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_prev_view_projection_after_reset() {
+        let a = glam::Mat4::from_translation(glam::vec3(1.0, 0.0, 0.0));
+        let b = glam::Mat4::from_translation(glam::vec3(2.0, 0.0, 0.0));
+
+        let mut history = CameraHistory::default();
+        history.update(a, false);
+        assert!(!history.prev_valid());
+        assert_eq!(history.prev_view_projection(), a);
+
+        history.update(b, false);
+        assert!(history.prev_valid());
+        assert_eq!(history.prev_view_projection(), a);
+        assert_eq!(history.view_projection(), b);
+
+        history.reset();
+        history.update(a, false);
+        assert!(!history.prev_valid());
+        assert_eq!(history.prev_view_projection(), a);
+    }
+
+    #[test]
+    fn test_jitter_is_within_pixel() {
+        let mut history = CameraHistory::default();
+        for _ in 0..CameraHistory::JITTER_SEQUENCE_LEN * 2 {
+            history.update(glam::Mat4::IDENTITY, true);
+            let jitter = history.jitter();
+            assert!(jitter.abs().max_element() <= 0.5);
+        }
+
+        history.update(glam::Mat4::IDENTITY, false);
+        assert_eq!(history.jitter(), glam::Vec2::ZERO);
+    }
+
+    #[test]
+    fn test_jittered_projection_offsets_ndc() {
+        let projection = glam::Mat4::perspective_infinite_rh(1.0, 1.0, 0.1);
+        let extent = glam::vec2(100.0, 50.0);
+        let jittered = CameraHistory::jittered_projection(projection, glam::vec2(0.5, -0.5), extent);
+
+        let point = glam::vec4(0.3, -0.2, -5.0, 1.0);
+        let ndc = (projection * point).truncate() / (projection * point).w;
+        let jittered_ndc = (jittered * point).truncate() / (jittered * point).w;
+        assert!((jittered_ndc.x - ndc.x - 0.01).abs() < 1e-5);
+        assert!((jittered_ndc.y - ndc.y + 0.02).abs() < 1e-5);
+        assert!((jittered_ndc.z - ndc.z).abs() < 1e-6);
+    }
+}
@@ -14,15 +14,19 @@ use truvis_gfx::{
 ///
 /// # Frames in Flight
 /// - 每帧独立的 CommandPool（避免同步冲突）
+/// - CommandPool 记录最后一次使用它的帧 timeline 值，只有 GPU 完成该帧之后才能重置
 /// - 帧结束时统一释放命令缓冲
 /// - 命令缓冲自动添加帧标签：`[F42A]my-pass`
 pub struct CmdAllocator {
     /// 为每个 frame 分配一个 command pool
-    graphics_command_pools: [GfxCommandPool; FrameCounter::fif_count()],
+    graphics_command_pools: Vec<GfxCommandPool>,
 
     /// 每个 command pool 已经分配出去的 command buffer，用于集中 free
     /// 或其他操作
-    allocated_command_buffers: [Vec<GfxCommandBuffer>; FrameCounter::fif_count()],
+    allocated_command_buffers: Vec<Vec<GfxCommandBuffer>>,
+
+    /// 每个 command pool 最后一次被使用的帧 timeline 值
+    pool_last_use_frame_ids: Vec<u64>,
 }
 
 // new & init
@@ -34,18 +38,21 @@ impl Default for CmdAllocator {
 
 impl CmdAllocator {
     pub fn new() -> Self {
-        let graphics_command_pools = FrameCounter::frame_labes().map(|i| {
-            GfxCommandPool::new(
-                Gfx::get().gfx_queue_family(),
-                vk::CommandPoolCreateFlags::TRANSIENT,
-                &format!("render_context_graphics_command_pool_{}", i),
-            )
-        });
-        let allocated_command_buffers = FrameCounter::frame_labes().map(|_| Vec::new());
+        let graphics_command_pools = FrameCounter::frame_labes()
+            .map(|i| {
+                GfxCommandPool::new(
+                    Gfx::get().gfx_queue_family(),
+                    vk::CommandPoolCreateFlags::TRANSIENT,
+                    &format!("render_context_graphics_command_pool_{}", i),
+                )
+            })
+            .collect();
+        let allocated_command_buffers = FrameCounter::frame_labes().map(|_| Vec::new()).collect();
 
         Self {
             graphics_command_pools,
             allocated_command_buffers,
+            pool_last_use_frame_ids: vec![0; FrameCounter::fif_count()],
         }
     }
 }
@@ -57,6 +64,14 @@ impl Drop for CmdAllocator {
         }
     }
 }
+// getter
+impl CmdAllocator {
+    /// `frame_label` 对应的帧分配的 command buffer 数量，即每帧录制的 command buffer 数量
+    #[inline]
+    pub fn frame_command_buffer_count(&self, frame_label: FrameLabel) -> usize {
+        self.allocated_command_buffers[*frame_label].len()
+    }
+}
 // destroy
 impl CmdAllocator {
     pub fn destroy(self) {}
@@ -73,10 +88,24 @@ impl CmdAllocator {
     }
 
     /// 重置当前 frame 的 command buffers，这些 command buffers 可以重新录制
-    pub fn reset_frame_commands(&mut self, frame_label: FrameLabel) {
+    ///
+    /// 当前 frame 的 command pool 在这一帧中被使用；上一次使用它的帧必须已经被 GPU 完成，
+    /// 即不大于 [`FrameCounter::completed_frame_id`]
+    pub fn reset_frame_commands(&mut self, frame_counter: &FrameCounter) {
         let _span = tracy_client::span!("reset_frame_commands");
 
-        self.graphics_command_pools[*frame_label].reset_command_pool();
+        let frame_label = *frame_counter.frame_label();
+        let last_use_frame_id = self.pool_last_use_frame_ids[frame_label];
+        assert!(
+            last_use_frame_id <= frame_counter.completed_frame_id(),
+            "command pool {} is still used by frame {}, completed frame is {}",
+            frame_label,
+            last_use_frame_id,
+            frame_counter.completed_frame_id()
+        );
+
+        self.graphics_command_pools[frame_label].reset_command_pool();
+        self.pool_last_use_frame_ids[frame_label] = frame_counter.frame_id();
     }
 
     /// 释放当前 frame 的 command buffers，这些 commands 无法再使用
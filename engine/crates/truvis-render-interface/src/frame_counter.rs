@@ -1,55 +1,136 @@
-use crate::pipeline_settings::FrameLabel;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::pipeline_settings::{DefaultRendererSettings, FrameLabel};
+
+/// per-frame 资源的份数，参见 [`FrameCounter::set_fif_count`]
+static FIF_COUNT: AtomicUsize = AtomicUsize::new(DefaultRendererSettings::DEFAULT_FRAMES_IN_FLIGHT);
 
 pub struct FrameCounter {
     /// 当前的帧序号，一直累加
+    ///
+    /// 同时也是帧 timeline 的值：第 `frame_id` 帧的渲染完成时 signal `frame_id`
     frame_id: u64,
-    frame_limit: f32,
+    /// GPU 已经完成的帧 timeline 值，参见 [`Self::completed_frame_id`]
+    completed_frame_id: u64,
+    /// 同时在 GPU 上执行的帧数，不超过 [`Self::fif_count`]
+    frames_in_flight: usize,
 }
 // new & init
 impl FrameCounter {
-    pub fn new(init_frame_id: u64, frame_limit: f32) -> Self {
+    /// `frames_in_flight` 会被限制在 `1..=fif_count()` 之间，通常与 [`Self::fif_count`] 相同
+    pub fn new(init_frame_id: u64, frames_in_flight: usize) -> Self {
+        let clamped_frames_in_flight = frames_in_flight.clamp(1, Self::fif_count());
+        if clamped_frames_in_flight != frames_in_flight {
+            log::warn!(
+                "frames in flight {} is out of range 1..={}, use {}",
+                frames_in_flight,
+                Self::fif_count(),
+                clamped_frames_in_flight
+            );
+        }
+
         Self {
             frame_id: init_frame_id,
-            frame_limit,
+            completed_frame_id: 0,
+            frames_in_flight: clamped_frames_in_flight,
         }
     }
 }
 // update
 impl FrameCounter {
+    /// 设置 per-frame 资源的份数，会被限制在 `1..=MAX_FIF_COUNT` 之间，返回实际使用的数量
+    ///
+    /// 由 renderer 在初始化时根据配置设置，需要在创建任何 per-frame 资源之前调用
+    pub fn set_fif_count(fif_count: usize) -> usize {
+        let clamped_fif_count = fif_count.clamp(1, Self::MAX_FIF_COUNT);
+        if clamped_fif_count != fif_count {
+            log::warn!(
+                "fif count {} is out of range 1..={}, use {}",
+                fif_count,
+                Self::MAX_FIF_COUNT,
+                clamped_fif_count
+            );
+        }
+        FIF_COUNT.store(clamped_fif_count, Ordering::Relaxed);
+        clamped_fif_count
+    }
+
     #[inline]
     pub fn next_frame(&mut self) {
         self.frame_id = self.frame_id.wrapping_add(1);
     }
+
+    /// 记录 GPU 已经完成的帧 timeline 值，由 renderer 在帧开始等待之后调用
+    #[inline]
+    pub fn set_completed_frame_id(&mut self, completed_frame_id: u64) {
+        self.completed_frame_id = self.completed_frame_id.max(completed_frame_id);
+    }
 }
 // getters
 impl FrameCounter {
-    const FIF_COUNT: usize = 3;
+    /// fif count 的上限，受 [`FrameLabel`] 的数量限制
+    pub const MAX_FIF_COUNT: usize = 3;
+
     #[inline]
     pub fn frame_id(&self) -> u64 {
         self.frame_id
     }
+    /// GPU 已经完成的帧 timeline 值，最后使用的 timeline 值不大于它的资源可以安全回收
     #[inline]
-    pub fn frame_limit(&self) -> f32 {
-        self.frame_limit
+    pub fn completed_frame_id(&self) -> u64 {
+        self.completed_frame_id
     }
+    /// per-frame 资源的份数，也是 frames in flight 的上限，来源于初始化配置，参见 [`Self::set_fif_count`]
+    ///
+    /// per-frame 资源都按照这个数量分配
     #[inline]
-    pub fn frame_delta_time_limit_us(&self) -> f32 {
-        1000.0 * 1000.0 / self.frame_limit()
+    pub fn fif_count() -> usize {
+        FIF_COUNT.load(Ordering::Relaxed)
     }
+    /// 同时在 GPU 上执行的帧数，由初始化配置决定
     #[inline]
-    pub const fn fif_count() -> usize {
-        Self::FIF_COUNT
+    pub fn frames_in_flight(&self) -> usize {
+        self.frames_in_flight
     }
+    /// 所有 per-frame 资源的 label，数量为 [`Self::fif_count`]
     #[inline]
-    pub const fn frame_labes() -> [FrameLabel; Self::FIF_COUNT] {
-        [FrameLabel::A, FrameLabel::B, FrameLabel::C]
+    pub fn frame_labes() -> impl Iterator<Item = FrameLabel> {
+        (0..Self::fif_count()).map(FrameLabel::from_usize)
     }
     #[inline]
     pub fn frame_label(&self) -> FrameLabel {
-        FrameLabel::from_usize(self.frame_id as usize % Self::fif_count())
+        FrameLabel::from_usize(self.frame_id as usize % self.frames_in_flight)
     }
     #[inline]
     pub fn frame_name(&self) -> String {
         format!("[F{}{}]", self.frame_id, self.frame_label())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_label_cycles_by_frames_in_flight() {
+        let mut frame_counter = FrameCounter::new(1, 2);
+        let mut labels = vec![];
+        for _ in 0..4 {
+            labels.push(*frame_counter.frame_label());
+            frame_counter.next_frame();
+        }
+        assert_eq!(labels, [1, 0, 1, 0]);
+    }
+
+    #[test]
+    fn test_frames_in_flight_is_clamped() {
+        assert_eq!(FrameCounter::new(1, 0).frames_in_flight(), 1);
+        assert_eq!(FrameCounter::new(1, 8).frames_in_flight(), FrameCounter::fif_count());
+    }
+
+    #[test]
+    fn test_frame_labels_match_fif_count() {
+        let labels = FrameCounter::frame_labes().map(|frame_label| *frame_label).collect::<Vec<_>>();
+        assert_eq!(labels, (0..FrameCounter::fif_count()).collect::<Vec<_>>());
+    }
+}
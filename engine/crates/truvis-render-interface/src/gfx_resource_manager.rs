@@ -1,8 +1,8 @@
-use crate::frame_counter::FrameCounter;
 use crate::handles::{GfxBufferHandle, GfxImageHandle, GfxImageViewHandle};
 use ash::vk;
 use slotmap::{SecondaryMap, SlotMap};
 use std::collections::HashMap;
+use truvis_gfx::commands::timeline::GfxRetireQueue;
 use truvis_gfx::resources::buffer::GfxBuffer;
 use truvis_gfx::resources::image::{GfxImage, GfxImageCreateInfo};
 use truvis_gfx::resources::image_view::GfxImageView;
@@ -12,7 +12,7 @@ use truvis_gfx::resources::image_view::GfxImageViewDesc;
 ///
 /// 负责管理所有的 GPU 资源，包括 Buffer、Image 和 ImageView。
 /// 使用 SlotMap 存储资源，对外提供轻量级的 Handle。
-/// 支持资源的延迟销毁（Frames in Flight）。
+/// 支持资源的延迟销毁：资源记录最后使用的帧 timeline 值，GPU 完成之后才真正销毁。
 pub struct GfxResourceManager {
     /// 存储所有的 Buffer 资源
     buffer_pool: SlotMap<GfxBufferHandle, GfxBuffer>,
@@ -26,10 +26,9 @@ pub struct GfxResourceManager {
     /// 用于缓存：ImageHandle -> 所有关联的 ImageViewHandle
     image_to_views: SecondaryMap<GfxImageHandle, Vec<GfxImageViewHandle>>,
 
-    // 待销毁队列 (用于延迟销毁，例如在帧结束时)
-    // (handle, frame_index)
-    pending_destroy_buffers: Vec<(GfxBufferHandle, u64)>,
-    pending_destroy_images: Vec<(GfxImageHandle, u64)>,
+    /// 待销毁队列，按照最后使用的帧 timeline 值回收
+    pending_destroy_buffers: GfxRetireQueue<GfxBufferHandle>,
+    pending_destroy_images: GfxRetireQueue<GfxImageHandle>,
 
     destroyed: bool,
 }
@@ -49,8 +48,8 @@ impl GfxResourceManager {
             image_view_lookup: HashMap::new(),
             image_to_views: SecondaryMap::new(),
 
-            pending_destroy_buffers: Vec::new(),
-            pending_destroy_images: Vec::new(),
+            pending_destroy_buffers: GfxRetireQueue::new(),
+            pending_destroy_images: GfxRetireQueue::new(),
 
             destroyed: false,
         }
@@ -103,22 +102,12 @@ impl Drop for GfxResourceManager {
 impl GfxResourceManager {
     /// 清理已过期的资源
     ///
-    /// 检查待销毁队列，销毁那些已经不再被 GPU 使用的资源（即提交销毁时的帧索引 <= completed_frame_index）。
-    pub fn cleanup(&mut self, current_frame_id: u64) {
+    /// 检查待销毁队列，销毁那些已经不再被 GPU 使用的资源（即最后使用的 timeline 值 <= completed_frame_id）。
+    pub fn cleanup(&mut self, completed_frame_id: u64) {
         let _span = tracy_client::span!("ResourceManager::cleanup");
 
-        const FIF: u64 = FrameCounter::fif_count() as u64;
-
         // 清理 buffers
-        let mut buffers_to_destroy = Vec::new();
-        self.pending_destroy_buffers.retain(|(buffer_handle, frame_index)| {
-            if *frame_index + FIF <= current_frame_id {
-                buffers_to_destroy.push(*buffer_handle);
-                false
-            } else {
-                true
-            }
-        });
+        let buffers_to_destroy = self.pending_destroy_buffers.drain_completed(completed_frame_id).collect::<Vec<_>>();
         for buffer_handle in buffers_to_destroy {
             if let Some(buffer) = self.buffer_pool.remove(buffer_handle) {
                 buffer.destroy()
@@ -126,15 +115,7 @@ impl GfxResourceManager {
         }
 
         // 清理 images
-        let mut images_to_destroy = Vec::new();
-        self.pending_destroy_images.retain(|(image_handle, frame_index)| {
-            if *frame_index + FIF <= current_frame_id {
-                images_to_destroy.push(*image_handle);
-                false
-            } else {
-                true
-            }
-        });
+        let images_to_destroy = self.pending_destroy_images.drain_completed(completed_frame_id).collect::<Vec<_>>();
         for image_handle in &images_to_destroy {
             // 先清理基于 image 创建的 image views
             if let Some(view_handles) = self.image_to_views.remove(*image_handle) {
@@ -185,11 +166,11 @@ impl GfxResourceManager {
         self.buffer_pool.get_mut(handle)
     }
 
-    /// 销毁 Buffer（指定帧索引）
+    /// 销毁 Buffer（指定最后使用的帧）
     ///
-    /// 将 Buffer 加入待销毁队列，在 `current_frame_index` 对应的帧完成后销毁。
-    pub fn destroy_buffer(&mut self, handle: GfxBufferHandle, current_frame_index: u64) {
-        self.pending_destroy_buffers.push((handle, current_frame_index));
+    /// 将 Buffer 加入待销毁队列，在帧 timeline 达到 `last_use_frame_id` 之后销毁。
+    pub fn destroy_buffer(&mut self, handle: GfxBufferHandle, last_use_frame_id: u64) {
+        self.pending_destroy_buffers.retire(last_use_frame_id, handle);
     }
 }
 // Image API
@@ -213,11 +194,11 @@ impl GfxResourceManager {
         self.image_pool.get(handle)
     }
 
-    /// 销毁 Image（指定帧索引）
+    /// 销毁 Image（指定最后使用的帧）
     ///
-    /// 同时会销毁默认的 ImageView。
-    pub fn destroy_image(&mut self, handle: GfxImageHandle, current_frame_index: u64) {
-        self.pending_destroy_images.push((handle, current_frame_index));
+    /// 在帧 timeline 达到 `last_use_frame_id` 之后销毁，同时会销毁默认的 ImageView。
+    pub fn destroy_image(&mut self, handle: GfxImageHandle, last_use_frame_id: u64) {
+        self.pending_destroy_images.retire(last_use_frame_id, handle);
     }
 
     /// 立即销毁 Image 及其关联的所有 ImageView
@@ -240,7 +221,7 @@ impl GfxResourceManager {
         }
 
         // 从待销毁队列中移除（如果存在）
-        self.pending_destroy_images.retain(|(h, _)| *h != handle);
+        self.pending_destroy_images.retain(|h| *h != handle);
 
         // 销毁 image 本身
         if let Some(image) = self.image_pool.remove(handle) {
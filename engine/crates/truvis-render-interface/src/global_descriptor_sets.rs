@@ -4,6 +4,8 @@ use ash::vk;
 use itertools::Itertools;
 use std::rc::Rc;
 use truvis_descriptor_layout_macro::DescriptorBinding;
+use truvis_descriptor_layout_trait::DescriptorBindingLayout;
+use truvis_gfx::commands::timeline::GfxRetireQueue;
 use truvis_gfx::descriptors::descriptor::{GfxDescriptorSet, GfxDescriptorSetLayout};
 use truvis_gfx::descriptors::descriptor_pool::{GfxDescriptorPool, GfxDescriptorPoolCreateInfo};
 
@@ -33,11 +35,12 @@ pub struct BindlessDescriptorBinding {
     #[flags = "PARTIALLY_BOUND | UPDATE_AFTER_BIND"]
     _uavs: (),
 
+    /// 实际数量在分配 descriptor set 时指定，参见 [`GlobalDescriptorSets::ensure_bindless_srv_capacity`]
     #[binding = 2]
     #[descriptor_type = "SAMPLED_IMAGE"]
     #[stage = "FRAGMENT | RAYGEN_KHR | CLOSEST_HIT_KHR | ANY_HIT_KHR | CALLABLE_KHR | MISS_KHR | COMPUTE"]
-    #[count = 128]
-    #[flags = "PARTIALLY_BOUND | UPDATE_AFTER_BIND"]
+    #[count = "runtime"]
+    #[flags = "PARTIALLY_BOUND | UPDATE_AFTER_BIND | VARIABLE_DESCRIPTOR_COUNT"]
     _srvs: (),
 }
 
@@ -61,16 +64,25 @@ pub struct GlobalDescriptorSets {
     set_0_static: GfxDescriptorSet<StaticDescriptorBinding>,
 
     layout_1_bindless: GfxDescriptorSetLayout<BindlessDescriptorBinding>,
-    set_1_bindless: [GfxDescriptorSet<BindlessDescriptorBinding>; FrameCounter::fif_count()],
+    set_1_bindless: Vec<GfxDescriptorSet<BindlessDescriptorBinding>>,
+    /// 每个 bindless set 使用独立的 pool，扩容时连同 pool 一起重建
+    bindless_pools: Vec<GfxDescriptorPool>,
+    /// 每个 bindless set 中 srv 数组实际分配的数量
+    bindless_srv_capacities: Vec<u32>,
+    /// 扩容时被替换的 pool，GPU 完成最后一次使用它的帧之后销毁
+    retired_bindless_pools: GfxRetireQueue<GfxDescriptorPool>,
 
     layout_2_perframe: GfxDescriptorSetLayout<PerFrameDescriptorBinding>,
-    set_2_perframe: [GfxDescriptorSet<PerFrameDescriptorBinding>; FrameCounter::fif_count()],
+    set_2_perframe: Vec<GfxDescriptorSet<PerFrameDescriptorBinding>>,
 
     _descriptor_pool: GfxDescriptorPool,
 }
 // new & init
 impl GlobalDescriptorSets {
-    pub fn new() -> Self {
+    /// # Params
+    /// * `bindless_srv_capacity` - bindless srv 数组的初始容量，之后可以通过
+    ///   [`Self::ensure_bindless_srv_capacity`] 扩容
+    pub fn new(bindless_srv_capacity: u32) -> Self {
         let descriptor_pool = Self::init_descriptor_pool();
 
         let layout_0_static = GfxDescriptorSetLayout::<StaticDescriptorBinding>::new(
@@ -87,25 +99,33 @@ impl GlobalDescriptorSets {
             vk::DescriptorSetLayoutCreateFlags::UPDATE_AFTER_BIND_POOL,
             "bindless-layout",
         );
-        let set_1_bindless = FrameCounter::frame_labes().map(|frame_label| {
-            GfxDescriptorSet::<BindlessDescriptorBinding>::new(
-                &descriptor_pool,
-                &layout_1_bindless,
-                format!("bindless-descriptor-set-{frame_label}"),
-            )
-        });
+        let bindless_pools = FrameCounter::frame_labes()
+            .map(|frame_label| Self::init_bindless_descriptor_pool(bindless_srv_capacity, frame_label))
+            .collect_vec();
+        let set_1_bindless = FrameCounter::frame_labes()
+            .map(|frame_label| {
+                Self::alloc_bindless_set(
+                    &bindless_pools[*frame_label],
+                    &layout_1_bindless,
+                    bindless_srv_capacity,
+                    frame_label,
+                )
+            })
+            .collect_vec();
 
         let layout_2_perframe = GfxDescriptorSetLayout::<PerFrameDescriptorBinding>::new(
             vk::DescriptorSetLayoutCreateFlags::empty(),
             "perframe-layout",
         );
-        let set_2_perframe = FrameCounter::frame_labes().map(|frame_label| {
-            GfxDescriptorSet::<PerFrameDescriptorBinding>::new(
-                &descriptor_pool,
-                &layout_2_perframe,
-                format!("perframe-descriptor-set-{frame_label}"),
-            )
-        });
+        let set_2_perframe = FrameCounter::frame_labes()
+            .map(|frame_label| {
+                GfxDescriptorSet::<PerFrameDescriptorBinding>::new(
+                    &descriptor_pool,
+                    &layout_2_perframe,
+                    format!("perframe-descriptor-set-{frame_label}"),
+                )
+            })
+            .collect_vec();
 
         Self {
             layout_0_static,
@@ -113,6 +133,9 @@ impl GlobalDescriptorSets {
 
             layout_1_bindless,
             set_1_bindless,
+            bindless_pools,
+            bindless_srv_capacities: vec![bindless_srv_capacity; FrameCounter::fif_count()],
+            retired_bindless_pools: GfxRetireQueue::new(),
 
             layout_2_perframe,
             set_2_perframe,
@@ -144,10 +167,39 @@ impl GlobalDescriptorSets {
 
         GfxDescriptorPool::new(pool_ci, "renderer")
     }
-}
-impl Default for GlobalDescriptorSets {
-    fn default() -> Self {
-        Self::new()
+
+    /// 只用于分配一个 bindless set，容量和 set 中各个数组的实际数量一致
+    fn init_bindless_descriptor_pool(srv_capacity: u32, frame_label: FrameLabel) -> GfxDescriptorPool {
+        let pool_size = BindlessDescriptorBinding::get_shader_bindings()
+            .iter()
+            .map(|item| vk::DescriptorPoolSize {
+                ty: item.descriptor_type,
+                descriptor_count: if Some(item.binding) == BindlessDescriptorBinding::variable_binding() {
+                    srv_capacity
+                } else {
+                    item.count
+                },
+            })
+            .collect_vec();
+
+        let pool_ci =
+            Rc::new(GfxDescriptorPoolCreateInfo::new(vk::DescriptorPoolCreateFlags::UPDATE_AFTER_BIND, 1, pool_size));
+
+        GfxDescriptorPool::new(pool_ci, &format!("bindless-{frame_label}"))
+    }
+
+    fn alloc_bindless_set(
+        descriptor_pool: &GfxDescriptorPool,
+        layout: &GfxDescriptorSetLayout<BindlessDescriptorBinding>,
+        srv_capacity: u32,
+        frame_label: FrameLabel,
+    ) -> GfxDescriptorSet<BindlessDescriptorBinding> {
+        GfxDescriptorSet::<BindlessDescriptorBinding>::new_with_variable_count(
+            descriptor_pool,
+            layout,
+            srv_capacity,
+            format!("bindless-descriptor-set-{frame_label}"),
+        )
     }
 }
 // destroy
@@ -164,6 +216,46 @@ impl Drop for GlobalDescriptorSets {
         self.destroy_mut();
     }
 }
+// update
+impl GlobalDescriptorSets {
+    /// 确保 frame_label 对应的 bindless set 中 srv 数组的容量不小于 `srv_capacity`
+    ///
+    /// 容量不足时重新分配一个更大的 set，旧的 set 中的 descriptor 不会迁移，需要调用者重新写入。
+    /// layout 不变，因此所有 pipeline layout 都不需要重建。
+    ///
+    /// 旧的 set 最晚在上一帧中被使用，跟随旧的 pool 一起在 GPU 完成上一帧之后销毁
+    ///
+    /// # Phase: Before Render
+    pub fn ensure_bindless_srv_capacity(&mut self, frame_counter: &FrameCounter, srv_capacity: u32) {
+        for pool in self.retired_bindless_pools.drain_completed(frame_counter.completed_frame_id()) {
+            pool.destroy();
+        }
+
+        let frame_label = frame_counter.frame_label();
+        if self.bindless_srv_capacities[*frame_label] >= srv_capacity {
+            return;
+        }
+        log::info!(
+            "grow bindless srv array of frame {}: {} -> {}",
+            frame_label,
+            self.bindless_srv_capacities[*frame_label],
+            srv_capacity
+        );
+
+        let old_pool = std::mem::replace(
+            &mut self.bindless_pools[*frame_label],
+            Self::init_bindless_descriptor_pool(srv_capacity, frame_label),
+        );
+        self.retired_bindless_pools.retire(frame_counter.frame_id().saturating_sub(1), old_pool);
+        self.set_1_bindless[*frame_label] = Self::alloc_bindless_set(
+            &self.bindless_pools[*frame_label],
+            &self.layout_1_bindless,
+            srv_capacity,
+            frame_label,
+        );
+        self.bindless_srv_capacities[*frame_label] = srv_capacity;
+    }
+}
 // getters
 impl GlobalDescriptorSets {
     #[inline]
@@ -176,6 +268,11 @@ impl GlobalDescriptorSets {
         &self.set_1_bindless[*frame_label]
     }
 
+    #[inline]
+    pub fn bindless_srv_capacity(&self, frame_label: FrameLabel) -> u32 {
+        self.bindless_srv_capacities[*frame_label]
+    }
+
     #[inline]
     pub fn current_perframe_set(&self, frame_label: FrameLabel) -> &GfxDescriptorSet<PerFrameDescriptorBinding> {
         &self.set_2_perframe[*frame_label]
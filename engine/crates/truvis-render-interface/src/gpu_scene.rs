@@ -1,13 +1,15 @@
-use crate::bindless_manager::BindlessManager;
+use crate::bindless_manager::{BindlessManager, BindlessSrvHandle};
 use crate::frame_counter::FrameCounter;
 use crate::gfx_resource_manager::GfxResourceManager;
 use crate::gpu_scene::helper::ImageLoader;
 use crate::handles::{GfxImageHandle, GfxImageViewHandle};
+use crate::ibl_maps::IblMaps;
 use crate::pipeline_settings::FrameLabel;
-use crate::render_data::RenderData;
+use crate::render_data::{InstanceBatch, InstanceBatches, RenderData};
 use ash::vk;
 use itertools::Itertools;
-use std::path::PathBuf;
+use std::cell::Cell;
+use std::path::{Path, PathBuf};
 use truvis_crate_tools::resource::TruvisPath;
 use truvis_gfx::basic::bytes::BytesConvert;
 use truvis_gfx::{
@@ -15,7 +17,7 @@ use truvis_gfx::{
         barrier::{GfxBarrierMask, GfxBufferBarrier},
         command_buffer::GfxCommandBuffer,
     },
-    raytracing::acceleration::GfxAcceleration,
+    raytracing::acceleration::{GfxAcceleration, GfxUpdatableTlas},
     resources::special_buffers::structured_buffer::GfxStructuredBuffer,
 };
 use truvis_shader_binding::truvisl;
@@ -25,6 +27,8 @@ struct GpuSceneBuffers {
     scene_buffer: GfxStructuredBuffer<truvisl::GPUScene>,
     light_buffer: GfxStructuredBuffer<truvisl::PointLight>,
     light_stage_buffer: GfxStructuredBuffer<truvisl::PointLight>,
+    rect_light_buffer: GfxStructuredBuffer<truvisl::RectLight>,
+    rect_light_stage_buffer: GfxStructuredBuffer<truvisl::RectLight>,
     material_buffer: GfxStructuredBuffer<truvisl::PBRMaterial>,
     material_stage_buffer: GfxStructuredBuffer<truvisl::PBRMaterial>,
     geometry_buffer: GfxStructuredBuffer<truvisl::Geometry>,
@@ -36,13 +40,25 @@ struct GpuSceneBuffers {
     geometry_indirect_buffer: GfxStructuredBuffer<u32>,
     geometry_indirect_stage_buffer: GfxStructuredBuffer<u32>,
 
-    // TODO 使用 frame id 来标记是否过期，scene manager 里面也需要有相应的标记
-    tlas: Option<GfxAcceleration>,
+    tlas: Option<GfxUpdatableTlas>,
+
+    // ========== 同步状态 ==========
+    /// 该组 buffer 已经同步到的场景 generation，None 表示需要全量上传
+    synced_generation: Option<u64>,
+    /// 该组 buffer 同步时场景的结构 generation
+    synced_structure_generation: Option<u64>,
+    /// 已上传材质引用的贴图 bindless 下标 (diffuse, normal, detail normal, metallic roughness, emissive, occlusion)
+    ///
+    /// bindless 下标会在注册新的贴图后重新分配，此时材质本身没有修改也需要重新上传
+    synced_material_textures: Vec<[i32; 6]>,
+    /// 已上传的 GPUScene 数据，内容没有变化时跳过上传
+    synced_scene_data: Vec<u8>,
 }
 // init & destroy
 impl GpuSceneBuffers {
     fn new(frame_label: FrameLabel) -> Self {
         let max_light_cnt = 512;
+        let max_rect_light_cnt = 64;
         let max_material_cnt = 1024;
         let max_geometry_cnt = 1024 * 8;
         let max_instance_cnt = 1024;
@@ -54,6 +70,14 @@ impl GpuSceneBuffers {
                 max_light_cnt,
                 format!("light stage buffer-{}", frame_label),
             ),
+            rect_light_buffer: GfxStructuredBuffer::new_ssbo(
+                max_rect_light_cnt,
+                format!("rect light buffer-{}", frame_label),
+            ),
+            rect_light_stage_buffer: GfxStructuredBuffer::new_stage_buffer(
+                max_rect_light_cnt,
+                format!("rect light stage buffer-{}", frame_label),
+            ),
             material_buffer: GfxStructuredBuffer::new_ssbo(
                 max_material_cnt,
                 format!("material buffer-{}", frame_label),
@@ -95,68 +119,170 @@ impl GpuSceneBuffers {
                 format!("instance geometry stage buffer-{}", frame_label),
             ),
             tlas: None,
+
+            synced_generation: None,
+            synced_structure_generation: None,
+            synced_material_textures: Vec::new(),
+            synced_scene_data: Vec::new(),
         }
     }
+
+    /// 判断 generation 为 `generation` 的元素是否需要上传
+    #[inline]
+    fn is_dirty(&self, generation: u64) -> bool {
+        self.synced_generation.is_none_or(|synced| generation > synced)
+    }
+}
+
+/// 单帧 GpuScene 实际上传到 GPU 的字节数统计
+#[derive(Copy, Clone, Debug, Default)]
+pub struct GpuSceneUploadStats {
+    pub scene_bytes: vk::DeviceSize,
+    pub instance_bytes: vk::DeviceSize,
+    pub material_bytes: vk::DeviceSize,
+    pub light_bytes: vk::DeviceSize,
+    pub geometry_bytes: vk::DeviceSize,
+}
+impl GpuSceneUploadStats {
+    #[inline]
+    pub fn total_bytes(&self) -> vk::DeviceSize {
+        self.scene_bytes + self.instance_bytes + self.material_bytes + self.light_bytes + self.geometry_bytes
+    }
+}
+
+/// 单帧通过 GpuScene 录制的光栅化 draw call 统计
+#[derive(Copy, Clone, Debug, Default)]
+pub struct GpuSceneDrawStats {
+    /// draw call 的数量，每个 submesh 一次
+    pub draw_calls: u32,
+    /// 绘制的实例数量，同一个实例被多个 pass 绘制时会重复计数
+    pub instances: u32,
 }
 
 /// 用于构建传输到 GPU 的场景数据
 pub struct GpuScene {
-    gpu_scene_buffers: [GpuSceneBuffers; FrameCounter::fif_count()],
+    gpu_scene_buffers: Vec<GpuSceneBuffers>,
 
     // TODO sky texture handle 不应该放在 GPU scene 里面
     sky_texture: (GfxImageHandle, GfxImageViewHandle),
+    /// 天空贴图的文件路径，用作 IBL 预计算结果的缓存 key
+    sky_path: PathBuf,
     // TODO uv checker texture handle 不应该放在 GPU scene 里面
     uv_checker_texture: (GfxImageHandle, GfxImageViewHandle),
+
+    /// 方向光的阴影贴图及其分辨率，由生成阴影贴图的 pass 设置
+    shadow_map: Option<(GfxImageViewHandle, u32)>,
+
+    /// 由天空贴图预计算的 IBL 贴图，由 IblBaker 设置
+    ibl_maps: Option<IblMaps>,
+
+    /// 最近一帧的上传统计
+    upload_stats: GpuSceneUploadStats,
+    /// 当前帧已经录制的 draw call 统计，在上传场景数据时清零
+    draw_stats: Cell<GpuSceneDrawStats>,
 }
 // getter
 impl GpuScene {
     #[inline]
     pub fn tlas(&self, frame_label: FrameLabel) -> Option<&GfxAcceleration> {
-        self.gpu_scene_buffers[*frame_label].tlas.as_ref()
+        self.gpu_scene_buffers[*frame_label].tlas.as_ref().map(GfxUpdatableTlas::acceleration)
     }
 
     #[inline]
     pub fn scene_buffer(&self, frame_label: FrameLabel) -> &GfxStructuredBuffer<truvisl::GPUScene> {
         &self.gpu_scene_buffers[*frame_label].scene_buffer
     }
+
+    /// 最近一帧实际上传到 GPU 的字节数
+    #[inline]
+    pub fn upload_stats(&self) -> &GpuSceneUploadStats {
+        &self.upload_stats
+    }
+
+    /// 从上一次上传场景数据开始录制的 draw call 统计，在下一帧上传之前读取即为上一帧的结果
+    #[inline]
+    pub fn draw_stats(&self) -> GpuSceneDrawStats {
+        self.draw_stats.get()
+    }
+
+    /// 天空贴图（等距柱状投影），已注册为 bindless srv
+    #[inline]
+    pub fn sky_texture(&self) -> (GfxImageHandle, GfxImageViewHandle) {
+        self.sky_texture
+    }
+
+    #[inline]
+    pub fn sky_path(&self) -> &Path {
+        &self.sky_path
+    }
+
+    #[inline]
+    pub fn ibl_maps(&self) -> Option<&IblMaps> {
+        self.ibl_maps.as_ref()
+    }
+
+    /// 是否可以作为 [`Self::load_sky`] 的天空贴图，只根据扩展名判断，不区分大小写
+    pub fn is_sky_file(path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| Self::SKY_EXTENSIONS.iter().any(|sky_ext| ext.eq_ignore_ascii_case(sky_ext)))
+    }
+}
+// update
+impl GpuScene {
+    /// 替换天空贴图，贴图为等距柱状投影（equirectangular），支持 `.hdr` 和 `.exr`
+    ///
+    /// 旧的贴图在帧 timeline 达到 `current_frame_id` 之后销毁
+    pub fn load_sky(
+        &mut self,
+        gfx_resource_manager: &mut GfxResourceManager,
+        bindless_manager: &mut BindlessManager,
+        sky_path: &Path,
+        current_frame_id: u64,
+    ) {
+        let sky_texture = Self::load_texture(gfx_resource_manager, bindless_manager, sky_path);
+        let (old_image_handle, old_view_handle) = std::mem::replace(&mut self.sky_texture, sky_texture);
+        self.sky_path = sky_path.to_path_buf();
+
+        bindless_manager.unregister_srv(old_view_handle);
+        gfx_resource_manager.destroy_image(old_image_handle, current_frame_id);
+    }
+
+    /// 设置方向光使用的阴影贴图，`view_handle` 需要已经注册为 bindless srv；None 表示不计算阴影
+    #[inline]
+    pub fn set_shadow_map(&mut self, shadow_map: Option<(GfxImageViewHandle, u32)>) {
+        self.shadow_map = shadow_map;
+    }
+
+    /// 设置 IBL 贴图，返回之前的贴图，由调用者负责销毁；None 表示不使用 IBL
+    ///
+    /// 天空贴图替换之后，之前的 IBL 贴图不再匹配，需要重新预计算
+    #[inline]
+    pub fn set_ibl_maps(&mut self, ibl_maps: Option<IblMaps>) -> Option<IblMaps> {
+        std::mem::replace(&mut self.ibl_maps, ibl_maps)
+    }
 }
 // new & init
 impl GpuScene {
+    /// [`Self::load_sky`] 支持的天空贴图扩展名
+    pub const SKY_EXTENSIONS: [&'static str; 2] = ["hdr", "exr"];
+
     pub fn new(gfx_resource_manager: &mut GfxResourceManager, bindless_manager: &mut BindlessManager) -> Self {
         let sky_path = TruvisPath::resources_path_str("sky.jpg");
         let uv_checker_path = TruvisPath::resources_path_str("uv_checker.png");
 
-        let sky_image = ImageLoader::load_image(&PathBuf::from(&sky_path));
-        let uv_checker_image = ImageLoader::load_image(&PathBuf::from(&uv_checker_path));
-
-        let sky_image_format = sky_image.format();
-        let uv_checker_image_format = uv_checker_image.format();
-
-        let sky_image_handle = gfx_resource_manager.register_image(sky_image);
-        let sky_view_handle = gfx_resource_manager.get_or_create_image_view(
-            sky_image_handle,
-            truvis_gfx::resources::image_view::GfxImageViewDesc::new_2d(sky_image_format, vk::ImageAspectFlags::COLOR),
-            &sky_path,
-        );
-
-        let uv_checker_image_handle = gfx_resource_manager.register_image(uv_checker_image);
-        let uv_checker_view_handle = gfx_resource_manager.get_or_create_image_view(
-            uv_checker_image_handle,
-            truvis_gfx::resources::image_view::GfxImageViewDesc::new_2d(
-                uv_checker_image_format,
-                vk::ImageAspectFlags::COLOR,
-            ),
-            &uv_checker_path,
-        );
+        Self {
+            gpu_scene_buffers: FrameCounter::frame_labes().map(GpuSceneBuffers::new).collect_vec(),
 
-        bindless_manager.register_srv(sky_view_handle);
-        bindless_manager.register_srv(uv_checker_view_handle);
+            sky_texture: Self::load_texture(gfx_resource_manager, bindless_manager, Path::new(&sky_path)),
+            sky_path: PathBuf::from(sky_path),
+            uv_checker_texture: Self::load_texture(gfx_resource_manager, bindless_manager, Path::new(&uv_checker_path)),
 
-        Self {
-            gpu_scene_buffers: FrameCounter::frame_labes().map(GpuSceneBuffers::new),
+            shadow_map: None,
+            ibl_maps: None,
 
-            sky_texture: (sky_image_handle, sky_view_handle),
-            uv_checker_texture: (uv_checker_image_handle, uv_checker_view_handle),
+            upload_stats: GpuSceneUploadStats::default(),
+            draw_stats: Cell::default(),
         }
     }
 }
@@ -170,11 +296,47 @@ impl GpuScene {
 }
 // tools
 impl GpuScene {
+    /// 同步加载一张贴图，并注册为 bindless srv
+    fn load_texture(
+        gfx_resource_manager: &mut GfxResourceManager,
+        bindless_manager: &mut BindlessManager,
+        path: &Path,
+    ) -> (GfxImageHandle, GfxImageViewHandle) {
+        let image = ImageLoader::load_image(path);
+        let image_format = image.format();
+
+        let image_handle = gfx_resource_manager.register_image(image);
+        let view_handle = gfx_resource_manager.get_or_create_image_view(
+            image_handle,
+            truvis_gfx::resources::image_view::GfxImageViewDesc::new_2d(image_format, vk::ImageAspectFlags::COLOR),
+            path.to_str().unwrap(),
+        );
+        bindless_manager.register_srv(view_handle);
+
+        (image_handle, view_handle)
+    }
+
+    /// IBL 贴图的 bindless handle，没有 IBL 时为 null
+    fn ibl_srv_handle(
+        &self,
+        bindless_manager: &BindlessManager,
+        view_handle: impl FnOnce(&IblMaps) -> GfxImageViewHandle,
+    ) -> truvisl::SrvHandle {
+        self.ibl_maps
+            .as_ref()
+            .map_or_else(BindlessSrvHandle::null, |ibl_maps| {
+                bindless_manager.get_shader_srv_handle(view_handle(ibl_maps))
+            })
+            .0
+    }
+
     /// # Phase: Before Render (基于 SceneData2)
     ///
     /// 将已经准备好的 GPU 格式的场景数据写入 Device Buffer 中。
     /// 此方法不依赖 SceneManager，仅使用 SceneData2 中的数据。
     ///
+    /// 只会上传当前帧 buffer 同步之后发生变化的元素；场景结构变化时全量上传。
+    ///
     /// # 参数
     /// - `cmd`: 用于提交 GPU 命令的命令缓冲区
     /// - `barrier_mask`: 用于同步的屏障掩码
@@ -191,15 +353,38 @@ impl GpuScene {
     ) {
         let _span = tracy_client::span!("GpuScene::prepare_render_data2");
 
-        self.upload_mesh_buffer(cmd, barrier_mask, render_data, frame_counter);
-        self.upload_instance_buffer(cmd, barrier_mask, render_data, frame_counter);
-        self.upload_material_buffer(cmd, barrier_mask, render_data, frame_counter);
-        self.upload_light_buffer(cmd, barrier_mask, render_data, frame_counter);
+        // 结构发生变化时，当前帧的 buffer 需要全量上传
+        {
+            let crt_gpu_buffers = &mut self.gpu_scene_buffers[*frame_counter.frame_label()];
+            if crt_gpu_buffers.synced_structure_generation != Some(render_data.structure_generation) {
+                crt_gpu_buffers.synced_generation = None;
+                crt_gpu_buffers.synced_material_textures.clear();
+            }
+        }
+
+        let geometry_bytes = self.upload_mesh_buffer(cmd, barrier_mask, render_data, frame_counter);
+        let (instance_bytes, instance_dirty) =
+            self.upload_instance_buffer(cmd, barrier_mask, render_data, frame_counter);
+        let material_bytes = self.upload_material_buffer(cmd, barrier_mask, render_data, frame_counter);
+        let light_bytes = self.upload_light_buffer(cmd, barrier_mask, render_data, frame_counter)
+            + self.upload_rect_light_buffer(cmd, barrier_mask, render_data, frame_counter);
 
         // 需要确保 instance 先于 tlas 构建
-        self.build_tlas(render_data, frame_counter);
+        self.build_tlas(render_data, frame_counter, instance_dirty);
+
+        let scene_bytes = self.upload_scene_buffer(cmd, frame_counter, barrier_mask, render_data, bindless_manager);
 
-        self.upload_scene_buffer(cmd, frame_counter, barrier_mask, render_data, bindless_manager);
+        let crt_gpu_buffers = &mut self.gpu_scene_buffers[*frame_counter.frame_label()];
+        crt_gpu_buffers.synced_generation = Some(render_data.generation);
+        crt_gpu_buffers.synced_structure_generation = Some(render_data.structure_generation);
+        self.upload_stats = GpuSceneUploadStats {
+            scene_bytes,
+            instance_bytes,
+            material_bytes,
+            light_bytes,
+            geometry_bytes,
+        };
+        self.draw_stats.set(GpuSceneDrawStats::default());
     }
 
     // TODO 改成：返回 Raster 模式的 RenderData
@@ -209,24 +394,80 @@ impl GpuScene {
     /// - `cmd`: 命令缓冲区
     /// - `scene_data`: 场景数据
     /// - `before_draw`: 每次绘制前的回调函数 (instance_idx, submesh_idx)
-    pub fn draw(&self, cmd: &GfxCommandBuffer, scene_data: &RenderData<'_>, mut before_draw: impl FnMut(u32, u32)) {
+    pub fn draw(&self, cmd: &GfxCommandBuffer, scene_data: &RenderData<'_>, before_draw: impl FnMut(u32, u32)) {
+        let all_instances = (0..scene_data.all_instances.len() as u32).collect::<Vec<_>>();
+        self.draw_instances(cmd, scene_data, &all_instances, before_draw);
+    }
+
+    /// 只绘制 `instance_indices` 中的实例，例如视锥剔除之后的结果
+    ///
+    /// # 参数
+    /// - `instance_indices`: 实例在 `scene_data.all_instances` 中的序号
+    /// - 其余参数与 [`Self::draw`] 相同
+    pub fn draw_instances(
+        &self,
+        cmd: &GfxCommandBuffer,
+        scene_data: &RenderData<'_>,
+        instance_indices: &[u32],
+        mut before_draw: impl FnMut(u32, u32),
+    ) {
         let _span = tracy_client::span!("GpuScene::draw2");
-        for (instance_idx, instance) in scene_data.all_instances.iter().enumerate() {
+        for &instance_idx in instance_indices {
+            let instance = &scene_data.all_instances[instance_idx as usize];
             let mesh = &scene_data.all_meshes[instance.mesh_index];
             for (submesh_idx, geometry) in mesh.geometries.iter().enumerate() {
                 geometry.cmd_bind_index_buffer(cmd);
                 geometry.cmd_bind_vertex_buffers(cmd);
 
-                before_draw(instance_idx as u32, submesh_idx as u32);
+                before_draw(instance_idx, submesh_idx as u32);
                 cmd.draw_indexed(geometry.index_cnt(), 0, 1, 0, 0);
             }
+            self.add_draw_stats(mesh.geometries.len() as u32, 1);
         }
     }
+
+    /// 按批次进行 instanced draw，每个批次的每个 submesh 只需要一次 draw call
+    ///
+    /// shader 通过 `SV_InstanceID` 和批次的 `first_instance` 在 `batches.instance_indices` 中找到实例，
+    /// 参见 [`InstanceBatches`]
+    ///
+    /// # 参数
+    /// - `batches`: 由 SceneManager 合批得到，序号与 `scene_data` 一致
+    /// - `before_draw`: 每次绘制前的回调函数 (batch, submesh_idx)
+    pub fn draw_batches(
+        &self,
+        cmd: &GfxCommandBuffer,
+        scene_data: &RenderData<'_>,
+        batches: &InstanceBatches,
+        mut before_draw: impl FnMut(&InstanceBatch, u32),
+    ) {
+        let _span = tracy_client::span!("GpuScene::draw_batches");
+        for batch in &batches.batches {
+            let mesh = &scene_data.all_meshes[batch.mesh_index];
+            for (submesh_idx, geometry) in mesh.geometries.iter().enumerate() {
+                geometry.cmd_bind_index_buffer(cmd);
+                geometry.cmd_bind_vertex_buffers(cmd);
+
+                before_draw(batch, submesh_idx as u32);
+                cmd.draw_indexed(geometry.index_cnt(), 0, batch.instance_count, 0, 0);
+            }
+            self.add_draw_stats(mesh.geometries.len() as u32, batch.instance_count);
+        }
+    }
+
+    fn add_draw_stats(&self, draw_calls: u32, instances: u32) {
+        let mut draw_stats = self.draw_stats.get();
+        draw_stats.draw_calls += draw_calls;
+        draw_stats.instances += instances;
+        self.draw_stats.set(draw_stats);
+    }
 }
 
 // 基于 SceneData2 的新方法
 impl GpuScene {
     /// 将整个场景的数据上传到 scene buffer 中去（基于 SceneData2）
+    ///
+    /// 返回实际上传的字节数，数据没有变化时不上传
     fn upload_scene_buffer(
         &mut self,
         cmd: &GfxCommandBuffer,
@@ -234,8 +475,8 @@ impl GpuScene {
         barrier_mask: GfxBarrierMask,
         scene_data: &RenderData<'_>,
         bindless_manager: &BindlessManager,
-    ) {
-        let crt_gpu_buffers = &self.gpu_scene_buffers[*frame_counter.frame_label()];
+    ) -> vk::DeviceSize {
+        let crt_gpu_buffers = &mut self.gpu_scene_buffers[*frame_counter.frame_label()];
         let gpu_scene_data = truvisl::GPUScene {
             all_instances: crt_gpu_buffers.instance_buffer.device_address(),
             all_mats: crt_gpu_buffers.material_buffer.device_address(),
@@ -244,16 +485,53 @@ impl GpuScene {
             instance_geometry_map: crt_gpu_buffers.geometry_indirect_buffer.device_address(),
             point_lights: crt_gpu_buffers.light_buffer.device_address(),
             spot_lights: 0, // TODO 暂时无用
+            rect_lights: crt_gpu_buffers.rect_light_buffer.device_address(),
             point_light_count: scene_data.all_point_lights.len() as u32,
             spot_light_count: 0, // TODO 暂时无用
+            rect_light_count: scene_data.all_rect_lights.len() as u32,
 
             sky: bindless_manager.get_shader_srv_handle(self.sky_texture.1).0,
             sky_sampler_type: truvisl::ESamplerType_LinearClamp,
             uv_checker: bindless_manager.get_shader_srv_handle(self.uv_checker_texture.1).0,
             uv_checker_sampler_type: truvisl::ESamplerType_LinearClamp,
+
+            shadow_map: self
+                .shadow_map
+                .map_or_else(BindlessSrvHandle::null, |(view_handle, _)| {
+                    bindless_manager.get_shader_srv_handle(view_handle)
+                })
+                .0,
+            directional_light_view_proj: scene_data.directional_light_view_proj.into(),
+            directional_light: scene_data.directional_light.unwrap_or(truvisl::DirectionalLight {
+                direction: glam::Vec3::NEG_Y.into(),
+                color: glam::Vec3::ZERO.into(),
+
+                _direction_padding: Default::default(),
+                _color_padding: Default::default(),
+            }),
+            has_directional_light: scene_data.directional_light.is_some() as u32,
+            shadow_map_resolution: self.shadow_map.map_or(0, |(_, resolution)| resolution),
+            _padding_0: Default::default(),
+            _padding_1: Default::default(),
+
+            ibl_irradiance: self.ibl_srv_handle(bindless_manager, |ibl_maps| ibl_maps.irradiance.1),
+            ibl_brdf_lut: self.ibl_srv_handle(bindless_manager, |ibl_maps| ibl_maps.brdf_lut.1),
+            has_ibl: self.ibl_maps.is_some() as u32,
+            _padding_2: Default::default(),
+            ibl_prefiltered: std::array::from_fn(|level| {
+                self.ibl_srv_handle(bindless_manager, |ibl_maps| ibl_maps.prefiltered[level].1)
+            }),
+            _padding_3: Default::default(),
+            _padding_4: Default::default(),
+            _padding_5: Default::default(),
         };
 
-        cmd.cmd_update_buffer(crt_gpu_buffers.scene_buffer.vk_buffer(), 0, BytesConvert::bytes_of(&gpu_scene_data));
+        let gpu_scene_bytes = BytesConvert::bytes_of(&gpu_scene_data);
+        if crt_gpu_buffers.synced_scene_data == gpu_scene_bytes {
+            return 0;
+        }
+
+        cmd.cmd_update_buffer(crt_gpu_buffers.scene_buffer.vk_buffer(), 0, gpu_scene_bytes);
         cmd.buffer_memory_barrier(
             vk::DependencyFlags::empty(),
             &[GfxBufferBarrier::default().mask(barrier_mask).buffer(
@@ -262,18 +540,30 @@ impl GpuScene {
                 vk::WHOLE_SIZE,
             )],
         );
+        crt_gpu_buffers.synced_scene_data = gpu_scene_bytes.to_vec();
+
+        gpu_scene_bytes.len() as vk::DeviceSize
     }
 
     /// 将 instance 数据上传到 GPU（基于 SceneData2）
+    ///
+    /// 返回实际上传的字节数，以及是否有 instance 被上传（需要重建 TLAS）。
+    /// 间接索引 buffer 只依赖场景结构，仅在全量上传时更新
     fn upload_instance_buffer(
         &mut self,
         cmd: &GfxCommandBuffer,
         barrier_mask: GfxBarrierMask,
         scene_data: &RenderData<'_>,
         frame_counter: &FrameCounter,
-    ) {
+    ) -> (vk::DeviceSize, bool) {
         let _span = tracy_client::span!("upload_instance_buffer2");
         let crt_gpu_buffers = &mut self.gpu_scene_buffers[*frame_counter.frame_label()];
+        let full_upload = crt_gpu_buffers.synced_generation.is_none();
+        let dirty_instances =
+            scene_data.all_instances.iter().map(|instance| crt_gpu_buffers.is_dirty(instance.generation)).collect_vec();
+        if !dirty_instances.contains(&true) {
+            return (0, false);
+        }
 
         let crt_instance_stage_buffer = &mut crt_gpu_buffers.instance_stage_buffer;
         let crt_geometry_indirect_stage_buffer = &mut crt_gpu_buffers.geometry_indirect_stage_buffer;
@@ -298,14 +588,22 @@ impl GpuScene {
                 panic!("instance material cnt can not be larger than buffer");
             }
 
-            instance_buffer_slices[instance_idx] = truvisl::Instance {
-                geometry_indirect_idx: crt_geometry_indirect_idx as u32,
-                geometry_count: submesh_cnt as u32,
-                material_indirect_idx: crt_material_indirect_idx as u32,
-                material_count: submesh_cnt as u32,
-                model: instance.transform.into(),
-                inv_model: instance.transform.inverse().into(),
-            };
+            if dirty_instances[instance_idx] {
+                instance_buffer_slices[instance_idx] = truvisl::Instance {
+                    geometry_indirect_idx: crt_geometry_indirect_idx as u32,
+                    geometry_count: submesh_cnt as u32,
+                    material_indirect_idx: crt_material_indirect_idx as u32,
+                    material_count: submesh_cnt as u32,
+                    model: instance.transform.into(),
+                    inv_model: instance.transform.inverse().into(),
+                };
+            }
+
+            if !full_upload {
+                crt_geometry_indirect_idx += submesh_cnt;
+                crt_material_indirect_idx += submesh_cnt;
+                continue;
+            }
 
             // 将 geometry 索引写入间接索引 buffer
             let mesh_startup_index = scene_data.mesh_geometry_start_indices[instance.mesh_index];
@@ -322,36 +620,79 @@ impl GpuScene {
             }
         }
 
-        helper::flush_copy_and_barrier(
+        let mut uploaded_bytes = helper::flush_copy_ranges_and_barrier(
             cmd,
             crt_instance_stage_buffer,
-            &mut crt_gpu_buffers.instance_buffer,
-            barrier_mask,
-        );
-        helper::flush_copy_and_barrier(
-            cmd,
-            crt_geometry_indirect_stage_buffer,
-            &mut crt_gpu_buffers.geometry_indirect_buffer,
-            barrier_mask,
-        );
-        helper::flush_copy_and_barrier(
-            cmd,
-            crt_material_indirect_stage_buffer,
-            &mut crt_gpu_buffers.material_indirect_buffer,
+            &crt_gpu_buffers.instance_buffer,
+            &helper::dirty_ranges(&dirty_instances),
+            size_of::<truvisl::Instance>() as vk::DeviceSize,
             barrier_mask,
         );
+        if full_upload {
+            uploaded_bytes += helper::flush_copy_ranges_and_barrier(
+                cmd,
+                crt_geometry_indirect_stage_buffer,
+                &crt_gpu_buffers.geometry_indirect_buffer,
+                &[0..crt_geometry_indirect_idx],
+                size_of::<u32>() as vk::DeviceSize,
+                barrier_mask,
+            );
+            uploaded_bytes += helper::flush_copy_ranges_and_barrier(
+                cmd,
+                crt_material_indirect_stage_buffer,
+                &crt_gpu_buffers.material_indirect_buffer,
+                &[0..crt_material_indirect_idx],
+                size_of::<u32>() as vk::DeviceSize,
+                barrier_mask,
+            );
+        }
+
+        (uploaded_bytes, true)
     }
 
     /// 将 material 数据上传到 GPU（基于 SceneData2）
+    ///
+    /// 返回实际上传的字节数
     fn upload_material_buffer(
         &mut self,
         cmd: &GfxCommandBuffer,
         barrier_mask: GfxBarrierMask,
         scene_data: &RenderData<'_>,
         frame_counter: &FrameCounter,
-    ) {
+    ) -> vk::DeviceSize {
         let _span = tracy_client::span!("upload_material_buffer2");
         let crt_gpu_buffers = &mut self.gpu_scene_buffers[*frame_counter.frame_label()];
+
+        // 材质本身被修改，或者引用的贴图 bindless 下标发生变化，都需要重新上传
+        let material_textures = scene_data
+            .all_materials
+            .iter()
+            .map(|mat| {
+                [
+                    mat.diffuse_bindless_handle,
+                    mat.normal_bindless_handle,
+                    mat.detail_normal_bindless_handle,
+                    mat.metallic_roughness_bindless_handle,
+                    mat.emissive_bindless_handle,
+                    mat.occlusion_bindless_handle,
+                ]
+                .map(|handle| handle.0.index)
+            })
+            .collect_vec();
+        let dirty_materials = scene_data
+            .all_materials
+            .iter()
+            .enumerate()
+            .map(|(mat_idx, mat)| {
+                crt_gpu_buffers.is_dirty(mat.generation)
+                    || crt_gpu_buffers.synced_material_textures.get(mat_idx) != Some(&material_textures[mat_idx])
+            })
+            .collect_vec();
+        crt_gpu_buffers.synced_material_textures = material_textures;
+        if !dirty_materials.contains(&true) {
+            return 0;
+        }
+
         let crt_material_stage_buffer = &mut crt_gpu_buffers.material_stage_buffer;
         let material_buffer_slices = crt_material_stage_buffer.mapped_slice();
         if material_buffer_slices.len() < scene_data.all_materials.len() {
@@ -359,40 +700,67 @@ impl GpuScene {
         }
 
         for (mat_idx, mat) in scene_data.all_materials.iter().enumerate() {
+            if !dirty_materials[mat_idx] {
+                continue;
+            }
             material_buffer_slices[mat_idx] = truvisl::PBRMaterial {
                 base_color: mat.base_color.truncate().into(),
                 emissive: mat.emissive.truncate().into(),
                 metallic: mat.metallic,
                 roughness: mat.roughness,
                 diffuse_map: mat.diffuse_bindless_handle.0,
-                diffuse_map_sampler_type: truvisl::ESamplerType_LinearRepeat,
+                diffuse_map_sampler_type: mat.texture_sampler,
                 normal_map: mat.normal_bindless_handle.0,
-                normal_map_sampler_type: truvisl::ESamplerType_LinearRepeat,
+                normal_map_sampler_type: mat.texture_sampler,
                 opaque: mat.opaque,
-                _padding_1: Default::default(),
+                normal_scale: mat.normal_scale,
+                detail_normal_map: mat.detail_normal_bindless_handle.0,
+                detail_normal_map_sampler_type: truvisl::ESamplerType_LinearRepeat,
+                detail_tiling: mat.detail_tiling.into(),
+                occlusion_strength: mat.occlusion_strength,
+                transparent: mat.transparent as u32,
+                metallic_roughness_map: mat.metallic_roughness_bindless_handle.0,
+                metallic_roughness_map_sampler_type: mat.texture_sampler,
+                emissive_map: mat.emissive_bindless_handle.0,
+                emissive_map_sampler_type: mat.texture_sampler,
+                occlusion_map: mat.occlusion_bindless_handle.0,
+                occlusion_map_sampler_type: mat.texture_sampler,
                 _padding_2: Default::default(),
                 _padding_3: Default::default(),
             };
         }
 
-        helper::flush_copy_and_barrier(
+        helper::flush_copy_ranges_and_barrier(
             cmd,
             crt_material_stage_buffer,
-            &mut crt_gpu_buffers.material_buffer,
+            &crt_gpu_buffers.material_buffer,
+            &helper::dirty_ranges(&dirty_materials),
+            size_of::<truvisl::PBRMaterial>() as vk::DeviceSize,
             barrier_mask,
-        );
+        )
     }
 
     /// 将 light 数据上传到 GPU（基于 SceneData2）
+    ///
+    /// 返回实际上传的字节数
     fn upload_light_buffer(
         &mut self,
         cmd: &GfxCommandBuffer,
         barrier_mask: GfxBarrierMask,
         scene_data: &RenderData<'_>,
         frame_counter: &FrameCounter,
-    ) {
+    ) -> vk::DeviceSize {
         let _span = tracy_client::span!("upload_light_buffer2");
         let crt_gpu_buffers = &mut self.gpu_scene_buffers[*frame_counter.frame_label()];
+        let dirty_lights = scene_data
+            .point_light_generations
+            .iter()
+            .map(|generation| crt_gpu_buffers.is_dirty(*generation))
+            .collect_vec();
+        if !dirty_lights.contains(&true) {
+            return 0;
+        }
+
         let crt_light_stage_buffer = &mut crt_gpu_buffers.light_stage_buffer;
         let light_buffer_slices = crt_light_stage_buffer.mapped_slice();
         if light_buffer_slices.len() < scene_data.all_point_lights.len() {
@@ -400,6 +768,9 @@ impl GpuScene {
         }
 
         for (light_idx, point_light) in scene_data.all_point_lights.iter().enumerate() {
+            if !dirty_lights[light_idx] {
+                continue;
+            }
             light_buffer_slices[light_idx] = truvisl::PointLight {
                 pos: point_light.pos,
                 color: point_light.color,
@@ -409,19 +780,74 @@ impl GpuScene {
             };
         }
 
-        helper::flush_copy_and_barrier(cmd, crt_light_stage_buffer, &mut crt_gpu_buffers.light_buffer, barrier_mask);
+        helper::flush_copy_ranges_and_barrier(
+            cmd,
+            crt_light_stage_buffer,
+            &crt_gpu_buffers.light_buffer,
+            &helper::dirty_ranges(&dirty_lights),
+            size_of::<truvisl::PointLight>() as vk::DeviceSize,
+            barrier_mask,
+        )
+    }
+
+    /// 将面光源数据上传到 GPU
+    ///
+    /// 返回实际上传的字节数
+    fn upload_rect_light_buffer(
+        &mut self,
+        cmd: &GfxCommandBuffer,
+        barrier_mask: GfxBarrierMask,
+        scene_data: &RenderData<'_>,
+        frame_counter: &FrameCounter,
+    ) -> vk::DeviceSize {
+        let _span = tracy_client::span!("upload_rect_light_buffer");
+        let crt_gpu_buffers = &mut self.gpu_scene_buffers[*frame_counter.frame_label()];
+        let dirty_lights = scene_data
+            .rect_light_generations
+            .iter()
+            .map(|generation| crt_gpu_buffers.is_dirty(*generation))
+            .collect_vec();
+        if !dirty_lights.contains(&true) {
+            return 0;
+        }
+
+        let crt_rect_light_stage_buffer = &mut crt_gpu_buffers.rect_light_stage_buffer;
+        let rect_light_buffer_slices = crt_rect_light_stage_buffer.mapped_slice();
+        if rect_light_buffer_slices.len() < scene_data.all_rect_lights.len() {
+            panic!("rect light cnt can not be larger than buffer");
+        }
+
+        for (light_idx, rect_light) in scene_data.all_rect_lights.iter().enumerate() {
+            if dirty_lights[light_idx] {
+                rect_light_buffer_slices[light_idx] = *rect_light;
+            }
+        }
+
+        helper::flush_copy_ranges_and_barrier(
+            cmd,
+            crt_rect_light_stage_buffer,
+            &crt_gpu_buffers.rect_light_buffer,
+            &helper::dirty_ranges(&dirty_lights),
+            size_of::<truvisl::RectLight>() as vk::DeviceSize,
+            barrier_mask,
+        )
     }
 
     /// 将 mesh 数据以 geometry 的形式上传到 GPU（基于 SceneData2）
+    ///
+    /// mesh 只会随场景结构变化，因此仅在全量上传时执行。返回实际上传的字节数
     fn upload_mesh_buffer(
         &mut self,
         cmd: &GfxCommandBuffer,
         barrier_mask: GfxBarrierMask,
         scene_data: &RenderData<'_>,
         frame_counter: &FrameCounter,
-    ) {
+    ) -> vk::DeviceSize {
         let _span = tracy_client::span!("upload_mesh_buffer2");
         let crt_gpu_buffers = &mut self.gpu_scene_buffers[*frame_counter.frame_label()];
+        if crt_gpu_buffers.synced_generation.is_some() {
+            return 0;
+        }
         let crt_geometry_stage_buffer = &mut crt_gpu_buffers.geometry_stage_buffer;
         let geometry_buffer_slices = crt_geometry_stage_buffer.mapped_slice();
 
@@ -431,23 +857,31 @@ impl GpuScene {
                 panic!("geometry cnt can not be larger than buffer");
             }
             for (submesh_idx, geometry) in mesh.geometries.iter().enumerate() {
+                let transform =
+                    mesh.geometry_transforms.map_or(glam::Mat4::IDENTITY, |transforms| transforms[submesh_idx]);
                 geometry_buffer_slices[crt_geometry_idx + submesh_idx] = truvisl::Geometry {
                     position_buffer: geometry.vertex_buffer.pos_address(),
                     normal_buffer: geometry.vertex_buffer.normal_address(),
                     tangent_buffer: geometry.vertex_buffer.tangent_address(),
                     uv_buffer: geometry.vertex_buffer.uv_address(),
                     index_buffer: geometry.index_buffer.device_address(),
+                    _padding_0: Default::default(),
+                    _padding_1: Default::default(),
+                    transform: transform.into(),
+                    inv_transform: transform.inverse().into(),
                 };
             }
             crt_geometry_idx += mesh.geometries.len();
         }
 
-        helper::flush_copy_and_barrier(
+        helper::flush_copy_ranges_and_barrier(
             cmd,
             crt_geometry_stage_buffer,
-            &mut crt_gpu_buffers.geometry_buffer,
+            &crt_gpu_buffers.geometry_buffer,
+            &[0..crt_geometry_idx],
+            size_of::<truvisl::Geometry>() as vk::DeviceSize,
             barrier_mask,
-        );
+        )
     }
 
     /// 根据 SceneData2 的 instance 信息获得加速结构的 instance 信息
@@ -473,15 +907,18 @@ impl GpuScene {
     }
 
     /// 构建 TLAS（基于 SceneData2）
-    fn build_tlas(&mut self, scene_data: &RenderData<'_>, frame_counter: &FrameCounter) {
+    ///
+    /// 只有在 instance 发生变化（`instance_dirty`）或者还没有构建过时才会更新：
+    /// instance 数量不变时原地 refit，否则完整重建
+    fn build_tlas(&mut self, scene_data: &RenderData<'_>, frame_counter: &FrameCounter, instance_dirty: bool) {
         let _span = tracy_client::span!("build_tlas2");
         if scene_data.all_instances.is_empty() {
             // 没有实例数据，直接返回
             return;
         }
 
-        if self.gpu_scene_buffers[*frame_counter.frame_label()].tlas.is_some() {
-            // 已经构建过 tlas，直接返回
+        if !instance_dirty && self.gpu_scene_buffers[*frame_counter.frame_label()].tlas.is_some() {
+            // tlas 已经是最新的，直接返回
             return;
         }
 
@@ -492,17 +929,23 @@ impl GpuScene {
             // BUG custom idx 的有效位数只有 24 位，如果场景内 instance 过多，可能会溢出
             .map(|(idx, ins)| self.get_as_instance_info(ins, idx as u32, scene_data))
             .collect_vec();
-        let tlas = GfxAcceleration::build_tlas_sync(
-            &instance_infos,
-            vk::BuildAccelerationStructureFlagsKHR::empty(),
-            format!("scene2-{}-{}", frame_counter.frame_label(), frame_counter.frame_id()),
-        );
 
-        self.gpu_scene_buffers[*frame_counter.frame_label()].tlas = Some(tlas);
+        // 当前帧的 buffer 已经不再被 GPU 使用，可以直接原地更新或者替换旧的 tlas
+        match &mut self.gpu_scene_buffers[*frame_counter.frame_label()].tlas {
+            Some(tlas) => {
+                tlas.update_tlas(&instance_infos);
+            }
+            tlas @ None => {
+                *tlas = Some(GfxUpdatableTlas::new_sync(
+                    &instance_infos,
+                    format!("scene2-{}", frame_counter.frame_label()),
+                ));
+            }
+        }
     }
 }
 
-mod helper {
+pub mod helper {
     use ash::vk;
     use truvis_gfx::resources::image::GfxImage;
     use truvis_gfx::{
@@ -541,6 +984,57 @@ mod helper {
         );
     }
 
+    /// 只上传 stage buffer 中指定的区段：
+    /// 1. 将每个区段 flush 到 buffer 中
+    /// 2. 将每个区段从 stage buffer 复制到目标 buffer 的相同位置
+    /// 3. 添加 barrier，确保后续访问时 copy 已经完成且数据可用
+    ///
+    /// `ranges` 以元素为单位，`stride` 为单个元素的字节数。返回复制的总字节数，没有区段时不做任何操作
+    pub fn flush_copy_ranges_and_barrier(
+        cmd: &GfxCommandBuffer,
+        stage_buffer: &GfxBuffer,
+        dst: &GfxBuffer,
+        ranges: &[std::ops::Range<usize>],
+        stride: vk::DeviceSize,
+        barrier_mask: GfxBarrierMask,
+    ) -> vk::DeviceSize {
+        let regions = ranges
+            .iter()
+            .filter(|range| !range.is_empty())
+            .map(|range| vk::BufferCopy {
+                src_offset: range.start as vk::DeviceSize * stride,
+                dst_offset: range.start as vk::DeviceSize * stride,
+                size: range.len() as vk::DeviceSize * stride,
+            })
+            .collect::<Vec<_>>();
+        if regions.is_empty() {
+            return 0;
+        }
+
+        for region in &regions {
+            stage_buffer.flush(region.src_offset, region.size);
+        }
+        cmd.cmd_copy_buffer(stage_buffer, dst, &regions);
+        cmd.buffer_memory_barrier(
+            vk::DependencyFlags::empty(),
+            &[GfxBufferBarrier::default().mask(barrier_mask).buffer(dst.vk_buffer(), 0, vk::WHOLE_SIZE)],
+        );
+
+        regions.iter().map(|region| region.size).sum()
+    }
+
+    /// 将逐元素的脏标记合并为连续的区段
+    pub fn dirty_ranges(dirty: &[bool]) -> Vec<std::ops::Range<usize>> {
+        let mut ranges: Vec<std::ops::Range<usize>> = Vec::new();
+        for (idx, _) in dirty.iter().enumerate().filter(|(_, dirty)| **dirty) {
+            match ranges.last_mut() {
+                Some(last) if last.end == idx => last.end = idx + 1,
+                _ => ranges.push(idx..idx + 1),
+            }
+        }
+        ranges
+    }
+
     pub fn get_rt_matrix(trans: &glam::Mat4) -> vk::TransformMatrixKHR {
         let c1 = &trans.x_axis;
         let c2 = &trans.y_axis;
@@ -560,7 +1054,17 @@ mod helper {
     // TODO 临时的图片加载器，后续需要整合到 TextureManager 中
     pub struct ImageLoader {}
     impl ImageLoader {
+        /// `.hdr` 和 `.exr` 以 RGBA32_SFLOAT 加载，保留超过 1 的数值；其余格式以 RGBA8_UNORM 加载
         pub fn load_image(tex_path: &std::path::Path) -> GfxImage {
+            let is_hdr = tex_path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("hdr") || ext.eq_ignore_ascii_case("exr"));
+            if is_hdr {
+                let img = image::ImageReader::open(tex_path).unwrap().decode().unwrap().to_rgba32f();
+                return GfxImage::from_rgba32f(img.width(), img.height(), img.as_raw(), tex_path.to_str().unwrap());
+            }
+
             let img = image::ImageReader::open(tex_path).unwrap().decode().unwrap().to_rgba8();
             let width = img.width();
             let height = img.height();
@@ -571,3 +1075,26 @@ mod helper {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_sky_file() {
+        assert!(GpuScene::is_sky_file(Path::new("resources/kloppenheim.hdr")));
+        assert!(GpuScene::is_sky_file(Path::new("C:/sky/Studio.EXR")));
+        assert!(!GpuScene::is_sky_file(Path::new("sponza.gltf")));
+        assert!(!GpuScene::is_sky_file(Path::new("no_extension")));
+    }
+
+    #[test]
+    fn test_dirty_ranges() {
+        assert!(helper::dirty_ranges(&[]).is_empty());
+        assert!(helper::dirty_ranges(&[false, false]).is_empty());
+        assert_eq!(helper::dirty_ranges(&[true, true, true]), vec![0..3]);
+        // 相邻的脏元素合并为一个区段，不相邻的各自成段
+        assert_eq!(helper::dirty_ranges(&[true, true, false, true, false, false, true, true]), vec![0..2, 3..4, 6..8]);
+        assert_eq!(helper::dirty_ranges(&[false, true]), vec![1..2]);
+    }
+}
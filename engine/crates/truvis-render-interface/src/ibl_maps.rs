@@ -0,0 +1,78 @@
+use ash::vk;
+use truvis_shader_binding::truvisl;
+
+use crate::bindless_manager::BindlessManager;
+use crate::gfx_resource_manager::GfxResourceManager;
+use crate::handles::{GfxImageHandle, GfxImageViewHandle};
+
+/// 基于图像的光照（IBL）预计算得到的贴图，均已注册为 bindless srv
+///
+/// irradiance 和 prefiltered 都是等距柱状投影，与天空贴图的映射方式相同
+#[derive(Clone, Copy)]
+pub struct IblMaps {
+    /// 漫反射的 irradiance
+    pub irradiance: (GfxImageHandle, GfxImageViewHandle),
+    /// 不同 roughness 的镜面反射环境贴图，第 i 层的分辨率参见 [`Self::prefiltered_extent`]
+    pub prefiltered: [(GfxImageHandle, GfxImageViewHandle); Self::PREFILTERED_LEVEL_COUNT],
+    /// split sum 的 BRDF 积分
+    pub brdf_lut: (GfxImageHandle, GfxImageViewHandle),
+}
+// new & init
+impl IblMaps {
+    pub const FORMAT: vk::Format = vk::Format::R32G32B32A32_SFLOAT;
+    pub const PREFILTERED_LEVEL_COUNT: usize = truvisl::ibl::PREFILTERED_LEVEL_COUNT as usize;
+
+    pub const IRRADIANCE_EXTENT: vk::Extent2D = vk::Extent2D { width: 64, height: 32 };
+    /// prefiltered 第 0 层（roughness = 0）的分辨率
+    pub const PREFILTERED_BASE_EXTENT: vk::Extent2D = vk::Extent2D {
+        width: 512,
+        height: 256,
+    };
+    pub const BRDF_LUT_EXTENT: vk::Extent2D = vk::Extent2D {
+        width: 128,
+        height: 128,
+    };
+}
+// getter
+impl IblMaps {
+    /// prefiltered 第 `level` 层的分辨率，逐层减半
+    #[inline]
+    pub fn prefiltered_extent(level: usize) -> vk::Extent2D {
+        vk::Extent2D {
+            width: (Self::PREFILTERED_BASE_EXTENT.width >> level).max(1),
+            height: (Self::PREFILTERED_BASE_EXTENT.height >> level).max(1),
+        }
+    }
+
+    /// prefiltered 第 `level` 层对应的 roughness
+    #[inline]
+    pub fn prefiltered_roughness(level: usize) -> f32 {
+        level as f32 / (Self::PREFILTERED_LEVEL_COUNT - 1) as f32
+    }
+
+    /// 所有贴图及其分辨率，依次为 irradiance、prefiltered 各层、BRDF LUT
+    pub fn images(&self) -> Vec<((GfxImageHandle, GfxImageViewHandle), vk::Extent2D)> {
+        let mut images = Vec::with_capacity(Self::PREFILTERED_LEVEL_COUNT + 2);
+        images.push((self.irradiance, Self::IRRADIANCE_EXTENT));
+        images.extend(
+            self.prefiltered.iter().enumerate().map(|(level, image)| (*image, Self::prefiltered_extent(level))),
+        );
+        images.push((self.brdf_lut, Self::BRDF_LUT_EXTENT));
+        images
+    }
+}
+// destroy
+impl IblMaps {
+    /// 注销 bindless srv，贴图在帧 timeline 达到 `current_frame_id` 之后销毁
+    pub fn destroy(
+        self,
+        gfx_resource_manager: &mut GfxResourceManager,
+        bindless_manager: &mut BindlessManager,
+        current_frame_id: u64,
+    ) {
+        for ((image_handle, view_handle), _) in self.images() {
+            bindless_manager.unregister_srv(view_handle);
+            gfx_resource_manager.destroy_image(image_handle, current_frame_id);
+        }
+    }
+}
@@ -1,4 +1,6 @@
 pub mod bindless_manager;
+pub mod camera_convention;
+pub mod camera_history;
 pub mod cmd_allocator;
 pub mod frame_counter;
 pub mod geometry;
@@ -6,6 +8,8 @@ pub mod gfx_resource_manager;
 pub mod global_descriptor_sets;
 pub mod gpu_scene;
 pub mod handles;
+pub mod ibl_maps;
+pub mod meshlet;
 pub mod pipeline_settings;
 pub mod render_data;
 pub mod sampler_manager;
@@ -0,0 +1,209 @@
+//! meshlet：将几何体划分为顶点数和三角形数都有上限的小块，供 mesh shader 绘制
+//!
+//! 使用 meshopt 构建 meshlet，同时计算每个 meshlet 的包围球和法线锥，用于在 task shader 中剔除。
+//! 数据布局与 shader 中的 `meshlet` namespace 一致，参见 `share/pass/meshlet.slangi`。
+
+use itertools::Itertools;
+use truvis_gfx::resources::special_buffers::structured_buffer::GfxStructuredBuffer;
+use truvis_shader_binding::truvisl;
+
+/// CPU 端的 meshlet 数据
+pub struct MeshletData {
+    pub meshlets: Vec<truvisl::meshlet::Meshlet>,
+    /// 与 meshlets 一一对应
+    pub bounds: Vec<truvisl::meshlet::MeshletBounds>,
+    /// meshlet 内的顶点在原始顶点数组中的序号
+    pub vertex_indices: Vec<u32>,
+    /// 每个三角形的 3 个 meshlet 内顶点序号，各占 8 位，打包在低 24 位
+    pub triangles: Vec<u32>,
+}
+impl MeshletData {
+    pub const MAX_VERTICES: usize = truvisl::meshlet::MAX_VERTICES as usize;
+    pub const MAX_TRIANGLES: usize = truvisl::meshlet::MAX_TRIANGLES as usize;
+
+    /// 越大越倾向于让 meshlet 内的三角形朝向一致，法线锥剔除的效果越好
+    const CONE_WEIGHT: f32 = 0.25;
+
+    /// # Params
+    /// * `positions` - 顶点位置，包围球和法线锥基于该位置计算
+    /// * `indices` - triangle list，绕序需要和光栅化时的 front face 一致，否则法线锥剔除的结果是错的
+    pub fn build(positions: &[glam::Vec3], indices: &[u32]) -> Self {
+        assert_eq!(indices.len() % 3, 0, "meshlet: indices must be a triangle list");
+
+        let vertex_adapter =
+            meshopt::VertexDataAdapter::new(bytemuck::cast_slice(positions), size_of::<glam::Vec3>(), 0).unwrap();
+        let raw_meshlets = meshopt::build_meshlets(
+            indices,
+            &vertex_adapter,
+            Self::MAX_VERTICES,
+            Self::MAX_TRIANGLES,
+            Self::CONE_WEIGHT,
+        );
+
+        let mut meshlets = Vec::with_capacity(raw_meshlets.len());
+        let mut bounds = Vec::with_capacity(raw_meshlets.len());
+        let mut vertex_indices = Vec::new();
+        let mut triangles = Vec::new();
+        for (meshlet_idx, raw_meshlet) in raw_meshlets.meshlets.iter().enumerate() {
+            meshlets.push(truvisl::meshlet::Meshlet {
+                vertex_offset: vertex_indices.len() as u32,
+                triangle_offset: triangles.len() as u32,
+                vertex_count: raw_meshlet.vertex_count,
+                triangle_count: raw_meshlet.triangle_count,
+            });
+
+            let vertex_begin = raw_meshlet.vertex_offset as usize;
+            vertex_indices.extend_from_slice(
+                &raw_meshlets.vertices[vertex_begin..vertex_begin + raw_meshlet.vertex_count as usize],
+            );
+
+            // meshopt 中每个三角形占 3 个字节，打包到一个 u32 中方便 shader 读取
+            let triangle_begin = raw_meshlet.triangle_offset as usize;
+            let triangle_bytes =
+                &raw_meshlets.triangles[triangle_begin..triangle_begin + raw_meshlet.triangle_count as usize * 3];
+            triangles.extend(
+                triangle_bytes.chunks_exact(3).map(|tri| tri[0] as u32 | (tri[1] as u32) << 8 | (tri[2] as u32) << 16),
+            );
+
+            let raw_bounds = meshopt::compute_meshlet_bounds(raw_meshlets.get(meshlet_idx), &vertex_adapter);
+            bounds.push(truvisl::meshlet::MeshletBounds {
+                center: glam::Vec3::from(raw_bounds.center).into(),
+                radius: raw_bounds.radius,
+                cone_axis: glam::Vec3::from(raw_bounds.cone_axis).into(),
+                cone_cutoff: raw_bounds.cone_cutoff,
+            });
+        }
+
+        Self {
+            meshlets,
+            bounds,
+            vertex_indices,
+            triangles,
+        }
+    }
+
+    #[inline]
+    pub fn meshlet_cnt(&self) -> u32 {
+        self.meshlets.len() as u32
+    }
+
+    #[inline]
+    pub fn triangle_cnt(&self) -> u32 {
+        self.triangles.len() as u32
+    }
+
+    /// 将 meshlet 还原为原始顶点序号的三角形，用于校验
+    pub fn unpack_triangles(&self) -> Vec<[u32; 3]> {
+        self.meshlets
+            .iter()
+            .flat_map(|meshlet| {
+                let triangle_begin = meshlet.triangle_offset as usize;
+                self.triangles[triangle_begin..triangle_begin + meshlet.triangle_count as usize].iter().map(
+                    move |&packed| {
+                        [0, 8, 16].map(|shift| {
+                            self.vertex_indices[meshlet.vertex_offset as usize + ((packed >> shift) & 0xFF) as usize]
+                        })
+                    },
+                )
+            })
+            .collect_vec()
+    }
+}
+
+/// 上传到 GPU 的 meshlet 几何体，shader 通过 device address 访问
+pub struct MeshletGeometry {
+    pub positions: GfxStructuredBuffer<glam::Vec3>,
+    pub normals: GfxStructuredBuffer<glam::Vec3>,
+
+    pub meshlets: GfxStructuredBuffer<truvisl::meshlet::Meshlet>,
+    pub bounds: GfxStructuredBuffer<truvisl::meshlet::MeshletBounds>,
+    pub vertex_indices: GfxStructuredBuffer<u32>,
+    pub triangles: GfxStructuredBuffer<u32>,
+
+    meshlet_cnt: u32,
+    triangle_cnt: u32,
+}
+// new & init
+impl MeshletGeometry {
+    /// 构建 meshlet 并同步上传到 GPU
+    pub fn new(positions: &[glam::Vec3], normals: &[glam::Vec3], indices: &[u32], name: &str) -> Self {
+        let _span = tracy_client::span!("MeshletGeometry::new");
+        assert_eq!(positions.len(), normals.len());
+        assert!(!indices.is_empty(), "meshlet geometry {name}: empty geometry");
+
+        let data = MeshletData::build(positions, indices);
+        log::info!("meshlet geometry {}: {} triangles -> {} meshlets", name, data.triangle_cnt(), data.meshlet_cnt());
+
+        fn upload<T: Copy>(data: &[T], name: String) -> GfxStructuredBuffer<T> {
+            let buffer = GfxStructuredBuffer::new_ssbo(data.len(), name);
+            buffer.transfer_data_sync(data);
+            buffer
+        }
+
+        Self {
+            positions: upload(positions, format!("{name}-meshlet-positions")),
+            normals: upload(normals, format!("{name}-meshlet-normals")),
+            meshlets: upload(&data.meshlets, format!("{name}-meshlets")),
+            bounds: upload(&data.bounds, format!("{name}-meshlet-bounds")),
+            vertex_indices: upload(&data.vertex_indices, format!("{name}-meshlet-vertex-indices")),
+            triangles: upload(&data.triangles, format!("{name}-meshlet-triangles")),
+            meshlet_cnt: data.meshlet_cnt(),
+            triangle_cnt: data.triangle_cnt(),
+        }
+    }
+}
+// getter
+impl MeshletGeometry {
+    #[inline]
+    pub fn meshlet_cnt(&self) -> u32 {
+        self.meshlet_cnt
+    }
+
+    #[inline]
+    pub fn triangle_cnt(&self) -> u32 {
+        self.triangle_cnt
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// n x n 个格子的平面，每个格子两个三角形
+    fn grid(n: u32) -> (Vec<glam::Vec3>, Vec<u32>) {
+        let positions = (0..=n).flat_map(|z| (0..=n).map(move |x| glam::vec3(x as f32, 0.0, z as f32))).collect_vec();
+        let indices = (0..n)
+            .flat_map(|z| (0..n).map(move |x| z * (n + 1) + x))
+            .flat_map(|a| [a, a + n + 1, a + n + 2, a, a + n + 2, a + 1])
+            .collect_vec();
+        (positions, indices)
+    }
+
+    #[test]
+    fn test_meshlet_limits() {
+        let (positions, indices) = grid(32);
+        let data = MeshletData::build(&positions, &indices);
+
+        assert!(data.meshlet_cnt() > 1);
+        assert_eq!(data.bounds.len(), data.meshlets.len());
+        for meshlet in &data.meshlets {
+            assert!(meshlet.vertex_count as usize <= MeshletData::MAX_VERTICES);
+            assert!(meshlet.triangle_count as usize <= MeshletData::MAX_TRIANGLES);
+        }
+    }
+
+    #[test]
+    fn test_meshlet_keeps_all_triangles() {
+        let (positions, indices) = grid(16);
+        let data = MeshletData::build(&positions, &indices);
+
+        // 绕序需要保持，因此按旋转后的规范形式比较
+        let canonical = |tri: [u32; 3]| {
+            let min_idx = (0..3).min_by_key(|&i| tri[i]).unwrap();
+            [tri[min_idx], tri[(min_idx + 1) % 3], tri[(min_idx + 2) % 3]]
+        };
+        let expected = indices.chunks_exact(3).map(|tri| canonical([tri[0], tri[1], tri[2]])).sorted().collect_vec();
+        let actual = data.unpack_triangles().into_iter().map(canonical).sorted().collect_vec();
+        assert_eq!(actual, expected);
+    }
+}
@@ -2,6 +2,12 @@ use std::{fmt::Display, ops::Deref};
 
 use ash::vk;
 
+use truvis_shader_binding::truvisl;
+use truvis_ui_edit_macro::UiEdit;
+use truvis_ui_edit_trait::{UiEditField, UiFieldOptions};
+
+use crate::camera_convention::CameraConvention;
+
 /// 渲染器默认配置
 pub struct DefaultRendererSettings;
 impl DefaultRendererSettings {
@@ -11,7 +17,8 @@ impl DefaultRendererSettings {
         // 通知 OS，将数值按照 sRGB 空间进行处理和显示
         color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR,
     };
-    pub const DEFAULT_PRESENT_MODE: vk::PresentModeKHR = vk::PresentModeKHR::MAILBOX;
+    /// 默认同时在 GPU 上执行的帧数，参见 [`crate::frame_counter::FrameCounter::frames_in_flight`]
+    pub const DEFAULT_FRAMES_IN_FLIGHT: usize = 3;
     pub const DEPTH_FORMAT_CANDIDATES: &'static [vk::Format] = &[
         vk::Format::D32_SFLOAT_S8_UINT,
         vk::Format::D32_SFLOAT,
@@ -27,6 +34,38 @@ pub struct FrameSettings {
     pub color_format: vk::Format,
     pub depth_format: vk::Format,
     pub frame_extent: vk::Extent2D,
+    /// 渲染分辨率相对于 swapchain 分辨率的缩放
+    pub render_scale: f32,
+    /// 当前帧相机矩阵所遵循的约定，来自 `Camera`
+    pub camera_convention: CameraConvention,
+    /// 自动质量调节给出的降级，叠加在 `render_scale` 和 [`PipelineSettings`] 之上
+    pub quality: QualityOverrides,
+}
+
+/// 自动质量调节给出的降级
+///
+/// 只对用户设置做限制，不会修改用户设置本身，质量恢复后用户设置原样生效
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct QualityOverrides {
+    /// 渲染分辨率在 `render_scale` 之上的额外缩放
+    pub render_scale_factor: f32,
+    /// 是否允许 SSAO
+    pub ssao_allowed: bool,
+    /// SSAO 每像素采样数的上限
+    pub ssao_max_sample_count: u32,
+    /// 降噪滤波核半径的上限
+    pub denoise_max_kernel_radius: i32,
+}
+
+impl Default for QualityOverrides {
+    fn default() -> Self {
+        Self {
+            render_scale_factor: 1.0,
+            ssao_allowed: true,
+            ssao_max_sample_count: u32::MAX,
+            denoise_max_kernel_radius: i32::MAX,
+        }
+    }
 }
 
 /// 降噪设置
@@ -65,14 +104,14 @@ impl Default for DenoiseSettings {
         Self {
             enabled: true,
             sigma_color: 0.1,
-            sigma_depth: 1.0,  // 提高默认值适应大场景
+            sigma_depth: 1.0, // 提高默认值适应大场景
             sigma_normal: 0.5,
-            kernel_radius: 3,  // 提高默认值提升降噪效果
+            kernel_radius: 3, // 提高默认值提升降噪效果
 
             // 增强联合双边滤波参数
             sigma_albedo: 0.1,
             sigma_position: 0.1,
-            scene_scale: 400.0,  // Cornell Box 尺度
+            scene_scale: 400.0, // Cornell Box 尺度
 
             // 粗糙度自适应参数
             roughness_adaptive_enabled: true,
@@ -82,6 +121,208 @@ impl Default for DenoiseSettings {
     }
 }
 
+/// SSAO 设置
+///
+/// 路径追踪本身已经包含遮蔽信息，SSAO 主要用于低 spp 下增强接触阴影，因此默认关闭
+#[derive(Copy, Clone)]
+pub struct SsaoSettings {
+    /// 是否启用 SSAO
+    pub enabled: bool,
+    /// 世界空间的采样半径
+    pub radius: f32,
+    /// 每个像素的半球采样数
+    pub sample_count: u32,
+    /// 遮蔽强度
+    pub intensity: f32,
+    /// 深度比较的偏移，避免自遮挡
+    pub bias: f32,
+}
+
+impl Default for SsaoSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            radius: 20.0, // Cornell Box 尺度
+            sample_count: 16,
+            intensity: 1.0,
+            bias: 0.5,
+        }
+    }
+}
+
+/// 指数高度雾设置
+///
+/// 雾密度随高度指数衰减，沿视线解析积分得到雾的浓度，默认关闭
+#[derive(Copy, Clone, UiEdit)]
+pub struct HeightFogSettings {
+    /// 是否启用高度雾
+    #[ui(label = "Enable Height Fog")]
+    pub enabled: bool,
+    /// 基准高度处的雾密度
+    #[ui(min = 0.0, max = 0.01, format = "%.5f", enabled_by = "enabled")]
+    pub density: f32,
+    /// 高度衰减系数，越大雾随高度衰减越快
+    #[ui(min = 0.0, max = 0.1, format = "%.4f", enabled_by = "enabled")]
+    pub height_falloff: f32,
+    /// 雾密度为 density 的高度
+    #[ui(min = -500.0, max = 500.0, enabled_by = "enabled")]
+    pub base_height: f32,
+    /// 雾的颜色
+    #[ui(label = "Fog Color", color, enabled_by = "enabled")]
+    pub color: [f32; 3],
+    /// 指向太阳的方向（世界空间）
+    #[ui(label = "Sun Dir", min = -1.0, max = 1.0, enabled_by = "enabled")]
+    pub sun_direction: [f32; 3],
+    /// 太阳内散射的颜色
+    #[ui(color, enabled_by = "enabled")]
+    pub sun_color: [f32; 3],
+    /// 太阳内散射的强度
+    #[ui(min = 0.0, max = 10.0, enabled_by = "enabled")]
+    pub sun_intensity: f32,
+    /// 太阳内散射的集中程度，越大光晕越小
+    #[ui(min = 1.0, max = 64.0, enabled_by = "enabled")]
+    pub sun_exponent: f32,
+    /// 天空（未命中几何体）处使用的雾距离
+    #[ui(min = 100.0, max = 20000.0, enabled_by = "enabled")]
+    pub max_distance: f32,
+}
+
+impl Default for HeightFogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            density: 0.0005,
+            height_falloff: 0.005,
+            base_height: 0.0,
+            color: [0.5, 0.6, 0.7],
+            sun_direction: [0.3, 0.6, 0.4],
+            sun_color: [1.0, 0.9, 0.7],
+            sun_intensity: 1.0,
+            sun_exponent: 8.0,
+            max_distance: 5000.0,
+        }
+    }
+}
+
+/// Bloom 设置
+///
+/// 提取亮度超过阈值的部分，模糊后叠加回 HDR 结果，默认关闭
+#[derive(Copy, Clone, UiEdit)]
+pub struct BloomSettings {
+    /// 是否启用 Bloom
+    #[ui(label = "Enable Bloom")]
+    pub enabled: bool,
+    /// 亮部提取的亮度阈值
+    #[ui(min = 0.0, max = 10.0, enabled_by = "enabled")]
+    pub threshold: f32,
+    /// 阈值附近的软过渡宽度，为 0 时是硬阈值
+    #[ui(min = 0.0, max = 5.0, enabled_by = "enabled")]
+    pub knee: f32,
+    /// 叠加回 HDR 结果时泛光的强度
+    #[ui(min = 0.0, max = 2.0, enabled_by = "enabled")]
+    pub intensity: f32,
+}
+
+impl Default for BloomSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: 1.0,
+            knee: 0.5,
+            intensity: 0.1,
+        }
+    }
+}
+
+/// TAA 设置
+///
+/// 启用时投影矩阵每帧叠加 Halton 序列的子像素 jitter，默认关闭
+#[derive(Copy, Clone, UiEdit)]
+pub struct TaaSettings {
+    /// 是否启用 TAA
+    #[ui(label = "Enable TAA")]
+    pub enabled: bool,
+    /// 混合时历史的权重，越大越平滑，但运动时越容易拖影
+    #[ui(min = 0.5, max = 0.98, format = "%.2f", enabled_by = "enabled")]
+    pub history_weight: f32,
+    /// 是否将历史 clamp 到当前帧的邻域颜色范围，关闭后可以对比拖影；两种情况使用不同的 shader 变体
+    #[ui(label = "Neighborhood Clamp", enabled_by = "enabled")]
+    pub neighborhood_clamp: bool,
+}
+
+impl Default for TaaSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            history_weight: 0.9,
+            neighborhood_clamp: true,
+        }
+    }
+}
+
+/// HDR -> LDR 的 tone mapping 算子
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ToneMappingOperator {
+    #[default]
+    Reinhard,
+    Aces,
+    Uncharted2,
+}
+impl ToneMappingOperator {
+    pub const ALL: [Self; 3] = [Self::Reinhard, Self::Aces, Self::Uncharted2];
+
+    #[inline]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Reinhard => "Reinhard",
+            Self::Aces => "ACES",
+            Self::Uncharted2 => "Uncharted2",
+        }
+    }
+
+    /// 对应 shader 中的 `tone_mapping::ToneMappingOperator`
+    #[inline]
+    pub fn to_shader(self) -> truvisl::tone_mapping::ToneMappingOperator {
+        match self {
+            Self::Reinhard => truvisl::tone_mapping::ToneMappingOperator_REINHARD,
+            Self::Aces => truvisl::tone_mapping::ToneMappingOperator_ACES,
+            Self::Uncharted2 => truvisl::tone_mapping::ToneMappingOperator_UNCHARTED2,
+        }
+    }
+}
+impl UiEditField for ToneMappingOperator {
+    fn edit_field(&mut self, ui: &truvis_ui_edit_trait::imgui::Ui, label: &str, _options: &UiFieldOptions) -> bool {
+        let mut idx = Self::ALL.iter().position(|op| op == self).unwrap_or_default();
+        let names = Self::ALL.map(Self::name);
+        let changed = ui.combo_simple_string(label, &mut idx, &names);
+        if changed {
+            *self = Self::ALL[idx];
+        }
+        changed
+    }
+}
+
+/// Tone mapping 设置
+///
+/// 默认的 Reinhard + 曝光 1.0 与之前固定的 `x / (1 + x)` 结果一致
+#[derive(Copy, Clone, UiEdit)]
+pub struct ToneMappingSettings {
+    /// tone mapping 算子
+    pub operator: ToneMappingOperator,
+    /// 曝光，tone mapping 之前乘到颜色上
+    #[ui(min = 0.01, max = 16.0, format = "%.2f")]
+    pub exposure: f32,
+}
+
+impl Default for ToneMappingSettings {
+    fn default() -> Self {
+        Self {
+            operator: ToneMappingOperator::default(),
+            exposure: 1.0,
+        }
+    }
+}
+
 /// 管线级配置
 #[derive(Copy, Clone)]
 pub struct PipelineSettings {
@@ -91,18 +332,47 @@ pub struct PipelineSettings {
     pub denoise: DenoiseSettings,
     /// 是否启用 Irradiance Cache
     pub ic_enabled: bool,
+    /// 光追中点光源、面光源的直接光照采样策略
+    pub light_sampling: truvisl::rt::LightSamplingMode,
+    /// SSAO 设置
+    pub ssao: SsaoSettings,
+    /// 高度雾设置
+    pub height_fog: HeightFogSettings,
+    /// Bloom 设置
+    pub bloom: BloomSettings,
+    /// Tone mapping 设置
+    pub tone_mapping: ToneMappingSettings,
+    /// TAA 设置
+    pub taa: TaaSettings,
 }
 
 impl Default for PipelineSettings {
     fn default() -> Self {
         Self {
-            channel: 0,
+            channel: Self::FINAL_CHANNEL,
             denoise: DenoiseSettings::default(),
             ic_enabled: true, // 默认启用 IC
+            light_sampling: truvisl::rt::LightSamplingMode_MIS,
+            ssao: SsaoSettings::default(),
+            height_fog: HeightFogSettings::default(),
+            bloom: BloomSettings::default(),
+            tone_mapping: ToneMappingSettings::default(),
+            taa: TaaSettings::default(),
         }
     }
 }
 
+impl PipelineSettings {
+    /// 最终结果的调试通道，与 shader 中的 `debug_channel::FINAL` 相同
+    pub const FINAL_CHANNEL: u32 = truvisl::debug_channel::FINAL;
+    /// 不做累积、只显示单帧结果的调试通道，与 shader 中的 `debug_channel::NOT_ACCUM` 相同
+    pub const NOT_ACCUM_CHANNEL: u32 = truvisl::debug_channel::NOT_ACCUM;
+    /// 只显示 SSAO 结果的调试通道，与 shader 中的 `ssao_blur::AO_CHANNEL` 相同
+    pub const SSAO_CHANNEL: u32 = truvisl::ssao_blur::AO_CHANNEL;
+    /// 只显示 ray query 阴影的调试通道
+    pub const RAY_QUERY_SHADOW_CHANNEL: u32 = 11;
+}
+
 /// 呈现配置
 #[derive(Copy, Clone)]
 pub struct PresentSettings {
@@ -158,29 +428,58 @@ impl FrameLabel {
 }
 
 /// 用于逐帧累积的数据
+///
+/// 相机或者场景发生变化时累积帧数归零，shader 中以 `1 / (accum_frames + 1)` 的权重混合新的样本
+///
+/// 暂停时冻结累积结果；暂停期间相机或场景发生了变化（例如编辑材质）则解除冻结，
+/// 继续累积变化之后的画面，否则会一直显示单帧的噪声画面
 #[derive(Copy, Clone, Default)]
 pub struct AccumData {
     last_camera_pos: glam::Vec3,
     last_camera_dir: glam::Vec3,
+    /// 上一帧的场景 generation，参见 `SceneManager::generation`
+    last_scene_generation: u64,
+    /// 上一帧是否暂停
+    last_paused: bool,
 
     accum_frames_num: usize,
+    /// 累积结果保持不变，累积帧数不增加
+    frozen: bool,
 }
 impl AccumData {
     /// call phase: BeforeRender-CollectData
-    pub fn update_accum_frames(&mut self, camera_pos: glam::Vec3, camera_dir: glam::Vec3) {
-        if self.last_camera_dir != camera_dir || self.last_camera_pos != camera_pos {
+    ///
+    /// `paused` 表示这一帧时间不推进（暂停且不是单步）
+    pub fn update_accum_frames(
+        &mut self,
+        camera_pos: glam::Vec3,
+        camera_dir: glam::Vec3,
+        scene_generation: u64,
+        paused: bool,
+    ) {
+        let changed = self.last_camera_dir != camera_dir
+            || self.last_camera_pos != camera_pos
+            || self.last_scene_generation != scene_generation;
+
+        // 刚暂停时冻结，暂停期间发生变化之后不再冻结；还没有累积结果时不冻结
+        self.frozen = paused && !changed && (self.frozen || (!self.last_paused && self.accum_frames_num > 0));
+        if changed {
             self.accum_frames_num = 0;
-        } else {
+        } else if !self.frozen {
             self.accum_frames_num += 1;
         }
 
         self.last_camera_pos = camera_pos;
         self.last_camera_dir = camera_dir;
+        self.last_scene_generation = scene_generation;
+        self.last_paused = paused;
     }
 
+    /// 下一次 `update_accum_frames` 时重新开始累积
     pub fn reset(&mut self) {
         self.last_camera_pos = glam::Vec3::ZERO;
         self.last_camera_dir = glam::Vec3::ZERO;
+        self.last_scene_generation = u64::MAX;
         self.accum_frames_num = 0;
     }
 
@@ -188,4 +487,77 @@ impl AccumData {
     pub fn accum_frames_num(&self) -> usize {
         self.accum_frames_num
     }
+
+    /// 这一帧的累积结果是否保持不变
+    #[inline]
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accum_frames() {
+        let mut accum_data = AccumData::default();
+        let pos = glam::vec3(1.0, 2.0, 3.0);
+        let dir = glam::vec3(90.0, 0.0, 0.0);
+        for _ in 0..3 {
+            accum_data.update_accum_frames(pos, dir, 1, false);
+        }
+        assert_eq!(accum_data.accum_frames_num(), 2);
+
+        // 场景变化
+        accum_data.update_accum_frames(pos, dir, 2, false);
+        assert_eq!(accum_data.accum_frames_num(), 0);
+        accum_data.update_accum_frames(pos, dir, 2, false);
+        assert_eq!(accum_data.accum_frames_num(), 1);
+
+        // 相机移动
+        accum_data.update_accum_frames(pos + glam::Vec3::X, dir, 2, false);
+        assert_eq!(accum_data.accum_frames_num(), 0);
+
+        // 手动重置，即使相机位于原点也会重新累积
+        accum_data.update_accum_frames(glam::Vec3::ZERO, glam::Vec3::ZERO, 2, false);
+        accum_data.reset();
+        accum_data.update_accum_frames(glam::Vec3::ZERO, glam::Vec3::ZERO, 2, false);
+        assert_eq!(accum_data.accum_frames_num(), 0);
+    }
+
+    #[test]
+    fn test_accum_frames_paused() {
+        let mut accum_data = AccumData::default();
+        let pos = glam::vec3(1.0, 2.0, 3.0);
+        let dir = glam::vec3(90.0, 0.0, 0.0);
+        for _ in 0..3 {
+            accum_data.update_accum_frames(pos, dir, 1, false);
+        }
+        assert_eq!(accum_data.accum_frames_num(), 2);
+
+        // 暂停之后累积结果冻结
+        for _ in 0..3 {
+            accum_data.update_accum_frames(pos, dir, 1, true);
+            assert!(accum_data.is_frozen());
+            assert_eq!(accum_data.accum_frames_num(), 2);
+        }
+
+        // 暂停期间编辑场景，重新开始累积并且之后不再冻结
+        accum_data.update_accum_frames(pos, dir, 2, true);
+        assert!(!accum_data.is_frozen());
+        assert_eq!(accum_data.accum_frames_num(), 0);
+        for _ in 0..3 {
+            accum_data.update_accum_frames(pos, dir, 2, true);
+            assert!(!accum_data.is_frozen());
+        }
+        assert_eq!(accum_data.accum_frames_num(), 3);
+
+        // 单步推进一帧之后再次冻结
+        accum_data.update_accum_frames(pos, dir, 2, false);
+        assert_eq!(accum_data.accum_frames_num(), 4);
+        accum_data.update_accum_frames(pos, dir, 2, true);
+        assert!(accum_data.is_frozen());
+        assert_eq!(accum_data.accum_frames_num(), 4);
+    }
 }
@@ -1,6 +1,7 @@
 use crate::bindless_manager::BindlessSrvHandle;
 use crate::geometry::RtGeometry;
 use ash::vk;
+use std::rc::Rc;
 use truvis_shader_binding::truvisl;
 
 /// 用于渲染的完整实例数据（只读快照）
@@ -15,6 +16,8 @@ pub struct InstanceRenderData {
     pub material_indices: Vec<usize>,
     /// 实例的变换矩阵
     pub transform: glam::Mat4,
+    /// 该实例最近一次被修改时的 generation
+    pub generation: u64,
 }
 
 /// 用于渲染的完整材质数据（只读快照）
@@ -25,11 +28,30 @@ pub struct MaterialRenderData {
     pub metallic: f32,
     pub roughness: f32,
     pub opaque: f32,
+    /// 是否为透明材质，透明材质在不透明物体之后按从远到近的顺序混合绘制
+    pub transparent: bool,
 
     /// 漫反射贴图的 Bindless Handle（如果没有则为 null）
     pub diffuse_bindless_handle: BindlessSrvHandle,
     /// 法线贴图的 Bindless Handle（如果没有则为 null）
     pub normal_bindless_handle: BindlessSrvHandle,
+    pub normal_scale: f32,
+    /// detail 法线贴图的 Bindless Handle（如果没有则为 null）
+    pub detail_normal_bindless_handle: BindlessSrvHandle,
+    pub detail_tiling: glam::Vec2,
+    /// metallic-roughness 贴图的 Bindless Handle（如果没有则为 null）
+    pub metallic_roughness_bindless_handle: BindlessSrvHandle,
+    /// 自发光贴图的 Bindless Handle（如果没有则为 null）
+    pub emissive_bindless_handle: BindlessSrvHandle,
+    /// 环境光遮蔽贴图的 Bindless Handle（如果没有则为 null）
+    pub occlusion_bindless_handle: BindlessSrvHandle,
+    pub occlusion_strength: f32,
+    /// 采样除 detail 法线贴图之外的所有贴图时使用的 sampler
+    pub texture_sampler: truvisl::ESamplerType,
+    /// 光栅化时的面剔除方式
+    pub cull_mode: vk::CullModeFlags,
+    /// 该材质最近一次被修改时的 generation
+    pub generation: u64,
 }
 
 /// 用于渲染的完整 Mesh 数据引用（只读快照）
@@ -37,13 +59,45 @@ pub struct MaterialRenderData {
 /// 注意：由于 RtGeometry 包含 GPU buffer，这里使用引用而非拷贝
 pub struct MeshRenderData<'a> {
     /// 该 mesh 包含的所有几何体数据
-    pub geometries: &'a [RtGeometry],
+    pub geometries: &'a [Rc<RtGeometry>],
+    /// 每个 geometry 相对于 mesh 的变换，为 None 时均为单位矩阵
+    pub geometry_transforms: Option<&'a [glam::Mat4]>,
     /// BLAS 的设备地址（用于 TLAS 构建）
     pub blas_device_address: Option<vk::DeviceAddress>,
     /// Mesh 名称
     pub name: &'a str,
 }
 
+/// 使用同一个 mesh 的一组实例，每个 submesh 可以用一次 instanced draw 绘制
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InstanceBatch {
+    /// 批次中所有实例使用的 mesh 在 `RenderData::all_meshes` 中的索引
+    pub mesh_index: usize,
+    /// 批次在 [`InstanceBatches::instance_indices`] 中的起始位置
+    pub first_instance: u32,
+    pub instance_count: u32,
+}
+
+/// 按 mesh 合批之后的实例列表，由 SceneManager 构建
+///
+/// shader 中第 i 个批次的第 `SV_InstanceID` 个实例为
+/// `instance_indices[batches[i].first_instance + SV_InstanceID]`
+#[derive(Clone, Debug, Default)]
+pub struct InstanceBatches {
+    pub batches: Vec<InstanceBatch>,
+    /// 实例在 `RenderData::all_instances` 中的序号，同一个批次的实例连续存放
+    pub instance_indices: Vec<u32>,
+}
+
+/// 光栅化 draw list 中的一项，对应一个实例的一个 submesh
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DrawItem {
+    /// 实例在 `RenderData::all_instances` 中的序号
+    pub instance_idx: u32,
+    /// submesh 在实例的 mesh 中的序号
+    pub submesh_idx: u32,
+}
+
 /// 由 SceneManager 构建的完整场景数据快照（只读）
 ///
 /// 这是一个自包含的场景数据结构，GpuScene 可以仅凭此结构完成
@@ -66,12 +120,27 @@ pub struct RenderData<'a> {
     pub all_materials: Vec<MaterialRenderData>,
     /// 所有点光源数据
     pub all_point_lights: Vec<truvisl::PointLight>,
+    /// 每个点光源最近一次被修改时的 generation，长度与 all_point_lights 相同
+    pub point_light_generations: Vec<u64>,
+    /// 所有面光源数据
+    pub all_rect_lights: Vec<truvisl::RectLight>,
+    /// 每个面光源最近一次被修改时的 generation，长度与 all_rect_lights 相同
+    pub rect_light_generations: Vec<u64>,
+    /// 方向光，场景中最多只有一个
+    pub directional_light: Option<truvisl::DirectionalLight>,
+    /// 世界空间到方向光裁剪空间的矩阵，用于生成和采样阴影贴图
+    pub directional_light_view_proj: glam::Mat4,
 
     /// 每个 mesh 在 geometry buffer 中的起始索引（预计算）
     /// 长度与 all_meshes 相同
     pub mesh_geometry_start_indices: Vec<usize>,
     /// 总 geometry 数量（预计算）
     pub total_geometry_count: usize,
+
+    /// 场景当前的 generation，见 `SceneManager` 的脏标记说明
+    pub generation: u64,
+    /// 最近一次结构变化（增删元素）时的 generation，变化后需要全量上传
+    pub structure_generation: u64,
 }
 impl<'a> RenderData<'a> {
     /// 创建一个空的场景数据
@@ -81,8 +150,15 @@ impl<'a> RenderData<'a> {
             all_meshes: Vec::new(),
             all_materials: Vec::new(),
             all_point_lights: Vec::new(),
+            point_light_generations: Vec::new(),
+            all_rect_lights: Vec::new(),
+            rect_light_generations: Vec::new(),
+            directional_light: None,
+            directional_light_view_proj: glam::Mat4::IDENTITY,
             mesh_geometry_start_indices: Vec::new(),
             total_geometry_count: 0,
+            generation: 0,
+            structure_generation: 0,
         }
     }
 
@@ -93,14 +169,24 @@ impl<'a> RenderData<'a> {
             && self.all_meshes.is_empty()
             && self.all_materials.is_empty()
             && self.all_point_lights.is_empty()
+            && self.all_rect_lights.is_empty()
     }
 
     /// 获取指定 mesh 的 geometry 数据
     #[inline]
-    pub fn get_mesh_geometries(&self, mesh_index: usize) -> Option<&[RtGeometry]> {
+    pub fn get_mesh_geometries(&self, mesh_index: usize) -> Option<&[Rc<RtGeometry>]> {
         self.all_meshes.get(mesh_index).map(|m| m.geometries)
     }
 
+    /// 实例的第 `submesh_idx` 个 submesh 所用材质的面剔除方式，找不到材质时剔除背面
+    #[inline]
+    pub fn submesh_cull_mode(&self, instance_idx: u32, submesh_idx: u32) -> vk::CullModeFlags {
+        self.all_instances[instance_idx as usize]
+            .material_indices
+            .get(submesh_idx as usize)
+            .map_or(vk::CullModeFlags::BACK, |&mat_idx| self.all_materials[mat_idx].cull_mode)
+    }
+
     /// 获取指定 mesh 在 geometry buffer 中的起始索引
     #[inline]
     pub fn get_mesh_geometry_start_index(&self, mesh_index: usize) -> Option<usize> {
@@ -1,49 +1,287 @@
 use crate::frame_counter::FrameCounter;
+use ash::vk;
+use truvis_gfx::commands::timeline::GfxRetireQueue;
 use truvis_gfx::resources::buffer::GfxBuffer;
 
-pub struct StageBufferManager {
-    buffers: [Vec<GfxBuffer>; FrameCounter::fif_count()],
+/// stage buffer 被回收时触发的回调，参数为被回收的 buffer 的 handle 与字节数
+///
+/// handle 在 buffer 释放之后可能被驱动复用，只能用于和分配时记录的 handle 对应起来
+pub type StageBufferRecycleFn = Box<dyn FnMut(vk::Buffer, vk::DeviceSize)>;
+
+/// [`StageBufferManager`] 管理的 buffer，统计与回收回调只需要 handle 和大小
+pub trait StageBuffer {
+    fn vk_buffer(&self) -> vk::Buffer;
+    fn size(&self) -> vk::DeviceSize;
+}
+
+impl StageBuffer for GfxBuffer {
+    #[inline]
+    fn vk_buffer(&self) -> vk::Buffer {
+        GfxBuffer::vk_buffer(self)
+    }
+
+    #[inline]
+    fn size(&self) -> vk::DeviceSize {
+        GfxBuffer::size(self)
+    }
+}
+
+/// [`StageBufferManager`] 的统计数据，用于定位上传带来的显存峰值
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UploadBufferStats {
+    /// 当前仍被持有的 buffer 数量（对应的帧还没有结束）
+    pub live_buffer_cnt: usize,
+    /// 当前仍被持有的 buffer 的总字节数
+    pub live_bytes: vk::DeviceSize,
+
+    /// 累计分配的字节数
+    pub total_alloc_bytes: vk::DeviceSize,
+    /// 累计回收的字节数
+    pub total_recycled_bytes: vk::DeviceSize,
+
+    /// 当前帧新建的 buffer 数量
+    pub frame_alloc_cnt: usize,
+    /// 当前帧新建的 buffer 的总字节数
+    pub frame_alloc_bytes: vk::DeviceSize,
+}
+
+/// 每帧上传使用的 stage buffer，记录分配时的帧 timeline 值，GPU 完成该帧之后回收
+pub struct StageBufferManager<B: StageBuffer = GfxBuffer> {
+    /// 按照分配时的帧 timeline 值排序
+    buffers: GfxRetireQueue<B>,
+
+    stats: UploadBufferStats,
+    /// `stats` 中 frame_* 字段对应的帧
+    stats_frame_id: u64,
+
+    recycle_callbacks: Vec<StageBufferRecycleFn>,
 }
 
 // new & init
-impl Default for StageBufferManager {
+impl<B: StageBuffer> Default for StageBufferManager<B> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl StageBufferManager {
+impl<B: StageBuffer> StageBufferManager<B> {
     pub fn new() -> Self {
-        let buffers = FrameCounter::frame_labes().map(|_| Vec::new());
-        Self { buffers }
+        Self {
+            buffers: GfxRetireQueue::new(),
+            stats: UploadBufferStats::default(),
+            stats_frame_id: 0,
+            recycle_callbacks: Vec::new(),
+        }
+    }
+
+    /// 注册回调，当某个 buffer 所在的帧结束、buffer 被释放之前触发，回调收到该 buffer 的 handle
+    ///
+    /// 回调在 [`Self::reclaim`] 中调用；manager 销毁时剩余的 buffer 不会触发回调，
+    /// 因此回调中捕获的对象（例如 profiler overlay）即使先于 manager 销毁也不会被访问
+    pub fn on_recycle(&mut self, cb: impl FnMut(vk::Buffer, vk::DeviceSize) + 'static) {
+        self.recycle_callbacks.push(Box::new(cb));
     }
 }
-impl Drop for StageBufferManager {
+impl<B: StageBuffer> Drop for StageBufferManager<B> {
     fn drop(&mut self) {
+        // 先丢弃回调，之后 buffer 的释放不会再访问回调捕获的对象
+        self.recycle_callbacks.clear();
         log::info!("UploadBufferManager dropped.");
     }
 }
 // destory
-impl StageBufferManager {
+impl<B: StageBuffer> StageBufferManager<B> {
     pub fn destroy(self) {}
 }
+// getter
+impl<B: StageBuffer> StageBufferManager<B> {
+    #[inline]
+    pub fn stats(&self) -> UploadBufferStats {
+        self.stats
+    }
+}
 // tools
-impl StageBufferManager {
+impl StageBufferManager<GfxBuffer> {
     pub fn alloc_buffer(&mut self, frame_counter: &FrameCounter, size: u64, debug_name: &str) -> &mut GfxBuffer {
         let buffer = GfxBuffer::new_stage_buffer(size, debug_name);
-        let frame_idx = *frame_counter.frame_label();
-        self.buffers[frame_idx].push(buffer);
-        self.buffers[frame_idx].last_mut().unwrap()
+        self.register_stage_buffer(frame_counter, buffer)
     }
+}
+impl<B: StageBuffer> StageBufferManager<B> {
+    /// buffer 在当前帧被 GPU 使用，帧 timeline 达到当前帧之后回收
+    pub fn register_stage_buffer(&mut self, frame_counter: &FrameCounter, stage_buffer: B) -> &mut B {
+        self.sync_stats_frame(frame_counter);
+
+        let size = stage_buffer.size();
+        self.stats.live_buffer_cnt += 1;
+        self.stats.live_bytes += size;
+        self.stats.total_alloc_bytes += size;
+        self.stats.frame_alloc_cnt += 1;
+        self.stats.frame_alloc_bytes += size;
+
+        self.buffers.retire(frame_counter.frame_id(), stage_buffer)
+    }
+
+    /// 释放帧 timeline 已经完成（不大于 [`FrameCounter::completed_frame_id`]）的帧中分配的所有 buffer
+    ///
+    /// 按照分配的顺序逐个回收：先触发所有回调，然后释放该 buffer
+    pub fn reclaim(&mut self, frame_counter: &FrameCounter) {
+        self.sync_stats_frame(frame_counter);
+
+        // 先将 buffer 移出，回调中不会和 manager 自身的借用冲突
+        let buffers = self.buffers.drain_completed(frame_counter.completed_frame_id()).collect::<Vec<_>>();
+        for buffer in buffers {
+            let handle = buffer.vk_buffer();
+            let size = buffer.size();
+            for cb in &mut self.recycle_callbacks {
+                cb(handle, size);
+            }
 
-    pub fn register_stage_buffer(&mut self, frame_counter: &FrameCounter, stage_buffer: GfxBuffer) {
-        let frame_idx = *frame_counter.frame_label();
-        self.buffers[frame_idx].push(stage_buffer);
+            self.stats.live_buffer_cnt -= 1;
+            self.stats.live_bytes -= size;
+            self.stats.total_recycled_bytes += size;
+        }
     }
 
-    pub fn clear_fif_buffers(&mut self, frame_counter: &FrameCounter) {
-        let frame_idx = *frame_counter.frame_label();
+    /// 进入新的一帧时清空 frame_* 统计
+    fn sync_stats_frame(&mut self, frame_counter: &FrameCounter) {
+        if self.stats_frame_id != frame_counter.frame_id() {
+            self.stats_frame_id = frame_counter.frame_id();
+            self.stats.frame_alloc_cnt = 0;
+            self.stats.frame_alloc_bytes = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ash::vk::Handle;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// 不依赖 GPU 的 buffer，释放时记录到事件日志中
+    struct FakeBuffer {
+        handle: u64,
+        size: vk::DeviceSize,
+        events: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl StageBuffer for FakeBuffer {
+        fn vk_buffer(&self) -> vk::Buffer {
+            vk::Buffer::from_raw(self.handle)
+        }
+
+        fn size(&self) -> vk::DeviceSize {
+            self.size
+        }
+    }
+
+    impl Drop for FakeBuffer {
+        fn drop(&mut self) {
+            self.events.borrow_mut().push(format!("drop {}", self.handle));
+        }
+    }
+
+    fn fake_buffer(handle: u64, size: vk::DeviceSize, events: &Rc<RefCell<Vec<String>>>) -> FakeBuffer {
+        FakeBuffer {
+            handle,
+            size,
+            events: events.clone(),
+        }
+    }
+
+    #[test]
+    fn test_stats_counters() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut manager = StageBufferManager::<FakeBuffer>::new();
+        let mut frame_counter = FrameCounter::new(1, FrameCounter::fif_count());
+
+        manager.register_stage_buffer(&frame_counter, fake_buffer(1, 100, &events));
+        manager.register_stage_buffer(&frame_counter, fake_buffer(2, 20, &events));
+        assert_eq!(
+            manager.stats(),
+            UploadBufferStats {
+                live_buffer_cnt: 2,
+                live_bytes: 120,
+                total_alloc_bytes: 120,
+                total_recycled_bytes: 0,
+                frame_alloc_cnt: 2,
+                frame_alloc_bytes: 120,
+            }
+        );
+
+        // 进入新的一帧之后 frame_* 统计被清空，其余统计保留
+        frame_counter.next_frame();
+        manager.register_stage_buffer(&frame_counter, fake_buffer(3, 7, &events));
+        assert_eq!(
+            manager.stats(),
+            UploadBufferStats {
+                live_buffer_cnt: 3,
+                live_bytes: 127,
+                total_alloc_bytes: 127,
+                total_recycled_bytes: 0,
+                frame_alloc_cnt: 1,
+                frame_alloc_bytes: 7,
+            }
+        );
+
+        // GPU 完成第一帧之后回收该帧的 buffer
+        frame_counter.next_frame();
+        frame_counter.set_completed_frame_id(1);
+        manager.reclaim(&frame_counter);
+        assert_eq!(
+            manager.stats(),
+            UploadBufferStats {
+                live_buffer_cnt: 1,
+                live_bytes: 7,
+                total_alloc_bytes: 127,
+                total_recycled_bytes: 120,
+                frame_alloc_cnt: 0,
+                frame_alloc_bytes: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_recycle_callback_order() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut manager = StageBufferManager::<FakeBuffer>::new();
+        let mut frame_counter = FrameCounter::new(1, FrameCounter::fif_count());
+
+        for idx in 0..2 {
+            let events = events.clone();
+            manager.on_recycle(move |handle, size| {
+                events.borrow_mut().push(format!("cb{} {} {}", idx, handle.as_raw(), size));
+            });
+        }
+
+        manager.register_stage_buffer(&frame_counter, fake_buffer(1, 100, &events));
+        manager.register_stage_buffer(&frame_counter, fake_buffer(2, 20, &events));
+        frame_counter.next_frame();
+        manager.register_stage_buffer(&frame_counter, fake_buffer(3, 7, &events));
+
+        // GPU 还没有完成任何一帧，不会回收
+        manager.reclaim(&frame_counter);
+        assert!(events.borrow().is_empty());
+
+        // 每个 buffer 按分配顺序，先依次触发回调，然后释放；GPU 仍在执行的帧的 buffer 不会被回收
+        frame_counter.set_completed_frame_id(1);
+        manager.reclaim(&frame_counter);
+        assert_eq!(*events.borrow(), ["cb0 1 100", "cb1 1 100", "drop 1", "cb0 2 20", "cb1 2 20", "drop 2"]);
+        events.borrow_mut().clear();
+
+        // GPU 一次完成多帧时，之前所有帧的 buffer 都会被回收
+        frame_counter.next_frame();
+        frame_counter.next_frame();
+        frame_counter.set_completed_frame_id(3);
+        manager.reclaim(&frame_counter);
+        assert_eq!(*events.borrow(), ["cb0 3 7", "cb1 3 7", "drop 3"]);
+        events.borrow_mut().clear();
 
-        self.buffers[frame_idx].clear();
+        // manager 销毁时剩余的 buffer 不触发回调
+        manager.register_stage_buffer(&frame_counter, fake_buffer(4, 1, &events));
+        drop(manager);
+        assert_eq!(*events.borrow(), ["drop 4"]);
     }
 }
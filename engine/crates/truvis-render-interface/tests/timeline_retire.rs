@@ -0,0 +1,100 @@
+//! 通过真实的 Vulkan 对象检查基于帧 timeline 的资源回收
+//!
+//! 需要 GPU，默认不执行：
+//! ```text
+//! cargo test -p truvis-render-interface --test timeline_retire -- --ignored
+//! ```
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use ash::vk;
+use truvis_gfx::commands::submit_info::GfxSubmitInfo;
+use truvis_gfx::commands::timeline::GfxTimeline;
+use truvis_gfx::gfx::Gfx;
+use truvis_gfx::resources::buffer::GfxBuffer;
+use truvis_render_interface::cmd_allocator::CmdAllocator;
+use truvis_render_interface::frame_counter::FrameCounter;
+use truvis_render_interface::stage_buffer_manager::StageBufferManager;
+
+const WAIT_TIMEOUT_NS: u64 = 10 * 1000 * 1000 * 1000;
+
+#[test]
+#[ignore = "requires a GPU"]
+fn stage_buffers_and_command_pools_follow_frame_timeline() {
+    // 没有 surface 也需要开启该扩展，否则 device 上的 swapchain 扩展不合法
+    Gfx::init("timeline-retire-test".to_string(), vec![ash::khr::surface::NAME], None);
+    {
+        const FRAME_CNT: u64 = 16;
+
+        let timeline = GfxTimeline::new("timeline-retire-test");
+        let mut frame_counter = FrameCounter::new(1, 2);
+        let mut cmd_allocator = CmdAllocator::new();
+        let mut stage_buffers = StageBufferManager::<GfxBuffer>::new();
+        let cmds = FrameCounter::frame_labes()
+            .map(|frame_label| cmd_allocator.alloc_command_buffer(frame_label, "timeline-retire-test"))
+            .collect::<Vec<_>>();
+        for frame_label in FrameCounter::frame_labes() {
+            assert_eq!(cmd_allocator.frame_command_buffer_count(frame_label), 1);
+        }
+
+        let recycled = Rc::new(RefCell::new(Vec::new()));
+        {
+            let recycled = recycled.clone();
+            stage_buffers.on_recycle(move |buffer, _| recycled.borrow_mut().push(buffer));
+        }
+        // 仍未回收的 buffer -> 分配时的帧
+        let mut alloc_frames = HashMap::new();
+
+        for _ in 0..FRAME_CNT {
+            let frame_id = frame_counter.frame_id();
+            timeline.wait(frame_id.saturating_sub(frame_counter.frames_in_flight() as u64), WAIT_TIMEOUT_NS);
+            frame_counter.set_completed_frame_id(timeline.completed_value());
+            let completed_frame_id = frame_counter.completed_frame_id();
+
+            // 回收的 buffer 所在的帧都已经完成，并且已经完成的帧的 buffer 全部被回收
+            stage_buffers.reclaim(&frame_counter);
+            for buffer in recycled.borrow_mut().drain(..) {
+                let alloc_frame: u64 = alloc_frames.remove(&buffer).unwrap();
+                assert!(alloc_frame <= completed_frame_id, "buffer of frame {} is recycled too early", alloc_frame);
+            }
+            assert!(alloc_frames.values().all(|alloc_frame| *alloc_frame > completed_frame_id));
+            assert_eq!(stage_buffers.stats().live_buffer_cnt, alloc_frames.len());
+
+            // 上一次使用 command pool 的帧已经完成，重置不会 panic
+            cmd_allocator.reset_frame_commands(&frame_counter);
+
+            let buffer = stage_buffers.alloc_buffer(&frame_counter, 256, "timeline-retire-test");
+            alloc_frames.insert(buffer.vk_buffer(), frame_id);
+
+            let cmd = &cmds[*frame_counter.frame_label()];
+            cmd.begin(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT, "timeline-retire-test");
+            cmd.end();
+            Gfx::get().gfx_queue().submit(
+                vec![GfxSubmitInfo::new(std::slice::from_ref(cmd)).signal(
+                    timeline.semaphore(),
+                    vk::PipelineStageFlags2::ALL_COMMANDS,
+                    Some(frame_id),
+                )],
+                None,
+            );
+
+            frame_counter.next_frame();
+        }
+
+        // 所有帧完成之后，所有 buffer 都会被回收
+        timeline.wait(frame_counter.frame_id() - 1, WAIT_TIMEOUT_NS);
+        frame_counter.set_completed_frame_id(timeline.completed_value());
+        stage_buffers.reclaim(&frame_counter);
+        assert_eq!(recycled.borrow().len(), alloc_frames.len());
+        assert_eq!(stage_buffers.stats().live_buffer_cnt, 0);
+        assert_eq!(stage_buffers.stats().total_recycled_bytes, FRAME_CNT * 256);
+
+        Gfx::get().wait_idel();
+        stage_buffers.destroy();
+        cmd_allocator.destroy();
+        timeline.destroy();
+    }
+    Gfx::destroy();
+}
@@ -0,0 +1,158 @@
+use std::path::Path;
+
+use ash::vk;
+use exr::prelude::*;
+use truvis_gfx::gfx::Gfx;
+use truvis_render_graph::render_context::RenderContext;
+use truvis_render_graph::resources::fif_buffer::FifBuffers;
+use truvis_render_interface::handles::GfxImageHandle;
+use truvis_render_interface::pipeline_settings::FrameLabel;
+
+/// 将当前帧的渲染目标导出为多层 OpenEXR 文件
+///
+/// 每个渲染目标对应 EXR 中的一个 layer：
+/// - `beauty`：累积后的 HDR 结果（RGBA, f32），原样写出，不做 tone mapping
+/// - `albedo`：GBufferC.rgb（f32，由 unorm 转换）
+/// - `normal`：GBufferA.xyz（f16）
+/// - `position`：GBufferB.xyz，世界空间坐标（f16）
+/// - `depth`：GBufferB.w，线性深度（f16）
+/// - `material`：roughness / metallic（f32）
+pub struct ExrFrameDump;
+
+impl ExrFrameDump {
+    /// 读回 `frame_label` 对应的渲染目标并写入 `path`
+    ///
+    /// 需要在该帧的命令提交之后调用；内部会等待 GPU 空闲，开销较大，只适合手动触发
+    pub fn dump(render_context: &RenderContext, frame_label: FrameLabel, path: impl AsRef<Path>) -> Result<()> {
+        let _span = tracy_client::span!("ExrFrameDump::dump");
+
+        // 渲染目标都处于 GENERAL layout，读回之前需要保证 GPU 的写入全部完成
+        Gfx::get().wait_idel();
+
+        let fif_buffers = &render_context.fif_buffers;
+        let read_back = |image_handle: GfxImageHandle| {
+            let image = render_context.gfx_resource_manager.get_image(image_handle).unwrap();
+            let size = (image.width() as usize, image.height() as usize);
+            (size, image.read_back(vk::ImageLayout::GENERAL))
+        };
+
+        let (beauty_size, beauty_data) = read_back(fif_buffers.accum_image_handle());
+        let (gbuffer_a_size, gbuffer_a_data) = read_back(fif_buffers.gbuffer_a_handle(frame_label).0);
+        let (gbuffer_b_size, gbuffer_b_data) = read_back(fif_buffers.gbuffer_b_handle(frame_label).0);
+        let (gbuffer_c_size, gbuffer_c_data) = read_back(fif_buffers.gbuffer_c_handle(frame_label).0);
+
+        debug_assert_eq!(fif_buffers.accum_image_format(), vk::Format::R32G32B32A32_SFLOAT);
+        debug_assert_eq!(FifBuffers::gbuffer_a_format(), vk::Format::R16G16B16A16_SFLOAT);
+        debug_assert_eq!(FifBuffers::gbuffer_b_format(), vk::Format::R16G16B16A16_SFLOAT);
+        debug_assert_eq!(FifBuffers::gbuffer_c_format(), vk::Format::R8G8B8A8_UNORM);
+
+        let beauty = Self::split_f32_channels(&beauty_data);
+        let gbuffer_a = Self::split_f16_channels(&gbuffer_a_data);
+        let gbuffer_b = Self::split_f16_channels(&gbuffer_b_data);
+        let gbuffer_c = Self::split_unorm8_channels(&gbuffer_c_data);
+
+        let [beauty_r, beauty_g, beauty_b, beauty_a] = beauty;
+        let [normal_x, normal_y, normal_z, roughness] = gbuffer_a;
+        let [position_x, position_y, position_z, depth] = gbuffer_b;
+        let [albedo_r, albedo_g, albedo_b, metallic] = gbuffer_c;
+
+        let layers = vec![
+            Self::layer(
+                "beauty",
+                beauty_size,
+                vec![
+                    AnyChannel::new("R", FlatSamples::F32(beauty_r)),
+                    AnyChannel::new("G", FlatSamples::F32(beauty_g)),
+                    AnyChannel::new("B", FlatSamples::F32(beauty_b)),
+                    AnyChannel::new("A", FlatSamples::F32(beauty_a)),
+                ],
+            ),
+            Self::layer(
+                "albedo",
+                gbuffer_c_size,
+                vec![
+                    AnyChannel::new("R", FlatSamples::F32(albedo_r)),
+                    AnyChannel::new("G", FlatSamples::F32(albedo_g)),
+                    AnyChannel::new("B", FlatSamples::F32(albedo_b)),
+                ],
+            ),
+            Self::layer(
+                "normal",
+                gbuffer_a_size,
+                vec![
+                    AnyChannel::new("X", FlatSamples::F16(normal_x)),
+                    AnyChannel::new("Y", FlatSamples::F16(normal_y)),
+                    AnyChannel::new("Z", FlatSamples::F16(normal_z)),
+                ],
+            ),
+            Self::layer(
+                "position",
+                gbuffer_b_size,
+                vec![
+                    AnyChannel::new("X", FlatSamples::F16(position_x)),
+                    AnyChannel::new("Y", FlatSamples::F16(position_y)),
+                    AnyChannel::new("Z", FlatSamples::F16(position_z)),
+                ],
+            ),
+            Self::layer("depth", gbuffer_b_size, vec![AnyChannel::new("Z", FlatSamples::F16(depth))]),
+            Self::layer(
+                "material",
+                gbuffer_a_size,
+                vec![
+                    AnyChannel::new("roughness", FlatSamples::F32(roughness.iter().map(|v| v.to_f32()).collect())),
+                    AnyChannel::new("metallic", FlatSamples::F32(metallic)),
+                ],
+            ),
+        ];
+
+        let image = Image::from_layers(ImageAttributes::new(IntegerBounds::from_dimensions(beauty_size)), layers);
+        image.write().to_file(path.as_ref())?;
+
+        log::info!("frame dumped to {}", path.as_ref().display());
+        Ok(())
+    }
+}
+
+// tools
+impl ExrFrameDump {
+    fn layer(
+        name: &str,
+        size: (usize, usize),
+        channels: Vec<AnyChannel<FlatSamples>>,
+    ) -> Layer<AnyChannels<FlatSamples>> {
+        Layer::new(size, LayerAttributes::named(name), Encoding::FAST_LOSSLESS, AnyChannels::sort(channels.into()))
+    }
+
+    /// 将 RGBA 交错排列的 f32 数据拆分为 4 个通道
+    fn split_f32_channels(data: &[u8]) -> [Vec<f32>; 4] {
+        let mut channels: [Vec<f32>; 4] = Default::default();
+        for pixel in data.chunks_exact(16) {
+            for (c, bytes) in pixel.chunks_exact(4).enumerate() {
+                channels[c].push(f32::from_le_bytes(bytes.try_into().unwrap()));
+            }
+        }
+        channels
+    }
+
+    /// 将 RGBA 交错排列的 f16 数据拆分为 4 个通道
+    fn split_f16_channels(data: &[u8]) -> [Vec<f16>; 4] {
+        let mut channels: [Vec<f16>; 4] = Default::default();
+        for pixel in data.chunks_exact(8) {
+            for (c, bytes) in pixel.chunks_exact(2).enumerate() {
+                channels[c].push(f16::from_bits(u16::from_le_bytes(bytes.try_into().unwrap())));
+            }
+        }
+        channels
+    }
+
+    /// 将 RGBA 交错排列的 unorm8 数据拆分为 4 个 [0, 1] 的 f32 通道
+    fn split_unorm8_channels(data: &[u8]) -> [Vec<f32>; 4] {
+        let mut channels: [Vec<f32>; 4] = Default::default();
+        for pixel in data.chunks_exact(4) {
+            for (c, value) in pixel.iter().enumerate() {
+                channels[c].push(*value as f32 / 255.0);
+            }
+        }
+        channels
+    }
+}
@@ -0,0 +1,8 @@
+//! 帧导出
+//!
+//! 将渲染目标从 GPU 读回并写入文件，用于离线对比、调试和后期合成。
+//! 目前支持 OpenEXR 格式，见 [`ExrFrameDump`]。
+
+pub mod exr_dump;
+
+pub use exr_dump::ExrFrameDump;
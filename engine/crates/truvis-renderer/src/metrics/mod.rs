@@ -0,0 +1,11 @@
+//! 渲染指标导出
+//!
+//! 仅在开启 `metrics` feature 时编译。
+//! [`RenderMetrics`] 收集帧时间、GPU pass 时间、显存占用等指标；
+//! [`PrometheusExporter`] 在后台线程中以 Prometheus text 格式通过 HTTP 暴露，默认不启动，由 app 按需开启。
+
+pub mod prometheus_exporter;
+pub mod render_metrics;
+
+pub use prometheus_exporter::PrometheusExporter;
+pub use render_metrics::{RenderMetrics, RenderMetricsSnapshot};
@@ -0,0 +1,87 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use crate::metrics::render_metrics::RenderMetricsSnapshot;
+
+/// 以 Prometheus text 格式暴露渲染指标的最小 HTTP 服务
+///
+/// 在独立线程中运行，不依赖额外的 HTTP 库；`GET /metrics` 返回当前快照。
+pub struct PrometheusExporter {
+    stop_flag: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl PrometheusExporter {
+    /// 轮询 listener 的间隔，决定了 stop 的最大响应延迟
+    const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+
+    pub fn start(addr: &str, snapshot: Arc<Mutex<RenderMetricsSnapshot>>) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        // 非阻塞模式，便于在没有请求时检查 stop_flag
+        listener.set_nonblocking(true)?;
+
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let thread_stop_flag = stop_flag.clone();
+        let thread = std::thread::Builder::new().name("metrics-exporter".to_string()).spawn(move || {
+            while !thread_stop_flag.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        if let Err(e) = Self::handle_connection(stream, &snapshot) {
+                            log::warn!("metrics exporter: failed to serve request: {}", e);
+                        }
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                        std::thread::sleep(Self::POLL_INTERVAL);
+                    }
+                    Err(e) => {
+                        log::warn!("metrics exporter: accept failed: {}", e);
+                        std::thread::sleep(Self::POLL_INTERVAL);
+                    }
+                }
+            }
+        })?;
+
+        Ok(Self {
+            stop_flag,
+            thread: Some(thread),
+        })
+    }
+
+    fn handle_connection(mut stream: TcpStream, snapshot: &Mutex<RenderMetricsSnapshot>) -> std::io::Result<()> {
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(std::time::Duration::from_secs(1)))?;
+
+        // 只需要读出请求行，不关心 header 和 body
+        let mut request = [0u8; 1024];
+        let len = stream.read(&mut request)?;
+        let request_line = String::from_utf8_lossy(&request[..len]);
+        let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+
+        let (status, content_type, body) = if path == "/metrics" {
+            let body = snapshot.lock().unwrap().encode_prometheus();
+            ("200 OK", "text/plain; version=0.0.4; charset=utf-8", body)
+        } else {
+            ("404 Not Found", "text/plain; charset=utf-8", "not found\n".to_string())
+        };
+
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            content_type,
+            body.len(),
+            body
+        );
+        stream.write_all(response.as_bytes())?;
+        stream.flush()
+    }
+
+    pub fn stop(mut self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
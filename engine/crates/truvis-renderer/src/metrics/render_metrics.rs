@@ -0,0 +1,264 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+
+use truvis_gfx::gfx::Gfx;
+
+use crate::metrics::prometheus_exporter::PrometheusExporter;
+use crate::platform::timer::Timer;
+
+/// 单个显存堆的占用情况
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HeapUsage {
+    pub heap_index: u32,
+    /// 当前进程在该堆上的占用（字节）
+    pub usage_bytes: u64,
+    /// 驱动给出的可用预算（字节）
+    pub budget_bytes: u64,
+}
+
+/// 某一时刻的渲染指标快照
+///
+/// 由渲染线程写入，由导出线程读取并编码
+#[derive(Clone, Debug, Default)]
+pub struct RenderMetricsSnapshot {
+    pub frame_id: u64,
+    pub frame_time_ms: f32,
+    pub fps: f32,
+    pub total_time_s: f32,
+
+    /// 每个 GPU pass 最近一次的耗时（毫秒），key 是 pass 名称
+    pub gpu_pass_time_ms: BTreeMap<String, f32>,
+
+    pub heaps: Vec<HeapUsage>,
+}
+
+/// 渲染指标收集器
+///
+/// 渲染线程每帧更新一次快照；导出器持有同一份快照的共享引用
+pub struct RenderMetrics {
+    snapshot: Arc<Mutex<RenderMetricsSnapshot>>,
+    exporter: Option<PrometheusExporter>,
+}
+
+// new & init
+impl RenderMetrics {
+    /// 默认的 HTTP 监听地址，参见 [`Self::exporter_addr`]
+    pub const DEFAULT_ADDR: &'static str = "127.0.0.1:9464";
+
+    pub fn new() -> Self {
+        Self {
+            snapshot: Arc::new(Mutex::new(RenderMetricsSnapshot::default())),
+            exporter: None,
+        }
+    }
+
+    /// 导出器的监听地址：优先使用环境变量 `TRUVIS_METRICS_ADDR`，否则使用 [`Self::DEFAULT_ADDR`]
+    pub fn exporter_addr() -> String {
+        std::env::var("TRUVIS_METRICS_ADDR").unwrap_or_else(|_| Self::DEFAULT_ADDR.to_string())
+    }
+
+    /// 启动 Prometheus HTTP 导出器
+    ///
+    /// 默认不启动，由交互式的 app 按需开启；同一进程中有多个 Renderer（例如 headless 测试）时，
+    /// 需要为每个导出器指定不同的地址，否则端口冲突
+    pub fn start_prometheus_exporter(&mut self, addr: &str) {
+        if self.exporter.is_some() {
+            log::warn!("metrics exporter is already running");
+            return;
+        }
+        match PrometheusExporter::start(addr, self.snapshot.clone()) {
+            Ok(exporter) => {
+                log::info!("metrics exporter listening on http://{}/metrics", addr);
+                self.exporter = Some(exporter);
+            }
+            Err(e) => {
+                log::error!("failed to start metrics exporter on {}: {}", addr, e);
+            }
+        }
+    }
+}
+
+impl Default for RenderMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// update
+impl RenderMetrics {
+    /// 每帧结束时调用，更新帧时间与显存占用
+    pub fn update_frame(&self, frame_id: u64, timer: &Timer) {
+        let _span = tracy_client::span!("RenderMetrics::update_frame");
+
+        let heaps = match Gfx::get().allocator().get_heap_budgets() {
+            Ok(budgets) => budgets
+                .iter()
+                .enumerate()
+                .filter(|(_, budget)| budget.budget > 0)
+                .map(|(heap_index, budget)| HeapUsage {
+                    heap_index: heap_index as u32,
+                    usage_bytes: budget.usage,
+                    budget_bytes: budget.budget,
+                })
+                .collect(),
+            Err(e) => {
+                log::warn!("failed to query vma heap budgets: {:?}", e);
+                Vec::new()
+            }
+        };
+
+        let mut snapshot = self.snapshot.lock().unwrap();
+        snapshot.frame_id = frame_id;
+        snapshot.frame_time_ms = timer.real_delta_time().as_secs_f32() * 1000.0;
+        snapshot.fps = timer.fps();
+        snapshot.total_time_s = timer.total_time_s();
+        snapshot.heaps = heaps;
+    }
+
+    /// 记录某个 GPU pass 的耗时（毫秒）
+    pub fn record_gpu_pass_time(&self, pass_name: impl AsRef<str>, time_ms: f32) {
+        self.snapshot.lock().unwrap().gpu_pass_time_ms.insert(pass_name.as_ref().to_string(), time_ms);
+    }
+
+    /// 用一帧完整的 GPU 计时结果替换所有 pass 的耗时，本帧没有执行的 pass 不再导出
+    pub fn record_gpu_pass_times(&self, results: &[(String, f32)]) {
+        let mut snapshot = self.snapshot.lock().unwrap();
+        snapshot.gpu_pass_time_ms.clear();
+        for (pass_name, time_ms) in results {
+            snapshot.gpu_pass_time_ms.insert(pass_name.clone(), *time_ms);
+        }
+    }
+
+    #[inline]
+    pub fn snapshot(&self) -> RenderMetricsSnapshot {
+        self.snapshot.lock().unwrap().clone()
+    }
+}
+
+// destroy
+impl RenderMetrics {
+    pub fn destroy(mut self) {
+        if let Some(exporter) = self.exporter.take() {
+            exporter.stop();
+        }
+    }
+}
+
+// tools
+impl RenderMetricsSnapshot {
+    /// 编码为 Prometheus text exposition format (version 0.0.4)
+    pub fn encode_prometheus(&self) -> String {
+        let mut out = String::new();
+
+        let mut gauge = |name: &str, help: &str, samples: &[(String, String)]| {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} gauge", name);
+            for (labels, value) in samples {
+                let _ = writeln!(out, "{}{} {}", name, labels, value);
+            }
+        };
+
+        gauge("truvis_frame_id", "Current frame id.", &[(String::new(), self.frame_id.to_string())]);
+        gauge(
+            "truvis_frame_time_ms",
+            "CPU frame time of the last frame in milliseconds.",
+            &[(String::new(), self.frame_time_ms.to_string())],
+        );
+        gauge(
+            "truvis_fps",
+            "Frames per second derived from the last frame time.",
+            &[(String::new(), self.fps.to_string())],
+        );
+        gauge(
+            "truvis_uptime_seconds",
+            "Total running time of the renderer in seconds.",
+            &[(String::new(), self.total_time_s.to_string())],
+        );
+
+        let pass_samples = self
+            .gpu_pass_time_ms
+            .iter()
+            .map(|(pass, time)| (format!("{{pass=\"{}\"}}", escape_label_value(pass)), time.to_string()))
+            .collect::<Vec<_>>();
+        gauge("truvis_gpu_pass_time_ms", "GPU time of each render pass in milliseconds.", &pass_samples);
+
+        let usage_samples = self
+            .heaps
+            .iter()
+            .map(|heap| (format!("{{heap=\"{}\"}}", heap.heap_index), heap.usage_bytes.to_string()))
+            .collect::<Vec<_>>();
+        gauge("truvis_vram_usage_bytes", "Device memory used by this process per heap.", &usage_samples);
+
+        let budget_samples = self
+            .heaps
+            .iter()
+            .map(|heap| (format!("{{heap=\"{}\"}}", heap.heap_index), heap.budget_bytes.to_string()))
+            .collect::<Vec<_>>();
+        gauge("truvis_vram_budget_bytes", "Device memory budget per heap.", &budget_samples);
+
+        out
+    }
+}
+
+/// Prometheus label value 中需要转义 `\`、`"` 和换行
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_prometheus() {
+        let snapshot = RenderMetricsSnapshot {
+            frame_id: 42,
+            frame_time_ms: 16.5,
+            fps: 60.0,
+            total_time_s: 1.25,
+            gpu_pass_time_ms: BTreeMap::from([("rt".to_string(), 2.5), ("blit \"final\"".to_string(), 0.25)]),
+            heaps: vec![HeapUsage {
+                heap_index: 1,
+                usage_bytes: 1024,
+                budget_bytes: 4096,
+            }],
+        };
+
+        let expected = r#"# HELP truvis_frame_id Current frame id.
+# TYPE truvis_frame_id gauge
+truvis_frame_id 42
+# HELP truvis_frame_time_ms CPU frame time of the last frame in milliseconds.
+# TYPE truvis_frame_time_ms gauge
+truvis_frame_time_ms 16.5
+# HELP truvis_fps Frames per second derived from the last frame time.
+# TYPE truvis_fps gauge
+truvis_fps 60
+# HELP truvis_uptime_seconds Total running time of the renderer in seconds.
+# TYPE truvis_uptime_seconds gauge
+truvis_uptime_seconds 1.25
+# HELP truvis_gpu_pass_time_ms GPU time of each render pass in milliseconds.
+# TYPE truvis_gpu_pass_time_ms gauge
+truvis_gpu_pass_time_ms{pass="blit \"final\""} 0.25
+truvis_gpu_pass_time_ms{pass="rt"} 2.5
+# HELP truvis_vram_usage_bytes Device memory used by this process per heap.
+# TYPE truvis_vram_usage_bytes gauge
+truvis_vram_usage_bytes{heap="1"} 1024
+# HELP truvis_vram_budget_bytes Device memory budget per heap.
+# TYPE truvis_vram_budget_bytes gauge
+truvis_vram_budget_bytes{heap="1"} 4096
+"#;
+        assert_eq!(snapshot.encode_prometheus(), expected);
+    }
+
+    #[test]
+    fn test_record_gpu_pass_times_replaces_previous_frame() {
+        let metrics = RenderMetrics::new();
+        metrics.record_gpu_pass_times(&[("gbuffer".to_string(), 1.0), ("rt".to_string(), 3.0)]);
+        metrics.record_gpu_pass_times(&[("rt".to_string(), 4.0)]);
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.gpu_pass_time_ms, BTreeMap::from([("rt".to_string(), 4.0)]));
+        assert!(snapshot.encode_prometheus().contains("truvis_gpu_pass_time_ms{pass=\"rt\"} 4\n"));
+    }
+}
@@ -1,163 +1,261 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, bail};
 use itertools::Itertools;
-use truvis_asset::asset_hub::AssetHub;
 use truvis_cxx_binding::truvixx;
-use truvis_gfx::resources::special_buffers::index_buffer::GfxIndex32Buffer;
-use truvis_gfx::resources::vertex_layout::soa_3d::VertexLayoutSoA3D;
-use truvis_render_interface::geometry::RtGeometry;
-use truvis_scene::components::instance::Instance;
-use truvis_scene::components::material::Material;
-use truvis_scene::components::mesh::Mesh;
-use truvis_scene::guid_new_type::{InstanceHandle, MaterialHandle, MeshHandle};
-use truvis_scene::scene_manager::SceneManager;
+use truvis_scene::aabb::Aabb;
+use truvis_scene::components::material::{CullMode, Material};
+
+use crate::model_loader::mesh_optimizer::MeshData;
+use crate::model_loader::parsed_scene::{ParseProgress, ParsedGeometry, ParsedInstance, ParsedMesh, ParsedScene};
+use crate::model_loader::tangent::{generate_tangents, is_missing_tangents, tangents_with_handedness};
+use crate::model_loader::{ModelLoadOptions, SceneLoader};
 
 /// Assimp 场景加载器
 ///
-/// 封装 Assimp 库，提供场景加载功能。支持多种 3D 模型格式（FBX、GLTF、OBJ 等）。
+/// 封装 Assimp 库，提供场景加载功能。支持多种 3D 模型格式（FBX、OBJ、DAE 等）。
+///
+/// Assimp 根据文件内容识别格式，[`SceneLoader::extensions`] 只列出常用的格式；
+/// 其他加载器都不支持的文件也交给 Assimp，参见 [`scene_loader_for`](super::scene_loader_for)
 ///
 /// # 使用示例
 /// ```ignore
-/// let instances = AssimpSceneLoader::load_scene(
-///     Path::new("model.fbx"),
-///     |ins| scene_manager.register_instance(ins),
-///     |mesh| scene_manager.register_mesh(mesh),
-///     |mat| scene_manager.register_material(mat),
-/// );
+/// let instances =
+///     AssimpSceneLoader.load(Path::new("model.fbx"), &ModelLoadOptions::default(), &mut scene_manager, &mut asset_hub);
 /// ```
-pub struct AssimpSceneLoader {
+pub struct AssimpSceneLoader;
+
+/// 解析一个 Assimp 场景时的中间状态
+struct AssimpParser {
     scene_handle: truvixx::TruvixxSceneHandle,
     model_name: String,
 
-    meshes: Vec<MeshHandle>,
-    mats: Vec<MaterialHandle>,
-    instances: Vec<InstanceHandle>,
+    /// 场景中所有包含几何体的 Assimp node
+    nodes: Vec<AssimpNode>,
+    /// Assimp mesh 索引 -> [`ParsedScene::geometries`] 中的索引，被多个 node 引用的 Assimp mesh 只解析一次
+    geometries: HashMap<u32, usize>,
+    /// node 引用的 Assimp mesh 索引列表 -> [`ParsedScene::meshes`] 中的索引，引用相同列表的 node 共享同一个 Mesh
+    meshes: HashMap<Vec<u32>, usize>,
+    /// 开启 [`ModelLoadOptions::merge_nodes`] 时，所有 node 合并得到的 Mesh
+    merged_mesh: Option<usize>,
+
+    /// 材质在 [`ParsedScene::materials`] 中的索引与 Assimp 材质索引相同
+    parsed: ParsedScene,
 }
 
-impl AssimpSceneLoader {
-    /// # return
-    /// 返回整个场景的所有 instance id
-    pub fn load_scene(
+/// Assimp 中的一个 node
+///
+/// Assimp 的 mesh 只有一个材质，对应这里的 geometry；一个 node 可以引用多个 mesh，对应这里的 Mesh
+struct AssimpNode {
+    name: String,
+    transform: glam::Mat4,
+    /// 引用的 Assimp mesh 索引，和 `mat_indices` 一一对应
+    geometry_indices: Vec<u32>,
+    mat_indices: Vec<u32>,
+}
+
+impl SceneLoader for AssimpSceneLoader {
+    fn extensions(&self) -> &'static [&'static str] {
+        &[
+            "obj", "fbx", "dae", "3ds", "blend", "ply", "stl", "x", "lwo", "ms3d", "3mf", "off",
+        ]
+    }
+
+    fn parse(
+        &self,
         model_file: &std::path::Path,
-        scene_manager: &mut SceneManager,
-        asset_hub: &mut AssetHub,
-    ) -> Vec<InstanceHandle> {
-        let _span = tracy_client::span!("AssimpSceneLoader::load_scene");
+        options: &ModelLoadOptions,
+        progress: &ParseProgress,
+    ) -> anyhow::Result<ParsedScene> {
+        let _span = tracy_client::span!("AssimpSceneLoader::parse");
 
-        let model_file = model_file.to_str().unwrap();
-        let c_model_file = std::ffi::CString::new(model_file).unwrap();
+        let model_file = model_file.to_str().with_context(|| format!("invalid model path: {:?}", model_file))?;
+        let c_model_file = std::ffi::CString::new(model_file)?;
 
-        let loader = unsafe {
+        let scene_handle = unsafe {
             let _span = tracy_client::span!("truvixx_scene_load");
             truvixx::truvixx_scene_load(c_model_file.as_ptr())
         };
+        if scene_handle.is_null() {
+            bail!("Assimp failed to load {}", model_file);
+        }
         let model_name = model_file.split('/').next_back().unwrap();
 
-        let mut scene_loader = AssimpSceneLoader {
-            scene_handle: loader,
+        let mut scene_loader = AssimpParser {
+            scene_handle,
             model_name: model_name.to_string(),
-            meshes: vec![],
-            mats: vec![],
-            instances: vec![],
+            nodes: vec![],
+            geometries: HashMap::new(),
+            meshes: HashMap::new(),
+            merged_mesh: None,
+            parsed: ParsedScene::default(),
         };
-
-        scene_loader.load_mesh(|mut mesh| {
-            mesh.build_blas();
-            scene_manager.register_mesh(mesh)
-        });
-        scene_loader.load_mats(|mat| {
-            if !mat.diffuse_map.is_empty() {
-                asset_hub.load_texture(std::path::PathBuf::from(&mat.diffuse_map));
-            }
-            scene_manager.register_mat(mat)
-        });
-        scene_loader.load_instance(|ins| scene_manager.register_instance(ins));
+        let result = scene_loader.parse_scene(options, progress);
 
         {
             let _span = tracy_client::span!("truvixx_scene_free");
-            unsafe { truvixx::truvixx_scene_free(loader) };
+            unsafe { truvixx::truvixx_scene_free(scene_handle) };
         }
 
-        scene_loader.instances
+        result.map(|()| scene_loader.parsed)
+    }
+}
+
+impl AssimpParser {
+    /// 依次读取 node、geometry、材质，解析结果存放在 `self.parsed` 中
+    fn parse_scene(&mut self, options: &ModelLoadOptions, progress: &ParseProgress) -> anyhow::Result<()> {
+        self.load_nodes()?;
+        self.parse_geometries(options, progress)?;
+        if options.merge_nodes {
+            self.load_merged_mesh();
+        } else {
+            self.load_mesh();
+        }
+        self.load_mats()?;
+        if options.merge_nodes {
+            self.load_merged_instance();
+        } else {
+            self.load_instance();
+        }
+        Ok(())
     }
 
-    unsafe fn create_mesh(scene_handle: truvixx::TruvixxSceneHandle, mesh_idx: u32, model_name: &str) -> Mesh {
+    unsafe fn read_mesh_data(
+        scene_handle: truvixx::TruvixxSceneHandle,
+        mesh_idx: u32,
+        model_name: &str,
+        optimize_mesh: bool,
+    ) -> anyhow::Result<MeshData> {
         unsafe {
             let mut mesh_info = truvixx::TruvixxMeshInfo::default();
             let res = truvixx::truvixx_mesh_get_info(scene_handle, mesh_idx, &mut mesh_info as *mut _);
             if res != truvixx::ResType_ResTypeSuccess {
-                panic!("Failed to get mesh info for mesh {}", mesh_idx);
+                bail!("Failed to get mesh info for mesh {}", mesh_idx);
             }
 
             let position_ptr = truvixx::truvixx_mesh_get_positions(scene_handle, mesh_idx);
             let normal_ptr = truvixx::truvixx_mesh_get_normals(scene_handle, mesh_idx);
             let tangent_ptr = truvixx::truvixx_mesh_get_tangents(scene_handle, mesh_idx);
             let uv_ptr = truvixx::truvixx_mesh_get_uvs(scene_handle, mesh_idx);
-            if position_ptr.is_null() || normal_ptr.is_null() || tangent_ptr.is_null() || uv_ptr.is_null() {
-                panic!("Mesh {} is missing vertex attributes", mesh_idx);
+            if position_ptr.is_null() || normal_ptr.is_null() || uv_ptr.is_null() {
+                bail!("Mesh {} is missing vertex attributes", mesh_idx);
             }
 
             let positions =
                 std::slice::from_raw_parts(position_ptr as *const glam::Vec3, mesh_info.vertex_count as usize);
             let normals = std::slice::from_raw_parts(normal_ptr as *const glam::Vec3, mesh_info.vertex_count as usize);
-            let tangents =
-                std::slice::from_raw_parts(tangent_ptr as *const glam::Vec3, mesh_info.vertex_count as usize);
+            let tangents = (!tangent_ptr.is_null())
+                .then(|| std::slice::from_raw_parts(tangent_ptr as *const glam::Vec3, mesh_info.vertex_count as usize));
             let uvs = std::slice::from_raw_parts(uv_ptr as *const glam::Vec2, mesh_info.vertex_count as usize);
 
-            let vertex_buffer = VertexLayoutSoA3D::create_vertex_buffer(
-                positions,
-                normals,
-                tangents,
-                uvs,
-                format!("{}-mesh-{}", model_name, mesh_idx),
-            );
-
             let indices_ptr = truvixx::truvixx_mesh_get_indices(scene_handle, mesh_idx);
             if indices_ptr.is_null() {
-                panic!("Mesh {} has no index data", mesh_idx);
+                bail!("Mesh {} has no index data", mesh_idx);
             }
 
             let indices = std::slice::from_raw_parts(indices_ptr, mesh_info.index_count as usize);
 
-            let index_buffer =
-                GfxIndex32Buffer::new_device_local(indices.len(), format!("{}-mesh-{}-indices", model_name, mesh_idx));
-            index_buffer.transfer_data_sync(indices);
-
-            // 只有 single geometry 的 mesh
-            Mesh {
-                geometries: vec![RtGeometry {
-                    vertex_buffer,
-                    index_buffer,
-                }],
-                blas: None,
-                blas_device_address: None,
-                name: format!("{}-{}", model_name, mesh_idx),
+            // 很多 obj 没有切线数据，此时切线全为 0，需要重新生成，否则法线贴图无法正确使用；
+            // Assimp 的切线没有 w 分量，根据 uv 补上
+            let tangents = match tangents {
+                Some(tangents) if !is_missing_tangents(tangents) => {
+                    tangents_with_handedness(positions, normals, uvs, indices, tangents)
+                }
+                _ => {
+                    log::info!("{}-mesh-{} has no tangents, generate from uv", model_name, mesh_idx);
+                    generate_tangents(positions, normals, uvs, indices)
+                }
+            };
+
+            // Assimp 持有的数据是只读的，复制一份之后再重排
+            let mut mesh_data = MeshData {
+                positions: positions.to_vec(),
+                normals: normals.to_vec(),
+                tangents,
+                uvs: uvs.to_vec(),
+                indices: indices.to_vec(),
+            };
+            if optimize_mesh {
+                mesh_data.optimize();
             }
+            Ok(mesh_data)
+        }
+    }
+
+    /// 解析 node 引用的所有 Assimp mesh，每个 Assimp mesh 只解析一次，每解析完一个更新一次进度
+    fn parse_geometries(&mut self, options: &ModelLoadOptions, progress: &ParseProgress) -> anyhow::Result<()> {
+        let _span = tracy_client::span!("parse_geometries");
+        let mesh_indices =
+            self.nodes.iter().flat_map(|node| node.geometry_indices.iter().copied()).unique().collect_vec();
+        progress.set_total(mesh_indices.len());
+
+        for mesh_idx in mesh_indices {
+            let mesh_data =
+                unsafe { Self::read_mesh_data(self.scene_handle, mesh_idx, &self.model_name, options.optimize_mesh)? };
+            self.geometries.insert(mesh_idx, self.parsed.geometries.len());
+            self.parsed.geometries.push(ParsedGeometry {
+                name: format!("{}-mesh-{}", self.model_name, mesh_idx),
+                aabb: Aabb::from_points(&mesh_data.positions),
+                mesh_data,
+            });
+            progress.advance();
         }
+        Ok(())
     }
 
-    /// 加载场景中基础的几何体
-    fn load_mesh(&mut self, mut mesh_register: impl FnMut(Mesh) -> MeshHandle) {
+    /// 为每种 geometry 组合创建一个 Mesh，BLAS 会包含 Mesh 中的所有 geometry
+    ///
+    /// 被多种组合引用的 Assimp mesh 只有一份顶点数据，各个 Mesh 共享，BLAS 仍然各自构建
+    fn load_mesh(&mut self) {
         let _span = tracy_client::span!("load_mesh");
-        let mesh_cnt = unsafe { truvixx::truvixx_scene_mesh_count(self.scene_handle) };
 
-        let mesh_uuids = (0..mesh_cnt)
-            .map(|mesh_idx| unsafe {
-                let mesh = Self::create_mesh(self.scene_handle, mesh_idx, &self.model_name);
-                mesh_register(mesh)
-            })
-            .collect_vec();
+        let combinations = self.nodes.iter().map(|node| node.geometry_indices.clone()).unique().collect_vec();
+        for geometry_indices in combinations {
+            self.meshes.insert(geometry_indices.clone(), self.parsed.meshes.len());
+            self.parsed.meshes.push(ParsedMesh {
+                name: format!("{}-{}", self.model_name, geometry_indices.iter().join("+")),
+                geometry_indices: geometry_indices.iter().map(|mesh_idx| self.geometries[mesh_idx]).collect_vec(),
+                geometry_transforms: None,
+            });
+        }
+    }
+
+    /// 将所有 node 的 geometry 合并为一个 Mesh，参见 [`ModelLoadOptions::merge_nodes`]
+    ///
+    /// Assimp 的 mesh 位于 node 空间，因此每个 geometry 相对于 mesh 的变换就是所在 node 的世界变换；
+    /// 被多个 node 引用的 Assimp mesh 在合并后的 Mesh 中出现多次，但只有一份顶点数据
+    fn load_merged_mesh(&mut self) {
+        let _span = tracy_client::span!("load_merged_mesh");
+
+        let (geometry_indices, geometry_transforms): (Vec<_>, Vec<_>) = self
+            .nodes
+            .iter()
+            .flat_map(|node| node.geometry_indices.iter().map(|mesh_idx| (self.geometries[mesh_idx], node.transform)))
+            .unzip();
+        if geometry_indices.is_empty() {
+            log::warn!("{} has no geometry, skipped", self.model_name);
+            return;
+        }
 
-        self.meshes = mesh_uuids;
+        self.merged_mesh = Some(self.parsed.meshes.len());
+        self.parsed.meshes.push(ParsedMesh {
+            name: format!("{}-merged", self.model_name),
+            geometry_indices,
+            geometry_transforms: Some(geometry_transforms),
+        });
     }
 
-    unsafe fn create_mat(scene_handle: truvixx::TruvixxSceneHandle, mat_idx: u32) -> Material {
+    unsafe fn create_mat(scene_handle: truvixx::TruvixxSceneHandle, mat_idx: u32) -> anyhow::Result<Material> {
         unsafe {
             let mut mat = truvixx::TruvixxMat::default();
             let res = truvixx::truvixx_material_get(scene_handle, mat_idx, &mut mat as *mut _);
             if res != truvixx::ResType_ResTypeSuccess {
-                panic!("Failed to get material {}", mat_idx);
+                bail!("Failed to get material {}", mat_idx);
             }
 
-            Material {
+            Ok(Material {
+                name: std::ffi::CStr::from_ptr(mat.name.as_ptr()).to_string_lossy().into_owned(),
+
                 base_color: std::mem::transmute::<truvixx::TruvixxFloat4, glam::Vec4>(mat.base_color),
                 emissive: std::mem::transmute::<truvixx::TruvixxFloat4, glam::Vec4>(mat.emissive),
                 metallic: mat.metallic,
@@ -166,82 +264,108 @@ impl AssimpSceneLoader {
 
                 diffuse_map: std::ffi::CStr::from_ptr(mat.diffuse_map.as_ptr()).to_str().unwrap().to_string(),
                 normal_map: std::ffi::CStr::from_ptr(mat.normal_map.as_ptr()).to_str().unwrap().to_string(),
-            }
+                normal_scale: mat.normal_scale,
+
+                cull_mode: if mat.two_sided != 0 { CullMode::None } else { CullMode::Back },
+
+                ..Default::default()
+            })
         }
     }
 
     /// 加载场景中的所有材质
-    fn load_mats(&mut self, mut mat_register: impl FnMut(Material) -> MaterialHandle) {
+    fn load_mats(&mut self) -> anyhow::Result<()> {
         let _span = tracy_client::span!("load_mats");
         let mat_cnt = unsafe { truvixx::truvixx_scene_material_count(self.scene_handle) };
 
-        let mat_uuids = (0..mat_cnt)
-            .map(|mat_idx| unsafe {
-                let mat = Self::create_mat(self.scene_handle, mat_idx);
-                mat_register(mat)
-            })
-            .collect_vec();
-
-        self.mats = mat_uuids;
+        self.parsed.materials = (0..mat_cnt)
+            .map(|mat_idx| unsafe { Self::create_mat(self.scene_handle, mat_idx) })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(())
     }
 
-    unsafe fn create_instance(&self, instance_idx: u32, instance: truvixx::TruvixxInstance) -> Vec<Instance> {
-        let mut mesh_indices = vec![0_u32; instance.mesh_count as usize];
+    unsafe fn create_node(&self, instance_idx: u32, instance: truvixx::TruvixxInstance) -> anyhow::Result<AssimpNode> {
+        let mut geometry_indices = vec![0_u32; instance.mesh_count as usize];
         let mut mat_indices = vec![0_u32; instance.mesh_count as usize];
 
         let res = unsafe {
             truvixx::truvixx_instance_get_refs(
                 self.scene_handle,
                 instance_idx,
-                mesh_indices.as_mut_ptr(),
+                geometry_indices.as_mut_ptr(),
                 mat_indices.as_mut_ptr(),
             )
         };
         if res != truvixx::ResType_ResTypeSuccess {
-            panic!("Failed to get instance {} refs", instance_idx);
+            bail!("Failed to get instance {} refs", instance_idx);
         }
 
-        let mesh_uuids = mesh_indices.iter().map(|mesh_idx| self.meshes[*mesh_idx as usize]);
-        let mat_uuids = mat_indices.iter().map(|mat_idx| self.mats[*mat_idx as usize]);
+        Ok(AssimpNode {
+            name: unsafe { std::ffi::CStr::from_ptr(instance.name.as_ptr()) }.to_string_lossy().into_owned(),
+            transform: unsafe { std::mem::transmute::<truvixx::TruvixxFloat4x4, glam::Mat4>(instance.world_transform) },
+            geometry_indices,
+            mat_indices,
+        })
+    }
 
-        std::iter::zip(mesh_uuids, mat_uuids)
-            .map(|(mesh_uuid, mat_uuid)| Instance {
-                transform: unsafe {
-                    std::mem::transmute::<truvixx::TruvixxFloat4x4, glam::Mat4>(instance.world_transform)
-                },
-                mesh: mesh_uuid,
-                materials: vec![mat_uuid],
-            })
-            .collect_vec()
+    /// 读取场景中所有包含几何体的 node
+    fn load_nodes(&mut self) -> anyhow::Result<()> {
+        let _span = tracy_client::span!("load_nodes");
+        let instance_cnt = unsafe { truvixx::truvixx_scene_instance_count(self.scene_handle) };
+
+        let mut nodes = vec![];
+        for instance_idx in 0..instance_cnt {
+            let mut instance = truvixx::TruvixxInstance::default();
+            let res =
+                unsafe { truvixx::truvixx_instance_get(self.scene_handle, instance_idx, &mut instance as *mut _) };
+            if res != truvixx::ResType_ResTypeSuccess {
+                bail!("Failed to get instance {}", instance_idx);
+            }
+
+            // 排除空间点，比如 camera, light
+            if instance.mesh_count != 0 {
+                nodes.push(unsafe { self.create_node(instance_idx, instance)? });
+            }
+        }
+        self.nodes = nodes;
+        Ok(())
     }
 
     /// 加载场景中的所有 instance
     ///
-    /// 由于 Assimp 的复用层级是 geometry，而应用需要的复用层级是 mesh
-    ///
-    /// 因此将 Assimp 中的一个 Instance 拆分为多个 Instance，将其 geometry
-    /// 提升为 mesh
-    fn load_instance(&mut self, instance_register: impl FnMut(Instance) -> InstanceHandle) {
+    /// 每个 Assimp node 对应一个 Instance，材质按照 Mesh 中 geometry 的顺序排列
+    fn load_instance(&mut self) {
         let _span = tracy_client::span!("load_instance");
-        let instance_cnt = unsafe { truvixx::truvixx_scene_instance_count(self.scene_handle) };
-        let instances = (0..instance_cnt)
-            .filter_map(|instance_idx| {
-                let mut instance = truvixx::TruvixxInstance::default();
-                let res =
-                    unsafe { truvixx::truvixx_instance_get(self.scene_handle, instance_idx, &mut instance as *mut _) };
-                if res != truvixx::ResType_ResTypeSuccess {
-                    panic!("Failed to get instance {}", instance_idx);
-                }
-
-                // 排除空间点，比如 camera, light
-                if instance.mesh_count == 0 { None } else { Some((instance_idx, instance)) }
+        self.parsed.instances = self
+            .nodes
+            .iter()
+            .map(|node| ParsedInstance {
+                mesh: self.meshes[&node.geometry_indices],
+                materials: node.mat_indices.iter().map(|&mat_idx| mat_idx as usize).collect_vec(),
+                transform: node.transform,
+                name: node.name.clone(),
             })
-            .flat_map(|(instance_idx, instance_info)| unsafe {
-                self.create_instance(instance_idx, instance_info).into_iter()
-            })
-            .map(instance_register)
             .collect_vec();
+    }
 
-        self.instances = instances
+    /// 为合并后的 Mesh 创建唯一的 Instance，材质按照 node 的顺序依次排列
+    fn load_merged_instance(&mut self) {
+        let _span = tracy_client::span!("load_merged_instance");
+        let Some(mesh) = self.merged_mesh else {
+            return;
+        };
+
+        let instance = ParsedInstance {
+            mesh,
+            materials: self
+                .nodes
+                .iter()
+                .flat_map(|node| &node.mat_indices)
+                .map(|&mat_idx| mat_idx as usize)
+                .collect_vec(),
+            transform: glam::Mat4::IDENTITY,
+            name: self.model_name.clone(),
+        };
+        self.parsed.instances = vec![instance];
     }
 }
@@ -1,361 +1,634 @@
-//! 进行模型处理
+//! 不经过 Assimp，直接使用 `gltf` crate 加载 glTF 2.0 模型
 //!
 //! gltf 的格式，参考 https://www.khronos.org/files/gltf20-reference-guide.pdf
 
-use std::{mem::size_of, rc::Rc};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 
-use ash::vk;
-use glam::f32::Mat4;
-use itertools::{izip, Itertools};
-use static_init::raw_static::Static;
+use anyhow::Context;
+use base64::Engine;
+use itertools::Itertools;
+use truvis_scene::aabb::Aabb;
+use truvis_scene::components::material::{CullMode, Material};
+use truvis_shader_binding::truvisl;
 
-use crate::{
-    resource::model::StaticMeshData,
-    gfx_type::{image::GfxImage2D, sampler::GfxSampler},
+use crate::model_loader::mesh_optimizer::MeshData;
+use crate::model_loader::parsed_scene::{
+    ParseProgress, ParsedGeometry, ParsedImage, ParsedInstance, ParsedMesh, ParsedScene,
 };
+use crate::model_loader::tangent::generate_tangents;
+use crate::model_loader::{ModelLoadOptions, SceneLoader};
 
-
-/// 导入 gltf 格式的模型
+/// glTF 2.0 场景加载器
+///
+/// 和 [`AssimpSceneLoader`](super::assimp_loader::AssimpSceneLoader) 产出相同的 Mesh、Material、Instance：
+/// - glTF 的 mesh 对应 Mesh，其中每个三角形 primitive 对应一个 geometry
+/// - 每个引用了 mesh 的 node 对应一个 Instance，变换为 node 层级累积后的世界变换
+/// - 开启 [`ModelLoadOptions::merge_nodes`] 时，所有 node 合并为一个 Mesh 和一个 Instance，
+///   node 的世界变换作为 geometry 相对于 mesh 的变换
+///
+/// 支持 metallic-roughness、normal、emissive、occlusion 贴图，buffer 和图片可以是外部文件、
+/// glb 内嵌数据或者 base64 data URI。不支持 skin 和动画。
 ///
-/// 支持 mesh，不支持 skin，动画
-pub struct GltfLoader
-{
-    gltf_doc: gltf::Document,
+/// # 使用示例
+/// ```ignore
+/// let instances =
+///     GltfSceneLoader.load(Path::new("model.gltf"), &ModelLoadOptions::default(), &mut scene_manager, &mut asset_hub);
+/// ```
+pub struct GltfSceneLoader;
+
+/// 解析一个 glTF 文件时的中间状态
+struct GltfParser {
+    document: gltf::Document,
     buffers: Vec<gltf::buffer::Data>,
-    images: Vec<gltf::image::Data>,
+    /// 外部资源的相对路径以此为基准
+    base_dir: PathBuf,
+    model_name: String,
+
+    /// gltf image 索引 -> 纹理路径；内嵌图片使用 `<模型路径>#image<索引>` 作为虚拟路径
+    image_paths: Vec<String>,
+    /// gltf mesh 索引 -> 其中每个三角形 primitive 在 [`ParsedScene::geometries`] 中的索引，
+    /// 被多个 node 引用的 mesh 只解析一次
+    mesh_geometries: Vec<Vec<usize>>,
+    /// gltf mesh 索引 -> [`ParsedScene::meshes`] 中的索引，没有三角形 primitive 的 mesh 为 None
+    meshes: Vec<Option<usize>>,
+    /// 开启 [`ModelLoadOptions::merge_nodes`] 时，所有 node 合并得到的 Mesh
+    merged_mesh: Option<usize>,
+    /// 没有指定材质的 primitive 使用 glTF 规定的默认材质，位于所有 gltf 材质之后；
+    /// 其余材质在 [`ParsedScene::materials`] 中的索引与 gltf material 索引相同
+    default_mat: Option<usize>,
+
+    parsed: ParsedScene,
 }
 
-impl GltfLoader
-{
-    /// 从 gltf 文件中载入模型
-    pub fn load(path: &std::path::Path) -> Vec<HissNode>
-    {
-        let loader = Self::from_file(core, path);
-        loader.process_scene()
+impl SceneLoader for GltfSceneLoader {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["gltf", "glb"]
     }
 
-    /// 根据 gltf 文件创建 loader，使用 loader 来读取模型数据
-    fn from_file(core: Rc<HissGfxCore>, path: &std::path::Path) -> Self
-    {
-        let (doc, buffers, images) =
-            gltf::import(path).unwrap_or_else(|_| panic!("failed to open gltf file: {:?}", path));
-
-        Self {
-            core,
-            gltf_doc: doc,
+    fn parse(
+        &self,
+        model_file: &Path,
+        options: &ModelLoadOptions,
+        progress: &ParseProgress,
+    ) -> anyhow::Result<ParsedScene> {
+        let _span = tracy_client::span!("GltfSceneLoader::parse");
+
+        let gltf::Gltf { document, blob } = gltf::Gltf::open(model_file)
+            .with_context(|| format!("failed to open gltf file {}", model_file.display()))?;
+        let base_dir = model_file.parent().unwrap_or(Path::new("")).to_path_buf();
+        let buffers = gltf::import_buffers(&document, Some(&base_dir), blob)
+            .with_context(|| format!("failed to load gltf buffers of {}", model_file.display()))?;
+
+        let mut scene_loader = GltfParser {
+            document,
             buffers,
-            images,
+            base_dir,
+            model_name: model_file.file_name().unwrap_or_default().to_string_lossy().to_string(),
+            image_paths: vec![],
+            mesh_geometries: vec![],
+            meshes: vec![],
+            merged_mesh: None,
+            default_mat: None,
+            parsed: ParsedScene::default(),
+        };
+
+        scene_loader.load_images(model_file);
+        if options.merge_nodes {
+            // 只解析场景中的 node 引用的 mesh
+            let mesh_indices = scene_loader
+                .scene_mesh_nodes()
+                .iter()
+                .map(|(node, _)| node.mesh().unwrap().index())
+                .collect::<HashSet<_>>();
+            scene_loader.parse_geometries(&mesh_indices, options, progress)?;
+            scene_loader.load_merged_mesh();
+        } else {
+            let mesh_indices =
+                scene_loader.document.meshes().map(|gltf_mesh| gltf_mesh.index()).collect::<HashSet<_>>();
+            scene_loader.parse_geometries(&mesh_indices, options, progress)?;
+            scene_loader.load_mesh();
+        }
+        scene_loader.load_mats();
+        if options.merge_nodes {
+            scene_loader.load_merged_instance();
+        } else {
+            scene_loader.load_instance();
         }
+
+        Ok(scene_loader.parsed)
     }
+}
 
-    /// 处理 gltf 的场景，场景是 gltf 中最大的层级
-    ///
-    /// ```json
-    /// {
-    ///     "scene": 0, // 通过该字段指定默认场景
-    ///     "scenes": [
-    ///         { "nodes": [ 0, 1, 2 ] },
-    ///         { "nodes": ... }
-    ///     ],
-    /// }
-    /// ```
-    fn process_scene(&self) -> Vec<HissNode>
-    {
-        // 读取默认场景，否则读取 0 号场景
-        let default_scene = self.gltf_doc.default_scene();
-        let scene = default_scene.unwrap_or_else(|| self.gltf_doc.scenes().next().unwrap());
+impl GltfParser {
+    /// 被材质用作 base color 或者 emissive 的图片，这些图片按 sRGB 加载，其余图片存放的是线性的数据
+    fn color_image_indices(&self) -> HashSet<usize> {
+        self.document
+            .materials()
+            .flat_map(|gltf_mat| {
+                [
+                    gltf_mat.pbr_metallic_roughness().base_color_texture().map(|info| info.texture()),
+                    gltf_mat.emissive_texture().map(|info| info.texture()),
+                ]
+            })
+            .flatten()
+            .map(|texture| texture.source().index())
+            .collect()
+    }
 
-        let mut nodes = vec![];
-        for node in scene.nodes() {
-            let node = self.process_node(&node, &Mat4::IDENTITY);
-            nodes.push(node);
-        }
+    /// 确定所有图片的路径，内嵌图片的数据放入 [`ParsedScene::embedded_images`]，注册时交给 AssetHub 解码
+    fn load_images(&mut self, model_file: &Path) {
+        let _span = tracy_client::span!("load_images");
+        let color_images = self.color_image_indices();
+        let mut embedded_images = vec![];
+        self.image_paths = self
+            .document
+            .images()
+            .map(|image| {
+                let is_srgb = color_images.contains(&image.index());
+                let mut embed = |encoded: Vec<u8>| {
+                    let path = PathBuf::from(format!("{}#image{}", model_file.display(), image.index()));
+                    embedded_images.push(ParsedImage {
+                        path: path.clone(),
+                        encoded,
+                        is_srgb,
+                    });
+                    path
+                };
+                let path = match image.source() {
+                    gltf::image::Source::View { view, .. } => {
+                        let buffer = &self.buffers[view.buffer().index()];
+                        embed(buffer[view.offset()..view.offset() + view.length()].to_vec())
+                    }
+                    gltf::image::Source::Uri { uri, .. } => match decode_data_uri(uri) {
+                        Some(encoded) => embed(encoded),
+                        None => self.base_dir.join(uri),
+                    },
+                };
+                path.to_string_lossy().to_string()
+            })
+            .collect_vec();
+        self.parsed.embedded_images = embedded_images;
+    }
 
-        nodes
+    fn mesh_name(&self, gltf_mesh: &gltf::Mesh<'_>) -> String {
+        format!("{}-{}", self.model_name, gltf_mesh.name().map_or(gltf_mesh.index().to_string(), String::from))
     }
 
-    /// 处理 gltf 中的一个 node
-    ///
-    /// node 可以包含 transform，mesh，camera，light 信息
-    ///
-    /// ```json
-    /// {
-    ///     "children": ...,
-    ///
-    ///     // transform 以 matrix 形式整体指定，或者分别指定
-    ///     "matrix": ...,
-    ///     "translation": ...,
-    ///     "rotation": ...,
-    ///     "scale": ...,
-    ///
-    ///     // node 中要么包含 mesh，要么包含 camera
-    ///     "mesh": 4,
-    ///     "camera": 5,
-    /// }
-    /// ```
-    fn process_node(&self, node: &gltf::Node, parent_matrix: &Mat4) -> HissNode
-    {
-        // 处理 transform 信息
-        // gltf 文件采用的是「右手系」
-        // gltf 这个库使用 column major 的方式存放矩阵（每个元素相当于矩阵的一列）
-        let local_matrix = Mat4::from_cols_array_2d(&node.transform().matrix());
-        let matrix = parent_matrix.mul_mat4(&local_matrix);
-
-        // gltf 的一个 mesh 中可以有多个 primitive，所以可以解析出多个可渲染的 Mesh 对象
-        let meshes: Vec<Box<dyn HissMesh>> = node.mesh().map_or(vec![], |gltf_mesh| self.process_mesh(&gltf_mesh));
-
-        let children: Vec<HissNode> = node.children().map(|node| self.process_node(&node, &matrix)).collect();
-
-        HissNode::new(meshes, children, matrix)
+    /// 解析 `mesh_indices` 中每个 mesh 的三角形 primitive，每解析完一个 primitive 更新一次进度
+    fn parse_geometries(&mut self, mesh_indices: &HashSet<usize>, progress: &ParseProgress) -> anyhow::Result<()> {
+        let _span = tracy_client::span!("parse_geometries");
+        let gltf_meshes =
+            self.document.meshes().filter(|gltf_mesh| mesh_indices.contains(&gltf_mesh.index())).collect_vec();
+        progress.set_total(gltf_meshes.iter().map(|gltf_mesh| triangle_primitives(gltf_mesh.clone()).count()).sum());
+
+        self.mesh_geometries = vec![vec![]; self.document.meshes().len()];
+        for gltf_mesh in gltf_meshes {
+            let mesh_name = self.mesh_name(&gltf_mesh);
+            if let Some(primitive) = gltf_mesh.primitives().find(|p| p.mode() != gltf::mesh::Mode::Triangles) {
+                log::warn!("gltf mesh {}: primitive mode {:?} is not supported, skipped", mesh_name, primitive.mode());
+            }
+            for (idx, primitive) in triangle_primitives(gltf_mesh.clone()).enumerate() {
+                let name = format!("{}-{}", mesh_name, idx);
+                let mut mesh_data = read_mesh_data(&self.buffers, &primitive, &name)?;
+                if options.optimize_mesh {
+                    mesh_data.optimize();
+                }
+                self.mesh_geometries[gltf_mesh.index()].push(self.parsed.geometries.len());
+                self.parsed.geometries.push(ParsedGeometry {
+                    aabb: Aabb::from_points(&mesh_data.positions),
+                    name,
+                    mesh_data,
+                });
+                progress.advance();
+            }
+        }
+        Ok(())
     }
 
-    /// 处理 gltf 中的 mesh
-    ///
-    /// mesh 包括：indices，vertices attribute
-    ///
-    /// 一个 mesh 中可以有多个 `primitive`，每个 `primitive` 都是单独可渲染的
-    /// 一个 mesh 的结构如下：
+    /// 每个 glTF mesh 对应一个 Mesh，mesh 中的每个三角形 primitive 作为一个 geometry
+    fn load_mesh(&mut self) {
+        let _span = tracy_client::span!("load_mesh");
+        let mut meshes = vec![];
+        for gltf_mesh in self.document.meshes() {
+            let mesh_name = self.mesh_name(&gltf_mesh);
+            let geometry_indices = self.mesh_geometries[gltf_mesh.index()].clone();
+            if geometry_indices.is_empty() {
+                log::warn!("gltf mesh {} has no triangle primitive, skipped", mesh_name);
+                meshes.push(None);
+                continue;
+            }
+
+            meshes.push(Some(self.parsed.meshes.len()));
+            self.parsed.meshes.push(ParsedMesh {
+                name: mesh_name,
+                geometry_indices,
+                geometry_transforms: None,
+            });
+        }
+        self.meshes = meshes;
+    }
+
+    /// 将场景中所有 node 的 primitive 合并为一个 Mesh，参见 [`ModelLoadOptions::merge_nodes`]
     ///
-    /// ```json
-    /// {
-    ///    "primitives": [
-    ///        {
-    ///            "mode": 4,  // 点，线，三角面
-    ///            "indices: n,
-    ///            "attributes": {
-    ///                "POSITION": n,
-    ///                "NORMAL": n,
-    ///            },
-    ///            "material": n,
-    ///        }
-    ///    ]
-    /// },
-    /// ```
-    fn process_mesh(&self, mesh: &gltf::Mesh) -> Vec<Box<dyn HissMesh>>
-    {
-        let mut meshes: Vec<Box<dyn HissMesh>> = vec![];
-
-        for primitive in mesh.primitives() {
-            assert_eq!(primitive.mode(), gltf::mesh::Mode::Triangles);
-
-            let (vertex_buffer, vertex_cnt) = self.loader_primitive_data(&primitive);
-            let (index_buffer, index_cnt) = self.create_index_buffer(&primitive);
-            let material = Rc::new(self.create_material(&primitive));
-
-            let mesh = HissMeshPNTBuilder::default()
-                .vertex_buffers(vec![vertex_buffer])
-                .vertex_cnt(vertex_cnt)
-                .index_buffer(index_buffer)
-                .indices_cnt(index_cnt)
-                .material(material)
-                .build()
-                .unwrap();
-            meshes.push(Box::new(mesh));
+    /// 被多个 node 引用的 mesh 在合并后的 Mesh 中出现多次，但只有一份顶点数据
+    fn load_merged_mesh(&mut self) {
+        let _span = tracy_client::span!("load_merged_mesh");
+
+        let mut geometry_indices = vec![];
+        let mut geometry_transforms = vec![];
+        for (node, transform) in self.scene_mesh_nodes() {
+            let mesh_geometries = &self.mesh_geometries[node.mesh().unwrap().index()];
+            geometry_indices.extend_from_slice(mesh_geometries);
+            geometry_transforms.extend(std::iter::repeat_n(transform, mesh_geometries.len()));
+        }
+        if geometry_indices.is_empty() {
+            log::warn!("gltf {} has no triangle primitive, skipped", self.model_name);
+            return;
         }
 
-        meshes
+        self.parsed.meshes.push(ParsedMesh {
+            name: format!("{}-merged", self.model_name),
+            geometry_indices,
+            geometry_transforms: Some(geometry_transforms),
+        });
+        self.merged_mesh = Some(self.parsed.meshes.len() - 1);
     }
 
-    fn loader_primitive_data(&self, primitive: &gltf::Primitive) -> StaticMeshData
-    {
-        const DEFAULT_NORMAL: [f32; 3] = [0_f32; 3];
-        const DEFAULT_TANGENT: [f32; 4] = [0_f32; 4];
-        const DEFAULT_UV: [f32; 2] = [0_f32; 2];
-
-        let mut mesh_data = StaticMeshData::default();
+    /// 将 glTF 的材质转换为 Material
+    fn create_mat(&self, gltf_mat: &gltf::Material) -> Material {
+        let texture_path = |texture: gltf::Texture, tex_coord: u32| {
+            if tex_coord != 0 {
+                log::warn!("gltf material {:?}: only TEXCOORD_0 is supported", gltf_mat.name());
+            }
+            self.image_paths[texture.source().index()].clone()
+        };
 
-        let reader = primitive.reader(|buffer| Some(self.buffers[buffer.index()].as_ref()));
+        let pbr = gltf_mat.pbr_metallic_roughness();
+
+        // 材质中所有贴图共用一个 sampler，优先使用 base color 贴图的 sampler
+        let textures = [
+            pbr.base_color_texture().map(|info| info.texture()),
+            gltf_mat.normal_texture().map(|info| info.texture()),
+            pbr.metallic_roughness_texture().map(|info| info.texture()),
+            gltf_mat.emissive_texture().map(|info| info.texture()),
+            gltf_mat.occlusion_texture().map(|info| info.texture()),
+        ];
+        let samplers = textures.iter().flatten().map(|texture| sampler_type(&texture.sampler())).collect_vec();
+        if !samplers.iter().all_equal() {
+            log::warn!("gltf material {:?}: textures use different samplers, the first one is used", gltf_mat.name());
+        }
+        let texture_sampler = samplers.first().copied().unwrap_or(truvisl::ESamplerType_LinearRepeat);
 
-        mesh_data.positions = reader.read_positions().unwrap().collect_vec();
-        let vertex_cnt = mesh_data.positions.len();
+        let base_color = glam::Vec4::from(pbr.base_color_factor());
+        let (opaque, diffuse_alpha) = match gltf_mat.alpha_mode() {
+            gltf::material::AlphaMode::Blend => (base_color.w, true),
+            gltf::material::AlphaMode::Opaque | gltf::material::AlphaMode::Mask => (1.0, false),
+        };
 
-        mesh_data.normal = reader.read_normals().map_or_else(|| vec![DEFAULT_NORMAL; vertex_cnt], Iterator::collect);
-        mesh_data.tangent = reader.read_tangents().map_or_else(|| vec![DEFAULT_TANGENT; vertex_cnt], Iterator::collect);
-        mesh_data.uv = reader.read_tex_coords(0).map_or_else(|| vec![DEFAULT_UV; vertex_cnt], Iterator::collect);
+        Material {
+            name: gltf_mat.name().unwrap_or_default().to_string(),
+
+            base_color,
+            emissive: glam::Vec3::from(gltf_mat.emissive_factor()).extend(1.0),
+            metallic: pbr.metallic_factor(),
+            roughness: pbr.roughness_factor(),
+            opaque,
+
+            diffuse_map: pbr
+                .base_color_texture()
+                .map_or_else(String::new, |info| texture_path(info.texture(), info.tex_coord())),
+            diffuse_alpha,
+            normal_map: gltf_mat
+                .normal_texture()
+                .map_or_else(String::new, |info| texture_path(info.texture(), info.tex_coord())),
+            normal_scale: gltf_mat.normal_texture().map_or(1.0, |info| info.scale()),
+
+            metallic_roughness_map: pbr
+                .metallic_roughness_texture()
+                .map_or_else(String::new, |info| texture_path(info.texture(), info.tex_coord())),
+            emissive_map: gltf_mat
+                .emissive_texture()
+                .map_or_else(String::new, |info| texture_path(info.texture(), info.tex_coord())),
+            occlusion_map: gltf_mat
+                .occlusion_texture()
+                .map_or_else(String::new, |info| texture_path(info.texture(), info.tex_coord())),
+            occlusion_strength: gltf_mat.occlusion_texture().map_or(1.0, |info| info.strength()),
+            texture_sampler,
+
+            cull_mode: if gltf_mat.double_sided() { CullMode::None } else { CullMode::Back },
 
-        assert!(
-            mesh_data.normal.len() == vertex_cnt &&
-                mesh_data.tangent.len() == vertex_cnt &&
-                mesh_data.uv.len() == vertex_cnt
-        );
+            ..Default::default()
+        }
+    }
 
-        mesh_data.index = reader.read_indices().expect("gltf file has no indices.").into_u32().collect();
+    /// 加载场景中的所有材质，只有存在未指定材质的 primitive 时才会创建默认材质
+    fn load_mats(&mut self) {
+        let _span = tracy_client::span!("load_mats");
+        self.parsed.materials = self.document.materials().map(|gltf_mat| self.create_mat(&gltf_mat)).collect_vec();
+
+        let default_mat_primitive = self
+            .document
+            .meshes()
+            .flat_map(triangle_primitives)
+            .find(|primitive| primitive.material().index().is_none());
+        if let Some(primitive) = default_mat_primitive {
+            let default_mat = self.create_mat(&primitive.material());
+            self.parsed.materials.push(default_mat);
+            self.default_mat = Some(self.parsed.materials.len() - 1);
+        }
+    }
 
-        mesh_data
+    /// mesh 中每个三角形 primitive 使用的材质
+    fn primitive_materials(&self, gltf_mesh: gltf::Mesh<'_>) -> Vec<usize> {
+        triangle_primitives(gltf_mesh)
+            .map(|primitive| match primitive.material().index() {
+                Some(mat_idx) => mat_idx,
+                None => self.default_mat.unwrap(),
+            })
+            .collect_vec()
     }
 
+    /// 默认场景中所有引用了 mesh 的 node 以及它们的世界变换，没有默认场景时使用第一个场景
+    fn scene_mesh_nodes(&self) -> Vec<(gltf::Node<'_>, glam::Mat4)> {
+        let Some(scene) = self.document.default_scene().or_else(|| self.document.scenes().next()) else {
+            log::warn!("gltf {} has no scene", self.model_name);
+            return vec![];
+        };
 
-    /// 创建材质对象
-    ///
-    /// gltf 中一个 material 的组成
-    /// ```json
-    /// {
-    ///     "pbrMetallicRoughness": {
-    ///         "baseColorTexture": {},
-    ///         "baseColorFactor": [f32; 4],
-    ///         "metallicRoughnessTexture": {},
-    ///         "metallicFactor": f32,
-    ///         "roughnessFactor": f32,
-    ///     },
-    ///     "normalTexture": {},
-    ///     "occlusionTexture": {},
-    ///     "emissiveTexture": {},
-    ///     "emissiveFactor": [f32; 3]
-    /// }
-    /// ```
-    fn create_material(&self, primitive: &gltf::Primitive) -> HissMatMR
-    {
-        let pbr = primitive.material().pbr_metallic_roughness();
-
-        // 读取 base color texture：以 sRGB 编码的
-        let base_color_tex = pbr.base_color_texture().map(|info| self.create_texture(info, true));
-
-        // 读取 metallic roughness texture
-        // metallic 位于 Blue 通道；Roughness 位于 Green 通道
-        // 线性编码
-        let mr_tex = pbr.metallic_roughness_texture().map(|info| self.create_texture(info, false));
-
-        let base_color_factor = pbr.base_color_factor();
-        let matallic_factor = pbr.metallic_factor();
-        let roughness_factor = pbr.roughness_factor();
-
-        HissMatMR::builder()
-            .base_color_tex(base_color_tex)
-            .base_color_factor(base_color_factor)
-            .metallic_roughness_tex(mr_tex)
-            .metallic_factor(matallic_factor)
-            .roughness_factor(roughness_factor)
-            .build()
-            .unwrap()
+        let mut nodes = vec![];
+        for node in scene.nodes() {
+            collect_mesh_nodes(node, &glam::Mat4::IDENTITY, &mut nodes);
+        }
+        nodes
     }
 
-    /// 根据 gltf 的 texture info 创建 Texture 对象
-    ///
-    /// 注 只支持 TexCoord == 0 的 texture
-    fn create_texture(&self, tex_info: gltf::texture::Info, s_rgb: bool) -> HissTexture
-    {
-        assert_eq!(tex_info.tex_coord(), 0);
-
-        let sampler = self.create_sampler(&tex_info.texture().sampler());
-        let image = self.create_image(&self.images[tex_info.texture().source().index()], s_rgb);
-        HissTexture::new(self.core.clone(), Rc::new(image), sampler)
+    /// 每个引用了 mesh 的 node 对应一个 instance
+    fn load_instance(&mut self) {
+        let _span = tracy_client::span!("load_instance");
+        let instances = self
+            .scene_mesh_nodes()
+            .into_iter()
+            .filter_map(|(node, transform)| {
+                let gltf_mesh = node.mesh().unwrap();
+                let mesh = self.meshes[gltf_mesh.index()]?;
+                Some(ParsedInstance {
+                    mesh,
+                    materials: self.primitive_materials(gltf_mesh),
+                    transform,
+                    name: node.name().unwrap_or_default().to_string(),
+                })
+            })
+            .collect_vec();
+
+        self.parsed.instances = instances;
     }
 
-    fn create_image(&self, image: &gltf::image::Data, s_rgb: bool) -> GfxImage2D
-    {
-        let image_info = vk::ImageCreateInfo::builder()
-            .image_type(vk::ImageType::TYPE_2D)
-            .format(Self::gltf_format_to_vk(image.format, s_rgb))
-            .extent(
-                vk::Extent2D {
-                    width: image.width,
-                    height: image.height,
-                }
-                .into(),
-            )
-            .usage(vk::ImageUsageFlags::SAMPLED);
+    /// 为合并后的 Mesh 创建唯一的 Instance，材质的顺序与 [`Self::load_merged_mesh`] 中的 geometry 一致
+    fn load_merged_instance(&mut self) {
+        let _span = tracy_client::span!("load_merged_instance");
+        let Some(mesh) = self.merged_mesh else {
+            return;
+        };
 
-        let alloc_info = vk_mem::AllocationCreateInfo {
-            usage: vk_mem::MemoryUsage::AutoPreferDevice,
-            ..Default::default()
+        let materials = self
+            .scene_mesh_nodes()
+            .into_iter()
+            .flat_map(|(node, _)| self.primitive_materials(node.mesh().unwrap()))
+            .collect_vec();
+        let instance = ParsedInstance {
+            mesh,
+            materials,
+            transform: glam::Mat4::IDENTITY,
+            name: self.model_name.clone(),
         };
+        self.parsed.instances = vec![instance];
+    }
+}
 
-        GfxImage2D::new(&image_info, &alloc_info)
+/// 递归收集引用了 mesh 的 node，`parent_transform` 为父节点的世界变换
+fn collect_mesh_nodes<'a>(
+    node: gltf::Node<'a>,
+    parent_transform: &glam::Mat4,
+    nodes: &mut Vec<(gltf::Node<'a>, glam::Mat4)>,
+) {
+    // gltf 使用列主序存放矩阵
+    let transform = *parent_transform * glam::Mat4::from_cols_array_2d(&node.transform().matrix());
+    if node.mesh().is_some() {
+        nodes.push((node.clone(), transform));
+    }
+    for child in node.children() {
+        collect_mesh_nodes(child, &transform, nodes);
     }
+}
 
-    fn create_sampler(&self, sampler: &gltf::texture::Sampler) -> GfxSampler
-    {
-        let (min_filter, mipmap_mode) = Self::gltf_min_filter_to_vk(sampler.min_filter());
-        let mag_filter = Self::gltf_mag_filter_to_vk(sampler.mag_filter());
-
-        let sampler_info = vk::SamplerCreateInfo::builder()
-            .min_filter(min_filter)
-            .mag_filter(mag_filter)
-            .mipmap_mode(mipmap_mode)
-            .address_mode_u(Self::gltf_wrap_mode_to_vk(sampler.wrap_s()))
-            .address_mode_v(Self::gltf_wrap_mode_to_vk(sampler.wrap_t()));
-
-        unsafe {
-            Gfx::instance()
-                .device()
-                .create_sampler(&sampler_info, None)
-                .expect("failed to create sampler for gltf")
+/// mesh 中可以作为 geometry 的 primitive，点和线会被忽略
+fn triangle_primitives(gltf_mesh: gltf::Mesh<'_>) -> impl Iterator<Item = gltf::Primitive<'_>> {
+    gltf_mesh.primitives().filter(|primitive| primitive.mode() == gltf::mesh::Mode::Triangles)
+}
+
+/// 读取 glTF primitive 的顶点与索引
+///
+/// 缺少的法线和切线会根据三角形重新计算，缺少的 uv 填充为 0
+fn read_mesh_data(buffers: &[gltf::buffer::Data], primitive: &gltf::Primitive, name: &str) -> anyhow::Result<MeshData> {
+    let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()][..]));
+
+    let positions = reader
+        .read_positions()
+        .with_context(|| format!("gltf primitive {} has no positions", name))?
+        .map(glam::Vec3::from)
+        .collect_vec();
+    let vertex_cnt = positions.len();
+
+    let indices = reader
+        .read_indices()
+        .map_or_else(|| (0..vertex_cnt as u32).collect_vec(), |indices| indices.into_u32().collect_vec());
+    let uvs = reader
+        .read_tex_coords(0)
+        .map_or_else(|| vec![glam::Vec2::ZERO; vertex_cnt], |uvs| uvs.into_f32().map(glam::Vec2::from).collect());
+    let normals = reader
+        .read_normals()
+        .map_or_else(|| compute_normals(&positions, &indices), |normals| normals.map(glam::Vec3::from).collect());
+    // 切线的 w 分量表示副切线的方向，着色时副切线为 cross(normal, tangent.xyz) * tangent.w
+    let tangents = reader.read_tangents().map_or_else(
+        || generate_tangents(&positions, &normals, &uvs, &indices),
+        |tangents| tangents.map(glam::Vec4::from).collect(),
+    );
+
+    Ok(MeshData {
+        positions,
+        normals,
+        tangents,
+        uvs,
+        indices,
+    })
+}
+
+/// 解析 `data:[<mime>];base64,<data>` 形式的 URI，不是 data URI 时返回 None
+fn decode_data_uri(uri: &str) -> Option<Vec<u8>> {
+    let data = uri.strip_prefix("data:")?;
+    let (_, encoded) = data.split_once(";base64,")?;
+    match base64::engine::general_purpose::STANDARD.decode(encoded) {
+        Ok(decoded) => Some(decoded),
+        Err(e) => {
+            log::error!("Failed to decode gltf data uri: {}", e);
+            None
         }
     }
 }
 
+/// glTF sampler 对应的 [`truvisl::ESamplerType`]
+///
+/// - 放大过滤为 Nearest 时使用 Point，否则（包括未指定）使用 Linear
+/// - s、t 都为 ClampToEdge 时使用 Clamp，否则使用 Repeat；MirroredRepeat 没有对应的 sampler，按 Repeat 处理
+fn sampler_type(sampler: &gltf::texture::Sampler) -> truvisl::ESamplerType {
+    use gltf::texture::{MagFilter, WrappingMode};
+
+    let point = sampler.mag_filter() == Some(MagFilter::Nearest);
+    let clamp = sampler.wrap_s() == WrappingMode::ClampToEdge && sampler.wrap_t() == WrappingMode::ClampToEdge;
+    if sampler.wrap_s() == WrappingMode::MirroredRepeat || sampler.wrap_t() == WrappingMode::MirroredRepeat {
+        log::warn!("gltf sampler {:?}: MirroredRepeat is not supported, Repeat is used", sampler.index());
+    }
 
-// 一些工具函数
-impl GltfLoader
-{
-    /// 将 gltf 内定义的 format 转换为 vulkan 的 format
-    ///
-    /// 注 只支持有限的几种格式
-    fn gltf_format_to_vk(format: gltf::image::Format, s_rgb: bool) -> vk::Format
-    {
-        use ash::vk::Format as v;
-        use gltf::image::Format as g;
-
-        if s_rgb {
-            match format {
-                g::R8 => v::R8_SRGB,
-                g::R8G8 => v::R8G8_SRGB,
-                g::R8G8B8 => v::R8G8B8_SRGB,
-                g::R8G8B8A8 => v::R8G8B8A8_SRGB,
-                _ => panic!("unsupported format"),
-            }
-        } else {
-            match format {
-                g::R8 => v::R8_UNORM,
-                g::R8G8 => v::R8G8_UNORM,
-                g::R8G8B8 => v::R8G8B8_UNORM,
-                g::R8G8B8A8 => v::R8G8B8A8_UNORM,
-                _ => panic!("unsupported format"),
-            }
+    match (point, clamp) {
+        (true, true) => truvisl::ESamplerType_PointClamp,
+        (true, false) => truvisl::ESamplerType_PointRepeat,
+        (false, true) => truvisl::ESamplerType_LinearClamp,
+        (false, false) => truvisl::ESamplerType_LinearRepeat,
+    }
+}
+
+/// 使用面积加权的面法线计算顶点法线
+fn compute_normals(positions: &[glam::Vec3], indices: &[u32]) -> Vec<glam::Vec3> {
+    let mut normals = vec![glam::Vec3::ZERO; positions.len()];
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|i| i as usize);
+        // 叉积的长度是三角形面积的两倍，直接累加即为面积加权
+        let face_normal = (positions[b] - positions[a]).cross(positions[c] - positions[a]);
+        for i in [a, b, c] {
+            normals[i] += face_normal;
         }
     }
+    normals.into_iter().map(|n| n.try_normalize().unwrap_or(glam::Vec3::Z)).collect()
+}
 
-    /// 将 gltf 文件中的 texture min 参数（OpenGL 风格）转换为 vulkan 格式
-    ///
-    /// gltf 中的 min 对应者 vulkan 中的 min filter 以及 mipmap mod
-    fn gltf_min_filter_to_vk(filter: Option<gltf::texture::MinFilter>) -> (vk::Filter, vk::SamplerMipmapMode)
-    {
-        use ash::vk::{Filter, SamplerMipmapMode};
-        use gltf::texture::MinFilter;
-
-        // 注：LinearMipmapNearest 表示 level 内 linear，level 之间 nearest
-        filter.map_or((Filter::default(), SamplerMipmapMode::default()), |filter| match filter {
-            MinFilter::Nearest => (Filter::NEAREST, SamplerMipmapMode::default()),
-            MinFilter::Linear => (Filter::LINEAR, SamplerMipmapMode::default()),
-            MinFilter::NearestMipmapNearest => (Filter::NEAREST, SamplerMipmapMode::NEAREST),
-            MinFilter::LinearMipmapNearest => (Filter::LINEAR, SamplerMipmapMode::NEAREST),
-            MinFilter::NearestMipmapLinear => (Filter::NEAREST, SamplerMipmapMode::LINEAR),
-            MinFilter::LinearMipmapLinear => (Filter::LINEAR, SamplerMipmapMode::LINEAR),
-        })
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_uri_is_decoded() {
+        assert_eq!(decode_data_uri("data:image/png;base64,AQID"), Some(vec![1, 2, 3]));
+        assert_eq!(decode_data_uri("data:application/octet-stream;base64,"), Some(vec![]));
+        assert_eq!(decode_data_uri("textures/albedo.png"), None);
+    }
+
+    #[test]
+    fn test_computed_normals_follow_winding() {
+        let positions = [glam::Vec3::ZERO, glam::Vec3::X, glam::Vec3::Y];
+        let normals = compute_normals(&positions, &[0, 1, 2]);
+        assert!(normals.iter().all(|n| n.abs_diff_eq(glam::Vec3::Z, 1e-6)));
+
+        let normals = compute_normals(&positions, &[0, 2, 1]);
+        assert!(normals.iter().all(|n| n.abs_diff_eq(-glam::Vec3::Z, 1e-6)));
     }
 
-    /// 将 gltf 中纹理的 mag 参数（OpenGL 风格）转换为 vulkan 格式
-    fn gltf_mag_filter_to_vk(filter: Option<gltf::texture::MagFilter>) -> vk::Filter
-    {
-        use ash::vk::Filter;
-        use gltf::texture::MagFilter;
+    #[test]
+    fn test_sampler_type_follows_gltf_sampler() {
+        // 9728 = NEAREST, 9729 = LINEAR, 33071 = CLAMP_TO_EDGE, 33648 = MIRRORED_REPEAT
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "samplers": [
+                {},
+                { "magFilter": 9728, "wrapS": 33071, "wrapT": 33071 },
+                { "magFilter": 9729, "wrapS": 33071 },
+                { "magFilter": 9729, "wrapS": 33071, "wrapT": 33071 },
+                { "magFilter": 9728, "wrapS": 33648, "wrapT": 33648 }
+            ]
+        }"#;
+        let gltf = gltf::Gltf::from_slice(json.as_bytes()).unwrap();
+        let sampler_types = gltf.document.samplers().map(|sampler| sampler_type(&sampler)).collect_vec();
+        assert_eq!(
+            sampler_types,
+            [
+                truvisl::ESamplerType_LinearRepeat,
+                truvisl::ESamplerType_PointClamp,
+                truvisl::ESamplerType_LinearRepeat,
+                truvisl::ESamplerType_LinearClamp,
+                truvisl::ESamplerType_PointRepeat,
+            ]
+        );
+    }
 
-        filter.map_or(Filter::default(), |filter| match filter {
-            MagFilter::Nearest => Filter::NEAREST,
-            MagFilter::Linear => Filter::LINEAR,
-        })
+    /// 一个三角形 mesh 被两个 node 引用，第二个 node 平移到 x = 10
+    const SHARED_MESH_GLTF: &str = r#"{
+        "asset": { "version": "2.0" },
+        "buffers": [
+            { "byteLength": 36, "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAA" }
+        ],
+        "bufferViews": [{ "buffer": 0, "byteLength": 36 }],
+        "accessors": [
+            { "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [1, 1, 0] }
+        ],
+        "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 } }] }],
+        "nodes": [{ "mesh": 0 }, { "mesh": 0, "translation": [10, 0, 0] }],
+        "scenes": [{ "nodes": [0, 1] }],
+        "scene": 0
+    }"#;
+
+    fn parse_shared_mesh(merge_nodes: bool) -> (ParsedScene, ParseProgress) {
+        let model_file = std::env::temp_dir().join(format!("truvis-gltf-loader-test-{}.gltf", merge_nodes));
+        std::fs::write(&model_file, SHARED_MESH_GLTF).unwrap();
+        let options = ModelLoadOptions {
+            merge_nodes,
+            ..Default::default()
+        };
+        let progress = ParseProgress::default();
+        let parsed = GltfSceneLoader.parse(&model_file, &options, &progress).unwrap();
+        std::fs::remove_file(&model_file).unwrap();
+        (parsed, progress)
     }
 
-    /// 将 gltf 中纹理的 sampler wrap mode 转换成 vk 的 wrap mode
-    fn gltf_wrap_mode_to_vk(wrap_mode: gltf::texture::WrappingMode) -> vk::SamplerAddressMode
-    {
-        use ash::vk::SamplerAddressMode;
-        use gltf::texture::WrappingMode;
+    #[test]
+    fn test_parse_shares_mesh_between_nodes() {
+        let (parsed, progress) = parse_shared_mesh(false);
+        assert_eq!(progress.fraction(), 1.0);
+        assert_eq!(parsed.geometries.len(), 1);
+        assert_eq!(parsed.meshes.len(), 1);
+        // primitive 没有指定材质，使用默认材质
+        assert_eq!(parsed.materials.len(), 1);
+        assert_eq!(parsed.instances.iter().map(|instance| instance.mesh).collect_vec(), [0, 0]);
+        assert_eq!(parsed.instances[1].transform, glam::Mat4::from_translation(glam::vec3(10.0, 0.0, 0.0)));
+
+        // 缺少的法线与切线会被补上
+        let mesh_data = &parsed.geometries[0].mesh_data;
+        assert_eq!(mesh_data.indices, [0, 1, 2]);
+        assert_eq!((mesh_data.normals.len(), mesh_data.tangents.len(), mesh_data.uvs.len()), (3, 3, 3));
+    }
 
-        match wrap_mode {
-            WrappingMode::ClampToEdge => SamplerAddressMode::CLAMP_TO_EDGE,
-            WrappingMode::MirroredRepeat => SamplerAddressMode::MIRRORED_REPEAT,
-            WrappingMode::Repeat => SamplerAddressMode::REPEAT,
-        }
+    #[test]
+    fn test_parse_merged_nodes_share_geometry() {
+        let (parsed, _) = parse_shared_mesh(true);
+        assert_eq!(parsed.geometries.len(), 1);
+        assert_eq!(parsed.meshes.len(), 1);
+        assert_eq!(parsed.meshes[0].geometry_indices, [0, 0]);
+        assert_eq!(
+            parsed.meshes[0].geometry_transforms.as_deref(),
+            Some(
+                &[
+                    glam::Mat4::IDENTITY,
+                    glam::Mat4::from_translation(glam::vec3(10.0, 0.0, 0.0))
+                ][..]
+            )
+        );
+        assert_eq!(parsed.instances.len(), 1);
+        assert_eq!(parsed.instances[0].materials, [0, 0]);
+    }
+
+    #[test]
+    fn test_parse_missing_file_is_error() {
+        let progress = ParseProgress::default();
+        let result = GltfSceneLoader.parse(Path::new("not-exist.gltf"), &ModelLoadOptions::default(), &progress);
+        assert!(result.is_err());
     }
 }
@@ -0,0 +1,177 @@
+//! 导入之后重排网格的索引与顶点，提升 GPU 的顶点着色效率
+//!
+//! 依次执行 meshopt 的三个步骤：
+//! 1. `optimize_vertex_cache`：重排三角形，让相邻的三角形尽量复用 post-transform vertex cache 中的顶点
+//! 2. `optimize_overdraw`：在 cache 命中率最多变差 5% 的前提下，
+//!    按照从外向内的顺序重排三角形块，减少 overdraw
+//! 3. `optimize_vertex_fetch`：按照索引第一次引用的顺序重排顶点，提升顶点读取的局部性，同时去掉没有被引用的顶点
+//!
+//! 三角形的集合与绕序都保持不变，只有顺序发生了变化。
+
+use itertools::Itertools;
+
+/// 导入后的几何数据，SoA 布局与 `VertexLayoutSoA3D` 一致
+pub struct MeshData {
+    pub positions: Vec<glam::Vec3>,
+    pub normals: Vec<glam::Vec3>,
+    /// w 分量为副切线的方向
+    pub tangents: Vec<glam::Vec4>,
+    pub uvs: Vec<glam::Vec2>,
+    /// triangle list
+    pub indices: Vec<u32>,
+}
+
+/// `optimize_vertex_fetch` 只能重排一个顶点数组，因此先将 SoA 打包为 AoS，重排之后再拆开
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+///
+/// tangent 放在最前面，避免 16 字节对齐的 Vec4 之前出现 padding
+struct PackedVertex {
+    tangent: glam::Vec4,
+    position: glam::Vec3,
+    normal: glam::Vec3,
+    uv: glam::Vec2,
+}
+
+// getter
+impl MeshData {
+    #[inline]
+    pub fn vertex_cnt(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// average cache miss ratio：平均每个三角形需要执行顶点着色的次数，范围为 [0.5, 3]，越小越好
+    #[inline]
+    pub fn acmr(&self) -> f32 {
+        acmr(&self.indices, self.vertex_cnt())
+    }
+}
+// tools
+impl MeshData {
+    /// 允许 overdraw 优化让 cache 命中率变差的比例，1.05 表示最多变差 5%
+    const OVERDRAW_THRESHOLD: f32 = 1.05;
+
+    /// 重排索引与顶点，参见模块文档
+    pub fn optimize(&mut self) {
+        let _span = tracy_client::span!("MeshData::optimize");
+        let vertex_cnt = self.vertex_cnt();
+        assert_eq!(self.indices.len() % 3, 0, "mesh optimizer: indices must be a triangle list");
+        assert!(
+            self.normals.len() == vertex_cnt && self.tangents.len() == vertex_cnt && self.uvs.len() == vertex_cnt,
+            "mesh optimizer: vertex attribute count mismatch"
+        );
+
+        let mut indices = meshopt::optimize_vertex_cache(&self.indices, vertex_cnt);
+
+        let vertex_adapter =
+            meshopt::VertexDataAdapter::new(bytemuck::cast_slice(&self.positions), size_of::<glam::Vec3>(), 0).unwrap();
+        meshopt::optimize_overdraw_in_place(&mut indices, &vertex_adapter, Self::OVERDRAW_THRESHOLD);
+
+        let vertices = (0..vertex_cnt)
+            .map(|idx| PackedVertex {
+                tangent: self.tangents[idx],
+                position: self.positions[idx],
+                normal: self.normals[idx],
+                uv: self.uvs[idx],
+            })
+            .collect_vec();
+        let vertices = meshopt::optimize_vertex_fetch(&mut indices, &vertices);
+
+        self.positions = vertices.iter().map(|v| v.position).collect();
+        self.normals = vertices.iter().map(|v| v.normal).collect();
+        self.tangents = vertices.iter().map(|v| v.tangent).collect();
+        self.uvs = vertices.iter().map(|v| v.uv).collect();
+        self.indices = indices;
+    }
+}
+
+/// 评估 cache 命中率时模拟的 FIFO cache 大小
+const CACHE_SIZE: u32 = 16;
+
+/// average cache miss ratio，参见 [`MeshData::acmr`]
+///
+/// 使用 meshopt 的 FIFO cache 模型估计，和实际 GPU 的结果不完全一致，只用于比较优化前后的效果
+pub fn acmr(indices: &[u32], vertex_cnt: usize) -> f32 {
+    if indices.is_empty() {
+        return 0.0;
+    }
+    meshopt::analyze_vertex_cache(indices, vertex_cnt, CACHE_SIZE, 0, 0).acmr
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// n x n 个格子的平面，每个格子两个三角形；三角形的顺序被打乱，模拟未经优化的导入结果
+    fn shuffled_grid(n: u32) -> MeshData {
+        let positions = (0..=n).flat_map(|z| (0..=n).map(move |x| glam::vec3(x as f32, 0.0, z as f32))).collect_vec();
+        let triangles = (0..n)
+            .flat_map(|z| (0..n).map(move |x| z * (n + 1) + x))
+            .flat_map(|a| [[a, a + n + 1, a + n + 2], [a, a + n + 2, a + 1]])
+            .collect_vec();
+        // 7919 是质数，和三角形数量互质，因此是一个排列
+        let indices = (0..triangles.len()).flat_map(|idx| triangles[idx * 7919 % triangles.len()]).collect_vec();
+
+        let vertex_cnt = positions.len();
+        MeshData {
+            positions,
+            normals: vec![glam::Vec3::Y; vertex_cnt],
+            tangents: vec![glam::Vec4::X; vertex_cnt],
+            uvs: (0..vertex_cnt).map(|idx| glam::vec2(idx as f32, 0.0)).collect(),
+            indices,
+        }
+    }
+
+    /// 用顶点位置表示的三角形，旋转为规范形式之后排序，绕序不同的三角形不相等
+    fn triangle_set(mesh: &MeshData) -> Vec<[[i32; 3]; 3]> {
+        mesh.indices
+            .chunks_exact(3)
+            .map(|tri| {
+                let tri = [tri[0], tri[1], tri[2]].map(|idx| mesh.positions[idx as usize].as_ivec3().to_array());
+                let min_idx = (0..3).min_by_key(|&i| tri[i]).unwrap();
+                [tri[min_idx], tri[(min_idx + 1) % 3], tri[(min_idx + 2) % 3]]
+            })
+            .sorted()
+            .collect_vec()
+    }
+
+    #[test]
+    fn test_optimize_reduces_acmr() {
+        let mut mesh = shuffled_grid(32);
+        let acmr_before = mesh.acmr();
+        mesh.optimize();
+        let acmr_after = mesh.acmr();
+
+        // 打乱顺序的网格几乎每个三角形都要变换 3 个顶点；规则网格优化之后接近理论下限 0.5
+        assert!(acmr_before > 2.0, "acmr before: {}", acmr_before);
+        assert!(acmr_after < 1.0, "acmr after: {}", acmr_after);
+    }
+
+    #[test]
+    fn test_optimize_keeps_triangles_and_attributes() {
+        let mut mesh = shuffled_grid(8);
+        // 没有被引用的顶点会被去掉
+        mesh.positions.push(glam::Vec3::splat(100.0));
+        mesh.normals.push(glam::Vec3::Y);
+        mesh.tangents.push(glam::Vec4::X);
+        mesh.uvs.push(glam::Vec2::ZERO);
+
+        let expected = triangle_set(&mesh);
+        let uv_of_position = |mesh: &MeshData, idx: usize| {
+            let p = mesh.positions[idx];
+            (p.z as usize * 9 + p.x as usize) as f32
+        };
+        mesh.optimize();
+
+        assert_eq!(mesh.vertex_cnt(), 81);
+        assert_eq!(triangle_set(&mesh), expected);
+        // 各个属性随顶点一起重排
+        for idx in 0..mesh.vertex_cnt() {
+            assert_eq!(mesh.uvs[idx].x, uv_of_position(&mesh, idx));
+        }
+        // 顶点按照第一次被引用的顺序排列
+        assert_eq!(mesh.indices[0], 0);
+        let first_refs = mesh.indices.iter().copied().unique().collect_vec();
+        assert_eq!(first_refs, (0..mesh.vertex_cnt() as u32).collect_vec());
+    }
+}
@@ -1 +1,242 @@
+//! 模型加载
+//!
+//! 各个格式的加载器实现 [`SceneLoader`]，将模型文件解析为 [`ParsedScene`]，之后由 [`ParsedScene::register`]
+//! 通过 [`SceneRegistry`] 将 Mesh、Material、Instance 注册到场景中。解析只使用 CPU，可以放在工作线程中执行；
+//! app 代码通过 [`load_any`] / [`parse_any`] 从 [`SCENE_LOADERS`] 中根据扩展名选择加载器，不需要关心具体的格式。
+//!
+//! 导入的行为由 [`ModelLoadOptions`] 控制，所有加载器共用同一份选项。
+
+use std::path::{Path, PathBuf};
+
+use itertools::Itertools;
+use truvis_asset::asset_hub::AssetHub;
+use truvis_scene::aabb::Aabb;
+use truvis_scene::components::instance::Instance;
+use truvis_scene::components::material::Material;
+use truvis_scene::components::mesh::Mesh;
+use truvis_scene::guid_new_type::{InstanceHandle, MaterialHandle, MeshHandle};
+use truvis_scene::scene_manager::SceneManager;
+
+use crate::model_loader::assimp_loader::AssimpSceneLoader;
+use crate::model_loader::gltf_loader::GltfSceneLoader;
+use crate::model_loader::parsed_scene::{ParseProgress, ParsedScene};
+
 pub mod assimp_loader;
+pub mod gltf_loader;
+pub mod mesh_optimizer;
+pub mod parsed_scene;
+pub mod tangent;
+
+/// 模型导入选项
+#[derive(Debug, Clone, Copy)]
+pub struct ModelLoadOptions {
+    /// 导入之后重排索引与顶点，提升 vertex cache 命中率、减少 overdraw，参见 [`mesh_optimizer`]
+    ///
+    /// 只改变三角形与顶点的顺序，大模型会增加导入的耗时
+    pub optimize_mesh: bool,
+
+    /// 将模型中所有的 node 合并为一个 Mesh 和一个 Instance，node 的世界变换作为 geometry 相对于 mesh 的变换
+    ///
+    /// 整个模型只有一个 BLAS 和一个 TLAS instance，适合不需要单独选中、移动各个部件的静态模型；
+    /// 被多个 node 引用的 mesh 在合并后的 Mesh 中出现多次，但只有一份顶点数据
+    pub merge_nodes: bool,
+}
+impl Default for ModelLoadOptions {
+    fn default() -> Self {
+        Self {
+            optimize_mesh: true,
+            merge_nodes: false,
+        }
+    }
+}
+
+/// 模型加载器：读取模型文件，将其中的 Mesh、Material、Instance 加入场景
+///
+/// 加载器不持有状态，通过 `&dyn SceneLoader` 统一调度，参见 [`SCENE_LOADERS`]
+pub trait SceneLoader {
+    /// 支持的扩展名，小写
+    fn extensions(&self) -> &'static [&'static str];
+
+    /// 读取并解析模型文件，不访问 GPU 与场景，可以在工作线程中调用
+    ///
+    /// 每解析完一个 geometry 更新一次 `progress`
+    fn parse(
+        &self,
+        model_file: &Path,
+        options: &ModelLoadOptions,
+        progress: &ParseProgress,
+    ) -> anyhow::Result<ParsedScene>;
+
+    /// 解析模型文件并注册到场景中，解析失败时返回空
+    ///
+    /// # return
+    /// 返回模型的所有 instance
+    fn load(
+        &self,
+        model_file: &Path,
+        options: &ModelLoadOptions,
+        scene_manager: &mut SceneManager,
+        asset_hub: &mut AssetHub,
+    ) -> Vec<InstanceHandle> {
+        match self.parse(model_file, options, &ParseProgress::default()) {
+            Ok(parsed_scene) => parsed_scene.register(scene_manager, asset_hub),
+            Err(e) => {
+                log::error!("Failed to load model {}: {:#}", model_file.display(), e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// 扩展名是否属于 [`Self::extensions`]，不区分大小写
+    fn supports(&self, model_file: &Path) -> bool {
+        model_file
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| self.extensions().iter().any(|supported| ext.eq_ignore_ascii_case(supported)))
+    }
+}
+
+/// 所有的加载器，按顺序匹配扩展名
+pub const SCENE_LOADERS: &[&dyn SceneLoader] = &[&GltfSceneLoader, &AssimpSceneLoader];
+
+/// 加载器向场景注册资源的统一入口
+///
+/// - Mesh 注册之前构建 BLAS
+/// - Material 注册之前请求加载它引用的所有纹理，已经请求过的路径不会重复加载
+pub struct SceneRegistry<'a> {
+    scene_manager: &'a mut SceneManager,
+    asset_hub: &'a mut AssetHub,
+}
+// new & init
+impl<'a> SceneRegistry<'a> {
+    pub fn new(scene_manager: &'a mut SceneManager, asset_hub: &'a mut AssetHub) -> Self {
+        Self {
+            scene_manager,
+            asset_hub,
+        }
+    }
+}
+// update
+impl SceneRegistry<'_> {
+    pub fn register_mesh(&mut self, mut mesh: Mesh) -> MeshHandle {
+        mesh.build_blas();
+        self.scene_manager.register_mesh(mesh)
+    }
+
+    pub fn register_mat(&mut self, mat: Material) -> MaterialHandle {
+        // 只有颜色贴图按 sRGB 加载，其余贴图存放的是线性的数据
+        for (tex_path, is_srgb) in [
+            (&mat.diffuse_map, true),
+            (&mat.normal_map, false),
+            (&mat.detail_normal_map, false),
+            (&mat.metallic_roughness_map, false),
+            (&mat.emissive_map, true),
+            (&mat.occlusion_map, false),
+        ] {
+            if !tex_path.is_empty() {
+                self.asset_hub.load_texture(PathBuf::from(tex_path), is_srgb);
+            }
+        }
+        self.scene_manager.register_mat(mat)
+    }
+
+    pub fn register_instance(&mut self, instance: Instance) -> InstanceHandle {
+        self.scene_manager.register_instance(instance)
+    }
+}
+
+/// Mesh 的包围盒：各个 geometry 的包围盒经过 geometry 相对于 mesh 的变换之后的并集
+///
+/// `geometry_transforms` 与 [`Mesh::geometry_transforms`] 的含义相同
+pub fn mesh_aabb(geometry_aabbs: &[Aabb], geometry_transforms: Option<&[glam::Mat4]>) -> Aabb {
+    match geometry_transforms {
+        Some(transforms) => geometry_aabbs
+            .iter()
+            .zip_eq(transforms)
+            .fold(Aabb::EMPTY, |mesh_aabb, (aabb, transform)| mesh_aabb.union(&aabb.transform(transform))),
+        None => geometry_aabbs.iter().fold(Aabb::EMPTY, |mesh_aabb, aabb| mesh_aabb.union(aabb)),
+    }
+}
+
+/// 所有加载器支持的扩展名
+pub fn supported_extensions() -> impl Iterator<Item = &'static str> {
+    SCENE_LOADERS.iter().flat_map(|loader| loader.extensions()).copied()
+}
+
+/// 是否有加载器支持该文件的扩展名，参见 [`SceneLoader::supports`]
+pub fn is_supported_model(model_file: &Path) -> bool {
+    SCENE_LOADERS.iter().any(|loader| loader.supports(model_file))
+}
+
+/// 加载该文件使用的加载器：第一个支持该扩展名的加载器
+///
+/// 没有加载器支持该扩展名时交给 [`AssimpSceneLoader`]，由 Assimp 根据文件内容识别格式
+pub fn scene_loader_for(model_file: &Path) -> &'static dyn SceneLoader {
+    SCENE_LOADERS.iter().copied().find(|loader| loader.supports(model_file)).unwrap_or(&AssimpSceneLoader)
+}
+
+/// 根据扩展名选择加载器解析模型文件，不访问 GPU 与场景，可以在工作线程中调用，参见 [`scene_loader_for`]
+pub fn parse_any(
+    model_file: &Path,
+    options: &ModelLoadOptions,
+    progress: &ParseProgress,
+) -> anyhow::Result<ParsedScene> {
+    scene_loader_for(model_file).parse(model_file, options, progress)
+}
+
+/// 根据扩展名选择加载器，将模型加入场景；解析失败时返回空，参见 [`scene_loader_for`]
+pub fn load_any(
+    model_file: &Path,
+    options: &ModelLoadOptions,
+    scene_manager: &mut SceneManager,
+    asset_hub: &mut AssetHub,
+) -> Vec<InstanceHandle> {
+    scene_loader_for(model_file).load(model_file, options, scene_manager, asset_hub)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_supported_model() {
+        assert!(GltfSceneLoader.supports(Path::new("assets/sponza.gltf")));
+        assert!(GltfSceneLoader.supports(Path::new("C:/models/Box.GLB")));
+        assert!(!GltfSceneLoader.supports(Path::new("cornell.obj")));
+        assert!(AssimpSceneLoader.supports(Path::new("cornell.obj")));
+
+        assert!(is_supported_model(Path::new("sponza.FBX")));
+        assert!(is_supported_model(Path::new("bunny.ply")));
+        assert!(!is_supported_model(Path::new("texture.png")));
+        assert!(!is_supported_model(Path::new("no_extension")));
+    }
+
+    #[test]
+    fn test_scene_loader_for_falls_back_to_assimp() {
+        let extensions = |model_file: &str| scene_loader_for(Path::new(model_file)).extensions();
+        assert_eq!(extensions("Box.glb"), GltfSceneLoader.extensions());
+        assert_eq!(extensions("cornell.obj"), AssimpSceneLoader.extensions());
+        // 扩展名未知的文件交给 Assimp 根据内容识别
+        assert_eq!(extensions("model.unknown"), AssimpSceneLoader.extensions());
+        assert_eq!(extensions("no_extension"), AssimpSceneLoader.extensions());
+    }
+
+    #[test]
+    fn test_mesh_aabb() {
+        let aabbs = [
+            Aabb::new(glam::Vec3::ZERO, glam::Vec3::ONE),
+            Aabb::new(-glam::Vec3::ONE, glam::Vec3::ZERO),
+        ];
+        assert_eq!(mesh_aabb(&aabbs, None), Aabb::new(-glam::Vec3::ONE, glam::Vec3::ONE));
+
+        let transforms = [
+            glam::Mat4::from_translation(glam::vec3(10.0, 0.0, 0.0)),
+            glam::Mat4::from_scale(glam::Vec3::splat(2.0)),
+        ];
+        assert_eq!(
+            mesh_aabb(&aabbs, Some(&transforms)),
+            Aabb::new(glam::vec3(-2.0, -2.0, -2.0), glam::vec3(11.0, 1.0, 1.0))
+        );
+        assert!(mesh_aabb(&[], Some(&[])).is_empty());
+    }
+}
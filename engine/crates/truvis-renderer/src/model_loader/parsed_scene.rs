@@ -0,0 +1,191 @@
+//! 加载器解析得到的场景数据
+//!
+//! 模型的加载分为两步：
+//! 1. [`SceneLoader::parse`](super::SceneLoader::parse)：读取文件、解码顶点与材质，只使用 CPU，可以在工作线程中执行
+//! 2. [`ParsedScene::register`]：上传顶点数据、构建 BLAS，将资源注册到场景中，需要在渲染线程中执行
+
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use itertools::Itertools;
+use truvis_asset::asset_hub::AssetHub;
+use truvis_gfx::resources::special_buffers::index_buffer::GfxIndex32Buffer;
+use truvis_gfx::resources::vertex_layout::soa_3d::VertexLayoutSoA3D;
+use truvis_render_interface::geometry::RtGeometry;
+use truvis_scene::aabb::Aabb;
+use truvis_scene::components::instance::Instance;
+use truvis_scene::components::material::Material;
+use truvis_scene::components::mesh::Mesh;
+use truvis_scene::guid_new_type::InstanceHandle;
+use truvis_scene::scene_manager::SceneManager;
+
+use crate::model_loader::mesh_optimizer::MeshData;
+use crate::model_loader::{SceneRegistry, mesh_aabb};
+
+/// 解析完成、还没有上传到 GPU 的 geometry
+pub struct ParsedGeometry {
+    pub name: String,
+    pub mesh_data: MeshData,
+    pub aabb: Aabb,
+}
+
+/// 解析完成的 Mesh，通过索引引用 [`ParsedScene::geometries`]，被多个 Mesh 引用的 geometry 只上传一份
+pub struct ParsedMesh {
+    pub name: String,
+    pub geometry_indices: Vec<usize>,
+    /// 与 [`Mesh::geometry_transforms`] 的含义相同
+    pub geometry_transforms: Option<Vec<glam::Mat4>>,
+}
+
+/// 解析完成的 Instance，通过索引引用 [`ParsedScene::meshes`] 和 [`ParsedScene::materials`]
+pub struct ParsedInstance {
+    pub name: String,
+    pub mesh: usize,
+    /// 和 Mesh 中的 geometry 一一对应
+    pub materials: Vec<usize>,
+    pub transform: glam::Mat4,
+}
+
+/// 模型内嵌的图片，以虚拟路径交给 AssetHub 解码
+pub struct ParsedImage {
+    /// 材质通过该路径引用这张图片
+    pub path: PathBuf,
+    pub encoded: Vec<u8>,
+    pub is_srgb: bool,
+}
+
+/// 加载器解析得到的场景数据，不包含任何 GPU 资源，可以在线程之间传递
+#[derive(Default)]
+pub struct ParsedScene {
+    pub geometries: Vec<ParsedGeometry>,
+    pub meshes: Vec<ParsedMesh>,
+    pub materials: Vec<Material>,
+    pub instances: Vec<ParsedInstance>,
+    pub embedded_images: Vec<ParsedImage>,
+}
+// tools
+impl ParsedScene {
+    /// 上传所有 geometry，将 Mesh、Material、Instance 注册到场景中，需要在渲染线程中调用
+    ///
+    /// # return
+    /// 返回模型的所有 instance
+    pub fn register(self, scene_manager: &mut SceneManager, asset_hub: &mut AssetHub) -> Vec<InstanceHandle> {
+        let _span = tracy_client::span!("ParsedScene::register");
+
+        // 内嵌的图片需要先以虚拟路径请求加载，之后材质引用这些路径时不会重复请求
+        for image in self.embedded_images {
+            asset_hub.load_texture_from_memory(image.path, image.encoded, image.is_srgb);
+        }
+
+        let geometries = self
+            .geometries
+            .into_iter()
+            .map(|geometry| (Rc::new(upload_geometry(&geometry.mesh_data, &geometry.name)), geometry.aabb))
+            .collect_vec();
+
+        let mut registry = SceneRegistry::new(scene_manager, asset_hub);
+        let meshes = self
+            .meshes
+            .into_iter()
+            .map(|mesh| {
+                let aabbs = mesh.geometry_indices.iter().map(|&idx| geometries[idx].1).collect_vec();
+                registry.register_mesh(Mesh {
+                    geometries: mesh.geometry_indices.iter().map(|&idx| geometries[idx].0.clone()).collect_vec(),
+                    local_aabb: mesh_aabb(&aabbs, mesh.geometry_transforms.as_deref()),
+                    geometry_transforms: mesh.geometry_transforms,
+                    blas: None,
+                    dynamic_blas: None,
+                    blas_device_address: None,
+                    name: mesh.name,
+                })
+            })
+            .collect_vec();
+        let mats = self.materials.into_iter().map(|mat| registry.register_mat(mat)).collect_vec();
+
+        self.instances
+            .into_iter()
+            .map(|instance| {
+                registry.register_instance(Instance {
+                    mesh: meshes[instance.mesh],
+                    materials: instance.materials.iter().map(|&idx| mats[idx]).collect_vec(),
+                    transform: instance.transform,
+                    name: instance.name,
+                })
+            })
+            .collect_vec()
+    }
+}
+
+/// 将顶点与索引上传到 device local 的 buffer 中
+fn upload_geometry(mesh_data: &MeshData, name: &str) -> RtGeometry {
+    let vertex_buffer = VertexLayoutSoA3D::create_vertex_buffer(
+        &mesh_data.positions,
+        &mesh_data.normals,
+        &mesh_data.tangents,
+        &mesh_data.uvs,
+        name,
+    );
+    let index_buffer = GfxIndex32Buffer::new_device_local(mesh_data.indices.len(), format!("{}-indices", name));
+    index_buffer.transfer_data_sync(&mesh_data.indices);
+
+    RtGeometry {
+        vertex_buffer,
+        index_buffer,
+    }
+}
+
+/// 解析的进度，工作线程更新，渲染线程读取
+#[derive(Debug, Default)]
+pub struct ParseProgress {
+    done: AtomicUsize,
+    total: AtomicUsize,
+}
+// getter
+impl ParseProgress {
+    /// 已完成的比例，总量未知时为 0
+    pub fn fraction(&self) -> f32 {
+        let total = self.total.load(Ordering::Relaxed);
+        if total == 0 {
+            return 0.0;
+        }
+        (self.done.load(Ordering::Relaxed).min(total) as f32) / (total as f32)
+    }
+}
+// update
+impl ParseProgress {
+    /// 设置需要解析的 geometry 总数，同时清空已完成的数量
+    pub fn set_total(&self, total: usize) {
+        self.done.store(0, Ordering::Relaxed);
+        self.total.store(total, Ordering::Relaxed);
+    }
+
+    /// 完成一个 geometry
+    pub fn advance(&self) {
+        self.done.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_progress_fraction() {
+        let progress = ParseProgress::default();
+        assert_eq!(progress.fraction(), 0.0);
+
+        progress.set_total(4);
+        progress.advance();
+        assert_eq!(progress.fraction(), 0.25);
+
+        // 超出总量时不会超过 1
+        for _ in 0..4 {
+            progress.advance();
+        }
+        assert_eq!(progress.fraction(), 1.0);
+
+        progress.set_total(2);
+        assert_eq!(progress.fraction(), 0.0);
+    }
+}
@@ -0,0 +1,165 @@
+//! 模型缺少切线数据时，根据 position、uv 和 normal 生成顶点切线
+
+/// 每个顶点累加相邻三角形的 uv 梯度，返回 (u 方向, v 方向)
+///
+/// 三角形越大、uv 越密集，权重越大；uv 面积为 0 的退化三角形不参与累加
+fn accumulate_uv_gradients(
+    positions: &[glam::Vec3],
+    uvs: &[glam::Vec2],
+    indices: &[u32],
+) -> (Vec<glam::Vec3>, Vec<glam::Vec3>) {
+    let mut u_dirs = vec![glam::Vec3::ZERO; positions.len()];
+    let mut v_dirs = vec![glam::Vec3::ZERO; positions.len()];
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|i| i as usize);
+        let (e1, e2) = (positions[b] - positions[a], positions[c] - positions[a]);
+        let (duv1, duv2) = (uvs[b] - uvs[a], uvs[c] - uvs[a]);
+        let det = duv1.x * duv2.y - duv2.x * duv1.y;
+        if det.abs() < f32::EPSILON {
+            continue;
+        }
+        let u_dir = (e1 * duv2.y - e2 * duv1.y) / det;
+        let v_dir = (e2 * duv1.x - e1 * duv2.x) / det;
+        for i in [a, b, c] {
+            u_dirs[i] += u_dir;
+            v_dirs[i] += v_dir;
+        }
+    }
+    (u_dirs, v_dirs)
+}
+
+/// 切线的 w 分量，使 `cross(normal, tangent) * w` 指向 v 减小的方向
+///
+/// 与 glTF 的约定一致：uv 的原点位于图片左上角，法线贴图的 y 指向图片的上方。
+/// `v_dir` 为 v 增大的方向，uv 镜像时 w 为 -1
+fn handedness(normal: glam::Vec3, tangent: glam::Vec3, v_dir: glam::Vec3) -> f32 {
+    if normal.cross(tangent).dot(v_dir) > 0.0 { -1.0 } else { 1.0 }
+}
+
+/// 生成顶点切线，xyz 与法线正交并且已经归一化，w 为副切线的方向
+///
+/// 与 MikkTSpace 的思路一致：每个三角形根据 uv 的梯度求出 u 方向的切线，
+/// 累加到三个顶点上，最后对法线做 Gram-Schmidt 正交化。
+/// 着色时副切线由 `cross(normal, tangent.xyz) * tangent.w` 得到，因此也与切线正交，w 参见 [`handedness`]。
+///
+/// 所有相邻三角形都退化的顶点，使用任意一个与法线垂直的方向作为切线
+pub fn generate_tangents(
+    positions: &[glam::Vec3],
+    normals: &[glam::Vec3],
+    uvs: &[glam::Vec2],
+    indices: &[u32],
+) -> Vec<glam::Vec4> {
+    let (u_dirs, v_dirs) = accumulate_uv_gradients(positions, uvs, indices);
+    itertools::izip!(u_dirs, v_dirs, normals)
+        .map(|(u_dir, v_dir, n)| {
+            // Gram-Schmidt 正交化
+            let t = (u_dir - *n * n.dot(u_dir)).try_normalize().unwrap_or_else(|| n.any_orthonormal_vector());
+            t.extend(handedness(*n, t, v_dir))
+        })
+        .collect()
+}
+
+/// 为模型自带的、没有 w 分量的切线（例如 Assimp 导出的切线）根据 uv 的 v 方向补上 w，参见 [`handedness`]
+pub fn tangents_with_handedness(
+    positions: &[glam::Vec3],
+    normals: &[glam::Vec3],
+    uvs: &[glam::Vec2],
+    indices: &[u32],
+    tangents: &[glam::Vec3],
+) -> Vec<glam::Vec4> {
+    let (_, v_dirs) = accumulate_uv_gradients(positions, uvs, indices);
+    itertools::izip!(tangents, v_dirs, normals).map(|(t, v_dir, n)| t.extend(handedness(*n, *t, v_dir))).collect()
+}
+
+/// 切线数据是否缺失：所有切线都是零向量时，通常是模型文件里没有切线
+pub fn is_missing_tangents(tangents: &[glam::Vec3]) -> bool {
+    tangents.iter().all(|t| *t == glam::Vec3::ZERO)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tangents_follow_u_direction() {
+        let positions = [glam::Vec3::ZERO, glam::Vec3::X, glam::Vec3::Y];
+        let normals = [glam::Vec3::Z; 3];
+
+        let uvs = [glam::Vec2::ZERO, glam::Vec2::X, glam::Vec2::Y];
+        let tangents = generate_tangents(&positions, &normals, &uvs, &[0, 1, 2]);
+        assert!(tangents.iter().all(|t| t.truncate().abs_diff_eq(glam::Vec3::X, 1e-6)));
+
+        // uv 旋转 90 度之后，u 方向指向 -y
+        let uvs = [glam::Vec2::ZERO, -glam::Vec2::Y, glam::Vec2::X];
+        let tangents = generate_tangents(&positions, &normals, &uvs, &[0, 1, 2]);
+        assert!(tangents.iter().all(|t| t.truncate().abs_diff_eq(glam::Vec3::Y, 1e-6)));
+    }
+
+    #[test]
+    fn test_tangents_are_orthogonal_to_normal_and_bitangent() {
+        // 法线与三角形平面不垂直（平滑法线），切线需要被正交化
+        let positions = [glam::Vec3::ZERO, glam::Vec3::X, glam::Vec3::Y];
+        let normals = [glam::vec3(0.3, 0.2, 1.0).normalize(); 3];
+        let uvs = [glam::Vec2::ZERO, glam::Vec2::X, glam::Vec2::Y];
+        let tangents = generate_tangents(&positions, &normals, &uvs, &[0, 1, 2]);
+
+        for (t, n) in std::iter::zip(&tangents, &normals) {
+            let t = t.truncate();
+            let bitangent = n.cross(t);
+            assert!(t.is_normalized());
+            assert!(t.dot(*n).abs() < 1e-6);
+            assert!(t.dot(bitangent).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_handedness_follows_v_direction() {
+        let positions = [glam::Vec3::ZERO, glam::Vec3::X, glam::Vec3::Y];
+        let normals = [glam::Vec3::Z; 3];
+        let indices = [0, 1, 2];
+
+        // v 沿 -y 增大，cross(normal, tangent) = +y 指向 v 减小的方向
+        let uvs = [glam::Vec2::ZERO, glam::Vec2::X, -glam::Vec2::Y];
+        let tangents = generate_tangents(&positions, &normals, &uvs, &indices);
+        assert!(tangents.iter().all(|t| t.abs_diff_eq(glam::vec4(1.0, 0.0, 0.0, 1.0), 1e-6)));
+
+        // 镜像的 uv
+        let mirrored_uvs = [glam::Vec2::ZERO, glam::Vec2::X, glam::Vec2::Y];
+        let tangents = generate_tangents(&positions, &normals, &mirrored_uvs, &indices);
+        assert!(tangents.iter().all(|t| t.abs_diff_eq(glam::vec4(1.0, 0.0, 0.0, -1.0), 1e-6)));
+
+        // 模型自带的切线同样按照 v 的方向补上 w
+        let existing = [glam::Vec3::X; 3];
+        let tangents = tangents_with_handedness(&positions, &normals, &uvs, &indices, &existing);
+        assert!(tangents.iter().all(|t| t.w == 1.0));
+        let tangents = tangents_with_handedness(&positions, &normals, &mirrored_uvs, &indices, &existing);
+        assert!(tangents.iter().all(|t| t.w == -1.0));
+    }
+
+    #[test]
+    fn test_shared_vertex_ignores_degenerate_triangle() {
+        // 顶点 0 同时属于一个正常的三角形和一个 uv 退化的三角形
+        let positions = [glam::Vec3::ZERO, glam::Vec3::X, glam::Vec3::Y, -glam::Vec3::X];
+        let normals = [glam::Vec3::Z; 4];
+        let uvs = [glam::Vec2::ZERO, glam::Vec2::X, glam::Vec2::Y, glam::Vec2::ZERO];
+        let tangents = generate_tangents(&positions, &normals, &uvs, &[0, 1, 2, 0, 2, 3]);
+        assert!(tangents[0].truncate().abs_diff_eq(glam::Vec3::X, 1e-6));
+        assert!(tangents.iter().all(|t| t.is_finite()));
+    }
+
+    #[test]
+    fn test_degenerate_uv_falls_back_to_orthogonal_tangent() {
+        let positions = [glam::Vec3::ZERO, glam::Vec3::X, glam::Vec3::Y];
+        let normals = [glam::Vec3::Z; 3];
+        let uvs = [glam::Vec2::ZERO; 3];
+        let tangents = generate_tangents(&positions, &normals, &uvs, &[0, 1, 2]);
+        assert!(tangents.iter().all(|t| t.truncate().is_normalized() && t.truncate().dot(glam::Vec3::Z).abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_all_zero_tangents_are_missing() {
+        assert!(is_missing_tangents(&[glam::Vec3::ZERO; 3]));
+        assert!(is_missing_tangents(&[]));
+        assert!(!is_missing_tangents(&[glam::Vec3::ZERO, glam::Vec3::X]));
+    }
+}
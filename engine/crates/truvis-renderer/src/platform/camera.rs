@@ -1,3 +1,73 @@
+use serde::{Deserialize, Serialize};
+use truvis_render_interface::camera_convention::{CameraConvention, DepthRange, Handedness, NdcYAxis};
+use truvis_scene::aabb::Aabb;
+use truvis_scene::frustum::Frustum;
+
+/// 相机的投影方式
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ProjectionMode {
+    /// 透视投影，远平面在无穷远处
+    Perspective {
+        /// 垂直方向的视场角（角度）
+        fov_y_deg: f32,
+    },
+    /// 正交投影，宽度由 [`Camera::asp`] 决定
+    Orthographic {
+        /// 可视区域在世界空间中的高度
+        height: f32,
+    },
+}
+impl ProjectionMode {
+    pub const DEFAULT_PERSPECTIVE: Self = Self::Perspective { fov_y_deg: 60.0 };
+    pub const DEFAULT_ORTHOGRAPHIC: Self = Self::Orthographic { height: 10.0 };
+
+    #[inline]
+    pub fn is_orthographic(&self) -> bool {
+        matches!(self, Self::Orthographic { .. })
+    }
+}
+
+/// 相机视角的快照：位置、朝向与投影，可以序列化，用于保存与恢复视角
+///
+/// 不包含宽高比与 [`CameraConvention`]，它们由窗口与渲染管线决定
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CameraBookmark {
+    pub position: glam::Vec3,
+    pub euler_yaw_deg: f32,
+    pub euler_pitch_deg: f32,
+    #[serde(default)]
+    pub euler_roll_deg: f32,
+    pub projection_mode: ProjectionMode,
+}
+impl CameraBookmark {
+    /// 在两个视角之间插值，`t` 为 0 时等于 `self`，为 1 时等于 `other`
+    ///
+    /// yaw 沿较短的方向旋转；投影方式不同时不插值，直接使用 `other` 的投影
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        // 将 yaw 的差值映射到 [-180, 180)
+        let yaw_delta = (other.euler_yaw_deg - self.euler_yaw_deg + 180.0).rem_euclid(360.0) - 180.0;
+        let projection_mode = match (self.projection_mode, other.projection_mode) {
+            (ProjectionMode::Perspective { fov_y_deg: a }, ProjectionMode::Perspective { fov_y_deg: b }) => {
+                ProjectionMode::Perspective { fov_y_deg: lerp(a, b) }
+            }
+            (ProjectionMode::Orthographic { height: a }, ProjectionMode::Orthographic { height: b }) => {
+                ProjectionMode::Orthographic { height: lerp(a, b) }
+            }
+            (_, projection_mode) => projection_mode,
+        };
+
+        Self {
+            position: self.position.lerp(other.position, t),
+            euler_yaw_deg: (self.euler_yaw_deg + yaw_delta * t).rem_euclid(360.0),
+            euler_pitch_deg: lerp(self.euler_pitch_deg, other.euler_pitch_deg),
+            euler_roll_deg: lerp(self.euler_roll_deg, other.euler_roll_deg),
+            projection_mode,
+        }
+    }
+}
+
 pub struct Camera {
     pub position: glam::Vec3,
 
@@ -6,8 +76,18 @@ pub struct Camera {
     pub euler_roll_deg: f32,
 
     pub asp: f32,
-    pub fov_deg_vertical: f32,
+    pub projection_mode: ProjectionMode,
     pub near: f32,
+    /// 只用于正交投影，透视投影的远平面在无穷远处
+    pub far: f32,
+
+    /// 轨道模式下围绕的点，参见 [`Self::begin_orbit`]
+    pub orbit_target: glam::Vec3,
+    /// 轨道模式下相机到 `orbit_target` 的距离
+    pub orbit_distance: f32,
+
+    /// view / projection 矩阵的约定，默认值见 [`CameraConvention`]
+    pub convention: CameraConvention,
 }
 
 // 一些常量
@@ -24,6 +104,9 @@ impl Camera {
     const CAMERA_RIGHT: glam::Vec3 = glam::Vec3::new(1.0, 0.0, 0.0);
 
     const K_PITCH: f32 = 89.5;
+
+    pub const MIN_ORBIT_DISTANCE: f32 = 0.01;
+    pub const MAX_ORBIT_DISTANCE: f32 = 100000.0;
 }
 
 // getter
@@ -47,12 +130,74 @@ impl Camera {
         let transform = glam::Mat4::from_euler(Self::CAMERA_EULER, self.yaw_rad(), self.pitch_rad(), self.roll_rad());
         let dir = transform.transform_vector3(Self::CAMERA_FORWAED);
 
-        glam::Mat4::look_to_rh(self.position, dir, Self::CAMERA_UP)
+        let view_rh = glam::Mat4::look_to_rh(self.position, dir, Self::CAMERA_UP);
+        match self.convention.handedness {
+            Handedness::Right => view_rh,
+            // 世界空间仍然是右手系，只将 ViewSpace 的 Z 轴翻转，使相机看向 +Z
+            Handedness::Left => glam::Mat4::from_scale(glam::vec3(1.0, 1.0, -1.0)) * view_rh,
+        }
     }
 
-    /// 从 RightHand-Y-Up 的 ViewSpace 转换到 LeftHand-Y-Up 的 NDC
+    /// 从 ViewSpace 转换到 NDC，ViewSpace 的手性、NDC 的 Y 轴方向与深度范围由 [`Self::convention`] 决定
+    ///
+    /// 默认约定下：从 RightHand-Y-Up 的 ViewSpace 转换到 LeftHand-Y-Up 的 NDC
+    ///
+    /// 透视投影与正交投影的深度范围都由 `near` 开始，正交投影在 `far` 处深度为 1
     pub fn get_projection_matrix(&self) -> glam::Mat4 {
-        glam::Mat4::perspective_infinite_rh(self.fov_deg_vertical.to_radians(), self.asp, self.near)
+        let mut projection = match self.projection_mode {
+            ProjectionMode::Perspective { fov_y_deg } => {
+                let fov_rad = fov_y_deg.to_radians();
+                match self.convention.handedness {
+                    Handedness::Right => glam::Mat4::perspective_infinite_rh(fov_rad, self.asp, self.near),
+                    Handedness::Left => glam::Mat4::perspective_infinite_lh(fov_rad, self.asp, self.near),
+                }
+            }
+            ProjectionMode::Orthographic { height } => {
+                let half_height = height * 0.5;
+                let half_width = half_height * self.asp;
+                match self.convention.handedness {
+                    Handedness::Right => glam::Mat4::orthographic_rh(
+                        -half_width,
+                        half_width,
+                        -half_height,
+                        half_height,
+                        self.near,
+                        self.far,
+                    ),
+                    Handedness::Left => glam::Mat4::orthographic_lh(
+                        -half_width,
+                        half_width,
+                        -half_height,
+                        half_height,
+                        self.near,
+                        self.far,
+                    ),
+                }
+            }
+        };
+
+        // [0, 1] -> [-1, 1]：z' = 2z - w
+        if self.convention.depth_range == DepthRange::NegativeOneToOne {
+            let depth_remap = glam::Mat4::from_cols(
+                glam::Vec4::X,
+                glam::Vec4::Y,
+                glam::vec4(0.0, 0.0, 2.0, 0.0),
+                glam::vec4(0.0, 0.0, -1.0, 1.0),
+            );
+            projection = depth_remap * projection;
+        }
+
+        if self.convention.ndc_y == NdcYAxis::Down {
+            projection = glam::Mat4::from_scale(glam::vec3(1.0, -1.0, 1.0)) * projection;
+        }
+
+        projection
+    }
+
+    /// 世界空间中的视锥体，透视投影的远平面在无穷远处，不会剔除远处的物体
+    pub fn frustum(&self) -> Frustum {
+        let view_projection = self.get_projection_matrix() * self.get_view_matrix();
+        Frustum::from_view_projection(&view_projection, self.convention.depth_range)
     }
 
     pub fn camera_forward(&self) -> glam::Vec3 {
@@ -110,6 +255,97 @@ impl Camera {
     }
 }
 
+// 视角的保存与恢复
+impl Camera {
+    pub fn bookmark(&self) -> CameraBookmark {
+        CameraBookmark {
+            position: self.position,
+            euler_yaw_deg: self.euler_yaw_deg,
+            euler_pitch_deg: self.euler_pitch_deg,
+            euler_roll_deg: self.euler_roll_deg,
+            projection_mode: self.projection_mode,
+        }
+    }
+
+    /// 恢复保存的视角，轨道模式的 target 位于相机前方 `orbit_distance` 处
+    pub fn apply_bookmark(&mut self, bookmark: &CameraBookmark) {
+        self.position = bookmark.position;
+        self.euler_yaw_deg = bookmark.euler_yaw_deg;
+        self.euler_pitch_deg = bookmark.euler_pitch_deg.clamp(-Self::K_PITCH, Self::K_PITCH);
+        self.euler_roll_deg = bookmark.euler_roll_deg;
+        self.projection_mode = bookmark.projection_mode;
+        self.orbit_target = self.position + self.camera_forward() * self.orbit_distance;
+    }
+}
+
+// 轨道模式：相机位于 orbit_target - forward * orbit_distance
+impl Camera {
+    /// 进入轨道模式，根据当前的位置与朝向推算 target，相机的位置与朝向保持不变
+    ///
+    /// 上一次的 target 位于相机前方时沿用它的深度，否则沿用 `orbit_distance`
+    pub fn begin_orbit(&mut self) {
+        let forward = self.camera_forward();
+        let target_depth = (self.orbit_target - self.position).dot(forward);
+        if target_depth > Self::MIN_ORBIT_DISTANCE {
+            self.orbit_distance = target_depth;
+        }
+        self.orbit_distance = self.orbit_distance.clamp(Self::MIN_ORBIT_DISTANCE, Self::MAX_ORBIT_DISTANCE);
+        self.orbit_target = self.position + forward * self.orbit_distance;
+    }
+
+    /// 围绕 target 旋转（角度）
+    pub fn orbit_rotate(&mut self, yaw_deg: f32, pitch_deg: f32) {
+        self.rotate_yaw(yaw_deg);
+        self.rotate_pitch(pitch_deg);
+        self.update_orbit_position();
+    }
+
+    /// 将到 target 的距离乘以 `scale`，小于 1 时靠近 target
+    pub fn orbit_zoom(&mut self, scale: f32) {
+        self.orbit_distance = (self.orbit_distance * scale).clamp(Self::MIN_ORBIT_DISTANCE, Self::MAX_ORBIT_DISTANCE);
+        self.update_orbit_position();
+    }
+
+    /// 沿相机的 right / up 方向平移 target，相机随之平移
+    pub fn orbit_pan(&mut self, right: f32, up: f32) {
+        let offset = self.camera_right() * right + self.camera_up() * up;
+        self.orbit_target += offset;
+        self.position += offset;
+    }
+
+    /// 保持朝向，将 target 设为 `aabb` 的中心，并拉开距离使其包围球完整地位于视野内
+    ///
+    /// - 正交投影同时调整可视区域的高度
+    /// - 包围盒为空或者不是有限值时不做任何事；退化为一个点时按照半径为 `near` 处理，避免被近平面裁剪
+    pub fn frame_aabb(&mut self, aabb: &Aabb) {
+        if aabb.is_empty() || !aabb.min.is_finite() || !aabb.max.is_finite() {
+            return;
+        }
+        let radius = (aabb.extent().length() * 0.5).max(self.near).max(Self::MIN_ORBIT_DISTANCE);
+
+        self.orbit_target = aabb.center();
+        self.orbit_distance = match &mut self.projection_mode {
+            ProjectionMode::Perspective { fov_y_deg } => {
+                let half_fov_y = fov_y_deg.to_radians() * 0.5;
+                let half_fov_x = (half_fov_y.tan() * self.asp).atan();
+                radius / half_fov_y.min(half_fov_x).sin()
+            }
+            ProjectionMode::Orthographic { height } => {
+                *height = 2.0 * radius * (1.0 / self.asp).max(1.0);
+                // 深度范围从 near 开始，包围球需要完整地位于 near 之后
+                radius + self.near + Self::MIN_ORBIT_DISTANCE
+            }
+        }
+        .clamp(Self::MIN_ORBIT_DISTANCE, Self::MAX_ORBIT_DISTANCE);
+        self.update_orbit_position();
+    }
+
+    #[inline]
+    fn update_orbit_position(&mut self) {
+        self.position = self.orbit_target - self.camera_forward() * self.orbit_distance;
+    }
+}
+
 impl Default for Camera {
     fn default() -> Self {
         Self {
@@ -118,8 +354,225 @@ impl Default for Camera {
             euler_pitch_deg: 0.0,
             euler_roll_deg: 0.0,
             asp: 1.0,
-            fov_deg_vertical: 60.0,
+            projection_mode: ProjectionMode::DEFAULT_PERSPECTIVE,
             near: 0.1,
+            far: 10000.0,
+            orbit_target: glam::vec3(0.0, 0.0, -10.0),
+            orbit_distance: 10.0,
+            convention: CameraConvention::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 将 ViewSpace 中的点变换到 NDC
+    fn project(camera: &Camera, pos_in_view: glam::Vec3) -> glam::Vec3 {
+        camera.get_projection_matrix().project_point3(pos_in_view)
+    }
+
+    #[test]
+    fn test_orthographic_projection() {
+        let camera = Camera {
+            asp: 2.0,
+            projection_mode: ProjectionMode::Orthographic { height: 4.0 },
+            near: 1.0,
+            far: 11.0,
+            ..Default::default()
+        };
+
+        // 可视区域为 [-4, 4] x [-2, 2]，深度从 near 到 far 线性映射到 [0, 1]
+        assert!(project(&camera, glam::vec3(4.0, 2.0, -1.0)).abs_diff_eq(glam::vec3(1.0, 1.0, 0.0), 1e-6));
+        assert!(project(&camera, glam::vec3(-4.0, -2.0, -11.0)).abs_diff_eq(glam::vec3(-1.0, -1.0, 1.0), 1e-6));
+        assert!(project(&camera, glam::vec3(2.0, 1.0, -6.0)).abs_diff_eq(glam::vec3(0.5, 0.5, 0.5), 1e-6));
+    }
+
+    #[test]
+    fn test_orthographic_ignores_distance() {
+        let camera = Camera {
+            projection_mode: ProjectionMode::DEFAULT_ORTHOGRAPHIC,
+            ..Default::default()
+        };
+
+        // 与透视投影不同，同一个点离相机越远，在 NDC 中的 xy 不变
+        let near_point = project(&camera, glam::vec3(1.0, 1.0, -2.0));
+        let far_point = project(&camera, glam::vec3(1.0, 1.0, -20.0));
+        assert!(near_point.truncate().abs_diff_eq(far_point.truncate(), 1e-6));
+    }
+
+    #[test]
+    fn test_frustum() {
+        // 相机位于 (0, 0, 10)，看向 -Z
+        let camera = Camera {
+            position: glam::vec3(0.0, 0.0, 10.0),
+            ..Default::default()
+        };
+        let frustum = camera.frustum();
+        let unit_box_at = |center: glam::Vec3| Aabb::new(center - 0.5, center + 0.5);
+
+        assert!(frustum.intersects_aabb(&unit_box_at(glam::Vec3::ZERO)));
+        assert!(frustum.intersects_aabb(&unit_box_at(glam::vec3(0.0, 0.0, -1.0e5))));
+        assert!(!frustum.intersects_aabb(&unit_box_at(glam::vec3(0.0, 0.0, 20.0))));
+        assert!(!frustum.intersects_aabb(&unit_box_at(glam::vec3(50.0, 0.0, 0.0))));
+    }
+
+    #[test]
+    fn test_begin_orbit_keeps_view() {
+        let mut camera = Camera {
+            position: glam::vec3(1.0, 2.0, 3.0),
+            euler_yaw_deg: 45.0,
+            euler_pitch_deg: -30.0,
+            // 上一次的 target 在相机后方，沿用 orbit_distance
+            orbit_target: glam::vec3(1.0, 2.0, 100.0),
+            orbit_distance: 5.0,
+            ..Default::default()
+        };
+        let view = camera.get_view_matrix();
+
+        camera.begin_orbit();
+        assert_eq!(camera.get_view_matrix(), view);
+        assert!((camera.orbit_target - camera.position).abs_diff_eq(camera.camera_forward() * 5.0, 1e-5));
+    }
+
+    #[test]
+    fn test_orbit_keeps_target() {
+        let mut camera = Camera {
+            position: glam::vec3(0.0, 0.0, 10.0),
+            orbit_target: glam::Vec3::ZERO,
+            ..Default::default()
+        };
+        // target 在相机正前方，沿用它的深度
+        camera.begin_orbit();
+        assert_eq!(camera.orbit_target, glam::Vec3::ZERO);
+        assert_eq!(camera.orbit_distance, 10.0);
+
+        camera.orbit_rotate(90.0, -30.0);
+        assert!(camera.orbit_target.abs_diff_eq(glam::Vec3::ZERO, 1e-5));
+        assert!((camera.position.length() - 10.0).abs() < 1e-4);
+        assert!((camera.position + camera.camera_forward() * 10.0).abs_diff_eq(glam::Vec3::ZERO, 1e-4));
+
+        camera.orbit_zoom(0.5);
+        assert!((camera.position.length() - 5.0).abs() < 1e-4);
+
+        camera.orbit_pan(1.0, 2.0);
+        let expected_target = camera.camera_right() + camera.camera_up() * 2.0;
+        assert!(camera.orbit_target.abs_diff_eq(expected_target, 1e-5));
+        assert!(((camera.position - camera.orbit_target).length() - 5.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_frame_aabb() {
+        let aabb = Aabb::new(glam::vec3(10.0, -2.0, 5.0), glam::vec3(14.0, 2.0, 9.0));
+        for projection_mode in [
+            ProjectionMode::DEFAULT_PERSPECTIVE,
+            ProjectionMode::DEFAULT_ORTHOGRAPHIC,
+        ] {
+            let mut camera = Camera {
+                euler_yaw_deg: 30.0,
+                euler_pitch_deg: -20.0,
+                asp: 0.5,
+                projection_mode,
+                ..Default::default()
+            };
+            camera.frame_aabb(&aabb);
+
+            assert_eq!(camera.orbit_target, aabb.center());
+            assert!(
+                (camera.position + camera.camera_forward() * camera.orbit_distance).abs_diff_eq(aabb.center(), 1e-4)
+            );
+            let view_projection = camera.get_projection_matrix() * camera.get_view_matrix();
+            for corner in aabb.corners() {
+                let ndc = view_projection.project_point3(corner);
+                assert!(ndc.x.abs() <= 1.0 && ndc.y.abs() <= 1.0, "{:?}: {:?}", projection_mode, ndc);
+                assert!(ndc.z >= 0.0 && ndc.z <= 1.0, "{:?}: {:?}", projection_mode, ndc);
+            }
         }
     }
+
+    #[test]
+    fn test_frame_degenerate_aabb() {
+        let mut camera = Camera::default();
+        let position = camera.position;
+        camera.frame_aabb(&Aabb::EMPTY);
+        camera.frame_aabb(&Aabb::new(glam::Vec3::splat(f32::NEG_INFINITY), glam::Vec3::splat(f32::INFINITY)));
+        assert_eq!(camera.position, position);
+
+        // 单点
+        let point = glam::vec3(1.0, 2.0, 3.0);
+        camera.frame_aabb(&Aabb::new(point, point));
+        assert_eq!(camera.orbit_target, point);
+        assert!(camera.orbit_distance > camera.near);
+        assert!(camera.position.is_finite());
+    }
+
+    #[test]
+    fn test_bookmark_round_trip() {
+        let mut camera = Camera {
+            position: glam::vec3(1.0, 2.0, 3.0),
+            euler_yaw_deg: 45.0,
+            euler_pitch_deg: -30.0,
+            projection_mode: ProjectionMode::Perspective { fov_y_deg: 40.0 },
+            ..Default::default()
+        };
+        let bookmark = camera.bookmark();
+        let view = camera.get_view_matrix();
+
+        camera.apply_bookmark(&Camera::default().bookmark());
+        assert_ne!(camera.get_view_matrix(), view);
+        camera.apply_bookmark(&bookmark);
+        assert_eq!(camera.get_view_matrix(), view);
+        assert_eq!(camera.projection_mode, bookmark.projection_mode);
+    }
+
+    #[test]
+    fn test_bookmark_lerp() {
+        let from = CameraBookmark {
+            position: glam::Vec3::ZERO,
+            euler_yaw_deg: 350.0,
+            euler_pitch_deg: 0.0,
+            euler_roll_deg: 0.0,
+            projection_mode: ProjectionMode::Perspective { fov_y_deg: 40.0 },
+        };
+        let to = CameraBookmark {
+            position: glam::vec3(10.0, 0.0, 0.0),
+            euler_yaw_deg: 10.0,
+            euler_pitch_deg: 20.0,
+            euler_roll_deg: 0.0,
+            projection_mode: ProjectionMode::Perspective { fov_y_deg: 80.0 },
+        };
+
+        assert_eq!(from.lerp(&to, 0.0), from);
+        let end = from.lerp(&to, 1.0);
+        assert!(end.position.abs_diff_eq(to.position, 1e-5));
+        assert!((end.euler_yaw_deg - 10.0).abs() < 1e-3);
+
+        // yaw 经过 0 度，而不是反方向转 340 度
+        let mid = from.lerp(&to, 0.5);
+        assert!(mid.euler_yaw_deg.abs() < 1e-3 || (mid.euler_yaw_deg - 360.0).abs() < 1e-3);
+        assert!((mid.euler_pitch_deg - 10.0).abs() < 1e-5);
+        assert_eq!(mid.projection_mode, ProjectionMode::Perspective { fov_y_deg: 60.0 });
+
+        // 投影方式不同时直接切换
+        let ortho = CameraBookmark {
+            projection_mode: ProjectionMode::DEFAULT_ORTHOGRAPHIC,
+            ..to
+        };
+        assert_eq!(from.lerp(&ortho, 0.5).projection_mode, ProjectionMode::DEFAULT_ORTHOGRAPHIC);
+    }
+
+    #[test]
+    fn test_switch_projection_mode_keeps_view() {
+        let mut camera = Camera {
+            position: glam::vec3(1.0, 2.0, 3.0),
+            euler_yaw_deg: 30.0,
+            euler_pitch_deg: -10.0,
+            ..Default::default()
+        };
+        let view = camera.get_view_matrix();
+
+        camera.projection_mode = ProjectionMode::DEFAULT_ORTHOGRAPHIC;
+        assert_eq!(camera.get_view_matrix(), view);
+    }
 }
@@ -0,0 +1,94 @@
+use std::time::{Duration, Instant};
+
+/// 帧率上限
+///
+/// 每帧结束时等待到距离帧开始 `1 / target_fps` 的时刻。`thread::sleep` 的精度取决于系统的调度粒度
+/// （Windows 上可能达到十几毫秒），因此先 sleep 到目标时刻之前的 [`Self::SPIN_THRESHOLD`]，剩余的时间自旋等待。
+///
+/// 与 present mode 无关：Fifo 由垂直同步限制帧率，Mailbox / Immediate 可以配合帧率上限降低 GPU 负载
+#[derive(Debug, Default)]
+pub struct FrameLimiter {
+    /// None 表示不限制帧率
+    target_fps: Option<u32>,
+}
+// new & init
+impl FrameLimiter {
+    pub fn new(target_fps: Option<u32>) -> Self {
+        let mut limiter = Self::default();
+        limiter.set_target_fps(target_fps);
+        limiter
+    }
+}
+// getter
+impl FrameLimiter {
+    #[inline]
+    pub fn target_fps(&self) -> Option<u32> {
+        self.target_fps
+    }
+
+    /// 目标的帧间隔，不限制帧率时为 None
+    #[inline]
+    pub fn frame_interval(&self) -> Option<Duration> {
+        self.target_fps.map(|fps| Duration::from_secs_f64(1.0 / fps as f64))
+    }
+}
+// update
+impl FrameLimiter {
+    /// 短于该时间的等待使用自旋，避免 sleep 的抖动
+    const SPIN_THRESHOLD: Duration = Duration::from_millis(2);
+
+    /// `Some(0)` 视为不限制帧率
+    pub fn set_target_fps(&mut self, target_fps: Option<u32>) {
+        self.target_fps = target_fps.filter(|&fps| fps > 0);
+    }
+
+    /// 等待到距离 `frame_start` 一个帧间隔的时刻，已经超时或者不限制帧率时立即返回
+    pub fn wait(&self, frame_start: Instant) {
+        let Some(frame_interval) = self.frame_interval() else {
+            return;
+        };
+        let deadline = frame_start + frame_interval;
+
+        let _span = tracy_client::span!("FrameLimiter::wait");
+        if let Some(sleep_time) = Self::sleep_time(deadline.saturating_duration_since(Instant::now())) {
+            std::thread::sleep(sleep_time);
+        }
+        while Instant::now() < deadline {
+            std::hint::spin_loop();
+        }
+    }
+
+    /// 剩余 `remaining` 时可以 sleep 的时长，剩下的部分自旋等待
+    fn sleep_time(remaining: Duration) -> Option<Duration> {
+        remaining.checked_sub(Self::SPIN_THRESHOLD).filter(|sleep_time| !sleep_time.is_zero())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_interval() {
+        assert_eq!(FrameLimiter::new(None).frame_interval(), None);
+        assert_eq!(FrameLimiter::new(Some(0)).frame_interval(), None);
+
+        let interval = FrameLimiter::new(Some(60)).frame_interval().unwrap();
+        assert!((interval.as_secs_f64() - 1.0 / 60.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_sleep_time() {
+        assert_eq!(FrameLimiter::sleep_time(Duration::from_millis(10)), Some(Duration::from_millis(8)));
+        assert_eq!(FrameLimiter::sleep_time(Duration::from_millis(2)), None);
+        assert_eq!(FrameLimiter::sleep_time(Duration::from_micros(500)), None);
+    }
+
+    #[test]
+    fn test_wait_reaches_deadline() {
+        let limiter = FrameLimiter::new(Some(200));
+        let frame_start = Instant::now();
+        limiter.wait(frame_start);
+        assert!(frame_start.elapsed() >= Duration::from_millis(5));
+    }
+}
@@ -1,2 +1,3 @@
 pub mod camera;
+pub mod frame_limiter;
 pub mod timer;
@@ -3,8 +3,20 @@ pub struct Timer {
     _start_time: std::time::Instant,
     last_tick: std::time::Instant,
 
+    /// 推进的游戏时间，暂停时为 0
     delta_time: std::time::Duration,
     total_time: std::time::Duration,
+    /// 两次 tick 之间真实经过的时间，不受暂停影响
+    real_delta_time: std::time::Duration,
+
+    paused: bool,
+    /// 暂停时推进一帧，在下一次 tick 时消耗
+    step_requested: bool,
+    /// 最近一次 tick 是否推进了时间：没有暂停，或者暂停时单步
+    advancing: bool,
+
+    /// 固定的帧间隔，设置后 `tick` 不再读取系统时间，用于确定性渲染
+    fixed_delta_time: Option<std::time::Duration>,
 }
 
 impl Default for Timer {
@@ -15,6 +27,11 @@ impl Default for Timer {
             last_tick: now,
             delta_time: std::time::Duration::ZERO,
             total_time: std::time::Duration::ZERO,
+            real_delta_time: std::time::Duration::ZERO,
+            paused: false,
+            step_requested: false,
+            advancing: true,
+            fixed_delta_time: None,
         }
     }
 }
@@ -23,15 +40,58 @@ impl Timer {
     /// 每帧开始的时候调用
     pub fn tick(&mut self) {
         let now = std::time::Instant::now();
-        self.delta_time = now.duration_since(self.last_tick);
+        self.real_delta_time = now.duration_since(self.last_tick);
         self.last_tick = now;
+
+        self.advancing = !self.paused || std::mem::take(&mut self.step_requested);
+        self.delta_time = if self.advancing {
+            self.fixed_delta_time.unwrap_or(self.real_delta_time)
+        } else {
+            std::time::Duration::ZERO
+        };
         self.total_time += self.delta_time;
     }
 
+    /// 暂停之后 `delta_time` 为 0，`total_time` 不再增加
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        if !paused {
+            self.step_requested = false;
+        }
+    }
+
+    /// 在下一次 tick 时只推进一帧，之后保持暂停；没有暂停时会先暂停
+    pub fn step_once(&mut self) {
+        self.paused = true;
+        self.step_requested = true;
+    }
+
+    #[inline]
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// 最近一次 tick 是否推进了时间
+    #[inline]
+    pub fn is_advancing(&self) -> bool {
+        self.advancing
+    }
+
+    /// 设置固定的帧间隔，None 表示使用真实经过的时间
+    pub fn set_fixed_delta_time(&mut self, fixed_delta_time: Option<std::time::Duration>) {
+        self.fixed_delta_time = fixed_delta_time;
+    }
+
     pub fn elapsed_since_tick(&self) -> std::time::Duration {
         self.last_tick.elapsed()
     }
 
+    /// 最近一次 tick 的时刻，也就是当前帧开始的时刻
+    #[inline]
+    pub fn last_tick(&self) -> std::time::Instant {
+        self.last_tick
+    }
+
     #[inline]
     pub fn delta_time(&self) -> std::time::Duration {
         self.delta_time
@@ -49,10 +109,16 @@ impl Timer {
         self.delta_time.as_secs_f32()
     }
 
-    /// 当前帧率
+    /// 两次 tick 之间真实经过的时间，暂停时同样会更新，用于 GUI 和帧率统计
+    #[inline]
+    pub fn real_delta_time(&self) -> std::time::Duration {
+        self.real_delta_time
+    }
+
+    /// 当前帧率，不受暂停影响
     #[inline]
     pub fn fps(&self) -> f32 {
-        1.0 / self.delta_time.as_secs_f32()
+        1.0 / self.real_delta_time.as_secs_f32()
     }
 
     /// 总运行时间
@@ -66,3 +132,39 @@ impl Timer {
         self.total_time.as_secs_f32() * 1000.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pause_and_step() {
+        let frame = std::time::Duration::from_millis(10);
+        let mut timer = Timer::default();
+        timer.set_fixed_delta_time(Some(frame));
+
+        timer.tick();
+        assert!(timer.is_advancing());
+        assert_eq!(timer.delta_time(), frame);
+
+        timer.set_paused(true);
+        timer.tick();
+        assert!(!timer.is_advancing());
+        assert_eq!(timer.delta_time(), std::time::Duration::ZERO);
+        assert!((timer.total_time_ms() - 10.0).abs() < 1e-3);
+
+        // 单步只推进一帧
+        timer.step_once();
+        timer.tick();
+        assert!(timer.is_advancing());
+        assert_eq!(timer.delta_time(), frame);
+        timer.tick();
+        assert!(!timer.is_advancing());
+        assert!((timer.total_time_ms() - 20.0).abs() < 1e-3);
+
+        timer.set_paused(false);
+        timer.tick();
+        assert!(timer.is_advancing());
+        assert!((timer.total_time_ms() - 30.0).abs() < 1e-3);
+    }
+}
@@ -7,7 +7,7 @@ use truvis_gfx::gfx::Gfx;
 use truvis_gfx::resources::image::GfxImage;
 use truvis_gfx::resources::image_view::GfxImageViewDesc;
 use truvis_gfx::swapchain::surface::GfxSurface;
-use truvis_gfx::swapchain::swapchain::{GfxSwapchain, GfxSwapchainImageInfo};
+use truvis_gfx::swapchain::swapchain::{GfxSwapchain, GfxSwapchainImageInfo, GfxSwapchainStatus};
 use truvis_gui_backend::gui_backend::GuiBackend;
 use truvis_render_interface::frame_counter::FrameCounter;
 use truvis_render_interface::gfx_resource_manager::GfxResourceManager;
@@ -41,13 +41,20 @@ pub struct RenderPresent {
     pub gui_backend: GuiBackend,
 
     /// 数量和 fif num 相同
-    pub present_complete_semaphores: [GfxSemaphore; FrameCounter::fif_count()],
+    pub present_complete_semaphores: Vec<GfxSemaphore>,
 
     /// 数量和 swapchain image num 相同
     pub render_complete_semaphores: Vec<GfxSemaphore>,
 
     window_physical_extent: vk::Extent2D,
     need_resize: bool,
+    /// acquire 或 present 返回了 out-of-date，即使尺寸没有变化也必须重建 swapchain
+    swapchain_out_of_date: bool,
+
+    /// 按照偏好排列的 present mode，重建 swapchain 时保持不变
+    present_modes: Vec<vk::PresentModeKHR>,
+    /// present mode 被修改，需要重建 swapchain
+    present_mode_dirty: bool,
 }
 
 // new & init
@@ -57,11 +64,13 @@ impl RenderPresent {
         raw_display_handle: RawDisplayHandle,
         raw_window_handle: RawWindowHandle,
         window_physical_extent: vk::Extent2D,
+        present_modes: &[vk::PresentModeKHR],
     ) -> Self {
         let surface = GfxSurface::new(raw_display_handle, raw_window_handle);
+        log::debug!("{}", Gfx::get().surface_capabilities(&surface));
         let swapchain = GfxSwapchain::new(
             &surface,
-            DefaultRendererSettings::DEFAULT_PRESENT_MODE,
+            present_modes,
             DefaultRendererSettings::DEFAULT_SURFACE_FORMAT,
             window_physical_extent,
             None,
@@ -74,7 +83,8 @@ impl RenderPresent {
         let gui_backend = GuiBackend::new();
 
         let present_complete_semaphores = FrameCounter::frame_labes()
-            .map(|frame_label| GfxSemaphore::new(&format!("window-present-complete-{}", frame_label)));
+            .map(|frame_label| GfxSemaphore::new(&format!("window-present-complete-{}", frame_label)))
+            .collect_vec();
         let render_complete_semaphores = (0..swapchain_image_infos.image_cnt)
             .map(|i| GfxSemaphore::new(&format!("window-render-complete-{}", i)))
             .collect_vec();
@@ -91,6 +101,10 @@ impl RenderPresent {
 
             window_physical_extent,
             need_resize: false,
+            swapchain_out_of_date: false,
+
+            present_modes: present_modes.to_vec(),
+            present_mode_dirty: false,
         }
     }
 
@@ -140,6 +154,12 @@ impl RenderPresent {
         self.swapchain.as_ref().unwrap().image_infos()
     }
 
+    /// swapchain 实际使用的 present mode
+    #[inline]
+    pub fn current_present_mode(&self) -> vk::PresentModeKHR {
+        self.swapchain.as_ref().unwrap().present_mode()
+    }
+
     #[inline]
     pub fn current_render_compute_semaphore(&self) -> &GfxSemaphore {
         let swapchain = self.swapchain.as_ref().unwrap();
@@ -168,10 +188,25 @@ impl RenderPresent {
         self.need_resize = true;
     }
 
+    /// 修改按照偏好排列的 present mode，会在下一帧重建 swapchain
+    pub fn set_present_modes(&mut self, present_modes: &[vk::PresentModeKHR]) {
+        if self.present_modes == present_modes {
+            return;
+        }
+        log::debug!("present modes change to: {:?}, need rebuild swapchain", present_modes);
+
+        self.present_modes = present_modes.to_vec();
+        self.present_mode_dirty = true;
+    }
+
     /// 判断是否需要重建 swapchain
     ///
-    /// 需要综合判断窗口尺寸是否发生变化，以及当前 surface 的实时状态
+    /// 需要综合判断窗口尺寸是否发生变化，以及当前 surface 的实时状态；
+    /// present mode 被修改或者 swapchain out-of-date 时总是需要重建
     pub fn need_resize(&mut self) -> bool {
+        if self.present_mode_dirty || self.swapchain_out_of_date {
+            return true;
+        }
         if !self.need_resize {
             return false;
         }
@@ -187,43 +222,80 @@ impl RenderPresent {
         self.need_resize
     }
 
+    /// 按照窗口的最新尺寸重建 swapchain，以及依赖 swapchain image 的 image view 和 semaphore
     pub fn rebuild_after_resized(&mut self, gfx_resource_manager: &mut GfxResourceManager) {
-        unsafe {
-            Gfx::get().gfx_device().device_wait_idle().unwrap();
-        }
+        // recreate 中会等待 device idle，之后旧的 swapchain image 可以立即销毁
+        let swapchain = self.swapchain.as_mut().unwrap();
+        swapchain.recreate(&self.surface, &self.present_modes, self.window_physical_extent);
 
         for image_handle in std::mem::take(&mut self.swapchain_images) {
             gfx_resource_manager.destroy_image_immediate(image_handle);
         }
-        let old_swapchain = self.swapchain.take();
-        self.swapchain = Some(GfxSwapchain::new(
-            &self.surface,
-            DefaultRendererSettings::DEFAULT_PRESENT_MODE,
-            DefaultRendererSettings::DEFAULT_SURFACE_FORMAT,
-            self.window_physical_extent,
-            old_swapchain,
-        ));
         (self.swapchain_images, self.swapchain_image_views) =
-            Self::create_swapchain_images_and_views(self.swapchain.as_ref().unwrap(), gfx_resource_manager);
+            Self::create_swapchain_images_and_views(swapchain, gfx_resource_manager);
+
+        // image 数量可能发生变化，render complete semaphore 需要与之保持一致
+        let image_cnt = swapchain.image_infos().image_cnt;
+        if self.render_complete_semaphores.len() != image_cnt {
+            for semaphore in std::mem::take(&mut self.render_complete_semaphores) {
+                semaphore.destroy();
+            }
+            self.render_complete_semaphores =
+                (0..image_cnt).map(|i| GfxSemaphore::new(&format!("window-render-complete-{}", i))).collect_vec();
+        }
 
         self.need_resize = false;
+        self.swapchain_out_of_date = false;
+        self.present_mode_dirty = false;
     }
 
-    pub fn acquire_image(&mut self, frame_label: FrameLabel) {
-        // 从 swapchain 获取图像
-        let swapchain = self.swapchain.as_mut().unwrap();
+    /// 从 swapchain 获取这一帧使用的 image
+    ///
+    /// swapchain out-of-date 时无法获取 image，会立即重建 swapchain 并重新获取。
+    /// 返回值表示 swapchain 是否被重建，此时依赖 swapchain 尺寸的资源也需要重建
+    pub fn acquire_image(&mut self, frame_label: FrameLabel, gfx_resource_manager: &mut GfxResourceManager) -> bool {
         let timeout_ns = 10 * 1000 * 1000 * 1000;
 
-        self.need_resize =
-            swapchain.acquire_next_image(Some(&self.present_complete_semaphores[*frame_label]), None, timeout_ns);
+        let mut rebuilt = false;
+        loop {
+            let swapchain = self.swapchain.as_mut().unwrap();
+            let status =
+                swapchain.acquire_next_image(Some(&self.present_complete_semaphores[*frame_label]), None, timeout_ns);
+            match status {
+                GfxSwapchainStatus::OutOfDate => {
+                    self.update_window_extent_from_surface();
+                    self.rebuild_after_resized(gfx_resource_manager);
+                    rebuilt = true;
+                }
+                // suboptimal 的 image 仍然可以使用，下一帧再重建
+                GfxSwapchainStatus::Suboptimal => {
+                    self.need_resize = true;
+                    return rebuilt;
+                }
+                GfxSwapchainStatus::Optimal => return rebuilt,
+            }
+        }
     }
 
     pub fn present_image(&mut self) {
         let swapchain = self.swapchain.as_ref().unwrap();
-        self.need_resize = swapchain.present_image(
+        let status = swapchain.present_image(
             Gfx::get().gfx_queue(),
             std::slice::from_ref(&self.render_complete_semaphores[swapchain.current_image_index()]),
         );
+        match status {
+            GfxSwapchainStatus::OutOfDate => self.swapchain_out_of_date = true,
+            GfxSwapchainStatus::Suboptimal => self.need_resize = true,
+            GfxSwapchainStatus::Optimal => {}
+        }
+    }
+
+    /// 窗口尺寸的事件可能晚于 surface 的变化，surface 给出了确定的尺寸时以 surface 为准
+    fn update_window_extent_from_surface(&mut self) {
+        let current_extent = self.surface.get_capabilities().current_extent;
+        if current_extent.width != 0xFFFFFFFF && current_extent.height != 0xFFFFFFFF {
+            self.window_physical_extent = current_extent;
+        }
     }
 }
 
@@ -0,0 +1,343 @@
+//! 帧时间预算与自动质量调节
+//!
+//! 监控每帧的耗时，持续超出预算时按优先级逐档降低质量，持续低于预算时按相反的顺序逐档恢复。
+//!
+//! # 质量维度
+//! 每个维度（[`QualityKnob`]）有若干档位，档位 0 为最高质量；每个维度可以单独锁定在某个档位，
+//! 锁定后不再参与自动调节。调节结果通过 [`QualityGovernor::overrides`] 转换为 [`QualityOverrides`]，
+//! 由 `Renderer` 写入 `FrameSettings`，各个 pass 据此限制用户设置。
+//!
+//! # 防抖
+//! - 帧耗时经过指数平滑，单帧的尖峰不会触发调节
+//! - 降级和升级使用不同的阈值，中间留出滞回区间
+//! - 每次调节后等待若干帧（分辨率变化需要重建 framebuffer）再做下一次判断
+
+use truvis_render_interface::pipeline_settings::QualityOverrides;
+
+/// 可调节的质量维度，按默认的降级顺序排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QualityKnob {
+    /// SSAO 的采样数，最低档关闭 SSAO
+    Ssao,
+    /// 降噪的滤波核半径
+    DenoiseKernel,
+    /// 渲染分辨率
+    RenderScale,
+}
+impl QualityKnob {
+    pub const ALL: [QualityKnob; 3] = [QualityKnob::Ssao, QualityKnob::DenoiseKernel, QualityKnob::RenderScale];
+
+    const SSAO_MAX_SAMPLE_COUNT: [u32; 4] = [u32::MAX, 8, 4, 0];
+    const DENOISE_MAX_KERNEL_RADIUS: [i32; 3] = [i32::MAX, 2, 1];
+    const RENDER_SCALE_FACTOR: [f32; 5] = [1.0, 0.85, 0.7, 0.6, 0.5];
+
+    /// 档位数量
+    pub const fn level_count(self) -> usize {
+        match self {
+            QualityKnob::Ssao => Self::SSAO_MAX_SAMPLE_COUNT.len(),
+            QualityKnob::DenoiseKernel => Self::DENOISE_MAX_KERNEL_RADIUS.len(),
+            QualityKnob::RenderScale => Self::RENDER_SCALE_FACTOR.len(),
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            QualityKnob::Ssao => "SSAO",
+            QualityKnob::DenoiseKernel => "Denoise Kernel",
+            QualityKnob::RenderScale => "Render Scale",
+        }
+    }
+
+    /// 将该维度的档位写入 overrides
+    fn apply(self, level: usize, overrides: &mut QualityOverrides) {
+        match self {
+            QualityKnob::Ssao => {
+                let max_sample_count = Self::SSAO_MAX_SAMPLE_COUNT[level];
+                overrides.ssao_allowed = max_sample_count > 0;
+                overrides.ssao_max_sample_count = max_sample_count;
+            }
+            QualityKnob::DenoiseKernel => overrides.denoise_max_kernel_radius = Self::DENOISE_MAX_KERNEL_RADIUS[level],
+            QualityKnob::RenderScale => overrides.render_scale_factor = Self::RENDER_SCALE_FACTOR[level],
+        }
+    }
+}
+
+/// 一个质量维度的状态
+#[derive(Debug, Clone, Copy)]
+pub struct QualityKnobState {
+    pub knob: QualityKnob,
+    /// 越小越先降级、越晚恢复
+    pub priority: i32,
+    /// 当前档位
+    pub level: usize,
+    /// 锁定的档位，锁定后不参与自动调节
+    pub locked: Option<usize>,
+}
+impl QualityKnobState {
+    #[inline]
+    fn effective_level(&self) -> usize {
+        self.locked.unwrap_or(self.level)
+    }
+}
+
+pub struct QualityGovernor {
+    /// 是否自动调节；关闭时未锁定的维度都恢复到最高质量
+    pub enabled: bool,
+    /// 每帧的耗时预算（毫秒）
+    pub frame_budget_ms: f32,
+    /// 平滑后的帧耗时超过 `frame_budget_ms * degrade_ratio` 时降级
+    pub degrade_ratio: f32,
+    /// 平滑后的帧耗时低于 `frame_budget_ms * upgrade_ratio` 时升级
+    pub upgrade_ratio: f32,
+    /// 每次调节后至少等待的帧数
+    pub cooldown_frames: u32,
+
+    knobs: Vec<QualityKnobState>,
+
+    /// 指数平滑后的帧耗时（毫秒）
+    smoothed_frame_ms: Option<f32>,
+    /// 距离下一次允许调节还需要的帧数
+    cooldown_remaining: u32,
+}
+
+// new & init
+impl Default for QualityGovernor {
+    fn default() -> Self {
+        Self::new(1000.0 / 60.0)
+    }
+}
+impl QualityGovernor {
+    /// 帧耗时的平滑系数，越大越灵敏
+    const SMOOTH_FACTOR: f32 = 0.1;
+
+    pub fn new(frame_budget_ms: f32) -> Self {
+        Self {
+            enabled: false,
+            frame_budget_ms,
+            degrade_ratio: 1.05,
+            upgrade_ratio: 0.75,
+            cooldown_frames: 30,
+            knobs: QualityKnob::ALL
+                .iter()
+                .enumerate()
+                .map(|(idx, &knob)| QualityKnobState {
+                    knob,
+                    priority: idx as i32,
+                    level: 0,
+                    locked: None,
+                })
+                .collect(),
+            smoothed_frame_ms: None,
+            cooldown_remaining: 0,
+        }
+    }
+}
+// getter
+impl QualityGovernor {
+    #[inline]
+    pub fn knobs(&self) -> &[QualityKnobState] {
+        &self.knobs
+    }
+
+    /// 平滑后的帧耗时（毫秒）
+    #[inline]
+    pub fn smoothed_frame_ms(&self) -> f32 {
+        self.smoothed_frame_ms.unwrap_or(0.0)
+    }
+
+    /// 某个维度当前生效的档位
+    pub fn level(&self, knob: QualityKnob) -> usize {
+        self.knob_state(knob).effective_level()
+    }
+
+    /// 当前档位对应的降级
+    pub fn overrides(&self) -> QualityOverrides {
+        let mut overrides = QualityOverrides::default();
+        for state in &self.knobs {
+            state.knob.apply(state.effective_level(), &mut overrides);
+        }
+        overrides
+    }
+
+    fn knob_state(&self, knob: QualityKnob) -> &QualityKnobState {
+        self.knobs.iter().find(|state| state.knob == knob).unwrap()
+    }
+
+    fn knob_state_mut(&mut self, knob: QualityKnob) -> &mut QualityKnobState {
+        self.knobs.iter_mut().find(|state| state.knob == knob).unwrap()
+    }
+}
+// tools
+impl QualityGovernor {
+    /// 将某个维度锁定在指定档位，None 表示解除锁定
+    pub fn lock(&mut self, knob: QualityKnob, level: Option<usize>) {
+        let state = self.knob_state_mut(knob);
+        state.locked = level.map(|level| level.min(knob.level_count() - 1));
+        // 解除锁定后从锁定的档位继续调节，避免质量突变
+        if let Some(level) = state.locked {
+            state.level = level;
+        }
+    }
+
+    /// 修改某个维度的优先级，越小越先降级
+    pub fn set_priority(&mut self, knob: QualityKnob, priority: i32) {
+        self.knob_state_mut(knob).priority = priority;
+    }
+
+    /// 所有未锁定的维度恢复到最高质量，并清空帧耗时的历史
+    pub fn reset(&mut self) {
+        self.knobs.iter_mut().filter(|state| state.locked.is_none()).for_each(|state| state.level = 0);
+        self.smoothed_frame_ms = None;
+        self.cooldown_remaining = 0;
+    }
+
+    /// 每帧调用一次，传入当前帧的耗时；返回档位是否发生了变化
+    pub fn update(&mut self, frame_ms: f32) -> bool {
+        if !self.enabled {
+            let changed = self.knobs.iter().any(|state| state.locked.is_none() && state.level != 0);
+            self.reset();
+            return changed;
+        }
+
+        let smoothed = match self.smoothed_frame_ms {
+            Some(smoothed) => smoothed + (frame_ms - smoothed) * Self::SMOOTH_FACTOR,
+            None => frame_ms,
+        };
+        self.smoothed_frame_ms = Some(smoothed);
+
+        if self.cooldown_remaining > 0 {
+            self.cooldown_remaining -= 1;
+            return false;
+        }
+
+        let changed = if smoothed > self.frame_budget_ms * self.degrade_ratio {
+            self.degrade()
+        } else if smoothed < self.frame_budget_ms * self.upgrade_ratio {
+            self.upgrade()
+        } else {
+            false
+        };
+        if changed {
+            self.cooldown_remaining = self.cooldown_frames;
+        }
+        changed
+    }
+
+    /// 优先级最小、还能降级的维度降低一档
+    fn degrade(&mut self) -> bool {
+        let Some(state) = self
+            .knobs
+            .iter_mut()
+            .filter(|state| state.locked.is_none() && state.level + 1 < state.knob.level_count())
+            .min_by_key(|state| state.priority)
+        else {
+            return false;
+        };
+
+        state.level += 1;
+        log::info!("quality governor: degrade {} to level {}", state.knob.name(), state.level);
+        true
+    }
+
+    /// 优先级最大、已经降级的维度恢复一档
+    fn upgrade(&mut self) -> bool {
+        let Some(state) = self
+            .knobs
+            .iter_mut()
+            .filter(|state| state.locked.is_none() && state.level > 0)
+            .max_by_key(|state| state.priority)
+        else {
+            return false;
+        };
+
+        state.level -= 1;
+        log::info!("quality governor: upgrade {} to level {}", state.knob.name(), state.level);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn governor() -> QualityGovernor {
+        let mut governor = QualityGovernor::new(10.0);
+        governor.enabled = true;
+        governor.cooldown_frames = 0;
+        governor
+    }
+
+    #[test]
+    fn test_degrade_by_priority() {
+        let mut governor = governor();
+
+        // SSAO 的优先级最小，最先降级，直到关闭
+        for level in 1..QualityKnob::Ssao.level_count() {
+            assert!(governor.update(20.0));
+            assert_eq!(governor.level(QualityKnob::Ssao), level);
+        }
+        assert!(!governor.overrides().ssao_allowed);
+
+        // 然后是降噪
+        assert!(governor.update(20.0));
+        assert_eq!(governor.level(QualityKnob::DenoiseKernel), 1);
+        assert_eq!(governor.level(QualityKnob::RenderScale), 0);
+    }
+
+    #[test]
+    fn test_upgrade_in_reverse_order() {
+        let mut governor = governor();
+        while governor.update(20.0) {}
+        assert_eq!(governor.overrides().render_scale_factor, 0.5);
+
+        // 帧耗时降下来之后，最后降级的分辨率最先恢复
+        governor.smoothed_frame_ms = None;
+        assert!(governor.update(1.0));
+        assert_eq!(governor.level(QualityKnob::RenderScale), QualityKnob::RenderScale.level_count() - 2);
+        assert_eq!(governor.level(QualityKnob::DenoiseKernel), QualityKnob::DenoiseKernel.level_count() - 1);
+    }
+
+    #[test]
+    fn test_hysteresis_keeps_level() {
+        let mut governor = governor();
+        assert!(governor.update(20.0));
+
+        // 处于升级阈值（7.5）和降级阈值（10.5）之间，保持当前档位
+        governor.smoothed_frame_ms = None;
+        assert!(!governor.update(9.0));
+        assert_eq!(governor.level(QualityKnob::Ssao), 1);
+    }
+
+    #[test]
+    fn test_locked_knob_is_skipped() {
+        let mut governor = governor();
+        governor.lock(QualityKnob::Ssao, Some(0));
+
+        assert!(governor.update(20.0));
+        assert_eq!(governor.level(QualityKnob::Ssao), 0);
+        assert_eq!(governor.level(QualityKnob::DenoiseKernel), 1);
+    }
+
+    #[test]
+    fn test_cooldown() {
+        let mut governor = governor();
+        governor.cooldown_frames = 2;
+
+        assert!(governor.update(20.0));
+        assert!(!governor.update(20.0));
+        assert!(!governor.update(20.0));
+        assert!(governor.update(20.0));
+    }
+
+    #[test]
+    fn test_disabled_restores_unlocked() {
+        let mut governor = governor();
+        governor.lock(QualityKnob::RenderScale, Some(2));
+        assert!(governor.update(20.0));
+
+        governor.enabled = false;
+        assert!(governor.update(20.0));
+        assert_eq!(governor.level(QualityKnob::Ssao), 0);
+        assert_eq!(governor.overrides().render_scale_factor, QualityKnob::RENDER_SCALE_FACTOR[2]);
+    }
+}
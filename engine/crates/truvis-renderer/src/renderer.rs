@@ -1,13 +1,24 @@
+use crate::frame_dump::ExrFrameDump;
+#[cfg(feature = "metrics")]
+use crate::metrics::RenderMetrics;
 use crate::platform::camera::Camera;
+use crate::platform::frame_limiter::FrameLimiter;
 use crate::platform::timer::Timer;
 use crate::present::render_present::RenderPresent;
+use crate::quality_governor::QualityGovernor;
+use crate::subsystems::frame_subsystems::{AssetUpload, ResourceCleanup};
+use crate::subsystems::gpu_skinning::GpuSkinning;
 use ash::vk;
 use raw_window_handle::{RawDisplayHandle, RawWindowHandle};
 use std::ffi::CStr;
+use std::path::Path;
 use truvis_asset::asset_hub::AssetHub;
+use truvis_crate_tools::resource::TruvisPath;
 use truvis_gfx::basic::bytes::BytesConvert;
 use truvis_gfx::commands::command_buffer::GfxCommandBuffer;
-use truvis_gfx::commands::semaphore::GfxSemaphore;
+use truvis_gfx::commands::timeline::GfxTimeline;
+use truvis_gfx::pipelines::shader_watcher::GfxShaderWatcher;
+use truvis_gfx::query::gpu_timer::GfxGpuTimer;
 use truvis_gfx::resources::special_buffers::structured_buffer::GfxStructuredBuffer;
 use truvis_gfx::swapchain::swapchain::GfxSwapchainImageInfo;
 use truvis_gfx::utilities::descriptor_cursor::GfxDescriptorCursor;
@@ -18,18 +29,22 @@ use truvis_gfx::{
     },
     gfx::Gfx,
 };
+use truvis_render_graph::frame_hooks::{FrameHookOrder, FrameHooks};
 use truvis_render_graph::render_context::RenderContext;
 use truvis_render_graph::resources::fif_buffer::FifBuffers;
 use truvis_render_interface::bindless_manager::BindlessManager;
+use truvis_render_interface::camera_convention::CameraConvention;
+use truvis_render_interface::camera_history::CameraHistory;
 use truvis_render_interface::cmd_allocator::CmdAllocator;
 use truvis_render_interface::frame_counter::FrameCounter;
 use truvis_render_interface::gfx_resource_manager::GfxResourceManager;
 use truvis_render_interface::global_descriptor_sets::{GlobalDescriptorSets, PerFrameDescriptorBinding};
 use truvis_render_interface::gpu_scene::GpuScene;
 use truvis_render_interface::pipeline_settings::{
-    AccumData, DefaultRendererSettings, FrameLabel, FrameSettings, PipelineSettings,
+    AccumData, DefaultRendererSettings, FrameLabel, FrameSettings, PipelineSettings, QualityOverrides,
 };
 use truvis_render_interface::sampler_manager::RenderSamplerManager;
+use truvis_scene::frustum::Frustum;
 use truvis_scene::scene_manager::SceneManager;
 use truvis_shader_binding::truvisl;
 
@@ -40,32 +55,60 @@ use truvis_shader_binding::truvisl;
 ///
 /// # 渲染流程
 /// ```ignore
-/// renderer.begin_frame();        // 等待 GPU、清理资源
+/// renderer.begin_frame();        // 等待 GPU，热重载 shader，调用帧级子系统的 on_frame_begin
 /// // OuterApp::update() / OuterApp::draw()
 /// renderer.before_render();      // 更新相机、输入状态
 /// // 录制命令...
-/// renderer.end_frame();          // 提交命令、推进帧计数
+/// renderer.end_frame();          // 调用帧级子系统的 on_frame_end，推进帧计数
+/// renderer.wait_frame_limit();   // 按照帧率上限等待
 /// ```
+///
+/// 需要在帧边界做事的子系统通过 [`RenderContext::register_frame_subsystem`] 挂载，
+/// 参见 [`truvis_render_graph::frame_hooks`]
 pub struct Renderer {
     pub render_context: RenderContext,
 
     pub cmd_allocator: CmdAllocator,
 
     pub timer: Timer,
-    pub fif_timeline_semaphore: GfxSemaphore,
+    /// 帧率上限，在 end_frame 之后等待
+    pub frame_limiter: FrameLimiter,
+    /// 帧 timeline，第 `frame_id` 帧的渲染完成时 signal `frame_id`，所有多帧资源的回收都以它为准
+    pub fif_timeline: GfxTimeline,
+
+    /// 根据帧耗时自动调节质量，默认关闭
+    pub quality_governor: QualityGovernor,
 
     gpu_scene_update_cmds: Vec<GfxCommandBuffer>,
 
+    gpu_skinning: GpuSkinning,
+
     pub render_present: Option<RenderPresent>,
+
+    /// 监听 spv 的变化，debug 构建下开启；变化的 spv 在 begin_frame 中交给
+    /// [`GfxPipelineReloader`](truvis_gfx::pipelines::hot_reload::GfxPipelineReloader) 重建对应的 pipeline
+    shader_watcher: Option<GfxShaderWatcher>,
+
+    /// 导出器默认不启动，参见 [`RenderMetrics::start_prometheus_exporter`]
+    #[cfg(feature = "metrics")]
+    pub metrics: RenderMetrics,
 }
 
 // new & init
 impl Renderer {
-    pub fn new(extra_instance_ext: Vec<&'static CStr>) -> Self {
+    /// 每帧最多统计的 GPU 计时区间数量
+    const MAX_GPU_TIMER_SCOPE_CNT: u32 = 64;
+
+    /// `frames_in_flight` 为同时在 GPU 上执行的帧数，所有 per-frame 资源都按照这个数量分配，
+    /// 参见 [`FrameCounter::set_fif_count`]
+    pub fn new(extra_instance_ext: Vec<&'static CStr>, frames_in_flight: usize) -> Self {
         let _span = tracy_client::span!("Renderer::new");
 
+        // 需要在创建任何 per-frame 资源之前设置
+        let fif_count = FrameCounter::set_fif_count(frames_in_flight);
+
         // 初始化 RenderContext 单例
-        Gfx::init("Truvis".to_string(), extra_instance_ext);
+        Gfx::init("Truvis".to_string(), extra_instance_ext, Some(TruvisPath::pipeline_cache_path()));
 
         let frame_settings = FrameSettings {
             color_format: vk::Format::R32G32B32A32_SFLOAT,
@@ -74,18 +117,21 @@ impl Renderer {
                 width: 400,
                 height: 400,
             },
+            render_scale: 1.0,
+            camera_convention: CameraConvention::default(),
+            quality: QualityOverrides::default(),
         };
 
         let timer = Timer::default();
         let accum_data = AccumData::default();
-        let fif_timeline_semaphore = GfxSemaphore::new_timeline(0, "render-timeline");
+        let fif_timeline = GfxTimeline::new("render-timeline");
 
         let mut gfx_resource_manager = GfxResourceManager::new();
         let mut cmd_allocator = CmdAllocator::new();
 
         // 初始值应该是 1，因为 timeline semaphore 初始值是 0
         let init_frame_id = 1;
-        let frame_counter = FrameCounter::new(init_frame_id, 60.0);
+        let frame_counter = FrameCounter::new(init_frame_id, fif_count);
 
         let mut bindless_manager = BindlessManager::new();
         let scene_manager = SceneManager::new();
@@ -94,24 +140,37 @@ impl Renderer {
         let fif_buffers =
             FifBuffers::new(&frame_settings, &mut bindless_manager, &mut gfx_resource_manager, &frame_counter);
 
-        let render_descriptor_sets = GlobalDescriptorSets::new();
+        let render_descriptor_sets = GlobalDescriptorSets::new(bindless_manager.srv_capacity() as u32);
         let sampler_manager = RenderSamplerManager::new(&render_descriptor_sets);
+        let gpu_skinning = GpuSkinning::new(&render_descriptor_sets);
 
-        let per_frame_data_buffers = FrameCounter::frame_labes().map(|frame_label| {
-            GfxStructuredBuffer::<truvisl::PerFrameData>::new_ubo(1, format!("per-frame-data-buffer-{frame_label}"))
-        });
+        let per_frame_data_buffers = FrameCounter::frame_labes()
+            .map(|frame_label| {
+                GfxStructuredBuffer::<truvisl::PerFrameData>::new_ubo(1, format!("per-frame-data-buffer-{frame_label}"))
+            })
+            .collect();
 
         let cmds = FrameCounter::frame_labes()
-            .into_iter()
             .map(|frame_label| cmd_allocator.alloc_command_buffer(frame_label, "gpu-scene-update"))
             .collect();
 
-        Self {
+        let mut renderer = Self {
             cmd_allocator,
             timer,
-            fif_timeline_semaphore,
+            frame_limiter: FrameLimiter::default(),
+            fif_timeline,
+            quality_governor: QualityGovernor::default(),
             gpu_scene_update_cmds: cmds,
+            gpu_skinning,
             render_present: None,
+            shader_watcher: if cfg!(debug_assertions) {
+                GfxShaderWatcher::new(&TruvisPath::shader_build_root_path())
+            } else {
+                None
+            },
+
+            #[cfg(feature = "metrics")]
+            metrics: RenderMetrics::new(),
 
             render_context: RenderContext {
                 asset_hub,
@@ -126,13 +185,27 @@ impl Renderer {
 
                 delta_time_s: 0.0,
                 total_time_s: 0.0,
+                frame_frozen: false,
                 accum_data,
 
                 frame_counter,
                 frame_settings,
                 pipeline_settings: PipelineSettings::default(),
+                camera_frustum: Frustum::default(),
+                camera_pos: glam::Vec3::ZERO,
+                camera_history: CameraHistory::default(),
+                taa_resolve_available: false,
+                gpu_timer: GfxGpuTimer::new(fif_count, Self::MAX_GPU_TIMER_SCOPE_CNT, "gpu-timer"),
+                selected_instance: None,
+                highlight_material: None,
+                frame_hooks: FrameHooks::default(),
             },
-        }
+        };
+
+        renderer.render_context.register_frame_subsystem(FrameHookOrder::RESOURCE_CLEANUP, ResourceCleanup);
+        renderer.render_context.register_frame_subsystem(FrameHookOrder::ASSET_UPLOAD, AssetUpload);
+
+        renderer
     }
 
     pub fn init_after_window(
@@ -140,6 +213,7 @@ impl Renderer {
         raw_display_handle: RawDisplayHandle,
         raw_window_handle: RawWindowHandle,
         window_physical_size: [u32; 2],
+        present_modes: &[vk::PresentModeKHR],
     ) {
         self.render_present = Some(RenderPresent::new(
             &mut self.render_context.gfx_resource_manager,
@@ -149,6 +223,7 @@ impl Renderer {
                 width: window_physical_size[0],
                 height: window_physical_size[1],
             },
+            present_modes,
         ));
     }
 
@@ -177,12 +252,29 @@ impl Renderer {
         self.render_context.frame_counter.frame_label()
     }
 }
+// tools
+impl Renderer {
+    /// 将当前帧的 HDR 结果和 GBuffer 导出为多层 OpenEXR 文件
+    ///
+    /// 需要在当前帧的渲染命令提交之后、`end_frame` 之前调用
+    pub fn dump_frame_exr(&self, path: impl AsRef<Path>) {
+        if let Err(e) = ExrFrameDump::dump(&self.render_context, self.frame_label(), path.as_ref()) {
+            log::error!("failed to dump frame to {}: {}", path.as_ref().display(), e);
+        }
+    }
+}
 // destroy
 impl Renderer {
     pub fn destroy(mut self) {
         // 在 Renderer 被销毁时，等待 Gfx 设备空闲
         Gfx::get().wait_idel();
 
+        #[cfg(feature = "metrics")]
+        self.metrics.destroy();
+
+        // 子系统可能持有 GPU 资源
+        self.render_context.frame_hooks.clear();
+
         if let Some(render_present) = self.render_present.take() {
             render_present.destroy(&mut self.render_context.gfx_resource_manager);
         }
@@ -196,9 +288,10 @@ impl Renderer {
             .destroy(&mut self.render_context.gfx_resource_manager, &mut self.render_context.bindless_manager);
         self.render_context.bindless_manager.destroy();
         self.render_context.gpu_scene.destroy();
+        self.render_context.gpu_timer.destroy();
         self.cmd_allocator.destroy();
         self.render_context.gfx_resource_manager.destroy();
-        self.fif_timeline_semaphore.destroy();
+        self.fif_timeline.destroy();
         self.render_context.global_descriptor_sets.destroy();
     }
 }
@@ -213,30 +306,43 @@ impl Renderer {
             let _span = tracy_client::span!("wait fif timeline");
 
             let current_frame_id = self.render_context.frame_counter.frame_id();
-            let fif_count = FrameCounter::fif_count();
-            let wait_frame_id = current_frame_id.saturating_sub(fif_count as u64);
+            let frames_in_flight = self.render_context.frame_counter.frames_in_flight();
+            let wait_frame_id = current_frame_id.saturating_sub(frames_in_flight as u64);
             const WAIT_SEMAPHORE_TIMEOUT_NS: u64 = 30 * 1000 * 1000 * 1000; // 30s
-            self.fif_timeline_semaphore.wait_timeline(wait_frame_id, WAIT_SEMAPHORE_TIMEOUT_NS);
+            self.fif_timeline.wait(wait_frame_id, WAIT_SEMAPHORE_TIMEOUT_NS);
+
+            // 等待之后 GPU 可能已经完成了更多的帧，延迟回收的资源以实际完成值为准
+            let completed_frame_id = self.fif_timeline.completed_value();
+            self.render_context.frame_counter.set_completed_frame_id(completed_frame_id);
         }
 
-        // 清理 fif 资源
-        {
-            self.cmd_allocator.reset_frame_commands(self.render_context.frame_counter.frame_label());
-            self.render_context.gfx_resource_manager.cleanup(self.render_context.frame_counter.frame_id());
+        // shader 热重载：这一帧的命令还没有录制，pass 会直接使用重建之后的 pipeline
+        if let Some(shader_watcher) = &self.shader_watcher {
+            let changed_shaders = shader_watcher.poll_changed();
+            if !changed_shaders.is_empty() {
+                Gfx::get().pipeline_reloader().reload(&changed_shaders);
+            }
         }
 
+        // 重置 fif 的 command buffer
+        self.cmd_allocator.reset_frame_commands(&self.render_context.frame_counter);
+
+        // 这一个 fif 的 GPU 工作已经完成，可以读取上一次的计时结果
+        self.render_context.gpu_timer.begin_frame(*self.render_context.frame_counter.frame_label());
+
         self.render_context.delta_time_s = self.timer.delta_time_s();
         self.render_context.total_time_s = self.timer.total_time_s();
 
-        // Update AssetHub
-        self.render_context
-            .asset_hub
-            .update(&mut self.render_context.gfx_resource_manager, &mut self.render_context.bindless_manager);
+        self.render_context.dispatch_frame_begin();
     }
 
-    pub fn acquire_image(&mut self) {
+    /// 返回 swapchain 是否因为 out-of-date 被重建，参见 [`RenderPresent::acquire_image`]
+    pub fn acquire_image(&mut self) -> bool {
         // swapchain image
-        self.render_present.as_mut().unwrap().acquire_image(self.render_context.frame_counter.frame_label());
+        self.render_present.as_mut().unwrap().acquire_image(
+            self.render_context.frame_counter.frame_label(),
+            &mut self.render_context.gfx_resource_manager,
+        )
     }
 
     pub fn present_image(&mut self) {
@@ -246,19 +352,48 @@ impl Renderer {
     pub fn end_frame(&mut self) {
         let _span = tracy_client::span!("Renderer::end_frame");
 
+        self.render_context.dispatch_frame_end();
+
+        // 从 begin_frame 到这里的耗时包含了等待 GPU 的时间，不包含帧率限制带来的空闲
+        let frame_ms = self.timer.elapsed_since_tick().as_secs_f32() * 1000.0;
+        self.quality_governor.update(frame_ms);
+        self.render_context.frame_settings.quality = self.quality_governor.overrides();
+
+        #[cfg(feature = "metrics")]
+        {
+            self.metrics.update_frame(self.render_context.frame_counter.frame_id(), &self.timer);
+            // 计时结果在 begin_frame 中读回，对应的是 fif 数量之前的帧
+            self.metrics.record_gpu_pass_times(&self.render_context.gpu_timer.results());
+        }
+
         self.render_context.frame_counter.next_frame();
     }
 
-    pub fn time_to_render(&mut self) -> bool {
-        self.render_context.frame_counter.frame_delta_time_limit_us()
-            < self.timer.elapsed_since_tick().as_micros() as f32
+    /// 按照帧率上限等待到下一帧开始的时刻，在 end_frame 之后调用
+    pub fn wait_frame_limit(&self) {
+        self.frame_limiter.wait(self.timer.last_tick());
     }
 
     pub fn before_render(&mut self, camera: &Camera) {
         let _span = tracy_client::span!("Renderer::before_render");
         let current_camera_dir = glam::vec3(camera.euler_yaw_deg, camera.euler_pitch_deg, camera.euler_roll_deg);
 
-        self.render_context.accum_data.update_accum_frames(current_camera_dir, camera.position);
+        self.render_context.accum_data.update_accum_frames(
+            camera.position,
+            current_camera_dir,
+            self.render_context.scene_manager.generation(),
+            !self.timer.is_advancing(),
+        );
+        self.render_context.frame_frozen = self.render_context.accum_data.is_frozen();
+        self.render_context.frame_settings.camera_convention = camera.convention;
+        self.render_context.camera_frustum = camera.frustum();
+        self.render_context.camera_pos = camera.position;
+        self.render_context.camera_history.update(
+            camera.get_projection_matrix() * camera.get_view_matrix(),
+            self.render_context.pipeline_settings.taa.enabled && self.render_context.taa_resolve_available,
+        );
+        // 蒙皮会修改 BLAS，需要在构建 TLAS 之前完成
+        self.gpu_skinning.update(&mut self.render_context);
         self.update_gpu_scene(camera);
         self.update_perframe_descriptor_set();
     }
@@ -270,16 +405,17 @@ impl Renderer {
 
     pub fn update_frame_settings(&mut self) {
         let swapchain_extent = self.render_present.as_ref().unwrap().swapchain.as_ref().unwrap().extent();
-        if self.render_context.frame_settings.frame_extent == swapchain_extent {
-            return;
-        }
 
-        // 更新 frame settings
-        let extent = self.render_present.as_ref().unwrap().swapchain.as_ref().unwrap().extent();
+        // 渲染分辨率 = swapchain 分辨率 * render_scale * 质量调节的缩放，最终由 resolve 缩放到 swapchain 上
+        let frame_settings = &self.render_context.frame_settings;
+        let render_scale = frame_settings.render_scale * frame_settings.quality.render_scale_factor;
+        let extent = vk::Extent2D {
+            width: ((swapchain_extent.width as f32 * render_scale).round() as u32).max(1),
+            height: ((swapchain_extent.height as f32 * render_scale).round() as u32).max(1),
+        };
 
         // Renderer: Resize Framebuffer
         if self.render_context.frame_settings.frame_extent != extent {
-            self.render_context.frame_settings.frame_extent = extent;
             self.resize_frame_buffer(extent);
         }
     }
@@ -289,8 +425,9 @@ impl Renderer {
     }
 
     pub fn resize_frame_buffer(&mut self, new_extent: vk::Extent2D) {
-        let mut accum_data = self.render_context.accum_data;
-        accum_data.reset();
+        self.render_context.accum_data.reset();
+        // 重建之后的历史图像内容无效
+        self.render_context.camera_history.reset();
 
         unsafe {
             Gfx::get().gfx_device().device_wait_idle().unwrap();
@@ -326,8 +463,8 @@ impl Renderer {
 
         self.render_context.bindless_manager.prepare_render_data(
             &self.render_context.gfx_resource_manager,
-            &self.render_context.global_descriptor_sets,
-            frame_label,
+            &mut self.render_context.global_descriptor_sets,
+            &self.render_context.frame_counter,
         );
 
         self.render_context.gpu_scene.upload_render_data(
@@ -344,13 +481,19 @@ impl Renderer {
         // 准备好当前帧的数据
         let per_frame_data = {
             let view = camera.get_view_matrix();
-            let projection = camera.get_projection_matrix();
+            let camera_history = &self.render_context.camera_history;
+            let projection = CameraHistory::jittered_projection(
+                camera.get_projection_matrix(),
+                camera_history.jitter(),
+                glam::vec2(frame_extent.width as f32, frame_extent.height as f32),
+            );
 
             truvisl::PerFrameData {
                 projection: projection.into(),
                 view: view.into(),
                 inv_view: view.inverse().into(),
                 inv_projection: projection.inverse().into(),
+                prev_view_projection: camera_history.prev_view_projection().into(),
                 camera_pos: camera.position.into(),
                 camera_forward: camera.camera_forward().into(),
                 time_ms: self.timer.total_time_ms(),
@@ -361,9 +504,11 @@ impl Renderer {
                     y: frame_extent.height as f32,
                 },
                 accum_frames: self.render_context.accum_data.accum_frames_num() as u32,
-                _padding_0: Default::default(),
-                _padding_1: Default::default(),
+                ndc_y_sign: camera.convention.ndc_y_sign(),
+                is_orthographic: camera.projection_mode.is_orthographic() as u32,
                 _padding_2: Default::default(),
+                jitter: camera_history.jitter().into(),
+                _padding_3: Default::default(),
             }
         };
         let crt_frame_data_buffer = &self.render_context.per_frame_data_buffers[*frame_label];
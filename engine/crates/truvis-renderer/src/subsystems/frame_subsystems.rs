@@ -0,0 +1,35 @@
+//! 渲染器内置的帧级子系统，在 [`crate::renderer::Renderer::new`] 中注册
+
+use truvis_gfx::gfx::Gfx;
+use truvis_render_graph::frame_hooks::FrameSubsystem;
+use truvis_render_graph::render_context::RenderContext;
+
+/// 延迟销毁：释放 GPU 已经不再使用的资源
+///
+/// 帧开始时已经查询了帧 timeline 的完成值，最后使用的 timeline 值不大于它的资源可以安全释放
+pub struct ResourceCleanup;
+impl FrameSubsystem for ResourceCleanup {
+    fn name(&self) -> &str {
+        "resource-cleanup"
+    }
+
+    fn on_frame_begin(&mut self, render_context: &mut RenderContext) {
+        let completed_frame_id = render_context.frame_counter.completed_frame_id();
+        render_context.gfx_resource_manager.cleanup(completed_frame_id);
+    }
+}
+
+/// 资源上传：将异步加载完成的资源上传到 GPU，并注册到 bindless
+///
+/// 同时推进 transfer queue 上的异步上传，提交已完成拷贝的 acquire 命令
+pub struct AssetUpload;
+impl FrameSubsystem for AssetUpload {
+    fn name(&self) -> &str {
+        "asset-upload"
+    }
+
+    fn on_frame_begin(&mut self, render_context: &mut RenderContext) {
+        Gfx::get().async_transfer().update();
+        render_context.asset_hub.update(&mut render_context.gfx_resource_manager, &mut render_context.bindless_manager);
+    }
+}
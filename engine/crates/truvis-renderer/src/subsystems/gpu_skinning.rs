@@ -0,0 +1,125 @@
+use ash::vk;
+use truvis_crate_tools::resource::TruvisPath;
+use truvis_gfx::gfx::Gfx;
+use truvis_render_graph::compute_pass::ComputePass;
+use truvis_render_graph::render_context::RenderContext;
+use truvis_render_interface::global_descriptor_sets::GlobalDescriptorSets;
+use truvis_shader_binding::truvisl;
+
+/// GPU 蒙皮
+///
+/// 每帧在构建 TLAS 之前执行：
+/// 1. 推进所有蒙皮 instance 的动画，CPU 计算骨骼矩阵 palette
+/// 2. compute shader 将绑定姿态的顶点蒙皮后写入每个 instance 的输出 mesh
+/// 3. refit 输出 mesh 的 BLAS，并标记对应的 instance 为脏，使 TLAS 重新构建
+///
+/// 输出 mesh 和 BLAS 每个 instance 只有一份，不区分 fif：提交时之前帧的光栅化 / 光追可能仍在读取它们。
+/// 蒙皮和前面的帧提交到同一个 graphics queue，因此在覆盖之前用一个 barrier 等待之前提交的所有读取完成
+pub struct GpuSkinning {
+    skinning_pass: ComputePass<truvisl::skinning::PushConstant>,
+}
+// new & init
+impl GpuSkinning {
+    pub fn new(global_descriptor_sets: &GlobalDescriptorSets) -> Self {
+        let skinning_pass = ComputePass::<truvisl::skinning::PushConstant>::new(
+            global_descriptor_sets,
+            c"main",
+            TruvisPath::shader_build_path_str("skinning/skinning.slang").as_str(),
+        );
+
+        Self { skinning_pass }
+    }
+}
+// update
+impl GpuSkinning {
+    pub fn update(&self, render_context: &mut RenderContext) {
+        let _span = tracy_client::span!("GpuSkinning::update");
+        let skinned_instances = render_context.scene_manager.update_animations(render_context.delta_time_s);
+        if skinned_instances.is_empty() {
+            return;
+        }
+
+        let render_context_ref: &RenderContext = render_context;
+        let scene_manager = &render_context_ref.scene_manager;
+        Gfx::get().one_time_exec(
+            |cmd| {
+                // WAR：等待之前的帧对输出顶点与 BLAS 的读取结束之后再覆盖，只需要执行依赖
+                cmd.memory_barrier(std::slice::from_ref(&vk::MemoryBarrier2 {
+                    src_stage_mask: vk::PipelineStageFlags2::ALL_GRAPHICS
+                        | vk::PipelineStageFlags2::COMPUTE_SHADER
+                        | vk::PipelineStageFlags2::RAY_TRACING_SHADER_KHR
+                        | vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR,
+                    dst_stage_mask: vk::PipelineStageFlags2::COMPUTE_SHADER
+                        | vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR,
+                    ..Default::default()
+                }));
+
+                for &handle in &skinned_instances {
+                    let skinned_instance = &scene_manager.skinned_instance_map()[handle];
+                    let skinned_mesh = &scene_manager.skinned_mesh_map()[skinned_instance.skinned_mesh];
+                    let output_mesh = &scene_manager.mesh_map()[skinned_instance.output_mesh];
+
+                    for ((src, skin_buffer), dst) in
+                        skinned_mesh.geometries.iter().zip(&skinned_mesh.skin_buffers).zip(&output_mesh.geometries)
+                    {
+                        let vertex_cnt = src.vertex_buffer.vertex_cnt() as u32;
+                        self.skinning_pass.exec(
+                            cmd,
+                            render_context_ref,
+                            &truvisl::skinning::PushConstant {
+                                src_position: src.vertex_buffer.pos_address(),
+                                src_normal: src.vertex_buffer.normal_address(),
+                                src_tangent: src.vertex_buffer.tangent_address(),
+                                skin_vertices: skin_buffer.device_address(),
+                                joint_matrices: skinned_instance.palette_buffer.device_address(),
+                                dst_position: dst.vertex_buffer.pos_address(),
+                                dst_normal: dst.vertex_buffer.normal_address(),
+                                dst_tangent: dst.vertex_buffer.tangent_address(),
+                                vertex_count: vertex_cnt,
+                                _padding0: 0,
+                                _padding1: 0,
+                                _padding2: 0,
+                            },
+                            glam::uvec3(vertex_cnt.div_ceil(truvisl::skinning::SHADER_X as u32), 1, 1),
+                        );
+                    }
+                }
+
+                // 蒙皮结果会被 BLAS refit、光栅化以及光追着色读取
+                cmd.memory_barrier(std::slice::from_ref(&vk::MemoryBarrier2 {
+                    src_stage_mask: vk::PipelineStageFlags2::COMPUTE_SHADER,
+                    src_access_mask: vk::AccessFlags2::SHADER_STORAGE_WRITE,
+                    dst_stage_mask: vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR
+                        | vk::PipelineStageFlags2::VERTEX_ATTRIBUTE_INPUT
+                        | vk::PipelineStageFlags2::RAY_TRACING_SHADER_KHR,
+                    dst_access_mask: vk::AccessFlags2::SHADER_READ | vk::AccessFlags2::VERTEX_ATTRIBUTE_READ,
+                    ..Default::default()
+                }));
+
+                // 每个输出 mesh 有自己的 scratch buffer，refit 之间不需要同步
+                for &handle in &skinned_instances {
+                    let skinned_instance = &scene_manager.skinned_instance_map()[handle];
+                    scene_manager.mesh_map()[skinned_instance.output_mesh].cmd_refit_blas(cmd);
+                }
+
+                cmd.memory_barrier(std::slice::from_ref(&vk::MemoryBarrier2 {
+                    src_stage_mask: vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR,
+                    src_access_mask: vk::AccessFlags2::ACCELERATION_STRUCTURE_WRITE_KHR,
+                    dst_stage_mask: vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR
+                        | vk::PipelineStageFlags2::RAY_TRACING_SHADER_KHR,
+                    dst_access_mask: vk::AccessFlags2::ACCELERATION_STRUCTURE_READ_KHR,
+                    ..Default::default()
+                }));
+            },
+            "gpu-skinning",
+        );
+
+        for handle in skinned_instances {
+            let instance = render_context.scene_manager.skinned_instance_map()[handle].instance;
+            render_context.scene_manager.touch_instance(instance);
+        }
+
+        // 几何体发生了变化，之前累积的结果不再有效
+        render_context.accum_data.reset();
+    }
+}
@@ -1 +1,2 @@
-pub mod subsystem;
+pub mod frame_subsystems;
+pub mod gpu_skinning;
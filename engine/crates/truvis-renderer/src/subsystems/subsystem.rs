@@ -1,4 +0,0 @@
-/// 表示一个渲染子系统
-pub trait Subsystem {
-    fn before_render(&mut self);
-}
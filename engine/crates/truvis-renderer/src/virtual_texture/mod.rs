@@ -0,0 +1,271 @@
+//! 虚拟纹理
+//!
+//! 基于 [`GfxSparseImage`] 实现按需驻留的超大纹理（例如大世界的地形贴图）：
+//! 纹理被划分为 tile，[`TileScheduler`] 根据相机位置决定哪些 tile 需要常驻显存，
+//! 新驻留的 tile 通过 tile source 回调获取像素数据并上传。
+//!
+//! # 使用
+//! 每帧在录制渲染命令之前调用 [`VirtualTexture::update`]，shader 通过 [`VirtualTexture::srv_handle`] 采样；
+//! shader 只应该访问驻留的 tile，可以借助 [`TileScheduler`] 的驻留半径约束采样范围。
+//!
+//! # 同步
+//! - 绑定和上传都是阻塞完成的，之后提交的渲染命令一定能看到新的 tile
+//! - 换出的 tile 可能仍在被其他 fif 的命令访问，因此会等到帧 timeline 完成换出时的帧再解绑
+
+pub mod tile_scheduler;
+
+use ash::vk;
+use itertools::Itertools;
+use truvis_gfx::commands::barrier::GfxImageBarrier;
+use truvis_gfx::gfx::Gfx;
+use truvis_gfx::resources::buffer::GfxBuffer;
+use truvis_gfx::resources::image::{GfxImage, VulkanFormatUtils};
+use truvis_gfx::resources::image_view::GfxImageViewDesc;
+use truvis_gfx::resources::sparse_image::{GfxSparseImage, GfxSparseTile};
+use truvis_render_interface::bindless_manager::{BindlessManager, BindlessSrvHandle};
+use truvis_render_interface::frame_counter::FrameCounter;
+use truvis_render_interface::gfx_resource_manager::GfxResourceManager;
+use truvis_render_interface::handles::{GfxImageHandle, GfxImageViewHandle};
+
+use crate::virtual_texture::tile_scheduler::TileScheduler;
+
+/// 为新驻留的 tile 提供像素数据：紧密排列、逐行存放的一个 tile 大小的像素
+pub type TileSourceFn = Box<dyn FnMut(GfxSparseTile) -> Vec<u8>>;
+
+/// 创建 [`VirtualTexture`] 所需的参数
+pub struct VirtualTextureDesc {
+    /// 虚拟纹理的像素尺寸，需要是 tile 尺寸的整数倍
+    pub extent: vk::Extent2D,
+    pub format: vk::Format,
+
+    /// 纹理覆盖的 XZ 平面区域
+    pub world_min: glam::Vec2,
+    pub world_size: glam::Vec2,
+
+    /// 与相机距离在该半径内的 tile 需要驻留
+    pub residency_radius: f32,
+    /// 最多同时驻留的 tile 数量，决定了虚拟纹理占用的显存上限
+    pub max_resident_tiles: u32,
+}
+
+pub struct VirtualTexture {
+    sparse_image: GfxSparseImage,
+    scheduler: TileScheduler,
+    tile_source: TileSourceFn,
+
+    image_handle: GfxImageHandle,
+    view_handle: GfxImageViewHandle,
+
+    /// 已经换出、等待 GPU 不再访问后解绑的 tile，以及换出时的帧序号
+    pending_unbind: Vec<(GfxSparseTile, u64)>,
+
+    name: String,
+}
+
+// new & init
+impl VirtualTexture {
+    /// 设备不支持 sparse residency image 时返回 None
+    pub fn new(
+        desc: &VirtualTextureDesc,
+        tile_source: TileSourceFn,
+        gfx_resource_manager: &mut GfxResourceManager,
+        bindless_manager: &mut BindlessManager,
+        name: impl AsRef<str>,
+    ) -> Option<Self> {
+        let usage = vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST;
+        if !GfxSparseImage::is_supported(desc.format, usage) {
+            log::warn!("virtual texture {}: sparse residency image is not supported", name.as_ref());
+            return None;
+        }
+
+        let sparse_image = GfxSparseImage::new(desc.extent, desc.format, usage, desc.max_resident_tiles, name.as_ref());
+        let scheduler = TileScheduler::new(
+            desc.world_min,
+            desc.world_size,
+            sparse_image.tile_count(),
+            desc.residency_radius,
+            desc.max_resident_tiles as usize,
+        );
+
+        // 还没有任何 tile 驻留，先转换到 shader 可读的 layout，之后的上传都基于该 layout
+        Gfx::get().one_time_exec(
+            |cmd| {
+                cmd.image_memory_barrier(
+                    vk::DependencyFlags::empty(),
+                    &[GfxImageBarrier::new()
+                        .image(sparse_image.handle())
+                        .image_aspect_flag(vk::ImageAspectFlags::COLOR)
+                        .layout_transfer(vk::ImageLayout::UNDEFINED, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                        .src_mask(vk::PipelineStageFlags2::TOP_OF_PIPE, vk::AccessFlags2::NONE)
+                        .dst_mask(vk::PipelineStageFlags2::ALL_COMMANDS, vk::AccessFlags2::SHADER_READ)],
+                );
+            },
+            format!("virtual-texture-init-{}", name.as_ref()),
+        );
+
+        let image_handle = gfx_resource_manager.register_image(GfxImage::from_external(
+            sparse_image.handle(),
+            desc.extent.into(),
+            desc.format,
+            name.as_ref(),
+        ));
+        let view_handle = gfx_resource_manager.get_or_create_image_view(
+            image_handle,
+            GfxImageViewDesc::new_2d(desc.format, vk::ImageAspectFlags::COLOR),
+            name.as_ref(),
+        );
+        bindless_manager.register_srv(view_handle);
+
+        Some(Self {
+            sparse_image,
+            scheduler,
+            tile_source,
+            image_handle,
+            view_handle,
+            pending_unbind: Vec::new(),
+            name: name.as_ref().to_string(),
+        })
+    }
+}
+// destroy
+impl VirtualTexture {
+    /// 需要保证 GPU 已经不再使用该纹理
+    pub fn destroy(self, gfx_resource_manager: &mut GfxResourceManager, bindless_manager: &mut BindlessManager) {
+        bindless_manager.unregister_srv(self.view_handle);
+        // 注册的是 external image，这里只销毁 view，vk::Image 和显存由 sparse image 释放
+        gfx_resource_manager.destroy_image_immediate(self.image_handle);
+    }
+}
+// getter
+impl VirtualTexture {
+    #[inline]
+    pub fn srv_handle(&self, bindless_manager: &BindlessManager) -> BindlessSrvHandle {
+        bindless_manager.get_shader_srv_handle(self.view_handle)
+    }
+
+    #[inline]
+    pub fn sparse_image(&self) -> &GfxSparseImage {
+        &self.sparse_image
+    }
+
+    #[inline]
+    pub fn scheduler(&self) -> &TileScheduler {
+        &self.scheduler
+    }
+
+    /// 可以调整驻留半径等调度参数
+    #[inline]
+    pub fn scheduler_mut(&mut self) -> &mut TileScheduler {
+        &mut self.scheduler
+    }
+}
+// update
+impl VirtualTexture {
+    /// 根据相机位置换入换出 tile，需要在录制当前帧的渲染命令之前调用
+    pub fn update(&mut self, camera_pos: glam::Vec3, frame_counter: &FrameCounter) {
+        let _span = tracy_client::span!("VirtualTexture::update");
+        let frame_id = frame_counter.frame_id();
+
+        // 1. 换出 tile 的帧已经在 GPU 上完成，不会再访问，可以解绑并归还显存
+        let completed_frame_id = frame_counter.completed_frame_id();
+        let (expired, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending_unbind)
+            .into_iter()
+            .partition(|(_, unbind_frame)| *unbind_frame <= completed_frame_id);
+        self.pending_unbind = pending;
+        self.sparse_image.unbind_tiles(&expired.into_iter().map(|(tile, _)| tile).collect_vec());
+
+        // 2. 等待解绑的 tile 仍然占用着显存，作为已驻留的 tile 参与调度
+        let schedule = self.scheduler.schedule(camera_pos, self.sparse_image.resident_tiles());
+        // 重新需要的 tile 取消解绑
+        self.pending_unbind.retain(|(tile, _)| schedule.to_unbind.contains(tile));
+        for tile in schedule.to_unbind {
+            if !self.pending_unbind.iter().any(|(pending_tile, _)| *pending_tile == tile) {
+                self.pending_unbind.push((tile, frame_id));
+            }
+        }
+
+        // 3. 显存不足时只换入最近的一部分，其余的等解绑完成后再换入
+        let free_pages = self.sparse_image.max_resident_tiles() - self.sparse_image.resident_tiles().count();
+        let to_bind = schedule.to_bind.into_iter().take(free_pages).collect_vec();
+        if to_bind.is_empty() {
+            return;
+        }
+        self.sparse_image.bind_tiles(&to_bind);
+        self.upload_tiles(&to_bind);
+    }
+
+    /// 从 tile source 获取数据并上传到新驻留的 tile
+    fn upload_tiles(&mut self, tiles: &[GfxSparseTile]) {
+        let tile_extent = self.sparse_image.tile_extent();
+        let tile_bytes = (tile_extent.width * tile_extent.height) as usize
+            * VulkanFormatUtils::pixel_size_in_bytes(self.sparse_image.format());
+
+        let mut data = Vec::with_capacity(tile_bytes * tiles.len());
+        for &tile in tiles {
+            let tile_data = (self.tile_source)(tile);
+            assert_eq!(tile_data.len(), tile_bytes, "virtual texture {}: invalid tile data size", self.name);
+            data.extend_from_slice(&tile_data);
+        }
+
+        let stage_buffer =
+            GfxBuffer::new_stage_buffer(data.len() as vk::DeviceSize, format!("virtual-texture-stage-{}", self.name));
+        stage_buffer.transfer_data_by_mmap(&data);
+
+        let regions = tiles
+            .iter()
+            .enumerate()
+            .map(|(tile_idx, &tile)| {
+                let (offset, extent) = self.sparse_image.tile_region(tile);
+                vk::BufferImageCopy2::default()
+                    .buffer_offset((tile_idx * tile_bytes) as vk::DeviceSize)
+                    .image_offset(offset)
+                    .image_extent(extent)
+                    .image_subresource(vk::ImageSubresourceLayers {
+                        aspect_mask: vk::ImageAspectFlags::COLOR,
+                        mip_level: 0,
+                        base_array_layer: 0,
+                        layer_count: 1,
+                    })
+            })
+            .collect_vec();
+
+        let image = self.sparse_image.handle();
+        Gfx::get().one_time_exec(
+            |cmd| {
+                // 保留已驻留 tile 的内容，需要等待之前提交的命令读取完毕
+                cmd.image_memory_barrier(
+                    vk::DependencyFlags::empty(),
+                    &[GfxImageBarrier::new()
+                        .image(image)
+                        .image_aspect_flag(vk::ImageAspectFlags::COLOR)
+                        .layout_transfer(
+                            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                        )
+                        .src_mask(vk::PipelineStageFlags2::ALL_COMMANDS, vk::AccessFlags2::NONE)
+                        .dst_mask(vk::PipelineStageFlags2::TRANSFER, vk::AccessFlags2::TRANSFER_WRITE)],
+                );
+                cmd.cmd_copy_buffer_to_image(
+                    &vk::CopyBufferToImageInfo2::default()
+                        .src_buffer(stage_buffer.vk_buffer())
+                        .dst_image(image)
+                        .dst_image_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+                        .regions(&regions),
+                );
+                cmd.image_memory_barrier(
+                    vk::DependencyFlags::empty(),
+                    &[GfxImageBarrier::new()
+                        .image(image)
+                        .image_aspect_flag(vk::ImageAspectFlags::COLOR)
+                        .layout_transfer(
+                            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                        )
+                        .src_mask(vk::PipelineStageFlags2::TRANSFER, vk::AccessFlags2::TRANSFER_WRITE)
+                        .dst_mask(vk::PipelineStageFlags2::ALL_COMMANDS, vk::AccessFlags2::SHADER_READ)],
+                );
+            },
+            format!("virtual-texture-upload-{}", self.name),
+        );
+    }
+}
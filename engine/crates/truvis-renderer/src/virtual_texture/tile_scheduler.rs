@@ -0,0 +1,161 @@
+//! 虚拟纹理的 tile 调度
+//!
+//! 虚拟纹理铺在世界空间的 XZ 平面上（例如地形），每个 tile 对应平面上的一个矩形区域。
+//! 以相机到 tile 区域的水平距离近似可见性：距离在驻留半径内的 tile 需要驻留，
+//! 超出驻留半径但还在保留半径内的已驻留 tile 继续保留，避免相机在边界附近移动时反复换入换出。
+
+use std::collections::HashSet;
+
+use truvis_gfx::resources::sparse_image::GfxSparseTile;
+
+/// 一次调度的结果
+#[derive(Debug, Default, PartialEq)]
+pub struct TileSchedule {
+    /// 需要新驻留的 tile，按与相机的距离从近到远排列
+    pub to_bind: Vec<GfxSparseTile>,
+    /// 不再需要驻留的 tile
+    pub to_unbind: Vec<GfxSparseTile>,
+}
+
+pub struct TileScheduler {
+    /// 纹理覆盖的 XZ 平面区域的最小角
+    world_min: glam::Vec2,
+    /// 一个 tile 覆盖的世界空间尺寸
+    tile_world_size: glam::Vec2,
+    tile_count: (u32, u32),
+
+    /// 与相机距离在该半径内的 tile 需要驻留
+    pub residency_radius: f32,
+    /// 已驻留的 tile 在该半径内会继续保留，不小于 `residency_radius`
+    pub keep_radius: f32,
+    /// 最多同时驻留的 tile 数量
+    pub max_resident_tiles: usize,
+}
+// new & init
+impl TileScheduler {
+    /// 保留半径相对驻留半径的默认倍数
+    const DEFAULT_KEEP_RADIUS_SCALE: f32 = 1.25;
+
+    /// # Params
+    /// * `world_min`、`world_size` - 纹理在 XZ 平面上覆盖的区域
+    /// * `tile_count` - 横向（X）和纵向（Z）的 tile 数量
+    pub fn new(
+        world_min: glam::Vec2,
+        world_size: glam::Vec2,
+        tile_count: (u32, u32),
+        residency_radius: f32,
+        max_resident_tiles: usize,
+    ) -> Self {
+        Self {
+            world_min,
+            tile_world_size: world_size / glam::vec2(tile_count.0 as f32, tile_count.1 as f32),
+            tile_count,
+            residency_radius,
+            keep_radius: residency_radius * Self::DEFAULT_KEEP_RADIUS_SCALE,
+            max_resident_tiles,
+        }
+    }
+}
+// tools
+impl TileScheduler {
+    /// 相机在 XZ 平面上到 tile 区域的最近距离，相机位于 tile 上方时为 0
+    pub fn tile_distance(&self, tile: GfxSparseTile, camera_pos: glam::Vec3) -> f32 {
+        let tile_min = self.world_min + glam::vec2(tile.x as f32, tile.y as f32) * self.tile_world_size;
+        let tile_max = tile_min + self.tile_world_size;
+
+        let camera_xz = glam::vec2(camera_pos.x, camera_pos.z);
+        camera_xz.distance(camera_xz.clamp(tile_min, tile_max))
+    }
+
+    /// 根据相机位置和当前已驻留的 tile，计算需要换入和换出的 tile
+    ///
+    /// 结果保证换入换出之后驻留的 tile 数量不超过 `max_resident_tiles`，超出时优先保留离相机近的 tile
+    pub fn schedule(
+        &self,
+        camera_pos: glam::Vec3,
+        resident_tiles: impl IntoIterator<Item = GfxSparseTile>,
+    ) -> TileSchedule {
+        let resident_tiles: HashSet<GfxSparseTile> = resident_tiles.into_iter().collect();
+        let keep_radius = self.keep_radius.max(self.residency_radius);
+
+        let mut wanted = (0..self.tile_count.1)
+            .flat_map(|y| (0..self.tile_count.0).map(move |x| GfxSparseTile { x, y }))
+            .filter_map(|tile| {
+                let distance = self.tile_distance(tile, camera_pos);
+                let radius = if resident_tiles.contains(&tile) { keep_radius } else { self.residency_radius };
+                (distance <= radius).then_some((distance, tile))
+            })
+            .collect::<Vec<_>>();
+        // 距离相同时按 tile 坐标排序，保证结果稳定
+        wanted.sort_by(|(dist_a, tile_a), (dist_b, tile_b)| dist_a.total_cmp(dist_b).then(tile_a.cmp(tile_b)));
+        wanted.truncate(self.max_resident_tiles);
+
+        let wanted_set: HashSet<GfxSparseTile> = wanted.iter().map(|(_, tile)| *tile).collect();
+        let to_bind = wanted.iter().map(|(_, tile)| *tile).filter(|tile| !resident_tiles.contains(tile)).collect();
+        let mut to_unbind = resident_tiles.difference(&wanted_set).copied().collect::<Vec<_>>();
+        to_unbind.sort();
+
+        TileSchedule { to_bind, to_unbind }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 4x4 个 tile，每个 tile 10x10，覆盖 [0, 40] x [0, 40]
+    fn scheduler(residency_radius: f32, max_resident_tiles: usize) -> TileScheduler {
+        TileScheduler::new(glam::Vec2::ZERO, glam::vec2(40.0, 40.0), (4, 4), residency_radius, max_resident_tiles)
+    }
+
+    fn tile(x: u32, y: u32) -> GfxSparseTile {
+        GfxSparseTile { x, y }
+    }
+
+    #[test]
+    fn test_tile_distance() {
+        let scheduler = scheduler(0.0, 16);
+
+        // 相机在 tile 上方，与高度无关
+        assert_eq!(scheduler.tile_distance(tile(0, 0), glam::vec3(5.0, 100.0, 5.0)), 0.0);
+        // 相机在 tile 的 +X 方向
+        assert_eq!(scheduler.tile_distance(tile(0, 0), glam::vec3(15.0, 0.0, 5.0)), 5.0);
+        // 相机在 tile 的对角方向
+        assert_eq!(scheduler.tile_distance(tile(0, 0), glam::vec3(13.0, 0.0, 14.0)), 5.0);
+    }
+
+    #[test]
+    fn test_bind_tiles_in_radius_nearest_first() {
+        let scheduler = scheduler(1.0, 16);
+
+        // 相机位于 4 个 tile 的交点附近，偏向 (1, 1)
+        let schedule = scheduler.schedule(glam::vec3(10.5, 0.0, 10.5), []);
+        assert_eq!(schedule.to_bind, [tile(1, 1), tile(0, 1), tile(1, 0), tile(0, 0)]);
+        assert!(schedule.to_unbind.is_empty());
+    }
+
+    #[test]
+    fn test_budget_keeps_nearest() {
+        let scheduler = scheduler(1.0, 2);
+
+        let schedule = scheduler.schedule(glam::vec3(10.5, 0.0, 10.5), [tile(0, 0)]);
+        assert_eq!(schedule.to_bind, [tile(1, 1), tile(0, 1)]);
+        assert_eq!(schedule.to_unbind, [tile(0, 0)]);
+    }
+
+    #[test]
+    fn test_keep_radius_avoids_thrashing() {
+        let mut scheduler = scheduler(5.0, 16);
+        scheduler.keep_radius = 10.0;
+
+        // (0, 0) 距离 8：超出驻留半径，但已驻留且在保留半径内，继续保留；(3, 3) 超出保留半径，换出
+        let camera_pos = glam::vec3(18.0, 0.0, 5.0);
+        let schedule = scheduler.schedule(camera_pos, [tile(0, 0), tile(3, 3)]);
+        assert!(!schedule.to_bind.contains(&tile(0, 0)));
+        assert_eq!(schedule.to_unbind, [tile(3, 3)]);
+
+        // 没有驻留时，(0, 0) 不会被换入
+        let schedule = scheduler.schedule(camera_pos, []);
+        assert!(!schedule.to_bind.contains(&tile(0, 0)));
+    }
+}
@@ -0,0 +1,121 @@
+/// 轴对齐包围盒
+///
+/// 默认值为空包围盒（`min` 为 +inf，`max` 为 -inf），与任何包围盒求并都得到对方
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: glam::Vec3,
+    pub max: glam::Vec3,
+}
+impl Default for Aabb {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+// new & init
+impl Aabb {
+    pub const EMPTY: Self = Self {
+        min: glam::Vec3::INFINITY,
+        max: glam::Vec3::NEG_INFINITY,
+    };
+
+    #[inline]
+    pub fn new(min: glam::Vec3, max: glam::Vec3) -> Self {
+        Self { min, max }
+    }
+
+    /// 包含所有点的最小包围盒，没有点时为空包围盒
+    pub fn from_points(points: &[glam::Vec3]) -> Self {
+        points.iter().fold(Self::EMPTY, |aabb, p| Self::new(aabb.min.min(*p), aabb.max.max(*p)))
+    }
+}
+// getter
+impl Aabb {
+    /// 不包含任何点
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.min.cmpgt(self.max).any()
+    }
+
+    #[inline]
+    pub fn center(&self) -> glam::Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    /// 各个轴上的尺寸，即 `max - min`
+    #[inline]
+    pub fn extent(&self) -> glam::Vec3 {
+        self.max - self.min
+    }
+
+    /// 8 个角点，第 i 个角点在 x/y/z 轴上分别由 i 的第 0/1/2 位决定取 min 还是 max
+    pub fn corners(&self) -> [glam::Vec3; 8] {
+        std::array::from_fn(|i| {
+            glam::vec3(
+                if i & 1 == 0 { self.min.x } else { self.max.x },
+                if i & 2 == 0 { self.min.y } else { self.max.y },
+                if i & 4 == 0 { self.min.z } else { self.max.z },
+            )
+        })
+    }
+}
+// tools
+impl Aabb {
+    /// 同时包含两个包围盒的最小包围盒
+    #[inline]
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Self::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    /// 变换 8 个角点之后重新求包围盒，结果可能比变换后的几何体更松
+    pub fn transform(&self, transform: &glam::Mat4) -> Aabb {
+        if self.is_empty() {
+            return Self::EMPTY;
+        }
+        Self::from_points(&self.corners().map(|corner| transform.transform_point3(corner)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_points() {
+        let aabb = Aabb::from_points(&[glam::vec3(1.0, -2.0, 3.0), glam::vec3(-1.0, 4.0, 0.0), glam::Vec3::ZERO]);
+        assert_eq!(aabb, Aabb::new(glam::vec3(-1.0, -2.0, 0.0), glam::vec3(1.0, 4.0, 3.0)));
+        assert_eq!(aabb.center(), glam::vec3(0.0, 1.0, 1.5));
+        assert_eq!(aabb.extent(), glam::vec3(2.0, 6.0, 3.0));
+
+        assert!(Aabb::from_points(&[]).is_empty());
+        assert!(!Aabb::from_points(&[glam::Vec3::ONE]).is_empty());
+    }
+
+    #[test]
+    fn test_union() {
+        let a = Aabb::new(glam::Vec3::ZERO, glam::Vec3::ONE);
+        let b = Aabb::new(glam::Vec3::splat(-1.0), glam::Vec3::splat(0.5));
+        assert_eq!(a.union(&b), Aabb::new(glam::Vec3::splat(-1.0), glam::Vec3::ONE));
+
+        // 空包围盒不影响求并的结果
+        assert_eq!(a.union(&Aabb::EMPTY), a);
+        assert_eq!(Aabb::EMPTY.union(&a), a);
+    }
+
+    #[test]
+    fn test_transform() {
+        let aabb = Aabb::new(glam::vec3(-1.0, -2.0, -3.0), glam::vec3(1.0, 2.0, 3.0));
+
+        let translated = aabb.transform(&glam::Mat4::from_translation(glam::vec3(10.0, 0.0, 0.0)));
+        assert_eq!(translated, Aabb::new(glam::vec3(9.0, -2.0, -3.0), glam::vec3(11.0, 2.0, 3.0)));
+
+        // 绕 y 轴旋转 90 度，x 和 z 的范围互换
+        let rotated = aabb.transform(&glam::Mat4::from_rotation_y(std::f32::consts::FRAC_PI_2));
+        assert!(rotated.min.abs_diff_eq(glam::vec3(-3.0, -2.0, -1.0), 1e-5));
+        assert!(rotated.max.abs_diff_eq(glam::vec3(3.0, 2.0, 1.0), 1e-5));
+
+        let scaled = aabb.transform(&glam::Mat4::from_scale(glam::Vec3::splat(2.0)));
+        assert_eq!(scaled.extent(), aabb.extent() * 2.0);
+
+        assert!(Aabb::EMPTY.transform(&glam::Mat4::IDENTITY).is_empty());
+    }
+}
@@ -1,9 +1,22 @@
+use crate::aabb::Aabb;
+use crate::components::mesh::Mesh;
 use crate::guid_new_type::{MaterialHandle, MeshHandle};
 
 /// CPU 侧的 Instance 数据
 #[derive(Clone)]
 pub struct Instance {
     pub mesh: MeshHandle,
+    /// 按 mesh 中 geometry 的顺序排列，第 i 个材质用于第 i 个 geometry
     pub materials: Vec<MaterialHandle>,
     pub transform: glam::Mat4,
+    /// 场景文件中节点的名字，用于 UI 显示，可以为空
+    pub name: String,
+}
+// tools
+impl Instance {
+    /// 世界空间的包围盒，`mesh` 需要是 `self.mesh` 对应的 mesh
+    #[inline]
+    pub fn world_aabb(&self, mesh: &Mesh) -> Aabb {
+        mesh.local_aabb.transform(&self.transform)
+    }
 }
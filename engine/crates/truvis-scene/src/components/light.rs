@@ -0,0 +1,122 @@
+use truvis_shader_binding::truvisl;
+
+use crate::aabb::Aabb;
+
+/// 方向光，没有位置，以相同的方向照亮整个场景，例如太阳光
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DirectionalLight {
+    /// 光线传播的方向（从光源指向场景），不需要是单位向量
+    pub direction: glam::Vec3,
+    pub color: glam::Vec3,
+}
+impl Default for DirectionalLight {
+    fn default() -> Self {
+        Self {
+            direction: glam::vec3(-0.3, -1.0, -0.5),
+            color: glam::Vec3::ONE,
+        }
+    }
+}
+// tools
+impl DirectionalLight {
+    /// 单位化之后的光线方向，`direction` 为 0 时视为竖直向下
+    #[inline]
+    pub fn normalized_direction(&self) -> glam::Vec3 {
+        self.direction.try_normalize().unwrap_or(glam::Vec3::NEG_Y)
+    }
+
+    pub fn to_gpu(&self) -> truvisl::DirectionalLight {
+        truvisl::DirectionalLight {
+            direction: self.normalized_direction().into(),
+            color: self.color.into(),
+
+            _direction_padding: Default::default(),
+            _color_padding: Default::default(),
+        }
+    }
+
+    /// 渲染阴影贴图使用的 view-projection 矩阵，场景为空时返回 None
+    ///
+    /// 从光源方向看过去，使用正交投影恰好覆盖 `scene_aabb`。
+    /// 结果与相机的约定无关：右手系，NDC 的 Y 轴向上，深度范围为 [0, 1]，阴影贴图的 `uv = ndc.xy * 0.5 + 0.5`
+    pub fn shadow_view_projection(&self, scene_aabb: &Aabb) -> Option<glam::Mat4> {
+        if scene_aabb.is_empty() {
+            return None;
+        }
+
+        let direction = self.normalized_direction();
+        // 光线接近竖直时，换一个 up 方向避免 look_to 退化
+        let up = if direction.y.abs() > 0.99 { glam::Vec3::Z } else { glam::Vec3::Y };
+        let view = glam::Mat4::look_to_rh(scene_aabb.center(), direction, up);
+
+        // 在光源空间中重新求包围盒，并稍微扩大，避免扁平的场景导致投影退化
+        let light_space_aabb = scene_aabb.transform(&view);
+        let padding = glam::Vec3::splat(light_space_aabb.extent().max_element() * 0.01 + 1.0e-3);
+        let min = light_space_aabb.min - padding;
+        let max = light_space_aabb.max + padding;
+
+        // 右手系的 view space 看向 -Z
+        let projection = glam::Mat4::orthographic_rh(min.x, max.x, min.y, max.y, -max.z, -min.z);
+        Some(projection * view)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shadow_view_projection_covers_scene() {
+        let scene_aabb = Aabb::new(glam::vec3(-10.0, 0.0, -5.0), glam::vec3(10.0, 4.0, 5.0));
+
+        for direction in [
+            glam::vec3(-0.3, -1.0, -0.5),
+            glam::Vec3::NEG_Y,
+            glam::vec3(1.0, 0.0, 0.0),
+        ] {
+            let light = DirectionalLight {
+                direction,
+                ..Default::default()
+            };
+            let view_projection = light.shadow_view_projection(&scene_aabb).unwrap();
+
+            for corner in scene_aabb.corners() {
+                let ndc = view_projection.project_point3(corner);
+                assert!(ndc.x.abs() <= 1.0 && ndc.y.abs() <= 1.0, "{direction}: {corner} -> {ndc}");
+                assert!((0.0..=1.0).contains(&ndc.z), "{direction}: {corner} -> {ndc}");
+            }
+
+            // 沿光线方向越远，深度越大
+            let center = scene_aabb.center();
+            let near = view_projection.project_point3(center - light.normalized_direction());
+            let far = view_projection.project_point3(center + light.normalized_direction());
+            assert!(near.z < far.z, "{direction}");
+        }
+    }
+
+    #[test]
+    fn test_shadow_view_projection_flat_and_empty() {
+        // 与光线垂直的平面
+        let floor = Aabb::new(glam::vec3(-1.0, 0.0, -1.0), glam::vec3(1.0, 0.0, 1.0));
+        let view_projection = DirectionalLight::default().shadow_view_projection(&floor).unwrap();
+        assert!(view_projection.is_finite());
+
+        let down = DirectionalLight {
+            direction: glam::Vec3::NEG_Y,
+            ..Default::default()
+        };
+        let ndc = down.shadow_view_projection(&floor).unwrap().project_point3(glam::Vec3::ZERO);
+        assert!(ndc.is_finite() && (0.0..=1.0).contains(&ndc.z));
+
+        assert!(DirectionalLight::default().shadow_view_projection(&Aabb::EMPTY).is_none());
+    }
+
+    #[test]
+    fn test_zero_direction() {
+        let light = DirectionalLight {
+            direction: glam::Vec3::ZERO,
+            ..Default::default()
+        };
+        assert_eq!(light.normalized_direction(), glam::Vec3::NEG_Y);
+    }
+}
@@ -1,6 +1,31 @@
+use ash::vk;
+use truvis_shader_binding::truvisl;
+
+/// 光栅化时的面剔除方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CullMode {
+    /// 剔除背面，适用于封闭的物体
+    #[default]
+    Back,
+    /// 不剔除，用于树叶、布料等薄片的双面材质
+    None,
+}
+// tools
+impl CullMode {
+    #[inline]
+    pub fn vk_flags(self) -> vk::CullModeFlags {
+        match self {
+            Self::Back => vk::CullModeFlags::BACK,
+            Self::None => vk::CullModeFlags::NONE,
+        }
+    }
+}
+
 /// CPU 侧的材质数据
-#[derive(Default)]
 pub struct Material {
+    /// 模型文件中材质的名字，用于 UI 显示和搜索，可以为空
+    pub name: String,
+
     pub base_color: glam::Vec4,
     pub emissive: glam::Vec4,
     pub metallic: f32,
@@ -8,5 +33,105 @@ pub struct Material {
     pub opaque: f32,
 
     pub diffuse_map: String,
+    /// `diffuse_map` 的 alpha 通道是否表示透明度，对应 glTF 的 `alphaMode = BLEND`
+    pub diffuse_alpha: bool,
     pub normal_map: String,
+    /// 切线空间法线 xy 的缩放，用于整体增强或减弱法线贴图的效果，对应 glTF 的 normalTexture.scale
+    pub normal_scale: f32,
+
+    /// 叠加在 normal_map 之上的细节法线贴图，为空时不使用
+    pub detail_normal_map: String,
+    /// detail 法线贴图在 uv 上的平铺次数
+    pub detail_tiling: glam::Vec2,
+
+    /// G 通道为 roughness，B 通道为 metallic，分别与 `roughness`、`metallic` 相乘，为空时不使用
+    pub metallic_roughness_map: String,
+    /// 与 `emissive` 相乘，为空时不使用
+    pub emissive_map: String,
+    /// R 通道为环境光遮蔽，为空时不使用
+    pub occlusion_map: String,
+    /// 对应 glTF 的 occlusionTexture.strength
+    pub occlusion_strength: f32,
+    /// 采样以上贴图（detail 法线贴图除外）时使用的 sampler，对应 glTF texture 的 sampler
+    pub texture_sampler: truvisl::ESamplerType,
+
+    /// 光栅化时的面剔除方式，双面材质为 [`CullMode::None`]
+    pub cull_mode: CullMode,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+
+            base_color: glam::Vec4::ZERO,
+            emissive: glam::Vec4::ZERO,
+            metallic: 0.0,
+            roughness: 0.0,
+            opaque: 0.0,
+
+            diffuse_map: String::new(),
+            diffuse_alpha: false,
+            normal_map: String::new(),
+            normal_scale: 1.0,
+
+            detail_normal_map: String::new(),
+            detail_tiling: glam::Vec2::ONE,
+
+            metallic_roughness_map: String::new(),
+            emissive_map: String::new(),
+            occlusion_map: String::new(),
+            occlusion_strength: 1.0,
+            texture_sampler: truvisl::ESamplerType_LinearRepeat,
+
+            cull_mode: CullMode::Back,
+        }
+    }
+}
+// tools
+impl Material {
+    /// 是否为透明材质：`opaque` 小于 1，或者使用了带 alpha 的 diffuse 贴图
+    ///
+    /// 透明材质不写入深度，在不透明物体之后按从远到近的顺序混合绘制
+    #[inline]
+    pub fn is_transparent(&self) -> bool {
+        self.opaque < 1.0 || (self.diffuse_alpha && !self.diffuse_map.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_transparent() {
+        let opaque = Material {
+            opaque: 1.0,
+            ..Default::default()
+        };
+        assert!(!opaque.is_transparent());
+
+        assert!(
+            Material {
+                opaque: 0.5,
+                ..Default::default()
+            }
+            .is_transparent()
+        );
+
+        // 没有贴图时 diffuse_alpha 不起作用
+        let alpha_without_map = Material {
+            opaque: 1.0,
+            diffuse_alpha: true,
+            ..Default::default()
+        };
+        assert!(!alpha_without_map.is_transparent());
+        assert!(
+            Material {
+                diffuse_map: "leaf.png".to_string(),
+                ..alpha_without_map
+            }
+            .is_transparent()
+        );
+    }
 }
@@ -1,25 +1,63 @@
+use std::rc::Rc;
+
 use ash::vk;
 use itertools::Itertools;
-use truvis_gfx::raytracing::acceleration::GfxAcceleration;
+use truvis_gfx::commands::command_buffer::GfxCommandBuffer;
+use truvis_gfx::raytracing::acceleration::{GfxAcceleration, GfxUpdatableBlas};
+use truvis_gfx::resources::special_buffers::acceleration_buffer::GfxAccelerationTransformBuffer;
 use truvis_render_interface::geometry::RtGeometry;
+use truvis_render_interface::gpu_scene::helper;
+
+use crate::aabb::Aabb;
 
 /// CPU 侧的 Mesh 数据
 pub struct Mesh {
-    pub geometries: Vec<RtGeometry>,
+    /// geometry 可以被多个 Mesh 共享，例如被多个 node 以不同组合引用的同一份顶点数据
+    pub geometries: Vec<Rc<RtGeometry>>,
+
+    /// 每个 geometry 相对于 mesh 的变换，构建 BLAS 时作为 transform data，同时上传到 GPU 供着色使用
+    ///
+    /// 为 None 时表示顶点已经位于 mesh 空间；否则长度需要和 geometries 一致
+    pub geometry_transforms: Option<Vec<glam::Mat4>>,
+
+    /// mesh 空间的包围盒，包含所有 geometry（已经应用了 `geometry_transforms`）
+    pub local_aabb: Aabb,
 
     pub blas: Option<GfxAcceleration>,
+    /// 顶点会在 GPU 上被修改（例如蒙皮输出）时使用的可 refit BLAS，与 `blas` 互斥
+    pub dynamic_blas: Option<GfxUpdatableBlas>,
     pub name: String,
     pub blas_device_address: Option<vk::DeviceAddress>,
 }
 
 impl Mesh {
     pub fn build_blas(&mut self) {
-        if self.blas.is_some() {
+        if self.blas.is_some() || self.dynamic_blas.is_some() {
             return; // 已经构建过了
         }
 
-        let blas_infos = self.geometries.iter().map(|g| g.get_blas_geometry_info()).collect_vec();
-        let blas = GfxAcceleration::build_blas_sync(
+        // BLAS 是同步构建的，transform buffer 只需要存活到构建结束
+        let transform_buffer = self.geometry_transforms.as_ref().map(|transforms| {
+            assert_eq!(
+                transforms.len(),
+                self.geometries.len(),
+                "Mesh {}: geometry_transforms count mismatch",
+                self.name
+            );
+            let rt_transforms = transforms.iter().map(helper::get_rt_matrix).collect_vec();
+            GfxAccelerationTransformBuffer::new_with_data(&rt_transforms, format!("{}-geometry-transforms", self.name))
+        });
+
+        let blas_infos = self
+            .geometries
+            .iter()
+            .enumerate()
+            .map(|(idx, g)| match &transform_buffer {
+                Some(transform_buffer) => g.get_blas_geometry_info().with_transform(transform_buffer, idx),
+                None => g.get_blas_geometry_info(),
+            })
+            .collect_vec();
+        let blas = GfxAcceleration::build_blas_compacted(
             &blas_infos,
             vk::BuildAccelerationStructureFlagsKHR::empty(),
             format!("{}-Blas", self.name),
@@ -28,4 +66,33 @@ impl Mesh {
         self.blas_device_address = Some(blas.device_address());
         self.blas = Some(blas);
     }
+
+    /// 构建可以 refit 的 BLAS，之后顶点发生变化时通过 [`Self::cmd_refit_blas`] 更新
+    pub fn build_dynamic_blas(&mut self) {
+        if self.blas.is_some() || self.dynamic_blas.is_some() {
+            return; // 已经构建过了
+        }
+        assert!(
+            self.geometry_transforms.is_none(),
+            "Mesh {}: dynamic blas does not support geometry_transforms",
+            self.name
+        );
+
+        let blas_infos = self.geometries.iter().map(|g| g.get_blas_geometry_info()).collect_vec();
+        let blas = GfxUpdatableBlas::new_sync(&blas_infos, format!("{}-DynamicBlas", self.name));
+
+        self.blas_device_address = Some(blas.device_address());
+        self.dynamic_blas = Some(blas);
+    }
+
+    /// 录制 refit BLAS 的命令，只对 [`Self::build_dynamic_blas`] 构建的 BLAS 有效
+    pub fn cmd_refit_blas(&self, cmd: &GfxCommandBuffer) {
+        let Some(blas) = &self.dynamic_blas else {
+            log::warn!("Mesh {}: refit requires a dynamic blas", self.name);
+            return;
+        };
+
+        let blas_infos = self.geometries.iter().map(|g| g.get_blas_geometry_info()).collect_vec();
+        blas.cmd_refit(cmd, &blas_infos);
+    }
 }
@@ -1,3 +1,6 @@
 pub mod instance;
+pub mod light;
 pub mod material;
 pub mod mesh;
+pub mod skeleton;
+pub mod skin;
@@ -0,0 +1,292 @@
+use itertools::Itertools;
+
+/// 骨骼的局部变换（相对于父骨骼）
+#[derive(Copy, Clone, Debug)]
+pub struct JointPose {
+    pub translation: glam::Vec3,
+    pub rotation: glam::Quat,
+    pub scale: glam::Vec3,
+}
+impl Default for JointPose {
+    fn default() -> Self {
+        Self {
+            translation: glam::Vec3::ZERO,
+            rotation: glam::Quat::IDENTITY,
+            scale: glam::Vec3::ONE,
+        }
+    }
+}
+impl JointPose {
+    #[inline]
+    pub fn to_mat4(&self) -> glam::Mat4 {
+        glam::Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+    }
+}
+
+/// 单根骨骼
+#[derive(Clone, Debug)]
+pub struct Joint {
+    pub name: String,
+    /// 父骨骼的索引，根骨骼为 None；父骨骼的索引必须小于自身
+    pub parent: Option<usize>,
+    /// 绑定姿态下的局部变换，没有动画通道的骨骼使用该变换
+    pub bind_pose: JointPose,
+    /// 将 mesh 空间的顶点变换到骨骼空间
+    pub inverse_bind_matrix: glam::Mat4,
+}
+
+/// 骨骼层级
+///
+/// 骨骼按照「父骨骼在前」的顺序存放，这样可以顺序计算全局变换
+#[derive(Clone, Debug, Default)]
+pub struct Skeleton {
+    pub joints: Vec<Joint>,
+}
+// tools
+impl Skeleton {
+    #[inline]
+    pub fn joint_cnt(&self) -> usize {
+        self.joints.len()
+    }
+
+    /// 根据每根骨骼的局部变换计算蒙皮使用的矩阵 palette：`joint_global * inverse_bind`
+    pub fn compute_palette(&self, local_poses: &[JointPose], palette: &mut Vec<glam::Mat4>) {
+        assert_eq!(local_poses.len(), self.joints.len());
+
+        let mut globals: Vec<glam::Mat4> = Vec::with_capacity(self.joints.len());
+        for (joint_idx, (joint, pose)) in self.joints.iter().zip_eq(local_poses).enumerate() {
+            let global = match joint.parent {
+                Some(parent) => {
+                    assert!(parent < joint_idx, "Skeleton: parent joint must be stored before its children");
+                    globals[parent] * pose.to_mat4()
+                }
+                None => pose.to_mat4(),
+            };
+            globals.push(global);
+        }
+
+        palette.clear();
+        palette.extend(self.joints.iter().zip_eq(&globals).map(|(joint, global)| *global * joint.inverse_bind_matrix));
+    }
+
+    /// 绑定姿态下的局部变换
+    pub fn bind_poses(&self) -> Vec<JointPose> {
+        self.joints.iter().map(|joint| joint.bind_pose).collect()
+    }
+}
+
+/// 某根骨骼的关键帧，时间单位为秒，需要按时间升序排列
+#[derive(Clone, Debug, Default)]
+pub struct JointChannel {
+    pub joint: usize,
+    pub translations: Vec<(f32, glam::Vec3)>,
+    pub rotations: Vec<(f32, glam::Quat)>,
+    pub scales: Vec<(f32, glam::Vec3)>,
+}
+
+/// 骨骼动画片段，关键帧之间线性插值（旋转使用 slerp）
+#[derive(Clone, Debug, Default)]
+pub struct AnimationClip {
+    pub name: String,
+    /// 片段时长（秒）
+    pub duration: f32,
+    pub channels: Vec<JointChannel>,
+}
+// tools
+impl AnimationClip {
+    /// 采样 `time` 时刻的局部变换，没有关键帧的分量保持 `local_poses` 中原有的值
+    pub fn sample(&self, time: f32, local_poses: &mut [JointPose]) {
+        for channel in &self.channels {
+            let Some(pose) = local_poses.get_mut(channel.joint) else {
+                log::warn!("AnimationClip {}: joint {} out of range", self.name, channel.joint);
+                continue;
+            };
+
+            if let Some(translation) = Self::sample_keys(&channel.translations, time, glam::Vec3::lerp) {
+                pose.translation = translation;
+            }
+            if let Some(rotation) = Self::sample_keys(&channel.rotations, time, glam::Quat::slerp) {
+                pose.rotation = rotation;
+            }
+            if let Some(scale) = Self::sample_keys(&channel.scales, time, glam::Vec3::lerp) {
+                pose.scale = scale;
+            }
+        }
+    }
+
+    fn sample_keys<T: Copy>(keys: &[(f32, T)], time: f32, lerp: impl Fn(T, T, f32) -> T) -> Option<T> {
+        let (first, last) = (keys.first()?, keys.last()?);
+        if time <= first.0 {
+            return Some(first.1);
+        }
+        if time >= last.0 {
+            return Some(last.1);
+        }
+
+        // 第一个时间大于 time 的关键帧，前面已经排除了两端的情况
+        let next = keys.partition_point(|(key_time, _)| *key_time <= time);
+        let (t0, v0) = keys[next - 1];
+        let (t1, v1) = keys[next];
+        let factor = if t1 > t0 { (time - t0) / (t1 - t0) } else { 0.0 };
+        Some(lerp(v0, v1, factor))
+    }
+}
+
+/// 一个 instance 的动画播放状态
+#[derive(Copy, Clone, Debug)]
+pub struct AnimationState {
+    /// 正在播放的片段在 `SkinnedMesh::clips` 中的索引
+    pub clip: usize,
+    /// 当前播放时间（秒）
+    pub time: f32,
+    /// 播放速度倍率
+    pub speed: f32,
+    pub looping: bool,
+    pub playing: bool,
+}
+impl Default for AnimationState {
+    fn default() -> Self {
+        Self {
+            clip: 0,
+            time: 0.0,
+            speed: 1.0,
+            looping: true,
+            playing: true,
+        }
+    }
+}
+// update
+impl AnimationState {
+    /// 推进播放时间，返回时间是否发生了变化
+    pub fn advance(&mut self, delta_time_s: f32, clip_duration: f32) -> bool {
+        if !self.playing || self.speed == 0.0 {
+            return false;
+        }
+
+        let old_time = self.time;
+        self.time += delta_time_s * self.speed;
+        if clip_duration <= 0.0 {
+            self.time = 0.0;
+        } else if self.looping {
+            self.time = self.time.rem_euclid(clip_duration);
+        } else {
+            self.time = self.time.clamp(0.0, clip_duration);
+        }
+        self.time != old_time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn joint(parent: Option<usize>, bind_pose: JointPose, inverse_bind_matrix: glam::Mat4) -> Joint {
+        Joint {
+            name: String::new(),
+            parent,
+            bind_pose,
+            inverse_bind_matrix,
+        }
+    }
+
+    fn translation(x: f32) -> JointPose {
+        JointPose {
+            translation: glam::vec3(x, 0.0, 0.0),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sample_keys_interpolation() {
+        let keys = [(0.0, 0.0_f32), (1.0, 10.0), (3.0, 30.0)];
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+
+        // 两端之外保持端点的值
+        assert_eq!(AnimationClip::sample_keys(&keys, -1.0, lerp), Some(0.0));
+        assert_eq!(AnimationClip::sample_keys(&keys, 5.0, lerp), Some(30.0));
+
+        // 恰好落在关键帧上，以及两个关键帧之间
+        assert_eq!(AnimationClip::sample_keys(&keys, 1.0, lerp), Some(10.0));
+        assert_eq!(AnimationClip::sample_keys(&keys, 0.5, lerp), Some(5.0));
+        assert_eq!(AnimationClip::sample_keys(&keys, 2.0, lerp), Some(20.0));
+
+        assert_eq!(AnimationClip::sample_keys(&[], 0.5, lerp), None);
+        assert_eq!(AnimationClip::sample_keys(&[(1.0, 7.0)], 0.5, lerp), Some(7.0));
+    }
+
+    #[test]
+    fn test_sample_keeps_unanimated_components() {
+        let clip = AnimationClip {
+            name: "clip".to_string(),
+            duration: 1.0,
+            channels: vec![JointChannel {
+                joint: 0,
+                translations: vec![(0.0, glam::Vec3::ZERO), (1.0, glam::vec3(2.0, 0.0, 0.0))],
+                ..Default::default()
+            }],
+        };
+
+        let scale = glam::Vec3::splat(3.0);
+        let mut poses = [JointPose {
+            scale,
+            ..Default::default()
+        }];
+        clip.sample(0.5, &mut poses);
+        assert_eq!(poses[0].translation, glam::vec3(1.0, 0.0, 0.0));
+        assert_eq!(poses[0].scale, scale);
+    }
+
+    #[test]
+    fn test_compute_palette_composes_parents() {
+        // 两根骨骼的链：绑定姿态下父骨骼位于原点，子骨骼相对父骨骼平移 3
+        let skeleton = Skeleton {
+            joints: vec![
+                joint(None, translation(0.0), glam::Mat4::IDENTITY),
+                joint(Some(0), translation(3.0), glam::Mat4::from_translation(glam::vec3(-3.0, 0.0, 0.0))),
+            ],
+        };
+
+        // 绑定姿态下 palette 为单位矩阵（子骨骼的 inverse bind 抵消了全局平移）
+        let mut palette = Vec::new();
+        skeleton.compute_palette(&skeleton.bind_poses(), &mut palette);
+        assert_eq!(palette[0], glam::Mat4::IDENTITY);
+        assert_eq!(palette[1], glam::Mat4::IDENTITY);
+
+        // 父骨骼的变换会传递给子骨骼
+        skeleton.compute_palette(&[translation(1.0), translation(3.0)], &mut palette);
+        assert_eq!(palette.len(), 2);
+        assert_eq!(palette[0], glam::Mat4::from_translation(glam::vec3(1.0, 0.0, 0.0)));
+        assert_eq!(palette[1], glam::Mat4::from_translation(glam::vec3(1.0, 0.0, 0.0)));
+
+        // 子骨骼自身的变换不影响父骨骼
+        skeleton.compute_palette(&[translation(0.0), translation(5.0)], &mut palette);
+        assert_eq!(palette[0], glam::Mat4::IDENTITY);
+        assert_eq!(palette[1], glam::Mat4::from_translation(glam::vec3(2.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn test_advance() {
+        let mut state = AnimationState::default();
+        assert!(state.advance(1.5, 1.0));
+        assert_eq!(state.time, 0.5);
+
+        // 不循环时停在片段末尾，之后时间不再变化
+        state.looping = false;
+        assert!(state.advance(1.0, 1.0));
+        assert_eq!(state.time, 1.0);
+        assert!(!state.advance(1.0, 1.0));
+
+        // 倒放
+        state.looping = true;
+        state.speed = -1.0;
+        assert!(state.advance(0.25, 1.0));
+        assert_eq!(state.time, 0.75);
+
+        // 暂停或者速度为 0 时不推进
+        state.playing = false;
+        assert!(!state.advance(1.0, 1.0));
+        state.playing = true;
+        state.speed = 0.0;
+        assert!(!state.advance(1.0, 1.0));
+    }
+}
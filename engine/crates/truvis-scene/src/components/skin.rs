@@ -0,0 +1,316 @@
+use std::rc::Rc;
+
+use ash::vk;
+use itertools::Itertools;
+use truvis_gfx::gfx::Gfx;
+use truvis_gfx::resources::special_buffers::index_buffer::GfxIndex32Buffer;
+use truvis_gfx::resources::special_buffers::structured_buffer::GfxStructuredBuffer;
+use truvis_gfx::resources::special_buffers::vertex_buffer::GfxVertexBuffer;
+use truvis_render_interface::geometry::RtGeometry;
+use truvis_shader_binding::truvisl;
+
+use crate::aabb::Aabb;
+use crate::components::mesh::Mesh;
+use crate::components::skeleton::{AnimationClip, AnimationState, JointPose, Skeleton};
+use crate::guid_new_type::{InstanceHandle, MeshHandle, SkinnedMeshHandle};
+
+/// 蒙皮 mesh：绑定姿态的几何体 + 骨骼 + 动画片段
+///
+/// 可以被多个 [`SkinnedInstance`] 共享，每个 instance 拥有自己的动画状态与蒙皮输出
+pub struct SkinnedMesh {
+    /// 绑定姿态下的几何体，只作为蒙皮的输入，不直接参与渲染
+    pub geometries: Vec<RtGeometry>,
+    /// 每个顶点的骨骼索引与权重，与 `geometries` 一一对应
+    pub skin_buffers: Vec<GfxStructuredBuffer<truvisl::skinning::SkinVertex>>,
+
+    pub skeleton: Skeleton,
+    pub clips: Vec<AnimationClip>,
+
+    /// 绑定姿态下的包围盒
+    pub bind_pose_aabb: Aabb,
+    /// 所有动画片段播放过程中顶点可能到达的范围，参见 [`Self::animation_aabb`]，作为输出 mesh 的包围盒用于视锥剔除
+    pub animated_aabb: Aabb,
+
+    pub name: String,
+}
+// new & init
+impl SkinnedMesh {
+    /// # 参数
+    /// - `skin_vertices`: 每个 geometry 的顶点蒙皮数据，长度需要与 geometry 的顶点数一致
+    /// - `bind_pose_aabb`: 绑定姿态下所有 geometry 的包围盒
+    pub fn new(
+        geometries: Vec<RtGeometry>,
+        skin_vertices: &[Vec<truvisl::skinning::SkinVertex>],
+        skeleton: Skeleton,
+        clips: Vec<AnimationClip>,
+        bind_pose_aabb: Aabb,
+        name: impl AsRef<str>,
+    ) -> Self {
+        let name = name.as_ref();
+        let skin_buffers = geometries
+            .iter()
+            .zip_eq(skin_vertices)
+            .enumerate()
+            .map(|(geometry_idx, (geometry, skin_vertices))| {
+                assert_eq!(
+                    geometry.vertex_buffer.vertex_cnt(),
+                    skin_vertices.len(),
+                    "SkinnedMesh {name}: skin vertex count mismatch"
+                );
+                let skin_buffer = GfxStructuredBuffer::<truvisl::skinning::SkinVertex>::new_ssbo(
+                    skin_vertices.len(),
+                    format!("{name}-skin-{geometry_idx}"),
+                );
+                skin_buffer.transfer_data_sync(skin_vertices.as_slice());
+                skin_buffer
+            })
+            .collect_vec();
+
+        let animated_aabb = Self::animation_aabb(&bind_pose_aabb, &skeleton, &clips);
+        Self {
+            geometries,
+            skin_buffers,
+            skeleton,
+            clips,
+            bind_pose_aabb,
+            animated_aabb,
+            name: name.to_string(),
+        }
+    }
+}
+// tools
+impl SkinnedMesh {
+    /// 每个动画片段额外均匀采样的次数；关键帧之间旋转插值的中间姿态可能超出关键帧处的范围
+    const ANIMATION_AABB_SAMPLE_CNT: usize = 32;
+
+    /// 所有动画片段播放过程中顶点可能到达的范围，包含绑定姿态
+    ///
+    /// 线性混合蒙皮的顶点位于各骨骼矩阵变换结果的凸包内，因此在每个采样时刻，
+    /// 用每根骨骼的 palette 变换绑定姿态的包围盒后求并集。采样时刻为所有关键帧以及均匀分布的若干时刻，
+    /// 结果偏保守，只用于剔除
+    pub fn animation_aabb(bind_pose_aabb: &Aabb, skeleton: &Skeleton, clips: &[AnimationClip]) -> Aabb {
+        let mut aabb = *bind_pose_aabb;
+        if bind_pose_aabb.is_empty() {
+            return aabb;
+        }
+
+        let mut local_poses = skeleton.bind_poses();
+        let mut palette = Vec::with_capacity(skeleton.joint_cnt());
+        for clip in clips {
+            let key_times = clip.channels.iter().flat_map(|channel| {
+                let translation_times = channel.translations.iter().map(|(time, _)| *time);
+                let rotation_times = channel.rotations.iter().map(|(time, _)| *time);
+                let scale_times = channel.scales.iter().map(|(time, _)| *time);
+                translation_times.chain(rotation_times).chain(scale_times)
+            });
+            let uniform_times = (0..Self::ANIMATION_AABB_SAMPLE_CNT)
+                .map(|idx| clip.duration * idx as f32 / (Self::ANIMATION_AABB_SAMPLE_CNT - 1) as f32);
+
+            for time in key_times.chain(uniform_times) {
+                local_poses.clear();
+                local_poses.extend(skeleton.joints.iter().map(|joint| joint.bind_pose));
+                clip.sample(time, &mut local_poses);
+                skeleton.compute_palette(&local_poses, &mut palette);
+                aabb = palette.iter().fold(aabb, |aabb, matrix| aabb.union(&bind_pose_aabb.transform(matrix)));
+            }
+        }
+        aabb
+    }
+
+    /// 为一个 instance 创建蒙皮的输出 mesh
+    ///
+    /// 顶点和索引都从绑定姿态复制而来（uv 不会被蒙皮修改，只需要复制一次），
+    /// 并基于绑定姿态构建可以 refit 的 BLAS
+    pub fn create_output_mesh(&self, name: impl AsRef<str>) -> Mesh {
+        let name = name.as_ref();
+        let geometries = self
+            .geometries
+            .iter()
+            .enumerate()
+            .map(|(geometry_idx, src)| RtGeometry {
+                vertex_buffer: GfxVertexBuffer::new_device_local(
+                    src.vertex_buffer.vertex_cnt(),
+                    format!("{name}-skinned-vertex-{geometry_idx}"),
+                ),
+                index_buffer: GfxIndex32Buffer::new_device_local(
+                    src.index_buffer.index_cnt(),
+                    format!("{name}-skinned-index-{geometry_idx}"),
+                ),
+            })
+            .map(Rc::new)
+            .collect_vec();
+
+        Gfx::get().one_time_exec(
+            |cmd| {
+                for (src, dst) in self.geometries.iter().zip_eq(&geometries) {
+                    cmd.cmd_copy_buffer(
+                        &src.vertex_buffer,
+                        &dst.vertex_buffer,
+                        &[vk::BufferCopy::default().size(src.vertex_buffer.size())],
+                    );
+                    cmd.cmd_copy_buffer(
+                        &src.index_buffer,
+                        &dst.index_buffer,
+                        &[vk::BufferCopy::default().size(src.index_buffer.size())],
+                    );
+                }
+            },
+            "copy-skinned-mesh",
+        );
+
+        let mut mesh = Mesh {
+            geometries,
+            geometry_transforms: None,
+            local_aabb: self.animated_aabb,
+            blas: None,
+            dynamic_blas: None,
+            name: name.to_string(),
+            blas_device_address: None,
+        };
+        mesh.build_dynamic_blas();
+        mesh
+    }
+}
+
+/// 使用蒙皮 mesh 的 instance
+///
+/// 每个 instance 有自己的动画状态、骨骼矩阵 palette 以及蒙皮后的输出 mesh，
+/// 场景中实际参与渲染的是引用输出 mesh 的普通 [`crate::components::instance::Instance`]
+pub struct SkinnedInstance {
+    pub skinned_mesh: SkinnedMeshHandle,
+    /// 该 instance 专属的输出 mesh（蒙皮后的顶点 + 可以 refit 的 BLAS）
+    pub output_mesh: MeshHandle,
+    /// 场景中引用 `output_mesh` 的 instance
+    pub instance: InstanceHandle,
+
+    pub animation: AnimationState,
+
+    /// 骨骼矩阵 palette，host 可见，每次蒙皮前由 CPU 写入
+    pub palette_buffer: GfxStructuredBuffer<truvisl::Float4x4>,
+    /// 采样动画时使用的局部变换，避免每帧分配
+    pub(crate) local_poses: Vec<JointPose>,
+    pub(crate) palette: Vec<glam::Mat4>,
+    /// palette 是否需要重新计算；新建或修改了动画状态时为 true
+    pub(crate) dirty: bool,
+}
+// new & init
+impl SkinnedInstance {
+    pub(crate) fn new(
+        skinned_mesh_handle: SkinnedMeshHandle,
+        skinned_mesh: &SkinnedMesh,
+        output_mesh: MeshHandle,
+        instance: InstanceHandle,
+        animation: AnimationState,
+    ) -> Self {
+        let joint_cnt = skinned_mesh.skeleton.joint_cnt();
+        Self {
+            skinned_mesh: skinned_mesh_handle,
+            output_mesh,
+            instance,
+            animation,
+            palette_buffer: GfxStructuredBuffer::new(
+                format!("{}-palette", skinned_mesh.name),
+                joint_cnt.max(1),
+                vk::BufferUsageFlags::STORAGE_BUFFER | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+                true,
+            ),
+            local_poses: skinned_mesh.skeleton.bind_poses(),
+            palette: Vec::with_capacity(joint_cnt),
+            dirty: true,
+        }
+    }
+}
+// update
+impl SkinnedInstance {
+    /// 推进动画并在需要时重新计算 palette，返回 palette 是否发生了变化
+    pub(crate) fn update_palette(&mut self, skinned_mesh: &SkinnedMesh, delta_time_s: f32) -> bool {
+        let clip = skinned_mesh.clips.get(self.animation.clip);
+        let advanced = self.animation.advance(delta_time_s, clip.map_or(0.0, |clip| clip.duration));
+        if !advanced && !self.dirty {
+            return false;
+        }
+
+        self.local_poses.clear();
+        self.local_poses.extend(skinned_mesh.skeleton.joints.iter().map(|joint| joint.bind_pose));
+        if let Some(clip) = clip {
+            clip.sample(self.animation.time, &mut self.local_poses);
+        }
+        skinned_mesh.skeleton.compute_palette(&self.local_poses, &mut self.palette);
+
+        let palette_data = self.palette.iter().map(|matrix| truvisl::Float4x4::from(*matrix)).collect_vec();
+        self.palette_buffer.transfer_data_by_mmap(&palette_data);
+
+        self.dirty = false;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::skeleton::{Joint, JointChannel};
+
+    fn unit_box() -> Aabb {
+        Aabb::new(glam::Vec3::splat(-0.5), glam::Vec3::splat(0.5))
+    }
+
+    fn single_joint_skeleton() -> Skeleton {
+        Skeleton {
+            joints: vec![Joint {
+                name: "root".to_string(),
+                parent: None,
+                bind_pose: JointPose::default(),
+                inverse_bind_matrix: glam::Mat4::IDENTITY,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_animation_aabb_without_clips_is_bind_pose() {
+        let aabb = SkinnedMesh::animation_aabb(&unit_box(), &single_joint_skeleton(), &[]);
+        assert_eq!(aabb, unit_box());
+    }
+
+    #[test]
+    fn test_animation_aabb_covers_animation() {
+        let clip = AnimationClip {
+            name: "move".to_string(),
+            duration: 2.0,
+            channels: vec![JointChannel {
+                joint: 0,
+                translations: vec![
+                    (0.0, glam::Vec3::ZERO),
+                    (1.0, glam::vec3(10.0, 0.0, 0.0)),
+                    (2.0, glam::Vec3::ZERO),
+                ],
+                ..Default::default()
+            }],
+        };
+        let aabb = SkinnedMesh::animation_aabb(&unit_box(), &single_joint_skeleton(), &[clip]);
+
+        // 动画中途的位移超出了绑定姿态的包围盒
+        assert_eq!(aabb.min, glam::Vec3::splat(-0.5));
+        assert!((aabb.max - glam::vec3(10.5, 0.5, 0.5)).abs().max_element() < 1.0e-4);
+    }
+
+    #[test]
+    fn test_animation_aabb_covers_rotation_between_keys() {
+        // 两个关键帧之间绕 z 轴旋转 180°，只采样关键帧时包围盒不会覆盖中间的 90°
+        let clip = AnimationClip {
+            name: "spin".to_string(),
+            duration: 1.0,
+            channels: vec![JointChannel {
+                joint: 0,
+                rotations: vec![
+                    (0.0, glam::Quat::IDENTITY),
+                    (1.0, glam::Quat::from_rotation_z(std::f32::consts::PI * 0.999)),
+                ],
+                ..Default::default()
+            }],
+        };
+        let bar = Aabb::new(glam::vec3(0.0, -0.1, -0.1), glam::vec3(4.0, 0.1, 0.1));
+        let aabb = SkinnedMesh::animation_aabb(&bar, &single_joint_skeleton(), &[clip]);
+        assert!(aabb.max.y > 3.9);
+        assert!(aabb.min.x < -3.9);
+    }
+}
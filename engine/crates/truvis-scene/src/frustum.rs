@@ -0,0 +1,181 @@
+use truvis_render_interface::camera_convention::DepthRange;
+
+use crate::aabb::Aabb;
+
+/// 视锥体，由 6 个指向内侧的平面组成，用于 CPU 端的可见性剔除
+///
+/// 平面直接从 view-projection 矩阵中提取（Gribb-Hartmann），在裁剪空间中完成，因此：
+/// - 左右手系：view 与 projection 总是成对变化，裁剪空间的结果不变
+/// - NDC 的 Y 轴翻转：只会交换上下两个平面，平面的集合不变
+/// - 深度范围：影响近平面的形式，需要由调用者传入
+/// - 无穷远的远平面（`perspective_infinite_*`）：提取出的平面法线为 0，视为总是在内侧
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frustum {
+    /// `(normal, d)`，点 `p` 在平面内侧等价于 `dot(normal, p) + d >= 0`，normal 为单位向量
+    ///
+    /// 顺序为：left, right, bottom, top, near, far
+    pub planes: [glam::Vec4; 6],
+}
+impl Default for Frustum {
+    /// 不剔除任何物体的视锥体
+    fn default() -> Self {
+        Self::INFINITE
+    }
+}
+// new & init
+impl Frustum {
+    /// 所有平面都退化，不剔除任何物体
+    pub const INFINITE: Self = Self {
+        planes: [Self::DEGENERATE_PLANE; 6],
+    };
+
+    /// 法线为 0、距离为正的平面，任何点都在其内侧
+    const DEGENERATE_PLANE: glam::Vec4 = glam::Vec4::W;
+
+    /// 从世界空间到裁剪空间的矩阵（`projection * view`）中提取视锥体
+    pub fn from_view_projection(view_projection: &glam::Mat4, depth_range: DepthRange) -> Self {
+        let row0 = view_projection.row(0);
+        let row1 = view_projection.row(1);
+        let row2 = view_projection.row(2);
+        let row3 = view_projection.row(3);
+
+        let near = match depth_range {
+            // 0 <= z
+            DepthRange::ZeroToOne => row2,
+            // -w <= z
+            DepthRange::NegativeOneToOne => row3 + row2,
+        };
+
+        Self {
+            planes: [
+                row3 + row0, // left:   -w <= x
+                row3 - row0, // right:   x <= w
+                row3 + row1, // bottom: -w <= y
+                row3 - row1, // top:     y <= w
+                near,
+                row3 - row2, // far:     z <= w
+            ]
+            .map(Self::normalize_plane),
+        }
+    }
+
+    /// 法线长度接近 0 的平面（例如无穷远的远平面）退化为总是在内侧的平面
+    fn normalize_plane(plane: glam::Vec4) -> glam::Vec4 {
+        let normal_length = plane.truncate().length();
+        if normal_length <= f32::EPSILON * plane.w.abs().max(1.0) {
+            Self::DEGENERATE_PLANE
+        } else {
+            plane / normal_length
+        }
+    }
+}
+// tools
+impl Frustum {
+    /// 包围盒与视锥体是否可能相交
+    ///
+    /// 只检查包围盒是否完全位于某个平面的外侧，位于视锥体角落附近的包围盒可能被误判为相交，但不会被误剔除。
+    /// 空包围盒总是返回 false
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        if aabb.is_empty() {
+            return false;
+        }
+
+        self.planes.iter().all(|plane| {
+            // 沿法线方向最远的角点（p-vertex）都在外侧，整个包围盒就在外侧
+            let normal = plane.truncate();
+            let p_vertex = glam::Vec3::select(normal.cmpge(glam::Vec3::ZERO), aabb.max, aabb.min);
+            normal.dot(p_vertex) + plane.w >= 0.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_box_at(center: glam::Vec3) -> Aabb {
+        Aabb::new(center - glam::Vec3::splat(0.5), center + glam::Vec3::splat(0.5))
+    }
+
+    #[test]
+    fn test_perspective_infinite_far() {
+        // 相机位于原点，看向 -Z
+        let projection = glam::Mat4::perspective_infinite_rh(90f32.to_radians(), 1.0, 0.1);
+        let frustum = Frustum::from_view_projection(&projection, DepthRange::ZeroToOne);
+
+        // 远平面退化，非常远的物体也不会被剔除
+        assert_eq!(frustum.planes[5], glam::Vec4::W);
+        assert!(frustum.intersects_aabb(&unit_box_at(glam::vec3(0.0, 0.0, -5.0))));
+        assert!(frustum.intersects_aabb(&unit_box_at(glam::vec3(0.0, 0.0, -1.0e6))));
+
+        // 相机后方、两侧视野之外
+        assert!(!frustum.intersects_aabb(&unit_box_at(glam::vec3(0.0, 0.0, 5.0))));
+        assert!(!frustum.intersects_aabb(&unit_box_at(glam::vec3(20.0, 0.0, -5.0))));
+        assert!(!frustum.intersects_aabb(&unit_box_at(glam::vec3(0.0, -20.0, -5.0))));
+
+        // 与侧面相交
+        assert!(frustum.intersects_aabb(&unit_box_at(glam::vec3(5.4, 0.0, -5.0))));
+    }
+
+    #[test]
+    fn test_handedness_and_y_flip() {
+        let view_rh = glam::Mat4::look_to_rh(glam::vec3(0.0, 0.0, 10.0), glam::Vec3::NEG_Z, glam::Vec3::Y);
+        let projection_rh = glam::Mat4::perspective_infinite_rh(60f32.to_radians(), 1.5, 0.1);
+
+        // 左手系：ViewSpace 的 Z 轴翻转，投影矩阵随之变化
+        let view_lh = glam::Mat4::from_scale(glam::vec3(1.0, 1.0, -1.0)) * view_rh;
+        let projection_lh = glam::Mat4::perspective_infinite_lh(60f32.to_radians(), 1.5, 0.1);
+        // NDC 的 Y 轴向下
+        let y_flip = glam::Mat4::from_scale(glam::vec3(1.0, -1.0, 1.0));
+
+        let frustums = [
+            Frustum::from_view_projection(&(projection_rh * view_rh), DepthRange::ZeroToOne),
+            Frustum::from_view_projection(&(projection_lh * view_lh), DepthRange::ZeroToOne),
+            Frustum::from_view_projection(&(y_flip * projection_rh * view_rh), DepthRange::ZeroToOne),
+        ];
+
+        let cases = [
+            (glam::vec3(0.0, 0.0, 0.0), true),
+            (glam::vec3(0.0, 3.0, 0.0), true),
+            (glam::vec3(0.0, 0.0, 20.0), false),
+            (glam::vec3(0.0, 20.0, 0.0), false),
+            (glam::vec3(0.0, -20.0, 0.0), false),
+            (glam::vec3(-30.0, 0.0, 0.0), false),
+        ];
+        for frustum in &frustums {
+            for (center, expected) in cases {
+                assert_eq!(frustum.intersects_aabb(&unit_box_at(center)), expected, "center: {center}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_orthographic_and_depth_range() {
+        let projection = glam::Mat4::orthographic_rh(-2.0, 2.0, -1.0, 1.0, 1.0, 10.0);
+        let frustum = Frustum::from_view_projection(&projection, DepthRange::ZeroToOne);
+
+        assert!(frustum.intersects_aabb(&unit_box_at(glam::vec3(0.0, 0.0, -5.0))));
+        // near 之前、far 之后
+        assert!(!frustum.intersects_aabb(&unit_box_at(glam::vec3(0.0, 0.0, 0.0))));
+        assert!(!frustum.intersects_aabb(&unit_box_at(glam::vec3(0.0, 0.0, -11.0))));
+        assert!(!frustum.intersects_aabb(&unit_box_at(glam::vec3(3.0, 0.0, -5.0))));
+
+        // [-1, 1] 的深度范围：z' = 2z - w
+        let depth_remap = glam::Mat4::from_cols(
+            glam::Vec4::X,
+            glam::Vec4::Y,
+            glam::vec4(0.0, 0.0, 2.0, 0.0),
+            glam::vec4(0.0, 0.0, -1.0, 1.0),
+        );
+        let gl_frustum = Frustum::from_view_projection(&(depth_remap * projection), DepthRange::NegativeOneToOne);
+        for (plane, gl_plane) in frustum.planes.iter().zip(gl_frustum.planes.iter()) {
+            assert!(plane.abs_diff_eq(*gl_plane, 1e-5), "{plane} != {gl_plane}");
+        }
+    }
+
+    #[test]
+    fn test_empty_and_infinite() {
+        assert!(!Frustum::INFINITE.intersects_aabb(&Aabb::EMPTY));
+        assert!(Frustum::INFINITE.intersects_aabb(&unit_box_at(glam::vec3(1.0e6, -1.0e6, 1.0e6))));
+    }
+}
@@ -4,3 +4,6 @@ new_key_type! {pub struct MeshHandle;}
 new_key_type! {pub struct MaterialHandle;}
 new_key_type! {pub struct InstanceHandle;}
 new_key_type! {pub struct LightHandle;}
+new_key_type! {pub struct RectLightHandle;}
+new_key_type! {pub struct SkinnedMeshHandle;}
+new_key_type! {pub struct SkinnedInstanceHandle;}
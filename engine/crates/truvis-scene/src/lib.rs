@@ -1,4 +1,6 @@
+pub mod aabb;
 pub mod components;
+pub mod frustum;
 pub mod guid_new_type;
 pub mod scene_manager;
 pub mod shapes;
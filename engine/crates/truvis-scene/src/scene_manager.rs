@@ -1,15 +1,42 @@
+use crate::aabb::Aabb;
 use crate::components::instance::Instance;
+use crate::components::light::DirectionalLight;
 use crate::components::material::Material;
 use crate::components::mesh::Mesh;
-use crate::guid_new_type::{InstanceHandle, LightHandle, MaterialHandle, MeshHandle};
+use crate::components::skeleton::AnimationState;
+use crate::components::skin::{SkinnedInstance, SkinnedMesh};
+use crate::frustum::Frustum;
+use crate::guid_new_type::{
+    InstanceHandle, LightHandle, MaterialHandle, MeshHandle, RectLightHandle, SkinnedInstanceHandle, SkinnedMeshHandle,
+};
 use indexmap::IndexMap;
-use slotmap::SlotMap;
+use slotmap::{SecondaryMap, SlotMap};
+use std::cell::RefCell;
 use truvis_asset::asset_hub::AssetHub;
 use truvis_render_interface::bindless_manager::{BindlessManager, BindlessSrvHandle};
-use truvis_render_interface::render_data::{InstanceRenderData, MaterialRenderData, MeshRenderData, RenderData};
+use truvis_render_interface::render_data::{
+    DrawItem, InstanceBatch, InstanceBatches, InstanceRenderData, MaterialRenderData, MeshRenderData, RenderData,
+};
 use truvis_shader_binding::truvisl;
 
 /// 在 CPU 侧管理场景数据
+///
+/// # 脏标记
+/// 场景内部维护一个单调递增的修改计数 `generation`，每次修改都会使其加一：
+/// - instance transform、material、point light、rect light 的修改只会更新对应元素的 generation
+/// - 增加或删除元素属于结构变化，会更新 `structure_generation`，此时 GPU 侧需要全量上传
+///
+/// GpuScene 为每个 fif buffer 记录已上传到的 generation，只上传比它更新的元素，
+/// 因此静止场景不会产生任何上传。
+///
+/// # 合批
+/// 使用同一个 mesh 的 instance 会被合并为一个批次，参见 [`Self::instance_batches`]。
+/// mesh 到 instance 的映射只依赖场景结构，在结构变化之后的第一次查询时重建。
+///
+/// # 蒙皮
+/// 蒙皮 mesh 本身不参与渲染；每个蒙皮 instance 注册时会创建一个专属的输出 mesh 和引用它的普通 instance。
+/// 动画每帧推进后，由 GPU 蒙皮写入输出 mesh 的顶点并 refit BLAS，然后通过 [`Self::touch_instance`]
+/// 标记对应的 instance 为脏，使 TLAS 得到更新。
 #[derive(Default)]
 pub struct SceneManager {
     all_mats: SlotMap<MaterialHandle, Material>,
@@ -17,6 +44,34 @@ pub struct SceneManager {
     all_meshes: SlotMap<MeshHandle, Mesh>,
 
     all_point_lights: SlotMap<LightHandle, truvisl::PointLight>,
+    all_rect_lights: SlotMap<RectLightHandle, truvisl::RectLight>,
+    /// 场景中最多只有一个方向光
+    directional_light: Option<DirectionalLight>,
+
+    all_skinned_meshes: SlotMap<SkinnedMeshHandle, SkinnedMesh>,
+    all_skinned_instances: SlotMap<SkinnedInstanceHandle, SkinnedInstance>,
+
+    /// 场景的修改计数，每次修改都会加一
+    generation: u64,
+    /// 最近一次结构变化时的 generation
+    structure_generation: u64,
+    /// 每个元素最近一次被修改时的 generation
+    mat_generations: SecondaryMap<MaterialHandle, u64>,
+    instance_generations: SecondaryMap<InstanceHandle, u64>,
+    point_light_generations: SecondaryMap<LightHandle, u64>,
+    rect_light_generations: SecondaryMap<RectLightHandle, u64>,
+
+    /// 按 mesh 分组的 instance，在 [`Self::instance_batches`] 中按需重建
+    instance_batch_cache: RefCell<InstanceBatchCache>,
+}
+
+/// mesh 到 instance 的映射，只依赖场景结构
+#[derive(Default)]
+struct InstanceBatchCache {
+    /// 构建时场景的结构 generation，None 表示还没有构建
+    structure_generation: Option<u64>,
+    /// (mesh 序号, 使用该 mesh 的 instance 序号)，序号与 [`SceneManager::prepare_render_data`] 的结果一致，均为升序
+    mesh_instances: Vec<(usize, Vec<u32>)>,
 }
 // new & init
 impl SceneManager {
@@ -43,11 +98,34 @@ impl SceneManager {
         &self.all_point_lights
     }
     #[inline]
+    pub fn rect_light_map(&self) -> &SlotMap<RectLightHandle, truvisl::RectLight> {
+        &self.all_rect_lights
+    }
+
+    #[inline]
+    pub fn directional_light(&self) -> Option<&DirectionalLight> {
+        self.directional_light.as_ref()
+    }
+    #[inline]
+    pub fn skinned_mesh_map(&self) -> &SlotMap<SkinnedMeshHandle, SkinnedMesh> {
+        &self.all_skinned_meshes
+    }
+    #[inline]
+    pub fn skinned_instance_map(&self) -> &SlotMap<SkinnedInstanceHandle, SkinnedInstance> {
+        &self.all_skinned_instances
+    }
+    /// 场景的修改计数，任何修改都会使其增加
+    #[inline]
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+    #[inline]
     pub fn is_empty(&self) -> bool {
         self.all_instances.is_empty()
             && self.all_meshes.is_empty()
             && self.all_mats.is_empty()
             && self.all_point_lights.is_empty()
+            && self.all_rect_lights.is_empty()
     }
 
     /// 构建完整的场景数据快照（SceneData2）
@@ -67,7 +145,11 @@ impl SceneManager {
         asset_hub: &AssetHub,
     ) -> RenderData<'a> {
         if self.is_empty() {
-            return RenderData::empty();
+            return RenderData {
+                generation: self.generation,
+                structure_generation: self.structure_generation,
+                ..RenderData::empty()
+            };
         }
 
         // 1. 构建 mesh handle -> index 映射，以及 mesh 数据
@@ -84,6 +166,7 @@ impl SceneManager {
 
             all_meshes.push(MeshRenderData {
                 geometries: &mesh.geometries,
+                geometry_transforms: mesh.geometry_transforms.as_deref(),
                 blas_device_address: mesh.blas_device_address,
                 name: &mesh.name,
             });
@@ -93,6 +176,17 @@ impl SceneManager {
         let mut mat_handle_to_index: IndexMap<MaterialHandle, usize> = IndexMap::new();
         let mut all_materials: Vec<MaterialRenderData> = Vec::with_capacity(self.all_mats.len());
 
+        // 存放数据而非颜色的贴图在加载完成之前不使用，避免 fallback 纹理被当作法线、粗糙度或者自发光
+        let ready_srv_handle = |tex_path: &str| {
+            if tex_path.is_empty() {
+                return BindlessSrvHandle::null();
+            }
+            asset_hub
+                .get_ready_texture_by_path(std::path::Path::new(tex_path))
+                .and_then(|asset_texture| bindless_manager.validate_srv_handle(asset_texture.srv_handle))
+                .unwrap_or_else(BindlessSrvHandle::null)
+        };
+
         for (handle, mat) in self.all_mats.iter() {
             let index = all_materials.len();
             mat_handle_to_index.insert(handle, index);
@@ -100,13 +194,16 @@ impl SceneManager {
             // 获取漫反射贴图的 bindless handle
             let diffuse_bindless_handle = if !mat.diffuse_map.is_empty() {
                 let asset_texture = asset_hub.get_texture_by_path(std::path::Path::new(&mat.diffuse_map));
-                bindless_manager.get_shader_srv_handle(asset_texture.view_handle)
+                bindless_manager.validate_srv_handle(asset_texture.srv_handle).unwrap_or_else(BindlessSrvHandle::null)
             } else {
                 BindlessSrvHandle::null()
             };
 
-            // 暂不支持法线贴图
-            let normal_bindless_handle = BindlessSrvHandle::null();
+            let normal_bindless_handle = ready_srv_handle(&mat.normal_map);
+            let detail_normal_bindless_handle = ready_srv_handle(&mat.detail_normal_map);
+            let metallic_roughness_bindless_handle = ready_srv_handle(&mat.metallic_roughness_map);
+            let emissive_bindless_handle = ready_srv_handle(&mat.emissive_map);
+            let occlusion_bindless_handle = ready_srv_handle(&mat.occlusion_map);
 
             all_materials.push(MaterialRenderData {
                 base_color: mat.base_color,
@@ -114,15 +211,26 @@ impl SceneManager {
                 metallic: mat.metallic,
                 roughness: mat.roughness,
                 opaque: mat.opaque,
+                transparent: mat.is_transparent(),
                 diffuse_bindless_handle,
                 normal_bindless_handle,
+                normal_scale: mat.normal_scale,
+                detail_normal_bindless_handle,
+                detail_tiling: mat.detail_tiling,
+                metallic_roughness_bindless_handle,
+                emissive_bindless_handle,
+                occlusion_bindless_handle,
+                occlusion_strength: mat.occlusion_strength,
+                texture_sampler: mat.texture_sampler,
+                cull_mode: mat.cull_mode.vk_flags(),
+                generation: self.mat_generations[handle],
             });
         }
 
         // 3. 构建 instance 数据
         let mut all_instances: Vec<InstanceRenderData> = Vec::with_capacity(self.all_instances.len());
 
-        for (_handle, instance) in self.all_instances.iter() {
+        for (handle, instance) in self.all_instances.iter() {
             let mesh_index = *mesh_handle_to_index.get(&instance.mesh).expect("Mesh not found for instance");
             let material_indices: Vec<usize> = instance
                 .materials
@@ -134,20 +242,41 @@ impl SceneManager {
                 mesh_index,
                 material_indices,
                 transform: instance.transform,
+                generation: self.instance_generations[handle],
             });
         }
 
         // 4. 构建点光源数据
         let all_point_lights: Vec<truvisl::PointLight> =
             self.all_point_lights.iter().map(|(_, light)| *light).collect();
+        let point_light_generations: Vec<u64> =
+            self.all_point_lights.keys().map(|handle| self.point_light_generations[handle]).collect();
+
+        // 5. 构建面光源数据
+        let all_rect_lights: Vec<truvisl::RectLight> = self.all_rect_lights.iter().map(|(_, light)| *light).collect();
+        let rect_light_generations: Vec<u64> =
+            self.all_rect_lights.keys().map(|handle| self.rect_light_generations[handle]).collect();
+
+        // 6. 方向光，阴影贴图覆盖整个场景
+        let directional_light_view_proj = self
+            .directional_light
+            .and_then(|light| light.shadow_view_projection(&self.scene_aabb()))
+            .unwrap_or(glam::Mat4::IDENTITY);
 
         RenderData {
             all_instances,
             all_meshes,
             all_materials,
             all_point_lights,
+            point_light_generations,
+            all_rect_lights,
+            rect_light_generations,
+            directional_light: self.directional_light.map(|light| light.to_gpu()),
+            directional_light_view_proj,
             mesh_geometry_start_indices,
             total_geometry_count,
+            generation: self.generation,
+            structure_generation: self.structure_generation,
         }
     }
 }
@@ -168,24 +297,357 @@ impl SceneManager {
         self.all_mats.get(handle)
     }
 
+    /// 世界空间中所有 instance 的包围盒，可以用于相机自动对准整个场景；场景为空时为空包围盒
+    ///
+    /// 蒙皮 instance 使用的是覆盖所有动画片段的包围盒，参见 [`SkinnedMesh::animation_aabb`]
+    pub fn scene_aabb(&self) -> Aabb {
+        self.all_instances
+            .values()
+            .filter_map(|instance| Some(instance.world_aabb(self.all_meshes.get(instance.mesh)?)))
+            .fold(Aabb::EMPTY, |scene_aabb, aabb| scene_aabb.union(&aabb))
+    }
+
+    /// 与视锥体相交的 instance 在 [`Self::prepare_render_data`] 结果中的序号，按升序排列，用于光栅化的 draw list
+    ///
+    /// 光追仍然需要所有的 instance，不能使用这里的结果。没有包围盒的 instance 保守地视为可见；
+    /// 蒙皮 instance 使用的是覆盖所有动画片段的包围盒，不会因为动画而被误剔除
+    pub fn visible_instances(&self, frustum: &Frustum) -> Vec<u32> {
+        self.all_instances
+            .values()
+            .enumerate()
+            .filter(|(_, instance)| {
+                let Some(mesh) = self.all_meshes.get(instance.mesh) else {
+                    return true;
+                };
+                mesh.local_aabb.is_empty() || frustum.intersects_aabb(&instance.world_aabb(mesh))
+            })
+            .map(|(instance_idx, _)| instance_idx as u32)
+            .collect()
+    }
+
+    /// 所有 instance 的 handle，第 i 个对应 [`Self::prepare_render_data`] 结果中序号为 i 的 instance
+    ///
+    /// 用于将 GPU 上的 instance 序号（例如鼠标拾取的结果）映射回 handle，场景结构变化之后序号会改变
+    pub fn instance_handles(&self) -> Vec<InstanceHandle> {
+        self.all_instances.keys().collect()
+    }
+
+    /// instance 在世界空间中的包围盒，mesh 不存在时返回 None
+    pub fn instance_world_aabb(&self, handle: InstanceHandle) -> Option<Aabb> {
+        let instance = self.all_instances.get(handle)?;
+        Some(instance.world_aabb(self.all_meshes.get(instance.mesh)?))
+    }
+
+    /// 至少有一个 geometry 使用 `material` 的所有 instance
+    pub fn instances_with_material(&self, material: MaterialHandle) -> Vec<InstanceHandle> {
+        self.all_instances
+            .iter()
+            .filter(|(_, instance)| instance.materials.contains(&material))
+            .map(|(handle, _)| handle)
+            .collect()
+    }
+
+    /// 将 `instance_indices` 中的 instance 按 mesh 合批，序号与 [`Self::prepare_render_data`] 的结果一致
+    ///
+    /// `instance_indices` 通常是 [`Self::visible_instances`] 的结果；批次按 mesh 的顺序排列，
+    /// 批次内的 instance 保持升序。场景结构变化之后会重建 mesh 到 instance 的映射
+    pub fn instance_batches(&self, instance_indices: &[u32]) -> InstanceBatches {
+        let mut cache = self.instance_batch_cache.borrow_mut();
+        if cache.structure_generation != Some(self.structure_generation) {
+            let mesh_handle_to_index: IndexMap<MeshHandle, usize> =
+                self.all_meshes.keys().enumerate().map(|(index, handle)| (handle, index)).collect();
+            let mut mesh_instances = vec![vec![]; mesh_handle_to_index.len()];
+            for (instance_idx, instance) in self.all_instances.values().enumerate() {
+                if let Some(&mesh_index) = mesh_handle_to_index.get(&instance.mesh) {
+                    mesh_instances[mesh_index].push(instance_idx as u32);
+                }
+            }
+
+            cache.mesh_instances =
+                mesh_instances.into_iter().enumerate().filter(|(_, instances)| !instances.is_empty()).collect();
+            cache.structure_generation = Some(self.structure_generation);
+        }
+
+        let mut selected = vec![false; self.all_instances.len()];
+        for &instance_idx in instance_indices {
+            selected[instance_idx as usize] = true;
+        }
+
+        let mut batches = InstanceBatches::default();
+        for (mesh_index, instances) in &cache.mesh_instances {
+            let first_instance = batches.instance_indices.len() as u32;
+            batches.instance_indices.extend(instances.iter().filter(|&&instance_idx| selected[instance_idx as usize]));
+            let instance_count = batches.instance_indices.len() as u32 - first_instance;
+            if instance_count > 0 {
+                batches.batches.push(InstanceBatch {
+                    mesh_index: *mesh_index,
+                    first_instance,
+                    instance_count,
+                });
+            }
+        }
+        batches
+    }
+
+    /// 使用不透明材质的 submesh，按 instance、submesh 的顺序排列，序号与 [`Self::prepare_render_data`] 的结果一致
+    ///
+    /// 找不到材质的 submesh 视为不透明
+    pub fn opaque_draw_items(&self) -> Vec<DrawItem> {
+        self.draw_items(false).map(|(item, _)| item).collect()
+    }
+
+    /// 使用透明材质的 submesh，参见 [`Material::is_transparent`]，按到 `camera_pos` 的距离从远到近排序，用于 alpha 混合
+    ///
+    /// 距离以 instance 世界空间包围盒的中心计算，同一个 instance 的 submesh 保持原有顺序，
+    /// 因此相互穿插的透明物体仍然可能出现排序错误
+    pub fn sorted_transparent(&self, camera_pos: glam::Vec3) -> Vec<DrawItem> {
+        let mut items = self
+            .draw_items(true)
+            .map(|(item, instance)| {
+                let center = match self.all_meshes.get(instance.mesh) {
+                    Some(mesh) if !mesh.local_aabb.is_empty() => instance.world_aabb(mesh).center(),
+                    _ => instance.transform.w_axis.truncate(),
+                };
+                (item, center.distance_squared(camera_pos))
+            })
+            .collect::<Vec<_>>();
+        // 稳定排序，距离相同时保持原有顺序
+        items.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        items.into_iter().map(|(item, _)| item).collect()
+    }
+
+    /// 材质透明性为 `transparent` 的所有 submesh，以及它所属的 instance
+    fn draw_items(&self, transparent: bool) -> impl Iterator<Item = (DrawItem, &Instance)> + '_ {
+        self.all_instances.values().enumerate().flat_map(move |(instance_idx, instance)| {
+            let submesh_cnt = self.all_meshes.get(instance.mesh).map_or(0, |mesh| mesh.geometries.len());
+            (0..submesh_cnt)
+                .filter(move |&submesh_idx| {
+                    let is_transparent = instance
+                        .materials
+                        .get(submesh_idx)
+                        .and_then(|&mat| self.all_mats.get(mat))
+                        .is_some_and(Material::is_transparent);
+                    is_transparent == transparent
+                })
+                .map(move |submesh_idx| {
+                    let item = DrawItem {
+                        instance_idx: instance_idx as u32,
+                        submesh_idx: submesh_idx as u32,
+                    };
+                    (item, instance)
+                })
+        })
+    }
+
     /// 向场景中添加材质
     pub fn register_mat(&mut self, mat: Material) -> MaterialHandle {
-        self.all_mats.insert(mat)
+        let generation = self.mark_structure_dirty();
+        let handle = self.all_mats.insert(mat);
+        self.mat_generations.insert(handle, generation);
+        handle
     }
 
     /// 向场景中添加 mesh
     pub fn register_mesh(&mut self, mesh: Mesh) -> MeshHandle {
+        self.mark_structure_dirty();
         self.all_meshes.insert(mesh)
     }
 
     /// 向场景中添加 instance
+    ///
+    /// `instance.materials` 需要和 mesh 的 geometry 一一对应
     pub fn register_instance(&mut self, instance: Instance) -> InstanceHandle {
-        self.all_instances.insert(instance)
+        if let Some(mesh) = self.all_meshes.get(instance.mesh) {
+            assert_eq!(
+                instance.materials.len(),
+                mesh.geometries.len(),
+                "Instance of mesh {}: materials count must match geometries count",
+                mesh.name
+            );
+        }
+        let generation = self.mark_structure_dirty();
+        let handle = self.all_instances.insert(instance);
+        self.instance_generations.insert(handle, generation);
+        handle
     }
 
     /// 向场景中添加点光源
     pub fn register_point_light(&mut self, light: truvisl::PointLight) -> LightHandle {
-        self.all_point_lights.insert(light)
+        let generation = self.mark_structure_dirty();
+        let handle = self.all_point_lights.insert(light);
+        self.point_light_generations.insert(handle, generation);
+        handle
+    }
+
+    /// 向场景中添加矩形面光源，`edge_u` 与 `edge_v` 需要互相垂直
+    pub fn register_rect_light(&mut self, light: truvisl::RectLight) -> RectLightHandle {
+        let generation = self.mark_structure_dirty();
+        let handle = self.all_rect_lights.insert(light);
+        self.rect_light_generations.insert(handle, generation);
+        handle
+    }
+
+    /// 向场景中添加蒙皮 mesh，蒙皮 mesh 只作为蒙皮的输入，需要通过 [`Self::register_skinned_instance`] 实例化
+    pub fn register_skinned_mesh(&mut self, skinned_mesh: SkinnedMesh) -> SkinnedMeshHandle {
+        self.all_skinned_meshes.insert(skinned_mesh)
+    }
+
+    /// 向场景中添加蒙皮 instance
+    ///
+    /// 会为该 instance 创建专属的输出 mesh 以及引用它的普通 instance，多个蒙皮 instance 可以共享同一个蒙皮 mesh
+    pub fn register_skinned_instance(
+        &mut self,
+        skinned_mesh_handle: SkinnedMeshHandle,
+        materials: Vec<MaterialHandle>,
+        transform: glam::Mat4,
+        animation: AnimationState,
+    ) -> SkinnedInstanceHandle {
+        let skinned_mesh = &self.all_skinned_meshes[skinned_mesh_handle];
+        let output_mesh = skinned_mesh.create_output_mesh(format!(
+            "{}-skinned-{}",
+            skinned_mesh.name,
+            self.all_skinned_instances.len()
+        ));
+
+        let name = output_mesh.name.clone();
+        let output_mesh_handle = self.register_mesh(output_mesh);
+        let instance_handle = self.register_instance(Instance {
+            mesh: output_mesh_handle,
+            materials,
+            transform,
+            name,
+        });
+
+        let skinned_instance = SkinnedInstance::new(
+            skinned_mesh_handle,
+            &self.all_skinned_meshes[skinned_mesh_handle],
+            output_mesh_handle,
+            instance_handle,
+            animation,
+        );
+        self.all_skinned_instances.insert(skinned_instance)
+    }
+
+    /// 修改蒙皮 instance 的动画状态，palette 会在下一次 [`Self::update_animations`] 时重新计算
+    pub fn update_animation(&mut self, handle: SkinnedInstanceHandle, f: impl FnOnce(&mut AnimationState)) {
+        let Some(skinned_instance) = self.all_skinned_instances.get_mut(handle) else {
+            log::warn!("update_animation: skinned instance not found");
+            return;
+        };
+        f(&mut skinned_instance.animation);
+        skinned_instance.dirty = true;
+    }
+
+    /// 推进所有蒙皮 instance 的动画，并将新的 palette 写入 palette buffer
+    ///
+    /// # 返回
+    /// palette 发生了变化、需要重新蒙皮的 instance
+    pub fn update_animations(&mut self, delta_time_s: f32) -> Vec<SkinnedInstanceHandle> {
+        self.all_skinned_instances
+            .iter_mut()
+            .filter_map(|(handle, skinned_instance)| {
+                let skinned_mesh = &self.all_skinned_meshes[skinned_instance.skinned_mesh];
+                skinned_instance.update_palette(skinned_mesh, delta_time_s).then_some(handle)
+            })
+            .collect()
+    }
+
+    /// instance 引用的数据在 GPU 上发生了变化（例如蒙皮后 refit 了 BLAS），只标记该 instance 为脏
+    pub fn touch_instance(&mut self, handle: InstanceHandle) {
+        if !self.all_instances.contains_key(handle) {
+            log::warn!("touch_instance: instance not found");
+            return;
+        }
+
+        self.generation += 1;
+        self.instance_generations[handle] = self.generation;
+    }
+
+    /// 修改 instance 的变换矩阵，只会标记该 instance 为脏
+    pub fn set_instance_transform(&mut self, handle: InstanceHandle, transform: glam::Mat4) {
+        let Some(instance) = self.all_instances.get_mut(handle) else {
+            log::warn!("set_instance_transform: instance not found");
+            return;
+        };
+        instance.transform = transform;
+
+        self.generation += 1;
+        self.instance_generations[handle] = self.generation;
+    }
+
+    /// 从场景中移除 instance，返回被移除的 instance；handle 无效时返回 None
+    ///
+    /// 移除属于结构变化，之后其余 instance 在 GPU 中的序号可能改变。蒙皮 instance 引用的 instance 不应该被移除
+    pub fn remove_instance(&mut self, handle: InstanceHandle) -> Option<Instance> {
+        let instance = self.all_instances.remove(handle)?;
+        self.instance_generations.remove(handle);
+        self.mark_structure_dirty();
+        Some(instance)
+    }
+
+    /// 修改材质参数，只会标记该材质为脏
+    pub fn update_material(&mut self, handle: MaterialHandle, f: impl FnOnce(&mut Material)) {
+        let Some(mat) = self.all_mats.get_mut(handle) else {
+            log::warn!("update_material: material not found");
+            return;
+        };
+        f(mat);
+
+        self.generation += 1;
+        self.mat_generations[handle] = self.generation;
+    }
+
+    /// 修改点光源参数，只会标记该点光源为脏
+    pub fn update_point_light(&mut self, handle: LightHandle, f: impl FnOnce(&mut truvisl::PointLight)) {
+        let Some(light) = self.all_point_lights.get_mut(handle) else {
+            log::warn!("update_point_light: point light not found");
+            return;
+        };
+        f(light);
+
+        self.generation += 1;
+        self.point_light_generations[handle] = self.generation;
+    }
+
+    /// 替换点光源参数，只会标记该点光源为脏
+    #[inline]
+    pub fn set_point_light(&mut self, handle: LightHandle, light: truvisl::PointLight) {
+        self.update_point_light(handle, |point_light| *point_light = light);
+    }
+
+    /// 从场景中移除点光源，返回被移除的点光源；handle 无效时返回 None
+    ///
+    /// 移除属于结构变化，之后其余点光源在 GPU 中的序号可能改变
+    pub fn remove_point_light(&mut self, handle: LightHandle) -> Option<truvisl::PointLight> {
+        let light = self.all_point_lights.remove(handle)?;
+        self.point_light_generations.remove(handle);
+        self.mark_structure_dirty();
+        Some(light)
+    }
+
+    /// 设置场景的方向光，None 表示移除
+    pub fn set_directional_light(&mut self, light: Option<DirectionalLight>) {
+        self.directional_light = light;
+        self.generation += 1;
+    }
+
+    /// 修改面光源参数，只会标记该面光源为脏
+    pub fn update_rect_light(&mut self, handle: RectLightHandle, f: impl FnOnce(&mut truvisl::RectLight)) {
+        let Some(light) = self.all_rect_lights.get_mut(handle) else {
+            log::warn!("update_rect_light: rect light not found");
+            return;
+        };
+        f(light);
+
+        self.generation += 1;
+        self.rect_light_generations[handle] = self.generation;
+    }
+
+    /// 标记场景结构发生了变化，返回新的 generation
+    fn mark_structure_dirty(&mut self) -> u64 {
+        self.generation += 1;
+        self.structure_generation = self.generation;
+        self.generation
     }
 }
 impl Drop for SceneManager {
@@ -201,5 +663,137 @@ impl SceneManager {
         self.all_instances.clear();
         self.all_meshes.clear();
         self.all_point_lights.clear();
+        self.all_rect_lights.clear();
+        self.all_skinned_instances.clear();
+        self.all_skinned_meshes.clear();
+        self.mat_generations.clear();
+        self.instance_generations.clear();
+        self.point_light_generations.clear();
+        self.rect_light_generations.clear();
+        self.mark_structure_dirty();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point_light(x: f32) -> truvisl::PointLight {
+        truvisl::PointLight {
+            pos: glam::vec3(x, 0.0, 0.0).into(),
+            color: glam::Vec3::ONE.into(),
+
+            _pos_padding: Default::default(),
+            _color_padding: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_point_light_add_update_remove() {
+        let mut scene_manager = SceneManager::new();
+        let l0 = scene_manager.register_point_light(point_light(0.0));
+        let l1 = scene_manager.register_point_light(point_light(1.0));
+        assert_eq!(scene_manager.point_light_map().len(), 2);
+
+        // 修改参数不是结构变化，只更新该点光源的 generation
+        let structure_generation = scene_manager.structure_generation;
+        scene_manager.set_point_light(l1, point_light(2.0));
+        assert_eq!(scene_manager.structure_generation, structure_generation);
+        assert_eq!(scene_manager.point_light_generations[l1], scene_manager.generation);
+        assert!(scene_manager.point_light_generations[l0] < scene_manager.generation);
+        assert_eq!(scene_manager.point_light_map()[l1].pos.x, 2.0);
+
+        // 移除是结构变化
+        assert_eq!(scene_manager.remove_point_light(l0).map(|light| light.pos.x), Some(0.0));
+        assert_eq!(scene_manager.structure_generation, scene_manager.generation);
+        assert_eq!(scene_manager.point_light_map().len(), 1);
+        assert!(!scene_manager.point_light_generations.contains_key(l0));
+
+        // 已经移除的 handle
+        assert!(scene_manager.remove_point_light(l0).is_none());
+        scene_manager.set_point_light(l0, point_light(3.0));
+        assert_eq!(scene_manager.point_light_map().len(), 1);
+    }
+
+    fn empty_mesh(name: &str) -> Mesh {
+        Mesh {
+            geometries: vec![],
+            geometry_transforms: None,
+            local_aabb: Aabb::EMPTY,
+            blas: None,
+            dynamic_blas: None,
+            name: name.to_string(),
+            blas_device_address: None,
+        }
+    }
+
+    fn instance(mesh: MeshHandle) -> Instance {
+        Instance {
+            mesh,
+            materials: vec![],
+            transform: glam::Mat4::IDENTITY,
+            name: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_instance_batches() {
+        let mut scene_manager = SceneManager::new();
+        let mesh_a = scene_manager.register_mesh(empty_mesh("a"));
+        let mesh_b = scene_manager.register_mesh(empty_mesh("b"));
+        scene_manager.register_instance(instance(mesh_a));
+        let i1 = scene_manager.register_instance(instance(mesh_b));
+        scene_manager.register_instance(instance(mesh_a));
+
+        // 相同 mesh 的 instance 合并为一个批次
+        let batches = scene_manager.instance_batches(&[0, 1, 2]);
+        assert_eq!(
+            batches.batches,
+            vec![
+                InstanceBatch {
+                    mesh_index: 0,
+                    first_instance: 0,
+                    instance_count: 2,
+                },
+                InstanceBatch {
+                    mesh_index: 1,
+                    first_instance: 2,
+                    instance_count: 1,
+                },
+            ]
+        );
+        assert_eq!(batches.instance_indices, vec![0, 2, 1]);
+
+        // 只包含选中的 instance，没有 instance 的批次被跳过
+        let batches = scene_manager.instance_batches(&[2]);
+        assert_eq!(batches.batches.len(), 1);
+        assert_eq!(batches.instance_indices, vec![2]);
+
+        // 移除 instance 之后重建映射
+        scene_manager.remove_instance(i1);
+        let batches = scene_manager.instance_batches(&[0, 1]);
+        assert_eq!(batches.batches.len(), 1);
+        assert_eq!(batches.batches[0].instance_count, 2);
+        assert_eq!(batches.instance_indices, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_instances_with_material() {
+        let mut scene_manager = SceneManager::new();
+        // 只检查 instance 引用的材质，不注册 mesh，避免材质数量与 geometry 数量的校验
+        let mesh = MeshHandle::default();
+        let m0 = scene_manager.register_mat(Material::default());
+        let m1 = scene_manager.register_mat(Material::default());
+        let i0 = scene_manager.register_instance(Instance {
+            materials: vec![m0, m1],
+            ..instance(mesh)
+        });
+        let i1 = scene_manager.register_instance(Instance {
+            materials: vec![m1],
+            ..instance(mesh)
+        });
+
+        assert_eq!(scene_manager.instances_with_material(m0), vec![i0]);
+        assert_eq!(scene_manager.instances_with_material(m1), vec![i0, i1]);
     }
 }
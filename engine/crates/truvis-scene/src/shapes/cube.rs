@@ -2,6 +2,8 @@ use truvis_gfx::resources::special_buffers::index_buffer::GfxIndex32Buffer;
 use truvis_gfx::resources::vertex_layout::soa_3d::VertexLayoutSoA3D;
 use truvis_render_interface::geometry::RtGeometry;
 
+use crate::aabb::Aabb;
+
 /// 坐标系：Right-Hand, X-Right, Y-Up
 ///
 /// 三角形绕序: CCW
@@ -110,38 +112,39 @@ impl CubeSoA {
     ];
 
     // 切线向量指向 U 轴正方向
-    // 对于每个面，切线与法线、副切线构成右手坐标系
-    const TANGENTS: [glam::Vec3; 24] = [
+    // w 为副切线的方向：副切线 cross(normal, tangent) * w 指向 V 轴负方向（图片的上方）；
+    // 相对的两个面 uv 布局相同，其中一个面是镜像的，w 为 -1
+    const TANGENTS: [glam::Vec4; 24] = [
         // Top face (Y+, normal: Y+): tangent 指向 X+ (U 轴方向)
-        glam::vec3(1.0, 0.0, 0.0),
-        glam::vec3(1.0, 0.0, 0.0),
-        glam::vec3(1.0, 0.0, 0.0),
-        glam::vec3(1.0, 0.0, 0.0),
+        glam::vec4(1.0, 0.0, 0.0, 1.0),
+        glam::vec4(1.0, 0.0, 0.0, 1.0),
+        glam::vec4(1.0, 0.0, 0.0, 1.0),
+        glam::vec4(1.0, 0.0, 0.0, 1.0),
         // Bottom face (Y-, normal: Y-): tangent 指向 X+ (U 轴方向)
-        glam::vec3(1.0, 0.0, 0.0),
-        glam::vec3(1.0, 0.0, 0.0),
-        glam::vec3(1.0, 0.0, 0.0),
-        glam::vec3(1.0, 0.0, 0.0),
+        glam::vec4(1.0, 0.0, 0.0, -1.0),
+        glam::vec4(1.0, 0.0, 0.0, -1.0),
+        glam::vec4(1.0, 0.0, 0.0, -1.0),
+        glam::vec4(1.0, 0.0, 0.0, -1.0),
         // Near face (Z+, normal: Z+): tangent 指向 X+ (U 轴方向)
-        glam::vec3(1.0, 0.0, 0.0),
-        glam::vec3(1.0, 0.0, 0.0),
-        glam::vec3(1.0, 0.0, 0.0),
-        glam::vec3(1.0, 0.0, 0.0),
+        glam::vec4(1.0, 0.0, 0.0, 1.0),
+        glam::vec4(1.0, 0.0, 0.0, 1.0),
+        glam::vec4(1.0, 0.0, 0.0, 1.0),
+        glam::vec4(1.0, 0.0, 0.0, 1.0),
         // Far face (Z-, normal: Z-): tangent 指向 X+ (U 轴方向)
-        glam::vec3(1.0, 0.0, 0.0),
-        glam::vec3(1.0, 0.0, 0.0),
-        glam::vec3(1.0, 0.0, 0.0),
-        glam::vec3(1.0, 0.0, 0.0),
+        glam::vec4(1.0, 0.0, 0.0, -1.0),
+        glam::vec4(1.0, 0.0, 0.0, -1.0),
+        glam::vec4(1.0, 0.0, 0.0, -1.0),
+        glam::vec4(1.0, 0.0, 0.0, -1.0),
         // Left face (X-, normal: X-): tangent 指向 Z+ (U 轴方向)
-        glam::vec3(0.0, 0.0, 1.0),
-        glam::vec3(0.0, 0.0, 1.0),
-        glam::vec3(0.0, 0.0, 1.0),
-        glam::vec3(0.0, 0.0, 1.0),
+        glam::vec4(0.0, 0.0, 1.0, 1.0),
+        glam::vec4(0.0, 0.0, 1.0, 1.0),
+        glam::vec4(0.0, 0.0, 1.0, 1.0),
+        glam::vec4(0.0, 0.0, 1.0, 1.0),
         // Right face (X+, normal: X+): tangent 指向 Z+ (U 轴方向)
-        glam::vec3(0.0, 0.0, 1.0),
-        glam::vec3(0.0, 0.0, 1.0),
-        glam::vec3(0.0, 0.0, 1.0),
-        glam::vec3(0.0, 0.0, 1.0),
+        glam::vec4(0.0, 0.0, 1.0, -1.0),
+        glam::vec4(0.0, 0.0, 1.0, -1.0),
+        glam::vec4(0.0, 0.0, 1.0, -1.0),
+        glam::vec4(0.0, 0.0, 1.0, -1.0),
     ];
 
     const INDICES: [u32; 36] = [
@@ -153,6 +156,11 @@ impl CubeSoA {
         20, 22, 21, 20, 23, 22, // right
     ];
 
+    /// 局部空间的包围盒
+    pub fn aabb() -> Aabb {
+        Aabb::from_points(&Self::POSITIONS)
+    }
+
     pub fn create_mesh() -> RtGeometry {
         let vertex_buffer = VertexLayoutSoA3D::create_vertex_buffer(
             &Self::POSITIONS,
@@ -2,6 +2,8 @@ use truvis_gfx::resources::special_buffers::index_buffer::GfxIndex32Buffer;
 use truvis_gfx::resources::vertex_layout::soa_3d::VertexLayoutSoA3D;
 use truvis_render_interface::geometry::RtGeometry;
 
+use crate::aabb::Aabb;
+
 /// 坐标系：RightHandle, X-Right, Y-Up
 ///
 /// 面片位于 XZ 平面上，朝向 +Y
@@ -37,12 +39,18 @@ impl FloorSoA {
         glam::vec2(0.0, 1.0), // C
         glam::vec2(1.0, 1.0), // D
     ];
-    const TANGENTS: [glam::Vec3; 4] = [glam::vec3(1.0, 0.0, 0.0); _];
+    // u 沿 Z+ 方向增大，v 沿 X- 方向增大
+    const TANGENTS: [glam::Vec4; 4] = [glam::vec4(0.0, 0.0, 1.0, 1.0); _];
     const INDICES: [u32; 6] = [
         0, 1, 2, // ABC
         0, 2, 3, // ACD
     ];
 
+    /// 局部空间的包围盒
+    pub fn aabb() -> Aabb {
+        Aabb::from_points(&Self::POSITIONS)
+    }
+
     pub fn create_mesh() -> RtGeometry {
         let vertex_buffer = VertexLayoutSoA3D::create_vertex_buffer(
             &Self::POSITIONS,
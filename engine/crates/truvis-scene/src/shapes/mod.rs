@@ -1,4 +1,52 @@
+use std::rc::Rc;
+
+use truvis_render_interface::geometry::RtGeometry;
+
+use crate::aabb::Aabb;
+use crate::components::instance::Instance;
+use crate::components::material::Material;
+use crate::components::mesh::Mesh;
+use crate::guid_new_type::{InstanceHandle, MeshHandle};
+use crate::scene_manager::SceneManager;
+
 pub mod cube;
 pub mod floor;
 pub mod rect;
 pub mod triangle;
+
+/// 由单个 geometry 创建 Mesh，构建 BLAS 之后注册到场景中
+///
+/// `shape` 为形体的几何体与包围盒，例如 `(CubeSoA::create_mesh(), CubeSoA::aabb())`。
+/// GpuScene 每帧会构建 TLAS，即使只做光栅化也需要 BLAS
+pub fn register_shape_mesh(scene_manager: &mut SceneManager, name: &str, shape: (RtGeometry, Aabb)) -> MeshHandle {
+    let (geometry, local_aabb) = shape;
+    let mut mesh = Mesh {
+        geometries: vec![Rc::new(geometry)],
+        geometry_transforms: None,
+        local_aabb,
+        blas: None,
+        dynamic_blas: None,
+        name: name.to_string(),
+        blas_device_address: None,
+    };
+    mesh.build_blas();
+    scene_manager.register_mesh(mesh)
+}
+
+/// 使用单个材质向场景中添加一个形体，Mesh 不与其他 instance 共享，参见 [`register_shape_mesh`]
+pub fn add_shape(
+    scene_manager: &mut SceneManager,
+    name: &str,
+    shape: (RtGeometry, Aabb),
+    material: Material,
+    transform: glam::Mat4,
+) -> InstanceHandle {
+    let mesh = register_shape_mesh(scene_manager, name, shape);
+    let mat = scene_manager.register_mat(material);
+    scene_manager.register_instance(Instance {
+        mesh,
+        materials: vec![mat],
+        transform,
+        name: name.to_string(),
+    })
+}
@@ -2,6 +2,8 @@ use truvis_gfx::resources::special_buffers::index_buffer::GfxIndex32Buffer;
 use truvis_gfx::resources::vertex_layout::soa_3d::VertexLayoutSoA3D;
 use truvis_render_interface::geometry::RtGeometry;
 
+use crate::aabb::Aabb;
+
 /// 坐标系：RightHand, X-Right, Y-Up
 ///
 /// 位于 XY 平面上的矩形，法线 +Z
@@ -46,12 +48,12 @@ impl RectSoA {
         glam::vec2(0.0, 0.0), // D (左上)
     ];
 
-    // 切线指向 X+ (U 轴方向)
-    const TANGENTS: [glam::Vec3; 4] = [
-        glam::vec3(1.0, 0.0, 0.0),
-        glam::vec3(1.0, 0.0, 0.0),
-        glam::vec3(1.0, 0.0, 0.0),
-        glam::vec3(1.0, 0.0, 0.0),
+    // 切线指向 X+ (U 轴方向)，v 沿 Y+ 方向增大，因此 w 为 -1
+    const TANGENTS: [glam::Vec4; 4] = [
+        glam::vec4(1.0, 0.0, 0.0, -1.0),
+        glam::vec4(1.0, 0.0, 0.0, -1.0),
+        glam::vec4(1.0, 0.0, 0.0, -1.0),
+        glam::vec4(1.0, 0.0, 0.0, -1.0),
     ];
 
     // 两个三角形：ABC, ACD
@@ -60,6 +62,11 @@ impl RectSoA {
         0, 2, 3, // ACD
     ];
 
+    /// 局部空间的包围盒
+    pub fn aabb() -> Aabb {
+        Aabb::from_points(&Self::POSITIONS)
+    }
+
     pub fn create_mesh() -> RtGeometry {
         let vertex_buffer = VertexLayoutSoA3D::create_vertex_buffer(
             &Self::POSITIONS,
@@ -2,6 +2,8 @@ use truvis_gfx::resources::special_buffers::index_buffer::GfxIndex32Buffer;
 use truvis_gfx::resources::vertex_layout::soa_3d::VertexLayoutSoA3D;
 use truvis_render_interface::geometry::RtGeometry;
 
+use crate::aabb::Aabb;
+
 /// 坐标系：RightHand, X-Right, Y-Up
 ///
 /// 位于 XY 平面上的正立三角形，法线 +Z
@@ -47,14 +49,19 @@ impl TriangleSoA {
     ];
 
     // 切线指向 X+ (U 轴方向)
-    const TANGENTS: [glam::Vec3; 3] = [
-        glam::vec3(1.0, 0.0, 0.0),
-        glam::vec3(1.0, 0.0, 0.0),
-        glam::vec3(1.0, 0.0, 0.0),
+    const TANGENTS: [glam::Vec4; 3] = [
+        glam::vec4(1.0, 0.0, 0.0, 1.0),
+        glam::vec4(1.0, 0.0, 0.0, 1.0),
+        glam::vec4(1.0, 0.0, 0.0, 1.0),
     ];
 
     const INDICES: [u32; 3] = [0, 1, 2];
 
+    /// 局部空间的包围盒
+    pub fn aabb() -> Aabb {
+        Aabb::from_points(&Self::POSITIONS)
+    }
+
     pub fn create_mesh() -> RtGeometry {
         let vertex_buffer = VertexLayoutSoA3D::create_vertex_buffer(
             &Self::POSITIONS,
@@ -0,0 +1,87 @@
+//! 检查 SceneManager 将 submesh 分为不透明与透明两组，透明的 submesh 按距离从远到近排序
+//!
+//! Mesh 需要真实的 geometry，因此需要 GPU，默认不执行：
+//! ```text
+//! cargo test -p truvis-scene --test sorted_transparent -- --ignored
+//! ```
+
+use std::rc::Rc;
+
+use truvis_gfx::gfx::Gfx;
+use truvis_render_interface::render_data::DrawItem;
+use truvis_scene::components::instance::Instance;
+use truvis_scene::components::material::Material;
+use truvis_scene::components::mesh::Mesh;
+use truvis_scene::scene_manager::SceneManager;
+use truvis_scene::shapes::cube::CubeSoA;
+
+fn draw_item(instance_idx: u32, submesh_idx: u32) -> DrawItem {
+    DrawItem {
+        instance_idx,
+        submesh_idx,
+    }
+}
+
+#[test]
+#[ignore = "requires a GPU"]
+fn transparent_submeshes_are_sorted_back_to_front() {
+    // 没有 surface 也需要开启该扩展，否则 device 上的 swapchain 扩展不合法
+    Gfx::init("sorted-transparent-test".to_string(), vec![ash::khr::surface::NAME], None);
+    {
+        let mut scene_manager = SceneManager::new();
+
+        // 两个 submesh 共享同一个 geometry
+        let cube = Rc::new(CubeSoA::create_mesh());
+        let mesh = scene_manager.register_mesh(Mesh {
+            geometries: vec![cube.clone(), cube],
+            geometry_transforms: None,
+            local_aabb: CubeSoA::aabb(),
+            blas: None,
+            dynamic_blas: None,
+            name: "two-cubes".to_string(),
+            blas_device_address: None,
+        });
+        let opaque = scene_manager.register_mat(Material {
+            opaque: 1.0,
+            ..Default::default()
+        });
+        let glass = scene_manager.register_mat(Material {
+            opaque: 0.5,
+            ..Default::default()
+        });
+
+        let mut add_instance = |materials: Vec<_>, z: f32| {
+            scene_manager.register_instance(Instance {
+                mesh,
+                materials,
+                transform: glam::Mat4::from_translation(glam::vec3(0.0, 0.0, z)),
+                name: format!("cube-{z}"),
+            })
+        };
+        add_instance(vec![opaque, glass], -1.0);
+        add_instance(vec![glass, glass], -10.0);
+        add_instance(vec![opaque, opaque], -5.0);
+        add_instance(vec![glass, opaque], -5.0);
+
+        assert_eq!(
+            scene_manager.opaque_draw_items(),
+            vec![draw_item(0, 0), draw_item(2, 0), draw_item(2, 1), draw_item(3, 1)]
+        );
+
+        // 相机在原点：最远的 instance 1 在前，同一个 instance 的 submesh 保持原有顺序
+        assert_eq!(
+            scene_manager.sorted_transparent(glam::Vec3::ZERO),
+            vec![draw_item(1, 0), draw_item(1, 1), draw_item(3, 0), draw_item(0, 1)]
+        );
+
+        // 相机移动到另一侧之后顺序反转
+        assert_eq!(
+            scene_manager.sorted_transparent(glam::vec3(0.0, 0.0, -20.0)),
+            vec![draw_item(0, 1), draw_item(3, 0), draw_item(1, 0), draw_item(1, 1)]
+        );
+
+        scene_manager.destroy();
+    }
+    Gfx::get().wait_idel();
+    Gfx::destroy();
+}
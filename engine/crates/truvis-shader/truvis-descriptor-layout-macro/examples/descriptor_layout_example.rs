@@ -1,5 +1,3 @@
-use ash::vk;
-
 use truvis_descriptor_layout_macro::DescriptorBinding;
 use truvis_descriptor_layout_trait::DescriptorBindingLayout;
 
@@ -34,10 +32,23 @@ struct MyShader {
     #[count = 1]
     #[stage = "FRAGMENT"]
     _sampler___: (),
+
+    /// bindless 纹理数组，绑定到绑定点 3
+    /// 数量在分配描述符集时决定，layout 中使用上限
+    #[binding = 3]
+    #[descriptor_type = "SAMPLED_IMAGE"]
+    #[count = "runtime"]
+    #[stage = "FRAGMENT"]
+    #[flags = "PARTIALLY_BOUND | VARIABLE_DESCRIPTOR_COUNT"]
+    _textures: (),
 }
 
 fn main() {
     // 获取完整的绑定信息，包括描述符类型、数量和着色器阶段
     let binding_items = <MyShader as DescriptorBindingLayout>::get_shader_bindings();
     println!("Shader binding items: {:?}", binding_items);
+
+    // 运行时数量的 binding，分配描述符集时需要为它指定实际数量
+    let variable_binding = <MyShader as DescriptorBindingLayout>::variable_binding();
+    println!("Variable binding: {:?}", variable_binding);
 }
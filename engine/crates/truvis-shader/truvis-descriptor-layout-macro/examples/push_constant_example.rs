@@ -0,0 +1,45 @@
+use ash::vk;
+
+use truvis_descriptor_layout_macro::PushConstant;
+use truvis_descriptor_layout_trait::PushConstantLayout;
+
+/// 示例：使用 PushConstant 派生宏定义 push constant
+///
+/// - 结构体上的 stage 是所有字段的默认值
+/// - `tint` 只有片段着色器需要，因此片段着色器的 range 会比顶点着色器的大
+#[repr(C)]
+#[derive(PushConstant, Clone, Copy)]
+#[stage = "VERTEX | FRAGMENT"]
+struct MyPushConstant {
+    model: [f32; 16],
+    instance_id: u32,
+    _padding_0: [u32; 3],
+
+    #[stage = "FRAGMENT"]
+    tint: [f32; 4],
+}
+
+fn main() {
+    // 创建 pipeline layout 使用的 range：VERTEX 覆盖 [0, 80)，FRAGMENT 覆盖 [0, 96)
+    let ranges = MyPushConstant::ranges();
+    println!("Push constant ranges: {:?}", ranges);
+
+    // 更新 fragment 可见的字段时，vkCmdPushConstants 需要分两次调用：
+    // [0, 80) 与两个 range 都重叠，stage 为 VERTEX | FRAGMENT；[80, 96) 只属于 FRAGMENT
+    let push_constant = MyPushConstant {
+        model: [0.0; 16],
+        instance_id: 0,
+        _padding_0: [0; 3],
+        tint: [1.0; 4],
+    };
+    let bytes = unsafe {
+        std::slice::from_raw_parts((&push_constant as *const MyPushConstant).cast::<u8>(), size_of::<MyPushConstant>())
+    };
+    for range in MyPushConstant::push_ranges(vk::ShaderStageFlags::FRAGMENT) {
+        let range_bytes = &bytes[range.offset as usize..(range.offset + range.size) as usize];
+        println!("vkCmdPushConstants({:?}, offset = {}, {} bytes)", range.stage_flags, range.offset, range_bytes.len());
+    }
+
+    // 与 shader 中的定义比对
+    println!("{}", MyPushConstant::shader_struct());
+}
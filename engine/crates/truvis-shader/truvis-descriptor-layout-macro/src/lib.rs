@@ -7,41 +7,81 @@ use syn::{Attribute, Data, DeriveInput, Fields, Meta, parse_macro_input};
 /// 支持的属性：
 /// - binding: 指定绑定点编号
 /// - descriptor_type: 指定描述符类型（如 UNIFORM_BUFFER, COMBINED_IMAGE_SAMPLER 等）
-/// - count: 指定描述符数量
+/// - count: 指定描述符数量；为 `"runtime"` 时数量在分配 descriptor set 时决定，
+///   layout 中使用 `DescriptorBindingItem::RUNTIME_COUNT_UPPER_BOUND` 作为上限，
+///   需要同时在 flags 中指定 `VARIABLE_DESCRIPTOR_COUNT`，并且只能用于 binding 编号最大的那个字段
 /// - stage: 指定着色器阶段（如 VERTEX, FRAGMENT 等）
 /// - flags: 指定描述符绑定标志（如 UPDATE_AFTER_BIND, PARTIALLY_BOUND 等）
 #[proc_macro_derive(DescriptorBinding, attributes(binding, descriptor_type, count, stage, flags))]
 pub fn derive_descriptor_binding(input: TokenStream) -> TokenStream {
-    // 解析输入为 DeriveInput 结构
     let input = parse_macro_input!(input as DeriveInput);
+    expand_descriptor_binding(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+fn expand_descriptor_binding(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
     let struct_name = &input.ident;
 
     // 只处理结构体类型，且只支持具名字段
     let fields = match &input.data {
         Data::Struct(data) => match &data.fields {
             Fields::Named(fields) => &fields.named,
-            _ => panic!("Only named fields are supported"),
+            _ => return Err(syn::Error::new_spanned(struct_name, format!("{}: 只支持具名字段", struct_name))),
         },
-        _ => panic!("Only structs are supported"),
+        _ => return Err(syn::Error::new_spanned(struct_name, format!("{}: 只支持结构体", struct_name))),
     };
 
     // 收集字段信息：名称、绑定、描述符类型、数量、着色器阶段和标志
     let mut field_infos = Vec::new();
+    // 数量在运行时决定的 binding
+    let mut variable_binding = None;
 
     for field in fields {
         let field_name = field.ident.as_ref().unwrap();
-        let binding = get_binding_value(&field.attrs);
-        let descriptor_type = get_descriptor_type(&field.attrs);
+        let Some(binding) = get_binding_value(&field.attrs)? else {
+            continue;
+        };
+        let descriptor_type = get_descriptor_type(&field.attrs)?;
         let count = get_count_value(&field.attrs);
-        let stage = get_stage_value(&field.attrs);
-        let flags = get_flags_value(&field.attrs);
+        let stage = get_stage_value(&field.attrs)?;
+        let flags = get_flags_value(&field.attrs)?;
+
+        if is_runtime_count(&field.attrs) {
+            if !has_flag(&field.attrs, "VARIABLE_DESCRIPTOR_COUNT") {
+                return Err(syn::Error::new_spanned(
+                    field_name,
+                    format!("{}: #[count = \"runtime\"] 需要在 #[flags] 中指定 VARIABLE_DESCRIPTOR_COUNT", field_name),
+                ));
+            }
+            if variable_binding.replace(binding).is_some() {
+                return Err(syn::Error::new_spanned(
+                    field_name,
+                    format!("{}: 只能有一个 binding 使用 #[count = \"runtime\"]", struct_name),
+                ));
+            }
+        }
+
+        // 创建不带前后缀下划线的方法名
+        let method_name = syn::Ident::new(field_name.to_string().trim_matches('_'), field_name.span());
+        field_infos.push((field_name, method_name, binding, descriptor_type, count, stage, flags));
+    }
 
-        if let Some(binding) = binding {
-            // 创建不带前后缀下划线的方法名
-            let method_name = syn::Ident::new(field_name.to_string().trim_matches('_'), field_name.span());
-            field_infos.push((field_name, method_name, binding, descriptor_type, count, stage, flags));
+    // 重复的 binding 在编译期报错，错误定位到后出现的那个字段上
+    let mut errors: Option<syn::Error> = None;
+    for (idx, (field_name, _, binding, ..)) in field_infos.iter().enumerate() {
+        if let Some((first_field_name, ..)) = field_infos[..idx].iter().find(|(_, _, b, ..)| b == binding) {
+            let error = syn::Error::new_spanned(
+                field_name,
+                format!("binding {} 被 {} 和 {} 重复使用", binding, first_field_name, field_name),
+            );
+            match &mut errors {
+                Some(errors) => errors.combine(error),
+                None => errors = Some(error),
+            }
         }
     }
+    if let Some(errors) = errors {
+        return Err(errors);
+    }
 
     // 生成获取绑定信息的方法
     let field_names = field_infos.iter().map(|(name, ..)| name).collect::<Vec<_>>();
@@ -52,16 +92,31 @@ pub fn derive_descriptor_binding(input: TokenStream) -> TokenStream {
     let stages = field_infos.iter().map(|(.., stage, _)| stage).collect::<Vec<_>>();
     let flags = field_infos.iter().map(|(.., flags)| flags).collect::<Vec<_>>();
 
+    // Vulkan 要求 VARIABLE_DESCRIPTOR_COUNT 只能用于 binding 编号最大的 binding
+    if let Some(variable_binding) = variable_binding
+        && binding_values.iter().any(|binding| **binding > variable_binding)
+    {
+        let (variable_field_name, ..) = field_infos.iter().find(|(_, _, b, ..)| *b == variable_binding).unwrap();
+        return Err(syn::Error::new_spanned(
+            variable_field_name,
+            format!("{}: #[count = \"runtime\"] 只能用于 binding 编号最大的字段", struct_name),
+        ));
+    }
+    let variable_binding = match variable_binding {
+        Some(binding) => quote!(Some(#binding)),
+        None => quote!(None),
+    };
+
     // 生成代码：
     // 1. 实现 get_shader_bindings 方法，返回字段名和绑定值的元组数组
-    // 2. 实现 DescriptorBindingLayout trait，返回完整的 DescriptorBindingItem 数组
-    let expanded = quote! {
+    // 2. 实现 DescriptorBindingLayout trait，返回完整的 DescriptorBindingItem 数组，以及运行时数量的 binding
+    Ok(quote! {
         impl #struct_name {
             #(
-                pub fn #method_names() -> &'static truvis_descriptor_layout_trait::DescriptorBindingItem {
+                pub fn #method_names() -> &'static ::truvis_descriptor_layout_trait::DescriptorBindingItem {
                     // OnceLock 的开销：get 大约是 1~3 cycles
-                    static CURSOR: std::sync::OnceLock<truvis_descriptor_layout_trait::DescriptorBindingItem> = std::sync::OnceLock::new();
-                    CURSOR.get_or_init(|| truvis_descriptor_layout_trait::DescriptorBindingItem{
+                    static CURSOR: ::std::sync::OnceLock<::truvis_descriptor_layout_trait::DescriptorBindingItem> = ::std::sync::OnceLock::new();
+                    CURSOR.get_or_init(|| ::truvis_descriptor_layout_trait::DescriptorBindingItem{
                         name: stringify!(#field_names).trim_matches('_'),
                         binding: #binding_values,
                         descriptor_type: #descriptor_types,
@@ -73,10 +128,10 @@ pub fn derive_descriptor_binding(input: TokenStream) -> TokenStream {
             )*
         }
 
-        impl truvis_descriptor_layout_trait::DescriptorBindingLayout for #struct_name {
-            fn get_shader_bindings() -> Vec<truvis_descriptor_layout_trait::DescriptorBindingItem> {
+        impl ::truvis_descriptor_layout_trait::DescriptorBindingLayout for #struct_name {
+            fn get_shader_bindings() -> Vec<::truvis_descriptor_layout_trait::DescriptorBindingItem> {
                 vec![
-                    #(truvis_descriptor_layout_trait::DescriptorBindingItem {
+                    #(::truvis_descriptor_layout_trait::DescriptorBindingItem {
                         name: stringify!(#field_names).trim_matches('_'),
                         binding: #binding_values,
                         descriptor_type: #descriptor_types,
@@ -86,54 +141,54 @@ pub fn derive_descriptor_binding(input: TokenStream) -> TokenStream {
                     }),*
                 ]
             }
-        }
-    };
 
-    expanded.into()
+            fn variable_binding() -> Option<u32> {
+                #variable_binding
+            }
+        }
+    })
 }
 
-/// 从字段属性中获取 binding 值
+/// 从字段属性中获取 binding 值，没有 binding 属性的字段不参与布局
 ///
 /// 属性格式示例：#[binding = 0]
-fn get_binding_value(attrs: &[Attribute]) -> Option<u32> {
+fn get_binding_value(attrs: &[Attribute]) -> syn::Result<Option<u32>> {
     for attr in attrs {
-        if attr.path().is_ident("binding")
-            && let Meta::NameValue(meta) = &attr.meta
-            && let syn::Expr::Lit(syn::ExprLit {
-                lit: syn::Lit::Int(lit_int),
-                ..
-            }) = &meta.value
-        {
-            return Some(lit_int.base10_parse().unwrap());
+        if attr.path().is_ident("binding") {
+            if let Meta::NameValue(meta) = &attr.meta
+                && let syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Int(lit_int),
+                    ..
+                }) = &meta.value
+            {
+                return lit_int.base10_parse().map(Some);
+            }
+            return Err(syn::Error::new_spanned(attr, "binding 的格式需要是 #[binding = 0]"));
         }
     }
-    None
+    Ok(None)
 }
 
 /// 从字段属性中获取 descriptor_type 值
 ///
 /// 属性格式示例：#[descriptor_type = "UNIFORM_BUFFER"]
-fn get_descriptor_type(attrs: &[Attribute]) -> syn::Expr {
+fn get_descriptor_type(attrs: &[Attribute]) -> syn::Result<syn::Expr> {
     for attr in attrs {
-        if attr.path().is_ident("descriptor_type")
-            && let Meta::NameValue(meta) = &attr.meta
-            && let syn::Expr::Lit(syn::ExprLit {
-                lit: syn::Lit::Str(lit_str),
-                ..
-            }) = &meta.value
-        {
-            let descriptor_type = format!("vk::DescriptorType::{}", lit_str.value().as_str());
-            return syn::parse_str(&descriptor_type).unwrap();
+        if attr.path().is_ident("descriptor_type") {
+            return parse_flags_attr(attr, "DescriptorType");
         }
     }
     // 默认值：统一缓冲区
-    syn::parse_quote!(vk::DescriptorType::UNIFORM_BUFFER)
+    Ok(syn::parse_quote!(::ash::vk::DescriptorType::UNIFORM_BUFFER))
 }
 
 /// 从字段属性中获取 count 值
 ///
-/// 属性格式示例：#[count = 1]
+/// 属性格式示例：#[count = 1]，#[count = "runtime"]
 fn get_count_value(attrs: &[Attribute]) -> syn::Expr {
+    if is_runtime_count(attrs) {
+        return syn::parse_quote!(::truvis_descriptor_layout_trait::DescriptorBindingItem::RUNTIME_COUNT_UPPER_BOUND);
+    }
     for attr in attrs {
         if attr.path().is_ident("count")
             && let Meta::NameValue(meta) = &attr.meta
@@ -145,33 +200,77 @@ fn get_count_value(attrs: &[Attribute]) -> syn::Expr {
     syn::parse_quote!(1)
 }
 
-/// 从字段属性中获取 stage 值
-///
-/// 属性格式示例：#[stage = "VERTEX | FRAGMENT"]
-fn get_stage_value(attrs: &[Attribute]) -> syn::Expr {
+/// count 是否为 "runtime"，即数量在分配 descriptor set 时才决定
+fn is_runtime_count(attrs: &[Attribute]) -> bool {
     for attr in attrs {
-        if attr.path().is_ident("stage")
+        if attr.path().is_ident("count")
             && let Meta::NameValue(meta) = &attr.meta
             && let syn::Expr::Lit(syn::ExprLit {
                 lit: syn::Lit::Str(lit_str),
                 ..
             }) = &meta.value
         {
-            let stage = lit_str.value();
-            let stage_flags =
-                stage.split(" | ").map(|s| format!("vk::ShaderStageFlags::{}", s)).collect::<Vec<_>>().join(" | ");
-            return syn::parse_str(&stage_flags).unwrap();
+            return lit_str.value() == "runtime";
+        }
+    }
+    false
+}
+
+/// 从字段属性中获取 stage 值
+///
+/// 属性格式示例：#[stage = "VERTEX | FRAGMENT"]
+fn get_stage_value(attrs: &[Attribute]) -> syn::Result<syn::Expr> {
+    for attr in attrs {
+        if attr.path().is_ident("stage") {
+            return parse_flags_attr(attr, "ShaderStageFlags");
         }
     }
 
     // 默认值：顶点和片段着色器
-    syn::parse_quote!(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT)
+    Ok(syn::parse_quote!(::ash::vk::ShaderStageFlags::VERTEX | ::ash::vk::ShaderStageFlags::FRAGMENT))
+}
+
+/// 将 `"A | B"` 按 `|` 拆分并去掉两侧的空白，`"A|B"` 与 `"A | B"` 等价
+fn split_flags(flags: &str) -> impl Iterator<Item = &str> {
+    flags.split('|').map(str::trim)
+}
+
+/// 将 `#[name = "A | B"]` 形式的属性解析为 `::ash::vk::<flags_type>::A | ::ash::vk::<flags_type>::B`
+fn parse_flags_attr(attr: &Attribute, flags_type: &str) -> syn::Result<syn::Expr> {
+    let Meta::NameValue(meta) = &attr.meta else {
+        return Err(syn::Error::new_spanned(attr, "属性的格式需要是 #[name = \"A | B\"]"));
+    };
+    let syn::Expr::Lit(syn::ExprLit {
+        lit: syn::Lit::Str(lit_str),
+        ..
+    }) = &meta.value
+    else {
+        return Err(syn::Error::new_spanned(&meta.value, "属性的值需要是字符串，例如 \"VERTEX | FRAGMENT\""));
+    };
+
+    let value = lit_str.value();
+    let flags =
+        split_flags(&value).map(|s| format!("::ash::vk::{}::{}", flags_type, s)).collect::<Vec<_>>().join(" | ");
+    syn::parse_str(&flags)
+        .map_err(|_| syn::Error::new_spanned(lit_str, format!("无法解析的 {}: {}", flags_type, value)))
 }
 
 /// 从字段属性中获取 flags 值
 ///
 /// 属性格式示例：#[flags = "UPDATE_AFTER_BIND | PARTIALLY_BOUND"]
-fn get_flags_value(attrs: &[Attribute]) -> syn::Expr {
+fn get_flags_value(attrs: &[Attribute]) -> syn::Result<syn::Expr> {
+    for attr in attrs {
+        if attr.path().is_ident("flags") {
+            return parse_flags_attr(attr, "DescriptorBindingFlags");
+        }
+    }
+
+    // 默认值：空标志
+    Ok(syn::parse_quote!(::ash::vk::DescriptorBindingFlags::empty()))
+}
+
+/// flags 属性中是否包含指定的标志
+fn has_flag(attrs: &[Attribute], flag: &str) -> bool {
     for attr in attrs {
         if attr.path().is_ident("flags")
             && let Meta::NameValue(meta) = &attr.meta
@@ -180,16 +279,309 @@ fn get_flags_value(attrs: &[Attribute]) -> syn::Expr {
                 ..
             }) = &meta.value
         {
-            let flags = lit_str.value();
-            let binding_flags = flags
-                .split(" | ")
-                .map(|s| format!("vk::DescriptorBindingFlags::{}", s))
-                .collect::<Vec<_>>()
-                .join(" | ");
-            return syn::parse_str(&binding_flags).unwrap();
+            return split_flags(&lit_str.value()).any(|s| s == flag);
         }
     }
+    false
+}
 
-    // 默认值：空标志
-    syn::parse_quote!(vk::DescriptorBindingFlags::empty())
+/// 为结构体实现 PushConstantLayout 派生宏
+///
+/// 结构体需要是 `#[repr(C)]`，编译期会检查大小是 16 字节的整数倍，并且不超过 Vulkan 保证的 128 字节。
+///
+/// 支持的属性：
+/// - stage: 指定字段可见的着色器阶段（如 `"VERTEX | FRAGMENT"`）；
+///   标注在结构体上时作为所有字段的默认值，都没有标注时为顶点和片段着色器
+#[proc_macro_derive(PushConstant, attributes(stage))]
+pub fn derive_push_constant(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_push_constant(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+fn expand_push_constant(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let struct_name = &input.ident;
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    struct_name,
+                    format!("{}: push constant 只支持具名字段", struct_name),
+                ));
+            }
+        },
+        _ => return Err(syn::Error::new_spanned(struct_name, format!("{}: push constant 只支持结构体", struct_name))),
+    };
+
+    if !is_repr_c(&input.attrs) {
+        return Err(syn::Error::new_spanned(struct_name, format!("{}: push constant 需要 #[repr(C)]", struct_name)));
+    }
+
+    let default_stage = get_stage_value(&input.attrs)?;
+
+    let field_names = fields.iter().map(|field| field.ident.as_ref().unwrap()).collect::<Vec<_>>();
+    let field_types = fields.iter().map(|field| &field.ty).collect::<Vec<_>>();
+    let stages = fields
+        .iter()
+        .map(|field| {
+            if field.attrs.iter().any(|attr| attr.path().is_ident("stage")) {
+                get_stage_value(&field.attrs)
+            } else {
+                Ok(default_stage.clone())
+            }
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    let shader_decls = fields
+        .iter()
+        .map(|field| {
+            let name = field.ident.as_ref().unwrap().to_string();
+            shader_field_decl(name.trim_matches('_'), &field.ty)
+        })
+        .collect::<Vec<_>>();
+
+    let size_error = format!("{}: push constant 的大小需要是 16 字节的整数倍", struct_name);
+    let limit_error = format!("{}: push constant 超过了 Vulkan 保证的 128 字节", struct_name);
+
+    Ok(quote! {
+        const _: () = {
+            assert!(::core::mem::size_of::<#struct_name>() % 16 == 0, #size_error);
+            assert!(::core::mem::size_of::<#struct_name>() <= 128, #limit_error);
+        };
+
+        impl ::truvis_descriptor_layout_trait::PushConstantLayout for #struct_name {
+            fn struct_name() -> &'static str {
+                stringify!(#struct_name)
+            }
+
+            fn get_push_constant_items() -> Vec<::truvis_descriptor_layout_trait::PushConstantItem> {
+                vec![
+                    #(::truvis_descriptor_layout_trait::PushConstantItem {
+                        name: stringify!(#field_names).trim_matches('_'),
+                        offset: ::core::mem::offset_of!(#struct_name, #field_names) as u32,
+                        size: ::core::mem::size_of::<#field_types>() as u32,
+                        stage_flags: #stages,
+                        shader_decl: #shader_decls,
+                    }),*
+                ]
+            }
+        }
+    })
+}
+
+/// 是否标注了 `#[repr(C)]`
+fn is_repr_c(attrs: &[Attribute]) -> bool {
+    attrs.iter().filter(|attr| attr.path().is_ident("repr")).any(|attr| {
+        let mut is_c = false;
+        let _ = attr.parse_nested_meta(|meta| {
+            is_c |= meta.path.is_ident("C");
+            Ok(())
+        });
+        is_c
+    })
+}
+
+/// 字段在 shader 中的声明，例如 `float4 mouse;`、`float padding[2];`
+///
+/// 无法识别的类型会原样输出 Rust 类型，方便比对时发现
+fn shader_field_decl(name: &str, ty: &syn::Type) -> String {
+    match ty {
+        syn::Type::Array(array) => {
+            let len = &array.len;
+            format!("{} {}[{}];", shader_type_name(&array.elem), name, quote!(#len))
+        }
+        _ => format!("{} {};", shader_type_name(ty), name),
+    }
+}
+
+/// Rust 类型对应的 shader 类型
+fn shader_type_name(ty: &syn::Type) -> String {
+    let ident = match ty {
+        syn::Type::Path(path) => path.path.segments.last().map(|segment| segment.ident.to_string()),
+        _ => None,
+    };
+
+    let name = match ident.as_deref() {
+        Some("f32") => "float",
+        Some("f64") => "double",
+        Some("i32") => "int",
+        Some("u32") => "uint",
+        Some("i64") => "int64_t",
+        Some("u64") => "uint64_t",
+        Some("Vec2") => "float2",
+        Some("Vec3") => "float3",
+        Some("Vec4") => "float4",
+        Some("IVec2") => "int2",
+        Some("IVec3") => "int3",
+        Some("IVec4") => "int4",
+        Some("UVec2") => "uint2",
+        Some("UVec3") => "uint3",
+        Some("UVec4") => "uint4",
+        Some("Mat3") => "float3x3",
+        Some("Mat4") => "float4x4",
+        _ => return quote!(#ty).to_string().replace(' ', ""),
+    };
+    name.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_descriptor_binding_expansion_uses_absolute_paths() {
+        let input: DeriveInput = syn::parse_quote! {
+            struct Layout {
+                #[binding = 0]
+                #[descriptor_type = "STORAGE_IMAGE"]
+                #[stage = "RAYGEN_KHR|CLOSEST_HIT_KHR"]
+                _image: (),
+                #[binding = 1]
+                #[descriptor_type = "SAMPLED_IMAGE"]
+                #[count = "runtime"]
+                #[flags = "PARTIALLY_BOUND|VARIABLE_DESCRIPTOR_COUNT"]
+                _textures: (),
+                _not_a_binding: (),
+            }
+        };
+        let expanded = expand_descriptor_binding(&input).unwrap().to_string().replace(' ', "");
+
+        assert!(expanded.contains("descriptor_type:::ash::vk::DescriptorType::STORAGE_IMAGE,"));
+        assert!(
+            expanded.contains("::ash::vk::ShaderStageFlags::RAYGEN_KHR|::ash::vk::ShaderStageFlags::CLOSEST_HIT_KHR")
+        );
+        assert!(expanded.contains(
+            "::ash::vk::DescriptorBindingFlags::PARTIALLY_BOUND|::ash::vk::DescriptorBindingFlags::VARIABLE_DESCRIPTOR_COUNT"
+        ));
+        assert!(
+            expanded
+                .contains("count:::truvis_descriptor_layout_trait::DescriptorBindingItem::RUNTIME_COUNT_UPPER_BOUND")
+        );
+        assert!(expanded.contains("Some(1u32)"));
+        assert!(!expanded.contains("not_a_binding"));
+    }
+
+    #[test]
+    fn test_descriptor_binding_errors() {
+        let expect_error = |input: DeriveInput, message: &str| {
+            let error = expand_descriptor_binding(&input).unwrap_err();
+            assert!(error.to_string().contains(message), "{}", error);
+        };
+
+        expect_error(
+            syn::parse_quote! {
+                struct Layout {
+                    #[binding = 0]
+                    _a: (),
+                    #[binding = 0]
+                    _b: (),
+                }
+            },
+            "binding 0 被 _a 和 _b 重复使用",
+        );
+        expect_error(
+            syn::parse_quote! {
+                struct Layout {
+                    #[binding = 0]
+                    #[count = "runtime"]
+                    _textures: (),
+                }
+            },
+            "VARIABLE_DESCRIPTOR_COUNT",
+        );
+        expect_error(
+            syn::parse_quote! {
+                struct Layout {
+                    #[binding = 1]
+                    #[count = "runtime"]
+                    #[flags = "VARIABLE_DESCRIPTOR_COUNT"]
+                    _textures: (),
+                    #[binding = 2]
+                    _buffer: (),
+                }
+            },
+            "binding 编号最大",
+        );
+        expect_error(
+            syn::parse_quote! {
+                struct Layout {
+                    #[binding = "0"]
+                    _a: (),
+                }
+            },
+            "#[binding = 0]",
+        );
+        expect_error(
+            syn::parse_quote! {
+                struct Layout {
+                    #[binding = 0]
+                    #[flags = "PARTIALLY_BOUND | 1"]
+                    _a: (),
+                }
+            },
+            "DescriptorBindingFlags",
+        );
+    }
+
+    #[test]
+    fn test_push_constant_expansion_uses_absolute_paths() {
+        let input: DeriveInput = syn::parse_quote! {
+            #[repr(C)]
+            #[stage = "VERTEX | FRAGMENT"]
+            struct Pc {
+                model: [f32; 16],
+                #[stage = "FRAGMENT"]
+                tint: glam::Vec4,
+            }
+        };
+        let expanded = expand_push_constant(&input).unwrap().to_string().replace(' ', "");
+
+        assert!(expanded.contains("::ash::vk::ShaderStageFlags::VERTEX|::ash::vk::ShaderStageFlags::FRAGMENT"));
+        assert!(expanded.contains("stage_flags:::ash::vk::ShaderStageFlags::FRAGMENT,"));
+        assert!(expanded.contains("impl::truvis_descriptor_layout_trait::PushConstantLayoutforPc"));
+        assert!(expanded.contains("\"floatmodel[16];\""));
+        assert!(expanded.contains("\"float4tint;\""));
+    }
+
+    #[test]
+    fn test_push_constant_errors() {
+        let expect_error = |input: DeriveInput, message: &str| {
+            let error = expand_push_constant(&input).unwrap_err();
+            assert!(error.to_string().contains(message), "{}", error);
+        };
+
+        expect_error(
+            syn::parse_quote! {
+                struct Pc { a: u32 }
+            },
+            "#[repr(C)]",
+        );
+        expect_error(
+            syn::parse_quote! {
+                #[repr(C)]
+                struct Pc(u32);
+            },
+            "具名字段",
+        );
+        expect_error(
+            syn::parse_quote! {
+                #[repr(C)]
+                struct Pc {
+                    #[stage = 1]
+                    a: u32,
+                }
+            },
+            "字符串",
+        );
+        expect_error(
+            syn::parse_quote! {
+                #[repr(C)]
+                struct Pc {
+                    #[stage = "VERTEX | 1"]
+                    a: u32,
+                }
+            },
+            "ShaderStageFlags",
+        );
+    }
 }
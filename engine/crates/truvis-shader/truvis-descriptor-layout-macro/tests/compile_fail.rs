@@ -0,0 +1,10 @@
+//! 派生宏的编译期诊断，错误需要定位到出问题的字段上
+//!
+//! 修改诊断信息之后使用 `TRYBUILD=overwrite cargo test -p truvis-descriptor-layout-macro --test compile_fail`
+//! 更新 `tests/ui/*.stderr`
+
+#[test]
+fn derive_errors() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
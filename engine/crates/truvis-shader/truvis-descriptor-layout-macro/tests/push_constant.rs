@@ -0,0 +1,71 @@
+//! `#[derive(PushConstant)]` 生成的布局
+
+// 不导入 `ash::vk`，生成的代码需要使用绝对路径
+mod layout {
+    use truvis_descriptor_layout_macro::PushConstant;
+
+    #[repr(C)]
+    #[derive(PushConstant, Clone, Copy)]
+    #[stage = "VERTEX | FRAGMENT"]
+    pub struct MyPushConstant {
+        pub model: [f32; 16],
+        pub instance_id: u32,
+        pub _padding_0: [u32; 3],
+
+        #[stage = "FRAGMENT"]
+        pub tint: [f32; 4],
+    }
+}
+
+use ash::vk;
+use layout::MyPushConstant;
+use truvis_descriptor_layout_trait::PushConstantLayout;
+
+#[test]
+fn items_follow_field_layout() {
+    let items = MyPushConstant::get_push_constant_items();
+    let layout = items.iter().map(|item| (item.name, item.offset, item.size)).collect::<Vec<_>>();
+    assert_eq!(
+        layout,
+        [
+            ("model", 0, 64),
+            ("instance_id", 64, 4),
+            ("padding_0", 68, 12),
+            ("tint", 80, 16)
+        ]
+    );
+
+    assert_eq!(items[0].stage_flags, vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT);
+    assert_eq!(items[3].stage_flags, vk::ShaderStageFlags::FRAGMENT);
+}
+
+#[test]
+fn ranges_put_each_stage_in_one_range() {
+    let ranges = MyPushConstant::ranges();
+    assert_eq!(ranges.len(), 2);
+    assert_eq!((ranges[0].stage_flags, ranges[0].offset, ranges[0].size), (vk::ShaderStageFlags::VERTEX, 0, 80));
+    assert_eq!((ranges[1].stage_flags, ranges[1].offset, ranges[1].size), (vk::ShaderStageFlags::FRAGMENT, 0, 96));
+}
+
+#[test]
+fn push_ranges_cover_overlapping_stages() {
+    let both = vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT;
+    let expected = [(both, 0, 80), (vk::ShaderStageFlags::FRAGMENT, 80, 16)];
+    let to_tuples = |ranges: Vec<vk::PushConstantRange>| {
+        ranges.into_iter().map(|range| (range.stage_flags, range.offset, range.size)).collect::<Vec<_>>()
+    };
+
+    // fragment 可见的字段与 vertex 的 range 重叠，重叠部分需要同时指定两个 stage
+    assert_eq!(to_tuples(MyPushConstant::push_ranges(vk::ShaderStageFlags::FRAGMENT)), expected);
+    // 同时更新两个 stage 时不会丢掉 fragment 独有的部分
+    assert_eq!(to_tuples(MyPushConstant::push_ranges(both)), expected);
+    assert_eq!(to_tuples(MyPushConstant::push_ranges(vk::ShaderStageFlags::VERTEX)), [(both, 0, 80)]);
+}
+
+#[test]
+fn shader_struct_matches_fields() {
+    assert_eq!(
+        MyPushConstant::shader_struct(),
+        "struct MyPushConstant\n{\n    float model[16];\n    uint instance_id;\n    uint padding_0[3];\n    float tint[4];\n};\n"
+    );
+}
@@ -0,0 +1,13 @@
+#![allow(dead_code)]
+
+use truvis_descriptor_layout_macro::DescriptorBinding;
+
+#[derive(DescriptorBinding)]
+struct Layout {
+    #[binding = 0]
+    _uniforms: (),
+    #[binding = 0]
+    _textures: (),
+}
+
+fn main() {}
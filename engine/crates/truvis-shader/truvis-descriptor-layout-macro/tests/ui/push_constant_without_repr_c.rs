@@ -0,0 +1,10 @@
+#![allow(dead_code)]
+
+use truvis_descriptor_layout_macro::PushConstant;
+
+#[derive(PushConstant)]
+struct Params {
+    tint: [f32; 4],
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+#![allow(dead_code)]
+
+use truvis_descriptor_layout_macro::DescriptorBinding;
+
+#[derive(DescriptorBinding)]
+struct Layout {
+    #[binding = 0]
+    #[count = "runtime"]
+    #[flags = "PARTIALLY_BOUND"]
+    _textures: (),
+}
+
+fn main() {}
@@ -12,9 +12,14 @@
 //!     #[sampler(binding = 2)] sampler: SamplerHandle,
 //! }
 //! ```
+//!
+//! push constant 使用 `#[derive(PushConstant)]`，参见 [`PushConstantLayout`]。
 
 use ash::vk;
 
+mod push_constant;
+pub use push_constant::{PushConstantItem, PushConstantLayout, merge_push_constant_ranges, split_push_constant_range};
+
 /// 描述符绑定的详细信息
 #[derive(Debug, Clone, Copy)]
 pub struct DescriptorBindingItem {
@@ -25,6 +30,11 @@ pub struct DescriptorBindingItem {
     pub count: u32,
     pub flags: vk::DescriptorBindingFlags,
 }
+impl DescriptorBindingItem {
+    /// `#[count = "runtime"]` 的 binding 在 layout 中声明的数量上限，
+    /// 实际数量在分配 descriptor set 时通过 `vk::DescriptorSetVariableDescriptorCountAllocateInfo` 指定
+    pub const RUNTIME_COUNT_UPPER_BOUND: u32 = 16384;
+}
 
 /// 着色器绑定布局 trait
 ///
@@ -35,6 +45,13 @@ pub trait DescriptorBindingLayout {
     /// 由宏自动实现，返回包含名称、绑定点、描述符类型、着色器阶段的数组。
     fn get_shader_bindings() -> Vec<DescriptorBindingItem>;
 
+    /// 数量在运行时决定的 binding（`#[count = "runtime"]`），没有时为 None
+    ///
+    /// 由宏自动实现。Vulkan 要求这样的 binding 只能有一个，并且是 binding 编号最大的那个。
+    fn variable_binding() -> Option<u32> {
+        None
+    }
+
     /// 获取 Vulkan 描述符集布局绑定
     ///
     /// 不应被覆盖，使用 `get_shader_bindings()` 生成 Vulkan 所需的绑定信息。
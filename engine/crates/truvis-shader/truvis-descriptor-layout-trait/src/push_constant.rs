@@ -0,0 +1,194 @@
+//! Push constant 布局
+//!
+//! 配合 `#[derive(PushConstant)]` 宏使用，根据字段上标注的 `#[stage = "..."]` 生成 `vk::PushConstantRange`。
+
+use ash::vk;
+
+/// push constant 中一个字段的信息
+#[derive(Debug, Clone, Copy)]
+pub struct PushConstantItem {
+    pub name: &'static str,
+    pub offset: u32,
+    pub size: u32,
+    pub stage_flags: vk::ShaderStageFlags,
+    /// 字段在 shader 中的声明，例如 `float4 mouse;`
+    pub shader_decl: &'static str,
+}
+
+/// push constant 布局 trait
+///
+/// 通过 `#[derive(PushConstant)]` 自动实现，宏同时会在编译期检查结构体大小是 16 字节的整数倍，并且不超过 128 字节
+pub trait PushConstantLayout {
+    /// 结构体名称
+    fn struct_name() -> &'static str;
+
+    /// 所有字段的信息，按 offset 排列
+    fn get_push_constant_items() -> Vec<PushConstantItem>;
+
+    /// 更新 `stage` 中任意一个 stage 可见的所有字段时，每次 `vkCmdPushConstants` 使用的 range
+    ///
+    /// 参见 [`split_push_constant_range`]，需要对返回的每个 range 分别调用一次 `vkCmdPushConstants`
+    fn push_ranges(stage: vk::ShaderStageFlags) -> Vec<vk::PushConstantRange> {
+        let items = Self::get_push_constant_items();
+        let (offset, end) = items
+            .iter()
+            .filter(|item| item.stage_flags.intersects(stage))
+            .map(|item| (item.offset, item.offset + item.size))
+            .reduce(|(offset, end), (item_offset, item_end)| (offset.min(item_offset), end.max(item_end)))
+            .unwrap_or_else(|| panic!("{}: no push constant field is visible to {:?}", Self::struct_name(), stage));
+
+        split_push_constant_range(&Self::ranges(), offset, end)
+    }
+
+    /// 合并之后的所有 range，用于创建 pipeline layout
+    ///
+    /// 参见 [`merge_push_constant_ranges`]
+    fn ranges() -> Vec<vk::PushConstantRange> {
+        merge_push_constant_ranges(&Self::get_push_constant_items())
+    }
+
+    /// 对应的 slang / HLSL 结构体声明，用于和 shader 中的定义比对
+    fn shader_struct() -> String {
+        let fields = Self::get_push_constant_items()
+            .iter()
+            .map(|item| format!("    {}\n", item.shader_decl))
+            .collect::<String>();
+        format!("struct {}\n{{\n{}}};\n", Self::struct_name(), fields)
+    }
+}
+
+/// 将字段合并为 pipeline layout 使用的 range
+///
+/// Vulkan 要求每个 shader stage 最多出现在一个 range 中：
+/// 先计算每个 stage 可见的字段覆盖的范围，再将范围相同的 stage 合并为一个 range
+pub fn merge_push_constant_ranges(items: &[PushConstantItem]) -> Vec<vk::PushConstantRange> {
+    let all_stages = items.iter().fold(vk::ShaderStageFlags::empty(), |acc, item| acc | item.stage_flags);
+
+    let mut ranges: Vec<vk::PushConstantRange> = Vec::new();
+    for bit in 0..u32::BITS {
+        let stage = vk::ShaderStageFlags::from_raw(1 << bit);
+        if !all_stages.contains(stage) {
+            continue;
+        }
+
+        let (offset, end) = items
+            .iter()
+            .filter(|item| item.stage_flags.contains(stage))
+            .map(|item| (item.offset, item.offset + item.size))
+            .reduce(|(offset, end), (item_offset, item_end)| (offset.min(item_offset), end.max(item_end)))
+            .unwrap();
+        let size = end - offset;
+
+        match ranges.iter_mut().find(|range| range.offset == offset && range.size == size) {
+            Some(range) => range.stage_flags |= stage,
+            None => ranges.push(vk::PushConstantRange {
+                stage_flags: stage,
+                offset,
+                size,
+            }),
+        }
+    }
+
+    ranges.sort_by_key(|range| (range.offset, range.size));
+    ranges
+}
+
+/// 将 `[offset, end)` 切分为若干段，每段的 stage flags 为 pipeline layout 中与这一段重叠的所有 range 的 stage 之和
+///
+/// `vkCmdPushConstants` 要求更新的每个字节：与之重叠的 range 的 stage 都包含在 stage flags 中，
+/// 并且 stage flags 中的每个 stage 都有一个包含该字节的 range。
+/// 因此在所有 range 的边界处切分
+pub fn split_push_constant_range(
+    ranges: &[vk::PushConstantRange],
+    offset: u32,
+    end: u32,
+) -> Vec<vk::PushConstantRange> {
+    let mut bounds = vec![offset, end];
+    for range in ranges {
+        bounds.extend([range.offset, range.offset + range.size].into_iter().filter(|&b| offset < b && b < end));
+    }
+    bounds.sort_unstable();
+    bounds.dedup();
+
+    bounds
+        .windows(2)
+        .map(|bound| {
+            let (seg_offset, seg_end) = (bound[0], bound[1]);
+            let stage_flags = ranges
+                .iter()
+                .filter(|range| range.offset < seg_end && seg_offset < range.offset + range.size)
+                .fold(vk::ShaderStageFlags::empty(), |acc, range| acc | range.stage_flags);
+            assert!(!stage_flags.is_empty(), "push constant bytes [{seg_offset}, {seg_end}) are not in any range");
+
+            vk::PushConstantRange {
+                stage_flags,
+                offset: seg_offset,
+                size: seg_end - seg_offset,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(offset: u32, size: u32, stage_flags: vk::ShaderStageFlags) -> PushConstantItem {
+        PushConstantItem {
+            name: "",
+            offset,
+            size,
+            stage_flags,
+            shader_decl: "",
+        }
+    }
+
+    #[test]
+    fn test_same_span_stages_are_merged() {
+        let ranges = merge_push_constant_ranges(&[
+            item(0, 16, vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT),
+            item(16, 16, vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT),
+        ]);
+
+        assert_eq!(ranges.len(), 1);
+        assert_eq!(ranges[0].stage_flags, vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT);
+        assert_eq!((ranges[0].offset, ranges[0].size), (0, 32));
+    }
+
+    #[test]
+    fn test_each_stage_in_one_range() {
+        let ranges = merge_push_constant_ranges(&[
+            item(0, 64, vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT),
+            item(64, 16, vk::ShaderStageFlags::FRAGMENT),
+        ]);
+
+        assert_eq!(ranges.len(), 2);
+        assert_eq!(ranges[0].stage_flags, vk::ShaderStageFlags::VERTEX);
+        assert_eq!((ranges[0].offset, ranges[0].size), (0, 64));
+        assert_eq!(ranges[1].stage_flags, vk::ShaderStageFlags::FRAGMENT);
+        assert_eq!((ranges[1].offset, ranges[1].size), (0, 80));
+    }
+
+    #[test]
+    fn test_split_overlapping_ranges() {
+        // VERTEX 覆盖 [0, 64)，FRAGMENT 覆盖 [0, 80)
+        let ranges = merge_push_constant_ranges(&[
+            item(0, 64, vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT),
+            item(64, 16, vk::ShaderStageFlags::FRAGMENT),
+        ]);
+
+        // 与两个 range 都重叠的部分需要同时指定两个 stage，fragment 独有的部分只能指定 FRAGMENT
+        let segments = split_push_constant_range(&ranges, 0, 80);
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].stage_flags, vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT);
+        assert_eq!((segments[0].offset, segments[0].size), (0, 64));
+        assert_eq!(segments[1].stage_flags, vk::ShaderStageFlags::FRAGMENT);
+        assert_eq!((segments[1].offset, segments[1].size), (64, 16));
+
+        // 只更新 fragment 独有的部分
+        let segments = split_push_constant_range(&ranges, 64, 80);
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].stage_flags, vk::ShaderStageFlags::FRAGMENT);
+        assert_eq!((segments[0].offset, segments[0].size), (64, 16));
+    }
+}
@@ -2,6 +2,7 @@
 
 use std::sync::OnceLock;
 use truvis_crate_tools::resource::TruvisPath;
+use truvis_crate_tools::shader_variant::ShaderVariant;
 
 /// Shader 的执行阶段
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -60,6 +61,18 @@ impl EnvPath {
         PATH.get_or_init(|| TruvisPath::shader_root_path().join(".build"))
     }
 
+    /// 根据 shader 反射信息生成的代码的输出路径
+    pub fn shader_generated_path() -> &'static std::path::Path {
+        static PATH: OnceLock<std::path::PathBuf> = OnceLock::new();
+        PATH.get_or_init(|| TruvisPath::shader_root_path().join("generated"))
+    }
+
+    /// shader 变体清单的路径
+    pub fn shader_permutation_manifest_path() -> &'static std::path::Path {
+        static PATH: OnceLock<std::path::PathBuf> = OnceLock::new();
+        PATH.get_or_init(|| TruvisPath::shader_root_path().join("permutations.toml"))
+    }
+
     pub fn shader_share_path() -> &'static std::path::Path {
         static PATH: OnceLock<std::path::PathBuf> = OnceLock::new();
         PATH.get_or_init(|| TruvisPath::shader_root_path().join("share"))
@@ -103,6 +116,8 @@ pub struct ShaderCompileTask {
     pub output_path: std::path::PathBuf,
     pub shader_stage: ShaderStage,
     pub compiler_type: ShaderCompilerType,
+    /// 编译时传入的宏定义，默认变体为空
+    pub variant: ShaderVariant,
 }
 
 impl ShaderCompileTask {
@@ -114,15 +129,24 @@ impl ShaderCompileTask {
     /// # Returns
     /// 如果文件扩展名不被支持，返回 None
     pub fn new(entry: &walkdir::DirEntry) -> Option<Self> {
-        let shader_path = entry.path().to_str()?.replace('\\', "/");
+        Self::new_variant(entry.path(), ShaderVariant::default())
+    }
+
+    /// 创建某个 shader 变体的编译任务，输出文件名带有变体后缀
+    ///
+    /// # Arguments
+    /// * `shader_path` - shader 的完整路径，需要位于 entry 目录下
+    /// * `variant` - 宏定义组合
+    pub fn new_variant(shader_path: &std::path::Path, variant: ShaderVariant) -> Option<Self> {
+        let shader_path = shader_path.to_str()?.replace('\\', "/");
         let shader_path = std::path::Path::new(&shader_path);
 
         // 相对于 shader src 的路径
         let relative_path = shader_path.strip_prefix(EnvPath::shader_entry_path()).ok()?;
-        let shader_name = entry.file_name().to_str()?;
+        let shader_name = shader_path.file_name()?.to_str()?;
 
         // 构造输出路径
-        let mut output_path = EnvPath::shader_build_path().join(relative_path);
+        let mut output_path = EnvPath::shader_build_path().join(variant.apply_to_path(relative_path.to_str()?));
         let mut new_ext = output_path.extension()?.to_os_string();
         new_ext.push(".spv");
         output_path.set_extension(new_ext);
@@ -135,6 +159,7 @@ impl ShaderCompileTask {
             output_path,
             shader_stage,
             compiler_type,
+            variant,
         })
     }
 
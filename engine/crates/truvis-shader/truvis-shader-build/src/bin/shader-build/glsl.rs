@@ -32,6 +32,7 @@ impl ShaderCompiler for GlslCompiler {
                 task.output_path.to_str().unwrap(),
                 task.shader_path.to_str().unwrap(),
             ])
+            .args(task.variant.defines().iter().map(|define| format!("-D{define}")))
             .output()
             .expect("Failed to execute glslc");
 
@@ -68,6 +68,7 @@ impl ShaderCompiler for HlslCompiler {
             // SPIR-V NonSemantic Shader DebugInfo Instructions，用于 Nsight 调试
             .arg("-fspv-debug=vulkan-with-source")
             .arg("-Zi") // 包含调试信息
+            .args(task.variant.defines().iter().flat_map(|define| ["-D", define.as_str()]))
             .output()
             .expect("Failed to execute dxc");
 
@@ -0,0 +1,114 @@
+//! 增量编译：源文件以及它 include 的文件都没有修改时，跳过编译
+//!
+//! 依赖通过轻量的文本扫描得到，只识别 `#include "..."`、`#include <...>` 和 slang 的 `import a.b;`，
+//! 不处理宏展开，因此条件编译中的 include 也会被视为依赖（只会导致多编译，不会漏编译）
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::common::{EnvPath, ShaderCompileTask, ShaderCompilerType};
+use crate::reflection;
+
+/// 判断编译任务是否需要重新编译
+///
+/// 多个编译任务会在多个线程中同时查询，每个文件的直接依赖只扫描一次
+#[derive(Default)]
+pub struct IncrementalChecker {
+    /// 文件 -> 直接依赖的文件
+    direct_deps: Mutex<HashMap<PathBuf, Vec<PathBuf>>>,
+}
+// new & init
+impl IncrementalChecker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+// tools
+impl IncrementalChecker {
+    /// 输出文件不存在，或者比源文件及其任意依赖更旧时需要编译
+    ///
+    /// slang shader 的输出还包括根据反射信息生成的描述符布局，参见 [`reflection::layout_rs_path`]
+    pub fn need_compile(&self, task: &ShaderCompileTask) -> bool {
+        let Some(output_mtime) = mtime(&task.output_path) else {
+            return true;
+        };
+        if task.compiler_type == ShaderCompilerType::Slang
+            && reflection::layout_rs_path(task).is_none_or(|layout_path| !layout_path.is_file())
+        {
+            return true;
+        }
+
+        // 深度优先遍历所有依赖，任意一个文件比输出更新就需要编译
+        let mut visited = HashSet::new();
+        let mut stack = vec![task.shader_path.clone()];
+        while let Some(path) = stack.pop() {
+            if !visited.insert(path.clone()) {
+                continue;
+            }
+            // 读取不到修改时间的文件（例如刚被删除）保守地认为需要编译
+            if mtime(&path).is_none_or(|source_mtime| source_mtime > output_mtime) {
+                return true;
+            }
+            stack.extend(self.direct_deps(&path));
+        }
+
+        false
+    }
+
+    fn direct_deps(&self, path: &Path) -> Vec<PathBuf> {
+        if let Some(deps) = self.direct_deps.lock().unwrap().get(path) {
+            return deps.clone();
+        }
+
+        // 扫描时不持有锁，多个线程同时扫描同一个文件只会多做一次无害的重复工作
+        let deps = scan_deps(path);
+        self.direct_deps.lock().unwrap().insert(path.to_path_buf(), deps.clone());
+        deps
+    }
+}
+
+fn mtime(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// 扫描文件中的 include 和 import，返回能找到的依赖文件
+fn scan_deps(path: &Path) -> Vec<PathBuf> {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return vec![];
+    };
+    let dir = path.parent().unwrap_or(Path::new(""));
+
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim_start();
+            if let Some(rest) = line.strip_prefix("#include").or_else(|| line.strip_prefix("__include")) {
+                let rest = rest.trim();
+                let name = rest
+                    .strip_prefix('"')
+                    .and_then(|rest| rest.split('"').next())
+                    .or_else(|| rest.strip_prefix('<').and_then(|rest| rest.split('>').next()))?;
+                resolve(dir, &[name.to_string()])
+            } else if let Some(rest) = line.strip_prefix("import ") {
+                // slang 的 module 名称中 `.` 对应目录，`_` 也可能对应文件名中的 `-`
+                let module = rest.split(';').next()?.trim().replace('.', "/");
+                resolve(dir, &[format!("{module}.slang"), format!("{}.slang", module.replace('_', "-"))])
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// 依次在当前文件所在目录和 shader 根目录（编译器的 include 路径）中查找
+///
+/// 返回规范化的路径，避免 `./` 等写法导致同一个文件被当作不同的依赖，在循环 include 时无法停止
+fn resolve(dir: &Path, names: &[String]) -> Option<PathBuf> {
+    [dir, EnvPath::shader_root_path()]
+        .iter()
+        .flat_map(|base| names.iter().map(move |name| base.join(name)))
+        .filter(|path| path.is_file())
+        .find_map(|path| std::fs::canonicalize(path).ok())
+}
@@ -1,15 +1,24 @@
 //! Shader 编译工具
 //!
-//! 将指定目录下的所有 shader 文件编译为 SPIR-V 文件，输出到 `.build` 目录
+//! 将指定目录下的所有 shader 文件编译为 SPIR-V 文件，输出到 `.build` 目录；
+//! 同时根据 `permutations.toml` 编译 shader 的宏定义变体；
+//! slang shader 还会根据反射信息在 `generated` 目录生成描述符布局
+//!
+//! 默认只编译源文件（包括 include 的文件）比输出更新的 shader，使用 `--force` 强制全部重新编译
 
 mod common;
 mod glsl;
 mod hlsl;
+mod incremental;
+mod permutation;
+mod reflection;
 mod slang;
 
 use common::{EnvPath, ShaderCompileTask, ShaderCompiler, ShaderCompilerType};
 use glsl::GlslCompiler;
 use hlsl::HlslCompiler;
+use incremental::IncrementalChecker;
+use permutation::PermutationManifest;
 use rayon::prelude::*;
 use slang::SlangCompiler;
 use truvis_crate_tools::init_log::init_log;
@@ -26,19 +35,48 @@ fn get_compiler(compiler_type: ShaderCompilerType) -> Box<dyn ShaderCompiler> {
 fn main() {
     init_log();
 
+    let force = std::env::args().skip(1).any(|arg| arg == "--force");
+
     log::info!("Shader include path: {:?}", EnvPath::shader_share_path());
     log::info!("Shader entry path: {:?}", EnvPath::shader_entry_path());
     log::info!("Shader output path: {:?}", EnvPath::shader_build_path());
+    log::info!("Shader generated code path: {:?}", EnvPath::shader_generated_path());
 
-    // 编译 shader 目录下的所有 shader 文件
-    walkdir::WalkDir::new(EnvPath::shader_entry_path())
+    // shader 目录下的所有 shader 文件
+    let mut tasks = walkdir::WalkDir::new(EnvPath::shader_entry_path())
         .into_iter()
         .filter_map(Result::ok)
         .filter(|entry| entry.path().is_file())
         .filter_map(|entry| ShaderCompileTask::new(&entry))
-        .par_bridge() // 并行化编译
+        .collect::<Vec<_>>();
+
+    // 清单中声明的变体
+    let permutation_tasks = PermutationManifest::load().compile_tasks();
+    log::info!("Shader permutations: {}", permutation_tasks.len());
+    tasks.extend(permutation_tasks);
+
+    // 输出路径相同的任务只编译一次（例如变体后缀恰好与某个已有的 shader 同名）
+    let mut output_paths = std::collections::HashSet::new();
+    tasks.retain(|task| {
+        let is_new = output_paths.insert(task.output_path.clone());
+        if !is_new {
+            log::warn!("Skip shader with duplicated output: {:?}", task.output_path);
+        }
+        is_new
+    });
+
+    // 跳过源文件和依赖都没有修改的 shader
+    if !force {
+        let checker = IncrementalChecker::new();
+        let task_cnt = tasks.len();
+        tasks = tasks.into_par_iter().filter(|task| checker.need_compile(task)).collect();
+        log::info!("Skip {} up-to-date shaders, use --force to rebuild all", task_cnt - tasks.len());
+    }
+
+    tasks
+        .par_iter() // 并行化编译
         .for_each(|task| {
-            log::info!("Compiling shader: {:?}", task.shader_path);
+            log::info!("Compiling shader: {:?} {:?}", task.shader_path, task.variant.defines());
 
             // 确保输出目录存在
             if let Some(parent) = task.output_path.parent() {
@@ -46,7 +84,7 @@ fn main() {
             }
 
             let compiler = get_compiler(task.compiler_type);
-            compiler.compile(&task);
+            compiler.compile(task);
         });
 
     log::info!("Shader compilation completed.");
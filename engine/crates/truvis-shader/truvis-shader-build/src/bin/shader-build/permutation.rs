@@ -0,0 +1,90 @@
+//! Shader 变体（permutation）清单
+//!
+//! 在 `shader/permutations.toml` 中声明每个 shader 需要额外编译的宏定义组合：
+//!
+//! ```toml
+//! [[shaders]]
+//! path = "phong/phong.ps.slang"  # 相对于 entry 目录
+//! variants = [
+//!     ["SHADOWS_ON"],
+//!     ["SHADOWS_ON", "LIGHT_COUNT=4"],
+//! ]
+//! ```
+//!
+//! 每个变体输出为带后缀的 spv，例如 `phong/phong_SHADOWS_ON.ps.slang.spv`，
+//! 命名规则参见 [`ShaderVariant`]。
+
+use std::collections::BTreeSet;
+
+use serde::Deserialize;
+use truvis_crate_tools::shader_variant::ShaderVariant;
+
+use crate::common::{EnvPath, ShaderCompileTask};
+
+/// 清单文件的内容
+#[derive(Debug, Deserialize)]
+pub struct PermutationManifest {
+    #[serde(default)]
+    pub shaders: Vec<ShaderPermutation>,
+}
+
+/// 单个 shader 的变体声明
+#[derive(Debug, Deserialize)]
+pub struct ShaderPermutation {
+    /// 相对于 entry 目录的路径
+    pub path: String,
+    /// 每个元素是一组宏定义，形如 `NAME` 或 `NAME=VALUE`
+    pub variants: Vec<Vec<String>>,
+}
+
+impl PermutationManifest {
+    /// 读取清单，文件不存在时视为没有任何变体
+    pub fn load() -> Self {
+        let manifest_path = EnvPath::shader_permutation_manifest_path();
+        if !manifest_path.exists() {
+            log::info!("No shader permutation manifest: {:?}", manifest_path);
+            return Self { shaders: vec![] };
+        }
+
+        let content = std::fs::read_to_string(manifest_path)
+            .unwrap_or_else(|e| panic!("Failed to read shader permutation manifest {manifest_path:?}: {e}"));
+        toml::from_str(&content)
+            .unwrap_or_else(|e| panic!("Failed to parse shader permutation manifest {manifest_path:?}: {e}"))
+    }
+
+    /// 生成所有变体的编译任务
+    ///
+    /// 同一个 shader 中规范化后相同的宏定义组合只会保留一份；
+    /// 没有宏定义的变体与原始 shader 相同，由常规编译流程负责，这里直接跳过
+    pub fn compile_tasks(&self) -> Vec<ShaderCompileTask> {
+        let mut variants = BTreeSet::new();
+        for shader in &self.shaders {
+            for defines in &shader.variants {
+                let variant = ShaderVariant::new(defines);
+                if variant.is_default() {
+                    continue;
+                }
+                if !variants.insert((shader.path.replace('\\', "/"), variant)) {
+                    log::warn!("Duplicated shader permutation: {} {:?}", shader.path, defines);
+                }
+            }
+        }
+
+        variants
+            .into_iter()
+            .filter_map(|(path, variant)| {
+                let shader_path = EnvPath::shader_entry_path().join(&path);
+                if !shader_path.is_file() {
+                    log::error!("Shader permutation source not found: {:?}", shader_path);
+                    return None;
+                }
+
+                let task = ShaderCompileTask::new_variant(&shader_path, variant);
+                if task.is_none() {
+                    log::error!("Unsupported shader permutation source: {:?}", shader_path);
+                }
+                task
+            })
+            .collect()
+    }
+}
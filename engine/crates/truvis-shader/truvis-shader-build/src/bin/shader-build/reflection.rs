@@ -0,0 +1,510 @@
+//! 根据 slang 的反射信息生成描述符布局
+//!
+//! slangc 通过 `-reflection-json` 输出 shader 参数的反射信息，
+//! 这里解析出每个 descriptor binding 的编号、类型、数量，以及实际使用它的 entry point stage，
+//! 为 pass 自己的每个 descriptor set（slang 中的 space）生成一个 `#[derive(DescriptorBinding)]` 的结构体，
+//! 写入 `generated/` 目录下的 `*.layout.rs` 文件，例如：
+//!
+//! ```text
+//! .build/rt/raygen.slang.spv -> generated/rt/raygen.slang.layout.rs
+//! ```
+//!
+//! 全局的 descriptor set（space 小于 [`GLOBAL_SETS_COUNT`]）由 `GlobalDescriptorSets` 统一管理，不会生成。
+//! 生成的文件需要包含在单独的 module 中使用，`generated/` 不纳入版本管理，需要先执行 shader-build：
+//!
+//! ```ignore
+//! mod layout {
+//!     include!(concat!(env!("CARGO_MANIFEST_DIR"), "/../../shader/generated/pp/ray_query_shadow.slang.layout.rs"));
+//! }
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use serde::Deserialize;
+
+use crate::common::{EnvPath, ShaderCompileTask};
+
+/// 全局 descriptor set 的数量，与 `share/global_binding_sets.slangi` 中的 `GLOBAL_SETS_COUNT` 保持一致
+const GLOBAL_SETS_COUNT: u32 = 3;
+
+/// `-reflection-json` 输出的内容，只包含生成布局需要的部分
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SlangReflection {
+    #[serde(default)]
+    parameters: Vec<SlangParameter>,
+    #[serde(default)]
+    entry_points: Vec<SlangEntryPoint>,
+}
+
+/// 全局的 shader 参数
+#[derive(Debug, Deserialize)]
+struct SlangParameter {
+    name: String,
+    /// 只占用一种资源的参数才有 binding；ParameterBlock 这类参数的信息在 `bindings` 中，暂不支持
+    binding: Option<SlangBinding>,
+    #[serde(rename = "type")]
+    ty: SlangType,
+}
+
+#[derive(Debug, Deserialize)]
+struct SlangBinding {
+    /// 只处理 `descriptorTableSlot`，push constant、uniform 等参数会被跳过
+    kind: String,
+    #[serde(default)]
+    space: u32,
+    #[serde(default)]
+    index: u32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SlangType {
+    kind: String,
+    /// resource 的形状，例如 `texture2D`、`structuredBuffer`
+    base_shape: Option<String>,
+    /// resource 的访问方式，例如 `read`、`readWrite`
+    access: Option<String>,
+    /// 是否为带有 sampler 的纹理（`Sampler2D` 等）
+    #[serde(default)]
+    combined: bool,
+    /// 数组的长度，0 表示运行时决定长度的数组
+    element_count: Option<u32>,
+    element_type: Option<Box<SlangType>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SlangEntryPoint {
+    name: String,
+    stage: String,
+    /// 每个全局参数是否被这个 entry point 使用；没有该信息时认为使用了所有参数
+    bindings: Option<Vec<SlangEntryPointBinding>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SlangEntryPointBinding {
+    name: String,
+    binding: SlangBindingUsage,
+}
+
+#[derive(Debug, Deserialize)]
+struct SlangBindingUsage {
+    /// slangc 输出 0 或 1
+    #[serde(default)]
+    used: u32,
+}
+
+/// 生成的结构体中的一个字段
+struct LayoutBinding {
+    name: String,
+    binding: u32,
+    descriptor_type: &'static str,
+    /// None 表示运行时决定的数量
+    count: Option<u32>,
+    /// `vk::ShaderStageFlags` 的名称，以 ` | ` 连接
+    stages: String,
+}
+
+// tools
+impl SlangType {
+    /// 对应的 `vk::DescriptorType` 名称，以及 descriptor 的数量
+    ///
+    /// 数组的数量为 None 表示运行时决定数量；不能放进 descriptor set 的类型返回 None
+    fn descriptor_type(&self) -> Option<(&'static str, Option<u32>)> {
+        let descriptor_type = match self.kind.as_str() {
+            "array" => {
+                let (descriptor_type, _) = self.element_type.as_ref()?.descriptor_type()?;
+                let count = self.element_count.filter(|cnt| *cnt > 0);
+                return Some((descriptor_type, count));
+            }
+            "constantBuffer" => "UNIFORM_BUFFER",
+            "samplerState" => "SAMPLER",
+            "resource" => {
+                let base_shape = self.base_shape.as_deref()?;
+                let read_write = self.access.as_deref().is_some_and(|access| access != "read");
+                match base_shape {
+                    "structuredBuffer" | "byteAddressBuffer" => "STORAGE_BUFFER",
+                    "accelerationStructure" => "ACCELERATION_STRUCTURE_KHR",
+                    "textureBuffer" if read_write => "STORAGE_TEXEL_BUFFER",
+                    "textureBuffer" => "UNIFORM_TEXEL_BUFFER",
+                    _ if !base_shape.starts_with("texture") => return None,
+                    _ if self.combined => "COMBINED_IMAGE_SAMPLER",
+                    _ if read_write => "STORAGE_IMAGE",
+                    _ => "SAMPLED_IMAGE",
+                }
+            }
+            _ => return None,
+        };
+        Some((descriptor_type, Some(1)))
+    }
+}
+
+// tools
+impl SlangEntryPoint {
+    /// 这个 entry point 是否使用了名为 `parameter` 的全局参数
+    fn uses(&self, parameter: &str) -> bool {
+        let Some(bindings) = &self.bindings else {
+            return true;
+        };
+        bindings.iter().any(|binding| binding.name == parameter && binding.binding.used != 0)
+    }
+}
+
+/// slang 的 stage 名称对应的 `vk::ShaderStageFlags` 名称
+fn stage_flag(stage: &str) -> Option<&'static str> {
+    let flag = match stage {
+        "vertex" => "VERTEX",
+        "hull" => "TESSELLATION_CONTROL",
+        "domain" => "TESSELLATION_EVALUATION",
+        "geometry" => "GEOMETRY",
+        "fragment" => "FRAGMENT",
+        "compute" => "COMPUTE",
+        "raygeneration" => "RAYGEN_KHR",
+        "intersection" => "INTERSECTION_KHR",
+        "anyhit" => "ANY_HIT_KHR",
+        "closesthit" => "CLOSEST_HIT_KHR",
+        "miss" => "MISS_KHR",
+        "callable" => "CALLABLE_KHR",
+        "amplification" => "TASK_EXT",
+        "mesh" => "MESH_EXT",
+        _ => return None,
+    };
+    Some(flag)
+}
+
+/// `raygen.slang` 和 `sdr-to-hdr.slang` 分别得到 `Raygen` 和 `SdrToHdr`
+fn struct_name_prefix(shader_path: &std::path::Path) -> String {
+    let file_name = shader_path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    let stem = file_name.split('.').next().unwrap_or_default();
+    stem.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map(|c| c.to_ascii_uppercase()).into_iter().chain(chars).collect::<String>()
+        })
+        .collect()
+}
+
+/// 根据 `task` 的输出路径得到反射信息的输出路径：`xxx.slang.spv` -> `xxx.slang.reflection.json`
+pub fn reflection_json_path(task: &ShaderCompileTask) -> std::path::PathBuf {
+    task.output_path.with_extension("reflection.json")
+}
+
+/// slang 的参数名转换为 snake case 的字段名，例如 `ray_query_shadow::tlas` 和 `gTlas` 分别得到
+/// `ray_query_shadow_tlas` 和 `gtlas`
+fn field_name(parameter: &str) -> String {
+    parameter
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_ascii_lowercase())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// 根据 `task` 的输出路径得到生成代码的路径：`.build/xxx.slang.spv` -> `generated/xxx.slang.layout.rs`
+pub fn layout_rs_path(task: &ShaderCompileTask) -> Option<std::path::PathBuf> {
+    let relative_path = task.output_path.strip_prefix(EnvPath::shader_build_path()).ok()?;
+    Some(EnvPath::shader_generated_path().join(relative_path).with_extension("layout.rs"))
+}
+
+/// 读取 slangc 输出的反射信息，生成描述符布局的代码
+pub fn generate_layout(task: &ShaderCompileTask) {
+    let json_path = reflection_json_path(task);
+    let Ok(content) = std::fs::read_to_string(&json_path) else {
+        log::error!("Failed to read slang reflection: {:?}", json_path);
+        return;
+    };
+    let reflection: SlangReflection = match serde_json::from_str(&content) {
+        Ok(reflection) => reflection,
+        Err(e) => {
+            log::error!("Failed to parse slang reflection {:?}: {}", json_path, e);
+            return;
+        }
+    };
+    let Some(layout_path) = layout_rs_path(task) else {
+        log::error!("Unexpected shader output path: {:?}", task.output_path);
+        return;
+    };
+
+    let code = layout_code(task, &reflection);
+    if let Some(parent) = layout_path.parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+    if let Err(e) = std::fs::write(&layout_path, code) {
+        log::error!("Failed to write descriptor layout {:?}: {}", layout_path, e);
+    }
+}
+
+/// 生成 `*.layout.rs` 的内容，pass 自己的每个 space 对应一个结构体
+///
+/// 每个 binding 的 stage 为实际使用它的 entry point 的 stage；没有任何 entry point 使用的 binding
+/// 保守地使用全部 entry point 的 stage
+fn layout_code(task: &ShaderCompileTask, reflection: &SlangReflection) -> String {
+    let entry_stages = reflection
+        .entry_points
+        .iter()
+        .filter_map(|entry| {
+            let flag = stage_flag(&entry.stage);
+            if flag.is_none() {
+                log::warn!("{:?}: unknown stage {} of entry {}", task.shader_path, entry.stage, entry.name);
+            }
+            Some((entry, flag?))
+        })
+        .collect::<Vec<_>>();
+    let all_stages = entry_stages.iter().map(|(_, flag)| *flag).collect::<BTreeSet<_>>();
+
+    // space -> bindings
+    let mut sets: BTreeMap<u32, Vec<LayoutBinding>> = BTreeMap::new();
+    for parameter in &reflection.parameters {
+        let Some(binding) = parameter.binding.as_ref().filter(|binding| binding.kind == "descriptorTableSlot") else {
+            continue;
+        };
+        if binding.space < GLOBAL_SETS_COUNT {
+            continue;
+        }
+        let Some((descriptor_type, count)) = parameter.ty.descriptor_type() else {
+            log::warn!("{:?}: unsupported descriptor type of {}", task.shader_path, parameter.name);
+            continue;
+        };
+
+        let mut stages = entry_stages
+            .iter()
+            .filter(|(entry, _)| entry.uses(&parameter.name))
+            .map(|(_, flag)| *flag)
+            .collect::<BTreeSet<_>>();
+        if stages.is_empty() {
+            stages = all_stages.clone();
+        }
+
+        sets.entry(binding.space).or_default().push(LayoutBinding {
+            name: field_name(&parameter.name),
+            binding: binding.index,
+            descriptor_type,
+            count,
+            stages: stages.into_iter().collect::<Vec<_>>().join(" | "),
+        });
+    }
+
+    let prefix = struct_name_prefix(&task.shader_path);
+    let source = task.shader_path.strip_prefix(EnvPath::shader_entry_path()).unwrap_or(&task.shader_path);
+
+    let mut code = String::new();
+    writeln!(code, "// 由 shader-build 根据 slang 的反射信息生成，不要手动修改").unwrap();
+    writeln!(code, "// source: {}", source.display()).unwrap();
+    if !task.variant.is_default() {
+        writeln!(code, "// defines: {:?}", task.variant.defines()).unwrap();
+    }
+    writeln!(code).unwrap();
+    if sets.is_empty() {
+        return code;
+    }
+    writeln!(code, "use truvis_descriptor_layout_macro::DescriptorBinding;").unwrap();
+
+    for (space, mut bindings) in sets {
+        bindings.sort_by_key(|binding| binding.binding);
+
+        writeln!(code).unwrap();
+        writeln!(code, "/// set {}", space).unwrap();
+        writeln!(code, "#[derive(DescriptorBinding)]").unwrap();
+        writeln!(code, "pub struct {}Set{}Layout {{", prefix, space).unwrap();
+        for binding in bindings {
+            writeln!(code, "    #[binding = {}]", binding.binding).unwrap();
+            writeln!(code, "    #[descriptor_type = \"{}\"]", binding.descriptor_type).unwrap();
+            match binding.count {
+                Some(count) => writeln!(code, "    #[count = {}]", count).unwrap(),
+                None => {
+                    writeln!(code, "    #[count = \"runtime\"]").unwrap();
+                    writeln!(code, "    #[flags = \"PARTIALLY_BOUND | VARIABLE_DESCRIPTOR_COUNT\"]").unwrap();
+                }
+            }
+            if !binding.stages.is_empty() {
+                writeln!(code, "    #[stage = \"{}\"]", binding.stages).unwrap();
+            }
+            writeln!(code, "    _{}: (),", binding.name).unwrap();
+        }
+        writeln!(code, "}}").unwrap();
+    }
+
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use truvis_crate_tools::shader_variant::ShaderVariant;
+
+    use super::*;
+    use crate::common::{ShaderCompilerType, ShaderStage};
+
+    fn task(relative_path: &str) -> ShaderCompileTask {
+        ShaderCompileTask {
+            shader_path: EnvPath::shader_entry_path().join(relative_path),
+            output_path: EnvPath::shader_build_path().join(format!("{relative_path}.spv")),
+            shader_stage: ShaderStage::General,
+            compiler_type: ShaderCompilerType::Slang,
+            variant: ShaderVariant::default(),
+        }
+    }
+
+    fn parse(json: &str) -> SlangReflection {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn parse_type(json: &str) -> SlangType {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_descriptor_type_of_resources() {
+        let cases = [
+            (r#"{"kind": "constantBuffer"}"#, Some(("UNIFORM_BUFFER", Some(1)))),
+            (r#"{"kind": "samplerState"}"#, Some(("SAMPLER", Some(1)))),
+            (
+                r#"{"kind": "resource", "baseShape": "accelerationStructure"}"#,
+                Some(("ACCELERATION_STRUCTURE_KHR", Some(1))),
+            ),
+            (r#"{"kind": "resource", "baseShape": "structuredBuffer"}"#, Some(("STORAGE_BUFFER", Some(1)))),
+            (r#"{"kind": "resource", "baseShape": "texture2D"}"#, Some(("SAMPLED_IMAGE", Some(1)))),
+            (r#"{"kind": "resource", "baseShape": "texture2D", "access": "read"}"#, Some(("SAMPLED_IMAGE", Some(1)))),
+            (
+                r#"{"kind": "resource", "baseShape": "texture2D", "access": "readWrite"}"#,
+                Some(("STORAGE_IMAGE", Some(1))),
+            ),
+            (
+                r#"{"kind": "resource", "baseShape": "texture2D", "combined": true}"#,
+                Some(("COMBINED_IMAGE_SAMPLER", Some(1))),
+            ),
+            (r#"{"kind": "struct"}"#, None),
+        ];
+        for (json, expected) in cases {
+            assert_eq!(parse_type(json).descriptor_type(), expected, "{}", json);
+        }
+    }
+
+    #[test]
+    fn test_descriptor_type_of_arrays() {
+        let fixed = parse_type(r#"{"kind": "array", "elementCount": 4, "elementType": {"kind": "samplerState"}}"#);
+        assert_eq!(fixed.descriptor_type(), Some(("SAMPLER", Some(4))));
+
+        // elementCount 为 0 表示运行时决定长度
+        let runtime = parse_type(
+            r#"{"kind": "array", "elementCount": 0, "elementType": {"kind": "resource", "baseShape": "texture2D"}}"#,
+        );
+        assert_eq!(runtime.descriptor_type(), Some(("SAMPLED_IMAGE", None)));
+    }
+
+    #[test]
+    fn test_struct_and_field_names() {
+        assert_eq!(struct_name_prefix(std::path::Path::new("rt/raygen.slang")), "Raygen");
+        assert_eq!(struct_name_prefix(std::path::Path::new("pp/sdr-to-hdr.slang")), "SdrToHdr");
+        assert_eq!(struct_name_prefix(std::path::Path::new("pp/ray_query_shadow.slang")), "RayQueryShadow");
+
+        assert_eq!(field_name("tlas"), "tlas");
+        assert_eq!(field_name("ray_query_shadow::tlas"), "ray_query_shadow_tlas");
+        assert_eq!(field_name("gTlas"), "gtlas");
+    }
+
+    #[test]
+    fn test_layout_uses_per_binding_stages() {
+        let reflection = parse(
+            r#"{
+                "parameters": [
+                    {"name": "tlas", "binding": {"kind": "descriptorTableSlot", "space": 3, "index": 0},
+                     "type": {"kind": "resource", "baseShape": "accelerationStructure"}},
+                    {"name": "output", "binding": {"kind": "descriptorTableSlot", "space": 3, "index": 1},
+                     "type": {"kind": "resource", "baseShape": "texture2D", "access": "readWrite"}},
+                    {"name": "unused", "binding": {"kind": "descriptorTableSlot", "space": 3, "index": 2},
+                     "type": {"kind": "resource", "baseShape": "structuredBuffer"}}
+                ],
+                "entryPoints": [
+                    {"name": "main_ray_gen", "stage": "raygeneration", "bindings": [
+                        {"name": "tlas", "binding": {"kind": "descriptorTableSlot", "index": 0, "used": 1}},
+                        {"name": "output", "binding": {"kind": "descriptorTableSlot", "index": 1, "used": 1}},
+                        {"name": "unused", "binding": {"kind": "descriptorTableSlot", "index": 2, "used": 0}}
+                    ]},
+                    {"name": "main_closest_hit", "stage": "closesthit", "bindings": [
+                        {"name": "tlas", "binding": {"kind": "descriptorTableSlot", "index": 0, "used": 1}},
+                        {"name": "output", "binding": {"kind": "descriptorTableSlot", "index": 1, "used": 0}},
+                        {"name": "unused", "binding": {"kind": "descriptorTableSlot", "index": 2, "used": 0}}
+                    ]}
+                ]
+            }"#,
+        );
+        let code = layout_code(&task("rt/raygen.slang"), &reflection);
+
+        assert!(code.contains("pub struct RaygenSet3Layout {"), "{}", code);
+        let expected = [
+            "    #[binding = 0]",
+            "    #[descriptor_type = \"ACCELERATION_STRUCTURE_KHR\"]",
+            "    #[count = 1]",
+            "    #[stage = \"CLOSEST_HIT_KHR | RAYGEN_KHR\"]",
+            "    _tlas: (),",
+            "    #[binding = 1]",
+            "    #[descriptor_type = \"STORAGE_IMAGE\"]",
+            "    #[count = 1]",
+            "    #[stage = \"RAYGEN_KHR\"]",
+            "    _output: (),",
+            // 没有 entry point 使用的 binding 保守地使用全部 stage
+            "    #[binding = 2]",
+            "    #[descriptor_type = \"STORAGE_BUFFER\"]",
+            "    #[count = 1]",
+            "    #[stage = \"CLOSEST_HIT_KHR | RAYGEN_KHR\"]",
+            "    _unused: (),",
+        ]
+        .join("\n");
+        assert!(code.contains(&expected), "{}", code);
+    }
+
+    #[test]
+    fn test_layout_without_usage_uses_all_stages() {
+        let reflection = parse(
+            r#"{
+                "parameters": [
+                    {"name": "tlas", "binding": {"kind": "descriptorTableSlot", "space": 3, "index": 0},
+                     "type": {"kind": "resource", "baseShape": "accelerationStructure"}}
+                ],
+                "entryPoints": [
+                    {"name": "vs_main", "stage": "vertex"},
+                    {"name": "ps_main", "stage": "fragment"}
+                ]
+            }"#,
+        );
+        let code = layout_code(&task("debug/tlas.slang"), &reflection);
+        assert!(code.contains("    #[stage = \"FRAGMENT | VERTEX\"]\n    _tlas: (),"), "{}", code);
+    }
+
+    #[test]
+    fn test_layout_skips_global_sets() {
+        let reflection = parse(
+            r#"{
+                "parameters": [
+                    {"name": "bindless_srvs", "binding": {"kind": "descriptorTableSlot", "space": 1, "index": 2},
+                     "type": {"kind": "array", "elementCount": 0,
+                              "elementType": {"kind": "resource", "baseShape": "texture2D"}}},
+                    {"name": "g_params", "binding": {"kind": "pushConstantBuffer", "index": 0},
+                     "type": {"kind": "constantBuffer"}},
+                    {"name": "history", "binding": {"kind": "descriptorTableSlot", "space": 3, "index": 0},
+                     "type": {"kind": "array", "elementCount": 0,
+                              "elementType": {"kind": "resource", "baseShape": "texture2D"}}}
+                ],
+                "entryPoints": [{"name": "main", "stage": "compute"}]
+            }"#,
+        );
+        let code = layout_code(&task("pp/taa.slang"), &reflection);
+
+        assert!(!code.contains("Set1Layout"), "{}", code);
+        assert!(!code.contains("g_params"), "{}", code);
+        assert!(code.contains("pub struct TaaSet3Layout {"), "{}", code);
+        assert!(code.contains("    #[count = \"runtime\"]\n"), "{}", code);
+        assert!(code.contains("    #[flags = \"PARTIALLY_BOUND | VARIABLE_DESCRIPTOR_COUNT\"]\n"), "{}", code);
+    }
+
+    #[test]
+    fn test_layout_without_pass_sets_is_header_only() {
+        let reflection = parse(r#"{"parameters": [], "entryPoints": [{"name": "main", "stage": "compute"}]}"#);
+        let code = layout_code(&task("pp/sdr.slang"), &reflection);
+        assert!(code.starts_with("// 由 shader-build 根据 slang 的反射信息生成"));
+        assert!(code.contains("// source: pp/sdr.slang\n"), "{}", code);
+        assert!(!code.contains("struct"), "{}", code);
+    }
+}
@@ -1,8 +1,9 @@
 //! Slang 着色器编译器
 //!
-//! 使用 slangc 将 Slang 着色器编译为 SPIR-V
+//! 使用 slangc 将 Slang 着色器编译为 SPIR-V，同时输出反射信息并生成描述符布局，参见 [`crate::reflection`]
 
 use crate::common::{EnvPath, ShaderCompileTask, ShaderCompiler, ShaderCompilerType};
+use crate::reflection;
 
 /// Slang 编译器
 ///
@@ -33,11 +34,20 @@ impl ShaderCompiler for SlangCompiler {
                 "spirv", // 如果想要输出字节码：spirv-asm
                 "-o",
                 task.output_path.to_str().unwrap(),
+                "-reflection-json",
+                reflection::reflection_json_path(task).to_str().unwrap(),
                 task.shader_path.to_str().unwrap(),
             ])
+            .args(task.variant.defines().iter().flat_map(|define| ["-D", define.as_str()]))
             .output()
             .expect("Failed to execute slangc");
 
+        let success = output.status.success();
         self.process_cmd_output(output);
+
+        // 编译成功之后，根据反射信息生成描述符布局
+        if success {
+            reflection::generate_layout(task);
+        }
     }
 }
@@ -0,0 +1,219 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{Attribute, Data, DeriveInput, Fields, LitStr, parse_macro_input};
+
+/// 为结构体实现 `truvis_ui_edit_trait::UiEdit`，每个字段生成一个 imgui 编辑控件
+///
+/// 字段的类型需要实现 `truvis_ui_edit_trait::UiEditField`。
+///
+/// 支持的属性 `#[ui(...)]`：
+/// - `skip`：不生成控件
+/// - `label = "..."`：控件的标签，默认由字段名转换而来：`height_falloff` -> `Height Falloff`
+/// - `min = ..., max = ...`：使用 slider，需要同时指定
+/// - `speed = ...`：drag 的速度
+/// - `format = "..."`：数值的显示格式，例如 `"%.4f"`
+/// - `color`：`[f32; 3]` / `[f32; 4]` 使用 color picker
+/// - `enabled_by = "field"`：仅当 bool 字段 `field` 为 true 时可编辑
+#[proc_macro_derive(UiEdit, attributes(ui))]
+pub fn derive_ui_edit(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_ui_edit(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+fn expand_ui_edit(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let struct_name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    // 只处理结构体类型，且只支持具名字段
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(syn::Error::new_spanned(struct_name, "UiEdit only supports structs with named fields")),
+        },
+        _ => return Err(syn::Error::new_spanned(struct_name, "UiEdit only supports structs")),
+    };
+
+    let mut field_widgets = Vec::new();
+    for field in fields {
+        let field_name = field.ident.as_ref().unwrap();
+        let attr = parse_ui_attr(&field.attrs)?;
+        if attr.skip {
+            continue;
+        }
+
+        let label = attr.label.unwrap_or_else(|| default_label(&field_name.to_string()));
+        let range = match (attr.min, attr.max) {
+            (Some(min), Some(max)) => quote! { Some(((#min) as f64, (#max) as f64)) },
+            (None, None) => quote! { None },
+            _ => return Err(syn::Error::new_spanned(field_name, "`min` and `max` must be specified together")),
+        };
+        let speed = match attr.speed {
+            Some(speed) => quote! { Some((#speed) as f32) },
+            None => quote! { None },
+        };
+        let format = match attr.format {
+            Some(format) => quote! { Some(#format) },
+            None => quote! { None },
+        };
+        let color = attr.color;
+
+        let edit = quote! {
+            changed |= truvis_ui_edit_trait::UiEditField::edit_field(
+                &mut self.#field_name,
+                ui,
+                #label,
+                &truvis_ui_edit_trait::UiFieldOptions {
+                    range: #range,
+                    speed: #speed,
+                    format: #format,
+                    color: #color,
+                },
+            );
+        };
+
+        field_widgets.push(match attr.enabled_by {
+            Some(enabled_by) => quote! {
+                {
+                    let _disabled = ui.begin_disabled(!self.#enabled_by);
+                    #edit
+                }
+            },
+            None => edit,
+        });
+    }
+
+    Ok(quote! {
+        impl #impl_generics truvis_ui_edit_trait::UiEdit for #struct_name #ty_generics #where_clause {
+            fn draw_ui(&mut self, ui: &truvis_ui_edit_trait::imgui::Ui) -> bool {
+                let mut changed = false;
+                #(#field_widgets)*
+                changed
+            }
+        }
+    })
+}
+
+/// 字段上 `#[ui(...)]` 属性的内容
+#[derive(Default)]
+struct UiAttr {
+    skip: bool,
+    label: Option<String>,
+    min: Option<syn::Expr>,
+    max: Option<syn::Expr>,
+    speed: Option<syn::Expr>,
+    format: Option<String>,
+    color: bool,
+    enabled_by: Option<syn::Ident>,
+}
+
+/// 解析字段上的 `#[ui(...)]` 属性，可以出现多次
+///
+/// 属性格式示例：#[ui(min = 0.0, max = 1.0, format = "%.3f", enabled_by = "enabled")]
+fn parse_ui_attr(attrs: &[Attribute]) -> syn::Result<UiAttr> {
+    let mut ui_attr = UiAttr::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("ui")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                ui_attr.skip = true;
+            } else if meta.path.is_ident("color") {
+                ui_attr.color = true;
+            } else if meta.path.is_ident("label") {
+                ui_attr.label = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("format") {
+                ui_attr.format = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("min") {
+                ui_attr.min = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("max") {
+                ui_attr.max = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("speed") {
+                ui_attr.speed = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("enabled_by") {
+                ui_attr.enabled_by = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+            } else {
+                return Err(meta.error("unsupported ui attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(ui_attr)
+}
+
+/// 由字段名生成默认的标签：`height_falloff` -> `Height Falloff`
+fn default_label(field_name: &str) -> String {
+    field_name
+        .split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ui_edit_expansion() {
+        let input: DeriveInput = syn::parse_quote! {
+            struct Fog {
+                enabled: bool,
+                #[ui(min = 0.0, max = 1.0, enabled_by = "enabled")]
+                height_falloff: f32,
+                #[ui(skip)]
+                dirty: bool,
+            }
+        };
+        let expanded = expand_ui_edit(&input).unwrap().to_string();
+        assert!(expanded.contains("\"Height Falloff\""));
+
+        let expanded = expanded.replace(' ', "");
+        assert!(expanded.contains("begin_disabled(!self.enabled)"));
+        assert!(expanded.contains("range:Some(((0.0)asf64,(1.0)asf64))"));
+        assert!(!expanded.contains("self.dirty"));
+    }
+
+    #[test]
+    fn test_ui_edit_errors() {
+        let expect_error = |input: DeriveInput, message: &str| {
+            let error = expand_ui_edit(&input).unwrap_err();
+            assert!(error.to_string().contains(message), "{}", error);
+        };
+
+        expect_error(
+            syn::parse_quote! {
+                struct Fog(f32);
+            },
+            "named fields",
+        );
+        expect_error(
+            syn::parse_quote! {
+                enum Fog { A }
+            },
+            "only supports structs",
+        );
+        expect_error(
+            syn::parse_quote! {
+                struct Fog {
+                    #[ui(min = 0.0)]
+                    density: f32,
+                }
+            },
+            "`min` and `max`",
+        );
+        expect_error(
+            syn::parse_quote! {
+                struct Fog {
+                    #[ui(step = 1.0)]
+                    density: f32,
+                }
+            },
+            "unsupported ui attribute",
+        );
+    }
+}
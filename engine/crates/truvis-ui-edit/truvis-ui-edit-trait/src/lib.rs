@@ -0,0 +1,153 @@
+//! 参数结构体的 imgui 编辑 trait
+//!
+//! 配合 `truvis-ui-edit-macro` 中的 `#[derive(UiEdit)]`，根据字段类型和 `#[ui(...)]` 属性
+//! 自动生成编辑控件，调参时不需要手写 UI 代码。
+//!
+//! # 使用示例
+//! ```ignore
+//! #[derive(UiEdit)]
+//! struct FogSettings {
+//!     #[ui(label = "Enable Fog")]
+//!     enabled: bool,
+//!     #[ui(min = 0.0, max = 0.01, format = "%.5f", enabled_by = "enabled")]
+//!     density: f32,
+//!     #[ui(color, enabled_by = "enabled")]
+//!     color: [f32; 3],
+//!     #[ui(skip)]
+//!     frame_cnt: u32,
+//! }
+//!
+//! if fog_settings.draw_ui(ui) {
+//!     // 参数被修改了
+//! }
+//! ```
+//!
+//! # 字段类型与控件
+//! - `bool`：checkbox
+//! - `f32` / `i32` / `u32`：指定了 `min` 和 `max` 时为 slider，否则为 drag
+//! - `[f32; 2]` / `[f32; 3]` / `[f32; 4]` 以及对应的 `glam::Vec*`：同上，逐分量编辑；
+//!   `[f32; 3]` / `[f32; 4]` 指定 `color` 时为 color picker
+//! - 派生了 [`UiEdit`] 的结构体：可折叠的 tree node
+
+pub use imgui;
+
+/// 单个字段的编辑选项，由 `#[ui(...)]` 属性生成
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UiFieldOptions {
+    /// slider 的范围，为 None 时使用 drag
+    pub range: Option<(f64, f64)>,
+    /// drag 的速度
+    pub speed: Option<f32>,
+    /// 数值的显示格式，例如 `%.4f`
+    pub format: Option<&'static str>,
+    /// 使用 color picker 编辑
+    pub color: bool,
+}
+
+/// 绘制结构体所有字段的编辑控件，通常由 `#[derive(UiEdit)]` 生成
+pub trait UiEdit {
+    /// 绘制编辑控件，返回是否有字段被修改
+    fn draw_ui(&mut self, ui: &imgui::Ui) -> bool;
+}
+
+/// 单个字段的编辑控件
+pub trait UiEditField {
+    /// 绘制编辑控件，返回是否被修改
+    fn edit_field(&mut self, ui: &imgui::Ui, label: &str, options: &UiFieldOptions) -> bool;
+}
+
+/// 嵌套的结构体显示为可折叠的 tree node
+impl<T: UiEdit> UiEditField for T {
+    fn edit_field(&mut self, ui: &imgui::Ui, label: &str, _options: &UiFieldOptions) -> bool {
+        match ui.tree_node(label) {
+            Some(_node) => self.draw_ui(ui),
+            None => false,
+        }
+    }
+}
+
+impl UiEditField for bool {
+    fn edit_field(&mut self, ui: &imgui::Ui, label: &str, _options: &UiFieldOptions) -> bool {
+        ui.checkbox(label, self)
+    }
+}
+
+macro_rules! impl_scalar_field {
+    ($($ty:ty),*) => {$(
+        impl UiEditField for $ty {
+            fn edit_field(&mut self, ui: &imgui::Ui, label: &str, options: &UiFieldOptions) -> bool {
+                match options.range {
+                    Some((min, max)) => {
+                        let slider = ui.slider_config(label, min as $ty, max as $ty);
+                        match options.format {
+                            Some(format) => slider.display_format(format).build(self),
+                            None => slider.build(self),
+                        }
+                    }
+                    None => {
+                        let drag = ui.drag_config(label).speed(options.speed.unwrap_or(1.0));
+                        match options.format {
+                            Some(format) => drag.display_format(format).build(ui, self),
+                            None => drag.build(ui, self),
+                        }
+                    }
+                }
+            }
+        }
+    )*};
+}
+impl_scalar_field!(f32, i32, u32);
+
+/// 逐分量编辑 f32 数组
+fn edit_f32_array(ui: &imgui::Ui, label: &str, options: &UiFieldOptions, values: &mut [f32]) -> bool {
+    match options.range {
+        Some((min, max)) => {
+            let slider = ui.slider_config(label, min as f32, max as f32);
+            match options.format {
+                Some(format) => slider.display_format(format).build_array(values),
+                None => slider.build_array(values),
+            }
+        }
+        None => {
+            let drag = ui.drag_config(label).speed(options.speed.unwrap_or(0.01));
+            match options.format {
+                Some(format) => drag.display_format(format).build_array(ui, values),
+                None => drag.build_array(ui, values),
+            }
+        }
+    }
+}
+
+impl UiEditField for [f32; 2] {
+    fn edit_field(&mut self, ui: &imgui::Ui, label: &str, options: &UiFieldOptions) -> bool {
+        edit_f32_array(ui, label, options, self)
+    }
+}
+
+impl UiEditField for [f32; 3] {
+    fn edit_field(&mut self, ui: &imgui::Ui, label: &str, options: &UiFieldOptions) -> bool {
+        if options.color { ui.color_edit3(label, self) } else { edit_f32_array(ui, label, options, self) }
+    }
+}
+
+impl UiEditField for [f32; 4] {
+    fn edit_field(&mut self, ui: &imgui::Ui, label: &str, options: &UiFieldOptions) -> bool {
+        if options.color { ui.color_edit4(label, self) } else { edit_f32_array(ui, label, options, self) }
+    }
+}
+
+macro_rules! impl_glam_field {
+    ($($ty:ty => $array:ty),*) => {$(
+        impl UiEditField for $ty {
+            fn edit_field(&mut self, ui: &imgui::Ui, label: &str, options: &UiFieldOptions) -> bool {
+                let mut values: $array = self.to_array();
+                let changed = values.edit_field(ui, label, options);
+                if changed {
+                    *self = <$ty>::from_array(values);
+                }
+                changed
+            }
+        }
+    )*};
+}
+impl_glam_field!(glam::Vec2 => [f32; 2], glam::Vec3 => [f32; 3], glam::Vec4 => [f32; 4]);
@@ -22,6 +22,8 @@ struct MaterialData
     float metallic = 0.0f;
     TruvixxFloat4 emissive = { 0.0f, 0.0f, 0.0f, 1.0f };
     float opacity = 1.0f; ///< 1 = opaque, 0 = transparent
+    float normal_scale = 1.0f; ///< 切线空间法线 xy 的缩放 (glTF normalTexture.scale)
+    bool two_sided = false; ///< 双面材质，渲染时不做背面剔除
 
     // 纹理路径 (绝对路径)
     std::string diffuse_map;
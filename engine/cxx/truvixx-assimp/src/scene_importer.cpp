@@ -1,5 +1,6 @@
 #include "TruvixxAssimp/scene_importer.hpp"
 
+#include <assimp/GltfMaterial.h>
 #include <assimp/Importer.hpp>
 #include <assimp/postprocess.h>
 #include <assimp/scene.h>
@@ -299,6 +300,19 @@ void SceneImporter::process_material(const aiMaterial* material, MaterialData& o
         out_material.opacity = out_real;
     }
 
+    // normal scale，只有 glTF 的 normalTexture 会带有这个属性
+    if (material->Get(AI_MATKEY_GLTF_TEXTURE_SCALE(aiTextureType_NORMALS, 0), out_real) == AI_SUCCESS)
+    {
+        out_material.normal_scale = out_real;
+    }
+
+    // 双面材质，例如树叶、布料等薄片
+    int out_int = 0;
+    if (material->Get(AI_MATKEY_TWOSIDED, out_int) == AI_SUCCESS)
+    {
+        out_material.two_sided = out_int != 0;
+    }
+
     out_material.diffuse_map = get_texture_path(aiTextureType_DIFFUSE);
     out_material.normal_map = get_texture_path(aiTextureType_NORMALS);
 }
@@ -29,6 +29,8 @@ typedef struct
     TruvixxFloat4 emissive;
     float metallic;
     float opacity;
+    float normal_scale; ///< 切线空间法线 xy 的缩放 (glTF normalTexture.scale)
+    uint32_t two_sided; ///< 非 0 表示双面材质，渲染时不做背面剔除
 
     char diffuse_map[256];
     char normal_map[256];
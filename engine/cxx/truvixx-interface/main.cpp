@@ -102,6 +102,8 @@ int main(const int argc, char* argv[])
 
             std::cout << " base color texture: " << mat_info.diffuse_map << "\n";
             std::cout << " normal texture: " << mat_info.normal_map << "\n";
+            std::cout << " normal scale: " << mat_info.normal_scale << "\n";
+            std::cout << " two sided: " << (mat_info.two_sided ? "yes" : "no") << "\n";
         }
     }
 
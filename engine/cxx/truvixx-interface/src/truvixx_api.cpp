@@ -88,6 +88,8 @@ ResType truvixx_material_get(const TruvixxSceneHandle scene, const uint32_t mat_
     out->metallic = mat.metallic;
     out->emissive = mat.emissive;
     out->opacity = mat.opacity;
+    out->normal_scale = mat.normal_scale;
+    out->two_sided = mat.two_sided ? 1 : 0;
 
     safe_strcpy(out->diffuse_map, sizeof(out->diffuse_map), mat.diffuse_map);
     safe_strcpy(out->normal_map, sizeof(out->normal_map), mat.normal_map);
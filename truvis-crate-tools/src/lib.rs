@@ -11,3 +11,4 @@
 pub mod fetch_resources;
 pub mod init_log;
 pub mod resource;
+pub mod shader_variant;
@@ -3,6 +3,8 @@ use std::{
     path::{Path, PathBuf},
 };
 
+use crate::shader_variant::ShaderVariant;
+
 /// 统一资源路径管理
 ///
 /// 所有路径基于工作区根目录（通过 `CARGO_MANIFEST_DIR` 推导）。
@@ -30,6 +32,21 @@ impl TruvisPath {
     pub fn temp_dir() -> PathBuf {
         Self::workspace_path().join(".temp")
     }
+
+    /// 持久化的 Vulkan pipeline cache，参见 `GfxPipelineCache`
+    pub fn pipeline_cache_path() -> PathBuf {
+        Self::target_path().join("pipeline_cache.bin")
+    }
+
+    /// IBL 预计算结果的缓存目录，参见 `IblBaker`
+    pub fn ibl_cache_dir() -> PathBuf {
+        Self::target_path().join("ibl_cache")
+    }
+
+    /// 用户配置文件的路径
+    pub fn user_settings_path() -> PathBuf {
+        Self::workspace_path().join("settings.toml")
+    }
 }
 // 根目录下
 impl TruvisPath {
@@ -63,14 +80,26 @@ impl TruvisPath {
         Self::engine_path().join("shader")
     }
 
+    /// 编译后的 SPIR-V 所在的 `shader/.build/` 目录
+    pub fn shader_build_root_path() -> PathBuf {
+        Self::shader_root_path().join(".build")
+    }
+
     /// 获取 `shader/.build/` 目录下的着色器路径（编译后的 SPIR-V）
     pub fn shader_build_path_str(filename: &str) -> String {
-        let shader_path = Self::shader_root_path().join(".build").join(filename);
+        let shader_path = Self::shader_build_root_path().join(filename);
         let mut shader_build_path = shader_path.to_str().unwrap().to_string();
         shader_build_path.push_str(".spv");
         shader_build_path
     }
 
+    /// 获取 shader 变体编译后的路径，变体由 `shader-build` 根据 `shader/permutations.toml` 生成
+    ///
+    /// `defines` 为空时与 [`Self::shader_build_path_str`] 相同，命名规则参见 [`ShaderVariant`]
+    pub fn shader_variant_build_path_str(filename: &str, defines: &[&str]) -> String {
+        Self::shader_build_path_str(&ShaderVariant::new(defines).apply_to_path(filename))
+    }
+
     pub fn cxx_root_path() -> PathBuf {
         Self::engine_path().join("cxx")
     }
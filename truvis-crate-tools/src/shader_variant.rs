@@ -0,0 +1,153 @@
+/// Shader 变体（permutation）的宏定义组合
+///
+/// 由 `shader-build` 批量编译，运行时通过 [`crate::resource::TruvisPath::shader_variant_build_path_str`]
+/// 找到对应的 spv 文件，两边共用这里的命名规则。
+///
+/// # 命名规则
+/// - 宏定义写作 `NAME` 或 `NAME=VALUE`，会去掉首尾空白、排序并去重，因此书写顺序不影响结果
+/// - `NAME` 只能包含字母、数字和 `_`，`VALUE` 只能包含字母、数字、`_` 和 `-`
+/// - 后缀为各个宏定义以 `+` 连接，其中 `=` 替换为 `-`：`["SHADOWS_ON", "LIGHT_COUNT=4"]` -> `LIGHT_COUNT-4+SHADOWS_ON`。
+///   `NAME` 中不会出现 `-` 和 `+`，`VALUE` 中不会出现 `+`，因此不同的宏定义组合得到的后缀一定不同
+/// - 后缀插入到文件名第一个 `.` 之前：`phong/phong.ps.slang` -> `phong/phong_SHADOWS_ON.ps.slang`
+/// - 没有宏定义的变体即原始 shader，文件名不变
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct ShaderVariant {
+    defines: Vec<String>,
+}
+
+// new & init
+impl ShaderVariant {
+    /// # Panics
+    /// 宏定义不符合命名规则
+    pub fn new<S: AsRef<str>>(defines: &[S]) -> Self {
+        let mut defines = defines
+            .iter()
+            .map(|define| define.as_ref().trim().to_string())
+            .filter(|define| !define.is_empty())
+            .collect::<Vec<_>>();
+        for define in &defines {
+            assert!(Self::is_valid_define(define), "Invalid shader define: {define:?}, expected NAME or NAME=VALUE");
+        }
+        defines.sort();
+        defines.dedup();
+
+        Self { defines }
+    }
+}
+
+// getter
+impl ShaderVariant {
+    /// 规范化后的宏定义，形如 `NAME` 或 `NAME=VALUE`
+    #[inline]
+    pub fn defines(&self) -> &[String] {
+        &self.defines
+    }
+
+    #[inline]
+    pub fn is_default(&self) -> bool {
+        self.defines.is_empty()
+    }
+}
+
+// tools
+impl ShaderVariant {
+    /// 变体后缀，默认变体为空字符串
+    pub fn suffix(&self) -> String {
+        self.defines.iter().map(|define| define.replace('=', "-")).collect::<Vec<_>>().join("+")
+    }
+
+    fn is_valid_define(define: &str) -> bool {
+        let (name, value) = match define.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (define, None),
+        };
+        let name_valid = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        let value_valid = value.is_none_or(|value| {
+            !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        });
+        name_valid && value_valid
+    }
+
+    /// 为 shader 的相对路径加上变体后缀，路径分隔符统一为 `/`
+    pub fn apply_to_path(&self, shader_path: &str) -> String {
+        let shader_path = shader_path.replace('\\', "/");
+        if self.is_default() {
+            return shader_path;
+        }
+
+        let (dir, file_name) = match shader_path.rsplit_once('/') {
+            Some((dir, file_name)) => (Some(dir), file_name),
+            None => (None, shader_path.as_str()),
+        };
+        let (stem, ext) = match file_name.split_once('.') {
+            Some((stem, ext)) => (stem, Some(ext)),
+            None => (file_name, None),
+        };
+
+        let mut result = String::new();
+        if let Some(dir) = dir {
+            result.push_str(dir);
+            result.push('/');
+        }
+        result.push_str(stem);
+        result.push('_');
+        result.push_str(&self.suffix());
+        if let Some(ext) = ext {
+            result.push('.');
+            result.push_str(ext);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defines_are_normalized() {
+        let variant = ShaderVariant::new(&[" SHADOWS_ON ", "LIGHT_COUNT=4", "", "SHADOWS_ON"]);
+        assert_eq!(variant.defines(), ["LIGHT_COUNT=4", "SHADOWS_ON"]);
+        assert_eq!(variant, ShaderVariant::new(&["LIGHT_COUNT=4", "SHADOWS_ON"]));
+
+        assert!(ShaderVariant::new::<&str>(&[]).is_default());
+        assert!(ShaderVariant::new(&["  "]).is_default());
+    }
+
+    #[test]
+    fn test_suffix_distinguishes_values() {
+        assert_eq!(ShaderVariant::new(&["SHADOWS_ON", "LIGHT_COUNT=4"]).suffix(), "LIGHT_COUNT-4+SHADOWS_ON");
+        assert_eq!(ShaderVariant::new(&["BIAS=-1"]).suffix(), "BIAS--1");
+
+        // 宏定义的值与名称中的 `_` 不会混淆
+        let cases = [
+            ShaderVariant::new(&["NAME=VALUE"]),
+            ShaderVariant::new(&["NAME_VALUE"]),
+            ShaderVariant::new(&["NAME", "VALUE"]),
+            ShaderVariant::new(&["NAME=A_B"]),
+            ShaderVariant::new(&["NAME=A", "B"]),
+        ];
+        for (i, a) in cases.iter().enumerate() {
+            for b in &cases[i + 1..] {
+                assert_ne!(a.suffix(), b.suffix(), "{:?} {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid shader define")]
+    fn test_invalid_define_panics() {
+        ShaderVariant::new(&["LIGHT COUNT=4"]);
+    }
+
+    #[test]
+    fn test_apply_to_path() {
+        let variant = ShaderVariant::new(&["SHADOWS_ON"]);
+        assert_eq!(variant.apply_to_path("phong/phong.ps.slang"), "phong/phong_SHADOWS_ON.ps.slang");
+        assert_eq!(variant.apply_to_path("phong\\phong.ps.slang"), "phong/phong_SHADOWS_ON.ps.slang");
+        assert_eq!(variant.apply_to_path("taa.slang"), "taa_SHADOWS_ON.slang");
+        assert_eq!(variant.apply_to_path("noext"), "noext_SHADOWS_ON");
+
+        assert_eq!(ShaderVariant::default().apply_to_path("pp\\taa.slang"), "pp/taa.slang");
+    }
+}
@@ -9,6 +9,8 @@ use std::num::NonZeroIsize;
 #[cfg(windows)]
 use std::sync::Mutex;
 #[cfg(windows)]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(windows)]
 use windows::Win32::Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, WPARAM};
 #[cfg(windows)]
 use windows::Win32::Graphics::Gdi::HBRUSH;
@@ -17,9 +19,10 @@ use windows::Win32::System::LibraryLoader::GetModuleHandleW;
 #[cfg(windows)]
 use windows::Win32::UI::WindowsAndMessaging::{
     BringWindowToTop, CS_HREDRAW, CS_OWNDC, CS_VREDRAW, CreateWindowExW, DefWindowProcW, GetClientRect, HWND_TOP,
-    RegisterClassExW, SWP_SHOWWINDOW, SetWindowPos, WINDOW_EX_STYLE, WM_DESTROY, WM_KEYDOWN, WM_KEYUP, WM_LBUTTONDOWN,
-    WM_LBUTTONUP, WM_MBUTTONDOWN, WM_MBUTTONUP, WM_MOUSEMOVE, WM_MOUSEWHEEL, WM_RBUTTONDOWN, WM_RBUTTONUP, WNDCLASSEXW,
-    WS_CHILD, WS_CLIPCHILDREN, WS_CLIPSIBLINGS, WS_VISIBLE,
+    RegisterClassExW, SET_WINDOW_POS_FLAGS, SW_HIDE, SW_SHOW, SWP_SHOWWINDOW, SetWindowPos, ShowWindowAsync,
+    WINDOW_EX_STYLE, WM_DESTROY, WM_KEYDOWN, WM_KEYUP, WM_LBUTTONDOWN, WM_LBUTTONUP, WM_MBUTTONDOWN, WM_MBUTTONUP,
+    WM_MOUSEMOVE, WM_MOUSEWHEEL, WM_RBUTTONDOWN, WM_RBUTTONUP, WNDCLASSEXW, WS_CHILD, WS_CLIPCHILDREN, WS_CLIPSIBLINGS,
+    WS_VISIBLE,
 };
 #[cfg(windows)]
 use windows::core::PCWSTR;
@@ -69,6 +72,8 @@ pub struct ChildWindow {
     pub parent_hwnd: SendableHwnd,
     /// 模块实例句柄
     hinstance_ptr: isize,
+    /// 渲染停止后子窗口会被隐藏，调整位置时不能重新显示
+    hidden: AtomicBool,
 }
 
 #[cfg(windows)]
@@ -159,6 +164,7 @@ impl ChildWindow {
                 hwnd: SendableHwnd::new(hwnd),
                 parent_hwnd: SendableHwnd::new(parent_hwnd),
                 hinstance_ptr: hinstance.0 as isize,
+                hidden: AtomicBool::new(false),
             };
 
             Ok((child_window, raw_handle))
@@ -167,9 +173,11 @@ impl ChildWindow {
 
     /// 调整子窗口大小和位置
     pub fn set_position(&self, x: i32, y: i32, width: i32, height: i32) -> Result<(), String> {
+        let show_flag =
+            if self.hidden.load(Ordering::SeqCst) { SET_WINDOW_POS_FLAGS::default() } else { SWP_SHOWWINDOW };
         unsafe {
             // 使用 HWND_TOP 确保子窗口在 WebView2 之上
-            SetWindowPos(self.hwnd.hwnd(), HWND_TOP, x, y, width, height, SWP_SHOWWINDOW)
+            SetWindowPos(self.hwnd.hwnd(), HWND_TOP, x, y, width, height, show_flag)
                 .map_err(|e| format!("Failed to set window position: {}", e))?;
 
             // 额外调用 BringWindowToTop 确保在最前面
@@ -178,6 +186,16 @@ impl ChildWindow {
         }
     }
 
+    /// 显示或隐藏子窗口，隐藏之后下方的 WebView 可见
+    ///
+    /// 可以在其他线程调用，使用异步版本避免主线程阻塞时死锁
+    pub fn set_visible(&self, visible: bool) {
+        self.hidden.store(!visible, Ordering::SeqCst);
+        unsafe {
+            let _ = ShowWindowAsync(self.hwnd.hwnd(), if visible { SW_SHOW } else { SW_HIDE });
+        }
+    }
+
     /// 获取父窗口客户区大小
     pub fn get_parent_client_size(&self) -> Result<(i32, i32), String> {
         unsafe {
@@ -308,15 +326,25 @@ unsafe extern "system" fn child_window_proc(hwnd: HWND, msg: u32, wparam: WPARAM
 #[cfg(windows)]
 fn wparam_to_keycode(vk: u32) -> truvis_app::platform::input_event::KeyCode {
     use truvis_app::platform::input_event::KeyCode;
-    match vk {
-        0x57 => KeyCode::KeyW, // 'W'
-        0x41 => KeyCode::KeyA, // 'A'
-        0x53 => KeyCode::KeyS, // 'S'
-        0x44 => KeyCode::KeyD, // 'D'
-        0x45 => KeyCode::KeyE, // 'E'
-        0x51 => KeyCode::KeyQ, // 'Q'
-        _ => KeyCode::Other,
-    }
+    let name = match vk {
+        // 'A'..'Z'、'0'..'9' 的虚拟键码与 ASCII 相同
+        0x30..=0x39 | 0x41..=0x5A => char::from(vk as u8).to_string(),
+        0x70..=0x7B => format!("F{}", vk - 0x6F), // VK_F1..VK_F12
+        0x25 => "ArrowLeft".to_string(),
+        0x26 => "ArrowUp".to_string(),
+        0x27 => "ArrowRight".to_string(),
+        0x28 => "ArrowDown".to_string(),
+        0x20 => "Space".to_string(),
+        0x0D => "Enter".to_string(),
+        0x1B => "Escape".to_string(),
+        0x09 => "Tab".to_string(),
+        0x08 => "Backspace".to_string(),
+        // WM_KEYDOWN 不区分左右
+        0x10 => "ShiftLeft".to_string(),
+        0x11 => "ControlLeft".to_string(),
+        _ => return KeyCode::Other,
+    };
+    KeyCode::from_name(&name).unwrap_or(KeyCode::Other)
 }
 
 /// 计算子窗口布局（Vulkan 渲染区域）
@@ -373,6 +401,8 @@ impl ChildWindow {
         Err("Child window is only supported on Windows".to_string())
     }
 
+    pub fn set_visible(&self, _visible: bool) {}
+
     pub fn get_parent_client_size(&self) -> Result<(i32, i32), String> {
         Err("Child window is only supported on Windows".to_string())
     }
@@ -8,12 +8,12 @@ mod tauri_event_adapter;
 use crate::child_window::{
     ChildWindow, calculate_vulkan_region, calculate_vulkan_region_with_margins, set_mouse_event_callback,
 };
-use crate::render_thread::RenderThread;
+use crate::render_thread::{RenderThread, RenderThreadStatus};
 use crate::tauri_event_adapter::TauriEventAdapter;
 use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
 use serde::Deserialize;
 use std::sync::Mutex;
-use tauri::{Listener, Manager, RunEvent, WebviewWindow, WindowEvent};
+use tauri::{Emitter, Listener, Manager, RunEvent, WebviewWindow, WindowEvent};
 use truvis_app::outer_app::cornell_app::CornellApp;
 use truvis_app::outer_app::sponza_app::SponzaApp;
 use truvis_app::outer_app::triangle::triangle_app::HelloTriangleApp;
@@ -156,7 +156,8 @@ fn init_vulkan_child_window(main_window: &WebviewWindow) -> Result<(), Box<dyn s
     println!("Created Vulkan child window at ({}, {}) with size {}x{}", x, y, width, height);
 
     // 在独立线程中启动渲染器
-    let render_thread = RenderThread::spawn(raw_display_handle, || Box::new(SponzaApp::default()));
+    let render_thread =
+        RenderThread::spawn(raw_display_handle, || Box::new(SponzaApp::default()), on_render_thread_status);
 
     // 发送窗口初始化消息（使用子窗口的句柄）
     render_thread.init_window(raw_display_handle, child_raw_handle, scale_factor, [width as u32, height as u32]);
@@ -176,6 +177,28 @@ fn init_vulkan_child_window(main_window: &WebviewWindow) -> Result<(), Box<dyn s
     Ok(())
 }
 
+/// 渲染线程状态变化时通知前端（在渲染线程中调用）
+///
+/// 渲染无法恢复时隐藏子窗口，由前端在 Vulkan 区域显示错误画面
+fn on_render_thread_status(status: RenderThreadStatus) {
+    eprintln!("Render thread status: {:?}", status);
+
+    #[cfg(windows)]
+    {
+        let child_window = CHILD_WINDOW.lock().unwrap();
+        if let Some(ref cw) = *child_window {
+            cw.set_visible(!matches!(status, RenderThreadStatus::Failed { .. }));
+        }
+    }
+
+    let app_handle = APP_HANDLE.lock().unwrap();
+    if let Some(ref app_handle) = *app_handle {
+        if let Err(e) = app_handle.emit("render:status", &status) {
+            eprintln!("Failed to emit render status: {}", e);
+        }
+    }
+}
+
 /// 设置主窗口的事件监听
 fn setup_window_events(app: &tauri::App, main_window: &WebviewWindow) {
     // 监听窗口关闭
@@ -1,4 +1,6 @@
 use raw_window_handle::{RawDisplayHandle, RawWindowHandle};
+use serde::Serialize;
+use std::panic::{self, AssertUnwindSafe};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{self, Receiver, Sender};
@@ -58,6 +60,27 @@ pub enum RenderThreadMessage {
     Shutdown,
 }
 
+/// 渲染线程上报给主线程的状态
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RenderThreadStatus {
+    /// 渲染线程 panic，资源已经清理，正在重新创建渲染器
+    Restarting { message: String, restart_cnt: u32 },
+    /// 重启之后渲染恢复正常
+    Running,
+    /// 重启次数用尽，或者资源无法安全清理，渲染停止，直到应用退出
+    Failed { message: String },
+}
+
+/// 最近一次的窗口信息，渲染线程重启时用于重新初始化
+#[derive(Clone, Copy)]
+struct WindowInfo {
+    raw_display_handle: SendableDisplayHandle,
+    raw_window_handle: SendableWindowHandle,
+    scale_factor: f64,
+    window_physical_extent: [u32; 2],
+}
+
 /// 渲染线程句柄
 pub struct RenderThread {
     /// 消息发送端
@@ -69,14 +92,28 @@ pub struct RenderThread {
 }
 
 impl RenderThread {
+    /// 渲染线程 panic 之后最多重启的次数，超过之后进入 Failed 状态
+    const MAX_RESTART_CNT: u32 = 3;
+
     /// 创建并启动渲染线程
     ///
+    /// 渲染过程中的 panic 会被捕获：清理 Vulkan 资源之后重新创建渲染器，
+    /// 状态变化通过 `on_status` 在渲染线程中回调
+    ///
+    /// # 限制
+    /// 重启依赖 `catch_unwind`，只有 `panic = "unwind"` 时才生效。workspace 的 release profile 使用
+    /// `panic = "abort"`，因此发布版本需要使用 `release-tauri` profile 构建（参见 README）；
+    /// 否则任何 panic 都会直接终止整个进程。即使是 unwind，也无法从 abort 类的错误中恢复
+    /// （例如 unwind 过程中再次 panic、驱动导致的段错误），这类错误需要在进程外监控并重启
+    ///
     /// # Arguments
     /// * `raw_display_handle` - 显示句柄（用于初始化 Vulkan）
-    /// * `outer_app_factory` - 创建 OuterApp 的工厂函数
-    pub fn spawn<F>(raw_display_handle: RawDisplayHandle, outer_app_factory: F) -> Self
+    /// * `outer_app_factory` - 创建 OuterApp 的工厂函数，每次重启都会重新调用
+    /// * `on_status` - 渲染线程状态变化的回调
+    pub fn spawn<F, S>(raw_display_handle: RawDisplayHandle, outer_app_factory: F, on_status: S) -> Self
     where
-        F: FnOnce() -> Box<dyn OuterApp> + Send + 'static,
+        F: Fn() -> Box<dyn OuterApp> + Send + 'static,
+        S: Fn(RenderThreadStatus) + Send + 'static,
     {
         let (sender, receiver) = mpsc::channel::<RenderThreadMessage>();
         let running = Arc::new(AtomicBool::new(true));
@@ -87,7 +124,13 @@ impl RenderThread {
         let thread_handle = thread::Builder::new()
             .name("RenderThread".to_string())
             .spawn(move || {
-                Self::render_thread_main(sendable_display_handle, outer_app_factory, receiver, running_clone);
+                Self::render_thread_main(
+                    sendable_display_handle,
+                    outer_app_factory,
+                    on_status,
+                    receiver,
+                    running_clone,
+                );
             })
             .expect("Failed to spawn render thread");
 
@@ -141,33 +184,144 @@ impl RenderThread {
     }
 
     /// 渲染线程主函数
-    fn render_thread_main<F>(
+    ///
+    /// 每次 panic 之后清理资源并重新进入渲染循环，重启次数用尽之后只等待退出
+    fn render_thread_main<F, S>(
         sendable_display_handle: SendableDisplayHandle,
         outer_app_factory: F,
+        on_status: S,
         receiver: Receiver<RenderThreadMessage>,
         running: Arc<AtomicBool>,
     ) where
-        F: FnOnce() -> Box<dyn OuterApp>,
+        F: Fn() -> Box<dyn OuterApp>,
+        S: Fn(RenderThreadStatus),
     {
         // 初始化环境
         RenderApp::init_env();
+        if cfg!(panic = "abort") {
+            eprintln!("Render thread: built with panic = \"abort\", panics can not be recovered");
+        }
+
+        let mut window_info: Option<WindowInfo> = None;
+        let recovered = Self::run_with_restart(
+            Self::MAX_RESTART_CNT,
+            |render_app: &mut Option<RenderApp>, restart_cnt| {
+                Self::render_loop(
+                    sendable_display_handle.raw(),
+                    &outer_app_factory,
+                    &receiver,
+                    &running,
+                    render_app,
+                    &mut window_info,
+                    || {
+                        if restart_cnt > 0 {
+                            on_status(RenderThreadStatus::Running);
+                        }
+                    },
+                );
+            },
+            |render_app: RenderApp| {
+                // 正常退出，清理资源
+                println!("Render thread: Cleaning up...");
+                render_app.destroy();
+            },
+            // 先确保 GPU 不再使用任何资源，再释放，避免 device hang
+            RenderApp::destroy_after_panic,
+            &on_status,
+        );
+        if !recovered {
+            Self::wait_for_shutdown(&receiver, &running);
+        }
+
+        println!("Render thread: Exited");
+    }
 
-        let raw_display_handle = sendable_display_handle.raw();
+    /// 运行 `run`，panic 之后清理资源并重新运行，最多重启 `max_restart_cnt` 次
+    ///
+    /// - `run` 的参数为存放资源的位置与当前的重启次数；正常返回时资源交给 `destroy`
+    /// - panic 时资源交给 `destroy_after_panic`，返回 false 表示无法安全清理，此时不再重启
+    ///
+    /// 返回 false 表示进入了 [`RenderThreadStatus::Failed`] 状态
+    fn run_with_restart<T>(
+        max_restart_cnt: u32,
+        mut run: impl FnMut(&mut Option<T>, u32),
+        destroy: impl FnOnce(T),
+        mut destroy_after_panic: impl FnMut(Option<T>) -> bool,
+        on_status: &impl Fn(RenderThreadStatus),
+    ) -> bool {
+        let mut restart_cnt = 0;
+        loop {
+            let mut resource: Option<T> = None;
+            let result = panic::catch_unwind(AssertUnwindSafe(|| run(&mut resource, restart_cnt)));
+
+            let Err(panic_payload) = result else {
+                if let Some(resource) = resource {
+                    destroy(resource);
+                }
+                return true;
+            };
+
+            let message = Self::panic_message(panic_payload.as_ref());
+            eprintln!("Render thread: panicked: {}", message);
+
+            let cleaned = destroy_after_panic(resource);
+            if !cleaned || restart_cnt >= max_restart_cnt {
+                let message = if cleaned { message } else { format!("{message} (failed to release gpu resources)") };
+                on_status(RenderThreadStatus::Failed { message });
+                return false;
+            }
+
+            restart_cnt += 1;
+            on_status(RenderThreadStatus::Restarting { message, restart_cnt });
+        }
+    }
 
+    /// 渲染循环，收到退出消息后返回；RenderApp 放在 `render_app` 中，panic 之后由调用方清理
+    ///
+    /// `window_info` 不为空时说明窗口已经存在（重启的情况），会直接用它初始化；
+    /// 初始化完成后调用 `on_ready`
+    fn render_loop<F>(
+        raw_display_handle: RawDisplayHandle,
+        outer_app_factory: &F,
+        receiver: &Receiver<RenderThreadMessage>,
+        running: &AtomicBool,
+        render_app: &mut Option<RenderApp>,
+        window_info: &mut Option<WindowInfo>,
+        on_ready: impl FnOnce(),
+    ) where
+        F: Fn() -> Box<dyn OuterApp>,
+    {
         // 创建 OuterApp
         let outer_app = outer_app_factory();
 
         // 创建 RenderApp（此时还没有窗口，只初始化 Vulkan 实例）
-        let mut render_app = RenderApp::new(raw_display_handle, outer_app);
+        let render_app = render_app.insert(RenderApp::new(raw_display_handle, outer_app));
 
         // 等待窗口初始化消息
         let mut window_initialized = false;
+        if let Some(info) = window_info {
+            Self::init_window_for(render_app, info);
+            window_initialized = true;
+            println!("Render thread: Window re-initialized after restart");
+        }
+        on_ready();
 
         while running.load(Ordering::SeqCst) {
             // 非阻塞地尝试接收消息
             match receiver.try_recv() {
                 Ok(message) => match message {
                     RenderThreadMessage::InputEvent(event) => {
+                        // 记录最新的窗口尺寸，重启时使用
+                        if let (
+                            InputEvent::Resized {
+                                physical_width,
+                                physical_height,
+                            },
+                            Some(info),
+                        ) = (&event, window_info.as_mut())
+                        {
+                            info.window_physical_extent = [*physical_width, *physical_height];
+                        }
                         if window_initialized {
                             render_app.handle_event(&event);
                         }
@@ -178,12 +332,13 @@ impl RenderThread {
                         scale_factor,
                         window_physical_extent,
                     } => {
-                        render_app.init_after_window(
-                            raw_display_handle.raw(),
-                            raw_window_handle.raw(),
+                        let info = window_info.insert(WindowInfo {
+                            raw_display_handle,
+                            raw_window_handle,
                             scale_factor,
                             window_physical_extent,
-                        );
+                        });
+                        Self::init_window_for(render_app, info);
                         window_initialized = true;
                         println!("Render thread: Window initialized");
                     }
@@ -210,11 +365,36 @@ impl RenderThread {
                 std::thread::sleep(std::time::Duration::from_millis(1));
             }
         }
+    }
 
-        // 清理资源
-        println!("Render thread: Cleaning up...");
-        render_app.destroy();
-        println!("Render thread: Exited");
+    fn init_window_for(render_app: &mut RenderApp, info: &WindowInfo) {
+        render_app.init_after_window(
+            info.raw_display_handle.raw(),
+            info.raw_window_handle.raw(),
+            info.scale_factor,
+            info.window_physical_extent,
+        );
+    }
+
+    /// 渲染停止之后，丢弃输入事件，直到收到退出消息
+    fn wait_for_shutdown(receiver: &Receiver<RenderThreadMessage>, running: &AtomicBool) {
+        while running.load(Ordering::SeqCst) {
+            match receiver.recv() {
+                Ok(RenderThreadMessage::Shutdown) | Err(_) => break,
+                Ok(_) => {}
+            }
+        }
+    }
+
+    /// 从 panic payload 中提取错误信息
+    fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+        if let Some(message) = payload.downcast_ref::<&str>() {
+            message.to_string()
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else {
+            "unknown panic".to_string()
+        }
     }
 }
 
@@ -226,3 +406,101 @@ impl Drop for RenderThread {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// 记录重启过程中发生的事件
+    #[derive(Default)]
+    struct Record {
+        statuses: RefCell<Vec<RenderThreadStatus>>,
+        events: RefCell<Vec<String>>,
+    }
+
+    impl Record {
+        fn push(&self, event: String) {
+            self.events.borrow_mut().push(event);
+        }
+    }
+
+    /// 前 `panic_cnt` 次运行在创建资源之后 panic，`cleaned` 为 destroy_after_panic 的返回值
+    fn run(record: &Record, max_restart_cnt: u32, panic_cnt: u32, cleaned: bool) -> bool {
+        RenderThread::run_with_restart(
+            max_restart_cnt,
+            |resource: &mut Option<u32>, restart_cnt| {
+                record.push(format!("run {restart_cnt}"));
+                *resource = Some(restart_cnt);
+                if restart_cnt < panic_cnt {
+                    panic!("render panic {restart_cnt}");
+                }
+            },
+            |resource| record.push(format!("destroy {resource}")),
+            |resource| {
+                record.push(format!("destroy after panic {resource:?}"));
+                cleaned
+            },
+            &|status| record.statuses.borrow_mut().push(status),
+        )
+    }
+
+    #[test]
+    fn test_restart_after_panic() {
+        let record = Record::default();
+        assert!(run(&record, 3, 2, true));
+
+        assert_eq!(
+            *record.events.borrow(),
+            [
+                "run 0",
+                "destroy after panic Some(0)",
+                "run 1",
+                "destroy after panic Some(1)",
+                "run 2",
+                "destroy 2",
+            ]
+        );
+        assert_eq!(
+            *record.statuses.borrow(),
+            [
+                RenderThreadStatus::Restarting {
+                    message: "render panic 0".to_string(),
+                    restart_cnt: 1
+                },
+                RenderThreadStatus::Restarting {
+                    message: "render panic 1".to_string(),
+                    restart_cnt: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fail_after_max_restart() {
+        let record = Record::default();
+        assert!(!run(&record, 2, u32::MAX, true));
+
+        assert_eq!(record.events.borrow().iter().filter(|event| event.starts_with("run")).count(), 3);
+        assert_eq!(
+            record.statuses.borrow().last(),
+            Some(&RenderThreadStatus::Failed {
+                message: "render panic 2".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_fail_when_cleanup_fails() {
+        let record = Record::default();
+        assert!(!run(&record, 3, 1, false));
+
+        assert_eq!(*record.events.borrow(), ["run 0", "destroy after panic Some(0)"]);
+        assert_eq!(
+            *record.statuses.borrow(),
+            [RenderThreadStatus::Failed {
+                message: "render panic 0 (failed to release gpu resources)".to_string()
+            }]
+        );
+    }
+}
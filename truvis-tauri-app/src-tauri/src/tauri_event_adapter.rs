@@ -53,16 +53,8 @@ impl TauriEventAdapter {
         }
     }
 
-    /// 将键盘字符串转换为 KeyCode
+    /// 将键盘字符串转换为 KeyCode，支持 KeyboardEvent.code（`KeyW`）以及按键字符（`w`）
     fn key_from_string(key: &str) -> KeyCode {
-        match key.to_lowercase().as_str() {
-            "w" | "keyw" => KeyCode::KeyW,
-            "a" | "keya" => KeyCode::KeyA,
-            "s" | "keys" => KeyCode::KeyS,
-            "d" | "keyd" => KeyCode::KeyD,
-            "e" | "keye" => KeyCode::KeyE,
-            "q" | "keyq" => KeyCode::KeyQ,
-            _ => KeyCode::Other,
-        }
+        KeyCode::from_name(key).unwrap_or(KeyCode::Other)
     }
 }
@@ -1,6 +1,6 @@
 import { useState, useEffect, useCallback, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { emit } from "@tauri-apps/api/event";
+import { emit, listen } from "@tauri-apps/api/event";
 import "./App.css";
 
 // 默认布局尺寸（像素）
@@ -13,6 +13,12 @@ const DEFAULT_RIGHT_WIDTH = 200;
 const MIN_PANEL_SIZE = 100;
 const MIN_VULKAN_SIZE = 200;
 
+// 渲染线程状态，与后端 RenderThreadStatus 对应
+type RenderStatus =
+  | { kind: "running" }
+  | { kind: "restarting"; message: string; restart_cnt: number }
+  | { kind: "failed"; message: string };
+
 interface VulkanBounds {
   top: number;
   left: number;
@@ -33,6 +39,18 @@ function App() {
   // Vulkan 区域引用
   const vulkanRef = useRef<HTMLDivElement>(null);
 
+  // 渲染线程状态
+  const [renderStatus, setRenderStatus] = useState<RenderStatus>({ kind: "running" });
+
+  useEffect(() => {
+    const unlisten = listen<RenderStatus>("render:status", (event) => {
+      setRenderStatus(event.payload);
+    });
+    return () => {
+      unlisten.then((f) => f());
+    };
+  }, []);
+
   // 通知后端更新 Vulkan 区域布局
   const updateVulkanBounds = useCallback(async (bounds: VulkanBounds) => {
     try {
@@ -214,6 +232,13 @@ function App() {
           onContextMenu={(e) => e.preventDefault()}
         >
           {/* 这个区域捕获鼠标事件并转发给 Vulkan 渲染器 */}
+          {/* 渲染线程无法恢复时子窗口会被隐藏，这里显示错误画面 */}
+          {renderStatus.kind === "failed" && (
+            <div className="w-full h-full flex flex-col items-center justify-center gap-2 bg-editor-panel text-editor-text-primary p-4">
+              <span className="text-sm font-semibold text-editor-text-white">Renderer stopped</span>
+              <pre className="text-xs text-editor-text-secondary whitespace-pre-wrap max-w-full">{renderStatus.message}</pre>
+            </div>
+          )}
         </div>
 
         {/* 右侧面板 */}
@@ -251,7 +276,11 @@ function App() {
           onMouseDown={handleMouseDown('bottom')}
         />
         <div className="flex-1 flex items-center justify-between px-3 text-xs text-editor-text-white">
-          <span>Ready</span>
+          <span>
+            {renderStatus.kind === "running" && "Ready"}
+            {renderStatus.kind === "restarting" && `Renderer crashed, restarting (${renderStatus.restart_cnt})...`}
+            {renderStatus.kind === "failed" && "Renderer stopped"}
+          </span>
           <span className="opacity-80">FPS: -- | Draw Calls: -- | Triangles: --</span>
         </div>
       </div>
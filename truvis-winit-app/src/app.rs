@@ -1,3 +1,4 @@
+use crate::gilrs_event_adapter::GilrsEventAdapter;
 use crate::winit_event_adapter::WinitEventAdapter;
 use raw_window_handle::{HasRawDisplayHandle, HasRawWindowHandle};
 use truvis_app::outer_app::base::OuterApp;
@@ -18,6 +19,8 @@ pub struct WinitApp {
     render_app: RenderApp,
 
     window: Option<Window>,
+    /// 手柄输入，初始化失败时为 None，此时只能使用键鼠
+    gilrs: Option<gilrs::Gilrs>,
 }
 // 总的 main 函数
 impl WinitApp {
@@ -30,6 +33,7 @@ impl WinitApp {
         let mut app = Self {
             render_app: RenderApp::new(event_loop.raw_display_handle().unwrap(), outer_app),
             window: None,
+            gilrs: Self::create_gilrs(),
         };
 
         event_loop.run_app(&mut app).unwrap();
@@ -43,7 +47,12 @@ impl WinitApp {
 impl WinitApp {
     /// 在 window 创建之后调用，初始化 Renderer 和 GUI
     fn init_after_window(&mut self, event_loop: &ActiveEventLoop) {
-        let window = Self::create_window(event_loop, "Truvis".to_string(), [1200.0, 800.0]);
+        let window_settings = self.render_app.settings.window;
+        let window = Self::create_window(
+            event_loop,
+            "Truvis".to_string(),
+            [window_settings.width as f64, window_settings.height as f64],
+        );
 
         let window_size = window.inner_size();
 
@@ -57,6 +66,16 @@ impl WinitApp {
         self.window = Some(window);
     }
 
+    fn create_gilrs() -> Option<gilrs::Gilrs> {
+        match gilrs::Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(e) => {
+                log::warn!("failed to init gamepad input: {}", e);
+                None
+            }
+        }
+    }
+
     fn create_window(event_loop: &ActiveEventLoop, window_title: String, window_extent: [f64; 2]) -> Window {
         fn load_icon(bytes: &[u8]) -> winit::window::Icon {
             let (icon_rgba, icon_width, icon_height) = {
@@ -84,6 +103,15 @@ impl WinitApp {
 // destroy
 impl WinitApp {
     fn destroy(mut self) {
+        // 记录窗口大小，随设置一起保存；最小化时大小为 0，保留之前的值
+        if let Some(window) = self.window.as_ref() {
+            let window_size = window.inner_size().to_logical::<u32>(window.scale_factor());
+            if window_size.width > 0 && window_size.height > 0 {
+                self.render_app.settings.window.width = window_size.width;
+                self.render_app.settings.window.height = window_size.height;
+            }
+        }
+
         self.render_app.destroy();
         self.window = None;
     }
@@ -130,6 +158,13 @@ impl ApplicationHandler<UserEvent> for WinitApp {
     }
 
     fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+        // 手柄没有对应的 winit 事件，在每一轮事件循环中轮询；热插拔同样以事件的形式给出
+        if let Some(gilrs) = self.gilrs.as_mut() {
+            while let Some(event) = gilrs.next_event() {
+                self.render_app.handle_event(&GilrsEventAdapter::from_gilrs_event(&event));
+            }
+        }
+
         self.window.as_ref().unwrap().request_redraw();
     }
 
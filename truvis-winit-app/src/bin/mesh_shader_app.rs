@@ -0,0 +1,7 @@
+use truvis_app::outer_app::mesh_shader::mesh_shader_app::MeshShaderApp;
+use truvis_winit_app::app::WinitApp;
+
+fn main() {
+    let outer_app = Box::new(MeshShaderApp::default());
+    WinitApp::run(outer_app);
+}
@@ -0,0 +1,7 @@
+use truvis_app::outer_app::multi_draw::multi_draw_app::MultiDrawApp;
+use truvis_winit_app::app::WinitApp;
+
+fn main() {
+    let outer_app = Box::new(MultiDrawApp::default());
+    WinitApp::run(outer_app);
+}
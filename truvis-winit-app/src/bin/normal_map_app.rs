@@ -0,0 +1,7 @@
+use truvis_app::outer_app::normal_map_app::NormalMapApp;
+use truvis_winit_app::app::WinitApp;
+
+fn main() {
+    let outer_app = Box::new(NormalMapApp::default());
+    WinitApp::run(outer_app);
+}
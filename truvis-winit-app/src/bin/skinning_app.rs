@@ -0,0 +1,7 @@
+use truvis_app::outer_app::skinning_app::SkinningApp;
+use truvis_winit_app::app::WinitApp;
+
+fn main() {
+    let outer_app = Box::new(SkinningApp::default());
+    WinitApp::run(outer_app);
+}
@@ -0,0 +1,7 @@
+use truvis_app::outer_app::terrain_strip::terrain_strip_app::TerrainStripApp;
+use truvis_winit_app::app::WinitApp;
+
+fn main() {
+    let outer_app = Box::new(TerrainStripApp::default());
+    WinitApp::run(outer_app);
+}
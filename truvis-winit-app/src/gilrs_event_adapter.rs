@@ -0,0 +1,39 @@
+use truvis_app::platform::input_event::{GamepadAxis, GamepadEvent, InputEvent};
+
+pub struct GilrsEventAdapter {}
+impl GilrsEventAdapter {
+    pub fn from_gilrs_event(event: &gilrs::Event) -> InputEvent {
+        let id = usize::from(event.id);
+        let gamepad_event = match event.event {
+            gilrs::EventType::Connected => GamepadEvent::Connected { id },
+            gilrs::EventType::Disconnected => GamepadEvent::Disconnected { id },
+            gilrs::EventType::AxisChanged(axis, value, _) => match Self::axis_from_gilrs(axis) {
+                Some(axis) => GamepadEvent::AxisChanged { id, axis, value },
+                None => return InputEvent::Other,
+            },
+            // 扳机以模拟量按键的形式上报，value 为 [0, 1]
+            gilrs::EventType::ButtonChanged(gilrs::Button::LeftTrigger2, value, _) => GamepadEvent::AxisChanged {
+                id,
+                axis: GamepadAxis::LeftTrigger,
+                value,
+            },
+            gilrs::EventType::ButtonChanged(gilrs::Button::RightTrigger2, value, _) => GamepadEvent::AxisChanged {
+                id,
+                axis: GamepadAxis::RightTrigger,
+                value,
+            },
+            _ => return InputEvent::Other,
+        };
+        InputEvent::Gamepad(gamepad_event)
+    }
+
+    fn axis_from_gilrs(axis: gilrs::Axis) -> Option<GamepadAxis> {
+        match axis {
+            gilrs::Axis::LeftStickX => Some(GamepadAxis::LeftStickX),
+            gilrs::Axis::LeftStickY => Some(GamepadAxis::LeftStickY),
+            gilrs::Axis::RightStickX => Some(GamepadAxis::RightStickX),
+            gilrs::Axis::RightStickY => Some(GamepadAxis::RightStickY),
+            _ => None,
+        }
+    }
+}
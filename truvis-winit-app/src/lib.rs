@@ -1,2 +1,3 @@
 pub mod app;
+pub mod gilrs_event_adapter;
 pub mod winit_event_adapter;
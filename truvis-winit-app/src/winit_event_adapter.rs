@@ -40,6 +40,7 @@ impl WinitEventAdapter {
                 physical_width: physical_size.width,
                 physical_height: physical_size.height,
             },
+            WindowEvent::DroppedFile(path) => InputEvent::FileDropped(path.clone()),
             _ => InputEvent::Other,
         }
     }
@@ -56,13 +57,71 @@ impl WinitEventAdapter {
     }
 
     fn key_from_winit(key: winit::keyboard::KeyCode) -> KeyCode {
+        use winit::keyboard::KeyCode as WinitKeyCode;
         match key {
-            winit::keyboard::KeyCode::KeyW => KeyCode::KeyW,
-            winit::keyboard::KeyCode::KeyA => KeyCode::KeyA,
-            winit::keyboard::KeyCode::KeyS => KeyCode::KeyS,
-            winit::keyboard::KeyCode::KeyD => KeyCode::KeyD,
-            winit::keyboard::KeyCode::KeyE => KeyCode::KeyE,
-            winit::keyboard::KeyCode::KeyQ => KeyCode::KeyQ,
+            WinitKeyCode::KeyA => KeyCode::KeyA,
+            WinitKeyCode::KeyB => KeyCode::KeyB,
+            WinitKeyCode::KeyC => KeyCode::KeyC,
+            WinitKeyCode::KeyD => KeyCode::KeyD,
+            WinitKeyCode::KeyE => KeyCode::KeyE,
+            WinitKeyCode::KeyF => KeyCode::KeyF,
+            WinitKeyCode::KeyG => KeyCode::KeyG,
+            WinitKeyCode::KeyH => KeyCode::KeyH,
+            WinitKeyCode::KeyI => KeyCode::KeyI,
+            WinitKeyCode::KeyJ => KeyCode::KeyJ,
+            WinitKeyCode::KeyK => KeyCode::KeyK,
+            WinitKeyCode::KeyL => KeyCode::KeyL,
+            WinitKeyCode::KeyM => KeyCode::KeyM,
+            WinitKeyCode::KeyN => KeyCode::KeyN,
+            WinitKeyCode::KeyO => KeyCode::KeyO,
+            WinitKeyCode::KeyP => KeyCode::KeyP,
+            WinitKeyCode::KeyQ => KeyCode::KeyQ,
+            WinitKeyCode::KeyR => KeyCode::KeyR,
+            WinitKeyCode::KeyS => KeyCode::KeyS,
+            WinitKeyCode::KeyT => KeyCode::KeyT,
+            WinitKeyCode::KeyU => KeyCode::KeyU,
+            WinitKeyCode::KeyV => KeyCode::KeyV,
+            WinitKeyCode::KeyW => KeyCode::KeyW,
+            WinitKeyCode::KeyX => KeyCode::KeyX,
+            WinitKeyCode::KeyY => KeyCode::KeyY,
+            WinitKeyCode::KeyZ => KeyCode::KeyZ,
+            WinitKeyCode::Digit0 => KeyCode::Digit0,
+            WinitKeyCode::Digit1 => KeyCode::Digit1,
+            WinitKeyCode::Digit2 => KeyCode::Digit2,
+            WinitKeyCode::Digit3 => KeyCode::Digit3,
+            WinitKeyCode::Digit4 => KeyCode::Digit4,
+            WinitKeyCode::Digit5 => KeyCode::Digit5,
+            WinitKeyCode::Digit6 => KeyCode::Digit6,
+            WinitKeyCode::Digit7 => KeyCode::Digit7,
+            WinitKeyCode::Digit8 => KeyCode::Digit8,
+            WinitKeyCode::Digit9 => KeyCode::Digit9,
+            WinitKeyCode::F1 => KeyCode::F1,
+            WinitKeyCode::F2 => KeyCode::F2,
+            WinitKeyCode::F3 => KeyCode::F3,
+            WinitKeyCode::F4 => KeyCode::F4,
+            WinitKeyCode::F5 => KeyCode::F5,
+            WinitKeyCode::F6 => KeyCode::F6,
+            WinitKeyCode::F7 => KeyCode::F7,
+            WinitKeyCode::F8 => KeyCode::F8,
+            WinitKeyCode::F9 => KeyCode::F9,
+            WinitKeyCode::F10 => KeyCode::F10,
+            WinitKeyCode::F11 => KeyCode::F11,
+            WinitKeyCode::F12 => KeyCode::F12,
+            WinitKeyCode::ArrowUp => KeyCode::ArrowUp,
+            WinitKeyCode::ArrowDown => KeyCode::ArrowDown,
+            WinitKeyCode::ArrowLeft => KeyCode::ArrowLeft,
+            WinitKeyCode::ArrowRight => KeyCode::ArrowRight,
+            WinitKeyCode::Space => KeyCode::Space,
+            WinitKeyCode::Enter => KeyCode::Enter,
+            WinitKeyCode::Escape => KeyCode::Escape,
+            WinitKeyCode::Tab => KeyCode::Tab,
+            WinitKeyCode::Backspace => KeyCode::Backspace,
+            WinitKeyCode::ShiftLeft => KeyCode::ShiftLeft,
+            WinitKeyCode::ShiftRight => KeyCode::ShiftRight,
+            WinitKeyCode::ControlLeft => KeyCode::ControlLeft,
+            WinitKeyCode::ControlRight => KeyCode::ControlRight,
+            WinitKeyCode::AltLeft => KeyCode::AltLeft,
+            WinitKeyCode::AltRight => KeyCode::AltRight,
             _ => KeyCode::Other,
         }
     }